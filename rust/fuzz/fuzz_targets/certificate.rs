@@ -0,0 +1,17 @@
+//! Raw bytes through the consensus certificate and DAG sync parsers
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use modal_validator_consensus::narwhal::{Certificate, SyncResponse};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(cert) = Certificate::from_json_slice(data) {
+        cert.digest();
+        cert.get_signer_indices();
+        let _ = cert.header.verify_parents(cert.header.round);
+    }
+    if let Ok(response) = serde_json::from_slice::<SyncResponse>(data) {
+        let _ = response.validate_fields();
+    }
+});
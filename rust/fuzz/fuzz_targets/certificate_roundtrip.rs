@@ -0,0 +1,17 @@
+//! Structured certificates through serialization and parsing
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use modal_validator_consensus::narwhal::Certificate;
+
+fuzz_target!(|cert: Certificate| {
+    let json = serde_json::to_vec(&cert).unwrap();
+    match Certificate::from_json_slice(&json) {
+        Ok(parsed) => {
+            assert_eq!(parsed.digest(), cert.digest());
+            assert_eq!(parsed.signers, cert.signers);
+        }
+        Err(_) => assert!(cert.validate_fields().is_err()),
+    }
+});
@@ -0,0 +1,14 @@
+//! Raw bytes through the contract commit parser and validation
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use modal_common::contract_store::CommitFile;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(commit) = CommitFile::from_slice(data) {
+        let _ = commit.validate();
+        commit.compute_id().unwrap();
+        commit.signing_digest().unwrap();
+    }
+});
@@ -0,0 +1,17 @@
+//! Structured commits through validation, serialization and parsing
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use modal_common::contract_store::CommitFile;
+
+fuzz_target!(|commit: CommitFile| {
+    let _ = commit.validate();
+    let json = serde_json::to_vec(&commit).unwrap();
+    // A `null` head field parses back as absent, so compare ids from the
+    // first parse on
+    if let Ok(parsed) = CommitFile::from_slice(&json) {
+        let reparsed = CommitFile::from_slice(&serde_json::to_vec(&parsed).unwrap()).unwrap();
+        assert_eq!(reparsed.compute_id().unwrap(), parsed.compute_id().unwrap());
+    }
+});
@@ -0,0 +1,18 @@
+//! Raw bytes through the miner block parsers used for sync and gossip
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use modal_datastore::models::MinerBlock;
+use modal_node::gossip::miner::block::MinerBlockGossip;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(block) = MinerBlock::from_json_slice(data) {
+        let json = serde_json::to_vec(&block).unwrap();
+        assert_eq!(MinerBlock::from_json_slice(&json).unwrap(), block);
+    }
+    if let Ok(gossip) = MinerBlockGossip::from_slice(data) {
+        // Accepted gossip always converts to a well-formed block
+        gossip.to_miner_block().validate_fields().unwrap();
+    }
+});
@@ -0,0 +1,21 @@
+//! Structured miner blocks through serialization, parsing and gossip
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use modal_datastore::models::MinerBlock;
+use modal_node::gossip::miner::block::MinerBlockGossip;
+
+fuzz_target!(|block: MinerBlock| {
+    let json = serde_json::to_vec(&block).unwrap();
+    match MinerBlock::from_json_slice(&json) {
+        Ok(parsed) => assert_eq!(parsed, block),
+        Err(_) => assert!(block.validate_fields().is_err()),
+    }
+
+    let gossip = serde_json::to_vec(&MinerBlockGossip::from_miner_block(&block)).unwrap();
+    if let Ok(parsed) = MinerBlockGossip::from_json_slice(&gossip) {
+        assert_eq!(parsed.hash, block.hash);
+        assert_eq!(parsed.index, block.index);
+    }
+});
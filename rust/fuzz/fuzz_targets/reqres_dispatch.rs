@@ -0,0 +1,18 @@
+//! Structured requests through the node's request handlers, against an
+//! empty in-memory datastore. Handlers may refuse a request but must not
+//! panic on it.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use modal_datastore::DatastoreManager;
+use modal_node::reqres::{handle_request, Request};
+
+fuzz_target!(|req: Request| {
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    runtime.block_on(async {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        let (consensus_tx, _consensus_rx) = tokio::sync::mpsc::channel(16);
+        let _ = handle_request(req, &mgr, consensus_tx).await;
+    });
+});
@@ -0,0 +1,24 @@
+//! Raw bytes through the request-response message parsers
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use modal_common::wire::{self, WireFormat};
+use modal_node::reqres::{Request, Response};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(req) = Request::from_slice(data) {
+        for format in [WireFormat::Json, WireFormat::Cbor] {
+            let encoded = wire::to_vec(&req, format).unwrap();
+            assert_eq!(Request::from_slice(&encoded).unwrap(), req);
+        }
+    }
+    if let Ok(res) = Response::from_slice(data) {
+        for format in [WireFormat::Json, WireFormat::Cbor] {
+            let encoded = wire::to_vec(&res, format).unwrap();
+            assert_eq!(Response::from_slice(&encoded).unwrap(), res);
+        }
+        // Decompression is bounded, whatever the frame claims
+        let _ = res.decode();
+    }
+});
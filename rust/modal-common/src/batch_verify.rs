@@ -0,0 +1,188 @@
+//! Batched ed25519 signature verification
+//!
+//! Certified blocks carry one signature per ack and checkpoint certificates
+//! one per validator, and a syncing node checks thousands of them. Verifying
+//! them together with ed25519 batch verification costs about half as much
+//! per signature as one at a time. A batch only says whether every signature
+//! in it is valid, so a failed batch is split in half until the bad ones are
+//! found; with mostly honest peers that's rare.
+//!
+//! `VerificationPool` runs batches on blocking threads, a few at a time, so
+//! large syncs use every core and don't stall the networking task.
+
+use anyhow::{anyhow, Result};
+use base64::prelude::*;
+use ed25519_dalek::{PublicKey, Signature, Verifier};
+use libp2p_identity::PeerId;
+use std::sync::{Arc, LazyLock};
+use tokio::sync::Semaphore;
+
+/// Signatures verified together in one batch
+pub const DEFAULT_BATCH_SIZE: usize = 64;
+
+/// A signature to check: `signature` (base64, as made by `Keypair::sign_*`)
+/// over `message` by the ed25519 key of `peer_id`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureCheck {
+    pub peer_id: String,
+    pub message: Vec<u8>,
+    pub signature: String,
+}
+
+impl SignatureCheck {
+    pub fn new(peer_id: &str, message: impl Into<Vec<u8>>, signature: &str) -> Self {
+        Self {
+            peer_id: peer_id.to_string(),
+            message: message.into(),
+            signature: signature.to_string(),
+        }
+    }
+
+    /// A check of a signature made with `Keypair::sign_json`
+    pub fn json(peer_id: &str, json: &serde_json::Value, signature: &str) -> Self {
+        let message = crate::json_stringify_deterministic::stringify_deterministic(json, None);
+        Self::new(peer_id, message, signature)
+    }
+}
+
+/// The ed25519 key a peer ID embeds
+fn public_key(peer_id: &str) -> Result<PublicKey> {
+    let peer_id: PeerId = peer_id.parse().map_err(|e| anyhow!("Invalid peer ID: {:?}", e))?;
+    let key = libp2p_identity::PublicKey::try_decode_protobuf(peer_id.as_ref().digest())?;
+    let key = key.try_into_ed25519().map_err(|e| anyhow!("Not an ed25519 key: {}", e))?;
+    Ok(PublicKey::from_bytes(&key.to_bytes())?)
+}
+
+fn signature(signature: &str) -> Result<Signature> {
+    let bytes = BASE64_STANDARD.decode(signature)?;
+    Ok(Signature::try_from(bytes.as_slice())?)
+}
+
+/// Whether each signature is valid, in order. Signatures that don't decode,
+/// or whose peer ID doesn't embed an ed25519 key, are invalid.
+pub fn verify_batch(checks: &[SignatureCheck]) -> Vec<bool> {
+    let mut valid = vec![false; checks.len()];
+    let mut decoded = Vec::with_capacity(checks.len());
+    for (index, check) in checks.iter().enumerate() {
+        if let (Ok(key), Ok(signature)) = (public_key(&check.peer_id), signature(&check.signature)) {
+            decoded.push((index, check.message.as_slice(), signature, key));
+        }
+    }
+    verify_decoded(&decoded, &mut valid);
+    valid
+}
+
+type Decoded<'a> = (usize, &'a [u8], Signature, PublicKey);
+
+/// Mark the signatures of `decoded` that verify, splitting failed batches
+fn verify_decoded(decoded: &[Decoded], valid: &mut [bool]) {
+    match decoded {
+        [] => {}
+        [(index, message, signature, key)] => {
+            valid[*index] = key.verify(message, signature).is_ok();
+        }
+        _ => {
+            let messages: Vec<&[u8]> = decoded.iter().map(|(_, message, _, _)| *message).collect();
+            let signatures: Vec<Signature> = decoded.iter().map(|(_, _, signature, _)| *signature).collect();
+            let keys: Vec<PublicKey> = decoded.iter().map(|(_, _, _, key)| *key).collect();
+            if ed25519_dalek::verify_batch(&messages, &signatures, &keys).is_ok() {
+                for (index, _, _, _) in decoded {
+                    valid[*index] = true;
+                }
+            } else {
+                let (left, right) = decoded.split_at(decoded.len() / 2);
+                verify_decoded(left, valid);
+                verify_decoded(right, valid);
+            }
+        }
+    }
+}
+
+/// Verifies batches of signatures on blocking threads, at most `workers` at once
+#[derive(Debug, Clone)]
+pub struct VerificationPool {
+    workers: Arc<Semaphore>,
+    batch_size: usize,
+}
+
+impl VerificationPool {
+    pub fn new(workers: usize, batch_size: usize) -> Self {
+        Self {
+            workers: Arc::new(Semaphore::new(workers.max(1))),
+            batch_size: batch_size.max(1),
+        }
+    }
+
+    /// Whether each signature is valid, in order
+    pub async fn verify(&self, checks: Vec<SignatureCheck>) -> Vec<bool> {
+        let mut tasks = Vec::new();
+        for chunk in checks.chunks(self.batch_size) {
+            let permit = self.workers.clone().acquire_owned().await.expect("pool semaphore is never closed");
+            let chunk = chunk.to_vec();
+            let len = chunk.len();
+            let task = tokio::task::spawn_blocking(move || {
+                let _permit = permit;
+                verify_batch(&chunk)
+            });
+            tasks.push((task, len));
+        }
+        let mut valid = Vec::with_capacity(checks.len());
+        for (task, len) in tasks {
+            match task.await {
+                Ok(results) => valid.extend(results),
+                Err(e) => {
+                    log::warn!("Signature verification task failed: {}", e);
+                    valid.resize(valid.len() + len, false);
+                }
+            }
+        }
+        valid
+    }
+
+    /// Whether every signature is valid
+    pub async fn verify_all(&self, checks: Vec<SignatureCheck>) -> bool {
+        self.verify(checks).await.into_iter().all(|valid| valid)
+    }
+}
+
+/// A pool with a worker per core, shared by everything in the process
+pub fn shared() -> &'static VerificationPool {
+    static POOL: LazyLock<VerificationPool> = LazyLock::new(|| {
+        let workers = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4);
+        VerificationPool::new(workers, DEFAULT_BATCH_SIZE)
+    });
+    &POOL
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keypair::Keypair;
+
+    fn signed(keypair: &Keypair, message: &str) -> SignatureCheck {
+        let signature = keypair.sign_string_as_base64_pad(message).unwrap();
+        SignatureCheck::new(&keypair.as_public_address(), message, &signature)
+    }
+
+    #[tokio::test]
+    async fn test_batch_finds_bad_signatures() {
+        let keypairs: Vec<Keypair> = (0..3).map(|_| Keypair::generate().unwrap()).collect();
+        let mut checks: Vec<SignatureCheck> = (0..10)
+            .map(|i| signed(&keypairs[i % 3], &format!("message {}", i)))
+            .collect();
+        assert!(verify_batch(&checks).iter().all(|valid| *valid));
+
+        // Signed by someone else, over other bytes, and not a signature at all
+        checks[2].peer_id = keypairs[0].as_public_address();
+        checks[7].message = b"tampered".to_vec();
+        checks[9].signature = "not base64!".to_string();
+        let expected: Vec<bool> = (0..10).map(|i| ![2, 7, 9].contains(&i)).collect();
+        assert_eq!(verify_batch(&checks), expected);
+
+        // Split over several workers, results stay in order
+        let pool = VerificationPool::new(2, 3);
+        assert_eq!(pool.verify(checks.clone()).await, expected);
+        assert!(!pool.verify_all(checks).await);
+        assert!(pool.verify_all(Vec::new()).await);
+    }
+}
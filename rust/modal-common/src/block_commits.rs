@@ -0,0 +1,172 @@
+//! Contract commits included in mined blocks.
+//!
+//! Miners may include a bounded list of pending contract commit digests in a
+//! block. The header carries the merkle root of the included digests so the
+//! list is covered by proof-of-work, and every node checks the list against
+//! the same count, size and gas limits.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Gas charged for every included commit
+pub const GAS_PER_COMMIT: u64 = 1_000;
+
+/// Gas charged per byte of commit data
+pub const GAS_PER_BYTE: u64 = 16;
+
+/// Minimum gas for a commit of `size` bytes
+pub fn commit_gas(size: u64) -> u64 {
+    GAS_PER_COMMIT.saturating_add(size.saturating_mul(GAS_PER_BYTE))
+}
+
+/// A contract commit included in a block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct CommitDigest {
+    pub contract_id: String,
+    /// sha256 of the commit data
+    pub commit_id: String,
+    /// Size of the commit data in bytes
+    pub size: u64,
+    pub gas: u64,
+}
+
+impl CommitDigest {
+    /// Digest for a commit, charged the minimum gas for its size
+    pub fn new(contract_id: String, commit_id: String, size: u64) -> Self {
+        Self {
+            contract_id,
+            commit_id,
+            size,
+            gas: commit_gas(size),
+        }
+    }
+
+    /// Merkle leaf for this commit
+    pub fn leaf(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(format!("{}:{}:{}:{}", self.contract_id, self.commit_id, self.size, self.gas).as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+/// Per-block bounds on included commits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitLimits {
+    pub max_commits: usize,
+    pub max_bytes: u64,
+    pub max_gas: u64,
+}
+
+impl Default for CommitLimits {
+    fn default() -> Self {
+        Self {
+            max_commits: 256,
+            max_bytes: 256 * 1024,
+            max_gas: 5_000_000,
+        }
+    }
+}
+
+/// Merkle root of the included commits (empty when there are none, so blocks
+/// without commits hash the same as before)
+pub fn commits_root(commits: &[CommitDigest]) -> String {
+    if commits.is_empty() {
+        return String::new();
+    }
+    let leaves: Vec<String> = commits.iter().map(|c| c.leaf()).collect();
+    crate::merkle::compute_merkle_root_owned(&leaves)
+}
+
+/// Take candidates in order while they fit within the limits, skipping any
+/// that would exceed them
+pub fn select_commits(candidates: Vec<CommitDigest>, limits: &CommitLimits) -> Vec<CommitDigest> {
+    let mut selected: Vec<CommitDigest> = Vec::new();
+    let mut bytes = 0u64;
+    let mut gas = 0u64;
+    for commit in candidates {
+        if selected.len() >= limits.max_commits {
+            break;
+        }
+        if selected.iter().any(|c| c.commit_id == commit.commit_id && c.contract_id == commit.contract_id) {
+            continue;
+        }
+        let next_bytes = bytes.saturating_add(commit.size);
+        let next_gas = gas.saturating_add(commit.gas);
+        if next_bytes > limits.max_bytes || next_gas > limits.max_gas {
+            continue;
+        }
+        bytes = next_bytes;
+        gas = next_gas;
+        selected.push(commit);
+    }
+    selected
+}
+
+/// Check included commits against the limits
+pub fn validate_commits(commits: &[CommitDigest], limits: &CommitLimits) -> Result<(), String> {
+    if commits.len() > limits.max_commits {
+        return Err(format!("{} commits exceeds the limit of {}", commits.len(), limits.max_commits));
+    }
+    let mut bytes = 0u64;
+    let mut gas = 0u64;
+    for (i, commit) in commits.iter().enumerate() {
+        if commit.gas < commit_gas(commit.size) {
+            return Err(format!(
+                "commit {} pays {} gas but needs {}",
+                commit.commit_id, commit.gas, commit_gas(commit.size)
+            ));
+        }
+        if commits[..i].iter().any(|c| c.commit_id == commit.commit_id && c.contract_id == commit.contract_id) {
+            return Err(format!("commit {} included twice", commit.commit_id));
+        }
+        bytes = bytes.saturating_add(commit.size);
+        gas = gas.saturating_add(commit.gas);
+    }
+    if bytes > limits.max_bytes {
+        return Err(format!("{} bytes of commits exceeds the limit of {}", bytes, limits.max_bytes));
+    }
+    if gas > limits.max_gas {
+        return Err(format!("{} gas exceeds the limit of {}", gas, limits.max_gas));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digest(id: &str, size: u64) -> CommitDigest {
+        CommitDigest::new("contract".to_string(), id.to_string(), size)
+    }
+
+    #[test]
+    fn test_select_and_validate_commits() {
+        let limits = CommitLimits {
+            max_commits: 3,
+            max_bytes: 100,
+            max_gas: u64::MAX,
+        };
+        let candidates = vec![digest("a", 60), digest("b", 50), digest("a", 60), digest("c", 40), digest("d", 1)];
+
+        // "b" and then "d" don't fit in the byte limit, the duplicate "a" is skipped
+        let selected = select_commits(candidates, &limits);
+        let ids: Vec<&str> = selected.iter().map(|c| c.commit_id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "c"]);
+        assert!(validate_commits(&selected, &limits).is_ok());
+
+        assert!(validate_commits(&[digest("a", 60), digest("b", 50)], &limits).is_err());
+        assert!(validate_commits(&[digest("a", 1), digest("a", 1)], &limits).is_err());
+        let mut underpaid = digest("a", 10);
+        underpaid.gas -= 1;
+        assert!(validate_commits(&[underpaid], &limits).is_err());
+    }
+
+    #[test]
+    fn test_commits_root() {
+        assert_eq!(commits_root(&[]), "");
+        let root = commits_root(&[digest("a", 1), digest("b", 2)]);
+        assert_eq!(root.len(), 64);
+        assert_ne!(root, commits_root(&[digest("b", 2), digest("a", 1)]));
+    }
+}
@@ -0,0 +1,259 @@
+//! BLS12-381 keys for consensus signatures.
+//!
+//! Signatures by many validators on the same message aggregate into a single
+//! signature that verifies against the signers' public keys, so a certificate
+//! carries one signature whatever the size of the committee. Uses the min-pk
+//! variant (48 byte public keys, 96 byte signatures) with the proof of
+//! possession ciphersuite: a public key must come with a valid proof before
+//! it takes part in aggregate verification, which rules out rogue key attacks.
+
+use anyhow::{anyhow, bail, Result};
+use blst::min_pk::{AggregateSignature, PublicKey, SecretKey, Signature};
+use blst::BLST_ERROR;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use zeroize::Zeroizing;
+
+use crate::encrypted_text::EncryptedText;
+
+pub const SCHEME: &str = "bls12-381";
+pub const PUBLIC_KEY_LEN: usize = 48;
+pub const SIGNATURE_LEN: usize = 96;
+
+const SIGNATURE_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+const POP_DST: &[u8] = b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+#[derive(Clone)]
+pub struct BlsKeypair {
+    secret: SecretKey,
+    public: PublicKey,
+}
+
+impl std::fmt::Debug for BlsKeypair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlsKeypair").field("public", &self.public_key_hex()).finish_non_exhaustive()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BlsKeypairJSON {
+    pub scheme: String,
+    pub public_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub private_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encrypted_private_key: Option<String>,
+}
+
+impl BlsKeypair {
+    pub fn generate() -> Result<Self> {
+        use rand::rngs::OsRng;
+        use rand::RngCore;
+
+        let mut ikm = Zeroizing::new([0u8; 32]);
+        OsRng.fill_bytes(ikm.as_mut());
+        Self::from_seed(ikm.as_ref())
+    }
+
+    /// Derive a keypair from at least 32 bytes of key material
+    pub fn from_seed(seed: &[u8]) -> Result<Self> {
+        let secret = SecretKey::key_gen(seed, &[])
+            .map_err(|e| anyhow!("failed to derive BLS key: {:?}", e))?;
+        Ok(Self::from_secret(secret))
+    }
+
+    pub fn from_secret_bytes(bytes: &[u8]) -> Result<Self> {
+        let secret = SecretKey::from_bytes(bytes)
+            .map_err(|e| anyhow!("invalid BLS private key: {:?}", e))?;
+        Ok(Self::from_secret(secret))
+    }
+
+    fn from_secret(secret: SecretKey) -> Self {
+        let public = secret.sk_to_pk();
+        Self { secret, public }
+    }
+
+    pub fn public_key_bytes(&self) -> Vec<u8> {
+        self.public.to_bytes().to_vec()
+    }
+
+    pub fn public_key_hex(&self) -> String {
+        hex::encode(self.public.to_bytes())
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+        self.secret.sign(message, SIGNATURE_DST, &[]).to_bytes().to_vec()
+    }
+
+    /// Proof that we hold the private key, to be checked with `verify_possession`
+    pub fn prove_possession(&self) -> Vec<u8> {
+        let public_key = self.public.to_bytes();
+        self.secret.sign(&public_key, POP_DST, &[]).to_bytes().to_vec()
+    }
+
+    pub fn as_json(&self) -> BlsKeypairJSON {
+        BlsKeypairJSON {
+            scheme: SCHEME.to_string(),
+            public_key: self.public_key_hex(),
+            private_key: Some(hex::encode(self.secret.to_bytes())),
+            encrypted_private_key: None,
+        }
+    }
+
+    pub fn as_encrypted_json(&self, password: &str) -> Result<BlsKeypairJSON> {
+        let private_key = Zeroizing::new(hex::encode(self.secret.to_bytes()));
+        let encrypted_private_key =
+            EncryptedText::encrypt(&private_key, password).map_err(|e| anyhow!(e))?;
+        Ok(BlsKeypairJSON {
+            scheme: SCHEME.to_string(),
+            public_key: self.public_key_hex(),
+            private_key: None,
+            encrypted_private_key: Some(encrypted_private_key),
+        })
+    }
+
+    pub fn as_json_file(&self, path: &str) -> Result<()> {
+        fs::write(path, serde_json::to_string(&self.as_json())?)?;
+        Ok(())
+    }
+
+    pub fn as_encrypted_json_file(&self, path: &str, password: &str) -> Result<()> {
+        fs::write(path, serde_json::to_string(&self.as_encrypted_json(password)?)?)?;
+        Ok(())
+    }
+
+    pub fn from_json(json: &BlsKeypairJSON) -> Result<Self> {
+        if json.scheme != SCHEME {
+            bail!("not a {} keypair: {}", SCHEME, json.scheme);
+        }
+        let private_key = json
+            .private_key
+            .as_ref()
+            .ok_or_else(|| anyhow!("BLS passfile has no private key"))?;
+        let keypair = Self::from_secret_bytes(&hex::decode(private_key)?)?;
+        if keypair.public_key_hex() != json.public_key {
+            bail!("BLS public key does not match the private key");
+        }
+        Ok(keypair)
+    }
+
+    pub fn from_json_file(path: &str) -> Result<Self> {
+        let json: BlsKeypairJSON = serde_json::from_str(&fs::read_to_string(path)?)?;
+        Self::from_json(&json)
+    }
+
+    pub fn from_encrypted_json_file(path: &str, password: &str) -> Result<Self> {
+        let mut json: BlsKeypairJSON = serde_json::from_str(&fs::read_to_string(path)?)?;
+        if let Some(encrypted) = json.encrypted_private_key.take() {
+            json.private_key =
+                Some(EncryptedText::decrypt(&encrypted, password).map_err(|e| anyhow!(e))?);
+        }
+        Self::from_json(&json)
+    }
+}
+
+fn parse_public_key(bytes: &[u8]) -> Result<PublicKey> {
+    PublicKey::key_validate(bytes).map_err(|e| anyhow!("invalid BLS public key: {:?}", e))
+}
+
+fn parse_signature(bytes: &[u8]) -> Result<Signature> {
+    Signature::from_bytes(bytes).map_err(|e| anyhow!("invalid BLS signature: {:?}", e))
+}
+
+/// Verify a single signature
+pub fn verify(public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<()> {
+    let public_key = parse_public_key(public_key)?;
+    match parse_signature(signature)?.verify(true, message, SIGNATURE_DST, &[], &public_key, false) {
+        BLST_ERROR::BLST_SUCCESS => Ok(()),
+        e => bail!("BLS signature verification failed: {:?}", e),
+    }
+}
+
+/// Verify a proof of possession made with `BlsKeypair::prove_possession`
+pub fn verify_possession(public_key: &[u8], proof: &[u8]) -> Result<()> {
+    let key = parse_public_key(public_key)?;
+    match parse_signature(proof)?.verify(true, public_key, POP_DST, &[], &key, false) {
+        BLST_ERROR::BLST_SUCCESS => Ok(()),
+        e => bail!("BLS proof of possession verification failed: {:?}", e),
+    }
+}
+
+/// Aggregate signatures on the same message into one
+pub fn aggregate(signatures: &[&[u8]]) -> Result<Vec<u8>> {
+    if signatures.is_empty() {
+        bail!("no BLS signatures to aggregate");
+    }
+    let signatures = signatures
+        .iter()
+        .map(|signature| parse_signature(signature))
+        .collect::<Result<Vec<_>>>()?;
+    let refs: Vec<&Signature> = signatures.iter().collect();
+    let aggregate = AggregateSignature::aggregate(&refs, true)
+        .map_err(|e| anyhow!("failed to aggregate BLS signatures: {:?}", e))?;
+    Ok(aggregate.to_signature().to_bytes().to_vec())
+}
+
+/// Verify an aggregate of signatures on `message` by every one of `public_keys`.
+/// The keys must have had their proofs of possession verified.
+pub fn verify_aggregate(public_keys: &[&[u8]], message: &[u8], signature: &[u8]) -> Result<()> {
+    if public_keys.is_empty() {
+        bail!("no BLS public keys to verify against");
+    }
+    let public_keys = public_keys
+        .iter()
+        .map(|key| parse_public_key(key))
+        .collect::<Result<Vec<_>>>()?;
+    let refs: Vec<&PublicKey> = public_keys.iter().collect();
+    match parse_signature(signature)?.fast_aggregate_verify(true, message, SIGNATURE_DST, &refs) {
+        BLST_ERROR::BLST_SUCCESS => Ok(()),
+        e => bail!("BLS aggregate signature verification failed: {:?}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let keypair = BlsKeypair::from_seed(&[7u8; 32]).unwrap();
+        let signature = keypair.sign(b"header");
+
+        assert_eq!(keypair.public_key_bytes().len(), PUBLIC_KEY_LEN);
+        assert_eq!(signature.len(), SIGNATURE_LEN);
+        assert!(verify(&keypair.public_key_bytes(), b"header", &signature).is_ok());
+        assert!(verify(&keypair.public_key_bytes(), b"other", &signature).is_err());
+        assert!(verify_possession(&keypair.public_key_bytes(), &keypair.prove_possession()).is_ok());
+    }
+
+    #[test]
+    fn test_aggregate_verifies_against_all_signers() {
+        let keypairs: Vec<BlsKeypair> = (1..=4u8)
+            .map(|seed| BlsKeypair::from_seed(&[seed; 32]).unwrap())
+            .collect();
+        let signatures: Vec<Vec<u8>> = keypairs[..3].iter().map(|k| k.sign(b"header")).collect();
+        let aggregate =
+            aggregate(&signatures.iter().map(Vec::as_slice).collect::<Vec<_>>()).unwrap();
+        assert_eq!(aggregate.len(), SIGNATURE_LEN);
+
+        let keys: Vec<Vec<u8>> = keypairs.iter().map(BlsKeypair::public_key_bytes).collect();
+        let signers: Vec<&[u8]> = keys[..3].iter().map(Vec::as_slice).collect();
+        assert!(verify_aggregate(&signers, b"header", &aggregate).is_ok());
+        assert!(verify_aggregate(&signers, b"other", &aggregate).is_err());
+
+        // Claiming a validator that did not sign
+        let claimed: Vec<&[u8]> = keys[1..].iter().map(Vec::as_slice).collect();
+        assert!(verify_aggregate(&claimed, b"header", &aggregate).is_err());
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        let keypair = BlsKeypair::generate().unwrap();
+        let restored = BlsKeypair::from_json(&keypair.as_json()).unwrap();
+        assert_eq!(restored.public_key_hex(), keypair.public_key_hex());
+
+        let encrypted = keypair.as_encrypted_json("password").unwrap();
+        assert!(encrypted.private_key.is_none());
+        assert!(BlsKeypair::from_json(&encrypted).is_err());
+    }
+}
@@ -0,0 +1,68 @@
+//! Canonical JSON for anything that is hashed or signed
+//!
+//! Default serde serialization writes struct fields in declaration order, so
+//! the same commit hashes differently depending on whether it went through a
+//! struct or a `serde_json::Value` (whose keys are sorted). Anything whose
+//! hash or signature another node has to reproduce goes through
+//! [`CanonicalHash`] instead, which encodes with
+//! [`stringify_deterministic`]: keys sorted at every level, no whitespace.
+
+use anyhow::Result;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::json_stringify_deterministic::stringify_deterministic;
+
+/// Deterministic JSON encoding and SHA-256 for any serializable value
+pub trait CanonicalHash: Serialize {
+    /// The value as deterministic JSON
+    fn canonical_json(&self) -> Result<String> {
+        Ok(stringify_deterministic(&serde_json::to_value(self)?, None))
+    }
+
+    /// Hex SHA-256 of the canonical JSON
+    fn canonical_hash(&self) -> Result<String> {
+        Ok(sha256_hex(self.canonical_json()?.as_bytes()))
+    }
+}
+
+impl<T: Serialize + ?Sized> CanonicalHash for T {}
+
+/// Hex SHA-256 of `bytes`
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Serialize)]
+    struct Unsorted {
+        zebra: u32,
+        apple: Option<String>,
+        nested: Nested,
+    }
+
+    #[derive(Serialize)]
+    struct Nested {
+        y: bool,
+        x: Vec<u32>,
+    }
+
+    #[test]
+    fn test_struct_and_value_hash_alike() {
+        let value = Unsorted {
+            zebra: 1,
+            apple: None,
+            nested: Nested { y: true, x: vec![2, 1] },
+        };
+        assert_eq!(
+            value.canonical_json().unwrap(),
+            r#"{"apple":null,"nested":{"x":[2,1],"y":true},"zebra":1}"#
+        );
+        let same = json!({"nested": {"y": true, "x": [2, 1]}, "zebra": 1, "apple": null});
+        assert_eq!(value.canonical_hash().unwrap(), same.canonical_hash().unwrap());
+    }
+}
@@ -1,9 +1,16 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use sha2::{Digest, Sha256};
 use std::path::Path;
 
+use crate::canonical::{sha256_hex, CanonicalHash};
+
+/// Largest commit file accepted, in bytes of JSON
+pub const MAX_COMMIT_FILE_BYTES: usize = 1024 * 1024;
+
+/// Most actions a commit may contain
+pub const MAX_COMMIT_ACTIONS: usize = 1024;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommitFile {
     pub body: Vec<CommitAction>,
@@ -32,6 +39,7 @@ pub struct CommitHead {
 
 /// A rule that applies only to the commit it's attached to
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct RuleForThisCommit {
     /// The formula to evaluate (e.g., "signed_by_n(2, [/users/alice.id, /users/bob.id])")
     pub formula: String,
@@ -72,16 +80,37 @@ impl CommitFile {
         self.body.push(CommitAction { method, path, value });
     }
 
+    /// SHA-256 of the commit's canonical JSON, the same id a node computes
+    /// from the commit's body and head
     pub fn compute_id(&self) -> Result<String> {
-        let json = serde_json::to_string(self)?;
-        let mut hasher = Sha256::new();
-        hasher.update(json.as_bytes());
-        Ok(format!("{:x}", hasher.finalize()))
+        self.canonical_hash()
+    }
+
+    /// The commit as it is sent to peers, whose hash is its id
+    pub fn payload(&self) -> Result<Vec<u8>> {
+        Ok(self.canonical_json()?.into_bytes())
+    }
+
+    /// What signers sign: the body as canonical JSON
+    pub fn signing_payload(&self) -> Result<String> {
+        self.body.canonical_json()
+    }
+
+    /// SHA-256 of the signing payload, for checking that two machines are
+    /// looking at the same commit before one of them signs it
+    pub fn signing_digest(&self) -> Result<String> {
+        Ok(sha256_hex(self.signing_payload()?.as_bytes()))
     }
 
     pub fn load(path: &Path) -> Result<Self> {
-        let content = std::fs::read_to_string(path)?;
-        let commit: CommitFile = serde_json::from_str(&content)?;
+        Self::from_slice(&std::fs::read(path)?)
+    }
+
+    /// Parse a commit received from a peer or read from disk, within the
+    /// size and action limits
+    pub fn from_slice(data: &[u8]) -> Result<Self> {
+        let commit: CommitFile = crate::wire::from_json_slice("commit file", data, MAX_COMMIT_FILE_BYTES)?;
+        crate::wire::check_len("commit body", commit.body.len(), MAX_COMMIT_ACTIONS)?;
         Ok(commit)
     }
 
@@ -414,6 +443,85 @@ fn is_valid_datetime(s: &str) -> bool {
     }
     
     // Validate the date part
-    is_valid_date(&s[..10])
+    s.get(..10).is_some_and(is_valid_date)
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for CommitAction {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        const METHODS: &[&str] = &["create", "send", "recv", "invoke", "post", "rule", "repost", "genesis"];
+        let method = if u.ratio(1, 8)? {
+            u.arbitrary()?
+        } else {
+            u.choose(METHODS)?.to_string()
+        };
+        let path = if u.arbitrary()? {
+            let name: String = u.arbitrary()?;
+            Some(format!("{}{}", name, u.choose(KNOWN_EXTENSIONS)?))
+        } else {
+            u.arbitrary()?
+        };
+        Ok(Self {
+            method,
+            path,
+            value: crate::wire::arbitrary_json(u, 3)?,
+        })
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for CommitHead {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let json = |u: &mut arbitrary::Unstructured<'a>| -> arbitrary::Result<Option<Value>> {
+            Ok(if u.arbitrary()? { Some(crate::wire::arbitrary_json(u, 3)?) } else { None })
+        };
+        Ok(Self {
+            parent: u.arbitrary()?,
+            signatures: json(u)?,
+            evolution: json(u)?,
+            rule_for_this_commit: u.arbitrary()?,
+        })
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for CommitFile {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self {
+            body: u.arbitrary()?,
+            head: u.arbitrary()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_slice_limits() {
+        let mut commit = CommitFile::new();
+        commit.add_action("post".to_string(), Some("/name.text".to_string()), Value::from("alice"));
+        let parsed = CommitFile::from_slice(&serde_json::to_vec(&commit).unwrap()).unwrap();
+        assert_eq!(parsed.compute_id().unwrap(), commit.compute_id().unwrap());
+
+        assert!(CommitFile::from_slice(b"{\"body\": [").is_err());
+        assert!(CommitFile::from_slice(&vec![b' '; MAX_COMMIT_FILE_BYTES + 1]).is_err());
+        for _ in 0..MAX_COMMIT_ACTIONS {
+            commit.add_action("post".to_string(), Some("/name.text".to_string()), Value::from("alice"));
+        }
+        assert!(CommitFile::from_slice(&serde_json::to_vec(&commit).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_multibyte_datetime() {
+        // The tenth byte falls inside a multi-byte character
+        let action = CommitAction {
+            method: "post".to_string(),
+            path: Some("/at.datetime".to_string()),
+            value: Value::from("2024-01-1€T10:30:00Z"),
+        };
+        assert!(action.validate().is_err());
+    }
 }
 
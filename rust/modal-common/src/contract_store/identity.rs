@@ -0,0 +1,237 @@
+//! Identity documents
+//!
+//! An identity publishes a signed document at `/ids/<id>.json` in a contract,
+//! listing the keys that currently sign for it, where to reach it, and where
+//! to check its status:
+//!
+//! ```json
+//! {
+//!   "id": "12D3KooW...",
+//!   "version": 2,
+//!   "keys": ["12D3KooWnew..."],
+//!   "services": [{ "type": "hub", "url": "https://hub.example.com" }],
+//!   "status_url": "https://example.com/status",
+//!   "updated_at": 1760486400,
+//!   "signatures": { "12D3KooWold...": "..." }
+//! }
+//! ```
+//!
+//! A document is accepted if it's signed by the identity itself or by one of
+//! the keys in the version it replaces, which is how keys are rotated while
+//! the root key stays offline. `signed_by(<id>)` is then satisfied by any of
+//! the identity's current keys.
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::json_stringify_deterministic::stringify_deterministic;
+use crate::keypair::Keypair;
+use crate::signer::Signer;
+
+/// Directory in contract state holding identity documents
+pub const IDS_DIR: &str = "ids";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceEndpoint {
+    #[serde(rename = "type")]
+    pub service_type: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdentityDocument {
+    /// The identity's root public key id
+    pub id: String,
+    /// Increases with every publication
+    pub version: u64,
+    /// Keys that currently sign for the identity
+    #[serde(default)]
+    pub keys: Vec<String>,
+    #[serde(default)]
+    pub services: Vec<ServiceEndpoint>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_url: Option<String>,
+    /// Unix timestamp
+    #[serde(default)]
+    pub updated_at: u64,
+    /// Signer id → base64 signature over the document without `signatures`
+    #[serde(default)]
+    pub signatures: BTreeMap<String, String>,
+}
+
+impl IdentityDocument {
+    pub fn new(id: &str, version: u64) -> Self {
+        Self {
+            id: id.to_string(),
+            version,
+            keys: Vec::new(),
+            services: Vec::new(),
+            status_url: None,
+            updated_at: 0,
+            signatures: BTreeMap::new(),
+        }
+    }
+
+    /// Canonical JSON of the document without its signatures
+    pub fn signing_payload(&self) -> Result<String> {
+        let mut value = serde_json::to_value(self)?;
+        if let Some(obj) = value.as_object_mut() {
+            obj.remove("signatures");
+        }
+        Ok(stringify_deterministic(&value, None))
+    }
+
+    pub fn sign(&mut self, signer: &dyn Signer) -> Result<()> {
+        let signature = signer.sign_string_as_base64_pad(&self.signing_payload()?)?;
+        self.signatures.insert(signer.public_key_as_base58_identity(), signature);
+        Ok(())
+    }
+
+    /// Ids whose signatures check out. Fails if any signature is bad.
+    pub fn verify_signatures(&self) -> Result<Vec<String>> {
+        let payload = self.signing_payload()?;
+        let mut signers = Vec::new();
+        for (public_key, signature) in &self.signatures {
+            let keypair = Keypair::from_public_key(public_key, "ed25519")?;
+            if !keypair.verify_signature_for_string(signature, &payload)? {
+                bail!("Invalid signature from {} on identity {}", public_key, self.id);
+            }
+            signers.push(public_key.clone());
+        }
+        Ok(signers)
+    }
+
+    /// Whether `key` signs for this identity
+    pub fn authorizes(&self, key: &str) -> bool {
+        key == self.id || self.keys.iter().any(|k| k == key)
+    }
+}
+
+/// Contract path of an identity's document, e.g. `/ids/<id>.json`
+pub fn document_path(id: &str) -> String {
+    format!("/{}/{}.json", IDS_DIR, id)
+}
+
+/// The identity a path names, if it's an identity document path
+pub fn id_from_path(path: &str) -> Option<&str> {
+    path.trim_start_matches('/')
+        .strip_prefix(IDS_DIR)?
+        .strip_prefix('/')?
+        .strip_suffix(".json")
+        .filter(|id| !id.is_empty() && !id.contains('/'))
+}
+
+/// The published document of `id` in contract state, if any
+pub fn resolve(state: &Value, id: &str) -> Option<IdentityDocument> {
+    let path = document_path(id);
+    let value = state.get(path.trim_start_matches('/'))?;
+    serde_json::from_value(value.clone()).ok()
+}
+
+/// Keys that sign for `id`: the id itself plus its published keys
+pub fn resolve_keys(state: &Value, id: &str) -> Vec<String> {
+    let mut keys = vec![id.to_string()];
+    if let Some(document) = resolve(state, id) {
+        keys.extend(document.keys.into_iter().filter(|k| k != id));
+    }
+    keys
+}
+
+/// Check a document posted to `path` against the one it replaces in `state`
+pub fn validate_publication(state: &Value, path: &str, value: &Value) -> Result<()> {
+    let id = id_from_path(path)
+        .ok_or_else(|| anyhow!("{} isn't an identity document path", path))?;
+    let document: IdentityDocument = serde_json::from_value(value.clone())
+        .map_err(|e| anyhow!("Invalid identity document at {}: {}", path, e))?;
+    if document.id != id {
+        bail!("Identity document for {} posted to {}", document.id, path);
+    }
+
+    let signers = document.verify_signatures()?;
+    let previous = resolve(state, id);
+    let authorized = |key: &String| match &previous {
+        Some(previous) => previous.authorizes(key),
+        None => key == id,
+    };
+    if !signers.iter().any(authorized) {
+        bail!(
+            "Identity document for {} must be signed by {}",
+            id,
+            if previous.is_some() { "the identity or one of its current keys" } else { "the identity" }
+        );
+    }
+
+    if let Some(previous) = previous {
+        if document.version <= previous.version {
+            bail!(
+                "Identity document for {} has version {}, but version {} is already published",
+                id,
+                document.version,
+                previous.version
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn state_with(document: &IdentityDocument) -> Value {
+        let path = document_path(&document.id);
+        json!({ path.trim_start_matches('/'): serde_json::to_value(document).unwrap() })
+    }
+
+    #[test]
+    fn test_id_from_path() {
+        assert_eq!(id_from_path("/ids/abc.json"), Some("abc"));
+        assert_eq!(id_from_path("ids/abc.json"), Some("abc"));
+        assert_eq!(id_from_path("/ids/abc.id"), None);
+        assert_eq!(id_from_path("/ids/a/b.json"), None);
+        assert_eq!(id_from_path("/users/abc.json"), None);
+    }
+
+    #[test]
+    fn test_publish_and_rotate() {
+        let root = Keypair::generate().unwrap();
+        let old_key = Keypair::generate().unwrap();
+        let new_key = Keypair::generate().unwrap();
+        let id = root.as_public_address();
+        let path = document_path(&id);
+
+        // First version must be signed by the root key
+        let mut first = IdentityDocument::new(&id, 1);
+        first.keys = vec![old_key.as_public_address()];
+        first.sign(&old_key).unwrap();
+        assert!(validate_publication(&Value::Null, &path, &json!(first)).is_err());
+        first.signatures.clear();
+        first.sign(&root).unwrap();
+        validate_publication(&Value::Null, &path, &json!(first)).unwrap();
+        let state = state_with(&first);
+
+        // The current key rotates itself out
+        let mut second = IdentityDocument::new(&id, 2);
+        second.keys = vec![new_key.as_public_address()];
+        second.sign(&old_key).unwrap();
+        validate_publication(&state, &path, &json!(second)).unwrap();
+
+        // Replays and tampering are rejected
+        let mut stale = second.clone();
+        stale.version = 1;
+        assert!(validate_publication(&state, &path, &json!(stale)).is_err());
+        let mut tampered = second.clone();
+        tampered.keys.push("someone-else".into());
+        assert!(validate_publication(&state, &path, &json!(tampered)).is_err());
+
+        let state = state_with(&second);
+        assert_eq!(resolve_keys(&state, &id), vec![id.clone(), new_key.as_public_address()]);
+        // Keys that were rotated out no longer count
+        let mut third = IdentityDocument::new(&id, 3);
+        third.sign(&old_key).unwrap();
+        assert!(validate_publication(&state, &path, &json!(third)).is_err());
+    }
+}
@@ -2,6 +2,8 @@ pub mod config;
 pub mod commit_file;
 pub mod refs;
 pub mod one_step_rule;
+pub mod offline_commit;
+pub mod identity;
 
 #[cfg(test)]
 mod tests;
@@ -12,6 +14,8 @@ use std::path::{Path, PathBuf};
 pub use config::ContractConfig;
 pub use commit_file::{CommitFile, RuleForThisCommit};
 pub use refs::Refs;
+pub use offline_commit::OfflineCommit;
+pub use identity::IdentityDocument;
 pub use one_step_rule::{
     CommitSignature, CommitRuleFormula,
     parse_formula, parse_signatures,
@@ -489,7 +493,24 @@ impl ContractStore {
         use crate::contract_store::one_step_rule::EvalContext;
         
         // Build current state and collect rules
-        let (state, rules) = self.build_state_and_rules()?;
+        let (state, rules, height) = self.build_state_and_rules()?;
+        let height = height + 1;
+        
+        // Identity documents and key rotations are checked whether or not
+        // there are rules
+        for action in &commit.body {
+            if let Some(path) = &action.path {
+                if action.method != "post" {
+                    continue;
+                }
+                if identity::id_from_path(path).is_some() {
+                    identity::validate_publication(&state, path, &action.value)?;
+                }
+                if let Some(old_key) = crate::key_rotation::old_key_from_path(path) {
+                    self.validate_key_rotation(&state, height, old_key, &action.value)?;
+                }
+            }
+        }
         
         if rules.is_empty() {
             return Ok(()); // No rules to validate against
@@ -502,7 +523,7 @@ impl ContractStore {
         let body_value = serde_json::to_value(&commit.body)?;
         
         // Create evaluation context
-        let ctx = EvalContext::new(&signers, &state, &body_value);
+        let ctx = EvalContext::new(&signers, &state, &body_value).at_height(height);
         
         // Validate each rule
         for rule_content in &rules {
@@ -512,8 +533,53 @@ impl ContractStore {
         Ok(())
     }
     
-    /// Build current state and collect all rules from commits
-    fn build_state_and_rules(&self) -> Result<(serde_json::Value, Vec<String>)> {
+    /// Number of commits from genesis to HEAD
+    pub fn height(&self) -> Result<u64> {
+        let mut height = 0;
+        let mut current = self.get_head()?;
+        while let Some(commit_id) = current {
+            height += 1;
+            current = self.load_commit(&commit_id)?.head.parent;
+        }
+        Ok(height)
+    }
+    
+    /// The identity document `id` has published in this contract, if any
+    pub fn resolve_identity(&self, id: &str) -> Result<Option<IdentityDocument>> {
+        let (state, _, _) = self.build_state_and_rules()?;
+        Ok(identity::resolve(&state, id))
+    }
+    
+    /// Check a rotation record posted for `old_key` by the commit at `height`
+    fn validate_key_rotation(
+        &self,
+        state: &serde_json::Value,
+        height: u64,
+        old_key: &str,
+        value: &serde_json::Value,
+    ) -> Result<()> {
+        use crate::key_rotation::{KeyHistory, KeyRotation};
+        
+        let rotation: KeyRotation = serde_json::from_value(value.clone())
+            .map_err(|e| anyhow::anyhow!("Invalid key rotation for {}: {}", old_key, e))?;
+        if rotation.old_key != old_key {
+            anyhow::bail!("Rotation of {} posted at {}", rotation.old_key, rotation.path());
+        }
+        // Signatures already made by the old key must stay valid
+        if rotation.effective_height < height {
+            anyhow::bail!(
+                "Rotation of {} takes effect at height {}, before this commit at {}",
+                old_key,
+                rotation.effective_height,
+                height
+            );
+        }
+        KeyHistory::from_state(state).check(&rotation)
+    }
+    
+    /// Build current state and collect all rules from commits, along with
+    /// the number of commits
+    fn build_state_and_rules(&self) -> Result<(serde_json::Value, Vec<String>, u64)> {
         use std::collections::HashMap;
         
         let mut state: HashMap<String, serde_json::Value> = HashMap::new();
@@ -522,7 +588,7 @@ impl ContractStore {
         // Get all commits in order (oldest first)
         let head = self.get_head()?;
         if head.is_none() {
-            return Ok((serde_json::json!({}), rules));
+            return Ok((serde_json::json!({}), rules, 0));
         }
         
         // Collect commits from HEAD to genesis
@@ -536,6 +602,7 @@ impl ContractStore {
         
         // Replay in order (oldest first)
         commits.reverse();
+        let height = commits.len() as u64;
         for commit in commits {
             for action in &commit.body {
                 if let Some(path) = &action.path {
@@ -556,7 +623,7 @@ impl ContractStore {
             }
         }
         
-        Ok((serde_json::json!(state), rules))
+        Ok((serde_json::json!(state), rules, height))
     }
     
     /// Extract signer identities from commit signatures
@@ -0,0 +1,105 @@
+//! Commits signed away from the contract
+//!
+//! `modal contract commit --offline` writes the commit it would make to a
+//! file instead of the contract; the file is signed on another (possibly
+//! air-gapped) machine and brought back with `modal contract push --signed`.
+//! Every step shows the commit's signing digest so the machines can be
+//! checked to be looking at the same commit.
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::contract_store::CommitFile;
+use crate::keypair::Keypair;
+use crate::signer::Signer;
+
+pub const OFFLINE_COMMIT_TYPE: &str = "modality-offline-commit";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfflineCommit {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub contract_id: String,
+    pub commit: CommitFile,
+    /// Signing digest of the commit when the file was written
+    pub digest: String,
+}
+
+impl OfflineCommit {
+    pub fn new(contract_id: String, commit: CommitFile) -> Result<Self> {
+        let digest = commit.signing_digest()?;
+        Ok(Self {
+            kind: OFFLINE_COMMIT_TYPE.to_string(),
+            contract_id,
+            commit,
+            digest,
+        })
+    }
+
+    /// Load an offline commit, checking it hasn't changed since it was
+    /// written
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let offline: Self = serde_json::from_str(&content)
+            .with_context(|| format!("{} isn't an offline commit", path.display()))?;
+        if offline.kind != OFFLINE_COMMIT_TYPE {
+            return Err(anyhow!("{} isn't an offline commit", path.display()));
+        }
+        let digest = offline.commit.signing_digest()?;
+        if digest != offline.digest {
+            return Err(anyhow!(
+                "{} was modified: its commit's digest is {}, not {}",
+                path.display(),
+                digest,
+                offline.digest
+            ));
+        }
+        Ok(offline)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Add a signature, keeping any others; returns the signer's ID
+    pub fn sign(&mut self, signer: &dyn Signer) -> Result<String> {
+        let public_key = signer.public_key_as_base58_identity();
+        let signature = signer.sign_string_as_base64_pad(&self.commit.signing_payload()?)?;
+        let signatures = self
+            .commit
+            .head
+            .signatures
+            .get_or_insert_with(|| serde_json::json!({}));
+        let signatures = signatures
+            .as_object_mut()
+            .ok_or_else(|| anyhow!("The commit's signatures aren't an object"))?;
+        signatures.insert(public_key.clone(), serde_json::Value::String(signature));
+        Ok(public_key)
+    }
+
+    /// Check every signature against the commit; returns the signers' IDs
+    pub fn verify_signatures(&self) -> Result<Vec<String>> {
+        let Some(signatures) = &self.commit.head.signatures else {
+            return Ok(Vec::new());
+        };
+        let signatures = signatures
+            .as_object()
+            .ok_or_else(|| anyhow!("The commit's signatures aren't an object"))?;
+        let payload = self.commit.signing_payload()?;
+        let mut signers = Vec::new();
+        for (public_key, signature) in signatures {
+            let signature = signature
+                .as_str()
+                .ok_or_else(|| anyhow!("Signature of {} isn't a string", public_key))?;
+            let keypair = Keypair::from_public_key(public_key, "ed25519")?;
+            if !keypair.verify_signature_for_string(signature, &payload)? {
+                return Err(anyhow!("Invalid signature from {}", public_key));
+            }
+            signers.push(public_key.clone());
+        }
+        Ok(signers)
+    }
+}
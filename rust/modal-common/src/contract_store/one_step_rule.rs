@@ -23,6 +23,8 @@ use anyhow::{Result, bail};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::key_rotation::KeyHistory;
+
 /// Signature entry in a commit
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommitSignature {
@@ -189,6 +191,8 @@ pub struct EvalContext<'a> {
     pub state: &'a Value,
     /// Paths modified by the commit body
     pub modified_paths: Vec<String>,
+    /// Height of the commit, for key rotations (None: the latest keys)
+    pub height: Option<u64>,
 }
 
 impl<'a> Default for EvalContext<'a> {
@@ -198,6 +202,7 @@ impl<'a> Default for EvalContext<'a> {
             signers: &[],
             state: &EMPTY,
             modified_paths: Vec::new(),
+            height: None,
         }
     }
 }
@@ -205,7 +210,34 @@ impl<'a> Default for EvalContext<'a> {
 impl<'a> EvalContext<'a> {
     pub fn new(signers: &'a [String], state: &'a Value, commit_body: &Value) -> Self {
         let modified_paths = extract_modified_paths(commit_body);
-        Self { signers, state, modified_paths }
+        Self { signers, state, modified_paths, height: None }
+    }
+
+    /// Evaluate as of the commit at `height`
+    pub fn at_height(mut self, height: u64) -> Self {
+        self.height = Some(height);
+        self
+    }
+
+    /// Whether `identity` signed, either directly or through one of the keys
+    /// its identity document lists. Keys rotated out by this height no longer
+    /// count, their successors do. `identity` may also be a path to an `.id`
+    /// value in state.
+    pub fn is_signed_by(&self, identity: &str) -> bool {
+        let history = KeyHistory::from_state(self.state);
+        let height = self.height.unwrap_or(u64::MAX);
+        if self.signers.iter().any(|s| s == identity) && history.key_at(identity, height) == identity {
+            return true;
+        }
+        let id = self.state
+            .get(identity.trim_start_matches('/'))
+            .and_then(|v| v.as_str())
+            .filter(|_| identity.ends_with(".id"))
+            .unwrap_or(identity);
+        super::identity::resolve_keys(self.state, id)
+            .iter()
+            .map(|key| history.key_at(key, height))
+            .any(|key| self.signers.contains(&key))
     }
 }
 
@@ -237,6 +269,7 @@ pub fn evaluate_formula(
         signers: present_signers,
         state: &Value::Null,
         modified_paths: Vec::new(),
+        height: None,
     };
     evaluate_formula_full(formula, &ctx)
 }
@@ -251,6 +284,7 @@ pub fn evaluate_formula_with_state(
         signers: present_signers,
         state: contract_state,
         modified_paths: Vec::new(),
+        height: None,
     };
     evaluate_formula_full(formula, &ctx)
 }
@@ -263,19 +297,19 @@ pub fn evaluate_formula_full(
     match formula {
         CommitRuleFormula::SignedByN { required, signers } => {
             let count = signers.iter()
-                .filter(|s| ctx.signers.contains(s))
+                .filter(|s| ctx.is_signed_by(s))
                 .count();
             count >= *required
         }
         CommitRuleFormula::SignedBy(signer) => {
-            ctx.signers.contains(signer)
+            ctx.is_signed_by(signer)
         }
         CommitRuleFormula::AllSigned(path) => {
             let members = resolve_path_as_strings(ctx.state, path);
             if members.is_empty() {
                 true
             } else {
-                members.iter().all(|m| ctx.signers.contains(m))
+                members.iter().all(|m| ctx.is_signed_by(m))
             }
         }
         CommitRuleFormula::AnySigned(path) => {
@@ -283,7 +317,7 @@ pub fn evaluate_formula_full(
             if members.is_empty() {
                 true
             } else {
-                members.iter().any(|m| ctx.signers.contains(m))
+                members.iter().any(|m| ctx.is_signed_by(m))
             }
         }
         CommitRuleFormula::Modifies(prefix) => {
@@ -771,4 +805,41 @@ mod tests {
         assert!(!evaluate_formula_full(&modifies_members, &ctx3));
         // all_signed still fails but doesn't matter since we're not modifying members
     }
+
+    #[test]
+    fn test_signed_by_rotated_key() {
+        let state = serde_json::json!({
+            "users/alice.id": "alice_root",
+            "ids/alice_root.json": { "id": "alice_root", "version": 2, "keys": ["alice_laptop"] }
+        });
+        let body = serde_json::json!([]);
+        let signers = vec!["alice_laptop".to_string()];
+        let ctx = EvalContext::new(&signers, &state, &body);
+
+        assert!(ctx.is_signed_by("alice_root"));
+        assert!(ctx.is_signed_by("/users/alice.id"));
+        assert!(!ctx.is_signed_by("bob_root"));
+        assert!(evaluate_formula_full(&parse_formula("signed_by(alice_root)").unwrap(), &ctx));
+    }
+
+    #[test]
+    fn test_signed_by_after_key_rotation() {
+        use crate::key_rotation::KeyRotation;
+        use crate::keypair::Keypair;
+
+        let old = Keypair::generate().unwrap();
+        let (old_key, new_key) = (old.as_public_address(), Keypair::generate().unwrap().as_public_address());
+        let rotation = KeyRotation::sign(&old, &new_key, 5, None).unwrap();
+        let state = serde_json::json!({ rotation.path().trim_start_matches('/'): rotation });
+        let body = serde_json::json!([]);
+        let formula = parse_formula(&format!("signed_by({})", old_key)).unwrap();
+
+        let old_signers = vec![old_key.clone()];
+        let new_signers = vec![new_key.clone()];
+        // The old key signs until the rotation takes effect, the new key after
+        assert!(evaluate_formula_full(&formula, &EvalContext::new(&old_signers, &state, &body).at_height(4)));
+        assert!(!evaluate_formula_full(&formula, &EvalContext::new(&old_signers, &state, &body).at_height(5)));
+        assert!(!evaluate_formula_full(&formula, &EvalContext::new(&new_signers, &state, &body).at_height(4)));
+        assert!(evaluate_formula_full(&formula, &EvalContext::new(&new_signers, &state, &body).at_height(5)));
+    }
 }
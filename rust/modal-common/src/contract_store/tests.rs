@@ -396,3 +396,81 @@ fn test_parse_repost_path_fails_with_empty_remote_path() {
     assert!(parse_repost_path("$abc123:").is_err());
 }
 
+
+#[test]
+fn test_offline_commit_signing() {
+    use crate::contract_store::OfflineCommit;
+    use crate::keypair::Keypair;
+
+    let mut commit = CommitFile::with_parent("abc".to_string());
+    commit.add_action("post".to_string(), Some("/data.text".to_string()), json!("hello"));
+    let mut offline = OfflineCommit::new("contract1".to_string(), commit).unwrap();
+    let digest = offline.digest.clone();
+    assert!(offline.verify_signatures().unwrap().is_empty());
+
+    let alice = Keypair::generate().unwrap();
+    let bob = Keypair::generate().unwrap();
+    offline.sign(&alice).unwrap();
+    offline.sign(&bob).unwrap();
+
+    // Signatures don't change the digest, and both verify
+    assert_eq!(offline.commit.signing_digest().unwrap(), digest);
+    let mut signers = offline.verify_signatures().unwrap();
+    signers.sort();
+    let mut expected = vec![alice.as_public_address(), bob.as_public_address()];
+    expected.sort();
+    assert_eq!(signers, expected);
+
+    // A changed body invalidates them
+    offline.commit.body[0].value = json!("goodbye");
+    assert!(offline.verify_signatures().is_err());
+}
+
+#[test]
+fn test_offline_commit_load_detects_changes() {
+    use crate::contract_store::OfflineCommit;
+
+    let path = std::env::temp_dir().join(format!("offline-commit-{}.json", std::process::id()));
+    let mut commit = CommitFile::new();
+    commit.add_action("post".to_string(), Some("/data.text".to_string()), json!("hello"));
+    let offline = OfflineCommit::new("contract1".to_string(), commit).unwrap();
+    offline.save(&path).unwrap();
+    assert!(OfflineCommit::load(&path).is_ok());
+
+    let tampered = std::fs::read_to_string(&path).unwrap().replace("hello", "goodbye");
+    std::fs::write(&path, tampered).unwrap();
+    assert!(OfflineCommit::load(&path).is_err());
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_key_rotation_commits() {
+    use crate::contract_store::ContractStore;
+    use crate::key_rotation::KeyRotation;
+    use crate::keypair::Keypair;
+
+    let dir = std::env::temp_dir().join(format!("key-rotation-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let store = ContractStore::init(&dir, "contract1".to_string()).unwrap();
+    let old = Keypair::generate().unwrap();
+    let new_key = Keypair::generate().unwrap().as_public_address();
+
+    let mut first = CommitFile::new();
+    first.add_action("post".to_string(), Some("/data.text".to_string()), json!("hello"));
+    store.validate_commit_against_rules(&first).unwrap();
+    let first_id = first.compute_id().unwrap();
+    store.save_commit(&first_id, &first).unwrap();
+    store.set_head(&first_id).unwrap();
+
+    // The next commit is at height 2, so the rotation can't take effect at 1
+    let rotation_commit = |effective_height| {
+        let rotation = KeyRotation::sign(&old, &new_key, effective_height, None).unwrap();
+        let mut commit = CommitFile::with_parent(first_id.clone());
+        commit.add_action("post".to_string(), Some(rotation.path()), json!(rotation));
+        commit
+    };
+    assert!(store.validate_commit_against_rules(&rotation_commit(1)).is_err());
+    store.validate_commit_against_rules(&rotation_commit(2)).unwrap();
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
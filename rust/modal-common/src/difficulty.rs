@@ -0,0 +1,413 @@
+//! Difficulty adjustment algorithms.
+//!
+//! An algorithm maps the blocks preceding a height to the target difficulty a
+//! block at that height must meet. Algorithms only look at `(index, timestamp,
+//! difficulty)` samples so the miner chain and chain observers can share them.
+//!
+//! The algorithm is selected by the `difficulty_algorithm` entry of a network
+//! config, e.g. `{"type": "lwma", "window": 45}`. Networks that don't set one
+//! keep the original per-epoch retargeting.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+
+/// The parts of a block that difficulty algorithms look at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DifficultySample {
+    pub index: u64,
+    /// Unix timestamp in seconds
+    pub timestamp: i64,
+    pub difficulty: u128,
+}
+
+/// Chain parameters shared by all algorithms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DifficultyParams {
+    pub initial_difficulty: u128,
+    pub target_block_time_secs: u64,
+    pub blocks_per_epoch: u64,
+    pub min_difficulty: u128,
+    pub max_difficulty: u128,
+}
+
+impl DifficultyParams {
+    fn clamp(&self, difficulty: u128) -> u128 {
+        difficulty.clamp(self.min_difficulty, self.max_difficulty)
+    }
+
+    fn target_time(&self) -> u128 {
+        self.target_block_time_secs.max(1) as u128
+    }
+}
+
+/// A difficulty adjustment algorithm
+pub trait DifficultyAlgorithm: fmt::Debug + Send + Sync {
+    /// Short name shown in logs and on the status page
+    fn name(&self) -> &'static str;
+
+    /// Difficulty required for the block at `block_index`.
+    ///
+    /// `history` holds the blocks before `block_index`, ordered by index. It may
+    /// include the genesis block and may include unrelated later blocks, which
+    /// are ignored.
+    fn next_difficulty(&self, params: &DifficultyParams, block_index: u64, history: &[DifficultySample]) -> u128;
+}
+
+/// Preceding mined blocks (genesis excluded), ordered by index
+fn mined_before(block_index: u64, history: &[DifficultySample]) -> Vec<DifficultySample> {
+    history
+        .iter()
+        .filter(|s| s.index >= 1 && s.index < block_index)
+        .copied()
+        .collect()
+}
+
+/// `a * b / c` without overflowing the intermediate product
+fn mul_div(a: u128, b: u128, c: u128) -> u128 {
+    let c = c.max(1);
+    match a.checked_mul(b) {
+        Some(product) => product / c,
+        None => (a / c).saturating_mul(b),
+    }
+}
+
+/// Stepwise retarget used at each epoch boundary.
+///
+/// Compares the time the last epoch actually took with the expected time and
+/// scales the difficulty by at most 8x up or 0.5x down.
+pub fn epoch_step(current_difficulty: u128, actual_time_secs: u64, expected_time_secs: u64) -> u128 {
+    let ratio = (actual_time_secs.max(1) as f64) / (expected_time_secs.max(1) as f64);
+
+    if ratio < 0.125 {
+        current_difficulty.saturating_mul(8)
+    } else if ratio < 0.25 {
+        current_difficulty.saturating_mul(4)
+    } else if ratio < 0.5 {
+        current_difficulty.saturating_mul(2)
+    } else if ratio < 0.75 {
+        current_difficulty.saturating_mul(3) / 2
+    } else if ratio < 0.9 {
+        current_difficulty.saturating_mul(11) / 10
+    } else if ratio > 2.0 {
+        current_difficulty / 2
+    } else if ratio > 1.5 {
+        current_difficulty * 2 / 3
+    } else if ratio > 1.1 {
+        current_difficulty * 9 / 10
+    } else {
+        current_difficulty
+    }
+}
+
+/// Original algorithm: constant difficulty within an epoch, retargeted from
+/// the previous epoch's duration
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EpochRetarget;
+
+impl DifficultyAlgorithm for EpochRetarget {
+    fn name(&self) -> &'static str {
+        "epoch"
+    }
+
+    fn next_difficulty(&self, params: &DifficultyParams, block_index: u64, history: &[DifficultySample]) -> u128 {
+        let blocks_per_epoch = params.blocks_per_epoch.max(1);
+        if block_index == 0 {
+            return params.initial_difficulty;
+        }
+        let epoch = (block_index - 1) / blocks_per_epoch;
+        if epoch == 0 {
+            return params.initial_difficulty;
+        }
+
+        let prev_start = (epoch - 1) * blocks_per_epoch + 1;
+        let prev_end = epoch * blocks_per_epoch;
+        let epoch_blocks: Vec<&DifficultySample> = history
+            .iter()
+            .filter(|s| s.index >= prev_start && s.index <= prev_end)
+            .collect();
+
+        let (Some(first), Some(last)) = (epoch_blocks.first(), epoch_blocks.last()) else {
+            return params.initial_difficulty;
+        };
+        if epoch_blocks.len() < blocks_per_epoch as usize {
+            return last.difficulty;
+        }
+
+        let actual_time_secs = (last.timestamp - first.timestamp).max(1) as u64;
+        let expected_time_secs = params.target_block_time_secs * blocks_per_epoch;
+        params.clamp(epoch_step(last.difficulty, actual_time_secs, expected_time_secs))
+    }
+}
+
+/// Never adjusts: every block uses the initial difficulty
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Fixed;
+
+impl DifficultyAlgorithm for Fixed {
+    fn name(&self) -> &'static str {
+        "fixed"
+    }
+
+    fn next_difficulty(&self, params: &DifficultyParams, _block_index: u64, _history: &[DifficultySample]) -> u128 {
+        params.clamp(params.initial_difficulty)
+    }
+}
+
+/// Simple moving average over the last `window` solve times
+#[derive(Debug, Clone, Copy)]
+pub struct SimpleMovingAverage {
+    pub window: u64,
+}
+
+impl DifficultyAlgorithm for SimpleMovingAverage {
+    fn name(&self) -> &'static str {
+        "sma"
+    }
+
+    fn next_difficulty(&self, params: &DifficultyParams, block_index: u64, history: &[DifficultySample]) -> u128 {
+        let mined = mined_before(block_index, history);
+        let n = (self.window.max(1) as usize).min(mined.len().saturating_sub(1));
+        if n == 0 {
+            return params.initial_difficulty;
+        }
+
+        let window = &mined[mined.len() - n - 1..];
+        let total_difficulty: u128 = window[1..].iter().fold(0u128, |acc, s| acc.saturating_add(s.difficulty));
+        let total_time = (window[n].timestamp - window[0].timestamp).max(1) as u128;
+
+        // avg_difficulty * target_time / avg_solve_time
+        params.clamp(mul_div(total_difficulty, params.target_time(), total_time))
+    }
+}
+
+/// Linearly weighted moving average (zawy's LWMA): recent solve times weigh more
+#[derive(Debug, Clone, Copy)]
+pub struct Lwma {
+    pub window: u64,
+}
+
+impl DifficultyAlgorithm for Lwma {
+    fn name(&self) -> &'static str {
+        "lwma"
+    }
+
+    fn next_difficulty(&self, params: &DifficultyParams, block_index: u64, history: &[DifficultySample]) -> u128 {
+        let mined = mined_before(block_index, history);
+        let n = (self.window.max(1) as usize).min(mined.len().saturating_sub(1));
+        if n == 0 {
+            return params.initial_difficulty;
+        }
+
+        let window = &mined[mined.len() - n - 1..];
+        let target = params.target_time();
+        let mut weighted_time: u128 = 0;
+        let mut total_difficulty: u128 = 0;
+        for i in 1..=n {
+            // Clamp solve times so a single bad timestamp can't swing the result
+            let solve_time = (window[i].timestamp - window[i - 1].timestamp).clamp(1, 6 * target as i64) as u128;
+            weighted_time += i as u128 * solve_time;
+            total_difficulty = total_difficulty.saturating_add(window[i].difficulty);
+        }
+
+        // avg_difficulty * T * (n(n+1)/2) / weighted_time
+        let k = (n as u128) + 1;
+        let numerator_scale = target * k;
+        params.clamp(mul_div(total_difficulty, numerator_scale, 2 * weighted_time.max(1)))
+    }
+}
+
+/// Absolutely scheduled exponential rise targeting (ASERT).
+///
+/// Difficulty doubles for every `half_life_secs` the chain is ahead of schedule
+/// relative to the anchor (block 1) and halves for every half-life behind.
+/// Uses the integer approximation from aserti3-2d so results are bit-exact.
+#[derive(Debug, Clone, Copy)]
+pub struct Asert {
+    pub half_life_secs: u64,
+}
+
+impl DifficultyAlgorithm for Asert {
+    fn name(&self) -> &'static str {
+        "asert"
+    }
+
+    fn next_difficulty(&self, params: &DifficultyParams, block_index: u64, history: &[DifficultySample]) -> u128 {
+        let mined = mined_before(block_index, history);
+        let (Some(anchor), Some(parent)) = (mined.first(), mined.last()) else {
+            return params.initial_difficulty;
+        };
+        if anchor.index != 1 {
+            return params.initial_difficulty;
+        }
+
+        let ideal = params.target_time() as i128 * (parent.index - anchor.index) as i128;
+        let actual = (parent.timestamp - anchor.timestamp) as i128;
+        let exponent = ((ideal - actual) * 65536) / self.half_life_secs.max(1) as i128;
+
+        let shifts = exponent >> 16;
+        let frac = (exponent - (shifts << 16)) as u128;
+        let factor = 65536
+            + ((195_766_423_245_049 * frac + 971_821_376 * frac * frac + 5127 * frac * frac * frac + (1u128 << 47))
+                >> 48);
+
+        let scaled = anchor.difficulty.saturating_mul(factor);
+        let shift = shifts - 16;
+        let difficulty = if shift >= 0 {
+            if shift >= 128 || scaled.leading_zeros() < shift as u32 {
+                u128::MAX
+            } else {
+                scaled << shift
+            }
+        } else if -shift >= 128 {
+            0
+        } else {
+            scaled >> (-shift)
+        };
+
+        params.clamp(difficulty)
+    }
+}
+
+fn default_window() -> u64 {
+    45
+}
+
+fn default_half_life_secs() -> u64 {
+    2 * 3600
+}
+
+/// Serializable selection of a difficulty algorithm, as it appears in network configs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DifficultyConfig {
+    #[default]
+    Epoch,
+    Fixed,
+    Sma {
+        #[serde(default = "default_window")]
+        window: u64,
+    },
+    Lwma {
+        #[serde(default = "default_window")]
+        window: u64,
+    },
+    Asert {
+        #[serde(default = "default_half_life_secs")]
+        half_life_secs: u64,
+    },
+}
+
+impl DifficultyConfig {
+    /// Parse the `difficulty_algorithm` entry of a network config JSON, defaulting to `Epoch`
+    pub fn from_network_config(network_config: &serde_json::Value) -> anyhow::Result<Self> {
+        match network_config.get("difficulty_algorithm") {
+            Some(value) => Ok(serde_json::from_value(value.clone())?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn build(&self) -> Arc<dyn DifficultyAlgorithm> {
+        match *self {
+            DifficultyConfig::Epoch => Arc::new(EpochRetarget),
+            DifficultyConfig::Fixed => Arc::new(Fixed),
+            DifficultyConfig::Sma { window } => Arc::new(SimpleMovingAverage { window }),
+            DifficultyConfig::Lwma { window } => Arc::new(Lwma { window }),
+            DifficultyConfig::Asert { half_life_secs } => Arc::new(Asert { half_life_secs }),
+        }
+    }
+}
+
+impl fmt::Display for DifficultyConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DifficultyConfig::Epoch => write!(f, "epoch"),
+            DifficultyConfig::Fixed => write!(f, "fixed"),
+            DifficultyConfig::Sma { window } => write!(f, "sma (window {})", window),
+            DifficultyConfig::Lwma { window } => write!(f, "lwma (window {})", window),
+            DifficultyConfig::Asert { half_life_secs } => write!(f, "asert (half-life {}s)", half_life_secs),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> DifficultyParams {
+        DifficultyParams {
+            initial_difficulty: 1000,
+            target_block_time_secs: 60,
+            blocks_per_epoch: 10,
+            min_difficulty: 1,
+            max_difficulty: u128::MAX,
+        }
+    }
+
+    /// Genesis plus `count` blocks at a constant solve time and difficulty
+    fn chain(count: u64, solve_time: i64, difficulty: u128) -> Vec<DifficultySample> {
+        (0..=count)
+            .map(|i| DifficultySample { index: i, timestamp: 1_000_000 + i as i64 * solve_time, difficulty })
+            .collect()
+    }
+
+    #[test]
+    fn test_on_target_chain_is_stable() {
+        let history = chain(30, 60, 1000);
+        for config in [
+            DifficultyConfig::Fixed,
+            DifficultyConfig::Sma { window: 10 },
+            DifficultyConfig::Lwma { window: 10 },
+            DifficultyConfig::Asert { half_life_secs: 3600 },
+        ] {
+            let d = config.build().next_difficulty(&params(), 31, &history);
+            assert_eq!(d, 1000, "{} should hold difficulty on an on-target chain", config);
+        }
+    }
+
+    #[test]
+    fn test_fast_blocks_raise_difficulty() {
+        let history = chain(30, 30, 1000);
+        for config in [
+            DifficultyConfig::Sma { window: 10 },
+            DifficultyConfig::Lwma { window: 10 },
+            DifficultyConfig::Asert { half_life_secs: 600 },
+        ] {
+            let d = config.build().next_difficulty(&params(), 31, &history);
+            assert!(d > 1000, "{} should raise difficulty, got {}", config, d);
+        }
+        // Twice as fast -> SMA doubles
+        assert_eq!(DifficultyConfig::Sma { window: 10 }.build().next_difficulty(&params(), 31, &history), 2000);
+    }
+
+    #[test]
+    fn test_asert_half_life() {
+        // Block 2 arrives one half-life late -> difficulty halves
+        let history = vec![
+            DifficultySample { index: 0, timestamp: 0, difficulty: 1000 },
+            DifficultySample { index: 1, timestamp: 1_000_000, difficulty: 1000 },
+            DifficultySample { index: 2, timestamp: 1_000_000 + 60 + 3600, difficulty: 1000 },
+        ];
+        let d = Asert { half_life_secs: 3600 }.next_difficulty(&params(), 3, &history);
+        assert_eq!(d, 500);
+    }
+
+    #[test]
+    fn test_epoch_retarget_matches_stepwise_rules() {
+        let p = params();
+        // Epoch 0 (blocks 1-10) took 135s of an expected 600s (ratio 0.225) -> x4
+        let history = chain(10, 15, 1000);
+        assert_eq!(EpochRetarget.next_difficulty(&p, 5, &history), 1000);
+        assert_eq!(EpochRetarget.next_difficulty(&p, 11, &history), 4000);
+    }
+
+    #[test]
+    fn test_config_from_network_config() {
+        let json = serde_json::json!({"name": "devnet", "difficulty_algorithm": {"type": "lwma"}});
+        assert_eq!(DifficultyConfig::from_network_config(&json).unwrap(), DifficultyConfig::Lwma { window: 45 });
+        let json = serde_json::json!({"name": "devnet"});
+        assert_eq!(DifficultyConfig::from_network_config(&json).unwrap(), DifficultyConfig::Epoch);
+        let json = serde_json::json!({"difficulty_algorithm": {"type": "bogus"}});
+        assert!(DifficultyConfig::from_network_config(&json).is_err());
+    }
+}
@@ -0,0 +1,280 @@
+//! Era-based protocol parameters.
+//!
+//! A network starts with a base epoch length and target block time. Eras
+//! change those parameters from an activation height onwards, so a network can
+//! e.g. switch to shorter epochs without rewriting the epochs that came
+//! before. Each era must activate on an epoch boundary of the era before it;
+//! epoch numbers keep counting across eras.
+//!
+//! Eras are listed under `eras` in a network config:
+//! `{"blocks_per_epoch": 40, "eras": [{"activation_height": 4000, "blocks_per_epoch": 20}]}`
+
+use serde::{Deserialize, Serialize};
+
+/// Parameter changes taking effect at `activation_height`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Era {
+    pub activation_height: u64,
+    /// New epoch length (unchanged if unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocks_per_epoch: Option<u64>,
+    /// New target block time (unchanged if unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_block_time_secs: Option<u64>,
+}
+
+/// Parameters in effect over a contiguous range of heights
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Segment {
+    start_height: u64,
+    first_epoch: u64,
+    blocks_per_epoch: u64,
+    target_block_time_secs: u64,
+}
+
+/// Base parameters plus the eras that change them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EraSchedule {
+    pub blocks_per_epoch: u64,
+    pub target_block_time_secs: u64,
+    #[serde(default)]
+    pub eras: Vec<Era>,
+}
+
+impl EraSchedule {
+    /// A schedule whose parameters never change
+    pub fn fixed(blocks_per_epoch: u64, target_block_time_secs: u64) -> Self {
+        Self {
+            blocks_per_epoch,
+            target_block_time_secs,
+            eras: Vec::new(),
+        }
+    }
+
+    /// Build a schedule, sorting the eras by activation height and validating them
+    pub fn new(blocks_per_epoch: u64, target_block_time_secs: u64, mut eras: Vec<Era>) -> Result<Self, String> {
+        eras.sort_by_key(|era| era.activation_height);
+        let schedule = Self {
+            blocks_per_epoch,
+            target_block_time_secs,
+            eras,
+        };
+        schedule.validate()?;
+        Ok(schedule)
+    }
+
+    /// Read the schedule from a network config, falling back to the given
+    /// defaults for parameters it doesn't set
+    pub fn from_network_config(
+        network_config: &serde_json::Value,
+        default_blocks_per_epoch: u64,
+        default_target_block_time_secs: u64,
+    ) -> anyhow::Result<Self> {
+        let blocks_per_epoch = network_config
+            .get("blocks_per_epoch")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(default_blocks_per_epoch);
+        let target_block_time_secs = network_config
+            .get("target_block_time_secs")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(default_target_block_time_secs);
+        let eras = match network_config.get("eras") {
+            Some(value) => serde_json::from_value(value.clone())?,
+            None => Vec::new(),
+        };
+        Self::new(blocks_per_epoch, target_block_time_secs, eras).map_err(|e| anyhow::anyhow!(e))
+    }
+
+    /// Check epoch lengths are positive and every era starts on an epoch boundary
+    pub fn validate(&self) -> Result<(), String> {
+        if self.blocks_per_epoch == 0 {
+            return Err("blocks_per_epoch must be positive".to_string());
+        }
+        let mut previous = self.segments()[0];
+        for (era, segment) in self.eras.iter().zip(self.segments().into_iter().skip(1)) {
+            if era.activation_height <= previous.start_height {
+                return Err(format!(
+                    "era activating at {} must come after the era activating at {}",
+                    era.activation_height, previous.start_height
+                ));
+            }
+            if era.blocks_per_epoch == Some(0) {
+                return Err(format!("era activating at {} has zero blocks_per_epoch", era.activation_height));
+            }
+            if !(era.activation_height - previous.start_height).is_multiple_of(previous.blocks_per_epoch) {
+                return Err(format!(
+                    "era activating at {} is not on an epoch boundary (epochs of {} blocks from {})",
+                    era.activation_height, previous.blocks_per_epoch, previous.start_height
+                ));
+            }
+            previous = segment;
+        }
+        Ok(())
+    }
+
+    fn segments(&self) -> Vec<Segment> {
+        let mut segments = vec![Segment {
+            start_height: 0,
+            first_epoch: 0,
+            blocks_per_epoch: self.blocks_per_epoch.max(1),
+            target_block_time_secs: self.target_block_time_secs,
+        }];
+        for era in &self.eras {
+            let previous = *segments.last().unwrap();
+            let elapsed = era.activation_height.saturating_sub(previous.start_height);
+            segments.push(Segment {
+                start_height: era.activation_height,
+                first_epoch: previous.first_epoch + elapsed.div_ceil(previous.blocks_per_epoch),
+                blocks_per_epoch: era.blocks_per_epoch.unwrap_or(previous.blocks_per_epoch).max(1),
+                target_block_time_secs: era.target_block_time_secs.unwrap_or(previous.target_block_time_secs),
+            });
+        }
+        segments
+    }
+
+    fn segment_at(&self, height: u64) -> Segment {
+        self.segments()
+            .into_iter()
+            .rev()
+            .find(|s| s.start_height <= height)
+            .expect("base segment starts at height 0")
+    }
+
+    fn segment_for_epoch(&self, epoch: u64) -> Segment {
+        self.segments()
+            .into_iter()
+            .rev()
+            .find(|s| s.first_epoch <= epoch)
+            .expect("base segment starts at epoch 0")
+    }
+
+    /// Epoch length in effect at `height`
+    pub fn blocks_per_epoch_at(&self, height: u64) -> u64 {
+        self.segment_at(height).blocks_per_epoch
+    }
+
+    /// Target block time in effect at `height`
+    pub fn target_block_time_at(&self, height: u64) -> u64 {
+        self.segment_at(height).target_block_time_secs
+    }
+
+    /// Epoch containing `height`
+    pub fn epoch_of(&self, height: u64) -> u64 {
+        let segment = self.segment_at(height);
+        segment.first_epoch + (height - segment.start_height) / segment.blocks_per_epoch
+    }
+
+    /// First height of `epoch`
+    pub fn epoch_start(&self, epoch: u64) -> u64 {
+        let segment = self.segment_for_epoch(epoch);
+        segment.start_height + (epoch - segment.first_epoch) * segment.blocks_per_epoch
+    }
+
+    /// Number of heights in `epoch`
+    pub fn epoch_length(&self, epoch: u64) -> u64 {
+        self.segment_for_epoch(epoch).blocks_per_epoch
+    }
+
+    /// Whether `height` is the first height of its epoch
+    pub fn is_epoch_start(&self, height: u64) -> bool {
+        self.epoch_start(self.epoch_of(height)) == height
+    }
+
+    /// Check that switching to `next` doesn't change the parameters of any
+    /// height up to `tip_height`, which would re-number existing epochs
+    pub fn check_transition(&self, next: &EraSchedule, tip_height: u64) -> Result<(), String> {
+        let base_changed = self.blocks_per_epoch != next.blocks_per_epoch
+            || self.target_block_time_secs != next.target_block_time_secs;
+        if base_changed && tip_height > 0 {
+            return Err(format!(
+                "base parameters changed from {} blocks/{}s to {} blocks/{}s after the chain reached height {}",
+                self.blocks_per_epoch, self.target_block_time_secs,
+                next.blocks_per_epoch, next.target_block_time_secs, tip_height
+            ));
+        }
+        let active = |schedule: &EraSchedule| -> Vec<Era> {
+            schedule
+                .eras
+                .iter()
+                .filter(|era| era.activation_height <= tip_height)
+                .cloned()
+                .collect()
+        };
+        if active(self) != active(next) {
+            return Err(format!(
+                "eras activating at or below the chain tip ({}) were changed",
+                tip_height
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn era(activation_height: u64, blocks_per_epoch: u64) -> Era {
+        Era {
+            activation_height,
+            blocks_per_epoch: Some(blocks_per_epoch),
+            target_block_time_secs: None,
+        }
+    }
+
+    #[test]
+    fn test_epochs_continue_across_eras() {
+        let schedule = EraSchedule::new(40, 60, vec![era(400, 20)]).unwrap();
+
+        assert_eq!(schedule.epoch_of(0), 0);
+        assert_eq!(schedule.epoch_of(399), 9);
+        assert_eq!(schedule.epoch_of(400), 10);
+        assert_eq!(schedule.epoch_of(419), 10);
+        assert_eq!(schedule.epoch_of(420), 11);
+
+        assert_eq!(schedule.epoch_start(9), 360);
+        assert_eq!(schedule.epoch_start(11), 420);
+        assert_eq!(schedule.epoch_length(9), 40);
+        assert_eq!(schedule.epoch_length(11), 20);
+        assert!(schedule.is_epoch_start(420));
+        assert!(!schedule.is_epoch_start(410));
+        assert_eq!(schedule.blocks_per_epoch_at(399), 40);
+        assert_eq!(schedule.target_block_time_at(500), 60);
+    }
+
+    #[test]
+    fn test_invalid_schedules_rejected() {
+        assert!(EraSchedule::new(0, 60, vec![]).is_err());
+        assert!(EraSchedule::new(40, 60, vec![era(30, 20)]).is_err()); // not on an epoch boundary
+        assert!(EraSchedule::new(40, 60, vec![era(400, 0)]).is_err());
+        assert!(EraSchedule::new(40, 60, vec![era(0, 20)]).is_err());
+        assert!(EraSchedule::new(40, 60, vec![era(400, 20), era(400, 10)]).is_err());
+        // Sorted before validation, and later eras align to the preceding era
+        assert!(EraSchedule::new(40, 60, vec![era(420, 10), era(400, 20)]).is_ok());
+    }
+
+    #[test]
+    fn test_transition_check() {
+        let current = EraSchedule::new(40, 60, vec![era(400, 20)]).unwrap();
+
+        // Adding a future era is fine, changing a past one is not
+        let extended = EraSchedule::new(40, 60, vec![era(400, 20), era(800, 10)]).unwrap();
+        assert!(current.check_transition(&extended, 500).is_ok());
+        let rewritten = EraSchedule::new(40, 60, vec![era(400, 10)]).unwrap();
+        assert!(current.check_transition(&rewritten, 500).is_err());
+        assert!(current.check_transition(&rewritten, 399).is_ok());
+        assert!(current.check_transition(&EraSchedule::fixed(20, 60), 10).is_err());
+    }
+
+    #[test]
+    fn test_from_network_config() {
+        let config = serde_json::json!({
+            "blocks_per_epoch": 40,
+            "eras": [{"activation_height": 4000, "blocks_per_epoch": 20, "target_block_time_secs": 30}]
+        });
+        let schedule = EraSchedule::from_network_config(&config, 100, 60).unwrap();
+        assert_eq!(schedule.blocks_per_epoch, 40);
+        assert_eq!(schedule.target_block_time_at(4000), 30);
+        assert_eq!(EraSchedule::from_network_config(&serde_json::json!({}), 100, 60).unwrap(), EraSchedule::fixed(100, 60));
+    }
+}
@@ -0,0 +1,202 @@
+//! Machine-readable error codes shared by every Modality crate.
+//!
+//! Each crate's error type maps its variants to an `ErrorCode` through
+//! `HasErrorCode`, and RPC and reqres responses carry the code next to the
+//! message, so clients can tell a missing contract from a full mempool, and
+//! whether trying again might help, without matching message text.
+//!
+//! Codes are stable: they're grouped by category in blocks of 1000, new
+//! codes are added at the end of their block and retired codes are never
+//! reused.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Broad kind of an error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// The request itself was malformed or not allowed
+    Request,
+    /// What the request named doesn't exist
+    NotFound,
+    /// Data failed validation against the protocol or a contract
+    Validation,
+    Consensus,
+    Mining,
+    Mempool,
+    Storage,
+    Network,
+    Internal,
+}
+
+macro_rules! error_codes {
+    ($($variant:ident = $code:literal, $name:literal, $category:ident, $retriable:literal;)*) => {
+        /// A registered error code
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum ErrorCode {
+            $($variant,)*
+        }
+
+        impl ErrorCode {
+            /// Every registered code, in code order
+            pub const ALL: &'static [ErrorCode] = &[$(ErrorCode::$variant,)*];
+
+            /// The stable numeric code
+            pub fn code(self) -> u32 {
+                match self {
+                    $(ErrorCode::$variant => $code,)*
+                }
+            }
+
+            /// The stable snake_case name
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(ErrorCode::$variant => $name,)*
+                }
+            }
+
+            pub fn category(self) -> ErrorCategory {
+                match self {
+                    $(ErrorCode::$variant => ErrorCategory::$category,)*
+                }
+            }
+
+            /// Whether the same request may succeed later
+            pub fn retriable(self) -> bool {
+                match self {
+                    $(ErrorCode::$variant => $retriable,)*
+                }
+            }
+        }
+    };
+}
+
+error_codes! {
+    InvalidRequest = 1000, "invalid_request", Request, false;
+    ParseError = 1001, "parse_error", Request, false;
+    InvalidParams = 1002, "invalid_params", Request, false;
+    MethodNotFound = 1003, "method_not_found", Request, false;
+    Unauthorized = 1004, "unauthorized", Request, false;
+    RateLimited = 1005, "rate_limited", Request, true;
+    Busy = 1006, "busy", Request, true;
+
+    NotFound = 2000, "not_found", NotFound, false;
+    ContractNotFound = 2001, "contract_not_found", NotFound, false;
+    BlockNotFound = 2002, "block_not_found", NotFound, false;
+    CommitNotFound = 2003, "commit_not_found", NotFound, false;
+
+    InvalidData = 3000, "invalid_data", Validation, false;
+    InvalidSignature = 3001, "invalid_signature", Validation, false;
+    RuleViolation = 3002, "rule_violation", Validation, false;
+    UnauthorizedParty = 3003, "unauthorized_party", Validation, false;
+    InvalidPhase = 3004, "invalid_phase", Validation, false;
+    InvalidBlock = 3005, "invalid_block", Validation, false;
+    InvalidChain = 3006, "invalid_chain", Validation, false;
+    InvalidNonce = 3007, "invalid_nonce", Validation, false;
+
+    ConsensusFailed = 4000, "consensus_failed", Consensus, true;
+    ValidatorInitFailed = 4001, "validator_init_failed", Consensus, false;
+    ObservationFailed = 4002, "observation_failed", Consensus, true;
+    SyncFailed = 4003, "sync_failed", Consensus, true;
+
+    MiningFailed = 5000, "mining_failed", Mining, true;
+    MiningCancelled = 5001, "mining_cancelled", Mining, true;
+    HashError = 5002, "hash_error", Mining, false;
+
+    StaleNonce = 6000, "stale_nonce", Mempool, false;
+    FeeTooLow = 6001, "fee_too_low", Mempool, false;
+    SenderFull = 6002, "sender_full", Mempool, true;
+    MempoolFull = 6003, "mempool_full", Mempool, true;
+
+    StorageError = 7000, "storage_error", Storage, true;
+    SerializationError = 7001, "serialization_error", Storage, false;
+    PersistenceError = 7002, "persistence_error", Storage, true;
+
+    ConnectionError = 8000, "connection_error", Network, true;
+    Timeout = 8001, "timeout", Network, true;
+
+    Internal = 9000, "internal_error", Internal, false;
+}
+
+impl ErrorCode {
+    /// Look a code up by number
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::ALL.iter().copied().find(|c| c.code() == code)
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name(), self.code())
+    }
+}
+
+/// An error that maps to a registered code
+pub trait HasErrorCode {
+    fn error_code(&self) -> ErrorCode;
+}
+
+/// An error as responses carry it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorInfo {
+    pub code: u32,
+    pub name: String,
+    pub category: ErrorCategory,
+    pub retriable: bool,
+    pub message: String,
+}
+
+impl ErrorInfo {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code: code.code(),
+            name: code.name().to_string(),
+            category: code.category(),
+            retriable: code.retriable(),
+            message: message.into(),
+        }
+    }
+
+    /// Describe `err` by its code and message
+    pub fn of<E: HasErrorCode + fmt::Display>(err: &E) -> Self {
+        Self::new(err.error_code(), err.to_string())
+    }
+
+    /// The registered code, if this side knows it
+    pub fn error_code(&self) -> Option<ErrorCode> {
+        ErrorCode::from_code(self.code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_codes_are_unique_and_in_their_category_block() {
+        let mut seen = HashSet::new();
+        let mut names = HashSet::new();
+        for code in ErrorCode::ALL {
+            assert!(seen.insert(code.code()), "duplicate code {}", code.code());
+            assert!(names.insert(code.name()), "duplicate name {}", code.name());
+            assert_eq!(ErrorCode::from_code(code.code()), Some(*code));
+        }
+        let block = |category| ErrorCode::ALL.iter().filter(move |c| c.category() == category).map(|c| c.code() / 1000);
+        assert!(block(ErrorCategory::Request).all(|b| b == 1));
+        assert!(block(ErrorCategory::Mempool).all(|b| b == 6));
+        assert!(block(ErrorCategory::Internal).all(|b| b == 9));
+    }
+
+    #[test]
+    fn test_error_info_serializes_code() {
+        let info = ErrorInfo::new(ErrorCode::MempoolFull, "Mempool full: need a fee above 5");
+        let json = serde_json::to_value(&info).unwrap();
+        assert_eq!(json["code"], 6003);
+        assert_eq!(json["category"], "mempool");
+        assert_eq!(json["retriable"], true);
+        let back: ErrorInfo = serde_json::from_value(json).unwrap();
+        assert_eq!(back.error_code(), Some(ErrorCode::MempoolFull));
+    }
+}
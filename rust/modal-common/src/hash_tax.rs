@@ -8,32 +8,254 @@ use num_traits::{Num, Zero};
 use randomx_rs::{RandomXFlag, RandomXVM};
 use serde::{Deserialize};
 use std::cell::RefCell;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
 
 const DEFAULT_MAX_TRIES: u128 = 100_000_000_000;
-const DEFAULT_HASH_FUNC_NAME: &str = "randomx";
+pub const DEFAULT_HASH_FUNC_NAME: &str = "randomx";
 const DEFAULT_DIFFICULTY_COEFFICIENT: u128 = 0xffff;
 const DEFAULT_DIFFICULTY_EXPONENT: u128 = 0x1d;
 const DEFAULT_DIFFICULTY_BASE: u128 = 8;
 const RANDOMX_KEY: &[u8] = b"modality-network-randomx-key";
+const ARGON2_SALT: &[u8] = b"modality-network-argon2id-salt";
 
 /// RandomX-specific hashing parameters
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct RandomXParams {
     pub key: Option<String>,  // Custom key (default: "modality-network-randomx-key")
     pub flags: Option<String>, // "recommended", "light", "full", or comma-separated flags
 }
 
+/// Argon2id hashing parameters
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Argon2Params {
+    pub memory_kib: Option<u32>,  // Memory cost in KiB (default: 4096)
+    pub iterations: Option<u32>,  // Time cost (default: 1)
+    pub parallelism: Option<u32>, // Lanes (default: 1)
+    pub salt: Option<String>,     // Custom salt (default: "modality-network-argon2id-salt")
+}
+
+/// A proof-of-work hash function, registered by name
+pub trait HashFunction: Send + Sync {
+    /// Name used in `miner_hash_func` and network parameters
+    fn name(&self) -> &str;
+
+    /// Hash the input and return the digest as lowercase hex
+    fn hash(&self, input: &[u8]) -> Result<String, Box<dyn Error>>;
+}
+
+macro_rules! digest_hash_function {
+    ($name:ident, $label:literal, $hasher:ty) => {
+        pub struct $name;
+
+        impl HashFunction for $name {
+            fn name(&self) -> &str {
+                $label
+            }
+
+            fn hash(&self, input: &[u8]) -> Result<String, Box<dyn Error>> {
+                Ok(hex::encode(<$hasher>::digest(input)))
+            }
+        }
+    };
+}
+
+digest_hash_function!(Sha1Hash, "sha1", Sha1);
+digest_hash_function!(Sha256Hash, "sha256", Sha256);
+digest_hash_function!(Sha384Hash, "sha384", Sha384);
+digest_hash_function!(Sha512Hash, "sha512", Sha512);
+
+pub struct Blake3Hash;
+
+impl HashFunction for Blake3Hash {
+    fn name(&self) -> &str {
+        "blake3"
+    }
+
+    fn hash(&self, input: &[u8]) -> Result<String, Box<dyn Error>> {
+        Ok(blake3::hash(input).to_hex().to_string())
+    }
+}
+
+/// RandomX, using a thread-local VM. The default hashes with the parameters
+/// `set_randomx_params` configured on the calling thread; one made
+/// `with_params` reconfigures the thread first if it was set up otherwise,
+/// so registries with different parameters can share threads.
+#[derive(Default)]
+pub struct RandomXHash {
+    params: Option<Option<RandomXParams>>,
+}
+
+impl RandomXHash {
+    pub fn with_params(params: Option<RandomXParams>) -> Self {
+        Self { params: Some(params) }
+    }
+}
+
+impl HashFunction for RandomXHash {
+    fn name(&self) -> &str {
+        "randomx"
+    }
+
+    fn hash(&self, input: &[u8]) -> Result<String, Box<dyn Error>> {
+        if let Some(params) = &self.params {
+            if RANDOMX_PARAMS.with(|p| p.borrow().as_ref() != params.as_ref()) {
+                set_randomx_params(params.clone());
+            }
+        }
+        hash_with_randomx(input)
+    }
+}
+
+/// Memory-hard Argon2id with a fixed per-network salt
+pub struct Argon2idHash {
+    params: argon2::Params,
+    salt: Vec<u8>,
+}
+
+impl Argon2idHash {
+    pub fn new(params: &Argon2Params) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            params: argon2::Params::new(
+                params.memory_kib.unwrap_or(4096),
+                params.iterations.unwrap_or(1),
+                params.parallelism.unwrap_or(1),
+                Some(32),
+            )
+            .map_err(|e| format!("Invalid Argon2id parameters: {}", e))?,
+            salt: params
+                .salt
+                .as_ref()
+                .map(|s| s.as_bytes().to_vec())
+                .unwrap_or_else(|| ARGON2_SALT.to_vec()),
+        })
+    }
+}
+
+impl Default for Argon2idHash {
+    fn default() -> Self {
+        Self::new(&Argon2Params::default()).expect("default Argon2id params are valid")
+    }
+}
+
+impl HashFunction for Argon2idHash {
+    fn name(&self) -> &str {
+        "argon2id"
+    }
+
+    fn hash(&self, input: &[u8]) -> Result<String, Box<dyn Error>> {
+        let argon2 = argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, self.params.clone());
+        let mut output = [0u8; 32];
+        argon2
+            .hash_password_into(input, &self.salt, &mut output)
+            .map_err(|e| format!("Argon2id hashing failed: {}", e))?;
+        Ok(hex::encode(output))
+    }
+}
+
+fn builtin_hash_functions() -> HashMap<String, Arc<dyn HashFunction>> {
+    let functions: Vec<Arc<dyn HashFunction>> = vec![
+        Arc::new(Sha1Hash),
+        Arc::new(Sha256Hash),
+        Arc::new(Sha384Hash),
+        Arc::new(Sha512Hash),
+        Arc::new(Blake3Hash),
+        Arc::new(RandomXHash::with_params(None)),
+        Arc::new(Argon2idHash::default()),
+    ];
+    functions
+        .into_iter()
+        .map(|f| (f.name().to_string(), f))
+        .collect()
+}
+
+/// Hash functions by name, starting with the built-in ones. Networks can
+/// configure the same function differently, so a node keeps a registry per
+/// network; clones share the functions they register.
+#[derive(Clone)]
+pub struct HashRegistry {
+    functions: Arc<RwLock<HashMap<String, Arc<dyn HashFunction>>>>,
+}
+
+impl Default for HashRegistry {
+    fn default() -> Self {
+        Self {
+            functions: Arc::new(RwLock::new(builtin_hash_functions())),
+        }
+    }
+}
+
+impl std::fmt::Debug for HashRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HashRegistry").field("functions", &self.names()).finish()
+    }
+}
+
+impl HashRegistry {
+    /// The process-wide registry the free functions in this module use
+    pub fn global() -> Self {
+        HASH_FUNCTIONS.clone()
+    }
+
+    /// Register a hash function, replacing any existing one with the same name
+    pub fn register(&self, hash_function: Arc<dyn HashFunction>) {
+        let mut functions = self.functions.write().unwrap_or_else(|e| e.into_inner());
+        functions.insert(hash_function.name().to_string(), hash_function);
+    }
+
+    /// Look up a registered hash function by name
+    pub fn get(&self, name: &str) -> Result<Arc<dyn HashFunction>, Box<dyn Error>> {
+        let functions = self.functions.read().unwrap_or_else(|e| e.into_inner());
+        functions
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("Unsupported hash function: {}", name).into())
+    }
+
+    /// Names of all registered hash functions, sorted
+    pub fn names(&self) -> Vec<String> {
+        let functions = self.functions.read().unwrap_or_else(|e| e.into_inner());
+        let mut names: Vec<String> = functions.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Re-register `hash_func_name` with parameters from a JSON value: RandomX's
+    /// key and flags or Argon2id's costs and salt. Other functions take none.
+    pub fn configure(&self, hash_func_name: &str, params_json: Option<&serde_json::Value>) -> Result<(), Box<dyn Error>> {
+        match hash_func_name {
+            "randomx" => {
+                let params = params_json.map(|v| serde_json::from_value::<RandomXParams>(v.clone())).transpose()?;
+                self.register(Arc::new(RandomXHash::with_params(params)));
+            }
+            "argon2id" => {
+                let hash_function = match params_json {
+                    Some(v) => Argon2idHash::new(&serde_json::from_value::<Argon2Params>(v.clone())?)?,
+                    None => Argon2idHash::default(),
+                };
+                self.register(Arc::new(hash_function));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub fn hash_with_nonce(&self, data: &str, nonce: u128, hash_func_name: &str) -> Result<String, Box<dyn Error>> {
+        self.get(hash_func_name)?.hash(format!("{}{}", data, nonce).as_bytes())
+    }
+
+    pub fn validate_nonce(&self, data: &str, nonce: u128, difficulty: u128, hash_func_name: &str) -> Result<bool, Box<dyn Error>> {
+        let hash = self.hash_with_nonce(data, nonce, hash_func_name)?;
+        Ok(is_hash_acceptable(&hash, difficulty, hash_func_name))
+    }
+}
+
 lazy_static::lazy_static! {
-    static ref HASH_FUNC_HEXADECIMAL_LENGTH: HashMap<&'static str, usize> = {
-        let mut map = HashMap::new();
-        map.insert("sha1", 40);
-        map.insert("sha256", 64);
-        map.insert("sha384", 96);
-        map.insert("sha512", 128);
-        map.insert("randomx", 64);  // RandomX outputs 256 bits = 64 hex chars
-        map
+    /// The process-wide registry hashes RandomX with each thread's `set_randomx_params`
+    static ref HASH_FUNCTIONS: HashRegistry = {
+        let registry = HashRegistry::default();
+        registry.register(Arc::new(RandomXHash::default()));
+        registry
     };
     
     /// Global flag to signal mining should stop
@@ -42,6 +264,21 @@ lazy_static::lazy_static! {
     static ref MINING_SHOULD_STOP: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
 }
 
+/// Register a hash function, replacing any existing one with the same name
+pub fn register_hash_function(hash_function: Arc<dyn HashFunction>) {
+    HASH_FUNCTIONS.register(hash_function);
+}
+
+/// Look up a registered hash function by name
+pub fn get_hash_function(name: &str) -> Result<Arc<dyn HashFunction>, Box<dyn Error>> {
+    HASH_FUNCTIONS.get(name)
+}
+
+/// Names of all registered hash functions, sorted
+pub fn hash_function_names() -> Vec<String> {
+    HASH_FUNCTIONS.names()
+}
+
 /// Set the global mining shutdown flag (called by node shutdown handler)
 pub fn set_mining_shutdown(should_stop: bool) {
     MINING_SHOULD_STOP.store(should_stop, Ordering::Relaxed);
@@ -86,6 +323,19 @@ pub fn set_randomx_params_from_json(params_json: Option<&serde_json::Value>) {
     set_randomx_params(params);
 }
 
+/// Like `set_randomx_params_from_json`, but keeps this thread's VM if the parameters are unchanged
+pub fn ensure_randomx_params_from_json(params_json: Option<&serde_json::Value>) {
+    let params = params_json.and_then(|v| serde_json::from_value::<RandomXParams>(v.clone()).ok());
+    if RANDOMX_PARAMS.with(|p| *p.borrow() != params) {
+        set_randomx_params(params);
+    }
+}
+
+/// Re-register `argon2id` with parameters from a JSON value
+pub fn set_argon2_params_from_json(params_json: Option<&serde_json::Value>) -> Result<(), Box<dyn Error>> {
+    HASH_FUNCTIONS.configure("argon2id", params_json)
+}
+
 
 /// Get or create the thread-local RandomX VM instance
 fn with_randomx_vm<F, R>(f: F) -> Result<R, Box<dyn Error>>
@@ -141,20 +391,40 @@ where
 }
 
 /// Hash data using RandomX (uses thread-local VM for efficiency)
-fn hash_with_randomx(data: &str) -> Result<String, Box<dyn Error>> {
+fn hash_with_randomx(data: &[u8]) -> Result<String, Box<dyn Error>> {
     with_randomx_vm(|vm| {
-        let hash_bytes = vm.calculate_hash(data.as_bytes())
+        let hash_bytes = vm.calculate_hash(data)
             .map_err(|e| format!("RandomX hashing failed: {}", e))?;
         Ok(hex::encode(hash_bytes))
     })
 }
 
+/// Hash attempts made by a single mining thread
+#[derive(Debug, Clone, Default)]
+pub struct ThreadMiningStats {
+    pub thread: usize,
+    pub attempts: u128,
+    pub duration_secs: f64,
+}
+
+impl ThreadMiningStats {
+    pub fn hashrate(&self) -> f64 {
+        if self.duration_secs > 0.0 {
+            self.attempts as f64 / self.duration_secs
+        } else {
+            0.0
+        }
+    }
+}
+
 /// Mining result including nonce and stats
 #[derive(Debug, Clone)]
 pub struct MiningResult {
     pub nonce: u128,
     pub attempts: u128,
     pub duration_secs: f64,
+    /// Per-thread breakdown of `attempts`
+    pub threads: Vec<ThreadMiningStats>,
 }
 
 impl MiningResult {
@@ -186,121 +456,346 @@ pub fn mine_with_stats(
     max_tries: Option<u128>,
     hash_func_name: Option<&str>,
     mining_delay_ms: Option<u64>,
+) -> Result<MiningResult, Box<dyn Error>> {
+    mine_parallel(data, difficulty, max_tries, hash_func_name, mining_delay_ms, 1, None)
+}
+
+/// Error message returned when mining is cancelled through the `cancel` flag
+pub const MINING_CANCELLED: &str = "Mining cancelled";
+
+/// Why a mining worker stopped
+enum WorkerOutcome {
+    Found(u128),
+    /// Another worker found a nonce first
+    Stopped,
+    Exhausted,
+    Shutdown,
+    Cancelled,
+    Failed(String),
+}
+
+/// Nonce search state shared by all mining workers
+struct NonceSearch<'a> {
+    data: &'a str,
+    difficulty: u128,
+    hash_func_name: &'a str,
+    hash_function: &'a dyn HashFunction,
+    threads: usize,
+    tries_per_thread: u128,
+    mining_delay: u64,
+    cancel: Option<&'a AtomicBool>,
+    found: AtomicBool,
+    total_attempts: AtomicU64,
+}
+
+impl NonceSearch<'_> {
+    /// Run a worker per thread and collect how each of them stopped
+    fn run(&self) -> Vec<(WorkerOutcome, ThreadMiningStats)> {
+        if self.threads == 1 {
+            // Mine on the calling thread so its RandomX VM is reused across blocks
+            return vec![self.run_worker(0)];
+        }
+        // RandomX parameters are thread-local, so hand them to each worker
+        let randomx_params = RANDOMX_PARAMS.with(|p| p.borrow().clone());
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..self.threads)
+                .map(|thread| {
+                    let randomx_params = randomx_params.clone();
+                    scope.spawn(move || {
+                        set_randomx_params(randomx_params);
+                        self.run_worker(thread)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .enumerate()
+                .map(|(thread, handle)| {
+                    handle.join().unwrap_or_else(|_| {
+                        let stats = ThreadMiningStats { thread, ..Default::default() };
+                        (WorkerOutcome::Failed("mining thread panicked".to_string()), stats)
+                    })
+                })
+                .collect()
+        })
+    }
+
+    /// Try nonces `thread, thread + threads, ...` until something stops the search
+    fn run_worker(&self, thread: usize) -> (WorkerOutcome, ThreadMiningStats) {
+        let start_time = std::time::Instant::now();
+        let mut nonce = thread as u128;
+        let mut attempts = 0;
+        let mut last_status_log = std::time::Instant::now();
+        let status_interval = std::time::Duration::from_secs(10);
+        let mut last_total = 0;
+
+        let outcome = loop {
+            if attempts >= self.tries_per_thread {
+                break WorkerOutcome::Exhausted;
+            }
+            if self.found.load(Ordering::Relaxed) {
+                break WorkerOutcome::Stopped;
+            }
+            // Check if we should stop mining (e.g., Ctrl-C was pressed)
+            if MINING_SHOULD_STOP.load(Ordering::Relaxed) {
+                break WorkerOutcome::Shutdown;
+            }
+            if self.cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
+                break WorkerOutcome::Cancelled;
+            }
+
+            attempts += 1;
+            let total = self.total_attempts.fetch_add(1, Ordering::Relaxed) + 1;
+
+            // Add artificial delay for testing race conditions
+            if self.mining_delay > 0 {
+                std::thread::sleep(std::time::Duration::from_millis(self.mining_delay));
+            }
+
+            // Log periodic status updates from the first thread (only if we're doing a lot of attempts)
+            if thread == 0 && total > 1000 && last_status_log.elapsed() >= status_interval {
+                let hash_rate = (total - last_total) as f64 / last_status_log.elapsed().as_secs_f64();
+                log::info!("⛏️  Mining status: tried {} nonces on {} threads, hash rate: {:.2} H/s",
+                    total, self.threads, hash_rate);
+                last_status_log = std::time::Instant::now();
+                last_total = total;
+            }
+
+            let hash = match self.hash_function.hash(format!("{}{}", self.data, nonce).as_bytes()) {
+                Ok(hash) => hash,
+                Err(e) => break WorkerOutcome::Failed(e.to_string()),
+            };
+            // Callers only ask for zero difficulty when mining a regtest chain
+            if is_hash_acceptable_regtest(&hash, self.difficulty, self.hash_func_name) {
+                self.found.store(true, Ordering::Relaxed);
+                break WorkerOutcome::Found(nonce);
+            }
+            nonce += self.threads as u128;
+        };
+
+        let stats = ThreadMiningStats {
+            thread,
+            attempts,
+            duration_secs: start_time.elapsed().as_secs_f64(),
+        };
+        (outcome, stats)
+    }
+}
+
+/// Mine on `threads` worker threads, splitting the nonce space between them.
+///
+/// Worker `i` tries nonces `i, i + threads, i + 2 * threads, ...` so workers
+/// never repeat each other's work. All workers stop as soon as one of them
+/// finds a valid nonce, the global shutdown flag is set, or `cancel` is set
+/// (e.g. because a competing block for the same height arrived), in which case
+/// the error message is [`MINING_CANCELLED`].
+pub fn mine_parallel(
+    data: &str,
+    difficulty: u128,
+    max_tries: Option<u128>,
+    hash_func_name: Option<&str>,
+    mining_delay_ms: Option<u64>,
+    threads: usize,
+    cancel: Option<&AtomicBool>,
+) -> Result<MiningResult, Box<dyn Error>> {
+    let hash_function = get_hash_function(hash_func_name.unwrap_or(DEFAULT_HASH_FUNC_NAME))?;
+    mine_parallel_with(hash_function.as_ref(), data, difficulty, max_tries, mining_delay_ms, threads, cancel)
+}
+
+/// Like [`mine_parallel`], with a hash function looked up from any registry
+pub fn mine_parallel_with(
+    hash_function: &dyn HashFunction,
+    data: &str,
+    difficulty: u128,
+    max_tries: Option<u128>,
+    mining_delay_ms: Option<u64>,
+    threads: usize,
+    cancel: Option<&AtomicBool>,
 ) -> Result<MiningResult, Box<dyn Error>> {
     let max_tries = max_tries.unwrap_or(DEFAULT_MAX_TRIES);
-    let hash_func_name = hash_func_name.unwrap_or(DEFAULT_HASH_FUNC_NAME);
+    let hash_func_name = hash_function.name();
+    let threads = threads.max(1);
     let mining_delay = mining_delay_ms.unwrap_or(0);
 
-    log::info!("⛏️  Starting mining with {} algorithm (difficulty: {})", hash_func_name, difficulty);
+    log::info!("⛏️  Starting mining with {} algorithm on {} thread(s) (difficulty: {})",
+        hash_func_name, threads, difficulty);
     
     if mining_delay > 0 {
         log::info!("🐌 Mining slowdown enabled: {}ms delay per attempt (for testing)", mining_delay);
     }
 
+    let search = NonceSearch {
+        data,
+        difficulty,
+        hash_func_name,
+        hash_function,
+        threads,
+        tries_per_thread: max_tries.div_ceil(threads as u128),
+        mining_delay,
+        cancel,
+        found: AtomicBool::new(false),
+        total_attempts: AtomicU64::new(0),
+    };
+
     let start_time = std::time::Instant::now();
-    let mut nonce = 0;
-    let mut try_count = 0;
-    let mut last_status_log = std::time::Instant::now();
-    let status_interval = std::time::Duration::from_secs(10);
-    let mut last_try_count = 0;
-
-    while try_count < max_tries {
-        // Check if we should stop mining (e.g., Ctrl-C was pressed)
-        if MINING_SHOULD_STOP.load(Ordering::Relaxed) {
-            log::info!("🛑 Mining stopped by shutdown signal after {} attempts", try_count);
-            return Err("Mining interrupted by shutdown signal".into());
-        }
-        
-        try_count += 1;
-        
-        // Add artificial delay for testing race conditions
-        if mining_delay > 0 {
-            std::thread::sleep(std::time::Duration::from_millis(mining_delay));
-        }
-        
-        // Log periodic status updates (only if we're doing a lot of attempts)
-        if try_count > 1000 && last_status_log.elapsed() >= status_interval {
-            let attempts_since_last = try_count - last_try_count;
-            let hash_rate = attempts_since_last as f64 / last_status_log.elapsed().as_secs_f64();
-            log::info!("⛏️  Mining status: tried {} nonces, hash rate: {:.2} H/s, current nonce: {}", 
-                try_count, hash_rate, nonce);
-            last_status_log = std::time::Instant::now();
-            last_try_count = try_count;
-        }
-        
-        let hash = hash_with_nonce(data, nonce, hash_func_name)?;
-        if is_hash_acceptable(&hash, difficulty, hash_func_name) {
-            let duration = start_time.elapsed();
-            log::info!("✅ Found valid nonce {} after {} attempts", nonce, try_count);
-            return Ok(MiningResult {
-                nonce,
-                attempts: try_count,
-                duration_secs: duration.as_secs_f64(),
-            });
+    let results = search.run();
+    let duration_secs = start_time.elapsed().as_secs_f64();
+
+    let (outcomes, thread_stats): (Vec<_>, Vec<_>) = results.into_iter().unzip();
+    let attempts = thread_stats.iter().map(|s| s.attempts).sum();
+
+    if let Some(nonce) = outcomes.iter().find_map(|o| match o {
+        WorkerOutcome::Found(nonce) => Some(*nonce),
+        _ => None,
+    }) {
+        log::info!("✅ Found valid nonce {} after {} attempts", nonce, attempts);
+        return Ok(MiningResult {
+            nonce,
+            attempts,
+            duration_secs,
+            threads: thread_stats,
+        });
+    }
+
+    for outcome in &outcomes {
+        if let WorkerOutcome::Failed(e) = outcome {
+            return Err(e.clone().into());
         }
-        nonce += 1;
+    }
+    if outcomes.iter().any(|o| matches!(o, WorkerOutcome::Shutdown)) {
+        log::info!("🛑 Mining stopped by shutdown signal after {} attempts", attempts);
+        return Err("Mining interrupted by shutdown signal".into());
+    }
+    if outcomes.iter().any(|o| matches!(o, WorkerOutcome::Cancelled)) {
+        log::info!("⏹️  Mining cancelled after {} attempts", attempts);
+        return Err(MINING_CANCELLED.into());
     }
 
     Err("maxTries reached, no nonce found".into())
 }
 
-#[allow(dead_code)]
-pub fn hash_with_nonce(data: &str, nonce: u128, hash_func_name: &str) -> Result<String, Box<dyn Error>> {
-    let hash = match hash_func_name {
-        "sha1" => {
-            let mut hasher = Sha1::new();
-            hasher.update(format!("{}{}", data, nonce));
-            format!("{:x}", hasher.finalize())
-        }
-        "sha256" => {
-            let mut hasher = Sha256::new();
-            hasher.update(format!("{}{}", data, nonce));
-            format!("{:x}", hasher.finalize())
-        }
-        "sha384" => {
-            let mut hasher = Sha384::new();
-            hasher.update(format!("{}{}", data, nonce));
-            format!("{:x}", hasher.finalize())
-        }
-        "sha512" => {
-            let mut hasher = Sha512::new();
-            hasher.update(format!("{}{}", data, nonce));
-            format!("{:x}", hasher.finalize())
-        }
-        "randomx" => {
-            let input = format!("{}{}", data, nonce);
-            hash_with_randomx(&input)?
+/// Hashrate measured by [`benchmark`]
+#[derive(Debug, Clone)]
+pub struct BenchmarkResult {
+    pub hash_func_name: String,
+    pub attempts: u128,
+    pub duration_secs: f64,
+    /// Per-thread breakdown of `attempts`
+    pub threads: Vec<ThreadMiningStats>,
+}
+
+impl BenchmarkResult {
+    pub fn hashrate(&self) -> f64 {
+        if self.duration_secs > 0.0 {
+            self.attempts as f64 / self.duration_secs
+        } else {
+            0.0
         }
-        _ => return Err(format!("Unsupported hash function: {}", hash_func_name).into()),
+    }
+}
+
+/// Hash for `duration` on `threads` worker threads, searching for a nonce
+/// that can't be found, and report how many hashes were tried
+pub fn benchmark(
+    hash_func_name: &str,
+    threads: usize,
+    duration: std::time::Duration,
+    mining_delay_ms: Option<u64>,
+) -> Result<BenchmarkResult, Box<dyn Error>> {
+    let hash_function = get_hash_function(hash_func_name)?;
+    let threads = threads.max(1);
+    let stop = AtomicBool::new(false);
+    let search = NonceSearch {
+        data: "modal-miner-benchmark",
+        difficulty: u128::MAX,
+        hash_func_name,
+        hash_function,
+        threads,
+        tries_per_thread: u128::MAX,
+        mining_delay: mining_delay_ms.unwrap_or(0),
+        cancel: Some(&stop),
+        found: AtomicBool::new(false),
+        total_attempts: AtomicU64::new(0),
     };
 
-    Ok(hash)
+    let finished = AtomicBool::new(false);
+    let start_time = std::time::Instant::now();
+    let results = std::thread::scope(|scope| {
+        // Stop the workers once the duration is up, or sooner if they stop on their own
+        scope.spawn(|| {
+            while !finished.load(Ordering::Relaxed) {
+                let remaining = duration.saturating_sub(start_time.elapsed());
+                if remaining.is_zero() {
+                    break;
+                }
+                std::thread::sleep(remaining.min(std::time::Duration::from_millis(50)));
+            }
+            stop.store(true, Ordering::Relaxed);
+        });
+        let results = search.run();
+        finished.store(true, Ordering::Relaxed);
+        results
+    });
+    let duration_secs = start_time.elapsed().as_secs_f64();
+
+    let (outcomes, thread_stats): (Vec<_>, Vec<_>) = results.into_iter().unzip();
+    for outcome in &outcomes {
+        if let WorkerOutcome::Failed(e) = outcome {
+            return Err(e.clone().into());
+        }
+    }
+    if outcomes.iter().any(|o| matches!(o, WorkerOutcome::Shutdown)) {
+        return Err("Benchmark interrupted by shutdown signal".into());
+    }
+
+    Ok(BenchmarkResult {
+        hash_func_name: hash_func_name.to_string(),
+        attempts: thread_stats.iter().map(|s| s.attempts).sum(),
+        duration_secs,
+        threads: thread_stats,
+    })
+}
+
+#[allow(dead_code)]
+pub fn hash_with_nonce(data: &str, nonce: u128, hash_func_name: &str) -> Result<String, Box<dyn Error>> {
+    HASH_FUNCTIONS.hash_with_nonce(data, nonce, hash_func_name)
 }
 
 pub fn difficulty_to_target_hash(
     difficulty: u128,
-    hash_func_name: &str,
+    _hash_func_name: &str,
     coefficient: u128,
     exponent: u128,
     base: u128,
 ) -> String {
-    let _hex_length = HASH_FUNC_HEXADECIMAL_LENGTH[hash_func_name];
     let max_target = coefficient.to_biguint().unwrap() << (exponent * base);
-    let target_bignum = max_target / difficulty;
+    // Zero difficulty (regtest) sets no target
+    let target_bignum = max_target / difficulty.max(1);
     target_bignum.to_str_radix(16)
 }
 
+/// Whether `hash` meets `difficulty`. Zero difficulty sets no target, and
+/// only regtest networks may mine at it, so no hash meets it here; see
+/// [`is_hash_acceptable_regtest`]
 pub fn is_hash_acceptable(hash: &str, difficulty: u128, hash_func_name: &str) -> bool {
+    if difficulty == 0 {
+        return false;
+    }
     let target_hash = difficulty_to_target_hash(difficulty, hash_func_name, DEFAULT_DIFFICULTY_COEFFICIENT, DEFAULT_DIFFICULTY_EXPONENT, DEFAULT_DIFFICULTY_BASE);
     let hash_big_int = BigUint::from_str_radix(hash, 16).unwrap();
     let target_big_int = BigUint::from_str_radix(&target_hash, 16).unwrap();
     hash_big_int < target_big_int
 }
 
+/// Like [`is_hash_acceptable`], except that any hash meets zero difficulty,
+/// as on regtest networks
+pub fn is_hash_acceptable_regtest(hash: &str, difficulty: u128, hash_func_name: &str) -> bool {
+    difficulty == 0 || is_hash_acceptable(hash, difficulty, hash_func_name)
+}
+
 #[allow(dead_code)]
 pub fn validate_nonce(data: &str, nonce: u128, difficulty: u128, hash_func_name: &str) -> Result<bool, Box<dyn Error>> {
-    let hash = hash_with_nonce(data, nonce, hash_func_name)?;
-    Ok(is_hash_acceptable(&hash, difficulty, hash_func_name))
+    HASH_FUNCTIONS.validate_nonce(data, nonce, difficulty, hash_func_name)
 }
 
 /// Calculate the actualized (realized) difficulty from a hash value
@@ -364,6 +859,80 @@ mod tests {
         assert_eq!(nonce, 2401); // Known value for SHA256
     }
     
+    #[test]
+    fn test_builtin_hash_functions_registered() {
+        let names = hash_function_names();
+        for name in ["argon2id", "blake3", "randomx", "sha1", "sha256", "sha384", "sha512"] {
+            assert!(names.contains(&name.to_string()), "{} should be registered", name);
+        }
+        assert!(hash_with_nonce("data", 0, "md5").is_err());
+    }
+
+    #[test]
+    fn test_blake3_and_argon2id_hashes() {
+        let blake3 = hash_with_nonce("data", 7, "blake3").unwrap();
+        assert_eq!(blake3, blake3::hash(b"data7").to_hex().to_string());
+
+        let argon2id = hash_with_nonce("data", 7, "argon2id").unwrap();
+        assert_eq!(argon2id.len(), 64);
+        assert_eq!(argon2id, hash_with_nonce("data", 7, "argon2id").unwrap());
+        assert_ne!(argon2id, hash_with_nonce("data", 8, "argon2id").unwrap());
+    }
+
+    #[test]
+    fn test_registries_configure_separately() {
+        let registry = HashRegistry::default();
+        let other = HashRegistry::default();
+        registry.configure("argon2id", Some(&serde_json::json!({"salt": "another-network-salt"}))).unwrap();
+        let salted = registry.hash_with_nonce("data", 7, "argon2id").unwrap();
+        assert_ne!(salted, other.hash_with_nonce("data", 7, "argon2id").unwrap());
+        assert_eq!(other.hash_with_nonce("data", 7, "argon2id").unwrap(), hash_with_nonce("data", 7, "argon2id").unwrap());
+        assert!(registry.configure("argon2id", Some(&serde_json::json!({"memory_kib": "lots"}))).is_err());
+        assert_eq!(registry.hash_with_nonce("data", 7, "argon2id").unwrap(), salted);
+    }
+
+    #[test]
+    fn test_mine_and_validate_with_blake3() {
+        let nonce = mine("data", 500, None, Some("blake3")).unwrap();
+        assert!(validate_nonce("data", nonce, 500, "blake3").unwrap());
+    }
+
+    #[test]
+    fn test_zero_difficulty_is_regtest_only() {
+        let hash = "f".repeat(64);
+        assert!(!is_hash_acceptable(&hash, 0, "sha256"));
+        assert!(is_hash_acceptable_regtest(&hash, 0, "sha256"));
+        assert!(!is_hash_acceptable_regtest(&hash, 1, "sha256"));
+        assert!(!validate_nonce("data", 0, 0, "sha256").unwrap());
+        assert_eq!(mine("data", 0, Some(1), Some("sha256")).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_mine_parallel_splits_nonce_space() {
+        let result = mine_parallel("data", 500, None, Some("sha256"), None, 4, None).unwrap();
+        assert!(validate_nonce("data", result.nonce, 500, "sha256").unwrap());
+        assert_eq!(result.threads.len(), 4);
+        assert_eq!(result.attempts, result.threads.iter().map(|t| t.attempts).sum::<u128>());
+    }
+
+    #[test]
+    fn test_mine_parallel_cancellation() {
+        let cancel = AtomicBool::new(true);
+        let err = mine_parallel("data", u128::MAX, None, Some("sha256"), None, 2, Some(&cancel)).unwrap_err();
+        assert_eq!(err.to_string(), MINING_CANCELLED);
+    }
+
+    #[test]
+    fn test_benchmark_runs_for_duration() {
+        let duration = std::time::Duration::from_millis(200);
+        let result = benchmark("blake3", 2, duration, None).unwrap();
+        assert!(result.duration_secs >= duration.as_secs_f64());
+        assert_eq!(result.threads.len(), 2);
+        assert!(result.threads.iter().all(|t| t.attempts > 0));
+        assert!(result.hashrate() > 0.0);
+        assert!(benchmark("md5", 1, duration, None).is_err());
+    }
+
     #[test]
     fn test_hash_to_actualized_difficulty() {
         // A hash with more leading zeros should have higher actualized difficulty
@@ -0,0 +1,271 @@
+//! Key rotation records
+//!
+//! A key is replaced by a record in which the old key endorses its
+//! successor from a given height on. Signatures the old key made before that
+//! height stay valid; from that height, only the new key signs for whoever
+//! the old key did. Records are posted at `/rotations/<old key>.json`, in
+//! contracts and in the `modal.keys` system contract validators use.
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::keypair::Keypair;
+use crate::signer::Signer;
+
+/// Directory holding rotation records
+pub const ROTATIONS_DIR: &str = "rotations";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyRotation {
+    pub old_key: String,
+    pub new_key: String,
+    /// First height the new key signs at: a commit height in contracts, a
+    /// round for validators
+    pub effective_height: u64,
+    /// Why the key was replaced, e.g. "retired" or "compromised"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// The old key's signature over the signing payload
+    pub signature: String,
+}
+
+impl KeyRotation {
+    /// Endorse `new_key` as the successor of the key `old` holds
+    pub fn sign(old: &dyn Signer, new_key: &str, effective_height: u64, reason: Option<String>) -> Result<Self> {
+        let mut rotation = Self {
+            old_key: old.public_key_as_base58_identity(),
+            new_key: new_key.to_string(),
+            effective_height,
+            reason,
+            signature: String::new(),
+        };
+        let payload = crate::json_stringify_deterministic::stringify_deterministic(&rotation.signing_payload(), None);
+        rotation.signature = old.sign_string_as_base64_pad(&payload)?;
+        Ok(rotation)
+    }
+
+    /// What the old key signs
+    pub fn signing_payload(&self) -> Value {
+        serde_json::json!({
+            "old_key": self.old_key,
+            "new_key": self.new_key,
+            "effective_height": self.effective_height,
+        })
+    }
+
+    /// Whether the signature is the old key's
+    pub fn verify(&self) -> bool {
+        Keypair::from_public_key(&self.old_key, "ed25519")
+            .and_then(|key| key.verify_json(&self.signature, &self.signing_payload()))
+            .unwrap_or(false)
+    }
+
+    /// Path of the record
+    pub fn path(&self) -> String {
+        format!("/{}/{}.json", ROTATIONS_DIR, self.old_key)
+    }
+
+    /// Commit action posting the record
+    pub fn commit_action(&self) -> Value {
+        serde_json::json!({ "method": "post", "path": self.path(), "value": self })
+    }
+}
+
+/// The old key a path holds the rotation record of
+pub fn old_key_from_path(path: &str) -> Option<&str> {
+    path.trim_start_matches('/')
+        .strip_prefix(ROTATIONS_DIR)?
+        .strip_prefix('/')?
+        .strip_suffix(".json")
+        .filter(|key| !key.is_empty() && !key.contains('/'))
+}
+
+/// Accepted rotations, forming chains from each original key
+#[derive(Debug, Clone, Default)]
+pub struct KeyHistory {
+    rotations: Vec<KeyRotation>,
+}
+
+impl KeyHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rotations recorded in contract state. Records that don't check out
+    /// are skipped.
+    pub fn from_state(state: &Value) -> Self {
+        let mut rotations: Vec<KeyRotation> = state
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter(|(path, _)| old_key_from_path(path).is_some())
+            .filter_map(|(_, value)| serde_json::from_value(value.clone()).ok())
+            .collect();
+        rotations.sort_by_key(|r| r.effective_height);
+        Self::from_rotations(rotations)
+    }
+
+    /// History from records in the order they were accepted. Records that
+    /// don't check out are skipped.
+    pub fn from_rotations(rotations: impl IntoIterator<Item = KeyRotation>) -> Self {
+        let mut history = Self::new();
+        for rotation in rotations {
+            let _ = history.add(rotation);
+        }
+        history
+    }
+
+    pub fn rotations(&self) -> &[KeyRotation] {
+        &self.rotations
+    }
+
+    /// Check a rotation against the history and accept it
+    pub fn add(&mut self, rotation: KeyRotation) -> Result<()> {
+        self.check(&rotation)?;
+        self.rotations.push(rotation);
+        Ok(())
+    }
+
+    /// Check a rotation could be accepted: it's signed by the old key, which
+    /// hasn't been rotated yet, the new key is fresh, and it takes effect
+    /// after the old key did
+    pub fn check(&self, rotation: &KeyRotation) -> Result<()> {
+        if rotation.old_key == rotation.new_key {
+            bail!("Key {} can't be rotated to itself", rotation.old_key);
+        }
+        if !rotation.verify() {
+            bail!("Invalid signature on rotation of {}", rotation.old_key);
+        }
+        Keypair::from_public_key(&rotation.new_key, "ed25519")
+            .map_err(|e| anyhow!("Invalid new key {}: {}", rotation.new_key, e))?;
+        if self.rotated_from(&rotation.old_key).is_some() {
+            bail!("Key {} has already been rotated", rotation.old_key);
+        }
+        if self.original_key(&rotation.new_key) != rotation.new_key || self.rotated_from(&rotation.new_key).is_some() {
+            bail!("Key {} has already been used", rotation.new_key);
+        }
+        if let Some(previous) = self.rotated_to(&rotation.old_key) {
+            if rotation.effective_height <= previous.effective_height {
+                bail!(
+                    "Rotation of {} takes effect at {}, before the key itself did at {}",
+                    rotation.old_key,
+                    rotation.effective_height,
+                    previous.effective_height
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// The rotation replacing `key`, if any
+    pub fn rotated_from(&self, key: &str) -> Option<&KeyRotation> {
+        self.rotations.iter().find(|r| r.old_key == key)
+    }
+
+    /// The rotation introducing `key`, if any
+    pub fn rotated_to(&self, key: &str) -> Option<&KeyRotation> {
+        self.rotations.iter().find(|r| r.new_key == key)
+    }
+
+    /// The first key of the chain `key` is part of
+    pub fn original_key(&self, key: &str) -> String {
+        let mut current = key.to_string();
+        while let Some(rotation) = self.rotated_to(&current) {
+            current = rotation.old_key.clone();
+        }
+        current
+    }
+
+    /// The latest key of the chain `key` is part of
+    pub fn current_key(&self, key: &str) -> String {
+        self.key_at(key, u64::MAX)
+    }
+
+    /// The key signing at `height` for the chain `key` is part of
+    pub fn key_at(&self, key: &str, height: u64) -> String {
+        let mut current = self.original_key(key);
+        while let Some(rotation) = self.rotated_from(&current) {
+            if rotation.effective_height > height {
+                break;
+            }
+            current = rotation.new_key.clone();
+        }
+        current
+    }
+
+    /// Whether a signature by `signer` at `height` counts as one by `key`,
+    /// or by any other key in its chain
+    pub fn signs_for_at(&self, signer: &str, key: &str, height: u64) -> bool {
+        self.key_at(key, height) == signer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_chain() {
+        let first = Keypair::generate().unwrap();
+        let second = Keypair::generate().unwrap();
+        let third = Keypair::generate().unwrap();
+        let (a, b, c) = (first.as_public_address(), second.as_public_address(), third.as_public_address());
+
+        let mut history = KeyHistory::new();
+        history.add(KeyRotation::sign(&first, &b, 10, None).unwrap()).unwrap();
+        history.add(KeyRotation::sign(&second, &c, 20, Some("compromised".into())).unwrap()).unwrap();
+
+        assert_eq!(history.original_key(&c), a);
+        assert_eq!(history.current_key(&a), c);
+        assert_eq!(history.key_at(&c, 5), a);
+        assert_eq!(history.key_at(&a, 15), b);
+
+        // Old signatures stay valid, later ones by retired keys don't
+        assert!(history.signs_for_at(&a, &a, 9));
+        assert!(!history.signs_for_at(&a, &a, 10));
+        assert!(history.signs_for_at(&b, &a, 15));
+        assert!(!history.signs_for_at(&b, &c, 20));
+        assert!(history.signs_for_at(&c, &a, 20));
+    }
+
+    #[test]
+    fn test_rejected_rotations() {
+        let first = Keypair::generate().unwrap();
+        let second = Keypair::generate().unwrap();
+        let b = second.as_public_address();
+
+        let mut history = KeyHistory::new();
+        let rotation = KeyRotation::sign(&first, &b, 10, None).unwrap();
+
+        // Tampered with
+        let mut forged = rotation.clone();
+        forged.effective_height = 1;
+        assert!(history.add(forged).is_err());
+
+        history.add(rotation).unwrap();
+        // Each key is rotated once, and can't go back to an earlier key
+        let other = Keypair::generate().unwrap().as_public_address();
+        assert!(history.add(KeyRotation::sign(&first, &other, 20, None).unwrap()).is_err());
+        assert!(history.add(KeyRotation::sign(&second, &first.as_public_address(), 20, None).unwrap()).is_err());
+        // The new key can only be rotated after it took effect
+        assert!(history.add(KeyRotation::sign(&second, &other, 10, None).unwrap()).is_err());
+        history.add(KeyRotation::sign(&second, &other, 11, None).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_from_state() {
+        let first = Keypair::generate().unwrap();
+        let b = Keypair::generate().unwrap().as_public_address();
+        let rotation = KeyRotation::sign(&first, &b, 3, None).unwrap();
+        let state = serde_json::json!({
+            rotation.path().trim_start_matches('/'): rotation,
+            "rotations/notes.text": "hi",
+        });
+
+        let history = KeyHistory::from_state(&state);
+        assert_eq!(history.rotations(), &[rotation]);
+        assert_eq!(old_key_from_path("/rotations/abc.json"), Some("abc"));
+        assert_eq!(old_key_from_path("/rotations/a/b.json"), None);
+    }
+}
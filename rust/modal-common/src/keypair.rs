@@ -11,6 +11,8 @@ use std::fs;
 use crate::encrypted_text::EncryptedText;
 use crate::json_stringify_deterministic::stringify_deterministic;
 use crate::mnemonic::Mnemonic;
+use crate::passfile_v2::{split_key_name, PassfileV2};
+use crate::signer::SignerConfig;
 
 #[derive(Clone)]
 pub enum KeypairOrPublicKey {
@@ -37,6 +39,9 @@ pub struct KeypairJSON {
     pub encrypted_mnemonic: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub derivation_path: Option<String>,
+    /// Set when the private key lives on a hardware signer instead
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer: Option<SignerConfig>,
 }
 
 impl KeypairJSON {
@@ -67,6 +72,10 @@ impl KeypairJSON {
     pub fn derivation_path(&self) -> Option<&str> {
         self.derivation_path.as_deref()
     }
+
+    pub fn signer(&self) -> Option<&SignerConfig> {
+        self.signer.as_ref()
+    }
 }
 
 impl Keypair {
@@ -82,16 +91,27 @@ impl Keypair {
 
     /// Generate a keypair from a mnemonic phrase with BIP44 derivation
     /// account, change, and index follow the BIP44 standard
-    /// Default path: m/44'/501'/account'/change'/index'
+    /// Default path: m/44'/177017'/account'/change'/index'
     pub fn from_mnemonic(
         mnemonic_phrase: &str,
         account: u32,
         change: u32,
         index: u32,
         passphrase: Option<&str>,
+    ) -> Result<Self> {
+        let path = Mnemonic::default_derivation_path(account, change, index);
+        Self::from_mnemonic_at_path(mnemonic_phrase, &path, passphrase)
+    }
+
+    /// Generate a keypair from a mnemonic phrase at any hardened derivation
+    /// path, so one seed can back many identities
+    pub fn from_mnemonic_at_path(
+        mnemonic_phrase: &str,
+        path: &str,
+        passphrase: Option<&str>,
     ) -> Result<Self> {
         let mnemonic = Mnemonic::from_phrase(mnemonic_phrase)?;
-        let ed25519_keypair = mnemonic.derive_ed25519_keypair(account, change, index, passphrase)?;
+        let ed25519_keypair = mnemonic.derive_ed25519_keypair_at_path(path, passphrase)?;
         
         // Convert ed25519-dalek keypair to libp2p keypair
         let libp2p_ed25519_keypair = ed25519::Keypair::from(
@@ -336,13 +356,25 @@ impl Keypair {
         }
     }
 
+    /// Load a v1 passfile, or the default key of a v2 passfile
     pub fn from_json_string(json_str: &str) -> Result<Self> {
+        if PassfileV2::is_v2_json(json_str) {
+            return PassfileV2::from_json_string(json_str)?.key(None)?.1.keypair();
+        }
         let json: KeypairJSON = serde_json::from_str(json_str)?;
         Self::from_json(&json)
     }
 
+    /// Load an encrypted passfile of either version; a key of a v2 passfile
+    /// can be named as `path#name`
     pub fn from_encrypted_json_file(filepath: &str, password: &str) -> Result<Self> {
+        let (filepath, name) = split_key_name(filepath);
         let json_str = fs::read_to_string(filepath)?;
+        if PassfileV2::is_v2_json(&json_str) {
+            let mut passfile = PassfileV2::from_json_string(&json_str)?;
+            passfile.decrypt(password)?;
+            return passfile.key(name)?.1.keypair();
+        }
         let json: KeypairJSON = serde_json::from_str(&json_str)?;
 
         if let Some(encrypted_key) = json.encrypted_private_key() {
@@ -359,6 +391,7 @@ impl Keypair {
                 mnemonic: decrypted_mnemonic,
                 encrypted_mnemonic: None,
                 derivation_path: json.derivation_path().map(|s| s.to_string()),
+                signer: json.signer.clone(),
             };
             Self::from_json(&decrypted_json)
         } else {
@@ -366,8 +399,14 @@ impl Keypair {
         }
     }
 
+    /// Load a passfile of either version; a key of a v2 passfile can be
+    /// named as `path#name`
     pub fn from_json_file(filepath: &str) -> Result<Self> {
+        let (filepath, name) = split_key_name(filepath);
         let json_str = fs::read_to_string(filepath)?;
+        if name.is_some() {
+            return PassfileV2::from_json_string(&json_str)?.key(name)?.1.keypair();
+        }
         Self::from_json_string(&json_str)
     }
 
@@ -380,6 +419,7 @@ impl Keypair {
             mnemonic: None,
             encrypted_mnemonic: None,
             derivation_path: None,
+            signer: None,
         })
     }
 
@@ -418,6 +458,7 @@ impl Keypair {
             mnemonic,
             encrypted_mnemonic: None,
             derivation_path,
+            signer: None,
         })
     }
 
@@ -455,6 +496,7 @@ impl Keypair {
             mnemonic: None,
             encrypted_mnemonic,
             derivation_path,
+            signer: None,
         })
     }
 
@@ -2,15 +2,29 @@
 #[macro_use]
 extern crate lazy_static;
 
+pub mod batch_verify;
+pub mod block_commits;
+pub mod canonical;
+pub mod difficulty;
+pub mod eras;
+pub mod error_codes;
 pub mod hash_tax;
 pub mod json_stringify_deterministic;
 pub mod keypair;
+pub mod key_rotation;
 pub mod mnemonic;
 pub mod passfile;
+pub mod passfile_v2;
+pub mod signer;
 pub mod encrypted_text;
 pub mod libp2p_identity_keypair;
 pub mod multiaddr_list;
 pub mod shuffle;
+pub mod slashing;
+pub mod uncles;
+pub mod wire;
 pub mod merkle;
 pub mod contract_store;
 pub mod hub_client;
+#[cfg(feature = "bls")]
+pub mod bls;
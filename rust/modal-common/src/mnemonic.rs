@@ -81,7 +81,7 @@ impl Mnemonic {
     }
 
     /// Derive an Ed25519 keypair at a specific BIP44 path
-    /// Path format: m/44'/501'/account'/change'/index'
+    /// Path format: m/44'/177017'/account'/change'/index'
     pub fn derive_ed25519_keypair(
         &self,
         account: u32,
@@ -89,20 +89,22 @@ impl Mnemonic {
         index: u32,
         passphrase: Option<&str>,
     ) -> Result<ed25519_dalek::Keypair> {
+        let path = Self::default_derivation_path(account, change, index);
+        self.derive_ed25519_keypair_at_path(&path, passphrase)
+    }
+
+    /// Derive an Ed25519 keypair at any hardened derivation path,
+    /// e.g. m/44'/177017'/1'/0'/7'
+    pub fn derive_ed25519_keypair_at_path(
+        &self,
+        path: &str,
+        passphrase: Option<&str>,
+    ) -> Result<ed25519_dalek::Keypair> {
+        let components = parse_derivation_path(path)?;
         let seed = self.to_seed(passphrase);
-        
-        // Derive using BIP44 path: m/44'/501'/account'/change'/index'
-        let path = format!(
-            "m/{}'/{}'/{}'/{}'/{}'",
-            MODALITY_BIP44_PURPOSE,
-            MODALITY_BIP44_COIN_TYPE,
-            account,
-            change,
-            index
-        );
 
         // For Ed25519, we use SLIP-0010 derivation
-        let derived_key = derive_ed25519_from_seed(&seed, &path)?;
+        let derived_key = derive_ed25519_from_seed(&seed, &components)?;
         
         let secret = ed25519_dalek::SecretKey::from_bytes(&derived_key)
             .map_err(|e| anyhow!("Failed to create Ed25519 secret key: {}", e))?;
@@ -125,22 +127,47 @@ impl Mnemonic {
     }
 }
 
+/// Parse a derivation path like m/44'/177017'/0'/0'/0' into its indices.
+/// Ed25519 derivation only supports hardened components, so every
+/// component must end with '.
+pub fn parse_derivation_path(path: &str) -> Result<Vec<u32>> {
+    let rest = path
+        .trim()
+        .strip_prefix("m/")
+        .ok_or_else(|| anyhow!("Derivation path must start with m/: {}", path))?;
+    rest.split('/')
+        .map(|component| {
+            let index = component.strip_suffix('\'').ok_or_else(|| {
+                anyhow!(
+                    "Derivation path component {} must be hardened ({}') for Ed25519",
+                    component, component
+                )
+            })?;
+            let index: u32 = index
+                .parse()
+                .map_err(|_| anyhow!("Invalid path component: {}", component))?;
+            if index >= 0x80000000 {
+                return Err(anyhow!("Path component out of range: {}", component));
+            }
+            Ok(index)
+        })
+        .collect()
+}
+
+/// Format derivation path indices as m/a'/b'/...
+pub fn format_derivation_path(components: &[u32]) -> String {
+    let components: Vec<String> = components.iter().map(|c| format!("{}'", c)).collect();
+    format!("m/{}", components.join("/"))
+}
+
 /// Derive an Ed25519 key from a seed using SLIP-0010 derivation
 /// This is a simplified implementation for Ed25519 derivation
-fn derive_ed25519_from_seed(seed: &[u8; 64], path: &str) -> Result<[u8; 32]> {
-    // Parse the path
-    let components: Vec<&str> = path.trim_start_matches("m/").split('/').collect();
-    
+fn derive_ed25519_from_seed(seed: &[u8; 64], components: &[u32]) -> Result<[u8; 32]> {
     let mut key = [0u8; 32];
     key.copy_from_slice(&seed[0..32]);
     
     // For each component in the path
-    for component in components {
-        let component = component.trim_end_matches('\'');
-        let index: u32 = component
-            .parse()
-            .map_err(|_| anyhow!("Invalid path component: {}", component))?;
-        
+    for index in components {
         // For hardened derivation (indicated by ')
         let hardened_index = index | 0x80000000;
         
@@ -202,4 +229,36 @@ mod tests {
         let path = Mnemonic::default_derivation_path(0, 0, 0);
         assert_eq!(path, "m/44'/177017'/0'/0'/0'");
     }
+
+    #[test]
+    fn test_derive_keypair_at_path() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::from_phrase(phrase).unwrap();
+
+        // The default path gives the same keypair as account/change/index
+        let by_index = mnemonic.derive_ed25519_keypair(1, 0, 7, None).unwrap();
+        let by_path = mnemonic
+            .derive_ed25519_keypair_at_path("m/44'/177017'/1'/0'/7'", None)
+            .unwrap();
+        assert_eq!(by_index.secret.as_bytes(), by_path.secret.as_bytes());
+
+        // Paths of other shapes derive other keys
+        let shorter = mnemonic
+            .derive_ed25519_keypair_at_path("m/44'/177017'/1'", None)
+            .unwrap();
+        assert_ne!(by_path.secret.as_bytes(), shorter.secret.as_bytes());
+    }
+
+    #[test]
+    fn test_parse_derivation_path() {
+        assert_eq!(
+            parse_derivation_path("m/44'/177017'/0'/0'/3'").unwrap(),
+            vec![44, 177017, 0, 0, 3]
+        );
+        assert_eq!(format_derivation_path(&[44, 177017, 2]), "m/44'/177017'/2'");
+        assert!(parse_derivation_path("44'/177017'").is_err());
+        assert!(parse_derivation_path("m/44'/177017'/0").is_err());
+        assert!(parse_derivation_path("m/44'/x'").is_err());
+        assert!(parse_derivation_path("m/2147483648'").is_err());
+    }
 }
@@ -0,0 +1,545 @@
+//! Passfile v2
+//!
+//! A v2 passfile holds any number of named keys. Encrypted passfiles derive
+//! one key from the passphrase with Argon2id (parameters stored in the file so
+//! they can be raised later) and seal each key's secrets with AES-256-GCM,
+//! authenticating the key's name and ID so entries can't be swapped between
+//! names or files undetected.
+//!
+//! ```json
+//! {
+//!   "version": 2,
+//!   "kdf": { "algorithm": "argon2id", "m_cost": 19456, "t_cost": 2, "p_cost": 1, "salt": "..." },
+//!   "default_key": "alice",
+//!   "keys": {
+//!     "alice": { "id": "12D3KooW...", "public_key": "...", "sealed": "..." }
+//!   }
+//! }
+//! ```
+//!
+//! v1 passfiles (a single `KeypairJSON`) still load everywhere and are
+//! upgraded to v2 the next time they're encrypted.
+
+use anyhow::{anyhow, Context, Result};
+use base64::prelude::*;
+use ring::aead;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use zeroize::Zeroizing;
+
+use crate::encrypted_text::EncryptedText;
+use crate::keypair::{Keypair, KeypairJSON};
+use crate::signer::SignerConfig;
+
+pub const PASSFILE_VERSION: u32 = 2;
+
+/// Name given to the key of an upgraded v1 passfile
+pub const DEFAULT_KEY_NAME: &str = "default";
+
+/// Argon2id parameters for passphrase key derivation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub algorithm: String,
+    /// Memory in KiB
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+    pub salt: String,
+}
+
+impl KdfParams {
+    /// OWASP's recommended Argon2id parameters, with a fresh salt
+    pub fn recommended() -> Result<Self> {
+        Self::with_costs(19 * 1024, 2, 1)
+    }
+
+    pub fn with_costs(m_cost: u32, t_cost: u32, p_cost: u32) -> Result<Self> {
+        let mut salt = [0u8; 16];
+        SystemRandom::new()
+            .fill(&mut salt)
+            .map_err(|_| anyhow!("Failed to generate salt"))?;
+        Ok(Self {
+            algorithm: "argon2id".to_string(),
+            m_cost,
+            t_cost,
+            p_cost,
+            salt: BASE64_STANDARD.encode(salt),
+        })
+    }
+
+    fn derive_key(&self, password: &str) -> Result<Zeroizing<[u8; 32]>> {
+        if self.algorithm != "argon2id" {
+            return Err(anyhow!("Unsupported passfile KDF: {}", self.algorithm));
+        }
+        let salt = BASE64_STANDARD.decode(&self.salt)?;
+        let params = argon2::Params::new(self.m_cost, self.t_cost, self.p_cost, Some(32))
+            .map_err(|e| anyhow!("Invalid Argon2 parameters: {}", e))?;
+        let argon2 = argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
+        let mut key = Zeroizing::new([0u8; 32]);
+        argon2
+            .hash_password_into(password.as_bytes(), &salt, key.as_mut())
+            .map_err(|e| anyhow!("Failed to derive passfile key: {}", e))?;
+        Ok(key)
+    }
+}
+
+/// One named key of a passfile
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PassfileKey {
+    pub id: String,
+    pub public_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub private_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mnemonic: Option<String>,
+    /// Private key and mnemonic, encrypted: base64 of nonce || ciphertext
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sealed: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub derivation_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer: Option<SignerConfig>,
+}
+
+/// What gets sealed of a key
+#[derive(Serialize, Deserialize)]
+struct KeySecrets {
+    private_key: Option<String>,
+    mnemonic: Option<String>,
+}
+
+impl PassfileKey {
+    pub fn from_keypair(
+        keypair: &Keypair,
+        mnemonic: Option<String>,
+        derivation_path: Option<String>,
+    ) -> Result<Self> {
+        Ok(Self {
+            id: keypair.as_public_address(),
+            public_key: keypair.public_key_as_base64_pad(),
+            private_key: keypair.private_key_as_base64_pad().ok(),
+            mnemonic,
+            sealed: None,
+            derivation_path,
+            signer: None,
+        })
+    }
+
+    pub fn is_sealed(&self) -> bool {
+        self.sealed.is_some()
+    }
+
+    /// The keypair, public-only if the key is sealed or held by a signer
+    pub fn keypair(&self) -> Result<Keypair> {
+        Keypair::from_json(&self.as_keypair_json())
+    }
+
+    /// The key as a v1 passfile
+    pub fn as_keypair_json(&self) -> KeypairJSON {
+        KeypairJSON {
+            id: self.id.clone(),
+            public_key: self.public_key.clone(),
+            private_key: self.private_key.clone(),
+            encrypted_private_key: None,
+            mnemonic: self.mnemonic.clone(),
+            encrypted_mnemonic: None,
+            derivation_path: self.derivation_path.clone(),
+            signer: self.signer.clone(),
+        }
+    }
+
+    fn aad(&self, name: &str) -> Vec<u8> {
+        format!("modality-passfile-v2:{}:{}", name, self.id).into_bytes()
+    }
+
+    fn seal(&mut self, name: &str, key: &[u8; 32]) -> Result<()> {
+        if self.private_key.is_none() && self.mnemonic.is_none() {
+            return Ok(());
+        }
+        let secrets = Zeroizing::new(serde_json::to_vec(&KeySecrets {
+            private_key: self.private_key.take(),
+            mnemonic: self.mnemonic.take(),
+        })?);
+
+        let mut nonce = [0u8; 12];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| anyhow!("Failed to generate nonce"))?;
+        let mut in_out = secrets.to_vec();
+        aead_key(key)?
+            .seal_in_place_append_tag(
+                aead::Nonce::assume_unique_for_key(nonce),
+                aead::Aad::from(self.aad(name)),
+                &mut in_out,
+            )
+            .map_err(|_| anyhow!("Encryption failed"))?;
+
+        let mut combined = nonce.to_vec();
+        combined.extend_from_slice(&in_out);
+        self.sealed = Some(BASE64_STANDARD.encode(combined));
+        Ok(())
+    }
+
+    fn unseal(&mut self, name: &str, key: &[u8; 32]) -> Result<()> {
+        let Some(sealed) = &self.sealed else {
+            return Ok(());
+        };
+        let combined = BASE64_STANDARD.decode(sealed)?;
+        if combined.len() < 12 {
+            return Err(anyhow!("Sealed key {} is too short", name));
+        }
+        let (nonce, ciphertext) = combined.split_at(12);
+        let mut in_out = Zeroizing::new(ciphertext.to_vec());
+        let plaintext = aead_key(key)?
+            .open_in_place(
+                aead::Nonce::try_assume_unique_for_key(nonce).map_err(|_| anyhow!("Invalid nonce"))?,
+                aead::Aad::from(self.aad(name)),
+                &mut in_out,
+            )
+            .map_err(|_| anyhow!("Decryption failed - invalid password or corrupted data"))?;
+        let secrets: KeySecrets = serde_json::from_slice(plaintext)?;
+
+        // The private key has to match the identity it's filed under
+        if let Some(private_key) = &secrets.private_key {
+            let keypair = Keypair::from_json(&KeypairJSON {
+                private_key: Some(private_key.clone()),
+                ..self.as_keypair_json()
+            })?;
+            if keypair.as_public_address() != self.id {
+                return Err(anyhow!("Sealed key {} doesn't match its ID {}", name, self.id));
+            }
+        }
+
+        self.private_key = secrets.private_key;
+        self.mnemonic = secrets.mnemonic;
+        self.sealed = None;
+        Ok(())
+    }
+}
+
+fn aead_key(key: &[u8; 32]) -> Result<aead::LessSafeKey> {
+    let unbound = aead::UnboundKey::new(&aead::AES_256_GCM, key).map_err(|_| anyhow!("Failed to create key"))?;
+    Ok(aead::LessSafeKey::new(unbound))
+}
+
+/// A v2 passfile
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PassfileV2 {
+    pub version: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kdf: Option<KdfParams>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_key: Option<String>,
+    pub keys: BTreeMap<String, PassfileKey>,
+}
+
+impl Default for PassfileV2 {
+    fn default() -> Self {
+        Self {
+            version: PASSFILE_VERSION,
+            kdf: None,
+            default_key: None,
+            keys: BTreeMap::new(),
+        }
+    }
+}
+
+impl PassfileV2 {
+    /// Whether a passfile's JSON is v2
+    pub fn is_v2_json(json_str: &str) -> bool {
+        serde_json::from_str::<serde_json::Value>(json_str)
+            .ok()
+            .and_then(|v| v.get("version").and_then(|v| v.as_u64()))
+            == Some(PASSFILE_VERSION as u64)
+    }
+
+    pub fn from_json_string(json_str: &str) -> Result<Self> {
+        let passfile: Self = serde_json::from_str(json_str)?;
+        if passfile.version != PASSFILE_VERSION {
+            return Err(anyhow!("Unsupported passfile version {}", passfile.version));
+        }
+        Ok(passfile)
+    }
+
+    /// Load a passfile of either version. A v1 passfile becomes a v2 one
+    /// with a single key named `default`; an encrypted v1 passfile needs
+    /// `password` to be read.
+    pub fn load(filepath: &str, password: Option<&str>) -> Result<Self> {
+        let json_str = fs::read_to_string(filepath)
+            .with_context(|| format!("Failed to read passfile {}", filepath))?;
+        if Self::is_v2_json(&json_str) {
+            return Self::from_json_string(&json_str);
+        }
+
+        let mut json: KeypairJSON = serde_json::from_str(&json_str)?;
+        if let Some(encrypted_key) = json.encrypted_private_key.take().filter(|k| !k.is_empty()) {
+            let password = password.ok_or_else(|| anyhow!("{} is encrypted; a password is needed", filepath))?;
+            json.private_key = Some(
+                EncryptedText::decrypt(&encrypted_key, password).map_err(|e| anyhow!("{}", e))?,
+            );
+            json.mnemonic = json
+                .encrypted_mnemonic
+                .take()
+                .and_then(|m| EncryptedText::decrypt(&m, password).ok());
+        }
+
+        let mut passfile = Self::default();
+        passfile.insert(
+            DEFAULT_KEY_NAME,
+            PassfileKey {
+                id: json.id,
+                public_key: json.public_key,
+                private_key: json.private_key,
+                mnemonic: json.mnemonic,
+                sealed: None,
+                derivation_path: json.derivation_path,
+                signer: json.signer,
+            },
+        )?;
+        Ok(passfile)
+    }
+
+    /// Write the passfile, replacing any existing file only once the new
+    /// one is complete
+    pub fn save(&self, filepath: &str) -> Result<()> {
+        let tmp_path = format!("{}.tmp", filepath);
+        fs::write(&tmp_path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write passfile {}", tmp_path))?;
+        fs::rename(&tmp_path, filepath)
+            .with_context(|| format!("Failed to write passfile {}", filepath))
+    }
+
+    /// Add a key; the first one added becomes the default
+    pub fn insert(&mut self, name: &str, key: PassfileKey) -> Result<()> {
+        if name.is_empty() || name.contains('#') {
+            return Err(anyhow!("Invalid key name '{}'", name));
+        }
+        if self.keys.contains_key(name) {
+            return Err(anyhow!("The passfile already has a key named '{}'", name));
+        }
+        if self.is_encrypted() && (key.private_key.is_some() || key.mnemonic.is_some()) {
+            return Err(anyhow!("Decrypt the passfile before adding unencrypted keys to it"));
+        }
+        self.keys.insert(name.to_string(), key);
+        if self.default_key.is_none() {
+            self.default_key = Some(name.to_string());
+        }
+        Ok(())
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.kdf.is_some()
+    }
+
+    /// The named key, or the default key
+    pub fn key(&self, name: Option<&str>) -> Result<(&str, &PassfileKey)> {
+        let name = name
+            .or(self.default_key.as_deref())
+            .ok_or_else(|| anyhow!("The passfile has no default key"))?;
+        self.keys
+            .get_key_value(name)
+            .map(|(name, key)| (name.as_str(), key))
+            .ok_or_else(|| anyhow!("No key named '{}' in the passfile", name))
+    }
+
+    /// Encrypt every key's secrets under `password`, with fresh Argon2id
+    /// parameters
+    pub fn encrypt(&mut self, password: &str) -> Result<()> {
+        self.encrypt_with(password, KdfParams::recommended()?)
+    }
+
+    pub fn encrypt_with(&mut self, password: &str, kdf: KdfParams) -> Result<()> {
+        if self.is_encrypted() {
+            return Err(anyhow!("The passfile is already encrypted"));
+        }
+        if password.is_empty() {
+            return Err(anyhow!("Password cannot be empty"));
+        }
+        let key = kdf.derive_key(password)?;
+        for (name, entry) in self.keys.iter_mut() {
+            entry.seal(name, &key)?;
+        }
+        self.kdf = Some(kdf);
+        Ok(())
+    }
+
+    /// Decrypt every key's secrets
+    pub fn decrypt(&mut self, password: &str) -> Result<()> {
+        let Some(kdf) = &self.kdf else {
+            return Ok(());
+        };
+        let key = kdf.derive_key(password)?;
+        // Unseal a copy so a bad password leaves the passfile untouched
+        let mut keys = self.keys.clone();
+        for (name, entry) in keys.iter_mut() {
+            entry.unseal(name, &key)?;
+        }
+        self.keys = keys;
+        self.kdf = None;
+        Ok(())
+    }
+
+    /// Re-encrypt under a new password, keeping every identity
+    pub fn rotate(&mut self, old_password: &str, new_password: &str) -> Result<()> {
+        let ids = self.ids();
+        self.decrypt(old_password)?;
+        self.encrypt(new_password)?;
+        if self.ids() != ids {
+            return Err(anyhow!("Rotation changed the passfile's identities"));
+        }
+        Ok(())
+    }
+
+    /// Name and ID of every key
+    pub fn ids(&self) -> Vec<(String, String)> {
+        self.keys
+            .iter()
+            .map(|(name, key)| (name.clone(), key.id.clone()))
+            .collect()
+    }
+}
+
+/// Whether a passfile of either version is encrypted
+pub fn is_encrypted_json(json_str: &str) -> bool {
+    if PassfileV2::is_v2_json(json_str) {
+        return PassfileV2::from_json_string(json_str).is_ok_and(|p| p.is_encrypted());
+    }
+    serde_json::from_str::<KeypairJSON>(json_str)
+        .is_ok_and(|json| json.encrypted_private_key().is_some_and(|k| !k.is_empty()))
+}
+
+/// Read a key of a passfile of either version without decrypting it; its
+/// keypair can sign only if the key isn't encrypted. `filepath` may name a
+/// key as `path#name`.
+pub fn load_key(filepath: &str) -> Result<PassfileKey> {
+    let (filepath, name) = split_key_name(filepath);
+    let json_str = fs::read_to_string(filepath)
+        .with_context(|| format!("Failed to read passfile {}", filepath))?;
+    if PassfileV2::is_v2_json(&json_str) {
+        let passfile = PassfileV2::from_json_string(&json_str)?;
+        return Ok(passfile.key(name)?.1.clone());
+    }
+    if let Some(name) = name {
+        return Err(anyhow!("{} is a v1 passfile and has no key named '{}'", filepath, name));
+    }
+    let json: KeypairJSON = serde_json::from_str(&json_str)?;
+    Ok(PassfileKey {
+        id: json.id,
+        public_key: json.public_key,
+        private_key: json.private_key,
+        mnemonic: json.mnemonic,
+        sealed: None,
+        derivation_path: json.derivation_path,
+        signer: json.signer,
+    })
+}
+
+/// Split `path#name` into the passfile path and key name, when the path
+/// itself isn't a file
+pub fn split_key_name(filepath: &str) -> (&str, Option<&str>) {
+    if std::path::Path::new(filepath).exists() {
+        return (filepath, None);
+    }
+    match filepath.rsplit_once('#') {
+        Some((path, name)) if !name.is_empty() => (path, Some(name)),
+        _ => (filepath, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cheap parameters keep the tests fast
+    fn test_kdf() -> KdfParams {
+        KdfParams::with_costs(64, 1, 1).unwrap()
+    }
+
+    fn two_key_passfile() -> (PassfileV2, Keypair, Keypair) {
+        let alice = Keypair::generate().unwrap();
+        let bob = Keypair::generate().unwrap();
+        let mut passfile = PassfileV2::default();
+        passfile
+            .insert("alice", PassfileKey::from_keypair(&alice, Some("abandon".to_string()), None).unwrap())
+            .unwrap();
+        passfile.insert("bob", PassfileKey::from_keypair(&bob, None, None).unwrap()).unwrap();
+        (passfile, alice, bob)
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let (mut passfile, alice, bob) = two_key_passfile();
+        passfile.encrypt_with("hunter2", test_kdf()).unwrap();
+        assert!(passfile.is_encrypted());
+
+        let json = serde_json::to_string(&passfile).unwrap();
+        assert!(!json.contains(&alice.private_key_as_base64_pad().unwrap()));
+        assert!(!json.contains("abandon"));
+        assert!(PassfileV2::is_v2_json(&json));
+
+        // Sealed keys still know their identity
+        let (name, key) = passfile.key(None).unwrap();
+        assert_eq!(name, "alice");
+        assert!(!key.keypair().unwrap().can_sign());
+
+        let mut reloaded = PassfileV2::from_json_string(&json).unwrap();
+        assert!(reloaded.decrypt("wrong").is_err());
+        assert!(reloaded.is_encrypted());
+        reloaded.decrypt("hunter2").unwrap();
+        let (_, alice_key) = reloaded.key(Some("alice")).unwrap();
+        assert_eq!(alice_key.mnemonic.as_deref(), Some("abandon"));
+        assert_eq!(
+            alice_key.keypair().unwrap().private_key_as_base64_pad().unwrap(),
+            alice.private_key_as_base64_pad().unwrap()
+        );
+        let (_, bob_key) = reloaded.key(Some("bob")).unwrap();
+        assert_eq!(bob_key.keypair().unwrap().as_public_address(), bob.as_public_address());
+    }
+
+    #[test]
+    fn test_sealed_keys_bound_to_names() {
+        let (mut passfile, _, _) = two_key_passfile();
+        passfile.encrypt_with("hunter2", test_kdf()).unwrap();
+
+        // Swap the sealed secrets between names
+        let alice_sealed = passfile.keys["alice"].sealed.clone();
+        let bob_sealed = passfile.keys["bob"].sealed.clone();
+        passfile.keys.get_mut("alice").unwrap().sealed = bob_sealed;
+        passfile.keys.get_mut("bob").unwrap().sealed = alice_sealed;
+        assert!(passfile.decrypt("hunter2").is_err());
+    }
+
+    #[test]
+    fn test_rotate_keeps_identities() {
+        let (mut passfile, _, _) = two_key_passfile();
+        passfile.encrypt_with("old", test_kdf()).unwrap();
+        let ids = passfile.ids();
+
+        passfile.decrypt("old").unwrap();
+        passfile.encrypt_with("new", test_kdf()).unwrap();
+        assert_eq!(passfile.ids(), ids);
+        assert!(passfile.clone().decrypt("old").is_err());
+        passfile.decrypt("new").unwrap();
+    }
+
+    #[test]
+    fn test_insert_rules() {
+        let (mut passfile, _, _) = two_key_passfile();
+        let carol = Keypair::generate().unwrap();
+        let key = PassfileKey::from_keypair(&carol, None, None).unwrap();
+        assert!(passfile.insert("alice", key.clone()).is_err());
+        assert!(passfile.insert("a#b", key.clone()).is_err());
+
+        passfile.encrypt_with("hunter2", test_kdf()).unwrap();
+        assert!(passfile.insert("carol", key).is_err());
+    }
+
+    #[test]
+    fn test_split_key_name() {
+        assert_eq!(split_key_name("/no/such/keys.mod_passfile#bob"), ("/no/such/keys.mod_passfile", Some("bob")));
+        assert_eq!(split_key_name("/no/such/keys.mod_passfile"), ("/no/such/keys.mod_passfile", None));
+        assert_eq!(split_key_name("/no/such/keys.mod_passfile#"), ("/no/such/keys.mod_passfile#", None));
+    }
+}
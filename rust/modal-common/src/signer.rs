@@ -0,0 +1,197 @@
+//! Signing backends
+//!
+//! Passfile keypairs sign in-process. A hardware signer keeps the private key
+//! on the device; its passfile holds only the public key and a
+//! [`SignerConfig`] saying how to reach it.
+//!
+//! The `external` backend runs a helper program that talks to the device,
+//! similar to ssh's security key providers:
+//!
+//! - `<program> public-key <key>` prints the hex Ed25519 public key
+//! - `<program> sign <key>` reads the message on stdin and prints the hex
+//!   Ed25519 signature
+//!
+//! where `<key>` names the key on the device, usually a derivation path.
+//! Signatures are verified against the public key before they're used, so a
+//! wrong device or key fails early instead of producing unusable commits.
+
+use anyhow::{anyhow, Context, Result};
+use base64::prelude::*;
+use libp2p::identity::{ed25519, PublicKey as Libp2pPublicKey};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::keypair::{Keypair, KeypairOrPublicKey};
+
+/// Something that can sign with an Ed25519 Modality key
+pub trait Signer {
+    /// Modality ID of the signing key
+    fn public_key_as_base58_identity(&self) -> String;
+
+    fn sign_bytes(&self, bytes: &[u8]) -> Result<Vec<u8>>;
+
+    fn sign_string_as_base64_pad(&self, s: &str) -> Result<String> {
+        let signature = self.sign_bytes(s.as_bytes())?;
+        Ok(BASE64_STANDARD.encode(signature))
+    }
+}
+
+impl Signer for Keypair {
+    fn public_key_as_base58_identity(&self) -> String {
+        Keypair::public_key_as_base58_identity(self)
+    }
+
+    fn sign_bytes(&self, bytes: &[u8]) -> Result<Vec<u8>> {
+        Keypair::sign_bytes(self, bytes)
+    }
+}
+
+/// How to reach a key that isn't in the passfile
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "backend", rename_all = "snake_case")]
+pub enum SignerConfig {
+    /// A helper program speaking the protocol in the module docs
+    External { program: String, key: String },
+}
+
+impl SignerConfig {
+    /// Connect to the signer, checking it holds the key with `expected_id`
+    /// when given
+    pub fn connect(&self, expected_id: Option<&str>) -> Result<Box<dyn Signer>> {
+        let signer = match self {
+            SignerConfig::External { program, key } => ExternalSigner::connect(program, key)?,
+        };
+        if let Some(expected_id) = expected_id {
+            let id = signer.public_key_as_base58_identity();
+            if id != expected_id {
+                return Err(anyhow!(
+                    "Signer holds {} but the passfile is for {}; is the right device connected?",
+                    id,
+                    expected_id
+                ));
+            }
+        }
+        Ok(Box::new(signer))
+    }
+}
+
+/// Signer backed by an external helper program
+pub struct ExternalSigner {
+    program: String,
+    key: String,
+    public_key: Keypair,
+}
+
+impl ExternalSigner {
+    /// Ask the helper for the public key of `key`
+    pub fn connect(program: &str, key: &str) -> Result<Self> {
+        let output = Self::call(program, &["public-key", key], None)?;
+        let bytes = hex::decode(output.trim())
+            .with_context(|| format!("{} returned a public key that isn't hex", program))?;
+        let public_key = ed25519::PublicKey::try_from_bytes(&bytes)
+            .map_err(|e| anyhow!("{} returned an invalid Ed25519 public key: {}", program, e))?;
+        Ok(Self {
+            program: program.to_string(),
+            key: key.to_string(),
+            public_key: Keypair::new(KeypairOrPublicKey::PublicKey(Libp2pPublicKey::from(
+                public_key,
+            ))),
+        })
+    }
+
+    /// Public-only keypair of the signing key, e.g. for writing its passfile
+    pub fn public_keypair(&self) -> &Keypair {
+        &self.public_key
+    }
+
+    pub fn config(&self) -> SignerConfig {
+        SignerConfig::External {
+            program: self.program.clone(),
+            key: self.key.clone(),
+        }
+    }
+
+    fn call(program: &str, args: &[&str], stdin: Option<&[u8]>) -> Result<String> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            // The helper may ask the user to confirm on the device
+            .stderr(Stdio::inherit())
+            .spawn()
+            .with_context(|| format!("Failed to run signer program {}", program))?;
+        {
+            let mut child_stdin = child.stdin.take().expect("stdin is piped");
+            if let Some(input) = stdin {
+                child_stdin.write_all(input)?;
+            }
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(anyhow!("{} {} failed ({})", program, args.join(" "), output.status));
+        }
+        Ok(String::from_utf8(output.stdout)?)
+    }
+}
+
+impl Signer for ExternalSigner {
+    fn public_key_as_base58_identity(&self) -> String {
+        self.public_key.public_key_as_base58_identity()
+    }
+
+    fn sign_bytes(&self, bytes: &[u8]) -> Result<Vec<u8>> {
+        let output = Self::call(&self.program, &["sign", &self.key], Some(bytes))?;
+        let signature = hex::decode(output.trim())
+            .with_context(|| format!("{} returned a signature that isn't hex", self.program))?;
+        let valid = self
+            .public_key
+            .verify_signature_for_bytes(&BASE64_STANDARD.encode(&signature), bytes)?;
+        if !valid {
+            return Err(anyhow!(
+                "{} returned a signature that doesn't verify for {}",
+                self.program,
+                self.public_key_as_base58_identity()
+            ));
+        }
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keypair::KeypairJSON;
+
+    #[test]
+    fn test_keypair_signer() {
+        let keypair = Keypair::generate().unwrap();
+        let signer: &dyn Signer = &keypair;
+        let signature = signer.sign_string_as_base64_pad("hello").unwrap();
+        assert!(keypair.verify_signature_for_string(&signature, "hello").unwrap());
+        assert_eq!(signer.public_key_as_base58_identity(), keypair.as_public_address());
+    }
+
+    #[test]
+    fn test_signer_config_in_passfile() {
+        let keypair = Keypair::generate().unwrap();
+        let mut json = keypair.as_public_json().unwrap();
+        json.signer = Some(SignerConfig::External {
+            program: "modal-ledger".to_string(),
+            key: "m/44'/177017'/0'/0'/0'".to_string(),
+        });
+        let json_str = serde_json::to_string(&json).unwrap();
+        assert!(json_str.contains("\"backend\":\"external\""));
+
+        let parsed: KeypairJSON = serde_json::from_str(&json_str).unwrap();
+        assert_eq!(parsed.signer(), json.signer.as_ref());
+        // Still loads as a public-only keypair
+        let loaded = Keypair::from_json(&parsed).unwrap();
+        assert!(!loaded.can_sign());
+        assert_eq!(loaded.as_public_address(), keypair.as_public_address());
+
+        // Passfiles without a signer are unaffected
+        let plain: KeypairJSON = serde_json::from_str(&keypair.as_json_string().unwrap()).unwrap();
+        assert!(plain.signer().is_none());
+    }
+}
@@ -0,0 +1,128 @@
+//! Equivocation evidence carried in mined blocks.
+//!
+//! A validator that signs two different drafts for the same consensus round
+//! has equivocated. Nodes that see both drafts gossip the pair as evidence,
+//! miners include verified evidence in the blocks they mine, and validator
+//! selection excludes offenders by the evidence in canonical blocks only, so
+//! every node seats the same validators. This module checks what evidence
+//! says about itself; the drafts' signatures are verified where validator
+//! blocks are (`modal_datastore::slashing`).
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Maximum number of pieces of evidence a single block may include
+pub const MAX_EVIDENCE_PER_BLOCK: usize = 2;
+
+/// Largest serialized draft accepted as evidence
+pub const MAX_EVIDENCE_DRAFT_BYTES: usize = 64 * 1024;
+
+/// Two drafts signed by the same validator for the same round
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EquivocationEvidence {
+    pub peer_id: String,
+    pub round: u64,
+    /// The signed drafts as gossiped, ordered by closing signature
+    pub first: serde_json::Value,
+    pub second: serde_json::Value,
+}
+
+fn closing_sig(draft: &serde_json::Value) -> &str {
+    draft.get("closing_sig").and_then(|sig| sig.as_str()).unwrap_or_default()
+}
+
+impl EquivocationEvidence {
+    /// Evidence from two drafts, in a fixed order so the same pair is the
+    /// same evidence whichever arrived first
+    pub fn new(peer_id: String, round: u64, a: serde_json::Value, b: serde_json::Value) -> Self {
+        let (first, second) = if closing_sig(&a) <= closing_sig(&b) { (a, b) } else { (b, a) };
+        Self { peer_id, round, first, second }
+    }
+
+    /// String committed to by the including block's data hash
+    pub fn to_hash_string(&self) -> String {
+        format!("{}{}{}{}", self.peer_id, self.round, closing_sig(&self.first), closing_sig(&self.second))
+    }
+
+    /// Check the drafts are two different ones from `peer_id` for `round`.
+    /// Their signatures aren't checked here.
+    pub fn check(&self) -> Result<(), String> {
+        for draft in [&self.first, &self.second] {
+            let size = serde_json::to_vec(draft).map(|bytes| bytes.len()).unwrap_or(usize::MAX);
+            if size > MAX_EVIDENCE_DRAFT_BYTES {
+                return Err(format!("draft of {} bytes exceeds the maximum of {}", size, MAX_EVIDENCE_DRAFT_BYTES));
+            }
+            if draft.get("peer_id").and_then(|p| p.as_str()) != Some(self.peer_id.as_str()) {
+                return Err(format!("draft is not from {}", self.peer_id));
+            }
+            if draft.get("round_id").and_then(|r| r.as_u64()) != Some(self.round) {
+                return Err(format!("draft is not for round {}", self.round));
+            }
+            if closing_sig(draft).is_empty() {
+                return Err("draft is not signed".to_string());
+            }
+        }
+        if closing_sig(&self.first) >= closing_sig(&self.second) {
+            return Err("drafts are the same or out of order".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Check the evidence included by a block: its count, each piece on its own,
+/// and that no offense is included twice
+pub fn validate_evidence(evidence: &[EquivocationEvidence]) -> Result<(), String> {
+    if evidence.len() > MAX_EVIDENCE_PER_BLOCK {
+        return Err(format!(
+            "{} pieces of evidence exceeds the maximum of {}",
+            evidence.len(),
+            MAX_EVIDENCE_PER_BLOCK
+        ));
+    }
+    let mut seen = HashSet::new();
+    for item in evidence {
+        item.check()?;
+        if !seen.insert((item.peer_id.as_str(), item.round)) {
+            return Err(format!("equivocation of {} in round {} is included twice", item.peer_id, item.round));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for EquivocationEvidence {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self {
+            peer_id: u.arbitrary()?,
+            round: u.arbitrary()?,
+            first: crate::wire::arbitrary_json(u, 3)?,
+            second: crate::wire::arbitrary_json(u, 3)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn draft(peer_id: &str, round: u64, sig: &str) -> serde_json::Value {
+        json!({ "peer_id": peer_id, "round_id": round, "events": [], "closing_sig": sig })
+    }
+
+    #[test]
+    fn test_evidence_checks() {
+        let evidence = EquivocationEvidence::new("peer".to_string(), 7, draft("peer", 7, "sig_b"), draft("peer", 7, "sig_a"));
+        assert_eq!(closing_sig(&evidence.first), "sig_a");
+        assert!(evidence.check().is_ok());
+        assert!(validate_evidence(&[evidence.clone()]).is_ok());
+        assert!(validate_evidence(&[evidence.clone(), evidence.clone()]).is_err());
+
+        let same = EquivocationEvidence::new("peer".to_string(), 7, draft("peer", 7, "sig_a"), draft("peer", 7, "sig_a"));
+        assert!(same.check().is_err());
+        let other_round = EquivocationEvidence::new("peer".to_string(), 7, draft("peer", 7, "sig_a"), draft("peer", 8, "sig_b"));
+        assert!(other_round.check().is_err());
+        let other_peer = EquivocationEvidence::new("peer".to_string(), 7, draft("other", 7, "sig_a"), draft("peer", 7, "sig_b"));
+        assert!(other_peer.check().is_err());
+    }
+}
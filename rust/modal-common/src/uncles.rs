@@ -0,0 +1,93 @@
+//! Uncle (near-miss) block references.
+//!
+//! A block that loses the first-seen race to a competing block at the same
+//! height is stored as an orphan, and its nomination is lost. Miners may
+//! reference such recent orphans as uncles in the next blocks they produce,
+//! and validator selection credits uncle nominations alongside canonical ones,
+//! so a miner's latency matters less for how many nominations it gets counted.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Maximum number of uncles a single block may reference
+pub const MAX_UNCLES_PER_BLOCK: usize = 2;
+
+/// How many blocks back an uncle may be, relative to the block including it
+pub const MAX_UNCLE_DEPTH: u64 = 6;
+
+/// Reference to an orphaned block included in a later block
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct UncleRef {
+    pub hash: String,
+    pub index: u64,
+    /// Peer the uncle nominated; credited during validator selection
+    pub nominated_peer_id: String,
+}
+
+impl UncleRef {
+    /// String committed to by the including block's data hash
+    pub fn to_hash_string(&self) -> String {
+        format!("{}{}{}", self.hash, self.index, self.nominated_peer_id)
+    }
+}
+
+/// Whether an orphan at `uncle_index` may be referenced by a block at `block_index`
+pub fn is_within_uncle_depth(block_index: u64, uncle_index: u64) -> bool {
+    uncle_index < block_index && block_index - uncle_index <= MAX_UNCLE_DEPTH
+}
+
+/// Check the uncles referenced by a block at `block_index`.
+///
+/// This only checks what can be verified from the block itself: the number of
+/// uncles, their depth, and that none is listed twice.
+pub fn validate_uncles(block_index: u64, uncles: &[UncleRef]) -> Result<(), String> {
+    if uncles.len() > MAX_UNCLES_PER_BLOCK {
+        return Err(format!(
+            "{} uncles exceeds the maximum of {}",
+            uncles.len(),
+            MAX_UNCLES_PER_BLOCK
+        ));
+    }
+    let mut seen = HashSet::new();
+    for uncle in uncles {
+        if !is_within_uncle_depth(block_index, uncle.index) {
+            return Err(format!(
+                "uncle {} at index {} is not within {} blocks before {}",
+                uncle.hash, uncle.index, MAX_UNCLE_DEPTH, block_index
+            ));
+        }
+        if !seen.insert(uncle.hash.as_str()) {
+            return Err(format!("uncle {} is referenced twice", uncle.hash));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uncle(hash: &str, index: u64) -> UncleRef {
+        UncleRef {
+            hash: hash.to_string(),
+            index,
+            nominated_peer_id: "peer".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_validate_uncles() {
+        assert!(validate_uncles(10, &[]).is_ok());
+        assert!(validate_uncles(10, &[uncle("a", 9), uncle("b", 4)]).is_ok());
+
+        // Too deep, same height, or in the future
+        assert!(validate_uncles(10, &[uncle("a", 3)]).is_err());
+        assert!(validate_uncles(10, &[uncle("a", 10)]).is_err());
+        assert!(validate_uncles(10, &[uncle("a", 11)]).is_err());
+
+        // Duplicates and too many
+        assert!(validate_uncles(10, &[uncle("a", 9), uncle("a", 9)]).is_err());
+        assert!(validate_uncles(10, &[uncle("a", 9), uncle("b", 8), uncle("c", 7)]).is_err());
+    }
+}
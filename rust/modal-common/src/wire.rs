@@ -0,0 +1,154 @@
+//! Wire formats and limits on untrusted input.
+//!
+//! Blocks, consensus messages and commits arrive from peers, so their parsers
+//! check sizes before and after deserializing and reject malformed fields
+//! with an error rather than panicking further down. Messages are JSON or,
+//! between peers that both support it, CBOR behind a one-byte marker that no
+//! JSON text starts with, so `from_slice` reads either. With the `arbitrary`
+//! feature this module also generates JSON values for the fuzz targets.
+
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Longest peer ID, hash or other identifier accepted in a message
+pub const MAX_ID_LEN: usize = 128;
+
+/// Fail if `len` exceeds `max`
+pub fn check_len(what: &str, len: usize, max: usize) -> Result<()> {
+    if len > max {
+        anyhow::bail!("{} length {} exceeds the maximum of {}", what, len, max);
+    }
+    Ok(())
+}
+
+/// Deserialize JSON of at most `max_bytes`
+pub fn from_json_slice<T: DeserializeOwned>(what: &str, data: &[u8], max_bytes: usize) -> Result<T> {
+    check_len(what, data.len(), max_bytes)?;
+    serde_json::from_slice(data).map_err(|e| anyhow::anyhow!("Malformed {}: {}", what, e))
+}
+
+/// First byte of a CBOR message, version 1 of the binary format
+pub const CBOR_MARKER: u8 = 0x01;
+
+/// How a message is encoded on the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WireFormat {
+    #[default]
+    Json,
+    Cbor,
+}
+
+impl WireFormat {
+    /// The format `data` is in, judging by its first byte
+    pub fn of(data: &[u8]) -> Self {
+        if data.first() == Some(&CBOR_MARKER) {
+            WireFormat::Cbor
+        } else {
+            WireFormat::Json
+        }
+    }
+}
+
+/// Encode `value` in `format`
+pub fn to_vec<T: Serialize>(value: &T, format: WireFormat) -> Result<Vec<u8>> {
+    match format {
+        WireFormat::Json => Ok(serde_json::to_vec(value)?),
+        WireFormat::Cbor => {
+            let mut data = vec![CBOR_MARKER];
+            ciborium::into_writer(value, &mut data).map_err(|e| anyhow::anyhow!("Failed to encode CBOR: {}", e))?;
+            Ok(data)
+        }
+    }
+}
+
+/// Deserialize JSON or marked CBOR of at most `max_bytes`
+pub fn from_slice<T: DeserializeOwned>(what: &str, data: &[u8], max_bytes: usize) -> Result<T> {
+    match WireFormat::of(data) {
+        WireFormat::Json => from_json_slice(what, data, max_bytes),
+        WireFormat::Cbor => {
+            check_len(what, data.len(), max_bytes)?;
+            ciborium::from_reader(&data[1..]).map_err(|e| anyhow::anyhow!("Malformed {}: {}", what, e))
+        }
+    }
+}
+
+/// Whether `s` is a 64 character lowercase hex SHA-256 digest
+pub fn is_hex_digest(s: &str) -> bool {
+    s.len() == 64 && s.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+/// Fail unless `s` is non-empty lowercase hex of at most `MAX_ID_LEN`
+/// characters, as block hashes are for every mining hash function
+pub fn check_hex(what: &str, s: &str) -> Result<()> {
+    check_len(what, s.len(), MAX_ID_LEN)?;
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)) {
+        anyhow::bail!("{} is not lowercase hex", what);
+    }
+    Ok(())
+}
+
+/// Generate a JSON value nested at most `depth` levels
+#[cfg(feature = "arbitrary")]
+pub fn arbitrary_json(u: &mut arbitrary::Unstructured, depth: usize) -> arbitrary::Result<serde_json::Value> {
+    use serde_json::Value;
+    let max_kind = if depth == 0 { 3 } else { 5 };
+    Ok(match u.int_in_range(0..=max_kind)? {
+        0 => Value::Null,
+        1 => Value::Bool(u.arbitrary()?),
+        2 => Value::from(u.arbitrary::<i64>()?),
+        3 => Value::String(u.arbitrary()?),
+        4 => {
+            let len = u.int_in_range(0..=4)?;
+            Value::Array((0..len).map(|_| arbitrary_json(u, depth - 1)).collect::<arbitrary::Result<_>>()?)
+        }
+        _ => {
+            let len = u.int_in_range(0..=4)?;
+            let mut map = serde_json::Map::new();
+            for _ in 0..len {
+                map.insert(u.arbitrary()?, arbitrary_json(u, depth - 1)?);
+            }
+            Value::Object(map)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits() {
+        assert!(check_len("hash", 64, 64).is_ok());
+        assert!(check_len("hash", 65, 64).is_err());
+        assert!(from_json_slice::<Vec<u8>>("list", b"[1,2]", 5).is_ok());
+        assert!(from_json_slice::<Vec<u8>>("list", b"[1,2,3]", 5).is_err());
+        assert!(from_json_slice::<Vec<u8>>("list", b"[1,", 5).is_err());
+
+        assert!(is_hex_digest(&"ab".repeat(32)));
+        assert!(!is_hex_digest(&"AB".repeat(32)));
+        assert!(!is_hex_digest("abc"));
+        // Same length in bytes, but not hex
+        assert!(!is_hex_digest(&format!("{}é", "a".repeat(62))));
+        assert!(check_hex("hash", &"0f".repeat(64)).is_ok());
+        assert!(check_hex("hash", &"0f".repeat(65)).is_err());
+        assert!(check_hex("hash", "").is_err());
+        assert!(check_hex("hash", "0x12").is_err());
+    }
+
+    #[test]
+    fn test_formats_round_trip() {
+        let value = serde_json::json!({"hash": "ab".repeat(32), "index": 7, "uncles": [], "nonce": "123"});
+        let json = to_vec(&value, WireFormat::Json).unwrap();
+        let cbor = to_vec(&value, WireFormat::Cbor).unwrap();
+        assert_eq!(WireFormat::of(&json), WireFormat::Json);
+        assert_eq!(WireFormat::of(&cbor), WireFormat::Cbor);
+        assert!(cbor.len() < json.len());
+        assert_eq!(from_slice::<serde_json::Value>("block", &json, 1024).unwrap(), value);
+        assert_eq!(from_slice::<serde_json::Value>("block", &cbor, 1024).unwrap(), value);
+
+        assert!(from_slice::<serde_json::Value>("block", &cbor, cbor.len() - 1).is_err());
+        assert!(from_slice::<serde_json::Value>("block", &cbor[..cbor.len() - 1], 1024).is_err());
+        assert!(from_slice::<serde_json::Value>("block", &[CBOR_MARKER], 1024).is_err());
+    }
+}
@@ -0,0 +1,373 @@
+//! Datastore backup and restore
+//!
+//! A backup is a zstd-compressed tar archive holding `manifest.json` and one
+//! `<store>.kv` dump per store, each a sequence of length-prefixed key/value
+//! records. The manifest records each dump's entry count and SHA-256, the
+//! miner chain tip and the validator round, and restore checks all of them
+//! before the restored datastore is used.
+//!
+//! Every store is read through the same `&DatastoreManager`, so the snapshot
+//! is consistent across stores as long as nothing else writes meanwhile: take
+//! it while holding the node's datastore lock, or with the node stopped (the
+//! stores can only be opened by one process at a time).
+
+use crate::models::MinerBlock;
+use crate::stores::{StorageConfig, Store, StoreBackend, WriteBatch};
+use crate::datastore_manager::STORE_NAMES;
+use crate::{DatastoreManager, Error, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Backup format version written to the manifest
+pub const BACKUP_VERSION: u32 = 1;
+
+const MANIFEST_NAME: &str = "manifest.json";
+const TAR_BLOCK: usize = 512;
+/// Records written to a store per batch during restore
+const RESTORE_BATCH_SIZE: usize = 10_000;
+
+/// Contents of one store's dump
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoreManifest {
+    pub name: String,
+    pub entries: u64,
+    /// Hex SHA-256 of the dump
+    pub sha256: String,
+}
+
+/// Canonical miner block at the tip of the chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainTip {
+    pub index: u64,
+    pub hash: String,
+}
+
+/// Description of a backup, checked on restore
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub version: u32,
+    /// Unix seconds when the backup was taken
+    pub created_at: u64,
+    pub stores: Vec<StoreManifest>,
+    pub miner_tip: Option<ChainTip>,
+    pub current_round: u64,
+}
+
+impl DatastoreManager {
+    pub(crate) fn store_backend(&self, name: &str) -> Option<&dyn StoreBackend> {
+        match name {
+            "miner_canon" => Some(self.miner_canon().backend()),
+            "miner_forks" => Some(self.miner_forks().backend()),
+            "miner_active" => Some(self.miner_active().backend()),
+            "validator_final" => Some(self.validator_final().backend()),
+            "validator_active" => Some(self.validator_active().backend()),
+            "node_state" => Some(self.node_state().backend()),
+            "node_metrics" => Some(self.node_metrics().backend()),
+            "index" => Some(self.index().backend()),
+            _ => None,
+        }
+    }
+
+    /// Write a backup of every store to `out`.
+    ///
+    /// Nothing else may write to the datastore until this returns.
+    pub async fn backup<W: Write>(&self, out: W) -> Result<BackupManifest> {
+        // Dump each store to a temporary file first: tar needs the size up front
+        let mut dumps = Vec::new();
+        let mut stores = Vec::new();
+        for name in STORE_NAMES {
+            let db = self.store_backend(name).expect("known store");
+            let mut file = tempfile::tempfile()?;
+            let (entries, sha256) = dump_store(db, &mut file)?;
+            file.seek(SeekFrom::Start(0))?;
+            dumps.push(file);
+            stores.push(StoreManifest { name: name.to_string(), entries, sha256 });
+        }
+
+        let manifest = BackupManifest {
+            version: BACKUP_VERSION,
+            created_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            stores,
+            miner_tip: self.miner_tip().await?,
+            current_round: self.get_current_round().await?,
+        };
+
+        let mut encoder = zstd::Encoder::new(out, 0)?;
+        let manifest_json = serde_json::to_vec_pretty(&manifest)?;
+        write_tar_entry(&mut encoder, MANIFEST_NAME, manifest.created_at, manifest_json.len() as u64, &mut manifest_json.as_slice())?;
+        for (store, mut dump) in manifest.stores.iter().zip(dumps) {
+            let size = dump.metadata()?.len();
+            write_tar_entry(&mut encoder, &format!("{}.kv", store.name), manifest.created_at, size, &mut dump)?;
+        }
+        encoder.write_all(&[0u8; TAR_BLOCK * 2])?;
+        encoder.finish()?.flush()?;
+        Ok(manifest)
+    }
+
+    /// Restore a backup read from `input` into a new datastore at `data_dir`,
+    /// which must not hold a datastore yet. Each store's dump and the chain
+    /// tip are checked against the manifest; if any check fails the partly
+    /// restored directory is removed.
+    pub async fn restore<R: Read>(data_dir: &Path, input: R) -> Result<(Self, BackupManifest)> {
+        Self::restore_with_config(data_dir, &StorageConfig::default(), input).await
+    }
+
+    /// Restore a backup like `restore`, into stores on the configured
+    /// storage engines. Backups don't depend on the engines they were taken
+    /// from, so this also moves a datastore to other engines.
+    pub async fn restore_with_config<R: Read>(
+        data_dir: &Path,
+        config: &StorageConfig,
+        input: R,
+    ) -> Result<(Self, BackupManifest)> {
+        if data_dir.exists() && data_dir.read_dir()?.next().is_some() {
+            return Err(Error::InvalidData(format!("{} is not empty", data_dir.display())));
+        }
+        let mgr = Self::open_with_config(data_dir, config)?;
+        match mgr.restore_from(input).await {
+            Ok(manifest) => Ok((mgr, manifest)),
+            Err(e) => {
+                drop(mgr);
+                let _ = std::fs::remove_dir_all(data_dir);
+                Err(e)
+            }
+        }
+    }
+
+    async fn restore_from<R: Read>(&self, input: R) -> Result<BackupManifest> {
+        let mut decoder = zstd::Decoder::new(input)?;
+
+        let (name, size) = read_tar_header(&mut decoder)?
+            .ok_or_else(|| Error::InvalidData("Backup is empty".to_string()))?;
+        if name != MANIFEST_NAME {
+            return Err(Error::InvalidData(format!("Expected {} first, found {}", MANIFEST_NAME, name)));
+        }
+        let mut manifest_json = Vec::new();
+        (&mut decoder).take(size).read_to_end(&mut manifest_json)?;
+        skip_tar_padding(&mut decoder, size)?;
+        let manifest: BackupManifest = serde_json::from_slice(&manifest_json)?;
+        if manifest.version != BACKUP_VERSION {
+            return Err(Error::InvalidData(format!("Unsupported backup version {}", manifest.version)));
+        }
+
+        let mut restored = Vec::new();
+        while let Some((name, size)) = read_tar_header(&mut decoder)? {
+            let store = name.strip_suffix(".kv")
+                .and_then(|store| manifest.stores.iter().find(|s| s.name == store))
+                .ok_or_else(|| Error::InvalidData(format!("Unexpected backup entry {}", name)))?;
+            let db = self.store_backend(&store.name)
+                .ok_or_else(|| Error::InvalidData(format!("Unknown store {}", store.name)))?;
+
+            let (entries, sha256) = load_store(db, (&mut decoder).take(size))?;
+            skip_tar_padding(&mut decoder, size)?;
+            if entries != store.entries || sha256 != store.sha256 {
+                return Err(Error::InvalidData(format!("Store {} does not match the manifest", store.name)));
+            }
+            restored.push(store.name.clone());
+        }
+        for store in &manifest.stores {
+            if !restored.contains(&store.name) {
+                return Err(Error::InvalidData(format!("Backup is missing store {}", store.name)));
+            }
+        }
+
+        self.flush_all()?;
+        if self.miner_tip().await? != manifest.miner_tip {
+            return Err(Error::InvalidData("Restored chain tip does not match the manifest".to_string()));
+        }
+        if self.get_current_round().await? != manifest.current_round {
+            return Err(Error::InvalidData("Restored round does not match the manifest".to_string()));
+        }
+        // Backups from older versions are brought up to date
+        self.migrate()?;
+        self.ensure_indexes()?;
+        Ok(manifest)
+    }
+
+    async fn miner_tip(&self) -> Result<Option<ChainTip>> {
+        let blocks = MinerBlock::find_all_canonical_multi(self).await?;
+        Ok(blocks.last().map(|b| ChainTip { index: b.index, hash: b.hash.clone() }))
+    }
+}
+
+/// Write every record of a store, returning the count and hex SHA-256
+fn dump_store<W: Write>(db: &dyn StoreBackend, out: &mut W) -> Result<(u64, String)> {
+    let mut hasher = Sha256::new();
+    let mut entries = 0;
+    for item in db.range(None, None) {
+        let (key, value) = item?;
+        for part in [&key[..], &value[..]] {
+            let len = (part.len() as u32).to_be_bytes();
+            hasher.update(len);
+            hasher.update(part);
+            out.write_all(&len)?;
+            out.write_all(part)?;
+        }
+        entries += 1;
+    }
+    Ok((entries, hex::encode(hasher.finalize())))
+}
+
+/// Write the records of a dump to a store, returning the count and hex SHA-256
+fn load_store<R: Read>(db: &dyn StoreBackend, mut input: R) -> Result<(u64, String)> {
+    let mut hasher = Sha256::new();
+    let mut entries = 0;
+    let mut batch = WriteBatch::default();
+    while let Some(key) = read_part(&mut input, &mut hasher, true)? {
+        let value = read_part(&mut input, &mut hasher, false)?
+            .ok_or_else(|| Error::InvalidData("Truncated record".to_string()))?;
+        batch.put(key, value);
+        entries += 1;
+        if batch.len() >= RESTORE_BATCH_SIZE {
+            db.write(std::mem::take(&mut batch))?;
+        }
+    }
+    db.write(batch)?;
+    Ok((entries, hex::encode(hasher.finalize())))
+}
+
+/// Read one length-prefixed part; `None` at a clean end of input when allowed
+fn read_part<R: Read>(input: &mut R, hasher: &mut Sha256, end_ok: bool) -> Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 4];
+    let read = read_full(input, &mut len)?;
+    if read == 0 && end_ok {
+        return Ok(None);
+    }
+    if read < len.len() {
+        return Err(Error::InvalidData("Truncated record".to_string()));
+    }
+    let mut part = vec![0u8; u32::from_be_bytes(len) as usize];
+    input.read_exact(&mut part)?;
+    hasher.update(len);
+    hasher.update(&part);
+    Ok(Some(part))
+}
+
+fn read_full<R: Read>(input: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match input.read(&mut buf[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+/// Write a regular file entry in ustar format
+fn write_tar_entry<W: Write, R: Read>(out: &mut W, name: &str, mtime: u64, size: u64, content: &mut R) -> Result<()> {
+    let mut header = [0u8; TAR_BLOCK];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    header[100..108].copy_from_slice(b"0000644\0");
+    header[108..116].copy_from_slice(b"0000000\0");
+    header[116..124].copy_from_slice(b"0000000\0");
+    header[124..136].copy_from_slice(format!("{:011o}\0", size).as_bytes());
+    header[136..148].copy_from_slice(format!("{:011o}\0", mtime).as_bytes());
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    // The checksum is computed with its own field as spaces
+    header[148..156].copy_from_slice(b"        ");
+    let checksum: u32 = header.iter().map(|b| *b as u32).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+    out.write_all(&header)?;
+
+    let copied = std::io::copy(&mut content.take(size), out)?;
+    if copied != size {
+        return Err(Error::InvalidData(format!("{} changed while it was archived", name)));
+    }
+    let padding = (TAR_BLOCK - (size as usize % TAR_BLOCK)) % TAR_BLOCK;
+    out.write_all(&vec![0u8; padding])?;
+    Ok(())
+}
+
+/// Read the next entry's name and size; `None` at the end of the archive
+fn read_tar_header<R: Read>(input: &mut R) -> Result<Option<(String, u64)>> {
+    let mut header = [0u8; TAR_BLOCK];
+    if read_full(input, &mut header)? < TAR_BLOCK || header.iter().all(|b| *b == 0) {
+        return Ok(None);
+    }
+    let stored: u32 = parse_octal(&header[148..156])? as u32;
+    let computed: u32 = header.iter().enumerate()
+        .map(|(i, b)| if (148..156).contains(&i) { b' ' as u32 } else { *b as u32 })
+        .sum();
+    if stored != computed {
+        return Err(Error::InvalidData("Corrupt archive header".to_string()));
+    }
+    let name_len = header[..100].iter().position(|b| *b == 0).unwrap_or(100);
+    let name = String::from_utf8(header[..name_len].to_vec())?;
+    Ok(Some((name, parse_octal(&header[124..136])?)))
+}
+
+fn parse_octal(field: &[u8]) -> Result<u64> {
+    let digits = String::from_utf8_lossy(field);
+    let digits = digits.trim_matches(|c: char| c == '\0' || c == ' ');
+    u64::from_str_radix(digits, 8).map_err(|_| Error::InvalidData("Corrupt archive header".to_string()))
+}
+
+fn skip_tar_padding<R: Read>(input: &mut R, size: u64) -> Result<()> {
+    let padding = (TAR_BLOCK - (size as usize % TAR_BLOCK)) % TAR_BLOCK;
+    std::io::copy(&mut input.take(padding as u64), &mut std::io::sink())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Model;
+
+    fn block(hash: &str, index: u64) -> MinerBlock {
+        MinerBlock::new_canonical(
+            hash.to_string(), index, 0, 0, "prev".to_string(), "data".to_string(), 0, 1,
+            "peer".to_string(), 1,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_backup_and_restore() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        block("a", 0).save_to_active(&mgr).await.unwrap();
+        block("b", 1).save_to_store(mgr.miner_canon()).await.unwrap();
+        mgr.set_current_round(7).await.unwrap();
+        mgr.put("/contracts/alice/name.text", b"alice").await.unwrap();
+
+        let mut archive = Vec::new();
+        let manifest = mgr.backup(&mut archive).await.unwrap();
+        assert_eq!(manifest.miner_tip, Some(ChainTip { index: 1, hash: "b".to_string() }));
+        assert_eq!(manifest.current_round, 7);
+
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("restored");
+        let (restored, restored_manifest) = DatastoreManager::restore(&target, archive.as_slice()).await.unwrap();
+        assert_eq!(restored_manifest, manifest);
+        assert_eq!(restored.get_string("/contracts/alice/name.text").await.unwrap().as_deref(), Some("alice"));
+        assert_eq!(MinerBlock::find_canonical_by_index_simple(&restored, 0).await.unwrap().unwrap().hash, "a");
+        drop(restored);
+
+        // A non-empty target is refused
+        assert!(DatastoreManager::restore(&target, archive.as_slice()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_restore_rejects_tampered_dump() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        mgr.put("/key", b"value").await.unwrap();
+        let mut archive = Vec::new();
+        mgr.backup(&mut archive).await.unwrap();
+
+        let mut tar = zstd::decode_all(archive.as_slice()).unwrap();
+        let at = tar.windows(5).position(|w| w == b"value").unwrap();
+        tar[at] = b'V';
+        let tampered = zstd::encode_all(tar.as_slice(), 0).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("restored");
+        assert!(DatastoreManager::restore(&target, tampered.as_slice()).await.is_err());
+        assert!(!target.exists());
+    }
+}
@@ -0,0 +1,112 @@
+//! Node startup timings and what startup can skip next time
+//!
+//! A node records how long each startup phase took (opening the datastore,
+//! checking chain integrity, creating the swarm, the initial sync) in
+//! node_state, so `modal node inspect --boot-profile` can show where the last
+//! startup spent its time. The chain integrity check also leaves a checkpoint
+//! here: the last canonical block it found properly linked, so the next
+//! startup only has to check the blocks after it.
+
+use crate::stores::Store;
+use crate::{DatastoreManager, Result};
+use serde::{Deserialize, Serialize};
+
+/// Key of the last startup's profile, in node_state
+const BOOT_PROFILE_KEY: &str = "/boot/profile";
+
+/// Key of the chain integrity checkpoint, in node_state
+const INTEGRITY_CHECKPOINT_KEY: &str = "/boot/integrity_checkpoint";
+
+/// How long one startup phase took
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BootPhase {
+    pub name: String,
+    pub millis: u64,
+}
+
+/// Timings of a node's startup, in the order the phases ran
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BootProfile {
+    /// Unix seconds when the node started
+    pub started_at: i64,
+    pub phases: Vec<BootPhase>,
+    /// From start until the node was running, including time outside the
+    /// recorded phases
+    pub total_millis: u64,
+}
+
+impl BootProfile {
+    /// Time spent in the recorded phases
+    pub fn phase_millis(&self) -> u64 {
+        self.phases.iter().map(|p| p.millis).sum()
+    }
+}
+
+/// The last canonical block a chain integrity check found linked to genesis
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IntegrityCheckpoint {
+    pub index: u64,
+    pub hash: String,
+    /// Unix seconds when the check ran
+    pub checked_at: i64,
+}
+
+impl DatastoreManager {
+    /// Profile of the node's last startup
+    pub fn boot_profile(&self) -> Result<Option<BootProfile>> {
+        match self.node_state().get(BOOT_PROFILE_KEY)? {
+            Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn save_boot_profile(&self, profile: &BootProfile) -> Result<()> {
+        self.node_state().put(BOOT_PROFILE_KEY, &serde_json::to_vec(profile)?)
+    }
+
+    /// Where the last chain integrity check stopped, if one has run
+    pub fn integrity_checkpoint(&self) -> Result<Option<IntegrityCheckpoint>> {
+        match self.node_state().get(INTEGRITY_CHECKPOINT_KEY)? {
+            Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn save_integrity_checkpoint(&self, checkpoint: &IntegrityCheckpoint) -> Result<()> {
+        self.node_state().put(INTEGRITY_CHECKPOINT_KEY, &serde_json::to_vec(checkpoint)?)
+    }
+
+    /// Forget the checkpoint, so the next check covers the whole chain
+    pub fn clear_integrity_checkpoint(&self) -> Result<()> {
+        self.node_state().delete(INTEGRITY_CHECKPOINT_KEY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_boot_profile_round_trip() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        assert_eq!(mgr.boot_profile().unwrap(), None);
+
+        let profile = BootProfile {
+            started_at: 1_700_000_000,
+            phases: vec![
+                BootPhase { name: "datastore_open".to_string(), millis: 120 },
+                BootPhase { name: "chain_integrity".to_string(), millis: 800 },
+            ],
+            total_millis: 1000,
+        };
+        mgr.save_boot_profile(&profile).unwrap();
+        assert_eq!(mgr.boot_profile().unwrap(), Some(profile.clone()));
+        assert_eq!(profile.phase_millis(), 920);
+
+        let checkpoint = IntegrityCheckpoint { index: 42, hash: "abc".to_string(), checked_at: 1_700_000_000 };
+        mgr.save_integrity_checkpoint(&checkpoint).unwrap();
+        assert_eq!(mgr.integrity_checkpoint().unwrap(), Some(checkpoint));
+        mgr.clear_integrity_checkpoint().unwrap();
+        assert_eq!(mgr.integrity_checkpoint().unwrap(), None);
+    }
+}
@@ -0,0 +1,103 @@
+//! Whether a validator's consensus is making progress
+//!
+//! Under hybrid consensus, rounds stop being certified once more than f of
+//! the epoch's validators are offline. The consensus loop then falls back to
+//! mining-only: the chain keeps growing by proof of work, without
+//! checkpoints, until certificates form again. The loop records its mode in
+//! node_state so the status page and RPC can show it.
+
+use crate::stores::Store;
+use crate::{DatastoreManager, Result};
+use serde::{Deserialize, Serialize};
+
+/// Key of the consensus liveness record, in node_state
+const CONSENSUS_LIVENESS_KEY: &str = "/consensus/liveness";
+
+/// How the chain is being finalized
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsensusMode {
+    /// Validators certify rounds and checkpoint mining epochs
+    #[default]
+    Hybrid,
+    /// Too few validators for a quorum; blocks are mined without checkpoints
+    MiningOnly,
+}
+
+impl ConsensusMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConsensusMode::Hybrid => "hybrid",
+            ConsensusMode::MiningOnly => "mining_only",
+        }
+    }
+}
+
+/// Liveness of the consensus this node takes part in
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsensusLiveness {
+    pub mode: ConsensusMode,
+    /// Epoch of the validator set running consensus
+    pub validator_epoch: u64,
+    pub round: u64,
+    pub last_certified_round: Option<u64>,
+    pub committee_size: usize,
+    /// Validators needed to certify a round (2f+1)
+    pub quorum: usize,
+    /// Validators heard from in recent rounds, this node included
+    pub live_validators: usize,
+    /// Unix seconds when the mode last changed
+    pub mode_since: i64,
+    /// Times this node has fallen back to mining-only
+    pub fallbacks: u64,
+    /// Unix seconds when this was recorded
+    pub updated_at: i64,
+}
+
+impl DatastoreManager {
+    /// Consensus liveness as the consensus loop last recorded it
+    pub fn consensus_liveness(&self) -> Result<Option<ConsensusLiveness>> {
+        match self.node_state().get(CONSENSUS_LIVENESS_KEY)? {
+            Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn save_consensus_liveness(&self, liveness: &ConsensusLiveness) -> Result<()> {
+        self.node_state().put(CONSENSUS_LIVENESS_KEY, &serde_json::to_vec(liveness)?)
+    }
+
+    /// Forget the record once the node stops running consensus
+    pub fn clear_consensus_liveness(&self) -> Result<()> {
+        self.node_state().delete(CONSENSUS_LIVENESS_KEY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_consensus_liveness_round_trip() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        assert_eq!(mgr.consensus_liveness().unwrap(), None);
+
+        let liveness = ConsensusLiveness {
+            mode: ConsensusMode::MiningOnly,
+            validator_epoch: 4,
+            round: 120,
+            last_certified_round: Some(100),
+            committee_size: 7,
+            quorum: 5,
+            live_validators: 3,
+            mode_since: 1_700_000_000,
+            fallbacks: 1,
+            updated_at: 1_700_000_060,
+        };
+        mgr.save_consensus_liveness(&liveness).unwrap();
+        assert_eq!(mgr.consensus_liveness().unwrap(), Some(liveness));
+        mgr.clear_consensus_liveness().unwrap();
+        assert_eq!(mgr.consensus_liveness().unwrap(), None);
+        assert_eq!(serde_json::to_value(ConsensusMode::MiningOnly).unwrap(), "mining_only");
+    }
+}
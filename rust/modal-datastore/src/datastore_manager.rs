@@ -1,4 +1,4 @@
-//! DatastoreManager - manages all 6 RocksDB stores
+//! DatastoreManager - manages all 8 stores
 //! 
 //! The DatastoreManager is the central coordinator for the multi-datastore architecture.
 //! It handles opening/closing stores, provides access to individual stores, and
@@ -13,18 +13,41 @@
 //! ├── miner_active/     # Recent miner blocks
 //! ├── validator_final/  # Finalized validator data
 //! ├── validator_active/ # Active validator consensus
-//! └── node_state/       # Node-specific state
+//! ├── node_state/       # Node-specific state
+//! ├── node_metrics/     # Metrics history
+//! └── index/            # Indexer lookups
 //! ```
+//!
+//! Stores configured with the `rocksdb_cf` engine live as column families of
+//! one database in `shared/` instead of their own directories, and the
+//! engine of each store is recorded in `storage.json` so later opens use the
+//! same layout.
 
-use crate::Result;
+use crate::{Error, Result, ValidatorBlsKey};
+use modal_common::eras::EraSchedule;
+use modal_common::hash_tax::{self, HashRegistry};
 use crate::stores::{
-    Store,
+    CacheStats, Store, StoreBackend, StorageConfig, StorageEngine,
+    MemoryBackend, RocksDbBackend, RocksDbColumnFamilyBackend,
     MinerCanonStore, MinerForksStore, MinerActiveStore,
-    ValidatorFinalStore, ValidatorActiveStore, NodeStateStore,
+    ValidatorFinalStore, ValidatorActiveStore, NodeStateStore, NodeMetricsStore, IndexStore,
 };
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use std::fs;
 
+/// Names of the stores, which are also their directory and column family names
+pub const STORE_NAMES: [&str; 8] = [
+    "miner_canon", "miner_forks", "miner_active",
+    "validator_final", "validator_active", "node_state", "node_metrics", "index",
+];
+
+/// File in the data directory recording the storage engine of each store
+const STORAGE_CONFIG_FILE: &str = "storage.json";
+/// Directory of the database shared by the `rocksdb_cf` stores
+const SHARED_DB_DIR: &str = "shared";
+
 /// Configuration for epoch-based block lifecycle
 #[derive(Debug, Clone)]
 pub struct EpochConfig {
@@ -32,8 +55,8 @@ pub struct EpochConfig {
     pub promotion_delay_epochs: u64,
     /// Number of epochs before a block is purged from active store (default: 12)
     pub purge_delay_epochs: u64,
-    /// Number of blocks per epoch (loaded from network params)
-    pub blocks_per_epoch: u64,
+    /// Epoch length and other per-era parameters (loaded from network params)
+    pub schedule: EraSchedule,
 }
 
 impl Default for EpochConfig {
@@ -41,12 +64,12 @@ impl Default for EpochConfig {
         Self {
             promotion_delay_epochs: 2,
             purge_delay_epochs: 12,
-            blocks_per_epoch: 100, // Default, should be loaded from network params
+            schedule: EraSchedule::fixed(100, 60), // Default, should be loaded from network params
         }
     }
 }
 
-/// Manager for all 6 datastores
+/// Manager for all 8 datastores
 pub struct DatastoreManager {
     data_dir: PathBuf,
     miner_canon: MinerCanonStore,
@@ -55,7 +78,12 @@ pub struct DatastoreManager {
     validator_final: ValidatorFinalStore,
     validator_active: ValidatorActiveStore,
     node_state: NodeStateStore,
+    node_metrics: NodeMetricsStore,
+    index: IndexStore,
     epoch_config: EpochConfig,
+    hash_registry: HashRegistry,
+    mining_hash_func: Option<String>,
+    flush_interval: Option<Duration>,
 }
 
 impl std::fmt::Debug for DatastoreManager {
@@ -68,20 +96,65 @@ impl std::fmt::Debug for DatastoreManager {
 }
 
 impl DatastoreManager {
-    /// Open or create all stores in the given data directory
+    /// Open or create all stores in the given data directory, using the
+    /// storage engines recorded there (RocksDB for a new directory)
     pub fn open(data_dir: &Path) -> Result<Self> {
+        let config = Self::recorded_storage_config(data_dir)?.unwrap_or_default();
+        Self::open_with_config(data_dir, &config)
+    }
+    
+    /// Open or create all stores in the given data directory on the
+    /// configured storage engines. A directory created with different
+    /// engines is refused: move its data over with a backup and restore.
+    pub fn open_with_config(data_dir: &Path, config: &StorageConfig) -> Result<Self> {
         // Ensure data directory exists
         fs::create_dir_all(data_dir)?;
+        match Self::recorded_storage_config(data_dir)? {
+            Some(recorded) if !recorded.same_engines(config) => {
+                return Err(Error::InvalidData(format!(
+                    "{} was created with different storage engines: {}",
+                    data_dir.display(),
+                    serde_json::to_string(&recorded)?
+                )));
+            }
+            Some(_) => {}
+            None => fs::write(data_dir.join(STORAGE_CONFIG_FILE), serde_json::to_vec_pretty(config)?)?,
+        }
+        
+        // Open the database shared by the column family stores, if any
+        let column_families: Vec<&str> = STORE_NAMES.iter()
+            .copied()
+            .filter(|name| config.engine_for(name) == StorageEngine::RocksDbColumnFamily)
+            .collect();
+        let shared = if column_families.is_empty() {
+            None
+        } else {
+            Some(RocksDbColumnFamilyBackend::open_shared(&data_dir.join(SHARED_DB_DIR), &column_families)?)
+        };
+        let backend = |name: &str| -> Result<Box<dyn StoreBackend>> {
+            Ok(match config.engine_for(name) {
+                StorageEngine::RocksDb => {
+                    Box::new(RocksDbBackend::open(&data_dir.join(name))?.with_durability(config.durability))
+                }
+                StorageEngine::RocksDbColumnFamily => {
+                    let db = shared.clone().expect("shared database is open");
+                    Box::new(RocksDbColumnFamilyBackend::new(db, name)?.with_durability(config.durability))
+                }
+                StorageEngine::Memory => Box::new(MemoryBackend::new()),
+            })
+        };
         
         // Open each store
-        let miner_canon = MinerCanonStore::open(&data_dir.join("miner_canon"))?;
-        let miner_forks = MinerForksStore::open(&data_dir.join("miner_forks"))?;
-        let miner_active = MinerActiveStore::open(&data_dir.join("miner_active"))?;
-        let validator_final = ValidatorFinalStore::open(&data_dir.join("validator_final"))?;
-        let validator_active = ValidatorActiveStore::open(&data_dir.join("validator_active"))?;
-        let node_state = NodeStateStore::open(&data_dir.join("node_state"))?;
+        let miner_canon = MinerCanonStore::with_backend(backend("miner_canon")?);
+        let miner_forks = MinerForksStore::with_backend(backend("miner_forks")?);
+        let miner_active = MinerActiveStore::with_backend(backend("miner_active")?);
+        let validator_final = ValidatorFinalStore::with_backend(backend("validator_final")?);
+        let validator_active = ValidatorActiveStore::with_backend(backend("validator_active")?);
+        let node_state = NodeStateStore::with_backend(backend("node_state")?);
+        let node_metrics = NodeMetricsStore::with_backend(backend("node_metrics")?);
+        let index = IndexStore::with_backend(backend("index")?);
         
-        Ok(Self {
+        let mgr = Self {
             data_dir: data_dir.to_path_buf(),
             miner_canon,
             miner_forks,
@@ -89,8 +162,27 @@ impl DatastoreManager {
             validator_final,
             validator_active,
             node_state,
+            node_metrics,
+            index,
             epoch_config: EpochConfig::default(),
-        })
+            hash_registry: HashRegistry::default(),
+            mining_hash_func: None,
+            flush_interval: config.flush_interval(),
+        };
+        // Bring stored records to the current encoding, then build any
+        // index the stores were written without
+        mgr.migrate()?;
+        mgr.ensure_indexes()?;
+        Ok(mgr)
+    }
+    
+    /// Storage engines recorded in a data directory, if it has been opened before
+    pub fn recorded_storage_config(data_dir: &Path) -> Result<Option<StorageConfig>> {
+        match fs::read(data_dir.join(STORAGE_CONFIG_FILE)) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
     }
     
     /// Create an in-memory manager for testing
@@ -104,6 +196,8 @@ impl DatastoreManager {
         let validator_final = ValidatorFinalStore::create_in_memory()?;
         let validator_active = ValidatorActiveStore::create_in_memory()?;
         let node_state = NodeStateStore::create_in_memory()?;
+        let node_metrics = NodeMetricsStore::create_in_memory()?;
+        let index = IndexStore::create_in_memory()?;
         
         Ok(Self {
             data_dir,
@@ -113,10 +207,44 @@ impl DatastoreManager {
             validator_final,
             validator_active,
             node_state,
+            node_metrics,
+            index,
             epoch_config: EpochConfig::default(),
+            hash_registry: HashRegistry::default(),
+            mining_hash_func: None,
+            flush_interval: None,
         })
     }
     
+    /// Build the secondary indexes that aren't built yet in each store.
+    /// Returns the number of indexes rebuilt.
+    pub fn ensure_indexes(&self) -> Result<usize> {
+        use crate::model::Model;
+        use crate::models::{DAGCertificate, MinerBlock};
+
+        let rebuilt = [
+            MinerBlock::ensure_indexes_in_store(&self.miner_active)?,
+            MinerBlock::ensure_indexes_in_store(&self.miner_canon)?,
+            MinerBlock::ensure_indexes_in_store(&self.miner_forks)?,
+            DAGCertificate::ensure_indexes_in_store(&self.validator_final)?,
+        ];
+        Ok(rebuilt.iter().filter(|r| **r).count())
+    }
+
+    /// Drop and rebuild every secondary index.
+    /// Returns `(store, collection, models indexed)` for each index.
+    pub fn rebuild_indexes(&self) -> Result<Vec<(&'static str, &'static str, usize)>> {
+        use crate::model::Model;
+        use crate::models::{DAGCertificate, MinerBlock};
+
+        Ok(vec![
+            ("miner_active", MinerBlock::collection_prefix(), MinerBlock::rebuild_indexes_in_store(&self.miner_active)?),
+            ("miner_canon", MinerBlock::collection_prefix(), MinerBlock::rebuild_indexes_in_store(&self.miner_canon)?),
+            ("miner_forks", MinerBlock::collection_prefix(), MinerBlock::rebuild_indexes_in_store(&self.miner_forks)?),
+            ("validator_final", DAGCertificate::collection_prefix(), DAGCertificate::rebuild_indexes_in_store(&self.validator_final)?),
+        ])
+    }
+    
     /// Get the data directory path
     pub fn data_dir(&self) -> &Path {
         &self.data_dir
@@ -182,6 +310,16 @@ impl DatastoreManager {
         &mut self.node_state
     }
     
+    /// Get reference to NodeMetrics store
+    pub fn node_metrics(&self) -> &NodeMetricsStore {
+        &self.node_metrics
+    }
+    
+    /// Get reference to the Index store
+    pub fn index(&self) -> &IndexStore {
+        &self.index
+    }
+    
     /// Get the epoch configuration
     pub fn epoch_config(&self) -> &EpochConfig {
         &self.epoch_config
@@ -192,14 +330,42 @@ impl DatastoreManager {
         self.epoch_config = config;
     }
     
-    /// Set the blocks per epoch (typically from network params)
+    /// Set a fixed number of blocks per epoch with no eras
     pub fn set_blocks_per_epoch(&mut self, blocks_per_epoch: u64) {
-        self.epoch_config.blocks_per_epoch = blocks_per_epoch;
+        let target_block_time_secs = self.epoch_config.schedule.target_block_time_secs;
+        self.epoch_config.schedule = EraSchedule::fixed(blocks_per_epoch, target_block_time_secs);
+    }
+    
+    /// Set the era schedule (typically from network params)
+    pub fn set_era_schedule(&mut self, schedule: EraSchedule) {
+        self.epoch_config.schedule = schedule;
+    }
+    
+    /// Get the era schedule
+    pub fn era_schedule(&self) -> &EraSchedule {
+        &self.epoch_config.schedule
+    }
+    
+    /// Hash functions this network's blocks are mined and checked with, as
+    /// configured by its parameters. Each manager has its own, so networks
+    /// run in one process can configure the same function differently.
+    pub fn hash_registry(&self) -> &HashRegistry {
+        &self.hash_registry
+    }
+    
+    /// Name of the hash function this network's blocks are mined with
+    pub fn mining_hash_func(&self) -> &str {
+        self.mining_hash_func.as_deref().unwrap_or(hash_tax::DEFAULT_HASH_FUNC_NAME)
+    }
+    
+    /// Set the hash function blocks are mined with (typically from node config)
+    pub fn set_mining_hash_func(&mut self, hash_func: Option<String>) {
+        self.mining_hash_func = hash_func;
     }
     
     /// Calculate the epoch for a given block index
     pub fn block_index_to_epoch(&self, block_index: u64) -> u64 {
-        block_index / self.epoch_config.blocks_per_epoch
+        self.epoch_config.schedule.epoch_of(block_index)
     }
     
     /// Check if a block at the given epoch should be promoted to canon/forks
@@ -214,6 +380,24 @@ impl DatastoreManager {
         current_epoch >= block_epoch + self.epoch_config.purge_delay_epochs
     }
     
+    /// How often the owner should `flush_all`: set when writes skip the
+    /// write-ahead log (`Durability::Deferred`), so they only reach disk then
+    pub fn flush_interval(&self) -> Option<Duration> {
+        self.flush_interval
+    }
+    
+    /// Hits and misses of the stores' model caches together
+    pub fn cache_stats(&self) -> CacheStats {
+        let caches = [
+            self.miner_canon.cache(),
+            self.miner_forks.cache(),
+            self.miner_active.cache(),
+            self.validator_final.cache(),
+            self.validator_active.cache(),
+        ];
+        CacheStats::sum(caches.into_iter().flatten().map(|cache| cache.stats()))
+    }
+    
     /// Flush all stores to disk
     pub fn flush_all(&self) -> Result<()> {
         self.miner_canon.flush()?;
@@ -222,6 +406,8 @@ impl DatastoreManager {
         self.validator_final.flush()?;
         self.validator_active.flush()?;
         self.node_state.flush()?;
+        self.node_metrics.flush()?;
+        self.index.flush()?;
         Ok(())
     }
     
@@ -274,9 +460,56 @@ impl DatastoreManager {
             }
         }
         
+        // Networks without a genesis contract may set their slashing policy here
+        if let Some(slashing) = network_config.get("slashing") {
+            let policy: crate::SlashingPolicy = serde_json::from_value(slashing.clone())?;
+            self.store_slashing_policy(&policy)?;
+        }
+        
+        if let Some(keys) = network_config.get("validator_bls_keys") {
+            let keys: BTreeMap<String, ValidatorBlsKey> = serde_json::from_value(keys.clone())?;
+            self.set_validator_bls_keys(&keys).await?;
+        }
+        
         Ok(())
     }
     
+    /// Get the network config stored by `load_network_config`, if any
+    pub async fn get_network_config(&self) -> Result<Option<serde_json::Value>> {
+        match self.node_state.get("network_config")? {
+            Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            None => Ok(None),
+        }
+    }
+    
+    /// Get the era schedule stored by `store_era_schedule`, if any
+    pub async fn get_stored_era_schedule(&self) -> Result<Option<EraSchedule>> {
+        match self.node_state.get("era_schedule")? {
+            Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            None => Ok(None),
+        }
+    }
+    
+    /// Remember the era schedule so later parameter changes can be checked against it
+    pub async fn store_era_schedule(&self, schedule: &EraSchedule) -> Result<()> {
+        let json = serde_json::to_vec(schedule)?;
+        self.node_state.put("era_schedule", &json)
+    }
+    
+    /// Gas quotas for contract commits, unlimited unless stored
+    pub async fn get_gas_quotas(&self) -> Result<crate::GasQuotas> {
+        match self.node_state.get("gas_quotas")? {
+            Some(data) => Ok(serde_json::from_slice(&data)?),
+            None => Ok(crate::GasQuotas::default()),
+        }
+    }
+    
+    /// Set the gas quotas contract commits are held to
+    pub async fn store_gas_quotas(&self, quotas: &crate::GasQuotas) -> Result<()> {
+        let json = serde_json::to_vec(quotas)?;
+        self.node_state.put("gas_quotas", &json)
+    }
+    
     /// Load network parameters from a genesis contract
     pub async fn load_network_parameters_from_contract(&self, contract_id: &str) -> Result<crate::NetworkParameters> {
         // Try to load from ValidatorFinal store where contracts live
@@ -312,6 +545,20 @@ impl DatastoreManager {
         }
     }
     
+    /// Set the validators' BLS keys in NodeState store
+    pub async fn set_validator_bls_keys(&self, keys: &BTreeMap<String, ValidatorBlsKey>) -> Result<()> {
+        let json = serde_json::to_vec(keys)?;
+        self.node_state.put("validator_bls_keys", &json)
+    }
+    
+    /// Get the validators' BLS keys from NodeState store, by peer ID
+    pub async fn get_validator_bls_keys(&self) -> Result<BTreeMap<String, ValidatorBlsKey>> {
+        match self.node_state.get("validator_bls_keys")? {
+            Some(data) => Ok(serde_json::from_slice(&data)?),
+            None => Ok(BTreeMap::new()),
+        }
+    }
+    
     /// Get current round from NodeState
     pub async fn get_current_round(&self) -> Result<u64> {
         if let Some(data) = self.node_state.get("current_round")? {
@@ -356,33 +603,37 @@ impl DatastoreManager {
     }
     
     /// Clear all data from all stores
-    /// WARNING: This will delete all data in all 6 stores!
+    /// WARNING: This will delete all data in all 8 stores!
     pub async fn clear_all(&self) -> Result<u64> {
         use crate::stores::Store;
-        use rocksdb::IteratorMode;
         
         let mut count = 0u64;
         
         // Helper to clear a store by iterating all keys
-        fn clear_db(db: &rocksdb::DB, count: &mut u64) -> Result<()> {
-            let keys: Vec<Vec<u8>> = db.iterator(IteratorMode::Start)
+        fn clear_store<S: Store>(store: &S, count: &mut u64) -> Result<()> {
+            let keys: Vec<Vec<u8>> = store.iter_all()
                 .filter_map(|result| result.ok().map(|(key, _)| key.to_vec()))
                 .collect();
             
             for key in keys {
-                db.delete(&key)?;
+                store.backend().delete(&key)?;
                 *count += 1;
             }
+            if let Some(cache) = store.cache() {
+                cache.clear();
+            }
             Ok(())
         }
         
-        // Clear each store's underlying database
-        clear_db(self.miner_active.db(), &mut count)?;
-        clear_db(self.miner_canon.db(), &mut count)?;
-        clear_db(self.miner_forks.db(), &mut count)?;
-        clear_db(self.validator_active.db(), &mut count)?;
-        clear_db(self.validator_final.db(), &mut count)?;
-        clear_db(self.node_state.db(), &mut count)?;
+        // Clear each store's underlying engine
+        clear_store(&self.miner_active, &mut count)?;
+        clear_store(&self.miner_canon, &mut count)?;
+        clear_store(&self.miner_forks, &mut count)?;
+        clear_store(&self.validator_active, &mut count)?;
+        clear_store(&self.validator_final, &mut count)?;
+        clear_store(&self.node_state, &mut count)?;
+        clear_store(&self.node_metrics, &mut count)?;
+        clear_store(&self.index, &mut count)?;
         
         // Flush all stores
         self.miner_active.flush()?;
@@ -391,6 +642,8 @@ impl DatastoreManager {
         self.validator_active.flush()?;
         self.validator_final.flush()?;
         self.node_state.flush()?;
+        self.node_metrics.flush()?;
+        self.index.flush()?;
         
         Ok(count)
     }
@@ -406,6 +659,39 @@ mod tests {
         assert!(mgr.data_dir().exists() || true); // In-memory may use temp dir
     }
     
+    #[test]
+    fn test_storage_engines_are_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().join("data");
+        let mut config = StorageConfig { engine: StorageEngine::RocksDbColumnFamily, ..Default::default() };
+        config.stores.insert("validator_active".to_string(), StorageEngine::Memory);
+        {
+            let mgr = DatastoreManager::open_with_config(&data_dir, &config).unwrap();
+            mgr.miner_canon().put("/k/1", b"canon").unwrap();
+            mgr.node_state().put("/k/1", b"state").unwrap();
+            mgr.validator_active().put("/k/1", b"draft").unwrap();
+        }
+        assert!(data_dir.join(SHARED_DB_DIR).exists());
+        assert!(!data_dir.join("miner_canon").exists());
+
+        // A plain open uses the recorded engines
+        let mgr = DatastoreManager::open(&data_dir).unwrap();
+        assert_eq!(mgr.miner_canon().get("/k/1").unwrap().as_deref(), Some(&b"canon"[..]));
+        assert_eq!(mgr.node_state().get("/k/1").unwrap().as_deref(), Some(&b"state"[..]));
+        assert_eq!(mgr.validator_active().get("/k/1").unwrap(), None);
+        drop(mgr);
+
+        assert!(DatastoreManager::open_with_config(&data_dir, &StorageConfig::default()).is_err());
+
+        // Durability isn't part of the layout
+        config.durability = crate::stores::Durability::Deferred;
+        let mgr = DatastoreManager::open_with_config(&data_dir, &config).unwrap();
+        assert_eq!(mgr.flush_interval(), Some(Duration::from_millis(crate::stores::backend::DEFAULT_FLUSH_INTERVAL_MS)));
+        mgr.miner_canon().put("/k/2", b"deferred").unwrap();
+        mgr.flush_all().unwrap();
+        assert_eq!(mgr.miner_canon().get("/k/2").unwrap().as_deref(), Some(&b"deferred"[..]));
+    }
+
     #[test]
     fn test_epoch_calculation() {
         let mut mgr = DatastoreManager::create_in_memory().unwrap();
@@ -416,6 +702,16 @@ mod tests {
         assert_eq!(mgr.block_index_to_epoch(99), 0);
         assert_eq!(mgr.block_index_to_epoch(100), 1);
         assert_eq!(mgr.block_index_to_epoch(250), 2);
+        
+        // Epochs keep counting after an era shortens them
+        let schedule = EraSchedule::new(100, 60, vec![modal_common::eras::Era {
+            activation_height: 200,
+            blocks_per_epoch: Some(50),
+            target_block_time_secs: None,
+        }]).unwrap();
+        mgr.set_era_schedule(schedule);
+        assert_eq!(mgr.block_index_to_epoch(199), 1);
+        assert_eq!(mgr.block_index_to_epoch(250), 3);
     }
     
     #[test]
@@ -28,4 +28,16 @@ pub enum Error {
 
     #[error(transparent)]
     Other(#[from] anyhow::Error)
-}
\ No newline at end of file
+}
+impl modal_common::error_codes::HasErrorCode for Error {
+    fn error_code(&self) -> modal_common::error_codes::ErrorCode {
+        use modal_common::error_codes::ErrorCode;
+        match self {
+            Error::Io(_) | Error::Database(_) | Error::RocksDb(_) => ErrorCode::StorageError,
+            Error::KeyNotFound(_) => ErrorCode::NotFound,
+            Error::JsonSerialization(_) | Error::Utf8(_) | Error::ParseInt(_) => ErrorCode::SerializationError,
+            Error::InvalidData(_) => ErrorCode::InvalidData,
+            Error::Other(_) => ErrorCode::Internal,
+        }
+    }
+}
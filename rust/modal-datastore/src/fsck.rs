@@ -0,0 +1,397 @@
+//! Record checksums and storage consistency checks
+//!
+//! Every record written through `Store` gets a checksum record at
+//! `/_checksum<key>` holding the CRC32 and length of its value, written in
+//! the same batch. `DatastoreManager::fsck` scans every store for records
+//! that no longer match their checksum (corrupt or truncated), checksums
+//! whose record is gone (missing), and records written before checksums
+//! existed (unchecked).
+//!
+//! With repair on, a damaged record is copied back from another store
+//! holding the same collection (miner blocks live in miner_canon,
+//! miner_active and miner_forks; validator data in validator_final and
+//! validator_active) if a verified copy exists there. Otherwise it is
+//! dropped, and a miner block's index is recorded as a range to re-sync
+//! from peers (see `resync_ranges`). Damaged index entries are dropped and
+//! the indexes rebuilt, and unchecked records get a checksum.
+
+use crate::datastore_manager::STORE_NAMES;
+use crate::model::{index_value, Model};
+use crate::models::MinerBlock;
+use crate::stores::{Store, StoreBackend, WriteBatch};
+use crate::{DatastoreManager, Result};
+use serde::{Deserialize, Serialize};
+
+/// Key prefix of record checksums
+pub const CHECKSUM_PREFIX: &str = "/_checksum";
+
+/// Key prefix of the block ranges marked for re-sync, in node_state
+const RESYNC_PREFIX: &str = "/_resync/miner_blocks";
+
+/// Stores holding copies of the same collections
+const STORE_GROUPS: &[&[&str]] = &[
+    &["miner_canon", "miner_active", "miner_forks"],
+    &["validator_final", "validator_active"],
+];
+
+/// Key of a record's checksum
+pub fn checksum_key(key: &[u8]) -> Vec<u8> {
+    [CHECKSUM_PREFIX.as_bytes(), key].concat()
+}
+
+/// Checksum of a record value: CRC32 then length, both big-endian
+pub fn record_checksum(value: &[u8]) -> [u8; 8] {
+    let mut checksum = [0u8; 8];
+    checksum[..4].copy_from_slice(&crc32fast::hash(value).to_be_bytes());
+    checksum[4..].copy_from_slice(&(value.len() as u32).to_be_bytes());
+    checksum
+}
+
+/// Add the checksum puts and deletes for the records in a batch
+pub(crate) fn with_checksums(batch: WriteBatch) -> WriteBatch {
+    let mut checked = WriteBatch::default();
+    for (key, value) in batch.iter() {
+        let is_checksum = key.starts_with(CHECKSUM_PREFIX.as_bytes());
+        match value {
+            Some(value) => {
+                checked.put(key, value);
+                if !is_checksum {
+                    checked.put(checksum_key(key), record_checksum(value));
+                }
+            }
+            None => {
+                checked.delete(key);
+                if !is_checksum {
+                    checked.delete(checksum_key(key));
+                }
+            }
+        }
+    }
+    checked
+}
+
+/// What is wrong with a record
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// The value doesn't match its checksum
+    Corrupt,
+    /// The value is shorter than when it was written
+    Truncated,
+    /// The checksum exists but the record doesn't
+    Missing,
+    /// The record has no checksum
+    Unchecked,
+}
+
+/// How a record was repaired
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Repair {
+    /// Replaced with the verified copy in another store
+    CopiedFrom(String),
+    /// An index entry, dropped and rebuilt from the records
+    IndexRebuilt,
+    /// Dropped, with the block marked for re-sync
+    MarkedForResync(u64),
+    /// Dropped, nothing to recover it from
+    Dropped,
+    /// Checksum added
+    Sealed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FsckIssue {
+    pub store: String,
+    pub key: String,
+    pub kind: IssueKind,
+    /// Set when repairing
+    pub repair: Option<Repair>,
+}
+
+/// Inclusive range of miner block indexes to fetch again from peers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResyncRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FsckReport {
+    pub records_checked: u64,
+    pub issues: Vec<FsckIssue>,
+}
+
+impl FsckReport {
+    /// Whether any record is damaged. Unchecked records only predate checksums.
+    pub fn has_damage(&self) -> bool {
+        self.issues.iter().any(|issue| issue.kind != IssueKind::Unchecked)
+    }
+}
+
+/// Check a value against its stored checksum
+fn verify(value: &[u8], checksum: &[u8]) -> Option<IssueKind> {
+    if checksum.len() != 8 {
+        return Some(IssueKind::Corrupt);
+    }
+    let expected_len = u32::from_be_bytes(checksum[4..].try_into().unwrap()) as usize;
+    if value.len() < expected_len {
+        Some(IssueKind::Truncated)
+    } else if record_checksum(value)[..] != checksum[..] {
+        Some(IssueKind::Corrupt)
+    } else {
+        None
+    }
+}
+
+/// Value of a record if it matches its checksum
+fn verified_value(backend: &dyn StoreBackend, key: &[u8]) -> Result<Option<Vec<u8>>> {
+    let (Some(value), Some(checksum)) = (backend.get(key)?, backend.get(&checksum_key(key))?) else {
+        return Ok(None);
+    };
+    Ok(verify(&value, &checksum).is_none().then_some(value))
+}
+
+/// Block index of a miner block record, from its index entries
+fn block_index_of(backend: &dyn StoreBackend, key: &str) -> Result<Option<u64>> {
+    let root = format!("/_index{}/index/", MinerBlock::collection_prefix());
+    let upper = format!("/_index{}/index0", MinerBlock::collection_prefix());
+    for item in backend.range(Some(root.as_bytes()), Some(upper.as_bytes())) {
+        let (entry, id) = item?;
+        if &id[..] != key.as_bytes() {
+            continue;
+        }
+        let entry = String::from_utf8_lossy(&entry);
+        if let Some(index) = entry[root.len()..].split('/').next().and_then(|i| i.parse().ok()) {
+            return Ok(Some(index));
+        }
+    }
+    Ok(None)
+}
+
+impl DatastoreManager {
+    /// Check every record against its checksum, repairing what can be
+    /// repaired when `repair` is set. See the module docs.
+    pub async fn fsck(&self, repair: bool) -> Result<FsckReport> {
+        let mut report = FsckReport::default();
+        let mut rebuild_indexes = false;
+
+        for name in STORE_NAMES {
+            let backend = self.store_backend(name).expect("known store");
+            let mut damaged = Vec::new();
+
+            // Records against their checksums
+            for item in backend.range(None, None) {
+                let (key, value) = item?;
+                if key.starts_with(CHECKSUM_PREFIX.as_bytes()) {
+                    continue;
+                }
+                report.records_checked += 1;
+                let kind = match backend.get(&checksum_key(&key))? {
+                    Some(checksum) => match verify(&value, &checksum) {
+                        Some(kind) => kind,
+                        None => continue,
+                    },
+                    None => IssueKind::Unchecked,
+                };
+                damaged.push((String::from_utf8_lossy(&key).into_owned(), kind));
+            }
+
+            // Checksums whose record is gone
+            let lower = format!("{}/", CHECKSUM_PREFIX);
+            let upper = format!("{}0", CHECKSUM_PREFIX);
+            for item in backend.range(Some(lower.as_bytes()), Some(upper.as_bytes())) {
+                let (key, _) = item?;
+                let record_key = &key[CHECKSUM_PREFIX.len()..];
+                if backend.get(record_key)?.is_none() {
+                    damaged.push((String::from_utf8_lossy(record_key).into_owned(), IssueKind::Missing));
+                }
+            }
+
+            for (key, kind) in damaged {
+                let repair = if repair {
+                    let repaired = self.repair_record(name, &key, kind)?;
+                    rebuild_indexes |= repaired != Repair::Sealed;
+                    Some(repaired)
+                } else {
+                    None
+                };
+                if kind != IssueKind::Unchecked {
+                    log::warn!("Store {} record {} is {:?}, repair: {:?}", name, key, kind, repair);
+                }
+                report.issues.push(FsckIssue { store: name.to_string(), key, kind, repair });
+            }
+        }
+
+        if rebuild_indexes {
+            self.rebuild_indexes()?;
+        }
+        if repair {
+            self.flush_all()?;
+        }
+        Ok(report)
+    }
+
+    fn repair_record(&self, store: &str, key: &str, kind: IssueKind) -> Result<Repair> {
+        let backend = self.store_backend(store).expect("known store");
+        let mut batch = WriteBatch::default();
+
+        if kind == IssueKind::Unchecked {
+            if let Some(value) = backend.get(key.as_bytes())? {
+                batch.put(key, value);
+            }
+            backend.write(with_checksums(batch))?;
+            return Ok(Repair::Sealed);
+        }
+
+        if key.starts_with("/_index") {
+            batch.delete(key);
+            backend.write(with_checksums(batch))?;
+            return Ok(Repair::IndexRebuilt);
+        }
+
+        // A verified copy in a store holding the same collection
+        let siblings = STORE_GROUPS.iter()
+            .find(|group| group.contains(&store))
+            .map_or(&[][..], |group| &group[..]);
+        for sibling in siblings.iter().filter(|sibling| **sibling != store) {
+            let sibling_backend = self.store_backend(sibling).expect("known store");
+            if let Some(value) = verified_value(sibling_backend, key.as_bytes())? {
+                batch.put(key, value);
+                backend.write(with_checksums(batch))?;
+                return Ok(Repair::CopiedFrom(sibling.to_string()));
+            }
+        }
+
+        let block_index = if key.starts_with(&format!("{}/", MinerBlock::collection_prefix())) {
+            block_index_of(backend, key)?
+        } else {
+            None
+        };
+        batch.delete(key);
+        backend.write(with_checksums(batch))?;
+        match block_index {
+            Some(index) => {
+                self.mark_for_resync(index, index)?;
+                Ok(Repair::MarkedForResync(index))
+            }
+            None => Ok(Repair::Dropped),
+        }
+    }
+
+    /// Record a range of miner blocks to fetch again from peers
+    pub fn mark_for_resync(&self, start: u64, end: u64) -> Result<()> {
+        let key = format!("{}/{}", RESYNC_PREFIX, index_value(&start.into()).unwrap());
+        self.node_state().put(&key, &serde_json::to_vec(&ResyncRange { start, end })?)
+    }
+
+    /// Miner block ranges marked for re-sync, in order, with adjacent
+    /// ranges merged
+    pub fn resync_ranges(&self) -> Result<Vec<ResyncRange>> {
+        let mut ranges: Vec<ResyncRange> = Vec::new();
+        for item in self.node_state().iterator(RESYNC_PREFIX) {
+            let (_, value) = item?;
+            let range: ResyncRange = serde_json::from_slice(&value)?;
+            match ranges.last_mut() {
+                Some(last) if range.start <= last.end.saturating_add(1) => last.end = last.end.max(range.end),
+                _ => ranges.push(range),
+            }
+        }
+        Ok(ranges)
+    }
+
+    /// Clear the re-sync marks within a range once its blocks are fetched
+    pub fn clear_resync_range(&self, range: ResyncRange) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        for item in self.node_state().iterator(RESYNC_PREFIX) {
+            let (key, value) = item?;
+            let marked: ResyncRange = serde_json::from_slice(&value)?;
+            if marked.start >= range.start && marked.end <= range.end {
+                batch.delete(key);
+            }
+        }
+        self.node_state().write(batch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(hash: &str, index: u64) -> MinerBlock {
+        MinerBlock::new_canonical(
+            hash.to_string(), index, 0, 1000, "prev".to_string(), "data".to_string(),
+            1, 1000, "peer".to_string(), 1,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_clean_datastore_has_no_issues() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        block("a1", 1).save_to_active(&mgr).await.unwrap();
+        mgr.node_state().put("/status", b"ok").unwrap();
+
+        let report = mgr.fsck(false).await.unwrap();
+        assert!(report.records_checked > 2);
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+
+        mgr.node_state().delete("/status").unwrap();
+        assert!(mgr.node_state().get(std::str::from_utf8(&checksum_key(b"/status")).unwrap()).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_damage_is_found_and_repaired() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        block("a1", 1).save_to_active(&mgr).await.unwrap();
+        block("a2", 2).save_to_active(&mgr).await.unwrap();
+        mgr.miner_canon().put("/miner_blocks/hash/a1", &mgr.miner_active().get("/miner_blocks/hash/a1").unwrap().unwrap()).unwrap();
+
+        // Flip bytes in the canon copy of a1, truncate a2, write around the checksums
+        let mut a1 = mgr.miner_canon().get("/miner_blocks/hash/a1").unwrap().unwrap();
+        a1[5] ^= 0xff;
+        mgr.miner_canon().backend().put(b"/miner_blocks/hash/a1", &a1).unwrap();
+        let a2 = mgr.miner_active().get("/miner_blocks/hash/a2").unwrap().unwrap();
+        mgr.miner_active().backend().put(b"/miner_blocks/hash/a2", &a2[..10]).unwrap();
+        mgr.node_state().backend().put(b"/legacy", b"old").unwrap();
+
+        let report = mgr.fsck(false).await.unwrap();
+        let kind_of = |store: &str, key: &str| report.issues.iter()
+            .find(|i| i.store == store && i.key == key)
+            .map(|i| i.kind);
+        assert_eq!(kind_of("miner_canon", "/miner_blocks/hash/a1"), Some(IssueKind::Corrupt));
+        assert_eq!(kind_of("miner_active", "/miner_blocks/hash/a2"), Some(IssueKind::Truncated));
+        assert_eq!(kind_of("node_state", "/legacy"), Some(IssueKind::Unchecked));
+        assert!(report.has_damage());
+
+        let report = mgr.fsck(true).await.unwrap();
+        let repair_of = |store: &str, key: &str| report.issues.iter()
+            .find(|i| i.store == store && i.key == key)
+            .and_then(|i| i.repair.clone());
+        assert_eq!(repair_of("miner_canon", "/miner_blocks/hash/a1"), Some(Repair::CopiedFrom("miner_active".to_string())));
+        assert_eq!(repair_of("miner_active", "/miner_blocks/hash/a2"), Some(Repair::MarkedForResync(2)));
+        assert_eq!(repair_of("node_state", "/legacy"), Some(Repair::Sealed));
+
+        let restored = mgr.miner_canon().get("/miner_blocks/hash/a1").unwrap().unwrap();
+        assert_eq!(restored, mgr.miner_active().get("/miner_blocks/hash/a1").unwrap().unwrap());
+        assert_eq!(mgr.resync_ranges().unwrap(), vec![ResyncRange { start: 2, end: 2 }]);
+        assert!(MinerBlock::find_by_index_multi(&mgr, 2).await.unwrap().is_empty());
+
+        let report = mgr.fsck(false).await.unwrap();
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+
+        mgr.clear_resync_range(ResyncRange { start: 0, end: 10 }).unwrap();
+        assert!(mgr.resync_ranges().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_resync_ranges_merge() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        mgr.mark_for_resync(7, 7).unwrap();
+        mgr.mark_for_resync(5, 6).unwrap();
+        mgr.mark_for_resync(10, 12).unwrap();
+        assert_eq!(
+            mgr.resync_ranges().unwrap(),
+            vec![ResyncRange { start: 5, end: 7 }, ResyncRange { start: 10, end: 12 }]
+        );
+    }
+}
@@ -0,0 +1,312 @@
+//! Network parameter governance
+//!
+//! Validators change network parameters by committing to the governance
+//! system contract. A proposal is posted at `/proposals/<id>.json`, and
+//! each validator approving it posts its signature over the proposal at
+//! `/proposals/<id>/approvals/<peer id>.json`. Once more than two thirds of
+//! the active validators approved, nodes apply the changes: epoch length and
+//! block time as an era starting at the activation height, the difficulty
+//! algorithm and gas quotas when the chain reaches it.
+
+use crate::stores::Store;
+use crate::{DatastoreManager, Error, Result};
+use modal_common::difficulty::DifficultyConfig;
+use modal_common::keypair::Keypair;
+use serde::{Deserialize, Serialize};
+
+/// Id of the system contract holding proposals and approvals
+pub const GOVERNANCE_CONTRACT_ID: &str = "modal.governance";
+
+/// Key prefixes of the applied and rejected proposal records, in node_state
+const APPLIED_PREFIX: &str = "/governance/applied";
+const REJECTED_PREFIX: &str = "/governance/rejected";
+
+/// Parameters a proposal changes; unset ones stay as they are
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParameterChange {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub difficulty_algorithm: Option<DifficultyConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocks_per_epoch: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_block_time_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit_gas_quota: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epoch_gas_quota: Option<u64>,
+}
+
+impl ParameterChange {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether it changes epoch length or block time, which take effect as an era
+    pub fn changes_era(&self) -> bool {
+        self.blocks_per_epoch.is_some() || self.target_block_time_secs.is_some()
+    }
+}
+
+/// A proposed parameter change
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParameterProposal {
+    pub id: String,
+    /// Peer id of the proposing validator
+    pub proposer: String,
+    /// Block height the changes take effect at
+    pub activation_height: u64,
+    pub changes: ParameterChange,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+}
+
+impl ParameterProposal {
+    /// Contract path of the proposal
+    pub fn path(&self) -> String {
+        format!("/proposals/{}.json", self.id)
+    }
+
+    /// Check the proposal is well formed
+    pub fn validate(&self) -> Result<()> {
+        let valid_id = !self.id.is_empty()
+            && self.id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_id {
+            return Err(Error::InvalidData(format!("Invalid proposal id '{}'", self.id)));
+        }
+        if self.changes.is_empty() {
+            return Err(Error::InvalidData(format!("Proposal {} changes nothing", self.id)));
+        }
+        if self.changes.blocks_per_epoch == Some(0) {
+            return Err(Error::InvalidData(format!("Proposal {} sets zero blocks per epoch", self.id)));
+        }
+        Ok(())
+    }
+
+    /// What approvals sign: everything that takes effect
+    pub fn signing_payload(&self) -> serde_json::Value {
+        serde_json::json!({
+            "id": self.id,
+            "activation_height": self.activation_height,
+            "changes": self.changes,
+        })
+    }
+
+    /// Commit action posting the proposal
+    pub fn commit_action(&self) -> serde_json::Value {
+        serde_json::json!({ "method": "post", "path": self.path(), "value": self })
+    }
+}
+
+/// A validator's signed approval of a proposal
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProposalApproval {
+    pub proposal_id: String,
+    /// Peer id of the approving validator
+    pub validator: String,
+    /// The validator's signature over the proposal's signing payload
+    pub signature: String,
+}
+
+impl ProposalApproval {
+    /// Approve `proposal` as the validator holding `keypair`
+    pub fn sign(proposal: &ParameterProposal, keypair: &Keypair) -> anyhow::Result<Self> {
+        Ok(Self {
+            proposal_id: proposal.id.clone(),
+            validator: keypair.as_public_key_id(),
+            signature: keypair.sign_json(&proposal.signing_payload())?,
+        })
+    }
+
+    /// Whether the signature is the validator's, over `proposal`
+    pub fn verify(&self, proposal: &ParameterProposal) -> bool {
+        self.proposal_id == proposal.id
+            && Keypair::from_public_key(&self.validator, "ed25519")
+                .and_then(|key| key.verify_json(&self.signature, &proposal.signing_payload()))
+                .unwrap_or(false)
+    }
+
+    /// Contract path of the approval
+    pub fn path(&self) -> String {
+        format!("/proposals/{}/approvals/{}.json", self.proposal_id, self.validator)
+    }
+
+    /// Commit action posting the approval
+    pub fn commit_action(&self) -> serde_json::Value {
+        serde_json::json!({ "method": "post", "path": self.path(), "value": self })
+    }
+}
+
+/// Approvals needed out of `validators`: more than two thirds
+pub fn quorum(validators: usize) -> usize {
+    validators * 2 / 3 + 1
+}
+
+/// What a governance contract path holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GovernancePath {
+    Proposal { id: String },
+    Approval { proposal_id: String, validator: String },
+}
+
+impl GovernancePath {
+    pub fn parse(path: &str) -> Option<Self> {
+        let rest = path.strip_prefix("/proposals/")?.strip_suffix(".json")?;
+        match rest.split('/').collect::<Vec<_>>().as_slice() {
+            [id] => Some(Self::Proposal { id: id.to_string() }),
+            [proposal_id, "approvals", validator] => Some(Self::Approval {
+                proposal_id: proposal_id.to_string(),
+                validator: validator.to_string(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+fn contract_key(path: &str) -> String {
+    format!("/contracts/{}{}", GOVERNANCE_CONTRACT_ID, path)
+}
+
+impl DatastoreManager {
+    /// Every proposal committed to the governance contract
+    pub fn governance_proposals(&self) -> Result<Vec<ParameterProposal>> {
+        let root = contract_key("");
+        let mut proposals = Vec::new();
+        for item in self.node_state().iterator(&contract_key("/proposals")) {
+            let (key, value) = item?;
+            let key = String::from_utf8(key.to_vec())?;
+            if let Some(GovernancePath::Proposal { .. }) = GovernancePath::parse(&key[root.len()..]) {
+                proposals.push(serde_json::from_slice(&value)?);
+            }
+        }
+        Ok(proposals)
+    }
+
+    /// A proposal by id
+    pub fn governance_proposal(&self, id: &str) -> Result<Option<ParameterProposal>> {
+        match self.node_state().get(&contract_key(&format!("/proposals/{}.json", id)))? {
+            Some(value) => Ok(Some(serde_json::from_slice(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Approvals committed for a proposal whose signatures check out
+    pub fn proposal_approvals(&self, proposal: &ParameterProposal) -> Result<Vec<ProposalApproval>> {
+        let prefix = contract_key(&format!("/proposals/{}/approvals", proposal.id));
+        let mut approvals = Vec::new();
+        for item in self.node_state().iterator(&prefix) {
+            let (_, value) = item?;
+            let approval: ProposalApproval = serde_json::from_slice(&value)?;
+            if approval.verify(proposal) {
+                approvals.push(approval);
+            }
+        }
+        Ok(approvals)
+    }
+
+    /// Whether `validators` approved a proposal with a quorum
+    pub fn proposal_approved(&self, proposal: &ParameterProposal, validators: &[String]) -> Result<bool> {
+        if validators.is_empty() {
+            return Ok(false);
+        }
+        let approvals = self
+            .proposal_approvals(proposal)?
+            .into_iter()
+            .filter(|a| validators.contains(&a.validator))
+            .count();
+        Ok(approvals >= quorum(validators.len()))
+    }
+
+    /// Height a proposal was applied at, if it was
+    pub fn proposal_applied_at(&self, id: &str) -> Result<Option<u64>> {
+        match self.node_state().get(&format!("{}/{}", APPLIED_PREFIX, id))? {
+            Some(value) => Ok(Some(String::from_utf8(value)?.parse()?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Record that a proposal was applied at `height`
+    pub fn mark_proposal_applied(&self, id: &str, height: u64) -> Result<()> {
+        self.node_state().put(&format!("{}/{}", APPLIED_PREFIX, id), height.to_string().as_bytes())
+    }
+
+    /// Why an approved proposal couldn't be applied, if it couldn't
+    pub fn proposal_rejection(&self, id: &str) -> Result<Option<String>> {
+        match self.node_state().get(&format!("{}/{}", REJECTED_PREFIX, id))? {
+            Some(value) => Ok(Some(String::from_utf8(value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Record that an approved proposal can't be applied
+    pub fn mark_proposal_rejected(&self, id: &str, reason: &str) -> Result<()> {
+        self.node_state().put(&format!("{}/{}", REJECTED_PREFIX, id), reason.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proposal() -> ParameterProposal {
+        ParameterProposal {
+            id: "shorter-epochs".to_string(),
+            proposer: "peer".to_string(),
+            activation_height: 400,
+            changes: ParameterChange { blocks_per_epoch: Some(20), ..Default::default() },
+            description: String::new(),
+        }
+    }
+
+    fn post<T: Serialize>(mgr: &DatastoreManager, path: &str, value: &T) {
+        mgr.node_state().put(&contract_key(path), &serde_json::to_vec(value).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_approval_signatures_and_paths() {
+        let proposal = proposal();
+        proposal.validate().unwrap();
+        let keypair = Keypair::generate().unwrap();
+        let approval = ProposalApproval::sign(&proposal, &keypair).unwrap();
+        assert!(approval.verify(&proposal));
+
+        // Approvals don't carry over to a changed proposal
+        let mut changed = proposal.clone();
+        changed.activation_height = 800;
+        assert!(!approval.verify(&changed));
+
+        assert_eq!(GovernancePath::parse(&proposal.path()), Some(GovernancePath::Proposal { id: proposal.id.clone() }));
+        assert_eq!(
+            GovernancePath::parse(&approval.path()),
+            Some(GovernancePath::Approval { proposal_id: proposal.id.clone(), validator: approval.validator.clone() })
+        );
+        assert_eq!(GovernancePath::parse("/other.json"), None);
+        assert!(ParameterProposal { changes: ParameterChange::default(), ..proposal }.validate().is_err());
+    }
+
+    #[test]
+    fn test_quorum_of_validators() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        let proposal = proposal();
+        post(&mgr, &proposal.path(), &proposal);
+        assert_eq!(mgr.governance_proposals().unwrap(), vec![proposal.clone()]);
+
+        let keypairs: Vec<Keypair> = (0..4).map(|_| Keypair::generate().unwrap()).collect();
+        let validators: Vec<String> = keypairs.iter().map(|k| k.as_public_key_id()).collect();
+        assert_eq!(quorum(validators.len()), 3);
+        for keypair in &keypairs[..2] {
+            let approval = ProposalApproval::sign(&proposal, keypair).unwrap();
+            post(&mgr, &approval.path(), &approval);
+        }
+        assert!(!mgr.proposal_approved(&proposal, &validators).unwrap());
+
+        let approval = ProposalApproval::sign(&proposal, &keypairs[2]).unwrap();
+        post(&mgr, &approval.path(), &approval);
+        assert!(mgr.proposal_approved(&proposal, &validators).unwrap());
+        // Only approvals of current validators count
+        assert!(!mgr.proposal_approved(&proposal, &validators[3..]).unwrap());
+
+        assert_eq!(mgr.proposal_applied_at(&proposal.id).unwrap(), None);
+        mgr.mark_proposal_applied(&proposal.id, 400).unwrap();
+        assert_eq!(mgr.proposal_applied_at(&proposal.id).unwrap(), Some(400));
+    }
+}
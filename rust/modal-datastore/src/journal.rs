@@ -0,0 +1,213 @@
+//! Append-only event journal with durable consumer cursors
+//!
+//! Node components append events (new canonical blocks, reorgs, applied
+//! commits, finalized rounds, consensus stalls, validator offenses) to a
+//! journal in node_state. Each event gets the next sequence number, starting at 0 with no gaps, and
+//! is never rewritten.
+//! External consumers such as indexers read events after a cursor and store
+//! the cursor under their name once they've handled them, so a consumer that
+//! restarts, or a node that restarts, picks up exactly where it left off.
+
+use crate::stores::{Store, WriteBatch};
+use crate::{DatastoreManager, Error, Result};
+use serde::{Deserialize, Serialize};
+
+/// Key prefix of the journal events, in node_state
+const JOURNAL_EVENT_PREFIX: &str = "/journal/events";
+
+/// Key holding the sequence number the next event gets
+const JOURNAL_HEAD_KEY: &str = "/journal/head";
+
+/// Key prefix of the consumer cursors
+const JOURNAL_CURSOR_PREFIX: &str = "/journal/cursors";
+
+/// Most events returned by one read
+pub const MAX_JOURNAL_READ: usize = 1000;
+
+/// What happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalEventKind {
+    /// A miner block joined the canonical chain
+    NewBlock,
+    /// Canonical blocks were replaced by another branch
+    Reorg,
+    /// A contract commit was applied to contract state
+    CommitApplied,
+    /// Validator consensus committed a round
+    RoundFinalized,
+    /// Consensus lost its quorum and the node fell back to mining-only
+    ConsensusStalled,
+    /// Consensus certified rounds again after a fallback
+    ConsensusResumed,
+    /// This node verified equivocation evidence and gossiped it to be mined
+    OffenseRecorded,
+}
+
+/// An event in the journal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEvent {
+    pub seq: u64,
+    pub kind: JournalEventKind,
+    /// Unix seconds when the event was appended
+    pub timestamp: i64,
+    /// Event details; their shape depends on the kind
+    pub data: serde_json::Value,
+}
+
+fn event_key(seq: u64) -> String {
+    format!("{}/{:020}", JOURNAL_EVENT_PREFIX, seq)
+}
+
+fn cursor_key(consumer: &str) -> String {
+    format!("{}/{}", JOURNAL_CURSOR_PREFIX, consumer)
+}
+
+impl DatastoreManager {
+    /// Sequence number the next journal event gets (the journal's length)
+    pub fn journal_head(&self) -> Result<u64> {
+        match self.node_state().get(JOURNAL_HEAD_KEY)? {
+            Some(value) => String::from_utf8(value)?
+                .parse()
+                .map_err(|e| Error::InvalidData(format!("Invalid journal head: {}", e))),
+            None => Ok(0),
+        }
+    }
+
+    /// Append an event to the journal, returning its sequence number
+    pub fn append_event(&self, kind: JournalEventKind, data: serde_json::Value) -> Result<u64> {
+        let seq = self.journal_head()?;
+        let event = JournalEvent {
+            seq,
+            kind,
+            timestamp: chrono::Utc::now().timestamp(),
+            data,
+        };
+        // The event and the head move together, so a crash can't leave a gap
+        let mut batch = WriteBatch::default();
+        batch.put(event_key(seq), serde_json::to_vec(&event)?);
+        batch.put(JOURNAL_HEAD_KEY, (seq + 1).to_string());
+        self.node_state().write(batch)?;
+        Ok(seq)
+    }
+
+    /// Up to `limit` events from `cursor` on (the sequence number of the
+    /// first event wanted; 0 reads from the start)
+    pub fn read_events(&self, cursor: u64, limit: usize) -> Result<Vec<JournalEvent>> {
+        let head = self.journal_head()?;
+        let mut events = Vec::new();
+        for seq in (cursor..head).take(limit.min(MAX_JOURNAL_READ)) {
+            match self.node_state().get(&event_key(seq))? {
+                Some(value) => events.push(serde_json::from_slice(&value)?),
+                None => return Err(Error::InvalidData(format!("Journal event {} is missing", seq))),
+            }
+        }
+        Ok(events)
+    }
+
+    /// Cursor stored by `consumer`: the sequence number of the next event it
+    /// wants, or 0 if it never stored one
+    pub fn journal_cursor(&self, consumer: &str) -> Result<u64> {
+        match self.node_state().get(&cursor_key(consumer))? {
+            Some(value) => String::from_utf8(value)?
+                .parse()
+                .map_err(|e| Error::InvalidData(format!("Invalid journal cursor: {}", e))),
+            None => Ok(0),
+        }
+    }
+
+    /// Store `consumer`'s cursor. It can't point past the journal's head.
+    pub fn set_journal_cursor(&self, consumer: &str, cursor: u64) -> Result<()> {
+        if consumer.is_empty() || consumer.contains('/') {
+            return Err(Error::InvalidData(format!("Invalid journal consumer name: {}", consumer)));
+        }
+        let head = self.journal_head()?;
+        if cursor > head {
+            return Err(Error::InvalidData(format!("Cursor {} is past the journal head {}", cursor, head)));
+        }
+        self.node_state().put(&cursor_key(consumer), cursor.to_string().as_bytes())
+    }
+
+    /// Read events for a poll: from `cursor` if given, else from
+    /// `consumer`'s stored cursor. With both, the cursor is stored for the
+    /// consumer first, acknowledging the events before it. Returns the
+    /// events and the cursor to poll with next.
+    pub fn poll_events(
+        &self,
+        consumer: Option<&str>,
+        cursor: Option<u64>,
+        limit: usize,
+    ) -> Result<(Vec<JournalEvent>, u64)> {
+        let cursor = match (consumer, cursor) {
+            (Some(consumer), Some(cursor)) => {
+                self.set_journal_cursor(consumer, cursor)?;
+                cursor
+            }
+            (Some(consumer), None) => self.journal_cursor(consumer)?,
+            (None, cursor) => cursor.unwrap_or(0),
+        };
+        let events = self.read_events(cursor, limit)?;
+        let next_cursor = events.last().map(|e| e.seq + 1).unwrap_or(cursor);
+        Ok((events, next_cursor))
+    }
+
+    /// Every stored consumer cursor, by consumer name
+    pub fn journal_cursors(&self) -> Result<Vec<(String, u64)>> {
+        let mut cursors = Vec::new();
+        for item in self.node_state().iterator(JOURNAL_CURSOR_PREFIX) {
+            let (key, value) = item?;
+            let key = String::from_utf8(key.to_vec())?;
+            let cursor = String::from_utf8(value.to_vec())?
+                .parse()
+                .map_err(|e| Error::InvalidData(format!("Invalid journal cursor: {}", e)))?;
+            cursors.push((key[JOURNAL_CURSOR_PREFIX.len() + 1..].to_string(), cursor));
+        }
+        Ok(cursors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_read_from_cursor() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        for index in 0..3u64 {
+            let seq = mgr.append_event(JournalEventKind::NewBlock, serde_json::json!({ "index": index })).unwrap();
+            assert_eq!(seq, index);
+        }
+        mgr.append_event(JournalEventKind::RoundFinalized, serde_json::json!({ "round": 7 })).unwrap();
+        assert_eq!(mgr.journal_head().unwrap(), 4);
+
+        let events = mgr.read_events(2, 10).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].data["index"], 2);
+        assert_eq!(events[1].kind, JournalEventKind::RoundFinalized);
+        assert!(mgr.read_events(4, 10).unwrap().is_empty());
+        assert_eq!(mgr.read_events(0, 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_consumer_cursors() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        mgr.append_event(JournalEventKind::CommitApplied, serde_json::json!({})).unwrap();
+        mgr.append_event(JournalEventKind::CommitApplied, serde_json::json!({})).unwrap();
+
+        assert_eq!(mgr.journal_cursor("indexer").unwrap(), 0);
+        mgr.set_journal_cursor("indexer", 2).unwrap();
+        assert_eq!(mgr.journal_cursor("indexer").unwrap(), 2);
+        assert_eq!(mgr.journal_cursors().unwrap(), vec![("indexer".to_string(), 2)]);
+
+        assert!(mgr.set_journal_cursor("indexer", 3).is_err());
+        assert!(mgr.set_journal_cursor("bad/name", 0).is_err());
+
+        // Polling acknowledges the cursor it's given and resumes from it later
+        let (events, next) = mgr.poll_events(Some("explorer"), None, 1).unwrap();
+        assert_eq!((events.len(), next), (1, 1));
+        assert_eq!(mgr.journal_cursor("explorer").unwrap(), 0);
+        mgr.poll_events(Some("explorer"), Some(next), 10).unwrap();
+        let (events, next) = mgr.poll_events(Some("explorer"), None, 10).unwrap();
+        assert_eq!((events[0].seq, next), (1, 2));
+    }
+}
@@ -0,0 +1,81 @@
+//! Validator key rotations
+//!
+//! A validator replaces its key by committing a rotation record signed by
+//! the old key (see `modal_common::key_rotation`) to the key rotation system
+//! contract at `/rotations/<old key>.json`. Signatures the old key made
+//! before the rotation took effect keep verifying; after, only the new key
+//! signs for the validator.
+
+use crate::stores::Store;
+use crate::{DatastoreManager, Result};
+pub use modal_common::key_rotation::{old_key_from_path, KeyHistory, KeyRotation};
+
+/// Id of the system contract holding key rotation records
+pub const KEYS_CONTRACT_ID: &str = "modal.keys";
+
+fn contract_key(path: &str) -> String {
+    format!("/contracts/{}{}", KEYS_CONTRACT_ID, path)
+}
+
+impl DatastoreManager {
+    /// Every rotation committed to the key rotation contract, in the order
+    /// they take effect
+    pub fn key_rotations(&self) -> Result<Vec<KeyRotation>> {
+        let root = contract_key("");
+        let mut rotations = Vec::new();
+        for item in self.node_state().iterator(&contract_key("/rotations")) {
+            let (key, value) = item?;
+            let key = String::from_utf8(key.to_vec())?;
+            if old_key_from_path(&key[root.len()..]).is_some() {
+                rotations.push(serde_json::from_slice::<KeyRotation>(&value)?);
+            }
+        }
+        rotations.sort_by_key(|r| r.effective_height);
+        Ok(rotations)
+    }
+
+    /// The rotation replacing `old_key`, if it was rotated
+    pub fn key_rotation(&self, old_key: &str) -> Result<Option<KeyRotation>> {
+        match self.node_state().get(&contract_key(&format!("/rotations/{}.json", old_key)))? {
+            Some(value) => Ok(Some(serde_json::from_slice(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Chains of validator keys from the committed rotations
+    pub fn key_history(&self) -> Result<KeyHistory> {
+        Ok(KeyHistory::from_rotations(self.key_rotations()?))
+    }
+
+    /// Whether a signature by `signer` at `height` counts as one by the
+    /// validator that `key` is, or was, a key of
+    pub fn signs_for_at(&self, signer: &str, key: &str, height: u64) -> Result<bool> {
+        Ok(self.key_history()?.signs_for_at(signer, key, height))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use modal_common::keypair::Keypair;
+
+    #[test]
+    fn test_rotations_from_contract_state() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        let old = Keypair::generate().unwrap();
+        let new_key = Keypair::generate().unwrap().as_public_address();
+        let rotation = KeyRotation::sign(&old, &new_key, 100, Some("retired".into())).unwrap();
+        mgr.node_state()
+            .put(&contract_key(&rotation.path()), &serde_json::to_vec(&rotation).unwrap())
+            .unwrap();
+
+        assert_eq!(mgr.key_rotations().unwrap(), vec![rotation.clone()]);
+        assert_eq!(mgr.key_rotation(&rotation.old_key).unwrap(), Some(rotation.clone()));
+        assert_eq!(mgr.key_rotation(&new_key).unwrap(), None);
+
+        // Blocks the old key signed before height 100 still verify
+        assert!(mgr.signs_for_at(&rotation.old_key, &rotation.old_key, 99).unwrap());
+        assert!(!mgr.signs_for_at(&rotation.old_key, &rotation.old_key, 100).unwrap());
+        assert!(mgr.signs_for_at(&new_key, &rotation.old_key, 100).unwrap());
+    }
+}
@@ -8,14 +8,32 @@ pub mod models;
 // Multi-datastore architecture
 pub mod stores;
 pub mod datastore_manager;
+pub mod backup;
+pub mod migrations;
+pub mod fsck;
+pub mod journal;
+pub mod governance;
+pub mod key_rotations;
+pub mod boot_profile;
+pub mod consensus_liveness;
+pub mod slashing;
 
 pub use error::Error;
-pub use network_params::NetworkParameters;
+pub use network_params::{GasQuotas, NetworkParameters, ValidatorBlsKey};
 pub use datastore_manager::DatastoreManager;
+pub use backup::{BackupManifest, ChainTip, StoreManifest};
+pub use fsck::{FsckIssue, FsckReport, IssueKind, Repair, ResyncRange};
+pub use journal::{JournalEvent, JournalEventKind};
+pub use governance::{GovernancePath, ParameterChange, ParameterProposal, ProposalApproval, GOVERNANCE_CONTRACT_ID};
+pub use key_rotations::KEYS_CONTRACT_ID;
+pub use boot_profile::{BootPhase, BootProfile, IntegrityCheckpoint};
+pub use consensus_liveness::{ConsensusLiveness, ConsensusMode};
+pub use slashing::{Exclusion, Offense, OffenseKind, Penalty, SlashingPolicy, SlashingRecord};
 pub use stores::{
-    Store,
+    Store, StoreBackend, StorageConfig, StorageEngine, Durability, WriteBuffer, CacheStats,
     MinerCanonStore, MinerForksStore, MinerActiveStore,
-    ValidatorFinalStore, ValidatorActiveStore, NodeStateStore,
+    ValidatorFinalStore, ValidatorActiveStore, NodeStateStore, NodeMetricsStore, IndexStore,
+    MetricPoint, MetricSeries, IndexedBalance, IndexedCommit,
 };
 
 pub type Result<T> = std::result::Result<T, Error>;
\ No newline at end of file
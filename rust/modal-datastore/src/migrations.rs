@@ -0,0 +1,162 @@
+//! Schema versions and datastore migrations
+//!
+//! Each store keeps its schema version under `/_schema/version`. Models list
+//! the steps that bring their stored records up to date in
+//! `Model::MIGRATIONS`; a step's version is the store's schema version after
+//! it runs, so models sharing a store number their steps in one sequence.
+//! `DatastoreManager::open` runs the steps newer than each store's version,
+//! in order, and records the new version with the rewritten records.
+//!
+//! A store with no version record and no data is new and starts at the
+//! current version. One with data but no record predates versioning and
+//! starts at 0. A store at a newer version than this build knows is refused
+//! rather than read with the wrong encoding.
+
+use crate::model::Model;
+use crate::models::{DAGCertificate, MinerBlock};
+use crate::stores::Store;
+use crate::{DatastoreManager, Error, Result};
+
+/// Key of a store's schema version
+pub const SCHEMA_VERSION_KEY: &str = "/_schema/version";
+
+/// A step rewriting one model's stored records
+pub struct Migration {
+    /// Store schema version after this step
+    pub version: u32,
+    pub description: &'static str,
+    /// Rewrite a record's JSON; `None` deletes the record
+    pub apply: fn(serde_json::Value) -> anyhow::Result<Option<serde_json::Value>>,
+}
+
+/// A migration step bound to the store type it runs on
+struct Step<S> {
+    migration: &'static Migration,
+    collection: &'static str,
+    run: fn(&S, &Migration) -> anyhow::Result<usize>,
+}
+
+fn steps_for<M: Model, S: Store + Send + Sync>() -> Vec<Step<S>> {
+    M::MIGRATIONS
+        .iter()
+        .map(|migration| Step {
+            migration,
+            collection: M::collection_prefix(),
+            run: M::migrate_in_store::<S>,
+        })
+        .collect()
+}
+
+/// Schema version recorded in a store, if any
+pub fn schema_version<S: Store>(store: &S) -> Result<Option<u32>> {
+    match store.get(SCHEMA_VERSION_KEY)? {
+        Some(bytes) => {
+            let version = String::from_utf8(bytes)?.parse()?;
+            Ok(Some(version))
+        }
+        None => Ok(None),
+    }
+}
+
+fn set_schema_version<S: Store>(store: &S, version: u32) -> Result<()> {
+    store.put(SCHEMA_VERSION_KEY, version.to_string().as_bytes())
+}
+
+/// Run the steps newer than the store's version. Returns the number of steps run.
+fn migrate_store<S: Store + Send + Sync>(name: &str, store: &S, mut steps: Vec<Step<S>>) -> Result<usize> {
+    steps.sort_by_key(|step| step.migration.version);
+    let target = steps.last().map_or(0, |step| step.migration.version);
+
+    let current = match schema_version(store)? {
+        Some(version) => version,
+        None if store.iter_all().next().is_none() => {
+            set_schema_version(store, target)?;
+            return Ok(0);
+        }
+        None => 0,
+    };
+    if current > target {
+        return Err(Error::InvalidData(format!(
+            "{} is at schema version {}, newer than the supported version {}",
+            name, current, target
+        )));
+    }
+
+    let mut ran = 0;
+    for step in steps.iter().filter(|step| step.migration.version > current) {
+        let records = (step.run)(store, step.migration)
+            .map_err(|e| Error::Database(format!("Migration {} of {} failed: {}", step.migration.version, name, e)))?;
+        set_schema_version(store, step.migration.version)?;
+        log::info!(
+            "Migrated {} to schema version {} ({}): {} {} records",
+            name, step.migration.version, step.migration.description, records, step.collection
+        );
+        ran += 1;
+    }
+    Ok(ran)
+}
+
+impl DatastoreManager {
+    /// Bring every store up to the current schema version.
+    /// Returns the number of migration steps run.
+    pub fn migrate(&self) -> Result<usize> {
+        Ok(migrate_store("miner_canon", self.miner_canon(), steps_for::<MinerBlock, _>())?
+            + migrate_store("miner_forks", self.miner_forks(), steps_for::<MinerBlock, _>())?
+            + migrate_store("miner_active", self.miner_active(), steps_for::<MinerBlock, _>())?
+            + migrate_store("validator_final", self.validator_final(), steps_for::<DAGCertificate, _>())?
+            + migrate_store("validator_active", self.validator_active(), Vec::new())?
+            + migrate_store("node_state", self.node_state(), Vec::new())?
+            + migrate_store("node_metrics", self.node_metrics(), Vec::new())?
+            + migrate_store("index", self.index(), Vec::new())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_new_stores_start_at_current_version() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        assert_eq!(mgr.migrate().unwrap(), 0);
+        assert_eq!(schema_version(mgr.miner_active()).unwrap(), Some(1));
+        assert_eq!(schema_version(mgr.validator_final()).unwrap(), Some(1));
+        assert_eq!(schema_version(mgr.node_state()).unwrap(), Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_unversioned_records_are_migrated() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        // A block stored before actualized_difficulty existed
+        let old_block = serde_json::json!({
+            "hash": "abc", "index": 3, "epoch": 0, "timestamp": 0,
+            "previous_hash": "prev", "data_hash": "data", "nonce": "1",
+            "target_difficulty": "1000", "nominated_peer_id": "peer", "miner_number": 1,
+            "is_orphaned": false, "is_canonical": true,
+            "seen_at": null, "orphaned_at": null, "orphan_reason": null,
+            "height_at_time": null, "competing_hash": null,
+        });
+        mgr.miner_active().put("/miner_blocks/hash/abc", old_block.to_string().as_bytes()).unwrap();
+        assert!(MinerBlock::find_by_hash_multi(&mgr, "abc").await.is_err());
+
+        assert_eq!(mgr.migrate().unwrap(), 1);
+        assert_eq!(schema_version(mgr.miner_active()).unwrap(), Some(1));
+        let block = MinerBlock::find_by_hash_multi(&mgr, "abc").await.unwrap().unwrap();
+        let expected = modal_common::hash_tax::hash_to_actualized_difficulty("abc")
+            .map(|d| d.to_string())
+            .unwrap_or_else(|_| "1000".to_string());
+        assert_eq!(block.actualized_difficulty, expected);
+        // The migrated block is indexed
+        assert_eq!(MinerBlock::find_by_index_multi(&mgr, 3).await.unwrap().len(), 1);
+
+        // Nothing left to run
+        assert_eq!(mgr.migrate().unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_newer_schema_is_refused() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        set_schema_version(mgr.node_state(), 5).unwrap();
+        assert!(mgr.migrate().is_err());
+    }
+}
@@ -5,12 +5,44 @@ use anyhow::{Result, Context, anyhow};
 
 use crate::stores::Store;
 
+/// Key prefix of secondary index entries
+const INDEX_PREFIX: &str = "/_index";
+
+/// Key prefix of the markers recording which indexes a collection has built
+const INDEX_MARKER_PREFIX: &str = "/_indexes";
+
+/// How a field value appears in index keys: strings with `/` escaped,
+/// unsigned numbers zero-padded so they sort in order, and booleans.
+/// Other values aren't indexed.
+pub fn index_value(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(s) => Some(s.replace('%', "%25").replace('/', "%2F")),
+        serde_json::Value::Number(n) => match n.as_u64() {
+            Some(n) => Some(format!("{:020}", n)),
+            None => Some(n.to_string()),
+        },
+        serde_json::Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
 #[async_trait]
-pub trait Model: Sized + Serialize + for<'de> Deserialize<'de> {
+pub trait Model: Sized + Clone + Send + Sync + 'static + Serialize + for<'de> Deserialize<'de> {
     const ID_PATH: &'static str;
     const FIELDS: &'static [&'static str];
     const FIELD_DEFAULTS: &'static [(&'static str, serde_json::Value)];
 
+    /// Fields with a secondary index. Index entries are written in the same
+    /// batch as the model, so they never disagree with it.
+    const INDEXES: &'static [&'static str] = &[];
+
+    /// Steps bringing stored records up to date, see `crate::migrations`
+    const MIGRATIONS: &'static [crate::migrations::Migration] = &[];
+
+    /// Whether stores keep loaded copies in their read-through cache, see
+    /// `crate::stores::cache`
+    const CACHED: bool = false;
+
     fn create_from_json(obj: serde_json::Value) -> Result<Self> {
         let mut model: Self = serde_json::from_value(obj.clone())
             .context("Failed to deserialize object")?;
@@ -48,11 +80,201 @@ pub trait Model: Sized + Serialize + for<'de> Deserialize<'de> {
         serde_json::to_value(self).context("Failed to serialize to JSON value")
     }
 
+    /// Load the model stored under `id`, through the store's cache if the
+    /// model is cached
+    fn load_from_store<S: Store>(store: &S, id: &str) -> Result<Option<Self>> {
+        let cache = store.cache().filter(|_| Self::CACHED);
+        if let Some(model) = cache.and_then(|cache| cache.get::<Self>(id.as_bytes())) {
+            return Ok(Some(model));
+        }
+        let generation = cache.map(|cache| cache.generation());
+        let Some(data) = store.get(id)? else {
+            return Ok(None);
+        };
+        let json = String::from_utf8(data).context("Failed to convert value to string")?;
+        let model = Self::from_json_string(&json)?;
+        if let (Some(cache), Some(generation)) = (cache, generation) {
+            cache.insert(id.as_bytes(), model.clone(), generation);
+        }
+        Ok(Some(model))
+    }
+
     /// Save this model to the specified store
     async fn save_to_store<S: Store + Send + Sync>(&self, store: &S) -> Result<()> {
         let json = self.to_json_string()?;
-        store.put(&self.get_id(), json.as_bytes())
-            .context("Failed to save model to store")
+        let id = self.get_id();
+        if Self::INDEXES.is_empty() {
+            return store.put(&id, json.as_bytes())
+                .context("Failed to save model to store");
+        }
+
+        // Replace the index entries of the stored version, if any
+        let mut batch = crate::stores::WriteBatch::default();
+        let new_entries = self.index_entries()?;
+        if let Some(previous) = store.get(&id)? {
+            let previous: serde_json::Value = serde_json::from_slice(&previous)
+                .context("Failed to parse stored model")?;
+            for key in Self::index_entries_for(&previous, &id) {
+                if !new_entries.contains(&key) {
+                    batch.delete(key);
+                }
+            }
+        }
+        batch.put(&id, json.as_bytes());
+        for key in &new_entries {
+            batch.put(key, id.as_bytes());
+        }
+        store.write(batch).context("Failed to save model to store")
+    }
+
+    /// Collection key prefix: the part of ID_PATH before its first key
+    fn collection_prefix() -> &'static str {
+        let end = Self::ID_PATH.find("/${").unwrap_or(Self::ID_PATH.len());
+        &Self::ID_PATH[..end]
+    }
+
+    /// Key prefix of the entries of one index value
+    fn index_prefix(field: &str, value: &str) -> String {
+        format!("{}{}/{}/{}", INDEX_PREFIX, Self::collection_prefix(), field, value)
+    }
+
+    /// Index entry keys for a model's JSON and id
+    fn index_entries_for(obj: &serde_json::Value, id: &str) -> Vec<String> {
+        Self::INDEXES
+            .iter()
+            .filter_map(|field| {
+                let value = index_value(obj.get(*field)?)?;
+                Some(format!("{}{}", Self::index_prefix(field, &value), id))
+            })
+            .collect()
+    }
+
+    /// Index entry keys of this model
+    fn index_entries(&self) -> Result<Vec<String>> {
+        Ok(Self::index_entries_for(&self.to_json_object()?, &self.get_id()))
+    }
+
+    /// Find the models whose indexed `field` equals `value`
+    async fn find_by_index_from_store<S: Store + Send + Sync>(
+        store: &S,
+        field: &str,
+        value: &serde_json::Value,
+    ) -> Result<Vec<Self>> {
+        if !Self::INDEXES.contains(&field) {
+            return Err(anyhow!("{} is not indexed in {}", field, Self::ID_PATH));
+        }
+        let Some(value) = index_value(value) else {
+            return Ok(vec![]);
+        };
+        let mut models = Vec::new();
+        for item in store.iterator(&Self::index_prefix(field, &value)) {
+            let (_, id) = item?;
+            let id = String::from_utf8(id.to_vec()).context("Failed to convert index entry to string")?;
+            if let Some(model) = Self::load_from_store(store, &id)? {
+                models.push(model);
+            }
+        }
+        Ok(models)
+    }
+
+    /// Find the models whose indexed string `field` starts with `prefix`
+    async fn find_by_index_prefix_from_store<S: Store + Send + Sync>(
+        store: &S,
+        field: &str,
+        prefix: &str,
+    ) -> Result<Vec<Self>> {
+        if !Self::INDEXES.contains(&field) {
+            return Err(anyhow!("{} is not indexed in {}", field, Self::ID_PATH));
+        }
+        let Some(prefix) = index_value(&serde_json::Value::String(prefix.to_string())) else {
+            return Ok(vec![]);
+        };
+        let mut models = Vec::new();
+        for item in store.prefix_iterator(&Self::index_prefix(field, &prefix)) {
+            let (_, id) = item?;
+            let id = String::from_utf8(id.to_vec()).context("Failed to convert index entry to string")?;
+            if let Some(model) = Self::load_from_store(store, &id)? {
+                models.push(model);
+            }
+        }
+        Ok(models)
+    }
+
+    /// Whether the store has this model's current indexes built
+    fn indexes_built<S: Store + Send + Sync>(store: &S) -> Result<bool> {
+        let marker = format!("{}{}", INDEX_MARKER_PREFIX, Self::collection_prefix());
+        Ok(store.get(&marker)?.as_deref() == Some(Self::INDEXES.join(",").as_bytes()))
+    }
+
+    /// Drop and rebuild this model's index entries from the stored models.
+    /// Returns the number of models indexed.
+    fn rebuild_indexes_in_store<S: Store + Send + Sync>(store: &S) -> Result<usize> {
+        let collection = Self::collection_prefix();
+        let index_root = format!("{}{}", INDEX_PREFIX, collection);
+
+        let mut batch = crate::stores::WriteBatch::default();
+        for item in store.iterator(&index_root) {
+            let (key, _) = item?;
+            batch.delete(key);
+        }
+        store.write(batch).context("Failed to drop indexes")?;
+
+        let mut batch = crate::stores::WriteBatch::default();
+        let mut count = 0;
+        for item in store.iterator(collection) {
+            let (key, value) = item?;
+            let id = String::from_utf8(key.to_vec()).context("Failed to convert key to string")?;
+            // Skip keys that aren't models, like other collections' keys under this prefix
+            let Ok(obj) = serde_json::from_slice::<serde_json::Value>(&value) else {
+                continue;
+            };
+            if !obj.is_object() {
+                continue;
+            }
+            for entry in Self::index_entries_for(&obj, &id) {
+                batch.put(entry, id.as_bytes());
+            }
+            count += 1;
+        }
+        batch.put(format!("{}{}", INDEX_MARKER_PREFIX, collection), Self::INDEXES.join(","));
+        store.write(batch).context("Failed to write indexes")?;
+        Ok(count)
+    }
+
+    /// Build this model's indexes if the store doesn't have them yet.
+    /// Returns whether they were rebuilt.
+    fn ensure_indexes_in_store<S: Store + Send + Sync>(store: &S) -> Result<bool> {
+        if Self::INDEXES.is_empty() || Self::indexes_built(store)? {
+            return Ok(false);
+        }
+        Self::rebuild_indexes_in_store(store)?;
+        Ok(true)
+    }
+
+    /// Apply a migration to every record of this model in the store, then
+    /// rebuild its indexes. Returns the number of records rewritten.
+    fn migrate_in_store<S: Store + Send + Sync>(store: &S, migration: &crate::migrations::Migration) -> Result<usize> {
+        let mut batch = crate::stores::WriteBatch::default();
+        let mut count = 0;
+        for item in store.iterator(Self::collection_prefix()) {
+            let (key, value) = item?;
+            let Ok(obj) = serde_json::from_slice::<serde_json::Value>(&value) else {
+                continue;
+            };
+            if !obj.is_object() {
+                continue;
+            }
+            match (migration.apply)(obj)? {
+                Some(obj) => batch.put(&key, serde_json::to_vec(&obj)?),
+                None => batch.delete(&key),
+            }
+            count += 1;
+        }
+        store.write(batch).context("Failed to write migrated records")?;
+        if !Self::INDEXES.is_empty() {
+            Self::rebuild_indexes_in_store(store)?;
+        }
+        Ok(count)
     }
 
     fn get_id_for(keys: &HashMap<String, String>) -> String {
@@ -79,15 +301,7 @@ pub trait Model: Sized + Serialize + for<'de> Deserialize<'de> {
 
     /// Find one model from the specified store
     async fn find_one_from_store<S: Store + Send + Sync>(store: &S, keys: HashMap<String, String>) -> Result<Option<Self>> {
-        let key = Self::get_id_for(&keys);
-        match store.get(&key)? {
-            Some(value) => {
-                let value_str = String::from_utf8(value.to_vec())
-                    .context("Failed to convert value to string")?;
-                Ok(Some(Self::from_json_string(&value_str)?))
-            },
-            None => Ok(None),
-        }
+        Self::load_from_store(store, &Self::get_id_for(&keys))
     }
 
     /// Reload this model from the specified store
@@ -103,7 +317,22 @@ pub trait Model: Sized + Serialize + for<'de> Deserialize<'de> {
 
     /// Delete this model from the specified store
     async fn delete_from_store<S: Store + Send + Sync>(&self, store: &S) -> Result<()> {
-        store.delete(&self.get_id())
-            .context("Failed to delete model from store")
+        let id = self.get_id();
+        if Self::INDEXES.is_empty() {
+            return store.delete(&id)
+                .context("Failed to delete model from store");
+        }
+
+        // Remove the entries of the stored version, which may differ from self
+        let mut batch = crate::stores::WriteBatch::default();
+        if let Some(stored) = store.get(&id)? {
+            let stored: serde_json::Value = serde_json::from_slice(&stored)
+                .context("Failed to parse stored model")?;
+            for key in Self::index_entries_for(&stored, &id) {
+                batch.delete(key);
+            }
+        }
+        batch.delete(&id);
+        store.write(batch).context("Failed to delete model from store")
     }
 }
\ No newline at end of file
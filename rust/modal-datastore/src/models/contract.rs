@@ -6,6 +6,7 @@ use async_trait::async_trait;
 use crate::DatastoreManager;
 use crate::stores::Store;
 use crate::model::Model;
+use modal_common::block_commits::CommitDigest;
 
 /// A contract represents a stateful entity with a unique ID
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -18,6 +19,7 @@ pub struct Contract {
 #[async_trait]
 impl Model for Contract {
     const ID_PATH: &'static str = "/contracts/${contract_id}";
+    const CACHED: bool = true;
     const FIELDS: &'static [&'static str] = &["contract_id", "genesis", "created_at"];
     const FIELD_DEFAULTS: &'static [(&'static str, serde_json::Value)] = &[];
 
@@ -92,6 +94,7 @@ pub struct Commit {
 #[async_trait]
 impl Model for Commit {
     const ID_PATH: &'static str = "/commits/${contract_id}/${commit_id}";
+    const CACHED: bool = true;
     const FIELDS: &'static [&'static str] = &["contract_id", "commit_id", "commit_data", "timestamp", "in_batch"];
     const FIELD_DEFAULTS: &'static [(&'static str, serde_json::Value)] = &[];
 
@@ -151,6 +154,61 @@ impl Commit {
         Self::find_one_from_store(datastore.validator_final(), keys).await
     }
 
+    /// Find commits across all contracts
+    pub async fn find_all_multi(datastore: &DatastoreManager) -> Result<Vec<Self>> {
+        let mut seen = std::collections::HashSet::new();
+        let mut commits = Vec::new();
+        
+        let store = datastore.validator_final();
+        for result in store.iterator("/commits") {
+            let (key, _) = result?;
+            let key_str = String::from_utf8(key.to_vec())?;
+            
+            let parts: Vec<&str> = key_str.split('/').collect();
+            if let (Some(cid), Some(cmid)) = (parts.get(2), parts.get(3)) {
+                if !seen.insert((cid.to_string(), cmid.to_string())) {
+                    continue;
+                }
+                let keys = [
+                    ("contract_id".to_string(), cid.to_string()),
+                    ("commit_id".to_string(), cmid.to_string()),
+                ].into_iter().collect();
+                
+                if let Some(commit) = Self::find_one_from_store(store, keys).await? {
+                    commits.push(commit);
+                }
+            }
+        }
+        
+        Ok(commits)
+    }
+
+    /// The mempool: commits not yet batched by validators or included in a
+    /// canonical mined block, oldest first
+    pub async fn find_pending_multi(datastore: &DatastoreManager) -> Result<Vec<Self>> {
+        let included: std::collections::HashSet<(String, String)> =
+            crate::models::MinerBlock::find_all_canonical_multi(datastore)
+                .await?
+                .into_iter()
+                .flat_map(|b| b.commits)
+                .map(|c| (c.contract_id, c.commit_id))
+                .collect();
+        
+        let mut pending: Vec<Self> = Self::find_all_multi(datastore)
+            .await?
+            .into_iter()
+            .filter(|c| c.in_batch.is_none())
+            .filter(|c| !included.contains(&(c.contract_id.clone(), c.commit_id.clone())))
+            .collect();
+        pending.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then_with(|| a.commit_id.cmp(&b.commit_id)));
+        Ok(pending)
+    }
+
+    /// Digest of this commit for inclusion in a mined block
+    pub fn digest(&self) -> CommitDigest {
+        CommitDigest::new(self.contract_id.clone(), self.commit_id.clone(), self.commit_data.len() as u64)
+    }
+
     /// Save this commit to the ValidatorFinal store
     pub async fn save_to_final(&self, datastore: &DatastoreManager) -> Result<()> {
         self.save_to_store(datastore.validator_final()).await
@@ -410,3 +468,209 @@ impl ReceivedSend {
         self.save_to_store(datastore.validator_final()).await
     }
 }
+
+/// A message one contract's commit sent to another contract. It is
+/// delivered once, in the recipient's next processed commit, and the
+/// delivery receipt is written back to the sender's state.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ContractMessage {
+    /// `{sender_commit_id}.{action_index}`
+    pub message_id: String,
+    pub from_contract: String,
+    pub to_contract: String,
+    pub sender_commit_id: String,
+    /// JSON-serialized message body
+    pub payload: String,
+    pub enqueued_at: u64,
+    /// Commit of the recipient the message was delivered in
+    pub delivered_in_commit: Option<String>,
+    pub delivered_at: Option<u64>,
+}
+
+#[async_trait]
+impl Model for ContractMessage {
+    const ID_PATH: &'static str = "/contract_messages/${to_contract}/${message_id}";
+    const FIELDS: &'static [&'static str] = &[
+        "message_id",
+        "from_contract",
+        "to_contract",
+        "sender_commit_id",
+        "payload",
+        "enqueued_at",
+        "delivered_in_commit",
+        "delivered_at",
+    ];
+    const FIELD_DEFAULTS: &'static [(&'static str, serde_json::Value)] = &[];
+
+    fn set_field(&mut self, field: &str, value: serde_json::Value) {
+        match field {
+            "message_id" => self.message_id = value.as_str().unwrap_or_default().to_string(),
+            "from_contract" => self.from_contract = value.as_str().unwrap_or_default().to_string(),
+            "to_contract" => self.to_contract = value.as_str().unwrap_or_default().to_string(),
+            "sender_commit_id" => self.sender_commit_id = value.as_str().unwrap_or_default().to_string(),
+            "payload" => self.payload = value.as_str().unwrap_or_default().to_string(),
+            "enqueued_at" => self.enqueued_at = value.as_u64().unwrap_or_default(),
+            "delivered_in_commit" => self.delivered_in_commit = value.as_str().map(|s| s.to_string()),
+            "delivered_at" => self.delivered_at = value.as_u64(),
+            _ => {},
+        }
+    }
+
+    fn get_id_keys(&self) -> HashMap<String, String> {
+        let mut keys = HashMap::new();
+        keys.insert("to_contract".to_string(), self.to_contract.clone());
+        keys.insert("message_id".to_string(), self.message_id.clone());
+        keys
+    }
+}
+
+impl ContractMessage {
+    /// Id of the message sent by the action at `action_index` of a commit
+    pub fn message_id_for(sender_commit_id: &str, action_index: usize) -> String {
+        format!("{}.{:04}", sender_commit_id, action_index)
+    }
+
+    pub fn is_delivered(&self) -> bool {
+        self.delivered_in_commit.is_some()
+    }
+
+    pub async fn find_one_multi(datastore: &DatastoreManager, keys: HashMap<String, String>) -> Result<Option<Self>> {
+        Self::find_one_from_store(datastore.validator_final(), keys).await
+    }
+
+    /// Messages to a contract, delivered or not, ordered by message id
+    pub async fn find_by_recipient_multi(
+        datastore: &DatastoreManager,
+        to_contract: &str,
+    ) -> Result<Vec<Self>> {
+        let prefix = format!("/contract_messages/{}", to_contract);
+        let mut messages = Vec::new();
+        for result in datastore.validator_final().iterator(&prefix) {
+            let (_, value) = result?;
+            messages.push(Self::from_json_string(&String::from_utf8(value.to_vec())?)?);
+        }
+        messages.sort_by(|a: &Self, b: &Self| a.message_id.cmp(&b.message_id));
+        Ok(messages)
+    }
+
+    /// Messages to a contract that haven't been delivered yet, ordered by message id
+    pub async fn find_pending_multi(
+        datastore: &DatastoreManager,
+        to_contract: &str,
+    ) -> Result<Vec<Self>> {
+        let messages = Self::find_by_recipient_multi(datastore, to_contract).await?;
+        Ok(messages.into_iter().filter(|m| !m.is_delivered()).collect())
+    }
+
+    /// Save this message to the ValidatorFinal store
+    pub async fn save_to_final(&self, datastore: &DatastoreManager) -> Result<()> {
+        self.save_to_store(datastore.validator_final()).await
+    }
+}
+
+/// Gas a contract's commits used in an epoch
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ContractGasUsage {
+    pub contract_id: String,
+    pub epoch: u64,
+    pub gas_used: u64,
+    /// Commits charged, including ones that used no gas
+    pub commits: u64,
+}
+
+#[async_trait]
+impl Model for ContractGasUsage {
+    const ID_PATH: &'static str = "/gas_usage/${epoch}/${contract_id}";
+    const FIELDS: &'static [&'static str] = &["contract_id", "epoch", "gas_used", "commits"];
+    const FIELD_DEFAULTS: &'static [(&'static str, serde_json::Value)] = &[];
+
+    fn set_field(&mut self, field: &str, value: serde_json::Value) {
+        match field {
+            "contract_id" => self.contract_id = value.as_str().unwrap_or_default().to_string(),
+            "epoch" => self.epoch = value.as_u64().unwrap_or_default(),
+            "gas_used" => self.gas_used = value.as_u64().unwrap_or_default(),
+            "commits" => self.commits = value.as_u64().unwrap_or_default(),
+            _ => {},
+        }
+    }
+
+    fn get_id_keys(&self) -> HashMap<String, String> {
+        let mut keys = HashMap::new();
+        keys.insert("epoch".to_string(), self.epoch.to_string());
+        keys.insert("contract_id".to_string(), self.contract_id.clone());
+        keys
+    }
+}
+
+impl ContractGasUsage {
+    /// Usage of a contract in an epoch, zero if it hasn't used any
+    pub async fn find_or_default_multi(
+        datastore: &DatastoreManager,
+        contract_id: &str,
+        epoch: u64,
+    ) -> Result<Self> {
+        let mut keys = HashMap::new();
+        keys.insert("epoch".to_string(), epoch.to_string());
+        keys.insert("contract_id".to_string(), contract_id.to_string());
+        Ok(Self::find_one_from_store(datastore.validator_final(), keys).await?
+            .unwrap_or_else(|| Self {
+                contract_id: contract_id.to_string(),
+                epoch,
+                ..Default::default()
+            }))
+    }
+
+    /// Usage of every contract that used gas in an epoch
+    pub async fn find_by_epoch_multi(datastore: &DatastoreManager, epoch: u64) -> Result<Vec<Self>> {
+        let prefix = format!("/gas_usage/{}", epoch);
+        let mut usages = Vec::new();
+        for result in datastore.validator_final().iterator(&prefix) {
+            let (_, value) = result?;
+            usages.push(Self::from_json_string(&String::from_utf8(value.to_vec())?)?);
+        }
+        Ok(usages)
+    }
+
+    /// Save this usage to the ValidatorFinal store
+    pub async fn save_to_final(&self, datastore: &DatastoreManager) -> Result<()> {
+        self.save_to_store(datastore.validator_final()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::MinerBlock;
+
+    fn commit(commit_id: &str, timestamp: u64, in_batch: Option<&str>) -> Commit {
+        Commit {
+            contract_id: "contract".to_string(),
+            commit_id: commit_id.to_string(),
+            commit_data: "{}".to_string(),
+            timestamp,
+            in_batch: in_batch.map(|s| s.to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_find_pending_commits() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        for c in [commit("late", 3, None), commit("early", 1, None), commit("batched", 2, Some("batch")), commit("mined", 2, None)] {
+            c.save_to_final(&mgr).await.unwrap();
+        }
+        
+        let block = MinerBlock::new_canonical(
+            "block_hash".to_string(), 1, 0, 0, "genesis".to_string(), String::new(),
+            0, 1, "peer".to_string(), 0,
+        ).with_commits(vec![commit("mined", 2, None).digest()]);
+        block.save_to_active(&mgr).await.unwrap();
+        
+        let pending: Vec<String> = Commit::find_pending_multi(&mgr)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|c| c.commit_id)
+            .collect();
+        assert_eq!(pending, vec!["early", "late"]);
+    }
+}
@@ -0,0 +1,407 @@
+//! Cold storage archives of finalized epochs
+//!
+//! An archive is a directory of immutable segment files plus `manifest.json`,
+//! laid out so it can be copied as-is to S3-compatible object storage and
+//! served over HTTP. Each segment is the zstd-compressed JSON of one epoch's
+//! canonical blocks together with its checkpoint and checkpoint certificates.
+//! The manifest lists every segment with its block range, the merkle root of
+//! its block hashes and the SHA-256 of the file, so a node bootstrapping from
+//! an archive URL can check each segment before importing it.
+//!
+//! Archives can be served by anyone, so importing a segment only trusts what
+//! can be checked: blocks still go through the node's block validation, and
+//! a checkpoint is only kept with a certificate from 2f+1 of its epoch's
+//! validators.
+//!
+//! Exports are incremental: segments already in the manifest are kept and only
+//! newly finalized epochs are written. Archive before pruning, or segments of
+//! pruned epochs will hold block headers without their commit lists.
+
+use super::{CheckpointCertificate, MinerBlock, MinerCheckpoint};
+use crate::models::validator::get_validator_set_for_epoch_multi;
+use crate::DatastoreManager;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Archive format version written to the manifest
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Name of the manifest in an archive
+pub const ARCHIVE_MANIFEST_NAME: &str = "manifest.json";
+
+/// Largest decompressed segment accepted from an archive
+pub const MAX_SEGMENT_BYTES: usize = 64 * 1024 * 1024;
+
+/// One archived epoch
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ArchiveSegment {
+    pub epoch: u64,
+    /// File name relative to the archive root
+    pub file: String,
+    pub first_block_index: u64,
+    pub last_block_index: u64,
+    pub last_block_hash: String,
+    pub block_count: u64,
+    /// Merkle root of the epoch's canonical block hashes
+    pub merkle_root: String,
+    /// Compressed size in bytes
+    pub size: u64,
+    /// Hex SHA-256 of the segment file
+    pub sha256: String,
+}
+
+/// Description of an archive
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ArchiveManifest {
+    pub version: u32,
+    /// Unix timestamp of the last export
+    pub updated_at: i64,
+    /// Segments sorted by epoch
+    pub segments: Vec<ArchiveSegment>,
+}
+
+impl ArchiveManifest {
+    pub fn new() -> Self {
+        Self {
+            version: ARCHIVE_VERSION,
+            updated_at: chrono::Utc::now().timestamp(),
+            segments: Vec::new(),
+        }
+    }
+
+    /// Parse a manifest, rejecting unsupported versions
+    pub fn from_slice(data: &[u8]) -> Result<Self> {
+        let manifest: Self = serde_json::from_slice(data).context("Failed to parse archive manifest")?;
+        if manifest.version != ARCHIVE_VERSION {
+            anyhow::bail!("Unsupported archive version {}", manifest.version);
+        }
+        Ok(manifest)
+    }
+
+    /// Read the manifest of the archive in `dir`, if there is one
+    pub fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(ARCHIVE_MANIFEST_NAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(Self::from_slice(&std::fs::read(&path)?)?))
+    }
+
+    /// Latest archived epoch
+    pub fn last_epoch(&self) -> Option<u64> {
+        self.segments.last().map(|s| s.epoch)
+    }
+
+    /// Segments holding blocks at or above `index`
+    pub fn segments_from(&self, index: u64) -> impl Iterator<Item = &ArchiveSegment> {
+        self.segments.iter().filter(move |s| s.last_block_index >= index)
+    }
+}
+
+impl Default for ArchiveManifest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Contents of a segment file
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SegmentData {
+    pub epoch: u64,
+    /// Canonical blocks sorted by index
+    pub blocks: Vec<MinerBlock>,
+    #[serde(default)]
+    pub checkpoint: Option<MinerCheckpoint>,
+    #[serde(default)]
+    pub certificates: Vec<CheckpointCertificate>,
+}
+
+impl SegmentData {
+    /// Compress the segment, returning the file contents and their manifest entry
+    pub fn encode(&self) -> Result<(Vec<u8>, ArchiveSegment)> {
+        let (Some(first), Some(last)) = (self.blocks.first(), self.blocks.last()) else {
+            anyhow::bail!("Cannot archive epoch {} without blocks", self.epoch);
+        };
+        let bytes = zstd::encode_all(serde_json::to_vec(self)?.as_slice(), 0)?;
+        let segment = ArchiveSegment {
+            epoch: self.epoch,
+            file: segment_file_name(self.epoch),
+            first_block_index: first.index,
+            last_block_index: last.index,
+            last_block_hash: last.hash.clone(),
+            block_count: self.blocks.len() as u64,
+            merkle_root: merkle_root(&self.blocks),
+            size: bytes.len() as u64,
+            sha256: hex::encode(Sha256::digest(&bytes)),
+        };
+        Ok((bytes, segment))
+    }
+
+    /// Decompress a segment file and check it against its manifest entry:
+    /// the file hash, the block range, the links between blocks, the merkle
+    /// root, and the checkpoint if the segment carries one.
+    pub fn decode(bytes: &[u8], segment: &ArchiveSegment) -> Result<Self> {
+        if bytes.len() as u64 != segment.size || hex::encode(Sha256::digest(bytes)) != segment.sha256 {
+            anyhow::bail!("Segment {} does not match its checksum", segment.file);
+        }
+        let json = zstd::bulk::decompress(bytes, MAX_SEGMENT_BYTES).context("Failed to decompress segment")?;
+        let data: Self = serde_json::from_slice(&json).context("Failed to parse segment")?;
+
+        if data.epoch != segment.epoch || data.blocks.len() as u64 != segment.block_count {
+            anyhow::bail!("Segment {} does not match the manifest", segment.file);
+        }
+        for (i, block) in data.blocks.iter().enumerate() {
+            if block.index != segment.first_block_index + i as u64 || block.epoch != segment.epoch {
+                anyhow::bail!("Segment {} has an unexpected block at index {}", segment.file, block.index);
+            }
+            if i > 0 && block.previous_hash != data.blocks[i - 1].hash {
+                anyhow::bail!("Block {} in segment {} does not link to its parent", block.index, segment.file);
+            }
+        }
+        let last = data.blocks.last().map(|b| b.hash.as_str());
+        if last != Some(segment.last_block_hash.as_str()) || merkle_root(&data.blocks) != segment.merkle_root {
+            anyhow::bail!("Segment {} does not match the manifest", segment.file);
+        }
+        // Manual checkpoints may name any block of the epoch; consensus ones cover all of it
+        if let Some(checkpoint) = &data.checkpoint {
+            let checkpointed = data.blocks.iter().find(|b| b.index == checkpoint.last_block_index);
+            let matches = checkpointed.is_some_and(|b| b.hash == checkpoint.last_block_hash)
+                && (checkpoint.is_manual || checkpoint.merkle_root == segment.merkle_root);
+            if !matches {
+                anyhow::bail!("Segment {} does not match its checkpoint", segment.file);
+            }
+        }
+        Ok(data)
+    }
+}
+
+/// File name of an epoch's segment
+pub fn segment_file_name(epoch: u64) -> String {
+    format!("epoch-{:012}.json.zst", epoch)
+}
+
+fn merkle_root(blocks: &[MinerBlock]) -> String {
+    let hashes: Vec<String> = blocks.iter().map(|b| b.hash.clone()).collect();
+    modal_common::merkle::compute_merkle_root_owned(&hashes)
+}
+
+/// Write a segment for every epoch at least `keep_epochs` behind the chain
+/// tip's epoch that isn't archived in `dir` yet, then rewrite the manifest.
+/// Returns the manifest and the number of segments written.
+pub async fn export_archive_multi(
+    mgr: &DatastoreManager,
+    dir: &Path,
+    keep_epochs: u64,
+) -> Result<(ArchiveManifest, usize)> {
+    std::fs::create_dir_all(dir)?;
+    let mut manifest = ArchiveManifest::load(dir)?.unwrap_or_default();
+
+    let canonical = MinerBlock::find_all_canonical_multi(mgr).await?;
+    // The tip's epoch is still being mined, so at least one epoch is kept back
+    let last_final = canonical
+        .last()
+        .and_then(|tip| tip.epoch.checked_sub(keep_epochs.max(1)));
+
+    let mut epochs: BTreeMap<u64, Vec<MinerBlock>> = BTreeMap::new();
+    for block in canonical.into_iter().filter(|b| last_final.is_some_and(|last| b.epoch <= last)) {
+        if manifest.last_epoch().is_none_or(|archived| block.epoch > archived) {
+            epochs.entry(block.epoch).or_default().push(block);
+        }
+    }
+
+    let mut written = 0;
+    for (epoch, blocks) in epochs {
+        let data = SegmentData {
+            epoch,
+            blocks,
+            checkpoint: MinerCheckpoint::find_by_epoch_multi(mgr, epoch).await?,
+            certificates: CheckpointCertificate::find_by_epoch_multi(mgr, epoch).await?,
+        };
+        let (bytes, segment) = data.encode()?;
+        // Write then rename so an interrupted export never leaves a partial segment
+        let tmp = dir.join(format!("{}.tmp", segment.file));
+        std::fs::write(&tmp, &bytes)?;
+        std::fs::rename(&tmp, dir.join(&segment.file))?;
+        manifest.segments.push(segment);
+        written += 1;
+    }
+
+    manifest.updated_at = chrono::Utc::now().timestamp();
+    let tmp = dir.join(format!("{}.tmp", ARCHIVE_MANIFEST_NAME));
+    std::fs::write(&tmp, serde_json::to_vec_pretty(&manifest)?)?;
+    std::fs::rename(&tmp, dir.join(ARCHIVE_MANIFEST_NAME))?;
+    Ok((manifest, written))
+}
+
+/// Import a decoded segment whose new blocks the caller has validated. Its
+/// first block must follow the local chain tip (or be genesis on an empty
+/// chain); blocks the node already has are skipped. The segment's checkpoint
+/// is saved only once one of its certificates is signed by 2f+1 validators of
+/// the epoch's set. Returns the number of blocks saved.
+pub async fn import_segment_multi(mgr: &DatastoreManager, data: &SegmentData) -> Result<usize> {
+    let canonical = MinerBlock::find_all_canonical_multi(mgr).await?;
+    let mut saved = 0;
+    let mut tip = canonical.last().cloned();
+    for block in &data.blocks {
+        match &tip {
+            Some(tip) if block.index <= tip.index => {
+                let local = canonical.iter().find(|b| b.index == block.index);
+                if local.is_some_and(|b| b.hash != block.hash) {
+                    anyhow::bail!("Archived block {} conflicts with the local chain", block.index);
+                }
+                continue;
+            }
+            Some(tip) if block.index != tip.index + 1 || block.previous_hash != tip.hash => {
+                anyhow::bail!("Archived block {} does not follow the local tip {}", block.index, tip.index);
+            }
+            None if block.index != 0 => {
+                anyhow::bail!("Archived block {} does not follow an empty chain", block.index);
+            }
+            _ => {}
+        }
+        let mut block = block.clone();
+        block.is_canonical = true;
+        block.is_orphaned = false;
+        block.save_to_active(mgr).await?;
+        tip = Some(block);
+        saved += 1;
+    }
+
+    import_certificates(mgr, data).await?;
+    Ok(saved)
+}
+
+/// Save the certificates of a segment's checkpoint, keeping only signatures
+/// that check out, and the checkpoint itself if they certify it. Certificates
+/// for other checkpoints don't match the archived blocks and are dropped.
+async fn import_certificates(mgr: &DatastoreManager, data: &SegmentData) -> Result<()> {
+    let Some(checkpoint) = &data.checkpoint else {
+        return Ok(());
+    };
+    let digest = CheckpointCertificate::new(checkpoint.clone()).digest();
+    let mut certificates: Vec<CheckpointCertificate> =
+        data.certificates.iter().filter(|cert| cert.digest() == digest).cloned().collect();
+
+    let validators = match get_validator_set_for_epoch_multi(mgr, checkpoint.epoch).await {
+        Ok(set) => set.get_active_validators(),
+        Err(e) => {
+            log::debug!("Not importing the checkpoint for epoch {} without its validator set: {}", checkpoint.epoch, e);
+            CheckpointCertificate::retain_valid_signatures(&mut certificates).await;
+            for cert in &certificates {
+                cert.save(mgr).await?;
+            }
+            return Ok(());
+        }
+    };
+    for cert in &certificates {
+        CheckpointCertificate::merge_and_apply_multi(mgr, cert, &validators).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use modal_common::keypair::Keypair;
+
+    async fn mine_chain(mgr: &DatastoreManager, blocks: u64) {
+        let mut previous_hash = String::new();
+        for index in 0..blocks {
+            let block = MinerBlock::new_canonical(
+                format!("block_{}", index),
+                index,
+                index / 2,
+                1_700_000_000 + index as i64,
+                previous_hash.clone(),
+                String::new(),
+                index as u128,
+                1000,
+                "peer".to_string(),
+                index,
+            );
+            block.save_to_active(mgr).await.unwrap();
+            previous_hash = block.hash.clone();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_and_import_archive() {
+        let source = DatastoreManager::create_in_memory().unwrap();
+        mine_chain(&source, 7).await;
+        let dir = tempfile::tempdir().unwrap();
+
+        // Tip is in epoch 3, so epochs 0 and 1 are two or more behind it
+        let (manifest, written) = export_archive_multi(&source, dir.path(), 2).await.unwrap();
+        assert_eq!(written, 2);
+        assert_eq!(manifest.segments[1].first_block_index, 2);
+        assert_eq!(manifest.segments[1].last_block_hash, "block_3");
+
+        // Re-exporting only adds newly finalized epochs
+        mine_chain(&source, 9).await;
+        let (manifest, written) = export_archive_multi(&source, dir.path(), 2).await.unwrap();
+        assert_eq!(written, 1);
+        assert_eq!(manifest.last_epoch(), Some(2));
+        assert_eq!(ArchiveManifest::load(dir.path()).unwrap().unwrap(), manifest);
+
+        let target = DatastoreManager::create_in_memory().unwrap();
+        for segment in manifest.segments_from(0) {
+            let bytes = std::fs::read(dir.path().join(&segment.file)).unwrap();
+            let data = SegmentData::decode(&bytes, segment).unwrap();
+            assert_eq!(import_segment_multi(&target, &data).await.unwrap(), 2);
+        }
+        let imported = MinerBlock::find_all_canonical_multi(&target).await.unwrap();
+        assert_eq!(imported.len(), 6);
+        assert_eq!(imported.last().unwrap().hash, "block_5");
+    }
+
+    #[tokio::test]
+    async fn test_damaged_segment_rejected() {
+        let source = DatastoreManager::create_in_memory().unwrap();
+        mine_chain(&source, 6).await;
+        let blocks = MinerBlock::find_all_canonical_multi(&source).await.unwrap();
+        let data = SegmentData { epoch: 1, blocks: blocks[2..4].to_vec(), checkpoint: None, certificates: Vec::new() };
+        let (mut bytes, segment) = data.encode().unwrap();
+        assert_eq!(SegmentData::decode(&bytes, &segment).unwrap(), data);
+
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        assert!(SegmentData::decode(&bytes, &segment).is_err());
+
+        // A segment that doesn't follow the local tip isn't imported
+        let target = DatastoreManager::create_in_memory().unwrap();
+        assert!(import_segment_multi(&target, &data).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_archived_checkpoint_needs_a_certificate() {
+        let source = DatastoreManager::create_in_memory().unwrap();
+        mine_chain(&source, 2).await;
+        let blocks = MinerBlock::find_all_canonical_multi(&source).await.unwrap();
+        let checkpoint = MinerCheckpoint::new_consensus(
+            0, 0, 1, "block_1".to_string(), merkle_root(&blocks), 2, 5,
+        );
+        let keypairs: Vec<Keypair> = (0..4).map(|_| Keypair::generate().unwrap()).collect();
+        let validators: Vec<String> = keypairs.iter().map(|k| k.as_public_key_id()).collect();
+        let signed = |signers: usize| {
+            let mut cert = CheckpointCertificate::new(checkpoint.clone());
+            for (peer_id, keypair) in validators.iter().zip(&keypairs).take(signers) {
+                cert.sign(peer_id, keypair).unwrap();
+            }
+            SegmentData { epoch: 0, blocks: blocks.clone(), checkpoint: Some(checkpoint.clone()), certificates: vec![cert] }
+        };
+
+        // Two of four validators aren't 2f+1
+        let target = DatastoreManager::create_in_memory().unwrap();
+        target.set_static_validators(&validators).await.unwrap();
+        assert_eq!(import_segment_multi(&target, &signed(2)).await.unwrap(), 2);
+        assert!(MinerCheckpoint::find_by_epoch_multi(&target, 0).await.unwrap().is_none());
+
+        let target = DatastoreManager::create_in_memory().unwrap();
+        target.set_static_validators(&validators).await.unwrap();
+        import_segment_multi(&target, &signed(3)).await.unwrap();
+        assert!(MinerCheckpoint::find_by_epoch_multi(&target, 0).await.unwrap().is_some());
+    }
+}
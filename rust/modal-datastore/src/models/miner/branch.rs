@@ -0,0 +1,144 @@
+//! Fork branch tracking for heaviest-chain selection
+//!
+//! A branch is a run of non-canonical blocks that forks off the canonical
+//! chain. Each branch is recorded in MinerForks under its tip hash along with
+//! its cumulative actualized difficulty, so the observer can compare it
+//! against the canonical chain above the same fork point.
+
+use crate::{DatastoreManager, Store};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Key prefix for fork branches in MinerForks
+const BRANCH_PREFIX: &str = "/miner_branches/tip";
+
+/// A competing branch of the miner chain
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MinerBranch {
+    /// Hash of the branch's highest block
+    pub tip_hash: String,
+    pub tip_index: u64,
+    /// Index of the last canonical block the branch shares, if it doesn't fork at genesis
+    pub fork_index: Option<u64>,
+    /// Cumulative actualized difficulty of the blocks above the fork point
+    pub cumulative_difficulty: String,
+    /// Number of blocks above the fork point
+    pub block_count: u64,
+    /// Unix timestamp when the branch was last extended
+    pub updated_at: i64,
+}
+
+impl MinerBranch {
+    pub fn new(
+        tip_hash: String,
+        tip_index: u64,
+        fork_index: Option<u64>,
+        cumulative_difficulty: u128,
+        block_count: u64,
+    ) -> Self {
+        Self {
+            tip_hash,
+            tip_index,
+            fork_index,
+            cumulative_difficulty: cumulative_difficulty.to_string(),
+            block_count,
+            updated_at: chrono::Utc::now().timestamp(),
+        }
+    }
+    
+    /// Parse cumulative difficulty from string to u128
+    pub fn get_cumulative_difficulty_u128(&self) -> Result<u128> {
+        self.cumulative_difficulty
+            .parse::<u128>()
+            .context("Failed to parse cumulative difficulty as u128")
+    }
+    
+    /// Number of canonical blocks a reorg to this branch would replace, given the canonical tip
+    pub fn reorg_depth(&self, canonical_tip: u64) -> u64 {
+        match self.fork_index {
+            Some(fork_index) => canonical_tip.saturating_sub(fork_index),
+            None => canonical_tip + 1,
+        }
+    }
+    
+    /// Save the branch to MinerForks, replacing the record for the block it extends
+    pub async fn save_to_forks(&self, mgr: &DatastoreManager, extends: Option<&str>) -> Result<()> {
+        if let Some(previous_tip) = extends {
+            Self::delete_by_tip_multi(mgr, previous_tip).await?;
+        }
+        let key = format!("{}/{}", BRANCH_PREFIX, self.tip_hash);
+        mgr.miner_forks().put(&key, &serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+    
+    /// Find the branch ending at a given block
+    pub async fn find_by_tip_multi(mgr: &DatastoreManager, tip_hash: &str) -> Result<Option<Self>> {
+        let key = format!("{}/{}", BRANCH_PREFIX, tip_hash);
+        match mgr.miner_forks().get(&key)? {
+            Some(data) => Ok(Some(
+                serde_json::from_slice(&data).context("Failed to deserialize MinerBranch")?,
+            )),
+            None => Ok(None),
+        }
+    }
+    
+    /// Find all tracked branches, heaviest first
+    pub async fn find_all_multi(mgr: &DatastoreManager) -> Result<Vec<Self>> {
+        let mut branches = Vec::new();
+        for item in mgr.miner_forks().iterator(BRANCH_PREFIX) {
+            let (_, value) = item?;
+            let branch: MinerBranch = serde_json::from_slice(&value)
+                .context("Failed to deserialize MinerBranch")?;
+            branches.push(branch);
+        }
+        branches.sort_by_key(|b| std::cmp::Reverse(b.get_cumulative_difficulty_u128().unwrap_or(0)));
+        Ok(branches)
+    }
+    
+    /// Stop tracking the branch ending at a given block
+    pub async fn delete_by_tip_multi(mgr: &DatastoreManager, tip_hash: &str) -> Result<()> {
+        let key = format!("{}/{}", BRANCH_PREFIX, tip_hash);
+        mgr.miner_forks().delete(&key)?;
+        Ok(())
+    }
+    
+    /// Stop tracking branches that fork below `index`, e.g. once a checkpoint
+    /// makes them impossible to adopt
+    pub async fn prune_forking_before_multi(mgr: &DatastoreManager, index: u64) -> Result<usize> {
+        let mut pruned = 0;
+        for branch in Self::find_all_multi(mgr).await? {
+            if branch.fork_index.is_none_or(|fork_index| fork_index < index) {
+                Self::delete_by_tip_multi(mgr, &branch.tip_hash).await?;
+                pruned += 1;
+            }
+        }
+        Ok(pruned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_branch_tracking() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        
+        let branch = MinerBranch::new("a".to_string(), 5, Some(3), 2000, 2);
+        branch.save_to_forks(&mgr, None).await.unwrap();
+        assert_eq!(branch.reorg_depth(6), 3);
+        
+        // Extending the branch replaces the record for its old tip
+        let extended = MinerBranch::new("b".to_string(), 6, Some(3), 3000, 3);
+        extended.save_to_forks(&mgr, Some("a")).await.unwrap();
+        assert!(MinerBranch::find_by_tip_multi(&mgr, "a").await.unwrap().is_none());
+        assert_eq!(MinerBranch::find_by_tip_multi(&mgr, "b").await.unwrap(), Some(extended));
+        
+        MinerBranch::new("c".to_string(), 9, Some(8), 500, 1).save_to_forks(&mgr, None).await.unwrap();
+        let all = MinerBranch::find_all_multi(&mgr).await.unwrap();
+        assert_eq!(all.iter().map(|b| b.tip_hash.as_str()).collect::<Vec<_>>(), vec!["b", "c"]);
+        
+        assert_eq!(MinerBranch::prune_forking_before_multi(&mgr, 5).await.unwrap(), 1);
+        assert_eq!(MinerBranch::find_all_multi(&mgr).await.unwrap().len(), 1);
+    }
+}
@@ -6,6 +6,7 @@
 use crate::model::Model;
 use crate::{DatastoreManager, Store};
 use anyhow::{Context, Result};
+use modal_common::eras::EraSchedule;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -104,9 +105,9 @@ impl MinerCheckpoint {
     pub fn from_block_index(
         block_index: u64,
         block_hash: String,
-        blocks_per_epoch: u64,
+        era_schedule: &EraSchedule,
     ) -> Self {
-        let epoch = block_index / blocks_per_epoch;
+        let epoch = era_schedule.epoch_of(block_index);
         Self {
             epoch,
             validator_set_epoch: epoch + 2,
@@ -0,0 +1,267 @@
+//! Validator-signed checkpoint certificates
+//!
+//! In `Consensus` checkpoint mode each validator signs the checkpoint it
+//! derived from its canonical chain and gossips it. Signatures over the same
+//! checkpoint are merged into a certificate; once 2f+1 validators of the set
+//! have signed, the checkpoint is final and nodes save it to MinerCanon, where
+//! block acceptance and reorgs treat it as irreversible.
+
+use super::MinerCheckpoint;
+use crate::{DatastoreManager, Store};
+use anyhow::{Context, Result};
+use modal_common::batch_verify::{self, SignatureCheck};
+use modal_common::keypair::Keypair;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Key prefix for checkpoint certificates in MinerCanon
+const CHECKPOINT_CERT_PREFIX: &str = "/miner_checkpoint_certs/epoch";
+
+/// A validator's signature over a checkpoint
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CheckpointSignature {
+    pub peer_id: String,
+    pub signature: String,
+}
+
+/// A checkpoint together with the validator signatures collected for it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CheckpointCertificate {
+    pub checkpoint: MinerCheckpoint,
+    #[serde(default)]
+    pub signatures: Vec<CheckpointSignature>,
+}
+
+impl CheckpointCertificate {
+    pub fn new(checkpoint: MinerCheckpoint) -> Self {
+        Self {
+            checkpoint,
+            signatures: Vec::new(),
+        }
+    }
+
+    /// The message validators sign. Leaves out `created_at` and other local
+    /// fields so validators with the same canonical chain sign the same bytes.
+    pub fn signing_message(&self) -> String {
+        let c = &self.checkpoint;
+        format!(
+            "checkpoint:{}:{}:{}:{}:{}:{}",
+            c.epoch, c.validator_set_epoch, c.last_block_index, c.last_block_hash, c.merkle_root, c.block_count
+        )
+    }
+
+    /// Short digest of the signing message, used to keep conflicting
+    /// checkpoints for the same epoch apart
+    pub fn digest(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.signing_message().as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Add `keypair`'s signature as `peer_id`
+    pub fn sign(&mut self, peer_id: &str, keypair: &Keypair) -> Result<()> {
+        let signature = keypair.sign_string_as_base64_pad(&self.signing_message())?;
+        self.add_signature(CheckpointSignature {
+            peer_id: peer_id.to_string(),
+            signature,
+        });
+        Ok(())
+    }
+
+    /// Add a signature unless the peer already signed. Returns whether it was added.
+    pub fn add_signature(&mut self, signature: CheckpointSignature) -> bool {
+        if self.signatures.iter().any(|s| s.peer_id == signature.peer_id) {
+            return false;
+        }
+        self.signatures.push(signature);
+        true
+    }
+
+    /// Merge the signatures of another certificate for the same checkpoint.
+    /// Returns how many were added.
+    pub fn merge(&mut self, other: &CheckpointCertificate) -> usize {
+        if other.digest() != self.digest() {
+            return 0;
+        }
+        other
+            .signatures
+            .iter()
+            .filter(|s| self.add_signature((*s).clone()))
+            .count()
+    }
+
+    fn signature_check(&self, signature: &CheckpointSignature) -> SignatureCheck {
+        SignatureCheck::new(&signature.peer_id, self.signing_message(), &signature.signature)
+    }
+
+    /// Peers in `validators` with a valid signature on this checkpoint.
+    /// Their signatures are verified in one batch.
+    pub fn valid_signers(&self, validators: &[String]) -> Vec<String> {
+        let candidates: Vec<&CheckpointSignature> = self
+            .signatures
+            .iter()
+            .filter(|sig| validators.contains(&sig.peer_id))
+            .collect();
+        let checks: Vec<SignatureCheck> = candidates.iter().map(|sig| self.signature_check(sig)).collect();
+        let mut signers: Vec<String> = Vec::new();
+        for (sig, valid) in candidates.into_iter().zip(batch_verify::verify_batch(&checks)) {
+            if valid && !signers.contains(&sig.peer_id) {
+                signers.push(sig.peer_id.clone());
+            }
+        }
+        signers
+    }
+
+    /// How many of `validators` must sign: 2f+1
+    fn threshold(validators: &[String]) -> usize {
+        let f = (validators.len() - 1) / 3;
+        2 * f + 1
+    }
+
+    /// Whether 2f+1 validators of the set signed this checkpoint
+    pub fn is_certified(&self, validators: &[String]) -> bool {
+        if validators.is_empty() {
+            return false;
+        }
+        self.valid_signers(validators).len() >= Self::threshold(validators)
+    }
+
+    /// Drop the signatures that don't verify from each of `certs`, checking
+    /// every signature of every certificate together on the shared pool
+    pub async fn retain_valid_signatures(certs: &mut [Self]) {
+        let checks: Vec<SignatureCheck> = certs
+            .iter()
+            .flat_map(|cert| cert.signatures.iter().map(|sig| cert.signature_check(sig)))
+            .collect();
+        let mut valid = batch_verify::shared().verify(checks).await.into_iter();
+        for cert in certs {
+            cert.signatures.retain(|_| valid.next().unwrap_or(false));
+        }
+    }
+
+    fn key(epoch: u64, digest: &str) -> String {
+        format!("{}/{}/digest/{}", CHECKPOINT_CERT_PREFIX, epoch, digest)
+    }
+
+    /// Save the certificate to MinerCanon
+    pub async fn save(&self, mgr: &DatastoreManager) -> Result<()> {
+        let key = Self::key(self.checkpoint.epoch, &self.digest());
+        mgr.miner_canon().put(&key, &serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+
+    /// Find the certificate for a specific checkpoint
+    pub async fn find_by_digest_multi(mgr: &DatastoreManager, epoch: u64, digest: &str) -> Result<Option<Self>> {
+        match mgr.miner_canon().get(&Self::key(epoch, digest))? {
+            Some(data) => Ok(Some(
+                serde_json::from_slice(&data).context("Failed to deserialize CheckpointCertificate")?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Find every certificate collected for an epoch
+    pub async fn find_by_epoch_multi(mgr: &DatastoreManager, epoch: u64) -> Result<Vec<Self>> {
+        let prefix = format!("{}/{}/digest", CHECKPOINT_CERT_PREFIX, epoch);
+        let mut certs = Vec::new();
+        for item in mgr.miner_canon().iterator(&prefix) {
+            let (_, value) = item?;
+            certs.push(serde_json::from_slice(&value).context("Failed to deserialize CheckpointCertificate")?);
+        }
+        Ok(certs)
+    }
+
+    /// Merge `incoming` into the stored certificate for the same checkpoint,
+    /// save it, and save the checkpoint to MinerCanon once it's certified by
+    /// `validators`. Returns the merged certificate and whether it's certified.
+    pub async fn merge_and_apply_multi(
+        mgr: &DatastoreManager,
+        incoming: &CheckpointCertificate,
+        validators: &[String],
+    ) -> Result<(Self, bool)> {
+        let epoch = incoming.checkpoint.epoch;
+        let mut cert = match Self::find_by_digest_multi(mgr, epoch, &incoming.digest()).await? {
+            Some(mut existing) => {
+                existing.merge(incoming);
+                existing
+            }
+            None => incoming.clone(),
+        };
+        // Only keep signatures that check out so the stored certificate can't be padded
+        let signers = cert.valid_signers(validators);
+        cert.signatures.retain(|s| signers.contains(&s.peer_id));
+        cert.save(mgr).await?;
+
+        let certified = !validators.is_empty() && signers.len() >= Self::threshold(validators);
+        if certified {
+            let existing = MinerCheckpoint::find_by_epoch_multi(mgr, epoch).await?;
+            let already_final = existing
+                .map(|c| CheckpointCertificate::new(c).digest() == cert.digest())
+                .unwrap_or(false);
+            if !already_final {
+                cert.checkpoint.save_to_canon(mgr).await?;
+            }
+        }
+        Ok((cert, certified))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkpoint() -> MinerCheckpoint {
+        MinerCheckpoint::new_consensus(1, 3, 79, "hash_79".to_string(), "merkle_1".to_string(), 40, 12)
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_certified_by_quorum() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        let keypairs: Vec<Keypair> = (0..4).map(|_| Keypair::generate().unwrap()).collect();
+        let validators: Vec<String> = keypairs.iter().map(|k| k.as_public_key_id()).collect();
+
+        // n = 4 tolerates f = 1, so 3 signatures are needed
+        for (i, kp) in keypairs.iter().take(3).enumerate() {
+            let mut partial = CheckpointCertificate::new(checkpoint());
+            partial.sign(&validators[i], kp).unwrap();
+            let (cert, certified) = CheckpointCertificate::merge_and_apply_multi(&mgr, &partial, &validators).await.unwrap();
+            assert_eq!(cert.signatures.len(), i + 1);
+            assert_eq!(certified, i == 2);
+            assert_eq!(MinerCheckpoint::find_by_epoch_multi(&mgr, 1).await.unwrap().is_some(), i == 2);
+        }
+
+        // A signature from outside the set or over another checkpoint doesn't count
+        let outsider = Keypair::generate().unwrap();
+        let mut forged = CheckpointCertificate::new(checkpoint());
+        forged.sign(&outsider.as_public_key_id(), &outsider).unwrap();
+        forged.add_signature(CheckpointSignature {
+            peer_id: validators[3].clone(),
+            signature: keypairs[0].sign_string_as_base64_pad("something else").unwrap(),
+        });
+        assert!(!forged.is_certified(&validators));
+        assert!(forged.valid_signers(&validators).is_empty());
+
+        let mut other = checkpoint();
+        other.last_block_hash = "other_hash".to_string();
+        assert_ne!(CheckpointCertificate::new(other).digest(), forged.digest());
+    }
+
+    #[tokio::test]
+    async fn test_retain_valid_signatures() {
+        let keypairs: Vec<Keypair> = (0..2).map(|_| Keypair::generate().unwrap()).collect();
+        let mut cert = CheckpointCertificate::new(checkpoint());
+        cert.sign(&keypairs[0].as_public_key_id(), &keypairs[0]).unwrap();
+        cert.add_signature(CheckpointSignature {
+            peer_id: keypairs[1].as_public_key_id(),
+            signature: keypairs[1].sign_string_as_base64_pad("something else").unwrap(),
+        });
+        let mut certs = vec![cert.clone(), cert];
+        certs[1].signatures.reverse();
+
+        CheckpointCertificate::retain_valid_signatures(&mut certs).await;
+        for cert in &certs {
+            assert_eq!(cert.signatures.len(), 1);
+            assert_eq!(cert.signatures[0].peer_id, keypairs[0].as_public_key_id());
+        }
+    }
+}
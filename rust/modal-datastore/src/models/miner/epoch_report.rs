@@ -0,0 +1,258 @@
+//! Epoch reports
+//!
+//! At each epoch boundary the node records who earned what in the epoch
+//! that ended: blocks and uncles nominating each peer, which validator
+//! selection credits, and the DAG certificates each validator authored.
+//! Blocks record the peer they nominate rather than who mined them, so a
+//! block counts towards its nominee, which is its miner unless the miner
+//! nominated someone else. Certificates count towards the epoch if their
+//! timestamp falls between its first block and the next epoch's first block.
+
+use super::MinerBlock;
+use crate::models::{DAGCertificate, ValidatorSet};
+use crate::{DatastoreManager, Store};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Key prefix for epoch reports in MinerCanon
+const EPOCH_REPORT_PREFIX: &str = "/miner_epoch_reports/epoch";
+
+/// One peer's share of an epoch
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct PeerEpochStats {
+    pub peer_id: String,
+    /// Canonical blocks nominating the peer
+    pub blocks_mined: u64,
+    /// Uncles referenced by the epoch's blocks that nominate the peer
+    pub uncles: u64,
+    /// Nominations credited by validator selection: blocks plus uncles
+    pub nominations: u64,
+    /// Whether the peer was in the epoch's validator set
+    pub active_validator: bool,
+    /// DAG certificates the peer authored during the epoch
+    pub certificates: u64,
+    pub certificates_committed: u64,
+    pub anchors: u64,
+}
+
+/// Per-peer statistics of a finished epoch
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EpochReport {
+    pub epoch: u64,
+    pub first_block_index: u64,
+    pub last_block_index: u64,
+    /// Hash of the epoch's last canonical block when the report was made;
+    /// the report is stale if a reorg replaced it
+    pub last_block_hash: String,
+    pub block_count: u64,
+    pub uncle_count: u64,
+    pub certificate_count: u64,
+    /// Unix timestamps bounding the epoch
+    pub start_timestamp: i64,
+    pub end_timestamp: i64,
+    /// Peers by nominations, then blocks, then certificates, most first
+    pub peers: Vec<PeerEpochStats>,
+    pub generated_at: i64,
+}
+
+fn peer<'a>(peers: &'a mut BTreeMap<String, PeerEpochStats>, peer_id: &str) -> &'a mut PeerEpochStats {
+    peers.entry(peer_id.to_string()).or_insert_with(|| PeerEpochStats {
+        peer_id: peer_id.to_string(),
+        ..Default::default()
+    })
+}
+
+impl EpochReport {
+    /// Build the report of an epoch from its canonical blocks (sorted by
+    /// index), the timestamp the next epoch starts at, the certificates
+    /// stored and the validator set serving the epoch
+    pub fn from_parts(
+        epoch: u64,
+        blocks: &[MinerBlock],
+        end_timestamp: i64,
+        certificates: &[DAGCertificate],
+        validator_set: Option<&ValidatorSet>,
+    ) -> Result<Self> {
+        let (Some(first), Some(last)) = (blocks.first(), blocks.last()) else {
+            anyhow::bail!("Cannot report on epoch {} without blocks", epoch);
+        };
+        let mut peers: BTreeMap<String, PeerEpochStats> = BTreeMap::new();
+
+        let mut uncle_count = 0;
+        for block in blocks {
+            peer(&mut peers, &block.nominated_peer_id).blocks_mined += 1;
+            for uncle in &block.uncles {
+                peer(&mut peers, &uncle.nominated_peer_id).uncles += 1;
+                uncle_count += 1;
+            }
+        }
+        if let Some(set) = validator_set {
+            for validator in set.get_active_validators() {
+                peer(&mut peers, &validator).active_validator = true;
+            }
+        }
+
+        let start_timestamp = first.timestamp;
+        let in_epoch = |cert: &&DAGCertificate| {
+            let timestamp = cert.timestamp as i64;
+            timestamp >= start_timestamp && timestamp < end_timestamp
+        };
+        let mut certificate_count = 0;
+        for cert in certificates.iter().filter(in_epoch) {
+            let stats = peer(&mut peers, &cert.author);
+            stats.certificates += 1;
+            stats.certificates_committed += cert.committed as u64;
+            stats.anchors += cert.anchor as u64;
+            certificate_count += 1;
+        }
+
+        let mut peers: Vec<PeerEpochStats> = peers
+            .into_values()
+            .map(|mut stats| {
+                stats.nominations = stats.blocks_mined + stats.uncles;
+                stats
+            })
+            .collect();
+        peers.sort_by(|a, b| {
+            (b.nominations, b.blocks_mined, b.certificates)
+                .cmp(&(a.nominations, a.blocks_mined, a.certificates))
+                .then_with(|| a.peer_id.cmp(&b.peer_id))
+        });
+
+        Ok(Self {
+            epoch,
+            first_block_index: first.index,
+            last_block_index: last.index,
+            last_block_hash: last.hash.clone(),
+            block_count: blocks.len() as u64,
+            uncle_count,
+            certificate_count,
+            start_timestamp,
+            end_timestamp,
+            peers,
+            generated_at: chrono::Utc::now().timestamp(),
+        })
+    }
+
+    /// Generate the report of `epoch` from the datastore. The epoch must
+    /// have ended, i.e. the next one has a canonical block.
+    pub async fn generate_multi(mgr: &DatastoreManager, epoch: u64, current_epoch: u64) -> Result<Self> {
+        if epoch >= current_epoch {
+            anyhow::bail!("Epoch {} hasn't ended yet (current epoch is {})", epoch, current_epoch);
+        }
+        let blocks = MinerBlock::find_canonical_by_epoch_multi(mgr, epoch, current_epoch).await?;
+        let next = MinerBlock::find_canonical_by_epoch_multi(mgr, epoch + 1, current_epoch).await?;
+        let end_timestamp = match next.first() {
+            Some(block) => block.timestamp,
+            None => blocks.last().map(|b| b.timestamp + 1).unwrap_or_default(),
+        };
+        // Every digest starts with the empty prefix
+        let certificates = DAGCertificate::find_by_digest_prefix_multi(mgr, "").await?;
+        let validator_set = ValidatorSet::find_for_mining_epoch_multi(mgr, epoch).await?;
+        Self::from_parts(epoch, &blocks, end_timestamp, &certificates, validator_set.as_ref())
+    }
+
+    pub async fn save(&self, mgr: &DatastoreManager) -> Result<()> {
+        let key = format!("{}/{}", EPOCH_REPORT_PREFIX, self.epoch);
+        mgr.miner_canon().put(&key, &serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+
+    pub async fn find_by_epoch_multi(mgr: &DatastoreManager, epoch: u64) -> Result<Option<Self>> {
+        match mgr.miner_canon().get(&format!("{}/{}", EPOCH_REPORT_PREFIX, epoch))? {
+            Some(data) => Ok(Some(serde_json::from_slice(&data).context("Failed to deserialize EpochReport")?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use modal_common::uncles::UncleRef;
+
+    fn block(index: u64, timestamp: i64, nominee: &str) -> MinerBlock {
+        MinerBlock::new_canonical(
+            format!("hash{}", index),
+            index,
+            0,
+            timestamp,
+            String::new(),
+            String::new(),
+            0,
+            1000,
+            nominee.to_string(),
+            index,
+        )
+    }
+
+    fn cert(author: &str, round: u64, timestamp: u64, committed: bool) -> DAGCertificate {
+        DAGCertificate {
+            digest: format!("{}-{}", author, round),
+            author: author.to_string(),
+            round,
+            header: String::new(),
+            aggregated_signature: String::new(),
+            signers: vec![],
+            batch_digests: vec![],
+            parents: vec![],
+            timestamp,
+            committed,
+            committed_at_round: None,
+            anchor: false,
+            created_at: timestamp,
+        }
+    }
+
+    #[test]
+    fn test_epoch_report() {
+        let mut blocks = vec![block(0, 100, "alice"), block(1, 160, "alice"), block(2, 220, "bob")];
+        blocks[2].uncles.push(UncleRef {
+            hash: "orphan".to_string(),
+            index: 1,
+            nominated_peer_id: "carol".to_string(),
+        });
+        let certificates = vec![
+            cert("alice", 1, 90, true),  // before the epoch
+            cert("alice", 2, 150, true),
+            cert("dave", 2, 150, false),
+            cert("dave", 3, 280, true),  // in the next epoch
+        ];
+        let set = ValidatorSet::new(0, 1, vec!["dave".to_string(), "erin".to_string()], vec![], vec![]);
+
+        let report = EpochReport::from_parts(0, &blocks, 280, &certificates, Some(&set)).unwrap();
+        assert_eq!(report.block_count, 3);
+        assert_eq!(report.uncle_count, 1);
+        assert_eq!(report.certificate_count, 2);
+        assert_eq!(report.last_block_hash, "hash2");
+
+        let peers: Vec<&str> = report.peers.iter().map(|p| p.peer_id.as_str()).collect();
+        assert_eq!(peers, vec!["alice", "bob", "carol", "dave", "erin"]);
+        assert_eq!(report.peers[0].nominations, 2);
+        assert_eq!(report.peers[0].certificates_committed, 1);
+        assert_eq!(report.peers[2].uncles, 1);
+        assert!(report.peers[3].active_validator);
+        assert_eq!(report.peers[3].certificates, 1);
+        // In the validator set but silent
+        assert!(report.peers[4].active_validator);
+        assert_eq!(report.peers[4].certificates, 0);
+    }
+
+    #[tokio::test]
+    async fn test_generate_and_save() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        let mut second = block(1, 200, "bob");
+        second.epoch = 1;
+        for block in [block(0, 100, "alice"), second] {
+            block.save_to_active(&mgr).await.unwrap();
+        }
+        assert!(EpochReport::generate_multi(&mgr, 1, 1).await.is_err());
+
+        let report = EpochReport::generate_multi(&mgr, 0, 1).await.unwrap();
+        assert_eq!(report.end_timestamp, 200);
+        report.save(&mgr).await.unwrap();
+        assert_eq!(EpochReport::find_by_epoch_multi(&mgr, 0).await.unwrap(), Some(report));
+        assert_eq!(EpochReport::find_by_epoch_multi(&mgr, 1).await.unwrap(), None);
+    }
+}
@@ -1,12 +1,21 @@
+use crate::migrations::Migration;
 use crate::model::Model;
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use modal_common::block_commits::{CommitDigest, CommitLimits};
+use modal_common::slashing::EquivocationEvidence;
+use modal_common::wire;
+use modal_common::uncles::UncleRef;
 use std::collections::HashMap;
 
+/// Largest block accepted from a peer, in bytes of JSON
+pub const MAX_BLOCK_JSON_BYTES: usize = 512 * 1024;
+
 /// Represents a mining block stored in the datastore
 /// This includes both canonical chain blocks and orphaned blocks
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct MinerBlock {
     // Block header fields
     pub hash: String,
@@ -22,6 +31,14 @@ pub struct MinerBlock {
     // Block data fields
     pub nominated_peer_id: String, // Peer ID nominated by the miner
     pub miner_number: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub uncles: Vec<UncleRef>, // Recent orphans referenced by this block
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commits: Vec<CommitDigest>, // Contract commits included by the miner
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub commits_root: String, // Merkle root of `commits`, part of the mined header
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub evidence: Vec<EquivocationEvidence>, // Validator equivocations included by the miner
     
     // Chain status
     pub is_orphaned: bool,
@@ -66,6 +83,10 @@ impl MinerBlock {
             actualized_difficulty: actualized_difficulty.to_string(),
             nominated_peer_id,
             miner_number,
+            uncles: Vec::new(),
+            commits: Vec::new(),
+            commits_root: String::new(),
+            evidence: Vec::new(),
             is_orphaned: false,
             is_canonical: true,
             seen_at: Some(chrono::Utc::now().timestamp()),
@@ -108,6 +129,10 @@ impl MinerBlock {
             actualized_difficulty: actualized_difficulty.to_string(),
             nominated_peer_id,
             miner_number,
+            uncles: Vec::new(),
+            commits: Vec::new(),
+            commits_root: String::new(),
+            evidence: Vec::new(),
             is_orphaned: true,
             is_canonical: false,
             seen_at: Some(chrono::Utc::now().timestamp()),
@@ -118,6 +143,25 @@ impl MinerBlock {
         }
     }
     
+    /// Set the uncles referenced by this block
+    pub fn with_uncles(mut self, uncles: Vec<UncleRef>) -> Self {
+        self.uncles = uncles;
+        self
+    }
+    
+    /// Set the contract commits included in this block, along with their root
+    pub fn with_commits(mut self, commits: Vec<CommitDigest>) -> Self {
+        self.commits_root = modal_common::block_commits::commits_root(&commits);
+        self.commits = commits;
+        self
+    }
+    
+    /// Set the equivocation evidence included in this block
+    pub fn with_evidence(mut self, evidence: Vec<EquivocationEvidence>) -> Self {
+        self.evidence = evidence;
+        self
+    }
+    
     /// Mark this block as orphaned
     pub fn mark_as_orphaned(&mut self, reason: String, competing_hash: Option<String>) {
         self.is_orphaned = true;
@@ -127,6 +171,47 @@ impl MinerBlock {
         self.competing_hash = competing_hash;
     }
     
+    /// Parse a block received from a peer and check its fields
+    pub fn from_json_slice(data: &[u8]) -> Result<Self> {
+        let block: Self = wire::from_json_slice("miner block", data, MAX_BLOCK_JSON_BYTES)?;
+        block.validate_fields()?;
+        Ok(block)
+    }
+
+    /// Check the fields of a block received from a peer are well-formed:
+    /// hex hashes, numeric nonce and difficulty, bounded identifiers and
+    /// bounded uncle, commit and evidence lists. Says nothing about the
+    /// block's validity on the chain.
+    pub fn validate_fields(&self) -> Result<()> {
+        wire::check_hex("block hash", &self.hash)?;
+        // Genesis points at "0"
+        wire::check_hex("previous hash", &self.previous_hash)?;
+        if !self.data_hash.is_empty() {
+            wire::check_hex("data hash", &self.data_hash)?;
+        }
+        if !self.commits_root.is_empty() {
+            wire::check_hex("commits root", &self.commits_root)?;
+        }
+        self.get_nonce_u128()?;
+        self.get_target_difficulty_u128()?;
+        wire::check_len("nominated peer ID", self.nominated_peer_id.len(), wire::MAX_ID_LEN)?;
+
+        wire::check_len("uncle list", self.uncles.len(), modal_common::uncles::MAX_UNCLES_PER_BLOCK)?;
+        for uncle in &self.uncles {
+            wire::check_hex("uncle hash", &uncle.hash)?;
+            wire::check_len("uncle nominated peer ID", uncle.nominated_peer_id.len(), wire::MAX_ID_LEN)?;
+        }
+        wire::check_len("commit list", self.commits.len(), CommitLimits::default().max_commits)?;
+        for commit in &self.commits {
+            wire::check_len("commit contract ID", commit.contract_id.len(), wire::MAX_ID_LEN)?;
+            if !wire::is_hex_digest(&commit.commit_id) {
+                anyhow::bail!("Commit ID is not a hex SHA-256 digest");
+            }
+        }
+        modal_common::slashing::validate_evidence(&self.evidence).map_err(|e| anyhow::anyhow!("Invalid evidence: {}", e))?;
+        Ok(())
+    }
+
     /// Parse nonce from string to u128
     pub fn get_nonce_u128(&self) -> Result<u128> {
         self.nonce
@@ -163,11 +248,27 @@ impl MinerBlock {
     }
 }
 
+/// Blocks stored before `actualized_difficulty` existed get it from their
+/// hash, or their target difficulty if the hash can't be read
+fn fill_actualized_difficulty(mut obj: serde_json::Value) -> Result<Option<serde_json::Value>> {
+    if obj.get("actualized_difficulty").is_none() {
+        let hash = obj.get("hash").and_then(|v| v.as_str()).unwrap_or_default();
+        let difficulty = match modal_common::hash_tax::hash_to_actualized_difficulty(hash) {
+            Ok(difficulty) => difficulty.to_string(),
+            Err(_) => obj.get("target_difficulty").and_then(|v| v.as_str()).unwrap_or("0").to_string(),
+        };
+        obj["actualized_difficulty"] = serde_json::Value::String(difficulty);
+    }
+    Ok(Some(obj))
+}
+
 #[async_trait]
 impl Model for MinerBlock {
     // Store blocks by hash as primary key
     const ID_PATH: &'static str = "/miner_blocks/hash/${hash}";
     
+    const CACHED: bool = true;
+    
     const FIELDS: &'static [&'static str] = &[
         "hash",
         "index",
@@ -180,6 +281,10 @@ impl Model for MinerBlock {
         "actualized_difficulty",
         "nominated_peer_id",
         "miner_number",
+        "uncles",
+        "commits",
+        "commits_root",
+        "evidence",
         "is_orphaned",
         "is_canonical",
         "seen_at",
@@ -193,6 +298,14 @@ impl Model for MinerBlock {
         ("is_orphaned", serde_json::json!(false)),
         ("is_canonical", serde_json::json!(true)),
     ];
+
+    const INDEXES: &'static [&'static str] = &["index", "epoch", "nominated_peer_id", "is_canonical", "is_orphaned"];
+
+    const MIGRATIONS: &'static [Migration] = &[Migration {
+        version: 1,
+        description: "fill in actualized_difficulty",
+        apply: fill_actualized_difficulty,
+    }];
     
     fn set_field(&mut self, field: &str, value: serde_json::Value) {
         match field {
@@ -251,6 +364,26 @@ impl Model for MinerBlock {
                     self.miner_number = v;
                 }
             }
+            "uncles" => {
+                if let Ok(v) = serde_json::from_value(value) {
+                    self.uncles = v;
+                }
+            }
+            "commits" => {
+                if let Ok(v) = serde_json::from_value(value) {
+                    self.commits = v;
+                }
+            }
+            "commits_root" => {
+                if let Some(v) = value.as_str() {
+                    self.commits_root = v.to_string();
+                }
+            }
+            "evidence" => {
+                if let Ok(v) = serde_json::from_value(value) {
+                    self.evidence = v;
+                }
+            }
             "is_orphaned" => {
                 if let Some(v) = value.as_bool() {
                     self.is_orphaned = v;
@@ -319,6 +452,10 @@ impl MinerBlock {
             actualized_difficulty: actualized_difficulty.to_string(),
             nominated_peer_id,
             miner_number,
+            uncles: Vec::new(),
+            commits: Vec::new(),
+            commits_root: String::new(),
+            evidence: Vec::new(),
             is_orphaned: false,
             is_canonical: false, // Pending blocks are not canonical until verified
             seen_at: Some(chrono::Utc::now().timestamp()),
@@ -452,4 +589,39 @@ mod tests {
         assert_eq!(block.get_nonce_u128().unwrap(), 999999999999);
         assert_eq!(block.get_target_difficulty_u128().unwrap(), 777777777777);
     }
+
+    #[test]
+    fn test_from_json_slice_rejects_malformed_blocks() {
+        let block = MinerBlock::new_canonical(
+            "ab".repeat(32),
+            1,
+            0,
+            1234567890,
+            "0".to_string(),
+            "cd".repeat(32),
+            12345,
+            1000,
+            "peer_id_123".to_string(),
+            42,
+        );
+        let json = serde_json::to_vec(&block).unwrap();
+        assert_eq!(MinerBlock::from_json_slice(&json).unwrap(), block);
+
+        let malformed = |f: fn(&mut MinerBlock)| {
+            let mut block = block.clone();
+            f(&mut block);
+            MinerBlock::from_json_slice(&serde_json::to_vec(&block).unwrap()).is_err()
+        };
+        assert!(malformed(|b| b.hash = "é".repeat(8)));
+        assert!(malformed(|b| b.hash = String::new()));
+        assert!(malformed(|b| b.nonce = "-1".to_string()));
+        assert!(malformed(|b| b.nominated_peer_id = "p".repeat(1000)));
+        assert!(malformed(|b| {
+            b.uncles = (0..3)
+                .map(|i| UncleRef { hash: "ef".repeat(32), index: i, nominated_peer_id: String::new() })
+                .collect()
+        }));
+        assert!(MinerBlock::from_json_slice(&json[..json.len() / 2]).is_err());
+        assert!(MinerBlock::from_json_slice(&vec![b' '; MAX_BLOCK_JSON_BYTES + 1]).is_err());
+    }
 }
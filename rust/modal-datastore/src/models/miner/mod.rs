@@ -3,8 +3,20 @@ pub mod miner_block_height;
 pub mod integrity;
 pub mod multi_store;
 pub mod checkpoint;
+pub mod checkpoint_cert;
+pub mod branch;
+pub mod orphan_pool;
+pub mod pruning;
+pub mod archive;
+pub mod epoch_report;
 
 pub use miner_block::MinerBlock;
 pub use miner_block_height::MinerBlockHeight;
 pub use checkpoint::MinerCheckpoint;
+pub use checkpoint_cert::{CheckpointCertificate, CheckpointSignature};
+pub use branch::MinerBranch;
+pub use orphan_pool::OrphanPoolEntry;
+pub use pruning::{EpochSummary, PruneStatus};
+pub use archive::{ArchiveManifest, ArchiveSegment, SegmentData};
+pub use epoch_report::{EpochReport, PeerEpochStats};
 
@@ -9,7 +9,7 @@
 //! - `find_all_canonical`: Merge MinerActive + MinerCanon
 //! - `find_all_orphaned`: Merge MinerActive + MinerForks
 
-use crate::{DatastoreManager, Store};
+use crate::{DatastoreManager, Model, Store};
 use crate::models::miner::MinerBlock;
 use anyhow::{Context, Result};
 
@@ -31,29 +31,35 @@ impl MinerBlock {
         let key = format!("{}/{}", MINER_BLOCK_PREFIX, hash);
         
         // Try MinerActive first (hot path for recent blocks)
-        if let Some(data) = mgr.miner_active().get(&key)? {
-            let block: MinerBlock = serde_json::from_slice(&data)
-                .context("Failed to deserialize MinerBlock from MinerActive")?;
+        if let Some(block) = Self::load_from_store(mgr.miner_active(), &key)
+            .context("Failed to deserialize MinerBlock from MinerActive")? {
             return Ok(Some(block));
         }
         
         // Check MinerCanon for older canonical blocks
-        if let Some(data) = mgr.miner_canon().get(&key)? {
-            let block: MinerBlock = serde_json::from_slice(&data)
-                .context("Failed to deserialize MinerBlock from MinerCanon")?;
+        if let Some(block) = Self::load_from_store(mgr.miner_canon(), &key)
+            .context("Failed to deserialize MinerBlock from MinerCanon")? {
             return Ok(Some(block));
         }
         
         // Check MinerForks for orphaned blocks
-        if let Some(data) = mgr.miner_forks().get(&key)? {
-            let block: MinerBlock = serde_json::from_slice(&data)
-                .context("Failed to deserialize MinerBlock from MinerForks")?;
+        if let Some(block) = Self::load_from_store(mgr.miner_forks(), &key)
+            .context("Failed to deserialize MinerBlock from MinerForks")? {
             return Ok(Some(block));
         }
         
         Ok(None)
     }
     
+    /// Find the blocks in a store whose indexed `field` equals `value`
+    async fn find_indexed<S: Store + Send + Sync>(
+        store: &S,
+        field: &str,
+        value: serde_json::Value,
+    ) -> Result<Vec<Self>> {
+        Self::find_by_index_from_store(store, field, &value).await
+    }
+    
     /// Find the canonical block at a specific index, routing based on epoch
     /// 
     /// - Recent epochs (within promotion_delay): Query MinerActive
@@ -68,27 +74,15 @@ impl MinerBlock {
         // Check if block is old enough to be in MinerCanon
         if mgr.should_promote(block_epoch, current_epoch) {
             // Check MinerCanon first for finalized blocks
-            for item in mgr.miner_canon().iterator(MINER_BLOCK_PREFIX) {
-                let (_, value) = item?;
-                let block: MinerBlock = serde_json::from_slice(&value)
-                    .context("Failed to deserialize MinerBlock")?;
-                if block.index == index && block.is_canonical {
-                    return Ok(Some(block));
-                }
-            }
-        }
-        
-        // Fall back to MinerActive (may still have the block during overlap period)
-        for item in mgr.miner_active().iterator(MINER_BLOCK_PREFIX) {
-            let (_, value) = item?;
-            let block: MinerBlock = serde_json::from_slice(&value)
-                .context("Failed to deserialize MinerBlock")?;
-            if block.index == index && block.is_canonical {
+            let blocks = Self::find_indexed(mgr.miner_canon(), "index", index.into()).await?;
+            if let Some(block) = blocks.into_iter().find(|b| b.is_canonical) {
                 return Ok(Some(block));
             }
         }
         
-        Ok(None)
+        // Fall back to MinerActive (may still have the block during overlap period)
+        let blocks = Self::find_indexed(mgr.miner_active(), "index", index.into()).await?;
+        Ok(blocks.into_iter().find(|b| b.is_canonical))
     }
     
     /// Find canonical block by index (simple version - always checks both stores)
@@ -97,26 +91,14 @@ impl MinerBlock {
         index: u64,
     ) -> Result<Option<Self>> {
         // Check MinerCanon first
-        for item in mgr.miner_canon().iterator(MINER_BLOCK_PREFIX) {
-            let (_, value) = item?;
-            let block: MinerBlock = serde_json::from_slice(&value)
-                .context("Failed to deserialize MinerBlock")?;
-            if block.index == index && block.is_canonical {
-                return Ok(Some(block));
-            }
+        let blocks = Self::find_indexed(mgr.miner_canon(), "index", index.into()).await?;
+        if let Some(block) = blocks.into_iter().find(|b| b.is_canonical) {
+            return Ok(Some(block));
         }
         
         // Fall back to MinerActive
-        for item in mgr.miner_active().iterator(MINER_BLOCK_PREFIX) {
-            let (_, value) = item?;
-            let block: MinerBlock = serde_json::from_slice(&value)
-                .context("Failed to deserialize MinerBlock")?;
-            if block.index == index && block.is_canonical {
-                return Ok(Some(block));
-            }
-        }
-        
-        Ok(None)
+        let blocks = Self::find_indexed(mgr.miner_active(), "index", index.into()).await?;
+        Ok(blocks.into_iter().find(|b| b.is_canonical))
     }
     
     /// Find all canonical blocks, merging MinerActive and MinerCanon
@@ -127,22 +109,14 @@ impl MinerBlock {
         let mut seen_hashes = std::collections::HashSet::new();
         
         // Get from MinerCanon (finalized blocks)
-        for item in mgr.miner_canon().iterator(MINER_BLOCK_PREFIX) {
-            let (_, value) = item?;
-            let block: MinerBlock = serde_json::from_slice(&value)
-                .context("Failed to deserialize MinerBlock from MinerCanon")?;
-            if block.is_canonical {
-                seen_hashes.insert(block.hash.clone());
-                blocks.push(block);
-            }
+        for block in Self::find_indexed(mgr.miner_canon(), "is_canonical", true.into()).await? {
+            seen_hashes.insert(block.hash.clone());
+            blocks.push(block);
         }
         
         // Get from MinerActive (recent blocks, avoiding duplicates)
-        for item in mgr.miner_active().iterator(MINER_BLOCK_PREFIX) {
-            let (_, value) = item?;
-            let block: MinerBlock = serde_json::from_slice(&value)
-                .context("Failed to deserialize MinerBlock from MinerActive")?;
-            if block.is_canonical && !seen_hashes.contains(&block.hash) {
+        for block in Self::find_indexed(mgr.miner_active(), "is_canonical", true.into()).await? {
+            if !seen_hashes.contains(&block.hash) {
                 blocks.push(block);
             }
         }
@@ -159,22 +133,14 @@ impl MinerBlock {
         let mut seen_hashes = std::collections::HashSet::new();
         
         // Get from MinerForks (archived orphans)
-        for item in mgr.miner_forks().iterator(MINER_BLOCK_PREFIX) {
-            let (_, value) = item?;
-            let block: MinerBlock = serde_json::from_slice(&value)
-                .context("Failed to deserialize MinerBlock from MinerForks")?;
-            if block.is_orphaned {
-                seen_hashes.insert(block.hash.clone());
-                blocks.push(block);
-            }
+        for block in Self::find_indexed(mgr.miner_forks(), "is_orphaned", true.into()).await? {
+            seen_hashes.insert(block.hash.clone());
+            blocks.push(block);
         }
         
         // Get from MinerActive (recent orphans, avoiding duplicates)
-        for item in mgr.miner_active().iterator(MINER_BLOCK_PREFIX) {
-            let (_, value) = item?;
-            let block: MinerBlock = serde_json::from_slice(&value)
-                .context("Failed to deserialize MinerBlock from MinerActive")?;
-            if block.is_orphaned && !seen_hashes.contains(&block.hash) {
+        for block in Self::find_indexed(mgr.miner_active(), "is_orphaned", true.into()).await? {
+            if !seen_hashes.contains(&block.hash) {
                 blocks.push(block);
             }
         }
@@ -233,32 +199,71 @@ impl MinerBlock {
         let mut seen_hashes = std::collections::HashSet::new();
         
         // Check all three stores
-        for item in mgr.miner_canon().iterator(MINER_BLOCK_PREFIX) {
-            let (_, value) = item?;
-            let block: MinerBlock = serde_json::from_slice(&value)?;
-            if block.index == index {
-                seen_hashes.insert(block.hash.clone());
-                blocks.push(block);
+        for store_blocks in [
+            Self::find_indexed(mgr.miner_canon(), "index", index.into()).await?,
+            Self::find_indexed(mgr.miner_forks(), "index", index.into()).await?,
+            Self::find_indexed(mgr.miner_active(), "index", index.into()).await?,
+        ] {
+            for block in store_blocks {
+                if seen_hashes.insert(block.hash.clone()) {
+                    blocks.push(block);
+                }
             }
         }
         
-        for item in mgr.miner_forks().iterator(MINER_BLOCK_PREFIX) {
-            let (_, value) = item?;
-            let block: MinerBlock = serde_json::from_slice(&value)?;
-            if block.index == index && !seen_hashes.contains(&block.hash) {
-                seen_hashes.insert(block.hash.clone());
-                blocks.push(block);
+        Ok(blocks)
+    }
+    
+    /// Find blocks (canonical, orphaned, pending) whose hash starts with `prefix`
+    pub async fn find_by_hash_prefix_multi(
+        mgr: &DatastoreManager,
+        prefix: &str,
+    ) -> Result<Vec<Self>> {
+        let key_prefix = format!("{}/{}", MINER_BLOCK_PREFIX, prefix);
+        let mut blocks = Vec::new();
+        let mut seen_hashes = std::collections::HashSet::new();
+        
+        // Check all three stores
+        for items in [
+            mgr.miner_canon().prefix_iterator(&key_prefix).collect::<Vec<_>>(),
+            mgr.miner_forks().prefix_iterator(&key_prefix).collect(),
+            mgr.miner_active().prefix_iterator(&key_prefix).collect(),
+        ] {
+            for item in items {
+                let (_, value) = item?;
+                let block: MinerBlock = serde_json::from_slice(&value)
+                    .context("Failed to deserialize MinerBlock")?;
+                if seen_hashes.insert(block.hash.clone()) {
+                    blocks.push(block);
+                }
             }
         }
         
-        for item in mgr.miner_active().iterator(MINER_BLOCK_PREFIX) {
-            let (_, value) = item?;
-            let block: MinerBlock = serde_json::from_slice(&value)?;
-            if block.index == index && !seen_hashes.contains(&block.hash) {
-                blocks.push(block);
+        blocks.sort_by_key(|b| b.index);
+        Ok(blocks)
+    }
+    
+    /// Find the blocks at heights with more than one block, i.e. where
+    /// orphaned blocks competed with another block
+    pub async fn find_forks_multi(
+        mgr: &DatastoreManager,
+    ) -> Result<Vec<Self>> {
+        let mut heights: Vec<u64> = Self::find_all_orphaned_multi(mgr)
+            .await?
+            .iter()
+            .map(|b| b.index)
+            .collect();
+        heights.dedup();
+        
+        let mut blocks = Vec::new();
+        for index in heights {
+            let mut at_height = Self::find_by_index_multi(mgr, index).await?;
+            if at_height.len() > 1 {
+                // Canonical block first, then by hash
+                at_height.sort_by(|a, b| b.is_canonical.cmp(&a.is_canonical).then_with(|| a.hash.cmp(&b.hash)));
+                blocks.extend(at_height);
             }
         }
-        
         Ok(blocks)
     }
     
@@ -273,10 +278,8 @@ impl MinerBlock {
         
         // If epoch is old enough, check MinerCanon first
         if mgr.should_promote(epoch, current_epoch) {
-            for item in mgr.miner_canon().iterator(MINER_BLOCK_PREFIX) {
-                let (_, value) = item?;
-                let block: MinerBlock = serde_json::from_slice(&value)?;
-                if block.epoch == epoch && block.is_canonical {
+            for block in Self::find_indexed(mgr.miner_canon(), "epoch", epoch.into()).await? {
+                if block.is_canonical {
                     seen_hashes.insert(block.hash.clone());
                     blocks.push(block);
                 }
@@ -284,10 +287,33 @@ impl MinerBlock {
         }
         
         // Also check MinerActive (may have blocks during overlap period)
-        for item in mgr.miner_active().iterator(MINER_BLOCK_PREFIX) {
-            let (_, value) = item?;
-            let block: MinerBlock = serde_json::from_slice(&value)?;
-            if block.epoch == epoch && block.is_canonical && !seen_hashes.contains(&block.hash) {
+        for block in Self::find_indexed(mgr.miner_active(), "epoch", epoch.into()).await? {
+            if block.is_canonical && !seen_hashes.contains(&block.hash) {
+                blocks.push(block);
+            }
+        }
+        
+        blocks.sort_by_key(|b| b.index);
+        Ok(blocks)
+    }
+    
+    /// Find canonical blocks nominating a peer, merging MinerActive and MinerCanon
+    pub async fn find_canonical_by_peer_multi(
+        mgr: &DatastoreManager,
+        peer_id: &str,
+    ) -> Result<Vec<Self>> {
+        let mut blocks = Vec::new();
+        let mut seen_hashes = std::collections::HashSet::new();
+        
+        for block in Self::find_indexed(mgr.miner_canon(), "nominated_peer_id", peer_id.into()).await? {
+            if block.is_canonical {
+                seen_hashes.insert(block.hash.clone());
+                blocks.push(block);
+            }
+        }
+        
+        for block in Self::find_indexed(mgr.miner_active(), "nominated_peer_id", peer_id.into()).await? {
+            if block.is_canonical && !seen_hashes.contains(&block.hash) {
                 blocks.push(block);
             }
         }
@@ -296,15 +322,74 @@ impl MinerBlock {
         Ok(blocks)
     }
     
+    /// Find recent orphans that the block at `next_index` may reference as uncles
+    /// 
+    /// Candidates lost the race for their height but were otherwise valid: they
+    /// build on the canonical chain, meet their target difficulty, weren't
+    /// rejected outright, and no canonical block has referenced them yet.
+    /// Returns at most `MAX_UNCLES_PER_BLOCK` blocks, most recent first.
+    pub async fn find_uncle_candidates_multi(
+        mgr: &DatastoreManager,
+        next_index: u64,
+    ) -> Result<Vec<Self>> {
+        use modal_common::uncles::{is_within_uncle_depth, MAX_UNCLES_PER_BLOCK, MAX_UNCLE_DEPTH};
+        
+        let min_index = next_index.saturating_sub(MAX_UNCLE_DEPTH).max(1);
+        
+        // Canonical blocks from the uncles' parents up to the tip
+        let mut canonical = std::collections::HashMap::new();
+        for index in (min_index - 1)..next_index {
+            if let Some(block) = Self::find_canonical_by_index_simple(mgr, index).await? {
+                canonical.insert(index, block);
+            }
+        }
+        let referenced: std::collections::HashSet<&str> = canonical
+            .values()
+            .flat_map(|b| b.uncles.iter().map(|u| u.hash.as_str()))
+            .collect();
+        
+        let mut candidates: Vec<Self> = Self::find_all_orphaned_multi(mgr)
+            .await?
+            .into_iter()
+            .filter(|b| is_within_uncle_depth(next_index, b.index) && !b.is_canonical)
+            .filter(|b| !b.orphan_reason.as_deref().unwrap_or_default().starts_with("Rejected"))
+            .filter(|b| !referenced.contains(b.hash.as_str()))
+            .filter(|b| canonical.get(&(b.index - 1)).is_some_and(|parent| parent.hash == b.previous_hash))
+            .filter(|b| canonical.get(&b.index).is_some_and(|winner| winner.hash != b.hash))
+            .filter(|b| match (b.get_actualized_difficulty_u128(), b.get_target_difficulty_u128()) {
+                (Ok(actual), Ok(target)) => actual >= target,
+                _ => false,
+            })
+            .collect();
+        
+        candidates.sort_by(|a, b| b.index.cmp(&a.index).then_with(|| a.hash.cmp(&b.hash)));
+        candidates.dedup_by(|a, b| a.hash == b.hash);
+        candidates.truncate(MAX_UNCLES_PER_BLOCK);
+        Ok(candidates)
+    }
+    
     // ============================================================
     // Multi-store write methods
     // ============================================================
     
     /// Save a block to MinerActive (for recent blocks)
     pub async fn save_to_active(&self, mgr: &DatastoreManager) -> Result<()> {
-        let key = format!("{}/{}", MINER_BLOCK_PREFIX, self.hash);
-        let data = serde_json::to_vec(self)?;
-        mgr.miner_active().put(&key, &data)?;
+        self.save_active_to(mgr.miner_active()).await
+    }
+    
+    /// Save blocks to MinerActive as one write, as sync does with the
+    /// ranges it receives
+    pub async fn save_all_to_active(mgr: &DatastoreManager, blocks: &[Self]) -> Result<()> {
+        let buffer = mgr.miner_active().buffer();
+        for block in blocks {
+            block.save_active_to(&buffer).await?;
+        }
+        buffer.commit()?;
+        Ok(())
+    }
+    
+    async fn save_active_to<S: Store + Send + Sync>(&self, store: &S) -> Result<()> {
+        self.save_to_store(store).await?;
         
         // Also save height index
         let height_key = format!("/miner_blocks/index/{}/hash/{}", self.index, self.hash);
@@ -313,20 +398,22 @@ impl MinerBlock {
             "block_hash": self.hash,
             "is_canonical": self.is_canonical
         });
-        mgr.miner_active().put(&height_key, serde_json::to_string(&height_entry)?.as_bytes())?;
+        store.put(&height_key, serde_json::to_string(&height_entry)?.as_bytes())?;
         
         Ok(())
     }
     
     /// Promote a canonical block to MinerCanon
     pub async fn promote_to_canon(&self, mgr: &DatastoreManager) -> Result<()> {
+        self.promote_to(mgr.miner_canon()).await
+    }
+    
+    async fn promote_to<S: Store + Send + Sync>(&self, store: &S) -> Result<()> {
         if !self.is_canonical {
             anyhow::bail!("Cannot promote non-canonical block to MinerCanon");
         }
         
-        let key = format!("{}/{}", MINER_BLOCK_PREFIX, self.hash);
-        let data = serde_json::to_vec(self)?;
-        mgr.miner_canon().put(&key, &data)?;
+        self.save_to_store(store).await?;
         
         // Also save height index in canon store
         let height_key = format!("/miner_blocks/index/{}/hash/{}", self.index, self.hash);
@@ -335,20 +422,22 @@ impl MinerBlock {
             "block_hash": self.hash,
             "is_canonical": true
         });
-        mgr.miner_canon().put(&height_key, serde_json::to_string(&height_entry)?.as_bytes())?;
+        store.put(&height_key, serde_json::to_string(&height_entry)?.as_bytes())?;
         
         Ok(())
     }
     
     /// Archive an orphaned block to MinerForks
     pub async fn archive_to_forks(&self, mgr: &DatastoreManager) -> Result<()> {
+        self.archive_to(mgr.miner_forks()).await
+    }
+    
+    async fn archive_to<S: Store + Send + Sync>(&self, store: &S) -> Result<()> {
         if !self.is_orphaned {
             anyhow::bail!("Cannot archive non-orphaned block to MinerForks");
         }
         
-        let key = format!("{}/{}", MINER_BLOCK_PREFIX, self.hash);
-        let data = serde_json::to_vec(self)?;
-        mgr.miner_forks().put(&key, &data)?;
+        self.save_to_store(store).await?;
         
         // Also save height index in forks store
         let height_key = format!("/miner_blocks/index/{}/hash/{}", self.index, self.hash);
@@ -358,15 +447,14 @@ impl MinerBlock {
             "is_canonical": false,
             "is_orphaned": true
         });
-        mgr.miner_forks().put(&height_key, serde_json::to_string(&height_entry)?.as_bytes())?;
+        store.put(&height_key, serde_json::to_string(&height_entry)?.as_bytes())?;
         
         Ok(())
     }
     
     /// Delete a block from MinerActive (used during purge)
     pub async fn delete_from_active(&self, mgr: &DatastoreManager) -> Result<()> {
-        let key = format!("{}/{}", MINER_BLOCK_PREFIX, self.hash);
-        mgr.miner_active().delete(&key)?;
+        self.delete_from_store(mgr.miner_active()).await?;
         
         // Also delete height index
         let height_key = format!("/miner_blocks/index/{}/hash/{}", self.index, self.hash);
@@ -427,25 +515,30 @@ impl MinerBlock {
         
         let mut canonical_count = 0;
         let mut orphan_count = 0;
+        // An epoch of blocks goes to each store in one write
+        let canon = mgr.miner_canon().buffer();
+        let forks = mgr.miner_forks().buffer();
         
         for block in blocks_to_promote {
             if block.is_canonical {
                 // Check if already in MinerCanon
                 let key = format!("{}/{}", MINER_BLOCK_PREFIX, block.hash);
-                if mgr.miner_canon().get(&key)?.is_none() {
-                    block.promote_to_canon(mgr).await?;
+                if canon.get(&key)?.is_none() {
+                    block.promote_to(&canon).await?;
                     canonical_count += 1;
                 }
             } else if block.is_orphaned {
                 // Check if already in MinerForks
                 let key = format!("{}/{}", MINER_BLOCK_PREFIX, block.hash);
-                if mgr.miner_forks().get(&key)?.is_none() {
-                    block.archive_to_forks(mgr).await?;
+                if forks.get(&key)?.is_none() {
+                    block.archive_to(&forks).await?;
                     orphan_count += 1;
                 }
             }
             // Pending blocks (neither canonical nor orphaned) stay in MinerActive
         }
+        canon.commit()?;
+        forks.commit()?;
         
         Ok((canonical_count, orphan_count))
     }
@@ -508,11 +601,10 @@ impl MinerBlock {
         
         for block in &blocks_to_prune {
             // Delete from MinerActive if present
-            let key = format!("{}/{}", MINER_BLOCK_PREFIX, block.hash);
-            let _ = mgr.miner_active().delete(&key);
+            let _ = block.delete_from_store(mgr.miner_active()).await;
             
             // Delete from MinerForks if present  
-            let _ = mgr.miner_forks().delete(&key);
+            let _ = block.delete_from_store(mgr.miner_forks()).await;
             
             // Delete height index from both stores
             let height_key = format!("/miner_blocks/index/{}/hash/{}", block.index, block.hash);
@@ -634,10 +726,9 @@ impl MinerBlock {
         let count = invalid_blocks.len();
         
         for block in &invalid_blocks {
-            let key = format!("{}/{}", MINER_BLOCK_PREFIX, block.hash);
-            let _ = mgr.miner_active().delete(&key);
-            let _ = mgr.miner_canon().delete(&key);
-            let _ = mgr.miner_forks().delete(&key);
+            let _ = block.delete_from_store(mgr.miner_active()).await;
+            let _ = block.delete_from_store(mgr.miner_canon()).await;
+            let _ = block.delete_from_store(mgr.miner_forks()).await;
             
             let height_key = format!("/miner_blocks/index/{}/hash/{}", block.index, block.hash);
             let _ = mgr.miner_active().delete(&height_key);
@@ -670,6 +761,10 @@ mod tests {
             actualized_difficulty: "1000".to_string(), // Use same as target difficulty for tests
             nominated_peer_id: "peer".to_string(),
             miner_number: 1,
+            uncles: Vec::new(),
+            commits: Vec::new(),
+            commits_root: String::new(),
+            evidence: Vec::new(),
             is_orphaned,
             is_canonical,
             seen_at: Some(1234567890),
@@ -691,6 +786,40 @@ mod tests {
         assert!(found.is_some());
         assert_eq!(found.unwrap().index, 100);
     }
+
+    #[tokio::test]
+    async fn test_cached_blocks_follow_saves() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+
+        let mut block = create_test_block("cached", 100, 1, true, false);
+        block.save_to_active(&mgr).await.unwrap();
+        MinerBlock::find_by_hash_multi(&mgr, "cached").await.unwrap();
+        let found = MinerBlock::find_by_hash_multi(&mgr, "cached").await.unwrap();
+        assert_eq!(found.map(|b| b.epoch), Some(1));
+        assert_eq!(mgr.cache_stats().hits, 1);
+
+        // Saving the block drops the cached copy
+        block.epoch = 2;
+        block.save_to_active(&mgr).await.unwrap();
+        let found = MinerBlock::find_by_hash_multi(&mgr, "cached").await.unwrap();
+        assert_eq!(found.map(|b| b.epoch), Some(2));
+        assert_eq!(mgr.cache_stats().hits, 1);
+    }
+
+    #[tokio::test]
+    async fn test_save_all_to_active() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        
+        let blocks: Vec<MinerBlock> = (0..5)
+            .map(|i| create_test_block(&format!("batched{}", i), 100 + i, 1, true, false))
+            .collect();
+        MinerBlock::save_all_to_active(&mgr, &blocks).await.unwrap();
+        
+        for block in &blocks {
+            let found = MinerBlock::find_canonical_by_index_simple(&mgr, block.index).await.unwrap();
+            assert_eq!(found.map(|b| b.hash), Some(block.hash.clone()));
+        }
+    }
     
     #[tokio::test]
     async fn test_promote_to_canon() {
@@ -726,6 +855,61 @@ mod tests {
         assert_eq!(orphaned.len(), 1);
     }
     
+    #[tokio::test]
+    async fn test_indexes_follow_updates_and_rebuild() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        
+        let mut block = create_test_block("hash4", 100, 1, true, false);
+        block.save_to_active(&mgr).await.unwrap();
+        create_test_block("hash5", 101, 1, true, false).save_to_active(&mgr).await.unwrap();
+        assert_eq!(MinerBlock::find_canonical_by_peer_multi(&mgr, "peer").await.unwrap().len(), 2);
+        
+        // Orphaning the block moves its index entries
+        block.is_canonical = false;
+        block.is_orphaned = true;
+        block.save_to_active(&mgr).await.unwrap();
+        assert!(MinerBlock::find_canonical_by_index_simple(&mgr, 100).await.unwrap().is_none());
+        assert_eq!(MinerBlock::find_all_orphaned_multi(&mgr).await.unwrap().len(), 1);
+        assert_eq!(MinerBlock::find_by_index_multi(&mgr, 100).await.unwrap().len(), 1);
+        
+        block.delete_from_active(&mgr).await.unwrap();
+        assert!(MinerBlock::find_by_index_multi(&mgr, 100).await.unwrap().is_empty());
+        assert!(MinerBlock::find_all_orphaned_multi(&mgr).await.unwrap().is_empty());
+        
+        // Blocks written without index entries are found after a rebuild
+        let unindexed = create_test_block("hash6", 102, 2, true, false);
+        mgr.miner_active().put(&unindexed.get_id(), &serde_json::to_vec(&unindexed).unwrap()).unwrap();
+        assert!(MinerBlock::find_canonical_by_epoch_multi(&mgr, 2, 2).await.unwrap().is_empty());
+        assert!(!MinerBlock::indexes_built(mgr.miner_active()).unwrap());
+        assert!(MinerBlock::ensure_indexes_in_store(mgr.miner_active()).unwrap());
+        assert!(!MinerBlock::ensure_indexes_in_store(mgr.miner_active()).unwrap());
+        assert_eq!(MinerBlock::find_canonical_by_epoch_multi(&mgr, 2, 2).await.unwrap().len(), 1);
+        assert_eq!(MinerBlock::find_all_canonical_multi(&mgr).await.unwrap().len(), 2);
+    }
+    
+    #[tokio::test]
+    async fn test_find_by_hash_prefix_and_forks() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        
+        create_test_block("abc1", 100, 1, true, false).save_to_active(&mgr).await.unwrap();
+        create_test_block("abd2", 101, 1, true, false).save_to_active(&mgr).await.unwrap();
+        let orphan = create_test_block("abc3", 100, 1, false, true);
+        orphan.save_to_active(&mgr).await.unwrap();
+        orphan.archive_to_forks(&mgr).await.unwrap();
+        
+        let found = MinerBlock::find_by_hash_prefix_multi(&mgr, "abc").await.unwrap();
+        let hashes: Vec<&str> = found.iter().map(|b| b.hash.as_str()).collect();
+        assert_eq!(hashes.len(), 2);
+        assert!(hashes.contains(&"abc1") && hashes.contains(&"abc3"));
+        assert_eq!(MinerBlock::find_by_hash_prefix_multi(&mgr, "ab").await.unwrap().len(), 3);
+        assert!(MinerBlock::find_by_hash_prefix_multi(&mgr, "x").await.unwrap().is_empty());
+        
+        // Height 100 has a canonical block and an orphan; 101 doesn't fork
+        let forks = MinerBlock::find_forks_multi(&mgr).await.unwrap();
+        let hashes: Vec<&str> = forks.iter().map(|b| b.hash.as_str()).collect();
+        assert_eq!(hashes, vec!["abc1", "abc3"]);
+    }
+    
     #[tokio::test]
     async fn test_promotion_task() {
         let mut mgr = DatastoreManager::create_in_memory().unwrap();
@@ -770,5 +954,41 @@ mod tests {
         let found = MinerBlock::find_by_hash_multi(&mgr, "old_block").await.unwrap();
         assert!(found.is_none()); // Not in any store since we didn't promote it
     }
+    
+    #[tokio::test]
+    async fn test_find_uncle_candidates() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        
+        let child = |hash: &str, index: u64, parent: &str, is_canonical: bool| {
+            let mut block = create_test_block(hash, index, 0, is_canonical, !is_canonical);
+            block.previous_hash = parent.to_string();
+            block
+        };
+        for block in [
+            child("c0", 0, "0", true),
+            child("c1", 1, "c0", true),
+            child("c2", 2, "c1", true),
+            child("lost_race", 1, "c0", false),
+            child("wrong_parent", 2, "elsewhere", false),
+        ] {
+            block.save_to_active(&mgr).await.unwrap();
+        }
+        let mut rejected = child("rejected", 1, "c0", false);
+        rejected.orphan_reason = Some("Rejected: target difficulty 1 does not match expected 2".to_string());
+        rejected.save_to_active(&mgr).await.unwrap();
+        
+        let candidates = MinerBlock::find_uncle_candidates_multi(&mgr, 3).await.unwrap();
+        let hashes: Vec<&str> = candidates.iter().map(|b| b.hash.as_str()).collect();
+        assert_eq!(hashes, vec!["lost_race"]);
+        
+        // Once a canonical block references it, it is no longer a candidate
+        let uncle = modal_common::uncles::UncleRef {
+            hash: "lost_race".to_string(),
+            index: 1,
+            nominated_peer_id: "peer".to_string(),
+        };
+        child("c3", 3, "c2", true).with_uncles(vec![uncle]).save_to_active(&mgr).await.unwrap();
+        assert!(MinerBlock::find_uncle_candidates_multi(&mgr, 4).await.unwrap().is_empty());
+    }
 }
 
@@ -0,0 +1,124 @@
+//! Orphan pool: blocks waiting for a missing parent
+//!
+//! Blocks whose parent hasn't arrived yet are stored as orphans as usual and
+//! indexed here by the parent hash they're missing, so they can be promoted
+//! as soon as the parent arrives instead of waiting to be gossiped again.
+//! Entries expire after a TTL so blocks whose parent never shows up don't
+//! accumulate.
+
+use crate::{DatastoreManager, Store};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Key prefix for orphan pool entries in MinerActive
+const ORPHAN_POOL_PREFIX: &str = "/miner_orphan_pool/parent";
+
+/// An orphaned block waiting for its parent
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OrphanPoolEntry {
+    pub block_hash: String,
+    pub block_index: u64,
+    /// Hash of the missing parent
+    pub parent_hash: String,
+    /// Unix timestamp when the block entered the pool
+    pub added_at: i64,
+}
+
+impl OrphanPoolEntry {
+    pub fn new(block_hash: String, block_index: u64, parent_hash: String) -> Self {
+        Self {
+            block_hash,
+            block_index,
+            parent_hash,
+            added_at: chrono::Utc::now().timestamp(),
+        }
+    }
+    
+    fn key(parent_hash: &str, block_hash: &str) -> String {
+        format!("{}/{}/hash/{}", ORPHAN_POOL_PREFIX, parent_hash, block_hash)
+    }
+    
+    /// Add the entry to the pool
+    pub async fn save(&self, mgr: &DatastoreManager) -> Result<()> {
+        let key = Self::key(&self.parent_hash, &self.block_hash);
+        mgr.miner_active().put(&key, &serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+    
+    /// Remove the entry from the pool
+    pub async fn delete(&self, mgr: &DatastoreManager) -> Result<()> {
+        mgr.miner_active().delete(&Self::key(&self.parent_hash, &self.block_hash))?;
+        Ok(())
+    }
+    
+    /// Find the orphans waiting for `parent_hash`
+    pub async fn find_by_parent_multi(mgr: &DatastoreManager, parent_hash: &str) -> Result<Vec<Self>> {
+        let prefix = format!("{}/{}/hash", ORPHAN_POOL_PREFIX, parent_hash);
+        let mut entries = Vec::new();
+        for item in mgr.miner_active().iterator(&prefix) {
+            let (_, value) = item?;
+            entries.push(serde_json::from_slice(&value).context("Failed to deserialize OrphanPoolEntry")?);
+        }
+        Ok(entries)
+    }
+    
+    /// Find every orphan in the pool
+    pub async fn find_all_multi(mgr: &DatastoreManager) -> Result<Vec<Self>> {
+        let mut entries = Vec::new();
+        for item in mgr.miner_active().iterator(ORPHAN_POOL_PREFIX) {
+            let (_, value) = item?;
+            entries.push(serde_json::from_slice(&value).context("Failed to deserialize OrphanPoolEntry")?);
+        }
+        Ok(entries)
+    }
+    
+    /// Remove and return the orphans waiting for `parent_hash`
+    pub async fn take_by_parent_multi(mgr: &DatastoreManager, parent_hash: &str) -> Result<Vec<Self>> {
+        let entries = Self::find_by_parent_multi(mgr, parent_hash).await?;
+        for entry in &entries {
+            entry.delete(mgr).await?;
+        }
+        Ok(entries)
+    }
+    
+    /// Drop entries that have waited longer than `ttl_secs`, returning how many were evicted
+    pub async fn evict_expired_multi(mgr: &DatastoreManager, ttl_secs: i64) -> Result<usize> {
+        let cutoff = chrono::Utc::now().timestamp() - ttl_secs;
+        let mut evicted = 0;
+        for entry in Self::find_all_multi(mgr).await? {
+            if entry.added_at < cutoff {
+                entry.delete(mgr).await?;
+                evicted += 1;
+            }
+        }
+        Ok(evicted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_orphan_pool_by_parent() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        
+        OrphanPoolEntry::new("child_a".to_string(), 5, "parent".to_string()).save(&mgr).await.unwrap();
+        OrphanPoolEntry::new("child_b".to_string(), 5, "parent".to_string()).save(&mgr).await.unwrap();
+        OrphanPoolEntry::new("other".to_string(), 9, "parent_2".to_string()).save(&mgr).await.unwrap();
+        
+        // A parent hash that's a prefix of another doesn't pick up its orphans
+        assert!(OrphanPoolEntry::find_by_parent_multi(&mgr, "par").await.unwrap().is_empty());
+        
+        let taken = OrphanPoolEntry::take_by_parent_multi(&mgr, "parent").await.unwrap();
+        assert_eq!(taken.len(), 2);
+        assert!(OrphanPoolEntry::find_by_parent_multi(&mgr, "parent").await.unwrap().is_empty());
+        assert_eq!(OrphanPoolEntry::find_all_multi(&mgr).await.unwrap().len(), 1);
+        
+        let mut stale = OrphanPoolEntry::new("stale".to_string(), 3, "gone".to_string());
+        stale.added_at -= 120;
+        stale.save(&mgr).await.unwrap();
+        assert_eq!(OrphanPoolEntry::evict_expired_multi(&mgr, 60).await.unwrap(), 1);
+        assert_eq!(OrphanPoolEntry::find_all_multi(&mgr).await.unwrap().len(), 1);
+    }
+}
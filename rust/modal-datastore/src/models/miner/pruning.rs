@@ -0,0 +1,248 @@
+//! Chain pruning
+//!
+//! Below the latest checkpoint, a pruning node keeps only block headers in
+//! MinerCanon: the included commit list is dropped while its root stays in
+//! the header, so hashes still verify. Each fully pruned epoch gets a summary,
+//! and orphaned blocks below the boundary are deleted. The boundary is
+//! recorded so sync handlers can tell peers which blocks they can no longer
+//! serve in full.
+
+use super::{MinerBlock, MinerCheckpoint};
+use crate::{DatastoreManager, Store};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Key prefix for epoch summaries in MinerCanon
+const EPOCH_SUMMARY_PREFIX: &str = "/miner_epoch_summaries/epoch";
+
+/// Key of the prune status in MinerCanon
+const PRUNE_STATUS_KEY: &str = "/miner_pruning/status";
+
+/// Summary of a pruned epoch's canonical blocks
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EpochSummary {
+    pub epoch: u64,
+    pub first_block_index: u64,
+    pub last_block_index: u64,
+    pub last_block_hash: String,
+    pub block_count: u64,
+    /// Merkle root of the epoch's canonical block hashes
+    pub merkle_root: String,
+    pub cumulative_difficulty: String,
+    /// Number of contract commits included in the epoch before pruning
+    pub commit_count: u64,
+}
+
+impl EpochSummary {
+    /// Summarize an epoch's canonical blocks (sorted by index)
+    pub fn from_blocks(epoch: u64, blocks: &[MinerBlock]) -> Result<Self> {
+        let (Some(first), Some(last)) = (blocks.first(), blocks.last()) else {
+            anyhow::bail!("Cannot summarize epoch {} without blocks", epoch);
+        };
+        let hashes: Vec<String> = blocks.iter().map(|b| b.hash.clone()).collect();
+        Ok(Self {
+            epoch,
+            first_block_index: first.index,
+            last_block_index: last.index,
+            last_block_hash: last.hash.clone(),
+            block_count: blocks.len() as u64,
+            merkle_root: modal_common::merkle::compute_merkle_root_owned(&hashes),
+            cumulative_difficulty: MinerBlock::calculate_cumulative_difficulty(blocks)?.to_string(),
+            commit_count: blocks.iter().map(|b| b.commits.len() as u64).sum(),
+        })
+    }
+
+    pub async fn save(&self, mgr: &DatastoreManager) -> Result<()> {
+        let key = format!("{}/{}", EPOCH_SUMMARY_PREFIX, self.epoch);
+        mgr.miner_canon().put(&key, &serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+
+    pub async fn find_by_epoch_multi(mgr: &DatastoreManager, epoch: u64) -> Result<Option<Self>> {
+        match mgr.miner_canon().get(&format!("{}/{}", EPOCH_SUMMARY_PREFIX, epoch))? {
+            Some(data) => Ok(Some(serde_json::from_slice(&data).context("Failed to deserialize EpochSummary")?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Find all epoch summaries, sorted by epoch
+    pub async fn find_all_multi(mgr: &DatastoreManager) -> Result<Vec<Self>> {
+        let mut summaries: Vec<Self> = Vec::new();
+        for item in mgr.miner_canon().iterator(EPOCH_SUMMARY_PREFIX) {
+            let (_, value) = item?;
+            summaries.push(serde_json::from_slice(&value).context("Failed to deserialize EpochSummary")?);
+        }
+        summaries.sort_by_key(|s| s.epoch);
+        Ok(summaries)
+    }
+}
+
+/// How far the local chain has been pruned
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PruneStatus {
+    /// Blocks below this index are stored as headers only
+    pub pruned_below: u64,
+    /// Unix timestamp of the last prune
+    pub updated_at: i64,
+}
+
+impl PruneStatus {
+    pub async fn find_multi(mgr: &DatastoreManager) -> Result<Option<Self>> {
+        match mgr.miner_canon().get(PRUNE_STATUS_KEY)? {
+            Some(data) => Ok(Some(serde_json::from_slice(&data).context("Failed to deserialize PruneStatus")?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Index below which this node only has headers (None if never pruned)
+    pub async fn pruned_below_multi(mgr: &DatastoreManager) -> Result<Option<u64>> {
+        Ok(Self::find_multi(mgr).await?.map(|s| s.pruned_below))
+    }
+
+    async fn save(&self, mgr: &DatastoreManager) -> Result<()> {
+        mgr.miner_canon().put(PRUNE_STATUS_KEY, &serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+}
+
+/// Outcome of a prune run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PruneResult {
+    pub pruned_below: u64,
+    pub headers_pruned: usize,
+    pub orphans_deleted: usize,
+    pub epochs_summarized: usize,
+}
+
+/// Index below which blocks may be pruned: everything under the latest
+/// checkpoint, except the `keep_blocks` most recent canonical blocks.
+/// None when there's no checkpoint or nothing to prune.
+pub async fn prune_boundary_multi(mgr: &DatastoreManager, keep_blocks: u64) -> Result<Option<u64>> {
+    let Some(checkpoint) = MinerCheckpoint::find_latest_multi(mgr).await? else {
+        return Ok(None);
+    };
+    let Some(tip) = MinerBlock::find_all_canonical_multi(mgr).await?.last().map(|b| b.index) else {
+        return Ok(None);
+    };
+    let boundary = (checkpoint.last_block_index + 1).min((tip + 1).saturating_sub(keep_blocks));
+    Ok((boundary > 0).then_some(boundary))
+}
+
+/// Prune the chain below `below_index`: canonical blocks are reduced to
+/// headers in MinerCanon and dropped from MinerActive, completed epochs are
+/// summarized, and orphaned blocks are deleted.
+pub async fn prune_below_multi(mgr: &DatastoreManager, below_index: u64) -> Result<PruneResult> {
+    let previous = PruneStatus::pruned_below_multi(mgr).await?.unwrap_or(0);
+    let canonical = MinerBlock::find_all_canonical_multi(mgr).await?;
+
+    // Only epochs that end below the boundary are complete
+    let partial_epoch = canonical
+        .iter()
+        .find(|b| b.index >= below_index)
+        .map(|b| b.epoch)
+        .or_else(|| canonical.iter().rfind(|b| b.index < below_index).map(|b| b.epoch));
+
+    let mut result = PruneResult {
+        pruned_below: below_index.max(previous),
+        ..Default::default()
+    };
+    let mut epochs: BTreeMap<u64, Vec<MinerBlock>> = BTreeMap::new();
+    for block in canonical.into_iter().filter(|b| b.index < below_index) {
+        epochs.entry(block.epoch).or_default().push(block);
+    }
+
+    for (epoch, blocks) in epochs {
+        if Some(epoch) != partial_epoch && EpochSummary::find_by_epoch_multi(mgr, epoch).await?.is_none() {
+            EpochSummary::from_blocks(epoch, &blocks)?.save(mgr).await?;
+            result.epochs_summarized += 1;
+        }
+        for block in blocks {
+            if block.index < previous && block.commits.is_empty() {
+                continue;
+            }
+            let mut header = block;
+            header.commits.clear();
+            header.promote_to_canon(mgr).await?;
+            header.delete_from_active(mgr).await?;
+            result.headers_pruned += 1;
+        }
+    }
+
+    if below_index > 0 {
+        result.orphans_deleted = MinerBlock::prune_orphaned_before_checkpoint_multi(mgr, below_index - 1).await?;
+    }
+
+    PruneStatus {
+        pruned_below: result.pruned_below,
+        updated_at: chrono::Utc::now().timestamp(),
+    }
+    .save(mgr)
+    .await?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use modal_common::block_commits::CommitDigest;
+
+    #[tokio::test]
+    async fn test_prune_keeps_headers_and_summaries() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+
+        // Two blocks per epoch, each carrying a commit
+        let mut previous_hash = String::new();
+        for index in 0..8u64 {
+            let mut block = MinerBlock::new_canonical(
+                format!("block_{}", index),
+                index,
+                index / 2,
+                1_700_000_000 + index as i64,
+                previous_hash.clone(),
+                String::new(),
+                index as u128,
+                1000,
+                "peer".to_string(),
+                index,
+            );
+            block.commits = vec![CommitDigest::new("contract".to_string(), format!("commit_{}", index), 10)];
+            block.save_to_active(&mgr).await.unwrap();
+            previous_hash = block.hash.clone();
+        }
+        let mut orphan = MinerBlock::new_canonical(
+            "orphan_2".to_string(), 2, 1, 1_700_000_002, "block_1".to_string(), String::new(), 0, 1000, "peer".to_string(), 0,
+        );
+        orphan.mark_as_orphaned("test".to_string(), Some("block_2".to_string()));
+        orphan.save_to_active(&mgr).await.unwrap();
+
+        // Nothing to prune until there's a checkpoint
+        assert_eq!(prune_boundary_multi(&mgr, 2).await.unwrap(), None);
+        MinerCheckpoint::new_consensus(2, 4, 6, "block_6".to_string(), String::new(), 2, 1)
+            .save_to_canon(&mgr)
+            .await
+            .unwrap();
+        let boundary = prune_boundary_multi(&mgr, 3).await.unwrap();
+        assert_eq!(boundary, Some(5));
+
+        let result = prune_below_multi(&mgr, 5).await.unwrap();
+        assert_eq!(result.headers_pruned, 5);
+        assert_eq!(result.orphans_deleted, 1);
+        // Epoch 2 (blocks 4 and 5) is only partly below the boundary
+        assert_eq!(result.epochs_summarized, 2);
+        assert_eq!(PruneStatus::pruned_below_multi(&mgr).await.unwrap(), Some(5));
+
+        let canonical = MinerBlock::find_all_canonical_multi(&mgr).await.unwrap();
+        assert_eq!(canonical.len(), 8);
+        assert!(canonical.iter().filter(|b| b.index < 5).all(|b| b.commits.is_empty()));
+        assert!(canonical.iter().filter(|b| b.index >= 5).all(|b| b.commits.len() == 1));
+        assert!(MinerBlock::find_by_hash_multi(&mgr, "orphan_2").await.unwrap().is_none());
+
+        let summaries = EpochSummary::find_all_multi(&mgr).await.unwrap();
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[1].first_block_index, 2);
+        assert_eq!(summaries[1].last_block_hash, "block_3");
+        assert_eq!(summaries[1].commit_count, 2);
+        assert_eq!(summaries[1].cumulative_difficulty, "2000");
+    }
+}
@@ -16,11 +16,12 @@ pub use validator::{
     DAGBatch,
     DAGState,
     ConsensusMetadata,
+    SequencedLog,
 };
 
 pub use miner::{MinerBlock, MinerBlockHeight};
 pub use transaction::Transaction;
-pub use contract::{Contract, Commit, ContractAsset, AssetBalance, ReceivedSend};
+pub use contract::{Contract, Commit, ContractAsset, AssetBalance, ReceivedSend, ContractMessage, ContractGasUsage};
 pub use wasm_module::WasmModule;
-pub use peer_info::PeerInfo;
+pub use peer_info::{NodeManifest, PeerCapabilities, PeerInfo};
 pub use modality::{ModalityContract, ModalityRule, ModalityAction, ModalityCommitBody};
@@ -1,6 +1,8 @@
 use crate::model::Model;
+use crate::stores::Store;
 use crate::{DatastoreManager, Result};
 use async_trait::async_trait;
+use modal_common::keypair::Keypair;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -11,6 +13,94 @@ pub struct PeerInfo {
     pub status_url: Option<String>,
     pub role: Option<String>,
     pub last_seen: Option<i64>, // Unix timestamp
+    /// What the peer said it supports, once it has answered a capabilities handshake
+    #[serde(default)]
+    pub capabilities: Option<PeerCapabilities>,
+    /// The peer's signed description of itself, once verified; `status_url`
+    /// and `role` come from it when it is set
+    #[serde(default)]
+    pub manifest: Option<NodeManifest>,
+}
+
+/// Protocols and data a peer offers, from its capabilities handshake
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct PeerCapabilities {
+    /// Message protocol version the peer speaks
+    pub protocol_version: u32,
+    /// Request-response paths and stream protocols the peer answers
+    #[serde(default)]
+    pub protocols: Vec<String>,
+    /// How the peer can serve chain data: "blocks", and "headers" below `pruned_below`
+    #[serde(default)]
+    pub sync_modes: Vec<String>,
+    /// Blocks below this index are kept as headers only
+    #[serde(default)]
+    pub pruned_below: Option<u64>,
+    /// Whether the peer keeps every block in full
+    #[serde(default)]
+    pub archive: bool,
+    /// When the handshake was answered, Unix seconds
+    #[serde(default)]
+    pub received_at: i64,
+}
+
+impl PeerCapabilities {
+    /// Whether the peer answers `protocol`
+    pub fn supports(&self, protocol: &str) -> bool {
+        self.protocols.iter().any(|p| p == protocol)
+    }
+
+    /// Whether the peer can serve block bodies from `index` on
+    pub fn serves_blocks_from(&self, index: u64) -> bool {
+        self.pruned_below.is_none_or(|pruned_below| index >= pruned_below)
+    }
+}
+
+/// What a node says about itself in identify, signed with its peer key so
+/// nobody else can speak for it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct NodeManifest {
+    pub peer_id: String,
+    pub role: String,
+    pub network: String,
+    #[serde(default)]
+    pub genesis: Option<String>,
+    /// Message protocol version the node speaks
+    pub protocol_version: u32,
+    #[serde(default)]
+    pub status_url: Option<String>,
+    /// Whether the node keeps every block in full
+    #[serde(default)]
+    pub archive: bool,
+    /// Unix seconds
+    pub issued_at: i64,
+    /// Signature by `peer_id` over the other fields
+    #[serde(default)]
+    pub signature: String,
+}
+
+impl NodeManifest {
+    fn signing_payload(&self) -> serde_json::Value {
+        let mut payload = serde_json::to_value(self).unwrap_or_default();
+        if let Some(fields) = payload.as_object_mut() {
+            fields.remove("signature");
+        }
+        payload
+    }
+
+    /// Sign as the node holding `keypair`, which must be `peer_id`'s
+    pub fn sign(mut self, keypair: &Keypair) -> anyhow::Result<Self> {
+        self.signature = keypair.sign_json(&self.signing_payload())?;
+        Ok(self)
+    }
+
+    /// Whether `peer_id` signed this manifest about itself
+    pub fn verify(&self, peer_id: &str) -> bool {
+        self.peer_id == peer_id
+            && Keypair::from_public_key(&self.peer_id, "ed25519")
+                .and_then(|key| key.verify_json(&self.signature, &self.signing_payload()))
+                .unwrap_or(false)
+    }
 }
 
 impl PeerInfo {
@@ -21,6 +111,8 @@ impl PeerInfo {
             status_url: None,
             role: None,
             last_seen: None,
+            capabilities: None,
+            manifest: None,
         }
     }
 
@@ -31,6 +123,8 @@ impl PeerInfo {
             status_url,
             role,
             last_seen: None,
+            capabilities: None,
+            manifest: None,
         }
     }
 
@@ -41,6 +135,8 @@ impl PeerInfo {
             status_url,
             role: None,
             last_seen: None,
+            capabilities: None,
+            manifest: None,
         }
     }
 
@@ -53,6 +149,18 @@ impl PeerInfo {
             .map_err(|e| crate::Error::Database(e.to_string()))
     }
 
+    /// Find all known peers in NodeState
+    pub async fn find_all(datastore: &DatastoreManager) -> Result<Vec<Self>> {
+        let mut peers = Vec::new();
+        for item in datastore.node_state().iterator("/node/peers/id") {
+            let (_, value) = item?;
+            let peer: PeerInfo = serde_json::from_slice(&value)
+                .map_err(|e| crate::Error::Database(e.to_string()))?;
+            peers.push(peer);
+        }
+        Ok(peers)
+    }
+
     /// Save this peer info to NodeState
     pub async fn save_to(&self, store: &crate::stores::NodeStateStore) -> Result<()> {
         self.save_to_store(store)
@@ -70,6 +178,8 @@ impl Model for PeerInfo {
         "status_url",
         "role",
         "last_seen",
+        "capabilities",
+        "manifest",
     ];
     
     const FIELD_DEFAULTS: &'static [(&'static str, serde_json::Value)] = &[];
@@ -80,6 +190,8 @@ impl Model for PeerInfo {
             "status_url" => self.status_url = value.as_str().map(|s| s.to_string()),
             "role" => self.role = value.as_str().map(|s| s.to_string()),
             "last_seen" => self.last_seen = value.as_i64(),
+            "capabilities" => self.capabilities = serde_json::from_value(value).ok(),
+            "manifest" => self.manifest = serde_json::from_value(value).ok(),
             _ => {}
         }
     }
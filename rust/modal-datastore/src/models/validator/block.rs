@@ -1,6 +1,7 @@
 use crate::model::Model;
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
+use modal_common::batch_verify::SignatureCheck;
 use modal_common::keypair::Keypair;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -166,24 +167,41 @@ impl ValidatorBlock {
         self.block_number = Some(number);
     }
 
-    pub fn generate_opening_sig(&mut self, keypair: &Keypair) -> Result<String> {
-        let facts = serde_json::json!({
+    fn opening_facts(&self) -> serde_json::Value {
+        serde_json::json!({
             "peer_id": self.peer_id,
             "round_id": self.round_id,
             "prev_round_certs": self.prev_round_certs,
-        });
-        self.opening_sig = Some(keypair.sign_json(&facts)?);
-        Ok(self.opening_sig.clone().unwrap())
+        })
     }
 
-    pub fn generate_closing_sig(&mut self, keypair: &Keypair) -> Result<String> {
-        let facts = serde_json::json!({
+    fn closing_facts(&self) -> serde_json::Value {
+        serde_json::json!({
             "peer_id": self.peer_id,
             "round_id": self.round_id,
             "prev_round_certs": self.prev_round_certs,
             "opening_sig": self.opening_sig,
             "events": self.events,
-        });
+        })
+    }
+
+    fn ack_facts(&self, acker: &str) -> serde_json::Value {
+        serde_json::json!({
+            "peer_id": self.peer_id,
+            "round_id": self.round_id,
+            "closing_sig": self.closing_sig,
+            "acker": acker,
+        })
+    }
+
+    pub fn generate_opening_sig(&mut self, keypair: &Keypair) -> Result<String> {
+        let facts = self.opening_facts();
+        self.opening_sig = Some(keypair.sign_json(&facts)?);
+        Ok(self.opening_sig.clone().unwrap())
+    }
+
+    pub fn generate_closing_sig(&mut self, keypair: &Keypair) -> Result<String> {
+        let facts = self.closing_facts();
         self.closing_sig = Some(keypair.sign_json(&facts)?);
         Ok(self.closing_sig.clone().unwrap())
     }
@@ -196,11 +214,7 @@ impl ValidatorBlock {
 
     pub fn validate_opening_sig(&self) -> Result<bool> {
         let keypair = Keypair::from_public_key(&self.peer_id, "ed25519")?;
-        let facts = serde_json::json!({
-            "peer_id": self.peer_id,
-            "round_id": self.round_id,
-            "prev_round_certs": self.prev_round_certs,
-        });
+        let facts = self.opening_facts();
         keypair.verify_json(
             self.opening_sig
                 .as_ref()
@@ -211,13 +225,7 @@ impl ValidatorBlock {
 
     pub fn validate_closing_sig(&self) -> Result<bool> {
         let keypair = Keypair::from_public_key(&self.peer_id, "ed25519")?;
-        let facts = serde_json::json!({
-            "peer_id": self.peer_id,
-            "round_id": self.round_id,
-            "prev_round_certs": self.prev_round_certs,
-            "opening_sig": self.opening_sig,
-            "events": self.events,
-        });
+        let facts = self.closing_facts();
         keypair.verify_json(
             self.closing_sig
                 .as_ref()
@@ -233,6 +241,26 @@ impl ValidatorBlock {
         self.validate_closing_sig()
     }
 
+    /// The opening and closing signatures, to verify in a batch
+    pub fn sig_checks(&self) -> Result<Vec<SignatureCheck>> {
+        let opening_sig = self.opening_sig.as_ref().ok_or_else(|| anyhow!("Missing signature"))?;
+        let closing_sig = self.closing_sig.as_ref().ok_or_else(|| anyhow!("Missing signature"))?;
+        Ok(vec![
+            SignatureCheck::json(&self.peer_id, &self.opening_facts(), opening_sig),
+            SignatureCheck::json(&self.peer_id, &self.closing_facts(), closing_sig),
+        ])
+    }
+
+    /// A check per ack signature, in the order of `acks`
+    pub fn ack_checks(&self) -> Vec<(String, SignatureCheck)> {
+        self.acks
+            .iter()
+            .map(|(acker, acker_sig)| {
+                (acker.clone(), SignatureCheck::json(acker, &self.ack_facts(acker), acker_sig))
+            })
+            .collect()
+    }
+
     pub fn generate_ack(&self, keypair: &Keypair) -> Result<Ack> {
         let peer_id = keypair.as_public_address();
         let facts = serde_json::json!({
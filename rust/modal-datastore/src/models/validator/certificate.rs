@@ -1,4 +1,5 @@
 use crate::{DatastoreManager, Result};
+use crate::migrations::Migration;
 use crate::model::Model;
 use crate::stores::Store;
 use serde::{Serialize, Deserialize};
@@ -19,21 +20,38 @@ pub struct DAGCertificate {
     pub signers: Vec<bool>,          // Bitvec of signers
     
     // References
-    pub batch_digest: String,        // Hex-encoded batch digest
+    #[serde(default)]
+    pub batch_digests: Vec<String>,  // Hex-encoded digests of the referenced batches
     pub parents: Vec<String>,        // List of parent certificate digests
     
     // Metadata
     pub timestamp: u64,
     pub committed: bool,             // Whether this cert is committed
     pub committed_at_round: Option<u64>, // When it was committed
+    #[serde(default)]
+    pub anchor: bool,                // Whether this cert was selected as its round's anchor
     pub created_at: u64,             // Local timestamp when stored
 }
 
+/// Certificates stored before `batch_digests` and `anchor` existed have none
+/// and weren't anchors
+fn fill_batches_and_anchor(mut obj: serde_json::Value) -> anyhow::Result<Option<serde_json::Value>> {
+    if obj.get("batch_digests").is_none() {
+        obj["batch_digests"] = serde_json::json!([]);
+    }
+    if obj.get("anchor").is_none() {
+        obj["anchor"] = serde_json::json!(false);
+    }
+    Ok(Some(obj))
+}
+
 #[async_trait]
 impl Model for DAGCertificate {
     // Primary key: round + digest (allows efficient round queries)
     const ID_PATH: &'static str = "/dag/certificates/round/${round}/digest/${digest}";
     
+    const CACHED: bool = true;
+    
     const FIELDS: &'static [&'static str] = &[
         "digest",
         "author",
@@ -41,19 +59,30 @@ impl Model for DAGCertificate {
         "header",
         "aggregated_signature",
         "signers",
-        "batch_digest",
+        "batch_digests",
         "parents",
         "timestamp",
         "committed",
         "committed_at_round",
+        "anchor",
         "created_at",
     ];
     
     const FIELD_DEFAULTS: &'static [(&'static str, serde_json::Value)] = &[
         ("committed", serde_json::json!(false)),
         ("parents", serde_json::json!([])),
+        ("batch_digests", serde_json::json!([])),
+        ("anchor", serde_json::json!(false)),
     ];
 
+    const INDEXES: &'static [&'static str] = &["digest", "author", "committed"];
+
+    const MIGRATIONS: &'static [Migration] = &[Migration {
+        version: 1,
+        description: "store batch_digests and anchor explicitly",
+        apply: fill_batches_and_anchor,
+    }];
+
     fn set_field(&mut self, field: &str, value: serde_json::Value) {
         match field {
             "digest" => self.digest = value.as_str().unwrap_or_default().to_string(),
@@ -62,11 +91,12 @@ impl Model for DAGCertificate {
             "header" => self.header = value.to_string(),
             "aggregated_signature" => self.aggregated_signature = value.to_string(),
             "signers" => self.signers = serde_json::from_value(value).unwrap_or_default(),
-            "batch_digest" => self.batch_digest = value.as_str().unwrap_or_default().to_string(),
+            "batch_digests" => self.batch_digests = serde_json::from_value(value).unwrap_or_default(),
             "parents" => self.parents = serde_json::from_value(value).unwrap_or_default(),
             "timestamp" => self.timestamp = value.as_u64().unwrap_or_default(),
             "committed" => self.committed = value.as_bool().unwrap_or_default(),
             "committed_at_round" => self.committed_at_round = value.as_u64(),
+            "anchor" => self.anchor = value.as_bool().unwrap_or_default(),
             "created_at" => self.created_at = value.as_u64().unwrap_or_default(),
             _ => {},
         }
@@ -119,73 +149,44 @@ impl DAGCertificate {
         Ok(certs)
     }
     
+    /// Find a certificate by digest, whatever its round
+    pub async fn find_by_digest_multi(
+        datastore: &DatastoreManager,
+        digest: &str,
+    ) -> Result<Option<Self>> {
+        let certs = Self::find_by_index_from_store(datastore.validator_final(), "digest", &serde_json::json!(digest))
+            .await
+            .map_err(|e| crate::Error::Database(e.to_string()))?;
+        Ok(certs.into_iter().next())
+    }
+
+    /// Find the certificates whose digest starts with `prefix`
+    pub async fn find_by_digest_prefix_multi(
+        datastore: &DatastoreManager,
+        prefix: &str,
+    ) -> Result<Vec<Self>> {
+        Self::find_by_index_prefix_from_store(datastore.validator_final(), "digest", prefix)
+            .await
+            .map_err(|e| crate::Error::Database(e.to_string()))
+    }
+
     /// Find all certificates by a specific author
     pub async fn find_by_author_multi(
         datastore: &DatastoreManager,
         author: &str,
     ) -> Result<Vec<Self>> {
-        let prefix = "/dag/certificates/round";
-        let mut certs = Vec::new();
-        
-        let store = datastore.validator_final();
-        let iterator = store.iterator(prefix);
-        for result in iterator {
-            let (key, _) = result?;
-            let key_str = String::from_utf8(key.to_vec())?;
-            
-            // Parse key to extract round and digest
-            let parts: Vec<&str> = key_str.split('/').collect();
-            if parts.len() >= 6 {
-                if let (Some(round_str), Some(digest)) = (parts.get(4), parts.get(6)) {
-                    let keys = [
-                        ("round".to_string(), round_str.to_string()),
-                        ("digest".to_string(), digest.to_string()),
-                    ].into_iter().collect();
-                    
-                    if let Some(cert) = Self::find_one_from_store(store, keys).await? {
-                        if cert.author == author {
-                            certs.push(cert);
-                        }
-                    }
-                }
-            }
-        }
-        
-        Ok(certs)
+        Self::find_by_index_from_store(datastore.validator_final(), "author", &serde_json::json!(author))
+            .await
+            .map_err(|e| crate::Error::Database(e.to_string()))
     }
     
     /// Find all committed certificates
     pub async fn find_all_committed_multi(
         datastore: &DatastoreManager,
     ) -> Result<Vec<Self>> {
-        let prefix = "/dag/certificates/round";
-        let mut certs = Vec::new();
-        
-        let store = datastore.validator_final();
-        let iterator = store.iterator(prefix);
-        for result in iterator {
-            let (key, _) = result?;
-            let key_str = String::from_utf8(key.to_vec())?;
-            
-            // Parse key to extract round and digest
-            let parts: Vec<&str> = key_str.split('/').collect();
-            if parts.len() >= 6 {
-                if let (Some(round_str), Some(digest)) = (parts.get(4), parts.get(6)) {
-                    let keys = [
-                        ("round".to_string(), round_str.to_string()),
-                        ("digest".to_string(), digest.to_string()),
-                    ].into_iter().collect();
-                    
-                    if let Some(cert) = Self::find_one_from_store(store, keys).await? {
-                        if cert.committed {
-                            certs.push(cert);
-                        }
-                    }
-                }
-            }
-        }
-        
-        Ok(certs)
+        Self::find_by_index_from_store(datastore.validator_final(), "committed", &serde_json::json!(true))
+            .await
+            .map_err(|e| crate::Error::Database(e.to_string()))
     }
     
     /// Mark a certificate as committed
@@ -200,8 +201,62 @@ impl DAGCertificate {
         Ok(())
     }
 
+    /// Mark a certificate as the anchor of its round
+    pub async fn mark_anchor_multi(&mut self, datastore: &DatastoreManager) -> Result<()> {
+        self.anchor = true;
+        self.save_to_final(datastore).await?;
+        Ok(())
+    }
+
     /// Save this certificate to the ValidatorFinal store
     pub async fn save_to_final(&self, datastore: &DatastoreManager) -> Result<()> {
         self.save_to_store(datastore.validator_final()).await.map_err(|e| crate::Error::Database(e.to_string()))
     }
+
+    /// Save certificates to the ValidatorFinal store as one write, as a
+    /// commit of many certificates at once does
+    pub async fn save_all_to_final(datastore: &DatastoreManager, certs: &[Self]) -> Result<()> {
+        let buffer = datastore.validator_final().buffer();
+        for cert in certs {
+            cert.save_to_store(&buffer).await.map_err(|e| crate::Error::Database(e.to_string()))?;
+        }
+        buffer.commit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_cert(digest: &str, round: u64) -> DAGCertificate {
+        DAGCertificate {
+            digest: digest.to_string(),
+            author: "peer".to_string(),
+            round,
+            header: "{}".to_string(),
+            aggregated_signature: "{}".to_string(),
+            signers: vec![true],
+            batch_digests: vec![],
+            parents: vec![],
+            timestamp: 0,
+            committed: false,
+            committed_at_round: None,
+            anchor: false,
+            created_at: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_find_by_digest_prefix() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        create_test_cert("aa11", 1).save_to_final(&mgr).await.unwrap();
+        create_test_cert("aa22", 2).save_to_final(&mgr).await.unwrap();
+        create_test_cert("bb33", 2).save_to_final(&mgr).await.unwrap();
+
+        assert_eq!(DAGCertificate::find_by_digest_prefix_multi(&mgr, "aa").await.unwrap().len(), 2);
+        let found = DAGCertificate::find_by_digest_prefix_multi(&mgr, "bb3").await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].round, 2);
+        assert!(DAGCertificate::find_by_digest_prefix_multi(&mgr, "c").await.unwrap().is_empty());
+    }
 }
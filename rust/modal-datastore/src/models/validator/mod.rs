@@ -11,6 +11,9 @@ pub mod batch;
 pub mod dag_state;
 pub mod consensus_metadata;
 
+// Final ordered output of the sequencer
+pub mod sequenced_log;
+
 #[cfg(test)]
 mod weighted_validators_test;
 
@@ -18,11 +21,12 @@ pub use block_header::ValidatorBlockHeader;
 pub use block_message::ValidatorBlockMessage;
 pub use block::ValidatorBlock;
 pub use validator_set::ValidatorSet;
-pub use validator_selection::{get_validator_set_for_epoch_multi, get_validator_set_for_mining_epoch_hybrid_multi, generate_validator_set_from_epoch_multi};
+pub use validator_selection::{EpochNominations, epoch_nominations_multi, get_validator_set_for_epoch_multi, get_validator_set_for_mining_epoch_hybrid_multi, generate_validator_set_from_epoch_multi};
 
 // Export DAG models
 pub use certificate::DAGCertificate;
 pub use batch::DAGBatch;
 pub use dag_state::DAGState;
 pub use consensus_metadata::ConsensusMetadata;
+pub use sequenced_log::SequencedLog;
 
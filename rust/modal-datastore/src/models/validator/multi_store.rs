@@ -88,6 +88,29 @@ impl ValidatorBlock {
         Ok(blocks.into_iter().filter(|b| b.cert.is_some()).collect())
     }
     
+    /// Highest round with a certified block in either store
+    pub async fn find_latest_certified_round_multi(mgr: &DatastoreManager) -> Result<Option<u64>> {
+        let mut latest = None;
+        let mut consider = |value: &[u8]| -> Result<()> {
+            let block: ValidatorBlock = serde_json::from_slice(value)?;
+            if block.cert.is_some() {
+                latest = latest.max(Some(block.round_id));
+            }
+            Ok(())
+        };
+        
+        for item in mgr.validator_final().iterator(VALIDATOR_BLOCK_PREFIX) {
+            let (_, value) = item?;
+            consider(&value)?;
+        }
+        for item in mgr.validator_active().iterator(VALIDATOR_BLOCK_PREFIX) {
+            let (_, value) = item?;
+            consider(&value)?;
+        }
+        
+        Ok(latest)
+    }
+    
     // ============================================================
     // Multi-store write methods
     // ============================================================
@@ -244,6 +267,18 @@ mod tests {
         assert_eq!(deleted, 1);   // And deleted (5 + 3 <= 10)
     }
     
+    #[tokio::test]
+    async fn test_find_latest_certified_round() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        assert_eq!(ValidatorBlock::find_latest_certified_round_multi(&mgr).await.unwrap(), None);
+        
+        create_test_validator_block("peer1", 9, true).promote_to_final(&mgr).await.unwrap();
+        create_test_validator_block("peer1", 12, true).save_to_active(&mgr).await.unwrap();
+        create_test_validator_block("peer1", 13, false).save_to_active(&mgr).await.unwrap();
+        
+        assert_eq!(ValidatorBlock::find_latest_certified_round_multi(&mgr).await.unwrap(), Some(12));
+    }
+    
     #[tokio::test]
     async fn test_contract_multi_store() {
         let mgr = DatastoreManager::create_in_memory().unwrap();
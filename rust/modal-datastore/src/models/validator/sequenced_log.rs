@@ -0,0 +1,175 @@
+use crate::{DatastoreManager, Result};
+use crate::model::Model;
+use crate::stores::Store;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use async_trait::async_trait;
+
+/// Key prefix of the sequenced log entries
+const SEQUENCED_LOG_PREFIX: &str = "/sequencer/log/seq";
+
+/// Key holding the sequence number the next entry gets
+const SEQUENCED_LOG_HEAD_KEY: &str = "/sequencer/log/head";
+
+/// A committed transaction in the final order, with its position in the
+/// append-only log. Sequence numbers start at 0 and have no gaps.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SequencedLog {
+    // Identity
+    pub seq: u64,
+
+    // Origin
+    pub round: u64,
+    pub author: String,              // PeerId of the batch author
+    pub certificate: String,         // Hex-encoded certificate digest
+    pub batch: String,               // Hex-encoded batch digest
+
+    // Content
+    pub data: String,                // Hex-encoded transaction data
+    pub timestamp: u64,
+
+    // Metadata
+    pub sequenced_at: u64,
+}
+
+#[async_trait]
+impl Model for SequencedLog {
+    const ID_PATH: &'static str = "/sequencer/log/seq/${seq}";
+
+    const FIELDS: &'static [&'static str] = &[
+        "seq",
+        "round",
+        "author",
+        "certificate",
+        "batch",
+        "data",
+        "timestamp",
+        "sequenced_at",
+    ];
+
+    const FIELD_DEFAULTS: &'static [(&'static str, serde_json::Value)] = &[];
+
+    fn set_field(&mut self, field: &str, value: serde_json::Value) {
+        match field {
+            "seq" => self.seq = value.as_u64().unwrap_or_default(),
+            "round" => self.round = value.as_u64().unwrap_or_default(),
+            "author" => self.author = value.as_str().unwrap_or_default().to_string(),
+            "certificate" => self.certificate = value.as_str().unwrap_or_default().to_string(),
+            "batch" => self.batch = value.as_str().unwrap_or_default().to_string(),
+            "data" => self.data = value.as_str().unwrap_or_default().to_string(),
+            "timestamp" => self.timestamp = value.as_u64().unwrap_or_default(),
+            "sequenced_at" => self.sequenced_at = value.as_u64().unwrap_or_default(),
+            _ => {},
+        }
+    }
+
+    fn get_id_keys(&self) -> HashMap<String, String> {
+        Self::seq_keys(self.seq)
+    }
+}
+
+impl SequencedLog {
+    /// Id keys for a sequence number, zero-padded so keys sort in log order
+    fn seq_keys(seq: u64) -> HashMap<String, String> {
+        [("seq".to_string(), format!("{:020}", seq))].into_iter().collect()
+    }
+
+    /// Find the entry at `seq`
+    pub async fn find_by_seq_multi(
+        datastore: &DatastoreManager,
+        seq: u64,
+    ) -> Result<Option<Self>> {
+        Self::find_one_from_store(datastore.validator_final(), Self::seq_keys(seq)).await.map_err(|e| crate::Error::Database(e.to_string()))
+    }
+
+    /// Find up to `limit` consecutive entries starting at `from`
+    pub async fn find_range_multi(
+        datastore: &DatastoreManager,
+        from: u64,
+        limit: usize,
+    ) -> Result<Vec<Self>> {
+        let next_seq = Self::next_seq_multi(datastore).await?;
+        let mut entries = Vec::new();
+        for seq in (from..next_seq).take(limit) {
+            match Self::find_by_seq_multi(datastore, seq).await? {
+                Some(entry) => entries.push(entry),
+                None => break,
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Sequence number the next appended entry gets (the log's length)
+    pub async fn next_seq_multi(datastore: &DatastoreManager) -> Result<u64> {
+        match datastore.validator_final().get(SEQUENCED_LOG_HEAD_KEY)? {
+            Some(value) => Ok(String::from_utf8(value)?.parse()?),
+            None => Ok(0),
+        }
+    }
+
+    /// Append this entry to the log. It must take the next sequence number.
+    pub async fn append_multi(&self, datastore: &DatastoreManager) -> Result<()> {
+        let next_seq = Self::next_seq_multi(datastore).await?;
+        if self.seq != next_seq {
+            return Err(crate::Error::InvalidData(format!(
+                "sequenced log entry {} appended at position {}",
+                self.seq, next_seq
+            )));
+        }
+        let store = datastore.validator_final();
+        self.save_to_store(store).await.map_err(|e| crate::Error::Database(e.to_string()))?;
+        store.put(SEQUENCED_LOG_HEAD_KEY, (self.seq + 1).to_string().as_bytes())?;
+        Ok(())
+    }
+
+    /// Number of entries in the log, counted from the stored entries
+    pub async fn count_multi(datastore: &DatastoreManager) -> Result<usize> {
+        let mut count = 0;
+        for item in datastore.validator_final().iterator(SEQUENCED_LOG_PREFIX) {
+            item?;
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(seq: u64) -> SequencedLog {
+        SequencedLog {
+            seq,
+            round: seq / 2,
+            author: "peer".to_string(),
+            certificate: "cert".to_string(),
+            batch: "batch".to_string(),
+            data: hex::encode([seq as u8]),
+            timestamp: 1000 + seq,
+            sequenced_at: 2000,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sequenced_log_append_and_range() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        assert_eq!(SequencedLog::next_seq_multi(&mgr).await.unwrap(), 0);
+
+        for seq in 0..12 {
+            entry(seq).append_multi(&mgr).await.unwrap();
+        }
+        assert_eq!(SequencedLog::next_seq_multi(&mgr).await.unwrap(), 12);
+        assert_eq!(SequencedLog::count_multi(&mgr).await.unwrap(), 12);
+
+        // Gaps and rewrites are refused
+        assert!(entry(13).append_multi(&mgr).await.is_err());
+        assert!(entry(3).append_multi(&mgr).await.is_err());
+
+        let range = SequencedLog::find_range_multi(&mgr, 8, 10).await.unwrap();
+        let seqs: Vec<u64> = range.iter().map(|e| e.seq).collect();
+        assert_eq!(seqs, vec![8, 9, 10, 11]);
+        assert_eq!(range[2], entry(10));
+        assert!(SequencedLog::find_range_multi(&mgr, 12, 10).await.unwrap().is_empty());
+        assert!(SequencedLog::find_by_seq_multi(&mgr, 12).await.unwrap().is_none());
+    }
+}
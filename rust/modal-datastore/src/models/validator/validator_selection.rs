@@ -64,29 +64,59 @@ pub async fn get_validator_set_for_mining_epoch_hybrid_multi(
     }
 }
 
-/// Generate a validator set from a completed mining epoch (multi-store version)
-pub async fn generate_validator_set_from_epoch_multi(
-    mgr: &DatastoreManager,
-    epoch: u64,
-) -> Result<ValidatorSet> {
+/// What an epoch's validators are seated from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpochNominations {
+    pub epoch: u64,
+    /// Nonces of the epoch's canonical blocks, in block index order
+    pub nonces: Vec<u128>,
+    /// Each block's nominee in block index order, then the credited uncles' nominees
+    pub nominees: Vec<String>,
+    /// Number of nominees that came from uncles
+    pub uncle_nominees: usize,
+}
+
+/// Nominations of a mining epoch, as the shuffle takes them
+pub async fn epoch_nominations_multi(mgr: &DatastoreManager, epoch: u64) -> Result<EpochNominations> {
     let all_blocks = MinerBlock::find_all_canonical_multi(mgr).await?;
+    let uncle_nominees = credited_uncle_nominees(&all_blocks, epoch);
     let epoch_blocks: Vec<_> = all_blocks.into_iter().filter(|b| b.epoch == epoch).collect();
     
     if epoch_blocks.is_empty() {
         anyhow::bail!("No blocks found for epoch {}", epoch);
     }
 
+    let nonces = epoch_blocks.iter().filter_map(|b| b.nonce.parse::<u128>().ok()).collect();
+    let mut nominees: Vec<String> = epoch_blocks.into_iter().map(|b| b.nominated_peer_id).collect();
+    let uncle_count = uncle_nominees.len();
+    nominees.extend(uncle_nominees);
+    Ok(EpochNominations {
+        epoch,
+        nonces,
+        nominees,
+        uncle_nominees: uncle_count,
+    })
+}
+
+/// Generate a validator set from a completed mining epoch (multi-store version)
+pub async fn generate_validator_set_from_epoch_multi(
+    mgr: &DatastoreManager,
+    epoch: u64,
+) -> Result<ValidatorSet> {
+    let nominations = epoch_nominations_multi(mgr, epoch).await?;
+
     // Count nominations for each peer ID (for stakes)
     let mut nomination_counts: std::collections::HashMap<String, u64> = std::collections::HashMap::new();
-    for block in &epoch_blocks {
-        *nomination_counts.entry(block.nominated_peer_id.clone()).or_insert(0) += 1;
+    for peer_id in &nominations.nominees {
+        *nomination_counts.entry(peer_id.clone()).or_insert(0) += 1;
     }
     
     log::info!(
-        "Epoch {} nomination counts: {} unique validators, total {} nominations",
+        "Epoch {} nomination counts: {} unique validators, total {} nominations ({} from uncles)",
         epoch,
         nomination_counts.len(),
-        epoch_blocks.len()
+        nominations.nominees.len(),
+        nominations.uncle_nominees
     );
     
     // Log the nomination distribution
@@ -99,15 +129,18 @@ pub async fn generate_validator_set_from_epoch_multi(
         log::info!("  - {}: {} nominations", short_id, count);
     }
 
-    let seed = calculate_epoch_seed(&epoch_blocks);
-    let peer_ids: Vec<String> = epoch_blocks.iter().map(|b| b.nominated_peer_id.clone()).collect();
-    let shuffled_peer_ids = shuffle_peer_ids(seed, &peer_ids);
+    let seed = calculate_epoch_seed(&nominations.nonces);
+    let shuffled_peer_ids = shuffle_peer_ids(seed, &nominations.nominees);
     
-    // Deduplicate shuffled peer IDs while preserving order
+    // Deduplicate shuffled peer IDs while preserving order, skipping slashed peers
+    let excluded = mgr.excluded_peers(epoch).await?;
+    if !excluded.is_empty() {
+        log::info!("Epoch {}: {} peers excluded by the slashing ledger", epoch, excluded.len());
+    }
     let mut seen = std::collections::HashSet::new();
     let mut unique_shuffled: Vec<String> = Vec::new();
     for peer_id in shuffled_peer_ids {
-        if seen.insert(peer_id.clone()) {
+        if seen.insert(peer_id.clone()) && !excluded.contains(&peer_id) {
             unique_shuffled.push(peer_id);
         }
     }
@@ -130,14 +163,36 @@ pub async fn generate_validator_set_from_epoch_multi(
     ))
 }
 
+/// Nominations of uncles credited to `epoch`
+/// 
+/// An uncle counts once, in the epoch of the first canonical block that
+/// referenced it, and not at all if it later became canonical itself.
+fn credited_uncle_nominees(canonical_blocks: &[MinerBlock], epoch: u64) -> Vec<String> {
+    let canonical_hashes: std::collections::HashSet<&str> =
+        canonical_blocks.iter().map(|b| b.hash.as_str()).collect();
+    let mut sorted: Vec<&MinerBlock> = canonical_blocks.iter().collect();
+    sorted.sort_by_key(|b| b.index);
+    
+    let mut credited = std::collections::HashSet::new();
+    let mut nominees = Vec::new();
+    for block in sorted {
+        for uncle in &block.uncles {
+            if canonical_hashes.contains(uncle.hash.as_str()) || !credited.insert(uncle.hash.as_str()) {
+                continue;
+            }
+            if block.epoch == epoch {
+                nominees.push(uncle.nominated_peer_id.clone());
+            }
+        }
+    }
+    nominees
+}
+
 /// Calculate seed from XOR of all block nonces
-fn calculate_epoch_seed(blocks: &[MinerBlock]) -> u64 {
+fn calculate_epoch_seed(nonces: &[u128]) -> u64 {
     let mut seed = 0u64;
-    for block in blocks {
-        // Parse nonce and XOR with seed
-        if let Ok(nonce) = block.nonce.parse::<u128>() {
-            seed ^= nonce as u64;
-        }
+    for nonce in nonces {
+        seed ^= *nonce as u64;
     }
     seed
 }
@@ -181,10 +236,37 @@ mod tests {
             ),
         ];
         
-        let seed = calculate_epoch_seed(&blocks);
+        let nonces: Vec<u128> = blocks.iter().map(|b| b.nonce.parse().unwrap()).collect();
+        let seed = calculate_epoch_seed(&nonces);
         assert_eq!(seed, 100 ^ 200); // XOR of the two nonces
     }
 
+    #[test]
+    fn test_credited_uncle_nominees() {
+        use modal_common::uncles::UncleRef;
+        let uncle = |hash: &str, peer: &str| UncleRef {
+            hash: hash.to_string(),
+            index: 0,
+            nominated_peer_id: peer.to_string(),
+        };
+        let block = |hash: &str, index: u64, epoch: u64, uncles: Vec<UncleRef>| {
+            MinerBlock::new_canonical(
+                hash.to_string(), index, epoch, 0, "prev".to_string(), "data".to_string(),
+                1, 1000, "miner".to_string(), 0,
+            )
+            .with_uncles(uncles)
+        };
+        let blocks = vec![
+            block("a", 39, 0, vec![uncle("u1", "slow_peer")]),
+            // u1 was already credited in epoch 0; "b" is canonical so not an uncle
+            block("b", 40, 1, vec![uncle("u1", "slow_peer"), uncle("u2", "other_peer")]),
+            block("c", 41, 1, vec![uncle("b", "miner")]),
+        ];
+        
+        assert_eq!(credited_uncle_nominees(&blocks, 0), vec!["slow_peer"]);
+        assert_eq!(credited_uncle_nominees(&blocks, 1), vec!["other_peer"]);
+    }
+
     #[test]
     fn test_shuffle_peer_ids() {
         let peer_ids = vec![
@@ -237,6 +319,44 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_slashed_peers_are_not_seated() {
+        use modal_common::keypair::Keypair;
+        use modal_common::slashing::EquivocationEvidence;
+        use crate::models::ValidatorBlock;
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        let keypairs: Vec<Keypair> = (0..5).map(|_| Keypair::generate().unwrap()).collect();
+        let blocks: Vec<MinerBlock> = (1..=5)
+            .map(|i| MinerBlock::new_canonical(
+                format!("hash{}", i), i, 0, 0, "prev".to_string(), "data".to_string(),
+                i as u128 * 97, 1000, keypairs[i as usize - 1].as_public_address(), 0,
+            ))
+            .collect();
+        MinerBlock::save_all_to_active(&mgr, &blocks).await.unwrap();
+        
+        let slashed = keypairs[2].as_public_address();
+        let before = generate_validator_set_from_epoch_multi(&mgr, 0).await.unwrap();
+        assert!(before.nominated_validators.contains(&slashed));
+        
+        let draft = |event: &str| {
+            let mut block = ValidatorBlock::create_from_json(serde_json::json!({
+                "peer_id": slashed,
+                "round_id": 12,
+                "events": [{ "data": event }],
+            })).unwrap();
+            block.generate_sigs(&keypairs[2]).unwrap();
+            block.to_draft_json_object()
+        };
+        let evidence = EquivocationEvidence::new(slashed.clone(), 12, draft("a"), draft("b"));
+        let mut including = blocks[4].clone();
+        including.evidence = vec![evidence];
+        including.save_to_active(&mgr).await.unwrap();
+        
+        let after = generate_validator_set_from_epoch_multi(&mgr, 0).await.unwrap();
+        let expected: Vec<String> = before.nominated_validators.into_iter().filter(|p| p != &slashed).collect();
+        assert_eq!(after.nominated_validators, expected);
+    }
+
     #[tokio::test]
     async fn test_get_validator_set_for_epoch_without_static_validators() {
         // Create a datastore without static validators
@@ -218,6 +218,9 @@ impl NetworkDatastore {
         let mut validators = Vec::new();
         let mut miner_hash_func: Option<String> = None;
         let mut mining_hash_params: Option<serde_json::Value> = None;
+        let mut eras = Vec::new();
+        let mut commit_gas_quota: Option<u64> = None;
+        let mut epoch_gas_quota: Option<u64> = None;
         
         // Iterate over all keys with the prefix
         for result in self.iterator(&prefix) {
@@ -251,6 +254,15 @@ impl NetworkDatastore {
                     path if path.starts_with("miner_hash_func.") => {
                         miner_hash_func = Some(value_str.clone());
                     }
+                    path if path.starts_with("eras.") => {
+                        eras = serde_json::from_str(&value_str)?;
+                    }
+                    path if path.starts_with("commit_gas_quota.") => {
+                        commit_gas_quota = Some(value_str.parse()?);
+                    }
+                    path if path.starts_with("epoch_gas_quota.") => {
+                        epoch_gas_quota = Some(value_str.parse()?);
+                    }
                     path if path.starts_with("miner_hash_params.") => {
                         // Parse JSON value
                         mining_hash_params = serde_json::from_str(&value_str).ok();
@@ -277,6 +289,9 @@ impl NetworkDatastore {
             validators,
             miner_hash_func: miner_hash_func.unwrap_or_else(|| "randomx".to_string()),
             mining_hash_params,
+            eras,
+            commit_gas_quota,
+            epoch_gas_quota,
         })
     }
 
@@ -1,4 +1,8 @@
+use modal_common::eras::{Era, EraSchedule};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::slashing::SlashingPolicy;
 
 /// Network parameters loaded from the genesis contract
 /// Note: Bootstrappers are NOT included here - they are operational/networking
@@ -13,6 +17,35 @@ pub struct NetworkParameters {
     pub validators: Vec<String>,
     pub miner_hash_func: String,
     pub mining_hash_params: Option<serde_json::Value>,
+    /// Parameter changes activating at later heights
+    #[serde(default)]
+    pub eras: Vec<Era>,
+    /// Most gas one contract commit may use
+    #[serde(default)]
+    pub commit_gas_quota: Option<u64>,
+    /// Most gas one contract may use in an epoch
+    #[serde(default)]
+    pub epoch_gas_quota: Option<u64>,
+    /// Penalties for validator offenses; the default policy if unset
+    #[serde(default)]
+    pub slashing: Option<SlashingPolicy>,
+    /// BLS keys validators sign consensus votes with, by peer ID
+    #[serde(default)]
+    pub validator_bls_keys: BTreeMap<String, ValidatorBlsKey>,
+}
+
+/// A validator's BLS public key and its proof of possession, hex encoded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorBlsKey {
+    pub public_key: String,
+    pub proof_of_possession: String,
+}
+
+/// Gas limits for contract commits; `None` is unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GasQuotas {
+    pub per_commit: Option<u64>,
+    pub per_epoch: Option<u64>,
 }
 
 impl NetworkParameters {
@@ -27,6 +60,25 @@ impl NetworkParameters {
             validators: Vec::new(),
             miner_hash_func: "randomx".to_string(),
             mining_hash_params: None,
+            eras: Vec::new(),
+            commit_gas_quota: None,
+            epoch_gas_quota: None,
+            slashing: None,
+            validator_bls_keys: BTreeMap::new(),
+        }
+    }
+    
+    /// Epoch length and target block time schedule, including eras
+    pub fn era_schedule(&self) -> anyhow::Result<EraSchedule> {
+        EraSchedule::new(self.blocks_per_epoch, self.target_block_time_secs, self.eras.clone())
+            .map_err(|e| anyhow::anyhow!(e))
+    }
+
+    /// Contract gas quotas
+    pub fn gas_quotas(&self) -> GasQuotas {
+        GasQuotas {
+            per_commit: self.commit_gas_quota,
+            per_epoch: self.epoch_gas_quota,
         }
     }
 }
@@ -58,10 +110,31 @@ mod tests {
             validators: vec!["peer1".to_string()],
             miner_hash_func: "randomx".to_string(),
             mining_hash_params: Some(custom_params),
+            eras: Vec::new(),
+            commit_gas_quota: Some(1_000),
+            epoch_gas_quota: None,
+            slashing: None,
+            validator_bls_keys: BTreeMap::new(),
         };
         
         assert_eq!(params.miner_hash_func, "randomx");
         assert!(params.mining_hash_params.is_some());
+        assert_eq!(params.gas_quotas(), GasQuotas { per_commit: Some(1_000), per_epoch: None });
+    }
+
+    #[test]
+    fn test_gas_quotas_default_to_unlimited() {
+        let params: NetworkParameters = serde_json::from_value(serde_json::json!({
+            "name": "devnet",
+            "description": "",
+            "initial_difficulty": 1,
+            "target_block_time_secs": 60,
+            "blocks_per_epoch": 40,
+            "validators": [],
+            "miner_hash_func": "randomx",
+            "mining_hash_params": null,
+        })).unwrap();
+        assert_eq!(params.gas_quotas(), GasQuotas::default());
     }
 }
 
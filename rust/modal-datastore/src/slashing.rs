@@ -0,0 +1,402 @@
+//! Validator slashing ledger
+//!
+//! A validator that signs two different draft blocks for the same round has
+//! equivocated. Nodes that see both drafts verify them and gossip the pair as
+//! [`EquivocationEvidence`]; miners include pending evidence in the blocks
+//! they mine. Offenses are derived from the evidence in canonical blocks
+//! only, so every node with the same chain has the same ledger. The
+//! network's [`SlashingPolicy`] turns offenses into penalties: exclusion from
+//! nominee selection for some epochs, or for good. Validator selection skips
+//! excluded peers when it seats an epoch's nominees, and hybrid consensus
+//! checks seatings against the same ledger.
+//!
+//! Exclusions count in nomination epochs from the epoch of the block that
+//! included the evidence: a peer excluded at epoch N isn't seated from N's
+//! nominations. Validator sets seated before the offense aren't changed.
+
+use crate::models::miner::MinerBlock;
+use crate::models::ValidatorBlock;
+use crate::stores::Store;
+use crate::{DatastoreManager, Error, Result};
+use modal_common::slashing::EquivocationEvidence;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+/// Key of the slashing policy, in node_state
+const SLASHING_POLICY_KEY: &str = "/slashing/policy";
+
+/// Key prefix of verified evidence waiting to be mined, in node_state
+const SLASHING_PENDING_PREFIX: &str = "/slashing/pending";
+
+/// Misbehavior a validator can be slashed for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OffenseKind {
+    /// Signed two different draft blocks for the same round
+    Equivocation,
+}
+
+impl OffenseKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OffenseKind::Equivocation => "equivocation",
+        }
+    }
+}
+
+/// One offense, as committed by a canonical block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Offense {
+    pub kind: OffenseKind,
+    /// Epoch of the block that included the evidence
+    pub epoch: u64,
+    pub round: u64,
+    /// Index of the block that included the evidence
+    pub block_index: u64,
+    pub evidence: EquivocationEvidence,
+}
+
+impl Offense {
+    /// Whether both describe the same misbehavior, whatever the evidence or
+    /// the block that included it
+    pub fn same_as(&self, other: &Offense) -> bool {
+        self.kind == other.kind && self.round == other.round
+    }
+}
+
+/// What an offense costs the offender
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum Penalty {
+    /// Not seated for this many epochs, starting with the offense's
+    Exclusion { epochs: u64 },
+    /// Never seated again
+    Permanent,
+}
+
+/// Penalties the network applies, set in its parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlashingPolicy {
+    pub first_offense: Penalty,
+    /// Penalty for every offense after the first
+    pub repeat_offense: Penalty,
+}
+
+impl Default for SlashingPolicy {
+    fn default() -> Self {
+        Self {
+            first_offense: Penalty::Exclusion { epochs: 4 },
+            repeat_offense: Penalty::Permanent,
+        }
+    }
+}
+
+impl SlashingPolicy {
+    /// Add `offense` to `record` along with the exclusion it earns; returns
+    /// false if the offense was already recorded
+    pub fn apply(&self, record: &mut SlashingRecord, offense: Offense) -> bool {
+        if record.offenses.iter().any(|o| o.same_as(&offense)) {
+            return false;
+        }
+        let penalty = if record.offenses.is_empty() {
+            self.first_offense
+        } else {
+            self.repeat_offense
+        };
+        let until = match penalty {
+            Penalty::Exclusion { epochs } => Some(offense.epoch + epochs),
+            Penalty::Permanent => None,
+        };
+        record.exclusions.push(Exclusion { from: offense.epoch, until });
+        record.offenses.push(offense);
+        true
+    }
+}
+
+/// Epochs a peer may not be seated from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Exclusion {
+    pub from: u64,
+    /// First epoch the peer may be seated from again; `None` is never
+    pub until: Option<u64>,
+}
+
+impl Exclusion {
+    pub fn covers(&self, epoch: u64) -> bool {
+        self.from <= epoch && self.until.is_none_or(|until| epoch < until)
+    }
+}
+
+/// A peer's offenses and the exclusions they earned
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlashingRecord {
+    pub peer_id: String,
+    pub offenses: Vec<Offense>,
+    /// One per offense, in the same order
+    pub exclusions: Vec<Exclusion>,
+}
+
+impl SlashingRecord {
+    pub fn new(peer_id: impl Into<String>) -> Self {
+        Self {
+            peer_id: peer_id.into(),
+            offenses: Vec::new(),
+            exclusions: Vec::new(),
+        }
+    }
+
+    /// Whether the peer may not be seated from `epoch`'s nominations
+    pub fn excludes(&self, epoch: u64) -> bool {
+        self.exclusions.iter().any(|exclusion| exclusion.covers(epoch))
+    }
+
+    /// Whether the peer is never seated again
+    pub fn is_permanent(&self) -> bool {
+        self.exclusions.iter().any(|exclusion| exclusion.until.is_none())
+    }
+}
+
+fn pending_key(peer_id: &str, round: u64) -> String {
+    format!("{}/{}/{}", SLASHING_PENDING_PREFIX, peer_id, round)
+}
+
+/// Check that evidence holds two validly signed, different drafts from its
+/// peer for its round
+pub fn verify_evidence(evidence: &EquivocationEvidence) -> Result<()> {
+    evidence.check().map_err(Error::InvalidData)?;
+    for draft in [&evidence.first, &evidence.second] {
+        let block = ValidatorBlock::create_from_json(draft.clone())?;
+        if block.peer_id != evidence.peer_id || block.round_id != evidence.round {
+            return Err(Error::InvalidData(format!("draft is not from {} for round {}", evidence.peer_id, evidence.round)));
+        }
+        if !block.validate_sigs()? {
+            return Err(Error::InvalidData(format!("draft from {} for round {} has invalid signatures", evidence.peer_id, evidence.round)));
+        }
+    }
+    Ok(())
+}
+
+impl DatastoreManager {
+    /// The network's slashing policy, the default unless stored
+    pub fn slashing_policy(&self) -> Result<SlashingPolicy> {
+        match self.node_state().get(SLASHING_POLICY_KEY)? {
+            Some(data) => Ok(serde_json::from_slice(&data)?),
+            None => Ok(SlashingPolicy::default()),
+        }
+    }
+
+    pub fn store_slashing_policy(&self, policy: &SlashingPolicy) -> Result<()> {
+        self.node_state().put(SLASHING_POLICY_KEY, &serde_json::to_vec(policy)?)
+    }
+
+    /// Keep verified evidence until a mined block includes it; returns false
+    /// if it was already pending
+    pub fn add_pending_evidence(&self, evidence: &EquivocationEvidence) -> Result<bool> {
+        let key = pending_key(&evidence.peer_id, evidence.round);
+        if self.node_state().get(&key)?.is_some() {
+            return Ok(false);
+        }
+        self.node_state().put(&key, &serde_json::to_vec(evidence)?)?;
+        Ok(true)
+    }
+
+    /// Evidence waiting to be mined, oldest round first per peer
+    pub fn pending_evidence(&self) -> Result<Vec<EquivocationEvidence>> {
+        let mut evidence = Vec::new();
+        for item in self.node_state().iterator(SLASHING_PENDING_PREFIX) {
+            let (_, value) = item?;
+            evidence.push(serde_json::from_slice(&value)?);
+        }
+        Ok(evidence)
+    }
+
+    /// Offenses committed by canonical blocks, in chain order
+    pub async fn committed_offenses(&self) -> Result<Vec<(String, Offense)>> {
+        let mut offenses = Vec::new();
+        for block in MinerBlock::find_all_canonical_multi(self).await? {
+            for evidence in block.evidence {
+                offenses.push((
+                    evidence.peer_id.clone(),
+                    Offense {
+                        kind: OffenseKind::Equivocation,
+                        epoch: block.epoch,
+                        round: evidence.round,
+                        block_index: block.index,
+                        evidence,
+                    },
+                ));
+            }
+        }
+        Ok(offenses)
+    }
+
+    /// Whether a canonical block already included evidence of this offense
+    pub async fn is_offense_committed(&self, peer_id: &str, round: u64) -> Result<bool> {
+        Ok(self
+            .committed_offenses()
+            .await?
+            .iter()
+            .any(|(peer, offense)| peer == peer_id && offense.round == round))
+    }
+
+    /// Every peer with committed offenses, under the slashing policy
+    pub async fn slashing_records(&self) -> Result<Vec<SlashingRecord>> {
+        let policy = self.slashing_policy()?;
+        let mut records: BTreeMap<String, SlashingRecord> = BTreeMap::new();
+        for (peer_id, offense) in self.committed_offenses().await? {
+            let record = records
+                .entry(peer_id.clone())
+                .or_insert_with(|| SlashingRecord::new(peer_id));
+            policy.apply(record, offense);
+        }
+        Ok(records.into_values().collect())
+    }
+
+    /// A peer's slashing record, if it has committed offenses
+    pub async fn slashing_record(&self, peer_id: &str) -> Result<Option<SlashingRecord>> {
+        Ok(self
+            .slashing_records()
+            .await?
+            .into_iter()
+            .find(|record| record.peer_id == peer_id))
+    }
+
+    /// Peers that may not be seated from `epoch`'s nominations
+    pub async fn excluded_peers(&self, epoch: u64) -> Result<BTreeSet<String>> {
+        Ok(self
+            .slashing_records()
+            .await?
+            .into_iter()
+            .filter(|record| record.excludes(epoch))
+            .map(|record| record.peer_id)
+            .collect())
+    }
+
+    /// Pending evidence no canonical block has included yet, at most `limit`
+    pub async fn evidence_to_include(&self, limit: usize) -> Result<Vec<EquivocationEvidence>> {
+        let committed: HashSet<(String, u64)> = self
+            .committed_offenses()
+            .await?
+            .into_iter()
+            .map(|(peer_id, offense)| (peer_id, offense.round))
+            .collect();
+        Ok(self
+            .pending_evidence()?
+            .into_iter()
+            .filter(|evidence| !committed.contains(&(evidence.peer_id.clone(), evidence.round)))
+            .take(limit)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use modal_common::keypair::Keypair;
+
+    fn signed_draft(keypair: &Keypair, round: u64, event: &str) -> serde_json::Value {
+        let mut block = ValidatorBlock::create_from_json(serde_json::json!({
+            "peer_id": keypair.as_public_address(),
+            "round_id": round,
+            "events": [],
+        }))
+        .unwrap();
+        block.add_event(serde_json::json!({ "data": event }));
+        block.generate_sigs(keypair).unwrap();
+        block.to_draft_json_object()
+    }
+
+    fn equivocation(keypair: &Keypair, round: u64) -> EquivocationEvidence {
+        EquivocationEvidence::new(
+            keypair.as_public_address(),
+            round,
+            signed_draft(keypair, round, "a"),
+            signed_draft(keypair, round, "b"),
+        )
+    }
+
+    async fn mine(mgr: &DatastoreManager, index: u64, epoch: u64, evidence: Vec<EquivocationEvidence>) {
+        MinerBlock::new_canonical(
+            format!("hash{}", index), index, epoch, 0, "prev".to_string(), "data".to_string(),
+            index as u128, 1000, "peer".to_string(), 0,
+        )
+        .with_evidence(evidence)
+        .save_to_active(mgr)
+        .await
+        .unwrap();
+    }
+
+    #[test]
+    fn test_evidence_needs_valid_signatures() {
+        let keypair = Keypair::generate().unwrap();
+        let evidence = equivocation(&keypair, 3);
+        assert!(verify_evidence(&evidence).is_ok());
+
+        // Drafts signed by someone else don't implicate the peer
+        let other = Keypair::generate().unwrap();
+        let mut forged = equivocation(&other, 3);
+        forged.peer_id = keypair.as_public_address();
+        assert!(verify_evidence(&forged).is_err());
+
+        let mut tampered = evidence.clone();
+        tampered.second["events"] = serde_json::json!([{ "data": "c" }]);
+        assert!(verify_evidence(&tampered).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_policy_excludes_then_bans_repeat_offenders() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        let keypair = Keypair::generate().unwrap();
+        let peer = keypair.as_public_address();
+        assert!(mgr.excluded_peers(5).await.unwrap().is_empty());
+
+        // Pending evidence excludes no one until a canonical block includes it
+        let first = equivocation(&keypair, 120);
+        assert!(mgr.add_pending_evidence(&first).unwrap());
+        assert!(!mgr.add_pending_evidence(&first).unwrap());
+        assert!(mgr.excluded_peers(5).await.unwrap().is_empty());
+        assert_eq!(mgr.evidence_to_include(2).await.unwrap(), vec![first.clone()]);
+
+        mine(&mgr, 200, 5, vec![first.clone()]).await;
+        assert!(mgr.evidence_to_include(2).await.unwrap().is_empty());
+        let record = mgr.slashing_record(&peer).await.unwrap().unwrap();
+        assert_eq!(record.exclusions, vec![Exclusion { from: 5, until: Some(9) }]);
+        assert!(!record.excludes(4));
+        assert!(record.excludes(5) && record.excludes(8));
+        assert!(!record.excludes(9));
+
+        // The same equivocation included again isn't a second offense
+        mine(&mgr, 201, 5, vec![first]).await;
+        assert_eq!(mgr.slashing_record(&peer).await.unwrap().unwrap().offenses.len(), 1);
+        assert_eq!(mgr.excluded_peers(9).await.unwrap(), BTreeSet::new());
+
+        mine(&mgr, 480, 12, vec![equivocation(&keypair, 150)]).await;
+        let record = mgr.slashing_record(&peer).await.unwrap().unwrap();
+        assert_eq!(record.offenses.len(), 2);
+        assert!(record.is_permanent());
+        assert_eq!(mgr.excluded_peers(100).await.unwrap(), BTreeSet::from([peer]));
+        // Epochs between the two exclusions stay as they were
+        assert!(!record.excludes(10));
+        assert_eq!(mgr.slashing_records().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_stored_policy_applies() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        let policy = SlashingPolicy {
+            first_offense: Penalty::Permanent,
+            repeat_offense: Penalty::Permanent,
+        };
+        mgr.store_slashing_policy(&policy).unwrap();
+        assert_eq!(mgr.slashing_policy().unwrap(), policy);
+
+        let keypair = Keypair::generate().unwrap();
+        mine(&mgr, 80, 2, vec![equivocation(&keypair, 40)]).await;
+        assert!(mgr.excluded_peers(1).await.unwrap().is_empty());
+        assert!(mgr.excluded_peers(2).await.unwrap().contains(&keypair.as_public_address()));
+
+        let json = serde_json::to_value(SlashingPolicy::default()).unwrap();
+        assert_eq!(json["first_offense"], serde_json::json!({"type": "exclusion", "epochs": 4}));
+        assert_eq!(json["repeat_offense"], serde_json::json!({"type": "permanent"}));
+    }
+}
@@ -0,0 +1,504 @@
+//! Storage engines behind the stores
+//!
+//! Every store keeps its data in a `StoreBackend`. The engine is chosen per
+//! store in `StorageConfig`:
+//! - `rocksdb`: a RocksDB database in the store's own directory (default)
+//! - `rocksdb_cf`: a column family of one RocksDB database shared by all the
+//!   stores configured this way, so they share a write-ahead log and caches
+//! - `memory`: an in-memory map, lost on exit, for tests and throwaway nodes
+//!
+//! How durable each write is on the RocksDB engines is set by `Durability`.
+//! Writers that arrive while another write is in progress are committed
+//! together with the next one (group commit), so with `sync` durability a
+//! burst of writes shares one fsync instead of queueing behind one each.
+
+use crate::Result;
+use rocksdb::{IteratorMode, ReadOptions, DB};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+
+/// A key and its value
+pub type KvPair = (Box<[u8]>, Box<[u8]>);
+
+/// Puts and deletes applied together by `StoreBackend::write`
+#[derive(Debug, Clone, Default)]
+pub struct WriteBatch {
+    ops: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+}
+
+impl WriteBatch {
+    pub fn put<K: AsRef<[u8]>, V: AsRef<[u8]>>(&mut self, key: K, value: V) {
+        self.ops.push((key.as_ref().to_vec(), Some(value.as_ref().to_vec())));
+    }
+
+    pub fn delete<K: AsRef<[u8]>>(&mut self, key: K) {
+        self.ops.push((key.as_ref().to_vec(), None));
+    }
+
+    /// The puts (`Some` value) and deletes, in order
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], Option<&[u8]>)> {
+        self.ops.iter().map(|(key, value)| (key.as_slice(), value.as_deref()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+}
+
+/// Key-value engine a store keeps its data in
+pub trait StoreBackend: Send + Sync {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>>;
+
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<()>;
+
+    fn delete(&self, key: &[u8]) -> Result<()>;
+
+    /// Apply the batch atomically
+    fn write(&self, batch: WriteBatch) -> Result<()>;
+
+    /// Entries with keys from `lower` (inclusive) to `upper` (exclusive), in
+    /// key order. A missing bound is open.
+    fn range<'a>(&'a self, lower: Option<&[u8]>, upper: Option<&[u8]>) -> Box<dyn Iterator<Item = Result<KvPair>> + Send + 'a>;
+
+    fn flush(&self) -> Result<()>;
+}
+
+/// Storage engine of a store
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StorageEngine {
+    #[default]
+    #[serde(rename = "rocksdb")]
+    RocksDb,
+    #[serde(rename = "rocksdb_cf")]
+    RocksDbColumnFamily,
+    #[serde(rename = "memory")]
+    Memory,
+}
+
+/// When a write reaches disk on the RocksDB engines
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Durability {
+    /// The write-ahead log is fsynced before the write returns; nothing
+    /// acknowledged is lost on power failure
+    Sync,
+    /// The write-ahead log is written but the OS decides when to fsync it;
+    /// survives a crash of the process but not of the machine
+    #[default]
+    Wal,
+    /// No write-ahead log; writes reach disk when the stores are flushed
+    /// (every `flush_interval_ms`, and on shutdown). The fastest, and a
+    /// crash loses everything since the last flush.
+    Deferred,
+}
+
+/// Storage engine of each store
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageConfig {
+    /// Engine of the stores not listed in `stores`
+    #[serde(default)]
+    pub engine: StorageEngine,
+    /// Engine by store name, e.g. `"validator_active": "memory"`
+    #[serde(default)]
+    pub stores: HashMap<String, StorageEngine>,
+    /// Durability of writes to every store; can change between opens
+    #[serde(default)]
+    pub durability: Durability,
+    /// How often `deferred` stores are flushed to disk (default 1000)
+    #[serde(default)]
+    pub flush_interval_ms: Option<u64>,
+}
+
+/// Flush interval of `deferred` stores when the config doesn't set one
+pub const DEFAULT_FLUSH_INTERVAL_MS: u64 = 1000;
+
+impl StorageConfig {
+    /// Engine of the named store
+    pub fn engine_for(&self, store: &str) -> StorageEngine {
+        self.stores.get(store).copied().unwrap_or(self.engine)
+    }
+
+    /// Whether both configs put every store on the same engine
+    pub fn same_engines(&self, other: &StorageConfig) -> bool {
+        crate::datastore_manager::STORE_NAMES.iter().all(|name| self.engine_for(name) == other.engine_for(name))
+    }
+
+    /// How often to flush the stores, if writes skip the write-ahead log
+    pub fn flush_interval(&self) -> Option<std::time::Duration> {
+        (self.durability == Durability::Deferred)
+            .then(|| std::time::Duration::from_millis(self.flush_interval_ms.unwrap_or(DEFAULT_FLUSH_INTERVAL_MS)))
+    }
+}
+
+fn write_options(durability: Durability) -> rocksdb::WriteOptions {
+    let mut opts = rocksdb::WriteOptions::default();
+    match durability {
+        Durability::Sync => opts.set_sync(true),
+        Durability::Wal => {}
+        Durability::Deferred => opts.disable_wal(true),
+    }
+    opts
+}
+
+/// Batches queued by writers waiting on the commit in progress
+#[derive(Default)]
+struct CommitQueue {
+    pending: Vec<WriteBatch>,
+    /// Ticket of the next batch queued
+    next_ticket: u64,
+    /// Tickets below this have been committed (or failed)
+    committed: u64,
+    /// Whether a writer is committing
+    leader: bool,
+    /// Errors of failed tickets, until their writers collect them
+    failed: HashMap<u64, String>,
+}
+
+/// Group commit: the first writer commits its batch and every batch queued
+/// while it did, as one write; the others wait for it
+#[derive(Default)]
+struct GroupCommit {
+    queue: Mutex<CommitQueue>,
+    done: Condvar,
+}
+
+impl GroupCommit {
+    fn write(&self, batch: WriteBatch, commit: impl Fn(WriteBatch) -> Result<()>) -> Result<()> {
+        let mut queue = self.queue.lock().unwrap();
+        let ticket = queue.next_ticket;
+        queue.next_ticket += 1;
+        queue.pending.push(batch);
+        while queue.leader && queue.committed <= ticket {
+            queue = self.done.wait(queue).unwrap();
+        }
+        if queue.committed <= ticket {
+            queue.leader = true;
+            while !queue.pending.is_empty() {
+                let group = std::mem::take(&mut queue.pending);
+                let (first, end) = (queue.committed, queue.next_ticket);
+                drop(queue);
+                let mut merged = WriteBatch::default();
+                for batch in group {
+                    merged.ops.extend(batch.ops);
+                }
+                let result = commit(merged);
+                queue = self.queue.lock().unwrap();
+                if let Err(e) = result {
+                    let e = e.to_string();
+                    queue.failed.extend((first..end).map(|ticket| (ticket, e.clone())));
+                }
+                queue.committed = end;
+                self.done.notify_all();
+            }
+            queue.leader = false;
+        }
+        match queue.failed.remove(&ticket) {
+            Some(e) => Err(crate::Error::Database(e)),
+            None => Ok(()),
+        }
+    }
+}
+
+fn range_options(lower: Option<&[u8]>, upper: Option<&[u8]>) -> ReadOptions {
+    let mut readopts = ReadOptions::default();
+    if let Some(lower) = lower {
+        readopts.set_iterate_lower_bound(lower);
+    }
+    if let Some(upper) = upper {
+        readopts.set_iterate_upper_bound(upper);
+    }
+    readopts
+}
+
+/// A RocksDB database of its own
+pub struct RocksDbBackend {
+    db: DB,
+    durability: Durability,
+    commits: GroupCommit,
+}
+
+impl RocksDbBackend {
+    /// Open or create the database at `path`
+    pub fn open(path: &Path) -> Result<Self> {
+        Ok(Self::new(super::open_store(path)?))
+    }
+
+    /// Open the database at `path` in read-only mode
+    pub fn open_readonly(path: &Path) -> Result<Self> {
+        Ok(Self::new(super::open_store_readonly(path)?))
+    }
+
+    fn new(db: DB) -> Self {
+        Self { db, durability: Durability::default(), commits: GroupCommit::default() }
+    }
+
+    pub fn with_durability(mut self, durability: Durability) -> Self {
+        self.durability = durability;
+        self
+    }
+}
+
+impl StoreBackend for RocksDbBackend {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.db.get(key)?.map(|value| value.to_vec()))
+    }
+
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.db.put_opt(key, value, &write_options(self.durability))?;
+        Ok(())
+    }
+
+    fn delete(&self, key: &[u8]) -> Result<()> {
+        self.db.delete_opt(key, &write_options(self.durability))?;
+        Ok(())
+    }
+
+    fn write(&self, batch: WriteBatch) -> Result<()> {
+        self.commits.write(batch, |batch| {
+            let mut rocks_batch = rocksdb::WriteBatch::default();
+            for (key, value) in batch.ops {
+                match value {
+                    Some(value) => rocks_batch.put(key, value),
+                    None => rocks_batch.delete(key),
+                }
+            }
+            self.db.write_opt(rocks_batch, &write_options(self.durability))?;
+            Ok(())
+        })
+    }
+
+    fn range<'a>(&'a self, lower: Option<&[u8]>, upper: Option<&[u8]>) -> Box<dyn Iterator<Item = Result<KvPair>> + Send + 'a> {
+        let iter = self.db.iterator_opt(IteratorMode::Start, range_options(lower, upper));
+        Box::new(iter.map(|item| item.map_err(Into::into)))
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.db.flush()?;
+        Ok(())
+    }
+}
+
+/// A column family of a RocksDB database shared with other stores
+pub struct RocksDbColumnFamilyBackend {
+    db: Arc<DB>,
+    cf: String,
+    durability: Durability,
+    commits: GroupCommit,
+}
+
+impl RocksDbColumnFamilyBackend {
+    /// Open or create the database at `path` with a column family per name
+    pub fn open_shared(path: &Path, column_families: &[&str]) -> Result<Arc<DB>> {
+        let mut opts = super::default_db_options();
+        opts.create_missing_column_families(true);
+        Ok(Arc::new(DB::open_cf(&opts, path, column_families)?))
+    }
+
+    /// The `cf` column family of a database from `open_shared`
+    pub fn new(db: Arc<DB>, cf: &str) -> Result<Self> {
+        if db.cf_handle(cf).is_none() {
+            return Err(crate::Error::Database(format!("Column family {} is not open", cf)));
+        }
+        Ok(Self { db, cf: cf.to_string(), durability: Durability::default(), commits: GroupCommit::default() })
+    }
+
+    pub fn with_durability(mut self, durability: Durability) -> Self {
+        self.durability = durability;
+        self
+    }
+
+    fn handle(&self) -> &rocksdb::ColumnFamily {
+        // Checked in `new`, and column families are never dropped
+        self.db.cf_handle(&self.cf).expect("column family is open")
+    }
+}
+
+impl StoreBackend for RocksDbColumnFamilyBackend {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.db.get_cf(self.handle(), key)?.map(|value| value.to_vec()))
+    }
+
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.db.put_cf_opt(self.handle(), key, value, &write_options(self.durability))?;
+        Ok(())
+    }
+
+    fn delete(&self, key: &[u8]) -> Result<()> {
+        self.db.delete_cf_opt(self.handle(), key, &write_options(self.durability))?;
+        Ok(())
+    }
+
+    fn write(&self, batch: WriteBatch) -> Result<()> {
+        self.commits.write(batch, |batch| {
+            let cf = self.handle();
+            let mut rocks_batch = rocksdb::WriteBatch::default();
+            for (key, value) in batch.ops {
+                match value {
+                    Some(value) => rocks_batch.put_cf(cf, key, value),
+                    None => rocks_batch.delete_cf(cf, key),
+                }
+            }
+            self.db.write_opt(rocks_batch, &write_options(self.durability))?;
+            Ok(())
+        })
+    }
+
+    fn range<'a>(&'a self, lower: Option<&[u8]>, upper: Option<&[u8]>) -> Box<dyn Iterator<Item = Result<KvPair>> + Send + 'a> {
+        let iter = self.db.iterator_cf_opt(self.handle(), range_options(lower, upper), IteratorMode::Start);
+        Box::new(iter.map(|item| item.map_err(Into::into)))
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.db.flush_cf(self.handle())?;
+        Ok(())
+    }
+}
+
+/// An in-memory map
+#[derive(Default)]
+pub struct MemoryBackend {
+    map: RwLock<BTreeMap<Vec<u8>, Vec<u8>>>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl StoreBackend for MemoryBackend {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.map.read().unwrap().get(key).cloned())
+    }
+
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.map.write().unwrap().insert(key.to_vec(), value.to_vec());
+        Ok(())
+    }
+
+    fn delete(&self, key: &[u8]) -> Result<()> {
+        self.map.write().unwrap().remove(key);
+        Ok(())
+    }
+
+    fn write(&self, batch: WriteBatch) -> Result<()> {
+        let mut map = self.map.write().unwrap();
+        for (key, value) in batch.ops {
+            match value {
+                Some(value) => map.insert(key, value),
+                None => map.remove(&key),
+            };
+        }
+        Ok(())
+    }
+
+    fn range<'a>(&'a self, lower: Option<&[u8]>, upper: Option<&[u8]>) -> Box<dyn Iterator<Item = Result<KvPair>> + Send + 'a> {
+        use std::ops::Bound;
+        let lower = lower.map_or(Bound::Unbounded, |key| Bound::Included(key.to_vec()));
+        let upper = upper.map_or(Bound::Unbounded, |key| Bound::Excluded(key.to_vec()));
+        // Copy the range out so writes can proceed while it's iterated
+        let entries: Vec<KvPair> = match (&lower, &upper) {
+            (Bound::Included(l), Bound::Excluded(u)) if l >= u => Vec::new(),
+            _ => self.map.read().unwrap()
+                .range((lower, upper))
+                .map(|(key, value)| (key.clone().into_boxed_slice(), value.clone().into_boxed_slice()))
+                .collect(),
+        };
+        Box::new(entries.into_iter().map(Ok))
+    }
+
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exercise(backend: &dyn StoreBackend) {
+        backend.put(b"/a/1", b"one").unwrap();
+        backend.put(b"/a/2", b"two").unwrap();
+        backend.put(b"/b/1", b"other").unwrap();
+
+        let mut batch = WriteBatch::default();
+        batch.put(b"/a/3", b"three");
+        batch.delete(b"/a/1");
+        backend.write(batch).unwrap();
+
+        assert_eq!(backend.get(b"/a/1").unwrap(), None);
+        assert_eq!(backend.get(b"/a/3").unwrap().as_deref(), Some(&b"three"[..]));
+        let keys: Vec<Vec<u8>> = backend.range(Some(b"/a/"), Some(b"/a0"))
+            .map(|item| item.unwrap().0.to_vec())
+            .collect();
+        assert_eq!(keys, vec![b"/a/2".to_vec(), b"/a/3".to_vec()]);
+        assert_eq!(backend.range(None, None).count(), 3);
+        backend.delete(b"/b/1").unwrap();
+        assert_eq!(backend.range(None, None).count(), 2);
+    }
+
+    #[test]
+    fn test_backends_behave_alike() {
+        exercise(&MemoryBackend::new());
+
+        let dir = tempfile::tempdir().unwrap();
+        exercise(&RocksDbBackend::open(&dir.path().join("db")).unwrap());
+
+        let shared = RocksDbColumnFamilyBackend::open_shared(&dir.path().join("shared"), &["x", "y"]).unwrap();
+        let x = RocksDbColumnFamilyBackend::new(shared.clone(), "x").unwrap();
+        let y = RocksDbColumnFamilyBackend::new(shared.clone(), "y").unwrap();
+        exercise(&x);
+        // Column families don't see each other's keys
+        assert_eq!(y.range(None, None).count(), 0);
+        assert!(RocksDbColumnFamilyBackend::new(shared, "z").is_err());
+    }
+
+    #[test]
+    fn test_concurrent_writes_group_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = Arc::new(RocksDbBackend::open(&dir.path().join("db")).unwrap().with_durability(Durability::Sync));
+        let writers: Vec<_> = (0..8)
+            .map(|writer| {
+                let backend = backend.clone();
+                std::thread::spawn(move || {
+                    for i in 0..20 {
+                        let mut batch = WriteBatch::default();
+                        batch.put(format!("/w/{}/{}", writer, i), b"x");
+                        backend.write(batch).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        assert_eq!(backend.range(None, None).count(), 160);
+        let queue = backend.commits.queue.lock().unwrap();
+        assert_eq!(queue.committed, 160);
+        assert!(!queue.leader && queue.failed.is_empty());
+    }
+
+    #[test]
+    fn test_engine_per_store() {
+        let config: StorageConfig = serde_json::from_value(serde_json::json!({
+            "engine": "rocksdb_cf",
+            "stores": { "validator_active": "memory" }
+        }))
+        .unwrap();
+        assert_eq!(config.engine_for("validator_active"), StorageEngine::Memory);
+        assert_eq!(config.engine_for("miner_canon"), StorageEngine::RocksDbColumnFamily);
+        assert_eq!(StorageConfig::default().engine_for("node_state"), StorageEngine::RocksDb);
+        assert_eq!(config.durability, Durability::Wal);
+        assert_eq!(config.flush_interval(), None);
+        // Durability can change without changing the layout
+        let deferred = StorageConfig { durability: Durability::Deferred, ..config.clone() };
+        assert!(deferred.same_engines(&config));
+        assert!(deferred.flush_interval().is_some());
+    }
+}
@@ -0,0 +1,209 @@
+//! Read-through cache of deserialized models
+//!
+//! Status rendering, sync and consensus load the same recent blocks,
+//! certificates and contracts over and over, and each load parses the
+//! record's JSON again. Stores holding those models keep the most recently
+//! loaded ones here, by record key, for models that opt in with
+//! `Model::CACHED`. Every write through the store drops the keys it touches,
+//! so a cached model is never older than the record.
+
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+/// Models kept per store when the store doesn't say otherwise
+pub const DEFAULT_CACHE_CAPACITY: usize = 4096;
+
+type Cached = Arc<dyn Any + Send + Sync>;
+
+#[derive(Default)]
+struct Entries {
+    /// Model and last use, by record key
+    models: HashMap<Vec<u8>, (Cached, u64)>,
+    /// Record keys by last use, oldest first
+    by_use: BTreeMap<u64, Vec<u8>>,
+    clock: u64,
+}
+
+/// Hits and misses of a cache since it was created
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub capacity: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    /// Share of loads answered from the cache
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            total => self.hits as f64 / total as f64,
+        }
+    }
+
+    /// Stats of several caches together
+    pub fn sum(stats: impl IntoIterator<Item = CacheStats>) -> CacheStats {
+        stats.into_iter().fold(CacheStats::default(), |total, s| CacheStats {
+            entries: total.entries + s.entries,
+            capacity: total.capacity + s.capacity,
+            hits: total.hits + s.hits,
+            misses: total.misses + s.misses,
+            evictions: total.evictions + s.evictions,
+        })
+    }
+}
+
+/// Least recently used models of a store, by record key
+pub struct ModelCache {
+    entries: Mutex<Entries>,
+    capacity: usize,
+    /// Bumped by every invalidation, so a load that raced a write doesn't
+    /// cache what it read before the write
+    generation: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl Default for ModelCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_CAPACITY)
+    }
+}
+
+impl ModelCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(Entries::default()),
+            capacity,
+            generation: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    /// The model cached under `key`, if it is a `T`
+    pub fn get<T: Clone + 'static>(&self, key: &[u8]) -> Option<T> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.clock += 1;
+        let now = entries.clock;
+        let found = match entries.models.get_mut(key) {
+            Some((model, last_use)) => model.downcast_ref::<T>().cloned().map(|model| (model, std::mem::replace(last_use, now))),
+            None => None,
+        };
+        match found {
+            Some((model, previous_use)) => {
+                entries.by_use.remove(&previous_use);
+                entries.by_use.insert(now, key.to_vec());
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(model)
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// The generation to pass to `insert` for a load starting now
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Cache a model loaded from `key`, unless the store was written since
+    /// `generation`
+    pub fn insert<T: Send + Sync + 'static>(&self, key: &[u8], model: T, generation: u64) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if self.generation.load(Ordering::Acquire) != generation {
+            return;
+        }
+        entries.clock += 1;
+        let now = entries.clock;
+        if let Some((_, previous_use)) = entries.models.insert(key.to_vec(), (Arc::new(model), now)) {
+            entries.by_use.remove(&previous_use);
+        }
+        entries.by_use.insert(now, key.to_vec());
+        while entries.models.len() > self.capacity {
+            let Some((_, oldest)) = entries.by_use.pop_first() else { break };
+            entries.models.remove(&oldest);
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Drop the models cached under `keys`, which are being written
+    pub fn invalidate<'a>(&self, keys: impl IntoIterator<Item = &'a [u8]>) {
+        let mut entries = self.entries.lock().unwrap();
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        for key in keys {
+            if let Some((_, last_use)) = entries.models.remove(key) {
+                entries.by_use.remove(&last_use);
+            }
+        }
+    }
+
+    /// Drop every cached model
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        entries.models.clear();
+        entries.by_use.clear();
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.entries.lock().unwrap().models.len(),
+            capacity: self.capacity,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_least_recently_used_are_evicted() {
+        let cache = ModelCache::new(2);
+        let generation = cache.generation();
+        cache.insert(b"/a", "a".to_string(), generation);
+        cache.insert(b"/b", "b".to_string(), generation);
+        // Using /a makes /b the oldest
+        assert_eq!(cache.get::<String>(b"/a"), Some("a".to_string()));
+        cache.insert(b"/c", "c".to_string(), generation);
+        assert_eq!(cache.get::<String>(b"/b"), None);
+        assert_eq!(cache.get::<String>(b"/c"), Some("c".to_string()));
+        // Another type under the same key isn't returned
+        assert_eq!(cache.get::<u64>(b"/c"), None);
+
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.hits, stats.misses, stats.evictions), (2, 2, 2, 1));
+        assert_eq!(stats.hit_rate(), 0.5);
+    }
+
+    #[test]
+    fn test_writes_invalidate() {
+        let cache = ModelCache::new(10);
+        cache.insert(b"/a", 1u64, cache.generation());
+        cache.invalidate([&b"/a"[..]]);
+        assert_eq!(cache.get::<u64>(b"/a"), None);
+
+        // A load that started before a write isn't cached
+        let generation = cache.generation();
+        cache.invalidate([&b"/b"[..]]);
+        cache.insert(b"/b", 2u64, generation);
+        assert_eq!(cache.get::<u64>(b"/b"), None);
+    }
+}
@@ -0,0 +1,399 @@
+//! Index store - denormalized indexes kept by indexer nodes
+//!
+//! Validators answer what they need from the models in the other stores.
+//! Indexer nodes also keep lookups by who and what a commit touched, so
+//! they can answer questions like "contracts signed by key X":
+//!
+//! - contracts by the keys that signed their commits
+//! - commits by the path of each action, per contract
+//! - asset balances by owner, from the create, send and recv actions
+//!
+//! The index is local and derived: it can be dropped and rebuilt from the
+//! commits at any time. Nodes that don't index leave it empty.
+
+use crate::Result;
+use crate::stores::{MemoryBackend, RocksDbBackend, Store, StoreBackend, WriteBatch};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Set once a node starts indexing
+const ENABLED_KEY: &str = "/index/meta/enabled";
+/// Number of commits indexed
+const COMMIT_COUNT_KEY: &str = "/index/meta/commits";
+
+/// A commit action found under a path
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedCommit {
+    pub contract_id: String,
+    pub commit_id: String,
+    pub path: String,
+    pub method: String,
+    pub timestamp: u64,
+}
+
+/// What an owner contract holds of an asset
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedBalance {
+    pub owner_contract_id: String,
+    /// Contract that created the asset
+    pub contract_id: String,
+    pub asset_id: String,
+    pub balance: u64,
+}
+
+/// A send waiting to be received
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct IndexedSend {
+    from_contract: String,
+    to_contract: String,
+    asset_id: String,
+    amount: u64,
+}
+
+fn indexed_key(contract_id: &str, commit_id: &str) -> String {
+    format!("/index/indexed/{}/{}", contract_id, commit_id)
+}
+
+fn signer_prefix(public_key: &str) -> String {
+    format!("/index/signer/{}", public_key)
+}
+
+fn path_prefix(contract_id: &str) -> String {
+    format!("/index/path/{}", contract_id)
+}
+
+fn path_key(commit: &IndexedCommit) -> String {
+    format!("{}{}#{:020}#{}", path_prefix(&commit.contract_id), commit.path, commit.timestamp, commit.commit_id)
+}
+
+fn balance_prefix(owner_contract_id: &str) -> String {
+    format!("/index/balance/{}", owner_contract_id)
+}
+
+fn balance_key(owner_contract_id: &str, contract_id: &str, asset_id: &str) -> String {
+    format!("{}/{}/{}", balance_prefix(owner_contract_id), contract_id, asset_id)
+}
+
+fn send_key(send_commit_id: &str) -> String {
+    format!("/index/send/{}", send_commit_id)
+}
+
+fn pending_recv_key(send_commit_id: &str) -> String {
+    format!("/index/pending_recv/{}", send_commit_id)
+}
+
+fn received_key(send_commit_id: &str) -> String {
+    format!("/index/received/{}", send_commit_id)
+}
+
+/// Whether `path` is `prefix` or below it
+fn under(path: &str, prefix: &str) -> bool {
+    prefix.is_empty()
+        || (prefix.ends_with('/') && path.starts_with(prefix))
+        || path.strip_prefix(prefix).is_some_and(|rest| rest.is_empty() || rest.starts_with('/') || rest.starts_with('.'))
+}
+
+/// Store for indexer nodes' indexes
+pub struct IndexStore {
+    backend: Box<dyn StoreBackend>,
+}
+
+impl IndexStore {
+    /// Open or create the store at the given path
+    pub fn open(path: &Path) -> Result<Self> {
+        Ok(Self::with_backend(Box::new(RocksDbBackend::open(path)?)))
+    }
+
+    /// Open the store in read-only mode
+    pub fn open_readonly(path: &Path) -> Result<Self> {
+        Ok(Self::with_backend(Box::new(RocksDbBackend::open_readonly(path)?)))
+    }
+
+    /// Create an in-memory store for testing
+    pub fn create_in_memory() -> Result<Self> {
+        Ok(Self::with_backend(Box::new(MemoryBackend::new())))
+    }
+
+    /// Create the store on the given storage engine
+    pub fn with_backend(backend: Box<dyn StoreBackend>) -> Self {
+        Self { backend }
+    }
+
+    /// Mark the index as kept by this node, so queries are answered
+    pub fn enable(&self) -> Result<()> {
+        self.put(ENABLED_KEY, b"true")
+    }
+
+    /// Whether this node keeps the index
+    pub fn is_enabled(&self) -> Result<bool> {
+        Ok(self.get(ENABLED_KEY)?.is_some())
+    }
+
+    /// Number of commits indexed
+    pub fn commit_count(&self) -> Result<u64> {
+        match self.get(COMMIT_COUNT_KEY)? {
+            Some(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            None => Ok(0),
+        }
+    }
+
+    /// Whether a commit has been indexed
+    pub fn is_indexed(&self, contract_id: &str, commit_id: &str) -> Result<bool> {
+        Ok(self.get(&indexed_key(contract_id, commit_id))?.is_some())
+    }
+
+    /// Index a commit. A contract's commits must be indexed in order, so
+    /// assets are created before they're sent. Returns false if the commit
+    /// was already indexed.
+    pub fn index_commit(&self, contract_id: &str, commit_id: &str, commit_data: &str, timestamp: u64) -> Result<bool> {
+        if self.is_indexed(contract_id, commit_id)? {
+            return Ok(false);
+        }
+        let commit: serde_json::Value = serde_json::from_str(commit_data)?;
+        let mut batch = WriteBatch::default();
+        let mut balances = BTreeMap::new();
+
+        if let Some(signatures) = commit.pointer("/head/signatures").and_then(|s| s.as_object()) {
+            for public_key in signatures.keys() {
+                batch.put(format!("{}/{}", signer_prefix(public_key), contract_id), b"");
+            }
+        }
+
+        for action in commit.get("body").and_then(|b| b.as_array()).into_iter().flatten() {
+            let method = action.get("method").and_then(|m| m.as_str()).unwrap_or_default();
+            if let Some(path) = action.get("path").and_then(|p| p.as_str()) {
+                let indexed = IndexedCommit {
+                    contract_id: contract_id.to_string(),
+                    commit_id: commit_id.to_string(),
+                    path: path.to_string(),
+                    method: method.to_string(),
+                    timestamp,
+                };
+                batch.put(path_key(&indexed), serde_json::to_vec(&indexed)?);
+            }
+            let value = action.get("value").cloned().unwrap_or_default();
+            let field = |name: &str| value.get(name).and_then(|v| v.as_str()).map(str::to_string);
+            let amount = |name: &str| value.get(name).and_then(|v| v.as_u64());
+            match method {
+                "create" => {
+                    if let (Some(asset_id), Some(quantity)) = (field("asset_id"), amount("quantity")) {
+                        self.adjust(&mut balances, (contract_id, contract_id, &asset_id), quantity, 0)?;
+                    }
+                }
+                "send" => {
+                    let (Some(asset_id), Some(to_contract), Some(amount)) = (field("asset_id"), field("to_contract"), amount("amount")) else {
+                        continue;
+                    };
+                    self.adjust(&mut balances, (contract_id, contract_id, &asset_id), 0, amount)?;
+                    let send = IndexedSend { from_contract: contract_id.to_string(), to_contract, asset_id, amount };
+                    // A recv indexed before its send is credited now
+                    match self.get(&pending_recv_key(commit_id))? {
+                        Some(receiver) if receiver == send.to_contract.as_bytes() => {
+                            self.credit(&mut batch, &mut balances, commit_id, &send)?;
+                            batch.delete(pending_recv_key(commit_id));
+                        }
+                        _ => batch.put(send_key(commit_id), serde_json::to_vec(&send)?),
+                    }
+                }
+                "recv" => {
+                    let Some(send_commit_id) = field("send_commit_id") else {
+                        continue;
+                    };
+                    if self.get(&received_key(&send_commit_id))?.is_some() {
+                        continue;
+                    }
+                    match self.get(&send_key(&send_commit_id))? {
+                        Some(bytes) => {
+                            let send: IndexedSend = serde_json::from_slice(&bytes)?;
+                            if send.to_contract == contract_id {
+                                self.credit(&mut batch, &mut balances, &send_commit_id, &send)?;
+                                batch.delete(send_key(&send_commit_id));
+                            }
+                        }
+                        None => batch.put(pending_recv_key(&send_commit_id), contract_id.as_bytes()),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for ((owner, contract, asset_id), balance) in balances {
+            let indexed = IndexedBalance { owner_contract_id: owner, contract_id: contract, asset_id, balance };
+            batch.put(
+                balance_key(&indexed.owner_contract_id, &indexed.contract_id, &indexed.asset_id),
+                serde_json::to_vec(&indexed)?,
+            );
+        }
+        batch.put(indexed_key(contract_id, commit_id), b"");
+        batch.put(COMMIT_COUNT_KEY, serde_json::to_vec(&(self.commit_count()? + 1))?);
+        self.write(batch)?;
+        Ok(true)
+    }
+
+    /// Add `credit` to and take `debit` from a balance, starting from the
+    /// stored balance the first time the commit touches it
+    fn adjust(
+        &self,
+        balances: &mut BTreeMap<(String, String, String), u64>,
+        (owner, contract, asset_id): (&str, &str, &str),
+        credit: u64,
+        debit: u64,
+    ) -> Result<()> {
+        let key = (owner.to_string(), contract.to_string(), asset_id.to_string());
+        let balance = match balances.get(&key) {
+            Some(balance) => *balance,
+            None => match self.get(&balance_key(owner, contract, asset_id))? {
+                Some(bytes) => serde_json::from_slice::<IndexedBalance>(&bytes)?.balance,
+                None => 0,
+            },
+        };
+        balances.insert(key, balance.saturating_add(credit).saturating_sub(debit));
+        Ok(())
+    }
+
+    fn credit(
+        &self,
+        batch: &mut WriteBatch,
+        balances: &mut BTreeMap<(String, String, String), u64>,
+        send_commit_id: &str,
+        send: &IndexedSend,
+    ) -> Result<()> {
+        self.adjust(balances, (&send.to_contract, &send.from_contract, &send.asset_id), send.amount, 0)?;
+        batch.put(received_key(send_commit_id), send.to_contract.as_bytes());
+        Ok(())
+    }
+
+    /// Contracts with commits signed by `public_key`
+    pub fn contracts_by_signer(&self, public_key: &str) -> Result<Vec<String>> {
+        let prefix = signer_prefix(public_key);
+        let mut contracts = Vec::new();
+        for item in self.iterator(&prefix) {
+            let (key, _) = item?;
+            let key = String::from_utf8(key.to_vec())?;
+            contracts.push(key[prefix.len() + 1..].to_string());
+        }
+        Ok(contracts)
+    }
+
+    /// Up to `limit` actions of a contract's commits at or below
+    /// `path_prefix`, by path and then time
+    pub fn commits_by_path(&self, contract_id: &str, path_prefix: &str, limit: usize) -> Result<Vec<IndexedCommit>> {
+        let prefix = format!("{}{}", self::path_prefix(contract_id), path_prefix);
+        let mut commits = Vec::new();
+        for item in self.prefix_iterator(&prefix) {
+            if commits.len() >= limit {
+                break;
+            }
+            let (_, value) = item?;
+            let commit: IndexedCommit = serde_json::from_slice(&value)?;
+            if under(&commit.path, path_prefix) {
+                commits.push(commit);
+            }
+        }
+        Ok(commits)
+    }
+
+    /// Every asset balance `owner_contract_id` holds
+    pub fn balances(&self, owner_contract_id: &str) -> Result<Vec<IndexedBalance>> {
+        let mut balances = Vec::new();
+        for item in self.iterator(&balance_prefix(owner_contract_id)) {
+            let (_, value) = item?;
+            balances.push(serde_json::from_slice(&value)?);
+        }
+        Ok(balances)
+    }
+
+    /// Drop every index entry, keeping whether the node indexes.
+    /// Returns the number of entries dropped.
+    pub fn clear(&self) -> Result<usize> {
+        let mut batch = WriteBatch::default();
+        for item in self.iter_all() {
+            let (key, _) = item?;
+            if key.starts_with(b"/index/") && &*key != ENABLED_KEY.as_bytes() {
+                batch.delete(key);
+            }
+        }
+        let cleared = batch.len();
+        self.write(batch)?;
+        Ok(cleared)
+    }
+}
+
+impl Store for IndexStore {
+    fn backend(&self) -> &dyn StoreBackend {
+        self.backend.as_ref()
+    }
+}
+
+impl Drop for IndexStore {
+    fn drop(&mut self) {
+        let _ = self.backend.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn commit(signer: &str, body: serde_json::Value) -> String {
+        json!({"body": body, "head": {"signatures": {signer: "sig"}}}).to_string()
+    }
+
+    #[test]
+    fn test_indexes_signers_and_paths() {
+        let store = IndexStore::create_in_memory().unwrap();
+        let data = commit("alice", json!([
+            {"method": "post", "path": "/data/name.text", "value": "a"},
+            {"method": "post", "path": "/database.text", "value": "b"},
+        ]));
+        assert!(store.index_commit("c1", "k1", &data, 10).unwrap());
+        assert!(!store.index_commit("c1", "k1", &data, 10).unwrap());
+        store.index_commit("c2", "k2", &commit("alice", json!([])), 20).unwrap();
+
+        assert_eq!(store.contracts_by_signer("alice").unwrap(), vec!["c1", "c2"]);
+        assert!(store.contracts_by_signer("bob").unwrap().is_empty());
+        assert_eq!(store.commit_count().unwrap(), 2);
+
+        // "/data" doesn't take in "/database"
+        let found = store.commits_by_path("c1", "/data", 10).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path, "/data/name.text");
+        assert_eq!(store.commits_by_path("c1", "", 10).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_balances_follow_create_send_and_recv() {
+        let store = IndexStore::create_in_memory().unwrap();
+        let create = commit("alice", json!([
+            {"method": "create", "value": {"asset_id": "gold", "quantity": 100, "divisibility": 1}},
+        ]));
+        let send = commit("alice", json!([
+            {"method": "send", "value": {"asset_id": "gold", "to_contract": "bank", "amount": 30}},
+        ]));
+        let recv = commit("bob", json!([{"method": "recv", "value": {"send_commit_id": "s1"}}]));
+
+        // The recv arrives before the send it receives
+        store.index_commit("bank", "r1", &recv, 3).unwrap();
+        store.index_commit("mint", "c1", &create, 1).unwrap();
+        store.index_commit("mint", "s1", &send, 2).unwrap();
+
+        assert_eq!(store.balances("mint").unwrap()[0].balance, 70);
+        let bank = store.balances("bank").unwrap();
+        assert_eq!(bank, vec![IndexedBalance {
+            owner_contract_id: "bank".into(),
+            contract_id: "mint".into(),
+            asset_id: "gold".into(),
+            balance: 30,
+        }]);
+
+        // Receiving the same send twice credits once
+        store.index_commit("bank", "r2", &recv, 4).unwrap();
+        assert_eq!(store.balances("bank").unwrap()[0].balance, 30);
+
+        store.clear().unwrap();
+        assert!(store.balances("bank").unwrap().is_empty());
+        assert_eq!(store.commit_count().unwrap(), 0);
+    }
+}
@@ -1,19 +1,30 @@
 //! Store types for the multi-datastore architecture
 //! 
-//! The system uses 6 separate RocksDB stores:
+//! The system uses 8 separate stores:
 //! - MinerCanon: Finalized canonical miner blocks (2+ epochs old) - shareable
 //! - MinerForks: Archived orphaned miner blocks (2+ epochs old) - local
 //! - MinerActive: Recent miner blocks (12 epoch rolling window) - local
 //! - ValidatorFinal: Finalized validator blocks, contracts, network params - shareable
 //! - ValidatorActive: In-progress rounds, draft blocks, pending certs - local
 //! - NodeState: Node-specific state (status, peer info, ignored peers) - local
+//! - NodeMetrics: Metrics history (hashrate, peers, rounds) with downsampling - local
+//! - Index: Indexer nodes' lookups by signer, path and asset owner - local
+//!
+//! Each store keeps its data in a storage engine chosen per store, see `backend`.
+//! Bursts of writes can be collected and applied together, see `write_buffer`,
+//! and the block and validator stores keep recently loaded models, see `cache`.
 
+pub mod backend;
+pub mod cache;
 pub mod miner_canon;
 pub mod miner_forks;
 pub mod miner_active;
 pub mod validator_final;
 pub mod validator_active;
 pub mod node_state;
+pub mod node_metrics;
+pub mod index;
+pub mod write_buffer;
 
 pub use miner_canon::MinerCanonStore;
 pub use miner_forks::MinerForksStore;
@@ -21,52 +32,91 @@ pub use miner_active::MinerActiveStore;
 pub use validator_final::ValidatorFinalStore;
 pub use validator_active::ValidatorActiveStore;
 pub use node_state::NodeStateStore;
+pub use node_metrics::{NodeMetricsStore, MetricPoint, MetricSeries, MetricTier, METRIC_TIERS};
+pub use index::{IndexStore, IndexedBalance, IndexedCommit};
+pub use write_buffer::WriteBuffer;
+pub use cache::{CacheStats, ModelCache};
+
+pub use backend::{
+    Durability, KvPair, MemoryBackend, RocksDbBackend, RocksDbColumnFamilyBackend, StorageConfig, StorageEngine,
+    StoreBackend, WriteBatch,
+};
 
 use crate::Result;
-use rocksdb::{DB, Options, IteratorMode};
+use rocksdb::{DB, Options};
 use std::path::Path;
 
 /// Common trait for all store types
 pub trait Store {
-    /// Get a reference to the underlying RocksDB instance
-    fn db(&self) -> &DB;
+    /// Get the storage engine holding the store's data
+    fn backend(&self) -> &dyn StoreBackend;
+    
+    /// Get the store's cache of loaded models, if it keeps one
+    fn cache(&self) -> Option<&ModelCache> {
+        None
+    }
     
     /// Get a value by key
     fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
-        match self.db().get(key)? {
-            Some(value) => Ok(Some(value.to_vec())),
-            None => Ok(None),
-        }
+        self.backend().get(key.as_bytes())
     }
     
-    /// Put a value by key
+    /// Put a value by key, with its checksum
     fn put(&self, key: &str, value: &[u8]) -> Result<()> {
-        self.db().put(key, value)?;
-        Ok(())
+        let mut batch = WriteBatch::default();
+        batch.put(key, value);
+        self.write(batch)
     }
     
-    /// Delete a key
+    /// Delete a key and its checksum
     fn delete(&self, key: &str) -> Result<()> {
-        self.db().delete(key)?;
-        Ok(())
+        let mut batch = WriteBatch::default();
+        batch.delete(key);
+        self.write(batch)
+    }
+    
+    /// Apply a batch of puts and deletes atomically, keeping each record's
+    /// checksum (see `crate::fsck`) in the same batch, and drop the cached
+    /// models they replace
+    fn write(&self, batch: WriteBatch) -> Result<()> {
+        let Some(cache) = self.cache() else {
+            return self.backend().write(crate::fsck::with_checksums(batch));
+        };
+        let keys: Vec<Vec<u8>> = batch.iter().map(|(key, _)| key.to_vec()).collect();
+        let result = self.backend().write(crate::fsck::with_checksums(batch));
+        cache.invalidate(keys.iter().map(Vec::as_slice));
+        result
     }
     
     /// Iterate over keys with a prefix
-    #[allow(clippy::type_complexity)]
-    fn iterator(&self, prefix: &str) -> impl Iterator<Item = Result<(Box<[u8]>, Box<[u8]>)>> + '_ {
-        let mut readopts = rocksdb::ReadOptions::default();
-        readopts.set_iterate_lower_bound(format!("{}/", prefix).as_bytes());
-        readopts.set_iterate_upper_bound(format!("{}0", prefix).as_bytes());
-        let iter = self.db().iterator_opt(IteratorMode::Start, readopts);
-        iter.map(|result| {
-            result.map_err(|e| crate::Error::Database(e.to_string()))
-        })
+    fn iterator(&self, prefix: &str) -> impl Iterator<Item = Result<KvPair>> + Send + '_ {
+        let lower = format!("{}/", prefix);
+        let upper = format!("{}0", prefix);
+        self.backend().range(Some(lower.as_bytes()), Some(upper.as_bytes()))
+    }
+    
+    /// Iterate over keys starting with `prefix`, which may end partway
+    /// through a path segment (e.g. a hash prefix)
+    fn prefix_iterator(&self, prefix: &str) -> impl Iterator<Item = Result<KvPair>> + Send + '_ {
+        // Keys are ASCII, so every key with the prefix sorts below prefix + 0xff
+        let mut upper = prefix.as_bytes().to_vec();
+        upper.push(0xff);
+        self.backend().range(Some(prefix.as_bytes()), Some(&upper))
+    }
+    
+    /// Iterate over every key in the store
+    fn iter_all(&self) -> impl Iterator<Item = Result<KvPair>> + Send + '_ {
+        self.backend().range(None, None)
     }
     
     /// Flush the database to disk
     fn flush(&self) -> Result<()> {
-        self.db().flush()?;
-        Ok(())
+        self.backend().flush()
+    }
+    
+    /// Collect writes to apply together, see `WriteBuffer`
+    fn buffer(&self) -> WriteBuffer<'_> {
+        WriteBuffer::new(self.backend(), self.cache())
     }
 }
 
@@ -0,0 +1,255 @@
+//! NodeMetrics store - time series of node metrics (hashrate, peers, rounds)
+//!
+//! This store contains local metrics history that is not shared with other nodes.
+//! Each point is kept as recorded for a few hours and folded into 5 minute and
+//! hourly buckets kept for longer, so a year of history stays small.
+
+use crate::Result;
+use crate::stores::{MemoryBackend, RocksDbBackend, Store, StoreBackend, WriteBatch};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A resolution metrics are kept at, and for how long
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MetricTier {
+    pub name: &'static str,
+    /// Bucket width; 0 keeps points as recorded
+    pub bucket_secs: u64,
+    pub retention_secs: u64,
+}
+
+/// Tiers from finest to coarsest
+pub const METRIC_TIERS: [MetricTier; 3] = [
+    MetricTier { name: "raw", bucket_secs: 0, retention_secs: 6 * 3600 },
+    MetricTier { name: "5m", bucket_secs: 300, retention_secs: 7 * 86400 },
+    MetricTier { name: "1h", bucket_secs: 3600, retention_secs: 365 * 86400 },
+];
+
+/// Key prefix of the series names
+const SERIES_PREFIX: &str = "/metrics/series";
+
+/// Values recorded in one bucket (a single value for raw points)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricPoint {
+    /// Unix seconds of the value, or of the start of the bucket
+    pub timestamp: i64,
+    pub min: f64,
+    pub max: f64,
+    pub sum: f64,
+    pub count: u64,
+}
+
+impl MetricPoint {
+    fn new(timestamp: i64, value: f64) -> Self {
+        Self { timestamp, min: value, max: value, sum: value, count: 1 }
+    }
+
+    fn add(&mut self, value: f64) {
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.sum += value;
+        self.count += 1;
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 { 0.0 } else { self.sum / self.count as f64 }
+    }
+}
+
+/// Points of one series over a time range, at one resolution
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricSeries {
+    pub series: String,
+    /// Bucket width of the points; 0 for points as recorded
+    pub resolution_secs: u64,
+    pub points: Vec<MetricPoint>,
+}
+
+fn point_key(tier: &MetricTier, series: &str, timestamp: i64) -> String {
+    format!("/metrics/{}/{}/{:020}", tier.name, series, timestamp.max(0))
+}
+
+fn series_prefix(tier: &MetricTier, series: &str) -> String {
+    format!("/metrics/{}/{}", tier.name, series)
+}
+
+fn bucket_start(tier: &MetricTier, timestamp: i64) -> i64 {
+    if tier.bucket_secs == 0 {
+        timestamp
+    } else {
+        timestamp - timestamp.rem_euclid(tier.bucket_secs as i64)
+    }
+}
+
+/// Store for node metrics history
+pub struct NodeMetricsStore {
+    backend: Box<dyn StoreBackend>,
+}
+
+impl NodeMetricsStore {
+    /// Open or create the store at the given path
+    pub fn open(path: &Path) -> Result<Self> {
+        Ok(Self::with_backend(Box::new(RocksDbBackend::open(path)?)))
+    }
+
+    /// Open the store in read-only mode
+    pub fn open_readonly(path: &Path) -> Result<Self> {
+        Ok(Self::with_backend(Box::new(RocksDbBackend::open_readonly(path)?)))
+    }
+
+    /// Create an in-memory store for testing
+    pub fn create_in_memory() -> Result<Self> {
+        Ok(Self::with_backend(Box::new(MemoryBackend::new())))
+    }
+
+    /// Create the store on the given storage engine
+    pub fn with_backend(backend: Box<dyn StoreBackend>) -> Self {
+        Self { backend }
+    }
+
+    /// Record a value of each series at `timestamp` (Unix seconds).
+    /// Series names are dotted, like `mining.hashrate`.
+    pub fn record(&self, timestamp: i64, values: &[(&str, f64)]) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        for &(series, value) in values {
+            if series.is_empty() || series.contains('/') {
+                return Err(crate::Error::InvalidData(format!("Invalid metric series name: {}", series)));
+            }
+            if !value.is_finite() {
+                continue;
+            }
+            batch.put(format!("{}/{}", SERIES_PREFIX, series), b"");
+            for tier in &METRIC_TIERS {
+                let key = point_key(tier, series, bucket_start(tier, timestamp));
+                let point = match self.get(&key)? {
+                    Some(bytes) if tier.bucket_secs > 0 => {
+                        let mut point: MetricPoint = serde_json::from_slice(&bytes)?;
+                        point.add(value);
+                        point
+                    }
+                    _ => MetricPoint::new(bucket_start(tier, timestamp), value),
+                };
+                batch.put(key, serde_json::to_vec(&point)?);
+            }
+        }
+        self.write(batch)
+    }
+
+    /// Names of the recorded series
+    pub fn series_names(&self) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        for item in self.iterator(SERIES_PREFIX) {
+            let (key, _) = item?;
+            let key = String::from_utf8(key.to_vec())?;
+            names.push(key[SERIES_PREFIX.len() + 1..].to_string());
+        }
+        Ok(names)
+    }
+
+    /// Points of a series from `from` to `to` (inclusive, Unix seconds), at
+    /// the finest resolution still kept for `from` as of `now`
+    pub fn query(&self, series: &str, from: i64, to: i64, now: i64) -> Result<MetricSeries> {
+        let tier = METRIC_TIERS.iter()
+            .find(|tier| now - tier.retention_secs as i64 <= from)
+            .unwrap_or(&METRIC_TIERS[METRIC_TIERS.len() - 1]);
+        self.query_tier(series, tier, from, to)
+    }
+
+    /// Points of a series from `from` to `to` in one tier
+    pub fn query_tier(&self, series: &str, tier: &MetricTier, from: i64, to: i64) -> Result<MetricSeries> {
+        let first = bucket_start(tier, from);
+        let mut points = Vec::new();
+        for item in self.iterator(&series_prefix(tier, series)) {
+            let (_, value) = item?;
+            let point: MetricPoint = serde_json::from_slice(&value)?;
+            if point.timestamp > to {
+                break;
+            }
+            if point.timestamp >= first {
+                points.push(point);
+            }
+        }
+        Ok(MetricSeries { series: series.to_string(), resolution_secs: tier.bucket_secs, points })
+    }
+
+    /// Delete the points each tier no longer keeps as of `now`.
+    /// Returns the number of points deleted.
+    pub fn prune(&self, now: i64) -> Result<usize> {
+        let mut batch = WriteBatch::default();
+        for series in self.series_names()? {
+            for tier in &METRIC_TIERS {
+                let cutoff = now - tier.retention_secs as i64;
+                for item in self.iterator(&series_prefix(tier, &series)) {
+                    let (key, value) = item?;
+                    let point: MetricPoint = serde_json::from_slice(&value)?;
+                    if point.timestamp >= cutoff {
+                        break;
+                    }
+                    batch.delete(key);
+                }
+            }
+        }
+        let pruned = batch.len();
+        self.write(batch)?;
+        Ok(pruned)
+    }
+}
+
+impl Store for NodeMetricsStore {
+    fn backend(&self) -> &dyn StoreBackend {
+        self.backend.as_ref()
+    }
+}
+
+impl Drop for NodeMetricsStore {
+    fn drop(&mut self) {
+        let _ = self.backend.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_points_fold_into_buckets() {
+        let store = NodeMetricsStore::create_in_memory().unwrap();
+        for (i, value) in [10.0, 20.0, 30.0, 40.0].into_iter().enumerate() {
+            store.record(1_000_020 + i as i64 * 120, &[("net.peers", value)]).unwrap();
+        }
+        assert_eq!(store.series_names().unwrap(), vec!["net.peers".to_string()]);
+
+        // Recent range: every point as recorded
+        let now = 1_000_500;
+        let raw = store.query("net.peers", 1_000_000, now, now).unwrap();
+        assert_eq!(raw.resolution_secs, 0);
+        assert_eq!(raw.points.len(), 4);
+
+        // 1_000_020 and 1_000_140 share the bucket starting at 999_900
+        let buckets = store.query_tier("net.peers", &METRIC_TIERS[1], 0, now).unwrap();
+        assert_eq!(buckets.points.len(), 2);
+        assert_eq!(buckets.points[0].timestamp, 999_900);
+        assert_eq!(buckets.points[0].count, 2);
+        assert_eq!(buckets.points[0].mean(), 15.0);
+        assert_eq!(buckets.points[1].min, 30.0);
+        assert_eq!(buckets.points[1].max, 40.0);
+
+        // A range older than the raw retention is answered from buckets
+        let later = now + 24 * 3600;
+        assert_eq!(store.query("net.peers", 1_000_000, later, later).unwrap().resolution_secs, 300);
+    }
+
+    #[test]
+    fn test_prune_drops_expired_points() {
+        let store = NodeMetricsStore::create_in_memory().unwrap();
+        store.record(0, &[("chain.height", 1.0)]).unwrap();
+        store.record(10 * 86400, &[("chain.height", 2.0)]).unwrap();
+
+        // Raw and 5m points of the first value are past retention, its 1h bucket isn't
+        assert_eq!(store.prune(10 * 86400).unwrap(), 2);
+        assert_eq!(store.query_tier("chain.height", &METRIC_TIERS[0], 0, i64::MAX).unwrap().points.len(), 1);
+        assert_eq!(store.query_tier("chain.height", &METRIC_TIERS[2], 0, i64::MAX).unwrap().points.len(), 2);
+
+        assert!(store.record(0, &[("bad/name", 1.0)]).is_err());
+    }
+}
@@ -3,47 +3,44 @@
 //! This store contains local node state that is not shared with other nodes.
 
 use crate::Result;
-use crate::stores::{Store, open_store, open_store_readonly};
-use rocksdb::DB;
+use crate::stores::{MemoryBackend, RocksDbBackend, Store, StoreBackend};
 use std::path::Path;
 
 /// Store for node-specific state
 pub struct NodeStateStore {
-    db: DB,
+    backend: Box<dyn StoreBackend>,
 }
 
 impl NodeStateStore {
     /// Open or create the store at the given path
     pub fn open(path: &Path) -> Result<Self> {
-        let db = open_store(path)?;
-        Ok(Self { db })
+        Ok(Self::with_backend(Box::new(RocksDbBackend::open(path)?)))
     }
     
     /// Open the store in read-only mode
     pub fn open_readonly(path: &Path) -> Result<Self> {
-        let db = open_store_readonly(path)?;
-        Ok(Self { db })
+        Ok(Self::with_backend(Box::new(RocksDbBackend::open_readonly(path)?)))
     }
     
     /// Create an in-memory store for testing
     pub fn create_in_memory() -> Result<Self> {
-        let mut opts = rocksdb::Options::default();
-        opts.create_if_missing(true);
-        let temp_dir = tempfile::tempdir().unwrap();
-        let db = DB::open(&opts, temp_dir.path())?;
-        Ok(Self { db })
+        Ok(Self::with_backend(Box::new(MemoryBackend::new())))
+    }
+    
+    /// Create the store on the given storage engine
+    pub fn with_backend(backend: Box<dyn StoreBackend>) -> Self {
+        Self { backend }
     }
 }
 
 impl Store for NodeStateStore {
-    fn db(&self) -> &DB {
-        &self.db
+    fn backend(&self) -> &dyn StoreBackend {
+        self.backend.as_ref()
     }
 }
 
 impl Drop for NodeStateStore {
     fn drop(&mut self) {
-        let _ = self.db.flush();
+        let _ = self.backend.flush();
     }
 }
-
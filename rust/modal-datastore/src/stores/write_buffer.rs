@@ -0,0 +1,189 @@
+//! Buffered writes to a store
+//!
+//! Saving a model writes the record and its index entries in one batch, so
+//! saving a few hundred blocks or certificates in a row, as sync and
+//! consensus bursts do, is a few hundred commits made while holding the
+//! `DatastoreManager` lock. A `WriteBuffer` collects those writes instead:
+//! models save into it like into any store, reads through it see what was
+//! saved, and `commit` applies everything as one atomic write.
+
+use crate::Result;
+use crate::stores::{KvPair, ModelCache, Store, StoreBackend, WriteBatch};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::iter::Peekable;
+use std::ops::Bound;
+use std::sync::RwLock;
+
+/// Writes to a store held back until `commit`
+pub struct WriteBuffer<'a> {
+    buffered: Buffered<'a>,
+    /// Cache of the store, invalidated on commit
+    cache: Option<&'a ModelCache>,
+}
+
+/// The store as seen through the buffer
+struct Buffered<'a> {
+    store: &'a dyn StoreBackend,
+    /// Buffered values by key, `None` for deletes
+    pending: RwLock<BTreeMap<Vec<u8>, Option<Vec<u8>>>>,
+}
+
+impl<'a> WriteBuffer<'a> {
+    pub fn new(store: &'a dyn StoreBackend, cache: Option<&'a ModelCache>) -> Self {
+        Self { buffered: Buffered { store, pending: RwLock::new(BTreeMap::new()) }, cache }
+    }
+
+    /// Number of keys written or deleted
+    pub fn len(&self) -> usize {
+        self.buffered.pending.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffered.pending.read().unwrap().is_empty()
+    }
+
+    /// Apply the buffered writes to the store as one batch
+    pub fn commit(self) -> Result<()> {
+        let Buffered { store, pending } = self.buffered;
+        let pending = pending.into_inner().unwrap();
+        if pending.is_empty() {
+            return Ok(());
+        }
+        // Checksums were added as the writes were buffered
+        let mut batch = WriteBatch::default();
+        for (key, value) in &pending {
+            match value {
+                Some(value) => batch.put(key, value),
+                None => batch.delete(key),
+            }
+        }
+        let result = store.write(batch);
+        if let Some(cache) = self.cache {
+            cache.invalidate(pending.keys().map(Vec::as_slice));
+        }
+        result
+    }
+}
+
+impl Store for WriteBuffer<'_> {
+    fn backend(&self) -> &dyn StoreBackend {
+        &self.buffered
+    }
+}
+
+impl StoreBackend for Buffered<'_> {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        match self.pending.read().unwrap().get(key) {
+            Some(value) => Ok(value.clone()),
+            None => self.store.get(key),
+        }
+    }
+
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.pending.write().unwrap().insert(key.to_vec(), Some(value.to_vec()));
+        Ok(())
+    }
+
+    fn delete(&self, key: &[u8]) -> Result<()> {
+        self.pending.write().unwrap().insert(key.to_vec(), None);
+        Ok(())
+    }
+
+    fn write(&self, batch: WriteBatch) -> Result<()> {
+        let mut pending = self.pending.write().unwrap();
+        for (key, value) in batch.iter() {
+            pending.insert(key.to_vec(), value.map(|value| value.to_vec()));
+        }
+        Ok(())
+    }
+
+    fn range<'b>(&'b self, lower: Option<&[u8]>, upper: Option<&[u8]>) -> Box<dyn Iterator<Item = Result<KvPair>> + Send + 'b> {
+        let stored = self.store.range(lower, upper);
+        let lower_bound = lower.map_or(Bound::Unbounded, |key| Bound::Included(key.to_vec()));
+        let upper_bound = upper.map_or(Bound::Unbounded, |key| Bound::Excluded(key.to_vec()));
+        let pending: Vec<(Vec<u8>, Option<Vec<u8>>)> = match (&lower_bound, &upper_bound) {
+            (Bound::Included(l), Bound::Excluded(u)) if l >= u => Vec::new(),
+            _ => self.pending.read().unwrap()
+                .range((lower_bound, upper_bound))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        };
+        if pending.is_empty() {
+            return stored;
+        }
+        Box::new(Merged { stored: stored.peekable(), pending: pending.into_iter().peekable() })
+    }
+
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Stored entries with the buffered writes applied, in key order
+struct Merged<'a> {
+    stored: Peekable<Box<dyn Iterator<Item = Result<KvPair>> + Send + 'a>>,
+    pending: Peekable<std::vec::IntoIter<(Vec<u8>, Option<Vec<u8>>)>>,
+}
+
+impl Iterator for Merged<'_> {
+    type Item = Result<KvPair>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // Which side has the lower key; on a tie the buffered write wins
+            let order = match (self.stored.peek(), self.pending.peek()) {
+                (None, None) => return None,
+                (Some(Err(_)), _) | (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(Ok((key, _))), Some((pending_key, _))) => key.as_ref().cmp(pending_key.as_slice()),
+            };
+            if order == Ordering::Less {
+                return self.stored.next();
+            }
+            if order == Ordering::Equal {
+                self.stored.next();
+            }
+            if let Some((key, Some(value))) = self.pending.next() {
+                return Some(Ok((key.into_boxed_slice(), value.into_boxed_slice())));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stores::MemoryBackend;
+
+    #[test]
+    fn test_buffered_writes_commit_together() {
+        let backend = MemoryBackend::new();
+        backend.put(b"/a/1", b"one").unwrap();
+        backend.put(b"/a/2", b"two").unwrap();
+        backend.put(b"/a/4", b"four").unwrap();
+
+        let buffer = WriteBuffer::new(&backend, None);
+        buffer.put("/a/3", b"three").unwrap();
+        buffer.put("/a/1", b"uno").unwrap();
+        buffer.delete("/a/2").unwrap();
+
+        // Reads through the buffer see its writes, the store doesn't yet
+        assert_eq!(buffer.get("/a/1").unwrap().as_deref(), Some(&b"uno"[..]));
+        assert_eq!(buffer.get("/a/2").unwrap(), None);
+        let entries: Vec<(Vec<u8>, Vec<u8>)> = buffer.iterator("/a")
+            .map(|item| item.map(|(key, value)| (key.to_vec(), value.to_vec())).unwrap())
+            .collect();
+        assert_eq!(entries, vec![
+            (b"/a/1".to_vec(), b"uno".to_vec()),
+            (b"/a/3".to_vec(), b"three".to_vec()),
+            (b"/a/4".to_vec(), b"four".to_vec()),
+        ]);
+        assert_eq!(backend.get(b"/a/1").unwrap().as_deref(), Some(&b"one"[..]));
+
+        buffer.commit().unwrap();
+        assert_eq!(backend.get(b"/a/1").unwrap().as_deref(), Some(&b"uno"[..]));
+        assert_eq!(backend.get(b"/a/2").unwrap(), None);
+        assert_eq!(backend.get(b"/a/3").unwrap().as_deref(), Some(&b"three"[..]));
+    }
+}
@@ -8,6 +8,8 @@ use std::collections::HashMap;
 
 use modal_common::keypair::Keypair;
 
+pub mod local;
+
 pub const KEYPAIRS_JSON: &str = include_str!("../keypairs.json");
 
 lazy_static! {
@@ -0,0 +1,195 @@
+//! Local multi-node devnets
+//!
+//! Lays out a devnet of miners, validators and observers on localhost, each
+//! node using one of the devnet keypairs (in peer id order, like the devnet
+//! node templates) and listening on consecutive ports. Every node
+//! bootstraps from all the others and loads a shared network config naming
+//! the validators. The layout is kept in `devnet.json` in the devnet
+//! directory so later commands can find the nodes.
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+use crate::KEYPAIRS;
+
+/// Port of the first node's listener
+pub const DEFAULT_BASE_PORT: u16 = 10901;
+
+/// File holding the layout, in the devnet directory
+pub const LAYOUT_FILE: &str = "devnet.json";
+
+/// Network config shared by the nodes, in the devnet directory
+pub const NETWORK_CONFIG_FILE: &str = "network.json";
+
+/// What a devnet node runs as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocalRole {
+    Miner,
+    Validator,
+    Observer,
+}
+
+impl LocalRole {
+    /// Value of the node config's `run_as`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LocalRole::Miner => "miner",
+            LocalRole::Validator => "validator",
+            LocalRole::Observer => "observer",
+        }
+    }
+}
+
+/// One node of a local devnet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocalNode {
+    /// Name, also the node's directory under the devnet directory
+    pub name: String,
+    pub role: LocalRole,
+    pub peer_id: String,
+    pub port: u16,
+}
+
+impl LocalNode {
+    pub fn address(&self) -> String {
+        format!("/ip4/127.0.0.1/tcp/{}/ws/p2p/{}", self.port, self.peer_id)
+    }
+}
+
+/// Layout of a local devnet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocalDevnet {
+    pub nodes: Vec<LocalNode>,
+}
+
+impl LocalDevnet {
+    /// Lay out `nodes` nodes: `miners` miners, then `validators`
+    /// validators, then observers for the rest
+    pub fn plan(nodes: usize, miners: usize, validators: usize, base_port: u16) -> Result<Self> {
+        if nodes == 0 {
+            return Err(anyhow!("A devnet needs at least one node"));
+        }
+        if miners + validators > nodes {
+            return Err(anyhow!(
+                "{} miners and {} validators don't fit in {} nodes",
+                miners, validators, nodes
+            ));
+        }
+        if nodes > KEYPAIRS.len() {
+            return Err(anyhow!("Only {} devnet keypairs are available", KEYPAIRS.len()));
+        }
+        if base_port as usize + nodes > u16::MAX as usize {
+            return Err(anyhow!("Ports from {} don't fit {} nodes", base_port, nodes));
+        }
+
+        let mut peer_ids: Vec<&String> = KEYPAIRS.keys().collect();
+        peer_ids.sort();
+        let nodes = peer_ids
+            .into_iter()
+            .take(nodes)
+            .enumerate()
+            .map(|(i, peer_id)| LocalNode {
+                name: format!("node{}", i + 1),
+                role: if i < miners {
+                    LocalRole::Miner
+                } else if i < miners + validators {
+                    LocalRole::Validator
+                } else {
+                    LocalRole::Observer
+                },
+                peer_id: peer_id.clone(),
+                port: base_port + i as u16,
+            })
+            .collect();
+        Ok(Self { nodes })
+    }
+
+    pub fn node(&self, name: &str) -> Option<&LocalNode> {
+        self.nodes.iter().find(|n| n.name == name)
+    }
+
+    /// Directory of a node under the devnet directory
+    pub fn node_dir(dir: &Path, node: &LocalNode) -> PathBuf {
+        dir.join(&node.name)
+    }
+
+    /// Network config naming the validators, or none for dynamic validator selection
+    pub fn network_config(&self) -> Value {
+        let validators: Vec<&str> = self
+            .nodes
+            .iter()
+            .filter(|n| n.role == LocalRole::Validator)
+            .map(|n| n.peer_id.as_str())
+            .collect();
+        let mut config = json!({
+            "name": "devnet-local",
+            "description": format!("a local dev network of {} nodes", self.nodes.len()),
+            "difficulty": 1,
+            "bootstrappers": self.nodes.iter().map(|n| n.address()).collect::<Vec<_>>(),
+            "rounds": {},
+        });
+        if !validators.is_empty() {
+            config["validators"] = json!(validators);
+        }
+        config
+    }
+
+    /// Config of one node, bootstrapping from all the others
+    pub fn node_config(&self, node: &LocalNode, network_config_path: &Path) -> Value {
+        let bootstrappers: Vec<String> = self
+            .nodes
+            .iter()
+            .filter(|n| n.name != node.name)
+            .map(|n| n.address())
+            .collect();
+        json!({
+            "id": node.peer_id,
+            "passfile_path": "./node.modal_passfile",
+            "data_dir": "./data",
+            "logs_path": "./logs",
+            "logs_enabled": true,
+            "log_level": "info",
+            "network_config_path": network_config_path,
+            "listeners": [format!("/ip4/127.0.0.1/tcp/{}/ws", node.port)],
+            "bootstrappers": bootstrappers,
+            "run_as": node.role.as_str(),
+            "run_miner": node.role == LocalRole::Miner,
+        })
+    }
+
+    /// Write the layout, the network config, and each node's directory
+    /// with its config and passfile
+    pub fn write(&self, dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        let dir = dir.canonicalize()?;
+        let network_config_path = dir.join(NETWORK_CONFIG_FILE);
+        std::fs::write(&network_config_path, serde_json::to_string_pretty(&self.network_config())?)?;
+
+        for node in &self.nodes {
+            let node_dir = Self::node_dir(&dir, node);
+            std::fs::create_dir_all(node_dir.join("logs"))?;
+            let keypair = KEYPAIRS
+                .get(&node.peer_id)
+                .ok_or_else(|| anyhow!("No devnet keypair for {}", node.peer_id))?;
+            std::fs::write(node_dir.join("node.modal_passfile"), serde_json::to_string_pretty(keypair)?)?;
+            std::fs::write(
+                node_dir.join("config.json"),
+                serde_json::to_string_pretty(&self.node_config(node, &network_config_path))?,
+            )?;
+        }
+
+        std::fs::write(dir.join(LAYOUT_FILE), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Read the layout written to `dir`
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(LAYOUT_FILE);
+        let data = std::fs::read_to_string(&path)
+            .with_context(|| format!("No devnet at {} (missing {})", dir.display(), LAYOUT_FILE))?;
+        serde_json::from_str(&data).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+}
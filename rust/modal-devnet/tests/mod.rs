@@ -112,4 +112,33 @@ mod tests {
         
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_local_devnet_layout() -> Result<()> {
+        use modal_devnet::local::{LocalDevnet, LocalRole};
+
+        assert!(LocalDevnet::plan(3, 2, 2, 10901).is_err());
+        let devnet = LocalDevnet::plan(4, 1, 2, 10901)?;
+        let roles: Vec<LocalRole> = devnet.nodes.iter().map(|n| n.role).collect();
+        assert_eq!(roles, vec![LocalRole::Miner, LocalRole::Validator, LocalRole::Validator, LocalRole::Observer]);
+        // Keypairs in peer id order, like the devnet node templates
+        assert_eq!(devnet.nodes[0].peer_id, "12D3KooW9pte76rpnggcLYkFaawuTEs5DC5axHkg3cK3cewGxxHd");
+        assert_eq!(devnet.nodes[3].port, 10904);
+
+        let network = devnet.network_config();
+        assert_eq!(network["validators"].as_array().unwrap().len(), 2);
+        assert_eq!(network["bootstrappers"].as_array().unwrap().len(), 4);
+
+        let dir = tempfile::tempdir()?;
+        devnet.write(dir.path())?;
+        assert_eq!(LocalDevnet::load(dir.path())?, devnet);
+        let node = devnet.node("node2").unwrap();
+        let config: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(
+            LocalDevnet::node_dir(dir.path(), node).join("config.json"),
+        )?)?;
+        assert_eq!(config["run_as"], "validator");
+        assert_eq!(config["bootstrappers"].as_array().unwrap().len(), 3);
+        assert!(LocalDevnet::node_dir(dir.path(), node).join("node.modal_passfile").exists());
+        Ok(())
+    }
+}
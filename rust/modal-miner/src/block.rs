@@ -2,6 +2,10 @@ use chrono::{DateTime, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Digest};
 use modal_common::hash_tax;
+use modal_common::block_commits::{self, CommitDigest};
+use modal_common::slashing::EquivocationEvidence;
+use modal_common::uncles::UncleRef;
+use crate::error::MiningError;
 
 /// Special peer ID used for the genesis block (no nomination)
 pub const GENESIS_PEER_ID: &str = "";
@@ -17,6 +21,15 @@ pub struct BlockData {
     pub nominated_peer_id: String,
     /// Arbitrary number selected by the miner
     pub miner_number: u64,
+    /// Recent orphaned blocks referenced by this block
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub uncles: Vec<UncleRef>,
+    /// Pending contract commits included by the miner
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commits: Vec<CommitDigest>,
+    /// Validator equivocations included by the miner
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub evidence: Vec<EquivocationEvidence>,
 }
 
 impl BlockData {
@@ -24,12 +37,46 @@ impl BlockData {
         Self {
             nominated_peer_id,
             miner_number,
+            uncles: Vec::new(),
+            commits: Vec::new(),
+            evidence: Vec::new(),
         }
     }
     
+    /// Reference recent orphaned blocks as uncles
+    pub fn with_uncles(mut self, uncles: Vec<UncleRef>) -> Self {
+        self.uncles = uncles;
+        self
+    }
+    
+    /// Include contract commits (their merkle root goes in the header)
+    pub fn with_commits(mut self, commits: Vec<CommitDigest>) -> Self {
+        self.commits = commits;
+        self
+    }
+    
+    /// Include equivocation evidence against validators
+    pub fn with_evidence(mut self, evidence: Vec<EquivocationEvidence>) -> Self {
+        self.evidence = evidence;
+        self
+    }
+    
+    /// Merkle root of the included commits
+    pub fn commits_root(&self) -> String {
+        block_commits::commits_root(&self.commits)
+    }
+    
     /// Serialize block data to JSON-compatible string for hashing
+    /// (blocks without uncles or evidence hash the same as before they existed)
     pub fn to_hash_string(&self) -> String {
-        format!("{}{}", self.nominated_peer_id, self.miner_number)
+        let mut s = format!("{}{}", self.nominated_peer_id, self.miner_number);
+        for uncle in &self.uncles {
+            s.push_str(&uncle.to_hash_string());
+        }
+        for evidence in &self.evidence {
+            s.push_str(&evidence.to_hash_string());
+        }
+        s
     }
 }
 
@@ -41,6 +88,8 @@ pub struct BlockHeader {
     pub timestamp: DateTime<Utc>,
     pub previous_hash: String,
     pub data_hash: String,  // Hash of the BlockData
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub commits_root: String,  // Merkle root of the included commits
     pub nonce: u128,
     pub difficulty: u128,
     pub hash: String,
@@ -48,22 +97,30 @@ pub struct BlockHeader {
 
 impl BlockHeader {
     /// Create block data string for mining (excludes hash and nonce initially)
+    /// (headers without commits hash the same as before commits existed)
     pub fn mining_data(&self) -> String {
         format!(
-            "{}{}{}{}{}",
+            "{}{}{}{}{}{}",
             self.index,
             self.timestamp.timestamp(),
             self.previous_hash,
             self.data_hash,
+            self.commits_root,
             self.difficulty
         )
     }
     
     /// Calculate hash of header with given nonce
     pub fn calculate_hash(&self, nonce: u128) -> String {
-        // Use RandomX for hashing - no fallback
-        hash_tax::hash_with_nonce(&self.mining_data(), nonce, "randomx")
-            .expect("RandomX hashing failed - this should not happen in production")
+        self.calculate_hash_with(nonce, hash_tax::DEFAULT_HASH_FUNC_NAME)
+            .expect("the default hash function is built in")
+    }
+    
+    /// Calculate hash of header with given nonce using a registered hash
+    /// function; the name comes from network config or peers, so it may not be
+    pub fn calculate_hash_with(&self, nonce: u128, hash_func_name: &str) -> Result<String, MiningError> {
+        hash_tax::hash_with_nonce(&self.mining_data(), nonce, hash_func_name)
+            .map_err(|e| MiningError::HashError(format!("{}: {}", hash_func_name, e)))
     }
 }
 
@@ -83,12 +140,14 @@ impl Block {
         difficulty: u128,
     ) -> Self {
         let data_hash = Self::calculate_data_hash(&data);
+        let commits_root = data.commits_root();
         
         let header = BlockHeader {
             index,
             timestamp: Utc::now(),
             previous_hash,
             data_hash,
+            commits_root,
             nonce: 0,
             difficulty,
             hash: String::new(),
@@ -117,6 +176,7 @@ impl Block {
             timestamp: Utc.timestamp_opt(GENESIS_TIMESTAMP, 0).unwrap(),
             previous_hash: "0".to_string(),
             data_hash,
+            commits_root: String::new(),
             nonce: 0,
             difficulty,
             hash: String::new(),
@@ -167,10 +227,22 @@ impl Block {
         calculated == self.header.data_hash
     }
     
+    /// Verify the header's commits root matches the included commits
+    pub fn verify_commits_root(&self) -> bool {
+        self.data.commits_root() == self.header.commits_root
+    }
+    
     /// Verify this block's hash is valid
     pub fn verify_hash(&self) -> bool {
-        let calculated = self.header.calculate_hash(self.header.nonce);
-        calculated == self.header.hash
+        self.verify_hash_with(hash_tax::DEFAULT_HASH_FUNC_NAME)
+    }
+    
+    /// Verify the block hash using a registered hash function
+    pub fn verify_hash_with(&self, hash_func_name: &str) -> bool {
+        match hash_tax::hash_with_nonce(&self.mining_data(), self.header.nonce, hash_func_name) {
+            Ok(calculated) => calculated == self.header.hash,
+            Err(_) => false,
+        }
     }
     
     /// Get the mining data for this block
@@ -192,6 +264,41 @@ mod tests {
         assert!(!data.to_hash_string().is_empty());
     }
 
+    #[test]
+    fn test_uncles_change_data_hash() {
+        let plain = BlockData::new("peer".to_string(), 1);
+        let with_uncle = plain.clone().with_uncles(vec![UncleRef {
+            hash: "orphan".to_string(),
+            index: 4,
+            nominated_peer_id: "other_peer".to_string(),
+        }]);
+        
+        assert_eq!(plain.to_hash_string(), "peer1");
+        assert_ne!(plain.to_hash_string(), with_uncle.to_hash_string());
+        
+        // Uncle-free block data serializes exactly as before
+        let json = serde_json::to_string(&plain).unwrap();
+        assert!(!json.contains("uncles"));
+        let parsed: BlockData = serde_json::from_str(&json).unwrap();
+        assert!(parsed.uncles.is_empty());
+    }
+
+    #[test]
+    fn test_commits_root_in_header() {
+        let data = BlockData::new("peer".to_string(), 1);
+        let plain = Block::new(1, "prev".to_string(), data.clone(), 100);
+        assert!(plain.header.commits_root.is_empty());
+        
+        let commit = CommitDigest::new("contract".to_string(), "commit".to_string(), 10);
+        let mut block = Block::new(1, "prev".to_string(), data.with_commits(vec![commit]), 100);
+        block.header.timestamp = plain.header.timestamp;
+        assert!(block.verify_commits_root());
+        assert_ne!(block.mining_data(), plain.mining_data());
+        
+        block.data.commits.clear();
+        assert!(!block.verify_commits_root());
+    }
+
     #[test]
     fn test_default_genesis_block() {
         let genesis = Block::default_genesis(1);
@@ -248,6 +355,9 @@ mod tests {
 
         assert_eq!(hash1, hash2);
         assert_ne!(hash1, hash3);
+        
+        assert_eq!(block.header.calculate_hash_with(0, "sha256").unwrap(), hash1);
+        assert!(matches!(block.header.calculate_hash_with(0, "no-such-hash"), Err(MiningError::HashError(_))));
     }
 }
 
@@ -2,6 +2,10 @@ use crate::block::{Block, BlockData};
 use crate::epoch::EpochManager;
 use crate::error::MiningError;
 use crate::miner::Miner;
+use modal_common::difficulty::DifficultyConfig;
+use modal_common::block_commits::CommitLimits;
+use modal_common::eras::Era;
+use chrono::{DateTime, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -12,6 +16,21 @@ pub struct ChainConfig {
     pub target_block_time_secs: u64,
     #[serde(default)]
     pub mining_delay_ms: Option<u64>,
+    #[serde(default)]
+    pub difficulty_algorithm: DifficultyConfig,
+    #[serde(default = "default_blocks_per_epoch")]
+    pub blocks_per_epoch: u64,
+    #[serde(default)]
+    pub eras: Vec<Era>,
+    #[serde(default)]
+    pub commit_limits: CommitLimits,
+    /// Mine at zero difficulty with fixed timestamps, for reproducible tests
+    #[serde(default)]
+    pub regtest: bool,
+}
+
+fn default_blocks_per_epoch() -> u64 {
+    crate::BLOCKS_PER_EPOCH
 }
 
 impl Default for ChainConfig {
@@ -20,10 +39,51 @@ impl Default for ChainConfig {
             initial_difficulty: 1000,
             target_block_time_secs: 60, // 1 minute
             mining_delay_ms: None,
+            difficulty_algorithm: DifficultyConfig::default(),
+            blocks_per_epoch: crate::BLOCKS_PER_EPOCH,
+            eras: Vec::new(),
+            commit_limits: CommitLimits::default(),
+            regtest: false,
         }
     }
 }
 
+impl ChainConfig {
+    /// Configuration for regtest chains: every block has zero difficulty and
+    /// block `n` is timestamped `n * target_block_time_secs` after genesis
+    pub fn regtest() -> Self {
+        Self {
+            initial_difficulty: 0,
+            difficulty_algorithm: DifficultyConfig::Fixed,
+            regtest: true,
+            ..Default::default()
+        }
+    }
+    
+    /// Epoch manager applying this configuration's difficulty and epoch parameters
+    pub fn epoch_manager(&self) -> EpochManager {
+        let mut epoch_manager = EpochManager::new(
+            self.blocks_per_epoch,
+            self.target_block_time_secs,
+            self.initial_difficulty,
+        )
+        .with_difficulty_algorithm(self.difficulty_algorithm.build())
+        .with_eras(self.eras.clone());
+        if self.regtest {
+            epoch_manager.initial_difficulty = 0;
+            epoch_manager.min_difficulty = 0;
+            epoch_manager = epoch_manager.with_difficulty_algorithm(DifficultyConfig::Fixed.build());
+        }
+        epoch_manager
+    }
+    
+    /// Fixed timestamp of regtest block `index`
+    pub fn regtest_timestamp(&self, index: u64) -> DateTime<Utc> {
+        let secs = crate::block::GENESIS_TIMESTAMP + (index * self.target_block_time_secs) as i64;
+        Utc.timestamp_opt(secs, 0).unwrap()
+    }
+}
+
 /// The main blockchain structure
 #[derive(Debug, Clone)]
 pub struct Blockchain {
@@ -48,11 +108,7 @@ impl Blockchain {
     /// The default genesis has no nomination (empty nominated_peer_id)
     /// and a fixed timestamp, ensuring all nodes produce identical genesis blocks.
     pub fn new_with_default_genesis(config: ChainConfig) -> Self {
-        let epoch_manager = EpochManager::new(
-            40, // BLOCKS_PER_EPOCH
-            config.target_block_time_secs,
-            config.initial_difficulty,
-        );
+        let epoch_manager = config.epoch_manager();
         
         let genesis = Block::default_genesis(config.initial_difficulty);
         let mut block_index = HashMap::new();
@@ -63,6 +119,7 @@ impl Blockchain {
             max_tries: None,
             hash_func_name: Some("randomx"),
             mining_delay_ms: config.mining_delay_ms,
+            ..Default::default()
         };
         
         Self {
@@ -85,11 +142,7 @@ impl Blockchain {
     /// credited in the genesis block. For public networks, use `new_with_default_genesis()`.
     #[allow(deprecated)]
     pub fn new(config: ChainConfig, genesis_peer_id: String) -> Self {
-        let epoch_manager = EpochManager::new(
-            40, // BLOCKS_PER_EPOCH
-            config.target_block_time_secs,
-            config.initial_difficulty,
-        );
+        let epoch_manager = config.epoch_manager();
         
         let genesis = Block::genesis(config.initial_difficulty, genesis_peer_id.clone());
         let mut block_index = HashMap::new();
@@ -100,6 +153,7 @@ impl Blockchain {
             max_tries: None,
             hash_func_name: Some("randomx"),
             mining_delay_ms: config.mining_delay_ms,
+            ..Default::default()
         };
         
         Self {
@@ -133,11 +187,7 @@ impl Blockchain {
         config: ChainConfig,
         datastore_manager: std::sync::Arc<tokio::sync::Mutex<modal_datastore::DatastoreManager>>,
     ) -> Self {
-        let epoch_manager = EpochManager::new(
-            40,
-            config.target_block_time_secs,
-            config.initial_difficulty,
-        );
+        let epoch_manager = config.epoch_manager();
         
         let genesis = Block::default_genesis(config.initial_difficulty);
         let mut block_index = HashMap::new();
@@ -147,6 +197,7 @@ impl Blockchain {
             max_tries: None,
             hash_func_name: Some("randomx"),
             mining_delay_ms: config.mining_delay_ms,
+            ..Default::default()
         };
         
         Self {
@@ -169,11 +220,7 @@ impl Blockchain {
         genesis_peer_id: String,
         datastore_manager: std::sync::Arc<tokio::sync::Mutex<modal_datastore::DatastoreManager>>,
     ) -> Self {
-        let epoch_manager = EpochManager::new(
-            40,
-            config.target_block_time_secs,
-            config.initial_difficulty,
-        );
+        let epoch_manager = config.epoch_manager();
         
         let genesis = Block::genesis(config.initial_difficulty, genesis_peer_id.clone());
         let mut block_index = HashMap::new();
@@ -183,6 +230,7 @@ impl Blockchain {
             max_tries: None,
             hash_func_name: Some("randomx"),
             mining_delay_ms: config.mining_delay_ms,
+            ..Default::default()
         };
         
         Self {
@@ -244,11 +292,7 @@ impl Blockchain {
             Ok(chain)
         } else {
             // Load existing blockchain
-            let epoch_manager = EpochManager::new(
-                40,
-                config.target_block_time_secs,
-                config.initial_difficulty,
-            );
+            let epoch_manager = config.epoch_manager();
             
             let mut block_index = HashMap::new();
             for (idx, block) in loaded_blocks.iter().enumerate() {
@@ -259,6 +303,7 @@ impl Blockchain {
                 max_tries: None,
                 hash_func_name: Some("randomx"),
                 mining_delay_ms: config.mining_delay_ms,
+                ..Default::default()
             };
             
             // Extract genesis_peer_id from loaded genesis block
@@ -308,11 +353,7 @@ impl Blockchain {
             Ok(chain)
         } else {
             // Load existing blockchain
-            let epoch_manager = EpochManager::new(
-                40,
-                config.target_block_time_secs,
-                config.initial_difficulty,
-            );
+            let epoch_manager = config.epoch_manager();
             
             let mut block_index = HashMap::new();
             for (idx, block) in loaded_blocks.iter().enumerate() {
@@ -323,6 +364,7 @@ impl Blockchain {
                 max_tries: None,
                 hash_func_name: Some("randomx"),
                 mining_delay_ms: config.mining_delay_ms,
+                ..Default::default()
             };
             
             Ok(Self {
@@ -370,6 +412,24 @@ impl Blockchain {
             .get_difficulty_for_block(next_index, &self.blocks)
     }
     
+    /// Build the unmined next block on top of the current tip
+    ///
+    /// The returned block has the correct index, previous hash and difficulty;
+    /// only the nonce and hash still need to be found.
+    pub fn next_block_template(&self, data: BlockData) -> Block {
+        let index = self.height() + 1;
+        let mut block = Block::new(
+            index,
+            self.latest_block().header.hash.clone(),
+            data,
+            self.get_next_difficulty(),
+        );
+        if self.config.regtest {
+            block.header.timestamp = self.config.regtest_timestamp(index);
+        }
+        block
+    }
+    
     /// Mine a new block with the provided block data
     ///
     /// # Arguments
@@ -380,20 +440,7 @@ impl Blockchain {
         nominated_peer_id: String,
         miner_number: u64,
     ) -> Result<Block, MiningError> {
-        let next_index = self.height() + 1;
-        let next_difficulty = self.get_next_difficulty();
-        let previous_hash = self.latest_block().header.hash.clone();
-        
-        // Create block data with nominated peer ID
-        let block_data = BlockData::new(nominated_peer_id, miner_number);
-        
-        // Create new block
-        let block = Block::new(
-            next_index,
-            previous_hash,
-            block_data,
-            next_difficulty,
-        );
+        let block = self.next_block_template(BlockData::new(nominated_peer_id, miner_number));
         
         // Mine the block
         let mined_block = self.miner.mine_block(block)?;
@@ -412,20 +459,16 @@ impl Blockchain {
         nominated_peer_id: String,
         miner_number: u64,
     ) -> Result<(Block, Option<modal_common::hash_tax::MiningResult>), MiningError> {
-        let next_index = self.height() + 1;
-        let next_difficulty = self.get_next_difficulty();
-        let previous_hash = self.latest_block().header.hash.clone();
-        
-        // Create block data with nominated peer ID
-        let block_data = BlockData::new(nominated_peer_id, miner_number);
-        
-        // Create new block
-        let block = Block::new(
-            next_index,
-            previous_hash,
-            block_data,
-            next_difficulty,
-        );
+        self.mine_block_data_with_persistence(BlockData::new(nominated_peer_id, miner_number)).await
+    }
+    
+    #[cfg(feature = "persistence")]
+    /// Mine a new block with the given block data (e.g. including uncles) and persist it
+    pub async fn mine_block_data_with_persistence(
+        &mut self,
+        data: BlockData,
+    ) -> Result<(Block, Option<modal_common::hash_tax::MiningResult>), MiningError> {
+        let block = self.next_block_template(data);
         
         // Mine the block with stats
         let result = self.miner.mine_block_with_stats(block)?;
@@ -538,7 +581,10 @@ impl Blockchain {
                 block.header.difficulty,
                 block.data.nominated_peer_id.clone(),
                 block.data.miner_number,
-            );
+            )
+            .with_uncles(block.data.uncles.clone())
+            .with_commits(block.data.commits.clone())
+            .with_evidence(block.data.evidence.clone());
             
             // Process through fork choice
             let accepted = fork_choice.process_gossiped_block(miner_block).await?;
@@ -608,13 +654,24 @@ impl Blockchain {
             ));
         }
         
+        // Check uncle references
+        modal_common::uncles::validate_uncles(block.header.index, &block.data.uncles)
+            .map_err(|e| MiningError::InvalidBlock(format!("Invalid uncles: {}", e)))?;
+        
+        // Check included commits and the root committed to in the header
+        if !block.verify_commits_root() {
+            return Err(MiningError::InvalidBlock("Commits root doesn't match commits".to_string()));
+        }
+        modal_common::block_commits::validate_commits(&block.data.commits, &self.config.commit_limits)
+            .map_err(|e| MiningError::InvalidBlock(format!("Invalid commits: {}", e)))?;
+        
         // Verify hash
-        if !block.verify_hash() {
+        if !self.miner.verify_hash(block) {
             return Err(MiningError::InvalidBlock("Invalid hash".to_string()));
         }
         
         // Verify proof of work
-        if !self.miner.verify_block(block)? {
+        if !self.meets_difficulty(block)? {
             return Err(MiningError::InvalidBlock(
                 "Block doesn't meet difficulty requirement".to_string(),
             ));
@@ -633,6 +690,19 @@ impl Blockchain {
         Ok(())
     }
     
+    /// Whether a block's hash meets its difficulty; regtest chains mine at
+    /// zero difficulty, which no other chain may
+    fn meets_difficulty(&self, block: &Block) -> Result<bool, MiningError> {
+        if self.config.regtest {
+            return Ok(modal_common::hash_tax::is_hash_acceptable_regtest(
+                &block.header.hash,
+                block.header.difficulty,
+                self.miner.hash_func_name(),
+            ));
+        }
+        self.miner.verify_block(block)
+    }
+    
     /// Validate the entire blockchain
     pub fn validate_chain(&self) -> Result<(), MiningError> {
         // Genesis block validation
@@ -665,7 +735,7 @@ impl Blockchain {
             }
             
             // Verify hash
-            if !block.verify_hash() {
+            if !self.miner.verify_hash(block) {
                 return Err(MiningError::InvalidChain(format!(
                     "Invalid hash at block {}",
                     block.header.index
@@ -673,7 +743,7 @@ impl Blockchain {
             }
             
             // Verify proof of work
-            if !self.miner.verify_block(block)? {
+            if !self.meets_difficulty(block)? {
                 return Err(MiningError::InvalidChain(format!(
                     "Invalid proof of work at block {}",
                     block.header.index
@@ -725,7 +795,7 @@ impl Blockchain {
         let epoch_blocks = self.get_epoch_blocks(epoch);
         
         // Only return shuffled nominations if the epoch is complete
-        if epoch_blocks.len() < self.epoch_manager.blocks_per_epoch as usize {
+        if epoch_blocks.len() < self.epoch_manager.get_epoch_length(epoch) as usize {
             return None;
         }
         
@@ -797,6 +867,7 @@ mod tests {
                 initial_difficulty: 100, // Low difficulty for fast test
                 target_block_time_secs: 600,
                 mining_delay_ms: None,
+                ..Default::default()
             },
         );
         
@@ -816,6 +887,7 @@ mod tests {
                 initial_difficulty: 100,
                 target_block_time_secs: 600,
                 mining_delay_ms: None,
+                ..Default::default()
             },
         );
         
@@ -824,6 +896,23 @@ mod tests {
         assert!(chain.validate_chain().is_ok());
     }
     
+    #[test]
+    fn test_regtest_chain_is_reproducible() {
+        let mine_chain = || {
+            let mut chain = Blockchain::new_with_default_genesis(ChainConfig::regtest());
+            for i in 0..3 {
+                chain.mine_block("regtest_peer".to_string(), i).unwrap();
+            }
+            chain
+        };
+        
+        let chain = mine_chain();
+        assert!(chain.validate_chain().is_ok());
+        assert!(chain.blocks.iter().all(|b| b.header.difficulty == 0));
+        assert_eq!(chain.blocks[2].header.timestamp.timestamp(), 120);
+        assert_eq!(chain.latest_block().header.hash, mine_chain().latest_block().header.hash);
+    }
+    
     #[test]
     fn test_count_blocks_by_nominated_peer() {
         let nominated_peer_id = "nominated_peer_1".to_string();
@@ -833,6 +922,7 @@ mod tests {
                 initial_difficulty: 100,
                 target_block_time_secs: 600,
                 mining_delay_ms: None,
+                ..Default::default()
             },
         );
         
@@ -852,6 +942,7 @@ mod tests {
                 initial_difficulty: 50, // Very low for fast mining
                 target_block_time_secs: 600,
                 mining_delay_ms: None,
+                ..Default::default()
             },
         );
         
@@ -877,6 +968,7 @@ mod tests {
                 initial_difficulty: 100,
                 target_block_time_secs: 600,
                 mining_delay_ms: None,
+                ..Default::default()
             },
         );
         
@@ -895,6 +987,7 @@ mod tests {
                 initial_difficulty: 50,
                 target_block_time_secs: 600,
                 mining_delay_ms: None,
+                ..Default::default()
             },
         );
         
@@ -920,6 +1013,7 @@ mod tests {
                 initial_difficulty: 50,
                 target_block_time_secs: 60,
                 mining_delay_ms: None,
+                ..Default::default()
             },
         );
         
@@ -940,6 +1034,7 @@ mod tests {
                 initial_difficulty: 50,
                 target_block_time_secs: 60,
                 mining_delay_ms: None,
+                ..Default::default()
             },
         );
         
@@ -968,6 +1063,7 @@ mod tests {
                 initial_difficulty: 50,
                 target_block_time_secs: 60,
                 mining_delay_ms: None,
+                ..Default::default()
             },
         );
         
@@ -996,6 +1092,7 @@ mod tests {
                 initial_difficulty: 50,
                 target_block_time_secs: 60,
                 mining_delay_ms: None,
+                ..Default::default()
             },
         );
         
@@ -1,14 +1,49 @@
+//! Epochs, difficulty adjustment and the nominee shuffle.
+//!
+//! # Nominee shuffle
+//!
+//! Validators for an epoch are seated from the peers its blocks nominated,
+//! and anyone holding the chain can check the seating with the functions
+//! below:
+//!
+//! 1. The seed is the XOR of the low 64 bits of every block nonce in the
+//!    epoch ([`epoch_seed`]).
+//! 2. The nominees are each block's nominated peer in block index order,
+//!    followed by the nominees of the uncles credited to the epoch.
+//! 3. `fisher_yates_shuffle(seed, nominees.len())` from
+//!    `modal_common::shuffle` orders them; its generator hashes the state
+//!    with SHA-256 and takes the first 8 bytes, little-endian, as the next
+//!    state ([`shuffle_nominees`]).
+//! 4. The shuffled list keeps the first occurrence of each peer, then drops
+//!    peers the slashing ledger excludes at the epoch. The first 27 left are
+//!    the nominated validators and, when there are more than 27, the last 13
+//!    are the alternates ([`seat_nominees`]).
+
 use crate::block::Block;
 use crate::BLOCKS_PER_EPOCH;
+use modal_common::difficulty::{
+    epoch_step, DifficultyAlgorithm, DifficultyParams, DifficultySample, EpochRetarget,
+};
+use modal_common::eras::{Era, EraSchedule};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
 
 /// Manages epochs and difficulty adjustment
+///
+/// `blocks_per_epoch` and `target_block_time_secs` are the base parameters;
+/// `eras` change them from later heights. Since epoch 0 starts at block 1,
+/// era activation heights count from block 1 as well.
 #[derive(Debug, Clone)]
 pub struct EpochManager {
     pub blocks_per_epoch: u64,
     pub target_block_time_secs: u64,
+    pub eras: Vec<Era>,
     pub initial_difficulty: u128,
     pub min_difficulty: u128,
     pub max_difficulty: u128,
+    pub difficulty_algorithm: Arc<dyn DifficultyAlgorithm>,
+    /// Peers slashed out of nominee selection, by nomination epoch
+    pub excluded_peers: BTreeMap<u64, BTreeSet<String>>,
 }
 
 impl Default for EpochManager {
@@ -16,9 +51,12 @@ impl Default for EpochManager {
         Self {
             blocks_per_epoch: BLOCKS_PER_EPOCH,
             target_block_time_secs: 60, // 1 minute per block
+            eras: Vec::new(),
             initial_difficulty: 1000,
             min_difficulty: 1,
             max_difficulty: u128::MAX,
+            difficulty_algorithm: Arc::new(EpochRetarget),
+            excluded_peers: BTreeMap::new(),
         }
     }
 }
@@ -32,9 +70,65 @@ impl EpochManager {
         Self {
             blocks_per_epoch,
             target_block_time_secs,
+            eras: Vec::new(),
             initial_difficulty,
             min_difficulty: 1,
             max_difficulty: u128::MAX,
+            difficulty_algorithm: Arc::new(EpochRetarget),
+            excluded_peers: BTreeMap::new(),
+        }
+    }
+
+    /// Use a different difficulty adjustment algorithm (defaults to per-epoch retargeting)
+    pub fn with_difficulty_algorithm(mut self, difficulty_algorithm: Arc<dyn DifficultyAlgorithm>) -> Self {
+        self.difficulty_algorithm = difficulty_algorithm;
+        self
+    }
+
+    /// Change epoch length and target block time from later heights
+    pub fn with_eras(mut self, eras: Vec<Era>) -> Self {
+        self.eras = eras;
+        self
+    }
+
+    /// Leave `peers` out of the shuffled nominations of `epoch`
+    pub fn exclude_peers(&mut self, epoch: u64, peers: impl IntoIterator<Item = String>) {
+        self.excluded_peers.entry(epoch).or_default().extend(peers);
+    }
+
+    /// Peers left out of the shuffled nominations of `epoch`
+    pub fn excluded_peers_at(&self, epoch: u64) -> BTreeSet<String> {
+        self.excluded_peers.get(&epoch).cloned().unwrap_or_default()
+    }
+
+    /// Base parameters and eras as a schedule over block positions (block index - 1)
+    pub fn schedule(&self) -> EraSchedule {
+        EraSchedule {
+            blocks_per_epoch: self.blocks_per_epoch,
+            target_block_time_secs: self.target_block_time_secs,
+            eras: self.eras.clone(),
+        }
+    }
+
+    /// Parameters passed to the difficulty algorithm
+    pub fn difficulty_params(&self) -> DifficultyParams {
+        DifficultyParams {
+            initial_difficulty: self.initial_difficulty,
+            target_block_time_secs: self.target_block_time_secs,
+            blocks_per_epoch: self.blocks_per_epoch,
+            min_difficulty: self.min_difficulty,
+            max_difficulty: self.max_difficulty,
+        }
+    }
+
+    /// Difficulty parameters in effect for the block at `block_index`
+    pub fn difficulty_params_at(&self, block_index: u64) -> DifficultyParams {
+        let schedule = self.schedule();
+        let position = block_index.saturating_sub(1);
+        DifficultyParams {
+            target_block_time_secs: schedule.target_block_time_at(position),
+            blocks_per_epoch: schedule.blocks_per_epoch_at(position),
+            ..self.difficulty_params()
         }
     }
     
@@ -55,7 +149,7 @@ impl EpochManager {
         }
         // Epoch 0 is blocks 1 to blocks_per_epoch
         // Epoch 1 is blocks (blocks_per_epoch + 1) to (2 * blocks_per_epoch)
-        (block_index - 1) / self.blocks_per_epoch
+        self.schedule().epoch_of(block_index - 1)
     }
     
     /// Check if a block is the genesis block (precedes all epochs)
@@ -70,7 +164,7 @@ impl EpochManager {
             return false; // Genesis is not the start of an epoch
         }
         // Block 1 starts epoch 0, block 41 starts epoch 1, etc.
-        (block_index - 1).is_multiple_of(self.blocks_per_epoch)
+        self.schedule().is_epoch_start(block_index - 1)
     }
     
     /// Check if a block is the last in its epoch
@@ -80,17 +174,23 @@ impl EpochManager {
             return false; // Genesis is not part of an epoch
         }
         // Block 40 ends epoch 0, block 80 ends epoch 1, etc.
-        (block_index - 1) % self.blocks_per_epoch == self.blocks_per_epoch - 1
+        self.schedule().is_epoch_start(block_index)
     }
     
     /// Get the first block index of an epoch (excluding genesis from epoch 0)
     pub fn get_epoch_start_index(&self, epoch: u64) -> u64 {
-        epoch * self.blocks_per_epoch + 1
+        self.schedule().epoch_start(epoch) + 1
     }
     
     /// Get the last block index of an epoch
     pub fn get_epoch_end_index(&self, epoch: u64) -> u64 {
-        (epoch + 1) * self.blocks_per_epoch
+        let schedule = self.schedule();
+        schedule.epoch_start(epoch) + schedule.epoch_length(epoch)
+    }
+    
+    /// Number of blocks in an epoch
+    pub fn get_epoch_length(&self, epoch: u64) -> u64 {
+        self.schedule().epoch_length(epoch)
     }
     
     /// Calculate difficulty for next epoch based on previous epoch's blocks
@@ -104,55 +204,27 @@ impl EpochManager {
             return self.initial_difficulty;
         }
         
+        let first_block = &epoch_blocks[0];
+        let last_block = &epoch_blocks[epoch_blocks.len() - 1];
+        let params = self.difficulty_params_at(first_block.header.index);
+        
         // If we don't have a full epoch, keep current difficulty
-        if epoch_blocks.len() < self.blocks_per_epoch as usize {
+        if epoch_blocks.len() < params.blocks_per_epoch as usize {
             return current_difficulty;
         }
         
-        let first_block = &epoch_blocks[0];
-        let last_block = &epoch_blocks[epoch_blocks.len() - 1];
-        
         // Calculate actual time taken for the epoch
         let actual_time_secs = (last_block.header.timestamp - first_block.header.timestamp)
             .num_seconds()
             .max(1) as u64;
         
         // Calculate expected time for the epoch
-        let expected_time_secs = self.target_block_time_secs * self.blocks_per_epoch;
+        let expected_time_secs = params.target_block_time_secs * params.blocks_per_epoch;
         
         // Adjust difficulty based on ratio of actual to expected time
         // If blocks were mined too quickly, increase difficulty (max 8x)
         // If blocks were mined too slowly, decrease difficulty (min 0.5x/halve)
-        let ratio = (actual_time_secs as f64) / (expected_time_secs as f64);
-        
-        let new_difficulty = if ratio < 0.125 {
-            // Extremely too fast (8x faster), increase difficulty by 8x
-            current_difficulty.saturating_mul(8)
-        } else if ratio < 0.25 {
-            // Much too fast (4x faster), quadruple difficulty
-            current_difficulty.saturating_mul(4)
-        } else if ratio < 0.5 {
-            // Too fast (2x faster), double difficulty
-            current_difficulty.saturating_mul(2)
-        } else if ratio < 0.75 {
-            // Too fast, increase by 50%
-            current_difficulty.saturating_mul(3) / 2
-        } else if ratio < 0.9 {
-            // Slightly fast, increase by 10%
-            current_difficulty.saturating_mul(11) / 10
-        } else if ratio > 2.0 {
-            // Much too slow, halve difficulty (max decrease is 0.5x)
-            current_difficulty / 2
-        } else if ratio > 1.5 {
-            // Too slow, decrease by 33%
-            current_difficulty * 2 / 3
-        } else if ratio > 1.1 {
-            // Slightly slow, decrease by 10%
-            current_difficulty * 9 / 10
-        } else {
-            // Just right, keep the same
-            current_difficulty
-        };
+        let new_difficulty = epoch_step(current_difficulty, actual_time_secs, expected_time_secs);
         
         // Clamp to min/max bounds
         new_difficulty.clamp(self.min_difficulty, self.max_difficulty)
@@ -160,6 +232,8 @@ impl EpochManager {
     
     /// Get difficulty for a specific block index
     /// 
+    /// Delegates to the configured difficulty algorithm. With the default
+    /// per-epoch retargeting:
     /// Genesis block always uses initial_difficulty.
     /// Blocks in epoch 0 use initial_difficulty.
     /// Blocks in epoch N (N > 0) use adjusted difficulty based on epoch N-1.
@@ -168,53 +242,28 @@ impl EpochManager {
         block_index: u64,
         chain_blocks: &[Block],
     ) -> u128 {
-        // Genesis block always uses initial difficulty
-        if block_index == 0 {
-            return self.initial_difficulty;
-        }
-        
-        let current_epoch = self.get_epoch(block_index);
-        
-        // First epoch uses initial difficulty
-        if current_epoch == 0 {
-            return self.initial_difficulty;
-        }
-        
-        // Get blocks from previous epoch (excluding genesis)
-        let prev_epoch_start = self.get_epoch_start_index(current_epoch - 1);
-        let prev_epoch_end = self.get_epoch_end_index(current_epoch - 1);
-        
-        let epoch_blocks: Vec<Block> = chain_blocks
+        let history: Vec<DifficultySample> = chain_blocks
             .iter()
-            .filter(|b| {
-                b.header.index >= prev_epoch_start && b.header.index <= prev_epoch_end
+            .filter(|b| b.header.index < block_index)
+            .map(|b| DifficultySample {
+                index: b.header.index,
+                timestamp: b.header.timestamp.timestamp(),
+                difficulty: b.header.difficulty,
             })
-            .cloned()
             .collect();
         
-        if epoch_blocks.is_empty() {
-            return self.initial_difficulty;
-        }
-        
-        let last_difficulty = epoch_blocks.last().unwrap().header.difficulty;
-        
-        self.calculate_next_difficulty(&epoch_blocks, last_difficulty)
+        self.difficulty_for_samples(block_index, &history)
+    }
+    
+    /// Like `get_difficulty_for_block`, from the preceding blocks' samples
+    pub fn difficulty_for_samples(&self, block_index: u64, history: &[DifficultySample]) -> u128 {
+        self.difficulty_algorithm
+            .next_difficulty(&self.difficulty_params_at(block_index), block_index, history)
     }
     
     /// Calculate seed from XOR of all nonces in the epoch
     pub fn calculate_epoch_seed(&self, epoch_blocks: &[Block]) -> u64 {
-        if epoch_blocks.is_empty() {
-            return 0;
-        }
-        
-        // XOR all nonces together to create a seed
-        let mut seed = 0u64;
-        for block in epoch_blocks {
-            // XOR with the lower 64 bits of the nonce (u128)
-            seed ^= block.header.nonce as u64;
-        }
-        
-        seed
+        epoch_seed(epoch_blocks.iter().map(|block| block.header.nonce))
     }
     
     /// Get shuffled nominations for a completed epoch
@@ -223,7 +272,8 @@ impl EpochManager {
     /// XORs all the nonces to create a seed, and shuffles the nominations
     /// using the Fisher-Yates algorithm.
     /// 
-    /// Returns a vector of (index_in_epoch, nominated_peer_id) tuples in shuffled order
+    /// Returns a vector of (index_in_epoch, nominated_peer_id) tuples in shuffled order,
+    /// without the nominations of peers excluded at the epoch
     pub fn get_shuffled_nominations(&self, epoch_blocks: &[Block]) -> Vec<(usize, String)> {
         if epoch_blocks.is_empty() {
             return Vec::new();
@@ -234,11 +284,13 @@ impl EpochManager {
         
         // Get the shuffled indices
         let shuffled_indices = modal_common::shuffle::fisher_yates_shuffle(seed, epoch_blocks.len());
+        let excluded = self.excluded_peers.get(&self.get_epoch(epoch_blocks[0].header.index));
         
         // Map shuffled indices to (original_index, nominated_peer_id) tuples
         shuffled_indices
             .into_iter()
             .map(|idx| (idx, epoch_blocks[idx].data.nominated_peer_id.clone()))
+            .filter(|(_, peer_id)| excluded.is_none_or(|excluded| !excluded.contains(peer_id)))
             .collect()
     }
     
@@ -251,6 +303,95 @@ impl EpochManager {
     }
 }
 
+/// Number of nominated validators seated from an epoch's shuffle
+pub const NOMINATED_SEATS: usize = 27;
+
+/// Number of alternates seated from the end of an epoch's shuffle
+pub const ALTERNATE_SEATS: usize = 13;
+
+/// Seed of an epoch's shuffle: the XOR of the low 64 bits of its block nonces
+pub fn epoch_seed(nonces: impl IntoIterator<Item = u128>) -> u64 {
+    nonces.into_iter().fold(0, |seed, nonce| seed ^ nonce as u64)
+}
+
+/// Nominees in the order the shuffle seeded by `seed` puts them
+pub fn shuffle_nominees(seed: u64, nominees: &[String]) -> Vec<String> {
+    modal_common::shuffle::fisher_yates_shuffle(seed, nominees.len())
+        .into_iter()
+        .map(|i| nominees[i].clone())
+        .collect()
+}
+
+/// Validators seated from an epoch's nominations
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Seating {
+    pub nominated: Vec<String>,
+    pub alternates: Vec<String>,
+}
+
+/// Seat validators from a shuffled nominee list, skipping excluded peers
+pub fn seat_nominees(shuffled: &[String], excluded: &BTreeSet<String>) -> Seating {
+    let mut seen = std::collections::HashSet::new();
+    let unique: Vec<String> = shuffled
+        .iter()
+        .filter(|peer_id| seen.insert(*peer_id) && !excluded.contains(*peer_id))
+        .cloned()
+        .collect();
+
+    let alternates = if unique.len() > NOMINATED_SEATS {
+        unique[unique.len().saturating_sub(ALTERNATE_SEATS)..].to_vec()
+    } else {
+        Vec::new()
+    };
+    Seating {
+        nominated: unique.into_iter().take(NOMINATED_SEATS).collect(),
+        alternates,
+    }
+}
+
+/// Seating an epoch with these block nonces, nominees and excluded peers should have
+pub fn expected_seating(
+    nonces: impl IntoIterator<Item = u128>,
+    nominees: &[String],
+    excluded: &BTreeSet<String>,
+) -> Seating {
+    seat_nominees(&shuffle_nominees(epoch_seed(nonces), nominees), excluded)
+}
+
+/// How a seating differs from the one the shuffle gives
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ShuffleMismatch {
+    #[error("nominated validators differ from the shuffle: expected {expected:?}, got {actual:?}")]
+    Nominated { expected: Vec<String>, actual: Vec<String> },
+
+    #[error("alternate validators differ from the shuffle: expected {expected:?}, got {actual:?}")]
+    Alternates { expected: Vec<String>, actual: Vec<String> },
+}
+
+/// Check `seating` against the seating the shuffle gives for these block
+/// nonces, nominees and excluded peers
+pub fn verify_seating(
+    nonces: impl IntoIterator<Item = u128>,
+    nominees: &[String],
+    excluded: &BTreeSet<String>,
+    seating: &Seating,
+) -> Result<(), ShuffleMismatch> {
+    let expected = expected_seating(nonces, nominees, excluded);
+    if expected.nominated != seating.nominated {
+        return Err(ShuffleMismatch::Nominated {
+            expected: expected.nominated,
+            actual: seating.nominated.clone(),
+        });
+    }
+    if expected.alternates != seating.alternates {
+        return Err(ShuffleMismatch::Alternates {
+            expected: expected.alternates,
+            actual: seating.alternates.clone(),
+        });
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -322,6 +463,30 @@ mod tests {
         assert_eq!(manager.get_epoch_end_index(2), 120);
     }
     
+    #[test]
+    fn test_epoch_length_changes_with_era() {
+        use modal_common::eras::Era;
+        
+        // Epochs 0-9 are 40 blocks, then 20 blocks from block 401
+        let manager = EpochManager::new(40, 60, 1000).with_eras(vec![Era {
+            activation_height: 400,
+            blocks_per_epoch: Some(20),
+            target_block_time_secs: Some(30),
+        }]);
+        
+        assert_eq!(manager.get_epoch(400), 9);
+        assert_eq!(manager.get_epoch(401), 10);
+        assert_eq!(manager.get_epoch(421), 11);
+        assert!(manager.is_epoch_end(400));
+        assert!(manager.is_epoch_start(421));
+        assert!(manager.is_epoch_end(420));
+        assert_eq!(manager.get_epoch_start_index(11), 421);
+        assert_eq!(manager.get_epoch_end_index(11), 440);
+        assert_eq!(manager.get_epoch_length(11), 20);
+        assert_eq!(manager.difficulty_params_at(401).target_block_time_secs, 30);
+        assert_eq!(manager.difficulty_params_at(400).blocks_per_epoch, 40);
+    }
+    
     #[test]
     fn test_difficulty_adjustment_fast_mining() {
         use crate::block::BlockData;
@@ -599,5 +764,72 @@ mod tests {
             assert!(blocks.iter().any(|b| &b.data.nominated_peer_id == peer_id));
         }
     }
+    
+    #[test]
+    fn test_seat_nominees_dedupes_and_takes_both_ends() {
+        let peers: Vec<String> = (0..45).map(|i| format!("peer_{}", i)).collect();
+        let mut shuffled = peers.clone();
+        shuffled.insert(5, "peer_0".to_string());
+        
+        let seating = seat_nominees(&shuffled, &BTreeSet::new());
+        assert_eq!(seating.nominated, peers[..27].to_vec());
+        assert_eq!(seating.alternates, peers[32..].to_vec());
+        
+        // 27 or fewer distinct nominees leave no alternates
+        let seating = seat_nominees(&peers[..27], &BTreeSet::new());
+        assert_eq!(seating.nominated.len(), 27);
+        assert!(seating.alternates.is_empty());
+    }
+    
+    #[test]
+    fn test_verify_seating() {
+        let nonces: Vec<u128> = (1..=40).map(|n| n * 7919).collect();
+        let nominees: Vec<String> = (0..40).map(|i| format!("peer_{}", i)).collect();
+        let none = BTreeSet::new();
+        let seating = expected_seating(nonces.clone(), &nominees, &none);
+        assert_eq!(seating.nominated.len(), NOMINATED_SEATS);
+        assert_eq!(seating.alternates.len(), ALTERNATE_SEATS);
+        assert!(verify_seating(nonces.clone(), &nominees, &none, &seating).is_ok());
+        
+        let mut tampered = seating.clone();
+        tampered.nominated.swap(0, 1);
+        assert!(matches!(
+            verify_seating(nonces.clone(), &nominees, &none, &tampered),
+            Err(ShuffleMismatch::Nominated { .. })
+        ));
+        
+        // A different nonce gives a different seed and, here, a different order
+        let mut other_nonces = nonces;
+        other_nonces[0] += 1;
+        assert!(verify_seating(other_nonces, &nominees, &none, &seating).is_err());
+    }
+    
+    #[test]
+    fn test_excluded_peers_are_not_seated() {
+        use crate::block::BlockData;
+        
+        let nonces: Vec<u128> = (1..=40).map(|n| n * 7919).collect();
+        let nominees: Vec<String> = (0..40).map(|i| format!("peer_{}", i)).collect();
+        let seating = expected_seating(nonces.clone(), &nominees, &BTreeSet::new());
+        let slashed = seating.nominated[3].clone();
+        let excluded = BTreeSet::from([slashed.clone()]);
+        
+        // Everyone after the excluded peer moves up a rank
+        let with_exclusion = expected_seating(nonces.clone(), &nominees, &excluded);
+        assert!(!with_exclusion.nominated.contains(&slashed));
+        assert_eq!(with_exclusion.nominated[3], seating.nominated[4]);
+        assert!(verify_seating(nonces.clone(), &nominees, &excluded, &with_exclusion).is_ok());
+        assert!(verify_seating(nonces, &nominees, &excluded, &seating).is_err());
+        
+        // The epoch manager leaves the same peer out of the epoch's shuffle
+        let mut manager = EpochManager::default();
+        let blocks: Vec<Block> = (0..40)
+            .map(|i| Block::new(i, format!("prev_{}", i), BlockData::new(format!("peer_{}", i), i), 100))
+            .collect();
+        manager.exclude_peers(0, [blocks[7].data.nominated_peer_id.clone()]);
+        let shuffled = manager.get_shuffled_nominated_peer_ids(&blocks);
+        assert_eq!(shuffled.len(), 39);
+        assert!(!shuffled.contains(&"peer_7".to_string()));
+        assert_eq!(manager.excluded_peers_at(1), BTreeSet::new());
+    }
 }
-
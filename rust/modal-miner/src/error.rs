@@ -17,6 +17,9 @@ pub enum MiningError {
     #[error("Invalid nonce")]
     InvalidNonce,
     
+    #[error("Mining cancelled")]
+    Cancelled,
+    
     #[error("Serialization error: {0}")]
     SerializationError(String),
     
@@ -27,3 +30,19 @@ pub enum MiningError {
     PersistenceError(String),
 }
 
+impl modal_common::error_codes::HasErrorCode for MiningError {
+    fn error_code(&self) -> modal_common::error_codes::ErrorCode {
+        use modal_common::error_codes::ErrorCode;
+        match self {
+            MiningError::MiningFailed(_) => ErrorCode::MiningFailed,
+            MiningError::InvalidBlock(_) => ErrorCode::InvalidBlock,
+            MiningError::InvalidChain(_) => ErrorCode::InvalidChain,
+            MiningError::BlockNotFound(_) => ErrorCode::BlockNotFound,
+            MiningError::InvalidNonce => ErrorCode::InvalidNonce,
+            MiningError::Cancelled => ErrorCode::MiningCancelled,
+            MiningError::SerializationError(_) => ErrorCode::SerializationError,
+            MiningError::HashError(_) => ErrorCode::HashError,
+            MiningError::PersistenceError(_) => ErrorCode::PersistenceError,
+        }
+    }
+}
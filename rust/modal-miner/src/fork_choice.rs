@@ -128,7 +128,10 @@ fn block_to_miner_block(block: &Block) -> Result<MinerBlock, MiningError> {
         block.header.difficulty,
         block.data.nominated_peer_id.clone(),
         block.data.miner_number,
-    ))
+    )
+    .with_uncles(block.data.uncles.clone())
+    .with_commits(block.data.commits.clone())
+    .with_evidence(block.data.evidence.clone()))
 }
 
 #[cfg(feature = "persistence")]
@@ -17,6 +17,9 @@ pub use block::{Block, BlockData, BlockHeader, GENESIS_PEER_ID, GENESIS_TIMESTAM
 pub use chain::{Blockchain, ChainConfig};
 pub use miner::{Miner, MinerConfig};
 pub use epoch::EpochManager;
+pub use modal_common::difficulty::{DifficultyAlgorithm, DifficultyConfig};
+pub use modal_common::block_commits::{CommitDigest, CommitLimits};
+pub use modal_common::uncles::UncleRef;
 pub use error::MiningError;
 
 #[cfg(feature = "persistence")]
@@ -28,6 +31,6 @@ pub use fork_choice::MinerForkChoice;
 #[cfg(feature = "persistence")]
 pub use modal_observer::ForkConfig;
 
-/// The number of blocks in each epoch
+/// Default number of blocks in each epoch; networks can change it through eras
 pub const BLOCKS_PER_EPOCH: u64 = 40;
 
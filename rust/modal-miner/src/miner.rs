@@ -1,6 +1,8 @@
-use crate::block::Block;
+use crate::block::{Block, BlockHeader};
 use crate::error::MiningError;
-use modal_common::hash_tax;
+use modal_common::hash_tax::{self, HashRegistry};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 /// Configuration for the miner
 #[derive(Debug, Clone)]
@@ -8,6 +10,12 @@ pub struct MinerConfig {
     pub max_tries: Option<u128>,
     pub hash_func_name: Option<&'static str>,
     pub mining_delay_ms: Option<u64>,
+    /// Number of worker threads to split the nonce space across (default: 1)
+    pub threads: Option<usize>,
+    /// Set to abandon the block being mined, e.g. when a competing block arrives
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// Hash functions to mine and verify with, as configured for the network
+    pub hash_registry: HashRegistry,
 }
 
 impl Default for MinerConfig {
@@ -16,6 +24,9 @@ impl Default for MinerConfig {
             max_tries: None,
             hash_func_name: Some("randomx"),
             mining_delay_ms: None,
+            threads: None,
+            cancel: None,
+            hash_registry: HashRegistry::global(),
         }
     }
 }
@@ -42,6 +53,11 @@ impl Miner {
         Self::new(MinerConfig::default())
     }
     
+    /// Name of the hash function used for mining and verification
+    pub fn hash_func_name(&self) -> &'static str {
+        self.config.hash_func_name.unwrap_or(hash_tax::DEFAULT_HASH_FUNC_NAME)
+    }
+    
     /// Mine a block by finding a valid nonce
     pub fn mine_block(&self, block: Block) -> Result<Block, MiningError> {
         self.mine_block_with_stats(block)
@@ -54,19 +70,29 @@ impl Miner {
         let difficulty = block.header.difficulty;
         
         // Use hash_tax to find a valid nonce with stats
-        let mining_result = hash_tax::mine_with_stats(
+        let hash_function = self.config.hash_registry.get(self.hash_func_name())
+            .map_err(|e| MiningError::HashError(format!("{}: {}", self.hash_func_name(), e)))?;
+        let mining_result = hash_tax::mine_parallel_with(
+            hash_function.as_ref(),
             &mining_data,
             difficulty,
             self.config.max_tries,
-            self.config.hash_func_name,
             self.config.mining_delay_ms,
+            self.config.threads.unwrap_or(1),
+            self.config.cancel.as_deref(),
         )
-        .map_err(|e| MiningError::MiningFailed(e.to_string()))?;
+        .map_err(|e| {
+            if self.config.cancel.as_ref().is_some_and(|c| c.load(Ordering::Relaxed)) {
+                MiningError::Cancelled
+            } else {
+                MiningError::MiningFailed(e.to_string())
+            }
+        })?;
         
         // Update block with found nonce and hash
         let mut mined_block = block;
         mined_block.header.nonce = mining_result.nonce;
-        mined_block.header.hash = mined_block.header.calculate_hash(mining_result.nonce);
+        mined_block.header.hash = self.calculate_hash(&mined_block.header, mining_result.nonce)?;
         
         Ok(MinedBlockResult {
             block: mined_block,
@@ -74,16 +100,28 @@ impl Miner {
         })
     }
     
+    /// Hash of `header` with `nonce`, under the miner's hash function
+    pub fn calculate_hash(&self, header: &BlockHeader, nonce: u128) -> Result<String, MiningError> {
+        self.config.hash_registry.hash_with_nonce(&header.mining_data(), nonce, self.hash_func_name())
+            .map_err(|e| MiningError::HashError(format!("{}: {}", self.hash_func_name(), e)))
+    }
+
+    /// Verify a block's hash under the miner's hash function
+    pub fn verify_hash(&self, block: &Block) -> bool {
+        self.calculate_hash(&block.header, block.header.nonce)
+            .is_ok_and(|calculated| calculated == block.header.hash)
+    }
+
     /// Verify a mined block's nonce is valid
     pub fn verify_block(&self, block: &Block) -> Result<bool, MiningError> {
-        // Verify hash is correct
-        if !block.verify_hash() {
-            return Ok(false);
+        // Genesis block (index 0) is shared by all nodes and always hashed with the default function
+        if block.header.index == 0 {
+            return Ok(block.verify_hash());
         }
         
-        // Genesis block (index 0) is always valid if hash is correct
-        if block.header.index == 0 {
-            return Ok(true);
+        // Verify hash is correct
+        if !self.verify_hash(block) {
+            return Ok(false);
         }
         
         let mining_data = block.mining_data();
@@ -91,11 +129,11 @@ impl Miner {
         let difficulty = block.header.difficulty;
         
         // Verify nonce meets difficulty using hash_tax
-        hash_tax::validate_nonce(
+        self.config.hash_registry.validate_nonce(
             &mining_data,
             nonce,
             difficulty,
-            self.config.hash_func_name.unwrap_or("sha256"),
+            self.hash_func_name(),
         )
         .map_err(|e| MiningError::HashError(e.to_string()))
     }
@@ -141,5 +179,49 @@ mod tests {
         // Genesis block has difficulty 1 and nonce 0, should be valid
         assert!(miner.verify_block(&genesis).unwrap());
     }
+    #[test]
+    fn test_mine_and_verify_with_blake3() {
+        let miner = Miner::new(MinerConfig {
+            hash_func_name: Some("blake3"),
+            ..Default::default()
+        });
+
+        let data = BlockData::new("peer_id_blake3".to_string(), 7);
+        let mined_block = miner.mine_block(Block::new(1, "prev_hash".to_string(), data, 100)).unwrap();
+
+        assert!(mined_block.verify_hash_with("blake3"));
+        assert!(miner.verify_block(&mined_block).unwrap());
+
+        // A different hash function doesn't accept the block
+        let sha256_miner = Miner::new(MinerConfig {
+            hash_func_name: Some("sha256"),
+            ..Default::default()
+        });
+        assert!(!sha256_miner.verify_block(&mined_block).unwrap());
+    }
+
+    #[test]
+    fn test_multithreaded_mining_and_cancellation() {
+        let miner = Miner::new(MinerConfig {
+            hash_func_name: Some("sha256"),
+            threads: Some(4),
+            ..Default::default()
+        });
+        let data = BlockData::new("peer_id_threads".to_string(), 1);
+        let result = miner.mine_block_with_stats(Block::new(1, "prev_hash".to_string(), data, 1000)).unwrap();
+        assert!(miner.verify_block(&result.block).unwrap());
+        assert_eq!(result.mining_stats.threads.len(), 4);
+
+        let cancel = Arc::new(AtomicBool::new(true));
+        let cancelled_miner = Miner::new(MinerConfig {
+            hash_func_name: Some("sha256"),
+            threads: Some(2),
+            cancel: Some(cancel),
+            ..Default::default()
+        });
+        let data = BlockData::new("peer_id_threads".to_string(), 2);
+        let err = cancelled_miner.mine_block(Block::new(1, "prev_hash".to_string(), data, u128::MAX)).unwrap_err();
+        assert!(matches!(err, MiningError::Cancelled));
+    }
 }
 
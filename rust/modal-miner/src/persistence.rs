@@ -49,7 +49,10 @@ impl BlockchainPersistence for DatastoreManager {
             block.header.difficulty,
             block.data.nominated_peer_id.clone(),
             block.data.miner_number,
-        );
+        )
+        .with_uncles(block.data.uncles.clone())
+        .with_commits(block.data.commits.clone())
+        .with_evidence(block.data.evidence.clone());
         
         miner_block
             .save_to_active(self)
@@ -110,7 +113,7 @@ impl BlockchainPersistence for DatastoreManager {
 
 #[cfg(feature = "persistence")]
 /// Convert a MinerBlock from the datastore to a Block
-fn miner_block_to_block(mb: &MinerBlock) -> Result<Block, MiningError> {
+pub fn miner_block_to_block(mb: &MinerBlock) -> Result<Block, MiningError> {
     use crate::block::{BlockData, BlockHeader};
     use chrono::{DateTime, Utc};
     use sha2::{Sha256, Digest};
@@ -127,7 +130,10 @@ fn miner_block_to_block(mb: &MinerBlock) -> Result<Block, MiningError> {
     let data = BlockData::new(
         mb.nominated_peer_id.clone(),
         mb.miner_number,
-    );
+    )
+    .with_uncles(mb.uncles.clone())
+    .with_commits(mb.commits.clone())
+    .with_evidence(mb.evidence.clone());
     
     // Recalculate data_hash from the BlockData instead of using stored value
     // This is necessary because gossip doesn't include data_hash
@@ -140,6 +146,7 @@ fn miner_block_to_block(mb: &MinerBlock) -> Result<Block, MiningError> {
         timestamp,
         previous_hash: mb.previous_hash.clone(),
         data_hash, // Use recalculated hash
+        commits_root: mb.commits_root.clone(),
         nonce,
         difficulty,
         hash: mb.hash.clone(),
@@ -0,0 +1,102 @@
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{unquote, DnsProvider, RecordDiff};
+
+const API_BASE: &str = "https://api.cloudflare.com/client/v4";
+
+/// Records in a Cloudflare zone, through the v4 API. Cloudflare keeps each
+/// TXT value as its own record, so changes add and delete single records.
+pub struct CloudflareProvider {
+    client: reqwest::Client,
+    api_token: String,
+    zone_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiResponse<T> {
+    success: bool,
+    #[serde(default)]
+    errors: Vec<serde_json::Value>,
+    result: Option<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DnsRecord {
+    id: String,
+    content: String,
+}
+
+impl CloudflareProvider {
+    pub fn new(api_token: String, zone_id: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            api_token,
+            zone_id,
+        }
+    }
+
+    /// Read the API token and zone from `CLOUDFLARE_API_TOKEN` and `CLOUDFLARE_ZONE_ID`
+    pub fn from_env() -> Result<Self> {
+        let api_token = std::env::var("CLOUDFLARE_API_TOKEN").context("CLOUDFLARE_API_TOKEN is not set")?;
+        let zone_id = std::env::var("CLOUDFLARE_ZONE_ID").context("CLOUDFLARE_ZONE_ID is not set")?;
+        Ok(Self::new(api_token, zone_id))
+    }
+
+    fn records_url(&self) -> String {
+        format!("{}/zones/{}/dns_records", API_BASE, self.zone_id)
+    }
+
+    async fn send<T: serde::de::DeserializeOwned>(&self, request: reqwest::RequestBuilder) -> Result<Option<T>> {
+        let response: ApiResponse<T> = request
+            .bearer_auth(&self.api_token)
+            .send()
+            .await
+            .context("Failed to reach the Cloudflare API")?
+            .json()
+            .await
+            .context("Failed to parse the Cloudflare API response")?;
+        if !response.success {
+            return Err(anyhow!("Cloudflare API error: {:?}", response.errors));
+        }
+        Ok(response.result)
+    }
+
+    async fn records(&self, record_name: &str) -> Result<Vec<DnsRecord>> {
+        let request = self
+            .client
+            .get(self.records_url())
+            .query(&[("type", "TXT"), ("name", record_name), ("per_page", "100")]);
+        Ok(self.send(request).await?.unwrap_or_default())
+    }
+}
+
+#[async_trait]
+impl DnsProvider for CloudflareProvider {
+    fn name(&self) -> &str {
+        "cloudflare"
+    }
+
+    async fn txt_records(&self, record_name: &str) -> Result<Vec<String>> {
+        Ok(self.records(record_name).await?.iter().map(|r| unquote(&r.content)).collect())
+    }
+
+    async fn apply(&self, record_name: &str, diff: &RecordDiff, ttl: i64) -> Result<()> {
+        for value in &diff.added {
+            let body = json!({ "type": "TXT", "name": record_name, "content": value, "ttl": ttl });
+            self.send::<serde_json::Value>(self.client.post(self.records_url()).json(&body)).await?;
+        }
+        if diff.removed.is_empty() {
+            return Ok(());
+        }
+        for record in self.records(record_name).await? {
+            if diff.removed.contains(&unquote(&record.content)) {
+                let url = format!("{}/{}", self.records_url(), record.id);
+                self.send::<serde_json::Value>(self.client.delete(url)).await?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,229 @@
+//! Bootstrapper DNS records
+//!
+//! Each network's bootstrappers are published as dnsaddr TXT records at
+//! `_dnsaddr.<network>.<base domain>`. The records are managed through a
+//! `DnsProvider` (Route53 or Cloudflare): `DnsManager` reads the current
+//! records, diffs them against the network's bootstrappers and applies only
+//! the changes. `verify` checks what public resolvers see.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::NetworkInfo;
+
+pub mod cloudflare;
+pub mod route53;
+pub mod verify;
+
+pub use cloudflare::CloudflareProvider;
+pub use route53::Route53Provider;
+
+const BASE_DOMAIN: &str = "modality.network";
+
+/// TTL of the records we write, in seconds
+const RECORD_TTL: i64 = 300;
+
+/// A DNS service hosting the network records
+#[async_trait]
+pub trait DnsProvider: Send + Sync {
+    /// Provider name, for messages
+    fn name(&self) -> &str;
+
+    /// Current TXT values at `record_name`, without quotes
+    async fn txt_records(&self, record_name: &str) -> Result<Vec<String>>;
+
+    /// Add and remove TXT values at `record_name`
+    async fn apply(&self, record_name: &str, diff: &RecordDiff, ttl: i64) -> Result<()>;
+}
+
+/// Difference between the TXT values at a record and the wanted ones
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecordDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub unchanged: Vec<String>,
+}
+
+impl RecordDiff {
+    pub fn compute(current: &[String], desired: &[String]) -> Self {
+        let mut diff = Self::default();
+        for value in desired {
+            if current.contains(value) {
+                diff.unchanged.push(value.clone());
+            } else if !diff.added.contains(value) {
+                diff.added.push(value.clone());
+            }
+        }
+        diff.removed = current.iter().filter(|v| !desired.contains(v)).cloned().collect();
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    /// The values the record has once the diff is applied
+    pub fn desired(&self) -> Vec<String> {
+        self.unchanged.iter().chain(&self.added).cloned().collect()
+    }
+}
+
+/// Strip the quotes DNS APIs put around TXT values
+pub(crate) fn unquote(value: &str) -> String {
+    value.trim().trim_matches('"').to_string()
+}
+
+/// DNS manager for a network's bootstrapper records
+pub struct DnsManager {
+    provider: Box<dyn DnsProvider>,
+    base_domain: String,
+}
+
+impl DnsManager {
+    /// Create a new DNS manager using Route53 with default AWS configuration
+    pub async fn new() -> Result<Self> {
+        Ok(Self::with_provider(Box::new(Route53Provider::new().await), BASE_DOMAIN.to_string()))
+    }
+
+    /// Create a Route53 DNS manager with custom configuration
+    pub fn with_config(
+        client: aws_sdk_route53::Client,
+        hosted_zone_id: String,
+        base_domain: String,
+    ) -> Self {
+        Self::with_provider(Box::new(Route53Provider::with_client(client, hosted_zone_id)), base_domain)
+    }
+
+    /// Create a DNS manager using any provider
+    pub fn with_provider(provider: Box<dyn DnsProvider>, base_domain: String) -> Self {
+        Self {
+            provider,
+            base_domain,
+        }
+    }
+
+    /// Name of the TXT record holding a network's bootstrappers
+    pub fn record_name(&self, network: &NetworkInfo) -> String {
+        record_name(network, &self.base_domain)
+    }
+
+    /// Diff a network's published records against its bootstrappers
+    pub async fn diff_network_records(&self, network: &NetworkInfo) -> Result<RecordDiff> {
+        let current = self.provider.txt_records(&self.record_name(network)).await?;
+        Ok(RecordDiff::compute(&current, &desired_records(network)))
+    }
+
+    /// Set TXT records for a network's bootstrappers, changing only what
+    /// differs. Returns the changes made.
+    /// Following the dnsaddr protocol: https://github.com/multiformats/multiaddr/blob/master/protocols/DNSADDR.md
+    pub async fn set_network_records(&self, network: &NetworkInfo) -> Result<RecordDiff> {
+        if network.bootstrappers.is_empty() {
+            println!("No bootstrappers for network {}, skipping DNS update", network.name);
+            return Ok(RecordDiff::default());
+        }
+
+        let diff = self.diff_network_records(network).await?;
+        if diff.is_empty() {
+            println!("DNS records for {} are up to date", network.name);
+            return Ok(diff);
+        }
+
+        self.provider.apply(&self.record_name(network), &diff, RECORD_TTL).await?;
+        println!(
+            "Successfully updated DNS records for {} via {} (+{} -{})",
+            network.name,
+            self.provider.name(),
+            diff.added.len(),
+            diff.removed.len()
+        );
+        Ok(diff)
+    }
+
+    /// Update DNS records for all networks
+    pub async fn update_all_networks(&self, networks: &[NetworkInfo]) -> Result<()> {
+        for network in networks {
+            println!("Setting records for {}...", network.name);
+            if let Err(e) = self.set_network_records(network).await {
+                eprintln!("Error setting records for {}: {}", network.name, e);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Name of the TXT record holding a network's bootstrappers under `base_domain`
+pub fn record_name(network: &NetworkInfo, base_domain: &str) -> String {
+    format!("_dnsaddr.{}.{}", network.name, base_domain)
+}
+
+/// The dnsaddr TXT values for a network's bootstrappers
+pub fn desired_records(network: &NetworkInfo) -> Vec<String> {
+    network
+        .bootstrappers
+        .iter()
+        .map(|addr| format!("dnsaddr={}", addr))
+        .collect()
+}
+
+/// Default base domain of the network records
+pub fn base_domain() -> &'static str {
+    BASE_DOMAIN
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MemoryProvider {
+        records: Mutex<HashMap<String, Vec<String>>>,
+        applied: Mutex<Vec<RecordDiff>>,
+    }
+
+    #[async_trait]
+    impl DnsProvider for MemoryProvider {
+        fn name(&self) -> &str {
+            "memory"
+        }
+
+        async fn txt_records(&self, record_name: &str) -> Result<Vec<String>> {
+            Ok(self.records.lock().unwrap().get(record_name).cloned().unwrap_or_default())
+        }
+
+        async fn apply(&self, record_name: &str, diff: &RecordDiff, _ttl: i64) -> Result<()> {
+            self.records.lock().unwrap().insert(record_name.to_string(), diff.desired());
+            self.applied.lock().unwrap().push(diff.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_record_diff() {
+        let current = vec!["dnsaddr=a".to_string(), "dnsaddr=b".to_string()];
+        let desired = vec!["dnsaddr=b".to_string(), "dnsaddr=c".to_string()];
+        let diff = RecordDiff::compute(&current, &desired);
+        assert_eq!(diff.added, vec!["dnsaddr=c"]);
+        assert_eq!(diff.removed, vec!["dnsaddr=a"]);
+        assert_eq!(diff.desired(), desired);
+        assert!(RecordDiff::compute(&desired, &desired).is_empty());
+        assert_eq!(unquote("\"dnsaddr=a\""), "dnsaddr=a");
+    }
+
+    #[tokio::test]
+    async fn test_only_changes_are_applied() {
+        let manager = DnsManager::with_provider(Box::<MemoryProvider>::default(), "example.com".to_string());
+        let mut network = crate::networks::devnet1();
+        network.bootstrappers = vec!["/ip4/10.0.0.1/tcp/4040/ws".to_string()];
+
+        let diff = manager.set_network_records(&network).await.unwrap();
+        assert_eq!(diff.added.len(), 1);
+        assert!(manager.set_network_records(&network).await.unwrap().is_empty());
+
+        network.bootstrappers.push("/ip4/10.0.0.2/tcp/4040/ws".to_string());
+        let diff = manager.set_network_records(&network).await.unwrap();
+        assert_eq!((diff.added.len(), diff.removed.len(), diff.unchanged.len()), (1, 0, 1));
+        assert_eq!(manager.diff_network_records(&network).await.unwrap().unchanged.len(), 2);
+    }
+}
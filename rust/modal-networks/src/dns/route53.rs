@@ -0,0 +1,119 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use aws_config::BehaviorVersion;
+use aws_sdk_route53::{
+    types::{Change, ChangeAction, ChangeBatch, ResourceRecord, ResourceRecordSet, RrType},
+    Client,
+};
+
+use super::{unquote, DnsProvider, RecordDiff};
+
+const HOSTED_ZONE: &str = "Z05376073QDH3S1XSX7X7";
+
+/// Records in an AWS Route53 hosted zone. Route53 keeps all TXT values of a
+/// name in one record set, so changes replace the set.
+pub struct Route53Provider {
+    client: Client,
+    hosted_zone_id: String,
+}
+
+impl Route53Provider {
+    /// Use the default AWS configuration and the modality.network zone
+    pub async fn new() -> Self {
+        let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+        Self::with_client(Client::new(&config), HOSTED_ZONE.to_string())
+    }
+
+    pub fn with_client(client: Client, hosted_zone_id: String) -> Self {
+        Self {
+            client,
+            hosted_zone_id,
+        }
+    }
+
+    async fn record_set(&self, record_name: &str) -> Result<Option<ResourceRecordSet>> {
+        let output = self
+            .client
+            .list_resource_record_sets()
+            .hosted_zone_id(&self.hosted_zone_id)
+            .start_record_name(record_name)
+            .start_record_type(RrType::Txt)
+            .max_items(1)
+            .send()
+            .await
+            .context("Failed to list DNS records")?;
+        // Listing starts at the name, so the first set may be a later one
+        Ok(output
+            .resource_record_sets()
+            .iter()
+            .find(|set| set.name().trim_end_matches('.') == record_name && set.r#type() == &RrType::Txt)
+            .cloned())
+    }
+
+    async fn change(&self, action: ChangeAction, record_set: ResourceRecordSet) -> Result<()> {
+        let change = Change::builder()
+            .action(action)
+            .resource_record_set(record_set)
+            .build()
+            .context("Failed to build change")?;
+
+        let change_batch = ChangeBatch::builder()
+            .set_changes(Some(vec![change]))
+            .build()
+            .context("Failed to build change batch")?;
+
+        self.client
+            .change_resource_record_sets()
+            .hosted_zone_id(&self.hosted_zone_id)
+            .change_batch(change_batch)
+            .send()
+            .await
+            .context("Failed to update DNS records")?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DnsProvider for Route53Provider {
+    fn name(&self) -> &str {
+        "route53"
+    }
+
+    async fn txt_records(&self, record_name: &str) -> Result<Vec<String>> {
+        Ok(self
+            .record_set(record_name)
+            .await?
+            .map(|set| set.resource_records().iter().map(|r| unquote(r.value())).collect())
+            .unwrap_or_default())
+    }
+
+    async fn apply(&self, record_name: &str, diff: &RecordDiff, ttl: i64) -> Result<()> {
+        let desired = diff.desired();
+        if desired.is_empty() {
+            // Deleting takes the exact current set
+            return match self.record_set(record_name).await? {
+                Some(current) => self.change(ChangeAction::Delete, current).await,
+                None => Ok(()),
+            };
+        }
+
+        let resource_records: Vec<ResourceRecord> = desired
+            .iter()
+            .map(|value| {
+                ResourceRecord::builder()
+                    .value(format!("\"{}\"", value))
+                    .build()
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .context("Failed to build resource records")?;
+
+        let record_set = ResourceRecordSet::builder()
+            .name(record_name)
+            .r#type(RrType::Txt)
+            .ttl(ttl)
+            .set_resource_records(Some(resource_records))
+            .build()
+            .context("Failed to build resource record set")?;
+        self.change(ChangeAction::Upsert, record_set).await
+    }
+}
@@ -0,0 +1,57 @@
+use hickory_resolver::config::{ResolverConfig, ResolverOpts};
+use hickory_resolver::TokioAsyncResolver;
+
+use super::RecordDiff;
+
+/// What one public resolver returns for a record
+#[derive(Debug, Clone)]
+pub struct PropagationCheck {
+    pub resolver: &'static str,
+    /// The resolver's values compared to the wanted ones, or the lookup error
+    pub result: Result<RecordDiff, String>,
+}
+
+impl PropagationCheck {
+    /// The resolver returns exactly the wanted values
+    pub fn is_propagated(&self) -> bool {
+        matches!(&self.result, Ok(diff) if diff.is_empty())
+    }
+}
+
+fn public_resolvers() -> Vec<(&'static str, ResolverConfig)> {
+    vec![
+        ("google", ResolverConfig::google()),
+        ("cloudflare", ResolverConfig::cloudflare()),
+        ("quad9", ResolverConfig::quad9()),
+    ]
+}
+
+/// Look up `record_name` on public resolvers and compare each answer with `desired`
+pub async fn check_propagation(record_name: &str, desired: &[String]) -> Vec<PropagationCheck> {
+    let mut checks = Vec::new();
+    for (name, config) in public_resolvers() {
+        let mut opts = ResolverOpts::default();
+        // Ask each resolver, not a cached answer of ours
+        opts.cache_size = 0;
+        let resolver = TokioAsyncResolver::tokio(config, opts);
+        let result = match resolver.txt_lookup(record_name).await {
+            Ok(response) => {
+                let values: Vec<String> = response
+                    .iter()
+                    .map(|record| {
+                        record
+                            .txt_data()
+                            .iter()
+                            .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+                            .collect::<Vec<_>>()
+                            .join("")
+                    })
+                    .collect();
+                Ok(RecordDiff::compute(&values, desired))
+            }
+            Err(e) => Err(e.to_string()),
+        };
+        checks.push(PropagationCheck { resolver: name, result });
+    }
+    checks
+}
@@ -0,0 +1,145 @@
+//! Bootstrapper health and selection
+//!
+//! Nodes probe bootstrappers (dial, ping, ask for their chain height) and
+//! keep the latest result for each. Bootstrappers are then tried in order of
+//! health: reachable ones close to the best known height first, fastest
+//! first, then ones without a recent result, then the rest.
+
+use serde::{Deserialize, Serialize};
+
+/// Results older than this count as unknown
+pub const MAX_HEALTH_AGE_SECS: i64 = 1_800;
+
+/// Blocks a bootstrapper can be behind the best probed height and still be healthy
+pub const MAX_HEIGHT_LAG: u64 = 10;
+
+/// Latency penalty per block behind the best probed height, in milliseconds
+pub const HEIGHT_LAG_PENALTY_MS: u64 = 100;
+
+/// Result of probing a bootstrapper
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BootstrapperHealth {
+    /// Bootstrapper multiaddress
+    pub address: String,
+    /// Whether it answered the ping
+    pub reachable: bool,
+    /// Ping round trip in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    /// Its canonical chain height
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
+    /// Unix seconds of the probe
+    pub checked_at: i64,
+    /// Failed probes in a row
+    #[serde(default)]
+    pub consecutive_failures: u32,
+    /// Why the probe failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl BootstrapperHealth {
+    /// A successful probe
+    pub fn reachable(address: String, latency_ms: u64, height: Option<u64>, checked_at: i64) -> Self {
+        Self {
+            address,
+            reachable: true,
+            latency_ms: Some(latency_ms),
+            height,
+            checked_at,
+            consecutive_failures: 0,
+            error: None,
+        }
+    }
+
+    /// A failed probe, following the `previous` result if there is one
+    pub fn unreachable(address: String, error: String, checked_at: i64, previous: Option<&Self>) -> Self {
+        Self {
+            address,
+            reachable: false,
+            latency_ms: None,
+            height: None,
+            checked_at,
+            consecutive_failures: previous.map(|p| p.consecutive_failures + 1).unwrap_or(1),
+            error: Some(error),
+        }
+    }
+
+    /// Blocks behind `best_height`
+    pub fn lag(&self, best_height: u64) -> u64 {
+        best_height.saturating_sub(self.height.unwrap_or(0))
+    }
+
+    /// Reachable and no more than `MAX_HEIGHT_LAG` blocks behind `best_height`
+    pub fn is_healthy(&self, best_height: u64) -> bool {
+        self.reachable && self.lag(best_height) <= MAX_HEIGHT_LAG
+    }
+
+    /// Selection weight among healthy bootstrappers; lower is better
+    pub fn score(&self, best_height: u64) -> u64 {
+        self.latency_ms.unwrap_or(u64::MAX / 2) + self.lag(best_height) * HEIGHT_LAG_PENALTY_MS
+    }
+}
+
+/// Order `addresses` for dialing using the probe results in `health`, as of
+/// `now` (unix seconds). Addresses keep their order within a tier.
+pub fn rank_bootstrappers(addresses: &[String], health: &[BootstrapperHealth], now: i64) -> Vec<String> {
+    let current = |address: &String| {
+        health
+            .iter()
+            .find(|h| &h.address == address && now - h.checked_at <= MAX_HEALTH_AGE_SECS)
+    };
+    let best_height = addresses
+        .iter()
+        .filter_map(current)
+        .filter(|h| h.reachable)
+        .filter_map(|h| h.height)
+        .max()
+        .unwrap_or(0);
+
+    let mut ranked: Vec<(u8, u64, &String)> = addresses
+        .iter()
+        .map(|address| match current(address) {
+            Some(h) if h.is_healthy(best_height) => (0, h.score(best_height), address),
+            None => (1, 0, address),
+            Some(h) if h.reachable => (2, h.score(best_height), address),
+            Some(h) => (3, h.consecutive_failures as u64, address),
+        })
+        .collect();
+    ranked.sort_by_key(|(tier, weight, _)| (*tier, *weight));
+    ranked.into_iter().map(|(_, _, address)| address.clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank_bootstrappers() {
+        let now = 1_700_000_000;
+        let addresses: Vec<String> = ["down", "slow", "fast", "behind", "unknown", "stale"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let health = vec![
+            BootstrapperHealth::unreachable("down".to_string(), "timeout".to_string(), now, None),
+            BootstrapperHealth::reachable("slow".to_string(), 400, Some(1000), now),
+            BootstrapperHealth::reachable("fast".to_string(), 20, Some(998), now),
+            BootstrapperHealth::reachable("behind".to_string(), 5, Some(500), now),
+            BootstrapperHealth::reachable("stale".to_string(), 1, Some(1000), now - MAX_HEALTH_AGE_SECS - 1),
+        ];
+        assert_eq!(
+            rank_bootstrappers(&addresses, &health, now),
+            vec!["fast", "slow", "unknown", "stale", "behind", "down"]
+        );
+    }
+
+    #[test]
+    fn test_failures_accumulate() {
+        let first = BootstrapperHealth::unreachable("a".to_string(), "refused".to_string(), 1, None);
+        let second = BootstrapperHealth::unreachable("a".to_string(), "refused".to_string(), 2, Some(&first));
+        assert_eq!(second.consecutive_failures, 2);
+        assert!(!second.is_healthy(0));
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// Checkpoint mode for a network
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
@@ -26,6 +27,73 @@ pub struct ManualCheckpoint {
     pub description: Option<String>,
 }
 
+/// What every node of a network must agree on from the first block. Nodes
+/// check their chain against it at startup and only peer with nodes
+/// advertising the same genesis block.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct GenesisSpec {
+    /// Hash of the genesis block (index 0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_hash: Option<String>,
+    /// Target difficulty of the genesis block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub initial_difficulty: Option<u128>,
+    /// Blocks per epoch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocks_per_epoch: Option<u64>,
+    /// Target seconds between blocks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_block_time_secs: Option<u64>,
+    /// Other protocol parameters, by name
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub parameters: serde_json::Map<String, serde_json::Value>,
+}
+
+impl GenesisSpec {
+    /// Check a genesis block's hash and target difficulty against the spec
+    pub fn check_block(&self, hash: &str, target_difficulty: u128) -> Result<(), String> {
+        if let Some(expected) = &self.block_hash {
+            if expected != hash {
+                return Err(format!("genesis block is {}, the network's is {}", hash, expected));
+            }
+        }
+        if let Some(expected) = self.initial_difficulty {
+            if expected != target_difficulty {
+                return Err(format!(
+                    "genesis block has difficulty {}, the network's is {}",
+                    target_difficulty, expected
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Protocol a node must speak to peer on a network. Nodes refuse peers
+/// that don't advertise every required feature or speak too old a message
+/// protocol, and refuse to start if their own build falls short.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct CompatibilitySpec {
+    /// Protocol features every node must advertise
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_features: Vec<String>,
+    /// Oldest message protocol version a node may speak
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_protocol_version: Option<u32>,
+}
+
+/// How a network lays out its gossip topics. Nodes only hear each other
+/// when they use the same layout.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct GossipSpec {
+    /// Prefix of every topic (default: the network name)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// Shards to split high-volume topics into, e.g. {"/miner/block": 4}
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub shards: BTreeMap<String, u32>,
+}
+
 /// Represents information about a Modality network
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkInfo {
@@ -52,6 +120,29 @@ pub struct NetworkInfo {
     /// Manual checkpoints (only used when checkpoint_mode is Manual)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub checkpoints: Option<Vec<ManualCheckpoint>>,
+    
+    /// Regtest networks only produce blocks on demand, at zero difficulty
+    /// and with fixed timestamps
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub regtest: bool,
+    
+    /// Base URLs of cold storage archives of finalized epochs, each serving
+    /// a `manifest.json` and its segment files. Syncing nodes import the
+    /// archived chain from these before syncing the rest from peers.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub archives: Vec<String>,
+    
+    /// Genesis block and protocol parameters every node must share
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub genesis: Option<GenesisSpec>,
+    
+    /// Protocol features and version peers must have
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compatibility: Option<CompatibilitySpec>,
+    
+    /// Namespaced and sharded gossip topics (default: the shared unsharded topics)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gossip: Option<GossipSpec>,
 }
 
 impl NetworkInfo {
@@ -119,6 +210,11 @@ pub mod networks {
             .expect("Failed to parse mainnet info")
     }
     
+    pub fn regtest() -> NetworkInfo {
+        serde_json::from_str(include_str!("../networks/regtest/info.json"))
+            .expect("Failed to parse regtest info")
+    }
+    
     /// Get all networks
     pub fn all() -> Vec<NetworkInfo> {
         vec![
@@ -130,6 +226,7 @@ pub mod networks {
             devnet3_hybrid(),
             testnet(),
             mainnet(),
+            regtest(),
         ]
     }
     
@@ -144,6 +241,7 @@ pub mod networks {
             "devnet3-hybrid" => Some(devnet3_hybrid()),
             "testnet" => Some(testnet()),
             "mainnet" => Some(mainnet()),
+            "regtest" => Some(regtest()),
             _ => None,
         }
     }
@@ -203,6 +301,8 @@ pub mod templates {
 }
 
 pub mod dns;
+pub mod health;
+pub mod registry;
 
 #[cfg(test)]
 mod tests {
@@ -258,6 +358,9 @@ mod tests {
             validators: None,
             checkpoint_mode: None,
             checkpoints: None,
+            regtest: false,
+            archives: Vec::new(),
+            genesis: None,
         };
         assert_eq!(network.get_checkpoint_mode(), CheckpointMode::None);
         assert!(!network.checkpoints_enabled());
@@ -272,6 +375,9 @@ mod tests {
             validators: None,
             checkpoint_mode: Some(CheckpointMode::Consensus),
             checkpoints: None,
+            regtest: false,
+            archives: Vec::new(),
+            genesis: None,
         };
         assert_eq!(network.get_checkpoint_mode(), CheckpointMode::Consensus);
         assert!(network.checkpoints_enabled());
@@ -297,6 +403,9 @@ mod tests {
                     description: None,
                 },
             ]),
+            regtest: false,
+            archives: Vec::new(),
+            genesis: None,
         };
         
         let checkpoints = network.get_manual_checkpoints();
@@ -306,6 +415,14 @@ mod tests {
         assert_eq!(checkpoints[1].block_index, 100);
     }
 
+    #[test]
+    fn test_regtest_network() {
+        let regtest = networks::by_name("regtest").unwrap();
+        assert!(regtest.regtest);
+        assert!(regtest.bootstrappers.is_empty());
+        assert!(!networks::testnet().regtest);
+    }
+
     #[test]
     fn test_checkpoint_mode_serialization() {
         // Test that checkpoint mode serializes to lowercase
@@ -334,5 +451,45 @@ mod tests {
         assert_eq!(network.get_checkpoint_mode(), CheckpointMode::Manual);
         assert_eq!(network.checkpoints.unwrap().len(), 1);
     }
+
+    #[test]
+    fn test_archive_urls() {
+        let json = serde_json::json!({
+            "name": "test",
+            "description": "test",
+            "bootstrappers": [],
+            "archives": ["https://archive.example.com/testnet"]
+        });
+        let network: NetworkInfo = serde_json::from_value(json).unwrap();
+        assert_eq!(network.archives, vec!["https://archive.example.com/testnet".to_string()]);
+        
+        // Networks without archives don't mention them
+        assert!(networks::devnet1().archives.is_empty());
+        assert!(serde_json::to_value(networks::devnet1()).unwrap().get("archives").is_none());
+    }
+
+    #[test]
+    fn test_genesis_spec() {
+        let json = serde_json::json!({
+            "name": "test",
+            "description": "test",
+            "bootstrappers": [],
+            "genesis": {
+                "block_hash": "abc",
+                "initial_difficulty": 10,
+                "blocks_per_epoch": 40,
+                "parameters": { "max_commits_per_block": 100 }
+            }
+        });
+        let network: NetworkInfo = serde_json::from_value(json).unwrap();
+        let genesis = network.genesis.unwrap();
+        assert_eq!(genesis.blocks_per_epoch, Some(40));
+        assert_eq!(genesis.parameters["max_commits_per_block"], 100);
+
+        assert!(genesis.check_block("abc", 10).is_ok());
+        assert!(genesis.check_block("abd", 10).is_err());
+        assert!(genesis.check_block("abc", 11).is_err());
+        assert!(GenesisSpec::default().check_block("anything", 1).is_ok());
+    }
 }
 
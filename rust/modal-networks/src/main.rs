@@ -1,6 +1,7 @@
 use anyhow::Result;
-use clap::{Parser, Subcommand};
-use modal_networks::{dns::DnsManager, networks};
+use clap::{Parser, Subcommand, ValueEnum};
+use modal_networks::dns::{self, CloudflareProvider, DnsManager};
+use modal_networks::{networks, NetworkInfo};
 
 #[derive(Parser)]
 #[command(name = "modal-networks")]
@@ -27,12 +28,39 @@ enum Commands {
         #[arg(short, long)]
         network: Option<String>,
         
-        /// Dry run - show what would be updated without making changes
+        /// Dry run - show what would change without making changes
         #[arg(short, long)]
         dry_run: bool,
+        
+        /// DNS provider hosting the records
+        #[arg(long, value_enum, default_value = "route53")]
+        provider: Provider,
+    },
+    
+    /// Check that public resolvers return the expected DNS records
+    VerifyDns {
+        /// Specific network to check (if not provided, checks all)
+        #[arg(short, long)]
+        network: Option<String>,
     },
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum Provider {
+    /// AWS Route53 (default AWS credentials)
+    Route53,
+    /// Cloudflare (CLOUDFLARE_API_TOKEN and CLOUDFLARE_ZONE_ID)
+    Cloudflare,
+}
+
+fn selected_networks(network: Option<String>) -> Result<Vec<NetworkInfo>> {
+    match network {
+        Some(name) => Ok(vec![networks::by_name(&name)
+            .ok_or_else(|| anyhow::anyhow!("Network '{}' not found", name))?]),
+        None => Ok(networks::all()),
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -58,46 +86,71 @@ async fn main() -> Result<()> {
                 }
                 None => {
                     eprintln!("Network '{}' not found", network);
-                    eprintln!("Available networks: devnet1, devnet2, devnet3, devnet5, testnet, mainnet");
+                    eprintln!("Available networks: devnet1, devnet2, devnet3, devnet5, testnet, mainnet, regtest");
                     std::process::exit(1);
                 }
             }
         }
         
-        Commands::UpdateDns { network, dry_run } => {
-            if dry_run {
-                let networks_to_update = if let Some(name) = network {
-                    vec![networks::by_name(&name)
-                        .ok_or_else(|| anyhow::anyhow!("Network '{}' not found", name))?]
-                } else {
-                    networks::all()
-                };
+        Commands::UpdateDns { network, dry_run, provider } => {
+            println!("Initializing DNS manager...");
+            let base_domain = dns::base_domain().to_string();
+            let dns_manager = match provider {
+                Provider::Route53 => DnsManager::new().await?,
+                Provider::Cloudflare => DnsManager::with_provider(Box::new(CloudflareProvider::from_env()?), base_domain),
+            };
+            let networks_to_update = selected_networks(network)?;
 
-                println!("Dry run - would update the following networks:\n");
-                for net in networks_to_update {
+            if dry_run {
+                println!("Dry run - would make the following changes:\n");
+                for net in networks_to_update.iter().filter(|n| !n.bootstrappers.is_empty()) {
+                    let diff = dns_manager.diff_network_records(net).await?;
                     println!("Network: {}", net.name);
-                    println!("  Record: _dnsaddr.{}.modality.network", net.name);
-                    println!("  Values:");
-                    for addr in &net.bootstrappers {
-                        println!("    dnsaddr={}", addr);
+                    println!("  Record: {}", dns_manager.record_name(net));
+                    if diff.is_empty() {
+                        println!("  Up to date");
+                    }
+                    for value in &diff.added {
+                        println!("  + {}", value);
+                    }
+                    for value in &diff.removed {
+                        println!("  - {}", value);
                     }
                     println!();
                 }
             } else {
-                println!("Initializing DNS manager...");
-                let dns_manager = DnsManager::new().await?;
-
-                if let Some(name) = network {
-                    let net = networks::by_name(&name)
-                        .ok_or_else(|| anyhow::anyhow!("Network '{}' not found", name))?;
-                    dns_manager.set_network_records(&net).await?;
-                } else {
-                    dns_manager.update_all_networks(&networks::all()).await?;
-                }
+                dns_manager.update_all_networks(&networks_to_update).await?;
 
                 println!("\nDNS records updated successfully!");
-                println!("\nYou can verify with:");
-                println!("  dig +short txt _dnsaddr.testnet.modality.network");
+                println!("\nCheck propagation with:");
+                println!("  modal-networks verify-dns");
+            }
+        }
+        
+        Commands::VerifyDns { network } => {
+            let mut all_propagated = true;
+            for net in selected_networks(network)?.iter().filter(|n| !n.bootstrappers.is_empty()) {
+                let record_name = dns::record_name(net, dns::base_domain());
+                println!("{} ({})", net.name, record_name);
+                for check in dns::verify::check_propagation(&record_name, &dns::desired_records(net)).await {
+                    match &check.result {
+                        Ok(diff) if diff.is_empty() => println!("  ✓ {}", check.resolver),
+                        Ok(diff) => {
+                            println!("  ✗ {}: {} missing, {} stale", check.resolver, diff.added.len(), diff.removed.len());
+                            for value in &diff.added {
+                                println!("      missing {}", value);
+                            }
+                            for value in &diff.removed {
+                                println!("      stale   {}", value);
+                            }
+                        }
+                        Err(e) => println!("  ✗ {}: {}", check.resolver, e),
+                    }
+                    all_propagated &= check.is_propagated();
+                }
+            }
+            if !all_propagated {
+                std::process::exit(1);
             }
         }
     }
@@ -0,0 +1,215 @@
+//! Networks defined outside the binary
+//!
+//! Besides the built-in networks, each `*.json` file in
+//! `~/.modality/networks` defines a network (a `NetworkInfo`), named after
+//! the file. A file named after a built-in network overrides it.
+//!
+//! Registries publish a list of networks at a URL, signed by a known key:
+//!
+//! ```json
+//! { "networks": [ ... ], "signer": "12D3KooW...", "signature": "..." }
+//! ```
+//!
+//! The signature is the signer's ed25519 signature over the deterministic
+//! JSON of `networks`. `fetch_registry` only accepts registries signed by a
+//! trusted signer, and `install` writes their networks to the networks
+//! directory, where they're found like any other file.
+
+use crate::{networks, NetworkInfo};
+use anyhow::{anyhow, Context, Result};
+use modal_common::keypair::Keypair;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// Directory holding network files: `~/.modality/networks`
+pub fn networks_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".modality").join("networks"))
+}
+
+/// Network names are used as file names, so only allow a safe subset
+fn check_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(anyhow!("Invalid network name '{}'", name));
+    }
+    Ok(())
+}
+
+fn read_network_file(path: &Path, name: &str) -> Result<NetworkInfo> {
+    let data = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let network: NetworkInfo = serde_json::from_str(&data)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    if network.name != name {
+        return Err(anyhow!("{} defines network '{}', expected '{}'", path.display(), network.name, name));
+    }
+    Ok(network)
+}
+
+/// Find a network by name: a file in `dir` first, then the built-in networks
+pub fn find_in(dir: &Path, name: &str) -> Result<Option<NetworkInfo>> {
+    if check_name(name).is_ok() {
+        let path = dir.join(format!("{}.json", name));
+        if path.exists() {
+            return read_network_file(&path, name).map(Some);
+        }
+    }
+    Ok(networks::by_name(name))
+}
+
+/// Find a network by name in the networks directory or among the built-in networks
+pub fn find(name: &str) -> Result<Option<NetworkInfo>> {
+    match networks_dir() {
+        Some(dir) => find_in(&dir, name),
+        None => Ok(networks::by_name(name)),
+    }
+}
+
+/// All networks: the built-in ones, with overrides from `dir` applied, then
+/// the ones only defined in `dir`, by name
+pub fn all_in(dir: &Path) -> Result<Vec<NetworkInfo>> {
+    let mut files = Vec::new();
+    if dir.exists() {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()).map(str::to_string) else {
+                continue;
+            };
+            files.push(read_network_file(&path, &name)?);
+        }
+    }
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut all: Vec<NetworkInfo> = networks::all()
+        .into_iter()
+        .map(|builtin| match files.iter().position(|f| f.name == builtin.name) {
+            Some(i) => files.remove(i),
+            None => builtin,
+        })
+        .collect();
+    all.extend(files);
+    Ok(all)
+}
+
+/// All networks, including those in the networks directory
+pub fn all() -> Result<Vec<NetworkInfo>> {
+    match networks_dir() {
+        Some(dir) => all_in(&dir),
+        None => Ok(networks::all()),
+    }
+}
+
+/// Build a registry document of `networks` signed by `keypair`
+pub fn sign_registry(networks: &[NetworkInfo], keypair: &Keypair) -> Result<Value> {
+    let networks = serde_json::to_value(networks)?;
+    let signature = keypair.sign_json(&networks)?;
+    Ok(serde_json::json!({
+        "networks": networks,
+        "signer": keypair.as_public_key_id(),
+        "signature": signature,
+    }))
+}
+
+/// Check a registry document's signature and return its networks. The
+/// signer must be one of `trusted_signers` (peer ids).
+pub fn verify_registry(document: &Value, trusted_signers: &[String]) -> Result<Vec<NetworkInfo>> {
+    let field = |name: &str| {
+        document.get(name).and_then(|v| v.as_str()).ok_or_else(|| anyhow!("Registry has no {}", name))
+    };
+    let signer = field("signer")?;
+    let signature = field("signature")?;
+    let networks = document.get("networks").ok_or_else(|| anyhow!("Registry has no networks"))?;
+
+    if !trusted_signers.iter().any(|s| s == signer) {
+        return Err(anyhow!("Registry is signed by {}, which is not a trusted signer", signer));
+    }
+    let valid = Keypair::from_public_key(signer, "ed25519")
+        .and_then(|key| key.verify_json(signature, networks))
+        .unwrap_or(false);
+    if !valid {
+        return Err(anyhow!("Registry signature is invalid"));
+    }
+
+    let networks: Vec<NetworkInfo> = serde_json::from_value(networks.clone())
+        .context("Failed to parse registry networks")?;
+    for network in &networks {
+        check_name(&network.name)?;
+    }
+    Ok(networks)
+}
+
+/// Download a registry and return its networks once its signature checks out
+pub async fn fetch_registry(url: &str, trusted_signers: &[String]) -> Result<Vec<NetworkInfo>> {
+    let response = reqwest::get(url).await.with_context(|| format!("Failed to fetch {}", url))?;
+    if !response.status().is_success() {
+        return Err(anyhow!("Failed to fetch {}: HTTP {}", url, response.status()));
+    }
+    let document: Value = response.json().await.context("Failed to parse registry")?;
+    verify_registry(&document, trusted_signers)
+}
+
+/// Write networks to `dir` as `<name>.json`, replacing earlier versions.
+/// Returns the files written.
+pub fn install(networks: &[NetworkInfo], dir: &Path) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    let mut written = Vec::new();
+    for network in networks {
+        check_name(&network.name)?;
+        let path = dir.join(format!("{}.json", network.name));
+        std::fs::write(&path, serde_json::to_string_pretty(network)?)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network(name: &str, description: &str) -> NetworkInfo {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "description": description,
+            "bootstrappers": [],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_files_add_and_override_networks() {
+        let dir = tempfile::tempdir().unwrap();
+        install(&[network("community1", "a community network"), network("testnet", "local testnet")], dir.path()).unwrap();
+
+        assert_eq!(find_in(dir.path(), "community1").unwrap().unwrap().description, "a community network");
+        assert_eq!(find_in(dir.path(), "testnet").unwrap().unwrap().description, "local testnet");
+        assert_eq!(find_in(dir.path(), "devnet1").unwrap().unwrap().name, "devnet1");
+        assert!(find_in(dir.path(), "nope").unwrap().is_none());
+
+        let all = all_in(dir.path()).unwrap();
+        assert_eq!(all.len(), networks::all().len() + 1);
+        assert_eq!(all.iter().find(|n| n.name == "testnet").unwrap().description, "local testnet");
+        assert_eq!(all.last().unwrap().name, "community1");
+
+        // A file has to define the network it's named after
+        std::fs::write(dir.path().join("other.json"), serde_json::to_string(&network("community1", "")).unwrap()).unwrap();
+        assert!(find_in(dir.path(), "other").is_err());
+    }
+
+    #[test]
+    fn test_registry_signature() {
+        let keypair = Keypair::generate().unwrap();
+        let signers = vec![keypair.as_public_key_id()];
+        let mut document = sign_registry(&[network("community1", "a community network")], &keypair).unwrap();
+
+        let networks = verify_registry(&document, &signers).unwrap();
+        assert_eq!(networks[0].name, "community1");
+
+        // Unknown signers and tampered networks are rejected
+        assert!(verify_registry(&document, &[Keypair::generate().unwrap().as_public_key_id()]).is_err());
+        document["networks"][0]["bootstrappers"] = serde_json::json!(["/ip4/10.0.0.1/tcp/1/ws"]);
+        assert!(verify_registry(&document, &signers).is_err());
+    }
+}
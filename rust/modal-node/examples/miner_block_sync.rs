@@ -130,6 +130,7 @@ async fn main() -> Result<()> {
     let request = Request {
         path: "/data/miner_block/canonical".to_string(),
         data: None,
+        accept_encoding: None,
     };
     
     println!("  📤 Node 2 requesting canonical blocks from Node 1...");
@@ -179,6 +180,7 @@ async fn main() -> Result<()> {
     let epoch_request = Request {
         path: "/data/miner_block/epoch".to_string(),
         data: Some(serde_json::json!({"epoch": 0})),
+        accept_encoding: None,
     };
     
     let request_id = node2_swarm.behaviour_mut().reqres.send_request(&node1_peer_id, epoch_request);
@@ -207,6 +209,7 @@ async fn main() -> Result<()> {
             "from_index": 3,
             "to_index": 7
         })),
+        accept_encoding: None,
     };
     
     let request_id = node2_swarm.behaviour_mut().reqres.send_request(&node1_peer_id, range_request);
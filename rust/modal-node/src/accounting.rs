@@ -0,0 +1,118 @@
+//! Epoch accounting
+//!
+//! Writes an `EpochReport` for every epoch once the next one has started,
+//! with each peer's blocks, uncles, nominations and validator participation.
+//! A report is rewritten if a reorg replaced the last block of its epoch.
+
+use modal_datastore::models::miner::EpochReport;
+use modal_datastore::models::MinerBlock;
+use modal_datastore::DatastoreManager;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, Mutex};
+
+use crate::constants::{EPOCH_ACCOUNTING_INTERVAL_SECS, EPOCH_REPORTS_PER_CHECK};
+
+/// Spawn a task that reports on finished epochs until shutdown
+pub fn start_epoch_accounting(
+    datastore_manager: Arc<Mutex<DatastoreManager>>,
+    mut shutdown_rx: broadcast::Receiver<()>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(EPOCH_ACCOUNTING_INTERVAL_SECS));
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => break,
+                _ = interval.tick() => {
+                    let mgr = datastore_manager.lock().await;
+                    match report_finished_epochs(&mgr).await {
+                        Ok(epochs) if !epochs.is_empty() => {
+                            log::info!("Wrote epoch reports for epochs {:?}", epochs);
+                        }
+                        Ok(_) => {}
+                        Err(e) => log::warn!("Failed to write epoch reports: {}", e),
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Write reports for finished epochs that have none, or whose report was
+/// made before a reorg replaced the epoch's last block. Returns the epochs
+/// reported on.
+pub async fn report_finished_epochs(mgr: &DatastoreManager) -> anyhow::Result<Vec<u64>> {
+    let canonical = MinerBlock::find_all_canonical_multi(mgr).await?;
+    let Some(current_epoch) = canonical.iter().max_by_key(|b| b.index).map(|b| b.epoch) else {
+        return Ok(Vec::new());
+    };
+    let mut last_hashes: BTreeMap<u64, (u64, &str)> = BTreeMap::new();
+    for block in &canonical {
+        let last = last_hashes.entry(block.epoch).or_insert((block.index, &block.hash));
+        if block.index >= last.0 {
+            *last = (block.index, &block.hash);
+        }
+    }
+
+    let mut reported = Vec::new();
+    for (&epoch, &(_, last_hash)) in last_hashes.range(..current_epoch) {
+        if let Some(report) = EpochReport::find_by_epoch_multi(mgr, epoch).await? {
+            if report.last_block_hash == last_hash {
+                continue;
+            }
+        }
+        EpochReport::generate_multi(mgr, epoch, current_epoch).await?.save(mgr).await?;
+        reported.push(epoch);
+        if reported.len() >= EPOCH_REPORTS_PER_CHECK {
+            break;
+        }
+    }
+    Ok(reported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(index: u64, epoch: u64, hash: &str, nominee: &str) -> MinerBlock {
+        MinerBlock::new_canonical(
+            hash.to_string(),
+            index,
+            epoch,
+            1_700_000_000 + index as i64 * 60,
+            String::new(),
+            String::new(),
+            0,
+            1000,
+            nominee.to_string(),
+            index,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_reports_finished_epochs() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        block(0, 0, "a0", "alice").save_to_active(&mgr).await.unwrap();
+        block(1, 0, "a1", "bob").save_to_active(&mgr).await.unwrap();
+        assert!(report_finished_epochs(&mgr).await.unwrap().is_empty());
+
+        block(2, 1, "a2", "alice").save_to_active(&mgr).await.unwrap();
+        assert_eq!(report_finished_epochs(&mgr).await.unwrap(), vec![0]);
+        assert!(report_finished_epochs(&mgr).await.unwrap().is_empty());
+        let report = EpochReport::find_by_epoch_multi(&mgr, 0).await.unwrap().unwrap();
+        assert_eq!(report.block_count, 2);
+        assert_eq!(report.last_block_hash, "a1");
+
+        // A reorg replacing epoch 0's last block makes its report stale
+        let mut orphan = MinerBlock::find_by_hash_multi(&mgr, "a1").await.unwrap().unwrap();
+        orphan.mark_as_orphaned("test".to_string(), None);
+        orphan.save_to_active(&mgr).await.unwrap();
+        block(1, 0, "b1", "carol").save_to_active(&mgr).await.unwrap();
+        assert_eq!(report_finished_epochs(&mgr).await.unwrap(), vec![0]);
+        let report = EpochReport::find_by_epoch_multi(&mgr, 0).await.unwrap().unwrap();
+        assert_eq!(report.last_block_hash, "b1");
+        assert!(report.peers.iter().any(|p| p.peer_id == "carol"));
+        assert!(!report.peers.iter().any(|p| p.peer_id == "bob"));
+    }
+}
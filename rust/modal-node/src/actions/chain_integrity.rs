@@ -3,17 +3,27 @@
 //! This module provides functions to validate that the canonical chain is internally
 //! consistent (each block's prev_hash matches the previous block's hash) and to
 //! automatically repair any inconsistencies by orphaning broken blocks.
+//!
+//! Each check records the last block it found properly linked as the
+//! integrity checkpoint, and startup checks resume from there, so restarting
+//! a node with a long chain only checks the blocks added since, plus a random
+//! sample of the blocks below.
 
 use anyhow::Result;
-use modal_datastore::DatastoreManager;
+use modal_datastore::{DatastoreManager, IntegrityCheckpoint};
 use modal_datastore::models::MinerBlock;
 use std::collections::HashMap;
 
+use crate::constants::INTEGRITY_SAMPLE_SIZE;
+
 /// Result of a chain integrity check
 #[derive(Debug)]
 pub struct ChainIntegrityReport {
     /// Total canonical blocks checked
     pub total_blocks: usize,
+    /// Index the check started from; above 0 when it resumed from the
+    /// integrity checkpoint
+    pub checked_from: u64,
     /// Number of blocks with valid linkage
     pub valid_blocks: usize,
     /// Index where the chain breaks (if any)
@@ -50,6 +60,7 @@ pub async fn validate_and_repair_chain(
         log::info!("✓ No canonical blocks to validate");
         return Ok(ChainIntegrityReport {
             total_blocks: 0,
+            checked_from: 0,
             valid_blocks: 0,
             break_point: None,
             orphaned_count: 0,
@@ -108,8 +119,10 @@ pub async fn validate_and_repair_chain(
     
     if break_point.is_none() {
         log::info!("✅ Chain integrity validated: {} blocks properly linked", valid_blocks);
+        save_checkpoint(mgr, blocks_by_index.get(&max_index));
         return Ok(ChainIntegrityReport {
             total_blocks,
+            checked_from: min_index,
             valid_blocks,
             break_point: None,
             orphaned_count: 0,
@@ -120,11 +133,13 @@ pub async fn validate_and_repair_chain(
     let break_index = break_point.unwrap();
     log::warn!("⚠️  Chain integrity issue: break at index {}, {} valid blocks before break", 
         break_index, valid_blocks);
+    save_checkpoint(mgr, break_index.checked_sub(1).and_then(|index| blocks_by_index.get(&index)));
     
     if !repair {
         log::info!("🔧 Repair not requested - run with repair=true to fix");
         return Ok(ChainIntegrityReport {
             total_blocks,
+            checked_from: min_index,
             valid_blocks,
             break_point: Some(break_index),
             orphaned_count: 0,
@@ -133,35 +148,113 @@ pub async fn validate_and_repair_chain(
     }
     
     // Repair: orphan all canonical blocks from break_point onwards
-    log::info!("🔧 Repairing chain: orphaning canonical blocks from index {} onwards", break_index);
+    let broken: Vec<&MinerBlock> = (break_index..=max_index).filter_map(|index| blocks_by_index.get(&index)).collect();
+    let orphaned_count = orphan_broken_blocks(mgr, broken, break_index).await;
     
-    let mut orphaned_count = 0;
-    for index in break_index..=max_index {
-        if let Some(block) = blocks_by_index.get(&index) {
-            if block.is_canonical && !block.is_orphaned {
-                let mut orphaned_block = block.clone();
-                orphaned_block.mark_as_orphaned(
-                    format!("Chain integrity repair: block built on broken/orphaned chain at index {}", break_index),
-                    None,
-                );
-                
-                if let Err(e) = orphaned_block.save_to_active(mgr).await {
-                    log::error!("Failed to orphan block {} at index {}: {}", 
-                        &block.hash[..16], index, e);
-                } else {
-                    log::info!("   Orphaned block {} at index {}", &block.hash[..16], index);
-                    orphaned_count += 1;
-                }
-            }
+    Ok(ChainIntegrityReport {
+        total_blocks,
+        checked_from: min_index,
+        valid_blocks,
+        break_point: Some(break_index),
+        orphaned_count,
+        repaired: true,
+    })
+}
+
+/// Validate and optionally repair the canonical chain, starting from the
+/// integrity checkpoint
+/// 
+/// Blocks up to the checkpoint were found properly linked by an earlier check.
+/// While the checkpoint block is still canonical, only the blocks after it are
+/// loaded and checked, along with `INTEGRITY_SAMPLE_SIZE` random blocks below
+/// it; otherwise (first run, a reorg replaced it, or a sampled block no longer
+/// links to its parent) the whole chain is validated with
+/// `validate_and_repair_chain`. Canonical blocks past a gap aren't reached
+/// from the checkpoint, so only a full check reports them.
+pub async fn validate_and_repair_chain_incremental(
+    mgr: &DatastoreManager,
+    repair: bool,
+) -> Result<ChainIntegrityReport> {
+    let checkpoint = match mgr.integrity_checkpoint() {
+        Ok(checkpoint) => checkpoint,
+        Err(e) => {
+            log::warn!("Failed to read integrity checkpoint, checking the whole chain: {}", e);
+            None
         }
+    };
+    let Some(checkpoint) = checkpoint else {
+        return validate_and_repair_chain(mgr, repair).await;
+    };
+    let mut previous = match MinerBlock::find_canonical_by_index_simple(mgr, checkpoint.index).await? {
+        Some(block) if block.hash == checkpoint.hash => block,
+        _ => {
+            log::info!("🔍 Integrity checkpoint at index {} is no longer canonical, checking the whole chain", checkpoint.index);
+            return validate_and_repair_chain(mgr, repair).await;
+        }
+    };
+    
+    if let Some(index) = find_unlinked_sample(mgr, checkpoint.index, INTEGRITY_SAMPLE_SIZE).await? {
+        log::warn!("⚠️  Block {} below the integrity checkpoint doesn't link to its parent, checking the whole chain", index);
+        return validate_and_repair_chain(mgr, repair).await;
     }
     
-    log::info!("✅ Chain repair complete: orphaned {} blocks", orphaned_count);
-    log::info!("   Valid chain now ends at index {}", break_index.saturating_sub(1));
-    log::info!("   Auto-healing should sync correct blocks from peers");
+    log::info!("🔍 Resuming chain integrity validation from checkpoint at index {}", checkpoint.index);
+    let checked_from = checkpoint.index + 1;
+    let mut valid_blocks = 0;
+    let mut break_point: Option<u64> = None;
+    let mut broken: Vec<MinerBlock> = Vec::new();
+    let mut next_index = checked_from;
+    while let Some(block) = MinerBlock::find_canonical_by_index_simple(mgr, next_index).await? {
+        next_index += 1;
+        if break_point.is_none() && block.previous_hash != previous.hash {
+            log::error!("❌ Chain break at index {}: prev_hash {} doesn't match block {} hash {}", 
+                block.index, &block.previous_hash[..16.min(block.previous_hash.len())],
+                previous.index, &previous.hash[..16.min(previous.hash.len())]);
+            break_point = Some(block.index);
+        }
+        if break_point.is_some() {
+            broken.push(block);
+        } else {
+            valid_blocks += 1;
+            previous = block;
+        }
+    }
+    let total_blocks = valid_blocks + broken.len();
     
+    let Some(break_index) = break_point else {
+        log::info!("✅ Chain integrity validated: {} new blocks properly linked since index {}", valid_blocks, checkpoint.index);
+        save_checkpoint(mgr, Some(&previous));
+        return Ok(ChainIntegrityReport {
+            total_blocks,
+            checked_from,
+            valid_blocks,
+            break_point: None,
+            orphaned_count: 0,
+            repaired: false,
+        });
+    };
+    
+    log::warn!("⚠️  Chain integrity issue: break at index {}, {} valid blocks since the checkpoint", 
+        break_index, valid_blocks);
+    // `previous` is the last block linked before the break
+    save_checkpoint(mgr, Some(&previous));
+    
+    if !repair {
+        log::info!("🔧 Repair not requested - run with repair=true to fix");
+        return Ok(ChainIntegrityReport {
+            total_blocks,
+            checked_from,
+            valid_blocks,
+            break_point: Some(break_index),
+            orphaned_count: 0,
+            repaired: false,
+        });
+    }
+    
+    let orphaned_count = orphan_broken_blocks(mgr, broken.iter().collect(), break_index).await;
     Ok(ChainIntegrityReport {
         total_blocks,
+        checked_from,
         valid_blocks,
         break_point: Some(break_index),
         orphaned_count,
@@ -169,6 +262,68 @@ pub async fn validate_and_repair_chain(
     })
 }
 
+/// A block among `sample_size` random ones from 1 to `up_to` whose parent's
+/// hash isn't its `previous_hash`. Sampled blocks that are missing, or whose
+/// parent is, are skipped: gaps are left to the full check.
+async fn find_unlinked_sample(mgr: &DatastoreManager, up_to: u64, sample_size: usize) -> Result<Option<u64>> {
+    let count = sample_size.min(up_to as usize);
+    let mut indexes: Vec<u64> = rand::seq::index::sample(&mut rand::thread_rng(), up_to as usize, count)
+        .into_iter()
+        .map(|i| i as u64 + 1)
+        .collect();
+    indexes.sort_unstable();
+    for index in indexes {
+        let Some(block) = MinerBlock::find_canonical_by_index_simple(mgr, index).await? else { continue };
+        let Some(parent) = MinerBlock::find_canonical_by_index_simple(mgr, index - 1).await? else { continue };
+        if block.previous_hash != parent.hash {
+            return Ok(Some(index));
+        }
+    }
+    Ok(None)
+}
+
+/// Orphan the canonical blocks from a chain break onwards
+async fn orphan_broken_blocks(mgr: &DatastoreManager, blocks: Vec<&MinerBlock>, break_index: u64) -> usize {
+    log::info!("🔧 Repairing chain: orphaning canonical blocks from index {} onwards", break_index);
+    
+    let mut orphaned_count = 0;
+    for block in blocks {
+        if block.is_canonical && !block.is_orphaned {
+            let mut orphaned_block = block.clone();
+            orphaned_block.mark_as_orphaned(
+                format!("Chain integrity repair: block built on broken/orphaned chain at index {}", break_index),
+                None,
+            );
+            
+            if let Err(e) = orphaned_block.save_to_active(mgr).await {
+                log::error!("Failed to orphan block {} at index {}: {}", 
+                    &block.hash[..16], block.index, e);
+            } else {
+                log::info!("   Orphaned block {} at index {}", &block.hash[..16], block.index);
+                orphaned_count += 1;
+            }
+        }
+    }
+    
+    log::info!("✅ Chain repair complete: orphaned {} blocks", orphaned_count);
+    log::info!("   Valid chain now ends at index {}", break_index.saturating_sub(1));
+    log::info!("   Auto-healing should sync correct blocks from peers");
+    orphaned_count
+}
+
+/// Record `block` as the last one found properly linked
+fn save_checkpoint(mgr: &DatastoreManager, block: Option<&MinerBlock>) {
+    let Some(block) = block else { return };
+    let checkpoint = IntegrityCheckpoint {
+        index: block.index,
+        hash: block.hash.clone(),
+        checked_at: crate::bootstrapper_health::unix_now(),
+    };
+    if let Err(e) = mgr.save_integrity_checkpoint(&checkpoint) {
+        log::warn!("Failed to save integrity checkpoint: {}", e);
+    }
+}
+
 /// Quick check if the chain has integrity issues (doesn't repair)
 pub async fn check_chain_integrity(mgr: &DatastoreManager) -> Result<bool> {
     let canonical_blocks = MinerBlock::find_all_canonical_multi(mgr).await?;
@@ -372,5 +527,83 @@ mod tests {
         let canonical = MinerBlock::find_all_canonical_multi(&datastore).await.unwrap();
         assert_eq!(canonical.len(), 2);
     }
+    
+    #[tokio::test]
+    async fn test_incremental_validation_resumes_from_checkpoint() {
+        let datastore = DatastoreManager::create_in_memory().unwrap();
+        let block = |i: u64, prev_hash: String| {
+            MinerBlock::new_canonical(
+                format!("hash_{}", i),
+                i,
+                0,
+                1234567890 + i as i64,
+                prev_hash,
+                format!("data_{}", i),
+                12345,
+                1000,
+                "peer_id".to_string(),
+                1,
+            )
+        };
+        for i in 0..4 {
+            let prev_hash = if i == 0 { "genesis".to_string() } else { format!("hash_{}", i - 1) };
+            block(i, prev_hash).save_to_active(&datastore).await.unwrap();
+        }
+        
+        // Without a checkpoint the whole chain is checked
+        let report = validate_and_repair_chain_incremental(&datastore, false).await.unwrap();
+        assert_eq!((report.checked_from, report.valid_blocks), (0, 4));
+        assert_eq!(datastore.integrity_checkpoint().unwrap().map(|c| c.index), Some(3));
+        
+        // Then only the blocks added since, including a broken one
+        block(4, "hash_3".to_string()).save_to_active(&datastore).await.unwrap();
+        block(5, "wrong_hash".to_string()).save_to_active(&datastore).await.unwrap();
+        block(6, "hash_5".to_string()).save_to_active(&datastore).await.unwrap();
+        let report = validate_and_repair_chain_incremental(&datastore, true).await.unwrap();
+        assert_eq!(report.checked_from, 4);
+        assert_eq!(report.total_blocks, 3);
+        assert_eq!(report.valid_blocks, 1);
+        assert_eq!(report.break_point, Some(5));
+        assert_eq!(report.orphaned_count, 2);
+        assert_eq!(datastore.integrity_checkpoint().unwrap().map(|c| c.index), Some(4));
+        
+        let canonical = MinerBlock::find_all_canonical_multi(&datastore).await.unwrap();
+        assert_eq!(canonical.len(), 5);
+    }
+    
+    #[tokio::test]
+    async fn test_sampled_break_below_checkpoint_triggers_full_check() {
+        let datastore = DatastoreManager::create_in_memory().unwrap();
+        for i in 0..4 {
+            let prev_hash = match i {
+                0 => "genesis".to_string(),
+                2 => "wrong_hash".to_string(),
+                _ => format!("hash_{}", i - 1),
+            };
+            MinerBlock::new_canonical(
+                format!("hash_{}", i),
+                i,
+                0,
+                1234567890 + i as i64,
+                prev_hash,
+                format!("data_{}", i),
+                12345,
+                1000,
+                "peer_id".to_string(),
+                1,
+            ).save_to_active(&datastore).await.unwrap();
+        }
+        // A checkpoint left from before block 2 was replaced
+        datastore.save_integrity_checkpoint(&IntegrityCheckpoint {
+            index: 3,
+            hash: "hash_3".to_string(),
+            checked_at: 0,
+        }).unwrap();
+        
+        // Fewer blocks than the sample size, so every one is sampled
+        let report = validate_and_repair_chain_incremental(&datastore, false).await.unwrap();
+        assert_eq!(report.checked_from, 0);
+        assert_eq!(report.break_point, Some(2));
+        assert_eq!(datastore.integrity_checkpoint().unwrap().map(|c| c.index), Some(1));
+    }
 }
-
@@ -0,0 +1,371 @@
+use anyhow::Result;
+use libp2p::multiaddr::Multiaddr;
+use libp2p::PeerId;
+use std::io::Write;
+use std::path::Path;
+
+use crate::contract_sync::{
+    commit_id_of, decode_chunk, encode_chunk, ContractSyncRequest, ContractSyncResponse,
+    UnsupportedProtocol, CHUNK_SIZE,
+};
+use crate::gossip::contract::commits::{CommitAnnouncement, TOPIC as COMMITS_TOPIC};
+use crate::node::Node;
+use crate::reqres::contract::export::{ExportRequest, PATH as EXPORT_PATH};
+use crate::reqres::contract::simulate_commit::{SimulateCommitRequest, PATH as SIMULATE_COMMIT_PATH};
+
+pub use crate::contract_export::ContractExport;
+pub use crate::reqres::contract::simulate_commit::SimulateCommitResponse;
+
+/// Times to reconnect and retry a chunk before giving up
+const MAX_RETRIES: u32 = 3;
+
+/// Outcome of a push
+#[derive(Debug, Default)]
+pub struct PushSummary {
+    /// Commits the peer took, in the order sent
+    pub pushed: Vec<String>,
+    /// Commits that continued an earlier, interrupted push
+    pub resumed: Vec<String>,
+    pub head: Option<String>,
+}
+
+/// Outcome of a pull
+#[derive(Debug, Default)]
+pub struct PullSummary {
+    /// Commit ids and payloads, parents first
+    pub commits: Vec<(String, Vec<u8>)>,
+    pub head: Option<String>,
+}
+
+struct Peer {
+    addr: Multiaddr,
+    peer_id: PeerId,
+}
+
+impl Peer {
+    fn parse(target: &str) -> Result<Self> {
+        let addr = target.parse::<Multiaddr>()?;
+        let Some(libp2p::multiaddr::Protocol::P2p(peer_id)) = addr.iter().last() else {
+            anyhow::bail!("Provided address must end in `/p2p` and include PeerID");
+        };
+        Ok(Self { addr, peer_id })
+    }
+
+    /// Connect unless already connected, e.g. by a caller following gossip.
+    /// Returns whether a new connection was made.
+    async fn connect(&self, node: &mut Node) -> Result<bool> {
+        if node.swarm.is_connected(self.peer_id).await? {
+            return Ok(false);
+        }
+        node.connect_to_peer_multiaddr(self.addr.clone()).await?;
+        Ok(true)
+    }
+
+    /// Send a request, reconnecting and retrying when the connection drops
+    async fn request(&self, node: &mut Node, request: ContractSyncRequest) -> Result<ContractSyncResponse> {
+        let mut attempt = 0;
+        loop {
+            match node.send_contract_sync_request(self.peer_id, request.clone()).await {
+                Ok(ContractSyncResponse::Error { message }) => anyhow::bail!("{}", message),
+                Ok(response) => return Ok(response),
+                Err(e) if e.is::<UnsupportedProtocol>() || attempt >= MAX_RETRIES => return Err(e),
+                Err(e) => {
+                    attempt += 1;
+                    log::warn!("{}; reconnecting (attempt {} of {})", e, attempt, MAX_RETRIES);
+                    node.connect_to_peer_multiaddr(self.addr.clone()).await?;
+                }
+            }
+        }
+    }
+}
+
+/// Send `commits` (id and payload, parents first) that the peer doesn't have
+/// yet. `have` is every commit we have, so the peer can say whether it's
+/// ahead of us.
+pub async fn push(
+    node: &mut Node,
+    target: &str,
+    contract_id: &str,
+    have: Vec<String>,
+    commits: &[(String, Vec<u8>)],
+) -> Result<PushSummary> {
+    let peer = Peer::parse(target)?;
+    let connected = peer.connect(node).await?;
+
+    let result = push_to(node, &peer, contract_id, have, commits).await;
+    if connected {
+        let _ = node.disconnect_from_peer_id(peer.peer_id).await;
+    }
+    result
+}
+
+async fn push_to(
+    node: &mut Node,
+    peer: &Peer,
+    contract_id: &str,
+    have: Vec<String>,
+    commits: &[(String, Vec<u8>)],
+) -> Result<PushSummary> {
+    let ContractSyncResponse::Negotiated { missing, want, .. } = peer.request(node, ContractSyncRequest::Negotiate {
+        contract_id: contract_id.to_string(),
+        have,
+        offer: commits.iter().map(|(id, _)| id.clone()).collect(),
+    }).await? else {
+        anyhow::bail!("Unexpected response to negotiate");
+    };
+
+    if !missing.is_empty() {
+        anyhow::bail!(
+            "The remote has {} commit(s) you don't have. Pull first.",
+            missing.len()
+        );
+    }
+
+    let mut summary = PushSummary::default();
+    for (commit_id, payload) in commits {
+        let Some(wanted) = want.iter().find(|w| &w.commit_id == commit_id) else {
+            continue;
+        };
+        if wanted.offset > 0 {
+            log::info!("Resuming {} from byte {}", commit_id, wanted.offset);
+            summary.resumed.push(commit_id.clone());
+        }
+
+        let total = payload.len() as u64;
+        let mut offset = wanted.offset.min(total);
+        loop {
+            let end = (offset as usize + CHUNK_SIZE).min(payload.len());
+            let response = peer.request(node, ContractSyncRequest::PutChunk {
+                contract_id: contract_id.to_string(),
+                commit_id: commit_id.clone(),
+                offset,
+                total,
+                data: encode_chunk(&payload[offset as usize..end]),
+            }).await?;
+            match response {
+                ContractSyncResponse::Stored { complete: true, .. } => break,
+                // Carry on from wherever the peer is, which is also how a
+                // chunk lost in a reconnect gets resent
+                ContractSyncResponse::Stored { received, .. } => offset = received.min(total),
+                _ => anyhow::bail!("Unexpected response to chunk of {}", commit_id),
+            }
+        }
+        summary.pushed.push(commit_id.clone());
+        summary.head = Some(commit_id.clone());
+    }
+
+    Ok(summary)
+}
+
+/// Fetch the commits the peer has and we don't. Partly downloaded commits are
+/// kept in `partial_dir`, so a pull that's cut off resumes where it stopped.
+pub async fn pull(
+    node: &mut Node,
+    target: &str,
+    contract_id: &str,
+    have: Vec<String>,
+    partial_dir: &Path,
+) -> Result<PullSummary> {
+    let peer = Peer::parse(target)?;
+    let connected = peer.connect(node).await?;
+
+    let result = pull_from(node, &peer, contract_id, have, partial_dir).await;
+    if connected {
+        let _ = node.disconnect_from_peer_id(peer.peer_id).await;
+    }
+    result
+}
+
+async fn pull_from(
+    node: &mut Node,
+    peer: &Peer,
+    contract_id: &str,
+    have: Vec<String>,
+    partial_dir: &Path,
+) -> Result<PullSummary> {
+    let ContractSyncResponse::Negotiated { head, missing, .. } = peer.request(node, ContractSyncRequest::Negotiate {
+        contract_id: contract_id.to_string(),
+        have,
+        offer: Vec::new(),
+    }).await? else {
+        anyhow::bail!("Unexpected response to negotiate");
+    };
+
+    std::fs::create_dir_all(partial_dir)?;
+    let mut summary = PullSummary { commits: Vec::new(), head };
+    for wanted in missing {
+        let payload = download(node, peer, contract_id, &wanted.commit_id, wanted.size, partial_dir).await?;
+        summary.commits.push((wanted.commit_id, payload));
+    }
+
+    Ok(summary)
+}
+
+/// Ask the peer what a commit would do if it were pushed: whether validators
+/// would accept it, the gas it would use and the state it would change.
+/// Nothing is stored.
+pub async fn simulate_commit(
+    node: &mut Node,
+    target: &str,
+    contract_id: &str,
+    body: serde_json::Value,
+    head: serde_json::Value,
+) -> Result<SimulateCommitResponse> {
+    let peer = Peer::parse(target)?;
+    let connected = peer.connect(node).await?;
+
+    let request = SimulateCommitRequest {
+        contract_id: contract_id.to_string(),
+        body,
+        head,
+        epoch: None,
+    };
+    let result = async {
+        let response = node
+            .send_request(peer.peer_id, SIMULATE_COMMIT_PATH.to_string(), serde_json::to_string(&request)?)
+            .await?;
+        if !response.ok {
+            anyhow::bail!("Failed to simulate commit: {:?}", response.errors);
+        }
+        let data = response.data.ok_or_else(|| anyhow::anyhow!("Empty simulate response"))?;
+        Ok(serde_json::from_value(data)?)
+    }.await;
+    if connected {
+        let _ = node.disconnect_from_peer_id(peer.peer_id).await;
+    }
+    result
+}
+
+/// Ask the peer for the contract as of the finalized checkpoint of `epoch`,
+/// with the certificate and commits to check it by
+pub async fn export_contract(
+    node: &mut Node,
+    target: &str,
+    contract_id: &str,
+    epoch: u64,
+) -> Result<ContractExport> {
+    let peer = Peer::parse(target)?;
+    let connected = peer.connect(node).await?;
+
+    let request = ExportRequest {
+        contract_id: contract_id.to_string(),
+        checkpoint: epoch,
+    };
+    let result = async {
+        let response = node
+            .send_request(peer.peer_id, EXPORT_PATH.to_string(), serde_json::to_string(&request)?)
+            .await?;
+        if !response.ok {
+            anyhow::bail!("Failed to export contract: {:?}", response.errors);
+        }
+        let data = response.data.ok_or_else(|| anyhow::anyhow!("Empty export response"))?;
+        Ok(serde_json::from_value(data)?)
+    }.await;
+    if connected {
+        let _ = node.disconnect_from_peer_id(peer.peer_id).await;
+    }
+    result
+}
+
+/// Connect to `target` and listen for commit announcements, so that
+/// `next_announcement` sees commits as the network accepts them
+pub async fn follow(node: &mut Node, target: &str) -> Result<()> {
+    let peer = Peer::parse(target)?;
+    peer.connect(node).await?;
+    node.subscribe_gossip(COMMITS_TOPIC).await
+}
+
+/// Wait for the next announced commit of `contract_id`
+pub async fn next_announcement(node: &mut Node, contract_id: &str) -> Result<CommitAnnouncement> {
+    loop {
+        let message = node.next_gossip_message(COMMITS_TOPIC).await?;
+        match serde_json::from_slice::<CommitAnnouncement>(&message.data) {
+            Ok(announcement) if announcement.contract_id == contract_id => return Ok(announcement),
+            Ok(_) => {}
+            Err(e) => log::debug!("Ignoring bad commit announcement: {}", e),
+        }
+    }
+}
+
+/// Fetch one commit's payload, e.g. one that was just announced
+pub async fn fetch(
+    node: &mut Node,
+    target: &str,
+    contract_id: &str,
+    commit_id: &str,
+    partial_dir: &Path,
+) -> Result<Vec<u8>> {
+    let peer = Peer::parse(target)?;
+    let connected = peer.connect(node).await?;
+
+    let result = async {
+        // The first chunk says how big the commit is
+        let ContractSyncResponse::Chunk { total, .. } = peer.request(node, ContractSyncRequest::GetChunk {
+            contract_id: contract_id.to_string(),
+            commit_id: commit_id.to_string(),
+            offset: 0,
+        }).await? else {
+            anyhow::bail!("Unexpected response to chunk of {}", commit_id);
+        };
+        std::fs::create_dir_all(partial_dir)?;
+        download(node, &peer, contract_id, commit_id, total, partial_dir).await
+    }.await;
+    if connected {
+        let _ = node.disconnect_from_peer_id(peer.peer_id).await;
+    }
+    result
+}
+
+/// Download a commit of `size` bytes chunk by chunk, continuing from any
+/// partial download in `partial_dir`, and check it against its id
+async fn download(
+    node: &mut Node,
+    peer: &Peer,
+    contract_id: &str,
+    commit_id: &str,
+    size: u64,
+    partial_dir: &Path,
+) -> Result<Vec<u8>> {
+    let part_path = partial_dir.join(format!("{}.part", commit_id));
+    let mut received = std::fs::metadata(&part_path).map(|m| m.len()).unwrap_or(0);
+    if received > size {
+        // Not a prefix of this commit; start over
+        received = 0;
+    }
+    if received > 0 {
+        log::info!("Resuming {} from byte {}", commit_id, received);
+    }
+    let mut part = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&part_path)?;
+    part.set_len(received)?;
+
+    while received < size {
+        let response = peer.request(node, ContractSyncRequest::GetChunk {
+            contract_id: contract_id.to_string(),
+            commit_id: commit_id.to_string(),
+            offset: received,
+        }).await?;
+        let ContractSyncResponse::Chunk { offset, data, .. } = response else {
+            anyhow::bail!("Unexpected response to chunk of {}", commit_id);
+        };
+        if offset != received {
+            anyhow::bail!("Peer sent {} from byte {} instead of {}", commit_id, offset, received);
+        }
+        let bytes = decode_chunk(&data)?;
+        if bytes.is_empty() {
+            anyhow::bail!("Peer stopped sending {} at byte {}", commit_id, received);
+        }
+        part.write_all(&bytes)?;
+        received += bytes.len() as u64;
+    }
+    drop(part);
+
+    let payload = std::fs::read(&part_path)?;
+    std::fs::remove_file(&part_path)?;
+    if commit_id_of(&payload) != commit_id {
+        anyhow::bail!("Commit {} doesn't match its contents", commit_id);
+    }
+    Ok(payload)
+}
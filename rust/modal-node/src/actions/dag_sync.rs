@@ -35,6 +35,7 @@ pub async fn sync_request(
     
     let sync_response: SyncResponse = serde_json::from_value(data)
         .context("Failed to deserialize sync response")?;
+    sync_response.validate_fields()?;
     
     Ok(sync_response)
 }
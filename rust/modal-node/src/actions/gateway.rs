@@ -0,0 +1,17 @@
+use anyhow::Result;
+
+use crate::actions::observer;
+use crate::node::Node;
+
+/// Run a gateway node: an observer that also serves the read-only RPC
+/// methods to the public, rate limited and cached per block.
+///
+/// Gateways never mine, vote or accept commits over RPC, so they can sit
+/// behind public explorers and wallets.
+pub async fn run(node: &mut Node) -> Result<()> {
+    log::info!("Starting gateway node");
+
+    node.start_rpc_gateway().await?;
+
+    observer::run(node).await
+}
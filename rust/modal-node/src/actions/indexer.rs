@@ -0,0 +1,16 @@
+use anyhow::Result;
+
+use crate::actions::observer;
+use crate::node::Node;
+
+/// Run an indexer node: an observer that also indexes the contracts it
+/// follows and answers the `/index/*` queries validators don't serve, like
+/// the contracts signed by a key, and over GraphQL if configured.
+pub async fn run(node: &mut Node) -> Result<()> {
+    log::info!("Starting indexer node");
+
+    node.start_indexer().await?;
+    node.start_graphql().await?;
+
+    observer::run(node).await
+}
@@ -30,10 +30,9 @@ pub fn start_sync_request_handler(
     mut sync_request_rx: tokio::sync::mpsc::UnboundedReceiver<(libp2p::PeerId, String)>,
     syncing_peers: Arc<Mutex<HashSet<libp2p::PeerId>>>,
     bootstrappers: Vec<libp2p::Multiaddr>,
-    swarm: Arc<Mutex<crate::swarm::NodeSwarm>>,
+    swarm: crate::swarm_driver::SwarmHandle,
     datastore: Arc<Mutex<DatastoreManager>>,
     ignored_peers: Arc<Mutex<std::collections::HashMap<libp2p::PeerId, IgnoredPeerInfo>>>,
-    reqres_response_txs: Arc<Mutex<std::collections::HashMap<libp2p::request_response::OutboundRequestId, tokio::sync::oneshot::Sender<crate::reqres::Response>>>>,
     mining_update_tx: tokio::sync::mpsc::UnboundedSender<u64>,
 ) {
     tokio::spawn(async move {
@@ -69,7 +68,6 @@ pub fn start_sync_request_handler(
                         swarm.clone(),
                         datastore.clone(),
                         ignored_peers.clone(),
-                        reqres_response_txs.clone(),
                     ).await;
                     
                     match result {
@@ -103,12 +101,11 @@ pub fn start_sync_request_handler(
 pub fn start_sync_listener(
     mut sync_trigger_rx: tokio::sync::broadcast::Receiver<u64>,
     datastore: Arc<Mutex<DatastoreManager>>,
-    swarm: Arc<Mutex<crate::swarm::NodeSwarm>>,
+    swarm: crate::swarm_driver::SwarmHandle,
     bootstrappers: Vec<libp2p::Multiaddr>,
-    reqres_response_txs: Arc<Mutex<std::collections::HashMap<libp2p::request_response::OutboundRequestId, tokio::sync::oneshot::Sender<crate::reqres::Response>>>>,
     sync_in_progress: Arc<AtomicBool>,
     mining_update_tx: tokio::sync::mpsc::UnboundedSender<u64>,
-) {
+) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
         let mut last_sync_time = std::time::Instant::now();
         let sync_cooldown = std::time::Duration::from_millis(SYNC_COOLDOWN_MS);
@@ -132,21 +129,19 @@ pub fn start_sync_listener(
                 &datastore,
                 &swarm,
                 &bootstrappers,
-                &reqres_response_txs,
                 target_index,
                 &mining_update_tx,
             ).await;
             
             sync_in_progress.store(false, Ordering::Relaxed);
         }
-    });
+    })
 }
 
 /// Start the auto-healing task.
 pub fn start_auto_healing_task(
     datastore: Arc<Mutex<DatastoreManager>>,
-    swarm: Arc<Mutex<crate::swarm::NodeSwarm>>,
-    reqres_response_txs: Arc<Mutex<std::collections::HashMap<libp2p::request_response::OutboundRequestId, tokio::sync::oneshot::Sender<crate::reqres::Response>>>>,
+    swarm: crate::swarm_driver::SwarmHandle,
     ignored_peers: Arc<Mutex<std::collections::HashMap<libp2p::PeerId, IgnoredPeerInfo>>>,
     bootstrappers: Vec<libp2p::Multiaddr>,
     shutdown: Arc<AtomicBool>,
@@ -196,7 +191,6 @@ pub fn start_auto_healing_task(
                         swarm.clone(),
                         datastore.clone(),
                         ignored_peers.clone(),
-                        reqres_response_txs.clone(),
                     ).await;
                     
                     match result {
@@ -4,79 +4,67 @@
 //! mining a single block and announcing it to peers.
 
 use anyhow::Result;
-use libp2p::gossipsub::IdentTopic;
 use modal_datastore::models::MinerBlock;
+use modal_common::difficulty::DifficultyConfig;
+use modal_common::block_commits::{select_commits, CommitDigest, CommitLimits};
+use modal_common::slashing::{EquivocationEvidence, MAX_EVIDENCE_PER_BLOCK};
+use modal_common::uncles::UncleRef;
+use modal_datastore::models::Commit;
 use modal_datastore::DatastoreManager;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use crate::actions::observer::get_chain_tip_index;
 use crate::gossip;
-use crate::constants::{BLOCKS_PER_EPOCH, ROLLING_INTEGRITY_CHECK_INTERVAL, ROLLING_INTEGRITY_WINDOW};
+use crate::constants::{
+    MINING_CANCEL_POLL_MS, ROLLING_INTEGRITY_CHECK_INTERVAL, ROLLING_INTEGRITY_WINDOW,
+    METRIC_BLOCK_MINING_SECS, METRIC_MINER_HASHRATE,
+};
 use super::mining_loop::MiningOutcome;
+use super::nomination::{nominee_for_index, NominationPolicy};
 
 /// Mine a block and gossip it to peers.
 pub async fn mine_and_gossip_block(
     index: u64,
     peer_id: &str,
-    miner_nominees: &Option<Vec<String>>,
+    nomination_policy: &dyn NominationPolicy,
     datastore: Arc<Mutex<DatastoreManager>>,
-    swarm: Arc<Mutex<crate::swarm::NodeSwarm>>,
+    swarm: crate::swarm_driver::SwarmHandle,
     fork_config: modal_observer::ForkConfig,
     mining_metrics: crate::mining_metrics::SharedMiningMetrics,
     initial_difficulty: Option<u128>,
     miner_hash_func: Option<String>,
     miner_hash_params: Option<serde_json::Value>,
     mining_delay_ms: Option<u64>,
+    miner_threads: Option<usize>,
     epoch_transition_tx: Option<tokio::sync::broadcast::Sender<u64>>,
 ) -> Result<MiningOutcome> {
-    use modal_miner::{Blockchain, ChainConfig};
+    use modal_miner::MiningError;
     
-    // Determine the nominee
-    let nominated_peer_id = match miner_nominees {
-        Some(nominees) if !nominees.is_empty() => {
-            let nominee_index = (index as usize) % nominees.len();
-            nominees[nominee_index].clone()
-        }
-        _ => peer_id.to_string(),
-    };
+    let nominated_peer_id = nominee_for_index(nomination_policy, index, peer_id, &datastore).await;
 
     log::info!("Mining block {} with nominated peer: {}", index, nominated_peer_id);
 
-    // Create ChainConfig
-    let chain_config = ChainConfig {
-        initial_difficulty: initial_difficulty.unwrap_or(1000),
-        target_block_time_secs: 60,
-        mining_delay_ms,
-    };
-
-    // Load blockchain
-    let mut chain = Blockchain::load_or_create_with_fork_config(
-        chain_config,
-        peer_id.to_string(),
+    let (mut chain, final_hash_func, _) = load_mining_chain(
+        peer_id,
         datastore.clone(),
         fork_config,
-    ).await?;
-    
-    log::info!("Loaded chain with {} blocks (height: {})", chain.blocks.len(), chain.height());
-    
-    // Determine hash function
-    let (final_hash_func, final_hash_params) = get_hash_config(
-        &datastore,
+        initial_difficulty,
         miner_hash_func,
         miner_hash_params,
-    ).await;
-    
-    // Set RandomX parameters if needed
-    if final_hash_func == "randomx" && final_hash_params.is_some() {
-        modal_common::hash_tax::set_randomx_params_from_json(final_hash_params.as_ref());
-        log::info!("Set custom RandomX parameters for mining");
-    }
+        mining_delay_ms,
+    ).await?;
     
     // Create miner with hash function
+    let cancel = Arc::new(AtomicBool::new(false));
     let custom_miner = modal_miner::Miner::new(modal_miner::MinerConfig {
         max_tries: None,
         hash_func_name: Some(final_hash_func.leak()),
         mining_delay_ms: chain.config.mining_delay_ms,
+        threads: miner_threads,
+        cancel: Some(cancel.clone()),
+        hash_registry: datastore.lock().await.hash_registry().clone(),
     });
     chain.miner = custom_miner;
     
@@ -95,22 +83,44 @@ pub async fn mine_and_gossip_block(
     
     log::info!("Chain ready for mining. Height: {}, Mining next index: {}", chain.height(), index);
     
-    // Mine the block
-    let miner_number = rand::random::<u64>();
-    let (mined_block, mining_stats) = chain.mine_block_with_persistence(
-        nominated_peer_id.clone(),
-        miner_number
-    ).await?;
+    // Mine the block, abandoning it if a competing block lands first
+    let uncles = uncles_for_index(&datastore, index).await;
+    let commits = commits_for_index(&datastore, index, &chain.config.commit_limits).await;
+    let evidence = evidence_for_index(&datastore, index).await;
+    let data = modal_miner::BlockData::new(nominated_peer_id.clone(), rand::random::<u64>())
+        .with_uncles(uncles)
+        .with_commits(commits)
+        .with_evidence(evidence);
+    let watcher = watch_for_competing_block(datastore.clone(), index, cancel);
+    let mined = chain.mine_block_data_with_persistence(data).await;
+    watcher.abort();
+    let (mined_block, mining_stats) = match mined {
+        Err(MiningError::Cancelled) => return Ok(MiningOutcome::Cancelled),
+        result => result?,
+    };
     
     // Update metrics
     if let Some(stats) = mining_stats {
         let mut metrics = mining_metrics.write().await;
         metrics.record_block_mined(stats.attempts as u64, stats.duration_secs);
+        metrics.record_thread_hashrates(stats.threads.iter().map(|t| t.hashrate()).collect());
         
         log::info!("⛏️  Block {} mined: {} attempts in {:.2}s, instant: {:.2} H/s",
             index, stats.attempts, stats.duration_secs, stats.hashrate());
         log::info!("📊 Miner Stats: avg_hashrate={:.2} H/s, total_blocks={}, total_hashes={}",
             metrics.average_hashrate(), metrics.blocks_mined, metrics.total_hashes);
+        drop(metrics);
+        
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        if let Err(e) = datastore.lock().await.node_metrics().record(now, &[
+            (METRIC_BLOCK_MINING_SECS, stats.duration_secs),
+            (METRIC_MINER_HASHRATE, stats.hashrate()),
+        ]) {
+            log::warn!("Failed to record mining metrics: {}", e);
+        }
     }
     
     // Verify mined block index
@@ -119,11 +129,139 @@ pub async fn mine_and_gossip_block(
         return Err(anyhow::anyhow!("Mined block index mismatch"));
     }
 
+    publish_mined_block(&mined_block, &datastore, &swarm, epoch_transition_tx).await;
+
+    Ok(MiningOutcome::Mined)
+}
+
+/// Recent orphans for block `index` to reference as uncles
+pub(crate) async fn uncles_for_index(datastore: &Arc<Mutex<DatastoreManager>>, index: u64) -> Vec<UncleRef> {
+    let mgr = datastore.lock().await;
+    match MinerBlock::find_uncle_candidates_multi(&mgr, index).await {
+        Ok(candidates) => {
+            if !candidates.is_empty() {
+                log::info!("Including {} uncle(s) in block {}", candidates.len(), index);
+            }
+            candidates
+                .into_iter()
+                .map(|b| UncleRef {
+                    hash: b.hash,
+                    index: b.index,
+                    nominated_peer_id: b.nominated_peer_id,
+                })
+                .collect()
+        }
+        Err(e) => {
+            log::warn!("Failed to look up uncle candidates for block {}: {:?}", index, e);
+            Vec::new()
+        }
+    }
+}
+
+/// Pending contract commits for block `index`, oldest first within the limits
+pub(crate) async fn commits_for_index(
+    datastore: &Arc<Mutex<DatastoreManager>>,
+    index: u64,
+    limits: &CommitLimits,
+) -> Vec<CommitDigest> {
+    let mgr = datastore.lock().await;
+    match Commit::find_pending_multi(&mgr).await {
+        Ok(pending) => {
+            let commits = select_commits(pending.iter().map(|c| c.digest()).collect(), limits);
+            if !commits.is_empty() {
+                log::info!("Including {} of {} pending commit(s) in block {}", commits.len(), pending.len(), index);
+            }
+            commits
+        }
+        Err(e) => {
+            log::warn!("Failed to look up pending commits for block {}: {:?}", index, e);
+            Vec::new()
+        }
+    }
+}
+
+/// Verified equivocation evidence for block `index` that no canonical block
+/// has included yet
+pub(crate) async fn evidence_for_index(datastore: &Arc<Mutex<DatastoreManager>>, index: u64) -> Vec<EquivocationEvidence> {
+    let mgr = datastore.lock().await;
+    match mgr.evidence_to_include(MAX_EVIDENCE_PER_BLOCK).await {
+        Ok(evidence) => {
+            if !evidence.is_empty() {
+                log::info!("Including {} piece(s) of equivocation evidence in block {}", evidence.len(), index);
+            }
+            evidence
+        }
+        Err(e) => {
+            log::warn!("Failed to look up equivocation evidence for block {}: {:?}", index, e);
+            Vec::new()
+        }
+    }
+}
+
+/// Load the local chain for producing the next block.
+///
+/// Returns the chain along with the hash function and parameters blocks must
+/// be mined with, after applying those parameters to the datastore's hash
+/// functions.
+pub(crate) async fn load_mining_chain(
+    peer_id: &str,
+    datastore: Arc<Mutex<DatastoreManager>>,
+    fork_config: modal_observer::ForkConfig,
+    initial_difficulty: Option<u128>,
+    miner_hash_func: Option<String>,
+    miner_hash_params: Option<serde_json::Value>,
+    mining_delay_ms: Option<u64>,
+) -> Result<(modal_miner::Blockchain, String, Option<serde_json::Value>)> {
+    use modal_miner::Blockchain;
+
+    let chain_config = network_chain_config(&datastore, initial_difficulty, mining_delay_ms).await;
+
+    // Load blockchain
+    let chain = Blockchain::load_or_create_with_fork_config(
+        chain_config,
+        peer_id.to_string(),
+        datastore.clone(),
+        fork_config,
+    ).await?;
+    
+    log::info!("Loaded chain with {} blocks (height: {})", chain.blocks.len(), chain.height());
+    
+    // Determine hash function
+    let (final_hash_func, final_hash_params) = get_hash_config(
+        &datastore,
+        miner_hash_func,
+        miner_hash_params,
+    ).await;
+    
+    // Configure the network's hash function with its parameters
+    if let Err(e) = datastore.lock().await.hash_registry().configure(&final_hash_func, final_hash_params.as_ref()) {
+        return Err(anyhow::anyhow!("Invalid {} parameters: {}", final_hash_func, e));
+    }
+    if final_hash_params.is_some() {
+        log::info!("Set custom {} parameters for mining", final_hash_func);
+    }
+
+    Ok((chain, final_hash_func, final_hash_params))
+}
+
+/// Gossip a block we produced and run the post-mining bookkeeping
+pub(crate) async fn publish_mined_block(
+    mined_block: &modal_miner::Block,
+    datastore: &Arc<Mutex<DatastoreManager>>,
+    swarm: &crate::swarm_driver::SwarmHandle,
+    epoch_transition_tx: Option<tokio::sync::broadcast::Sender<u64>>,
+) {
+    let index = mined_block.header.index;
+    let (epoch, starts_epoch) = {
+        let mgr = datastore.lock().await;
+        (mgr.block_index_to_epoch(index), mgr.era_schedule().is_epoch_start(index))
+    };
+
     // Convert to MinerBlock
     let miner_block = MinerBlock::new_canonical(
         mined_block.header.hash.clone(),
         index,
-        index / BLOCKS_PER_EPOCH,
+        epoch,
         mined_block.header.timestamp.timestamp(),
         mined_block.header.previous_hash.clone(),
         mined_block.header.data_hash.clone(),
@@ -131,21 +269,24 @@ pub async fn mine_and_gossip_block(
         mined_block.header.difficulty,
         mined_block.data.nominated_peer_id.clone(),
         mined_block.data.miner_number,
-    );
+    )
+    .with_uncles(mined_block.data.uncles.clone())
+    .with_commits(mined_block.data.commits.clone())
+    .with_evidence(mined_block.data.evidence.clone());
 
     // Gossip the block
-    gossip_block(&swarm, &miner_block).await;
+    gossip_block(swarm, &miner_block).await;
 
     log::info!("Mined block {} (epoch {}) with hash {} and difficulty {}",
         miner_block.index, miner_block.epoch, &miner_block.hash[..16], miner_block.target_difficulty);
     
     // Rolling integrity check
     if miner_block.index > 0 && miner_block.index.is_multiple_of(ROLLING_INTEGRITY_CHECK_INTERVAL) {
-        run_integrity_check(&datastore, miner_block.index).await;
+        run_integrity_check(datastore, miner_block.index).await;
     }
     
     // Log epoch changes
-    if miner_block.index > 0 && miner_block.index.is_multiple_of(BLOCKS_PER_EPOCH) {
+    if miner_block.index > 0 && starts_epoch {
         log::info!("🎯 EPOCH {} STARTED - New target difficulty: {}", miner_block.epoch, miner_block.target_difficulty);
         
         if let Some(tx) = epoch_transition_tx {
@@ -156,8 +297,6 @@ pub async fn mine_and_gossip_block(
             }
         }
     }
-
-    Ok(MiningOutcome::Mined)
 }
 
 /// Get hash configuration from genesis contract or config
@@ -189,20 +328,87 @@ async fn get_hash_config(
     }
 }
 
+/// Set `cancel` once the canonical chain reaches `index` (i.e. someone else mined it first)
+fn watch_for_competing_block(
+    datastore: Arc<Mutex<DatastoreManager>>,
+    index: u64,
+    cancel: Arc<AtomicBool>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(tokio::time::Duration::from_millis(MINING_CANCEL_POLL_MS)).await;
+            if get_chain_tip_index(&datastore).await >= index {
+                log::info!("Competing block {} accepted while mining, cancelling", index);
+                cancel.store(true, Ordering::Relaxed);
+                break;
+            }
+        }
+    })
+}
+
+/// Chain configuration of the network in the datastore's config, which
+/// mining and block validation share
+pub(crate) async fn network_chain_config(
+    datastore: &Arc<Mutex<DatastoreManager>>,
+    initial_difficulty: Option<u128>,
+    mining_delay_ms: Option<u64>,
+) -> modal_miner::ChainConfig {
+    use modal_miner::ChainConfig;
+
+    let era_schedule = datastore.lock().await.era_schedule().clone();
+    let chain_config = ChainConfig {
+        initial_difficulty: initial_difficulty.unwrap_or(1000),
+        target_block_time_secs: era_schedule.target_block_time_secs,
+        mining_delay_ms,
+        difficulty_algorithm: get_difficulty_config(datastore).await,
+        blocks_per_epoch: era_schedule.blocks_per_epoch,
+        eras: era_schedule.eras,
+        commit_limits: Default::default(),
+        regtest: false,
+    };
+    if !is_regtest(datastore).await {
+        return chain_config;
+    }
+    let regtest = ChainConfig::regtest();
+    ChainConfig {
+        initial_difficulty: regtest.initial_difficulty,
+        difficulty_algorithm: regtest.difficulty_algorithm,
+        mining_delay_ms: None,
+        regtest: true,
+        ..chain_config
+    }
+}
+
+/// Get the difficulty adjustment algorithm from the network config
+async fn get_difficulty_config(datastore: &Arc<Mutex<DatastoreManager>>) -> DifficultyConfig {
+    let network_config = datastore.lock().await.get_network_config().await.ok().flatten();
+    match network_config {
+        Some(config) => DifficultyConfig::from_network_config(&config).unwrap_or_else(|e| {
+            log::warn!("Invalid difficulty_algorithm in network config, using default: {}", e);
+            DifficultyConfig::default()
+        }),
+        None => DifficultyConfig::default(),
+    }
+}
+
+/// Whether the network config marks this as a regtest network
+pub(crate) async fn is_regtest(datastore: &Arc<Mutex<DatastoreManager>>) -> bool {
+    let network_config = datastore.lock().await.get_network_config().await.ok().flatten();
+    network_config
+        .and_then(|config| config.get("regtest").and_then(|v| v.as_bool()))
+        .unwrap_or(false)
+}
+
 /// Gossip a block to peers
-async fn gossip_block(swarm: &Arc<Mutex<crate::swarm::NodeSwarm>>, miner_block: &MinerBlock) {
+async fn gossip_block(swarm: &crate::swarm_driver::SwarmHandle, miner_block: &MinerBlock) {
     let gossip_msg = gossip::miner::block::MinerBlockGossip::from_miner_block(miner_block);
-    let topic = IdentTopic::new(gossip::miner::block::TOPIC);
     
-    if let Ok(json) = serde_json::to_string(&gossip_msg) {
-        let mut swarm_lock = swarm.lock().await;
-        match swarm_lock.behaviour_mut().gossipsub.publish(topic, json.as_bytes()) {
-            Ok(_) => {
-                log::debug!("Gossipped block {} to peers", miner_block.index);
-            }
-            Err(e) => {
-                log::debug!("Could not gossip block {} (no peers available): {}", miner_block.index, e);
-            }
+    match gossip::wire::publish_sharded(swarm, gossip::miner::block::TOPIC, miner_block.epoch, &gossip_msg).await {
+        Ok(_) => {
+            log::debug!("Gossipped block {} to peers", miner_block.index);
+        }
+        Err(e) => {
+            log::debug!("Could not gossip block {} (no peers available): {}", miner_block.index, e);
         }
     }
 }
@@ -0,0 +1,480 @@
+//! External miner protocol.
+//!
+//! A small stratum-like getwork interface that lets external miner processes
+//! and hardware mine for this node. Miners connect over TCP and exchange
+//! newline-delimited JSON requests:
+//!
+//! - `{"id": 1, "method": "login", "params": {"worker": "rig-1"}}`
+//! - `{"id": 2, "method": "getwork"}` returns a [`WorkTemplate`]
+//! - `{"id": 3, "method": "submit", "params": {"job_id": "...", "nonce": "1234"}}`
+//!
+//! A miner hashes `mining_data` followed by the decimal nonce with
+//! `hash_func`. Hashes meeting `share_difficulty` count as shares for the
+//! worker in `mining_metrics`; hashes that also meet `difficulty` complete the
+//! block, which is added to the chain and gossiped like a locally mined one.
+//! A nonce counts once per job.
+//!
+//! The server listens on localhost unless `getwork_bind` says otherwise, and
+//! then only with a `getwork_token`, which miners send as `token` in their
+//! login before anything else. Templates are built from a snapshot of the
+//! chain tip that is refreshed when the tip moves or every few seconds, so
+//! getwork calls don't reload the chain.
+
+use anyhow::{anyhow, Result};
+use modal_common::hash_tax;
+use modal_datastore::DatastoreManager;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use modal_common::block_commits::CommitDigest;
+use modal_common::slashing::EquivocationEvidence;
+use modal_common::uncles::UncleRef;
+use modal_datastore::models::MinerBlock;
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+
+use crate::actions::observer::get_chain_tip_index;
+use crate::constants::{
+    GETWORK_MAX_JOBS, GETWORK_MAX_LINE_BYTES, GETWORK_SHARE_DIFFICULTY_DIVISOR, GETWORK_TEMPLATE_REFRESH_SECS,
+};
+use crate::mining_metrics::{SharedMiningMetrics, ShareOutcome};
+use super::block_producer::{commits_for_index, evidence_for_index, load_mining_chain, publish_mined_block, uncles_for_index};
+use super::nomination::{nominee_for_index, SharedNominationPolicy};
+
+/// Block template handed to external miners
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkTemplate {
+    pub job_id: String,
+    pub index: u64,
+    /// Data to hash, followed by the decimal nonce
+    pub mining_data: String,
+    /// Block difficulty (decimal string, may exceed JSON number precision)
+    pub difficulty: String,
+    /// Minimum difficulty for a share to be accepted
+    pub share_difficulty: String,
+    pub hash_func: String,
+    pub hash_params: Option<Value>,
+}
+
+/// Everything the getwork server needs from the node
+#[derive(Clone)]
+pub struct GetworkContext {
+    pub peer_id: String,
+    pub nomination_policy: SharedNominationPolicy,
+    pub datastore: Arc<Mutex<DatastoreManager>>,
+    pub swarm: crate::swarm_driver::SwarmHandle,
+    pub fork_config: modal_observer::ForkConfig,
+    pub mining_metrics: SharedMiningMetrics,
+    pub initial_difficulty: Option<u128>,
+    pub miner_hash_func: Option<String>,
+    pub miner_hash_params: Option<Value>,
+    pub epoch_transition_tx: Option<tokio::sync::broadcast::Sender<u64>>,
+    /// Miners must log in with this token; required off localhost
+    pub token: Option<String>,
+}
+
+struct Job {
+    block: modal_miner::Block,
+    hash_func: String,
+    share_difficulty: u128,
+    created_at: Instant,
+    /// Nonces already submitted for this job
+    submitted: HashSet<u128>,
+}
+
+/// What the next block's templates take from the chain, until the tip moves
+#[derive(Clone)]
+struct ChainSnapshot {
+    index: u64,
+    tip_hash: String,
+    difficulty: u128,
+    regtest_timestamp: Option<chrono::DateTime<chrono::Utc>>,
+    nominee: String,
+    uncles: Vec<UncleRef>,
+    commits: Vec<CommitDigest>,
+    evidence: Vec<EquivocationEvidence>,
+    hash_func: String,
+    hash_params: Option<Value>,
+    taken_at: Instant,
+}
+
+/// Getwork server state shared by all connections
+pub struct GetworkServer {
+    ctx: GetworkContext,
+    jobs: Mutex<HashMap<String, Job>>,
+    snapshot: Mutex<Option<ChainSnapshot>>,
+}
+
+/// Share difficulty for a block difficulty
+pub fn share_difficulty_for(block_difficulty: u128) -> u128 {
+    (block_difficulty / GETWORK_SHARE_DIFFICULTY_DIVISOR).max(1)
+}
+
+/// Judge a hash against the share and block difficulties
+pub fn evaluate_share(hash: &str, hash_func: &str, share_difficulty: u128, block_difficulty: u128) -> ShareOutcome {
+    if hash_tax::is_hash_acceptable(hash, block_difficulty, hash_func) {
+        ShareOutcome::Block
+    } else if hash_tax::is_hash_acceptable(hash, share_difficulty, hash_func) {
+        ShareOutcome::Accepted
+    } else {
+        ShareOutcome::Rejected
+    }
+}
+
+/// Nonces may be sent as JSON numbers or decimal strings
+fn parse_nonce(value: &Value) -> Option<u128> {
+    match value {
+        Value::String(s) => s.parse().ok(),
+        Value::Number(n) => n.as_u64().map(u128::from),
+        _ => None,
+    }
+}
+
+impl GetworkServer {
+    pub fn new(ctx: GetworkContext) -> Self {
+        Self {
+            ctx,
+            jobs: Mutex::new(HashMap::new()),
+            snapshot: Mutex::new(None),
+        }
+    }
+
+    /// The chain snapshot for the next block, taken again once the tip has
+    /// moved or it has aged
+    async fn chain_snapshot(&self) -> Result<ChainSnapshot> {
+        let tip_index = get_chain_tip_index(&self.ctx.datastore).await;
+        let tip_hash = {
+            let mgr = self.ctx.datastore.lock().await;
+            MinerBlock::find_canonical_by_index_simple(&mgr, tip_index).await?.map(|tip| tip.hash)
+        };
+        let mut snapshot = self.snapshot.lock().await;
+        if let Some(current) = snapshot.as_ref() {
+            let fresh = current.taken_at.elapsed() < Duration::from_secs(GETWORK_TEMPLATE_REFRESH_SECS);
+            if fresh && current.index == tip_index + 1 && Some(&current.tip_hash) == tip_hash.as_ref() {
+                return Ok(current.clone());
+            }
+        }
+
+        let (chain, hash_func, hash_params) = load_mining_chain(
+            &self.ctx.peer_id,
+            self.ctx.datastore.clone(),
+            self.ctx.fork_config.clone(),
+            self.ctx.initial_difficulty,
+            self.ctx.miner_hash_func.clone(),
+            self.ctx.miner_hash_params.clone(),
+            None,
+        ).await?;
+        let index = chain.height() + 1;
+        let nominee = nominee_for_index(
+            self.ctx.nomination_policy.as_ref(),
+            index,
+            &self.ctx.peer_id,
+            &self.ctx.datastore,
+        ).await;
+        let taken = ChainSnapshot {
+            index,
+            tip_hash: chain.latest_block().header.hash.clone(),
+            difficulty: chain.get_next_difficulty(),
+            regtest_timestamp: chain.config.regtest.then(|| chain.config.regtest_timestamp(index)),
+            nominee,
+            uncles: uncles_for_index(&self.ctx.datastore, index).await,
+            commits: commits_for_index(&self.ctx.datastore, index, &chain.config.commit_limits).await,
+            evidence: evidence_for_index(&self.ctx.datastore, index).await,
+            hash_func,
+            hash_params,
+            taken_at: Instant::now(),
+        };
+        *snapshot = Some(taken.clone());
+        Ok(taken)
+    }
+
+    /// Build a template for the next block on top of the current tip
+    async fn get_work(&self) -> Result<WorkTemplate> {
+        let snapshot = self.chain_snapshot().await?;
+        let index = snapshot.index;
+        // A fresh miner number gives every job its own mining data
+        let mut block = modal_miner::Block::new(
+            index,
+            snapshot.tip_hash,
+            modal_miner::BlockData::new(snapshot.nominee, rand::random::<u64>())
+                .with_uncles(snapshot.uncles)
+                .with_commits(snapshot.commits)
+                .with_evidence(snapshot.evidence),
+            snapshot.difficulty,
+        );
+        if let Some(timestamp) = snapshot.regtest_timestamp {
+            block.header.timestamp = timestamp;
+        }
+        let (hash_func, hash_params) = (snapshot.hash_func, snapshot.hash_params);
+        let share_difficulty = share_difficulty_for(block.header.difficulty);
+        let job_id = format!("{:016x}", rand::random::<u64>());
+
+        let template = WorkTemplate {
+            job_id: job_id.clone(),
+            index,
+            mining_data: block.mining_data(),
+            difficulty: block.header.difficulty.to_string(),
+            share_difficulty: share_difficulty.to_string(),
+            hash_func: hash_func.clone(),
+            hash_params: hash_params.clone(),
+        };
+
+        let mut jobs = self.jobs.lock().await;
+        jobs.retain(|_, job| job.block.header.index >= index);
+        if jobs.len() >= GETWORK_MAX_JOBS {
+            if let Some(oldest) = jobs.iter().min_by_key(|(_, job)| job.created_at).map(|(id, _)| id.clone()) {
+                jobs.remove(&oldest);
+            }
+        }
+        jobs.insert(job_id, Job {
+            block,
+            hash_func,
+            share_difficulty,
+            created_at: Instant::now(),
+            submitted: HashSet::new(),
+        });
+
+        Ok(template)
+    }
+
+    /// Check a submitted nonce, completing the block if it meets the block difficulty
+    async fn submit(&self, worker: &str, job_id: &str, nonce: u128) -> Result<(ShareOutcome, Option<String>)> {
+        let (mut block, hash_func, share_difficulty) = {
+            let mut jobs = self.jobs.lock().await;
+            let Some(job) = jobs.get_mut(job_id) else {
+                return Ok((ShareOutcome::Rejected, Some("unknown job".to_string())));
+            };
+            if !job.submitted.insert(nonce) {
+                return Ok((ShareOutcome::Rejected, Some("duplicate share".to_string())));
+            }
+            (job.block.clone(), job.hash_func.clone(), job.share_difficulty)
+        };
+
+        let index = block.header.index;
+        if get_chain_tip_index(&self.ctx.datastore).await >= index {
+            return Ok((ShareOutcome::Stale, Some(format!("block {} already mined", index))));
+        }
+
+        // Hashing can be expensive (RandomX, Argon2id), so keep it off the async workers.
+        // Loading the chain for the job configured the network's hash functions.
+        let mining_data = block.mining_data();
+        let hashes = self.ctx.datastore.lock().await.hash_registry().clone();
+        let hash = tokio::task::spawn_blocking({
+            let hash_func = hash_func.clone();
+            move || hashes.hash_with_nonce(&mining_data, nonce, &hash_func).map_err(|e| e.to_string())
+        })
+        .await?
+        .map_err(|e| anyhow!(e))?;
+
+        let outcome = evaluate_share(&hash, &hash_func, share_difficulty, block.header.difficulty);
+        match outcome {
+            ShareOutcome::Rejected => return Ok((outcome, Some("low difficulty share".to_string()))),
+            ShareOutcome::Block => {}
+            _ => return Ok((outcome, None)),
+        }
+
+        block.header.nonce = nonce;
+        block.header.hash = hash;
+        if !block.verify_data_hash() {
+            return Ok((ShareOutcome::Rejected, Some("invalid block data".to_string())));
+        }
+
+        let (mut chain, _, _) = load_mining_chain(
+            &self.ctx.peer_id,
+            self.ctx.datastore.clone(),
+            self.ctx.fork_config.clone(),
+            self.ctx.initial_difficulty,
+            self.ctx.miner_hash_func.clone(),
+            self.ctx.miner_hash_params.clone(),
+            None,
+        ).await?;
+        if chain.height() + 1 != index || chain.latest_block().header.hash != block.header.previous_hash {
+            return Ok((ShareOutcome::Stale, Some("chain tip moved".to_string())));
+        }
+        chain.add_block_with_fork_choice(block.clone()).await?;
+        self.jobs.lock().await.remove(job_id);
+
+        log::info!("⛏️  Block {} mined by external worker {}", index, worker);
+        publish_mined_block(&block, &self.ctx.datastore, &self.ctx.swarm, self.ctx.epoch_transition_tx.clone()).await;
+
+        Ok((ShareOutcome::Block, None))
+    }
+
+    /// Handle a single JSON request, returning the `result` value
+    async fn handle_request(&self, session: &mut Session, method: &str, params: &Value) -> Result<Value> {
+        if method == "login" {
+            if let Some(token) = &self.ctx.token {
+                if params.get("token").and_then(|t| t.as_str()) != Some(token.as_str()) {
+                    return Err(anyhow!("invalid token"));
+                }
+            }
+            session.logged_in = true;
+            if let Some(name) = params.get("worker").and_then(|w| w.as_str()) {
+                session.worker = name.to_string();
+            }
+            return Ok(json!({ "worker": session.worker }));
+        }
+        if self.ctx.token.is_some() && !session.logged_in {
+            return Err(anyhow!("log in with the getwork token first"));
+        }
+        let worker = &session.worker;
+        match method {
+            "getwork" => Ok(serde_json::to_value(self.get_work().await?)?),
+            "submit" => {
+                let job_id = params
+                    .get("job_id")
+                    .and_then(|j| j.as_str())
+                    .ok_or_else(|| anyhow!("missing job_id"))?;
+                let nonce = params
+                    .get("nonce")
+                    .and_then(parse_nonce)
+                    .ok_or_else(|| anyhow!("missing or invalid nonce"))?;
+
+                let (outcome, reason) = self.submit(worker, job_id, nonce).await?;
+                self.ctx.mining_metrics.write().await.record_share(worker, outcome);
+
+                Ok(json!({
+                    "accepted": matches!(outcome, ShareOutcome::Accepted | ShareOutcome::Block),
+                    "block": outcome == ShareOutcome::Block,
+                    "reason": reason,
+                }))
+            }
+            other => Err(anyhow!("unknown method '{}'", other)),
+        }
+    }
+
+    async fn handle_connection(self: Arc<Self>, stream: TcpStream) -> Result<()> {
+        let peer = stream.peer_addr()?;
+        let mut session = Session {
+            worker: peer.to_string(),
+            logged_in: false,
+        };
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        let mut line = Vec::new();
+
+        log::info!("External miner connected from {}", peer);
+
+        loop {
+            line.clear();
+            // Read one byte past the limit to tell a full line from an overlong one
+            let read = (&mut reader).take(GETWORK_MAX_LINE_BYTES as u64 + 1).read_until(b'\n', &mut line).await?;
+            if read == 0 {
+                break;
+            }
+            if line.len() > GETWORK_MAX_LINE_BYTES {
+                let response = json!({ "id": null, "result": null, "error": "request too long" });
+                let mut bytes = serde_json::to_vec(&response)?;
+                bytes.push(b'\n');
+                writer.write_all(&bytes).await?;
+                log::warn!("External miner {} sent a request over {} bytes, disconnecting", session.worker, GETWORK_MAX_LINE_BYTES);
+                return Ok(());
+            }
+            let line = String::from_utf8_lossy(&line);
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let response = match serde_json::from_str::<Value>(&line) {
+                Ok(request) => {
+                    let id = request.get("id").cloned().unwrap_or(Value::Null);
+                    let method = request.get("method").and_then(|m| m.as_str()).unwrap_or_default();
+                    let params = request.get("params").cloned().unwrap_or(Value::Null);
+                    match self.handle_request(&mut session, method, &params).await {
+                        Ok(result) => json!({ "id": id, "result": result, "error": null }),
+                        Err(e) => json!({ "id": id, "result": null, "error": e.to_string() }),
+                    }
+                }
+                Err(e) => json!({ "id": null, "result": null, "error": format!("invalid request: {}", e) }),
+            };
+
+            let mut bytes = serde_json::to_vec(&response)?;
+            bytes.push(b'\n');
+            writer.write_all(&bytes).await?;
+        }
+
+        log::info!("External miner {} disconnected", session.worker);
+        Ok(())
+    }
+}
+
+/// A miner's connection
+struct Session {
+    worker: String,
+    logged_in: bool,
+}
+
+/// Address the getwork server listens on when `getwork_bind` isn't set
+pub const DEFAULT_GETWORK_BIND: &str = "127.0.0.1";
+
+/// The address to listen on, refusing anything but localhost without a token
+fn listen_address(bind: &str, has_token: bool) -> Result<IpAddr> {
+    let address: IpAddr = bind.parse().map_err(|e| anyhow!("Invalid getwork_bind '{}': {}", bind, e))?;
+    if !address.is_loopback() && !has_token {
+        anyhow::bail!("The getwork server only listens on {} with a getwork_token", address);
+    }
+    Ok(address)
+}
+
+/// Serve the getwork protocol on `bind`:`port` in the background; anywhere
+/// but localhost needs a token
+pub async fn start_getwork_server(bind: &str, port: u16, ctx: GetworkContext) -> Result<tokio::task::JoinHandle<()>> {
+    let address = listen_address(bind, ctx.token.is_some())?;
+    let listener = TcpListener::bind((address, port)).await?;
+    log::info!("Starting getwork server for external miners on tcp://{}:{}", bind, port);
+
+    let server = Arc::new(GetworkServer::new(ctx));
+    Ok(tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    let server = server.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = server.handle_connection(stream).await {
+                            log::debug!("External miner connection closed: {}", e);
+                        }
+                    });
+                }
+                Err(e) => log::warn!("Failed to accept external miner connection: {}", e),
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate_share() {
+        let easy = "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff";
+        let medium = "0000000fffffffffffffffffffffffffffffffffffffffffffffffffffffffff";
+        let hard = "0000000000000000000000000000000000000000000000000000000000000001";
+
+        assert_eq!(evaluate_share(easy, "sha256", 100, 10_000), ShareOutcome::Rejected);
+        assert_eq!(evaluate_share(medium, "sha256", 100, u128::MAX), ShareOutcome::Accepted);
+        assert_eq!(evaluate_share(hard, "sha256", 100, 10_000), ShareOutcome::Block);
+    }
+
+    #[test]
+    fn test_share_difficulty_and_nonce_parsing() {
+        assert_eq!(share_difficulty_for(10), 1);
+        assert_eq!(share_difficulty_for(6400), 6400 / GETWORK_SHARE_DIFFICULTY_DIVISOR);
+        assert_eq!(parse_nonce(&json!("340282366920938463463374607431768211455")), Some(u128::MAX));
+        assert_eq!(parse_nonce(&json!(42)), Some(42));
+        assert_eq!(parse_nonce(&json!("abc")), None);
+    }
+
+    #[test]
+    fn test_listen_address_needs_a_token_off_localhost() {
+        assert!(listen_address(DEFAULT_GETWORK_BIND, false).unwrap().is_loopback());
+        assert!(listen_address("::1", false).is_ok());
+        assert!(listen_address("0.0.0.0", false).is_err());
+        assert!(listen_address("0.0.0.0", true).is_ok());
+        assert!(listen_address("localhost:1234", true).is_err());
+    }
+}
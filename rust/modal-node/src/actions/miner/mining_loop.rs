@@ -11,6 +11,7 @@ use tokio::sync::Mutex;
 use crate::actions::observer::get_chain_tip_index;
 use crate::constants::{MINING_LOOP_PAUSE_MS, MINING_RETRY_PAUSE_MS};
 use super::block_producer::mine_and_gossip_block;
+use super::nomination::SharedNominationPolicy;
 use super::MiningState;
 
 /// Result of a mining operation
@@ -20,6 +21,8 @@ pub enum MiningOutcome {
     Mined,
     /// Block was skipped because it already exists
     Skipped,
+    /// Mining was abandoned because a competing block arrived
+    Cancelled,
 }
 
 /// Start the mining loop as a background task.
@@ -29,18 +32,19 @@ pub fn start_mining_loop(
     sync_in_progress: Arc<AtomicBool>,
     mut mining_update_rx: tokio::sync::mpsc::UnboundedReceiver<u64>,
     datastore: Arc<Mutex<DatastoreManager>>,
-    swarm: Arc<Mutex<crate::swarm::NodeSwarm>>,
+    swarm: crate::swarm_driver::SwarmHandle,
     peerid_str: String,
-    miner_nominees: Option<Vec<String>>,
+    nomination_policy: SharedNominationPolicy,
     fork_config: modal_observer::ForkConfig,
     mining_metrics: crate::mining_metrics::SharedMiningMetrics,
     initial_difficulty: Option<u128>,
     miner_hash_func: Option<String>,
     miner_hash_params: Option<serde_json::Value>,
     mining_delay_ms: Option<u64>,
+    miner_threads: Option<usize>,
     epoch_transition_tx: Option<tokio::sync::broadcast::Sender<u64>>,
     mining_state: Arc<Mutex<MiningState>>,
-) {
+) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
         let mut current_index = starting_index;
         
@@ -70,7 +74,7 @@ pub fn start_mining_loop(
             match mine_and_gossip_block(
                 current_index,
                 &peerid_str,
-                &miner_nominees,
+                nomination_policy.as_ref(),
                 datastore.clone(),
                 swarm.clone(),
                 fork_config.clone(),
@@ -79,6 +83,7 @@ pub fn start_mining_loop(
                 miner_hash_func.clone(),
                 miner_hash_params.clone(),
                 mining_delay_ms,
+                miner_threads,
                 epoch_transition_tx.clone(),
             ).await {
                 Ok(MiningOutcome::Mined) => {
@@ -89,8 +94,12 @@ pub fn start_mining_loop(
                     let mut state = mining_state.lock().await;
                     state.current_mining_index = current_index;
                 }
-                Ok(MiningOutcome::Skipped) => {
-                    log::info!("⏭️  Block {} already exists, moving to next block", current_index);
+                Ok(outcome @ (MiningOutcome::Skipped | MiningOutcome::Cancelled)) => {
+                    if outcome == MiningOutcome::Cancelled {
+                        log::info!("⏹️  Competing block {} arrived, abandoning it", current_index);
+                    } else {
+                        log::info!("⏭️  Block {} already exists, moving to next block", current_index);
+                    }
                     
                     // Verify actual chain tip
                     current_index = get_next_mining_index(&datastore).await;
@@ -119,7 +128,7 @@ pub fn start_mining_loop(
             // Small delay between mining attempts
             tokio::time::sleep(tokio::time::Duration::from_millis(MINING_LOOP_PAUSE_MS)).await;
         }
-    });
+    })
 }
 
 /// Process pending mining updates from the channel
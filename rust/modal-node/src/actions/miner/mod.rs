@@ -7,6 +7,8 @@
 //! - Block production and gossip
 //! - Background sync and healing tasks (miner-specific)
 //! - Chain reorganization during sync
+//! - Getwork server for external miners
+//! - On-demand block production for regtest networks
 //!
 //! ## Relationship with Observer
 //!
@@ -19,18 +21,22 @@ mod mining_loop;
 mod background_tasks;
 mod block_producer;
 mod sync_helpers;
+pub mod getwork;
+pub mod nomination;
+pub mod regtest;
 
 use anyhow::Result;
 use std::sync::Arc;
 use std::sync::atomic::AtomicBool;
 use tokio::sync::Mutex;
 
+use crate::actions::roles::{self, MiningUpdates, Role};
 use crate::node::Node;
-use crate::gossip;
 
 // Re-export public items
 pub use mining_loop::MiningOutcome;
 pub use block_producer::mine_and_gossip_block;
+pub(crate) use block_producer::network_chain_config;
 pub use sync_helpers::{request_chain_info_impl, find_common_ancestor_efficient};
 
 /// Shared state for coordinating mining with sync operations
@@ -40,100 +46,46 @@ struct MiningState {
     current_mining_index: u64,
 }
 
-/// Run a mining node that continuously mines and gossips blocks.
-/// This function will run until a shutdown signal is received (Ctrl-C).
-pub async fn run(node: &mut Node) -> Result<()> {
-    // Validate and repair chain integrity before starting mining
-    validate_chain_before_mining(node).await;
-    
-    // Set up channels and shared state
-    let shutdown = Arc::new(AtomicBool::new(false));
+/// Sync with the bootstrappers whenever gossip brings a block whose parent is
+/// missing. Returns the channel that carries the new chain tip after each sync,
+/// whose sender is also kept in `node.mining_update_tx` for the gossip handler.
+pub fn start_orphan_sync(
+    node: &mut Node,
+) -> (tokio::sync::mpsc::UnboundedSender<u64>, tokio::sync::mpsc::UnboundedReceiver<u64>) {
     let (mining_update_tx, mining_update_rx) = tokio::sync::mpsc::unbounded_channel::<u64>();
     node.mining_update_tx = Some(mining_update_tx.clone());
     
-    // Set up sync request handler
     let (sync_request_tx, sync_request_rx) = tokio::sync::mpsc::unbounded_channel();
     node.sync_request_tx = Some(sync_request_tx);
     
-    // Shared flags
-    let sync_in_progress = Arc::new(AtomicBool::new(false));
     let syncing_peers = Arc::new(Mutex::new(std::collections::HashSet::<libp2p::PeerId>::new()));
-    
-    // Start sync request handler task
     background_tasks::start_sync_request_handler(
         sync_request_rx,
-        syncing_peers.clone(),
+        syncing_peers,
         node.bootstrappers.clone(),
         node.swarm.clone(),
         node.datastore_manager.clone(),
         node.ignored_peers.clone(),
-        node.reqres_response_txs.clone(),
-        mining_update_tx.clone(),
-    );
-    
-    // Subscribe to miner gossip
-    gossip::add_miner_event_listeners(node).await?;
-    
-    // Start services
-    node.start_status_server().await?;
-    node.start_status_html_writer().await?;
-    node.start_networking().await?;
-    node.start_autoupgrade().await?;
-    
-    // Start block promotion/purge background task
-    background_tasks::start_promotion_task(
-        node.datastore_manager.clone(),
-        shutdown.clone(),
-    );
-    
-    // Get starting index
-    let starting_index = get_starting_index(&node.datastore_manager).await?;
-    
-    // Start sync listener task
-    let sync_trigger_rx = node.sync_trigger_tx.subscribe();
-    background_tasks::start_sync_listener(
-        sync_trigger_rx,
-        node.datastore_manager.clone(),
-        node.swarm.clone(),
-        node.bootstrappers.clone(),
-        node.reqres_response_txs.clone(),
-        sync_in_progress.clone(),
         mining_update_tx.clone(),
     );
     
-    // Create shared mining state
-    let mining_state = Arc::new(Mutex::new(MiningState {
-        current_mining_index: starting_index,
-    }));
-    
-    // Start mining loop
-    mining_loop::start_mining_loop(
-        starting_index,
-        shutdown.clone(),
-        sync_in_progress.clone(),
-        mining_update_rx,
-        node.datastore_manager.clone(),
-        node.swarm.clone(),
-        node.peerid.to_string(),
-        node.miner_nominees.clone(),
-        node.fork_config.clone(),
-        node.mining_metrics.clone(),
-        node.initial_difficulty,
-        node.miner_hash_func.clone(),
-        node.miner_hash_params.clone(),
-        node.mining_delay_ms,
-        if node.hybrid_consensus {
-            Some(node.epoch_transition_tx.clone())
-        } else {
-            None
-        },
-        mining_state.clone(),
-    );
-    
-    // Wait for connections and sync
+    (mining_update_tx, mining_update_rx)
+}
+
+/// Run a mining node that continuously mines and gossips blocks.
+/// This function will run until a shutdown signal is received (Ctrl-C).
+pub async fn run(node: &mut Node) -> Result<()> {
+    roles::run(node, Role::Miner).await
+}
+
+/// Wait for peers, announce our chain and sync from them on startup, unless
+/// mining solo
+pub async fn initial_sync(node: &mut Node) -> Result<()> {
     if !node.bootstrappers.is_empty() {
         log::info!("Waiting for peer connections...");
+        let phase_started = std::time::Instant::now();
         node.wait_for_connections().await?;
+        node.boot_timer.record(crate::boot_profile::PHASE_PEER_CONNECTIONS, phase_started);
         
         log::info!("Announcing our chain to connected peers...");
         if let Err(e) = sync_helpers::announce_chain_tip(node).await {
@@ -141,43 +93,172 @@ pub async fn run(node: &mut Node) -> Result<()> {
         }
         
         log::info!("Syncing blockchain state from peers...");
+        let phase_started = std::time::Instant::now();
         if let Err(e) = sync_helpers::sync_from_peers(node).await {
             log::warn!("Failed to sync from peers: {:?}. Starting with local chain.", e);
         }
+        node.boot_timer.record(crate::boot_profile::PHASE_INITIAL_SYNC, phase_started);
     } else {
         log::info!("No bootstrappers configured - mining in solo mode");
     }
     
-    log::info!("Starting miner...");
-    
-    // Store shutdown flag
-    node.mining_shutdown = Some(shutdown.clone());
-    
-    // Start auto-healing task
-    background_tasks::start_auto_healing_task(
-        node.datastore_manager.clone(),
-        node.swarm.clone(),
-        node.reqres_response_txs.clone(),
-        node.ignored_peers.clone(),
-        node.bootstrappers.clone(),
-        shutdown.clone(),
-        sync_in_progress.clone(),
-        mining_update_tx.clone(),
-        node.fork_config.fork_recovery_min_peers.unwrap_or(1),
-        node.fork_config.fork_recovery_epoch_threshold.unwrap_or(2),
-    );
-    
-    // Wait for shutdown
-    node.wait_for_shutdown().await?;
-    
-    log::info!("🛑 Miner shutdown complete");
     Ok(())
 }
 
+/// The miner role: the mining loop, getwork server and the background tasks
+/// that keep the chain it mines on healthy
+pub struct MinerComponent {
+    shutdown: Arc<AtomicBool>,
+    mining_loop: Option<tokio::task::JoinHandle<()>>,
+    sync_listener: tokio::task::JoinHandle<()>,
+    getwork: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl MinerComponent {
+    pub async fn start(node: &mut Node, updates: &MiningUpdates) -> Result<Self> {
+        log::info!("Starting miner...");
+        
+        // Set up shared state
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let sync_in_progress = Arc::new(AtomicBool::new(false));
+        let mining_update_tx = updates.sender();
+        
+        // Start block promotion/purge background task
+        background_tasks::start_promotion_task(
+            node.datastore_manager.clone(),
+            shutdown.clone(),
+            node.prune_keep_blocks,
+        );
+        
+        // Get starting index
+        let starting_index = get_starting_index(&node.datastore_manager).await?;
+        
+        // Start sync listener task
+        let sync_trigger_rx = node.sync_trigger_tx.subscribe();
+        let sync_listener = background_tasks::start_sync_listener(
+            sync_trigger_rx,
+            node.datastore_manager.clone(),
+            node.swarm.clone(),
+            node.bootstrappers.clone(),
+            sync_in_progress.clone(),
+            mining_update_tx.clone(),
+        );
+        
+        // Create shared mining state
+        let mining_state = Arc::new(Mutex::new(MiningState {
+            current_mining_index: starting_index,
+        }));
+        
+        // Start mining loop (regtest networks only produce blocks on demand)
+        let mining_loop = if block_producer::is_regtest(&node.datastore_manager).await {
+            log::info!("Regtest network: blocks are only mined on demand (`modal node mine-blocks`)");
+            None
+        } else {
+            Some(mining_loop::start_mining_loop(
+                starting_index,
+                shutdown.clone(),
+                sync_in_progress.clone(),
+                updates.subscribe(),
+                node.datastore_manager.clone(),
+                node.swarm.clone(),
+                node.peerid.to_string(),
+                node.nomination_policy.clone(),
+                node.fork_config.clone(),
+                node.mining_metrics.clone(),
+                node.initial_difficulty,
+                node.miner_hash_func.clone(),
+                node.miner_hash_params.clone(),
+                node.mining_delay_ms,
+                node.miner_threads,
+                if node.hybrid_consensus {
+                    Some(node.epoch_transition_tx.clone())
+                } else {
+                    None
+                },
+                mining_state.clone(),
+            ))
+        };
+        
+        // Serve block templates to external miners
+        let getwork = match node.getwork_port {
+            Some(port) => {
+                let ctx = getwork::GetworkContext {
+                    peer_id: node.peerid.to_string(),
+                    nomination_policy: node.nomination_policy.clone(),
+                    datastore: node.datastore_manager.clone(),
+                    swarm: node.swarm.clone(),
+                    fork_config: node.fork_config.clone(),
+                    mining_metrics: node.mining_metrics.clone(),
+                    initial_difficulty: node.initial_difficulty,
+                    miner_hash_func: node.miner_hash_func.clone(),
+                    miner_hash_params: node.miner_hash_params.clone(),
+                    epoch_transition_tx: if node.hybrid_consensus {
+                        Some(node.epoch_transition_tx.clone())
+                    } else {
+                        None
+                    },
+                    token: node.getwork_token.clone(),
+                };
+                let bind = node.getwork_bind.as_deref().unwrap_or(getwork::DEFAULT_GETWORK_BIND);
+                Some(getwork::start_getwork_server(bind, port, ctx).await?)
+            }
+            None => None,
+        };
+        
+        // Store shutdown flag
+        node.mining_shutdown = Some(shutdown.clone());
+        
+        // Start auto-healing task
+        background_tasks::start_auto_healing_task(
+            node.datastore_manager.clone(),
+            node.swarm.clone(),
+            node.ignored_peers.clone(),
+            node.bootstrappers.clone(),
+            shutdown.clone(),
+            sync_in_progress.clone(),
+            mining_update_tx,
+            node.fork_config.fork_recovery_min_peers.unwrap_or(1),
+            node.fork_config.fork_recovery_epoch_threshold.unwrap_or(2),
+        );
+        
+        Ok(Self {
+            shutdown,
+            mining_loop,
+            sync_listener,
+            getwork,
+        })
+    }
+
+    /// Stop mining after the block in progress and close the getwork port
+    pub fn signal_stop(&self) {
+        self.shutdown.store(true, std::sync::atomic::Ordering::Relaxed);
+        self.sync_listener.abort();
+        if let Some(getwork) = &self.getwork {
+            getwork.abort();
+        }
+    }
+
+    pub async fn stopped(self) {
+        if let Some(mining_loop) = self.mining_loop {
+            log::info!("Waiting for the block in progress before leaving the miner role...");
+            let _ = mining_loop.await;
+        }
+        if let Some(getwork) = self.getwork {
+            let _ = getwork.await;
+        }
+        let _ = self.sync_listener.await;
+    }
+}
+
 /// Validate and repair chain integrity before mining
-async fn validate_chain_before_mining(node: &Node) {
+pub async fn validate_chain_before_mining(node: &Node) {
     let mgr = node.datastore_manager.lock().await;
-    match crate::actions::chain_integrity::validate_and_repair_chain(&mgr, true).await {
+    let result = if node.full_integrity_check {
+        crate::actions::chain_integrity::validate_and_repair_chain(&mgr, true).await
+    } else {
+        crate::actions::chain_integrity::validate_and_repair_chain_incremental(&mgr, true).await
+    };
+    match result {
         Ok(report) => {
             if let Some(break_point) = report.break_point {
                 log::warn!(
@@ -187,7 +268,7 @@ async fn validate_chain_before_mining(node: &Node) {
                 );
                 log::info!("   Auto-healing will sync correct blocks from peers");
             } else {
-                log::info!("✅ Chain integrity validated: {} blocks properly linked", report.valid_blocks);
+                log::info!("✅ Chain integrity validated: {} blocks properly linked from index {}", report.valid_blocks, report.checked_from);
             }
         }
         Err(e) => {
@@ -0,0 +1,286 @@
+//! Nomination policies for block production.
+//!
+//! Every mined block nominates a peer. By default the miner rotates through
+//! `miner_nominees` (or nominates itself), but operators can plug in a
+//! different policy: stake-weighted selection from the latest validator set,
+//! a nominee list read from a file, or an RPC callback to an external service.
+//! If a policy fails or has nothing to offer, the miner nominates itself.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use modal_datastore::models::ValidatorSet;
+use modal_datastore::DatastoreManager;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// Default timeout for RPC nomination callbacks
+const DEFAULT_RPC_TIMEOUT_MS: u64 = 2000;
+
+/// What a policy gets to see when choosing a nominee
+pub struct NominationContext<'a> {
+    /// Index of the block being produced
+    pub index: u64,
+    /// Our own peer id
+    pub peer_id: &'a str,
+    pub datastore: &'a Arc<Mutex<DatastoreManager>>,
+}
+
+/// Chooses which peer a produced block nominates
+#[async_trait]
+pub trait NominationPolicy: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Nominee for the block, or `None` to nominate ourselves
+    async fn nominate(&self, ctx: &NominationContext<'_>) -> Result<Option<String>>;
+}
+
+/// Pick `nominees[index % len]`
+fn rotate(index: u64, nominees: &[String]) -> Option<String> {
+    if nominees.is_empty() {
+        return None;
+    }
+    Some(nominees[(index % nominees.len() as u64) as usize].clone())
+}
+
+/// Rotates through a fixed list of nominees
+pub struct RoundRobin {
+    nominees: Vec<String>,
+}
+
+impl RoundRobin {
+    pub fn new(nominees: Vec<String>) -> Self {
+        Self { nominees }
+    }
+}
+
+#[async_trait]
+impl NominationPolicy for RoundRobin {
+    fn name(&self) -> &'static str {
+        "round_robin"
+    }
+
+    async fn nominate(&self, ctx: &NominationContext<'_>) -> Result<Option<String>> {
+        Ok(rotate(ctx.index, &self.nominees))
+    }
+}
+
+/// Deterministically pick a peer with probability proportional to its stake.
+///
+/// The draw is seeded by the block index so every miner using this policy
+/// agrees on the nominee for a given height.
+pub fn weighted_pick(index: u64, stakes: &[(String, u64)]) -> Option<String> {
+    let total: u128 = stakes.iter().map(|(_, stake)| *stake as u128).sum();
+    if total == 0 {
+        return None;
+    }
+    let digest = Sha256::digest(index.to_be_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    let mut target = u128::from_be_bytes(bytes) % total;
+    for (peer, stake) in stakes {
+        let stake = *stake as u128;
+        if target < stake {
+            return Some(peer.clone());
+        }
+        target -= stake;
+    }
+    None
+}
+
+/// Nominates active validators weighted by their stake in the latest validator set
+pub struct StakeWeighted;
+
+#[async_trait]
+impl NominationPolicy for StakeWeighted {
+    fn name(&self) -> &'static str {
+        "stake_weighted"
+    }
+
+    async fn nominate(&self, ctx: &NominationContext<'_>) -> Result<Option<String>> {
+        let validator_set = {
+            let mgr = ctx.datastore.lock().await;
+            ValidatorSet::find_latest_multi(&mgr).await?
+        };
+        let Some(validator_set) = validator_set else {
+            return Ok(None);
+        };
+        Ok(weighted_pick(ctx.index, &validator_set.get_active_validators_with_stakes()))
+    }
+}
+
+/// Parse a nominee file: either a JSON array of peer ids or one peer id per
+/// line (blank lines and `#` comments are ignored)
+pub fn parse_nominee_file(contents: &str) -> Result<Vec<String>> {
+    let trimmed = contents.trim_start();
+    if trimmed.starts_with('[') {
+        return serde_json::from_str(trimmed).context("Invalid JSON nominee list");
+    }
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Rotates through nominees read from a file, re-read for every block so the
+/// list can be edited while the miner runs
+pub struct ExternalFile {
+    path: PathBuf,
+}
+
+impl ExternalFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+#[async_trait]
+impl NominationPolicy for ExternalFile {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    async fn nominate(&self, ctx: &NominationContext<'_>) -> Result<Option<String>> {
+        let contents = tokio::fs::read_to_string(&self.path)
+            .await
+            .with_context(|| format!("Failed to read nominee file {}", self.path.display()))?;
+        Ok(rotate(ctx.index, &parse_nominee_file(&contents)?))
+    }
+}
+
+#[derive(Serialize)]
+struct RpcNominationRequest<'a> {
+    index: u64,
+    peer_id: &'a str,
+}
+
+#[derive(Deserialize)]
+struct RpcNominationResponse {
+    nominee: Option<String>,
+}
+
+/// Asks an external service for the nominee.
+///
+/// POSTs `{"index": .., "peer_id": ..}` and expects `{"nominee": "<peer id>"}`
+/// back; a null or missing nominee means nominate ourselves.
+pub struct RpcCallback {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl RpcCallback {
+    pub fn new(url: String, timeout: Duration) -> Result<Self> {
+        let client = crate::proxy::http_client_builder().timeout(timeout).build()?;
+        Ok(Self { url, client })
+    }
+}
+
+#[async_trait]
+impl NominationPolicy for RpcCallback {
+    fn name(&self) -> &'static str {
+        "rpc"
+    }
+
+    async fn nominate(&self, ctx: &NominationContext<'_>) -> Result<Option<String>> {
+        let response: RpcNominationResponse = self
+            .client
+            .post(&self.url)
+            .json(&RpcNominationRequest { index: ctx.index, peer_id: ctx.peer_id })
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(response.nominee.filter(|nominee| !nominee.is_empty()))
+    }
+}
+
+/// Nomination policy selection in the node config
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NominationPolicyConfig {
+    /// Rotate through `miner_nominees` (the default)
+    #[default]
+    RoundRobin,
+    StakeWeighted,
+    File { path: PathBuf },
+    Rpc { url: String, timeout_ms: Option<u64> },
+}
+
+impl NominationPolicyConfig {
+    pub fn build(&self, miner_nominees: Option<Vec<String>>) -> Result<SharedNominationPolicy> {
+        Ok(match self {
+            Self::RoundRobin => Arc::new(RoundRobin::new(miner_nominees.unwrap_or_default())),
+            Self::StakeWeighted => Arc::new(StakeWeighted),
+            Self::File { path } => Arc::new(ExternalFile::new(path.clone())),
+            Self::Rpc { url, timeout_ms } => {
+                if url.is_empty() {
+                    return Err(anyhow!("RPC nomination policy requires a url"));
+                }
+                let timeout = Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_RPC_TIMEOUT_MS));
+                Arc::new(RpcCallback::new(url.clone(), timeout)?)
+            }
+        })
+    }
+}
+
+/// Wrapper for sharing a policy between the mining loop and getwork server
+pub type SharedNominationPolicy = Arc<dyn NominationPolicy>;
+
+/// Nominee for block `index`, falling back to ourselves if the policy fails
+pub async fn nominee_for_index(
+    policy: &dyn NominationPolicy,
+    index: u64,
+    peer_id: &str,
+    datastore: &Arc<Mutex<DatastoreManager>>,
+) -> String {
+    let ctx = NominationContext { index, peer_id, datastore };
+    match policy.nominate(&ctx).await {
+        Ok(Some(nominee)) => nominee,
+        Ok(None) => peer_id.to_string(),
+        Err(e) => {
+            log::warn!("Nomination policy '{}' failed for block {}: {:?}; nominating self", policy.name(), index, e);
+            peer_id.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weighted_pick_is_deterministic_and_respects_stake() {
+        let stakes = vec![("heavy".to_string(), 900), ("light".to_string(), 100), ("none".to_string(), 0)];
+        assert_eq!(weighted_pick(7, &stakes), weighted_pick(7, &stakes));
+
+        let heavy = (0..1000).filter(|i| weighted_pick(*i, &stakes).as_deref() == Some("heavy")).count();
+        assert!(heavy > 800 && heavy < 980, "heavy picked {} times", heavy);
+        assert!((0..1000).all(|i| weighted_pick(i, &stakes).as_deref() != Some("none")));
+        assert_eq!(weighted_pick(0, &[("a".to_string(), 0)]), None);
+    }
+
+    #[test]
+    fn test_parse_nominee_file_formats() {
+        assert_eq!(parse_nominee_file(r#"["a", "b"]"#).unwrap(), vec!["a", "b"]);
+        assert_eq!(parse_nominee_file("# nominees\na\n\n  b  \n").unwrap(), vec!["a", "b"]);
+        assert!(parse_nominee_file("[not json").is_err());
+        assert_eq!(rotate(3, &["a".to_string(), "b".to_string()]), Some("b".to_string()));
+        assert_eq!(rotate(3, &[]), None);
+    }
+
+    #[test]
+    fn test_policy_config_parsing() {
+        let config: NominationPolicyConfig =
+            serde_json::from_str(r#"{"type": "rpc", "url": "http://127.0.0.1:9000/nominate"}"#).unwrap();
+        assert_eq!(config.build(None).unwrap().name(), "rpc");
+        let config: NominationPolicyConfig = serde_json::from_str(r#"{"type": "stake_weighted"}"#).unwrap();
+        assert_eq!(config.build(None).unwrap().name(), "stake_weighted");
+        assert_eq!(NominationPolicyConfig::default().build(None).unwrap().name(), "round_robin");
+    }
+}
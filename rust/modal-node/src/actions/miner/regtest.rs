@@ -0,0 +1,52 @@
+//! On-demand block production for regtest networks.
+//!
+//! Regtest chains aren't mined continuously. Blocks are produced on request
+//! at zero difficulty with fixed timestamps, and each block's miner number is
+//! its index, so the same sequence of requests always produces the same chain.
+
+use anyhow::Result;
+
+use crate::node::Node;
+use super::block_producer::{commits_for_index, is_regtest, load_mining_chain, publish_mined_block, uncles_for_index};
+use super::nomination::nominee_for_index;
+
+/// Mine `count` blocks on top of the local chain tip
+pub async fn mine_blocks(node: &Node, count: u64) -> Result<Vec<modal_miner::Block>> {
+    if !is_regtest(&node.datastore_manager).await {
+        anyhow::bail!("Blocks can only be mined on demand on a regtest network");
+    }
+
+    let peer_id = node.peerid.to_string();
+    let (mut chain, hash_func, _) = load_mining_chain(
+        &peer_id,
+        node.datastore_manager.clone(),
+        node.fork_config.clone(),
+        None,
+        node.miner_hash_func.clone(),
+        node.miner_hash_params.clone(),
+        None,
+    ).await?;
+    chain.miner = modal_miner::Miner::new(modal_miner::MinerConfig {
+        hash_func_name: Some(hash_func.leak()),
+        hash_registry: node.datastore_manager.lock().await.hash_registry().clone(),
+        ..Default::default()
+    });
+
+    let epoch_transition_tx = node.hybrid_consensus.then(|| node.epoch_transition_tx.clone());
+    let mut mined = Vec::new();
+    for _ in 0..count {
+        let index = chain.height() + 1;
+        let nominee = nominee_for_index(node.nomination_policy.as_ref(), index, &peer_id, &node.datastore_manager).await;
+        let uncles = uncles_for_index(&node.datastore_manager, index).await;
+        let commits = commits_for_index(&node.datastore_manager, index, &chain.config.commit_limits).await;
+        let data = modal_miner::BlockData::new(nominee, index)
+            .with_uncles(uncles)
+            .with_commits(commits);
+
+        let (block, _) = chain.mine_block_data_with_persistence(data).await?;
+        publish_mined_block(&block, &node.datastore_manager, &node.swarm, epoch_transition_tx.clone()).await;
+        mined.push(block);
+    }
+
+    Ok(mined)
+}
@@ -4,7 +4,6 @@
 //! common sync functionality from observer.
 
 use anyhow::Result;
-use libp2p::gossipsub::IdentTopic;
 use modal_datastore::models::MinerBlock;
 
 use crate::gossip;
@@ -31,11 +30,8 @@ pub async fn announce_chain_tip(node: &Node) -> Result<()> {
         log::info!("Announcing chain tip: block {} (index: {})", &block.hash[..16], block.index);
         
         let gossip_msg = gossip::miner::block::MinerBlockGossip::from_miner_block(&block);
-        let topic = IdentTopic::new(gossip::miner::block::TOPIC);
-        let json = serde_json::to_string(&gossip_msg)?;
         
-        let mut swarm_lock = node.swarm.lock().await;
-        match swarm_lock.behaviour_mut().gossipsub.publish(topic, json.as_bytes()) {
+        match gossip::wire::publish_sharded(&node.swarm, gossip::miner::block::TOPIC, block.epoch, &gossip_msg).await {
             Ok(_) => {
                 log::info!("✓ Announced our chain tip (block {}) to peers", block.index);
             }
@@ -2,7 +2,11 @@
 pub mod miner;
 pub mod noop;
 pub mod observer;
+pub mod roles;
+pub mod gateway;
+pub mod indexer;
 pub mod request;
+pub mod contract_sync;
 pub mod validator;
 pub mod server;
 pub mod sync_blocks;
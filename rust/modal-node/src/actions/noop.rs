@@ -10,6 +10,7 @@ pub async fn run(node: &mut Node) -> Result<()> {
 
     // Start status server and autoupgrade if configured
     node.start_status_server().await?;
+    node.start_explorer().await?;
     node.start_autoupgrade().await?;
 
     // Log periodic status messages
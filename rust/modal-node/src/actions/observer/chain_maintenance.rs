@@ -9,6 +9,7 @@
 //! that extend observer (miner, validator).
 
 use modal_datastore::models::MinerBlock;
+use modal_datastore::models::miner::{pruning, PruneStatus};
 use modal_datastore::DatastoreManager;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -22,11 +23,13 @@ use super::get_chain_tip_index;
 /// This task periodically:
 /// - Promotes pending blocks to active storage
 /// - Purges old blocks that are no longer needed
+/// - Prunes blocks below the latest checkpoint to headers, if `prune_keep_blocks` is set
 ///
 /// Any node maintaining chain state should run this task.
 pub fn start_promotion_task(
     datastore: Arc<Mutex<DatastoreManager>>,
     shutdown: Arc<AtomicBool>,
+    prune_keep_blocks: Option<u64>,
 ) {
     tokio::spawn(async move {
         log::info!("🗃️  Starting block promotion/purge background task");
@@ -62,11 +65,35 @@ pub fn start_promotion_task(
                     log::warn!("Block purge task failed: {}", e);
                 }
             }
+            
+            // Run pruning
+            if let Some(keep_blocks) = prune_keep_blocks {
+                let mgr_lock = datastore.lock().await;
+                if let Err(e) = run_pruning(&mgr_lock, keep_blocks).await {
+                    log::warn!("Block pruning task failed: {}", e);
+                }
+            }
         }
         log::info!("🗃️  Block promotion/purge background task stopped");
     });
 }
 
+/// Prune below the latest checkpoint, keeping `keep_blocks` recent blocks in full
+async fn run_pruning(mgr: &DatastoreManager, keep_blocks: u64) -> anyhow::Result<()> {
+    let Some(boundary) = pruning::prune_boundary_multi(mgr, keep_blocks).await? else {
+        return Ok(());
+    };
+    if PruneStatus::pruned_below_multi(mgr).await? >= Some(boundary) {
+        return Ok(());
+    }
+    let result = pruning::prune_below_multi(mgr, boundary).await?;
+    log::info!(
+        "✂️  Pruned chain below block {}: {} headers kept, {} orphans deleted, {} epochs summarized",
+        result.pruned_below, result.headers_pruned, result.orphans_deleted, result.epochs_summarized
+    );
+    Ok(())
+}
+
 /// Validate and cleanup local chain.
 ///
 /// This function:
@@ -143,16 +170,20 @@ pub async fn validate_and_cleanup_chain(
 
 /// Sync missing blocks from peers.
 ///
-/// Requests blocks from the first available bootstrapper to fill gaps
-/// in the local chain up to the target index.
+/// Requests blocks from the healthiest bootstrapper that can serve them
+/// to fill gaps in the local chain up to the target index.
 pub async fn sync_missing_blocks(
     datastore: &Arc<Mutex<DatastoreManager>>,
-    swarm: &Arc<Mutex<crate::swarm::NodeSwarm>>,
+    swarm: &crate::swarm_driver::SwarmHandle,
     bootstrappers: &[libp2p::Multiaddr],
-    reqres_response_txs: &Arc<Mutex<std::collections::HashMap<libp2p::request_response::OutboundRequestId, tokio::sync::oneshot::Sender<crate::reqres::Response>>>>,
     target_index: u64,
     update_tx: &tokio::sync::mpsc::UnboundedSender<u64>,
 ) {
+    let bootstrappers = &crate::bootstrapper_health::ranked_bootstrappers(datastore, bootstrappers).await;
+    
+    // Blocks dropped as damaged by fsck-storage leave gaps below the tip
+    sync_resync_ranges(datastore, swarm, bootstrappers).await;
+    
     // Determine blocks needed
     let first_index = get_chain_tip_index(datastore).await + 1;
     
@@ -164,24 +195,22 @@ pub async fn sync_missing_blocks(
     log::info!("Requesting blocks from {} to {} from peers", first_index, target_index);
     
     // Try to get blocks from peers
-    if let Some(peer_addr) = bootstrappers.first() {
+    if let Some(peer_addr) = bootstrappers.iter().find(|addr| can_sync_from(addr, first_index)) {
         match crate::sync::block_range::request_block_range(
             swarm,
             &peer_addr.to_string(),
             first_index,
             target_index,
-            reqres_response_txs,
         ).await {
             Ok(result) if !result.blocks.is_empty() => {
                 log::info!("✓ Received {} blocks from peer", result.blocks.len());
                 
-                // Save blocks
+                // Save the blocks that validate, up to the first that doesn't
+                let validated = crate::sync::validation::shared().validate(datastore, result.blocks).await;
                 {
                     let ds = datastore.lock().await;
-                    for block in &result.blocks {
-                        if let Err(e) = block.save_to_active(&ds).await {
-                            log::warn!("Failed to save block {}: {}", block.index, e);
-                        }
+                    if let Err(e) = MinerBlock::save_all_to_active(&ds, &validated.valid).await {
+                        log::warn!("Failed to save {} blocks: {}", validated.valid.len(), e);
                     }
                 }
                 
@@ -199,3 +228,62 @@ pub async fn sync_missing_blocks(
     }
 }
 
+/// Fetch the block ranges marked for re-sync (see `modal_datastore::fsck`)
+/// from the first of `bootstrappers` that can serve them, clearing each
+/// mark once its blocks are saved.
+async fn sync_resync_ranges(
+    datastore: &Arc<Mutex<DatastoreManager>>,
+    swarm: &crate::swarm_driver::SwarmHandle,
+    bootstrappers: &[libp2p::Multiaddr],
+) {
+    let ranges = match datastore.lock().await.resync_ranges() {
+        Ok(ranges) => ranges,
+        Err(e) => {
+            log::warn!("Failed to read re-sync ranges: {}", e);
+            return;
+        }
+    };
+    for range in ranges {
+        let Some(peer_addr) = bootstrappers.iter().find(|addr| can_sync_from(addr, range.start)) else {
+            log::warn!("No peer can serve blocks {} to {} for re-sync", range.start, range.end);
+            continue;
+        };
+        log::info!("Re-syncing damaged blocks {} to {} from peers", range.start, range.end);
+        match crate::sync::block_range::request_block_range(
+            swarm,
+            &peer_addr.to_string(),
+            range.start,
+            range.end,
+        ).await {
+            Ok(result) if !result.blocks.is_empty() => {
+                let validated = crate::sync::validation::shared().validate(datastore, result.blocks).await;
+                let ds = datastore.lock().await;
+                let mut saved = validated.rejected.is_none();
+                if let Err(e) = MinerBlock::save_all_to_active(&ds, &validated.valid).await {
+                    log::warn!("Failed to save {} blocks: {}", validated.valid.len(), e);
+                    saved = false;
+                }
+                if saved {
+                    if let Err(e) = ds.clear_resync_range(range) {
+                        log::warn!("Failed to clear re-sync range: {}", e);
+                    }
+                }
+            }
+            Ok(_) => {
+                log::warn!("No blocks received from peer for re-sync");
+            }
+            Err(e) => {
+                log::warn!("Failed to re-sync blocks: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Whether the peer at `addr` can serve blocks from `index`, going by its
+/// capabilities (see `crate::capabilities`)
+fn can_sync_from(addr: &libp2p::Multiaddr, index: u64) -> bool {
+    match addr.iter().last() {
+        Some(libp2p::multiaddr::Protocol::P2p(peer_id)) => crate::capabilities::can_sync_from(&peer_id, index),
+        _ => true,
+    }
+}
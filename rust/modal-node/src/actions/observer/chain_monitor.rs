@@ -22,7 +22,7 @@ pub fn start_chain_monitor(
     datastore: Arc<Mutex<DatastoreManager>>,
     starting_index: u64,
     node_type: &'static str,
-) {
+) -> tokio::task::JoinHandle<()> {
     let mut current_tip = starting_index;
     
     tokio::spawn(async move {
@@ -58,7 +58,7 @@ pub fn start_chain_monitor(
                 current_tip = latest_tip_index;
             }
         }
-    });
+    })
 }
 
 /// Get the current chain tip index from datastore
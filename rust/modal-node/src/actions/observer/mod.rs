@@ -8,7 +8,8 @@
 //! - Status server and networking
 //!
 //! Observer serves as the foundation for both miner and validator nodes,
-//! while also being functional as a standalone read-only node.
+//! while also being functional as a standalone read-only node. The shared
+//! base and switching between roles live in `actions::roles`.
 //!
 //! ## Node Type Hierarchy
 //!
@@ -31,7 +32,9 @@ pub use chain_monitor::{start_chain_monitor, get_chain_tip_index};
 pub use chain_maintenance::{start_promotion_task, validate_and_cleanup_chain, sync_missing_blocks};
 
 use anyhow::Result;
+use tokio::task::JoinHandle;
 
+use crate::actions::roles::{self, MiningUpdates, Role};
 use crate::gossip;
 use crate::node::Node;
 
@@ -44,8 +47,15 @@ use crate::node::Node;
 /// - Sync from peers on startup
 /// - Do NOT mine blocks or participate in consensus
 pub async fn run(node: &mut Node) -> Result<()> {
-    log::info!("Starting observer node");
-    
+    roles::run(node, Role::Observer).await
+}
+
+/// Answer peers' sync requests, returning the channel that carries the new
+/// chain tip after each sync. Its sender is also kept in `node.mining_update_tx`
+/// for the gossip handler.
+pub fn start_sync_handling(
+    node: &mut Node,
+) -> (tokio::sync::mpsc::UnboundedSender<u64>, tokio::sync::mpsc::UnboundedReceiver<u64>) {
     // Create a channel to receive mining chain updates
     let (mining_update_tx, mining_update_rx) = tokio::sync::mpsc::unbounded_channel::<u64>();
     
@@ -62,56 +72,71 @@ pub async fn run(node: &mut Node) -> Result<()> {
         node.datastore_manager.clone(),
         node.swarm.clone(),
         node.ignored_peers.clone(),
-        node.reqres_response_txs.clone(),
-        mining_update_tx,
+        mining_update_tx.clone(),
     );
     
-    // Subscribe to mining block gossip
-    gossip::add_miner_event_listeners(node).await?;
-    log::info!("Subscribed to mining block gossip");
-    
-    // Start status server
-    node.start_status_server().await?;
-    node.start_status_html_writer().await?;
-    
-    // Start networking
-    node.start_networking().await?;
-    
-    // Start autoupgrade if configured
-    node.start_autoupgrade().await?;
-    
+    (mining_update_tx, mining_update_rx)
+}
+
+/// Wait for peers and sync from them on startup if bootstrappers are configured
+pub async fn initial_sync(node: &mut Node) -> Result<()> {
     // Wait for connections to peers
+    let phase_started = std::time::Instant::now();
     node.wait_for_connections().await?;
+    node.boot_timer.record(crate::boot_profile::PHASE_PEER_CONNECTIONS, phase_started);
     
-    // Sync from peers on startup if bootstrappers are configured
     if !node.bootstrappers.is_empty() {
         log::info!("Syncing blockchain state from peers...");
+        let phase_started = std::time::Instant::now();
         match sync_from_peers(node).await {
             Ok(()) => log::info!("Initial sync completed"),
             Err(e) => log::warn!("Initial sync failed (will continue via gossip): {}", e),
         }
+        node.boot_timer.record(crate::boot_profile::PHASE_INITIAL_SYNC, phase_started);
     }
     
-    // Get the starting chain tip
+    Ok(())
+}
+
+/// Start a chain monitor from the current chain tip, labelled by `node_type`
+pub async fn start_chain_monitor_from_tip(
+    node: &Node,
+    updates: &MiningUpdates,
+    node_type: &'static str,
+) -> JoinHandle<()> {
     let starting_index = get_chain_tip_index(&node.datastore_manager).await;
     if starting_index > 0 {
-        log::info!("Starting chain observer at index {}", starting_index);
+        log::info!("Starting {} chain monitor at index {}", node_type, starting_index);
     } else {
-        log::info!("Starting chain observer with empty chain");
+        log::info!("Starting {} chain monitor with empty chain", node_type);
     }
     
-    // Start chain monitor task
     start_chain_monitor(
-        mining_update_rx,
+        updates.subscribe(),
         node.datastore_manager.clone(),
         starting_index,
-        "Observer",
-    );
-    
-    log::info!("Observer node running - observing mining chain");
-    
-    // Wait for shutdown signal
-    node.wait_for_shutdown().await?;
-    
-    Ok(())
+        node_type,
+    )
+}
+
+/// The observer role: follows contract gossip and monitors the chain
+pub struct ObserverComponent {
+    chain_monitor: JoinHandle<()>,
+}
+
+impl ObserverComponent {
+    pub async fn start(node: &mut Node, updates: &MiningUpdates) -> Result<Self> {
+        gossip::add_contract_event_listeners(node).await?;
+        let chain_monitor = start_chain_monitor_from_tip(node, updates, "Observer").await;
+        log::info!("Observing mining chain");
+        Ok(Self { chain_monitor })
+    }
+
+    pub fn signal_stop(&self) {
+        self.chain_monitor.abort();
+    }
+
+    pub async fn stopped(self) {
+        let _ = self.chain_monitor.await;
+    }
 }
@@ -19,7 +19,6 @@ use tokio::sync::Mutex;
 use crate::chain::fork_choice::{compare_chains, ForkChoiceResult};
 use crate::chain::metrics::calculate_cumulative_difficulty;
 use crate::node::{Node, IgnoredPeerInfo};
-use crate::reqres;
 
 /// Request chain info from a peer and perform sync if their chain has higher cumulative difficulty.
 ///
@@ -31,10 +30,9 @@ use crate::reqres;
 pub async fn request_chain_info_impl(
     peer_id: libp2p::PeerId,
     peer_addr: String,
-    swarm: Arc<Mutex<crate::swarm::NodeSwarm>>,
+    swarm: crate::swarm_driver::SwarmHandle,
     datastore: Arc<Mutex<DatastoreManager>>,
     ignored_peers: Arc<Mutex<std::collections::HashMap<libp2p::PeerId, IgnoredPeerInfo>>>,
-    reqres_response_txs: Arc<Mutex<std::collections::HashMap<libp2p::request_response::OutboundRequestId, tokio::sync::oneshot::Sender<reqres::Response>>>>,
 ) -> Result<()> {
     // Check if peer is ignored
     {
@@ -47,11 +45,17 @@ pub async fn request_chain_info_impl(
         }
     }
     
+    // Peers on an older message protocol may misread our requests
+    if !crate::capabilities::compatible(&peer_id) {
+        log::debug!("Peer {} speaks an unsupported protocol version, skipping chain info request", peer_id);
+        return Ok(());
+    }
+    
     log::info!("🔄 Syncing with peer {} using efficient find_ancestor", peer_id);
     
     // Find common ancestor
     let (common_ancestor, peer_chain_length, peer_cumulative_difficulty) = 
-        find_common_ancestor_efficient(&swarm, peer_addr.clone(), &datastore, &reqres_response_txs).await?;
+        find_common_ancestor_efficient(&swarm, peer_addr.clone(), &datastore).await?;
     
     // Determine blocks to request
     let from_index = match common_ancestor {
@@ -96,13 +100,16 @@ pub async fn request_chain_info_impl(
     
     log::info!("✅ Peer chain has higher cumulative difficulty - adopting it");
     
+    if !crate::capabilities::can_sync_from(&peer_id, from_index) {
+        anyhow::bail!("Peer {} has pruned the blocks from {} we need", peer_id, from_index);
+    }
+    
     // Request blocks from peer
     let all_blocks = request_blocks_from_peer(
         &swarm,
         &peer_addr,
         from_index,
         peer_chain_length,
-        &reqres_response_txs,
     ).await?;
     
     if all_blocks.is_empty() {
@@ -123,17 +130,15 @@ pub async fn request_chain_info_impl(
 
 /// Efficiently find the common ancestor between local and remote chains using binary search.
 pub async fn find_common_ancestor_efficient(
-    swarm: &Arc<Mutex<crate::swarm::NodeSwarm>>,
+    swarm: &crate::swarm_driver::SwarmHandle,
     peer_addr: String,
     datastore: &Arc<Mutex<DatastoreManager>>,
-    reqres_response_txs: &Arc<Mutex<std::collections::HashMap<libp2p::request_response::OutboundRequestId, tokio::sync::oneshot::Sender<reqres::Response>>>>,
 ) -> Result<(Option<u64>, u64, u128)> {
     // Delegate to the sync module implementation
     let result = crate::sync::common_ancestor::find_common_ancestor_efficient(
         swarm,
         peer_addr,
         datastore,
-        reqres_response_txs,
     ).await?;
     
     Ok((result.ancestor_index, result.remote_chain_length, result.remote_cumulative_difficulty))
@@ -141,32 +146,27 @@ pub async fn find_common_ancestor_efficient(
 
 /// Request blocks from a peer
 async fn request_blocks_from_peer(
-    swarm: &Arc<Mutex<crate::swarm::NodeSwarm>>,
+    swarm: &crate::swarm_driver::SwarmHandle,
     peer_addr: &str,
     from_index: u64,
     to_index: u64,
-    reqres_response_txs: &Arc<Mutex<std::collections::HashMap<libp2p::request_response::OutboundRequestId, tokio::sync::oneshot::Sender<reqres::Response>>>>,
 ) -> Result<Vec<MinerBlock>> {
     use crate::sync::block_range::request_all_blocks_in_range;
     
     log::info!("📥 Requesting blocks from index {} onwards from peer", from_index);
     
-    request_all_blocks_in_range(swarm, peer_addr, from_index, to_index, reqres_response_txs).await
+    request_all_blocks_in_range(swarm, peer_addr, from_index, to_index).await
 }
 
 /// Adopt blocks from peer after validation
 async fn adopt_peer_blocks(
     datastore: &Arc<Mutex<DatastoreManager>>,
-    mut all_blocks: Vec<MinerBlock>,
+    all_blocks: Vec<MinerBlock>,
     peer_cumulative_difficulty: u128,
     local_cumulative_difficulty: u128,
 ) -> Result<()> {
-    // Sort blocks
-    all_blocks.sort_by_key(|b| b.index);
-    
-    // Validate chain
-    use crate::chain::reorg::validate_block_chain;
-    validate_block_chain(&all_blocks)?;
+    // Validate chain, sorted by index
+    let all_blocks = crate::sync::validation::shared().validate(datastore, all_blocks).await.into_result()?;
     
     log::info!("✓ Peer chain validation passed");
     
@@ -197,9 +197,7 @@ async fn adopt_peer_blocks(
         }
         
         // Save peer blocks
-        for block in &all_blocks {
-            block.save_to_active(&ds).await?;
-        }
+        MinerBlock::save_all_to_active(&ds, &all_blocks).await?;
     }
     
     log::info!("🎉 Successfully adopted peer's chain with {} blocks!", all_blocks.len());
@@ -209,6 +207,12 @@ async fn adopt_peer_blocks(
 
 /// Sync blockchain state from peers on startup
 pub async fn sync_from_peers(node: &Node) -> Result<()> {
+    // Import finalized epochs from the network's archives before asking peers
+    let archive_urls = crate::sync::archive::archive_urls(&node.datastore_manager).await;
+    if !archive_urls.is_empty() {
+        crate::sync::archive::bootstrap_from_archives(&node.datastore_manager, &archive_urls).await?;
+    }
+    
     // Get our current chain state
     let (local_chain_length, local_cumulative_difficulty) = {
         let ds = node.datastore_manager.lock().await;
@@ -251,7 +255,6 @@ pub async fn sync_from_peers(node: &Node) -> Result<()> {
                 node.swarm.clone(),
                 node.datastore_manager.clone(),
                 node.ignored_peers.clone(),
-                node.reqres_response_txs.clone(),
             ).await {
                 Ok(()) => {
                     log::info!("Successfully synced from bootstrapper");
@@ -275,9 +278,8 @@ pub async fn sync_from_peers(node: &Node) -> Result<()> {
 pub async fn handle_sync_from_peer(
     peer_addr: String,
     datastore: Arc<Mutex<DatastoreManager>>,
-    swarm: Arc<Mutex<crate::swarm::NodeSwarm>>,
+    swarm: crate::swarm_driver::SwarmHandle,
     ignored_peers: Arc<Mutex<std::collections::HashMap<libp2p::PeerId, IgnoredPeerInfo>>>,
-    reqres_txs: Arc<Mutex<std::collections::HashMap<libp2p::request_response::OutboundRequestId, tokio::sync::oneshot::Sender<reqres::Response>>>>,
 ) -> Result<Option<u64>> {
     use libp2p::multiaddr::{Multiaddr, Protocol};
     
@@ -299,7 +301,6 @@ pub async fn handle_sync_from_peer(
         swarm,
         datastore.clone(),
         ignored_peers,
-        reqres_txs,
     ).await {
         Ok(()) => {
             // Get the new chain tip
@@ -318,9 +319,8 @@ pub async fn handle_sync_from_peer(
 pub fn start_sync_request_handler(
     mut sync_request_rx: tokio::sync::mpsc::UnboundedReceiver<(libp2p::PeerId, String)>,
     datastore: Arc<Mutex<DatastoreManager>>,
-    swarm: Arc<Mutex<crate::swarm::NodeSwarm>>,
+    swarm: crate::swarm_driver::SwarmHandle,
     ignored_peers: Arc<Mutex<std::collections::HashMap<libp2p::PeerId, IgnoredPeerInfo>>>,
-    reqres_txs: Arc<Mutex<std::collections::HashMap<libp2p::request_response::OutboundRequestId, tokio::sync::oneshot::Sender<reqres::Response>>>>,
     mining_update_tx: tokio::sync::mpsc::UnboundedSender<u64>,
 ) {
     let syncing_peers = Arc::new(Mutex::new(HashSet::<libp2p::PeerId>::new()));
@@ -343,7 +343,6 @@ pub fn start_sync_request_handler(
             let datastore_clone = datastore.clone();
             let swarm_clone = swarm.clone();
             let ignored_peers_clone = ignored_peers.clone();
-            let reqres_txs_clone = reqres_txs.clone();
             let syncing_peers_clone = syncing_peers.clone();
             let mining_update_tx_clone = mining_update_tx.clone();
             
@@ -353,7 +352,6 @@ pub fn start_sync_request_handler(
                     datastore_clone,
                     swarm_clone,
                     ignored_peers_clone,
-                    reqres_txs_clone,
                 ).await {
                     Ok(new_tip) => {
                         if let Some(tip) = new_tip {
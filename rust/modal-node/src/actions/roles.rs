@@ -0,0 +1,332 @@
+//! Node roles and switching between them at runtime.
+//!
+//! Observer, miner and validator nodes share a base: sync handling, block
+//! gossip, the status and explorer servers and networking. What each role
+//! adds on top is a component that can be started and stopped on a running
+//! node, so a node can change role without restarting:
+//!
+//! - when an admin peer asks over `/node/role` (`modal node set-role`)
+//! - when the chain reaches an epoch its `role_schedule` names
+//!
+//! Stopping the miner lets the block in progress finish; stopping the
+//! validator retires it from consensus after finalizing what it has.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::sync::Mutex;
+
+use modal_datastore::DatastoreManager;
+
+use crate::actions::{miner, observer, validator};
+use crate::constants::ROLE_SCHEDULE_CHECK_INTERVAL_SECS;
+use crate::gossip;
+use crate::node::Node;
+
+/// A role a running node can switch between
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Observer,
+    Miner,
+    Validator,
+}
+
+impl Role {
+    /// Name of the role as `Node::role` and the status pages show it
+    pub fn label(&self) -> &'static str {
+        match self {
+            Role::Observer => "Observer",
+            Role::Miner => "Miner",
+            Role::Validator => "Validator",
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Role::Observer => "observer",
+            Role::Miner => "miner",
+            Role::Validator => "validator",
+        })
+    }
+}
+
+impl FromStr for Role {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "observer" => Ok(Role::Observer),
+            "miner" => Ok(Role::Miner),
+            "validator" => Ok(Role::Validator),
+            _ => anyhow::bail!("Unknown role: '{}'. Valid roles: observer, miner, validator", s),
+        }
+    }
+}
+
+/// Role a node takes on once the chain reaches `from_epoch`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoleScheduleEntry {
+    pub from_epoch: u64,
+    pub role: Role,
+}
+
+/// The role `schedule` calls for at `epoch`, if any entry has started
+pub fn scheduled_role(schedule: &[RoleScheduleEntry], epoch: u64) -> Option<Role> {
+    schedule
+        .iter()
+        .filter(|entry| entry.from_epoch <= epoch)
+        .max_by_key(|entry| entry.from_epoch)
+        .map(|entry| entry.role)
+}
+
+/// Chain tip updates from sync and gossip, fanned out to whichever role
+/// components are listening
+#[derive(Clone)]
+pub struct MiningUpdates {
+    tx: mpsc::UnboundedSender<u64>,
+    subscribers: Arc<std::sync::Mutex<Vec<mpsc::UnboundedSender<u64>>>>,
+}
+
+impl MiningUpdates {
+    /// Forward the updates sent on `tx` and received on `rx` to subscribers
+    pub fn start(tx: mpsc::UnboundedSender<u64>, mut rx: mpsc::UnboundedReceiver<u64>) -> Self {
+        let subscribers: Arc<std::sync::Mutex<Vec<mpsc::UnboundedSender<u64>>>> = Arc::default();
+        let forward_to = subscribers.clone();
+        tokio::spawn(async move {
+            while let Some(index) = rx.recv().await {
+                let mut subscribers = forward_to.lock().unwrap_or_else(|e| e.into_inner());
+                // Components that stopped have dropped their receivers
+                subscribers.retain(|subscriber| subscriber.send(index).is_ok());
+            }
+        });
+        Self { tx, subscribers }
+    }
+
+    /// Sender for tasks that move the chain tip
+    pub fn sender(&self) -> mpsc::UnboundedSender<u64> {
+        self.tx.clone()
+    }
+
+    /// Receive the updates from now on
+    pub fn subscribe(&self) -> mpsc::UnboundedReceiver<u64> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.subscribers.lock().unwrap_or_else(|e| e.into_inner()).push(tx);
+        rx
+    }
+}
+
+/// The running part of a role
+pub enum RoleComponent {
+    Observer(observer::ObserverComponent),
+    Miner(miner::MinerComponent),
+    Validator(validator::ValidatorComponent),
+}
+
+impl RoleComponent {
+    /// Start `role` on a node whose base is running
+    pub async fn start(role: Role, node: &mut Node, updates: &MiningUpdates) -> Result<Self> {
+        Ok(match role {
+            Role::Observer => RoleComponent::Observer(observer::ObserverComponent::start(node, updates).await?),
+            Role::Miner => RoleComponent::Miner(miner::MinerComponent::start(node, updates).await?),
+            Role::Validator => RoleComponent::Validator(validator::ValidatorComponent::start(node, updates).await?),
+        })
+    }
+
+    pub fn role(&self) -> Role {
+        match self {
+            RoleComponent::Observer(_) => Role::Observer,
+            RoleComponent::Miner(_) => Role::Miner,
+            RoleComponent::Validator(_) => Role::Validator,
+        }
+    }
+
+    /// Tell the component's tasks to stop, without waiting for them
+    pub fn signal_stop(&self) {
+        match self {
+            RoleComponent::Observer(component) => component.signal_stop(),
+            RoleComponent::Miner(component) => component.signal_stop(),
+            RoleComponent::Validator(component) => component.signal_stop(),
+        }
+    }
+
+    /// Stop the component and wait for its tasks to finish
+    pub async fn stop(self) {
+        self.signal_stop();
+        match self {
+            RoleComponent::Observer(component) => component.stopped().await,
+            RoleComponent::Miner(component) => component.stopped().await,
+            RoleComponent::Validator(component) => component.stopped().await,
+        }
+    }
+}
+
+/// Run a node as `role`, switching roles as admin requests and the role
+/// schedule call for, until shutdown
+pub async fn run(node: &mut Node, role: Role) -> Result<()> {
+    log::info!("Starting {} node", role);
+
+    if role == Role::Miner {
+        // Validate and repair chain integrity before starting mining
+        let phase_started = std::time::Instant::now();
+        miner::validate_chain_before_mining(node).await;
+        node.boot_timer.record(crate::boot_profile::PHASE_CHAIN_INTEGRITY, phase_started);
+    }
+
+    // Miners sync more aggressively, from every bootstrapper
+    let (mining_update_tx, mining_update_rx) = match role {
+        Role::Miner => miner::start_orphan_sync(node),
+        Role::Observer | Role::Validator => observer::start_sync_handling(node),
+    };
+    let updates = MiningUpdates::start(mining_update_tx, mining_update_rx);
+
+    // Subscribe to mining block gossip
+    gossip::add_miner_event_listeners(node).await?;
+    log::info!("Subscribed to mining block gossip");
+
+    // Start services
+    node.start_status_server().await?;
+    node.start_explorer().await?;
+    node.start_status_html_writer().await?;
+    node.start_networking().await?;
+    node.start_autoupgrade().await?;
+
+    match role {
+        Role::Miner => miner::initial_sync(node).await?,
+        Role::Observer | Role::Validator => observer::initial_sync(node).await?,
+    }
+
+    let mut component = RoleComponent::start(role, node, &updates).await?;
+    log::info!("{} node running", role.label());
+    node.finish_boot().await;
+
+    let mut role_rx = node.take_role_requests().context("Role requests are already being handled")?;
+    let role_schedule = node.role_schedule.clone();
+    let mut scheduled = None;
+    let mut schedule_check = tokio::time::interval(std::time::Duration::from_secs(ROLE_SCHEDULE_CHECK_INTERVAL_SECS));
+
+    node.listen_for_ctrl_c();
+    let mut shutdown_rx = node.subscribe_shutdown();
+    loop {
+        let next = tokio::select! {
+            _ = shutdown_rx.recv() => break,
+            Some(next) = role_rx.recv() => {
+                log::info!("Admin request to run as {}", next);
+                next
+            }
+            _ = schedule_check.tick(), if !role_schedule.is_empty() => {
+                let epoch = current_epoch(&node.datastore_manager).await;
+                // Only a newly started entry switches, leaving admin requests in force
+                let Some(next) = scheduled_role(&role_schedule, epoch).filter(|due| Some(*due) != scheduled) else {
+                    continue;
+                };
+                scheduled = Some(next);
+                log::info!("Role schedule calls for {} at epoch {}", next, epoch);
+                next
+            }
+        };
+        if next == component.role() {
+            log::info!("Already running as {}", next);
+            continue;
+        }
+        component = switch_role(node, component, next, &updates).await?;
+    }
+
+    component.signal_stop();
+    node.finish_shutdown().await?;
+    log::info!("🛑 {} shutdown complete", component.role().label());
+    Ok(())
+}
+
+/// Stop `current` and start `next` in its place, falling back to observing
+/// if `next` fails to start
+async fn switch_role(
+    node: &mut Node,
+    current: RoleComponent,
+    next: Role,
+    updates: &MiningUpdates,
+) -> Result<RoleComponent> {
+    log::info!("🔀 Switching role: {} -> {}", current.role(), next);
+    current.stop().await;
+    node.mining_shutdown = None;
+
+    if next == Role::Miner {
+        miner::validate_chain_before_mining(node).await;
+    }
+    let component = match RoleComponent::start(next, node, updates).await {
+        Ok(component) => component,
+        Err(e) if next != Role::Observer => {
+            log::error!("Failed to start the {} role: {} - falling back to observer", next, e);
+            RoleComponent::start(Role::Observer, node, updates).await?
+        }
+        Err(e) => return Err(e),
+    };
+    node.role = component.role().label().to_string();
+    log::info!("🔀 Now running as {}", component.role());
+    Ok(component)
+}
+
+/// Ask the node at `target` to switch to `role`; this node must be one of
+/// its `admin_peers`
+pub async fn request_role(node: &mut Node, target: String, role: Role) -> Result<crate::reqres::Response> {
+    let data = serde_json::json!({ "role": role }).to_string();
+    crate::actions::request::run(node, target, crate::reqres::node::role::PATH.to_string(), data).await
+}
+
+/// Epoch of the chain tip
+async fn current_epoch(datastore: &Arc<Mutex<DatastoreManager>>) -> u64 {
+    let tip = observer::get_chain_tip_index(datastore).await;
+    datastore.lock().await.block_index_to_epoch(tip)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(from_epoch: u64, role: Role) -> RoleScheduleEntry {
+        RoleScheduleEntry { from_epoch, role }
+    }
+
+    #[test]
+    fn test_parse_roles() {
+        assert_eq!("Miner".parse::<Role>().unwrap(), Role::Miner);
+        assert_eq!(Role::Validator.to_string().parse::<Role>().unwrap(), Role::Validator);
+        assert!("gateway".parse::<Role>().is_err());
+
+        let entries: Vec<RoleScheduleEntry> =
+            serde_json::from_str(r#"[{"from_epoch": 10, "role": "validator"}]"#).unwrap();
+        assert_eq!(entries, vec![entry(10, Role::Validator)]);
+    }
+
+    #[test]
+    fn test_scheduled_role_is_latest_started_entry() {
+        let schedule = vec![entry(20, Role::Observer), entry(5, Role::Miner), entry(10, Role::Validator)];
+        assert_eq!(scheduled_role(&schedule, 4), None);
+        assert_eq!(scheduled_role(&schedule, 5), Some(Role::Miner));
+        assert_eq!(scheduled_role(&schedule, 19), Some(Role::Validator));
+        assert_eq!(scheduled_role(&schedule, 100), Some(Role::Observer));
+        assert_eq!(scheduled_role(&[], 100), None);
+    }
+
+    #[tokio::test]
+    async fn test_mining_updates_reach_current_subscribers() {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let updates = MiningUpdates::start(tx, rx);
+
+        let mut first = updates.subscribe();
+        let second = updates.subscribe();
+        updates.sender().send(7).unwrap();
+        assert_eq!(first.recv().await, Some(7));
+
+        // A stopped component's receiver is dropped and the rest still get updates
+        drop(second);
+        updates.sender().send(8).unwrap();
+        assert_eq!(first.recv().await, Some(8));
+        assert_eq!(updates.subscribers.lock().unwrap().len(), 1);
+    }
+}
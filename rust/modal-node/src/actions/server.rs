@@ -7,6 +7,7 @@ pub async fn run(node: &mut Node) -> Result<()> {
     gossip::add_validator_event_listeners(node).await?;
 
     node.start_status_server().await?;
+    node.start_explorer().await?;
     node.start_status_html_writer().await?;
     node.start_networking().await?;
     node.start_autoupgrade().await?;
@@ -78,6 +78,7 @@ async fn persist_blocks(
         // Deserialize JSON to MinerBlock
         let block: MinerBlock = serde_json::from_value(block_json.clone())
             .map_err(|e| anyhow::anyhow!("Failed to deserialize block: {}", e))?;
+        block.validate_fields()?;
 
         // Check if block already exists
         match MinerBlock::find_by_hash_multi(&mgr, &block.hash).await {
@@ -5,6 +5,8 @@
 //! - Generating acks for valid incoming blocks
 //! - Collecting acks for our own blocks
 //! - Forming certificates when 2f+1 acks are received
+//!
+//! Acks for our blocks are counted with a [`Quorum`] per block.
 
 use anyhow::Result;
 use modal_common::keypair::Keypair;
@@ -15,17 +17,19 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use super::quorum::{bft_threshold, Quorum, Vote};
+
 /// Tracks acks received for blocks in a given round
 pub struct AckCollector {
     /// Our peer ID
     pub peer_id: String,
     /// Keypair for signing acks
     pub keypair: Keypair,
-    /// Committee size (total number of validators)
+    /// Committee size (total number of validators); change it with `set_committee_size`
     pub committee_size: usize,
     /// Map of (round, peer_id) -> collected acks
     /// Each entry maps the block author's peer_id to the acks we've received for their block
-    pub pending_acks: HashMap<(u64, String), Vec<Ack>>,
+    pub pending_acks: HashMap<(u64, String), Quorum<Ack>>,
     /// Blocks we're waiting for acks on (our own blocks)
     pub our_pending_blocks: HashMap<u64, ValidatorBlock>,
     /// Blocks we've received from other validators that need acks
@@ -50,8 +54,16 @@ impl AckCollector {
 
     /// Calculate the BFT threshold (2f+1 where f = floor((n-1)/3))
     pub fn threshold(&self) -> usize {
-        let f = (self.committee_size - 1) / 3;
-        2 * f + 1
+        bft_threshold(self.committee_size)
+    }
+
+    /// Change the committee size, for the acks still being collected too
+    pub fn set_committee_size(&mut self, committee_size: usize) {
+        self.committee_size = committee_size;
+        let needed = self.threshold() as u64;
+        for quorum in self.pending_acks.values_mut() {
+            quorum.set_needed(needed);
+        }
     }
 
     /// Register a block we created and want to collect acks for
@@ -97,6 +109,18 @@ impl AckCollector {
         Ok(Some(ack))
     }
 
+    /// The draft we already have from `block`'s author for its round, if
+    /// `block` is a different, validly signed one: the author equivocated
+    pub fn conflicting_draft(&self, block: &ValidatorBlock) -> Result<Option<&ValidatorBlock>> {
+        let Some(earlier) = self.incoming_blocks.get(&(block.round_id, block.peer_id.clone())) else {
+            return Ok(None);
+        };
+        if earlier.closing_sig == block.closing_sig || !block.validate_sigs()? {
+            return Ok(None);
+        }
+        Ok(Some(earlier))
+    }
+
     /// Handle an incoming ack for one of our blocks
     /// Returns true if we now have enough acks to form a certificate
     pub fn handle_incoming_ack(&mut self, ack: &Ack) -> Result<bool> {
@@ -117,18 +141,18 @@ impl AckCollector {
             return Ok(false);
         }
 
-        // Add to pending acks
-        let acks = self.pending_acks.entry(key.clone()).or_default();
-
-        // Check if we already have an ack from this validator
-        if acks.iter().any(|a| a.acker == ack.acker) {
+        // Add to pending acks, once per validator
+        let committee_size = self.committee_size;
+        let acks = self
+            .pending_acks
+            .entry(key)
+            .or_insert_with(|| Quorum::bft(committee_size));
+        if acks.add(&ack.acker, ack.clone()) != Vote::Counted {
             return Ok(false);
         }
 
-        acks.push(ack.clone());
-
         // Check if we have enough acks
-        Ok(acks.len() >= self.threshold())
+        Ok(acks.is_reached())
     }
 
     /// Get our block for a round if it exists
@@ -144,7 +168,7 @@ impl AckCollector {
     pub fn get_acks(&self, round: u64, peer_id: &str) -> Vec<Ack> {
         self.pending_acks
             .get(&(round, peer_id.to_string()))
-            .cloned()
+            .map(|acks| acks.votes().map(|(_, ack)| ack.clone()).collect())
             .unwrap_or_default()
     }
 
@@ -154,7 +178,7 @@ impl AckCollector {
         let key = (round, self.peer_id.clone());
         let acks = self.pending_acks.get(&key)?;
 
-        if acks.len() < self.threshold() {
+        if !acks.is_reached() {
             return None;
         }
 
@@ -162,13 +186,13 @@ impl AckCollector {
         let block = self.our_pending_blocks.get_mut(&round)?;
 
         // Add acks to the block
-        for ack in acks {
+        for (_, ack) in acks.votes() {
             block.acks.insert(ack.acker.clone(), ack.acker_sig.clone());
         }
 
         // Generate certificate by combining ack signatures
         // The certificate is a JSON-encoded list of acker signatures
-        let cert_data: Vec<&str> = acks.iter().map(|a| a.acker_sig.as_str()).collect();
+        let cert_data: Vec<&str> = acks.votes().map(|(_, a)| a.acker_sig.as_str()).collect();
         let cert = serde_json::to_string(&cert_data).ok()?;
         block.cert = Some(cert);
 
@@ -203,23 +227,23 @@ fn validate_ack(ack: &Ack) -> Result<bool> {
         "peer_id": ack.peer_id,
         "round_id": ack.round_id,
         "closing_sig": ack.closing_sig,
+        "acker": ack.acker,
     });
     
     // Verify the signature
     acker_keypair.verify_json(&ack.acker_sig, &facts)
 }
 
-/// Validate a certificate by checking that it has enough valid ack signatures
-pub fn validate_certificate(block: &ValidatorBlock, committee_size: usize) -> Result<bool> {
+/// Validate a certificate by checking that it has enough valid ack signatures.
+/// The block's own signatures and every ack are verified in one batch.
+pub async fn validate_certificate(block: &ValidatorBlock, committee_size: usize) -> Result<bool> {
     // Check that the block has a certificate
     let cert = match &block.cert {
         Some(c) => c,
         None => return Ok(false),
     };
     
-    // Calculate threshold
-    let f = (committee_size - 1) / 3;
-    let threshold = 2 * f + 1;
+    let threshold = bft_threshold(committee_size);
     
     // Validate that we have enough acks
     if block.acks.len() < threshold {
@@ -231,30 +255,27 @@ pub fn validate_certificate(block: &ValidatorBlock, committee_size: usize) -> Re
         return Ok(false);
     }
     
+    if block.closing_sig.is_none() {
+        anyhow::bail!("Block missing closing signature");
+    }
+    let mut checks = block.sig_checks()?;
+    let (ackers, ack_checks): (Vec<String>, Vec<_>) = block.ack_checks().into_iter().unzip();
+    checks.extend(ack_checks);
+    let valid = modal_common::batch_verify::shared().verify(checks).await;
+    
     // Validate the block's own signatures
-    if !block.validate_sigs()? {
+    if !valid[0] || !valid[1] {
         log::warn!("Certificate validation failed: invalid block signatures");
         return Ok(false);
     }
     
-    // Validate each ack signature
+    // Count the valid ack signatures
     let mut valid_acks = 0;
-    let closing_sig = block.closing_sig.as_ref()
-        .ok_or_else(|| anyhow::anyhow!("Block missing closing signature"))?;
-        
-    for (acker, acker_sig) in &block.acks {
-        let ack = Ack {
-            peer_id: block.peer_id.clone(),
-            round_id: block.round_id,
-            closing_sig: closing_sig.clone(),
-            acker: acker.clone(),
-            acker_sig: acker_sig.clone(),
-        };
-        
-        match validate_ack(&ack) {
-            Ok(true) => valid_acks += 1,
-            Ok(false) => log::warn!("Invalid ack from {}", &acker[..16.min(acker.len())]),
-            Err(e) => log::warn!("Error validating ack from {}: {}", &acker[..16.min(acker.len())], e),
+    for (acker, valid) in ackers.iter().zip(&valid[2..]) {
+        if *valid {
+            valid_acks += 1;
+        } else {
+            log::warn!("Invalid ack from {}", &acker[..16.min(acker.len())]);
         }
     }
     
@@ -431,6 +452,57 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[test]
+    fn test_conflicting_draft() {
+        let our_keypair = create_test_keypair();
+        let mut collector = AckCollector::new(our_keypair.as_public_address(), our_keypair.clone(), 4);
+        let other_keypair = create_test_keypair();
+        let other_peer_id = other_keypair.as_public_address();
+
+        let block = create_test_block(&other_peer_id, 1, &other_keypair);
+        assert!(collector.conflicting_draft(&block).unwrap().is_none());
+        collector.handle_incoming_block(&block).unwrap();
+        // The same draft again is no offense
+        assert!(collector.conflicting_draft(&block).unwrap().is_none());
+
+        let mut other = block.clone();
+        other.add_event(serde_json::json!({"type": "extra"}));
+        other.generate_sigs(&other_keypair).unwrap();
+        let earlier = collector.conflicting_draft(&other).unwrap().unwrap();
+        assert_eq!(earlier.closing_sig, block.closing_sig);
+
+        // Nor is a draft the author didn't sign
+        let mut forged = other.clone();
+        forged.generate_sigs(&create_test_keypair()).unwrap();
+        assert!(collector.conflicting_draft(&forged).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_certificate_after_quorum_of_acks() {
+        let keypair = create_test_keypair();
+        let peer_id = keypair.as_public_address();
+        let mut collector = AckCollector::new(peer_id.clone(), keypair.clone(), 4);
+        let block = create_test_block(&peer_id, 1, &keypair);
+        collector.register_our_block(block.clone());
+
+        let ackers: Vec<Keypair> = (0..3).map(|_| create_test_keypair()).collect();
+        let first = block.generate_ack(&ackers[0]).unwrap();
+        assert!(!collector.handle_incoming_ack(&first).unwrap());
+        assert!(!collector.handle_incoming_ack(&first).unwrap());
+        assert!(!collector.handle_incoming_ack(&block.generate_ack(&ackers[1]).unwrap()).unwrap());
+        assert!(collector.form_certificate(1).is_none());
+
+        // Shrinking the committee lowers the threshold of acks already collected
+        collector.set_committee_size(3);
+        let certified = collector.form_certificate(1).unwrap();
+        assert_eq!(certified.acks.len(), 2);
+        assert!(certified.cert.is_some());
+        assert_eq!(collector.get_acks(1, &peer_id).len(), 2);
+
+        collector.set_committee_size(4);
+        assert!(collector.handle_incoming_ack(&block.generate_ack(&ackers[2]).unwrap()).unwrap());
+    }
+
     #[test]
     fn test_cleanup_round() {
         let keypair = create_test_keypair();
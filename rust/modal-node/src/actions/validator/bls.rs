@@ -0,0 +1,76 @@
+//! BLS keys validators sign certificate votes with.
+//!
+//! The network config (or genesis contract) registers each validator's BLS
+//! public key with a proof of possession. Once every member of a committee
+//! has one, its certificates carry an aggregate of the voters' signatures
+//! instead of a placeholder.
+
+use anyhow::Result;
+use modal_datastore::DatastoreManager;
+use modal_validator::ShoalValidatorConfig;
+use std::path::Path;
+
+#[cfg(feature = "bls")]
+pub use modal_common::bls::BlsKeypair;
+
+/// Stand-in for builds without the `bls` feature, which never have a BLS key
+#[cfg(not(feature = "bls"))]
+#[derive(Debug, Clone)]
+pub enum BlsKeypair {}
+
+/// Load the BLS passfile at `path`, if one is configured
+#[cfg(feature = "bls")]
+pub fn load_keypair(path: Option<&Path>) -> Result<Option<BlsKeypair>> {
+    let Some(path) = path else {
+        return Ok(None);
+    };
+    let path_str = path.to_str()
+        .ok_or_else(|| anyhow::anyhow!("Invalid BLS passfile path: {}", path.display()))?;
+    let keypair = BlsKeypair::from_json_file(path_str)
+        .map_err(|e| anyhow::anyhow!("Failed to load BLS passfile from {}: {}", path.display(), e))?;
+    log::info!("🔏 BLS public key: {}", keypair.public_key_hex());
+    Ok(Some(keypair))
+}
+
+#[cfg(not(feature = "bls"))]
+pub fn load_keypair(path: Option<&Path>) -> Result<Option<BlsKeypair>> {
+    if path.is_some() {
+        anyhow::bail!("bls_passfile_path is set, but this build lacks the bls feature");
+    }
+    Ok(None)
+}
+
+/// Register the validators' stored BLS keys with the config's committee,
+/// along with the keypair this node signs its votes with
+#[cfg(feature = "bls")]
+pub async fn with_bls_keys(
+    config: ShoalValidatorConfig,
+    datastore: &DatastoreManager,
+    keypair: Option<BlsKeypair>,
+) -> Result<ShoalValidatorConfig> {
+    let mut keys = Vec::new();
+    for (peer_id, key) in datastore.get_validator_bls_keys().await? {
+        let validator = peer_id.parse()
+            .map_err(|e| anyhow::anyhow!("invalid peer ID '{}' for a BLS key: {}", peer_id, e))?;
+        keys.push((validator, hex::decode(&key.public_key)?, hex::decode(&key.proof_of_possession)?));
+    }
+    let config = config.with_bls_keys(keypair, keys)?;
+    if config.committee.uses_bls() {
+        log::info!("🔏 Committee signs certificates with BLS keys");
+    }
+    Ok(config)
+}
+
+/// Builds without the `bls` feature can't sign or check BLS votes, so their
+/// certificates keep placeholder signatures
+#[cfg(not(feature = "bls"))]
+pub async fn with_bls_keys(
+    config: ShoalValidatorConfig,
+    datastore: &DatastoreManager,
+    _keypair: Option<BlsKeypair>,
+) -> Result<ShoalValidatorConfig> {
+    if !datastore.get_validator_bls_keys().await?.is_empty() {
+        log::warn!("Network registers validator BLS keys, but this build lacks the bls feature");
+    }
+    Ok(config)
+}
@@ -6,7 +6,8 @@
 //! - Consensus: Checkpoints are created when a new validator set's second certified round completes
 
 use anyhow::Result;
-use modal_datastore::models::miner::MinerCheckpoint;
+use modal_common::eras::EraSchedule;
+use modal_datastore::models::miner::{MinerBranch, MinerCheckpoint};
 use modal_datastore::models::MinerBlock;
 use modal_datastore::DatastoreManager;
 use modal_networks::{CheckpointMode, NetworkInfo};
@@ -24,8 +25,8 @@ pub struct CheckpointTracker {
     /// Checkpoint mode from network configuration
     pub checkpoint_mode: CheckpointMode,
     
-    /// Blocks per epoch (for epoch calculations)
-    pub blocks_per_epoch: u64,
+    /// Epoch lengths by height (for epoch calculations)
+    pub era_schedule: EraSchedule,
     
     /// Whether checkpoint has been created for current validator epoch
     pub checkpoint_created: bool,
@@ -33,19 +34,19 @@ pub struct CheckpointTracker {
 
 impl CheckpointTracker {
     /// Create a new checkpoint tracker
-    pub fn new(checkpoint_mode: CheckpointMode, blocks_per_epoch: u64) -> Self {
+    pub fn new(checkpoint_mode: CheckpointMode, era_schedule: EraSchedule) -> Self {
         Self {
             current_validator_epoch: 0,
             certified_rounds_in_epoch: 0,
             checkpoint_mode,
-            blocks_per_epoch,
+            era_schedule,
             checkpoint_created: false,
         }
     }
     
     /// Create a tracker from network info
-    pub fn from_network_info(network_info: &NetworkInfo, blocks_per_epoch: u64) -> Self {
-        Self::new(network_info.get_checkpoint_mode(), blocks_per_epoch)
+    pub fn from_network_info(network_info: &NetworkInfo, era_schedule: EraSchedule) -> Self {
+        Self::new(network_info.get_checkpoint_mode(), era_schedule)
     }
     
     /// Called when the validator set epoch changes
@@ -88,6 +89,12 @@ impl CheckpointTracker {
         false
     }
     
+    /// Called when a block marking a consensus stall is certified; rounds
+    /// certified before the stall don't count towards the checkpoint
+    pub fn on_stall(&mut self) {
+        self.certified_rounds_in_epoch = 0;
+    }
+    
     /// Get the selection epoch for the current validator set
     /// In hybrid consensus, validators for epoch N are selected from epoch N-2
     pub fn get_selection_epoch(&self) -> Option<u64> {
@@ -105,7 +112,6 @@ pub async fn create_checkpoint_for_epoch(
     selection_epoch: u64,
     validator_set_epoch: u64,
     validator_round: u64,
-    _blocks_per_epoch: u64,
 ) -> Result<MinerCheckpoint> {
     let mgr = datastore.lock().await;
     
@@ -145,6 +151,12 @@ pub async fn create_checkpoint_for_epoch(
     // Save the checkpoint
     checkpoint.save_to_canon(&mgr).await?;
     
+    // Branches forking below the checkpoint can no longer be adopted
+    let pruned = MinerBranch::prune_forking_before_multi(&mgr, last_block.index).await?;
+    if pruned > 0 {
+        log::info!("Stopped tracking {} fork branches below the checkpoint", pruned);
+    }
+    
     log::info!(
         "🏁 Created checkpoint for epoch {} (validator epoch {}): {} blocks, last block index {}, merkle root {}",
         selection_epoch,
@@ -161,7 +173,6 @@ pub async fn create_checkpoint_for_epoch(
 pub async fn load_manual_checkpoints(
     datastore: &Arc<Mutex<DatastoreManager>>,
     network_info: &NetworkInfo,
-    blocks_per_epoch: u64,
 ) -> Result<usize> {
     if network_info.get_checkpoint_mode() != CheckpointMode::Manual {
         return Ok(0);
@@ -177,7 +188,7 @@ pub async fn load_manual_checkpoints(
     let mut loaded = 0;
     
     for manual in manual_checkpoints {
-        let epoch = manual.block_index / blocks_per_epoch;
+        let epoch = mgr.block_index_to_epoch(manual.block_index);
         
         // Check if checkpoint already exists
         if MinerCheckpoint::find_by_epoch_multi(&mgr, epoch).await?.is_some() {
@@ -230,7 +241,6 @@ pub async fn load_manual_checkpoints(
 pub async fn ensure_checkpoints_initialized(
     datastore: &Arc<Mutex<DatastoreManager>>,
     network_info: Option<&NetworkInfo>,
-    blocks_per_epoch: u64,
 ) -> Result<()> {
     let checkpoint_mode = network_info
         .map(|n| n.get_checkpoint_mode())
@@ -242,7 +252,7 @@ pub async fn ensure_checkpoints_initialized(
         }
         CheckpointMode::Manual => {
             if let Some(network) = network_info {
-                let count = load_manual_checkpoints(datastore, network, blocks_per_epoch).await?;
+                let count = load_manual_checkpoints(datastore, network).await?;
                 log::info!("Loaded {} manual checkpoints", count);
             }
         }
@@ -260,7 +270,7 @@ mod tests {
 
     #[test]
     fn test_checkpoint_tracker_none_mode() {
-        let mut tracker = CheckpointTracker::new(CheckpointMode::None, 100);
+        let mut tracker = CheckpointTracker::new(CheckpointMode::None, EraSchedule::fixed(100, 60));
         tracker.on_epoch_change(5);
         assert!(!tracker.on_round_certified(1));
         assert!(!tracker.on_round_certified(2));
@@ -268,7 +278,7 @@ mod tests {
 
     #[test]
     fn test_checkpoint_tracker_consensus_mode() {
-        let mut tracker = CheckpointTracker::new(CheckpointMode::Consensus, 100);
+        let mut tracker = CheckpointTracker::new(CheckpointMode::Consensus, EraSchedule::fixed(100, 60));
         tracker.on_epoch_change(5);
         
         // First certified round
@@ -283,7 +293,7 @@ mod tests {
 
     #[test]
     fn test_checkpoint_tracker_epoch_change() {
-        let mut tracker = CheckpointTracker::new(CheckpointMode::Consensus, 100);
+        let mut tracker = CheckpointTracker::new(CheckpointMode::Consensus, EraSchedule::fixed(100, 60));
         
         // First epoch
         tracker.on_epoch_change(5);
@@ -298,7 +308,7 @@ mod tests {
 
     #[test]
     fn test_get_selection_epoch() {
-        let mut tracker = CheckpointTracker::new(CheckpointMode::Consensus, 100);
+        let mut tracker = CheckpointTracker::new(CheckpointMode::Consensus, EraSchedule::fixed(100, 60));
         
         tracker.on_epoch_change(0);
         assert_eq!(tracker.get_selection_epoch(), None);
@@ -312,5 +322,15 @@ mod tests {
         tracker.on_epoch_change(5);
         assert_eq!(tracker.get_selection_epoch(), Some(3));
     }
+    
+    #[test]
+    fn test_rounds_before_a_stall_dont_count() {
+        let mut tracker = CheckpointTracker::new(CheckpointMode::Consensus, EraSchedule::fixed(100, 60));
+        tracker.on_epoch_change(5);
+        assert!(!tracker.on_round_certified(1));
+        
+        tracker.on_stall();
+        assert!(!tracker.on_round_certified(30));
+        assert!(tracker.on_round_certified(31));
+    }
 }
-
@@ -5,19 +5,32 @@
 
 use anyhow::Result;
 use modal_common::keypair::Keypair;
+use modal_common::eras::EraSchedule;
 use modal_datastore::models::ValidatorBlock;
-use modal_datastore::DatastoreManager;
+use modal_datastore::models::miner::{CheckpointCertificate, MinerCheckpoint};
+use modal_common::slashing::EquivocationEvidence;
+use modal_datastore::{DatastoreManager, JournalEventKind};
 use modal_networks::CheckpointMode;
 use modal_validator_consensus::communication::{Communication, Message as ConsensusMessage};
+use modal_validator_consensus::narwhal::RoundTimer;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::{mpsc, Mutex};
 
+use crate::bootstrapper_health::unix_now;
 use crate::consensus::node_communication::NodeCommunication;
-use crate::swarm::NodeSwarm;
+use crate::constants::{CONSENSUS_LIVENESS_RECORD_ROUNDS, CONSENSUS_STALL_ROUNDS};
+use crate::mempool::SharedMempool;
+use crate::swarm_driver::SwarmHandle;
 
+use super::bls::BlsKeypair;
 use super::ack_collector::{AckCollector, save_certified_block, validate_certificate, run_finalization_task};
 use super::checkpoint::{CheckpointTracker, create_checkpoint_for_epoch};
+use super::liveness::{LivenessMonitor, Transition};
+use super::reconfiguration::{
+    load_state_handoff, ConsensusHandle, Reconfiguration, StateHandoff, ValidatorSetChange,
+};
 
 /// Start static validator consensus for a node that is in the static validators list.
 pub async fn start_static_validator_consensus(
@@ -25,9 +38,12 @@ pub async fn start_static_validator_consensus(
     validators: &[String],
     datastore: &Arc<Mutex<DatastoreManager>>,
     keypair: Keypair,
-    swarm: Arc<Mutex<NodeSwarm>>,
+    bls_keypair: Option<BlsKeypair>,
+    mempool: SharedMempool,
+    swarm: SwarmHandle,
     consensus_tx: mpsc::Sender<ConsensusMessage>,
-) {
+    handoff: Option<StateHandoff>,
+) -> Option<ConsensusHandle> {
     // Find our index in the validator list
     let my_index = validators.iter()
         .position(|v| v == node_peer_id_str)
@@ -41,31 +57,126 @@ pub async fn start_static_validator_consensus(
         my_index,
         datastore.clone(),
         keypair,
+        bls_keypair,
+        mempool,
         swarm,
         consensus_tx,
+        handoff,
     ).await {
-        Ok(()) => log::info!("✅ Static validator consensus started"),
-        Err(e) => log::error!("Failed to start static validator consensus: {}", e),
+        Ok(handle) => {
+            log::info!("✅ Static validator consensus started");
+            Some(handle)
+        }
+        Err(e) => {
+            log::error!("Failed to start static validator consensus: {}", e);
+            None
+        }
     }
 }
 
+/// Start the static validators monitor.
+///
+/// This spawns a background task that polls the static validators list and
+/// proposes changes to the running consensus loop, or starts consensus from
+/// the state handoff once a committed reconfiguration seats this node.
+/// Stopping the task retires the loop.
+pub fn start_static_validators_monitor(
+    node_peer_id: String,
+    mut validators: Vec<String>,
+    datastore: Arc<Mutex<DatastoreManager>>,
+    keypair: Keypair,
+    bls_keypair: Option<BlsKeypair>,
+    mempool: SharedMempool,
+    swarm: SwarmHandle,
+    consensus_tx: mpsc::Sender<ConsensusMessage>,
+    mut consensus: Option<ConsensusHandle>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(
+            crate::constants::STATIC_VALIDATORS_CHECK_INTERVAL_SECS,
+        ));
+        interval.tick().await; // Skip first immediate tick
+
+        loop {
+            interval.tick().await;
+
+            let latest = {
+                let ds = datastore.lock().await;
+                match ds.get_static_validators().await {
+                    Ok(latest) => latest.unwrap_or_default(),
+                    Err(e) => {
+                        log::warn!("Failed to read static validators: {}", e);
+                        continue;
+                    }
+                }
+            };
+
+            if let Some(handle) = consensus.as_ref().filter(|handle| handle.is_running()) {
+                if latest != validators {
+                    log::info!("🔁 Static validators changed ({} validators), proposing them to the committee", latest.len());
+                    validators = latest;
+                    handle.reconfigure(ValidatorSetChange {
+                        epoch: None,
+                        validators: validators.clone(),
+                        stakes: Vec::new(),
+                    });
+                }
+            } else if latest.contains(&node_peer_id) {
+                let handoff = {
+                    let ds = datastore.lock().await;
+                    load_state_handoff(&ds, &node_peer_id).await
+                };
+                let handoff = match handoff {
+                    Ok(handoff) => handoff,
+                    Err(e) => {
+                        log::debug!("Not joining the static validators yet: {}", e);
+                        continue;
+                    }
+                };
+                log::info!("🏛️  This node joined the static validators - starting Shoal consensus");
+                validators = match &handoff {
+                    Some(handoff) => handoff.validator_ids(),
+                    None => latest,
+                };
+                consensus = start_static_validator_consensus(
+                    &node_peer_id,
+                    &validators,
+                    &datastore,
+                    keypair.clone(),
+                    bls_keypair.clone(),
+                    mempool.clone(),
+                    swarm.clone(),
+                    consensus_tx.clone(),
+                    handoff,
+                ).await;
+            }
+        }
+    })
+}
+
 /// Create and start a Shoal validator for consensus participation.
 pub async fn create_and_start_shoal_validator(
     validators: Vec<String>,
     my_index: usize,
     datastore: Arc<Mutex<DatastoreManager>>,
     keypair: Keypair,
-    swarm: Arc<Mutex<NodeSwarm>>,
+    bls_keypair: Option<BlsKeypair>,
+    mempool: SharedMempool,
+    swarm: SwarmHandle,
     consensus_tx: mpsc::Sender<ConsensusMessage>,
-) -> Result<()> {
+    handoff: Option<StateHandoff>,
+) -> Result<ConsensusHandle> {
     create_and_start_shoal_validator_weighted(
         validators,
         Vec::new(),
         my_index,
         datastore,
         keypair,
+        bls_keypair,
+        mempool,
         swarm,
         consensus_tx,
+        handoff,
     ).await
 }
 
@@ -76,52 +187,79 @@ pub async fn create_and_start_shoal_validator_weighted(
     my_index: usize,
     datastore: Arc<Mutex<DatastoreManager>>,
     keypair: Keypair,
-    swarm: Arc<Mutex<NodeSwarm>>,
+    bls_keypair: Option<BlsKeypair>,
+    mempool: SharedMempool,
+    swarm: SwarmHandle,
     consensus_tx: mpsc::Sender<ConsensusMessage>,
-) -> Result<()> {
+    handoff: Option<StateHandoff>,
+) -> Result<ConsensusHandle> {
     create_and_start_shoal_validator_weighted_with_epoch(
         validators,
         stakes,
         my_index,
         datastore,
         keypair,
+        bls_keypair,
+        mempool,
         swarm,
         consensus_tx,
-        0, // Default epoch for static validators
+        // Static validators start at epoch 0, or at the epoch that seats them
+        handoff.as_ref().map_or(0, |h| h.reconfiguration.epoch),
         CheckpointMode::None, // Default to no checkpoints for backward compatibility
+        handoff,
     ).await
 }
 
 /// Create and start a Shoal validator with weighted stakes and epoch tracking for checkpoints.
+///
+/// A validator joining a running committee passes the state handoff so it
+/// joins at the activation round of the reconfiguration that seats it rather
+/// than starting at genesis.
 pub async fn create_and_start_shoal_validator_weighted_with_epoch(
     validators: Vec<String>,
     stakes: Vec<u64>,
     my_index: usize,
     datastore: Arc<Mutex<DatastoreManager>>,
     keypair: Keypair,
-    swarm: Arc<Mutex<NodeSwarm>>,
+    bls_keypair: Option<BlsKeypair>,
+    mempool: SharedMempool,
+    swarm: SwarmHandle,
     consensus_tx: mpsc::Sender<ConsensusMessage>,
     validator_epoch: u64,
     checkpoint_mode: CheckpointMode,
-) -> Result<()> {
+    handoff: Option<StateHandoff>,
+) -> Result<ConsensusHandle> {
     let datastore_for_loop = datastore.clone();
     let committee_size = validators.len();
     let validators_for_loop = validators.clone();
     
-    // Get blocks per epoch from datastore config
-    let blocks_per_epoch = {
+    // Get the epoch schedule from datastore config
+    let era_schedule = {
         let mgr = datastore.lock().await;
-        mgr.epoch_config().blocks_per_epoch
+        mgr.era_schedule().clone()
     };
     
     match modal_validator::ShoalValidatorConfig::from_peer_ids_with_stakes(validators, stakes, my_index) {
         Ok(config) => {
+            let config = super::bls::with_bls_keys(config, &*datastore.lock().await, bls_keypair)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to register validator BLS keys: {}", e))?;
             let validator_peer_id = config.validator_key.to_string();
+            let mempool = crate::mempool::for_validator(&mempool, &config.narwhal_config).await;
             
-            // Create and initialize ShoalValidator
-            match modal_validator::ShoalValidator::new(datastore, config).await {
+            // Create and initialize ShoalValidator, or join the committee handed over
+            let shoal_validator = match &handoff {
+                Some(handoff) => modal_validator::ShoalValidator::join(datastore, config, handoff.narwhal()).await,
+                None => modal_validator::ShoalValidator::new(datastore, config).await,
+            };
+            match shoal_validator {
                 Ok(shoal_validator) => {
-                    match shoal_validator.initialize().await {
+                    let shoal_validator = shoal_validator.with_mempool(mempool);
+                    let initialized = match &handoff {
+                        Some(_) => Ok(()),
+                        None => shoal_validator.initialize().await,
+                    };
+                    match initialized {
                         Ok(()) => {
                             log::info!("✅ ShoalValidator initialized successfully");
                             spawn_consensus_loop_with_checkpoints(
@@ -135,7 +273,8 @@ pub async fn create_and_start_shoal_validator_weighted_with_epoch(
                                 consensus_tx,
                                 validator_epoch,
                                 checkpoint_mode,
-                                blocks_per_epoch,
+                                era_schedule,
+                                handoff,
                             ).await
                         }
                         Err(e) => {
@@ -179,10 +318,11 @@ async fn get_prev_round_certs(
 }
 
 /// Create a new ValidatorBlock for the current round
-fn create_validator_block(
+pub(super) fn create_validator_block(
     peer_id: &str,
     round_id: u64,
     prev_round_certs: HashMap<String, String>,
+    events: Vec<serde_json::Value>,
     keypair: &Keypair,
 ) -> Result<ValidatorBlock> {
     let mut block = ValidatorBlock {
@@ -190,7 +330,7 @@ fn create_validator_block(
         round_id,
         prev_round_certs,
         opening_sig: None,
-        events: Vec::new(),
+        events,
         closing_sig: None,
         hash: None,
         acks: HashMap::new(),
@@ -210,6 +350,25 @@ fn create_validator_block(
     Ok(block)
 }
 
+/// Sign our checkpoint, merge it into the local certificate and gossip it so
+/// other validators, observers and miners can collect a quorum
+async fn sign_and_broadcast_checkpoint(
+    checkpoint: MinerCheckpoint,
+    peer_id: &str,
+    keypair: &Keypair,
+    datastore: &Arc<Mutex<DatastoreManager>>,
+    communication: &mut NodeCommunication,
+) -> Result<()> {
+    let mut cert = CheckpointCertificate::new(checkpoint);
+    cert.sign(peer_id, keypair)?;
+    let data = serde_json::to_vec(&cert)?;
+    {
+        let mgr = datastore.lock().await;
+        crate::gossip::consensus::checkpoint::handler(&data, &mgr).await?;
+    }
+    communication.broadcast_checkpoint(&cert).await
+}
+
 /// Spawn a background task to run the Shoal consensus loop.
 // TODO: Integrate with node startup for validator mode
 #[allow(dead_code)]
@@ -220,9 +379,10 @@ pub async fn spawn_consensus_loop(
     committee_size: usize,
     validators: Vec<String>,
     keypair: Keypair,
-    swarm: Arc<Mutex<NodeSwarm>>,
+    swarm: SwarmHandle,
     consensus_tx: mpsc::Sender<ConsensusMessage>,
-) -> Result<()> {
+) -> Result<ConsensusHandle> {
+    let era_schedule = datastore.lock().await.era_schedule().clone();
     spawn_consensus_loop_with_checkpoints(
         shoal_validator,
         datastore,
@@ -234,24 +394,35 @@ pub async fn spawn_consensus_loop(
         consensus_tx,
         0,
         CheckpointMode::None,
-        100, // Default blocks per epoch
+        era_schedule,
+        None,
     ).await
 }
 
 /// Spawn a background task to run the Shoal consensus loop with checkpoint support.
+///
+/// The returned handle hands validator set changes to the loop, which votes
+/// for them in its drafts. Once a quorum-signed certificate for the change is
+/// committed in round R it takes over in round R + `ACTIVATION_DELAY`; if
+/// this node isn't in the new set it finalizes what it has and the loop
+/// stops. Dropping the handle stops the loop the same way.
 pub async fn spawn_consensus_loop_with_checkpoints(
-    _shoal_validator: modal_validator::ShoalValidator,
+    shoal_validator: modal_validator::ShoalValidator,
     datastore: Arc<Mutex<DatastoreManager>>,
     validator_peer_id: String,
-    committee_size: usize,
-    _validators: Vec<String>,
+    mut committee_size: usize,
+    mut validators: Vec<String>,
     keypair: Keypair,
-    swarm: Arc<Mutex<NodeSwarm>>,
+    swarm: SwarmHandle,
     consensus_tx: mpsc::Sender<ConsensusMessage>,
     validator_epoch: u64,
     checkpoint_mode: CheckpointMode,
-    blocks_per_epoch: u64,
-) -> Result<()> {
+    era_schedule: EraSchedule,
+    handoff: Option<StateHandoff>,
+) -> Result<ConsensusHandle> {
+    let (reconfig_tx, mut reconfig_rx) = mpsc::unbounded_channel::<ValidatorSetChange>();
+    let round_timer_config = shoal_validator.round_timer_config().clone();
+    
     // Create a receiver for consensus messages
     // Note: We create a new channel and subscribe the consensus loop to it
     let (msg_tx, mut msg_rx) = mpsc::channel::<ConsensusMessage>(100);
@@ -268,7 +439,14 @@ pub async fn spawn_consensus_loop_with_checkpoints(
     
     tokio::spawn(async move {
         log::info!("🚀 Starting Shoal consensus loop");
-        let mut round = 0u64;
+        let mut round = match resume_round(&datastore, handoff.as_ref()).await {
+            Ok(round) => round,
+            Err(e) => {
+                log::error!("Not starting consensus, failed to read the last signed round: {}", e);
+                return;
+            }
+        };
+        let mut reconfiguration = Reconfiguration::new(validator_epoch, shoal_validator.committee().await);
         
         // Create communication channel for gossip
         let mut communication = NodeCommunication {
@@ -284,20 +462,39 @@ pub async fn spawn_consensus_loop_with_checkpoints(
         );
         
         // Create checkpoint tracker
-        let mut checkpoint_tracker = CheckpointTracker::new(checkpoint_mode, blocks_per_epoch);
+        let mut checkpoint_tracker = CheckpointTracker::new(checkpoint_mode, era_schedule);
         checkpoint_tracker.on_epoch_change(validator_epoch);
         
+        // The committee we're joining may already have checkpointed this epoch
+        if let Some(handoff) = &handoff {
+            log::info!("🤝 Joining consensus after round {}", handoff.start_after_round);
+            let handed_off_epoch = handoff.last_checkpoint.as_ref().map(|c| c.epoch);
+            if handed_off_epoch.is_some() && handed_off_epoch == checkpoint_tracker.get_selection_epoch() {
+                checkpoint_tracker.checkpoint_created = true;
+            }
+        }
+        
+        // Watch for rounds going uncertified, falling back to mining-only
+        let mut liveness = LivenessMonitor::new(
+            validator_peer_id.clone(),
+            validators.clone(),
+            CONSENSUS_STALL_ROUNDS,
+            round,
+            unix_now(),
+        );
+        
         // Initialize consensus metadata
         {
             let mgr = datastore.lock().await;
-            if let Err(e) = mgr.set_current_round(0).await {
+            if let Err(e) = mgr.set_current_round(round).await {
                 log::warn!("Failed to initialize current round: {}", e);
             }
         }
         
-        // Round timer
-        let mut round_interval = tokio::time::interval(tokio::time::Duration::from_secs(2));
-        round_interval.tick().await; // Skip first immediate tick
+        // Round timer, adapted to how long our blocks take to be certified
+        let mut round_timer = RoundTimer::new(round_timer_config);
+        let round_deadline = tokio::time::sleep(round_timer.timeout());
+        tokio::pin!(round_deadline);
         
         loop {
             tokio::select! {
@@ -307,6 +504,16 @@ pub async fn spawn_consensus_loop_with_checkpoints(
                         ConsensusMessage::DraftValidatorBlock { from, block, .. } => {
                             log::debug!("Received draft block from {} for round {}", 
                                 &from[..16.min(from.len())], block.round_id);
+                            liveness.heard_from(&block.peer_id, block.round_id);
+                            
+                            // A second, different draft for the round is evidence of equivocation
+                            match ack_collector.conflicting_draft(&block) {
+                                Ok(Some(earlier)) => {
+                                    record_equivocation(&datastore, &mut communication, earlier, &block).await;
+                                }
+                                Ok(None) => {}
+                                Err(e) => log::warn!("Error checking block for equivocation: {}", e),
+                            }
                             
                             // Generate an ack if valid
                             match ack_collector.handle_incoming_block(&block) {
@@ -337,6 +544,7 @@ pub async fn spawn_consensus_loop_with_checkpoints(
                         ConsensusMessage::ValidatorBlockAck { ack, .. } => {
                             log::debug!("Received ack from {} for round {}", 
                                 &ack.acker[..16.min(ack.acker.len())], ack.round_id);
+                            liveness.heard_from(&ack.acker, ack.round_id);
                             
                             // Process the ack
                             match ack_collector.handle_incoming_ack(&ack) {
@@ -344,11 +552,17 @@ pub async fn spawn_consensus_loop_with_checkpoints(
                                     // We have enough acks! Form certificate
                                     if let Some(certified_block) = ack_collector.form_certificate(ack.round_id) {
                                         log::info!("🎉 Certificate formed for round {}", ack.round_id);
+                                        round_timer.on_certified(ack.round_id, Instant::now());
+                                        if let Some(transition) = liveness.on_certified(ack.round_id, unix_now()) {
+                                            on_liveness_transition(&datastore, &liveness, transition, &checkpoint_tracker).await;
+                                        }
+                                        on_stall_certified(&mut liveness, &certified_block, &mut checkpoint_tracker);
                                         
                                         // Save certified block
                                         if let Err(e) = save_certified_block(&certified_block, &datastore).await {
                                             log::error!("Failed to save certified block: {}", e);
                                         }
+                                        on_block_certified(&mut reconfiguration, &shoal_validator, &certified_block).await;
                                         
                                         // Check if we should create a checkpoint
                                         if checkpoint_tracker.on_round_certified(ack.round_id) {
@@ -359,7 +573,6 @@ pub async fn spawn_consensus_loop_with_checkpoints(
                                                     selection_epoch,
                                                     checkpoint_tracker.current_validator_epoch,
                                                     ack.round_id,
-                                                    blocks_per_epoch,
                                                 ).await {
                                                     Ok(checkpoint) => {
                                                         log::info!(
@@ -368,6 +581,15 @@ pub async fn spawn_consensus_loop_with_checkpoints(
                                                             checkpoint.block_count,
                                                             &checkpoint.merkle_root[..16.min(checkpoint.merkle_root.len())]
                                                         );
+                                                        if let Err(e) = sign_and_broadcast_checkpoint(
+                                                            checkpoint,
+                                                            &validator_peer_id,
+                                                            &keypair,
+                                                            &datastore,
+                                                            &mut communication,
+                                                        ).await {
+                                                            log::warn!("Failed to broadcast checkpoint: {}", e);
+                                                        }
                                                     }
                                                     Err(e) => {
                                                         log::error!("Failed to create checkpoint: {}", e);
@@ -400,11 +622,17 @@ pub async fn spawn_consensus_loop_with_checkpoints(
                             // Validate and save the certified block
                             if block.cert.is_some() {
                                 // Validate the certificate signatures
-                                match validate_certificate(&block, committee_size) {
+                                match validate_certificate(&block, committee_size).await {
                                     Ok(true) => {
                                         if let Err(e) = save_certified_block(&block, &datastore).await {
                                             log::warn!("Failed to save certified block from {}: {}", from, e);
                                         }
+                                        on_block_certified(&mut reconfiguration, &shoal_validator, &block).await;
+                                        liveness.heard_from(&block.peer_id, block.round_id);
+                                        if let Some(transition) = liveness.on_certified(block.round_id, unix_now()) {
+                                            on_liveness_transition(&datastore, &liveness, transition, &checkpoint_tracker).await;
+                                        }
+                                        on_stall_certified(&mut liveness, &block, &mut checkpoint_tracker);
                                     }
                                     Ok(false) => {
                                         log::warn!("Invalid certificate from {} for round {}", 
@@ -422,15 +650,87 @@ pub async fn spawn_consensus_loop_with_checkpoints(
                     }
                 }
                 
+                // A new validator set to vote for in our drafts
+                change = reconfig_rx.recv() => {
+                    let Some(change) = change else {
+                        // The handle was dropped, e.g. the node left the validator role
+                        run_finalization_task(&datastore, round.saturating_sub(1)).await;
+                        log::info!("👋 Left consensus at round {}", round);
+                        break;
+                    };
+                    match reconfiguration.propose(change, &keypair) {
+                        Ok(Some(epoch)) => log::info!("🗳️ Voting for the validator set of epoch {}", epoch),
+                        Ok(None) => log::debug!("Ignoring a validator set change for a past epoch"),
+                        Err(e) => log::warn!("Failed to vote for validator set change: {}", e),
+                    }
+                }
+                
                 // Time to create a new round
-                _ = round_interval.tick() => {
+                _ = &mut round_deadline => {
                     round += 1;
+                    round_timer.start_round(round, Instant::now());
+                    let timeout = round_timer.timeout();
+                    round_deadline.as_mut().reset(tokio::time::Instant::now() + timeout);
+                    if round_timer.consecutive_timeouts() > 0 {
+                        log::debug!(
+                            "Round {} timed out {} times in a row, waiting {}ms",
+                            round,
+                            round_timer.consecutive_timeouts(),
+                            timeout.as_millis()
+                        );
+                    }
+                    
+                    // Switch committee once a committed validator set change activates
+                    if let Some(cert) = reconfiguration.take_due(round) {
+                        let next_validators: Vec<String> = cert.validators.iter()
+                            .map(|v| v.public_key.to_string())
+                            .collect();
+                        if !next_validators.contains(&validator_peer_id) {
+                            run_finalization_task(&datastore, round - 1).await;
+                            log::info!("👋 Retired from the validator set at round {} (epoch {})", round, cert.epoch);
+                            break;
+                        }
+                        log::info!(
+                            "🔁 Validator set for epoch {} active from round {} ({} -> {} validators)",
+                            cert.epoch,
+                            round,
+                            validators.len(),
+                            next_validators.len()
+                        );
+                        validators = next_validators;
+                        committee_size = validators.len();
+                        ack_collector.set_committee_size(committee_size);
+                        checkpoint_tracker.on_epoch_change(cert.epoch);
+                        liveness.set_committee(validators.clone());
+                    }
+                    
+                    match liveness.on_round_started(round, unix_now()) {
+                        Some(transition) => {
+                            on_liveness_transition(&datastore, &liveness, transition, &checkpoint_tracker).await;
+                        }
+                        None if round.is_multiple_of(CONSENSUS_LIVENESS_RECORD_ROUNDS) => {
+                            let record = liveness.liveness(checkpoint_tracker.current_validator_epoch, unix_now());
+                            if let Err(e) = datastore.lock().await.save_consensus_liveness(&record) {
+                                log::warn!("Failed to record consensus liveness: {}", e);
+                            }
+                        }
+                        None => {}
+                    }
                     
                     // Cleanup old data from ack collector
                     if round > 10 {
                         ack_collector.cleanup_round(round - 10);
                     }
                     
+                    // Record the round before signing in it, so a restart never signs it twice
+                    {
+                        let mgr = datastore.lock().await;
+                        if let Err(e) = mgr.set_current_round(round).await {
+                            log::error!("Failed to record round {}, not signing in it: {}", round, e);
+                            continue;
+                        }
+                    }
+                    
                     // Get previous round certificates
                     let prev_round_certs = {
                         let mgr = datastore.lock().await;
@@ -438,10 +738,13 @@ pub async fn spawn_consensus_loop_with_checkpoints(
                     };
                     
                     // Create and sign our block for this round
+                    let mut events = reconfiguration.draft_events();
+                    events.extend(liveness.draft_events());
                     let block = match create_validator_block(
                         &validator_peer_id,
                         round,
                         prev_round_certs.clone(),
+                        events,
                         &keypair,
                     ) {
                         Ok(b) => b,
@@ -469,14 +772,6 @@ pub async fn spawn_consensus_loop_with_checkpoints(
                         // Continue anyway - local block is saved
                     }
                     
-                    // Update current round in datastore for status page
-                    {
-                        let mgr = datastore.lock().await;
-                        if let Err(e) = mgr.set_current_round(round).await {
-                            log::warn!("Failed to update current round: {}", e);
-                        }
-                    }
-                    
                     // Log progress
                     if round.is_multiple_of(10) {
                         log::info!("📦 Round {} block created (validator: {}, committee: {}, prev_certs: {})", 
@@ -489,12 +784,158 @@ pub async fn spawn_consensus_loop_with_checkpoints(
                     
                     // Run periodic finalization task
                     if round.is_multiple_of(5) {
-                        run_finalization_task(&datastore, round).await;
+                        run_finalization_task(&datastore, round.saturating_sub(1)).await;
                     }
                 }
             }
         }
+        
+        // This node no longer runs consensus
+        if let Err(e) = datastore.lock().await.clear_consensus_liveness() {
+            log::warn!("Failed to clear consensus liveness: {}", e);
+        }
     });
     
-    Ok(())
+    Ok(ConsensusHandle::new(reconfig_tx))
+}
+
+/// Round the loop starts after: the last round this node signed a block in,
+/// which is persisted before signing, or the handoff's if that's later
+async fn resume_round(datastore: &Arc<Mutex<DatastoreManager>>, handoff: Option<&StateHandoff>) -> Result<u64> {
+    let last_signed_round = datastore.lock().await.get_current_round().await?;
+    let handoff_round = handoff.map_or(0, |h| h.start_after_round);
+    if last_signed_round > handoff_round {
+        log::info!("⏩ Resuming consensus after round {}, the last one signed", last_signed_round);
+    }
+    Ok(last_signed_round.max(handoff_round))
+}
+
+/// Schedule the reconfigurations committed by a certified block, in the loop
+/// and in the Shoal committee schedule
+async fn on_block_certified(
+    reconfiguration: &mut Reconfiguration,
+    shoal_validator: &modal_validator::ShoalValidator,
+    block: &ValidatorBlock,
+) {
+    for cert in reconfiguration.on_certified(block) {
+        log::info!(
+            "🔁 Validator set for epoch {} committed in round {}, active from round {}",
+            cert.epoch,
+            cert.round,
+            cert.activation_round()
+        );
+        if let Err(e) = shoal_validator.apply_reconfiguration(&cert).await {
+            log::warn!("Failed to schedule committee for epoch {}: {}", cert.epoch, e);
+        }
+    }
+}
+
+/// Log, journal and record a change between hybrid consensus and mining-only
+async fn on_liveness_transition(
+    datastore: &Arc<Mutex<DatastoreManager>>,
+    liveness: &LivenessMonitor,
+    transition: Transition,
+    checkpoint_tracker: &CheckpointTracker,
+) {
+    let record = liveness.liveness(checkpoint_tracker.current_validator_epoch, unix_now());
+    let kind = match transition {
+        Transition::Stalled => {
+            log::error!(
+                "🚨 Consensus stalled at round {}: {}/{} validators live, {} needed - mining continues without checkpoints until quorum returns",
+                record.round,
+                record.live_validators,
+                record.committee_size,
+                record.quorum
+            );
+            JournalEventKind::ConsensusStalled
+        }
+        Transition::Resumed => {
+            log::info!(
+                "✅ Consensus quorum is back: round {} certified, resuming hybrid consensus",
+                record.last_certified_round.unwrap_or(record.round)
+            );
+            JournalEventKind::ConsensusResumed
+        }
+    };
+    
+    let mgr = datastore.lock().await;
+    match serde_json::to_value(&record) {
+        Ok(data) => {
+            if let Err(e) = mgr.append_event(kind, data) {
+                log::warn!("Failed to journal consensus liveness change: {}", e);
+            }
+        }
+        Err(e) => log::warn!("Failed to serialize consensus liveness: {}", e),
+    }
+    if let Err(e) = mgr.save_consensus_liveness(&record) {
+        log::warn!("Failed to record consensus liveness: {}", e);
+    }
+}
+
+/// Drop the rounds certified before a stall from the epoch's checkpoint
+/// count once a block marking the stall is certified
+fn on_stall_certified(
+    liveness: &mut LivenessMonitor,
+    block: &ValidatorBlock,
+    checkpoint_tracker: &mut CheckpointTracker,
+) {
+    if let Some(stalled_after) = liveness.on_stall_certified(block) {
+        log::warn!(
+            "Round {} certifies a consensus stall after round {}; rounds certified before it no longer count towards the checkpoint",
+            block.round_id,
+            stalled_after
+        );
+        checkpoint_tracker.on_stall();
+    }
+}
+
+/// Gossip evidence that a validator signed two drafts for one round, for
+/// miners to include. The validator is only slashed once a canonical block
+/// carries the evidence.
+async fn record_equivocation(
+    datastore: &Arc<Mutex<DatastoreManager>>,
+    communication: &mut NodeCommunication,
+    first: &ValidatorBlock,
+    second: &ValidatorBlock,
+) {
+    let evidence = EquivocationEvidence::new(
+        second.peer_id.clone(),
+        second.round_id,
+        first.to_draft_json_object(),
+        second.to_draft_json_object(),
+    );
+    // Oversized drafts can't be mined, so they aren't gossiped either
+    if let Err(e) = modal_datastore::slashing::verify_evidence(&evidence) {
+        log::warn!("Equivocation by {} in round {} can't be used as evidence: {}", second.peer_id, second.round_id, e);
+        return;
+    }
+    
+    {
+        let mgr = datastore.lock().await;
+        match mgr.add_pending_evidence(&evidence) {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(e) => {
+                log::warn!("Failed to keep equivocation evidence: {}", e);
+                return;
+            }
+        }
+        log::error!(
+            "⚔️  Validator {} equivocated in round {}, gossiping the evidence",
+            &second.peer_id[..16.min(second.peer_id.len())],
+            second.round_id
+        );
+        match serde_json::to_value(&evidence) {
+            Ok(data) => {
+                if let Err(e) = mgr.append_event(JournalEventKind::OffenseRecorded, data) {
+                    log::warn!("Failed to journal validator offense: {}", e);
+                }
+            }
+            Err(e) => log::warn!("Failed to serialize equivocation evidence: {}", e),
+        }
+    }
+    
+    if let Err(e) = communication.broadcast_evidence(&evidence).await {
+        log::warn!("Failed to gossip equivocation evidence: {}", e);
+    }
 }
@@ -1,21 +1,29 @@
 //! Hybrid consensus functionality for validator nodes.
 //!
 //! In hybrid consensus mode, validators are selected based on mining nominations
-//! from epoch N-2.
+//! from epoch N-2. Each epoch's validator set is handed to the running consensus
+//! loop as a reconfiguration; nodes joining the set start from the state handoff.
+//! Before seating a set, the node checks it against its own run of the nominee
+//! shuffle (`modal_miner::epoch`), leaving out peers the slashing ledger
+//! excludes, and skips the epoch if they disagree.
 
 use modal_common::keypair::Keypair;
 use modal_datastore::models::MinerBlock;
-use modal_datastore::models::validator::get_validator_set_for_mining_epoch_hybrid_multi;
+use modal_datastore::models::validator::{
+    epoch_nominations_multi, get_validator_set_for_mining_epoch_hybrid_multi, ValidatorSet,
+};
 use modal_datastore::DatastoreManager;
+use modal_miner::epoch::{verify_seating, Seating};
 use modal_networks::CheckpointMode;
 use modal_validator_consensus::communication::Message as ConsensusMessage;
 use std::sync::Arc;
 use tokio::sync::{broadcast, mpsc, Mutex};
 
-use crate::swarm::NodeSwarm;
+use crate::mempool::SharedMempool;
+use crate::swarm_driver::SwarmHandle;
 
-/// Blocks per epoch constant for epoch calculations
-const BLOCKS_PER_EPOCH: u64 = 40;
+use super::bls::BlsKeypair;
+use super::reconfiguration::{load_state_handoff, ConsensusHandle, ValidatorSetChange};
 
 /// Start the hybrid consensus monitor.
 ///
@@ -26,14 +34,18 @@ pub fn start_hybrid_consensus_monitor(
     node_peer_id: String,
     epoch_rx: broadcast::Receiver<u64>,
     keypair: Keypair,
-    swarm: Arc<Mutex<NodeSwarm>>,
+    bls_keypair: Option<BlsKeypair>,
+    mempool: SharedMempool,
+    swarm: SwarmHandle,
     consensus_tx: mpsc::Sender<ConsensusMessage>,
-) {
+) -> tokio::task::JoinHandle<()> {
     start_hybrid_consensus_monitor_with_checkpoints(
         datastore,
         node_peer_id,
         epoch_rx,
         keypair,
+        bls_keypair,
+        mempool,
         swarm,
         consensus_tx,
         CheckpointMode::None,
@@ -42,20 +54,24 @@ pub fn start_hybrid_consensus_monitor(
 
 /// Start the hybrid consensus monitor with checkpoint support.
 ///
-/// This spawns a background task that monitors epoch transitions and starts
-/// consensus if this node is selected as a validator.
+/// This spawns a background task that monitors epoch transitions, starts
+/// consensus if this node is selected as a validator and reconfigures it as
+/// the validator set changes. Stopping the task retires the consensus loop.
 pub fn start_hybrid_consensus_monitor_with_checkpoints(
     datastore: Arc<Mutex<DatastoreManager>>,
     node_peer_id: String,
     mut epoch_rx: broadcast::Receiver<u64>,
     keypair: Keypair,
-    swarm: Arc<Mutex<NodeSwarm>>,
+    bls_keypair: Option<BlsKeypair>,
+    mempool: SharedMempool,
+    swarm: SwarmHandle,
     consensus_tx: mpsc::Sender<ConsensusMessage>,
     checkpoint_mode: CheckpointMode,
-) {
+) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
         log::info!("Hybrid consensus coordinator started, waiting for epoch >= 2...");
         log::info!("Checkpoint mode: {:?}", checkpoint_mode);
+        let mut consensus: Option<ConsensusHandle> = None;
         
         // Check current epoch on startup
         let current_epoch = get_current_epoch(&datastore).await;
@@ -67,9 +83,12 @@ pub fn start_hybrid_consensus_monitor_with_checkpoints(
                 &node_peer_id,
                 current_epoch,
                 &keypair,
+                &bls_keypair,
+                &mempool,
                 swarm.clone(),
                 consensus_tx.clone(),
                 checkpoint_mode.clone(),
+                &mut consensus,
             ).await;
         }
         
@@ -83,9 +102,12 @@ pub fn start_hybrid_consensus_monitor_with_checkpoints(
                         &node_peer_id,
                         new_epoch,
                         &keypair,
+                        &bls_keypair,
+                        &mempool,
                         swarm.clone(),
                         consensus_tx.clone(),
                         checkpoint_mode.clone(),
+                        &mut consensus,
                     ).await;
                 }
                 Err(e) => {
@@ -94,7 +116,7 @@ pub fn start_hybrid_consensus_monitor_with_checkpoints(
                 }
             }
         }
-    });
+    })
 }
 
 /// Get the current epoch from the chain tip.
@@ -103,30 +125,53 @@ async fn get_current_epoch(datastore: &Arc<Mutex<DatastoreManager>>) -> u64 {
     match MinerBlock::find_all_canonical_multi(&ds).await {
         Ok(blocks) if !blocks.is_empty() => {
             let max_index = blocks.iter().map(|b| b.index).max().unwrap_or(0);
-            max_index / BLOCKS_PER_EPOCH
+            ds.block_index_to_epoch(max_index)
         }
         _ => 0
     }
 }
 
+/// Check that `set` seats the validators the nominee shuffle of its
+/// nomination epoch gives, without slashed peers
+async fn cross_check_shuffle(ds: &DatastoreManager, set: &ValidatorSet) -> anyhow::Result<()> {
+    let nominations = epoch_nominations_multi(ds, set.epoch).await?;
+    let excluded = ds.excluded_peers(set.epoch).await?;
+    let seating = Seating {
+        nominated: set.nominated_validators.clone(),
+        alternates: set.alternate_validators.clone(),
+    };
+    verify_seating(nominations.nonces, &nominations.nominees, &excluded, &seating)
+        .map_err(|e| anyhow::anyhow!("epoch {} shuffle check failed: {}", set.epoch, e))
+}
+
 /// Check if this node should be a validator for the current epoch and start consensus if so.
+/// If consensus is already running, the epoch's validator set is handed to it instead.
 async fn check_and_start_validator(
     datastore: &Arc<Mutex<DatastoreManager>>,
     node_peer_id: &str,
     current_epoch: u64,
     keypair: &Keypair,
-    swarm: Arc<Mutex<NodeSwarm>>,
+    bls_keypair: &Option<BlsKeypair>,
+    mempool: &SharedMempool,
+    swarm: SwarmHandle,
     consensus_tx: mpsc::Sender<ConsensusMessage>,
     checkpoint_mode: CheckpointMode,
+    consensus: &mut Option<ConsensusHandle>,
 ) {
     // Get validator set for this epoch (from epoch N-2 nominations)
     let validator_set = {
         let ds = datastore.lock().await;
         match get_validator_set_for_mining_epoch_hybrid_multi(&ds, current_epoch).await {
-            Ok(Some(set)) => {
-                log::info!("Validator set for epoch {}: {} validators", current_epoch, set.nominated_validators.len());
-                Some(set)
-            }
+            Ok(Some(set)) => match cross_check_shuffle(&ds, &set).await {
+                Ok(()) => {
+                    log::info!("Validator set for epoch {}: {} validators", current_epoch, set.nominated_validators.len());
+                    Some(set)
+                }
+                Err(e) => {
+                    log::error!("❌ Not seating the validator set for epoch {}: {}", current_epoch, e);
+                    None
+                }
+            },
             Ok(None) => {
                 log::debug!("No validator set available for epoch {} yet (need epoch >= 2)", current_epoch);
                 None
@@ -143,6 +188,19 @@ async fn check_and_start_validator(
         let validators: Vec<String> = validators_with_stakes.iter().map(|(p, _)| p.clone()).collect();
         let stakes: Vec<u64> = validators_with_stakes.iter().map(|(_, s)| *s).collect();
         
+        // A loop that has retired no longer takes changes; start again if selected
+        if let Some(handle) = consensus.as_ref() {
+            let change = ValidatorSetChange {
+                epoch: Some(current_epoch),
+                validators: validators.clone(),
+                stakes: stakes.clone(),
+            };
+            if handle.reconfigure(change) {
+                log::info!("🔁 Handed the validator set for epoch {} to the running consensus", current_epoch);
+                return;
+            }
+        }
+        
         if validators.contains(&node_peer_id.to_string()) {
             log::info!("🏛️  This node IS a validator for epoch {} - starting Shoal consensus", current_epoch);
             
@@ -166,18 +224,47 @@ async fn check_and_start_validator(
             let total_stake: u64 = stakes.iter().sum();
             log::info!("📊 Total stake: {}, My stake: {}", total_stake, stakes[my_index]);
             
+            // Join at the activation of the committed reconfiguration that seats us
+            let handoff = {
+                let ds = datastore.lock().await;
+                match load_state_handoff(&ds, node_peer_id).await {
+                    Ok(handoff) => handoff,
+                    Err(e) => {
+                        log::error!("Failed to load state handoff: {}", e);
+                        return;
+                    }
+                }
+            };
+            // The committed committee is the one consensus runs with
+            let (validators, stakes, my_index) = match &handoff {
+                Some(handoff) => {
+                    let validators = handoff.validator_ids();
+                    let Some(my_index) = validators.iter().position(|v| v == node_peer_id) else {
+                        return;
+                    };
+                    (validators, handoff.stakes(), my_index)
+                }
+                None => (validators, stakes, my_index),
+            };
+            
             match super::consensus::create_and_start_shoal_validator_weighted_with_epoch(
                 validators,
                 stakes,
                 my_index,
                 datastore.clone(),
                 keypair.clone(),
+                bls_keypair.clone(),
+                mempool.clone(),
                 swarm,
                 consensus_tx,
                 current_epoch,
                 checkpoint_mode,
+                handoff,
             ).await {
-                Ok(()) => log::info!("✅ Hybrid consensus started for epoch {}", current_epoch),
+                Ok(handle) => {
+                    log::info!("✅ Hybrid consensus started for epoch {}", current_epoch);
+                    *consensus = Some(handle);
+                }
                 Err(e) => log::error!("Failed to start hybrid consensus: {}", e),
             }
         } else {
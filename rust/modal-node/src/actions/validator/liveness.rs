@@ -0,0 +1,273 @@
+//! Consensus liveness monitoring
+//!
+//! A round is certified once 2f+1 of the committee have acked a block, so
+//! with more than f validators offline consensus stops making progress. The
+//! [`LivenessMonitor`] notices rounds going by without a certificate and,
+//! after `CONSENSUS_STALL_ROUNDS` of them, reports mining-only: blocks keep
+//! being mined but none are checkpointed. The loop keeps proposing blocks,
+//! so the first new certificate, once enough validators are back, resumes
+//! hybrid consensus.
+//!
+//! How long a node waited is its own view, so it doesn't change what gets
+//! checkpointed. Instead its drafts carry a stall marker, and once a block
+//! with a marker is certified every validator drops the rounds certified
+//! before the stall from the epoch's checkpoint count at the same block.
+
+use modal_datastore::models::ValidatorBlock;
+use modal_datastore::{ConsensusLiveness, ConsensusMode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::quorum::bft_threshold;
+
+/// A change between hybrid consensus and mining-only
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    /// Consensus stalled and the node fell back to mining-only
+    Stalled,
+    /// A round was certified again
+    Resumed,
+}
+
+/// Liveness messages carried in draft blocks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LivenessEvent {
+    /// The author saw no certificate after `last_certified_round` for more
+    /// than the stall rounds
+    ConsensusStall { last_certified_round: u64 },
+}
+
+impl LivenessEvent {
+    /// The liveness events among a block's events
+    pub fn in_block(block: &ValidatorBlock) -> Vec<Self> {
+        block
+            .events
+            .iter()
+            .filter_map(|event| serde_json::from_value(event.clone()).ok())
+            .collect()
+    }
+}
+
+pub struct LivenessMonitor {
+    validator_peer_id: String,
+    committee: Vec<String>,
+    /// Rounds without a certificate before falling back
+    stall_rounds: u64,
+    round: u64,
+    last_certified_round: Option<u64>,
+    /// Round progress is measured from: the last certificate, or where the
+    /// loop started
+    progress_round: u64,
+    /// Latest round each committee member was heard from
+    last_heard: HashMap<String, u64>,
+    mode: ConsensusMode,
+    mode_since: i64,
+    fallbacks: u64,
+    /// Round of the certified block whose stall marker was last applied
+    stall_certified_at: Option<u64>,
+}
+
+impl LivenessMonitor {
+    pub fn new(validator_peer_id: String, committee: Vec<String>, stall_rounds: u64, start_round: u64, now: i64) -> Self {
+        Self {
+            validator_peer_id,
+            committee,
+            stall_rounds,
+            round: start_round,
+            last_certified_round: None,
+            progress_round: start_round,
+            last_heard: HashMap::new(),
+            mode: ConsensusMode::Hybrid,
+            mode_since: now,
+            fallbacks: 0,
+            stall_certified_at: None,
+        }
+    }
+
+    pub fn mode(&self) -> ConsensusMode {
+        self.mode
+    }
+
+    /// Follow a validator set change
+    pub fn set_committee(&mut self, committee: Vec<String>) {
+        self.last_heard.retain(|peer_id, _| committee.contains(peer_id));
+        self.committee = committee;
+    }
+
+    /// A committee member sent a block or ack for `round`
+    pub fn heard_from(&mut self, peer_id: &str, round: u64) {
+        if !self.committee.iter().any(|member| member == peer_id) {
+            return;
+        }
+        let last = self.last_heard.entry(peer_id.to_string()).or_insert(round);
+        *last = (*last).max(round);
+    }
+
+    /// A new round started
+    pub fn on_round_started(&mut self, round: u64, now: i64) -> Option<Transition> {
+        self.round = round;
+        if self.mode == ConsensusMode::Hybrid && round.saturating_sub(self.progress_round) > self.stall_rounds {
+            self.mode = ConsensusMode::MiningOnly;
+            self.mode_since = now;
+            self.fallbacks += 1;
+            return Some(Transition::Stalled);
+        }
+        None
+    }
+
+    /// `round` was certified, by this node or another
+    pub fn on_certified(&mut self, round: u64, now: i64) -> Option<Transition> {
+        // Certificates for rounds before the last one known say nothing new
+        if self.last_certified_round.is_some_and(|last| round <= last) {
+            return None;
+        }
+        self.last_certified_round = Some(round);
+        self.progress_round = self.progress_round.max(round);
+        if self.mode == ConsensusMode::MiningOnly {
+            self.mode = ConsensusMode::Hybrid;
+            self.mode_since = now;
+            return Some(Transition::Resumed);
+        }
+        None
+    }
+
+    /// Events for our next draft: a stall marker while we see consensus stalled
+    pub fn draft_events(&self) -> Vec<serde_json::Value> {
+        if self.mode != ConsensusMode::MiningOnly {
+            return Vec::new();
+        }
+        let marker = LivenessEvent::ConsensusStall { last_certified_round: self.progress_round };
+        serde_json::to_value(marker).ok().into_iter().collect()
+    }
+
+    /// Read the stall markers of a certified block. Returns the round the
+    /// stall began after if the block certifies a stall not already applied;
+    /// markers from the same stall in later blocks are ignored, so every
+    /// validator applies it once, at the first certified block carrying it.
+    pub fn on_stall_certified(&mut self, block: &ValidatorBlock) -> Option<u64> {
+        let stalled_after = LivenessEvent::in_block(block)
+            .into_iter()
+            .map(|LivenessEvent::ConsensusStall { last_certified_round }| last_certified_round)
+            // A marker must claim a stall the block's round allows
+            .filter(|after| block.round_id.saturating_sub(*after) > self.stall_rounds)
+            .filter(|after| self.stall_certified_at.is_none_or(|at| *after >= at))
+            .max()?;
+        self.stall_certified_at = Some(block.round_id);
+        Some(stalled_after)
+    }
+
+    /// Validators needed to certify a round
+    pub fn quorum(&self) -> usize {
+        bft_threshold(self.committee.len())
+    }
+
+    /// Committee members heard from in the last `stall_rounds` rounds, this
+    /// node included
+    pub fn live_validators(&self) -> usize {
+        let recent = self.round.saturating_sub(self.stall_rounds);
+        self.committee
+            .iter()
+            .filter(|member| {
+                **member == self.validator_peer_id
+                    || self.last_heard.get(member.as_str()).is_some_and(|round| *round >= recent)
+            })
+            .count()
+    }
+
+    /// The state to record for the status page and RPC
+    pub fn liveness(&self, validator_epoch: u64, now: i64) -> ConsensusLiveness {
+        ConsensusLiveness {
+            mode: self.mode,
+            validator_epoch,
+            round: self.round,
+            last_certified_round: self.last_certified_round,
+            committee_size: self.committee.len(),
+            quorum: self.quorum(),
+            live_validators: self.live_validators(),
+            mode_since: self.mode_since,
+            fallbacks: self.fallbacks,
+            updated_at: now,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use modal_common::keypair::Keypair;
+
+    fn monitor() -> LivenessMonitor {
+        let committee = ["me", "v1", "v2", "v3"].iter().map(|v| v.to_string()).collect();
+        LivenessMonitor::new("me".to_string(), committee, 5, 0, 100)
+    }
+
+    #[test]
+    fn test_falls_back_after_stalled_rounds_and_resumes() {
+        let mut monitor = monitor();
+        for round in 1..=5 {
+            assert_eq!(monitor.on_round_started(round, 100), None);
+        }
+        assert_eq!(monitor.on_certified(5, 100), None);
+        for round in 6..=10 {
+            assert_eq!(monitor.on_round_started(round, 100), None);
+        }
+        assert_eq!(monitor.on_round_started(11, 200), Some(Transition::Stalled));
+        assert_eq!(monitor.mode(), ConsensusMode::MiningOnly);
+        assert_eq!(monitor.on_round_started(12, 210), None);
+
+        // A late certificate for a round we already knew about doesn't resume
+        assert_eq!(monitor.on_certified(4, 220), None);
+        assert_eq!(monitor.on_certified(12, 230), Some(Transition::Resumed));
+        let liveness = monitor.liveness(3, 230);
+        assert_eq!(liveness.mode, ConsensusMode::Hybrid);
+        assert_eq!((liveness.fallbacks, liveness.mode_since), (1, 230));
+    }
+
+    fn block(round_id: u64, events: Vec<serde_json::Value>) -> ValidatorBlock {
+        let keypair = Keypair::generate().unwrap();
+        let address = keypair.as_public_address();
+        super::super::consensus::create_validator_block(&address, round_id, Default::default(), events, &keypair).unwrap()
+    }
+
+    #[test]
+    fn test_stall_applies_once_certified() {
+        let mut monitor = monitor();
+        assert!(monitor.draft_events().is_empty());
+        monitor.on_certified(3, 100);
+        assert_eq!(monitor.on_round_started(9, 100), Some(Transition::Stalled));
+        let marker = monitor.draft_events();
+        assert_eq!(
+            LivenessEvent::in_block(&block(9, marker.clone())),
+            vec![LivenessEvent::ConsensusStall { last_certified_round: 3 }]
+        );
+
+        // Only a certified marker applies, once, whichever draft carries it
+        assert_eq!(monitor.on_stall_certified(&block(10, Vec::new())), None);
+        assert_eq!(monitor.on_stall_certified(&block(10, marker.clone())), Some(3));
+        assert_eq!(monitor.on_stall_certified(&block(11, marker.clone())), None);
+
+        // A marker claiming fewer uncertified rounds than a stall is ignored
+        let early = vec![serde_json::to_value(LivenessEvent::ConsensusStall { last_certified_round: 20 }).unwrap()];
+        assert_eq!(monitor.on_stall_certified(&block(24, early)), None);
+
+        // A later stall applies again
+        let later = vec![serde_json::to_value(LivenessEvent::ConsensusStall { last_certified_round: 20 }).unwrap()];
+        assert_eq!(monitor.on_stall_certified(&block(30, later)), Some(20));
+    }
+
+    #[test]
+    fn test_live_validators() {
+        let mut monitor = monitor();
+        monitor.heard_from("v1", 2);
+        monitor.heard_from("v2", 9);
+        monitor.heard_from("outsider", 9);
+        monitor.on_round_started(10, 100);
+        // v1 was last heard more than 5 rounds ago and v3 never
+        assert_eq!(monitor.live_validators(), 2);
+        assert_eq!(monitor.quorum(), 3);
+
+        monitor.set_committee(vec!["me".to_string(), "v2".to_string()]);
+        assert_eq!(monitor.liveness(0, 100).committee_size, 2);
+    }
+}
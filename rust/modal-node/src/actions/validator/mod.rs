@@ -9,114 +9,82 @@
 //! - Do NOT mine blocks
 
 mod ack_collector;
+pub mod bls;
 pub mod checkpoint;
 mod consensus;
 mod hybrid;
+pub mod liveness;
+pub mod quorum;
+mod reconfiguration;
 
 use anyhow::Result;
 use modal_common::keypair::Keypair;
+use tokio::task::JoinHandle;
 
+use crate::actions::roles::{self, MiningUpdates, Role};
 use crate::gossip;
 use crate::node::Node;
 
-use super::observer::{
-    get_chain_tip_index, start_chain_monitor,
-    start_sync_request_handler, sync_from_peers,
-};
+use super::observer::start_chain_monitor_from_tip;
 
 /// Run a validator node that observes mining events and maintains the canonical chain
 /// without mining blocks itself.
 pub async fn run(node: &mut Node) -> Result<()> {
-    log::info!("Starting validator node");
-    
-    // Create a channel to receive mining chain updates
-    let (mining_update_tx, mining_update_rx) = tokio::sync::mpsc::unbounded_channel::<u64>();
-    
-    // Store the mining update channel in node so gossip handlers can use it
-    node.mining_update_tx = Some(mining_update_tx.clone());
-    
-    // Set up sync request handling
-    let (sync_request_tx, sync_request_rx) = tokio::sync::mpsc::unbounded_channel();
-    node.sync_request_tx = Some(sync_request_tx);
-    
-    // Start sync request handler task
-    start_sync_request_handler(
-        sync_request_rx,
-        node.datastore_manager.clone(),
-        node.swarm.clone(),
-        node.ignored_peers.clone(),
-        node.reqres_response_txs.clone(),
-        mining_update_tx,
-    );
-    
-    // Subscribe to mining block gossip
-    gossip::add_miner_event_listeners(node).await?;
-    log::info!("Subscribed to mining block gossip");
-    
-    // Subscribe to validator consensus gossip topics
-    gossip::add_validator_event_listeners(node).await?;
-    log::info!("Subscribed to validator consensus gossip topics");
-    
-    // Start status server
-    node.start_status_server().await?;
-    node.start_status_html_writer().await?;
-    
-    // Start networking
-    node.start_networking().await?;
-    
-    // Start autoupgrade if configured
-    node.start_autoupgrade().await?;
-    
-    // Wait for connections to peers
-    node.wait_for_connections().await?;
-    
-    // Sync from peers on startup if bootstrappers are configured
-    if !node.bootstrappers.is_empty() {
-        log::info!("Syncing blockchain state from peers...");
-        match sync_from_peers(node).await {
-            Ok(()) => log::info!("Initial sync completed"),
-            Err(e) => log::warn!("Initial sync failed (will continue via gossip): {}", e),
+    roles::run(node, Role::Validator).await
+}
+
+/// The validator role: consensus gossip, the consensus monitors and a chain monitor
+pub struct ValidatorComponent {
+    chain_monitor: JoinHandle<()>,
+    consensus_monitor: Option<JoinHandle<()>>,
+}
+
+impl ValidatorComponent {
+    pub async fn start(node: &mut Node, updates: &MiningUpdates) -> Result<Self> {
+        // Subscribe to validator consensus gossip topics
+        gossip::add_validator_event_listeners(node).await?;
+        log::info!("Subscribed to validator consensus gossip topics");
+        
+        // Check and start consensus based on configuration
+        let consensus_monitor = start_consensus_if_configured(node).await;
+        
+        let chain_monitor = start_chain_monitor_from_tip(node, updates, "Validator").await;
+        log::info!("Validating - observing mining chain");
+        Ok(Self { chain_monitor, consensus_monitor })
+    }
+
+    /// Stop the consensus monitors; the consensus loop they hold finalizes
+    /// what it has and retires
+    pub fn signal_stop(&self) {
+        self.chain_monitor.abort();
+        if let Some(monitor) = &self.consensus_monitor {
+            monitor.abort();
         }
     }
-    
-    // Check and start consensus based on configuration
-    start_consensus_if_configured(node).await;
-    
-    // Get the starting chain tip
-    let starting_index = get_chain_tip_index(&node.datastore_manager).await;
-    if starting_index > 0 {
-        log::info!("Starting chain validator at index {}", starting_index);
-    } else {
-        log::info!("Starting chain validator with empty chain");
+
+    pub async fn stopped(self) {
+        let _ = self.chain_monitor.await;
+        if let Some(monitor) = self.consensus_monitor {
+            let _ = monitor.await;
+        }
     }
-    
-    // Start chain monitor task
-    start_chain_monitor(
-        mining_update_rx,
-        node.datastore_manager.clone(),
-        starting_index,
-        "Validator",
-    );
-    
-    log::info!("Validator node running - observing mining chain");
-    
-    // Wait for shutdown signal
-    node.wait_for_shutdown().await?;
-    
-    Ok(())
 }
 
-/// Check and start consensus based on node configuration.
-async fn start_consensus_if_configured(node: &Node) {
+/// Check and start consensus based on node configuration, returning the
+/// task that follows validator set changes.
+async fn start_consensus_if_configured(node: &Node) -> Option<JoinHandle<()>> {
     // Convert libp2p keypair to modal_common Keypair for signing
     let keypair = match Keypair::from_libp2p_keypair(node.node_keypair.clone()) {
         Ok(kp) => kp,
         Err(e) => {
             log::error!("Failed to convert node keypair for consensus: {}", e);
-            return;
+            return None;
         }
     };
     
+    let bls_keypair = node.bls_keypair.clone();
+    let mempool = node.mempool.clone();
+    
     // Get swarm and consensus channel for communication
     let swarm = node.swarm.clone();
     let consensus_tx = node.get_consensus_tx();
@@ -129,37 +97,58 @@ async fn start_consensus_if_configured(node: &Node) {
 
     if let Some(validators) = static_validators {
         let node_peer_id_str = node.peerid.to_string();
-        if validators.contains(&node_peer_id_str) {
+        let consensus = if validators.contains(&node_peer_id_str) {
             log::info!("🏛️  This node is a static validator - starting Shoal consensus");
             consensus::start_static_validator_consensus(
                 &node_peer_id_str,
                 &validators,
                 &node.datastore_manager,
-                keypair,
-                swarm,
-                consensus_tx,
-            ).await;
+                keypair.clone(),
+                bls_keypair.clone(),
+                mempool.clone(),
+                swarm.clone(),
+                consensus_tx.clone(),
+                None,
+            ).await
         } else {
             log::info!("This node is not in the static validators list");
-        }
+            None
+        };
+        
+        // Follow changes to the static validators list
+        Some(consensus::start_static_validators_monitor(
+            node_peer_id_str,
+            validators,
+            node.datastore_manager.clone(),
+            keypair,
+            bls_keypair,
+            mempool,
+            swarm,
+            consensus_tx,
+            consensus,
+        ))
     } else {
         log::info!("No static validators configured");
         
         // Check if hybrid consensus is enabled
         if node.hybrid_consensus && node.run_validator {
             log::info!("🔄 Hybrid consensus mode enabled - validators selected from epoch N-2 mining nominations");
-            hybrid::start_hybrid_consensus_monitor(
+            Some(hybrid::start_hybrid_consensus_monitor(
                 node.datastore_manager.clone(),
                 node.peerid.to_string(),
                 node.epoch_transition_tx.subscribe(),
                 keypair,
+                bls_keypair,
+                mempool,
                 swarm,
                 consensus_tx,
-            );
+            ))
         } else if node.hybrid_consensus {
             log::info!("Hybrid consensus mode enabled but run_validator is false - running as miner only");
+            None
         } else {
             log::info!("Consensus not enabled (no static validators and hybrid consensus is off)");
+            None
         }
     }
 }
@@ -0,0 +1,212 @@
+//! Quorum collection
+//!
+//! A [`Quorum`] gathers signed votes (acks, checkpoint signatures, proposal
+//! approvals, key rotation endorsements) until enough weight has voted. Each
+//! voter counts once; a second vote from the same voter is reported as a
+//! duplicate and the first one kept. Voters have a weight, 1 unless given,
+//! and a quorum can be limited to a known electorate, in which case votes
+//! from anyone else are ineligible. A quorum with a timeout stops counting
+//! once it has expired. Verifying the votes is left to the caller, so this
+//! knows nothing about signatures or the network.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Votes needed out of a committee of `n` for Byzantine fault tolerance:
+/// 2f+1 where f = floor((n-1)/3)
+pub fn bft_threshold(n: usize) -> usize {
+    let f = n.saturating_sub(1) / 3;
+    2 * f + 1
+}
+
+/// What happened to a vote
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Vote {
+    /// Counted towards the quorum
+    Counted,
+    /// The voter had already voted
+    Duplicate,
+    /// The voter isn't in the electorate
+    Ineligible,
+    /// The quorum timed out before the vote arrived
+    Expired,
+}
+
+#[derive(Debug, Clone)]
+pub struct Quorum<V> {
+    /// Weight needed
+    needed: u64,
+    /// Weight of each voter; anyone may vote with weight 1 when unset
+    electorate: Option<HashMap<String, u64>>,
+    /// Votes in the order they arrived
+    votes: Vec<(String, V)>,
+    weight: u64,
+    deadline: Option<Instant>,
+}
+
+impl<V> Quorum<V> {
+    /// A quorum of `needed` votes from anyone
+    pub fn new(needed: u64) -> Self {
+        Self {
+            needed,
+            electorate: None,
+            votes: Vec::new(),
+            weight: 0,
+            deadline: None,
+        }
+    }
+
+    /// A 2f+1 quorum for a committee of `committee_size`, from anyone
+    pub fn bft(committee_size: usize) -> Self {
+        Self::new(bft_threshold(committee_size) as u64)
+    }
+
+    /// A quorum of `needed` votes among `voters`, one vote each
+    pub fn among(voters: &[String], needed: u64) -> Self {
+        Self::weighted(voters.iter().map(|voter| (voter.clone(), 1)).collect(), needed)
+    }
+
+    /// A quorum of `needed` weight among voters with the given weights
+    pub fn weighted(weights: HashMap<String, u64>, needed: u64) -> Self {
+        Self {
+            electorate: Some(weights),
+            ..Self::new(needed)
+        }
+    }
+
+    /// Stop counting votes `timeout` from now
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.deadline = Some(Instant::now() + timeout);
+        self
+    }
+
+    /// Stop counting votes at `deadline`
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Change the weight needed, e.g. when the committee changes
+    pub fn set_needed(&mut self, needed: u64) {
+        self.needed = needed;
+    }
+
+    /// Count `voter`'s vote
+    pub fn add(&mut self, voter: &str, vote: V) -> Vote {
+        self.add_at(voter, vote, Instant::now())
+    }
+
+    /// Count `voter`'s vote as if it arrived at `now`
+    pub fn add_at(&mut self, voter: &str, vote: V, now: Instant) -> Vote {
+        if self.is_expired_at(now) {
+            return Vote::Expired;
+        }
+        let weight = match &self.electorate {
+            Some(weights) => match weights.get(voter) {
+                Some(weight) => *weight,
+                None => return Vote::Ineligible,
+            },
+            None => 1,
+        };
+        if self.votes.iter().any(|(v, _)| v == voter) {
+            return Vote::Duplicate;
+        }
+        self.votes.push((voter.to_string(), vote));
+        self.weight += weight;
+        Vote::Counted
+    }
+
+    /// Whether enough weight has voted
+    pub fn is_reached(&self) -> bool {
+        self.weight >= self.needed
+    }
+
+    /// Whether the quorum has timed out
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(Instant::now())
+    }
+
+    pub fn is_expired_at(&self, now: Instant) -> bool {
+        self.deadline.is_some_and(|deadline| now >= deadline)
+    }
+
+    /// Weight voted so far
+    pub fn weight(&self) -> u64 {
+        self.weight
+    }
+
+    pub fn needed(&self) -> u64 {
+        self.needed
+    }
+
+    /// Votes in the order they arrived
+    pub fn votes(&self) -> impl Iterator<Item = (&str, &V)> {
+        self.votes.iter().map(|(voter, vote)| (voter.as_str(), vote))
+    }
+
+    pub fn len(&self) -> usize {
+        self.votes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.votes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bft_threshold() {
+        assert_eq!(bft_threshold(0), 1);
+        assert_eq!(bft_threshold(1), 1);
+        assert_eq!(bft_threshold(3), 1);
+        assert_eq!(bft_threshold(4), 3);
+        assert_eq!(bft_threshold(7), 5);
+        assert_eq!(bft_threshold(10), 7);
+    }
+
+    #[test]
+    fn test_duplicates_count_once() {
+        let mut quorum = Quorum::bft(4);
+        assert_eq!(quorum.add("a", "first"), Vote::Counted);
+        assert_eq!(quorum.add("a", "second"), Vote::Duplicate);
+        assert_eq!(quorum.add("b", "b"), Vote::Counted);
+        assert!(!quorum.is_reached());
+        assert_eq!(quorum.add("c", "c"), Vote::Counted);
+        assert!(quorum.is_reached());
+        assert_eq!(quorum.votes().next(), Some(("a", &"first")));
+        assert_eq!(quorum.len(), 3);
+    }
+
+    #[test]
+    fn test_weighted_electorate() {
+        let weights = HashMap::from([("whale".to_string(), 5), ("minnow".to_string(), 1)]);
+        let mut quorum = Quorum::weighted(weights, 6);
+        assert_eq!(quorum.add("stranger", ()), Vote::Ineligible);
+        assert_eq!(quorum.add("whale", ()), Vote::Counted);
+        assert!(!quorum.is_reached());
+        assert_eq!(quorum.add("minnow", ()), Vote::Counted);
+        assert_eq!(quorum.weight(), 6);
+        assert!(quorum.is_reached());
+
+        // A smaller committee needs less
+        let mut quorum = Quorum::among(&["a".to_string(), "b".to_string(), "c".to_string()], 3);
+        quorum.add("a", ());
+        quorum.add("b", ());
+        assert!(!quorum.is_reached());
+        quorum.set_needed(2);
+        assert!(quorum.is_reached());
+    }
+
+    #[test]
+    fn test_timeout() {
+        let start = Instant::now();
+        let mut quorum = Quorum::new(2).with_deadline(start + Duration::from_secs(10));
+        assert_eq!(quorum.add_at("a", (), start), Vote::Counted);
+        assert!(!quorum.is_expired_at(start + Duration::from_secs(9)));
+        assert_eq!(quorum.add_at("b", (), start + Duration::from_secs(10)), Vote::Expired);
+        assert!(!quorum.is_reached());
+    }
+}
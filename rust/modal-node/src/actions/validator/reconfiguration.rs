@@ -0,0 +1,412 @@
+//! Validator set reconfiguration for the consensus loop.
+//!
+//! In hybrid mode the validator set changes with each epoch's nominations; in
+//! static mode it changes when the static validator list is updated. Either
+//! way, a change only proposes a committee: each member of the current
+//! committee signs it and carries the vote in its drafts. Once certified
+//! blocks hold the votes of a quorum, members carry the resulting
+//! [`ReconfigurationCertificate`] in their drafts, and the round R of the
+//! earliest certified block holding it is the round it was committed in. The
+//! new committee takes over in round R + `ACTIVATION_DELAY`: the running loop
+//! switches committee there and retires if this node has left, while a node
+//! that joins starts from the committed certificate it has observed.
+
+use anyhow::{anyhow, bail, Result};
+use modal_common::keypair::Keypair;
+use modal_datastore::models::miner::MinerCheckpoint;
+use modal_datastore::models::ValidatorBlock;
+use modal_datastore::DatastoreManager;
+use modal_validator_consensus::narwhal::{
+    Committee, ReconfigurationCertificate, ReconfigurationSignature, ReconfigurationVotes, Validator,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use crate::constants::RECONFIGURATION_LOOKBACK_ROUNDS;
+
+/// A validator set proposed to the running consensus loop
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidatorSetChange {
+    /// Epoch the validator set operates in; the one after the latest
+    /// committed committee's if unset, as for static validator lists
+    pub epoch: Option<u64>,
+    pub validators: Vec<String>,
+    /// One per validator; equal stakes if empty
+    pub stakes: Vec<u64>,
+}
+
+/// Handle to a running consensus loop
+pub struct ConsensusHandle {
+    reconfig_tx: mpsc::UnboundedSender<ValidatorSetChange>,
+}
+
+impl ConsensusHandle {
+    pub fn new(reconfig_tx: mpsc::UnboundedSender<ValidatorSetChange>) -> Self {
+        Self { reconfig_tx }
+    }
+
+    /// Whether the loop is still running; it stops once this node retires
+    pub fn is_running(&self) -> bool {
+        !self.reconfig_tx.is_closed()
+    }
+
+    /// Propose a new validator set to the loop. Returns false if it has stopped.
+    pub fn reconfigure(&self, change: ValidatorSetChange) -> bool {
+        self.reconfig_tx.send(change).is_ok()
+    }
+}
+
+/// The committee of `validators`, in the order validators are configured
+pub fn committee_validators(validators: &[String], stakes: &[u64]) -> Result<Vec<Validator>> {
+    let config = modal_validator::ShoalValidatorConfig::from_peer_ids_with_stakes(validators.to_vec(), stakes.to_vec(), 0)?;
+    Ok(config
+        .committee
+        .validator_order
+        .iter()
+        .filter_map(|key| config.committee.get_validator(key).cloned())
+        .collect())
+}
+
+/// Reconfiguration messages carried in draft blocks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReconfigurationEvent {
+    /// A member's vote for the committee of `epoch`
+    ReconfigurationVote {
+        epoch: u64,
+        validators: Vec<Validator>,
+        vote: ReconfigurationSignature,
+    },
+    /// A quorum's certificate, committed in the round of the block carrying it
+    ReconfigurationCertificate { certificate: ReconfigurationCertificate },
+}
+
+impl ReconfigurationEvent {
+    /// Epoch of the committee the event is about
+    pub fn epoch(&self) -> u64 {
+        match self {
+            ReconfigurationEvent::ReconfigurationVote { epoch, .. } => *epoch,
+            ReconfigurationEvent::ReconfigurationCertificate { certificate } => certificate.epoch,
+        }
+    }
+
+    /// The reconfiguration events among a block's events
+    pub fn in_block(block: &ValidatorBlock) -> Vec<Self> {
+        block
+            .events
+            .iter()
+            .filter_map(|event| serde_json::from_value(event.clone()).ok())
+            .collect()
+    }
+}
+
+/// The loop's committee and the change on its way to replacing it
+pub struct Reconfiguration {
+    epoch: u64,
+    committee: Committee,
+    votes: ReconfigurationVotes,
+    /// Our votes and certificates, carried in our drafts until a change for
+    /// their epoch is committed
+    outbox: Vec<ReconfigurationEvent>,
+    /// Committed change waiting for its activation round
+    pending: Option<ReconfigurationCertificate>,
+}
+
+impl Reconfiguration {
+    pub fn new(epoch: u64, committee: Committee) -> Self {
+        Self {
+            epoch,
+            committee,
+            votes: ReconfigurationVotes::default(),
+            outbox: Vec::new(),
+            pending: None,
+        }
+    }
+
+    /// Epoch of the active committee
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Latest committed epoch, active or pending
+    fn committed_epoch(&self) -> u64 {
+        self.pending.as_ref().map_or(self.epoch, |pending| pending.epoch)
+    }
+
+    /// Sign our vote for `change` and carry it in our drafts. Returns the
+    /// epoch voted for, or `None` if a committee for it is already committed.
+    pub fn propose(&mut self, change: ValidatorSetChange, keypair: &Keypair) -> Result<Option<u64>> {
+        let epoch = change.epoch.unwrap_or(self.committed_epoch() + 1);
+        if epoch <= self.committed_epoch() {
+            return Ok(None);
+        }
+        let validators = committee_validators(&change.validators, &change.stakes)?;
+        let vote = ReconfigurationSignature::sign(keypair, epoch, &validators)?;
+        // A newer proposal replaces our earlier votes
+        self.outbox
+            .retain(|event| !matches!(event, ReconfigurationEvent::ReconfigurationVote { .. }));
+        self.outbox.push(ReconfigurationEvent::ReconfigurationVote { epoch, validators, vote });
+        Ok(Some(epoch))
+    }
+
+    /// Events for our next draft
+    pub fn draft_events(&self) -> Vec<serde_json::Value> {
+        self.outbox
+            .iter()
+            .filter_map(|event| serde_json::to_value(event).ok())
+            .collect()
+    }
+
+    /// Read the reconfiguration events of a certified block. Votes count
+    /// towards a certificate while no change is pending; certificates are
+    /// committed in the block's round. Returns the changes committed.
+    pub fn on_certified(&mut self, block: &ValidatorBlock) -> Vec<ReconfigurationCertificate> {
+        let mut committed = Vec::new();
+        for event in ReconfigurationEvent::in_block(block) {
+            match event {
+                ReconfigurationEvent::ReconfigurationVote { epoch, validators, vote } => {
+                    if self.pending.is_some() || epoch <= self.epoch {
+                        continue;
+                    }
+                    if let Some(certificate) = self.votes.add(epoch, &validators, vote, &self.committee) {
+                        self.outbox.push(ReconfigurationEvent::ReconfigurationCertificate { certificate });
+                    }
+                }
+                ReconfigurationEvent::ReconfigurationCertificate { certificate } => {
+                    let certificate = certificate.committed_in(block.round_id);
+                    match self.schedule(certificate.clone()) {
+                        Ok(()) => committed.push(certificate),
+                        Err(e) => log::debug!("Ignoring reconfiguration in round {}: {}", block.round_id, e),
+                    }
+                }
+            }
+        }
+        committed
+    }
+
+    /// Schedule a committed change. The same change committed in two rounds
+    /// counts from the earlier one.
+    fn schedule(&mut self, certificate: ReconfigurationCertificate) -> Result<()> {
+        if let Some(pending) = &self.pending {
+            if certificate.epoch != pending.epoch || certificate.round >= pending.round {
+                bail!("a change for epoch {} is already committed", pending.epoch);
+            }
+        }
+        if certificate.epoch <= self.epoch {
+            bail!("stale reconfiguration for epoch {} (at epoch {})", certificate.epoch, self.epoch);
+        }
+        certificate.verify(&self.committee)?;
+
+        self.votes = ReconfigurationVotes::default();
+        self.outbox.retain(|event| event.epoch() > certificate.epoch);
+        self.pending = Some(certificate);
+        Ok(())
+    }
+
+    /// Take the committed change if it activates by `round`; its committee
+    /// becomes the active one
+    pub fn take_due(&mut self, round: u64) -> Option<ReconfigurationCertificate> {
+        if self.pending.as_ref()?.activation_round() > round {
+            return None;
+        }
+        let certificate = self.pending.take()?;
+        self.epoch = certificate.epoch;
+        self.committee = certificate.committee();
+        Some(certificate)
+    }
+}
+
+/// Where a joining validator picks up the consensus
+#[derive(Debug, Clone)]
+pub struct StateHandoff {
+    /// Rounds up to this one are left to the current committee
+    pub start_after_round: u64,
+    /// Latest checkpoint the current committee created
+    pub last_checkpoint: Option<MinerCheckpoint>,
+    /// The committed change that seats this node
+    pub reconfiguration: ReconfigurationCertificate,
+}
+
+impl StateHandoff {
+    /// Peer IDs of the committee being joined
+    pub fn validator_ids(&self) -> Vec<String> {
+        self.reconfiguration
+            .validators
+            .iter()
+            .map(|validator| validator.public_key.to_string())
+            .collect()
+    }
+
+    /// Stakes of the committee being joined, in the same order
+    pub fn stakes(&self) -> Vec<u64> {
+        self.reconfiguration.validators.iter().map(|validator| validator.stake).collect()
+    }
+
+    /// The handoff for the joining validator's consensus state
+    pub fn narwhal(&self) -> modal_validator_consensus::narwhal::StateHandoff {
+        modal_validator_consensus::narwhal::StateHandoff {
+            epoch: self.reconfiguration.epoch,
+            activation_round: self.reconfiguration.activation_round(),
+            validators: self.reconfiguration.validators.clone(),
+            tips: Vec::new(),
+            last_checkpoint_round: None,
+        }
+    }
+}
+
+/// Build the handoff for `peer_id` joining the validator set from the
+/// certified rounds it has observed. Returns `None` if it hasn't observed
+/// any, so there's no committee to hand over from, and fails unless the
+/// latest committed reconfiguration seats it.
+pub async fn load_state_handoff(mgr: &DatastoreManager, peer_id: &str) -> Result<Option<StateHandoff>> {
+    let Some(latest_round) = ValidatorBlock::find_latest_certified_round_multi(mgr).await? else {
+        return Ok(None);
+    };
+
+    // The latest epoch committed, from the earliest round that carries it
+    let mut latest: Option<ReconfigurationCertificate> = None;
+    for round in latest_round.saturating_sub(RECONFIGURATION_LOOKBACK_ROUNDS)..=latest_round {
+        for block in ValidatorBlock::find_certified_in_round_multi(mgr, round).await? {
+            for event in ReconfigurationEvent::in_block(&block) {
+                let ReconfigurationEvent::ReconfigurationCertificate { certificate } = event else {
+                    continue;
+                };
+                if certificate.signers().is_empty() {
+                    continue;
+                }
+                if latest.as_ref().is_none_or(|latest| certificate.epoch > latest.epoch) {
+                    latest = Some(certificate.committed_in(round));
+                }
+            }
+        }
+    }
+
+    let reconfiguration = latest.ok_or_else(|| {
+        anyhow!("no committed reconfiguration in the last {} certified rounds", RECONFIGURATION_LOOKBACK_ROUNDS)
+    })?;
+    if !reconfiguration.validators.iter().any(|validator| validator.public_key.to_string() == peer_id) {
+        bail!("the committee of epoch {} doesn't seat this node", reconfiguration.epoch);
+    }
+    Ok(Some(StateHandoff {
+        start_after_round: reconfiguration.activation_round() - 1,
+        last_checkpoint: MinerCheckpoint::find_latest_multi(mgr).await?,
+        reconfiguration,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use modal_validator_consensus::narwhal::ACTIVATION_DELAY;
+
+    fn keypairs(n: usize) -> Vec<Keypair> {
+        (0..n).map(|_| Keypair::generate().unwrap()).collect()
+    }
+
+    fn ids(keypairs: &[Keypair]) -> Vec<String> {
+        keypairs.iter().map(|keypair| keypair.as_public_address()).collect()
+    }
+
+    fn certified_block(keypair: &Keypair, round: u64, events: Vec<serde_json::Value>) -> ValidatorBlock {
+        let mut block = super::super::consensus::create_validator_block(
+            &keypair.as_public_address(),
+            round,
+            Default::default(),
+            events,
+            keypair,
+        )
+        .unwrap();
+        block.cert = Some("cert".to_string());
+        block
+    }
+
+    #[test]
+    fn test_reconfiguration_commits_after_quorum_and_activates_after_delay() {
+        let members = keypairs(4);
+        let joiner = keypairs(1).remove(0);
+        let committee = Committee::new(committee_validators(&ids(&members), &[]).unwrap());
+        let mut reconfiguration = Reconfiguration::new(3, committee);
+        let mut new_set = ids(&members[..3]);
+        new_set.push(joiner.as_public_address());
+        let change = ValidatorSetChange { epoch: Some(4), validators: new_set, stakes: Vec::new() };
+
+        // Each member votes in its drafts; three certified votes make a certificate
+        let mut round = 10;
+        for member in &members[..3] {
+            let mut voter = Reconfiguration::new(3, Committee::new(committee_validators(&ids(&members), &[]).unwrap()));
+            assert_eq!(voter.propose(change.clone(), member).unwrap(), Some(4));
+            assert!(reconfiguration.on_certified(&certified_block(member, round, voter.draft_events())).is_empty());
+            round += 1;
+        }
+        let events = reconfiguration.draft_events();
+        assert_eq!(events.len(), 1);
+
+        // The certificate is committed in the round of the block carrying it
+        let committed = reconfiguration.on_certified(&certified_block(&members[0], 20, events.clone()));
+        assert_eq!(committed.len(), 1);
+        assert_eq!(committed[0].activation_round(), 20 + ACTIVATION_DELAY);
+        assert!(reconfiguration.draft_events().is_empty());
+        // Carried again in a later round, it doesn't move the activation
+        assert!(reconfiguration.on_certified(&certified_block(&members[1], 21, events)).is_empty());
+
+        assert!(reconfiguration.take_due(21).is_none());
+        let active = reconfiguration.take_due(22).unwrap();
+        assert_eq!(active.epoch, 4);
+        assert_eq!(reconfiguration.epoch(), 4);
+        assert!(active.committee().contains(&joiner.as_public_address().parse().unwrap()));
+        assert!(reconfiguration.take_due(23).is_none());
+    }
+
+    #[test]
+    fn test_reconfiguration_ignores_outside_and_stale_votes() {
+        let members = keypairs(4);
+        let outsiders = keypairs(3);
+        let committee = Committee::new(committee_validators(&ids(&members), &[]).unwrap());
+        let mut reconfiguration = Reconfiguration::new(3, committee.clone());
+        let change = ValidatorSetChange { epoch: None, validators: ids(&outsiders), stakes: Vec::new() };
+
+        for (round, outsider) in outsiders.iter().enumerate() {
+            let mut voter = Reconfiguration::new(3, committee.clone());
+            assert_eq!(voter.propose(change.clone(), outsider).unwrap(), Some(4));
+            reconfiguration.on_certified(&certified_block(outsider, round as u64, voter.draft_events()));
+        }
+        assert!(reconfiguration.draft_events().is_empty());
+
+        let stale = ValidatorSetChange { epoch: Some(3), ..change };
+        assert_eq!(reconfiguration.propose(stale, &members[0]).unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_state_handoff_starts_at_committed_activation() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        let members = keypairs(4);
+        let peer_id = members[3].as_public_address();
+        assert!(load_state_handoff(&mgr, &peer_id).await.unwrap().is_none());
+
+        certified_block(&members[0], 6, Vec::new()).save_to_active(&mgr).await.unwrap();
+        assert!(load_state_handoff(&mgr, &peer_id).await.is_err());
+
+        let validators = committee_validators(&ids(&members), &[]).unwrap();
+        let certificate = ReconfigurationCertificate {
+            epoch: 2,
+            round: 0,
+            signatures: members[..3]
+                .iter()
+                .map(|member| ReconfigurationSignature::sign(member, 2, &validators).unwrap())
+                .collect(),
+            validators,
+        };
+        let event = serde_json::to_value(ReconfigurationEvent::ReconfigurationCertificate { certificate }).unwrap();
+        certified_block(&members[1], 7, vec![event.clone()]).save_to_active(&mgr).await.unwrap();
+        certified_block(&members[2], 8, vec![event]).save_to_active(&mgr).await.unwrap();
+
+        let handoff = load_state_handoff(&mgr, &peer_id).await.unwrap().unwrap();
+        assert_eq!(handoff.start_after_round, 7 + ACTIVATION_DELAY - 1);
+        assert_eq!(handoff.narwhal().start_round(), 7 + ACTIVATION_DELAY);
+        assert_eq!(handoff.validator_ids().len(), 4);
+        assert!(handoff.last_checkpoint.is_none());
+
+        let outsider = Keypair::generate().unwrap().as_public_address();
+        assert!(load_state_handoff(&mgr, &outsider).await.is_err());
+    }
+}
@@ -0,0 +1,69 @@
+//! Anomaly monitor
+//!
+//! Runs the observer's anomaly checks on each new canonical block so
+//! suspicious timestamps, difficulty drops and nomination concentration show
+//! up in the logs and on the status page.
+
+use modal_datastore::models::MinerBlock;
+use modal_datastore::DatastoreManager;
+use modal_observer::{AnomalyConfig, AnomalyDetector};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, Mutex};
+
+use crate::bootstrapper_health::unix_now;
+use crate::constants::ANOMALY_CHECK_INTERVAL_SECS;
+
+/// Anomaly detector shared between the monitor and the status server
+pub type SharedAnomalyDetector = Arc<Mutex<AnomalyDetector>>;
+
+pub fn create_shared_detector(config: AnomalyConfig) -> SharedAnomalyDetector {
+    Arc::new(Mutex::new(AnomalyDetector::new(config)))
+}
+
+/// Spawn a task that checks canonical blocks above the current tip as they
+/// arrive, until shutdown
+pub fn start_anomaly_monitor(
+    datastore_manager: Arc<Mutex<DatastoreManager>>,
+    detector: SharedAnomalyDetector,
+    mut shutdown_rx: broadcast::Receiver<()>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(ANOMALY_CHECK_INTERVAL_SECS));
+        // Blocks already on disk at startup were checked (or synced) before
+        let mut checked_up_to: Option<u64> = None;
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => break,
+                _ = interval.tick() => {
+                    let blocks = {
+                        let mgr = datastore_manager.lock().await;
+                        MinerBlock::find_all_canonical_multi(&mgr).await
+                    };
+                    let blocks = match blocks {
+                        Ok(blocks) => blocks,
+                        Err(e) => {
+                            log::warn!("Anomaly monitor failed to load canonical blocks: {}", e);
+                            continue;
+                        }
+                    };
+                    let Some(tip) = blocks.last().map(|b| b.index) else {
+                        continue;
+                    };
+                    let Some(last_checked) = checked_up_to else {
+                        checked_up_to = Some(tip);
+                        continue;
+                    };
+                    let mut detector = detector.lock().await;
+                    let history_len = detector.config().history_len();
+                    let now = unix_now();
+                    for (position, block) in blocks.iter().enumerate().filter(|(_, b)| b.index > last_checked) {
+                        let history = &blocks[position.saturating_sub(history_len)..position];
+                        detector.check_block(block, history, now);
+                    }
+                    checked_up_to = Some(tip);
+                }
+            }
+        }
+    })
+}
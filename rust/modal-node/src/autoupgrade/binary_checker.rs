@@ -28,7 +28,7 @@ pub async fn fetch_manifest(base_url: &str, branch: &str) -> Result<Manifest> {
     
     log::debug!("Fetching manifest from: {}", manifest_url);
     
-    let response = reqwest::get(&manifest_url)
+    let response = crate::proxy::http_get(&manifest_url)
         .await
         .context("Failed to fetch manifest")?;
     
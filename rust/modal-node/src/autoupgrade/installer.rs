@@ -26,7 +26,7 @@ fn detect_platform() -> Result<String> {
 async fn download_binary(url: &str, dest_path: &PathBuf) -> Result<()> {
     log::info!("Downloading binary from: {}", url);
     
-    let response = reqwest::get(url)
+    let response = crate::proxy::http_get(url)
         .await
         .context("Failed to download binary")?;
     
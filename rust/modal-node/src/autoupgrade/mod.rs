@@ -1,16 +1,21 @@
 pub mod binary_checker;
 pub mod installer;
+pub mod rollout;
 pub mod self_replace;
 
 use anyhow::{Context, Result};
 use std::time::Duration;
 use tokio::sync::broadcast;
 
+use crate::bootstrapper_health::unix_now;
 use crate::config::Config;
+use rollout::{AutoupgradeStatus, RolloutPolicy, RolloutStage, SharedAutoupgradeStatus, UpgradeWindow};
 
 const DEFAULT_CHECK_INTERVAL_SECS: u64 = 3600;
 const DEFAULT_BASE_URL: &str = "http://get.modal.money";
 const DEFAULT_BRANCH: &str = "testnet";
+const DEFAULT_CANARY_PERCENT: u8 = 100;
+const DEFAULT_ROLLOUT_DELAY_SECS: u64 = 6 * 3600;
 
 /// Configuration for autoupgrade
 #[derive(Debug, Clone)]
@@ -19,14 +24,16 @@ pub struct AutoupgradeConfig {
     pub base_url: String,
     pub branch: String,
     pub check_interval: Duration,
+    pub rollout: RolloutPolicy,
+    pub bucket: u8,
 }
 
 impl AutoupgradeConfig {
-    pub fn from_node_config(config: &Config) -> Option<Self> {
+    pub fn from_node_config(config: &Config, peer_id: &str) -> Result<Option<Self>> {
         let enabled = config.autoupgrade_enabled.unwrap_or(false);
         
         if !enabled {
-            return None;
+            return Ok(None);
         }
 
         let base_url = config.autoupgrade_base_url.clone()
@@ -35,18 +42,52 @@ impl AutoupgradeConfig {
             .unwrap_or_else(|| DEFAULT_BRANCH.to_string());
         let check_interval_secs = config.autoupgrade_check_interval_secs.unwrap_or(DEFAULT_CHECK_INTERVAL_SECS);
 
-        Some(Self {
+        let canary_percent = config.autoupgrade_canary_percent.unwrap_or(DEFAULT_CANARY_PERCENT);
+        if canary_percent > 100 {
+            anyhow::bail!("autoupgrade_canary_percent must be between 0 and 100, got {}", canary_percent);
+        }
+        let windows = config
+            .autoupgrade_windows
+            .iter()
+            .flatten()
+            .map(|w| w.parse::<UpgradeWindow>())
+            .collect::<Result<Vec<_>>>()
+            .context("Invalid autoupgrade_windows")?;
+
+        Ok(Some(Self {
             enabled,
             base_url,
             branch,
             check_interval: Duration::from_secs(check_interval_secs),
-        })
+            rollout: RolloutPolicy {
+                canary_percent,
+                delay: Duration::from_secs(
+                    config.autoupgrade_rollout_delay_secs.unwrap_or(DEFAULT_ROLLOUT_DELAY_SECS),
+                ),
+                windows,
+            },
+            bucket: rollout::rollout_bucket(peer_id),
+        }))
     }
 }
 
+/// Create the shared autoupgrade status for a node
+pub fn create_shared_status(config: Option<&AutoupgradeConfig>) -> SharedAutoupgradeStatus {
+    let status = match config {
+        Some(config) => AutoupgradeStatus {
+            bucket: config.bucket,
+            canary: config.rollout.is_canary(config.bucket),
+            stage: RolloutStage::Disabled,
+        },
+        None => AutoupgradeStatus::default(),
+    };
+    std::sync::Arc::new(tokio::sync::RwLock::new(status))
+}
+
 /// Start the autoupgrade background task
 pub async fn start_autoupgrade_task(
     config: AutoupgradeConfig,
+    status: SharedAutoupgradeStatus,
     mut shutdown_rx: broadcast::Receiver<()>,
 ) -> Result<()> {
     log::info!(
@@ -55,6 +96,13 @@ pub async fn start_autoupgrade_task(
         config.branch,
         config.check_interval
     );
+    log::info!(
+        "Autoupgrade rollout: bucket {} ({}), delay {:?}, {} upgrade windows",
+        config.bucket,
+        if config.rollout.is_canary(config.bucket) { "canary" } else { "staged" },
+        config.rollout.delay,
+        config.rollout.windows.len()
+    );
 
     // Get the current version at startup
     let last_known_version = binary_checker::get_current_version(&config.base_url, &config.branch)
@@ -62,6 +110,10 @@ pub async fn start_autoupgrade_task(
         .context("Failed to get initial version")?;
     
     log::info!("Current version of 'modality': {}", last_known_version);
+    status.write().await.stage = RolloutStage::UpToDate { version: last_known_version.clone() };
+
+    // Release currently being rolled out and when this node first saw it
+    let mut pending: Option<(String, i64)> = None;
 
     let mut interval = tokio::time::interval(config.check_interval);
     interval.tick().await; // Skip the first immediate tick
@@ -75,7 +127,7 @@ pub async fn start_autoupgrade_task(
             _ = interval.tick() => {
                 log::debug!("Checking for updates at {}/{}", config.base_url, config.branch);
                 
-                match check_and_upgrade(&config, &last_known_version).await {
+                match check_and_upgrade(&config, &last_known_version, &mut pending, &status).await {
                     Ok(Some(new_version)) => {
                         log::info!("Upgrade initiated to version: {}", new_version);
                         // The upgrade process will replace this binary and restart
@@ -97,25 +149,52 @@ pub async fn start_autoupgrade_task(
     Ok(())
 }
 
-/// Check for updates and upgrade if available
+/// Check for updates and upgrade if available and allowed by the rollout policy
 /// Returns Some(new_version) if an upgrade was performed, None if no upgrade needed
 async fn check_and_upgrade(
     config: &AutoupgradeConfig,
     last_known_version: &str,
+    pending: &mut Option<(String, i64)>,
+    status: &SharedAutoupgradeStatus,
 ) -> Result<Option<String>> {
     let latest_version = binary_checker::get_current_version(&config.base_url, &config.branch)
         .await
         .context("Failed to check for updates")?;
 
     if latest_version == last_known_version {
+        *pending = None;
+        status.write().await.stage = RolloutStage::UpToDate { version: latest_version };
         return Ok(None);
     }
 
-    log::info!(
-        "New version detected: {} -> {}",
-        last_known_version,
-        latest_version
-    );
+    let now = unix_now();
+    let first_seen = match pending {
+        Some((version, first_seen)) if *version == latest_version => *first_seen,
+        _ => {
+            log::info!(
+                "New version detected: {} -> {}",
+                last_known_version,
+                latest_version
+            );
+            *pending = Some((latest_version.clone(), now));
+            now
+        }
+    };
+
+    let stage = config.rollout.stage(config.bucket, &latest_version, first_seen, now);
+    status.write().await.stage = stage.clone();
+
+    match stage {
+        RolloutStage::Upgrading { .. } => {}
+        RolloutStage::Waiting { eligible_at, .. } => {
+            log::info!("Version {} staged for rollout; eligible in {}s", latest_version, eligible_at - now);
+            return Ok(None);
+        }
+        _ => {
+            log::info!("Version {} eligible but outside upgrade windows", latest_version);
+            return Ok(None);
+        }
+    }
 
     log::info!("Starting upgrade process...");
     
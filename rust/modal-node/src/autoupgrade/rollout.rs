@@ -0,0 +1,218 @@
+//! Staged rollout of upgrades.
+//!
+//! Each node is assigned a stable bucket (0-99) derived from its peer id. Nodes
+//! whose bucket falls under the canary percentage upgrade as soon as a new
+//! release is seen; everyone else waits for the rollout delay. Upgrades are
+//! only installed inside the configured UTC windows, so a bad release doesn't
+//! restart the whole network at once.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+/// Deterministic rollout bucket (0-99) for a peer id
+pub fn rollout_bucket(peer_id: &str) -> u8 {
+    let digest = Sha256::digest(peer_id.as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    (u64::from_be_bytes(bytes) % 100) as u8
+}
+
+/// A daily UTC time window, e.g. "02:00-04:00" (may wrap past midnight)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UpgradeWindow {
+    start_minute: u16,
+    end_minute: u16,
+}
+
+impl UpgradeWindow {
+    /// Whether the given minute of the day (UTC) falls inside this window
+    pub fn contains(&self, minute_of_day: u16) -> bool {
+        if self.start_minute < self.end_minute {
+            minute_of_day >= self.start_minute && minute_of_day < self.end_minute
+        } else {
+            minute_of_day >= self.start_minute || minute_of_day < self.end_minute
+        }
+    }
+}
+
+fn parse_hh_mm(s: &str) -> Result<u16> {
+    let (h, m) = s
+        .trim()
+        .split_once(':')
+        .ok_or_else(|| anyhow!("Expected HH:MM, got '{}'", s))?;
+    let h: u16 = h.parse().map_err(|_| anyhow!("Invalid hour in '{}'", s))?;
+    let m: u16 = m.parse().map_err(|_| anyhow!("Invalid minute in '{}'", s))?;
+    if h > 23 || m > 59 {
+        return Err(anyhow!("Time out of range: '{}'", s));
+    }
+    Ok(h * 60 + m)
+}
+
+impl FromStr for UpgradeWindow {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| anyhow!("Upgrade window must look like HH:MM-HH:MM, got '{}'", s))?;
+        let start_minute = parse_hh_mm(start)?;
+        let end_minute = parse_hh_mm(end)?;
+        if start_minute == end_minute {
+            return Err(anyhow!("Upgrade window '{}' is empty", s));
+        }
+        Ok(Self { start_minute, end_minute })
+    }
+}
+
+/// When a node is allowed to install a newly seen release
+#[derive(Debug, Clone, PartialEq)]
+pub struct RolloutPolicy {
+    /// Buckets below this percentage upgrade immediately
+    pub canary_percent: u8,
+    /// How long non-canary nodes wait after a release is first seen
+    pub delay: Duration,
+    /// Allowed UTC windows; empty means any time
+    pub windows: Vec<UpgradeWindow>,
+}
+
+impl RolloutPolicy {
+    pub fn is_canary(&self, bucket: u8) -> bool {
+        bucket < self.canary_percent
+    }
+
+    /// Unix timestamp at which a release first seen at `first_seen` becomes eligible
+    pub fn eligible_at(&self, bucket: u8, first_seen: i64) -> i64 {
+        if self.is_canary(bucket) {
+            first_seen
+        } else {
+            first_seen + self.delay.as_secs() as i64
+        }
+    }
+
+    /// Whether `unix_secs` falls inside one of the allowed windows
+    pub fn in_window(&self, unix_secs: i64) -> bool {
+        if self.windows.is_empty() {
+            return true;
+        }
+        let minute_of_day = (unix_secs.rem_euclid(86_400) / 60) as u16;
+        self.windows.iter().any(|w| w.contains(minute_of_day))
+    }
+
+    /// Rollout stage for `version`, first seen at `first_seen`, evaluated at `now`
+    pub fn stage(&self, bucket: u8, version: &str, first_seen: i64, now: i64) -> RolloutStage {
+        let eligible_at = self.eligible_at(bucket, first_seen);
+        if now < eligible_at {
+            RolloutStage::Waiting { version: version.to_string(), eligible_at }
+        } else if !self.in_window(now) {
+            RolloutStage::OutsideWindow { version: version.to_string() }
+        } else {
+            RolloutStage::Upgrading { version: version.to_string() }
+        }
+    }
+}
+
+/// Where this node is in the rollout of the latest release
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "stage", rename_all = "snake_case")]
+pub enum RolloutStage {
+    #[default]
+    Disabled,
+    UpToDate { version: String },
+    Waiting { version: String, eligible_at: i64 },
+    OutsideWindow { version: String },
+    Upgrading { version: String },
+}
+
+/// Autoupgrade state exposed on the status page
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AutoupgradeStatus {
+    pub bucket: u8,
+    pub canary: bool,
+    pub stage: RolloutStage,
+}
+
+impl AutoupgradeStatus {
+    /// Human-readable description for the status page
+    pub fn describe(&self, now: i64) -> String {
+        let group = if self.canary {
+            format!("canary, bucket {}", self.bucket)
+        } else {
+            format!("bucket {}", self.bucket)
+        };
+        match &self.stage {
+            RolloutStage::Disabled => "disabled".to_string(),
+            RolloutStage::UpToDate { version } => format!("up to date ({}) · {}", version, group),
+            RolloutStage::Waiting { version, eligible_at } => {
+                let remaining = (eligible_at - now).max(0);
+                format!(
+                    "{} staged, eligible in {}h {}m · {}",
+                    version,
+                    remaining / 3600,
+                    (remaining % 3600) / 60,
+                    group
+                )
+            }
+            RolloutStage::OutsideWindow { version } => {
+                format!("{} eligible, waiting for upgrade window · {}", version, group)
+            }
+            RolloutStage::Upgrading { version } => format!("upgrading to {} · {}", version, group),
+        }
+    }
+}
+
+/// Wrapper for thread-safe access to autoupgrade status
+pub type SharedAutoupgradeStatus = Arc<RwLock<AutoupgradeStatus>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(canary_percent: u8, windows: &[&str]) -> RolloutPolicy {
+        RolloutPolicy {
+            canary_percent,
+            delay: Duration::from_secs(3600),
+            windows: windows.iter().map(|w| w.parse().unwrap()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_rollout_bucket_is_stable_and_in_range() {
+        let peer_id = "12D3KooWBGR3m1JmVFm2aZYR7TZXicjA7HSVSWi2fama5cPpgQiX";
+        assert_eq!(rollout_bucket(peer_id), rollout_bucket(peer_id));
+        for i in 0..200 {
+            assert!(rollout_bucket(&format!("peer-{}", i)) < 100);
+        }
+    }
+
+    #[test]
+    fn test_window_parsing_and_wraparound() {
+        let window: UpgradeWindow = "22:30-02:00".parse().unwrap();
+        assert!(window.contains(23 * 60));
+        assert!(window.contains(60));
+        assert!(!window.contains(12 * 60));
+        assert!("25:00-02:00".parse::<UpgradeWindow>().is_err());
+        assert!("02:00-02:00".parse::<UpgradeWindow>().is_err());
+        assert!("0200".parse::<UpgradeWindow>().is_err());
+    }
+
+    #[test]
+    fn test_stage_progression() {
+        let p = policy(10, &["02:00-04:00"]);
+        let first_seen = 2 * 3600; // 02:00 UTC on day 0
+
+        // Canary bucket upgrades immediately inside the window
+        assert!(matches!(p.stage(5, "v2", first_seen, first_seen), RolloutStage::Upgrading { .. }));
+
+        // Non-canary bucket waits for the delay, then upgrades while the window is still open
+        assert!(matches!(p.stage(50, "v2", first_seen, first_seen + 60), RolloutStage::Waiting { .. }));
+        assert!(matches!(p.stage(50, "v2", first_seen, first_seen + 3600), RolloutStage::Upgrading { .. }));
+
+        // Past the window it has to wait for the next one
+        assert!(matches!(p.stage(50, "v2", first_seen, first_seen + 3 * 3600), RolloutStage::OutsideWindow { .. }));
+    }
+}
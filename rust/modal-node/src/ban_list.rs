@@ -0,0 +1,518 @@
+//! IP and subnet bans
+//!
+//! Peer ID ignores (see `Node::ignore_peer`) expire and are dodged with a new
+//! key, so operators can also ban addresses. Rules are kept in a JSON file,
+//! `bans.json` in the data directory unless `ban_list_path` says otherwise,
+//! which `modal node ban` edits and the running node rereads every
+//! `BAN_LIST_RELOAD_SECS`. The swarm's `Behaviour` refuses connections to and
+//! from banned addresses before any handshake, and closes established ones
+//! when a rule is added. A peer that sends `AUTO_BAN_STRIKES` invalid
+//! messages within `AUTO_BAN_WINDOW_SECS` has its address banned for
+//! `AUTO_BAN_SECS`. `export_rules` renders the rules for nftables or
+//! iptables, for operators who would rather drop the traffic at the host.
+
+use anyhow::{anyhow, Context as _, Result};
+use ipnet::IpNet;
+use libp2p::core::transport::PortUse;
+use libp2p::core::Endpoint;
+use libp2p::multiaddr::Protocol;
+use libp2p::swarm::{
+    dummy, CloseConnection, ConnectionDenied, ConnectionId, FromSwarm, NetworkBehaviour, THandler,
+    THandlerInEvent, THandlerOutEvent, ToSwarm,
+};
+use libp2p::{Multiaddr, PeerId};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use crate::constants::{
+    AUTO_BAN_SECS, AUTO_BAN_STRIKES, AUTO_BAN_WINDOW_SECS, BAN_LIST_RELOAD_SECS, PEER_SCORE_INVALID_MESSAGE,
+};
+use crate::swarm::NodeSwarm;
+use crate::swarm_driver::SwarmHandle;
+
+/// File name of the ban list in the data directory
+pub const BAN_LIST_FILE: &str = "bans.json";
+
+/// A banned address or subnet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BanRule {
+    /// Subnet in CIDR notation; a single address is a /32 or /128
+    pub subnet: String,
+    pub reason: String,
+    /// Unix seconds
+    pub created_at: i64,
+    /// Unix seconds; never if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+    /// Whether the node added it for invalid data, rather than an operator
+    #[serde(default)]
+    pub automatic: bool,
+}
+
+impl BanRule {
+    fn net(&self) -> Option<IpNet> {
+        self.subnet.parse().ok()
+    }
+
+    fn is_active(&self, now: i64) -> bool {
+        self.expires_at.is_none_or(|expires_at| now < expires_at)
+    }
+}
+
+/// Ban rules, plus recent invalid-data strikes by address
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BanList {
+    pub rules: Vec<BanRule>,
+    #[serde(skip)]
+    strikes: HashMap<IpAddr, Vec<i64>>,
+}
+
+pub type SharedBanList = Arc<RwLock<BanList>>;
+
+impl BanList {
+    /// Read a ban list file; a missing file is an empty list
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read(path) {
+            Ok(data) => serde_json::from_slice(&data).with_context(|| format!("Malformed ban list {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read ban list {}", path.display())),
+        }
+    }
+
+    /// Write the list, replacing the file in one step
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Add a rule, replacing any for the same subnet
+    pub fn ban(&mut self, rule: BanRule) {
+        self.rules.retain(|existing| existing.subnet != rule.subnet);
+        self.rules.push(rule);
+    }
+
+    /// Remove the rule for `subnet`, returning whether there was one
+    pub fn unban(&mut self, subnet: &str) -> Result<bool> {
+        let subnet = parse_subnet(subnet)?.to_string();
+        let before = self.rules.len();
+        self.rules.retain(|rule| rule.subnet != subnet);
+        Ok(self.rules.len() < before)
+    }
+
+    /// The active rule banning `ip`, if any
+    pub fn banned(&self, ip: IpAddr, now: i64) -> Option<&BanRule> {
+        self.active(now).find(|rule| rule.net().is_some_and(|net| net.contains(&ip)))
+    }
+
+    /// Rules that haven't expired
+    pub fn active(&self, now: i64) -> impl Iterator<Item = &BanRule> {
+        self.rules.iter().filter(move |rule| rule.is_active(now))
+    }
+
+    /// Drop expired rules and strikes, returning whether any rule went
+    pub fn prune_expired(&mut self, now: i64) -> bool {
+        let before = self.rules.len();
+        self.rules.retain(|rule| rule.is_active(now));
+        self.strikes.retain(|_, times| {
+            times.retain(|&at| now - at < AUTO_BAN_WINDOW_SECS);
+            !times.is_empty()
+        });
+        self.rules.len() < before
+    }
+
+    /// Count an invalid message from `ip`, banning it once it has sent
+    /// `AUTO_BAN_STRIKES` within `AUTO_BAN_WINDOW_SECS`
+    pub fn strike(&mut self, ip: IpAddr, reason: &str, now: i64) -> Option<BanRule> {
+        if self.banned(ip, now).is_some() {
+            return None;
+        }
+        let times = self.strikes.entry(ip).or_default();
+        times.retain(|&at| now - at < AUTO_BAN_WINDOW_SECS);
+        times.push(now);
+        if times.len() < AUTO_BAN_STRIKES {
+            return None;
+        }
+        self.strikes.remove(&ip);
+        let rule = BanRule {
+            subnet: IpNet::from(ip).to_string(),
+            reason: format!("{} invalid messages, the last: {}", AUTO_BAN_STRIKES, reason),
+            created_at: now,
+            expires_at: Some(now + AUTO_BAN_SECS),
+            automatic: true,
+        };
+        self.ban(rule.clone());
+        Some(rule)
+    }
+}
+
+/// Parse an address, subnet or multiaddr (`/ip4/1.2.3.4/tcp/...`) into the
+/// subnet it names
+pub fn parse_subnet(s: &str) -> Result<IpNet> {
+    if s.starts_with('/') {
+        let addr: Multiaddr = s.parse()?;
+        return ip_of(&addr).map(IpNet::from).ok_or_else(|| anyhow!("{} has no IP address", s));
+    }
+    if let Ok(ip) = s.parse::<IpAddr>() {
+        return Ok(IpNet::from(ip));
+    }
+    let net: IpNet = s.parse().map_err(|_| anyhow!("{} is not an IP address or subnet", s))?;
+    Ok(net.trunc())
+}
+
+/// The IP address in a multiaddr, if it has one
+pub fn ip_of(addr: &Multiaddr) -> Option<IpAddr> {
+    addr.iter().find_map(|protocol| match protocol {
+        Protocol::Ip4(ip) => Some(IpAddr::V4(ip)),
+        Protocol::Ip6(ip) => Some(IpAddr::V6(ip)),
+        _ => None,
+    })
+}
+
+/// Where a node keeps its ban list: `ban_list_path`, else `bans.json` in
+/// its data directory. Nodes without either keep bans in memory only.
+pub fn path_for(config: &crate::config::Config) -> Option<PathBuf> {
+    config.ban_list_path.clone().or_else(|| {
+        config
+            .data_dir
+            .as_ref()
+            .or(config.storage_path.as_ref())
+            .map(|dir| dir.join(BAN_LIST_FILE))
+    })
+}
+
+/// Count an invalid message from `peer_id` against its peer score and the
+/// address it is connected from, saving and enforcing the ban that earns
+/// it, if any
+pub async fn strike_peer(swarm: &SwarmHandle, path: Option<&Path>, peer_id: &PeerId, reason: &str) {
+    let (path, peer_id, reason) = (path.map(Path::to_path_buf), *peer_id, reason.to_string());
+    let struck = swarm.run(move |swarm| strike(swarm, path.as_deref(), &peer_id, &reason)).await;
+    if struck.is_err() {
+        log::warn!("Could not strike peer {}: swarm driver stopped", peer_id);
+    }
+}
+
+fn strike(swarm: &mut NodeSwarm, path: Option<&Path>, peer_id: &PeerId, reason: &str) {
+    swarm.behaviour_mut().peer_slots.adjust_score(peer_id, -PEER_SCORE_INVALID_MESSAGE);
+    let Some(ip) = swarm.behaviour().ban_list.remote_ip(peer_id) else {
+        return;
+    };
+    let now = crate::bootstrapper_health::unix_now();
+    let bans = swarm.behaviour().ban_list.shared();
+    let Some(rule) = bans.write().unwrap().strike(ip, reason, now) else {
+        return;
+    };
+    log::warn!("Banning {} (peer {}): {}", rule.subnet, peer_id, rule.reason);
+    if let Some(path) = path {
+        if let Err(e) = bans.read().unwrap().save(path) {
+            log::warn!("Failed to save ban list: {}", e);
+        }
+    }
+    swarm.behaviour_mut().ban_list.enforce(now);
+}
+
+/// Spawn a task that rereads the ban list file, so rules added with
+/// `modal node ban` take effect, and drops connections they now ban
+pub fn start_ban_list_reload(
+    swarm: SwarmHandle,
+    path: Option<PathBuf>,
+    mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(BAN_LIST_RELOAD_SECS));
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => break,
+                _ = interval.tick() => {
+                    let now = crate::bootstrapper_health::unix_now();
+                    let loaded = match &path {
+                        Some(path) => match BanList::load(path) {
+                            Ok(loaded) => Some(loaded),
+                            Err(e) => {
+                                log::warn!("Failed to reload ban list: {}", e);
+                                None
+                            }
+                        },
+                        None => None,
+                    };
+                    let reloaded = swarm.run(move |swarm| {
+                        let bans = swarm.behaviour().ban_list.shared();
+                        {
+                            let mut bans = bans.write().unwrap();
+                            if let Some(loaded) = loaded {
+                                bans.rules = loaded.rules;
+                            }
+                            bans.prune_expired(now);
+                        }
+                        swarm.behaviour_mut().ban_list.enforce(now);
+                    }).await;
+                    if reloaded.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Firewall rule syntax for `export_rules`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum FirewallFormat {
+    Nftables,
+    Iptables,
+}
+
+/// Render the active rules as a firewall ruleset that drops traffic from
+/// banned addresses
+pub fn export_rules(list: &BanList, format: FirewallFormat, now: i64) -> String {
+    let nets: Vec<(IpNet, &BanRule)> = list.active(now).filter_map(|rule| Some((rule.net()?, rule))).collect();
+    let mut out = String::new();
+    match format {
+        FirewallFormat::Nftables => {
+            let elements = |v6: bool| -> Vec<String> {
+                nets.iter()
+                    .filter(|(net, _)| matches!(net, IpNet::V6(_)) == v6)
+                    .map(|(net, _)| net.to_string())
+                    .collect()
+            };
+            out.push_str("#!/usr/sbin/nft -f\n");
+            out.push_str("# Generated by modal node bans; reload with nft -f\n");
+            out.push_str("table inet modal_bans\n");
+            out.push_str("delete table inet modal_bans\n");
+            out.push_str("table inet modal_bans {\n");
+            for (name, kind, v6) in [("banned_v4", "ipv4_addr", false), ("banned_v6", "ipv6_addr", true)] {
+                out.push_str(&format!("    set {} {{\n        type {}\n        flags interval\n", name, kind));
+                let elements = elements(v6);
+                if !elements.is_empty() {
+                    out.push_str(&format!("        elements = {{ {} }}\n", elements.join(", ")));
+                }
+                out.push_str("    }\n");
+            }
+            out.push_str("    chain input {\n");
+            out.push_str("        type filter hook input priority filter; policy accept;\n");
+            out.push_str("        ip saddr @banned_v4 drop\n");
+            out.push_str("        ip6 saddr @banned_v6 drop\n");
+            out.push_str("    }\n");
+            out.push_str("}\n");
+        }
+        FirewallFormat::Iptables => {
+            out.push_str("#!/bin/sh\n");
+            out.push_str("# Generated by modal node bans\n");
+            for (net, rule) in &nets {
+                let command = match net {
+                    IpNet::V4(_) => "iptables",
+                    IpNet::V6(_) => "ip6tables",
+                };
+                out.push_str(&format!("# {}\n", rule.reason.replace('\n', " ")));
+                out.push_str(&format!("{} -A INPUT -s {} -j DROP\n", command, net));
+            }
+        }
+    }
+    out
+}
+
+/// Swarm behaviour that enforces a ban list on connections
+#[derive(Default)]
+pub struct Behaviour {
+    bans: SharedBanList,
+    /// Remote address of every established connection
+    connections: HashMap<ConnectionId, (PeerId, IpAddr)>,
+    closing: VecDeque<(PeerId, ConnectionId)>,
+    waker: Option<Waker>,
+}
+
+impl Behaviour {
+    /// The list this behaviour enforces, for the node to load and update
+    pub fn shared(&self) -> SharedBanList {
+        self.bans.clone()
+    }
+
+    /// An address `peer_id` is connected from
+    pub fn remote_ip(&self, peer_id: &PeerId) -> Option<IpAddr> {
+        self.connections.values().find(|(peer, _)| peer == peer_id).map(|(_, ip)| *ip)
+    }
+
+    /// Close established connections from addresses banned since they connected
+    pub fn enforce(&mut self, now: i64) {
+        let bans = self.bans.read().unwrap();
+        for (&connection_id, &(peer_id, ip)) in &self.connections {
+            if bans.banned(ip, now).is_some() && !self.closing.contains(&(peer_id, connection_id)) {
+                log::warn!("Closing connection from banned address {} (peer {})", ip, peer_id);
+                self.closing.push_back((peer_id, connection_id));
+            }
+        }
+        if !self.closing.is_empty() {
+            if let Some(waker) = self.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    fn deny(&self, addr: &Multiaddr) -> Result<(), ConnectionDenied> {
+        let Some(ip) = ip_of(addr) else {
+            return Ok(());
+        };
+        let now = crate::bootstrapper_health::unix_now();
+        match self.bans.read().unwrap().banned(ip, now) {
+            Some(rule) => Err(ConnectionDenied::new(anyhow!("{} is banned: {}", ip, rule.reason))),
+            None => Ok(()),
+        }
+    }
+}
+
+impl NetworkBehaviour for Behaviour {
+    type ConnectionHandler = dummy::ConnectionHandler;
+    type ToSwarm = Infallible;
+
+    fn handle_pending_inbound_connection(
+        &mut self,
+        _connection_id: ConnectionId,
+        _local_addr: &Multiaddr,
+        remote_addr: &Multiaddr,
+    ) -> Result<(), ConnectionDenied> {
+        self.deny(remote_addr)
+    }
+
+    fn handle_established_inbound_connection(
+        &mut self,
+        _connection_id: ConnectionId,
+        _peer: PeerId,
+        _local_addr: &Multiaddr,
+        remote_addr: &Multiaddr,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        self.deny(remote_addr)?;
+        Ok(dummy::ConnectionHandler)
+    }
+
+    fn handle_established_outbound_connection(
+        &mut self,
+        _connection_id: ConnectionId,
+        _peer: PeerId,
+        addr: &Multiaddr,
+        _role_override: Endpoint,
+        _port_use: PortUse,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        self.deny(addr)?;
+        Ok(dummy::ConnectionHandler)
+    }
+
+    fn on_swarm_event(&mut self, event: FromSwarm) {
+        match event {
+            FromSwarm::ConnectionEstablished(established) => {
+                if let Some(ip) = ip_of(established.endpoint.get_remote_address()) {
+                    self.connections.insert(established.connection_id, (established.peer_id, ip));
+                }
+            }
+            FromSwarm::ConnectionClosed(closed) => {
+                self.connections.remove(&closed.connection_id);
+            }
+            _ => {}
+        }
+    }
+
+    fn on_connection_handler_event(
+        &mut self,
+        _peer_id: PeerId,
+        _connection_id: ConnectionId,
+        event: THandlerOutEvent<Self>,
+    ) {
+        match event {}
+    }
+
+    fn poll(&mut self, cx: &mut Context<'_>) -> Poll<ToSwarm<Self::ToSwarm, THandlerInEvent<Self>>> {
+        if let Some((peer_id, connection_id)) = self.closing.pop_front() {
+            return Poll::Ready(ToSwarm::CloseConnection {
+                peer_id,
+                connection: CloseConnection::One(connection_id),
+            });
+        }
+        self.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(subnet: &str, expires_at: Option<i64>) -> BanRule {
+        BanRule {
+            subnet: parse_subnet(subnet).unwrap().to_string(),
+            reason: "test".to_string(),
+            created_at: 0,
+            expires_at,
+            automatic: false,
+        }
+    }
+
+    #[test]
+    fn test_rules_match_addresses_and_subnets() {
+        assert_eq!(parse_subnet("10.0.0.7").unwrap().to_string(), "10.0.0.7/32");
+        assert_eq!(parse_subnet("10.0.0.7/24").unwrap().to_string(), "10.0.0.0/24");
+        assert_eq!(parse_subnet("/ip4/10.0.0.7/tcp/10001/ws").unwrap().to_string(), "10.0.0.7/32");
+        assert!(parse_subnet("/memory/1").is_err());
+        assert!(parse_subnet("example.com").is_err());
+
+        let mut list = BanList::default();
+        list.ban(rule("192.168.1.0/24", None));
+        list.ban(rule("2001:db8::/32", Some(100)));
+        assert!(list.banned("192.168.1.9".parse().unwrap(), 0).is_some());
+        assert!(list.banned("192.168.2.9".parse().unwrap(), 0).is_none());
+        assert!(list.banned("2001:db8::1".parse().unwrap(), 99).is_some());
+        assert!(list.banned("2001:db8::1".parse().unwrap(), 100).is_none());
+
+        assert!(list.prune_expired(100));
+        assert!(list.unban("192.168.1.0/24").unwrap());
+        assert!(list.rules.is_empty());
+    }
+
+    #[test]
+    fn test_repeated_invalid_data_bans_the_address() {
+        let mut list = BanList::default();
+        let ip: IpAddr = "203.0.113.5".parse().unwrap();
+        for at in 0..AUTO_BAN_STRIKES as i64 - 1 {
+            assert!(list.strike(ip, "bad block", at).is_none());
+        }
+        // Strikes outside the window don't count
+        assert!(list.strike(ip, "bad block", AUTO_BAN_WINDOW_SECS + 10).is_none());
+        for at in 0..AUTO_BAN_STRIKES as i64 - 2 {
+            assert!(list.strike(ip, "bad block", AUTO_BAN_WINDOW_SECS + 11 + at).is_none());
+        }
+        let now = AUTO_BAN_WINDOW_SECS + 20;
+        let banned = list.strike(ip, "bad block", now).unwrap();
+        assert!(banned.automatic);
+        assert_eq!(banned.expires_at, Some(now + AUTO_BAN_SECS));
+        assert!(list.banned(ip, now).is_some());
+    }
+
+    #[test]
+    fn test_ban_list_file_and_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(BAN_LIST_FILE);
+        assert!(BanList::load(&path).unwrap().rules.is_empty());
+
+        let mut list = BanList::default();
+        list.ban(rule("198.51.100.0/24", None));
+        list.ban(rule("2001:db8::1", None));
+        list.ban(rule("192.0.2.1", Some(10)));
+        list.save(&path).unwrap();
+        let loaded = BanList::load(&path).unwrap();
+        assert_eq!(loaded.rules, list.rules);
+
+        let nft = export_rules(&loaded, FirewallFormat::Nftables, 20);
+        assert!(nft.contains("elements = { 198.51.100.0/24 }"));
+        assert!(nft.contains("elements = { 2001:db8::1/128 }"));
+        assert!(!nft.contains("192.0.2.1"));
+        let iptables = export_rules(&loaded, FirewallFormat::Iptables, 0);
+        assert!(iptables.contains("iptables -A INPUT -s 198.51.100.0/24 -j DROP"));
+        assert!(iptables.contains("ip6tables -A INPUT -s 2001:db8::1/128 -j DROP"));
+        assert!(iptables.contains("iptables -A INPUT -s 192.0.2.1/32 -j DROP"));
+    }
+}
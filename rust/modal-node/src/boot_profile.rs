@@ -0,0 +1,77 @@
+//! Startup phase timing
+//!
+//! The node times its startup phases as it goes through them and saves the
+//! profile to the datastore once it is running, for
+//! `modal node inspect --boot-profile`.
+
+use modal_datastore::{BootPhase, BootProfile};
+use std::time::Instant;
+
+/// Opening the datastore and loading the network config
+pub const PHASE_DATASTORE_OPEN: &str = "datastore_open";
+/// Creating the libp2p swarm
+pub const PHASE_SWARM_CREATION: &str = "swarm_creation";
+/// Configured bootup tasks
+pub const PHASE_BOOTUP_TASKS: &str = "bootup_tasks";
+/// Checking (and repairing) canonical chain linkage
+pub const PHASE_CHAIN_INTEGRITY: &str = "chain_integrity";
+/// Waiting for the first peer connections
+pub const PHASE_PEER_CONNECTIONS: &str = "peer_connections";
+/// Syncing from peers before the node starts its work
+pub const PHASE_INITIAL_SYNC: &str = "initial_sync";
+
+/// Times a node's startup phases
+#[derive(Debug, Clone)]
+pub struct BootTimer {
+    started: Instant,
+    started_at: i64,
+    phases: Vec<BootPhase>,
+}
+
+impl Default for BootTimer {
+    fn default() -> Self {
+        Self::start()
+    }
+}
+
+impl BootTimer {
+    pub fn start() -> Self {
+        Self {
+            started: Instant::now(),
+            started_at: crate::bootstrapper_health::unix_now(),
+            phases: Vec::new(),
+        }
+    }
+
+    /// Record that `phase` ran from `since` until now
+    pub fn record(&mut self, phase: &str, since: Instant) {
+        let millis = since.elapsed().as_millis() as u64;
+        log::debug!("Startup phase {} took {} ms", phase, millis);
+        self.phases.push(BootPhase { name: phase.to_string(), millis });
+    }
+
+    /// The phases recorded so far, with the total time since start
+    pub fn profile(&self) -> BootProfile {
+        BootProfile {
+            started_at: self.started_at,
+            phases: self.phases.clone(),
+            total_millis: self.started.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phases_recorded_in_order() {
+        let mut timer = BootTimer::start();
+        timer.record(PHASE_DATASTORE_OPEN, Instant::now());
+        timer.record(PHASE_SWARM_CREATION, Instant::now());
+        let profile = timer.profile();
+        let names: Vec<&str> = profile.phases.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec![PHASE_DATASTORE_OPEN, PHASE_SWARM_CREATION]);
+        assert!(profile.total_millis >= profile.phase_millis());
+    }
+}
@@ -0,0 +1,261 @@
+//! Bootstrapper health checks
+//!
+//! A background task probes each bootstrapper: dials it, times a `/ping`
+//! and asks for its chain height. Results are kept in node_state, one per
+//! bootstrapper, and `ranked_bootstrappers` orders bootstrappers by them
+//! (see `modal_networks::health`) so the node dials and syncs from healthy,
+//! fast, up-to-date bootstrappers first. `probe_with_swarm` runs the same
+//! probe on a swarm nobody else is polling, for the CLI.
+
+use anyhow::{anyhow, Result};
+use futures::StreamExt;
+use libp2p::request_response;
+use libp2p::swarm::SwarmEvent;
+use libp2p::{Multiaddr, PeerId};
+use modal_datastore::{DatastoreManager, Store};
+use modal_networks::health::{rank_bootstrappers, BootstrapperHealth};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, Mutex};
+
+use crate::constants::{BOOTSTRAPPER_HEALTH_INTERVAL_SECS, BOOTSTRAPPER_PROBE_TIMEOUT_SECS};
+use crate::reqres;
+use crate::swarm::{NodeBehaviourEvent, NodeSwarm};
+use crate::swarm_driver::SwarmHandle;
+
+/// Key prefix of the probe results in node_state, followed by the address
+const HEALTH_KEY_PREFIX: &str = "/bootstrapper_health";
+
+/// Latest probe result of every bootstrapper probed
+pub fn load_health(mgr: &DatastoreManager) -> Result<Vec<BootstrapperHealth>> {
+    let mut health = Vec::new();
+    for item in mgr.node_state().iterator(HEALTH_KEY_PREFIX) {
+        let (_, value) = item?;
+        health.push(serde_json::from_slice(&value)?);
+    }
+    Ok(health)
+}
+
+/// Store a probe result, replacing the bootstrapper's previous one
+pub fn save_health(mgr: &DatastoreManager, health: &BootstrapperHealth) -> Result<()> {
+    let key = format!("{}{}", HEALTH_KEY_PREFIX, health.address);
+    mgr.node_state().put(&key, &serde_json::to_vec(health)?)?;
+    Ok(())
+}
+
+/// Bootstrappers in the order to try them, healthiest first
+pub async fn ranked_bootstrappers(datastore: &Arc<Mutex<DatastoreManager>>, bootstrappers: &[Multiaddr]) -> Vec<Multiaddr> {
+    let health = {
+        let mgr = datastore.lock().await;
+        load_health(&mgr).unwrap_or_else(|e| {
+            log::warn!("Failed to load bootstrapper health: {}", e);
+            Vec::new()
+        })
+    };
+    let addresses: Vec<String> = bootstrappers.iter().map(|a| a.to_string()).collect();
+    rank_bootstrappers(&addresses, &health, unix_now())
+        .into_iter()
+        .filter_map(|address| bootstrappers.iter().find(|a| a.to_string() == address).cloned())
+        .collect()
+}
+
+/// Spawn a task that probes every bootstrapper periodically until shutdown
+pub fn start_bootstrapper_health_monitor(
+    datastore: Arc<Mutex<DatastoreManager>>,
+    swarm: SwarmHandle,
+    bootstrappers: Vec<Multiaddr>,
+    mut shutdown_rx: broadcast::Receiver<()>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(BOOTSTRAPPER_HEALTH_INTERVAL_SECS));
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => break,
+                _ = interval.tick() => {
+                    for address in &bootstrappers {
+                        let previous = {
+                            let mgr = datastore.lock().await;
+                            load_health(&mgr).ok().and_then(|all| all.into_iter().find(|h| h.address == address.to_string()))
+                        };
+                        let health = probe_bootstrapper(&swarm, address, previous.as_ref()).await;
+                        log::debug!("Bootstrapper {} health: {:?}", address, health);
+                        let mgr = datastore.lock().await;
+                        if let Err(e) = save_health(&mgr, &health) {
+                            log::warn!("Failed to save bootstrapper health: {}", e);
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Probe a bootstrapper through the running node's swarm
+pub async fn probe_bootstrapper(
+    swarm: &SwarmHandle,
+    address: &Multiaddr,
+    previous: Option<&BootstrapperHealth>,
+) -> BootstrapperHealth {
+    let now = unix_now();
+    let result = async {
+        let peer_id = peer_id_of(address)?;
+        swarm.add_peer_address(peer_id, address.clone()).await?;
+        let connected = swarm.is_connected(peer_id).await?;
+        if !connected {
+            // The first request dials; don't count the handshake as latency
+            request(swarm, &peer_id, ping_request()).await?;
+        }
+        let started = Instant::now();
+        request(swarm, &peer_id, ping_request()).await?;
+        let latency_ms = started.elapsed().as_millis() as u64;
+        let chain_info = request(swarm, &peer_id, chain_info_request()).await?;
+        Ok::<_, anyhow::Error>((latency_ms, chain_height(&chain_info)))
+    }
+    .await;
+    health_from_result(address, result, now, previous)
+}
+
+async fn request(
+    swarm: &SwarmHandle,
+    peer_id: &PeerId,
+    request: reqres::Request,
+) -> Result<reqres::Response> {
+    let timeout = Duration::from_secs(BOOTSTRAPPER_PROBE_TIMEOUT_SECS);
+    let response = tokio::time::timeout(timeout, swarm.send_request(*peer_id, request))
+        .await
+        .map_err(|_| anyhow!("Timed out"))??;
+    if !response.ok {
+        return Err(anyhow!("Request failed: {:?}", response.errors));
+    }
+    Ok(response)
+}
+
+/// Probe a bootstrapper by driving `swarm` directly. Only for swarms no
+/// other task is polling, such as the CLI's.
+pub async fn probe_with_swarm(swarm: &mut NodeSwarm, address: &Multiaddr) -> BootstrapperHealth {
+    let now = unix_now();
+    let timeout = Duration::from_secs(BOOTSTRAPPER_PROBE_TIMEOUT_SECS);
+    let result = async {
+        let peer_id = peer_id_of(address)?;
+        swarm.dial(address.clone())?;
+        tokio::time::timeout(timeout, wait_for_connection(swarm, peer_id))
+            .await
+            .map_err(|_| anyhow!("Dial timed out"))??;
+
+        let started = Instant::now();
+        let request_id = swarm.behaviour_mut().reqres.send_request(&peer_id, ping_request());
+        tokio::time::timeout(timeout, wait_for_response(swarm, request_id))
+            .await
+            .map_err(|_| anyhow!("Ping timed out"))??;
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        let request_id = swarm.behaviour_mut().reqres.send_request(&peer_id, chain_info_request());
+        let chain_info = tokio::time::timeout(timeout, wait_for_response(swarm, request_id))
+            .await
+            .map_err(|_| anyhow!("Chain info timed out"))??;
+        let _ = swarm.disconnect_peer_id(peer_id);
+        Ok::<_, anyhow::Error>((latency_ms, chain_height(&chain_info)))
+    }
+    .await;
+    health_from_result(address, result, now, None)
+}
+
+async fn wait_for_connection(swarm: &mut NodeSwarm, target: PeerId) -> Result<()> {
+    loop {
+        match swarm.select_next_some().await {
+            SwarmEvent::ConnectionEstablished { peer_id, .. } if peer_id == target => return Ok(()),
+            SwarmEvent::OutgoingConnectionError { peer_id: Some(peer_id), error, .. } if peer_id == target => {
+                return Err(anyhow!("Dial failed: {}", error));
+            }
+            _ => {}
+        }
+    }
+}
+
+async fn wait_for_response(swarm: &mut NodeSwarm, target: request_response::OutboundRequestId) -> Result<reqres::Response> {
+    loop {
+        match swarm.select_next_some().await {
+            SwarmEvent::Behaviour(NodeBehaviourEvent::Reqres(request_response::Event::Message {
+                message: request_response::Message::Response { request_id, response },
+                ..
+            })) if request_id == target => {
+                if !response.ok {
+                    return Err(anyhow!("Request failed: {:?}", response.errors));
+                }
+                return Ok(response);
+            }
+            SwarmEvent::Behaviour(NodeBehaviourEvent::Reqres(request_response::Event::OutboundFailure {
+                request_id,
+                error,
+                ..
+            })) if request_id == target => return Err(anyhow!("Request failed: {}", error)),
+            _ => {}
+        }
+    }
+}
+
+pub(crate) fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn peer_id_of(address: &Multiaddr) -> Result<PeerId> {
+    match address.iter().last() {
+        Some(libp2p::multiaddr::Protocol::P2p(peer_id)) => Ok(peer_id),
+        _ => Err(anyhow!("Address has no peer id")),
+    }
+}
+
+fn ping_request() -> reqres::Request {
+    reqres::Request {
+        path: "/ping".to_string(),
+        data: Some(serde_json::json!({})),
+        accept_encoding: None,
+    }
+}
+
+fn chain_info_request() -> reqres::Request {
+    reqres::Request {
+        path: "/data/miner_block/chain_info".to_string(),
+        data: None,
+        accept_encoding: None,
+    }
+}
+
+fn chain_height(response: &reqres::Response) -> Option<u64> {
+    response.data.as_ref()?.get("chain_height")?.as_u64()
+}
+
+fn health_from_result(
+    address: &Multiaddr,
+    result: Result<(u64, Option<u64>)>,
+    now: i64,
+    previous: Option<&BootstrapperHealth>,
+) -> BootstrapperHealth {
+    match result {
+        Ok((latency_ms, height)) => BootstrapperHealth::reachable(address.to_string(), latency_ms, height, now),
+        Err(e) => BootstrapperHealth::unreachable(address.to_string(), e.to_string(), now, previous),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_ranked_bootstrappers_use_stored_health() {
+        let datastore = Arc::new(Mutex::new(DatastoreManager::create_in_memory().unwrap()));
+        let a: Multiaddr = "/ip4/10.0.0.1/tcp/4040/ws/p2p/12D3KooWBGR3m1JmVFm2aZYR7TZXicjA7HSVSWi2fama5cPpgQiX".parse().unwrap();
+        let b: Multiaddr = "/ip4/10.0.0.2/tcp/4040/ws/p2p/12D3KooWEA6dRWvK1vutRDxKfdPZZr7ycHvQNWrDGZZQbiE6YibZ".parse().unwrap();
+        let now = unix_now();
+        {
+            let mgr = datastore.lock().await;
+            save_health(&mgr, &BootstrapperHealth::unreachable(a.to_string(), "refused".to_string(), now, None)).unwrap();
+            save_health(&mgr, &BootstrapperHealth::reachable(b.to_string(), 30, Some(100), now)).unwrap();
+            assert_eq!(load_health(&mgr).unwrap().len(), 2);
+        }
+        assert_eq!(ranked_bootstrappers(&datastore, &[a.clone(), b.clone()]).await, vec![b, a]);
+    }
+}
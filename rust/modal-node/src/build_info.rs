@@ -0,0 +1,224 @@
+//! Build info and protocol compatibility
+//!
+//! Besides its version and git commit, a build carries the protocol
+//! features it implements and the range of message protocol versions it
+//! talks to. `modal --build-info` prints them and RPC serves them as
+//! `getBuildInfo`. Nodes advertise the features in their identify agent
+//! string as `features=a,b,c` and their oldest supported version as
+//! `min_proto=`. A network config can require features and a minimum
+//! protocol version (`compatibility` in NetworkInfo): a node won't start if
+//! its own build falls short, and disconnects peers that do. Peers lacking
+//! features this build has but the network doesn't require are only
+//! warned about, so a network can upgrade one node at a time.
+
+use anyhow::{anyhow, Result};
+use modal_networks::CompatibilitySpec;
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{MESSAGE_PROTOCOL_VERSION, MIN_MESSAGE_PROTOCOL_VERSION};
+
+/// Protocol features this build implements; add one whenever peers need to
+/// know a node supports something new
+pub const PROTOCOL_FEATURES: &[&str] = &[
+    "blocks-sync",
+    "headers-sync",
+    "contract-sync",
+    "capabilities",
+    "wire-cbor1",
+    "canonical-json",
+    "topic-shards",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildInfo {
+    pub version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_commit: Option<String>,
+    pub protocol_version: u32,
+    pub min_protocol_version: u32,
+    pub wire_version: String,
+    pub features: Vec<String>,
+}
+
+impl BuildInfo {
+    /// Build info of the running binary, given its crate version and the
+    /// commit it was built from
+    pub fn new(version: &str, git_commit: Option<&str>) -> Self {
+        Self {
+            version: version.to_string(),
+            git_commit: git_commit.filter(|c| *c != "unknown").map(str::to_string),
+            protocol_version: MESSAGE_PROTOCOL_VERSION,
+            min_protocol_version: MIN_MESSAGE_PROTOCOL_VERSION,
+            wire_version: crate::gossip::wire::WIRE_VERSION.to_string(),
+            features: PROTOCOL_FEATURES.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+}
+
+/// How a peer's advertised protocol fits this node and its network
+#[derive(Debug, Clone, PartialEq)]
+pub enum Compatibility {
+    Compatible,
+    /// Peerable, but lacking features this build has
+    Degraded(String),
+    /// Must not be peered with
+    Incompatible(String),
+}
+
+/// The identify agent string parts advertising this build's protocol
+pub fn agent_parts() -> Vec<String> {
+    vec![
+        format!("min_proto={}", MIN_MESSAGE_PROTOCOL_VERSION),
+        format!("features={}", PROTOCOL_FEATURES.join(",")),
+    ]
+}
+
+/// The protocol features in a peer's identify agent string, if it
+/// advertises any
+pub fn advertised_features(agent_version: &str) -> Option<Vec<String>> {
+    agent_version
+        .split(';')
+        .find_map(|part| part.strip_prefix("features="))
+        .map(|features| {
+            features
+                .split(',')
+                .filter(|f| !f.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+}
+
+/// The oldest message protocol version a peer talks to, if it says
+pub fn advertised_min_version(agent_version: &str) -> Option<u32> {
+    agent_version
+        .split(';')
+        .find_map(|part| part.strip_prefix("min_proto="))
+        .and_then(|version| version.parse().ok())
+}
+
+/// The compatibility section of a network config, if it has one
+pub fn compatibility_spec(network_config: &serde_json::Value) -> Result<Option<CompatibilitySpec>> {
+    match network_config.get("compatibility") {
+        Some(value) if !value.is_null() => Ok(Some(serde_json::from_value(value.clone())?)),
+        _ => Ok(None),
+    }
+}
+
+/// Check this build against the network's requirements
+pub fn check_local(spec: &CompatibilitySpec) -> Result<()> {
+    let missing: Vec<&str> = spec
+        .required_features
+        .iter()
+        .map(String::as_str)
+        .filter(|f| !PROTOCOL_FEATURES.contains(f))
+        .collect();
+    if !missing.is_empty() {
+        return Err(anyhow!(
+            "This build lacks protocol features the network requires: {}; upgrade modal",
+            missing.join(", ")
+        ));
+    }
+    if let Some(min) = spec.min_protocol_version {
+        if MESSAGE_PROTOCOL_VERSION < min {
+            return Err(anyhow!(
+                "This build speaks protocol {}, the network requires {}; upgrade modal",
+                MESSAGE_PROTOCOL_VERSION,
+                min
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Check a peer's identify agent string against this build and the
+/// network's requirements
+pub fn check_peer(agent_version: &str, spec: Option<&CompatibilitySpec>) -> Compatibility {
+    let min_version = spec
+        .and_then(|spec| spec.min_protocol_version)
+        .map_or(MIN_MESSAGE_PROTOCOL_VERSION, |min| min.max(MIN_MESSAGE_PROTOCOL_VERSION));
+    if let Some(version) = crate::capabilities::advertised_version(agent_version) {
+        if version < min_version {
+            return Compatibility::Incompatible(format!("speaks protocol {}, below {}", version, min_version));
+        }
+    }
+    if let Some(theirs) = advertised_min_version(agent_version) {
+        if theirs > MESSAGE_PROTOCOL_VERSION {
+            return Compatibility::Incompatible(format!(
+                "only talks protocol {} and up, we speak {}",
+                theirs, MESSAGE_PROTOCOL_VERSION
+            ));
+        }
+    }
+
+    let required = spec.map(|spec| spec.required_features.as_slice()).unwrap_or_default();
+    let Some(features) = advertised_features(agent_version) else {
+        // Builds from before feature flags get the benefit of the doubt,
+        // unless the network requires features
+        if required.is_empty() {
+            return Compatibility::Compatible;
+        }
+        return Compatibility::Incompatible("advertises no protocol features".to_string());
+    };
+    let missing: Vec<&str> = required
+        .iter()
+        .map(String::as_str)
+        .filter(|f| !features.iter().any(|ours| ours == f))
+        .collect();
+    if !missing.is_empty() {
+        return Compatibility::Incompatible(format!("lacks required features {}", missing.join(", ")));
+    }
+    let lacking: Vec<&str> = PROTOCOL_FEATURES
+        .iter()
+        .copied()
+        .filter(|f| !features.iter().any(|theirs| theirs == f))
+        .collect();
+    if !lacking.is_empty() {
+        return Compatibility::Degraded(format!("lacks features {}", lacking.join(", ")));
+    }
+    Compatibility::Compatible
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agent(extra: &[String]) -> String {
+        let mut parts = vec!["modal-node/0.1.0".to_string(), format!("proto={}", MESSAGE_PROTOCOL_VERSION)];
+        parts.extend(extra.iter().cloned());
+        parts.join(";")
+    }
+
+    #[test]
+    fn test_peer_compatibility() {
+        let ours = agent(&agent_parts());
+        assert_eq!(advertised_features(&ours).unwrap().len(), PROTOCOL_FEATURES.len());
+        assert_eq!(check_peer(&ours, None), Compatibility::Compatible);
+
+        let spec = CompatibilitySpec {
+            required_features: vec!["contract-sync".to_string()],
+            min_protocol_version: None,
+        };
+        assert!(check_local(&spec).is_ok());
+        assert_eq!(check_peer(&ours, Some(&spec)), Compatibility::Compatible);
+
+        // Older builds are let in unless the network requires features
+        let old = agent(&[]);
+        assert_eq!(check_peer(&old, None), Compatibility::Compatible);
+        assert!(matches!(check_peer(&old, Some(&spec)), Compatibility::Incompatible(_)));
+
+        let partial = agent(&["features=blocks-sync,contract-sync".to_string()]);
+        assert!(matches!(check_peer(&partial, Some(&spec)), Compatibility::Degraded(_)));
+        let lacking = agent(&["features=blocks-sync".to_string()]);
+        assert!(matches!(check_peer(&lacking, Some(&spec)), Compatibility::Incompatible(_)));
+
+        let newer = agent(&[format!("min_proto={}", MESSAGE_PROTOCOL_VERSION + 1)]);
+        assert!(matches!(check_peer(&newer, None), Compatibility::Incompatible(_)));
+
+        let future = CompatibilitySpec {
+            required_features: vec!["teleportation".to_string()],
+            min_protocol_version: Some(MESSAGE_PROTOCOL_VERSION + 1),
+        };
+        assert!(check_local(&future).is_err());
+        assert!(matches!(check_peer(&ours, Some(&future)), Compatibility::Incompatible(_)));
+    }
+}
@@ -0,0 +1,198 @@
+//! Peer capabilities
+//!
+//! Every node advertises the message protocol version it speaks as `proto=`
+//! in its identify agent string, and answers `/node/capabilities` with the
+//! paths and protocols it serves, how it can serve chain data and how much
+//! history it keeps. When a peer identifies, the networking task asks for
+//! its capabilities unless it already has fresh ones for that version, and
+//! keeps them here and in the peer's `PeerInfo`. Sync and consensus use them
+//! to skip peers that speak too old a protocol or can't serve what they
+//! need. Peers that haven't answered are given the benefit of the doubt, so
+//! a network can upgrade one node at a time.
+
+use anyhow::{anyhow, Result};
+use libp2p::PeerId;
+use modal_datastore::models::miner::PruneStatus;
+use modal_datastore::models::{PeerCapabilities, PeerInfo};
+use modal_datastore::DatastoreManager;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+use crate::constants::{CAPABILITIES_REFRESH_SECS, MESSAGE_PROTOCOL_VERSION, MIN_MESSAGE_PROTOCOL_VERSION};
+use crate::reqres;
+
+/// Request path of the capabilities handshake
+pub const PATH: &str = "/node/capabilities";
+
+/// Most paths and protocols accepted from a peer
+const MAX_PROTOCOLS: usize = 256;
+
+/// Capabilities of the peers that have answered, shared by every network
+/// this process joins like `gossip::wire`'s binary peers
+static KNOWN: LazyLock<Mutex<HashMap<PeerId, PeerCapabilities>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// The message protocol version in a peer's identify agent string, if any
+pub fn advertised_version(agent_version: &str) -> Option<u32> {
+    agent_version
+        .split(';')
+        .find_map(|part| part.strip_prefix("proto="))
+        .and_then(|version| version.parse().ok())
+}
+
+/// This node's capabilities
+pub async fn local(datastore_manager: &DatastoreManager) -> Result<PeerCapabilities> {
+    let pruned_below = PruneStatus::pruned_below_multi(datastore_manager).await?;
+    let mut protocols: Vec<String> = reqres::PATHS.iter().map(|path| path.to_string()).collect();
+    if datastore_manager.index().is_enabled()? {
+        protocols.extend(reqres::index::PATHS.iter().map(|path| path.to_string()));
+    }
+    protocols.extend(
+        [reqres::PROTOCOL, reqres::BINARY_PROTOCOL, crate::contract_sync::PROTOCOL].map(String::from),
+    );
+    let mut sync_modes = vec!["blocks".to_string()];
+    if pruned_below.is_some() {
+        sync_modes.push("headers".to_string());
+    }
+    Ok(PeerCapabilities {
+        protocol_version: MESSAGE_PROTOCOL_VERSION,
+        protocols,
+        sync_modes,
+        pruned_below,
+        archive: pruned_below.is_none(),
+        received_at: 0,
+    })
+}
+
+/// The handshake request
+pub fn request() -> reqres::Request {
+    reqres::Request {
+        path: PATH.to_string(),
+        data: None,
+        accept_encoding: None,
+    }
+}
+
+/// Whether to ask `peer_id` for its capabilities, now that it has
+/// identified with `advertised` version
+pub fn needs_handshake(peer_id: &PeerId, advertised: u32, now: i64) -> bool {
+    match KNOWN.lock().unwrap().get(peer_id) {
+        Some(known) => known.protocol_version != advertised || now - known.received_at >= CAPABILITIES_REFRESH_SECS,
+        None => true,
+    }
+}
+
+/// Check and remember a peer's answer to the handshake
+pub fn record(peer_id: PeerId, response: &reqres::Response, now: i64) -> Result<PeerCapabilities> {
+    if !response.ok {
+        return Err(anyhow!("Capabilities request failed: {:?}", response.errors));
+    }
+    let data = response.data.clone().ok_or_else(|| anyhow!("No data in capabilities response"))?;
+    let mut capabilities: PeerCapabilities = serde_json::from_value(data)?;
+    modal_common::wire::check_len("capabilities protocols", capabilities.protocols.len(), MAX_PROTOCOLS)?;
+    modal_common::wire::check_len("capabilities sync modes", capabilities.sync_modes.len(), MAX_PROTOCOLS)?;
+    for protocol in capabilities.protocols.iter().chain(&capabilities.sync_modes) {
+        modal_common::wire::check_len("capabilities protocol", protocol.len(), reqres::MAX_PATH_LEN)?;
+    }
+    capabilities.received_at = now;
+    KNOWN.lock().unwrap().insert(peer_id, capabilities.clone());
+    Ok(capabilities)
+}
+
+/// Store a peer's capabilities with the rest of its `PeerInfo`
+pub async fn save(datastore_manager: &DatastoreManager, peer_id: &PeerId, capabilities: PeerCapabilities) -> Result<()> {
+    let mut info = PeerInfo::find_one(datastore_manager, &peer_id.to_string())
+        .await?
+        .unwrap_or_else(|| PeerInfo::new(peer_id.to_string()));
+    info.capabilities = Some(capabilities);
+    info.save_to(datastore_manager.node_state()).await?;
+    Ok(())
+}
+
+/// What `peer_id` said it supports, if it has answered
+pub fn get(peer_id: &PeerId) -> Option<PeerCapabilities> {
+    KNOWN.lock().unwrap().get(peer_id).cloned()
+}
+
+/// Whether `peer_id` speaks a message protocol this node still supports
+pub fn compatible(peer_id: &PeerId) -> bool {
+    get(peer_id).is_none_or(|known| known.protocol_version >= MIN_MESSAGE_PROTOCOL_VERSION)
+}
+
+/// Whether `peer_id` is compatible and answers `path`
+pub fn supports(peer_id: &PeerId, path: &str) -> bool {
+    get(peer_id).is_none_or(|known| known.protocol_version >= MIN_MESSAGE_PROTOCOL_VERSION && known.supports(path))
+}
+
+/// Whether `peer_id` is compatible and can serve block bodies from `index` on
+pub fn can_sync_from(peer_id: &PeerId, index: u64) -> bool {
+    supports(peer_id, "/data/miner_block/range") && get(peer_id).is_none_or(|known| known.serves_blocks_from(index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answer(capabilities: &PeerCapabilities) -> reqres::Response {
+        reqres::Response {
+            ok: true,
+            data: Some(serde_json::to_value(capabilities).unwrap()),
+            errors: None,
+            encoding: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handshake_records_capabilities() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        let ours = local(&mgr).await.unwrap();
+        assert!(ours.archive);
+        assert!(ours.supports(PATH));
+        assert_eq!(advertised_version("modal-node/0.1.0;role=Miner;proto=1"), Some(1));
+        assert_eq!(advertised_version("modal-node/0.1.0;role=Miner"), None);
+
+        let peer = PeerId::random();
+        assert!(needs_handshake(&peer, MESSAGE_PROTOCOL_VERSION, 1000));
+        // Not yet known, so assumed to be compatible
+        assert!(can_sync_from(&peer, 0));
+
+        let mut theirs = ours.clone();
+        theirs.pruned_below = Some(50);
+        theirs.archive = false;
+        let recorded = record(peer, &answer(&theirs), 1000).unwrap();
+        assert_eq!(recorded.received_at, 1000);
+        assert!(!needs_handshake(&peer, MESSAGE_PROTOCOL_VERSION, 1000));
+        assert!(needs_handshake(&peer, MESSAGE_PROTOCOL_VERSION + 1, 1000));
+        assert!(needs_handshake(&peer, MESSAGE_PROTOCOL_VERSION, 1000 + CAPABILITIES_REFRESH_SECS));
+        assert!(!can_sync_from(&peer, 10));
+        assert!(can_sync_from(&peer, 50));
+
+        save(&mgr, &peer, recorded.clone()).await.unwrap();
+        let info = PeerInfo::find_one(&mgr, &peer.to_string()).await.unwrap().unwrap();
+        assert_eq!(info.capabilities, Some(recorded));
+    }
+
+    #[test]
+    fn test_old_or_unhelpful_peers_are_skipped() {
+        let old = PeerId::random();
+        let capabilities = PeerCapabilities {
+            protocol_version: MIN_MESSAGE_PROTOCOL_VERSION - 1,
+            protocols: vec!["/data/miner_block/range".to_string()],
+            ..Default::default()
+        };
+        record(old, &answer(&capabilities), 0).unwrap();
+        assert!(!compatible(&old));
+        assert!(!can_sync_from(&old, 0));
+
+        let limited = PeerId::random();
+        let capabilities = PeerCapabilities {
+            protocol_version: MESSAGE_PROTOCOL_VERSION,
+            protocols: vec!["/ping".to_string()],
+            ..Default::default()
+        };
+        record(limited, &answer(&capabilities), 0).unwrap();
+        assert!(compatible(&limited));
+        assert!(!supports(&limited, "/consensus/block/ack"));
+
+        assert!(record(PeerId::random(), &reqres::Response::error("Unknown path".to_string()), 0).is_err());
+    }
+}
@@ -11,44 +11,80 @@ use std::collections::HashMap;
 pub struct Config {
     pub id: Option<String>,
     pub passfile_path: Option<PathBuf>,
+    pub bls_passfile_path: Option<PathBuf>, // BLS key from `modal id create --scheme bls` that signs certificate votes once the network registers validators' BLS keys (needs the bls feature)
     pub storage_path: Option<PathBuf>,
     pub data_dir: Option<PathBuf>, // New multi-store data directory (contains miner_canon/, miner_active/, etc.)
     pub logs_path: Option<PathBuf>,
+    pub ban_list_path: Option<PathBuf>, // IP and subnet ban rules, edited by `modal node ban` (default: bans.json in data_dir)
     pub logs_enabled: Option<bool>,
     pub log_level: Option<String>,
     pub bootup_enabled: Option<bool>,
     pub bootup_minimum_genesis_timestamp: Option<u64>,
     pub bootup_prune_old_genesis_blocks: Option<bool>,
+    pub full_integrity_check: Option<bool>, // Check every canonical block's linkage at miner startup instead of resuming from the last validated block (default: false)
     pub network_config_path: Option<PathBuf>,
     pub listeners: Option<Vec<Multiaddr>>,
     pub bootstrappers: Option<Vec<Multiaddr>>,
+    pub max_inbound_peers: Option<usize>, // Most peers that dialed this node to keep; when full, misbehaving peers are evicted for newcomers (default: 64)
+    pub max_outbound_peers: Option<usize>, // Most peers this node dialed to keep (default: 32)
+    pub reserved_peer_slots: Option<usize>, // Slots of each direction only bootstrappers, validators and reserved_peers may take (default: 8)
+    pub reserved_peers: Option<Vec<String>>, // Peer IDs always let in, evicting the lowest-scoring peer if full
+    pub outbound_proxy: Option<String>, // SOCKS5 proxy for every outbound connection and HTTP request, e.g. "socks5h://127.0.0.1:9050" for Tor; host names (and /onion3/ addresses) are resolved by the proxy, and the status server only listens on localhost
+    pub memory_transport: Option<bool>, // Connect over the in-process memory transport (/memory/<port> listeners and bootstrappers) instead of TCP and WebSocket, for simulations
+    pub networking_tick_ms: Option<u64>, // Longest the networking task holds the swarm between events (default 15s); simulations lower it so blocks are mined and published without waiting
     pub autoupgrade_enabled: Option<bool>,
     pub autoupgrade_base_url: Option<String>,
     pub autoupgrade_branch: Option<String>,
     pub autoupgrade_registry_url: Option<String>, // Deprecated: kept for backward compatibility
     pub autoupgrade_check_interval_secs: Option<u64>,
+    pub autoupgrade_canary_percent: Option<u8>, // Percent of nodes (bucketed by peer id hash) that upgrade as soon as a release is seen (default: 100)
+    pub autoupgrade_rollout_delay_secs: Option<u64>, // How long the remaining nodes wait after first seeing a release (default: 21600)
+    pub autoupgrade_windows: Option<Vec<String>>, // UTC windows in which upgrades may be installed, e.g. ["02:00-04:00"] (default: any time)
     pub noop_mode: Option<bool>,
     pub run_miner: Option<bool>,
     pub miner_nominees: Option<Vec<String>>,
+    pub miner_nomination_policy: Option<crate::actions::miner::nomination::NominationPolicyConfig>, // How produced blocks pick their nominee: round_robin over miner_nominees (default), stake_weighted, file, rpc
     pub hybrid_consensus: Option<bool>, // Enable hybrid consensus mode (validators selected from epoch N-2 mining nominations)
     pub run_validator: Option<bool>, // Run as validator (hybrid mode: wait for epoch >= 2)
     pub status_port: Option<u16>,
+    pub explorer_port: Option<u16>, // HTTP port for the chain explorer (blocks, epochs, contracts, validators); disabled if unset
+    pub rpc_port: Option<u16>, // Port of the read-only RPC gateway served when running as "gateway" (default: 8899)
+    pub rpc_gateway: Option<modal_rpc::GatewayConfig>, // Gateway limits, e.g. {"requests_per_minute": 120, "cache_entries": 10000, "max_age_secs": 5, "allowed_origins": ["https://explorer.example"], "trust_forwarded_for": false}
+    pub graphql_port: Option<u16>, // Port of the GraphQL endpoint (/graphql) served when running as "indexer"; needs the graphql feature, disabled if unset
     pub status_html_dir: Option<PathBuf>,
     pub status_url: Option<String>, // Public URL for this node's status page (e.g., "https://node1.testnet.modal.money")
     pub fork_name: Option<String>, // Predefined fork configuration (e.g., "testnet/pepi")
     pub minimum_block_timestamp: Option<i64>, // Reject blocks mined before this Unix timestamp (overrides fork_name)
     pub forced_blocks: Option<HashMap<u64, String>>, // Map of block_height -> required_block_hash for forced fork specification (overrides fork_name)
     pub initial_difficulty: Option<u128>, // Initial mining difficulty (testnet: 1, other networks: 10 if not specified)
-    pub miner_hash_func: Option<String>, // Hash function for mining: "randomx" (default), "sha256", etc.
+    pub miner_hash_func: Option<String>, // Hash function for mining: "randomx" (default), "sha256", "blake3", "argon2id", etc.
     pub miner_hash_params: Option<serde_json::Value>,
     pub mining_delay_ms: Option<u64>, // Artificial delay between mining attempts (for testing race conditions) // Hash algorithm parameters (e.g., RandomX key and flags)
+    pub miner_threads: Option<usize>, // Number of mining threads to split the nonce space across (default: 1)
+    pub getwork_port: Option<u16>, // TCP port for external miners (getwork protocol); disabled if unset
+    pub getwork_bind: Option<String>, // Address the getwork server listens on (default: "127.0.0.1"); anything but localhost requires getwork_token
+    pub getwork_token: Option<String>, // Token external miners must send as `token` when logging in to the getwork server
     pub inspect_whitelist: Option<Vec<String>>, // Peer IDs allowed to inspect this node via reqres. None = only self, empty vec = reject all, populated = allow those peers
+    pub admin_peers: Option<Vec<String>>, // Peer IDs allowed to send admin requests such as role changes (`modal node set-role`); refused from everyone if unset
+    pub gossip_shards: Option<Vec<u32>>, // Shards of the network's sharded gossip topics to subscribe to, e.g. [0, 1] (default: all); blocks on the others arrive through sync
+    pub reqres_dispatch: Option<crate::reqres::dispatcher::DispatchConfig>, // Incoming request scheduling, e.g. {"path_limits": {"/data/miner_block/range": 1}, "max_queued": 64, "retry_after_ms": 1000, "max_compression_level": 9} (default: per-class limits, consensus before sync before the rest; max_compression_level 0 stops compressing responses)
     
     // Auto-healing / fork recovery settings
     pub fork_recovery_min_peers: Option<usize>, // Minimum number of peers that must report a heavier chain before pausing mining (default: 1)
     pub fork_recovery_epoch_threshold: Option<u64>, // Pause mining if peers report chains this many epochs ahead (default: 2)
+    pub max_reorg_depth: Option<u64>, // Refuse reorgs replacing more than this many canonical blocks (default: unbounded, checkpoints still apply)
+    pub prune_keep_blocks: Option<u64>, // Prune blocks below the latest checkpoint to headers and epoch summaries, keeping this many recent blocks in full (default: no pruning)
+    pub reorg_webhook_url: Option<String>, // POST every reorg event (old tip, new tip, common ancestor, orphaned blocks) as JSON to this URL
+    pub event_sinks: Option<Vec<crate::event_sinks::EventSinkConfig>>, // Deliver journal events (new blocks, reorgs, finalized rounds, contract commits) to webhooks or commands, filtered by kind and contract, with retry and backoff
+    pub anomaly_max_future_drift_secs: Option<i64>, // Alert on blocks timestamped this far ahead of the local clock (default: 7200)
+    pub anomaly_max_nomination_share: Option<f64>, // Alert when one peer is nominated in more than this fraction of the last 100 blocks (default: 0.5)
     
-    pub run_as: Option<String>, // Node role: "miner", "observer", "validator", "noop" (default: determined by run_miner)
+    pub run_as: Option<String>, // Node role: "miner", "observer", "validator", "gateway", "indexer", "noop" (default: determined by run_miner)
+    pub role_schedule: Option<Vec<crate::actions::roles::RoleScheduleEntry>>, // Roles to switch to as the chain reaches epochs, e.g. [{"from_epoch": 100, "role": "validator"}] (observer, miner and validator nodes only)
+
+    pub networks: Option<Vec<crate::multi_network::NetworkMembership>>, // Join several networks from one process, each with its own swarm and datastore
+
+    pub storage: Option<modal_datastore::StorageConfig>, // Storage engine per store, e.g. {"engine": "rocksdb_cf", "stores": {"validator_active": "memory"}, "durability": "sync"} (default: what data_dir was created with, else rocksdb; durability "sync", "wal" (default) or "deferred")
 }
 
 impl Config {
@@ -65,6 +101,11 @@ impl Config {
             let abs_passfile_path = to_absolute_path(config_dir, passfile_path)?;
             config.passfile_path = Some(abs_passfile_path);
         }
+        
+        if let Some(bls_passfile_path_buf) = config.bls_passfile_path {
+            let abs_bls_passfile_path = to_absolute_path(config_dir, bls_passfile_path_buf.as_path())?;
+            config.bls_passfile_path = Some(abs_bls_passfile_path);
+        }
     
         if let Some(storage_path_buf) = config.storage_path {
             let storage_path = storage_path_buf.as_path();
@@ -84,6 +125,11 @@ impl Config {
             config.logs_path = Some(abs_logs_path);
         }
 
+        if let Some(ban_list_path_buf) = config.ban_list_path {
+            let abs_ban_list_path = to_absolute_path(config_dir, ban_list_path_buf.as_path())?;
+            config.ban_list_path = Some(abs_ban_list_path);
+        }
+
         if let Some(network_config_path_buf) = config.network_config_path {
             let network_config_path = network_config_path_buf.as_path();
             // Don't convert modal-networks:// URIs to absolute paths
@@ -102,6 +148,21 @@ impl Config {
             let abs_status_html_dir = to_absolute_path(config_dir, status_html_dir)?;
             config.status_html_dir = Some(abs_status_html_dir);
         }
+
+        if let Some(crate::actions::miner::nomination::NominationPolicyConfig::File { path }) = config.miner_nomination_policy.as_mut() {
+            *path = to_absolute_path(config_dir, path.as_path())?;
+        }
+
+        if let Some(ref mut networks) = config.networks {
+            for network in networks.iter_mut() {
+                if !network.network_config_path.to_string_lossy().starts_with("modal-networks://") {
+                    network.network_config_path = to_absolute_path(config_dir, network.network_config_path.as_path())?;
+                }
+                if let Some(ref data_dir) = network.data_dir {
+                    network.data_dir = Some(to_absolute_path(config_dir, data_dir.as_path())?);
+                }
+            }
+        }
     
         Ok(config)
     }
@@ -152,6 +213,18 @@ impl Config {
     /// Build a ForkConfig from node configuration
     /// Merges hardcoded fork settings (from fork_name) with user-provided settings
     /// User-provided settings override fork_name defaults
+    /// Thresholds for anomaly alerts, with config overrides applied to the defaults
+    pub fn get_anomaly_config(&self) -> modal_observer::AnomalyConfig {
+        let mut anomaly_config = modal_observer::AnomalyConfig::default();
+        if let Some(secs) = self.anomaly_max_future_drift_secs {
+            anomaly_config.max_future_drift_secs = secs;
+        }
+        if let Some(share) = self.anomaly_max_nomination_share {
+            anomaly_config.max_nomination_share = share;
+        }
+        anomaly_config
+    }
+
     pub fn get_fork_config(&self) -> modal_observer::ForkConfig {
         let mut fork_config = modal_observer::ForkConfig::new();
         
@@ -189,6 +262,7 @@ impl Config {
         // Apply fork recovery settings
         fork_config.fork_recovery_min_peers = self.fork_recovery_min_peers;
         fork_config.fork_recovery_epoch_threshold = self.fork_recovery_epoch_threshold;
+        fork_config.max_reorg_depth = self.max_reorg_depth;
         
         fork_config
     }
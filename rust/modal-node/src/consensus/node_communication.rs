@@ -1,7 +1,5 @@
 use anyhow::Result;
 use std::str::FromStr;
-use std::sync::Arc;
-use tokio::sync::Mutex;
 use tokio::sync::mpsc;
 
 use libp2p_identity::PeerId;
@@ -9,16 +7,34 @@ use libp2p_identity::PeerId;
 use modal_validator_consensus::communication::Communication;
 use modal_datastore::models::validator::block::Ack;
 use modal_datastore::models::validator::block::ValidatorBlock;
+use modal_datastore::models::miner::CheckpointCertificate;
+use modal_common::slashing::EquivocationEvidence;
 use modal_validator_consensus::communication::Message as ConsensusMessage;
 
 use crate::gossip::consensus::block::cert::TOPIC as BLOCK_CERT_TOPIC;
 use crate::gossip::consensus::block::draft::TOPIC as BLOCK_DRAFT_TOPIC;
+use crate::gossip::consensus::checkpoint::TOPIC as CHECKPOINT_TOPIC;
+use crate::gossip::consensus::evidence::TOPIC as EVIDENCE_TOPIC;
 
 pub struct NodeCommunication {
-    pub swarm: Arc<Mutex<crate::swarm::NodeSwarm>>,
+    pub swarm: crate::swarm_driver::SwarmHandle,
     pub consensus_tx: mpsc::Sender<ConsensusMessage>,
 }
 
+impl NodeCommunication {
+    /// Gossip our signed checkpoint to validators, observers and miners
+    pub async fn broadcast_checkpoint(&mut self, cert: &CheckpointCertificate) -> Result<()> {
+        crate::gossip::wire::publish(&self.swarm, CHECKPOINT_TOPIC, cert).await?;
+        Ok(())
+    }
+
+    /// Gossip equivocation evidence for miners to include in their blocks
+    pub async fn broadcast_evidence(&mut self, evidence: &EquivocationEvidence) -> Result<()> {
+        crate::gossip::wire::publish(&self.swarm, EVIDENCE_TOPIC, evidence).await?;
+        Ok(())
+    }
+}
+
 #[async_trait::async_trait]
 impl Communication for NodeCommunication {
     async fn broadcast_draft_block(&mut self, from_peer: &str, block: &ValidatorBlock) -> Result<()> {
@@ -28,13 +44,7 @@ impl Communication for NodeCommunication {
             block: block.clone(),
           };
         self.consensus_tx.send(msg).await?;
-        {
-            let mut swarm = self.swarm.lock().await;
-            swarm.behaviour_mut().gossipsub.publish(
-                libp2p::gossipsub::IdentTopic::new(BLOCK_DRAFT_TOPIC),
-                serde_json::to_string(block)?,
-            )?;
-        }
+        crate::gossip::wire::publish(&self.swarm, BLOCK_DRAFT_TOPIC, block).await?;
         Ok(())
     }
 
@@ -45,13 +55,7 @@ impl Communication for NodeCommunication {
             block: block.clone(),
           };
         self.consensus_tx.send(msg).await?;
-        {
-            let mut swarm = self.swarm.lock().await;
-            swarm.behaviour_mut().gossipsub.publish(
-                libp2p::gossipsub::IdentTopic::new(BLOCK_CERT_TOPIC),
-                serde_json::to_string(block)?,
-            )?;
-        }
+        crate::gossip::wire::publish(&self.swarm, BLOCK_CERT_TOPIC, block).await?;
         Ok(())
     }
 
@@ -60,6 +64,7 @@ impl Communication for NodeCommunication {
         let request = crate::reqres::Request {
             path: "/consensus/block/ack".into(),
             data: Some(serde_json::json!(ack)),
+            accept_encoding: None,
         };
         if ack.peer_id == ack.acker {
             let msg = ConsensusMessage::ValidatorBlockAck {
@@ -68,12 +73,16 @@ impl Communication for NodeCommunication {
                 ack: ack.clone(),
               };
             self.consensus_tx.send(msg).await?;
+        } else if !crate::capabilities::supports(&target_peer, &request.path) {
+            anyhow::bail!("Peer {} can't take block acks on its protocol version", target_peer);
         } else {
-            let mut swarm = self.swarm.lock().await;
-            let _req_id = swarm
-                .behaviour_mut()
-                .reqres
-                .send_request(&target_peer, request);
+            // The acker doesn't wait on the answer
+            let swarm = self.swarm.clone();
+            tokio::spawn(async move {
+                if let Err(e) = swarm.send_request(target_peer, request).await {
+                    log::debug!("Block ack to {} failed: {}", target_peer, e);
+                }
+            });
         }
 
         Ok(())
@@ -3,9 +3,13 @@
 //! This module consolidates magic numbers and configuration defaults
 //! to improve maintainability and consistency across the codebase.
 
-/// Number of blocks per epoch for mining/sequencing
+/// Default number of blocks per epoch for mining/sequencing; networks can
+/// change it with `blocks_per_epoch` and `eras` in their config
 pub const BLOCKS_PER_EPOCH: u64 = 40;
 
+/// Default target block time in seconds; networks can change it like `BLOCKS_PER_EPOCH`
+pub const TARGET_BLOCK_TIME_SECS: u64 = 60;
+
 /// Default initial mining difficulty
 pub const DEFAULT_INITIAL_DIFFICULTY: u128 = 1000;
 
@@ -15,6 +19,39 @@ pub const SYNC_COOLDOWN_MS: u64 = 500;
 /// Timeout for reqres protocol requests in seconds
 pub const REQRES_TIMEOUT_SECS: u64 = 60;
 
+/// Consensus requests handled at once per path
+pub const REQRES_CONSENSUS_CONCURRENCY: usize = 8;
+
+/// Sync requests (block, DAG and sequencer data) handled at once per path
+pub const REQRES_SYNC_CONCURRENCY: usize = 2;
+
+/// Other requests handled at once per path
+pub const REQRES_MISC_CONCURRENCY: usize = 2;
+
+/// Requests of one priority class that may wait before new ones are turned away
+pub const REQRES_MAX_QUEUED: usize = 64;
+
+/// How long a turned away peer is told to wait, in milliseconds
+pub const REQRES_RETRY_AFTER_MS: u64 = 1000;
+
+/// Times a sync request is retried when the peer is busy
+pub const REQRES_BUSY_RETRIES: u32 = 3;
+
+/// zstd level requested for compressible responses
+pub const REQRES_ZSTD_LEVEL: i32 = 3;
+
+/// Highest zstd level a node compresses responses at, unless configured
+pub const REQRES_MAX_COMPRESSION_LEVEL: i32 = 9;
+
+/// Response data smaller than this, as JSON, is sent uncompressed
+pub const REQRES_COMPRESS_MIN_BYTES: usize = 4 * 1024;
+
+/// Largest response data a compressed response may expand to
+pub const REQRES_MAX_DECOMPRESSED_BYTES: usize = 64 * 1024 * 1024;
+
+/// Largest gossip message decoded, above gossipsub's own transmit limit
+pub const MAX_GOSSIP_BYTES: usize = 1024 * 1024;
+
 /// Interval for auto-healing checks in seconds
 pub const AUTO_HEALING_INTERVAL_SECS: u64 = 60;
 
@@ -33,6 +70,9 @@ pub const MAX_BLOCKS_PER_RANGE_REQUEST: usize = 50;
 /// Rolling integrity check window size
 pub const ROLLING_INTEGRITY_WINDOW: usize = 160;
 
+/// Blocks below the integrity checkpoint rechecked at random on each startup
+pub const INTEGRITY_SAMPLE_SIZE: usize = 32;
+
 /// Interval for rolling integrity checks (every N blocks)
 pub const ROLLING_INTEGRITY_CHECK_INTERVAL: u64 = 10;
 
@@ -42,12 +82,39 @@ pub const PEER_IGNORE_INITIAL_SECS: u64 = 60;
 /// Maximum peer ignore exponent (caps at ~17 hours)
 pub const PEER_IGNORE_MAX_EXPONENT: u32 = 10;
 
+/// How often a running node rereads its ban list file in seconds
+pub const BAN_LIST_RELOAD_SECS: u64 = 30;
+
+/// Invalid messages from one address that get it banned
+pub const AUTO_BAN_STRIKES: usize = 5;
+
+/// Window the invalid messages must fall in, in seconds
+pub const AUTO_BAN_WINDOW_SECS: i64 = 600;
+
+/// How long an automatic ban lasts in seconds
+pub const AUTO_BAN_SECS: i64 = 24 * 60 * 60;
+
 /// Brief pause between mining retries in milliseconds
 pub const MINING_RETRY_PAUSE_MS: u64 = 500;
 
 /// Pause between mining attempts in milliseconds
 pub const MINING_LOOP_PAUSE_MS: u64 = 100;
 
+/// How often to check for a competing block while mining, in milliseconds
+pub const MINING_CANCEL_POLL_MS: u64 = 500;
+
+/// Shares for external miners are this many times easier than the block difficulty
+pub const GETWORK_SHARE_DIFFICULTY_DIVISOR: u128 = 64;
+
+/// Maximum number of outstanding getwork jobs kept for share validation
+pub const GETWORK_MAX_JOBS: usize = 1024;
+
+/// Longest getwork request line accepted before the connection is closed
+pub const GETWORK_MAX_LINE_BYTES: usize = 16 * 1024;
+
+/// Seconds a getwork chain snapshot is reused while the tip doesn't move
+pub const GETWORK_TEMPLATE_REFRESH_SECS: u64 = 10;
+
 /// Sync pause check interval in milliseconds
 pub const SYNC_PAUSE_CHECK_MS: u64 = 100;
 
@@ -72,6 +139,12 @@ pub const STATUS_PAGE_REFRESH_SECS: u64 = 10;
 /// Number of recent blocks to show in status page
 pub const STATUS_RECENT_BLOCKS_COUNT: usize = 80;
 
+/// Number of latest blocks on the explorer home page
+pub const EXPLORER_RECENT_BLOCKS_COUNT: usize = 50;
+
+/// Most rows in an explorer table (commits, contracts, nominations)
+pub const EXPLORER_MAX_ROWS: usize = 200;
+
 /// Number of first blocks to show in status page
 pub const STATUS_FIRST_BLOCKS_COUNT: usize = 40;
 
@@ -87,3 +160,160 @@ pub const NETWORK_HASHRATE_SAMPLE_SIZE: usize = 10;
 /// Byzantine fault tolerance threshold (2/3 + 1) for finalized rounds
 pub const BFT_THRESHOLD_PERCENTAGE: f32 = 66.67;
 
+
+/// Interval between status history samples in seconds
+pub const STATUS_HISTORY_SAMPLE_SECS: u64 = 30;
+
+/// Interval between anomaly checks of new canonical blocks in seconds
+pub const ANOMALY_CHECK_INTERVAL_SECS: u64 = 10;
+
+/// Interval between event journal checks of the canonical chain in seconds
+pub const EVENT_JOURNAL_INTERVAL_SECS: u64 = 2;
+
+/// Most new canonical blocks journaled per check, so backfilling a long
+/// chain doesn't hold the datastore for long
+pub const EVENT_JOURNAL_BLOCKS_PER_CHECK: usize = 1000;
+
+/// Interval between checks for new events to deliver to event sinks in seconds
+pub const EVENT_SINK_INTERVAL_SECS: u64 = 2;
+
+/// Most journal events an event sink reads at once
+pub const EVENT_SINK_BATCH_SIZE: usize = 100;
+
+/// Timeout for delivering a single event to an event sink in seconds
+pub const EVENT_SINK_TIMEOUT_SECS: u64 = 10;
+
+/// First wait before retrying a failed event delivery in seconds; doubles
+/// with every failure in a row
+pub const EVENT_SINK_INITIAL_BACKOFF_SECS: u64 = 1;
+
+/// Longest wait between retries of a failed event delivery in seconds
+pub const EVENT_SINK_MAX_BACKOFF_SECS: u64 = 300;
+
+/// Interval between checks for finished epochs to report on in seconds
+pub const EPOCH_ACCOUNTING_INTERVAL_SECS: u64 = 30;
+
+/// Most epoch reports written per check, so catching up on a long chain
+/// doesn't hold the datastore for long
+pub const EPOCH_REPORTS_PER_CHECK: usize = 10;
+
+/// Upcoming epochs the duty calendar covers after the current one; hybrid
+/// consensus decides the validators two epochs ahead
+pub const DUTY_LOOKAHEAD_EPOCHS: u64 = 2;
+
+/// The readiness probe fails when the chain tip is older than this many
+/// target block times
+pub const READY_MAX_TIP_AGE_BLOCKS: i64 = 10;
+
+/// The readiness probe fails when a current validator hasn't authored a
+/// certificate for this many seconds
+pub const READY_MAX_CERTIFICATE_AGE_SECS: i64 = 120;
+
+/// Interval between bootstrapper health probes in seconds
+pub const BOOTSTRAPPER_HEALTH_INTERVAL_SECS: u64 = 300;
+
+/// Timeout for each step of a bootstrapper health probe in seconds
+pub const BOOTSTRAPPER_PROBE_TIMEOUT_SECS: u64 = 10;
+
+/// Message protocol version this node speaks, advertised as `proto=` and in
+/// its capabilities; bump it when messages change incompatibly
+pub const MESSAGE_PROTOCOL_VERSION: u32 = 1;
+
+/// Oldest message protocol version this node syncs and runs consensus with
+pub const MIN_MESSAGE_PROTOCOL_VERSION: u32 = 1;
+
+/// Largest encoded node manifest accepted in an identify agent string
+pub const MAX_MANIFEST_BYTES: usize = 2048;
+
+/// How long a peer's capabilities are trusted before asking again, in seconds
+pub const CAPABILITIES_REFRESH_SECS: i64 = 600;
+
+/// Peers that dialed this node it keeps by default
+pub const DEFAULT_MAX_INBOUND_PEERS: usize = 64;
+
+/// Peers this node dialed it keeps by default
+pub const DEFAULT_MAX_OUTBOUND_PEERS: usize = 32;
+
+/// Slots of each direction held for bootstrappers, validators and reserved peers by default
+pub const DEFAULT_RESERVED_PEER_SLOTS: usize = 8;
+
+/// Bounds of a peer's score
+pub const PEER_SCORE_MIN: i64 = -100;
+pub const PEER_SCORE_MAX: i64 = 100;
+
+/// Score a peer gains for an answered ping
+pub const PEER_SCORE_PING_OK: i64 = 1;
+
+/// Score a peer loses for a failed ping
+pub const PEER_SCORE_PING_FAILED: i64 = 5;
+
+/// Score a peer loses for an invalid message
+pub const PEER_SCORE_INVALID_MESSAGE: i64 = 20;
+
+/// Interval between checks of the static validator list for changes in seconds
+pub const STATIC_VALIDATORS_CHECK_INTERVAL_SECS: u64 = 10;
+
+/// Certified rounds a joining validator searches for the committed
+/// reconfiguration that seats it
+pub const RECONFIGURATION_LOOKBACK_ROUNDS: u64 = 200;
+
+/// Consensus rounds without a certificate before falling back to mining-only
+pub const CONSENSUS_STALL_ROUNDS: u64 = 20;
+
+/// Interval between recordings of consensus liveness for the status page, in rounds
+pub const CONSENSUS_LIVENESS_RECORD_ROUNDS: u64 = 5;
+
+/// Interval between checks for approved governance proposals in seconds
+pub const GOVERNANCE_CHECK_INTERVAL_SECS: u64 = 30;
+
+/// Number of status history samples kept in memory (24 hours at the default interval)
+pub const STATUS_HISTORY_CAPACITY: usize = 2880;
+
+/// Time span of the status page charts in seconds
+pub const STATUS_CHART_WINDOW_SECS: i64 = 86_400;
+
+/// Interval between prunes of expired metrics history in seconds
+pub const METRICS_PRUNE_INTERVAL_SECS: u64 = 3_600;
+
+/// Metrics history series written by the node
+pub const METRIC_BLOCK_HEIGHT: &str = "chain.height";
+pub const METRIC_DIFFICULTY: &str = "chain.difficulty";
+pub const METRIC_NETWORK_HASHRATE: &str = "chain.network_hashrate";
+pub const METRIC_MINER_HASHRATE: &str = "mining.hashrate";
+pub const METRIC_BLOCK_MINING_SECS: &str = "mining.block_secs";
+pub const METRIC_CONNECTED_PEERS: &str = "network.peers";
+pub const METRIC_CONSENSUS_ROUND: &str = "consensus.round";
+pub const METRIC_CACHE_HIT_RATE: &str = "datastore.cache_hit_rate";
+
+/// Maximum sequenced log entries returned per request
+pub const MAX_SEQUENCED_ENTRIES_PER_REQUEST: usize = 500;
+
+/// Maximum mempool transactions returned per request
+pub const MAX_MEMPOOL_ENTRIES_PER_REQUEST: usize = 500;
+
+/// Longest a sequenced log subscription request waits for new entries in milliseconds
+pub const SEQUENCED_LOG_MAX_WAIT_MS: u64 = 30_000;
+
+/// Interval between checks for new sequenced log entries in milliseconds
+pub const SEQUENCED_LOG_POLL_MS: u64 = 100;
+
+/// Interval between checks of the role schedule against the chain's epoch in seconds
+pub const ROLE_SCHEDULE_CHECK_INTERVAL_SECS: u64 = 10;
+
+/// Interval between indexer passes over newly stored commits in seconds
+pub const INDEXER_INTERVAL_SECS: u64 = 5;
+
+/// Maximum index query results returned per request
+pub const MAX_INDEX_RESULTS_PER_REQUEST: usize = 500;
+
+/// Results per page of GraphQL lists unless `first` asks for fewer
+pub const GRAPHQL_DEFAULT_PAGE_SIZE: u32 = 20;
+
+/// Most results per page of GraphQL lists
+pub const GRAPHQL_MAX_PAGE_SIZE: u32 = 100;
+
+/// Deepest GraphQL query accepted
+pub const GRAPHQL_MAX_DEPTH: usize = 10;
+
+/// Most complex GraphQL query accepted, counting one per field
+pub const GRAPHQL_MAX_COMPLEXITY: usize = 1_000;
@@ -0,0 +1,323 @@
+//! Checkpoint-signed contract exports
+//!
+//! An export bundles a contract's state as of a finalized checkpoint with
+//! everything needed to check it offline: the checkpoint certificate and the
+//! validator set that signed it, the contract's commits included in
+//! canonical blocks up to the checkpoint, the state they replay to and its
+//! merkle root. Whoever exports it can sign the bundle too, so an auditor
+//! knows who vouches for the commit list. `verify` needs no node: it checks
+//! the validator signatures, the commit ids, the block heights and that the
+//! commits replay to the exported state.
+
+use anyhow::{anyhow, bail, Result};
+use modal_common::canonical::{sha256_hex, CanonicalHash};
+use modal_common::keypair::Keypair;
+use modal_common::merkle::compute_merkle_root_owned;
+use modal_common::signer::Signer;
+use modal_datastore::models::miner::{CheckpointCertificate, MinerCheckpoint};
+use modal_datastore::models::validator::get_validator_set_for_epoch_multi;
+use modal_datastore::models::{Commit, MinerBlock};
+use modal_datastore::DatastoreManager;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::actions::validator::quorum::bft_threshold;
+
+/// Bundle format version
+pub const EXPORT_VERSION: u32 = 1;
+
+/// A commit of the exported contract and the block that included it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedCommit {
+    pub commit_id: String,
+    pub block_index: u64,
+    /// The commit exactly as stored, whose SHA-256 is the commit id
+    pub commit_data: String,
+}
+
+/// Signature of whoever exported the bundle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportSignature {
+    pub signer: String,
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContractExport {
+    pub version: u32,
+    pub contract_id: String,
+    pub certificate: CheckpointCertificate,
+    /// Validator set selected from the checkpointed epoch
+    pub validators: Vec<String>,
+    /// Commits in the order they were included
+    pub commits: Vec<ExportedCommit>,
+    pub state: BTreeMap<String, Value>,
+    /// Merkle root of the state, one leaf per path
+    pub state_root: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exporter: Option<ExportSignature>,
+}
+
+/// What a verified export attests
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportReport {
+    pub contract_id: String,
+    pub epoch: u64,
+    pub last_block_index: u64,
+    pub last_block_hash: String,
+    pub state_root: String,
+    pub commits: usize,
+    pub state_paths: usize,
+    /// Validators with a valid signature on the checkpoint, out of `validators`
+    pub signers: Vec<String>,
+    pub validators: usize,
+    /// Whether the validator set came from the verifier rather than the bundle
+    pub validators_trusted: bool,
+    pub exporter: Option<String>,
+}
+
+impl ContractExport {
+    /// Bundle `commits` with the checkpoint they are covered by
+    pub fn assemble(
+        contract_id: &str,
+        certificate: CheckpointCertificate,
+        validators: Vec<String>,
+        commits: Vec<ExportedCommit>,
+    ) -> Result<Self> {
+        let state = replay(commits.iter().map(|c| c.commit_data.as_str()))?;
+        Ok(Self {
+            version: EXPORT_VERSION,
+            contract_id: contract_id.to_string(),
+            certificate,
+            validators,
+            commits,
+            state_root: state_root(&state)?,
+            state,
+            exporter: None,
+        })
+    }
+
+    /// The message an exporter signs: the contract, the checkpoint, the
+    /// commits and the state they replay to
+    pub fn signing_message(&self) -> String {
+        let commit_ids: Vec<String> = self.commits.iter().map(|c| c.commit_id.clone()).collect();
+        format!(
+            "contract-export:{}:{}:{}:{}",
+            self.contract_id,
+            self.certificate.digest(),
+            compute_merkle_root_owned(&commit_ids),
+            self.state_root
+        )
+    }
+
+    pub fn sign(&mut self, signer: &dyn Signer) -> Result<()> {
+        self.exporter = Some(ExportSignature {
+            signer: signer.public_key_as_base58_identity(),
+            signature: signer.sign_string_as_base64_pad(&self.signing_message())?,
+        });
+        Ok(())
+    }
+
+    /// Check the bundle on its own. The checkpoint must be signed by 2f+1 of
+    /// `trusted_validators`, or of the bundle's validator set if not given.
+    pub fn verify(&self, trusted_validators: Option<&[String]>) -> Result<ExportReport> {
+        if self.version != EXPORT_VERSION {
+            bail!("Unsupported export version {}", self.version);
+        }
+
+        let validators = trusted_validators.unwrap_or(&self.validators);
+        if validators.is_empty() {
+            bail!("No validator set to check the checkpoint against");
+        }
+        let signers = self.certificate.valid_signers(validators);
+        let threshold = bft_threshold(validators.len());
+        if signers.len() < threshold {
+            bail!(
+                "Checkpoint has {} valid validator signatures, {} needed",
+                signers.len(),
+                threshold
+            );
+        }
+
+        let checkpoint = &self.certificate.checkpoint;
+        let mut last_index = 0;
+        for commit in &self.commits {
+            if sha256_hex(commit.commit_data.as_bytes()) != commit.commit_id {
+                bail!("Commit {} doesn't match its data", commit.commit_id);
+            }
+            if commit.block_index > checkpoint.last_block_index {
+                bail!(
+                    "Commit {} was included at block {}, after the checkpoint ({})",
+                    commit.commit_id,
+                    commit.block_index,
+                    checkpoint.last_block_index
+                );
+            }
+            if commit.block_index < last_index {
+                bail!("Commit {} is out of order", commit.commit_id);
+            }
+            last_index = commit.block_index;
+        }
+
+        if replay(self.commits.iter().map(|c| c.commit_data.as_str()))? != self.state {
+            bail!("State doesn't match what the commits replay to");
+        }
+        if state_root(&self.state)? != self.state_root {
+            bail!("State root doesn't match the state");
+        }
+
+        let exporter = match &self.exporter {
+            Some(exporter) => {
+                let key = Keypair::from_public_key(&exporter.signer, "ed25519")?;
+                if !key.verify_signature_for_string(&exporter.signature, &self.signing_message())? {
+                    bail!("Invalid exporter signature from {}", exporter.signer);
+                }
+                Some(exporter.signer.clone())
+            }
+            None => None,
+        };
+
+        Ok(ExportReport {
+            contract_id: self.contract_id.clone(),
+            epoch: checkpoint.epoch,
+            last_block_index: checkpoint.last_block_index,
+            last_block_hash: checkpoint.last_block_hash.clone(),
+            state_root: self.state_root.clone(),
+            commits: self.commits.len(),
+            state_paths: self.state.len(),
+            signers,
+            validators: validators.len(),
+            validators_trusted: trusted_validators.is_some(),
+            exporter,
+        })
+    }
+}
+
+/// Export a contract as of the finalized checkpoint of `epoch`
+pub async fn build(mgr: &DatastoreManager, contract_id: &str, epoch: u64) -> Result<ContractExport> {
+    let checkpoint = MinerCheckpoint::find_by_epoch_multi(mgr, epoch)
+        .await?
+        .ok_or_else(|| anyhow!("No finalized checkpoint for epoch {}", epoch))?;
+    let digest = CheckpointCertificate::new(checkpoint.clone()).digest();
+    let certificate = CheckpointCertificate::find_by_digest_multi(mgr, epoch, &digest)
+        .await?
+        .ok_or_else(|| anyhow!("Checkpoint for epoch {} has no validator certificate", epoch))?;
+    let validators = get_validator_set_for_epoch_multi(mgr, epoch).await?.get_active_validators();
+
+    let mut stored: HashMap<String, Commit> = Commit::find_by_contract_multi(mgr, contract_id)
+        .await?
+        .into_iter()
+        .map(|commit| (commit.commit_id.clone(), commit))
+        .collect();
+    let mut blocks: Vec<MinerBlock> = MinerBlock::find_all_canonical_multi(mgr)
+        .await?
+        .into_iter()
+        .filter(|block| block.index <= checkpoint.last_block_index)
+        .collect();
+    blocks.sort_by_key(|block| block.index);
+
+    let mut commits = Vec::new();
+    for block in blocks {
+        for digest in block.commits.iter().filter(|c| c.contract_id == contract_id) {
+            let commit = stored
+                .remove(&digest.commit_id)
+                .ok_or_else(|| anyhow!("Commit {} included at block {} isn't stored", digest.commit_id, block.index))?;
+            commits.push(ExportedCommit {
+                commit_id: commit.commit_id,
+                block_index: block.index,
+                commit_data: commit.commit_data,
+            });
+        }
+    }
+
+    ContractExport::assemble(contract_id, certificate, validators, commits)
+}
+
+/// Replay commits, given as stored, into the state they leave behind
+pub fn replay<'a>(commits: impl IntoIterator<Item = &'a str>) -> Result<BTreeMap<String, Value>> {
+    let mut state = BTreeMap::new();
+    for commit_data in commits {
+        let data: Value = serde_json::from_str(commit_data)?;
+        let Some(actions) = data.get("body").and_then(|b| b.as_array()) else {
+            continue;
+        };
+        for action in actions {
+            let (Some(method), Some(path)) = (
+                action.get("method").and_then(|m| m.as_str()),
+                action.get("path").and_then(|p| p.as_str()),
+            ) else {
+                continue;
+            };
+            if matches!(method, "post" | "genesis" | "rule" | "repost") {
+                state.insert(path.to_string(), action.get("value").cloned().unwrap_or(Value::Null));
+            }
+        }
+    }
+    Ok(state)
+}
+
+/// Merkle root of the state, with a leaf per path in path order
+fn state_root(state: &BTreeMap<String, Value>) -> Result<String> {
+    let leaves = state
+        .iter()
+        .map(|(path, value)| Ok(sha256_hex(format!("{}:{}", path, value.canonical_json()?).as_bytes())))
+        .collect::<Result<Vec<String>>>()?;
+    Ok(compute_merkle_root_owned(&leaves))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn commit(block_index: u64, body: Value) -> ExportedCommit {
+        let commit_data = json!({"body": body, "head": {}}).canonical_json().unwrap();
+        ExportedCommit {
+            commit_id: sha256_hex(commit_data.as_bytes()),
+            block_index,
+            commit_data,
+        }
+    }
+
+    #[test]
+    fn test_export_verifies_offline() {
+        let keypairs: Vec<Keypair> = (0..4).map(|_| Keypair::generate().unwrap()).collect();
+        let validators: Vec<String> = keypairs.iter().map(|k| k.as_public_key_id()).collect();
+        let checkpoint = MinerCheckpoint::new_consensus(2, 4, 119, "hash_119".to_string(), "merkle_2".to_string(), 40, 30);
+        let mut certificate = CheckpointCertificate::new(checkpoint);
+        for (validator, keypair) in validators.iter().zip(&keypairs).take(3) {
+            certificate.sign(validator, keypair).unwrap();
+        }
+        let commits = vec![
+            commit(90, json!([{"method": "post", "path": "/data/balance.number", "value": 10}])),
+            commit(101, json!([{"method": "post", "path": "/data/balance.number", "value": 7}])),
+        ];
+
+        let mut export = ContractExport::assemble("contract1", certificate, validators.clone(), commits).unwrap();
+        assert_eq!(export.state["/data/balance.number"], json!(7));
+        let exporter = Keypair::generate().unwrap();
+        export.sign(&exporter).unwrap();
+
+        let report = export.verify(None).unwrap();
+        assert_eq!(report.signers.len(), 3);
+        assert_eq!(report.exporter, Some(exporter.public_key_as_base58_identity()));
+        assert!(!report.validators_trusted);
+
+        // A validator set the bundle's signers aren't in
+        let others: Vec<String> = (0..4).map(|_| Keypair::generate().unwrap().as_public_key_id()).collect();
+        assert!(export.verify(Some(&others)).is_err());
+
+        let mut tampered = export.clone();
+        tampered.state.insert("/data/balance.number".to_string(), json!(1000));
+        assert!(tampered.verify(None).is_err());
+
+        let mut late = export.clone();
+        late.commits.push(commit(120, json!([])));
+        assert!(late.verify(None).is_err());
+
+        let mut resigned = export;
+        resigned.state_root = "0".repeat(64);
+        assert!(resigned.verify(None).is_err());
+    }
+}
@@ -0,0 +1,422 @@
+//! Contract sync protocol (`/modality/contract/1.0.0`).
+//!
+//! Pushing and pulling a contract happens in two steps. First the peers
+//! negotiate: the requester says which commits it has and which it would like
+//! to send, and the responder answers with the commits the requester is
+//! missing and the ones it wants. Then each commit is transferred in chunks,
+//! so large commits don't have to fit in one message and an interrupted
+//! transfer picks up from the last chunk that arrived.
+
+use anyhow::Result;
+use base64::Engine;
+use libp2p::request_response;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{LazyLock, Mutex};
+
+use modal_datastore::models::Commit;
+use modal_datastore::DatastoreManager;
+
+pub const PROTOCOL: &str = "/modality/contract/1.0.0";
+
+/// Bytes of commit payload per chunk
+pub const CHUNK_SIZE: usize = 256 * 1024;
+
+/// Largest commit payload a node accepts
+pub const MAX_COMMIT_SIZE: u64 = 64 * 1024 * 1024;
+
+pub type Behaviour = request_response::json::Behaviour<ContractSyncRequest, ContractSyncResponse>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContractSyncRequest {
+    /// Have/want exchange: the commits the requester has and the ones it
+    /// would like to send
+    Negotiate {
+        contract_id: String,
+        have: Vec<String>,
+        offer: Vec<String>,
+    },
+    /// A chunk of a commit the responder has, starting at `offset`
+    GetChunk {
+        contract_id: String,
+        commit_id: String,
+        offset: u64,
+    },
+    /// A chunk of a commit the requester is sending
+    PutChunk {
+        contract_id: String,
+        commit_id: String,
+        offset: u64,
+        total: u64,
+        /// Base64 of the chunk's bytes
+        data: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContractSyncResponse {
+    Negotiated {
+        /// The responder's latest commit for the contract
+        head: Option<String>,
+        /// Commits the requester doesn't have, parents first
+        missing: Vec<CommitSummary>,
+        /// Offered commits the responder wants, with how much of each it
+        /// already received in earlier, interrupted pushes
+        want: Vec<WantedCommit>,
+    },
+    Chunk {
+        commit_id: String,
+        offset: u64,
+        total: u64,
+        data: String,
+    },
+    /// How much of a pushed commit the responder holds. When `received`
+    /// differs from what the requester sent, it resends from `received`.
+    Stored {
+        commit_id: String,
+        received: u64,
+        complete: bool,
+    },
+    Error {
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitSummary {
+    pub commit_id: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WantedCommit {
+    pub commit_id: String,
+    pub offset: u64,
+}
+
+/// Returned when the peer doesn't speak the contract sync protocol, so the
+/// caller can fall back to `/contract/push` and `/contract/pull`
+#[derive(Debug)]
+pub struct UnsupportedProtocol;
+
+impl std::fmt::Display for UnsupportedProtocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "peer doesn't support {}", PROTOCOL)
+    }
+}
+
+impl std::error::Error for UnsupportedProtocol {}
+
+/// Commit payloads received so far, by (contract id, commit id). They
+/// outlive the connection so that a push resumes where it was cut off.
+static UPLOADS: LazyLock<Mutex<HashMap<(String, String), Vec<u8>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// SHA-256 of a commit payload, which is its commit id. Payloads are
+/// canonical JSON, see `modal_common::canonical`.
+pub fn commit_id_of(payload: &[u8]) -> String {
+    modal_common::canonical::sha256_hex(payload)
+}
+
+pub fn encode_chunk(bytes: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+pub fn decode_chunk(data: &str) -> Result<Vec<u8>> {
+    Ok(base64::engine::general_purpose::STANDARD.decode(data)?)
+}
+
+pub async fn handle_request(
+    req: ContractSyncRequest,
+    datastore_manager: &DatastoreManager,
+) -> ContractSyncResponse {
+    let result = match req {
+        ContractSyncRequest::Negotiate { contract_id, have, offer } => {
+            negotiate(datastore_manager, &contract_id, &have, &offer).await
+        }
+        ContractSyncRequest::GetChunk { contract_id, commit_id, offset } => {
+            get_chunk(datastore_manager, &contract_id, &commit_id, offset).await
+        }
+        ContractSyncRequest::PutChunk { contract_id, commit_id, offset, total, data } => {
+            put_chunk(datastore_manager, &contract_id, &commit_id, offset, total, &data).await
+        }
+    };
+    result.unwrap_or_else(|e| ContractSyncResponse::Error { message: e.to_string() })
+}
+
+async fn negotiate(
+    datastore_manager: &DatastoreManager,
+    contract_id: &str,
+    have: &[String],
+    offer: &[String],
+) -> Result<ContractSyncResponse> {
+    let commits = order_by_parent(Commit::find_by_contract_multi(datastore_manager, contract_id).await?);
+    let head = commits.last().map(|c| c.commit_id.clone());
+
+    let have: HashSet<&String> = have.iter().collect();
+    let missing = commits
+        .iter()
+        .filter(|c| !have.contains(&c.commit_id))
+        .map(|c| CommitSummary {
+            commit_id: c.commit_id.clone(),
+            size: c.commit_data.len() as u64,
+        })
+        .collect();
+
+    let ours: HashSet<&String> = commits.iter().map(|c| &c.commit_id).collect();
+    let uploads = UPLOADS.lock().unwrap();
+    let want = offer
+        .iter()
+        .filter(|id| !ours.contains(id))
+        .map(|id| WantedCommit {
+            commit_id: id.clone(),
+            offset: uploads
+                .get(&(contract_id.to_string(), id.clone()))
+                .map(|buf| buf.len() as u64)
+                .unwrap_or(0),
+        })
+        .collect();
+
+    Ok(ContractSyncResponse::Negotiated { head, missing, want })
+}
+
+async fn get_chunk(
+    datastore_manager: &DatastoreManager,
+    contract_id: &str,
+    commit_id: &str,
+    offset: u64,
+) -> Result<ContractSyncResponse> {
+    let keys = [
+        ("contract_id".to_string(), contract_id.to_string()),
+        ("commit_id".to_string(), commit_id.to_string()),
+    ].into_iter().collect();
+    let commit = Commit::find_one_multi(datastore_manager, keys)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Commit not found: {}", commit_id))?;
+
+    let payload = commit.commit_data.as_bytes();
+    let start = (offset as usize).min(payload.len());
+    let end = (start + CHUNK_SIZE).min(payload.len());
+
+    Ok(ContractSyncResponse::Chunk {
+        commit_id: commit_id.to_string(),
+        offset: start as u64,
+        total: payload.len() as u64,
+        data: encode_chunk(&payload[start..end]),
+    })
+}
+
+async fn put_chunk(
+    datastore_manager: &DatastoreManager,
+    contract_id: &str,
+    commit_id: &str,
+    offset: u64,
+    total: u64,
+    data: &str,
+) -> Result<ContractSyncResponse> {
+    if total > MAX_COMMIT_SIZE {
+        anyhow::bail!("Commit {} is {} bytes; the limit is {}", commit_id, total, MAX_COMMIT_SIZE);
+    }
+
+    let key = (contract_id.to_string(), commit_id.to_string());
+    let payload = {
+        let mut uploads = UPLOADS.lock().unwrap();
+        let buf = uploads.entry(key.clone()).or_default();
+        if offset != buf.len() as u64 {
+            // Out of step, e.g. a resend of a chunk that did arrive
+            return Ok(ContractSyncResponse::Stored {
+                commit_id: commit_id.to_string(),
+                received: buf.len() as u64,
+                complete: false,
+            });
+        }
+        buf.extend_from_slice(&decode_chunk(data)?);
+        if (buf.len() as u64) < total {
+            return Ok(ContractSyncResponse::Stored {
+                commit_id: commit_id.to_string(),
+                received: buf.len() as u64,
+                complete: false,
+            });
+        }
+        uploads.remove(&key).unwrap_or_default()
+    };
+
+    if commit_id_of(&payload) != commit_id {
+        anyhow::bail!("Commit {} doesn't match its contents", commit_id);
+    }
+    let commit_data = String::from_utf8(payload)?;
+    // Check it's a commit before storing it
+    let parsed: serde_json::Value = serde_json::from_str(&commit_data)?;
+    if parsed.get("body").is_none() || parsed.get("head").is_none() {
+        anyhow::bail!("Commit {} has no body or head", commit_id);
+    }
+
+    let commit = Commit {
+        contract_id: contract_id.to_string(),
+        commit_id: commit_id.to_string(),
+        commit_data,
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs(),
+        in_batch: None,
+    };
+    commit.save_to_final(datastore_manager).await?;
+    log::info!("Received commit {} for contract {}", commit_id, contract_id);
+
+    Ok(ContractSyncResponse::Stored {
+        commit_id: commit_id.to_string(),
+        received: total,
+        complete: true,
+    })
+}
+
+/// Put commits in history order, parents before children. Commits are stored
+/// by id, so they come out of the datastore in hash order.
+pub fn order_by_parent(commits: Vec<Commit>) -> Vec<Commit> {
+    let parent_of = |commit: &Commit| -> Option<String> {
+        serde_json::from_str::<serde_json::Value>(&commit.commit_data)
+            .ok()
+            .and_then(|data| data.get("head")?.get("parent")?.as_str().map(|s| s.to_string()))
+    };
+
+    let ids: HashSet<String> = commits.iter().map(|c| c.commit_id.clone()).collect();
+    let mut children: HashMap<Option<String>, Vec<Commit>> = HashMap::new();
+    for commit in commits {
+        // Commits whose parent we don't have start a line of history
+        let parent = parent_of(&commit).filter(|p| ids.contains(p));
+        children.entry(parent).or_default().push(commit);
+    }
+
+    let mut ordered = Vec::new();
+    let mut next = children.remove(&None).unwrap_or_default();
+    next.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then_with(|| a.commit_id.cmp(&b.commit_id)));
+    next.reverse();
+    while let Some(commit) = next.pop() {
+        if let Some(mut kids) = children.remove(&Some(commit.commit_id.clone())) {
+            kids.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then_with(|| a.commit_id.cmp(&b.commit_id)));
+            kids.reverse();
+            next.extend(kids);
+        }
+        ordered.push(commit);
+    }
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(value: &str, parent: Option<&str>) -> String {
+        serde_json::to_string(&serde_json::json!({
+            "body": [{ "method": "post", "path": "/a.text", "value": value }],
+            "head": { "parent": parent },
+        }))
+        .unwrap()
+    }
+
+    async fn save(mgr: &DatastoreManager, contract_id: &str, data: &str) -> String {
+        let commit_id = commit_id_of(data.as_bytes());
+        Commit {
+            contract_id: contract_id.to_string(),
+            commit_id: commit_id.clone(),
+            commit_data: data.to_string(),
+            timestamp: 0,
+            in_batch: None,
+        }
+        .save_to_final(mgr)
+        .await
+        .unwrap();
+        commit_id
+    }
+
+    #[tokio::test]
+    async fn test_negotiate_orders_missing_commits() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        let first = save(&mgr, "sync-negotiate", &payload("1", None)).await;
+        let second = save(&mgr, "sync-negotiate", &payload("2", Some(&first))).await;
+        let third = save(&mgr, "sync-negotiate", &payload("3", Some(&second))).await;
+
+        let req = ContractSyncRequest::Negotiate {
+            contract_id: "sync-negotiate".into(),
+            have: vec![first.clone()],
+            offer: vec![second.clone(), "new".into()],
+        };
+        match handle_request(req, &mgr).await {
+            ContractSyncResponse::Negotiated { head, missing, want } => {
+                assert_eq!(head, Some(third.clone()));
+                let missing: Vec<String> = missing.into_iter().map(|c| c.commit_id).collect();
+                assert_eq!(missing, vec![second, third]);
+                assert_eq!(want, vec![WantedCommit { commit_id: "new".into(), offset: 0 }]);
+            }
+            other => panic!("Expected Negotiated, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chunked_push_resumes() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        let data = payload(&"x".repeat(CHUNK_SIZE + 10), None);
+        let commit_id = commit_id_of(data.as_bytes());
+        let bytes = data.as_bytes();
+        let total = bytes.len() as u64;
+        let put = |offset: usize, end: usize| ContractSyncRequest::PutChunk {
+            contract_id: "sync-push".into(),
+            commit_id: commit_id.clone(),
+            offset: offset as u64,
+            total,
+            data: encode_chunk(&bytes[offset..end]),
+        };
+
+        let res = handle_request(put(0, CHUNK_SIZE), &mgr).await;
+        assert!(matches!(res, ContractSyncResponse::Stored { complete: false, received, .. } if received == CHUNK_SIZE as u64));
+
+        // After a disconnect, negotiation reports how far the push got
+        let req = ContractSyncRequest::Negotiate {
+            contract_id: "sync-push".into(),
+            have: vec![],
+            offer: vec![commit_id.clone()],
+        };
+        match handle_request(req, &mgr).await {
+            ContractSyncResponse::Negotiated { want, .. } => assert_eq!(want[0].offset, CHUNK_SIZE as u64),
+            other => panic!("Expected Negotiated, got {:?}", other),
+        }
+
+        // A resent chunk is ignored
+        let res = handle_request(put(0, CHUNK_SIZE), &mgr).await;
+        assert!(matches!(res, ContractSyncResponse::Stored { complete: false, received, .. } if received == CHUNK_SIZE as u64));
+
+        let res = handle_request(put(CHUNK_SIZE, bytes.len()), &mgr).await;
+        assert!(matches!(res, ContractSyncResponse::Stored { complete: true, .. }));
+
+        // The stored commit reads back the same, chunk by chunk
+        let mut pulled = Vec::new();
+        while (pulled.len() as u64) < total {
+            let req = ContractSyncRequest::GetChunk {
+                contract_id: "sync-push".into(),
+                commit_id: commit_id.clone(),
+                offset: pulled.len() as u64,
+            };
+            match handle_request(req, &mgr).await {
+                ContractSyncResponse::Chunk { data, .. } => pulled.extend(decode_chunk(&data).unwrap()),
+                other => panic!("Expected Chunk, got {:?}", other),
+            }
+        }
+        assert_eq!(pulled, bytes);
+    }
+
+    #[tokio::test]
+    async fn test_push_rejects_mismatched_commit() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        let data = payload("1", None);
+        let req = ContractSyncRequest::PutChunk {
+            contract_id: "sync-mismatch".into(),
+            commit_id: "not-the-hash".into(),
+            offset: 0,
+            total: data.len() as u64,
+            data: encode_chunk(data.as_bytes()),
+        };
+        assert!(matches!(handle_request(req, &mgr).await, ContractSyncResponse::Error { .. }));
+    }
+}
@@ -0,0 +1,215 @@
+//! Validator duty calendar
+//!
+//! Under hybrid consensus the validators of mining epoch N are selected from
+//! the nominations in epoch N-2, so a node knows for certain whether it
+//! validates the current and the next epoch, and can tell from the
+//! nominations so far whether it's likely to validate the one after. With
+//! static validators, the configured list decides every epoch.
+
+use modal_datastore::models::validator::generate_validator_set_from_epoch_multi;
+use modal_datastore::models::{MinerBlock, ValidatorSet};
+use modal_datastore::DatastoreManager;
+use serde::{Deserialize, Serialize};
+
+use crate::constants::DUTY_LOOKAHEAD_EPOCHS;
+
+/// Epochs before this one have no hybrid validator set (no epoch N-2)
+const FIRST_HYBRID_EPOCH: u64 = 2;
+
+/// How settled a duty is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DutyStatus {
+    /// The validator set is decided
+    Final,
+    /// The nomination epoch is still in progress; based on nominations so far
+    Provisional,
+    /// The nomination epoch has no blocks yet
+    Unknown,
+}
+
+/// What the node does in an epoch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DutyRole {
+    Validator,
+    Alternate,
+    None,
+}
+
+/// The node's duty in one mining epoch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EpochDuty {
+    pub epoch: u64,
+    /// Epoch whose nominations select the validators (hybrid only)
+    pub nomination_epoch: Option<u64>,
+    pub status: DutyStatus,
+    pub role: DutyRole,
+    /// Nominations credited to the node in the nomination epoch
+    pub nominations: u64,
+    /// Size of the active validator set
+    pub validators: usize,
+    /// First block height of the epoch
+    pub start_height: u64,
+    /// Unix timestamp the epoch started at, or is expected to at the target block time
+    pub estimated_start: Option<i64>,
+}
+
+/// The node's duties in the current and upcoming epochs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DutyCalendar {
+    pub peer_id: String,
+    /// "static" or "hybrid"
+    pub mode: String,
+    pub current_epoch: u64,
+    pub tip_height: Option<u64>,
+    pub duties: Vec<EpochDuty>,
+}
+
+impl DutyCalendar {
+    /// Duty in the current epoch
+    pub fn current(&self) -> Option<&EpochDuty> {
+        self.duties.iter().find(|d| d.epoch == self.current_epoch)
+    }
+
+    /// Whether the node validates the current epoch
+    pub fn is_validator_now(&self) -> bool {
+        self.current().is_some_and(|d| d.role == DutyRole::Validator)
+    }
+}
+
+fn role_in(set: &ValidatorSet, peer_id: &str) -> DutyRole {
+    if set.is_active_validator(peer_id) {
+        DutyRole::Validator
+    } else if set.is_alternate_validator(peer_id) {
+        DutyRole::Alternate
+    } else {
+        DutyRole::None
+    }
+}
+
+/// Predict `peer_id`'s duties for the current epoch and the
+/// `DUTY_LOOKAHEAD_EPOCHS` after it
+pub async fn duty_calendar(mgr: &DatastoreManager, peer_id: &str) -> anyhow::Result<DutyCalendar> {
+    let canonical = MinerBlock::find_all_canonical_multi(mgr).await?;
+    let tip = canonical.iter().max_by_key(|b| b.index);
+    let current_epoch = tip.map(|b| mgr.block_index_to_epoch(b.index)).unwrap_or(0);
+    let schedule = mgr.era_schedule();
+    let static_validators = mgr.get_static_validators().await?;
+
+    let mut duties = Vec::new();
+    for epoch in current_epoch..=current_epoch + DUTY_LOOKAHEAD_EPOCHS {
+        let start_height = schedule.epoch_start(epoch);
+        let estimated_start = match tip {
+            Some(tip) if start_height <= tip.index => {
+                canonical.iter().find(|b| b.index == start_height).map(|b| b.timestamp)
+            }
+            Some(tip) => {
+                let block_time = schedule.target_block_time_at(tip.index + 1) as i64;
+                Some(tip.timestamp + (start_height - tip.index) as i64 * block_time)
+            }
+            None => None,
+        };
+        let mut duty = EpochDuty {
+            epoch,
+            nomination_epoch: None,
+            status: DutyStatus::Final,
+            role: DutyRole::None,
+            nominations: 0,
+            validators: 0,
+            start_height,
+            estimated_start,
+        };
+
+        if let Some(validators) = &static_validators {
+            duty.validators = validators.len();
+            if validators.iter().any(|v| v == peer_id) {
+                duty.role = DutyRole::Validator;
+            }
+        } else if epoch >= FIRST_HYBRID_EPOCH {
+            let nomination_epoch = epoch - FIRST_HYBRID_EPOCH;
+            duty.nomination_epoch = Some(nomination_epoch);
+            match generate_validator_set_from_epoch_multi(mgr, nomination_epoch).await {
+                Ok(set) => {
+                    duty.status = if nomination_epoch < current_epoch {
+                        DutyStatus::Final
+                    } else {
+                        DutyStatus::Provisional
+                    };
+                    duty.role = role_in(&set, peer_id);
+                    duty.nominations = set.get_validator_stake(peer_id);
+                    duty.validators = set.get_active_validators().len();
+                }
+                Err(_) => duty.status = DutyStatus::Unknown,
+            }
+        }
+        duties.push(duty);
+    }
+
+    Ok(DutyCalendar {
+        peer_id: peer_id.to_string(),
+        mode: if static_validators.is_some() { "static" } else { "hybrid" }.to_string(),
+        current_epoch,
+        tip_height: tip.map(|b| b.index),
+        duties,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use modal_common::eras::EraSchedule;
+
+    fn block(index: u64, nominee: &str) -> MinerBlock {
+        MinerBlock::new_canonical(
+            format!("hash{}", index),
+            index,
+            index / 2,
+            1_700_000_000 + index as i64 * 60,
+            String::new(),
+            String::new(),
+            index as u128,
+            1000,
+            nominee.to_string(),
+            index,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_duties() {
+        let mut mgr = DatastoreManager::create_in_memory().unwrap();
+        mgr.set_era_schedule(EraSchedule::fixed(2, 60));
+        // Epoch 0 nominates alice, epoch 1 bob, epoch 2 (in progress) alice
+        for (index, nominee) in [(0, "alice"), (1, "alice"), (2, "bob"), (3, "bob"), (4, "alice")] {
+            block(index, nominee).save_to_active(&mgr).await.unwrap();
+        }
+
+        let calendar = duty_calendar(&mgr, "alice").await.unwrap();
+        assert_eq!(calendar.mode, "hybrid");
+        assert_eq!(calendar.current_epoch, 2);
+        let summary: Vec<(u64, DutyStatus, DutyRole)> =
+            calendar.duties.iter().map(|d| (d.epoch, d.status, d.role)).collect();
+        assert_eq!(summary, vec![
+            (2, DutyStatus::Final, DutyRole::Validator),
+            (3, DutyStatus::Final, DutyRole::None),
+            (4, DutyStatus::Provisional, DutyRole::Validator),
+        ]);
+        assert!(calendar.is_validator_now());
+        assert_eq!(calendar.duties[0].nominations, 2);
+        assert_eq!(calendar.duties[0].estimated_start, Some(1_700_000_000 + 4 * 60));
+        assert_eq!(calendar.duties[2].start_height, 8);
+        assert_eq!(calendar.duties[2].estimated_start, Some(1_700_000_000 + 8 * 60));
+    }
+
+    #[tokio::test]
+    async fn test_static_duties() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        mgr.set_static_validators(&["alice".to_string()]).await.unwrap();
+
+        let calendar = duty_calendar(&mgr, "alice").await.unwrap();
+        assert_eq!(calendar.mode, "static");
+        assert!(calendar.is_validator_now());
+        assert_eq!(calendar.duties.len() as u64, DUTY_LOOKAHEAD_EPOCHS + 1);
+        assert!(!duty_calendar(&mgr, "bob").await.unwrap().is_validator_now());
+    }
+}
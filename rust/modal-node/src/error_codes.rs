@@ -0,0 +1,40 @@
+//! Error codes for errors that reach a node as `anyhow::Error`
+//!
+//! Handlers bubble crate errors up through anyhow; `code_of` finds the
+//! first one in the chain that has a registered code, so reqres responses
+//! can carry it.
+
+use modal_common::error_codes::{ErrorCode, HasErrorCode};
+
+/// Code of the first error in `err`'s chain that has one, or `Internal`
+pub fn code_of(err: &anyhow::Error) -> ErrorCode {
+    err.chain().find_map(code_of_cause).unwrap_or(ErrorCode::Internal)
+}
+
+fn code_of_cause(cause: &(dyn std::error::Error + 'static)) -> Option<ErrorCode> {
+    fn code<E: HasErrorCode + std::error::Error + 'static>(cause: &(dyn std::error::Error + 'static)) -> Option<ErrorCode> {
+        cause.downcast_ref::<E>().map(HasErrorCode::error_code)
+    }
+    code::<modal_validator::MempoolError>(cause)
+        .or_else(|| code::<modal_validator::ModalityError>(cause))
+        .or_else(|| code::<modal_validator::ValidatorError>(cause))
+        .or_else(|| code::<modal_observer::ValidationError>(cause))
+        .or_else(|| code::<modal_miner::error::MiningError>(cause))
+        .or_else(|| code::<modal_datastore::Error>(cause))
+        .or_else(|| code::<modal_rpc::RpcError>(cause))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_code_of_finds_wrapped_error() {
+        let err: anyhow::Result<()> = Err(modal_validator::MempoolError::Full { min_fee: 5 }.into());
+        let err = err.context("Failed to queue transaction").unwrap_err();
+        assert_eq!(code_of(&err), ErrorCode::MempoolFull);
+
+        assert_eq!(code_of(&anyhow::anyhow!("something broke")), ErrorCode::Internal);
+    }
+}
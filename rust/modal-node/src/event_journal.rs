@@ -0,0 +1,157 @@
+//! Chain events for the event journal
+//!
+//! Journals a `new_block` event for every block that joins the canonical
+//! chain and a `reorg` event whenever the chain switches branches. Instead of
+//! listening for blocks as they're accepted, the task compares the canonical
+//! chain with the last block it journaled, recorded in node_state, so blocks
+//! accepted while it was behind or before a restart are journaled too. After
+//! a crash a block may be journaled twice; consumers should key on its hash.
+
+use modal_datastore::models::MinerBlock;
+use modal_datastore::{DatastoreManager, JournalEventKind, Store};
+use modal_observer::{BlockRef, ReorgEvent};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, Mutex};
+
+use crate::constants::{EVENT_JOURNAL_BLOCKS_PER_CHECK, EVENT_JOURNAL_INTERVAL_SECS};
+
+/// Key of the last journaled canonical block, in node_state
+const JOURNALED_TIP_KEY: &str = "/journal/chain_tip";
+
+/// Spawn a task that journals canonical chain changes until shutdown
+pub fn start_event_journal(
+    datastore_manager: Arc<Mutex<DatastoreManager>>,
+    mut shutdown_rx: broadcast::Receiver<()>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(EVENT_JOURNAL_INTERVAL_SECS));
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => break,
+                _ = interval.tick() => {
+                    let mgr = datastore_manager.lock().await;
+                    if let Err(e) = journal_chain_updates(&mgr).await {
+                        log::warn!("Failed to journal chain events: {}", e);
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Journal the canonical chain changes since the last call. Returns the
+/// number of events appended.
+pub async fn journal_chain_updates(mgr: &DatastoreManager) -> anyhow::Result<usize> {
+    let canonical = MinerBlock::find_all_canonical_multi(mgr).await?;
+    let journaled: Option<BlockRef> = match mgr.node_state().get(JOURNALED_TIP_KEY)? {
+        Some(value) => Some(serde_json::from_slice(&value)?),
+        None => None,
+    };
+    let is_canonical = |block: &BlockRef| {
+        canonical.iter().any(|b| b.index == block.index && b.hash == block.hash)
+    };
+
+    let mut appended = 0;
+    let next_index = match journaled {
+        Some(tip) if is_canonical(&tip) => tip.index + 1,
+        Some(old_tip) => {
+            // Walk back from the old tip until we're on the canonical chain again
+            let mut orphaned = Vec::new();
+            let mut common_ancestor = None;
+            let mut hash = old_tip.hash.clone();
+            while let Some(block) = MinerBlock::find_by_hash_multi(mgr, &hash).await? {
+                let block_ref = BlockRef::from(&block);
+                if is_canonical(&block_ref) {
+                    common_ancestor = Some(block_ref);
+                    break;
+                }
+                orphaned.push(block_ref);
+                if block.index == 0 {
+                    break;
+                }
+                hash = block.previous_hash;
+            }
+            orphaned.reverse();
+            let Some(new_tip) = canonical.last() else {
+                return Ok(0);
+            };
+            let event = ReorgEvent::new(
+                old_tip,
+                BlockRef::from(new_tip),
+                common_ancestor.clone(),
+                orphaned,
+                "Canonical chain switched branches".to_string(),
+            );
+            mgr.append_event(JournalEventKind::Reorg, serde_json::to_value(&event)?)?;
+            appended += 1;
+            common_ancestor.map(|b| b.index + 1).unwrap_or(0)
+        }
+        None => 0,
+    };
+
+    for block in canonical.iter().filter(|b| b.index >= next_index).take(EVENT_JOURNAL_BLOCKS_PER_CHECK) {
+        mgr.append_event(JournalEventKind::NewBlock, serde_json::json!({
+            "index": block.index,
+            "hash": block.hash,
+            "previous_hash": block.previous_hash,
+            "epoch": block.epoch,
+            "timestamp": block.timestamp,
+            "nominated_peer_id": block.nominated_peer_id,
+            "commits": block.commits.len(),
+        }))?;
+        mgr.node_state().put(JOURNALED_TIP_KEY, &serde_json::to_vec(&BlockRef::from(block))?)?;
+        appended += 1;
+    }
+    Ok(appended)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(index: u64, hash: &str, previous_hash: &str) -> MinerBlock {
+        MinerBlock::new_canonical(
+            hash.to_string(),
+            index,
+            0,
+            1_700_000_000 + index as i64,
+            previous_hash.to_string(),
+            String::new(),
+            0,
+            1000,
+            "peer".to_string(),
+            index,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_journal_blocks_and_reorgs() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        block(0, "a0", "").save_to_active(&mgr).await.unwrap();
+        block(1, "a1", "a0").save_to_active(&mgr).await.unwrap();
+        block(2, "a2", "a1").save_to_active(&mgr).await.unwrap();
+        assert_eq!(journal_chain_updates(&mgr).await.unwrap(), 3);
+        assert_eq!(journal_chain_updates(&mgr).await.unwrap(), 0);
+
+        // Replace blocks 1 and 2 with another branch
+        for hash in ["a1", "a2"] {
+            let mut orphan = MinerBlock::find_by_hash_multi(&mgr, hash).await.unwrap().unwrap();
+            orphan.mark_as_orphaned("test".to_string(), None);
+            orphan.save_to_active(&mgr).await.unwrap();
+        }
+        block(1, "b1", "a0").save_to_active(&mgr).await.unwrap();
+        block(2, "b2", "b1").save_to_active(&mgr).await.unwrap();
+        block(3, "b3", "b2").save_to_active(&mgr).await.unwrap();
+        assert_eq!(journal_chain_updates(&mgr).await.unwrap(), 4);
+
+        let events = mgr.read_events(3, 10).unwrap();
+        assert_eq!(events[0].kind, JournalEventKind::Reorg);
+        let reorg: ReorgEvent = serde_json::from_value(events[0].data.clone()).unwrap();
+        assert_eq!(reorg.common_ancestor.unwrap().hash, "a0");
+        assert_eq!(reorg.orphaned.iter().map(|b| b.hash.as_str()).collect::<Vec<_>>(), vec!["a1", "a2"]);
+        assert_eq!(reorg.new_tip.hash, "b3");
+        assert_eq!(events[1].data["hash"], "b1");
+        assert_eq!(events[3].data["hash"], "b3");
+    }
+}
@@ -0,0 +1,358 @@
+//! Event sinks
+//!
+//! Delivers event journal entries (new blocks, reorgs, applied contract
+//! commits, finalized rounds) to destinations listed in the node's
+//! `event_sinks` config, e.g.:
+//!
+//! ```json
+//! "event_sinks": [
+//!   { "name": "indexer", "type": "webhook", "url": "https://indexer.example.com/events",
+//!     "events": ["new_block", "reorg"] },
+//!   { "name": "kafka", "type": "command", "program": "kcat", "args": ["-P", "-b", "localhost:9092", "-t", "modal"],
+//!     "events": ["commit_applied"], "contracts": ["my-contract"] }
+//! ]
+//! ```
+//!
+//! Webhooks get each event POSTed as JSON with an `X-Modal-Event` header
+//! naming its kind. Commands get it as a line of JSON on stdin, which covers
+//! message buses with a CLI publisher (`kcat`, `nats pub`); other
+//! destinations implement [`EventSink`]. Each sink stores its own journal
+//! cursor, so events are delivered in order and at least once, across
+//! restarts too. A failed delivery is retried with exponential backoff
+//! until it succeeds; later events wait for it.
+
+use anyhow::{anyhow, Context, Result};
+use modal_datastore::{DatastoreManager, JournalEvent, JournalEventKind};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{broadcast, Mutex};
+
+use crate::constants::{
+    EVENT_SINK_BATCH_SIZE, EVENT_SINK_INITIAL_BACKOFF_SECS, EVENT_SINK_INTERVAL_SECS,
+    EVENT_SINK_MAX_BACKOFF_SECS, EVENT_SINK_TIMEOUT_SECS,
+};
+
+/// A destination for journal events
+#[async_trait::async_trait]
+pub trait EventSink: Send + Sync {
+    /// Deliver one event. An error leaves it to be retried.
+    async fn send(&self, event: &JournalEvent) -> Result<()>;
+}
+
+/// Where a sink delivers events
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SinkTarget {
+    /// POST each event as JSON to a URL
+    Webhook {
+        url: String,
+        /// Extra request headers, e.g. for authentication
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+    /// Run a program per event with the event as a line of JSON on stdin
+    Command {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+/// A configured event sink
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventSinkConfig {
+    /// Unique name; the sink's journal cursor is stored under it
+    pub name: String,
+    #[serde(flatten)]
+    pub target: SinkTarget,
+    /// Kinds of events to deliver (default: all)
+    #[serde(default)]
+    pub events: Vec<JournalEventKind>,
+    /// Only deliver `commit_applied` events for these contracts (default: all)
+    #[serde(default)]
+    pub contracts: Vec<String>,
+}
+
+impl EventSinkConfig {
+    /// Journal consumer the sink's cursor is stored as
+    pub fn consumer(&self) -> String {
+        format!("event_sink.{}", self.name)
+    }
+
+    /// Whether the sink's filters let `event` through
+    pub fn matches(&self, event: &JournalEvent) -> bool {
+        if !self.events.is_empty() && !self.events.contains(&event.kind) {
+            return false;
+        }
+        if event.kind == JournalEventKind::CommitApplied && !self.contracts.is_empty() {
+            let contract_id = event.data["contract_id"].as_str().unwrap_or_default();
+            return self.contracts.iter().any(|c| c == contract_id);
+        }
+        true
+    }
+
+    pub fn build(&self) -> Box<dyn EventSink> {
+        match &self.target {
+            SinkTarget::Webhook { url, headers } => Box::new(WebhookSink::new(url, headers.clone())),
+            SinkTarget::Command { program, args } => Box::new(CommandSink {
+                program: program.clone(),
+                args: args.clone(),
+            }),
+        }
+    }
+}
+
+pub struct WebhookSink {
+    url: String,
+    headers: HashMap<String, String>,
+    client: reqwest::Client,
+}
+
+impl WebhookSink {
+    pub fn new(url: &str, headers: HashMap<String, String>) -> Self {
+        let client = crate::proxy::http_client_builder()
+            .timeout(Duration::from_secs(EVENT_SINK_TIMEOUT_SECS))
+            .build()
+            .unwrap_or_default();
+        Self {
+            url: url.to_string(),
+            headers,
+            client,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl EventSink for WebhookSink {
+    async fn send(&self, event: &JournalEvent) -> Result<()> {
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header("X-Modal-Event", kind_name(event.kind))
+            .json(event);
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("{} returned {}", self.url, response.status()));
+        }
+        Ok(())
+    }
+}
+
+pub struct CommandSink {
+    program: String,
+    args: Vec<String>,
+}
+
+#[async_trait::async_trait]
+impl EventSink for CommandSink {
+    async fn send(&self, event: &JournalEvent) -> Result<()> {
+        let mut child = tokio::process::Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to run {}", self.program))?;
+        let mut line = serde_json::to_vec(event)?;
+        line.push(b'\n');
+        {
+            let mut stdin = child.stdin.take().expect("stdin is piped");
+            stdin.write_all(&line).await?;
+        }
+        let status = tokio::time::timeout(Duration::from_secs(EVENT_SINK_TIMEOUT_SECS), child.wait())
+            .await
+            .map_err(|_| anyhow!("{} timed out", self.program))??;
+        if !status.success() {
+            return Err(anyhow!("{} failed ({})", self.program, status));
+        }
+        Ok(())
+    }
+}
+
+fn kind_name(kind: JournalEventKind) -> String {
+    serde_json::to_value(kind)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// How long to wait before retrying after `failures` failed deliveries in a row
+pub fn retry_delay(failures: u32) -> Duration {
+    let secs = EVENT_SINK_INITIAL_BACKOFF_SECS.saturating_mul(1 << failures.saturating_sub(1).min(16));
+    Duration::from_secs(secs.min(EVENT_SINK_MAX_BACKOFF_SECS))
+}
+
+/// Deliver the events after the sink's cursor that pass its filters,
+/// advancing the cursor past each event handled. Stops at the first failed
+/// delivery. Returns the number of events delivered.
+pub async fn deliver_events(
+    datastore_manager: &Mutex<DatastoreManager>,
+    config: &EventSinkConfig,
+    sink: &dyn EventSink,
+) -> Result<usize> {
+    let consumer = config.consumer();
+    let mut delivered = 0;
+    loop {
+        let events = {
+            let mgr = datastore_manager.lock().await;
+            let cursor = mgr.journal_cursor(&consumer)?;
+            mgr.read_events(cursor, EVENT_SINK_BATCH_SIZE)?
+        };
+        if events.is_empty() {
+            return Ok(delivered);
+        }
+        for event in events {
+            if config.matches(&event) {
+                sink.send(&event)
+                    .await
+                    .with_context(|| format!("Failed to deliver event {}", event.seq))?;
+                delivered += 1;
+            }
+            datastore_manager
+                .lock()
+                .await
+                .set_journal_cursor(&consumer, event.seq + 1)?;
+        }
+    }
+}
+
+/// Spawn a task per sink that delivers journal events until shutdown
+pub fn start_event_sinks(
+    datastore_manager: Arc<Mutex<DatastoreManager>>,
+    configs: Vec<EventSinkConfig>,
+    shutdown_tx: &broadcast::Sender<()>,
+) -> Vec<tokio::task::JoinHandle<()>> {
+    configs
+        .into_iter()
+        .map(|config| {
+            let datastore_manager = datastore_manager.clone();
+            let mut shutdown_rx = shutdown_tx.subscribe();
+            tokio::spawn(async move {
+                let sink = config.build();
+                let mut failures = 0;
+                loop {
+                    let delay = match deliver_events(&datastore_manager, &config, sink.as_ref()).await {
+                        Ok(_) => {
+                            failures = 0;
+                            Duration::from_secs(EVENT_SINK_INTERVAL_SECS)
+                        }
+                        Err(e) => {
+                            failures += 1;
+                            let delay = retry_delay(failures);
+                            log::warn!("Event sink {}: {:#}; retrying in {}s", config.name, e, delay.as_secs());
+                            delay
+                        }
+                    };
+                    tokio::select! {
+                        _ = shutdown_rx.recv() => break,
+                        _ = tokio::time::sleep(delay) => {}
+                    }
+                }
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex as StdMutex;
+
+    /// Records events and fails the first `failures` deliveries
+    struct TestSink {
+        received: StdMutex<Vec<u64>>,
+        failures: StdMutex<u32>,
+    }
+
+    #[async_trait::async_trait]
+    impl EventSink for TestSink {
+        async fn send(&self, event: &JournalEvent) -> Result<()> {
+            let mut failures = self.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                return Err(anyhow!("unavailable"));
+            }
+            self.received.lock().unwrap().push(event.seq);
+            Ok(())
+        }
+    }
+
+    fn config(value: serde_json::Value) -> EventSinkConfig {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_filters() {
+        let sink = config(serde_json::json!({
+            "name": "commits",
+            "type": "webhook",
+            "url": "http://localhost/events",
+            "events": ["commit_applied", "reorg"],
+            "contracts": ["c1"],
+        }));
+        let event = |kind, data| JournalEvent { seq: 0, kind, timestamp: 0, data };
+        assert!(sink.matches(&event(JournalEventKind::CommitApplied, serde_json::json!({ "contract_id": "c1" }))));
+        assert!(!sink.matches(&event(JournalEventKind::CommitApplied, serde_json::json!({ "contract_id": "c2" }))));
+        assert!(sink.matches(&event(JournalEventKind::Reorg, serde_json::json!({}))));
+        assert!(!sink.matches(&event(JournalEventKind::NewBlock, serde_json::json!({}))));
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(1), Duration::from_secs(EVENT_SINK_INITIAL_BACKOFF_SECS));
+        assert_eq!(retry_delay(2), Duration::from_secs(EVENT_SINK_INITIAL_BACKOFF_SECS * 2));
+        assert_eq!(retry_delay(100), Duration::from_secs(EVENT_SINK_MAX_BACKOFF_SECS));
+    }
+
+    #[tokio::test]
+    async fn test_delivery_resumes_after_failure() {
+        let mgr = Mutex::new(DatastoreManager::create_in_memory().unwrap());
+        {
+            let mgr = mgr.lock().await;
+            mgr.append_event(JournalEventKind::NewBlock, serde_json::json!({ "index": 0 })).unwrap();
+            mgr.append_event(JournalEventKind::RoundFinalized, serde_json::json!({ "round": 1 })).unwrap();
+            mgr.append_event(JournalEventKind::NewBlock, serde_json::json!({ "index": 1 })).unwrap();
+        }
+        let config = config(serde_json::json!({
+            "name": "blocks",
+            "type": "command",
+            "program": "true",
+            "events": ["new_block"],
+        }));
+        let sink = TestSink {
+            received: StdMutex::new(Vec::new()),
+            failures: StdMutex::new(1),
+        };
+
+        // The first delivery fails, so nothing is acknowledged
+        assert!(deliver_events(&mgr, &config, &sink).await.is_err());
+        assert_eq!(mgr.lock().await.journal_cursor(&config.consumer()).unwrap(), 0);
+
+        // The retry delivers the blocks and skips the round
+        assert_eq!(deliver_events(&mgr, &config, &sink).await.unwrap(), 2);
+        assert_eq!(*sink.received.lock().unwrap(), vec![0, 2]);
+        assert_eq!(mgr.lock().await.journal_cursor(&config.consumer()).unwrap(), 3);
+        assert_eq!(deliver_events(&mgr, &config, &sink).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_command_sink() {
+        let event = JournalEvent {
+            seq: 0,
+            kind: JournalEventKind::NewBlock,
+            timestamp: 0,
+            data: serde_json::json!({}),
+        };
+        let ok = config(serde_json::json!({ "name": "ok", "type": "command", "program": "cat" }));
+        ok.build().send(&event).await.unwrap();
+        let failing = config(serde_json::json!({ "name": "fail", "type": "command", "program": "false" }));
+        assert!(failing.build().send(&event).await.is_err());
+    }
+}
@@ -0,0 +1,522 @@
+//! Chain explorer
+//!
+//! An optional HTTP server, enabled with `explorer_port`, serving searchable
+//! pages for blocks, epochs, contracts, commits and validators straight from
+//! the datastore. Block lookups by epoch and nominated peer go through the
+//! MinerBlock secondary indexes.
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use warp::http::StatusCode;
+use warp::Filter;
+
+use modal_datastore::models::validator::get_validator_set_for_epoch_multi;
+use modal_datastore::models::{Commit, Contract, MinerBlock};
+use modal_datastore::DatastoreManager;
+
+use crate::constants::{EXPLORER_MAX_ROWS, EXPLORER_RECENT_BLOCKS_COUNT};
+use crate::templates::explorer::{
+    escape_html, render_explorer_page, render_fields, render_link, render_preformatted, render_table,
+    truncate_middle,
+};
+
+/// What the explorer handlers share
+#[derive(Clone)]
+pub struct ExplorerContext {
+    pub datastore_manager: Arc<Mutex<DatastoreManager>>,
+    pub network_name: String,
+}
+
+/// A rendered page, or why there is none
+enum Page {
+    Found { title: String, content: String },
+    NotFound(String),
+}
+
+impl Page {
+    fn found(title: impl Into<String>, content: String) -> Self {
+        Page::Found { title: title.into(), content }
+    }
+}
+
+/// Start the explorer server on the specified port
+pub async fn start_explorer(
+    port: u16,
+    datastore_manager: Arc<Mutex<DatastoreManager>>,
+    network_name: String,
+) -> Result<tokio::task::JoinHandle<()>, anyhow::Error> {
+    let routes = explorer_routes(ExplorerContext { datastore_manager, network_name });
+
+    log::info!("Starting chain explorer on http://0.0.0.0:{}", port);
+
+    let server = warp::serve(routes).bind(([0, 0, 0, 0], port));
+
+    let handle = tokio::spawn(async move {
+        server.await;
+    });
+
+    Ok(handle)
+}
+
+/// The explorer's routes
+pub fn explorer_routes(
+    ctx: ExplorerContext,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    let with_ctx = warp::any().map(move || ctx.clone());
+
+    let home = warp::path::end()
+        .and(with_ctx.clone())
+        .and_then(|ctx: ExplorerContext| async move { respond(&ctx, home_page(&ctx).await).await });
+
+    let search = warp::path!("search")
+        .and(warp::query::<HashMap<String, String>>())
+        .map(|query: HashMap<String, String>| {
+            let target = query.get("q").map(|q| search_target(q)).unwrap_or_else(|| "/".to_string());
+            // The target is built from escaped path segments, so it is a valid URI
+            let uri: warp::http::Uri = target.parse().unwrap_or_else(|_| warp::http::Uri::from_static("/"));
+            warp::redirect::see_other(uri)
+        });
+
+    let block = warp::path!("blocks" / String)
+        .and(with_ctx.clone())
+        .and_then(|id: String, ctx: ExplorerContext| async move {
+            let page = block_page(&ctx, &decode_segment(&id)).await;
+            respond(&ctx, page).await
+        });
+
+    let epoch = warp::path!("epochs" / u64)
+        .and(with_ctx.clone())
+        .and_then(|epoch: u64, ctx: ExplorerContext| async move {
+            let page = epoch_page(&ctx, epoch).await;
+            respond(&ctx, page).await
+        });
+
+    let contracts = warp::path!("contracts")
+        .and(with_ctx.clone())
+        .and_then(|ctx: ExplorerContext| async move { respond(&ctx, contracts_page(&ctx).await).await });
+
+    let contract = warp::path!("contracts" / String)
+        .and(with_ctx.clone())
+        .and_then(|id: String, ctx: ExplorerContext| async move {
+            let page = contract_page(&ctx, &decode_segment(&id)).await;
+            respond(&ctx, page).await
+        });
+
+    let commit = warp::path!("contracts" / String / "commits" / String)
+        .and(with_ctx.clone())
+        .and_then(|contract_id: String, commit_id: String, ctx: ExplorerContext| async move {
+            let page = commit_page(&ctx, &decode_segment(&contract_id), &decode_segment(&commit_id)).await;
+            respond(&ctx, page).await
+        });
+
+    let validators = warp::path!("validators")
+        .and(with_ctx.clone())
+        .and_then(|ctx: ExplorerContext| async move { respond(&ctx, validators_page(&ctx).await).await });
+
+    let validator = warp::path!("validators" / String)
+        .and(with_ctx)
+        .and_then(|peer_id: String, ctx: ExplorerContext| async move {
+            let page = validator_page(&ctx, &decode_segment(&peer_id)).await;
+            respond(&ctx, page).await
+        });
+
+    warp::get().and(
+        home.or(search)
+            .or(block)
+            .or(epoch)
+            .or(contracts)
+            .or(contract)
+            .or(commit)
+            .or(validators)
+            .or(validator),
+    )
+}
+
+/// Where a search query leads: block heights and hashes, `epoch:N`, peer
+/// ids and otherwise contract ids
+pub fn search_target(query: &str) -> String {
+    let query = query.trim();
+    if query.is_empty() {
+        return "/".to_string();
+    }
+    if let Some(epoch) = query.strip_prefix("epoch:").and_then(|e| e.trim().parse::<u64>().ok()) {
+        return format!("/epochs/{}", epoch);
+    }
+    let segment = percent_encode(query);
+    if query.chars().all(|c| c.is_ascii_digit()) || is_block_hash(query) {
+        format!("/blocks/{}", segment)
+    } else if query.starts_with("12D3Koo") {
+        format!("/validators/{}", segment)
+    } else {
+        format!("/contracts/{}", segment)
+    }
+}
+
+fn is_block_hash(text: &str) -> bool {
+    text.len() == 64 && text.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Characters escaped in a path segment
+const SEGMENT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.').remove(b'~');
+
+fn percent_encode(text: &str) -> String {
+    utf8_percent_encode(text, SEGMENT).to_string()
+}
+
+/// Path parameters arrive percent-encoded
+fn decode_segment(segment: &str) -> String {
+    percent_decode_str(segment).decode_utf8_lossy().into_owned()
+}
+
+/// Render a page, a 404 or a 500 as HTML
+async fn respond(
+    ctx: &ExplorerContext,
+    page: anyhow::Result<Page>,
+) -> Result<warp::reply::WithStatus<warp::reply::Html<String>>, warp::Rejection> {
+    let (status, title, content) = match page {
+        Ok(Page::Found { title, content }) => (StatusCode::OK, title, content),
+        Ok(Page::NotFound(what)) => (
+            StatusCode::NOT_FOUND,
+            "Not found".to_string(),
+            format!(r#"<div class="card"><p class="empty">{} not found</p></div>"#, escape_html(&what)),
+        ),
+        Err(e) => {
+            log::warn!("Explorer failed to render a page: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error".to_string(),
+                format!(r#"<div class="card"><p class="empty">{}</p></div>"#, escape_html(&e.to_string())),
+            )
+        }
+    };
+    let html = render_explorer_page(&ctx.network_name, &title, &content);
+    Ok(warp::reply::with_status(warp::reply::html(html), status))
+}
+
+fn block_link(block: &MinerBlock) -> String {
+    render_link(&format!("/blocks/{}", block.index), &block.index.to_string())
+}
+
+fn hash_link(hash: &str) -> String {
+    format!("<code>{}</code>", render_link(&format!("/blocks/{}", hash), &truncate_middle(hash, 8)))
+}
+
+fn epoch_link(epoch: u64) -> String {
+    render_link(&format!("/epochs/{}", epoch), &epoch.to_string())
+}
+
+fn peer_link(peer_id: &str) -> String {
+    format!("<code>{}</code>", render_link(&format!("/validators/{}", percent_encode(peer_id)), &truncate_middle(peer_id, 10)))
+}
+
+fn contract_link(contract_id: &str) -> String {
+    format!("<code>{}</code>", render_link(&format!("/contracts/{}", percent_encode(contract_id)), contract_id))
+}
+
+fn block_rows<'a>(blocks: impl Iterator<Item = &'a MinerBlock>) -> Vec<Vec<String>> {
+    blocks
+        .map(|block| {
+            vec![
+                block_link(block),
+                epoch_link(block.epoch),
+                hash_link(&block.hash),
+                peer_link(&block.nominated_peer_id),
+                block.commits.len().to_string(),
+                block.timestamp.to_string(),
+            ]
+        })
+        .collect()
+}
+
+const BLOCK_HEADERS: &[&str] = &["Height", "Epoch", "Hash", "Nominated peer", "Commits", "Timestamp"];
+
+async fn canonical_tip(mgr: &DatastoreManager) -> anyhow::Result<Option<MinerBlock>> {
+    Ok(MinerBlock::find_all_canonical_multi(mgr).await?.into_iter().max_by_key(|b| b.index))
+}
+
+async fn home_page(ctx: &ExplorerContext) -> anyhow::Result<Page> {
+    let mgr = ctx.datastore_manager.lock().await;
+    let blocks = MinerBlock::find_all_canonical_multi(&mgr).await?;
+    let tip = blocks.iter().max_by_key(|b| b.index);
+    let summary = render_fields(&[
+        ("Height", tip.map(block_link).unwrap_or_else(|| "-".to_string())),
+        ("Epoch", tip.map(|b| epoch_link(b.epoch)).unwrap_or_else(|| "-".to_string())),
+        ("Difficulty", tip.map(|b| escape_html(&b.target_difficulty)).unwrap_or_else(|| "-".to_string())),
+    ]);
+    let rows = block_rows(blocks.iter().rev().take(EXPLORER_RECENT_BLOCKS_COUNT));
+    Ok(Page::found(
+        "Latest blocks",
+        summary + &render_table(BLOCK_HEADERS, &rows, "No blocks yet"),
+    ))
+}
+
+async fn block_page(ctx: &ExplorerContext, id: &str) -> anyhow::Result<Page> {
+    let mgr = ctx.datastore_manager.lock().await;
+    let block = match id.parse::<u64>() {
+        Ok(index) => MinerBlock::find_canonical_by_index_simple(&mgr, index).await?,
+        Err(_) => MinerBlock::find_by_hash_multi(&mgr, id).await?,
+    };
+    let Some(block) = block else {
+        return Ok(Page::NotFound(format!("Block {}", id)));
+    };
+
+    let status = if block.is_canonical {
+        "canonical".to_string()
+    } else if block.is_orphaned {
+        format!("orphaned ({})", block.orphan_reason.as_deref().unwrap_or("no reason recorded"))
+    } else {
+        "pending".to_string()
+    };
+    let mut content = render_fields(&[
+        ("Height", block.index.to_string()),
+        ("Epoch", epoch_link(block.epoch)),
+        ("Hash", format!("<code>{}</code>", escape_html(&block.hash))),
+        ("Previous", if block.index > 0 { hash_link(&block.previous_hash) } else { "-".to_string() }),
+        ("Status", escape_html(&status)),
+        ("Timestamp", block.timestamp.to_string()),
+        ("Nominated peer", peer_link(&block.nominated_peer_id)),
+        ("Target difficulty", escape_html(&block.target_difficulty)),
+        ("Actualized difficulty", escape_html(&block.actualized_difficulty)),
+        ("Nonce", format!("<code>{}</code>", escape_html(&block.nonce))),
+        ("Uncles", block.uncles.len().to_string()),
+    ]);
+
+    let commit_rows: Vec<Vec<String>> = block
+        .commits
+        .iter()
+        .map(|commit| {
+            vec![
+                contract_link(&commit.contract_id),
+                format!(
+                    "<code>{}</code>",
+                    render_link(
+                        &format!("/contracts/{}/commits/{}", percent_encode(&commit.contract_id), percent_encode(&commit.commit_id)),
+                        &truncate_middle(&commit.commit_id, 8),
+                    )
+                ),
+                commit.size.to_string(),
+                commit.gas.to_string(),
+            ]
+        })
+        .collect();
+    content += &render_table(&["Contract", "Commit", "Size", "Gas"], &commit_rows, "No commits in this block");
+    Ok(Page::found(format!("Block {}", block.index), content))
+}
+
+async fn epoch_page(ctx: &ExplorerContext, epoch: u64) -> anyhow::Result<Page> {
+    let mgr = ctx.datastore_manager.lock().await;
+    let current_epoch = canonical_tip(&mgr).await?.map(|b| b.epoch).unwrap_or(0);
+    if epoch > current_epoch {
+        return Ok(Page::NotFound(format!("Epoch {}", epoch)));
+    }
+    let blocks = MinerBlock::find_canonical_by_epoch_multi(&mgr, epoch, current_epoch).await?;
+    let mut content = render_fields(&[
+        ("Blocks", blocks.len().to_string()),
+        ("Previous", if epoch > 0 { epoch_link(epoch - 1) } else { "-".to_string() }),
+        ("Next", if epoch < current_epoch { epoch_link(epoch + 1) } else { "-".to_string() }),
+        ("Validators", render_link("/validators", "current set")),
+    ]);
+    content += &render_table(BLOCK_HEADERS, &block_rows(blocks.iter()), "No canonical blocks in this epoch");
+    Ok(Page::found(format!("Epoch {}", epoch), content))
+}
+
+async fn contracts_page(ctx: &ExplorerContext) -> anyhow::Result<Page> {
+    let mgr = ctx.datastore_manager.lock().await;
+    let mut contracts = Contract::find_all_multi(&mgr).await?;
+    contracts.sort_by_key(|c| std::cmp::Reverse(c.created_at));
+    let rows: Vec<Vec<String>> = contracts
+        .iter()
+        .take(EXPLORER_MAX_ROWS)
+        .map(|contract| vec![contract_link(&contract.contract_id), contract.created_at.to_string()])
+        .collect();
+    Ok(Page::found(
+        format!("Contracts ({})", contracts.len()),
+        render_table(&["Contract", "Created"], &rows, "No contracts yet"),
+    ))
+}
+
+async fn contract_page(ctx: &ExplorerContext, contract_id: &str) -> anyhow::Result<Page> {
+    let mgr = ctx.datastore_manager.lock().await;
+    let contract = Contract::find_by_id_multi(&mgr, contract_id).await?;
+    let mut commits = Commit::find_by_contract_multi(&mgr, contract_id).await?;
+    if contract.is_none() && commits.is_empty() {
+        return Ok(Page::NotFound(format!("Contract {}", contract_id)));
+    }
+    commits.sort_by_key(|c| std::cmp::Reverse(c.timestamp));
+
+    let mut content = render_fields(&[
+        ("Id", format!("<code>{}</code>", escape_html(contract_id))),
+        ("Created", contract.as_ref().map(|c| c.created_at.to_string()).unwrap_or_else(|| "-".to_string())),
+        ("Commits", commits.len().to_string()),
+    ]);
+    let rows: Vec<Vec<String>> = commits
+        .iter()
+        .take(EXPLORER_MAX_ROWS)
+        .map(|commit| {
+            vec![
+                format!(
+                    "<code>{}</code>",
+                    render_link(
+                        &format!("/contracts/{}/commits/{}", percent_encode(contract_id), percent_encode(&commit.commit_id)),
+                        &truncate_middle(&commit.commit_id, 8),
+                    )
+                ),
+                commit.timestamp.to_string(),
+                commit.in_batch.as_deref().map(|b| format!("<code>{}</code>", escape_html(&truncate_middle(b, 8)))).unwrap_or_else(|| "pending".to_string()),
+            ]
+        })
+        .collect();
+    content += &render_table(&["Commit", "Timestamp", "Batch"], &rows, "No commits yet");
+    if let Some(contract) = contract {
+        content += &render_preformatted("Genesis", &pretty_json(&contract.genesis));
+    }
+    Ok(Page::found(format!("Contract {}", truncate_middle(contract_id, 12)), content))
+}
+
+async fn commit_page(ctx: &ExplorerContext, contract_id: &str, commit_id: &str) -> anyhow::Result<Page> {
+    let mgr = ctx.datastore_manager.lock().await;
+    let keys = [
+        ("contract_id".to_string(), contract_id.to_string()),
+        ("commit_id".to_string(), commit_id.to_string()),
+    ]
+    .into_iter()
+    .collect();
+    let Some(commit) = Commit::find_one_multi(&mgr, keys).await? else {
+        return Ok(Page::NotFound(format!("Commit {}", commit_id)));
+    };
+    let mut content = render_fields(&[
+        ("Contract", contract_link(contract_id)),
+        ("Commit", format!("<code>{}</code>", escape_html(commit_id))),
+        ("Timestamp", commit.timestamp.to_string()),
+        ("Batch", commit.in_batch.as_deref().map(|b| format!("<code>{}</code>", escape_html(b))).unwrap_or_else(|| "pending".to_string())),
+    ]);
+    content += &render_preformatted("Data", &pretty_json(&commit.commit_data));
+    Ok(Page::found(format!("Commit {}", truncate_middle(commit_id, 8)), content))
+}
+
+async fn validators_page(ctx: &ExplorerContext) -> anyhow::Result<Page> {
+    let mgr = ctx.datastore_manager.lock().await;
+    let epoch = canonical_tip(&mgr).await?.map(|b| b.epoch).unwrap_or(0);
+    let set = match get_validator_set_for_epoch_multi(&mgr, epoch).await {
+        Ok(set) => set,
+        Err(e) => {
+            return Ok(Page::found(
+                "Validators",
+                format!(r#"<div class="card"><p class="empty">No validator set for epoch {}: {}</p></div>"#, epoch, escape_html(&e.to_string())),
+            ))
+        }
+    };
+    let groups = [
+        ("nominated", &set.nominated_validators),
+        ("staked", &set.staked_validators),
+        ("alternate", &set.alternate_validators),
+    ];
+    let rows: Vec<Vec<String>> = groups
+        .iter()
+        .flat_map(|(group, validators)| {
+            validators.iter().map(|peer_id| {
+                vec![
+                    peer_link(peer_id),
+                    group.to_string(),
+                    set.validator_stakes.get(peer_id).map(|s| s.to_string()).unwrap_or_else(|| "-".to_string()),
+                ]
+            })
+        })
+        .collect();
+    Ok(Page::found(
+        format!("Validators for epoch {}", epoch),
+        render_table(&["Peer", "Group", "Stake"], &rows, "No validators"),
+    ))
+}
+
+async fn validator_page(ctx: &ExplorerContext, peer_id: &str) -> anyhow::Result<Page> {
+    let mgr = ctx.datastore_manager.lock().await;
+    let epoch = canonical_tip(&mgr).await?.map(|b| b.epoch).unwrap_or(0);
+    let active = get_validator_set_for_epoch_multi(&mgr, epoch)
+        .await
+        .map(|set| set.get_active_validators().iter().any(|v| v == peer_id))
+        .unwrap_or(false);
+    let nominations = MinerBlock::find_canonical_by_peer_multi(&mgr, peer_id).await?;
+    if nominations.is_empty() && !active {
+        return Ok(Page::NotFound(format!("Peer {}", peer_id)));
+    }
+
+    let mut content = render_fields(&[
+        ("Peer", format!("<code>{}</code>", escape_html(peer_id))),
+        ("Active validator", if active { format!("yes (epoch {})", epoch) } else { "no".to_string() }),
+        ("Nominations", nominations.len().to_string()),
+    ]);
+    content += &render_table(
+        BLOCK_HEADERS,
+        &block_rows(nominations.iter().rev().take(EXPLORER_MAX_ROWS)),
+        "No blocks nominate this peer",
+    );
+    Ok(Page::found(format!("Peer {}", truncate_middle(peer_id, 10)), content))
+}
+
+fn pretty_json(text: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(text)
+        .and_then(|value| serde_json::to_string_pretty(&value))
+        .unwrap_or_else(|_| text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_target() {
+        assert_eq!(search_target(" 42 "), "/blocks/42");
+        assert_eq!(search_target(&"ab".repeat(32)), format!("/blocks/{}", "ab".repeat(32)));
+        assert_eq!(search_target("epoch:3"), "/epochs/3");
+        assert_eq!(search_target("12D3KooWTest"), "/validators/12D3KooWTest");
+        assert_eq!(search_target("my contract"), "/contracts/my%20contract");
+        assert_eq!(search_target(""), "/");
+    }
+
+    #[tokio::test]
+    async fn test_block_and_epoch_pages() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        for index in 0..3u64 {
+            let block = MinerBlock::new_canonical(
+                format!("{:064x}", index + 1),
+                index,
+                0,
+                1_700_000_000 + index as i64,
+                if index == 0 { "0".repeat(64) } else { format!("{:064x}", index) },
+                "data".to_string(),
+                1,
+                10,
+                "12D3KooWPeer".to_string(),
+                0,
+            );
+            block.save_to_active(&mgr).await.unwrap();
+        }
+        let routes = explorer_routes(ExplorerContext {
+            datastore_manager: Arc::new(Mutex::new(mgr)),
+            network_name: "devnet".to_string(),
+        });
+
+        let response = warp::test::request().path("/blocks/1").reply(&routes).await;
+        assert_eq!(response.status(), 200);
+        let body = String::from_utf8(response.body().to_vec()).unwrap();
+        assert!(body.contains("Block 1"));
+        assert!(body.contains(&format!("{:064x}", 2)));
+
+        let response = warp::test::request().path("/epochs/0").reply(&routes).await;
+        let body = String::from_utf8(response.body().to_vec()).unwrap();
+        assert!(body.contains("/blocks/2"));
+
+        let response = warp::test::request().path("/validators/12D3KooWPeer").reply(&routes).await;
+        let body = String::from_utf8(response.body().to_vec()).unwrap();
+        assert!(body.contains("<th>Nominations</th><td>3</td>"));
+
+        let response = warp::test::request().path("/blocks/9").reply(&routes).await;
+        assert_eq!(response.status(), 404);
+
+        let response = warp::test::request().path("/search?q=epoch:0").reply(&routes).await;
+        assert_eq!(response.headers()["location"], "/epochs/0");
+    }
+}
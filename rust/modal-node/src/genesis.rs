@@ -0,0 +1,113 @@
+//! Network genesis checks
+//!
+//! A network's config can pin its genesis (`genesis` in NetworkInfo): the
+//! genesis block hash and difficulty, and the epoch parameters. At startup a
+//! node fills unset epoch parameters from it, refuses conflicting ones, and
+//! checks its own genesis block against it. Nodes advertise their genesis
+//! block hash in their identify agent version (`genesis=<hash>`) and drop
+//! connections to peers advertising a different one.
+
+use anyhow::{anyhow, Result};
+use modal_datastore::models::MinerBlock;
+use modal_datastore::DatastoreManager;
+use modal_networks::GenesisSpec;
+
+/// The genesis section of a network config, if it has one
+pub fn genesis_spec(network_config: &serde_json::Value) -> Result<Option<GenesisSpec>> {
+    match network_config.get("genesis") {
+        Some(value) if !value.is_null() => Ok(Some(serde_json::from_value(value.clone())?)),
+        _ => Ok(None),
+    }
+}
+
+/// Copy the genesis epoch parameters into the network config where it
+/// doesn't set them, failing where it sets them differently
+pub fn apply_genesis_parameters(network_config: &mut serde_json::Value, spec: &GenesisSpec) -> Result<()> {
+    let parameters = [
+        ("blocks_per_epoch", spec.blocks_per_epoch),
+        ("target_block_time_secs", spec.target_block_time_secs),
+    ];
+    for (name, value) in parameters {
+        let Some(value) = value else { continue };
+        match network_config.get(name).and_then(|v| v.as_u64()) {
+            Some(configured) if configured != value => {
+                return Err(anyhow!("Network config sets {} to {}, but its genesis sets {}", name, configured, value));
+            }
+            Some(_) => {}
+            None => network_config[name] = serde_json::json!(value),
+        }
+    }
+    Ok(())
+}
+
+/// Check the local genesis block, if there is one, against the spec
+pub async fn validate_genesis(mgr: &DatastoreManager, spec: &GenesisSpec) -> Result<()> {
+    let Some(block) = MinerBlock::find_canonical_by_index_simple(mgr, 0).await? else {
+        return Ok(());
+    };
+    let difficulty: u128 = block
+        .target_difficulty
+        .parse()
+        .map_err(|e| anyhow!("Invalid genesis block difficulty: {}", e))?;
+    spec.check_block(&block.hash, difficulty)
+        .map_err(|e| anyhow!("Datastore doesn't match the network's genesis: {}", e))
+}
+
+/// The genesis block hash this node is on: the network's if it pins one,
+/// else the local chain's
+pub async fn genesis_hash(mgr: &DatastoreManager) -> Option<String> {
+    let network_config = mgr.get_network_config().await.ok().flatten();
+    let pinned = network_config
+        .and_then(|config| genesis_spec(&config).ok().flatten())
+        .and_then(|spec| spec.block_hash);
+    if pinned.is_some() {
+        return pinned;
+    }
+    MinerBlock::find_canonical_by_index_simple(mgr, 0).await.ok().flatten().map(|block| block.hash)
+}
+
+/// The genesis block hash a peer advertises in its agent version
+pub fn advertised_genesis(agent_version: &str) -> Option<&str> {
+    agent_version.split(';').find_map(|part| part.strip_prefix("genesis="))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_validate_genesis() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        let spec = GenesisSpec {
+            block_hash: Some("genesis_a".to_string()),
+            initial_difficulty: Some(1000),
+            ..Default::default()
+        };
+        // Nothing to check on an empty chain
+        validate_genesis(&mgr, &spec).await.unwrap();
+
+        let block = MinerBlock::new_canonical(
+            "genesis_b".to_string(), 0, 0, 1_700_000_000, String::new(), String::new(), 0, 1000, "peer".to_string(), 0,
+        );
+        block.save_to_active(&mgr).await.unwrap();
+        assert!(validate_genesis(&mgr, &spec).await.is_err());
+        assert_eq!(genesis_hash(&mgr).await.as_deref(), Some("genesis_b"));
+
+        mgr.load_network_config(&serde_json::json!({ "genesis": spec })).await.unwrap();
+        assert_eq!(genesis_hash(&mgr).await.as_deref(), Some("genesis_a"));
+    }
+
+    #[test]
+    fn test_genesis_parameters_and_advertisement() {
+        let spec = GenesisSpec { blocks_per_epoch: Some(40), ..Default::default() };
+        let mut config = serde_json::json!({});
+        apply_genesis_parameters(&mut config, &spec).unwrap();
+        assert_eq!(config["blocks_per_epoch"], 40);
+
+        let mut conflicting = serde_json::json!({ "blocks_per_epoch": 20 });
+        assert!(apply_genesis_parameters(&mut conflicting, &spec).is_err());
+
+        assert_eq!(advertised_genesis("modal-node/0.1.0;role=Miner;genesis=abc"), Some("abc"));
+        assert_eq!(advertised_genesis("modal-node/0.1.0;role=Miner"), None);
+    }
+}
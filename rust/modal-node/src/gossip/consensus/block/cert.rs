@@ -1,6 +1,5 @@
 use anyhow::Result;
 use anyhow::anyhow;
-use serde_json;
 use tokio::sync::mpsc;
 
 use modal_datastore::DatastoreManager;
@@ -8,16 +7,23 @@ use modal_datastore::Model;
 use modal_datastore::models::ValidatorBlock;
 use modal_validator_consensus::communication::Message as ConsensusMessage;
 
+use crate::constants::MAX_GOSSIP_BYTES;
+
 pub const TOPIC: &str = "/consensus/block/cert";
 
-pub async fn handler(data: String, _datastore_manager: &mut DatastoreManager, consensus_tx: mpsc::Sender<ConsensusMessage>) -> Result<()> {
-  let block_data = serde_json::from_str::<serde_json::Value>(&data).unwrap_or(serde_json::Value::Null);
-  let block = ValidatorBlock::from_json_string(&data.clone())?;
+pub async fn handler(data: &[u8], _datastore_manager: &mut DatastoreManager, consensus_tx: mpsc::Sender<ConsensusMessage>) -> Result<()> {
+  let block_data: serde_json::Value = modal_common::wire::from_slice("validator block", data, MAX_GOSSIP_BYTES)?;
+  let block = ValidatorBlock::from_json_object(block_data.clone())?;
   let from = block_data.get("peer_id")
     .ok_or_else(|| anyhow!("Missing peer_id field"))?
     .as_str()
     .ok_or_else(|| anyhow!("peer_id is not a string"))?;
 
+  // Forged blocks stop here, before they reach the consensus loop
+  if !modal_common::batch_verify::shared().verify_all(block.sig_checks()?).await {
+    return Err(anyhow!("Invalid signatures on certified block from {} round {}", block.peer_id, block.round_id));
+  }
+
   let msg = ConsensusMessage::CertifiedValidatorBlock {
     from: from.to_string(),
     to: String::new(),
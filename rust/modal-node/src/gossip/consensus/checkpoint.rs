@@ -0,0 +1,44 @@
+use anyhow::Result;
+
+use modal_datastore::DatastoreManager;
+use modal_datastore::models::miner::CheckpointCertificate;
+use modal_datastore::models::validator::get_validator_set_for_epoch_multi;
+
+use crate::constants::MAX_GOSSIP_BYTES;
+
+pub const TOPIC: &str = "/consensus/checkpoint";
+
+/// Handler for validator-signed checkpoints. Signatures are merged into the
+/// stored certificate and the checkpoint becomes final once 2f+1 validators
+/// of the set selected from the checkpointed epoch have signed it.
+pub async fn handler(data: &[u8], datastore_manager: &DatastoreManager) -> Result<()> {
+  let incoming: CheckpointCertificate = modal_common::wire::from_slice("checkpoint", data, MAX_GOSSIP_BYTES)?;
+  let epoch = incoming.checkpoint.epoch;
+
+  let validators = match get_validator_set_for_epoch_multi(datastore_manager, epoch).await {
+    Ok(set) => set.get_active_validators(),
+    Err(e) => {
+      log::debug!("Cannot verify checkpoint for epoch {} yet: {}", epoch, e);
+      return Ok(());
+    }
+  };
+
+  let (cert, certified) = CheckpointCertificate::merge_and_apply_multi(datastore_manager, &incoming, &validators).await?;
+  if certified {
+    log::info!(
+      "🔒 Checkpoint for epoch {} finalized at block {} ({}/{} validator signatures)",
+      epoch,
+      cert.checkpoint.last_block_index,
+      cert.signatures.len(),
+      validators.len()
+    );
+  } else {
+    log::debug!(
+      "Checkpoint for epoch {} has {}/{} validator signatures",
+      epoch,
+      cert.signatures.len(),
+      validators.len()
+    );
+  }
+  Ok(())
+}
@@ -0,0 +1,29 @@
+use anyhow::Result;
+
+use modal_common::slashing::EquivocationEvidence;
+use modal_datastore::DatastoreManager;
+use modal_datastore::slashing::verify_evidence;
+
+use crate::constants::MAX_GOSSIP_BYTES;
+
+pub const TOPIC: &str = "/consensus/evidence";
+
+/// Handler for equivocation evidence. Evidence with valid signatures waits
+/// in the pending pool until a mined block includes it; only evidence in
+/// canonical blocks counts against a validator.
+pub async fn handler(data: &[u8], datastore_manager: &DatastoreManager) -> Result<()> {
+  let evidence: EquivocationEvidence = modal_common::wire::from_slice("equivocation evidence", data, MAX_GOSSIP_BYTES)?;
+  verify_evidence(&evidence)?;
+
+  if datastore_manager.is_offense_committed(&evidence.peer_id, evidence.round).await? {
+    return Ok(());
+  }
+  if datastore_manager.add_pending_evidence(&evidence)? {
+    log::info!(
+      "⚔️  Received evidence that {} equivocated in round {}",
+      &evidence.peer_id[..16.min(evidence.peer_id.len())],
+      evidence.round
+    );
+  }
+  Ok(())
+}
@@ -0,0 +1,217 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use modal_datastore::DatastoreManager;
+use modal_datastore::models::Commit;
+
+use crate::contract_sync::{commit_id_of, order_by_parent};
+use crate::reqres;
+use crate::swarm_driver::SwarmHandle;
+
+/// The contract-commits topic
+pub const TOPIC: &str = "/contract/commits";
+
+/// A commit a node has just accepted. Only ids are gossiped; nodes that want
+/// the commit fetch it with `/contract/pull`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitAnnouncement {
+    pub contract_id: String,
+    pub commit_id: String,
+    /// Position in the contract's history, counting from 1
+    pub height: u64,
+}
+
+/// Announcements for commits just stored for `contract_id`
+pub async fn announcements(
+    datastore_manager: &DatastoreManager,
+    contract_id: &str,
+    commit_ids: &[String],
+) -> Result<Vec<CommitAnnouncement>> {
+    let history = order_by_parent(Commit::find_by_contract_multi(datastore_manager, contract_id).await?);
+    Ok(history
+        .iter()
+        .enumerate()
+        .filter(|(_, commit)| commit_ids.contains(&commit.commit_id))
+        .map(|(i, commit)| CommitAnnouncement {
+            contract_id: contract_id.to_string(),
+            commit_id: commit.commit_id.clone(),
+            height: i as u64 + 1,
+        })
+        .collect())
+}
+
+pub async fn publish(swarm: &SwarmHandle, announcements: &[CommitAnnouncement]) {
+    for announcement in announcements {
+        match crate::gossip::wire::publish(swarm, TOPIC, announcement).await {
+            Ok(_) => log::info!(
+                "Announced commit {} (height {}) of contract {}",
+                announcement.commit_id,
+                announcement.height,
+                announcement.contract_id
+            ),
+            // No subscribers yet is normal for a new network
+            Err(e) => log::debug!("Could not gossip commit {}: {}", announcement.commit_id, e),
+        }
+    }
+}
+
+/// The contract and commit ids a reqres request stored, judging by its
+/// response
+pub fn accepted_by(path: &str, data: Option<&serde_json::Value>, response: &reqres::Response) -> Option<(String, Vec<String>)> {
+    if !response.ok {
+        return None;
+    }
+    let contract_id = data?.get("contract_id")?.as_str()?.to_string();
+    let response_data = response.data.as_ref()?;
+    let commit_ids = match path {
+        "/contract/push" => response_data
+            .get("commit_ids")?
+            .as_array()?
+            .iter()
+            .filter_map(|id| id.as_str().map(|s| s.to_string()))
+            .collect(),
+        "/contract/submit" => vec![response_data.get("commit_id")?.as_str()?.to_string()],
+        _ => return None,
+    };
+    Some((contract_id, commit_ids))
+}
+
+/// Handler for commit announcements. Returns the announcement if it's for a
+/// commit we don't have yet, so the caller can fetch it.
+pub async fn handler(data: &[u8], datastore_manager: &DatastoreManager) -> Result<Option<CommitAnnouncement>> {
+    let announcement: CommitAnnouncement = modal_common::wire::from_slice("commit announcement", data, MAX_GOSSIP_BYTES)?;
+    let keys = [
+        ("contract_id".to_string(), announcement.contract_id.clone()),
+        ("commit_id".to_string(), announcement.commit_id.clone()),
+    ].into_iter().collect();
+    if Commit::find_one_multi(datastore_manager, keys).await?.is_some() {
+        return Ok(None);
+    }
+    log::info!(
+        "Commit {} (height {}) of contract {} announced; fetching it",
+        announcement.commit_id,
+        announcement.height,
+        announcement.contract_id
+    );
+    Ok(Some(announcement))
+}
+
+/// The `/contract/pull` request for an announced commit's contract
+pub fn pull_request(announcement: &CommitAnnouncement) -> reqres::Request {
+    reqres::Request {
+        path: "/contract/pull".to_string(),
+        data: Some(serde_json::json!({
+            "contract_id": announcement.contract_id,
+            "since_commit_id": null,
+        })),
+        accept_encoding: Some(reqres::encoding::AcceptEncoding::zstd()),
+    }
+}
+
+/// Store the commits from a `/contract/pull` response that we don't have.
+/// Commits whose contents don't hash to their id are skipped.
+pub async fn save_pulled(
+    datastore_manager: &DatastoreManager,
+    contract_id: &str,
+    response: &reqres::Response,
+) -> Result<Vec<String>> {
+    if !response.ok {
+        anyhow::bail!("Pull failed: {:?}", response.errors);
+    }
+    let pulled: reqres::contract::pull::PullResponse = serde_json::from_value(
+        response.data.clone().ok_or_else(|| anyhow::anyhow!("No data in response"))?,
+    )?;
+
+    let mut saved = Vec::new();
+    for info in pulled.commits {
+        if commit_id_of(info.commit_data.as_bytes()) != info.commit_id {
+            log::warn!("Skipping commit {}: contents don't match its id", info.commit_id);
+            continue;
+        }
+        let keys = [
+            ("contract_id".to_string(), contract_id.to_string()),
+            ("commit_id".to_string(), info.commit_id.clone()),
+        ].into_iter().collect();
+        if Commit::find_one_multi(datastore_manager, keys).await?.is_some() {
+            continue;
+        }
+        let commit = Commit {
+            contract_id: contract_id.to_string(),
+            commit_id: info.commit_id.clone(),
+            commit_data: info.commit_data,
+            timestamp: info.timestamp,
+            in_batch: None,
+        };
+        commit.save_to_final(datastore_manager).await?;
+        saved.push(info.commit_id);
+    }
+    Ok(saved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reqres::contract::pull::{CommitInfo, PullResponse};
+
+    fn commit(contract_id: &str, value: &str, parent: Option<&str>) -> Commit {
+        let commit_data = serde_json::to_string(&serde_json::json!({
+            "body": [{ "method": "post", "path": "/a.text", "value": value }],
+            "head": { "parent": parent },
+        }))
+        .unwrap();
+        Commit {
+            contract_id: contract_id.to_string(),
+            commit_id: commit_id_of(commit_data.as_bytes()),
+            commit_data,
+            timestamp: 0,
+            in_batch: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_announcement_heights() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        let first = commit("announce", "1", None);
+        let second = commit("announce", "2", Some(&first.commit_id));
+        first.save_to_final(&mgr).await.unwrap();
+        second.save_to_final(&mgr).await.unwrap();
+
+        let announced = announcements(&mgr, "announce", std::slice::from_ref(&second.commit_id)).await.unwrap();
+        assert_eq!(announced, vec![CommitAnnouncement {
+            contract_id: "announce".into(),
+            commit_id: second.commit_id.clone(),
+            height: 2,
+        }]);
+
+        // Known commits aren't fetched again
+        let data = serde_json::to_vec(&announced[0]).unwrap();
+        assert!(handler(&data, &mgr).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_save_pulled_checks_ids() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        let good = commit("fetch", "1", None);
+        let info = |commit: &Commit, commit_id: &str| CommitInfo {
+            commit_id: commit_id.to_string(),
+            body: serde_json::Value::Null,
+            head: serde_json::Value::Null,
+            timestamp: 0,
+            commit_data: commit.commit_data.clone(),
+        };
+        let response = reqres::Response {
+            ok: true,
+            data: Some(serde_json::to_value(PullResponse {
+                contract_id: "fetch".into(),
+                commits: vec![info(&good, &good.commit_id), info(&good, "forged")],
+            }).unwrap()),
+            errors: None,
+            encoding: None,
+        };
+
+        let saved = save_pulled(&mgr, "fetch", &response).await.unwrap();
+        assert_eq!(saved, vec![good.commit_id.clone()]);
+        // Already stored the second time
+        assert!(save_pulled(&mgr, "fetch", &response).await.unwrap().is_empty());
+    }
+}
@@ -1,7 +1,12 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use modal_datastore::DatastoreManager;
 use modal_datastore::models::MinerBlock;
+use modal_datastore::models::miner::miner_block::MAX_BLOCK_JSON_BYTES;
 use modal_datastore::models::miner::checkpoint::validate_block_against_checkpoints;
+use modal_common::block_commits::CommitDigest;
+use modal_common::slashing::EquivocationEvidence;
+use modal_common::uncles::UncleRef;
+use modal_observer::{BlockRef, ReorgEvent};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -19,6 +24,14 @@ pub struct MinerBlockGossip {
     pub nonce: String,
     pub timestamp: String,
     pub miner_number: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub uncles: Vec<UncleRef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commits: Vec<CommitDigest>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub commits_root: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub evidence: Vec<EquivocationEvidence>,
 }
 
 impl MinerBlockGossip {
@@ -33,9 +46,34 @@ impl MinerBlockGossip {
             nonce: block.nonce.clone(),
             timestamp: block.timestamp.to_string(),
             miner_number: block.miner_number,
+            uncles: block.uncles.clone(),
+            commits: block.commits.clone(),
+            commits_root: block.commits_root.clone(),
+            evidence: block.evidence.clone(),
         }
     }
 
+    /// Parse a gossiped block and check its fields, rejecting the message
+    /// rather than defaulting fields that don't parse
+    pub fn from_json_slice(data: &[u8]) -> Result<Self> {
+        let msg: Self = modal_common::wire::from_json_slice("miner block gossip", data, MAX_BLOCK_JSON_BYTES)?;
+        msg.validate()
+    }
+
+    /// Like `from_json_slice`, for a block in either wire format
+    pub fn from_slice(data: &[u8]) -> Result<Self> {
+        let msg: Self = modal_common::wire::from_slice("miner block gossip", data, MAX_BLOCK_JSON_BYTES)?;
+        msg.validate()
+    }
+
+    fn validate(self) -> Result<Self> {
+        self.timestamp.parse::<i64>().context("Gossiped block timestamp is not a number")?;
+        self.nonce.parse::<u128>().context("Gossiped block nonce is not a number")?;
+        self.difficulty.parse::<u128>().context("Gossiped block difficulty is not a number")?;
+        self.to_miner_block().validate_fields()?;
+        Ok(self)
+    }
+
     pub fn to_miner_block(&self) -> MinerBlock {
         use std::time::{SystemTime, UNIX_EPOCH};
         
@@ -49,7 +87,7 @@ impl MinerBlockGossip {
         let nonce = self.nonce.parse::<u128>().unwrap_or(0);
         let difficulty = self.difficulty.parse::<u128>().unwrap_or(1000);
         
-        MinerBlock::new_canonical(
+        let mut block = MinerBlock::new_canonical(
             self.hash.clone(),
             self.index,
             self.epoch,
@@ -61,33 +99,45 @@ impl MinerBlockGossip {
             self.nominated_peer_id.clone(),
             self.miner_number,
         )
+        .with_uncles(self.uncles.clone())
+        .with_evidence(self.evidence.clone());
+        // Keep the root as gossiped so the observer can check it against the commits
+        block.commits = self.commits.clone();
+        block.commits_root = self.commits_root.clone();
+        block
     }
 }
 
+/// First 16 characters of a hash for logs; a peer may send a shorter one
+fn short(hash: &str) -> &str {
+    hash.get(..16).unwrap_or(hash)
+}
+
 /// Handler for incoming miner block gossip messages  
 pub async fn handler(
-    data: String,
+    data: &[u8],
     source_peer: Option<libp2p::PeerId>,
     datastore_manager: Arc<Mutex<DatastoreManager>>,
     sync_request_tx: Option<tokio::sync::mpsc::UnboundedSender<(libp2p::PeerId, String)>>,
     mining_update_tx: Option<tokio::sync::mpsc::UnboundedSender<u64>>,
     bootstrappers: Vec<libp2p::Multiaddr>,
     minimum_block_timestamp: Option<i64>,
+    reorg_tx: tokio::sync::broadcast::Sender<ReorgEvent>,
 ) -> Result<()> {
     log::debug!("Received miner block gossip");
     
     // Parse the gossip message
-    let gossip_msg: MinerBlockGossip = serde_json::from_str(&data)?;
+    let gossip_msg = MinerBlockGossip::from_slice(data)?;
     let miner_block = gossip_msg.to_miner_block();
     
-    log::debug!("Gossip block: index={}, hash={}", miner_block.index, &miner_block.hash[..16]);
+    log::debug!("Gossip block: index={}, hash={}", miner_block.index, short(&miner_block.hash));
     
     // Check if block timestamp is before the minimum allowed timestamp
     if let Some(min_timestamp) = minimum_block_timestamp {
         if miner_block.timestamp < min_timestamp {
             log::warn!(
                 "Block {} at height {} rejected: timestamp {} is before minimum allowed timestamp {}",
-                &miner_block.hash[..16], miner_block.index, miner_block.timestamp, min_timestamp
+                short(&miner_block.hash), miner_block.index, miner_block.timestamp, min_timestamp
             );
             return Ok(());
         }
@@ -97,7 +147,7 @@ pub async fn handler(
     {
         let mgr = datastore_manager.lock().await;
         if let Ok(Some(_)) = MinerBlock::find_by_hash_multi(&mgr, &miner_block.hash).await {
-            log::debug!("Block with hash {} already exists, skipping", &miner_block.hash[..16]);
+            log::debug!("Block with hash {} already exists, skipping", short(&miner_block.hash));
             return Ok(());
         }
     }
@@ -112,6 +162,30 @@ pub async fn handler(
         let mgr = datastore_manager.lock().await;
         if let Some(existing) = MinerBlock::find_canonical_by_index_simple(&mgr, miner_block.index).await? {
             // We have a different block at the same index - this is a fork!
+            // A competitor only replaces our block if it shares our parent;
+            // one from a different branch is settled by comparing chains
+            if miner_block.index > 0 {
+                let shares_parent = MinerBlock::find_canonical_by_index_simple(&mgr, miner_block.index - 1).await?
+                    .is_some_and(|parent| parent.hash == miner_block.previous_hash);
+                if !shares_parent {
+                    log::warn!(
+                        "Block {} at index {} competes with ours but builds on a different parent {}",
+                        short(&miner_block.hash),
+                        miner_block.index,
+                        short(&miner_block.previous_hash)
+                    );
+                    drop(mgr);
+                    request_chain_sync(&sync_request_tx, source_peer, &bootstrappers).await;
+                    return Ok(());
+                }
+            }
+            drop(mgr);
+            if let Err(e) = crate::sync::validation::check_new_block(&datastore_manager, &miner_block).await {
+                log::warn!("Competing block {} at index {} rejected: {}", short(&miner_block.hash), miner_block.index, e);
+                return Ok(());
+            }
+            let mgr = datastore_manager.lock().await;
+
             // Apply fork choice rules in priority order:
             // 1. Actualized difficulty (highest wins - based on actual hash value)
             // 2. First-seen (earliest seen_at wins)
@@ -160,7 +234,7 @@ pub async fn handler(
             
             if should_replace {
                 log::info!("Fork choice: Replacing existing block {} (difficulty: {}, hash: {}) with gossiped block (difficulty: {}, hash: {})",
-                    miner_block.index, existing_difficulty, &existing.hash[..16], new_difficulty, &miner_block.hash[..16]);
+                    miner_block.index, existing_difficulty, short(&existing.hash), new_difficulty, short(&miner_block.hash));
                 
                 let replaced_block_hash = existing.hash.clone();
                 let replaced_block_index = existing.index;
@@ -168,7 +242,7 @@ pub async fn handler(
                 // Mark old block as orphaned
                 let mut orphaned = existing.clone();
                 orphaned.mark_as_orphaned(
-                    format!("Replaced by gossiped block (difficulty: {}, hash: {})", new_difficulty, &miner_block.hash[..16]),
+                    format!("Replaced by gossiped block (difficulty: {}, hash: {})", new_difficulty, short(&miner_block.hash)),
                     Some(miner_block.hash.clone())
                 );
                 orphaned.save_to_active(&mgr).await?;
@@ -185,21 +259,23 @@ pub async fn handler(
                 
                 let mut orphaned_hashes = std::collections::HashSet::new();
                 orphaned_hashes.insert(replaced_block_hash.clone());
+                let mut orphaned_refs = vec![BlockRef::from(&existing)];
                 
                 for block in blocks_to_check {
                     if orphaned_hashes.contains(&block.previous_hash) {
                         log::info!("   Cascade orphaning block {} at index {} (built on orphaned chain)", 
-                            &block.hash[..16], block.index);
+                            short(&block.hash), block.index);
                         
                         let mut cascade_orphaned = block.clone();
                         cascade_orphaned.mark_as_orphaned(
                             format!("Built on orphaned block {} at index {} (cascade from fork choice)", 
-                                &replaced_block_hash[..16], replaced_block_index),
+                                short(&replaced_block_hash), replaced_block_index),
                             None
                         );
                         cascade_orphaned.save_to_active(&mgr).await?;
                         
                         orphaned_hashes.insert(block.hash.clone());
+                        orphaned_refs.push(BlockRef::from(block));
                         cascade_orphaned_count += 1;
                     }
                 }
@@ -211,7 +287,19 @@ pub async fn handler(
                 
                 // Save new block as canonical
                 miner_block.save_to_active(&mgr).await?;
-                log::info!("Accepted gossiped block {} at index {}", &miner_block.hash[..16], miner_block.index);
+                log::info!("Accepted gossiped block {} at index {}", short(&miner_block.hash), miner_block.index);
+                
+                // Let indexers know which blocks were rolled back
+                let common_ancestor = MinerBlock::find_by_hash_multi(&mgr, &miner_block.previous_hash).await?
+                    .map(|b| BlockRef::from(&b));
+                let old_tip = orphaned_refs.last().cloned().unwrap_or_else(|| BlockRef::from(&existing));
+                let _ = reorg_tx.send(ReorgEvent::new(
+                    old_tip,
+                    BlockRef::from(&miner_block),
+                    common_ancestor,
+                    orphaned_refs,
+                    format!("Replaced by gossiped block with higher actualized difficulty ({} vs {})", new_difficulty, existing_difficulty),
+                ));
                 
                 // Check if this updates the chain tip
                 let current_tip = MinerBlock::find_all_canonical_multi(&mgr).await?
@@ -225,7 +313,7 @@ pub async fn handler(
                 }
             } else {
                 log::debug!("Existing block {} wins fork choice (existing difficulty: {}, hash: {} vs new difficulty: {}, hash: {})", 
-                    miner_block.index, existing_difficulty, &existing.hash[..16], new_difficulty, &miner_block.hash[..16]);
+                    miner_block.index, existing_difficulty, short(&existing.hash), new_difficulty, short(&miner_block.hash));
             }
             
             // Fork handled - notify if needed and return
@@ -252,7 +340,7 @@ pub async fn handler(
                 log::warn!(
                     "Received block {} but missing parent block (prev_hash: {}). Orphan block detected!",
                     miner_block.index, 
-                    &miner_block.previous_hash[..16]
+                    short(&miner_block.previous_hash)
                 );
                 
                 // Check if this is a completely different chain by comparing genesis
@@ -260,7 +348,7 @@ pub async fn handler(
                 if let Some(genesis) = our_genesis {
                     log::warn!(
                         "⚠️  We have genesis block {} but received orphan from different chain.",
-                        &genesis.hash[..16]
+                        short(&genesis.hash)
                     );
                 } else {
                     log::info!("No local genesis - will need to sync from peers");
@@ -268,31 +356,7 @@ pub async fn handler(
                 
                 drop(mgr);
                 
-                // Send sync request via channel if available
-                if let Some(ref tx) = sync_request_tx {
-                    if let Some(peer_id) = source_peer {
-                        let peer_addr = bootstrappers.iter()
-                            .find(|addr| {
-                                addr.iter().any(|proto| matches!(proto, libp2p::multiaddr::Protocol::P2p(id) if id == peer_id))
-                            })
-                            .map(|addr| addr.to_string());
-                        
-                        if let Some(addr) = peer_addr {
-                            let delay_ms = 100 + (rand::random::<u64>() % 400);
-                            tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
-                            
-                            log::info!("📡 Orphan detected from peer {} - requesting chain sync (after {}ms delay)", peer_id, delay_ms);
-                            if let Err(e) = tx.send((peer_id, addr)) {
-                                log::warn!("Failed to send sync request: {}", e);
-                            }
-                        } else {
-                            log::warn!("Could not find address for peer {} in bootstrappers", peer_id);
-                        }
-                    }
-                } else {
-                    log::debug!("Sync request channel not initialized yet");
-                }
-                
+                request_chain_sync(&sync_request_tx, source_peer, &bootstrappers).await;
                 return Ok(());
             }
             Some(parent) => {
@@ -300,6 +364,9 @@ pub async fn handler(
                 if !parent.is_canonical {
                     log::warn!("Parent block {} is not canonical, rejecting gossiped block {}", 
                         parent.index, miner_block.index);
+                    // The peer is on a competing branch that may now be heavier
+                    drop(mgr);
+                    request_chain_sync(&sync_request_tx, source_peer, &bootstrappers).await;
                     return Ok(());
                 }
                 
@@ -321,9 +388,11 @@ pub async fn handler(
                             "⚠️  Block {} builds on orphaned parent. Canonical block at index {} has hash {}, but this block expects {}. Rejecting.",
                             miner_block.index,
                             miner_block.index - 1,
-                            &canonical_at_parent_index.hash[..16],
-                            &miner_block.previous_hash[..16]
+                            short(&canonical_at_parent_index.hash),
+                            short(&miner_block.previous_hash)
                         );
+                        drop(mgr);
+                        request_chain_sync(&sync_request_tx, source_peer, &bootstrappers).await;
                         return Ok(());
                     }
                 }
@@ -344,7 +413,7 @@ pub async fn handler(
             Ok(false) => {
                 log::warn!(
                     "⚠️  Block {} at index {} rejected: does not branch from required checkpoint",
-                    &miner_block.hash[..16],
+                    short(&miner_block.hash),
                     miner_block.index
                 );
                 return Ok(());
@@ -361,8 +430,15 @@ pub async fn handler(
         drop(mgr);
     }
     
+    // **FOURTH**: Check its proof of work and that its target difficulty is
+    // the one the network's difficulty algorithm expects at its height
+    if let Err(e) = crate::sync::validation::check_new_block(&datastore_manager, &miner_block).await {
+        log::warn!("Block {} at index {} rejected: {}", short(&miner_block.hash), miner_block.index, e);
+        return Ok(());
+    }
+    
     // Save block and notify the mining loop
-    log::info!("Accepting new gossiped block {} at index {}", &miner_block.hash[..16], miner_block.index);
+    log::info!("Accepting new gossiped block {} at index {}", short(&miner_block.hash), miner_block.index);
     
     let current_tip = {
         let mgr = datastore_manager.lock().await;
@@ -384,9 +460,43 @@ pub async fn handler(
     Ok(())
 }
 
+/// Ask the sync request handler to compare chains with the peer a block came
+/// from, when that block doesn't extend our canonical chain
+async fn request_chain_sync(
+    sync_request_tx: &Option<tokio::sync::mpsc::UnboundedSender<(libp2p::PeerId, String)>>,
+    source_peer: Option<libp2p::PeerId>,
+    bootstrappers: &[libp2p::Multiaddr],
+) {
+    let Some(tx) = sync_request_tx else {
+        log::debug!("Sync request channel not initialized yet");
+        return;
+    };
+    let Some(peer_id) = source_peer else {
+        return;
+    };
+    let peer_addr = bootstrappers.iter()
+        .find(|addr| {
+            addr.iter().any(|proto| matches!(proto, libp2p::multiaddr::Protocol::P2p(id) if id == peer_id))
+        })
+        .map(|addr| addr.to_string());
+    
+    if let Some(addr) = peer_addr {
+        let delay_ms = 100 + (rand::random::<u64>() % 400);
+        tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
+        
+        log::info!("📡 Block from peer {} doesn't extend our chain - requesting chain sync (after {}ms delay)", peer_id, delay_ms);
+        if let Err(e) = tx.send((peer_id, addr)) {
+            log::warn!("Failed to send sync request: {}", e);
+        }
+    } else {
+        log::warn!("Could not find address for peer {} in bootstrappers", peer_id);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use modal_common::wire::WireFormat;
 
     #[test]
     fn test_miner_block_gossip_serialization() {
@@ -400,6 +510,10 @@ mod tests {
             nonce: "12345".to_string(),
             timestamp: "2024-01-01T00:00:00Z".to_string(),
             miner_number: 42,
+            uncles: Vec::new(),
+            commits: Vec::new(),
+            commits_root: String::new(),
+            evidence: Vec::new(),
         };
 
         let json = serde_json::to_string(&gossip).unwrap();
@@ -421,6 +535,10 @@ mod tests {
             nonce: "12345".to_string(),
             timestamp: "2024-01-01T00:00:00Z".to_string(),
             miner_number: 42,
+            uncles: Vec::new(),
+            commits: Vec::new(),
+            commits_root: String::new(),
+            evidence: Vec::new(),
         };
 
         let miner_block = gossip.to_miner_block();
@@ -432,4 +550,94 @@ mod tests {
         assert_eq!(gossip2.hash, gossip.hash);
         assert_eq!(gossip2.index, gossip.index);
     }
+
+    #[test]
+    fn test_from_json_slice_rejects_malformed_gossip() {
+        let mut block = MinerBlock::new_canonical(
+            "ab".repeat(32),
+            1,
+            0,
+            1_700_000_000,
+            "cd".repeat(32),
+            String::new(),
+            12345,
+            1000,
+            "peer1".to_string(),
+            42,
+        );
+        let json = serde_json::to_vec(&MinerBlockGossip::from_miner_block(&block)).unwrap();
+        assert_eq!(MinerBlockGossip::from_json_slice(&json).unwrap().hash, block.hash);
+
+        // A parent hash too short to abbreviate in logs
+        block.previous_hash = "0".to_string();
+        let json = serde_json::to_vec(&MinerBlockGossip::from_miner_block(&block)).unwrap();
+        assert_eq!(short(&MinerBlockGossip::from_json_slice(&json).unwrap().previous_hash), "0");
+
+        let mut gossip = MinerBlockGossip::from_miner_block(&block);
+        gossip.timestamp = "2024-01-01T00:00:00Z".to_string();
+        assert!(MinerBlockGossip::from_json_slice(&serde_json::to_vec(&gossip).unwrap()).is_err());
+        let mut gossip = MinerBlockGossip::from_miner_block(&block);
+        gossip.hash = "é".to_string();
+        assert!(MinerBlockGossip::from_json_slice(&serde_json::to_vec(&gossip).unwrap()).is_err());
+        assert!(MinerBlockGossip::from_json_slice(b"{\"hash\":").is_err());
+    }
+
+    fn block(hash: &str, index: u64, previous_hash: &str) -> MinerBlock {
+        MinerBlock::new_canonical(
+            hash.to_string(),
+            index,
+            0,
+            1_700_000_000 + index as i64,
+            previous_hash.to_string(),
+            String::new(),
+            index as u128,
+            1000,
+            "peer1".to_string(),
+            index,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_competing_block_on_another_branch_does_not_replace() {
+        let ds = DatastoreManager::create_in_memory().unwrap();
+        let genesis = "10".repeat(32);
+        let ours = "20".repeat(32);
+        let theirs = "30".repeat(32);
+        block(&genesis, 0, "").save_to_active(&ds).await.unwrap();
+        block(&ours, 1, &genesis).save_to_active(&ds).await.unwrap();
+        let mut lost = block(&theirs, 1, &genesis);
+        lost.mark_as_orphaned("Lost fork choice".to_string(), Some(ours.clone()));
+        lost.save_to_active(&ds).await.unwrap();
+        let tip = "f0".repeat(32);
+        block(&tip, 2, &ours).save_to_active(&ds).await.unwrap();
+
+        // Far more work than our tip, but built on the block we orphaned
+        let competitor = block(&format!("{}01", "00".repeat(31)), 2, &theirs);
+        let data = modal_common::wire::to_vec(&MinerBlockGossip::from_miner_block(&competitor), WireFormat::Cbor).unwrap();
+        let datastore = Arc::new(Mutex::new(ds));
+        let (reorg_tx, _) = tokio::sync::broadcast::channel(1);
+        handler(&data, None, datastore.clone(), None, None, Vec::new(), None, reorg_tx).await.unwrap();
+
+        let mgr = datastore.lock().await;
+        let canonical = MinerBlock::find_canonical_by_index_simple(&mgr, 2).await.unwrap().unwrap();
+        assert_eq!(canonical.hash, tip);
+        assert!(MinerBlock::find_by_hash_multi(&mgr, &competitor.hash).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_block_without_proof_of_work_is_rejected() {
+        let ds = DatastoreManager::create_in_memory().unwrap();
+        let genesis = "10".repeat(32);
+        block(&genesis, 0, "").save_to_active(&ds).await.unwrap();
+
+        // Extends our tip, but its hash was never mined
+        let unmined = block(&"20".repeat(32), 1, &genesis);
+        let data = modal_common::wire::to_vec(&MinerBlockGossip::from_miner_block(&unmined), WireFormat::Cbor).unwrap();
+        let datastore = Arc::new(Mutex::new(ds));
+        let (reorg_tx, _) = tokio::sync::broadcast::channel(1);
+        handler(&data, None, datastore.clone(), None, None, Vec::new(), None, reorg_tx).await.unwrap();
+
+        let mgr = datastore.lock().await;
+        assert!(MinerBlock::find_by_hash_multi(&mgr, &unmined.hash).await.unwrap().is_none());
+    }
 }
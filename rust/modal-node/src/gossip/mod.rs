@@ -1,5 +1,5 @@
 use anyhow::Result;
-use libp2p::gossipsub::{self, Message};
+use libp2p::gossipsub::Message;
 use tokio::sync::mpsc;
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -10,35 +10,38 @@ use modal_validator_consensus::communication::Message as ConsensusMessage;
 use crate::node::Node;
 
 pub mod consensus;
+pub mod contract;
 pub mod miner;
+pub mod topics;
+pub mod wire;
 
 pub async fn add_validator_event_listeners(node: &mut Node) -> Result<()> {
-  {
-    let mut swarm = node.swarm.lock().await;
+  node.swarm.subscribe(consensus::block::draft::TOPIC).await?;
+  node.swarm.subscribe(consensus::block::cert::TOPIC).await?;
+  node.swarm.subscribe(consensus::evidence::TOPIC).await?;
 
-    let topic = gossipsub::IdentTopic::new(consensus::block::draft::TOPIC);
-    swarm.behaviour_mut().gossipsub.subscribe(&topic)?;
+  add_contract_event_listeners(node).await
+}
 
-    let topic = gossipsub::IdentTopic::new(consensus::block::cert::TOPIC);
-    swarm.behaviour_mut().gossipsub.subscribe(&topic)?;
-  }
+pub async fn add_contract_event_listeners(node: &mut Node) -> Result<()> {
+  node.swarm.subscribe(contract::commits::TOPIC).await?;
+  log::info!("Subscribed to contract commits gossip topic: {}", contract::commits::TOPIC);
 
   Ok(())
 }
 
 pub async fn add_miner_event_listeners(node: &mut Node) -> Result<()> {
-  {
-    let mut swarm = node.swarm.lock().await;
-
-    let topic = gossipsub::IdentTopic::new(miner::block::TOPIC);
-    swarm.behaviour_mut().gossipsub.subscribe(&topic)?;
-    log::info!("Subscribed to miner block gossip topic: {}", miner::block::TOPIC);
-  }
+  node.swarm.subscribe(miner::block::TOPIC).await?;
+  log::info!("Subscribed to miner block gossip topic: {}", miner::block::TOPIC);
+  node.swarm.subscribe(consensus::checkpoint::TOPIC).await?;
+  node.swarm.subscribe(consensus::evidence::TOPIC).await?;
 
   Ok(())
 }
 
+/// Handle a gossip message received on `topic`, as `TopicRouter::topic_of` names it
 pub async fn handle_event(
+    topic: &str,
     message: Message, 
     datastore_manager: Arc<Mutex<DatastoreManager>>,
     consensus_tx: mpsc::Sender<ConsensusMessage>,
@@ -46,10 +49,10 @@ pub async fn handle_event(
     mining_update_tx: Option<mpsc::UnboundedSender<u64>>,
     bootstrappers: Vec<libp2p::Multiaddr>,
     minimum_block_timestamp: Option<i64>,
+    reorg_tx: tokio::sync::broadcast::Sender<modal_observer::ReorgEvent>,
 ) -> Result<()> {
   log::info!("handling gossip: {:?}", message);
-  let data = String::from_utf8_lossy(&message.data).to_string();
-  let topic = message.topic.to_string();
+  let data = message.data.as_slice();
   let source_peer = message.source;
   
   if topic == consensus::block::draft::TOPIC {
@@ -58,8 +61,14 @@ pub async fn handle_event(
   } else if topic == consensus::block::cert::TOPIC {
     let mut mgr = datastore_manager.lock().await;
     consensus::block::cert::handler(data, &mut mgr, consensus_tx).await?;
+  } else if topic == consensus::checkpoint::TOPIC {
+    let mgr = datastore_manager.lock().await;
+    consensus::checkpoint::handler(data, &mgr).await?;
+  } else if topic == consensus::evidence::TOPIC {
+    let mgr = datastore_manager.lock().await;
+    consensus::evidence::handler(data, &mgr).await?;
   } else if topic == miner::block::TOPIC {
-    miner::block::handler(data, source_peer, datastore_manager, sync_request_tx, mining_update_tx, bootstrappers, minimum_block_timestamp).await?;
+    miner::block::handler(data, source_peer, datastore_manager, sync_request_tx, mining_update_tx, bootstrappers, minimum_block_timestamp, reorg_tx).await?;
   } else {
     log::warn!("Unknown gossip topic: {}", topic);
   }
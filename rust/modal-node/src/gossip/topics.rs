@@ -0,0 +1,155 @@
+//! Gossip topic routing
+//!
+//! Handlers name their topics without a network (`/miner/block`). A network
+//! with a `gossip` section in its config namespaces them, publishing
+//! `/miner/block` as `/testnet/miner/block`, so networks sharing peers or a
+//! process don't cross-pollinate. It can also split high-volume topics into
+//! shards: a message goes to the shard of its key modulo the shard count
+//! (miner blocks are keyed by epoch), as `/testnet/miner/block/shard/2`.
+//! Nodes subscribe to every shard unless `gossip_shards` picks some; blocks
+//! on the others still reach them through sync. Networks without a `gossip`
+//! section keep the shared unsharded topics.
+
+use anyhow::Result;
+use libp2p::gossipsub::TopicHash;
+use modal_networks::GossipSpec;
+use std::collections::BTreeMap;
+
+/// Maps a network's topics to the ones on the wire and back
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TopicRouter {
+    namespace: Option<String>,
+    shards: BTreeMap<String, u32>,
+    /// Shards to subscribe to; all if unset
+    followed: Option<Vec<u32>>,
+}
+
+impl TopicRouter {
+    /// Route the topics of the network `network_name` by `spec`
+    pub fn new(network_name: &str, spec: Option<&GossipSpec>, followed: Option<Vec<u32>>) -> Self {
+        let Some(spec) = spec else {
+            return Self::default();
+        };
+        Self {
+            namespace: Some(spec.namespace.clone().unwrap_or_else(|| network_name.to_string())),
+            shards: spec.shards.iter().filter(|(_, count)| **count > 1).map(|(t, c)| (t.clone(), *c)).collect(),
+            followed,
+        }
+    }
+
+    fn prefixed(&self, topic: &str) -> String {
+        match &self.namespace {
+            Some(namespace) => format!("/{}{}", namespace, topic),
+            None => topic.to_string(),
+        }
+    }
+
+    fn shard_topic(&self, topic: &str, shard: u32) -> String {
+        format!("{}/shard/{}", self.prefixed(topic), shard)
+    }
+
+    /// Shard count of `topic`, if it's sharded
+    pub fn shard_count(&self, topic: &str) -> Option<u32> {
+        self.shards.get(topic).copied()
+    }
+
+    /// Wire topics to subscribe to for `topic`
+    pub fn subscriptions(&self, topic: &str) -> Vec<String> {
+        match self.shard_count(topic) {
+            Some(count) => (0..count)
+                .filter(|shard| self.followed.as_ref().is_none_or(|followed| followed.contains(shard)))
+                .map(|shard| self.shard_topic(topic, shard))
+                .collect(),
+            None => vec![self.prefixed(topic)],
+        }
+    }
+
+    /// Wire topic to publish a message of `topic` on; `shard_key` picks the
+    /// shard of sharded topics
+    pub fn publish_topic(&self, topic: &str, shard_key: u64) -> String {
+        match self.shard_count(topic) {
+            Some(count) => self.shard_topic(topic, (shard_key % count as u64) as u32),
+            None => self.prefixed(topic),
+        }
+    }
+
+    /// The topic a message received on `wire_topic` belongs to, or None if
+    /// it isn't one of this network's
+    pub fn topic_of(&self, wire_topic: &str) -> Option<String> {
+        let topic = match &self.namespace {
+            Some(namespace) => wire_topic.strip_prefix('/')?.strip_prefix(namespace.as_str())?,
+            None => wire_topic,
+        };
+        if !topic.starts_with('/') {
+            return None;
+        }
+        if let Some((base, shard)) = topic.rsplit_once("/shard/") {
+            if let (Some(count), Ok(shard)) = (self.shard_count(base), shard.parse::<u32>()) {
+                return (shard < count).then(|| base.to_string());
+            }
+        }
+        Some(topic.to_string())
+    }
+
+    /// Whether a message received on `hash` belongs to `topic`
+    pub fn is(&self, hash: &TopicHash, topic: &str) -> bool {
+        self.topic_of(hash.as_str()).as_deref() == Some(topic)
+    }
+}
+
+/// The gossip section of a network config, if it has one
+pub fn gossip_spec(network_config: &serde_json::Value) -> Result<Option<GossipSpec>> {
+    match network_config.get("gossip") {
+        Some(value) if !value.is_null() => Ok(Some(serde_json::from_value(value.clone())?)),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gossip::{contract, miner};
+
+    fn sharded(followed: Option<Vec<u32>>) -> TopicRouter {
+        let spec = GossipSpec {
+            namespace: None,
+            shards: BTreeMap::from([(miner::block::TOPIC.to_string(), 4)]),
+        };
+        TopicRouter::new("testnet", Some(&spec), followed)
+    }
+
+    #[test]
+    fn test_unrouted_topics_are_shared() {
+        let router = TopicRouter::new("testnet", None, None);
+        assert_eq!(router.subscriptions(miner::block::TOPIC), vec!["/miner/block"]);
+        assert_eq!(router.publish_topic(miner::block::TOPIC, 7), "/miner/block");
+        assert_eq!(router.topic_of("/miner/block").as_deref(), Some("/miner/block"));
+    }
+
+    #[test]
+    fn test_namespaced_and_sharded() {
+        let router = sharded(None);
+        assert_eq!(router.publish_topic(miner::block::TOPIC, 6), "/testnet/miner/block/shard/2");
+        assert_eq!(router.publish_topic(contract::commits::TOPIC, 6), "/testnet/contract/commits");
+        assert_eq!(router.subscriptions(miner::block::TOPIC).len(), 4);
+
+        assert_eq!(router.topic_of("/testnet/miner/block/shard/2").as_deref(), Some(miner::block::TOPIC));
+        assert_eq!(router.topic_of("/testnet/contract/commits").as_deref(), Some(contract::commits::TOPIC));
+        // Other networks' topics, the shared topics and unknown shards aren't ours
+        assert_eq!(router.topic_of("/mainnet/miner/block/shard/2"), None);
+        assert_eq!(router.topic_of("/testnetx/miner/block/shard/2"), None);
+        assert_eq!(router.topic_of("/miner/block"), None);
+        assert_eq!(router.topic_of("/testnet/miner/block/shard/4"), None);
+    }
+
+    #[test]
+    fn test_followed_shards() {
+        let router = sharded(Some(vec![1, 3]));
+        assert_eq!(
+            router.subscriptions(miner::block::TOPIC),
+            vec!["/testnet/miner/block/shard/1", "/testnet/miner/block/shard/3"]
+        );
+        // Publishing isn't limited to followed shards
+        assert_eq!(router.publish_topic(miner::block::TOPIC, 4), "/testnet/miner/block/shard/0");
+    }
+}
@@ -0,0 +1,111 @@
+//! Gossip wire format negotiation
+//!
+//! Every node reads gossip in JSON and in CBOR, and advertises the binary
+//! version it reads as `wire=` in its identify agent string. Gossipsub can't
+//! negotiate per message, so a node publishes CBOR on a topic only once every
+//! peer it knows on that topic has advertised support, and JSON otherwise.
+//! While a network is upgrading, a peer without support that isn't connected
+//! to the publisher may still be relayed a binary message it can't read; it
+//! catches up on the block or commit when it next syncs.
+
+use anyhow::Result;
+use libp2p::gossipsub::{IdentTopic, MessageId};
+use libp2p::PeerId;
+use modal_common::wire::{self, WireFormat};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::{LazyLock, Mutex};
+
+use crate::swarm_driver::SwarmHandle;
+
+/// Binary gossip format version, advertised as `wire=cbor1`
+pub const WIRE_VERSION: &str = "cbor1";
+
+/// Remote peers that have advertised `WIRE_VERSION`. A peer's support
+/// doesn't depend on which of our swarms it connected to, so the set is
+/// shared by every network this process joins.
+static BINARY_PEERS: LazyLock<Mutex<HashSet<PeerId>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// The binary gossip version in a peer's identify agent string, if any
+pub fn advertised_wire(agent_version: &str) -> Option<&str> {
+    agent_version.split(';').find_map(|part| part.strip_prefix("wire="))
+}
+
+/// Remember whether `peer_id` reads binary gossip, from its agent string
+pub fn record_peer(peer_id: PeerId, agent_version: &str) {
+    let mut peers = BINARY_PEERS.lock().unwrap();
+    if advertised_wire(agent_version) == Some(WIRE_VERSION) {
+        peers.insert(peer_id);
+    } else {
+        peers.remove(&peer_id);
+    }
+}
+
+/// CBOR if every one of `peers` reads it and there is at least one, else JSON
+pub fn format_for<'a>(peers: impl IntoIterator<Item = &'a PeerId>) -> WireFormat {
+    let binary = BINARY_PEERS.lock().unwrap();
+    let mut any = false;
+    for peer in peers {
+        if !binary.contains(peer) {
+            return WireFormat::Json;
+        }
+        any = true;
+    }
+    if any {
+        WireFormat::Cbor
+    } else {
+        WireFormat::Json
+    }
+}
+
+/// Publish `message` on `topic` in the format its subscribers can all read
+pub async fn publish<T: Serialize>(swarm: &SwarmHandle, topic: &str, message: &T) -> Result<MessageId> {
+    publish_sharded(swarm, topic, 0, message).await
+}
+
+/// Publish `message` on the shard of `topic` that `shard_key` falls in
+pub async fn publish_sharded<T: Serialize>(
+    swarm: &SwarmHandle,
+    topic: &str,
+    shard_key: u64,
+    message: &T,
+) -> Result<MessageId> {
+    let hash = IdentTopic::new(swarm.topics().publish_topic(topic, shard_key)).hash();
+    let subscribers: Vec<PeerId> = swarm
+        .run(move |swarm| {
+            swarm
+                .behaviour()
+                .gossipsub
+                .all_peers()
+                .filter(|(_, topics)| topics.contains(&&hash))
+                .map(|(peer, _)| *peer)
+                .collect()
+        })
+        .await?;
+    let data = wire::to_vec(message, format_for(&subscribers))?;
+    swarm.publish_sharded(topic, shard_key, data).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binary_only_when_every_peer_supports_it() {
+        let (a, b) = (PeerId::random(), PeerId::random());
+        assert_eq!(advertised_wire("modal-node/0.1.0;role=Miner;wire=cbor1"), Some("cbor1"));
+        assert_eq!(advertised_wire("modal-node/0.1.0;role=Miner"), None);
+
+        record_peer(a, "modal-node/0.1.0;wire=cbor1");
+        record_peer(b, "modal-node/0.1.0");
+        assert_eq!(format_for([&a]), WireFormat::Cbor);
+        assert_eq!(format_for([&a, &b]), WireFormat::Json);
+        assert_eq!(format_for([]), WireFormat::Json);
+
+        record_peer(b, "modal-node/0.1.0;wire=cbor1");
+        assert_eq!(format_for([&a, &b]), WireFormat::Cbor);
+        // A peer that restarts on an older version loses it
+        record_peer(a, "modal-node/0.1.0;wire=cbor0");
+        assert_eq!(format_for([&a, &b]), WireFormat::Json);
+    }
+}
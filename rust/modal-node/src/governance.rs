@@ -0,0 +1,217 @@
+//! Network parameter governance
+//!
+//! Applies parameter-change proposals from the governance system contract
+//! (see `modal_datastore::governance`) once a quorum of the active
+//! validators approved them. Epoch length and block time changes are
+//! scheduled as an era as soon as the proposal is approved, since eras must
+//! be known before the chain reaches them; the difficulty algorithm and gas
+//! quotas switch when the chain reaches the activation height.
+
+use anyhow::Result;
+use modal_common::eras::Era;
+use modal_datastore::governance::ParameterProposal;
+use modal_datastore::models::validator::get_validator_set_for_epoch_multi;
+use modal_datastore::models::MinerBlock;
+use modal_datastore::DatastoreManager;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, Mutex};
+
+use crate::constants::GOVERNANCE_CHECK_INTERVAL_SECS;
+use crate::node::helpers::apply_era_schedule;
+
+/// Spawn a task that applies approved proposals until shutdown
+pub fn start_governance(
+    datastore_manager: Arc<Mutex<DatastoreManager>>,
+    mut shutdown_rx: broadcast::Receiver<()>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(GOVERNANCE_CHECK_INTERVAL_SECS));
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => break,
+                _ = interval.tick() => {
+                    if let Err(e) = apply_governance(&datastore_manager).await {
+                        log::warn!("Failed to apply governance proposals: {}", e);
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Apply what approved proposals change by now. Returns the ids of the
+/// proposals fully applied in this pass.
+pub async fn apply_governance(datastore_manager: &Arc<Mutex<DatastoreManager>>) -> Result<Vec<String>> {
+    let (pending, tip_height) = {
+        let mgr = datastore_manager.lock().await;
+        let tip_height = MinerBlock::find_all_canonical_multi(&mgr)
+            .await?
+            .iter()
+            .map(|b| b.index)
+            .max()
+            .unwrap_or(0);
+        let epoch = mgr.block_index_to_epoch(tip_height);
+        let validators = get_validator_set_for_epoch_multi(&mgr, epoch).await?.get_active_validators();
+
+        let mut pending = Vec::new();
+        for proposal in mgr.governance_proposals()? {
+            let settled = mgr.proposal_applied_at(&proposal.id)?.is_some()
+                || mgr.proposal_rejection(&proposal.id)?.is_some();
+            if !settled && mgr.proposal_approved(&proposal, &validators)? {
+                pending.push(proposal);
+            }
+        }
+        (pending, tip_height)
+    };
+
+    let mut applied = Vec::new();
+    for proposal in pending {
+        if let Err(e) = schedule_era(datastore_manager, &proposal).await {
+            log::warn!("Rejecting governance proposal {}: {}", proposal.id, e);
+            datastore_manager.lock().await.mark_proposal_rejected(&proposal.id, &e.to_string())?;
+            continue;
+        }
+        if tip_height >= proposal.activation_height {
+            apply_parameters(datastore_manager, &proposal, tip_height).await?;
+            applied.push(proposal.id);
+        }
+    }
+    Ok(applied)
+}
+
+/// Add the proposal's era to the schedule, unless it's there already
+async fn schedule_era(datastore_manager: &Arc<Mutex<DatastoreManager>>, proposal: &ParameterProposal) -> Result<()> {
+    if !proposal.changes.changes_era() {
+        return Ok(());
+    }
+    let era = Era {
+        activation_height: proposal.activation_height,
+        blocks_per_epoch: proposal.changes.blocks_per_epoch,
+        target_block_time_secs: proposal.changes.target_block_time_secs,
+    };
+    let current = datastore_manager.lock().await.era_schedule().clone();
+    if current.eras.contains(&era) {
+        return Ok(());
+    }
+    let mut eras = current.eras.clone();
+    eras.push(era);
+    let schedule = modal_common::eras::EraSchedule::new(current.blocks_per_epoch, current.target_block_time_secs, eras)
+        .map_err(|e| anyhow::anyhow!(e))?;
+    apply_era_schedule(datastore_manager, schedule).await?;
+    log::info!("Governance proposal {} changes epoch parameters at height {}", proposal.id, proposal.activation_height);
+    Ok(())
+}
+
+/// Switch the difficulty algorithm and gas quotas, and record the proposal applied
+async fn apply_parameters(
+    datastore_manager: &Arc<Mutex<DatastoreManager>>,
+    proposal: &ParameterProposal,
+    tip_height: u64,
+) -> Result<()> {
+    let mgr = datastore_manager.lock().await;
+    let changes = &proposal.changes;
+
+    if let Some(difficulty_algorithm) = changes.difficulty_algorithm {
+        let mut network_config = mgr.get_network_config().await?.unwrap_or_else(|| serde_json::json!({}));
+        network_config["difficulty_algorithm"] = serde_json::to_value(difficulty_algorithm)?;
+        mgr.load_network_config(&network_config).await?;
+        log::info!("Governance proposal {} switched the difficulty algorithm to {}", proposal.id, difficulty_algorithm);
+    }
+
+    if changes.commit_gas_quota.is_some() || changes.epoch_gas_quota.is_some() {
+        let mut quotas = mgr.get_gas_quotas().await?;
+        if let Some(quota) = changes.commit_gas_quota {
+            quotas.per_commit = Some(quota);
+        }
+        if let Some(quota) = changes.epoch_gas_quota {
+            quotas.per_epoch = Some(quota);
+        }
+        mgr.store_gas_quotas(&quotas).await?;
+        log::info!("Governance proposal {} set gas quotas to {:?}", proposal.id, quotas);
+    }
+
+    mgr.mark_proposal_applied(&proposal.id, tip_height)?;
+    log::info!("Applied governance proposal {} at height {}", proposal.id, tip_height);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use modal_common::difficulty::DifficultyConfig;
+    use modal_common::eras::EraSchedule;
+    use modal_common::keypair::Keypair;
+    use modal_datastore::governance::{ParameterChange, ProposalApproval, GOVERNANCE_CONTRACT_ID};
+
+    async fn post<T: serde::Serialize>(mgr: &DatastoreManager, path: &str, value: &T) {
+        let key = format!("/contracts/{}{}", GOVERNANCE_CONTRACT_ID, path);
+        mgr.set_data_by_key(&key, &serde_json::to_vec(value).unwrap()).await.unwrap();
+    }
+
+    async fn propose(mgr: &DatastoreManager, validators: &[Keypair], id: &str, activation_height: u64, changes: ParameterChange) {
+        let proposal = ParameterProposal {
+            id: id.to_string(),
+            proposer: validators[0].as_public_key_id(),
+            activation_height,
+            changes,
+            description: String::new(),
+        };
+        post(mgr, &proposal.path(), &proposal).await;
+        for keypair in validators {
+            let approval = ProposalApproval::sign(&proposal, keypair).unwrap();
+            post(mgr, &approval.path(), &approval).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_approved_proposals_are_applied() {
+        let mut mgr = DatastoreManager::create_in_memory().unwrap();
+        mgr.set_era_schedule(EraSchedule::fixed(40, 60));
+        let keypairs: Vec<Keypair> = (0..3).map(|_| Keypair::generate().unwrap()).collect();
+        let validators: Vec<String> = keypairs.iter().map(|k| k.as_public_key_id()).collect();
+        mgr.set_static_validators(&validators).await.unwrap();
+
+        // Two of three validators aren't a quorum
+        propose(&mgr, &keypairs[..2], "lwma", 0, ParameterChange {
+            difficulty_algorithm: Some(DifficultyConfig::Lwma { window: 30 }),
+            commit_gas_quota: Some(5000),
+            ..Default::default()
+        }).await;
+        propose(&mgr, &keypairs, "shorter-epochs", 400, ParameterChange {
+            blocks_per_epoch: Some(20),
+            epoch_gas_quota: Some(1000),
+            ..Default::default()
+        }).await;
+        propose(&mgr, &keypairs, "misaligned", 410, ParameterChange {
+            target_block_time_secs: Some(30),
+            ..Default::default()
+        }).await;
+        let datastore = Arc::new(Mutex::new(mgr));
+
+        // The era is scheduled ahead of time; the gas quota waits for height 400
+        assert!(apply_governance(&datastore).await.unwrap().is_empty());
+        let mgr = datastore.lock().await;
+        assert_eq!(mgr.era_schedule().blocks_per_epoch_at(400), 20);
+        assert_eq!(mgr.get_stored_era_schedule().await.unwrap().unwrap().eras.len(), 1);
+        assert_eq!(mgr.get_gas_quotas().await.unwrap().per_epoch, None);
+        assert!(mgr.proposal_rejection("misaligned").unwrap().unwrap().contains("epoch boundary"));
+        assert_eq!(mgr.get_network_config().await.unwrap(), None);
+
+        // With the last approval the difficulty change applies right away
+        let proposal = mgr.governance_proposal("lwma").unwrap().unwrap();
+        let approval = ProposalApproval::sign(&proposal, &keypairs[2]).unwrap();
+        post(&mgr, &approval.path(), &approval).await;
+        drop(mgr);
+        assert_eq!(apply_governance(&datastore).await.unwrap(), vec!["lwma".to_string()]);
+        let mgr = datastore.lock().await;
+        let config = mgr.get_network_config().await.unwrap().unwrap();
+        assert_eq!(DifficultyConfig::from_network_config(&config).unwrap(), DifficultyConfig::Lwma { window: 30 });
+        assert_eq!(mgr.get_gas_quotas().await.unwrap().per_commit, Some(5000));
+        assert_eq!(mgr.proposal_applied_at("lwma").unwrap(), Some(0));
+        drop(mgr);
+
+        // Applied and rejected proposals are settled
+        assert!(apply_governance(&datastore).await.unwrap().is_empty());
+    }
+}
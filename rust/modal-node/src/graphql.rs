@@ -0,0 +1,469 @@
+//! GraphQL query layer
+//!
+//! An optional endpoint, built with the `graphql` feature and served by
+//! indexer nodes on `graphql_port` at `/graphql`, answering queries over
+//! blocks, epochs, contracts, commits and validators in one round trip
+//! instead of one RPC call per object. Lists are paged with `first` and an
+//! opaque `after` cursor. Filters that need the indexer's `IndexStore`
+//! (contracts by signer, commits by path, balances) fail on nodes that
+//! don't index.
+
+use async_graphql::{
+    ComplexObject, Context, EmptyMutation, EmptySubscription, ErrorExtensions, Object, OutputType, Schema,
+    SimpleObject,
+};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use warp::Filter;
+
+use modal_common::error_codes::ErrorCode;
+use modal_datastore::models::validator::get_validator_set_for_epoch_multi;
+use modal_datastore::models::{Commit, Contract, MinerBlock};
+use modal_datastore::{DatastoreManager, IndexStore};
+
+use crate::constants::{GRAPHQL_DEFAULT_PAGE_SIZE, GRAPHQL_MAX_COMPLEXITY, GRAPHQL_MAX_DEPTH, GRAPHQL_MAX_PAGE_SIZE};
+
+pub type IndexerSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Build the schema over a node's datastore
+pub fn schema(datastore_manager: Arc<Mutex<DatastoreManager>>) -> IndexerSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(datastore_manager)
+        .limit_depth(GRAPHQL_MAX_DEPTH)
+        .limit_complexity(GRAPHQL_MAX_COMPLEXITY)
+        .finish()
+}
+
+/// Start the GraphQL endpoint on the specified port
+pub async fn start_graphql(
+    port: u16,
+    datastore_manager: Arc<Mutex<DatastoreManager>>,
+) -> Result<tokio::task::JoinHandle<()>, anyhow::Error> {
+    let routes = warp::path!("graphql").and(async_graphql_warp::graphql(schema(datastore_manager))).and_then(
+        |(schema, request): (IndexerSchema, async_graphql::Request)| async move {
+            Ok::<_, std::convert::Infallible>(async_graphql_warp::GraphQLResponse::from(schema.execute(request).await))
+        },
+    );
+
+    log::info!("Starting GraphQL endpoint on http://0.0.0.0:{}/graphql", port);
+
+    let server = warp::serve(routes).bind(([0, 0, 0, 0], port));
+    Ok(tokio::spawn(async move {
+        server.await;
+    }))
+}
+
+/// An error carrying its shared error code in `extensions`
+fn coded_error(code: ErrorCode, message: impl Into<String>) -> async_graphql::Error {
+    async_graphql::Error::new(message).extend_with(|_, e| {
+        e.set("code", code.code());
+        e.set("name", code.name());
+        e.set("retriable", code.retriable());
+    })
+}
+
+fn datastore<'a>(ctx: &Context<'a>) -> &'a Arc<Mutex<DatastoreManager>> {
+    ctx.data_unchecked::<Arc<Mutex<DatastoreManager>>>()
+}
+
+/// The index, if this node keeps one
+fn index(mgr: &DatastoreManager) -> async_graphql::Result<&IndexStore> {
+    if !mgr.index().is_enabled()? {
+        return Err(coded_error(ErrorCode::MethodNotFound, "This node doesn't keep an index; ask an indexer node"));
+    }
+    Ok(mgr.index())
+}
+
+/// One page of a list
+#[derive(SimpleObject)]
+#[graphql(concrete(name = "BlockPage", params(Block)))]
+#[graphql(concrete(name = "ContractPage", params(ContractNode)))]
+#[graphql(concrete(name = "CommitPage", params(CommitNode)))]
+pub struct Page<T: OutputType> {
+    pub nodes: Vec<T>,
+    pub total_count: u64,
+    /// Pass as `after` for the next page
+    pub end_cursor: Option<String>,
+    pub has_next_page: bool,
+}
+
+/// The page of `items` after the `after` cursor
+fn paginate<T, U: OutputType>(
+    items: Vec<T>,
+    first: Option<u32>,
+    after: Option<String>,
+    node: impl Fn(T) -> U,
+) -> async_graphql::Result<Page<U>> {
+    let start = match after {
+        Some(cursor) => cursor
+            .parse::<usize>()
+            .map_err(|_| coded_error(ErrorCode::InvalidParams, format!("Invalid cursor: {}", cursor)))?,
+        None => 0,
+    };
+    let first = first.unwrap_or(GRAPHQL_DEFAULT_PAGE_SIZE).min(GRAPHQL_MAX_PAGE_SIZE) as usize;
+    let total_count = items.len();
+    let nodes: Vec<U> = items.into_iter().skip(start).take(first).map(node).collect();
+    let end = start + nodes.len();
+    Ok(Page {
+        end_cursor: (!nodes.is_empty()).then(|| end.to_string()),
+        has_next_page: end < total_count,
+        total_count: total_count as u64,
+        nodes,
+    })
+}
+
+#[derive(SimpleObject)]
+pub struct BlockCommit {
+    pub contract_id: String,
+    pub commit_id: String,
+    pub size: u64,
+    pub gas: u64,
+}
+
+#[derive(SimpleObject)]
+pub struct Block {
+    pub height: u64,
+    pub epoch: u64,
+    pub hash: String,
+    pub previous_hash: String,
+    pub timestamp: i64,
+    pub nominated_peer_id: String,
+    pub target_difficulty: String,
+    pub canonical: bool,
+    pub commits: Vec<BlockCommit>,
+}
+
+impl From<MinerBlock> for Block {
+    fn from(block: MinerBlock) -> Self {
+        Self {
+            height: block.index,
+            epoch: block.epoch,
+            hash: block.hash,
+            previous_hash: block.previous_hash,
+            timestamp: block.timestamp,
+            nominated_peer_id: block.nominated_peer_id,
+            target_difficulty: block.target_difficulty,
+            canonical: block.is_canonical,
+            commits: block
+                .commits
+                .into_iter()
+                .map(|c| BlockCommit { contract_id: c.contract_id, commit_id: c.commit_id, size: c.size, gas: c.gas })
+                .collect(),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct ValidatorStake {
+    pub peer_id: String,
+    pub stake: u64,
+}
+
+#[derive(SimpleObject)]
+pub struct ValidatorSet {
+    pub epoch: u64,
+    pub nominated: Vec<String>,
+    pub staked: Vec<String>,
+    pub alternate: Vec<String>,
+    pub stakes: Vec<ValidatorStake>,
+}
+
+async fn validator_set(mgr: &DatastoreManager, epoch: u64) -> Option<ValidatorSet> {
+    let set = get_validator_set_for_epoch_multi(mgr, epoch).await.ok()?;
+    let mut stakes: Vec<ValidatorStake> = set
+        .validator_stakes
+        .iter()
+        .map(|(peer_id, stake)| ValidatorStake { peer_id: peer_id.clone(), stake: *stake })
+        .collect();
+    stakes.sort_by(|a, b| b.stake.cmp(&a.stake).then_with(|| a.peer_id.cmp(&b.peer_id)));
+    Some(ValidatorSet {
+        epoch,
+        nominated: set.nominated_validators,
+        staked: set.staked_validators,
+        alternate: set.alternate_validators,
+        stakes,
+    })
+}
+
+async fn current_epoch(mgr: &DatastoreManager) -> async_graphql::Result<u64> {
+    Ok(MinerBlock::find_all_canonical_multi(mgr).await?.last().map(|b| b.epoch).unwrap_or(0))
+}
+
+/// An epoch of the miner chain
+pub struct Epoch {
+    number: u64,
+}
+
+#[Object]
+impl Epoch {
+    async fn number(&self) -> u64 {
+        self.number
+    }
+
+    /// Canonical blocks of the epoch, by height
+    async fn blocks(&self, ctx: &Context<'_>, first: Option<u32>, after: Option<String>) -> async_graphql::Result<Page<Block>> {
+        let mgr = datastore(ctx).lock().await;
+        let current = current_epoch(&mgr).await?;
+        let blocks = MinerBlock::find_canonical_by_epoch_multi(&mgr, self.number, current).await?;
+        paginate(blocks, first, after, Block::from)
+    }
+
+    async fn validators(&self, ctx: &Context<'_>) -> Option<ValidatorSet> {
+        validator_set(&*datastore(ctx).lock().await, self.number).await
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct CommitNode {
+    pub contract_id: String,
+    pub id: String,
+    pub timestamp: u64,
+    /// Batch the commit was included in, if any yet
+    pub batch: Option<String>,
+    /// The commit as stored, `{body, head}` JSON
+    pub data: String,
+}
+
+impl From<Commit> for CommitNode {
+    fn from(commit: Commit) -> Self {
+        Self {
+            contract_id: commit.contract_id,
+            id: commit.commit_id,
+            timestamp: commit.timestamp,
+            batch: commit.in_batch,
+            data: commit.commit_data,
+        }
+    }
+}
+
+#[ComplexObject]
+impl CommitNode {
+    /// Keys that signed the commit
+    async fn signers(&self) -> Vec<String> {
+        serde_json::from_str::<serde_json::Value>(&self.data)
+            .ok()
+            .and_then(|data| data.pointer("/head/signatures").and_then(|s| s.as_object()).map(|s| s.keys().cloned().collect()))
+            .unwrap_or_default()
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct Balance {
+    /// Contract that created the asset
+    pub contract_id: String,
+    pub asset_id: String,
+    pub balance: u64,
+}
+
+/// A contract and what's known of it
+pub struct ContractNode {
+    id: String,
+    created_at: Option<u64>,
+    genesis: Option<String>,
+}
+
+impl From<Contract> for ContractNode {
+    fn from(contract: Contract) -> Self {
+        Self { id: contract.contract_id, created_at: Some(contract.created_at), genesis: Some(contract.genesis) }
+    }
+}
+
+#[Object]
+impl ContractNode {
+    async fn id(&self) -> &str {
+        &self.id
+    }
+
+    async fn created_at(&self) -> Option<u64> {
+        self.created_at
+    }
+
+    /// The genesis as stored, JSON
+    async fn genesis(&self) -> Option<&str> {
+        self.genesis.as_deref()
+    }
+
+    /// Commits, oldest first; with `path_prefix`, only those with an action
+    /// at or below that path (indexer nodes only)
+    async fn commits(
+        &self,
+        ctx: &Context<'_>,
+        path_prefix: Option<String>,
+        first: Option<u32>,
+        after: Option<String>,
+    ) -> async_graphql::Result<Page<CommitNode>> {
+        let mgr = datastore(ctx).lock().await;
+        let mut commits = Commit::find_by_contract_multi(&mgr, &self.id).await?;
+        if let Some(prefix) = path_prefix {
+            let touched: std::collections::HashSet<String> = index(&mgr)?
+                .commits_by_path(&self.id, &prefix, usize::MAX)?
+                .into_iter()
+                .map(|c| c.commit_id)
+                .collect();
+            commits.retain(|c| touched.contains(&c.commit_id));
+        }
+        commits.sort_by(|a, b| (a.timestamp, &a.commit_id).cmp(&(b.timestamp, &b.commit_id)));
+        paginate(commits, first, after, CommitNode::from)
+    }
+
+    /// Asset balances the contract holds (indexer nodes only)
+    async fn balances(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Balance>> {
+        let mgr = datastore(ctx).lock().await;
+        Ok(index(&mgr)?
+            .balances(&self.id)?
+            .into_iter()
+            .map(|b| Balance { contract_id: b.contract_id, asset_id: b.asset_id, balance: b.balance })
+            .collect())
+    }
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// A block by canonical height or by hash
+    async fn block(&self, ctx: &Context<'_>, height: Option<u64>, hash: Option<String>) -> async_graphql::Result<Option<Block>> {
+        let mgr = datastore(ctx).lock().await;
+        let block = match (height, hash) {
+            (Some(height), None) => MinerBlock::find_canonical_by_index_simple(&mgr, height).await?,
+            (None, Some(hash)) => MinerBlock::find_by_hash_multi(&mgr, &hash).await?,
+            _ => return Err(coded_error(ErrorCode::InvalidParams, "Pass either height or hash")),
+        };
+        Ok(block.map(Block::from))
+    }
+
+    /// Canonical blocks, newest first, optionally of one epoch or
+    /// nominating one peer
+    async fn blocks(
+        &self,
+        ctx: &Context<'_>,
+        epoch: Option<u64>,
+        nominated_peer_id: Option<String>,
+        first: Option<u32>,
+        after: Option<String>,
+    ) -> async_graphql::Result<Page<Block>> {
+        let mgr = datastore(ctx).lock().await;
+        let mut blocks = match (&epoch, &nominated_peer_id) {
+            (Some(epoch), _) => MinerBlock::find_canonical_by_epoch_multi(&mgr, *epoch, current_epoch(&mgr).await?).await?,
+            (None, Some(peer_id)) => MinerBlock::find_canonical_by_peer_multi(&mgr, peer_id).await?,
+            (None, None) => MinerBlock::find_all_canonical_multi(&mgr).await?,
+        };
+        if let (Some(_), Some(peer_id)) = (&epoch, &nominated_peer_id) {
+            blocks.retain(|b| &b.nominated_peer_id == peer_id);
+        }
+        blocks.sort_by_key(|b| std::cmp::Reverse(b.index));
+        paginate(blocks, first, after, Block::from)
+    }
+
+    /// An epoch up to the current one
+    async fn epoch(&self, ctx: &Context<'_>, number: u64) -> async_graphql::Result<Option<Epoch>> {
+        let current = current_epoch(&*datastore(ctx).lock().await).await?;
+        Ok((number <= current).then_some(Epoch { number }))
+    }
+
+    /// Contracts, newest first; with `signer`, those with commits signed by
+    /// that key (indexer nodes only)
+    async fn contracts(
+        &self,
+        ctx: &Context<'_>,
+        signer: Option<String>,
+        first: Option<u32>,
+        after: Option<String>,
+    ) -> async_graphql::Result<Page<ContractNode>> {
+        let mgr = datastore(ctx).lock().await;
+        let mut contracts: Vec<ContractNode> = match signer {
+            Some(signer) => {
+                let mut contracts = Vec::new();
+                for id in index(&mgr)?.contracts_by_signer(&signer)? {
+                    contracts.push(match Contract::find_by_id_multi(&mgr, &id).await? {
+                        Some(contract) => contract.into(),
+                        None => ContractNode { id, created_at: None, genesis: None },
+                    });
+                }
+                contracts
+            }
+            None => Contract::find_all_multi(&mgr).await?.into_iter().map(ContractNode::from).collect(),
+        };
+        contracts.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| a.id.cmp(&b.id)));
+        paginate(contracts, first, after, |c| c)
+    }
+
+    async fn contract(&self, ctx: &Context<'_>, id: String) -> async_graphql::Result<Option<ContractNode>> {
+        let mgr = datastore(ctx).lock().await;
+        if let Some(contract) = Contract::find_by_id_multi(&mgr, &id).await? {
+            return Ok(Some(contract.into()));
+        }
+        // Contracts known only from their commits
+        let known = !Commit::find_by_contract_multi(&mgr, &id).await?.is_empty();
+        Ok(known.then_some(ContractNode { id, created_at: None, genesis: None }))
+    }
+
+    async fn commit(&self, ctx: &Context<'_>, contract_id: String, id: String) -> async_graphql::Result<Option<CommitNode>> {
+        let mgr = datastore(ctx).lock().await;
+        let keys = [("contract_id".to_string(), contract_id), ("commit_id".to_string(), id)].into_iter().collect();
+        Ok(Commit::find_one_multi(&mgr, keys).await?.map(CommitNode::from))
+    }
+
+    /// The validator set of an epoch, the current one by default
+    async fn validators(&self, ctx: &Context<'_>, epoch: Option<u64>) -> async_graphql::Result<Option<ValidatorSet>> {
+        let mgr = datastore(ctx).lock().await;
+        let epoch = match epoch {
+            Some(epoch) => epoch,
+            None => current_epoch(&mgr).await?,
+        };
+        Ok(validator_set(&mgr, epoch).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn run(schema: &IndexerSchema, query: &str) -> serde_json::Value {
+        let response = schema.execute(query).await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        response.data.into_json().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_contracts_and_commits_page() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        for (i, id) in ["a", "b", "c"].into_iter().enumerate() {
+            Contract { contract_id: id.to_string(), genesis: "{}".to_string(), created_at: i as u64 }
+                .save_to_final(&mgr)
+                .await
+                .unwrap();
+        }
+        let commit_data = serde_json::json!({
+            "body": [{"method": "post", "path": "/name.text", "value": "x"}],
+            "head": {"signatures": {"alice": "sig"}},
+        })
+        .to_string();
+        Commit {
+            contract_id: "a".to_string(),
+            commit_id: "k1".to_string(),
+            commit_data: commit_data.clone(),
+            timestamp: 5,
+            in_batch: None,
+        }
+        .save_to_final(&mgr)
+        .await
+        .unwrap();
+        mgr.index().index_commit("a", "k1", &commit_data, 5).unwrap();
+        let schema = schema(Arc::new(Mutex::new(mgr)));
+
+        let data = run(&schema, "{ contracts(first: 2) { nodes { id } totalCount endCursor hasNextPage } }").await;
+        assert_eq!(data["contracts"]["nodes"], serde_json::json!([{"id": "c"}, {"id": "b"}]));
+        assert_eq!(data["contracts"]["hasNextPage"], true);
+        let data = run(&schema, r#"{ contracts(first: 2, after: "2") { nodes { id } hasNextPage } }"#).await;
+        assert_eq!(data["contracts"]["nodes"], serde_json::json!([{"id": "a"}]));
+
+        let data = run(&schema, r#"{ contract(id: "a") { commits { nodes { id signers } } } }"#).await;
+        assert_eq!(data["contract"]["commits"]["nodes"][0]["signers"], serde_json::json!(["alice"]));
+
+        // Signer filters need the index, which this node hasn't enabled
+        let response = schema.execute(r#"{ contracts(signer: "alice") { totalCount } }"#).await;
+        assert_eq!(response.errors.len(), 1);
+    }
+}
@@ -0,0 +1,101 @@
+//! Contract indexer
+//!
+//! Nodes running as `indexer` follow contracts like observers, storing the
+//! commits they hear about over gossip and pull, and keep denormalized
+//! indexes of them in the datastore's `IndexStore`: contracts by signer,
+//! commit actions by path and asset balances by owner. The `/index/*`
+//! reqres paths answer from it. Validators never need these, so only
+//! indexers pay for keeping them.
+
+use anyhow::Result;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use modal_datastore::models::{Commit, Contract};
+use modal_datastore::DatastoreManager;
+
+use crate::contract_sync::order_by_parent;
+
+/// Index every stored commit not indexed yet, each contract's in order.
+/// Returns the number of commits indexed.
+pub async fn index_new_commits(datastore_manager: &DatastoreManager) -> Result<usize> {
+    let index = datastore_manager.index();
+    let mut indexed = 0;
+    for contract in Contract::find_all_multi(datastore_manager).await? {
+        let commits = Commit::find_by_contract_multi(datastore_manager, &contract.contract_id).await?;
+        for commit in order_by_parent(commits) {
+            match index.index_commit(&commit.contract_id, &commit.commit_id, &commit.commit_data, commit.timestamp) {
+                Ok(true) => indexed += 1,
+                Ok(false) => {}
+                Err(e) => log::warn!("Failed to index commit {} of {}: {}", commit.commit_id, commit.contract_id, e),
+            }
+        }
+    }
+    Ok(indexed)
+}
+
+/// Start indexing newly stored commits every `interval` until shutdown
+pub fn start_indexer(
+    datastore: Arc<Mutex<DatastoreManager>>,
+    interval: std::time::Duration,
+    mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown_rx.recv() => break,
+            }
+            let mgr = datastore.lock().await;
+            match index_new_commits(&mgr).await {
+                Ok(0) => {}
+                Ok(count) => log::info!("Indexed {} commits ({} total)", count, mgr.index().commit_count().unwrap_or(0)),
+                Err(e) => log::warn!("Indexing failed: {}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_indexes_stored_commits_once() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        Contract {
+            contract_id: "c1".to_string(),
+            genesis: "{}".to_string(),
+            created_at: 1,
+        }
+        .save_to_final(&mgr)
+        .await
+        .unwrap();
+        let commit_data = json!({
+            "body": [{"method": "post", "path": "/name.text", "value": "a"}],
+            "head": {"signatures": {"alice": "sig"}},
+        })
+        .to_string();
+        Commit {
+            contract_id: "c1".to_string(),
+            commit_id: crate::contract_sync::commit_id_of(commit_data.as_bytes()),
+            commit_data,
+            timestamp: 5,
+            in_batch: None,
+        }
+        .save_to_final(&mgr)
+        .await
+        .unwrap();
+
+        assert_eq!(index_new_commits(&mgr).await.unwrap(), 1);
+        assert_eq!(index_new_commits(&mgr).await.unwrap(), 0);
+        assert_eq!(mgr.index().contracts_by_signer("alice").unwrap(), vec!["c1"]);
+
+        // A cleared index is rebuilt from the stored commits
+        mgr.index().clear().unwrap();
+        assert_eq!(index_new_commits(&mgr).await.unwrap(), 1);
+    }
+}
@@ -72,6 +72,24 @@ pub struct NetworkInfo {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub connected_peer_list: Option<Vec<String>>,
     pub bootstrappers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peer_slots: Option<PeerSlotsInfo>,
+}
+
+/// Connection slot usage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerSlotsInfo {
+    pub inbound: usize,
+    pub outbound: usize,
+    pub max_inbound: usize,
+    pub max_outbound: usize,
+    pub reserved_slots: usize,
+    pub reserved_connected: usize,
+    /// Peers evicted to make room since the node started
+    pub evicted: u64,
+    /// Score of each connected peer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scores: Option<std::collections::BTreeMap<String, i64>>,
 }
 
 /// Datastore-related inspection data
@@ -2,17 +2,46 @@
 #![allow(clippy::type_complexity)]
 
 pub mod reqres;
+pub mod contract_sync;
 pub mod gossip;
 pub mod config;
 pub mod config_resolution;
 pub mod logging;
 pub mod bootup;
+pub mod boot_profile;
 pub mod swarm;
+pub mod swarm_driver;
 pub mod node;
 pub mod status_server;
+pub mod explorer;
+pub mod rpc_gateway;
+pub mod indexer;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+pub mod status_history;
 pub mod mining_metrics;
 pub mod inspection;
 pub mod pid;
+pub mod multi_network;
+pub mod reorg_webhook;
+pub mod anomaly_monitor;
+pub mod ban_list;
+pub mod manifest;
+pub mod mempool;
+pub mod peer_slots;
+pub mod proxy;
+pub mod bootstrapper_health;
+pub mod capabilities;
+pub mod build_info;
+pub mod event_journal;
+pub mod event_sinks;
+pub mod accounting;
+pub mod duties;
+pub mod readiness;
+pub mod genesis;
+pub mod governance;
+pub mod contract_export;
+pub mod error_codes;
 
 pub mod actions;
 pub mod consensus;
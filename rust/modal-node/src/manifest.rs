@@ -0,0 +1,95 @@
+//! Signed node manifests
+//!
+//! The identify agent string carries loose `status_url=` and `role=` parts
+//! that any peer can set to anything. Alongside them each node now sends
+//! `manifest=`, its `NodeManifest` (role, network, genesis, protocol
+//! version, archive status and status URL) signed with its peer key. A
+//! receiver only believes a manifest signed by the peer it came from, and
+//! takes `status_url` and `role` from it; peers that send a bad one are
+//! struck like any other invalid message. Peers too old to send one are
+//! still listed with what they claim, but only a verified validator role
+//! earns a reserved peer slot.
+
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use libp2p::PeerId;
+use modal_common::keypair::Keypair;
+use modal_datastore::models::NodeManifest;
+
+use crate::constants::{MAX_MANIFEST_BYTES, MESSAGE_PROTOCOL_VERSION};
+
+/// How far ahead of our clock a manifest may be issued, in seconds
+const MAX_MANIFEST_DRIFT_SECS: i64 = 3600;
+
+/// What this node says about itself, signed with its key
+pub fn local(
+    keypair: &libp2p_identity::Keypair,
+    role: &str,
+    network: &str,
+    genesis: Option<String>,
+    status_url: Option<String>,
+    archive: bool,
+    now: i64,
+) -> Result<NodeManifest> {
+    NodeManifest {
+        peer_id: keypair.public().to_peer_id().to_string(),
+        role: role.to_string(),
+        network: network.to_string(),
+        genesis,
+        protocol_version: MESSAGE_PROTOCOL_VERSION,
+        status_url,
+        archive,
+        issued_at: now,
+        signature: String::new(),
+    }
+    .sign(&Keypair::from_libp2p_keypair(keypair.clone())?)
+}
+
+/// The agent string part carrying a manifest
+pub fn encode(manifest: &NodeManifest) -> Result<String> {
+    let json = serde_json::to_vec(manifest)?;
+    Ok(format!("manifest={}", base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(json)))
+}
+
+/// The manifest in a peer's identify agent string, checked against the
+/// peer it came from. `None` for peers that don't send one.
+pub fn verified(agent_version: &str, peer_id: &PeerId, now: i64) -> Result<Option<NodeManifest>> {
+    let Some(encoded) = agent_version.split(';').find_map(|part| part.strip_prefix("manifest=")) else {
+        return Ok(None);
+    };
+    modal_common::wire::check_len("manifest", encoded.len(), MAX_MANIFEST_BYTES)?;
+    let json = base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(encoded)?;
+    let manifest: NodeManifest = serde_json::from_slice(&json)?;
+    if !manifest.verify(&peer_id.to_string()) {
+        return Err(anyhow!("Manifest is not signed by {}", peer_id));
+    }
+    if manifest.issued_at > now + MAX_MANIFEST_DRIFT_SECS {
+        return Err(anyhow!("Manifest issued in the future ({})", manifest.issued_at));
+    }
+    Ok(Some(manifest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_round_trip() {
+        let keypair = libp2p_identity::Keypair::generate_ed25519();
+        let peer_id = keypair.public().to_peer_id();
+        let manifest = local(&keypair, "Validator", "devnet1", Some("abc".to_string()), Some("https://node1.example.com".to_string()), true, 1000).unwrap();
+        let agent_version = format!("modal-node/0.1.0;role=Validator;{}", encode(&manifest).unwrap());
+
+        assert_eq!(verified(&agent_version, &peer_id, 1000).unwrap(), Some(manifest.clone()));
+        assert_eq!(verified("modal-node/0.1.0;role=Validator", &peer_id, 1000).unwrap(), None);
+        // Sent by, or rewritten for, another peer
+        assert!(verified(&agent_version, &PeerId::random(), 1000).is_err());
+        let mut spoofed = manifest.clone();
+        spoofed.status_url = Some("https://phishing.example.com".to_string());
+        let agent_version = format!("modal-node/0.1.0;{}", encode(&spoofed).unwrap());
+        assert!(verified(&agent_version, &peer_id, 1000).is_err());
+        // Issued too far ahead
+        let agent_version = format!("modal-node/0.1.0;{}", encode(&manifest).unwrap());
+        assert!(verified(&agent_version, &peer_id, 1000 - MAX_MANIFEST_DRIFT_SECS - 1).is_err());
+    }
+}
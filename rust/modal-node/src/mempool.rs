@@ -0,0 +1,26 @@
+//! The sequencer mempool
+//!
+//! Transactions submitted to this node over `/sequencer/mempool/submit`
+//! wait here until the Shoal validator's workers batch them, best fee
+//! first. Each node holds one pool for its network, shared by the request
+//! handlers and the validator, so it can take transactions before this
+//! node joins the committee. It starts with the default Narwhal limits and
+//! takes the validator's own once it starts.
+
+use modal_validator::{Mempool, NarwhalConfig};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// A network's mempool, shared by its request handlers and validator
+pub type SharedMempool = Arc<Mutex<Mempool>>;
+
+/// An empty mempool with the default Narwhal limits
+pub fn create_shared_mempool() -> SharedMempool {
+    Arc::new(Mutex::new(Mempool::new(NarwhalConfig::default().mempool)))
+}
+
+/// The mempool the validator batches from, limited by its Narwhal config
+pub async fn for_validator(mempool: &SharedMempool, narwhal_config: &NarwhalConfig) -> SharedMempool {
+    mempool.lock().await.set_config(narwhal_config.mempool.clone());
+    mempool.clone()
+}
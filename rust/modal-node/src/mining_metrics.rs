@@ -1,7 +1,31 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
+/// How a share submitted by an external miner was judged
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShareOutcome {
+    /// Met the share difficulty
+    Accepted,
+    /// Met the block difficulty and completed a block
+    Block,
+    /// Did not meet the share difficulty, or referenced an unknown job
+    Rejected,
+    /// Was for a height that has already been mined
+    Stale,
+}
+
+/// Share accounting for a single external miner worker
+#[derive(Clone, Debug, Default)]
+pub struct WorkerShares {
+    pub accepted: u64,
+    pub rejected: u64,
+    pub stale: u64,
+    pub blocks: u64,
+    pub last_share: Option<Instant>,
+}
+
 /// Mining metrics for tracking hashrate and performance
 #[derive(Clone, Debug)]
 pub struct MiningMetrics {
@@ -15,6 +39,10 @@ pub struct MiningMetrics {
     pub current_hashrate: f64,
     /// Number of blocks successfully mined
     pub blocks_mined: u64,
+    /// Hashrate of each mining thread for the last mined block, in H/s
+    pub thread_hashrates: Vec<f64>,
+    /// Shares submitted by external miners, keyed by worker name
+    pub workers: HashMap<String, WorkerShares>,
 }
 
 impl MiningMetrics {
@@ -26,6 +54,8 @@ impl MiningMetrics {
             last_update: now,
             current_hashrate: 0.0,
             blocks_mined: 0,
+            thread_hashrates: Vec::new(),
+            workers: HashMap::new(),
         }
     }
     
@@ -44,6 +74,27 @@ impl MiningMetrics {
         }
     }
     
+    /// Record the per-thread hashrates for the last mined block
+    pub fn record_thread_hashrates(&mut self, hashrates: Vec<f64>) {
+        self.thread_hashrates = hashrates;
+    }
+    
+    /// Record a share submitted by an external miner worker
+    pub fn record_share(&mut self, worker: &str, outcome: ShareOutcome) {
+        let shares = self.workers.entry(worker.to_string()).or_default();
+        match outcome {
+            ShareOutcome::Accepted => shares.accepted += 1,
+            ShareOutcome::Block => {
+                shares.accepted += 1;
+                shares.blocks += 1;
+                self.blocks_mined += 1;
+            }
+            ShareOutcome::Rejected => shares.rejected += 1,
+            ShareOutcome::Stale => shares.stale += 1,
+        }
+        shares.last_share = Some(Instant::now());
+    }
+    
     /// Get the overall average hashrate since mining started
     pub fn average_hashrate(&self) -> f64 {
         let elapsed = self.start_time.elapsed().as_secs_f64();
@@ -0,0 +1,301 @@
+//! Multi-network support.
+//!
+//! A single modal-node process can join several networks at once (for example
+//! testnet plus a contract hub devnet), which is useful for bridge and relayer
+//! operators. Each network membership gets a fully isolated [`Node`]: its own
+//! swarm, its own `DatastoreManager` (under `<data_dir>/<network>`, along with
+//! the network's hash functions), its own mempool, and its own status page.
+//! Only [`run`] takes a config with `networks`; a single node refuses one.
+
+use anyhow::Result;
+use libp2p::Multiaddr;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use crate::actions;
+use crate::config::Config;
+use crate::node::Node;
+
+/// A single network that this process should participate in.
+///
+/// Fields left unset inherit from the top-level node config.
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct NetworkMembership {
+    /// Name used for data directories and status output (defaults to the network name)
+    pub name: Option<String>,
+    pub network_config_path: PathBuf,
+    pub data_dir: Option<PathBuf>,
+    pub listeners: Option<Vec<Multiaddr>>,
+    pub bootstrappers: Option<Vec<Multiaddr>>,
+    pub run_as: Option<String>,
+    pub status_port: Option<u16>,
+    pub status_url: Option<String>,
+    pub explorer_port: Option<u16>,
+    pub rpc_port: Option<u16>,
+    pub graphql_port: Option<u16>,
+    pub getwork_port: Option<u16>,
+    pub miner_nominees: Option<Vec<String>>,
+    pub miner_nomination_policy: Option<crate::actions::miner::nomination::NominationPolicyConfig>,
+    pub initial_difficulty: Option<u128>,
+}
+
+impl NetworkMembership {
+    /// Build the per-network node config by layering this membership over the base config
+    pub fn to_config(&self, base: &Config) -> Config {
+        let mut config = base.clone();
+        config.networks = None;
+        config.network_config_path = Some(self.network_config_path.clone());
+
+        let name = self.name.clone().unwrap_or_else(|| config.get_network_name());
+
+        let base_dir = base.data_dir.as_ref().or(base.storage_path.as_ref());
+        config.data_dir = self
+            .data_dir
+            .clone()
+            .or_else(|| base_dir.map(|dir| dir.join(&name)));
+        config.storage_path = None;
+        config.status_html_dir = base.status_html_dir.as_ref().map(|dir| dir.join(&name));
+
+        // Listeners, status, explorer, RPC, GraphQL and getwork ports can't be shared between swarms, so these never inherit
+        config.listeners = self.listeners.clone();
+        config.status_port = self.status_port;
+        config.status_url = self.status_url.clone();
+        config.explorer_port = self.explorer_port;
+        config.rpc_port = self.rpc_port;
+        config.graphql_port = self.graphql_port;
+        config.getwork_port = self.getwork_port;
+
+        if self.bootstrappers.is_some() {
+            config.bootstrappers = self.bootstrappers.clone();
+        }
+        if self.run_as.is_some() {
+            config.run_as = self.run_as.clone();
+        }
+        if self.miner_nominees.is_some() {
+            config.miner_nominees = self.miner_nominees.clone();
+        }
+        if self.miner_nomination_policy.is_some() {
+            config.miner_nomination_policy = self.miner_nomination_policy.clone();
+        }
+        if self.initial_difficulty.is_some() {
+            config.initial_difficulty = self.initial_difficulty;
+        }
+
+        config
+    }
+}
+
+/// Expand a config with `networks` into one config per network.
+///
+/// Returns an error if two memberships would share a name, data directory,
+/// listener, or status port.
+pub fn network_configs(config: &Config) -> Result<Vec<(String, Config)>> {
+    let Some(memberships) = config.networks.as_ref() else {
+        return Ok(vec![(config.get_network_name(), config.clone())]);
+    };
+
+    if memberships.is_empty() {
+        anyhow::bail!("Config has an empty 'networks' list");
+    }
+
+    let mut names = HashSet::new();
+    let mut data_dirs = HashSet::new();
+    let mut listeners = HashSet::new();
+    let mut ports = HashSet::new();
+    let mut configs = Vec::new();
+
+    for membership in memberships {
+        let network_config = membership.to_config(config);
+        let name = membership
+            .name
+            .clone()
+            .unwrap_or_else(|| network_config.get_network_name());
+
+        if !names.insert(name.clone()) {
+            anyhow::bail!("Network '{}' is listed more than once", name);
+        }
+        if let Some(ref dir) = network_config.data_dir {
+            if !data_dirs.insert(dir.clone()) {
+                anyhow::bail!("Network '{}' shares data_dir {} with another network", name, dir.display());
+            }
+        }
+        for listener in network_config.listeners.iter().flatten() {
+            if !listeners.insert(listener.clone()) {
+                anyhow::bail!("Network '{}' reuses listener {} from another network", name, listener);
+            }
+        }
+        if let Some(port) = network_config.status_port {
+            if !ports.insert(port) {
+                anyhow::bail!("Network '{}' reuses status_port {} from another network", name, port);
+            }
+        }
+        if let Some(port) = network_config.explorer_port {
+            if !ports.insert(port) {
+                anyhow::bail!("Network '{}' reuses explorer_port {} from another network", name, port);
+            }
+        }
+        if let Some(port) = network_config.rpc_port {
+            if !ports.insert(port) {
+                anyhow::bail!("Network '{}' reuses rpc_port {} from another network", name, port);
+            }
+        }
+        if let Some(port) = network_config.graphql_port {
+            if !ports.insert(port) {
+                anyhow::bail!("Network '{}' reuses graphql_port {} from another network", name, port);
+            }
+        }
+        if let Some(port) = network_config.getwork_port {
+            if !ports.insert(port) {
+                anyhow::bail!("Network '{}' reuses getwork_port {} from another network", name, port);
+            }
+        }
+
+        configs.push((name, network_config));
+    }
+
+    Ok(configs)
+}
+
+/// Run one node per configured network until shutdown.
+///
+/// Every node is driven by its own role action; the process exits when all of
+/// them have shut down, or as soon as one of them fails.
+pub async fn run(config: &Config) -> Result<()> {
+    let configs = network_configs(config)?;
+    log::info!("Starting multi-network node with {} networks", configs.len());
+
+    let mut nodes = Vec::new();
+    for (name, network_config) in configs {
+        log::info!("[{}] Creating node (role: {})", name, network_config.get_node_role());
+        let mut node = Node::from_config(network_config.clone()).await?;
+        node.setup(&network_config).await?;
+        nodes.push((name, network_config, node));
+    }
+
+    let runs = nodes.iter_mut().map(|(name, network_config, node)| async move {
+        let result = run_role(network_config, node).await;
+        if let Err(ref e) = result {
+            log::error!("[{}] Node stopped with error: {}", name, e);
+        } else {
+            log::info!("[{}] Node stopped", name);
+        }
+        result
+    });
+    futures::future::try_join_all(runs).await?;
+
+    Ok(())
+}
+
+async fn run_role(config: &Config, node: &mut Node) -> Result<()> {
+    match config.run_as.as_deref() {
+        Some("miner") => actions::miner::run(node).await,
+        Some("observer") => actions::observer::run(node).await,
+        Some("validator") => actions::validator::run(node).await,
+        Some("gateway") => actions::gateway::run(node).await,
+        Some("indexer") => actions::indexer::run(node).await,
+        Some("noop") => actions::noop::run(node).await,
+        Some(unknown) => anyhow::bail!("Unknown run_as value: '{}'. Valid values: miner, observer, validator, gateway, indexer, noop", unknown),
+        None if config.run_miner.unwrap_or(false) => actions::miner::run(node).await,
+        None => actions::server::run(node).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn membership(path: &str) -> NetworkMembership {
+        NetworkMembership {
+            network_config_path: PathBuf::from(path),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_single_network_passthrough() {
+        let config = Config {
+            network_config_path: Some(PathBuf::from("modal-networks://testnet")),
+            ..Default::default()
+        };
+        let configs = network_configs(&config).unwrap();
+        assert_eq!(configs.len(), 1);
+        assert_eq!(configs[0].0, "testnet");
+    }
+
+    #[test]
+    fn test_memberships_get_isolated_data_dirs() {
+        let config = Config {
+            data_dir: Some(PathBuf::from("/var/modal")),
+            run_as: Some("observer".to_string()),
+            networks: Some(vec![
+                membership("modal-networks://testnet"),
+                NetworkMembership {
+                    run_as: Some("miner".to_string()),
+                    ..membership("modal-networks://devnet1")
+                },
+            ]),
+            ..Default::default()
+        };
+
+        let configs = network_configs(&config).unwrap();
+        assert_eq!(configs.len(), 2);
+        assert_eq!(configs[0].1.data_dir, Some(PathBuf::from("/var/modal/testnet")));
+        assert_eq!(configs[1].1.data_dir, Some(PathBuf::from("/var/modal/devnet1")));
+        assert_eq!(configs[0].1.run_as.as_deref(), Some("observer"));
+        assert_eq!(configs[1].1.run_as.as_deref(), Some("miner"));
+        assert!(configs.iter().all(|(_, c)| c.networks.is_none()));
+    }
+
+    #[test]
+    fn test_storage_path_used_as_base_dir() {
+        let config = Config {
+            storage_path: Some(PathBuf::from("/nodes/relayer/storage")),
+            networks: Some(vec![membership("modal-networks://testnet")]),
+            ..Default::default()
+        };
+        let configs = network_configs(&config).unwrap();
+        assert_eq!(configs[0].1.data_dir, Some(PathBuf::from("/nodes/relayer/storage/testnet")));
+        assert_eq!(configs[0].1.storage_path, None);
+    }
+
+    #[test]
+    fn test_duplicate_networks_rejected() {
+        let config = Config {
+            networks: Some(vec![
+                membership("modal-networks://testnet"),
+                membership("modal-networks://testnet"),
+            ]),
+            ..Default::default()
+        };
+        assert!(network_configs(&config).is_err());
+    }
+
+    #[test]
+    fn test_shared_listener_rejected() {
+        let listener: Multiaddr = "/ip4/0.0.0.0/tcp/10001/ws".parse().unwrap();
+        let config = Config {
+            networks: Some(vec![
+                NetworkMembership {
+                    listeners: Some(vec![listener.clone()]),
+                    ..membership("modal-networks://testnet")
+                },
+                NetworkMembership {
+                    listeners: Some(vec![listener]),
+                    ..membership("modal-networks://devnet1")
+                },
+            ]),
+            ..Default::default()
+        };
+        assert!(network_configs(&config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_single_node_rejects_networks() {
+        let config = Config {
+            networks: Some(vec![membership("modal-networks://testnet")]),
+            ..Default::default()
+        };
+        assert!(Node::from_config(config).await.is_err());
+    }
+}
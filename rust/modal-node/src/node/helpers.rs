@@ -4,16 +4,18 @@
 //! but don't need to be part of the Node impl block.
 
 use anyhow::Result;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use libp2p::{Multiaddr, PeerId};
 use libp2p::multiaddr::Protocol;
 
-use modal_datastore::DatastoreManager;
+use modal_common::eras::EraSchedule;
+use modal_datastore::{DatastoreManager, GasQuotas};
 use modal_datastore::models::MinerBlock;
 
 use crate::config::Config;
+use crate::constants::{BLOCKS_PER_EPOCH, TARGET_BLOCK_TIME_SECS};
 use crate::inspection::{InspectionData, InspectionLevel, NodeStatus, NetworkInfo, DatastoreInfo, MiningInfo};
 
 /// Extract PeerId from a Multiaddr
@@ -40,16 +42,48 @@ pub fn exclude_multiaddresses_with_peerid(ma: Vec<Multiaddr>, peerid: PeerId) ->
         .collect()
 }
 
+fn open_datastore(data_dir: &Path, config: &Config) -> Result<DatastoreManager> {
+    Ok(match &config.storage {
+        Some(storage) => DatastoreManager::open_with_config(data_dir, storage)?,
+        None => DatastoreManager::open(data_dir)?,
+    })
+}
+
+/// Flush the datastore every `interval` and once more on shutdown, for
+/// stores whose writes skip the write-ahead log (`Durability::Deferred`)
+pub fn start_datastore_flusher(
+    datastore: Arc<Mutex<DatastoreManager>>,
+    interval: std::time::Duration,
+    mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            let stopping = tokio::select! {
+                _ = ticker.tick() => false,
+                _ = shutdown_rx.recv() => true,
+            };
+            if let Err(e) = datastore.lock().await.flush_all() {
+                log::warn!("Failed to flush datastore: {}", e);
+            }
+            if stopping {
+                break;
+            }
+        }
+    })
+}
+
 /// Initialize the DatastoreManager from config
 pub async fn initialize_datastore(config: &Config) -> Result<Arc<Mutex<DatastoreManager>>> {
     let datastore_manager = if let Some(data_dir) = config.data_dir.clone() {
         log::info!("📁 Initializing DatastoreManager at {:?}", data_dir);
-        let mgr = DatastoreManager::open(&data_dir)?;
+        let mgr = open_datastore(&data_dir, config)?;
         log::info!("✓ DatastoreManager initialized with 6 stores");
         Arc::new(Mutex::new(mgr))
     } else if let Some(storage_path) = config.storage_path.clone() {
         log::info!("📁 Using storage_path as data_dir: {:?}", storage_path);
-        let mgr = DatastoreManager::open(&storage_path)?;
+        let mgr = open_datastore(&storage_path, config)?;
         log::info!("✓ DatastoreManager initialized with 6 stores");
         Arc::new(Mutex::new(mgr))
     } else {
@@ -59,6 +93,12 @@ pub async fn initialize_datastore(config: &Config) -> Result<Arc<Mutex<Datastore
         Arc::new(Mutex::new(mgr))
     };
     
+    // Until a network config says otherwise
+    datastore_manager
+        .lock()
+        .await
+        .set_era_schedule(EraSchedule::fixed(BLOCKS_PER_EPOCH, TARGET_BLOCK_TIME_SECS));
+    
     Ok(datastore_manager)
 }
 
@@ -67,9 +107,9 @@ pub async fn load_network_config(
     datastore_manager: &Arc<Mutex<DatastoreManager>>,
     network_config_path: PathBuf,
 ) -> Result<()> {
-    let network_config = if let Some(network_name) = network_config_path.to_string_lossy().strip_prefix("modal-networks://") {
+    let mut network_config = if let Some(network_name) = network_config_path.to_string_lossy().strip_prefix("modal-networks://") {
         log::info!("Loading network config from modal-networks: {}", network_name);
-        let network_info = modal_networks::networks::by_name(network_name)
+        let network_info = modal_networks::registry::find(network_name)?
             .ok_or_else(|| anyhow::anyhow!("Network '{}' not found in modal-networks", network_name))?;
         
         let mut config_json = serde_json::json!({
@@ -83,6 +123,26 @@ pub async fn load_network_config(
             config_json["validators"] = serde_json::json!(validators);
         }
         
+        if network_info.regtest {
+            config_json["regtest"] = serde_json::json!(true);
+        }
+        
+        if !network_info.archives.is_empty() {
+            config_json["archives"] = serde_json::json!(network_info.archives);
+        }
+        
+        if let Some(genesis) = network_info.genesis {
+            config_json["genesis"] = serde_json::to_value(genesis)?;
+        }
+        
+        if let Some(compatibility) = network_info.compatibility {
+            config_json["compatibility"] = serde_json::to_value(compatibility)?;
+        }
+        
+        if let Some(gossip) = network_info.gossip {
+            config_json["gossip"] = serde_json::to_value(gossip)?;
+        }
+        
         config_json["rounds"] = serde_json::json!({});
         
         log::debug!("Network config JSON: {}", serde_json::to_string_pretty(&config_json).unwrap_or_default());
@@ -93,12 +153,25 @@ pub async fn load_network_config(
         serde_json::from_str(&config_str)?
     };
     
+    let genesis = crate::genesis::genesis_spec(&network_config)?;
+    if let Some(genesis) = &genesis {
+        crate::genesis::apply_genesis_parameters(&mut network_config, genesis)?;
+    }
+    if let Some(compatibility) = crate::build_info::compatibility_spec(&network_config)? {
+        crate::build_info::check_local(&compatibility)?;
+    }
+    
     // Load network config into NodeState store
     {
         let mgr = datastore_manager.lock().await;
+        if let Some(genesis) = &genesis {
+            crate::genesis::validate_genesis(&mgr, genesis).await?;
+        }
         mgr.load_network_config(&network_config).await?;
     }
     
+    let mut era_schedule = EraSchedule::from_network_config(&network_config, BLOCKS_PER_EPOCH, TARGET_BLOCK_TIME_SECS)?;
+    
     // Load network parameters from genesis contract if present
     if let Some(genesis_contract_id) = network_config.get("genesis_contract_id").and_then(|v| v.as_str()) {
         log::info!("Loading network parameters from genesis contract: {}", genesis_contract_id);
@@ -116,6 +189,27 @@ pub async fn load_network_config(
                 if !params.validators.is_empty() {
                     mgr.set_static_validators(&params.validators).await?;
                 }
+                
+                let gas_quotas = params.gas_quotas();
+                if gas_quotas != GasQuotas::default() {
+                    log::info!("  Gas quotas: {:?} per commit, {:?} per epoch", gas_quotas.per_commit, gas_quotas.per_epoch);
+                }
+                mgr.store_gas_quotas(&gas_quotas).await?;
+                
+                if let Some(policy) = &params.slashing {
+                    log::info!("  Slashing: {:?} for a first offense, {:?} after", policy.first_offense, policy.repeat_offense);
+                    mgr.store_slashing_policy(policy)?;
+                }
+                
+                if !params.validator_bls_keys.is_empty() {
+                    log::info!("  Validator BLS keys: {}", params.validator_bls_keys.len());
+                    mgr.set_validator_bls_keys(&params.validator_bls_keys).await?;
+                }
+                
+                match params.era_schedule() {
+                    Ok(schedule) => era_schedule = schedule,
+                    Err(e) => log::warn!("Ignoring invalid epoch parameters from contract: {}", e),
+                }
             }
             Err(e) => {
                 log::warn!("Failed to load network parameters from contract: {}", e);
@@ -126,6 +220,36 @@ pub async fn load_network_config(
         log::info!("No genesis_contract_id found in network config");
     }
     
+    apply_era_schedule(datastore_manager, era_schedule).await
+}
+
+/// Switch to a new era schedule, refusing changes that would re-number
+/// epochs the local chain already contains
+pub async fn apply_era_schedule(
+    datastore_manager: &Arc<Mutex<DatastoreManager>>,
+    era_schedule: EraSchedule,
+) -> Result<()> {
+    let mut mgr = datastore_manager.lock().await;
+    
+    if let Some(stored) = mgr.get_stored_era_schedule().await? {
+        let tip_height = MinerBlock::find_all_canonical_multi(&mgr)
+            .await?
+            .iter()
+            .map(|b| b.index)
+            .max()
+            .unwrap_or(0);
+        stored
+            .check_transition(&era_schedule, tip_height)
+            .map_err(|e| anyhow::anyhow!("Invalid epoch parameter change: {}", e))?;
+    }
+    
+    if !era_schedule.eras.is_empty() {
+        log::info!("Epoch parameters change at heights: {:?}",
+            era_schedule.eras.iter().map(|e| e.activation_height).collect::<Vec<_>>());
+    }
+    
+    mgr.store_era_schedule(&era_schedule).await?;
+    mgr.set_era_schedule(era_schedule);
     Ok(())
 }
 
@@ -139,8 +263,11 @@ pub async fn get_inspection_data(
     
     // Network information
     if InspectionData::should_include_network(level) {
-        let swarm = node.swarm.lock().await;
-        let connected_peers: Vec<_> = swarm.connected_peers().cloned().collect();
+        let detailed = InspectionData::should_include_detailed_peers(level);
+        let (connected_peers, peer_slots) = node.swarm.run(move |swarm| {
+            let connected_peers: Vec<_> = swarm.connected_peers().cloned().collect();
+            (connected_peers, swarm.behaviour().peer_slots.info(detailed))
+        }).await?;
         let connected_peer_list = if InspectionData::should_include_detailed_peers(level) {
             Some(connected_peers.iter().map(|p| p.to_string()).collect())
         } else {
@@ -152,6 +279,7 @@ pub async fn get_inspection_data(
             connected_peers: connected_peers.len(),
             connected_peer_list,
             bootstrappers: node.bootstrappers.iter().map(|a| a.to_string()).collect(),
+            peer_slots: Some(peer_slots),
         });
     }
     
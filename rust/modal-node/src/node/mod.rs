@@ -3,12 +3,9 @@
 //! This module contains the Node struct and its implementation,
 //! with helper modules for specific functionality areas.
 
-mod helpers;
+pub(crate) mod helpers;
 
-use anyhow::Result;
-use futures::prelude::*;
-use libp2p::gossipsub::IdentTopic;
-use libp2p::request_response::OutboundRequestId;
+use anyhow::{Context as _, Result};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -28,12 +25,16 @@ use modal_common::multiaddr_list::resolve_dns_multiaddrs;
 
 use crate::config::Config;
 use crate::consensus::net_comm::NetComm;
+use crate::contract_sync;
 use crate::gossip;
 use crate::reqres;
 use crate::swarm;
+use crate::swarm_driver::{NodeSwarmEvent, SwarmHandle};
+use crate::boot_profile::{self, BootTimer};
 use crate::constants::{
     NETWORKING_TICK_INTERVAL_SECS, SHUTDOWN_WAIT_MS, CONNECTION_WAIT_INTERVAL_SECS,
-    PEER_IGNORE_INITIAL_SECS, PEER_IGNORE_MAX_EXPONENT,
+    PEER_IGNORE_INITIAL_SECS, PEER_IGNORE_MAX_EXPONENT, PEER_SCORE_PING_FAILED, PEER_SCORE_PING_OK,
+    REQRES_TIMEOUT_SECS,
 };
 
 pub use helpers::{extract_peer_id, exclude_multiaddresses_with_peerid};
@@ -49,36 +50,78 @@ pub struct IgnoredPeerInfo {
 pub struct Node {
     pub peerid: libp2p_identity::PeerId,
     pub node_keypair: libp2p_identity::Keypair,
+    /// Key this node signs certificate votes with as a validator
+    pub bls_keypair: Option<crate::actions::validator::bls::BlsKeypair>,
     pub listeners: Vec<Multiaddr>,
     pub bootstrappers: Vec<Multiaddr>,
-    pub swarm: Arc<Mutex<swarm::NodeSwarm>>,
+    pub swarm: SwarmHandle,
     pub datastore_manager: Arc<Mutex<DatastoreManager>>,
     pub miner_nominees: Option<Vec<String>>,
+    pub nomination_policy: crate::actions::miner::nomination::SharedNominationPolicy,
     pub hybrid_consensus: bool,
     pub run_validator: bool,
     pub network_name: String,
     pub role: String,
     pub ignored_peers: Arc<Mutex<HashMap<PeerId, IgnoredPeerInfo>>>,
+    pub ban_list: crate::ban_list::SharedBanList,
+    pub ban_list_path: Option<PathBuf>,
     pub sync_request_tx: Option<mpsc::UnboundedSender<(PeerId, String)>>,
     pub mining_update_tx: Option<mpsc::UnboundedSender<u64>>,
     pub epoch_transition_tx: tokio::sync::broadcast::Sender<u64>,
-    pub reqres_response_txs: Arc<Mutex<HashMap<OutboundRequestId, tokio::sync::oneshot::Sender<reqres::Response>>>>,
+    pub reorg_tx: tokio::sync::broadcast::Sender<modal_observer::ReorgEvent>,
+    pub reorg_webhook_url: Option<String>,
+    pub event_sinks: Vec<crate::event_sinks::EventSinkConfig>,
+    pub prune_keep_blocks: Option<u64>,
+    pub anomaly_detector: crate::anomaly_monitor::SharedAnomalyDetector,
+    pub reqres_dispatcher: Arc<reqres::dispatcher::RequestDispatcher>,
     pub minimum_block_timestamp: Option<i64>,
     pub fork_config: modal_observer::ForkConfig,
     pub initial_difficulty: Option<u128>,
     pub miner_hash_func: Option<String>,
     pub miner_hash_params: Option<serde_json::Value>,
     pub mining_delay_ms: Option<u64>,
+    /// Check the whole chain's integrity at startup, ignoring the checkpoint
+    pub full_integrity_check: bool,
+    pub networking_tick_ms: Option<u64>,
+    pub miner_threads: Option<usize>,
+    pub getwork_port: Option<u16>,
+    pub getwork_bind: Option<String>,
+    pub getwork_token: Option<String>,
     pub mining_metrics: crate::mining_metrics::SharedMiningMetrics,
+    /// Transactions waiting for this network's validator to batch them
+    pub mempool: crate::mempool::SharedMempool,
+    pub status_history: crate::status_history::SharedStatusHistory,
     pub mining_shutdown: Option<Arc<std::sync::atomic::AtomicBool>>,
     networking_task: Option<tokio::task::JoinHandle<Result<()>>>,
     autoupgrade_task: Option<tokio::task::JoinHandle<Result<()>>>,
     status_server_task: Option<tokio::task::JoinHandle<()>>,
+    explorer_task: Option<tokio::task::JoinHandle<()>>,
+    rpc_gateway_task: Option<tokio::task::JoinHandle<()>>,
+    indexer_task: Option<tokio::task::JoinHandle<()>>,
+    graphql_task: Option<tokio::task::JoinHandle<()>>,
     status_html_writer_task: Option<tokio::task::JoinHandle<()>>,
+    status_sampler_task: Option<tokio::task::JoinHandle<()>>,
+    datastore_flush_task: Option<tokio::task::JoinHandle<()>>,
     pub autoupgrade_config: Option<crate::autoupgrade::AutoupgradeConfig>,
+    pub autoupgrade_status: crate::autoupgrade::rollout::SharedAutoupgradeStatus,
     pub status_port: Option<u16>,
+    pub explorer_port: Option<u16>,
+    pub rpc_port: Option<u16>,
+    pub rpc_gateway: Option<modal_rpc::GatewayConfig>,
+    pub graphql_port: Option<u16>,
     pub status_html_dir: Option<PathBuf>,
     pub status_url: Option<String>,
+    /// Peers allowed to change this node's role
+    pub admin_peers: Vec<String>,
+    /// Roles to switch to as the chain reaches epochs
+    pub role_schedule: Vec<crate::actions::roles::RoleScheduleEntry>,
+    /// Role changes requested by admin peers, for the role runtime
+    pub role_tx: mpsc::UnboundedSender<crate::actions::roles::Role>,
+    role_rx: Option<mpsc::UnboundedReceiver<crate::actions::roles::Role>>,
+    /// Swarm events for `next_gossip_message`, taken over by the networking task
+    swarm_events: Option<mpsc::UnboundedReceiver<NodeSwarmEvent>>,
+    /// Timings of this node's startup phases
+    pub boot_timer: BootTimer,
     consensus_tx: mpsc::Sender<ConsensusMessage>,
     #[allow(dead_code)]
     consensus_rx: Option<mpsc::Receiver<ConsensusMessage>>,
@@ -95,10 +138,22 @@ impl Node {
 
     /// Create a node from a Config
     pub async fn from_config(config: Config) -> Result<Node> {
+        // A node joins one network; `multi_network::run` creates one per entry
+        if config.networks.is_some() {
+            anyhow::bail!("Config lists 'networks', which only `modal node run` supports, running a node per network");
+        }
+        let mut boot_timer = BootTimer::start();
         let node_keypair = config.get_libp2p_keypair().await?;
         let peerid = node_keypair.public().to_peer_id();
-        let autoupgrade_config = crate::autoupgrade::AutoupgradeConfig::from_node_config(&config);
+        let bls_keypair = crate::actions::validator::bls::load_keypair(config.bls_passfile_path.as_deref())?;
+        let autoupgrade_config = crate::autoupgrade::AutoupgradeConfig::from_node_config(&config, &peerid.to_string())?;
+        let autoupgrade_status = crate::autoupgrade::create_shared_status(autoupgrade_config.as_ref());
         let miner_nominees = config.miner_nominees.clone();
+        let nomination_policy = config
+            .miner_nomination_policy
+            .clone()
+            .unwrap_or_default()
+            .build(miner_nominees.clone())?;
         
         // Hybrid consensus should be ON by default for all nodes
         let hybrid_consensus = config.hybrid_consensus.unwrap_or(true);
@@ -113,66 +168,189 @@ impl Node {
         let network_name = config.get_network_name();
         let role = config.get_node_role();
         let status_port = config.status_port;
+        let explorer_port = config.explorer_port;
+        let rpc_port = config.rpc_port;
+        let rpc_gateway = config.rpc_gateway.clone();
+        let graphql_port = config.graphql_port;
         let status_html_dir = config.status_html_dir.clone();
         let status_url = config.status_url.clone();
+        let reorg_webhook_url = config.reorg_webhook_url.clone();
+        let event_sinks = config.event_sinks.clone().unwrap_or_default();
+        let prune_keep_blocks = config.prune_keep_blocks;
+        let anomaly_detector = crate::anomaly_monitor::create_shared_detector(config.get_anomaly_config());
         let minimum_block_timestamp = config.minimum_block_timestamp;
         let fork_config = config.get_fork_config();
         let initial_difficulty = config.get_initial_difficulty();
         let miner_hash_func = config.miner_hash_func.clone();
         let miner_hash_params = config.miner_hash_params.clone();
         let mining_delay_ms = config.mining_delay_ms;
+        let full_integrity_check = config.full_integrity_check.unwrap_or(false);
+        let networking_tick_ms = config.networking_tick_ms;
+        let miner_threads = config.miner_threads;
+        let getwork_port = config.getwork_port;
+        let getwork_bind = config.getwork_bind.clone();
+        let getwork_token = config.getwork_token.clone();
+        let ban_list_path = crate::ban_list::path_for(&config);
         let listeners = config.listeners.clone().unwrap_or_default();
-        let resolved_bootstrappers =
-            resolve_dns_multiaddrs(config.bootstrappers.clone().unwrap_or_default()).await?;
+        let proxy = config.outbound_proxy.as_deref().map(crate::proxy::ProxyConfig::parse).transpose()?;
+        crate::proxy::set_outbound(proxy.clone())?;
+        let resolved_bootstrappers = match proxy {
+            Some(_) => crate::proxy::resolve_bootstrappers(config.bootstrappers.clone().unwrap_or_default()).await?,
+            None => resolve_dns_multiaddrs(config.bootstrappers.clone().unwrap_or_default()).await?,
+        };
         let bootstrappers = exclude_multiaddresses_with_peerid(resolved_bootstrappers, peerid);
-        let swarm = swarm::create_swarm_with_metadata(node_keypair.clone(), status_url.clone(), Some(role.clone())).await?;
         
         // Initialize the DatastoreManager
+        let phase_started = Instant::now();
         let datastore_manager = helpers::initialize_datastore(&config).await?;
+        // Synced blocks are checked with the hash function this node mines with
+        datastore_manager.lock().await.set_mining_hash_func(miner_hash_func.clone());
         
         // Load network config if provided
         if let Some(network_config_path) = config.network_config_path {
             helpers::load_network_config(&datastore_manager, network_config_path).await?;
         }
+        boot_timer.record(boot_profile::PHASE_DATASTORE_OPEN, phase_started);
+        
+        let (genesis_hash, genesis_difficulty, pruned_below, topics) = {
+            let mgr = datastore_manager.lock().await;
+            let network_config = mgr.get_network_config().await?;
+            let (genesis, gossip) = match &network_config {
+                Some(network_config) => (
+                    crate::genesis::genesis_spec(network_config)?,
+                    gossip::topics::gossip_spec(network_config)?,
+                ),
+                None => (None, None),
+            };
+            // Topics are namespaced by the name in the network config, which every node shares
+            let name = network_config
+                .as_ref()
+                .and_then(|c| c.get("name"))
+                .and_then(|n| n.as_str())
+                .unwrap_or(&network_name);
+            let topics = gossip::topics::TopicRouter::new(name, gossip.as_ref(), config.gossip_shards.clone());
+            let pruned_below = modal_datastore::models::miner::PruneStatus::pruned_below_multi(&mgr).await?;
+            (crate::genesis::genesis_hash(&mgr).await, genesis.and_then(|g| g.initial_difficulty), pruned_below, topics)
+        };
+        // The network's genesis difficulty applies unless the node config sets one
+        let initial_difficulty = match (config.initial_difficulty, genesis_difficulty) {
+            (None, Some(difficulty)) => Some(difficulty),
+            _ => initial_difficulty,
+        };
+        let manifest = crate::manifest::local(
+            &node_keypair,
+            &role,
+            &network_name,
+            genesis_hash.clone(),
+            status_url.clone(),
+            prune_keep_blocks.is_none() && pruned_below.is_none(),
+            crate::bootstrapper_health::unix_now(),
+        )?;
+        let phase_started = Instant::now();
+        let mut swarm = if config.memory_transport.unwrap_or(false) {
+            swarm::create_memory_swarm_with_metadata(node_keypair.clone(), status_url.clone(), Some(role.clone()), genesis_hash, Some(&manifest)).await?
+        } else if let Some(proxy) = proxy {
+            log::info!("Dialing peers through {}", proxy);
+            swarm::create_proxied_swarm_with_metadata(node_keypair.clone(), proxy, status_url.clone(), Some(role.clone()), genesis_hash, Some(&manifest)).await?
+        } else {
+            swarm::create_swarm_with_metadata(node_keypair.clone(), status_url.clone(), Some(role.clone()), genesis_hash, Some(&manifest)).await?
+        };
+        boot_timer.record(boot_profile::PHASE_SWARM_CREATION, phase_started);
+        let ban_list = swarm.behaviour().ban_list.shared();
+        if let Some(path) = &ban_list_path {
+            *ban_list.write().unwrap() = crate::ban_list::BanList::load(path)?;
+        }
+        let validators = crate::peer_slots::current_validators(&*datastore_manager.lock().await).await?;
+        {
+            let peer_slots = &mut swarm.behaviour_mut().peer_slots;
+            peer_slots.set_limits(crate::peer_slots::SlotLimits::from_config(&config));
+            for peer_id in crate::peer_slots::peer_ids_of(&bootstrappers) {
+                peer_slots.reserve(peer_id);
+            }
+            for peer_id in config.reserved_peers.iter().flatten() {
+                peer_slots.reserve(peer_id.parse().with_context(|| format!("Invalid reserved peer {}", peer_id))?);
+            }
+            peer_slots.set_validators(validators);
+        }
         
         let (shutdown_tx, _) = tokio::sync::broadcast::channel(1);
+        let flush_interval = datastore_manager.lock().await.flush_interval();
+        let datastore_flush_task = flush_interval.map(|interval| {
+            helpers::start_datastore_flusher(datastore_manager.clone(), interval, shutdown_tx.subscribe())
+        });
         let (consensus_tx, consensus_rx) = mpsc::channel(100);
         let (sync_trigger_tx, _sync_trigger_rx) = tokio::sync::broadcast::channel(100);
         let (epoch_transition_tx, _) = tokio::sync::broadcast::channel(10);
+        let (reorg_tx, _) = tokio::sync::broadcast::channel(modal_observer::reorg::REORG_CHANNEL_CAPACITY);
+        let (role_tx, role_rx) = mpsc::unbounded_channel();
         
         let node = Self {
             peerid,
             node_keypair,
+            bls_keypair,
             listeners,
             bootstrappers,
-            swarm: Arc::new(Mutex::new(swarm)),
+            swarm: SwarmHandle::spawn(swarm, topics),
             datastore_manager,
             miner_nominees,
+            nomination_policy,
             hybrid_consensus,
             run_validator,
             network_name,
             role,
             ignored_peers: Arc::new(Mutex::new(HashMap::new())),
+            ban_list,
+            ban_list_path,
             sync_request_tx: None,
             mining_update_tx: None,
             epoch_transition_tx,
-            reqres_response_txs: Arc::new(Mutex::new(HashMap::new())),
+            reorg_tx,
+            reorg_webhook_url,
+            event_sinks,
+            prune_keep_blocks,
+            anomaly_detector,
+            reqres_dispatcher: Arc::new(reqres::dispatcher::Dispatcher::new(config.reqres_dispatch.clone().unwrap_or_default())),
             minimum_block_timestamp,
             fork_config,
             initial_difficulty,
             miner_hash_func,
             miner_hash_params,
             mining_delay_ms,
+            full_integrity_check,
+            networking_tick_ms,
+            miner_threads,
+            getwork_port,
+            getwork_bind,
+            getwork_token,
             mining_metrics: crate::mining_metrics::create_shared_metrics(),
+            mempool: crate::mempool::create_shared_mempool(),
+            status_history: crate::status_history::create_shared_history(),
             mining_shutdown: None,
             networking_task: None,
             autoupgrade_task: None,
             status_server_task: None,
+            explorer_task: None,
+            rpc_gateway_task: None,
+            indexer_task: None,
+            graphql_task: None,
             status_html_writer_task: None,
+            status_sampler_task: None,
+            datastore_flush_task,
             autoupgrade_config,
+            autoupgrade_status,
             status_port,
+            explorer_port,
+            rpc_port,
+            rpc_gateway,
+            graphql_port,
             status_html_dir,
             status_url,
+            admin_peers: config.admin_peers.clone().unwrap_or_default(),
+            role_schedule: config.role_schedule.clone().unwrap_or_default(),
+            role_tx,
+            role_rx: Some(role_rx),
+            swarm_events: None,
+            boot_timer,
             consensus_tx,
             consensus_rx: Some(consensus_rx),
             shutdown_tx,
@@ -193,21 +371,22 @@ impl Node {
 
     /// Set up the node - run bootup tasks and configure swarm
     pub async fn setup(&mut self, config: &Config) -> Result<()> {
+        let phase_started = Instant::now();
         self.run_bootup_tasks(config).await?;
+        self.boot_timer.record(boot_profile::PHASE_BOOTUP_TASKS, phase_started);
 
-        let mut swarm = self.swarm.lock().await;
-        for listener in self.listeners.clone() {
-            swarm.listen_on(listener.clone())?;
-            swarm.add_external_address(listener.clone());
-        }
+        let listeners = self.listeners.clone();
+        self.swarm.run(move |swarm| -> Result<()> {
+            for listener in listeners {
+                swarm.listen_on(listener.clone())?;
+                swarm.add_external_address(listener);
+            }
+            Ok(())
+        }).await??;
         for bootstrapper in self.bootstrappers.clone() {
             if let Some(peer_id) = extract_peer_id(bootstrapper.clone()) {
                 log::info!("Adding Bootstrap Peer: {peer_id:?} {bootstrapper:?}");
-                swarm.add_peer_address(peer_id, bootstrapper.clone());
-                swarm
-                    .behaviour_mut()
-                    .kademlia
-                    .add_address(&peer_id.clone(), bootstrapper.clone());
+                self.swarm.add_peer_address(peer_id, bootstrapper.clone()).await?;
             } else {
                 log::info!("skipping bootstrapper missing peerid: {bootstrapper:?}");
             }
@@ -215,26 +394,29 @@ impl Node {
         Ok(())
     }
 
+    /// Save the startup profile, once the node has finished starting up
+    pub async fn finish_boot(&self) {
+        let profile = self.boot_timer.profile();
+        log::info!("Node started in {} ms", profile.total_millis);
+        if let Err(e) = self.datastore_manager.lock().await.save_boot_profile(&profile) {
+            log::warn!("Failed to save boot profile: {}", e);
+        }
+    }
+
     /// Wait for peer connections
     pub async fn wait_for_connections(&mut self) -> Result<()> {
-        let count = self.swarm.lock().await.connected_peers().count();
+        let count = self.swarm.connected_peers().await?.len();
         loop {
             log::info!("connecting to peers...");
             log::info!("{}", count);
-            let count = self.swarm.lock().await.connected_peers().count();
+            let count = self.swarm.connected_peers().await?.len();
             tokio::time::sleep(Duration::from_secs(CONNECTION_WAIT_INTERVAL_SECS)).await;
-            for bootstrapper in self.bootstrappers.clone() {
+            let bootstrappers = crate::bootstrapper_health::ranked_bootstrappers(&self.datastore_manager, &self.bootstrappers).await;
+            for bootstrapper in bootstrappers {
                 log::info!("{}", bootstrapper);
                 if let Some(peer_id) = extract_peer_id(bootstrapper.clone()) {
-                    {
-                        let mut swarm = self.swarm.lock().await;
-                        swarm.add_peer_address(peer_id, bootstrapper.clone());
-                        swarm
-                            .behaviour_mut()
-                            .kademlia
-                            .add_address(&peer_id.clone(), bootstrapper.clone());
-                        swarm.dial(bootstrapper.clone())?;
-                    }
+                    self.swarm.add_peer_address(peer_id, bootstrapper.clone()).await?;
+                    self.swarm.dial(bootstrapper.clone()).await?;
                 }
             }
             if count > 0 {
@@ -249,13 +431,13 @@ impl Node {
         NetComm::new(self)
     }
 
-    /// Send a request without waiting for response
-    pub async fn send_request_only(
+    /// Send a request and wait for response
+    pub async fn send_request(
         &mut self,
         target_peer_id: PeerId,
         path: String,
         data: String,
-    ) -> Result<OutboundRequestId> {
+    ) -> Result<reqres::Response> {
         let data_value = if data.is_empty() {
             None
         } else {
@@ -265,105 +447,87 @@ impl Node {
         let request = reqres::Request {
             path: path.clone().to_string(),
             data: data_value,
+            accept_encoding: None,
         };
-        let req_id = {
-            let mut swarm = self.swarm.lock().await;
-            swarm
-                .behaviour_mut()
-                .reqres
-                .send_request(&target_peer_id, request)
-        };
-        Ok(req_id)
+        self.swarm.send_request(target_peer_id, request).await
     }
 
-    /// Send a request and wait for response
-    pub async fn send_request(
-        &mut self,
+    /// Send a request and wait for the response, giving up after
+    /// `REQRES_TIMEOUT_SECS`
+    pub async fn send_request_via_networking(
+        &self,
         target_peer_id: PeerId,
         path: String,
-        data: String,
+        data: Option<serde_json::Value>,
     ) -> Result<reqres::Response> {
-        let target_request_id = self.send_request_only(target_peer_id, path, data).await?;
-        let res: reqres::Response;
-        loop {
-            let mut swarm = self.swarm.lock().await;
-            futures::select!(
-                event = swarm.select_next_some() => if let SwarmEvent::Behaviour(swarm::NodeBehaviourEvent::Reqres(
-                        request_response::Event::Message {
-                            message: request_response::Message::Response { response, request_id, .. },
-                            ..
-                        }
-                    )) = event {
-                    if target_request_id == request_id {
-                        res = response.clone();
-                        break;
-                    }
-                }
-            )
-        }
-        Ok(res)
+        let request = reqres::Request { path, data, accept_encoding: None };
+        tokio::time::timeout(Duration::from_secs(REQRES_TIMEOUT_SECS), self.swarm.send_request(target_peer_id, request))
+            .await
+            .map_err(|_| anyhow::anyhow!("Request to {} timed out", target_peer_id))?
+    }
+
+    /// Send a contract sync request and wait for the response
+    pub async fn send_contract_sync_request(
+        &mut self,
+        target_peer_id: PeerId,
+        request: contract_sync::ContractSyncRequest,
+    ) -> Result<contract_sync::ContractSyncResponse> {
+        self.swarm.send_contract_sync_request(target_peer_id, request).await
     }
 
     /// Connect to a peer by multiaddr
     pub async fn connect_to_peer_multiaddr(&mut self, ma: Multiaddr) -> Result<()> {
-        let mut swarm = self.swarm.lock().await;
-        swarm.dial(ma.clone())?;
-
-        let Some(Protocol::P2p(target_peer_id)) = ma.iter().last() else {
-            anyhow::bail!("Provided address must end in `/p2p` and include PeerID");
-        };
-
-        loop {
-            match swarm.select_next_some().await {
-                SwarmEvent::ConnectionEstablished { peer_id, .. } => {
-                    if peer_id == target_peer_id {
-                        log::debug!("Connected to peer {:?}", peer_id);
-                        break;
-                    }
-                }
-                SwarmEvent::OutgoingConnectionError { peer_id, error, .. } => {
-                    if let Some(peer_id) = peer_id {
-                        log::error!("Failed to dial peer {:?}", peer_id);
-                        log::error!("Error: {:?}", error);
-                        anyhow::bail!("Failed to dial peer");
-                    }
-                }
-                event => {
-                    log::debug!("Other Event {:?}", event)
-                }
-            }
-        }
-
+        let peer_id = self.swarm.connect(ma).await.inspect_err(|e| log::error!("{}", e))?;
+        log::debug!("Connected to peer {:?}", peer_id);
         Ok(())
     }
 
     /// Disconnect from a peer
     pub async fn disconnect_from_peer_id(&mut self, target_peer_id: PeerId) -> Result<()> {
-        let mut swarm = self.swarm.lock().await;
-        let _ = swarm.disconnect_peer_id(target_peer_id);
-
-        loop {
-            if let SwarmEvent::ConnectionClosed { peer_id, .. } = swarm.select_next_some().await {
-                if peer_id == target_peer_id {
-                    log::debug!("Connection closed with peer {:?}", peer_id);
-                    break;
-                }
-            }
-        }
-
+        self.swarm.disconnect(target_peer_id).await?;
+        log::debug!("Connection closed with peer {:?}", target_peer_id);
         Ok(())
     }
 
     /// Publish a gossip message
     pub async fn publish_gossip(&mut self, topic: String, data: String) -> Result<()> {
-        let mut swarm = self.swarm.lock().await;
-        swarm
-            .behaviour_mut()
-            .gossipsub
-            .publish(IdentTopic::new(topic), data)?;
+        self.swarm.publish(&topic, data.into_bytes()).await?;
         Ok(())
     }
 
+    /// Subscribe to a gossip topic
+    pub async fn subscribe_gossip(&mut self, topic: &str) -> Result<()> {
+        self.swarm.subscribe(topic).await?;
+        Ok(())
+    }
+
+    /// Wait for the next gossip message on a topic, for nodes that don't run
+    /// the networking task
+    pub async fn next_gossip_message(&mut self, topic: &str) -> Result<gossipsub::Message> {
+        if self.swarm_events.is_none() {
+            self.swarm_events = Some(self.swarm.events().await?);
+        }
+        let events = self.swarm_events.as_mut().expect("swarm events were just taken");
+        loop {
+            match events.recv().await.ok_or_else(|| anyhow::anyhow!("Swarm driver stopped"))? {
+                SwarmEvent::Behaviour(swarm::NodeBehaviourEvent::Gossipsub(
+                    gossipsub::Event::Message { message, .. }
+                )) if self.swarm.topics().is(&message.topic, topic) => {
+                    return Ok(message);
+                }
+                SwarmEvent::ConnectionClosed { peer_id, num_established: 0, .. } => {
+                    log::debug!("Connection closed with peer {:?}", peer_id);
+                    if self.swarm.connected_peers().await?.is_empty() {
+                        anyhow::bail!("Disconnected from every peer");
+                    }
+                }
+                event => {
+                    log::debug!("Other Event {:?}", event)
+                }
+            }
+        }
+    }
+
     /// Get inspection data about this node
     pub async fn get_inspection_data(&self, level: crate::inspection::InspectionLevel) -> Result<crate::inspection::InspectionData> {
         helpers::get_inspection_data(self, level).await
@@ -371,19 +535,36 @@ impl Node {
 
     /// Shutdown the node
     pub async fn shutdown(&mut self) -> Result<()> {
-        let mut swarm = self.swarm.lock().await;
-        let ids: Vec<_> = swarm.connected_peers().cloned().collect();
-        for peer_id in ids {
-            swarm
-                .disconnect_peer_id(peer_id)
-                .map_err(|_| anyhow::anyhow!("Failed to disconnect from peer {}", peer_id))?;
-        }
+        self.swarm.disconnect_all().await?;
         tokio::time::sleep(Duration::from_millis(SHUTDOWN_WAIT_MS)).await;
         Ok(())
     }
 
+    /// Signal the networking and background tasks to stop, as Ctrl-C does
+    pub fn signal_shutdown(&self) {
+        let _ = self.shutdown_tx.send(());
+    }
+
+    /// Subscribe to the shutdown signal
+    pub fn subscribe_shutdown(&self) -> tokio::sync::broadcast::Receiver<()> {
+        self.shutdown_tx.subscribe()
+    }
+
+    /// Take the role changes requested by admin peers; only one task may follow them
+    pub fn take_role_requests(&mut self) -> Option<mpsc::UnboundedReceiver<crate::actions::roles::Role>> {
+        self.role_rx.take()
+    }
+
     /// Wait for shutdown signal and cleanup
     pub async fn wait_for_shutdown(&mut self) -> Result<()> {
+        self.listen_for_ctrl_c();
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+        shutdown_rx.recv().await?;
+        self.finish_shutdown().await
+    }
+
+    /// Signal shutdown on Ctrl-C, stopping the miner straight away
+    pub fn listen_for_ctrl_c(&self) {
         let shutdown_tx = self.shutdown_tx.clone();
         let mining_shutdown = self.mining_shutdown.clone();
     
@@ -399,9 +580,11 @@ impl Node {
             
             let _ = shutdown_tx.send(());
         });
-    
-        let mut shutdown_rx = self.shutdown_tx.subscribe();
-        shutdown_rx.recv().await?;
+    }
+
+    /// Wait for the networking and background tasks to stop after the
+    /// shutdown signal, then disconnect
+    pub async fn finish_shutdown(&mut self) -> Result<()> {
         log::info!("Shutdown signal received in wait_for_shutdown");
     
         if let Some(handle) = self.autoupgrade_task.take() {
@@ -421,6 +604,18 @@ impl Node {
             handle.await.ok();
             log::info!("Status HTML writer task shutdown complete");
         }
+
+        if let Some(handle) = self.status_sampler_task.take() {
+            handle.await.ok();
+        }
+
+        if let Some(handle) = self.indexer_task.take() {
+            handle.await.ok();
+        }
+
+        if let Some(handle) = self.datastore_flush_task.take() {
+            handle.await.ok();
+        }
     
         self.shutdown().await?;
         log::info!("Node shutdown complete");
@@ -440,13 +635,79 @@ impl Node {
                 self.mining_metrics.clone(),
                 self.network_name.clone(),
                 self.role.clone(),
+                self.status_history.clone(),
+                self.autoupgrade_status.clone(),
+                self.anomaly_detector.clone(),
+                self.reqres_dispatcher.clone(),
+                !self.bootstrappers.is_empty(),
             )
             .await?;
             self.status_server_task = Some(handle);
+            self.start_status_sampler();
         }
         Ok(())
     }
 
+    /// Start the chain explorer
+    pub async fn start_explorer(&mut self) -> Result<()> {
+        if let Some(port) = self.explorer_port {
+            let handle = crate::explorer::start_explorer(
+                port,
+                self.datastore_manager.clone(),
+                self.network_name.clone(),
+            )
+            .await?;
+            self.explorer_task = Some(handle);
+        }
+        Ok(())
+    }
+
+    /// Start the read-only RPC gateway
+    pub async fn start_rpc_gateway(&mut self) -> Result<()> {
+        let port = self.rpc_port.unwrap_or(modal_rpc::DEFAULT_PORT);
+        let handle = crate::rpc_gateway::start_rpc_gateway(
+            port,
+            self.rpc_gateway.clone().unwrap_or_default(),
+            self.datastore_manager.clone(),
+        )
+        .await?;
+        self.rpc_gateway_task = Some(handle);
+        Ok(())
+    }
+
+    /// Start indexing the contracts this node stores
+    pub async fn start_indexer(&mut self) -> Result<()> {
+        {
+            let mgr = self.datastore_manager.lock().await;
+            mgr.index().enable()?;
+            let indexed = crate::indexer::index_new_commits(&mgr).await?;
+            log::info!("Indexer caught up: {} commits indexed", indexed);
+        }
+        let handle = crate::indexer::start_indexer(
+            self.datastore_manager.clone(),
+            std::time::Duration::from_secs(crate::constants::INDEXER_INTERVAL_SECS),
+            self.shutdown_tx.subscribe(),
+        );
+        self.indexer_task = Some(handle);
+        Ok(())
+    }
+
+    /// Start the GraphQL endpoint, if a port is configured and the node
+    /// was built with the `graphql` feature
+    pub async fn start_graphql(&mut self) -> Result<()> {
+        let Some(port) = self.graphql_port else {
+            return Ok(());
+        };
+        #[cfg(feature = "graphql")]
+        {
+            let handle = crate::graphql::start_graphql(port, self.datastore_manager.clone()).await?;
+            self.graphql_task = Some(handle);
+        }
+        #[cfg(not(feature = "graphql"))]
+        log::warn!("graphql_port {} is set, but this node was built without the graphql feature", port);
+        Ok(())
+    }
+
     /// Start the status HTML writer
     pub async fn start_status_html_writer(&mut self) -> Result<()> {
         if let Some(ref dir) = self.status_html_dir {
@@ -460,21 +721,46 @@ impl Node {
                 self.mining_metrics.clone(),
                 self.network_name.clone(),
                 self.role.clone(),
+                self.autoupgrade_status.clone(),
+                self.anomaly_detector.clone(),
                 self.shutdown_tx.subscribe(),
             )
             .await?;
             self.status_html_writer_task = Some(handle);
+            self.start_status_sampler();
         }
         Ok(())
     }
 
+    /// Start recording status history samples (once, shared by the server and HTML writer)
+    fn start_status_sampler(&mut self) {
+        if self.status_sampler_task.is_some() {
+            return;
+        }
+        let handle = crate::status_server::start_status_sampler(
+            self.peerid,
+            self.datastore_manager.clone(),
+            self.swarm.clone(),
+            self.listeners.clone(),
+            self.mining_metrics.clone(),
+            self.network_name.clone(),
+            self.role.clone(),
+            self.status_history.clone(),
+            self.shutdown_tx.subscribe(),
+        );
+        self.status_sampler_task = Some(handle);
+    }
+
     /// Start the networking task
     pub async fn start_networking(&mut self) -> Result<()> {
         let mut shutdown_rx = self.shutdown_tx.subscribe();
         let swarm = self.swarm.clone();
         let peerid = self.peerid;
 
-        let tick_interval = Duration::from_secs(NETWORKING_TICK_INTERVAL_SECS);
+        let tick_interval = self
+            .networking_tick_ms
+            .map(Duration::from_millis)
+            .unwrap_or(Duration::from_secs(NETWORKING_TICK_INTERVAL_SECS));
         let mut tick = futures_timer::Delay::new(tick_interval);
 
         let datastore_manager = self.datastore_manager.clone();
@@ -482,24 +768,62 @@ impl Node {
         let sync_request_tx = self.sync_request_tx.clone();
         let mining_update_tx = self.mining_update_tx.clone();
         let bootstrappers = self.bootstrappers.clone();
-        let reqres_response_txs = self.reqres_response_txs.clone();
+        let reqres_dispatcher = self.reqres_dispatcher.clone();
+        let admin_peers = self.admin_peers.clone();
+        let role_tx = self.role_tx.clone();
+        let (handled_tx, mut handled_rx) = mpsc::unbounded_channel();
+        reqres::dispatcher::start_dispatcher(
+            self.reqres_dispatcher.clone(),
+            self.datastore_manager.clone(),
+            self.mempool.clone(),
+            self.consensus_tx.clone(),
+            handled_tx,
+            self.shutdown_tx.subscribe(),
+        );
         let minimum_block_timestamp = self.minimum_block_timestamp;
+        let reorg_tx = self.reorg_tx.clone();
+        let ban_list_path = self.ban_list_path.clone();
+        crate::ban_list::start_ban_list_reload(self.swarm.clone(), self.ban_list_path.clone(), self.shutdown_tx.subscribe());
+
+        if let Some(url) = self.reorg_webhook_url.clone() {
+            log::info!("Posting reorg events to {}", url);
+            crate::reorg_webhook::start_reorg_webhook(url, self.reorg_tx.subscribe(), self.shutdown_tx.subscribe());
+        }
+        crate::anomaly_monitor::start_anomaly_monitor(
+            self.datastore_manager.clone(),
+            self.anomaly_detector.clone(),
+            self.shutdown_tx.subscribe(),
+        );
+        crate::event_journal::start_event_journal(self.datastore_manager.clone(), self.shutdown_tx.subscribe());
+        crate::accounting::start_epoch_accounting(self.datastore_manager.clone(), self.shutdown_tx.subscribe());
+        if !self.event_sinks.is_empty() {
+            log::info!("Delivering journal events to {} event sink(s)", self.event_sinks.len());
+            crate::event_sinks::start_event_sinks(self.datastore_manager.clone(), self.event_sinks.clone(), &self.shutdown_tx);
+        }
+        crate::governance::start_governance(self.datastore_manager.clone(), self.shutdown_tx.subscribe());
+        if !self.bootstrappers.is_empty() {
+            crate::bootstrapper_health::start_bootstrapper_health_monitor(
+                self.datastore_manager.clone(),
+                self.swarm.clone(),
+                self.bootstrappers.clone(),
+                self.shutdown_tx.subscribe(),
+            );
+        }
+
+        // Events go here from now on, rather than to `next_gossip_message`
+        self.swarm_events = None;
+        let mut events = self.swarm.events().await?;
 
         self.networking_task = Some(tokio::spawn(async move {
             loop {
-                let mut swarm_lock = swarm.lock().await;
                 tokio::select! {
                     _ = shutdown_rx.recv() => {
                         log::info!("Networking task shutting down");
-                        let ids: Vec<_> = swarm_lock.connected_peers().cloned().collect();
-                        for peer_id in ids {
-                            swarm_lock.disconnect_peer_id(peer_id)
-                                .map_err(|_| anyhow::anyhow!("Failed to disconnect from peer {}", peer_id))?;
-                        }
+                        swarm.disconnect_all().await?;
                         tokio::time::sleep(Duration::from_millis(SHUTDOWN_WAIT_MS)).await;
                         break;
                     }
-                    event = swarm_lock.select_next_some() => {
+                    Some(event) = events.recv() => {
                         log::info!("{:?}", event);
                         match event {
                             SwarmEvent::NewListenAddr { address, .. } => {
@@ -518,75 +842,232 @@ impl Node {
                                 }
                             }
                             SwarmEvent::Behaviour(swarm::NodeBehaviourEvent::Reqres(
-                                request_response::Event::Message { message, .. },
-                            )) => match message {
-                                request_response::Message::Request {
-                                    request,
-                                    channel,
+                                request_response::Event::Message {
+                                    peer,
+                                    message: request_response::Message::Request { request, channel, .. },
                                     ..
-                                } => {
-                                    log::info!("reqres request");
-                                    let res = {
-                                        let mgr = datastore_manager.lock().await;
-                                        reqres::handle_request(request, &mgr, consensus_tx.clone()).await?
-                                    };
-                                    swarm_lock.behaviour_mut().reqres.send_response(channel, res)
-                                        .expect("failed to respond")
+                                },
+                            )) => {
+                                log::info!("reqres request");
+                                // Admin requests depend on who's asking; the rest are handled
+                                // by the dispatcher and the response comes back below
+                                if request.path == reqres::node::role::PATH {
+                                    let response = reqres::node::role::handler(&peer, request.data, &admin_peers, &role_tx)
+                                        .unwrap_or_else(|e| reqres::Response::error(e.to_string()));
+                                    swarm.respond(channel, response)?;
+                                } else if let Err((channel, busy)) = reqres_dispatcher.submit(request, channel) {
+                                    log::warn!("Turning away reqres request: {:?}", busy.errors);
+                                    swarm.respond(channel, busy)?;
                                 }
-                                request_response::Message::Response { request_id, response } => {
-                                    log::debug!("reqres response received for request {:?}", request_id);
-                                    let mut txs = reqres_response_txs.lock().await;
-                                    if let Some(tx) = txs.remove(&request_id) {
-                                        log::debug!("Forwarding response to caller");
-                                        let _ = tx.send(response);
-                                    } else {
-                                        log::warn!("Received response for unknown request {:?}", request_id);
+                            }
+                            SwarmEvent::Behaviour(swarm::NodeBehaviourEvent::ContractSync(
+                                request_response::Event::Message {
+                                    message: request_response::Message::Request { request, channel, .. },
+                                    ..
+                                },
+                            )) => {
+                                let contract_id = match &request {
+                                    contract_sync::ContractSyncRequest::PutChunk { contract_id, .. } => Some(contract_id.clone()),
+                                    _ => None,
+                                };
+                                let (res, announcements) = {
+                                    let mgr = datastore_manager.lock().await;
+                                    let res = contract_sync::handle_request(request, &mgr).await;
+                                    let announcements = match (&contract_id, &res) {
+                                        (Some(contract_id), contract_sync::ContractSyncResponse::Stored { commit_id, complete: true, .. }) => {
+                                            gossip::contract::commits::announcements(&mgr, contract_id, std::slice::from_ref(commit_id)).await.unwrap_or_default()
+                                        }
+                                        _ => Vec::new(),
+                                    };
+                                    (res, announcements)
+                                };
+                                gossip::contract::commits::publish(&swarm, &announcements).await;
+                                swarm.respond_contract_sync(channel, res)?;
+                            }
+                            SwarmEvent::Behaviour(swarm::NodeBehaviourEvent::Gossipsub(
+                                gossipsub::Event::Message { propagation_source, message, .. },
+                            )) if swarm.topics().is(&message.topic, gossip::contract::commits::TOPIC) => {
+                                let wanted = {
+                                    let mgr = datastore_manager.lock().await;
+                                    gossip::contract::commits::handler(&message.data, &mgr).await
+                                };
+                                let announcement = match wanted {
+                                    Ok(Some(announcement)) => announcement,
+                                    Ok(None) => continue,
+                                    Err(e) => {
+                                        log::warn!("Bad commit announcement: {}", e);
+                                        if message.source == Some(propagation_source) {
+                                            crate::ban_list::strike_peer(&swarm, ban_list_path.as_deref(), &propagation_source, "bad commit announcement").await;
+                                        }
+                                        continue;
                                     }
-                                }
+                                };
+                                // Ask the announcer, or whoever relayed it if we aren't connected to them
+                                let source_connected = match message.source {
+                                    Some(source) => swarm.is_connected(source).await?,
+                                    None => false,
+                                };
+                                let peer = message.source
+                                    .filter(|_| source_connected)
+                                    .unwrap_or(propagation_source);
+                                let swarm = swarm.clone();
+                                let datastore_manager = datastore_manager.clone();
+                                tokio::spawn(async move {
+                                    let request = gossip::contract::commits::pull_request(&announcement);
+                                    let Ok(response) = swarm.send_request(peer, request).await else {
+                                        return;
+                                    };
+                                    let mgr = datastore_manager.lock().await;
+                                    match gossip::contract::commits::save_pulled(&mgr, &announcement.contract_id, &response).await {
+                                        Ok(saved) => log::info!("Fetched {} commit(s) of contract {} from {}", saved.len(), announcement.contract_id, peer),
+                                        Err(e) => log::warn!("Could not fetch commit {}: {}", announcement.commit_id, e),
+                                    }
+                                });
                             }
                             SwarmEvent::Behaviour(swarm::NodeBehaviourEvent::Gossipsub(
                                 gossipsub::Event::Message {
-                                    propagation_source: _peer_id,
+                                    propagation_source,
                                     message_id: _message_id,
                                     message,
                                 },
                             )) => {
                                 log::info!("Gossip received {:?}", message.topic.to_string());
-                                gossip::handle_event(message, datastore_manager.clone(), consensus_tx.clone(), sync_request_tx.clone(), mining_update_tx.clone(), bootstrappers.clone(), minimum_block_timestamp).await?;
+                                let Some(topic) = swarm.topics().topic_of(message.topic.as_str()) else {
+                                    log::debug!("Ignoring gossip on {}, which isn't this network's", message.topic);
+                                    continue;
+                                };
+                                // Only the author is to blame; relays forward before anyone validates
+                                let from_author = message.source == Some(propagation_source);
+                                if let Err(e) = gossip::handle_event(&topic, message, datastore_manager.clone(), consensus_tx.clone(), sync_request_tx.clone(), mining_update_tx.clone(), bootstrappers.clone(), minimum_block_timestamp, reorg_tx.clone()).await {
+                                    log::warn!("Invalid gossip from {}: {}", propagation_source, e);
+                                    if from_author {
+                                        crate::ban_list::strike_peer(&swarm, ban_list_path.as_deref(), &propagation_source, &e.to_string()).await;
+                                    }
+                                }
                             }
                             SwarmEvent::Behaviour(swarm::NodeBehaviourEvent::Identify(
                                 libp2p::identify::Event::Received { peer_id, info, .. }
                             )) => {
                                 log::debug!("Identify received from {:?}: agent_version={}", peer_id, info.agent_version);
                                 
-                                // Extract status_url and role from agent version string
-                                // Format: "modal-node/version;status_url=https://...;role=Miner"
-                                let parts: Vec<&str> = info.agent_version.split(';').collect();
-                                let status_url = parts.iter()
-                                    .find(|s| s.starts_with("status_url="))
-                                    .and_then(|s| s.strip_prefix("status_url="))
-                                    .map(|s| s.to_string());
-                                let role = parts.iter()
-                                    .find(|s| s.starts_with("role="))
-                                    .and_then(|s| s.strip_prefix("role="))
-                                    .map(|s| s.to_string());
+                                // Peers on another genesis are on another network
+                                if let Some(theirs) = crate::genesis::advertised_genesis(&info.agent_version) {
+                                    let ours = {
+                                        let mgr = datastore_manager.lock().await;
+                                        crate::genesis::genesis_hash(&mgr).await
+                                    };
+                                    if ours.as_deref().is_some_and(|ours| ours != theirs) {
+                                        log::warn!("Disconnecting peer {}: genesis {} doesn't match ours ({:?})", peer_id, theirs, ours);
+                                        swarm.run(move |swarm| { let _ = swarm.disconnect_peer_id(peer_id); }).await?;
+                                        continue;
+                                    }
+                                }
+                                
+                                // Peers missing what the network requires can't take part in it
+                                let compatibility = {
+                                    let mgr = datastore_manager.lock().await;
+                                    let network_config = mgr.get_network_config().await.ok().flatten();
+                                    network_config.and_then(|config| crate::build_info::compatibility_spec(&config).ok().flatten())
+                                };
+                                match crate::build_info::check_peer(&info.agent_version, compatibility.as_ref()) {
+                                    crate::build_info::Compatibility::Compatible => {}
+                                    crate::build_info::Compatibility::Degraded(reason) => {
+                                        log::warn!("Peer {} runs an older build: {}", peer_id, reason);
+                                    }
+                                    crate::build_info::Compatibility::Incompatible(reason) => {
+                                        log::warn!("Disconnecting peer {}: incompatible protocol, {}", peer_id, reason);
+                                        swarm.run(move |swarm| { let _ = swarm.disconnect_peer_id(peer_id); }).await?;
+                                        continue;
+                                    }
+                                }
+                                crate::gossip::wire::record_peer(peer_id, &info.agent_version);
+                                
+                                // Ask peers that speak a message protocol for their capabilities
+                                if let Some(version) = crate::capabilities::advertised_version(&info.agent_version) {
+                                    let now = crate::bootstrapper_health::unix_now();
+                                    if crate::capabilities::needs_handshake(&peer_id, version, now) {
+                                        let swarm = swarm.clone();
+                                        let datastore_manager = datastore_manager.clone();
+                                        tokio::spawn(async move {
+                                            let Ok(response) = swarm.send_request(peer_id, crate::capabilities::request()).await else {
+                                                return;
+                                            };
+                                            match crate::capabilities::record(peer_id, &response, crate::bootstrapper_health::unix_now()) {
+                                                Ok(capabilities) => {
+                                                    log::info!("Peer {} speaks protocol {} ({:?})", peer_id, capabilities.protocol_version, capabilities.sync_modes);
+                                                    let mgr = datastore_manager.lock().await;
+                                                    if let Err(e) = crate::capabilities::save(&mgr, &peer_id, capabilities).await {
+                                                        log::warn!("Failed to store peer capabilities: {}", e);
+                                                    }
+                                                }
+                                                Err(e) => log::warn!("Bad capabilities from peer {}: {}", peer_id, e),
+                                            }
+                                        });
+                                    }
+                                }
+                                
+                                // A signed manifest is believed over the loose agent string parts
+                                let manifest = match crate::manifest::verified(&info.agent_version, &peer_id, crate::bootstrapper_health::unix_now()) {
+                                    Ok(manifest) => manifest,
+                                    Err(e) => {
+                                        log::warn!("Ignoring metadata of peer {}: {}", peer_id, e);
+                                        crate::ban_list::strike_peer(&swarm, ban_list_path.as_deref(), &peer_id, &e.to_string()).await;
+                                        continue;
+                                    }
+                                };
+                                let (status_url, role) = match &manifest {
+                                    Some(manifest) => (manifest.status_url.clone(), Some(manifest.role.clone())),
+                                    None => {
+                                        // Extract status_url and role from agent version string
+                                        // Format: "modal-node/version;status_url=https://...;role=Miner"
+                                        let parts: Vec<&str> = info.agent_version.split(';').collect();
+                                        let status_url = parts.iter()
+                                            .find(|s| s.starts_with("status_url="))
+                                            .and_then(|s| s.strip_prefix("status_url="))
+                                            .map(|s| s.to_string());
+                                        let role = parts.iter()
+                                            .find(|s| s.starts_with("role="))
+                                            .and_then(|s| s.strip_prefix("role="))
+                                            .map(|s| s.to_string());
+                                        (status_url, role)
+                                    }
+                                };
+                                
+                                // Validators of the current set keep reserved slots, whatever role a peer claims
+                                let validators = crate::peer_slots::current_validators(&*datastore_manager.lock().await).await;
+                                match validators {
+                                    Ok(validators) => {
+                                        swarm.run(move |swarm| swarm.behaviour_mut().peer_slots.set_validators(validators)).await?;
+                                    }
+                                    Err(e) => log::warn!("Failed to load the current validator set: {}", e),
+                                }
                                 
                                 // Store peer info with status_url and role if either exists
                                 if status_url.is_some() || role.is_some() {
-                                    log::info!("Peer {} - status_url: {:?}, role: {:?}", peer_id, status_url, role);
-                                    let peer_info = modal_datastore::models::PeerInfo::with_metadata(
-                                        peer_id.to_string(),
-                                        status_url,
-                                        role
-                                    );
+                                    log::info!("Peer {} - status_url: {:?}, role: {:?}, verified: {}", peer_id, status_url, role, manifest.is_some());
                                     
-                                    // Store in NodeState
+                                    // Store in NodeState, keeping capabilities from an earlier handshake
                                     let mgr = datastore_manager.lock().await;
+                                    let mut peer_info = modal_datastore::models::PeerInfo::find_one(&mgr, &peer_id.to_string())
+                                        .await
+                                        .ok()
+                                        .flatten()
+                                        .unwrap_or_else(|| modal_datastore::models::PeerInfo::new(peer_id.to_string()));
+                                    peer_info.status_url = status_url;
+                                    peer_info.role = role;
+                                    peer_info.manifest = manifest;
                                     if let Err(e) = peer_info.save_to(mgr.node_state()).await {
                                         log::warn!("Failed to store peer info: {}", e);
                                     }
                                 }
                             }
+                            SwarmEvent::Behaviour(swarm::NodeBehaviourEvent::Ping(libp2p::ping::Event { peer, result, .. })) => {
+                                let delta = match result {
+                                    Ok(_) => PEER_SCORE_PING_OK,
+                                    Err(_) => -PEER_SCORE_PING_FAILED,
+                                };
+                                swarm.run(move |swarm| swarm.behaviour_mut().peer_slots.adjust_score(&peer, delta)).await?;
+                            }
                             SwarmEvent::Behaviour(event) => {
                                 log::info!("SwarmEvent::Behaviour event {:?}", event);
                             }
@@ -595,6 +1076,10 @@ impl Node {
                             }
                         }
                     }
+                    Some(handled) = handled_rx.recv() => {
+                        gossip::contract::commits::publish(&swarm, &handled.announcements).await;
+                        swarm.respond(handled.reply, handled.response)?;
+                    }
                     _ = &mut tick => {
                         log::debug!("tick");
                         tick = futures_timer::Delay::new(tick_interval);
@@ -620,9 +1105,10 @@ impl Node {
         }
 
         let shutdown_rx = self.shutdown_tx.subscribe();
+        let status = self.autoupgrade_status.clone();
         
         self.autoupgrade_task = Some(tokio::spawn(async move {
-            crate::autoupgrade::start_autoupgrade_task(config, shutdown_rx).await
+            crate::autoupgrade::start_autoupgrade_task(config, status, shutdown_rx).await
         }));
 
         log::info!("Autoupgrade task started");
@@ -0,0 +1,404 @@
+//! Connection limits and peer slots
+//!
+//! A node keeps at most `max_inbound_peers` peers that dialed it and
+//! `max_outbound_peers` that it dialed. `reserved_peer_slots` of each are
+//! held back for reserved peers: bootstrappers, `reserved_peers` from the
+//! config and the validators of the current set. A peer's own manifest
+//! doesn't earn it a slot, since any peer can sign one. A reserved peer is always let in, evicting the lowest-scoring
+//! unreserved peer if that direction is full. Other peers get in while an
+//! unreserved slot is free, or by evicting an unreserved peer that has
+//! scored below a newcomer. Peers gain score for answered pings and lose it
+//! for failed pings and invalid messages.
+
+use anyhow::anyhow;
+use libp2p::core::transport::PortUse;
+use libp2p::core::Endpoint;
+use libp2p::multiaddr::Protocol;
+use libp2p::swarm::{
+    dummy, CloseConnection, ConnectionDenied, ConnectionId, FromSwarm, NetworkBehaviour, THandler,
+    THandlerInEvent, THandlerOutEvent, ToSwarm,
+};
+use libp2p::{Multiaddr, PeerId};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::convert::Infallible;
+use std::task::{Context, Poll, Waker};
+
+use crate::constants::{
+    DEFAULT_MAX_INBOUND_PEERS, DEFAULT_MAX_OUTBOUND_PEERS, DEFAULT_RESERVED_PEER_SLOTS, PEER_SCORE_MAX, PEER_SCORE_MIN,
+};
+use crate::inspection::PeerSlotsInfo;
+use modal_datastore::models::validator::get_validator_set_for_mining_epoch_hybrid_multi;
+use modal_datastore::models::MinerBlock;
+use modal_datastore::DatastoreManager;
+
+/// How many peers a node keeps in each direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotLimits {
+    pub max_inbound: usize,
+    pub max_outbound: usize,
+    /// Slots of each direction only reserved peers may take
+    pub reserved_slots: usize,
+}
+
+impl Default for SlotLimits {
+    fn default() -> Self {
+        Self {
+            max_inbound: DEFAULT_MAX_INBOUND_PEERS,
+            max_outbound: DEFAULT_MAX_OUTBOUND_PEERS,
+            reserved_slots: DEFAULT_RESERVED_PEER_SLOTS,
+        }
+    }
+}
+
+impl SlotLimits {
+    pub fn from_config(config: &crate::config::Config) -> Self {
+        let defaults = Self::default();
+        Self {
+            max_inbound: config.max_inbound_peers.unwrap_or(defaults.max_inbound),
+            max_outbound: config.max_outbound_peers.unwrap_or(defaults.max_outbound),
+            reserved_slots: config.reserved_peer_slots.unwrap_or(defaults.reserved_slots),
+        }
+    }
+
+    fn max(&self, direction: Direction) -> usize {
+        match direction {
+            Direction::Inbound => self.max_inbound,
+            Direction::Outbound => self.max_outbound,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Inbound,
+    Outbound,
+}
+
+impl std::fmt::Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Direction::Inbound => write!(f, "inbound"),
+            Direction::Outbound => write!(f, "outbound"),
+        }
+    }
+}
+
+/// The peer IDs in `/p2p/` addresses, for reserving bootstrappers
+pub fn peer_ids_of(addrs: &[Multiaddr]) -> Vec<PeerId> {
+    addrs
+        .iter()
+        .filter_map(|addr| match addr.iter().last() {
+            Some(Protocol::P2p(peer_id)) => Some(peer_id),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Peer IDs of the validators seated for the current mining epoch: the
+/// static validators when configured, the hybrid set otherwise
+pub async fn current_validators(mgr: &DatastoreManager) -> anyhow::Result<HashSet<PeerId>> {
+    let validators = match mgr.get_static_validators().await? {
+        Some(validators) => validators,
+        None => {
+            let tip = MinerBlock::find_all_canonical_multi(mgr).await?.iter().map(|b| b.index).max().unwrap_or(0);
+            get_validator_set_for_mining_epoch_hybrid_multi(mgr, mgr.block_index_to_epoch(tip))
+                .await?
+                .map(|set| set.get_active_validators())
+                .unwrap_or_default()
+        }
+    };
+    Ok(validators.iter().filter_map(|peer_id| peer_id.parse().ok()).collect())
+}
+
+/// Swarm behaviour that enforces the slot limits
+#[derive(Default)]
+pub struct Behaviour {
+    limits: SlotLimits,
+    reserved: HashSet<PeerId>,
+    /// Validators of the current set, reserved while they stay in it
+    validators: HashSet<PeerId>,
+    /// Scores of connected peers, and of disconnected ones that scored
+    /// below zero so they can't reset it by reconnecting
+    scores: HashMap<PeerId, i64>,
+    connections: HashMap<ConnectionId, (PeerId, Direction)>,
+    /// Peers being disconnected to make room, no longer taking a slot
+    evicting: HashSet<PeerId>,
+    evicted: u64,
+    closing: VecDeque<PeerId>,
+    waker: Option<Waker>,
+}
+
+impl Behaviour {
+    pub fn set_limits(&mut self, limits: SlotLimits) {
+        self.limits = limits;
+    }
+
+    /// Always let `peer_id` in, evicting others to make room if needed
+    pub fn reserve(&mut self, peer_id: PeerId) {
+        self.reserved.insert(peer_id);
+    }
+
+    /// Reserve the validators of the current set in place of the last set's
+    pub fn set_validators(&mut self, validators: HashSet<PeerId>) {
+        self.validators = validators;
+    }
+
+    pub fn is_reserved(&self, peer_id: &PeerId) -> bool {
+        self.reserved.contains(peer_id) || self.validators.contains(peer_id)
+    }
+
+    pub fn score(&self, peer_id: &PeerId) -> i64 {
+        self.scores.get(peer_id).copied().unwrap_or(0)
+    }
+
+    /// Add `delta` to a peer's score, keeping it within
+    /// `PEER_SCORE_MIN..=PEER_SCORE_MAX`
+    pub fn adjust_score(&mut self, peer_id: &PeerId, delta: i64) {
+        let score = self.scores.entry(*peer_id).or_default();
+        *score = (*score + delta).clamp(PEER_SCORE_MIN, PEER_SCORE_MAX);
+    }
+
+    /// Slot usage for inspection, with per-peer scores if `detailed`
+    pub fn info(&self, detailed: bool) -> PeerSlotsInfo {
+        let inbound = self.peers(Direction::Inbound);
+        let outbound = self.peers(Direction::Outbound);
+        let reserved_connected = inbound.iter().chain(&outbound).filter(|peer| self.is_reserved(peer)).count();
+        let scores = detailed.then(|| {
+            inbound
+                .iter()
+                .chain(&outbound)
+                .map(|peer| (peer.to_string(), self.score(peer)))
+                .collect::<BTreeMap<_, _>>()
+        });
+        PeerSlotsInfo {
+            inbound: inbound.len(),
+            outbound: outbound.len(),
+            max_inbound: self.limits.max_inbound,
+            max_outbound: self.limits.max_outbound,
+            reserved_slots: self.limits.reserved_slots,
+            reserved_connected,
+            evicted: self.evicted,
+            scores,
+        }
+    }
+
+    /// Peers connected in `direction` that still hold a slot
+    fn peers(&self, direction: Direction) -> HashSet<PeerId> {
+        self.connections
+            .values()
+            .filter(|(peer, dir)| *dir == direction && !self.evicting.contains(peer))
+            .map(|(peer, _)| *peer)
+            .collect()
+    }
+
+    /// The lowest-scoring unreserved peer in `direction` scoring under `below`
+    fn lowest_scoring(&self, direction: Direction, below: i64) -> Option<PeerId> {
+        self.peers(direction)
+            .into_iter()
+            .filter(|peer| !self.is_reserved(peer) && self.score(peer) < below)
+            .min_by_key(|peer| self.score(peer))
+    }
+
+    fn evict(&mut self, peer_id: PeerId, direction: Direction) {
+        log::info!("Evicting {} peer {} (score {}) to free a slot", direction, peer_id, self.score(&peer_id));
+        self.evicting.insert(peer_id);
+        self.closing.push_back(peer_id);
+        self.evicted += 1;
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn admit(&mut self, peer_id: PeerId, direction: Direction) -> Result<(), ConnectionDenied> {
+        // Another connection to a peer that already has a slot
+        if self.connections.values().any(|(peer, _)| *peer == peer_id) && !self.evicting.contains(&peer_id) {
+            return Ok(());
+        }
+        let max = self.limits.max(direction);
+        let peers = self.peers(direction);
+        if self.is_reserved(&peer_id) {
+            if peers.len() >= max {
+                if let Some(victim) = self.lowest_scoring(direction, i64::MAX) {
+                    self.evict(victim, direction);
+                }
+            }
+            return Ok(());
+        }
+        let unreserved = peers.iter().filter(|peer| !self.is_reserved(peer)).count();
+        if peers.len() < max && unreserved < max.saturating_sub(self.limits.reserved_slots) {
+            return Ok(());
+        }
+        // Full, so a newcomer only displaces a peer that has done worse than nothing
+        match self.lowest_scoring(direction, 0) {
+            Some(victim) => {
+                self.evict(victim, direction);
+                Ok(())
+            }
+            None => Err(ConnectionDenied::new(anyhow!("No free {} peer slots", direction))),
+        }
+    }
+}
+
+impl NetworkBehaviour for Behaviour {
+    type ConnectionHandler = dummy::ConnectionHandler;
+    type ToSwarm = Infallible;
+
+    fn handle_established_inbound_connection(
+        &mut self,
+        _connection_id: ConnectionId,
+        peer: PeerId,
+        _local_addr: &Multiaddr,
+        _remote_addr: &Multiaddr,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        self.admit(peer, Direction::Inbound)?;
+        Ok(dummy::ConnectionHandler)
+    }
+
+    fn handle_established_outbound_connection(
+        &mut self,
+        _connection_id: ConnectionId,
+        peer: PeerId,
+        _addr: &Multiaddr,
+        _role_override: Endpoint,
+        _port_use: PortUse,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        self.admit(peer, Direction::Outbound)?;
+        Ok(dummy::ConnectionHandler)
+    }
+
+    fn on_swarm_event(&mut self, event: FromSwarm) {
+        match event {
+            FromSwarm::ConnectionEstablished(established) => {
+                let direction = if established.endpoint.is_dialer() {
+                    Direction::Outbound
+                } else {
+                    Direction::Inbound
+                };
+                self.connections.insert(established.connection_id, (established.peer_id, direction));
+            }
+            FromSwarm::ConnectionClosed(closed) => {
+                self.connections.remove(&closed.connection_id);
+                if closed.remaining_established == 0 {
+                    self.evicting.remove(&closed.peer_id);
+                    if self.score(&closed.peer_id) >= 0 {
+                        self.scores.remove(&closed.peer_id);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn on_connection_handler_event(
+        &mut self,
+        _peer_id: PeerId,
+        _connection_id: ConnectionId,
+        event: THandlerOutEvent<Self>,
+    ) {
+        match event {}
+    }
+
+    fn poll(&mut self, cx: &mut Context<'_>) -> Poll<ToSwarm<Self::ToSwarm, THandlerInEvent<Self>>> {
+        if let Some(peer_id) = self.closing.pop_front() {
+            return Poll::Ready(ToSwarm::CloseConnection {
+                peer_id,
+                connection: CloseConnection::All,
+            });
+        }
+        self.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connect(behaviour: &mut Behaviour, peer_id: PeerId, direction: Direction) -> bool {
+        if behaviour.admit(peer_id, direction).is_err() {
+            return false;
+        }
+        behaviour.connections.insert(ConnectionId::new_unchecked(behaviour.connections.len() + 1000), (peer_id, direction));
+        true
+    }
+
+    #[test]
+    fn test_slots_are_held_for_reserved_peers() {
+        let mut behaviour = Behaviour::default();
+        behaviour.set_limits(SlotLimits { max_inbound: 3, max_outbound: 2, reserved_slots: 1 });
+
+        assert!(connect(&mut behaviour, PeerId::random(), Direction::Inbound));
+        let bad = PeerId::random();
+        assert!(connect(&mut behaviour, bad, Direction::Inbound));
+        // The last slot is reserved
+        assert!(!connect(&mut behaviour, PeerId::random(), Direction::Inbound));
+
+        let validator = PeerId::random();
+        behaviour.reserve(validator);
+        assert!(connect(&mut behaviour, validator, Direction::Inbound));
+        assert_eq!(behaviour.info(false).reserved_connected, 1);
+
+        // Full, so a newcomer only gets in by displacing a misbehaving peer
+        behaviour.adjust_score(&bad, -5);
+        assert!(connect(&mut behaviour, PeerId::random(), Direction::Inbound));
+        assert_eq!(behaviour.closing.pop_front(), Some(bad));
+        assert!(!connect(&mut behaviour, PeerId::random(), Direction::Inbound));
+
+        // Directions are counted apart
+        assert!(connect(&mut behaviour, PeerId::random(), Direction::Outbound));
+        assert!(!connect(&mut behaviour, PeerId::random(), Direction::Outbound));
+    }
+
+    #[test]
+    fn test_reserved_peers_evict_the_lowest_score() {
+        let mut behaviour = Behaviour::default();
+        behaviour.set_limits(SlotLimits { max_inbound: 2, max_outbound: 2, reserved_slots: 0 });
+        let good = PeerId::random();
+        let worse = PeerId::random();
+        assert!(connect(&mut behaviour, good, Direction::Outbound));
+        assert!(connect(&mut behaviour, worse, Direction::Outbound));
+        behaviour.adjust_score(&good, 3);
+        behaviour.adjust_score(&worse, 1);
+        behaviour.adjust_score(&good, PEER_SCORE_MAX * 2);
+        assert_eq!(behaviour.score(&good), PEER_SCORE_MAX);
+
+        let bootstrapper = PeerId::random();
+        behaviour.reserve(bootstrapper);
+        assert!(connect(&mut behaviour, bootstrapper, Direction::Outbound));
+        assert_eq!(behaviour.closing.pop_front(), Some(worse));
+        let info = behaviour.info(true);
+        assert_eq!(info.outbound, 2);
+        assert_eq!(info.evicted, 1);
+        assert_eq!(info.scores.unwrap().get(&good.to_string()), Some(&PEER_SCORE_MAX));
+    }
+
+    #[test]
+    fn test_validators_are_reserved_while_in_the_set() {
+        let mut behaviour = Behaviour::default();
+        let validator = PeerId::random();
+        behaviour.set_validators(HashSet::from([validator]));
+        assert!(behaviour.is_reserved(&validator));
+
+        // A validator that leaves the set loses its slot
+        behaviour.set_validators(HashSet::new());
+        assert!(!behaviour.is_reserved(&validator));
+    }
+
+    #[tokio::test]
+    async fn test_current_validators_are_the_static_set() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        let validator = PeerId::random();
+        mgr.set_static_validators(&[validator.to_string(), "not a peer id".to_string()]).await.unwrap();
+        assert_eq!(current_validators(&mgr).await.unwrap(), HashSet::from([validator]));
+    }
+
+    #[test]
+    fn test_bootstrapper_peer_ids() {
+        let peer_id = PeerId::random();
+        let addrs: Vec<Multiaddr> = vec![
+            format!("/ip4/127.0.0.1/tcp/10001/ws/p2p/{}", peer_id).parse().unwrap(),
+            "/ip4/127.0.0.1/tcp/10002/ws".parse().unwrap(),
+        ];
+        assert_eq!(peer_ids_of(&addrs), vec![peer_id]);
+    }
+}
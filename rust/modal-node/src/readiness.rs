@@ -0,0 +1,150 @@
+//! Readiness probe
+//!
+//! `/ready` on the status server tells orchestrators whether the node can
+//! take traffic: its chain tip is recent enough to be caught up, it's
+//! connected to peers if it has any to connect to, and, if it validates the
+//! current epoch, it's taking part in consensus. The node answers 200 when
+//! every check passes and 503 otherwise, with the checks in the body.
+
+use modal_datastore::models::{DAGCertificate, MinerBlock};
+use modal_datastore::DatastoreManager;
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{READY_MAX_CERTIFICATE_AGE_SECS, READY_MAX_TIP_AGE_BLOCKS};
+use crate::duties::duty_calendar;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReadinessCheck {
+    pub name: String,
+    pub ok: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Readiness {
+    pub ready: bool,
+    pub checks: Vec<ReadinessCheck>,
+}
+
+fn check(name: &str, ok: bool, detail: String) -> ReadinessCheck {
+    ReadinessCheck {
+        name: name.to_string(),
+        ok,
+        detail,
+    }
+}
+
+/// Run the readiness checks at unix time `now`. `expect_peers` is whether
+/// the node has bootstrappers to connect to.
+pub async fn check_readiness(
+    mgr: &DatastoreManager,
+    peer_id: &str,
+    connected_peers: usize,
+    expect_peers: bool,
+    now: i64,
+) -> anyhow::Result<Readiness> {
+    let mut checks = Vec::new();
+
+    let canonical = MinerBlock::find_all_canonical_multi(mgr).await?;
+    let regtest = mgr
+        .get_network_config()
+        .await?
+        .and_then(|config| config.get("regtest").and_then(|v| v.as_bool()))
+        .unwrap_or(false);
+    checks.push(match canonical.iter().max_by_key(|b| b.index) {
+        None => check("sync", false, "No canonical blocks yet".to_string()),
+        // Regtest blocks are mined on demand, so an old tip is expected
+        Some(tip) if regtest => check("sync", true, format!("At height {} (regtest)", tip.index)),
+        Some(tip) => {
+            let max_age = READY_MAX_TIP_AGE_BLOCKS * mgr.era_schedule().target_block_time_at(tip.index) as i64;
+            let age = now - tip.timestamp;
+            check(
+                "sync",
+                age <= max_age,
+                format!("At height {}, tip is {}s old (max {}s)", tip.index, age, max_age),
+            )
+        }
+    });
+
+    checks.push(if expect_peers {
+        check("peers", connected_peers > 0, format!("{} connected peers", connected_peers))
+    } else {
+        check("peers", true, "No bootstrappers configured".to_string())
+    });
+
+    let calendar = duty_calendar(mgr, peer_id).await?;
+    checks.push(if calendar.is_validator_now() {
+        let latest = DAGCertificate::find_by_author_multi(mgr, peer_id)
+            .await?
+            .iter()
+            .map(|cert| cert.timestamp as i64)
+            .max();
+        match latest {
+            Some(timestamp) => {
+                let age = now - timestamp;
+                check(
+                    "consensus",
+                    age <= READY_MAX_CERTIFICATE_AGE_SECS,
+                    format!(
+                        "Validator for epoch {}, last certificate {}s ago (max {}s)",
+                        calendar.current_epoch, age, READY_MAX_CERTIFICATE_AGE_SECS
+                    ),
+                )
+            }
+            None => check(
+                "consensus",
+                false,
+                format!("Validator for epoch {} but has authored no certificates", calendar.current_epoch),
+            ),
+        }
+    } else {
+        check("consensus", true, format!("Not a validator for epoch {}", calendar.current_epoch))
+    });
+
+    Ok(Readiness {
+        ready: checks.iter().all(|c| c.ok),
+        checks,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tip(timestamp: i64) -> MinerBlock {
+        MinerBlock::new_canonical(
+            "hash0".to_string(),
+            0,
+            0,
+            timestamp,
+            String::new(),
+            String::new(),
+            0,
+            1000,
+            "alice".to_string(),
+            0,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_readiness_checks() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        let readiness = check_readiness(&mgr, "alice", 0, false, 1_000).await.unwrap();
+        assert!(!readiness.ready);
+        assert!(!readiness.checks[0].ok);
+
+        tip(1_000).save_to_active(&mgr).await.unwrap();
+        let readiness = check_readiness(&mgr, "alice", 0, false, 1_010).await.unwrap();
+        assert!(readiness.ready, "{:?}", readiness);
+        // Behind the network, or cut off from it
+        assert!(!check_readiness(&mgr, "alice", 0, false, 1_000_000).await.unwrap().ready);
+        assert!(!check_readiness(&mgr, "alice", 0, true, 1_010).await.unwrap().ready);
+
+        // A static validator has to be producing certificates
+        mgr.set_static_validators(&["alice".to_string()]).await.unwrap();
+        let readiness = check_readiness(&mgr, "alice", 1, true, 1_010).await.unwrap();
+        assert!(!readiness.ready);
+        assert_eq!(readiness.checks[2].name, "consensus");
+        assert!(!readiness.checks[2].ok);
+    }
+}
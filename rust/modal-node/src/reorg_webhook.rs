@@ -0,0 +1,46 @@
+//! Reorg webhook
+//!
+//! Posts every reorg event as JSON to a configured URL so downstream
+//! indexers can invalidate data derived from orphaned blocks.
+
+use modal_observer::ReorgEvent;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+/// Timeout for a single webhook request
+const WEBHOOK_TIMEOUT_SECS: u64 = 10;
+
+/// Spawn a task that posts reorg events to `url` until shutdown
+pub fn start_reorg_webhook(
+    url: String,
+    mut reorg_rx: broadcast::Receiver<ReorgEvent>,
+    mut shutdown_rx: broadcast::Receiver<()>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let client = crate::proxy::http_client_builder()
+            .timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECS))
+            .build()
+            .unwrap_or_default();
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => break,
+                event = reorg_rx.recv() => match event {
+                    Ok(event) => {
+                        match client.post(&url).json(&event).send().await {
+                            Ok(response) if !response.status().is_success() => {
+                                log::warn!("Reorg webhook {} returned {}", url, response.status());
+                            }
+                            Ok(_) => {}
+                            Err(e) => log::warn!("Failed to post reorg event to {}: {}", url, e),
+                        }
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        log::warn!("Reorg webhook fell behind and missed {} events", missed);
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+            }
+        }
+    })
+}
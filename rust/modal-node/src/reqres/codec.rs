@@ -0,0 +1,121 @@
+//! Request-response codec
+//!
+//! Requests and responses are CBOR on `BINARY_PROTOCOL` and JSON on
+//! `PROTOCOL`. Both are offered, binary first, so two nodes that support it
+//! settle on CBOR when the stream is negotiated and older peers fall back to
+//! JSON. Reads accept either format whatever the protocol.
+
+use async_trait::async_trait;
+use futures::prelude::*;
+use libp2p::request_response;
+use libp2p::StreamProtocol;
+use modal_common::wire::{self, WireFormat};
+use std::io;
+
+use super::{Request, Response, BINARY_PROTOCOL, MAX_REQUEST_BYTES, MAX_RESPONSE_BYTES};
+
+#[derive(Debug, Clone, Default)]
+pub struct Codec;
+
+/// The format a negotiated protocol speaks
+pub fn format_of(protocol: &StreamProtocol) -> WireFormat {
+    if protocol.as_ref() == BINARY_PROTOCOL {
+        WireFormat::Cbor
+    } else {
+        WireFormat::Json
+    }
+}
+
+#[async_trait]
+impl request_response::Codec for Codec {
+    type Protocol = StreamProtocol;
+    type Request = Request;
+    type Response = Response;
+
+    async fn read_request<T>(&mut self, _protocol: &StreamProtocol, io: &mut T) -> io::Result<Request>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let data = read_limited(io, MAX_REQUEST_BYTES).await?;
+        Request::from_slice(&data).map_err(invalid_data)
+    }
+
+    async fn read_response<T>(&mut self, _protocol: &StreamProtocol, io: &mut T) -> io::Result<Response>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let data = read_limited(io, MAX_RESPONSE_BYTES).await?;
+        Response::from_slice(&data).map_err(invalid_data)
+    }
+
+    async fn write_request<T>(&mut self, protocol: &StreamProtocol, io: &mut T, req: Request) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        let data = wire::to_vec(&req, format_of(protocol)).map_err(invalid_data)?;
+        io.write_all(&data).await
+    }
+
+    async fn write_response<T>(&mut self, protocol: &StreamProtocol, io: &mut T, res: Response) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        let data = wire::to_vec(&res, format_of(protocol)).map_err(invalid_data)?;
+        io.write_all(&data).await
+    }
+}
+
+/// Read the rest of the stream, failing past `limit` bytes
+async fn read_limited<T: AsyncRead + Unpin + Send>(io: &mut T, limit: usize) -> io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    io.take(limit as u64 + 1).read_to_end(&mut data).await?;
+    if data.len() > limit {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Message exceeds {} bytes", limit)));
+    }
+    Ok(data)
+}
+
+fn invalid_data(e: anyhow::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use request_response::Codec as _;
+
+    fn request() -> Request {
+        Request {
+            path: "/data/miner_block/range".to_string(),
+            data: Some(serde_json::json!({"from_index": 3, "to_index": 7})),
+            accept_encoding: Some(crate::reqres::encoding::AcceptEncoding::zstd()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_each_protocol_round_trips_in_its_format() {
+        for (protocol, format) in [(BINARY_PROTOCOL, WireFormat::Cbor), (crate::reqres::PROTOCOL, WireFormat::Json)] {
+            let protocol = StreamProtocol::new(protocol);
+            let mut codec = Codec;
+            let mut buf = Vec::new();
+            codec.write_request(&protocol, &mut buf, request()).await.unwrap();
+            assert_eq!(WireFormat::of(&buf), format);
+            let read = codec.read_request(&protocol, &mut futures::io::Cursor::new(buf)).await.unwrap();
+            assert_eq!(read, request());
+
+            let response = Response::error("Unknown path".to_string());
+            let mut buf = Vec::new();
+            codec.write_response(&protocol, &mut buf, response.clone()).await.unwrap();
+            let read = codec.read_response(&protocol, &mut futures::io::Cursor::new(buf)).await.unwrap();
+            assert_eq!(read, response);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_oversized_request_is_refused() {
+        let protocol = StreamProtocol::new(BINARY_PROTOCOL);
+        let big = vec![b' '; MAX_REQUEST_BYTES + 1];
+        let result = Codec.read_request(&protocol, &mut futures::io::Cursor::new(big)).await;
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
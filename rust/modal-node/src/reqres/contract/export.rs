@@ -0,0 +1,34 @@
+//! Checkpoint-signed export of a contract, see `contract_export`
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use modal_datastore::DatastoreManager;
+
+use crate::reqres::Response;
+
+pub const PATH: &str = "/contract/export";
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExportRequest {
+    pub contract_id: String,
+    /// Epoch of the finalized checkpoint to export at
+    pub checkpoint: u64,
+}
+
+pub async fn handler(data: Option<Value>, datastore_manager: &DatastoreManager) -> Result<Response> {
+    let req: ExportRequest = if let Some(d) = data {
+        serde_json::from_value(d)?
+    } else {
+        anyhow::bail!("Missing request data");
+    };
+
+    let export = crate::contract_export::build(datastore_manager, &req.contract_id, req.checkpoint).await?;
+    Ok(Response {
+        ok: true,
+        data: Some(serde_json::to_value(export)?),
+        errors: None,
+        encoding: None,
+    })
+}
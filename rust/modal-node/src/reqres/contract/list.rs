@@ -58,5 +58,6 @@ pub async fn handler(
         ok: true,
         data: Some(serde_json::to_value(response)?),
         errors: None,
+        encoding: None,
     })
 }
@@ -2,3 +2,5 @@ pub mod submit; // Keep for backwards compatibility
 pub mod push;
 pub mod pull;
 pub mod list;
+pub mod simulate_commit;
+pub mod export;
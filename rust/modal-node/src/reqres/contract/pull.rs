@@ -27,6 +27,9 @@ pub struct CommitInfo {
     pub body: Value,
     pub head: Value,
     pub timestamp: u64,
+    /// The commit exactly as stored, whose SHA-256 is the commit id
+    #[serde(default)]
+    pub commit_data: String,
 }
 
 pub async fn handler(
@@ -59,6 +62,7 @@ pub async fn handler(
             body: commit_data.get("body").cloned().unwrap_or_default(),
             head: commit_data.get("head").cloned().unwrap_or_default(),
             timestamp: commit.timestamp,
+            commit_data: commit.commit_data,
         });
     }
 
@@ -71,5 +75,6 @@ pub async fn handler(
         ok: true,
         data: Some(serde_json::to_value(response)?),
         errors: None,
+        encoding: None,
     })
 }
@@ -2,8 +2,8 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tokio::sync::mpsc;
-use sha2::{Sha256, Digest};
 
+use modal_common::canonical::{sha256_hex, CanonicalHash};
 use modal_datastore::DatastoreManager;
 use modal_datastore::models::Commit;
 
@@ -28,6 +28,9 @@ pub struct PushResponse {
     pub contract_id: String,
     pub pushed_count: usize,
     pub status: String,
+    /// Commits that were stored
+    #[serde(default)]
+    pub commit_ids: Vec<String>,
 }
 
 pub async fn handler(
@@ -41,20 +44,18 @@ pub async fn handler(
         anyhow::bail!("Missing request data");
     };
 
-    let mut saved_count = 0;
+    let mut saved_ids = Vec::new();
     let timestamp = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)?
         .as_secs();
 
     for commit_data in &req.commits {
-        let commit_json = serde_json::to_string(&serde_json::json!({
+        let commit_json = serde_json::json!({
             "body": commit_data.body,
             "head": commit_data.head,
-        }))?;
-        
-        let mut hasher = Sha256::new();
-        hasher.update(commit_json.as_bytes());
-        let computed_id = format!("{:x}", hasher.finalize());
+        })
+        .canonical_json()?;
+        let computed_id = sha256_hex(commit_json.as_bytes());
         
         if computed_id != commit_data.commit_id {
             log::warn!("Commit ID mismatch: expected {}, got {}", commit_data.commit_id, computed_id);
@@ -64,27 +65,66 @@ pub async fn handler(
         let commit = Commit {
             contract_id: req.contract_id.clone(),
             commit_id: commit_data.commit_id.clone(),
-            commit_data: serde_json::to_string(&serde_json::json!({
-                "body": commit_data.body,
-                "head": commit_data.head,
-            }))?,
+            commit_data: commit_json,
             timestamp,
             in_batch: None,
         };
 
         Commit::save_to_final(&commit, datastore_manager).await?;
-        saved_count += 1;
+        saved_ids.push(commit_data.commit_id.clone());
     }
 
     let response = PushResponse {
         contract_id: req.contract_id,
-        pushed_count: saved_count,
+        pushed_count: saved_ids.len(),
         status: "pushed".to_string(),
+        commit_ids: saved_ids,
     };
 
     Ok(Response {
         ok: true,
         data: Some(serde_json::to_value(response)?),
         errors: None,
+        encoding: None,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract_sync::commit_id_of;
+    use modal_common::contract_store::CommitFile;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_cli_and_node_agree_on_commit_ids() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        let (consensus_tx, _consensus_rx) = mpsc::channel(1);
+
+        // Head fields in struct order aren't sorted, which is where struct
+        // and Value encodings used to part ways
+        let mut commit = CommitFile::with_parent("parent".to_string());
+        commit.add_action("post".to_string(), Some("/a.text".to_string()), Value::String("a".to_string()));
+        commit.head.signatures = Some(serde_json::json!({ "key": "sig" }));
+        let commit_id = commit.compute_id().unwrap();
+        assert_eq!(commit_id_of(&commit.payload().unwrap()), commit_id);
+
+        let data = serde_json::json!({
+            "contract_id": "c1",
+            "commits": [{
+                "commit_id": commit_id,
+                "body": serde_json::to_value(&commit.body).unwrap(),
+                "head": serde_json::to_value(&commit.head).unwrap(),
+            }],
+        });
+        let response = handler(Some(data), &mgr, consensus_tx).await.unwrap();
+        let response: PushResponse = serde_json::from_value(response.data.unwrap()).unwrap();
+        assert_eq!(response.commit_ids, vec![commit_id.clone()]);
+
+        let mut keys = HashMap::new();
+        keys.insert("contract_id".to_string(), "c1".to_string());
+        keys.insert("commit_id".to_string(), commit_id);
+        let stored = Commit::find_one_multi(&mgr, keys).await.unwrap().unwrap();
+        assert_eq!(stored.commit_data.as_bytes(), commit.payload().unwrap().as_slice());
+    }
+}
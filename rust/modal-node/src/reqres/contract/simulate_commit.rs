@@ -0,0 +1,149 @@
+//! Dry run of a candidate commit
+//!
+//! Runs a commit through the same pipeline consensus applies commits with
+//! (actions, predicates, WASM programs and gas quotas) and rolls everything
+//! back, so a client can see the verdict, the gas and the state changes
+//! before signing and pushing it.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use modal_common::canonical::{sha256_hex, CanonicalHash};
+use modal_datastore::DatastoreManager;
+use modal_datastore::models::MinerBlock;
+use modal_validator::{ContractProcessor, StateChange};
+
+use crate::reqres::Response;
+
+pub const PATH: &str = "/contract/simulate_commit";
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SimulateCommitRequest {
+    pub contract_id: String,
+    pub body: Value,
+    #[serde(default)]
+    pub head: Value,
+    /// Epoch whose gas quota applies (default: the chain tip's)
+    #[serde(default)]
+    pub epoch: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct SimulateCommitResponse {
+    pub contract_id: String,
+    /// Id the commit would be stored under
+    pub commit_id: String,
+    /// Whether validators would apply the commit
+    pub valid: bool,
+    /// Why they wouldn't
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub gas_used: u64,
+    /// State changes the commit would make, tagged by `type`
+    pub state_changes: Vec<Value>,
+    pub epoch: u64,
+}
+
+/// Apply the commit speculatively and report what it would do
+pub async fn simulate(
+    datastore_manager: Arc<Mutex<DatastoreManager>>,
+    req: SimulateCommitRequest,
+) -> Result<SimulateCommitResponse> {
+    let commit_data = serde_json::json!({
+        "body": req.body,
+        "head": req.head,
+    })
+    .canonical_json()?;
+    let commit_id = sha256_hex(commit_data.as_bytes());
+    let epoch = match req.epoch {
+        Some(epoch) => epoch,
+        None => {
+            let ds = datastore_manager.lock().await;
+            let tip = MinerBlock::find_all_canonical_multi(&ds).await?.last().map(|b| b.index).unwrap_or(0);
+            ds.block_index_to_epoch(tip)
+        }
+    };
+
+    let processor = ContractProcessor::new(datastore_manager).with_epoch(epoch);
+    let (valid, error, state_changes) = match processor.dry_run(&req.contract_id, &commit_id, &commit_data).await {
+        Ok(changes) => (true, None, changes),
+        Err(e) => (false, Some(e.to_string()), Vec::new()),
+    };
+    Ok(SimulateCommitResponse {
+        contract_id: req.contract_id,
+        commit_id,
+        valid,
+        error,
+        gas_used: state_changes.iter().map(StateChange::gas_used).sum(),
+        state_changes: state_changes.iter().map(serde_json::to_value).collect::<Result<_, _>>()?,
+        epoch,
+    })
+}
+
+/// Answer a simulate request. Takes the shared datastore rather than a
+/// locked one, since the contract processor locks it as it goes.
+pub async fn handler(
+    data: Option<Value>,
+    datastore_manager: Arc<Mutex<DatastoreManager>>,
+) -> Result<Response> {
+    let req: SimulateCommitRequest = if let Some(d) = data {
+        serde_json::from_value(d)?
+    } else {
+        anyhow::bail!("Missing request data");
+    };
+
+    let response = simulate(datastore_manager, req).await?;
+    Ok(Response {
+        ok: true,
+        data: Some(serde_json::to_value(response)?),
+        errors: None,
+        encoding: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use modal_datastore::models::ContractAsset;
+
+    #[tokio::test]
+    async fn test_simulate_commit_leaves_no_state() {
+        let mgr = Arc::new(Mutex::new(DatastoreManager::create_in_memory().unwrap()));
+        let create = serde_json::json!({
+            "contract_id": "c1",
+            "body": [{
+                "method": "create",
+                "value": {"asset_id": "token", "quantity": 100, "divisibility": 1}
+            }],
+            "head": {},
+        });
+
+        let response = handler(Some(create.clone()), mgr.clone()).await.unwrap();
+        let result: SimulateCommitResponse = serde_json::from_value(response.data.unwrap()).unwrap();
+        assert!(result.valid, "{:?}", result.error);
+        assert_eq!(result.state_changes.len(), 1);
+        assert_eq!(result.state_changes[0]["type"], "asset_created");
+
+        // Nothing was kept, so the same commit still simulates cleanly
+        let mut keys = std::collections::HashMap::new();
+        keys.insert("contract_id".to_string(), "c1".to_string());
+        keys.insert("asset_id".to_string(), "token".to_string());
+        assert!(ContractAsset::find_one_multi(&*mgr.lock().await, keys).await.unwrap().is_none());
+        let response = handler(Some(create), mgr.clone()).await.unwrap();
+        let again: SimulateCommitResponse = serde_json::from_value(response.data.unwrap()).unwrap();
+        assert_eq!(again, result);
+
+        // An action the pipeline rejects comes back as a verdict, not an error
+        let bad = serde_json::json!({
+            "contract_id": "c1",
+            "body": [{"method": "create", "value": {"asset_id": "token"}}],
+        });
+        let response = handler(Some(bad), mgr).await.unwrap();
+        let result: SimulateCommitResponse = serde_json::from_value(response.data.unwrap()).unwrap();
+        assert!(!result.valid);
+        assert!(result.error.unwrap().contains("quantity"));
+    }
+}
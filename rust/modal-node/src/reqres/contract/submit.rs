@@ -2,8 +2,8 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tokio::sync::mpsc;
-use sha2::{Sha256, Digest};
 
+use modal_common::canonical::{sha256_hex, CanonicalHash};
 use modal_datastore::DatastoreManager;
 use modal_datastore::models::Commit;
 
@@ -34,10 +34,8 @@ pub async fn handler(
         anyhow::bail!("Missing request data");
     };
 
-    let commit_json = serde_json::to_string(&req.commit_data)?;
-    let mut hasher = Sha256::new();
-    hasher.update(commit_json.as_bytes());
-    let commit_id = format!("{:x}", hasher.finalize());
+    let commit_json = req.commit_data.canonical_json()?;
+    let commit_id = sha256_hex(commit_json.as_bytes());
     
     let timestamp = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)?
@@ -46,7 +44,7 @@ pub async fn handler(
     let commit = Commit {
         contract_id: req.contract_id.clone(),
         commit_id: commit_id.clone(),
-        commit_data: serde_json::to_string(&req.commit_data)?,
+        commit_data: commit_json,
         timestamp,
         in_batch: None,
     };
@@ -64,6 +62,7 @@ pub async fn handler(
         ok: true,
         data: Some(serde_json::to_value(response)?),
         errors: None,
+        encoding: None,
     })
 }
 
@@ -11,6 +11,7 @@ pub async fn handler(data: Option<Value>, _datastore_manager: &DatastoreManager)
             ok: false,
             data: None,
             errors: Some(serde_json::json!({"error": "Missing request data"})),
+            encoding: None,
         });
     };
     
@@ -21,6 +22,7 @@ pub async fn handler(data: Option<Value>, _datastore_manager: &DatastoreManager)
                 ok: false,
                 data: None,
                 errors: Some(serde_json::json!({"error": format!("Invalid sync request: {}", e)})),
+                encoding: None,
             });
         }
     };
@@ -32,5 +34,6 @@ pub async fn handler(data: Option<Value>, _datastore_manager: &DatastoreManager)
         errors: Some(serde_json::json!({
             "error": "DAG sync endpoint available but Shoal validator not yet integrated"
         })),
+        encoding: None,
     })
 }
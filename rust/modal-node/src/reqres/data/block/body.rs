@@ -10,7 +10,8 @@ pub async fn handler(data: Option<serde_json::Value>, _datastore_manager: &Datas
     let response = Response {
         ok: true,
         data: None,
-        errors: None
+        errors: None,
+        encoding: None,
     };
     Ok(response)
 }
@@ -25,6 +25,7 @@ pub async fn handler(data: Option<serde_json::Value>, datastore_manager: &Datast
                         "count": blocks.len(),
                     })),
                     errors: None,
+                    encoding: None,
                 })
             }
             Err(e) => {
@@ -32,6 +33,7 @@ pub async fn handler(data: Option<serde_json::Value>, datastore_manager: &Datast
                     ok: false,
                     data: None,
                     errors: Some(serde_json::json!({"error": e.to_string()})),
+                    encoding: None,
                 })
             }
         }
@@ -40,6 +42,7 @@ pub async fn handler(data: Option<serde_json::Value>, datastore_manager: &Datast
             ok: false,
             data: None,
             errors: Some(serde_json::json!({"error": "Missing 'epoch' parameter"})),
+            encoding: None,
         })
     }
 }
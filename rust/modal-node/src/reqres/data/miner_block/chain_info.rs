@@ -1,6 +1,7 @@
 use anyhow::Result;
 use modal_datastore::DatastoreManager;
 use modal_datastore::models::MinerBlock;
+use modal_datastore::models::miner::PruneStatus;
 use crate::reqres::Response;
 
 /// Handler for GET /data/miner_block/chain_info
@@ -26,6 +27,8 @@ pub async fn handler(
     let from_index = data.get("from_index")
         .and_then(|v| v.as_u64());
     
+    let pruned_below = PruneStatus::pruned_below_multi(datastore_manager).await?;
+    
     match MinerBlock::find_all_canonical_multi(datastore_manager).await {
         Ok(all_blocks) => {
             if all_blocks.is_empty() {
@@ -39,8 +42,10 @@ pub async fn handler(
                         "tip_epoch": 0,
                         "common_ancestor_index": null,
                         "blocks": null,
+                        "pruned_below": pruned_below,
                     })),
                     errors: None,
+                    encoding: None,
                 });
             }
             
@@ -51,6 +56,7 @@ pub async fn handler(
                         ok: false,
                         data: None,
                         errors: Some(serde_json::json!({"error": format!("Failed to calculate cumulative difficulty: {}", e)})),
+                        encoding: None,
                     });
                 }
             };
@@ -96,8 +102,10 @@ pub async fn handler(
                     "tip_epoch": tip_epoch,
                     "common_ancestor_index": common_ancestor_index,
                     "blocks": blocks_data,
+                    "pruned_below": pruned_below,
                 })),
                 errors: None,
+                encoding: None,
             })
         }
         Err(e) => {
@@ -105,6 +113,7 @@ pub async fn handler(
                 ok: false,
                 data: None,
                 errors: Some(serde_json::json!({"error": e.to_string()})),
+                encoding: None,
             })
         }
     }
@@ -15,6 +15,7 @@ pub async fn handler(data: Option<serde_json::Value>, datastore_manager: &Datast
                 ok: false,
                 data: None,
                 errors: Some(serde_json::json!({"error": "Missing 'index' parameter"})),
+                encoding: None,
             });
         }
     };
@@ -76,5 +77,6 @@ pub async fn handler(data: Option<serde_json::Value>, datastore_manager: &Datast
         ok: true,
         data: Some(result),
         errors: None,
+        encoding: None,
     })
 }
@@ -0,0 +1,52 @@
+use anyhow::Result;
+use modal_datastore::DatastoreManager;
+use modal_datastore::models::MinerBlock;
+use modal_datastore::models::miner::EpochReport;
+use crate::reqres::Response;
+
+/// Handler for GET /data/miner_block/epoch_report
+/// Returns the report of a finished epoch, generating it if the node hasn't yet
+pub async fn handler(data: Option<serde_json::Value>, datastore_manager: &DatastoreManager) -> Result<Response> {
+    let data = data.unwrap_or_default();
+
+    let Some(epoch) = data.get("epoch").and_then(|v| v.as_u64()) else {
+        return Ok(error_response("Missing 'epoch' parameter".to_string()));
+    };
+
+    let report = match EpochReport::find_by_epoch_multi(datastore_manager, epoch).await {
+        Ok(Some(report)) => Ok(report),
+        Ok(None) => generate(datastore_manager, epoch).await,
+        Err(e) => Err(e),
+    };
+
+    match report {
+        Ok(report) => Ok(Response {
+            ok: true,
+            data: Some(serde_json::to_value(&report)?),
+            errors: None,
+            encoding: None,
+        }),
+        Err(e) => Ok(error_response(e.to_string())),
+    }
+}
+
+async fn generate(datastore_manager: &DatastoreManager, epoch: u64) -> Result<EpochReport> {
+    let current_epoch = MinerBlock::find_all_canonical_multi(datastore_manager)
+        .await?
+        .iter()
+        .max_by_key(|b| b.index)
+        .map(|b| b.epoch)
+        .unwrap_or(0);
+    EpochReport::generate_multi(datastore_manager, epoch, current_epoch).await
+}
+
+fn error_response(error: String) -> Response {
+    Response {
+        ok: false,
+        data: None,
+        errors: Some(serde_json::json!({ "error": error })),
+    },
+    encoding: None,
+  encoding: None,
+    }
+}
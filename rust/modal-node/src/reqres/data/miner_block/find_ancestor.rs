@@ -26,6 +26,7 @@ pub async fn handler(
                         errors: Some(serde_json::json!({
                             "error": "Invalid check_point format"
                         })),
+                        encoding: None,
                     });
                 }
             }
@@ -38,6 +39,7 @@ pub async fn handler(
                 errors: Some(serde_json::json!({
                     "error": "Missing 'check_points' parameter"
                 })),
+                encoding: None,
             });
         }
     };
@@ -49,6 +51,7 @@ pub async fn handler(
                 ok: false,
                 data: None,
                 errors: Some(serde_json::json!({"error": format!("Failed to load canonical blocks: {}", e)})),
+                encoding: None,
             });
         }
     };
@@ -64,6 +67,7 @@ pub async fn handler(
                 errors: Some(serde_json::json!({
                     "error": format!("Failed to calculate cumulative difficulty: {}", e)
                 })),
+                encoding: None,
             });
         }
     };
@@ -104,5 +108,6 @@ pub async fn handler(
             "cumulative_difficulty": cumulative_difficulty,
         })),
         errors: None,
+        encoding: None,
     })
 }
@@ -18,6 +18,7 @@ pub async fn handler(
                     ok: true,
                     data: Some(serde_json::to_value(block)?),
                     errors: None,
+                    encoding: None,
                 })
             }
             Ok(None) => {
@@ -25,6 +26,7 @@ pub async fn handler(
                     ok: false,
                     data: None,
                     errors: Some(serde_json::json!({"error": "Block not found"})),
+                    encoding: None,
                 })
             }
             Err(e) => {
@@ -32,6 +34,7 @@ pub async fn handler(
                     ok: false,
                     data: None,
                     errors: Some(serde_json::json!({"error": e.to_string()})),
+                    encoding: None,
                 })
             }
         }
@@ -40,6 +43,7 @@ pub async fn handler(
             ok: false,
             data: None,
             errors: Some(serde_json::json!({"error": "Missing 'hash' parameter"})),
+            encoding: None,
         })
     }
 }
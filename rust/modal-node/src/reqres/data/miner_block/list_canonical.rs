@@ -18,6 +18,7 @@ pub async fn handler(
                     "count": blocks.len(),
                 })),
                 errors: None,
+                encoding: None,
             })
         }
         Err(e) => {
@@ -25,6 +26,7 @@ pub async fn handler(
                 ok: false,
                 data: None,
                 errors: Some(serde_json::json!({"error": e.to_string()})),
+                encoding: None,
             })
         }
     }
@@ -11,6 +11,9 @@ pub mod list_canonical;
 /// Get miner blocks by epoch
 pub mod by_epoch;
 
+/// Get the report of a finished epoch
+pub mod epoch_report;
+
 /// Get miner block range by indices
 pub mod range;
 
@@ -1,10 +1,12 @@
 use anyhow::Result;
 use modal_datastore::DatastoreManager;
 use modal_datastore::models::MinerBlock;
+use modal_datastore::models::miner::PruneStatus;
 use crate::reqres::Response;
 
 /// Handler for GET /data/miner_block/range
 /// Returns canonical miner blocks in a range (from_index..=to_index)
+/// Blocks below `pruned_below` are headers only (their commits were pruned)
 pub async fn handler(
     data: Option<serde_json::Value>, 
     datastore_manager: &DatastoreManager,
@@ -24,12 +26,14 @@ pub async fn handler(
                     ok: false,
                     data: None,
                     errors: Some(serde_json::json!({"error": "from_index must be <= to_index"})),
+                    encoding: None,
                 });
             }
             
             let chunk_size = std::cmp::min(max_chunk_size, 1000);
             let actual_to = std::cmp::min(to, from + chunk_size - 1);
             
+            let pruned_below = PruneStatus::pruned_below_multi(datastore_manager).await?;
             match MinerBlock::find_all_canonical_multi(datastore_manager).await {
                 Ok(all_blocks) => {
                     let blocks: Vec<_> = all_blocks
@@ -47,8 +51,10 @@ pub async fn handler(
                             "count": blocks.len(),
                             "has_more": actual_to < to && !blocks.is_empty(),
                             "chunk_size": chunk_size,
+                            "pruned_below": pruned_below,
                         })),
                         errors: None,
+                        encoding: None,
                     })
                 }
                 Err(e) => {
@@ -56,6 +62,7 @@ pub async fn handler(
                         ok: false,
                         data: None,
                         errors: Some(serde_json::json!({"error": e.to_string()})),
+                        encoding: None,
                     })
                 }
             }
@@ -65,6 +72,7 @@ pub async fn handler(
                 ok: false,
                 data: None,
                 errors: Some(serde_json::json!({"error": "Missing 'from_index' or 'to_index' parameter"})),
+                encoding: None,
             })
         }
     }
@@ -0,0 +1,454 @@
+//! Request dispatcher
+//!
+//! Incoming requests are queued by priority class (consensus, then sync, then
+//! everything else) and run off the networking task. The most urgent queued
+//! request whose path is under its concurrency limit runs next, so a storm of
+//! range requests from syncing peers can't hold up consensus acks. Once a
+//! class has too many requests waiting, new ones are turned away at once with
+//! a `retry_after_ms` hint instead of piling up.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc, Notify};
+
+use modal_datastore::DatastoreManager;
+use modal_validator_consensus::communication::Message as ConsensusMessage;
+
+use super::{Request, Response};
+use crate::constants::{
+    REQRES_CONSENSUS_CONCURRENCY, REQRES_MAX_COMPRESSION_LEVEL, REQRES_MAX_QUEUED,
+    REQRES_MISC_CONCURRENCY, REQRES_RETRY_AFTER_MS, REQRES_SYNC_CONCURRENCY,
+};
+use crate::gossip::contract::commits::{self, CommitAnnouncement};
+
+/// How urgently a request is handled, most urgent first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    Consensus,
+    Sync,
+    Misc,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 3] = [Priority::Consensus, Priority::Sync, Priority::Misc];
+
+    /// Class of a request path
+    pub fn of(path: &str) -> Self {
+        if path.starts_with("/consensus/") {
+            Priority::Consensus
+        } else if path.starts_with("/data/")
+            || path.starts_with("/sequencer/log/")
+            || path == "/dag/sync"
+            || path == "/contract/pull"
+        {
+            Priority::Sync
+        } else {
+            Priority::Misc
+        }
+    }
+
+    fn slot(self) -> usize {
+        self as usize
+    }
+
+    /// Requests for one path of this class handled at once, unless configured
+    pub fn default_concurrency(self) -> usize {
+        match self {
+            Priority::Consensus => REQRES_CONSENSUS_CONCURRENCY,
+            Priority::Sync => REQRES_SYNC_CONCURRENCY,
+            Priority::Misc => REQRES_MISC_CONCURRENCY,
+        }
+    }
+}
+
+/// Dispatcher settings, from the `reqres_dispatch` node config option
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DispatchConfig {
+    /// Requests handled at once for a path, e.g. {"/data/miner_block/range": 1};
+    /// other paths use their class's default
+    pub path_limits: HashMap<String, usize>,
+    /// Requests a class may have waiting before new ones are turned away
+    pub max_queued: Option<usize>,
+    /// How long a turned away peer is told to wait before retrying
+    pub retry_after_ms: Option<u64>,
+    /// Highest zstd level responses are compressed at for peers that ask;
+    /// 0 turns compression off
+    pub max_compression_level: Option<i32>,
+}
+
+impl DispatchConfig {
+    pub fn limit_for(&self, path: &str) -> usize {
+        self.path_limits
+            .get(path)
+            .copied()
+            .unwrap_or_else(|| Priority::of(path).default_concurrency())
+            .max(1)
+    }
+
+    pub fn max_queued(&self) -> usize {
+        self.max_queued.unwrap_or(REQRES_MAX_QUEUED)
+    }
+
+    pub fn retry_after_ms(&self) -> u64 {
+        self.retry_after_ms.unwrap_or(REQRES_RETRY_AFTER_MS)
+    }
+
+    pub fn max_compression_level(&self) -> i32 {
+        self.max_compression_level.unwrap_or(REQRES_MAX_COMPRESSION_LEVEL)
+    }
+}
+
+/// Queue counters for one priority class
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ClassMetrics {
+    pub queued: usize,
+    pub running: usize,
+    /// Most requests ever waiting at once
+    pub peak_queued: usize,
+    pub handled: u64,
+    pub rejected: u64,
+    /// Average time handled requests spent queued
+    pub avg_wait_ms: f64,
+}
+
+/// Queue counters per priority class, served at `/api/reqres.json`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DispatchMetrics {
+    pub consensus: ClassMetrics,
+    pub sync: ClassMetrics,
+    pub misc: ClassMetrics,
+}
+
+impl DispatchMetrics {
+    fn class_mut(&mut self, priority: Priority) -> &mut ClassMetrics {
+        match priority {
+            Priority::Consensus => &mut self.consensus,
+            Priority::Sync => &mut self.sync,
+            Priority::Misc => &mut self.misc,
+        }
+    }
+}
+
+struct Pending<T> {
+    request: Request,
+    reply: T,
+    queued_at: Instant,
+}
+
+#[derive(Default)]
+struct Counters {
+    peak_queued: usize,
+    handled: u64,
+    rejected: u64,
+    total_wait: Duration,
+}
+
+struct Queues<T> {
+    pending: [VecDeque<Pending<T>>; 3],
+    counters: [Counters; 3],
+}
+
+/// Requests running per path, shared with the permits that release them
+#[derive(Default)]
+struct Running {
+    by_path: Mutex<HashMap<String, usize>>,
+    released: Notify,
+}
+
+/// Holds a path's concurrency slot until the request is handled
+pub struct Permit {
+    running: Arc<Running>,
+    path: String,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut by_path = self.running.by_path.lock().unwrap();
+        if let Some(count) = by_path.get_mut(&self.path) {
+            *count -= 1;
+            if *count == 0 {
+                by_path.remove(&self.path);
+            }
+        }
+        drop(by_path);
+        self.running.released.notify_one();
+    }
+}
+
+/// A request taken off the queue, with where to send its response
+pub struct Job<T> {
+    pub request: Request,
+    pub reply: T,
+    pub priority: Priority,
+    pub permit: Permit,
+}
+
+/// Priority queues of requests waiting to be handled, generic over the
+/// handle a response is sent through
+pub struct Dispatcher<T> {
+    config: DispatchConfig,
+    queues: Mutex<Queues<T>>,
+    running: Arc<Running>,
+}
+
+impl<T> Dispatcher<T> {
+    pub fn new(config: DispatchConfig) -> Self {
+        Self {
+            config,
+            queues: Mutex::new(Queues {
+                pending: Default::default(),
+                counters: Default::default(),
+            }),
+            running: Arc::new(Running::default()),
+        }
+    }
+
+    pub fn config(&self) -> &DispatchConfig {
+        &self.config
+    }
+
+    /// Queue a request, or hand back its reply handle with a busy response
+    /// if its class's queue is full
+    pub fn submit(&self, request: Request, reply: T) -> std::result::Result<(), (T, Response)> {
+        let priority = Priority::of(&request.path);
+        let mut guard = self.queues.lock().unwrap();
+        let queues = &mut *guard;
+        let queue = &mut queues.pending[priority.slot()];
+        if queue.len() >= self.config.max_queued() {
+            queues.counters[priority.slot()].rejected += 1;
+            let response = Response::busy(&request.path, self.config.retry_after_ms());
+            return Err((reply, response));
+        }
+        queue.push_back(Pending {
+            request,
+            reply,
+            queued_at: Instant::now(),
+        });
+        let queued = queue.len();
+        let counters = &mut queues.counters[priority.slot()];
+        counters.peak_queued = counters.peak_queued.max(queued);
+        drop(guard);
+        self.running.released.notify_one();
+        Ok(())
+    }
+
+    /// Take the most urgent queued request whose path has a free slot, if any
+    pub fn try_next(&self) -> Option<Job<T>> {
+        let mut queues = self.queues.lock().unwrap();
+        let mut by_path = self.running.by_path.lock().unwrap();
+        for priority in Priority::ALL {
+            let queue = &queues.pending[priority.slot()];
+            let runnable = queue.iter().position(|pending| {
+                by_path.get(&pending.request.path).copied().unwrap_or(0) < self.config.limit_for(&pending.request.path)
+            });
+            let Some(position) = runnable else {
+                continue;
+            };
+            let pending = queues.pending[priority.slot()].remove(position)?;
+            let counters = &mut queues.counters[priority.slot()];
+            counters.handled += 1;
+            counters.total_wait += pending.queued_at.elapsed();
+            *by_path.entry(pending.request.path.clone()).or_insert(0) += 1;
+            return Some(Job {
+                permit: Permit {
+                    running: self.running.clone(),
+                    path: pending.request.path.clone(),
+                },
+                request: pending.request,
+                reply: pending.reply,
+                priority,
+            });
+        }
+        None
+    }
+
+    /// Wait for a request to be runnable and take it
+    pub async fn next(&self) -> Job<T> {
+        loop {
+            if let Some(job) = self.try_next() {
+                return job;
+            }
+            self.running.released.notified().await;
+        }
+    }
+
+    pub fn metrics(&self) -> DispatchMetrics {
+        let queues = self.queues.lock().unwrap();
+        let by_path = self.running.by_path.lock().unwrap();
+        let mut metrics = DispatchMetrics::default();
+        for priority in Priority::ALL {
+            let counters = &queues.counters[priority.slot()];
+            let class = metrics.class_mut(priority);
+            class.queued = queues.pending[priority.slot()].len();
+            class.peak_queued = counters.peak_queued;
+            class.handled = counters.handled;
+            class.rejected = counters.rejected;
+            if counters.handled > 0 {
+                class.avg_wait_ms = counters.total_wait.as_secs_f64() * 1000.0 / counters.handled as f64;
+            }
+        }
+        for (path, count) in by_path.iter() {
+            metrics.class_mut(Priority::of(path)).running += count;
+        }
+        metrics
+    }
+}
+
+/// Dispatcher for requests arriving over the reqres protocol
+pub type RequestDispatcher = Dispatcher<libp2p::request_response::ResponseChannel<Response>>;
+
+/// A handled request, for the networking task to answer
+pub struct Handled<T> {
+    pub reply: T,
+    pub response: Response,
+    /// Commits the request stored, to gossip before responding
+    pub announcements: Vec<CommitAnnouncement>,
+}
+
+/// Spawn a task that runs queued requests as their paths have free slots,
+/// sending each response to `handled_tx`, until shutdown
+pub fn start_dispatcher<T: Send + 'static>(
+    dispatcher: Arc<Dispatcher<T>>,
+    datastore_manager: Arc<tokio::sync::Mutex<DatastoreManager>>,
+    mempool: crate::mempool::SharedMempool,
+    consensus_tx: mpsc::Sender<ConsensusMessage>,
+    handled_tx: mpsc::UnboundedSender<Handled<T>>,
+    mut shutdown_rx: broadcast::Receiver<()>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let job = tokio::select! {
+                _ = shutdown_rx.recv() => break,
+                job = dispatcher.next() => job,
+            };
+            let datastore_manager = datastore_manager.clone();
+            let mempool = mempool.clone();
+            let consensus_tx = consensus_tx.clone();
+            let handled_tx = handled_tx.clone();
+            let max_compression_level = dispatcher.config().max_compression_level();
+            tokio::spawn(async move {
+                let Job { request, reply, permit, .. } = job;
+                let (path, data) = (request.path.clone(), request.data.clone());
+                let accept_encoding = request.accept_encoding;
+                let (response, announcements) = if path == super::contract::simulate_commit::PATH {
+                    // The contract processor locks the datastore itself
+                    let response = match super::contract::simulate_commit::handler(data, datastore_manager).await {
+                        Ok(response) => response,
+                        Err(e) => {
+                            log::warn!("Request to {} failed: {}", path, e);
+                            Response::from_error(&e)
+                        }
+                    };
+                    (response, Vec::new())
+                } else {
+                    let mgr = datastore_manager.lock().await;
+                    let response = match super::handle_request(request, &mgr, &mempool, consensus_tx).await {
+                        Ok(response) => response,
+                        Err(e) => {
+                            log::warn!("Request to {} failed: {}", path, e);
+                            Response::from_error(&e)
+                        }
+                    };
+                    let announcements = match commits::accepted_by(&path, data.as_ref(), &response) {
+                        Some((contract_id, ids)) => commits::announcements(&mgr, &contract_id, &ids).await.unwrap_or_default(),
+                        None => Vec::new(),
+                    };
+                    (response, announcements)
+                };
+                drop(permit);
+                let response = match response.clone().encode_for(accept_encoding, max_compression_level) {
+                    Ok(encoded) => encoded,
+                    Err(e) => {
+                        log::warn!("Failed to compress response to {}: {}", path, e);
+                        response
+                    }
+                };
+                let _ = handled_tx.send(Handled { reply, response, announcements });
+            });
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(path: &str) -> Request {
+        Request {
+            path: path.to_string(),
+            data: None,
+            accept_encoding: None,
+        }
+    }
+
+    #[test]
+    fn test_consensus_runs_before_queued_sync() {
+        let dispatcher = Dispatcher::new(DispatchConfig::default());
+        dispatcher.submit(request("/data/miner_block/range"), 1).unwrap();
+        dispatcher.submit(request("/ping"), 2).unwrap();
+        dispatcher.submit(request("/consensus/block/ack"), 3).unwrap();
+
+        let order: Vec<_> = std::iter::from_fn(|| dispatcher.try_next()).map(|job| (job.priority, job.reply)).collect();
+        assert_eq!(order, vec![(Priority::Consensus, 3), (Priority::Sync, 1), (Priority::Misc, 2)]);
+    }
+
+    #[test]
+    fn test_path_limit_holds_requests_until_permit_drops() {
+        let config = DispatchConfig {
+            path_limits: [("/data/miner_block/range".to_string(), 1)].into_iter().collect(),
+            ..Default::default()
+        };
+        let dispatcher = Dispatcher::new(config);
+        dispatcher.submit(request("/data/miner_block/range"), 1).unwrap();
+        dispatcher.submit(request("/data/miner_block/range"), 2).unwrap();
+        dispatcher.submit(request("/data/miner_block/get"), 3).unwrap();
+
+        let first = dispatcher.try_next().unwrap();
+        assert_eq!(first.reply, 1);
+        // The saturated path doesn't block other paths of its class
+        let second = dispatcher.try_next().unwrap();
+        assert_eq!(second.reply, 3);
+        assert!(dispatcher.try_next().is_none());
+        assert_eq!(dispatcher.metrics().sync.running, 2);
+
+        drop(first);
+        assert_eq!(dispatcher.try_next().unwrap().reply, 2);
+    }
+
+    #[test]
+    fn test_full_queue_is_turned_away_with_retry_after() {
+        let config = DispatchConfig {
+            max_queued: Some(1),
+            retry_after_ms: Some(250),
+            ..Default::default()
+        };
+        let dispatcher = Dispatcher::new(config);
+        dispatcher.submit(request("/data/block"), 1).unwrap();
+        let (reply, response) = dispatcher.submit(request("/data/block/head"), 2).unwrap_err();
+        assert_eq!(reply, 2);
+        assert!(!response.ok);
+        assert_eq!(response.retry_after_ms(), Some(250));
+        // Other classes have their own queues
+        dispatcher.submit(request("/consensus/status"), 3).unwrap();
+
+        let metrics = dispatcher.metrics();
+        assert_eq!((metrics.sync.queued, metrics.sync.rejected, metrics.sync.peak_queued), (1, 1, 1));
+        assert_eq!(metrics.consensus.queued, 1);
+    }
+
+    #[tokio::test]
+    async fn test_next_wakes_when_a_request_arrives() {
+        let dispatcher = Arc::new(Dispatcher::new(DispatchConfig::default()));
+        let waiter = tokio::spawn({
+            let dispatcher = dispatcher.clone();
+            async move { dispatcher.next().await.reply }
+        });
+        tokio::task::yield_now().await;
+        dispatcher.submit(request("/ping"), 7).unwrap();
+        assert_eq!(tokio::time::timeout(Duration::from_secs(1), waiter).await.unwrap().unwrap(), 7);
+    }
+}
@@ -0,0 +1,145 @@
+//! Response compression
+//!
+//! A requester that can decode compressed responses says so with
+//! `accept_encoding` and the zstd level it would like. The responder
+//! compresses `data` only when it is large enough to be worth it, at the lower
+//! of that level and its own `max_compression_level`, and marks the response
+//! with `encoding`. Compressed data travels as a base64 string of the zstd
+//! frame of its JSON. Peers that don't know these fields never ask for
+//! compression, and never get it.
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use super::Response;
+use crate::constants::{REQRES_COMPRESS_MIN_BYTES, REQRES_MAX_DECOMPRESSED_BYTES, REQRES_ZSTD_LEVEL};
+
+/// How a response's `data` is encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentEncoding {
+    Zstd,
+}
+
+/// Compression a requester can decode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AcceptEncoding {
+    pub encoding: ContentEncoding,
+    /// Level the requester asks for; the responder may use a lower one
+    pub level: i32,
+}
+
+impl AcceptEncoding {
+    /// zstd at the default level, for requests that can return many blocks or commits
+    pub fn zstd() -> Self {
+        Self {
+            encoding: ContentEncoding::Zstd,
+            level: REQRES_ZSTD_LEVEL,
+        }
+    }
+}
+
+impl Response {
+    /// Compress `data` as `accept` allows, if it's worth it. A
+    /// `max_level` of 0 or below turns compression off.
+    pub fn encode_for(mut self, accept: Option<AcceptEncoding>, max_level: i32) -> Result<Self> {
+        let Some(accept) = accept else {
+            return Ok(self);
+        };
+        let level = accept.level.min(max_level);
+        if self.encoding.is_some() || level <= 0 {
+            return Ok(self);
+        }
+        let Some(data) = self.data.as_ref() else {
+            return Ok(self);
+        };
+        let json = serde_json::to_vec(data)?;
+        if json.len() < REQRES_COMPRESS_MIN_BYTES {
+            return Ok(self);
+        }
+        let compressed = match accept.encoding {
+            ContentEncoding::Zstd => zstd::bulk::compress(&json, level)?,
+        };
+        if compressed.len() >= json.len() {
+            return Ok(self);
+        }
+        self.data = Some(serde_json::Value::String(
+            base64::engine::general_purpose::STANDARD.encode(compressed),
+        ));
+        self.encoding = Some(accept.encoding);
+        Ok(self)
+    }
+
+    /// Undo `encode_for`, refusing data that would decompress past
+    /// `REQRES_MAX_DECOMPRESSED_BYTES`
+    pub fn decode(mut self) -> Result<Self> {
+        let Some(encoding) = self.encoding.take() else {
+            return Ok(self);
+        };
+        let Some(serde_json::Value::String(encoded)) = self.data.take() else {
+            anyhow::bail!("Encoded response data must be a string");
+        };
+        let compressed = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .context("Encoded response data isn't base64")?;
+        let json = match encoding {
+            ContentEncoding::Zstd => zstd::bulk::decompress(&compressed, REQRES_MAX_DECOMPRESSED_BYTES)
+                .context("Failed to decompress response data")?,
+        };
+        self.data = Some(serde_json::from_slice(&json).context("Decompressed response data isn't JSON")?);
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocks_response(count: usize) -> Response {
+        let blocks: Vec<_> = (0..count)
+            .map(|index| serde_json::json!({"index": index, "hash": "ab".repeat(32), "previous_hash": "cd".repeat(32)}))
+            .collect();
+        Response {
+            ok: true,
+            data: Some(serde_json::json!({"blocks": blocks, "has_more": false})),
+            errors: None,
+            encoding: None,
+        }
+    }
+
+    #[test]
+    fn test_large_response_round_trips_compressed() {
+        let response = blocks_response(100);
+        let encoded = response.clone().encode_for(Some(AcceptEncoding::zstd()), 19).unwrap();
+        assert_eq!(encoded.encoding, Some(ContentEncoding::Zstd));
+        let wire = serde_json::to_vec(&encoded).unwrap();
+        assert!(wire.len() < serde_json::to_vec(&response).unwrap().len() / 4);
+
+        let received = Response::from_json_slice(&wire).unwrap();
+        assert_eq!(received.decode().unwrap(), response);
+    }
+
+    #[test]
+    fn test_response_left_alone_unless_asked_and_worth_it() {
+        let response = blocks_response(100);
+        assert_eq!(response.clone().encode_for(None, 19).unwrap(), response);
+        assert_eq!(response.clone().encode_for(Some(AcceptEncoding::zstd()), 0).unwrap(), response);
+        let small = blocks_response(1);
+        assert_eq!(small.clone().encode_for(Some(AcceptEncoding::zstd()), 19).unwrap(), small);
+        // Responses from peers that never compress decode as they are
+        assert_eq!(response.clone().decode().unwrap(), response);
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_data() {
+        let mut response = blocks_response(1);
+        response.encoding = Some(ContentEncoding::Zstd);
+        assert!(response.clone().decode().is_err());
+        response.data = Some(serde_json::Value::String("not base64!".to_string()));
+        assert!(response.clone().decode().is_err());
+        let bomb = zstd::bulk::compress(&vec![b' '; REQRES_MAX_DECOMPRESSED_BYTES + 1], 1).unwrap();
+        response.data = Some(serde_json::Value::String(base64::engine::general_purpose::STANDARD.encode(bomb)));
+        assert!(response.decode().is_err());
+    }
+}
@@ -0,0 +1,34 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use modal_datastore::DatastoreManager;
+
+use crate::reqres::Response;
+
+pub const PATH: &str = "/index/balances";
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BalancesRequest {
+    pub owner_contract_id: String,
+}
+
+/// Handler for /index/balances
+/// Lists the asset balances a contract holds, as the indexer has followed
+/// them from create, send and recv actions.
+pub async fn handler(data: Option<Value>, datastore_manager: &DatastoreManager) -> Result<Response> {
+    if let Some(refusal) = super::not_indexing(datastore_manager)? {
+        return Ok(refusal);
+    }
+    let req: BalancesRequest = if let Some(d) = data {
+        serde_json::from_value(d)?
+    } else {
+        anyhow::bail!("Missing request data");
+    };
+
+    let balances = datastore_manager.index().balances(&req.owner_contract_id)?;
+    Ok(super::ok(serde_json::json!({
+        "owner_contract_id": req.owner_contract_id,
+        "balances": balances,
+    })))
+}
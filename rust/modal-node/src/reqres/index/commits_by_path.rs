@@ -0,0 +1,41 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use modal_datastore::DatastoreManager;
+
+use crate::constants::MAX_INDEX_RESULTS_PER_REQUEST;
+use crate::reqres::Response;
+
+pub const PATH: &str = "/index/commits_by_path";
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CommitsByPathRequest {
+    pub contract_id: String,
+    /// Path the actions are at or below, e.g. `/members`
+    #[serde(default)]
+    pub path_prefix: String,
+    pub limit: Option<usize>,
+}
+
+/// Handler for /index/commits_by_path
+/// Lists a contract's commit actions at or below a path, by path and then
+/// time.
+pub async fn handler(data: Option<Value>, datastore_manager: &DatastoreManager) -> Result<Response> {
+    if let Some(refusal) = super::not_indexing(datastore_manager)? {
+        return Ok(refusal);
+    }
+    let req: CommitsByPathRequest = if let Some(d) = data {
+        serde_json::from_value(d)?
+    } else {
+        anyhow::bail!("Missing request data");
+    };
+
+    let limit = req.limit.unwrap_or(MAX_INDEX_RESULTS_PER_REQUEST).min(MAX_INDEX_RESULTS_PER_REQUEST);
+    let commits = datastore_manager.index().commits_by_path(&req.contract_id, &req.path_prefix, limit)?;
+    Ok(super::ok(serde_json::json!({
+        "contract_id": req.contract_id,
+        "path_prefix": req.path_prefix,
+        "commits": commits,
+    })))
+}
@@ -0,0 +1,33 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use modal_datastore::DatastoreManager;
+
+use crate::reqres::Response;
+
+pub const PATH: &str = "/index/contracts_by_signer";
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ContractsBySignerRequest {
+    pub public_key: String,
+}
+
+/// Handler for /index/contracts_by_signer
+/// Lists the contracts with commits signed by a key.
+pub async fn handler(data: Option<Value>, datastore_manager: &DatastoreManager) -> Result<Response> {
+    if let Some(refusal) = super::not_indexing(datastore_manager)? {
+        return Ok(refusal);
+    }
+    let req: ContractsBySignerRequest = if let Some(d) = data {
+        serde_json::from_value(d)?
+    } else {
+        anyhow::bail!("Missing request data");
+    };
+
+    let contracts = datastore_manager.index().contracts_by_signer(&req.public_key)?;
+    Ok(super::ok(serde_json::json!({
+        "public_key": req.public_key,
+        "contracts": contracts,
+    })))
+}
@@ -0,0 +1,56 @@
+//! Queries answered by indexer nodes from their `IndexStore`. Other nodes
+//! don't keep the index and turn these away, and only indexers advertise
+//! the paths in their capabilities.
+
+pub mod balances;
+pub mod commits_by_path;
+pub mod contracts_by_signer;
+
+use modal_common::error_codes::ErrorCode;
+use modal_datastore::DatastoreManager;
+
+use crate::reqres::Response;
+
+/// Every index query path
+pub const PATHS: &[&str] = &[contracts_by_signer::PATH, commits_by_path::PATH, balances::PATH];
+
+/// The refusal for a node that doesn't keep the index, if this is one
+fn not_indexing(datastore_manager: &DatastoreManager) -> anyhow::Result<Option<Response>> {
+    if datastore_manager.index().is_enabled()? {
+        return Ok(None);
+    }
+    Ok(Some(Response::error_with_code(
+        ErrorCode::MethodNotFound,
+        "This node doesn't keep an index; ask an indexer node".to_string(),
+    )))
+}
+
+fn ok(data: serde_json::Value) -> Response {
+    Response {
+        ok: true,
+        data: Some(data),
+        errors: None,
+        encoding: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_only_indexers_answer() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        let commit = json!({"body": [], "head": {"signatures": {"alice": "sig"}}}).to_string();
+        mgr.index().index_commit("c1", "k1", &commit, 1).unwrap();
+        let request = Some(json!({"public_key": "alice"}));
+
+        let refused = contracts_by_signer::handler(request.clone(), &mgr).await.unwrap();
+        assert_eq!(refused.error_code(), Some(ErrorCode::MethodNotFound));
+
+        mgr.index().enable().unwrap();
+        let response = contracts_by_signer::handler(request, &mgr).await.unwrap();
+        assert_eq!(response.data.unwrap()["contracts"], json!(["c1"]));
+    }
+}
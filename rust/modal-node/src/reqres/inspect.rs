@@ -26,6 +26,7 @@ pub async fn handler(
         ok: true,
         data: Some(serde_json::to_value(inspection_data)?),
         errors: None,
+        encoding: None,
     })
 }
 
@@ -4,16 +4,25 @@ mod consensus;
 mod ping;
 mod data;
 mod dag;
-mod contract;
+pub(crate) mod contract;
+mod sequencer;
+pub(crate) mod node;
+pub mod index;
 pub mod inspect;
+pub mod codec;
+pub mod dispatcher;
+pub mod encoding;
 use data as reqres_data;
 use tokio::sync::mpsc;
 
+use modal_common::error_codes::ErrorCode;
 use modal_datastore::DatastoreManager;
 use modal_validator_consensus::communication::Message as ConsensusMessage;
 
-#[allow(dead_code)]
+/// JSON requests and responses, spoken by every node
 pub const PROTOCOL: &str = "/modality-network/reqres/0.0.1";
+/// CBOR requests and responses, preferred when both sides support it
+pub const BINARY_PROTOCOL: &str = "/modality-network/reqres/cbor/0.0.1";
 #[allow(dead_code)]
 pub const PROTOCOL_VERSION: &str = "0.0.1";
 #[allow(dead_code)]
@@ -21,26 +30,212 @@ pub const PROTOCOL_PREFIX: &str = "modality-network";
 #[allow(dead_code)]
 pub const PROTOCOL_NAME: &str = "reqres";
 
-pub type Behaviour = request_response::json::Behaviour::<Request, Response>;
+pub type Behaviour = request_response::Behaviour<codec::Codec>;
+
+/// Largest request accepted, matching the JSON codec's limit
+pub const MAX_REQUEST_BYTES: usize = 1024 * 1024;
+
+/// Largest response accepted, matching the JSON codec's limit
+pub const MAX_RESPONSE_BYTES: usize = 10 * 1024 * 1024;
+
+/// Longest request path
+pub const MAX_PATH_LEN: usize = 256;
+
+/// Every path `handle_request` answers, advertised in this node's capabilities
+pub const PATHS: &[&str] = &[
+    "/ping",
+    "/inspect",
+    "/node/capabilities",
+    "/data/block",
+    "/data/block/head",
+    "/data/block/body",
+    "/data/block/inclusions",
+    "/consensus/status",
+    "/consensus/block/ack",
+    "/data/miner_block/get",
+    "/data/miner_block/canonical",
+    "/data/miner_block/epoch",
+    "/data/miner_block/epoch_report",
+    "/data/miner_block/range",
+    "/data/miner_block/chain_info",
+    "/data/miner_block/find_ancestor",
+    "/data/miner_block/debug_index",
+    "/dag/sync",
+    "/sequencer/log/range",
+    "/sequencer/log/head",
+    "/sequencer/mempool/submit",
+    "/sequencer/mempool/list",
+    "/contract/submit",
+    "/contract/push",
+    "/contract/pull",
+    "/contract/list",
+    contract::simulate_commit::PATH,
+    contract::export::PATH,
+];
 
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct Request {
     pub path: String,
     pub data: Option<serde_json::Value>,
+    /// Compression the requester can decode the response in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accept_encoding: Option<encoding::AcceptEncoding>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct Response {
     pub ok: bool,
     pub data: Option<serde_json::Value>,
-    pub errors: Option<serde_json::Value>
+    pub errors: Option<serde_json::Value>,
+    /// How `data` is encoded, if it was compressed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<encoding::ContentEncoding>,
+}
+
+impl Request {
+    /// Parse a request received from a peer
+    pub fn from_json_slice(data: &[u8]) -> Result<Self> {
+        let req: Self = modal_common::wire::from_json_slice("request", data, MAX_REQUEST_BYTES)?;
+        req.validate()?;
+        Ok(req)
+    }
+
+    /// Parse a request received from a peer, in either wire format
+    pub fn from_slice(data: &[u8]) -> Result<Self> {
+        let req: Self = modal_common::wire::from_slice("request", data, MAX_REQUEST_BYTES)?;
+        req.validate()?;
+        Ok(req)
+    }
+
+    /// Check the path is short and absolute
+    pub fn validate(&self) -> Result<()> {
+        modal_common::wire::check_len("request path", self.path.len(), MAX_PATH_LEN)?;
+        if !self.path.starts_with('/') {
+            anyhow::bail!("Request path must start with '/'");
+        }
+        Ok(())
+    }
+}
+
+impl Response {
+    /// Parse a response received from a peer
+    pub fn from_json_slice(data: &[u8]) -> Result<Self> {
+        modal_common::wire::from_json_slice("response", data, MAX_RESPONSE_BYTES)
+    }
+
+    /// Parse a response received from a peer, in either wire format
+    pub fn from_slice(data: &[u8]) -> Result<Self> {
+        modal_common::wire::from_slice("response", data, MAX_RESPONSE_BYTES)
+    }
+
+    pub fn error(message: String) -> Self {
+        Self {
+            ok: false,
+            data: None,
+            errors: Some(serde_json::json!({"error": message})),
+            encoding: None,
+        }
+    }
+
+    /// An error carrying its shared error code next to the message
+    pub fn error_with_code(code: ErrorCode, message: String) -> Self {
+        Self {
+            ok: false,
+            data: None,
+            errors: Some(serde_json::json!({
+                "error": message,
+                "code": code.code(),
+                "name": code.name(),
+                "category": code.category(),
+                "retriable": code.retriable(),
+            })),
+            encoding: None,
+        }
+    }
+
+    /// An error response for a failed handler
+    pub fn from_error(err: &anyhow::Error) -> Self {
+        Self::error_with_code(crate::error_codes::code_of(err), err.to_string())
+    }
+
+    /// Turn a request away because too many like it are queued
+    pub fn busy(path: &str, retry_after_ms: u64) -> Self {
+        let mut response = Self::error_with_code(ErrorCode::Busy, format!("Busy: too many {} requests queued", path));
+        if let Some(errors) = response.errors.as_mut().and_then(|e| e.as_object_mut()) {
+            errors.insert("retry_after_ms".to_string(), retry_after_ms.into());
+        }
+        response
+    }
+
+    /// The shared error code of a failed response, if the peer sent one
+    /// this node knows
+    pub fn error_code(&self) -> Option<ErrorCode> {
+        let code = self.errors.as_ref()?.get("code")?.as_u64()?;
+        ErrorCode::from_code(code as u32)
+    }
+
+    /// How long the peer asked us to wait before retrying, if it was busy
+    pub fn retry_after_ms(&self) -> Option<u64> {
+        self.errors.as_ref()?.get("retry_after_ms")?.as_u64()
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Request {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        const PATHS: &[&str] = &[
+            "/ping",
+            "/inspect",
+            "/data/block",
+            "/data/miner_block/get",
+            "/data/miner_block/range",
+            "/data/miner_block/epoch_report",
+            "/contract/push",
+            "/contract/pull",
+            "/dag/sync",
+        ];
+        let path = if u.ratio(1, 4)? {
+            format!("/{}", u.arbitrary::<String>()?)
+        } else {
+            u.choose(PATHS)?.to_string()
+        };
+        let data = if u.arbitrary()? { Some(modal_common::wire::arbitrary_json(u, 4)?) } else { None };
+        let accept_encoding = if u.arbitrary()? {
+            Some(encoding::AcceptEncoding {
+                encoding: encoding::ContentEncoding::Zstd,
+                level: u.int_in_range(-1..=22)?,
+            })
+        } else {
+            None
+        };
+        Ok(Self { path, data, accept_encoding })
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Response {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let json = |u: &mut arbitrary::Unstructured<'a>| -> arbitrary::Result<Option<serde_json::Value>> {
+            Ok(if u.arbitrary()? { Some(modal_common::wire::arbitrary_json(u, 4)?) } else { None })
+        };
+        Ok(Self {
+            ok: u.arbitrary()?,
+            data: json(u)?,
+            errors: json(u)?,
+            encoding: if u.arbitrary()? { Some(encoding::ContentEncoding::Zstd) } else { None },
+        })
+    }
 }
 
 pub async fn handle_request(
     req: Request, 
     datastore_manager: &DatastoreManager,
+    mempool: &crate::mempool::SharedMempool,
     consensus_tx: mpsc::Sender<ConsensusMessage>
 ) -> Result<Response> {
+    if let Err(e) = req.validate() {
+        return Ok(Response::error_with_code(ErrorCode::InvalidRequest, e.to_string()));
+    }
     log::info!("Handling request: {:?}", req);
     let path = req.path;
     let data = req.data.unwrap_or_default();
@@ -51,6 +246,9 @@ pub async fn handle_request(
         "/inspect" => {
             inspect::handler(Some(data.clone()), datastore_manager).await?
         },
+        crate::capabilities::PATH => {
+            node::capabilities::handler(Some(data.clone()), datastore_manager).await?
+        }
         "/data/block" => {
             reqres_data::block::handler(Some(data.clone()), datastore_manager).await?
         }
@@ -78,6 +276,9 @@ pub async fn handle_request(
         "/data/miner_block/epoch" => {
             reqres_data::miner_block::by_epoch::handler(Some(data.clone()), datastore_manager).await?
         }
+        "/data/miner_block/epoch_report" => {
+            reqres_data::miner_block::epoch_report::handler(Some(data.clone()), datastore_manager).await?
+        }
         "/data/miner_block/range" => {
             reqres_data::miner_block::range::handler(Some(data.clone()), datastore_manager).await?
         }
@@ -93,6 +294,18 @@ pub async fn handle_request(
         "/dag/sync" => {
             dag::sync::handler(Some(data.clone()), datastore_manager).await?
         }
+        "/sequencer/log/range" => {
+            sequencer::range::handler(Some(data.clone()), datastore_manager).await?
+        }
+        "/sequencer/log/head" => {
+            sequencer::head::handler(Some(data.clone()), datastore_manager).await?
+        }
+        "/sequencer/mempool/submit" => {
+            sequencer::mempool::submit::handler(Some(data.clone()), mempool).await?
+        }
+        "/sequencer/mempool/list" => {
+            sequencer::mempool::list::handler(Some(data.clone()), mempool).await?
+        }
         "/contract/submit" => {
             contract::submit::handler(Some(data.clone()), datastore_manager, consensus_tx.clone()).await?
         }
@@ -105,14 +318,54 @@ pub async fn handle_request(
         "/contract/list" => {
             contract::list::handler(Some(data.clone()), datastore_manager, consensus_tx).await?
         }
+        contract::export::PATH => {
+            contract::export::handler(Some(data.clone()), datastore_manager).await?
+        }
+        index::contracts_by_signer::PATH => {
+            index::contracts_by_signer::handler(Some(data.clone()), datastore_manager).await?
+        }
+        index::commits_by_path::PATH => {
+            index::commits_by_path::handler(Some(data.clone()), datastore_manager).await?
+        }
+        index::balances::PATH => {
+            index::balances::handler(Some(data.clone()), datastore_manager).await?
+        }
         _ => {
-            Response {
-                ok: false,
-                data: None,
-                errors: Some(serde_json::json!({"error": "Unknown path"}))
-            }
+            Response::error_with_code(ErrorCode::MethodNotFound, "Unknown path".to_string())
         }
     };
     log::info!("Response: {:?}", response);
     Ok(response)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_from_json_slice() {
+        let req = Request::from_json_slice(br#"{"path":"/ping","data":{"n":1}}"#).unwrap();
+        assert_eq!(req.path, "/ping");
+        assert!(Request::from_json_slice(br#"{"path":"ping","data":null}"#).is_err());
+        let long_path = format!(r#"{{"path":"/{}","data":null}}"#, "a".repeat(MAX_PATH_LEN));
+        assert!(Request::from_json_slice(long_path.as_bytes()).is_err());
+        assert!(Request::from_json_slice(br#"{"path":"/ping""#).is_err());
+        assert!(Response::from_json_slice(br#"{"ok":true,"data":null,"errors":null}"#).unwrap().ok);
+    }
+
+    #[test]
+    fn test_error_responses_carry_codes() {
+        let busy = Response::busy("/contract/submit", 250);
+        assert_eq!(busy.error_code(), Some(ErrorCode::Busy));
+        assert_eq!(busy.retry_after_ms(), Some(250));
+        assert_eq!(busy.errors.as_ref().unwrap()["retriable"], true);
+
+        let err = anyhow::Error::new(modal_validator::MempoolError::FeeTooLow { min_fee: 3 });
+        let response = Response::from_error(&err);
+        assert_eq!(response.error_code(), Some(ErrorCode::FeeTooLow));
+        assert_eq!(response.errors.as_ref().unwrap()["category"], "mempool");
+
+        // Responses from nodes without codes still parse
+        assert_eq!(Response::error("Unknown path".to_string()).error_code(), None);
+    }
+}
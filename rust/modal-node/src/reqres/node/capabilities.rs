@@ -0,0 +1,18 @@
+use anyhow::Result;
+use modal_datastore::DatastoreManager;
+
+use crate::reqres::Response;
+
+/// Handler for /node/capabilities, the capabilities handshake
+pub async fn handler(
+    _data: Option<serde_json::Value>,
+    datastore_manager: &DatastoreManager,
+) -> Result<Response> {
+    let capabilities = crate::capabilities::local(datastore_manager).await?;
+    Ok(Response {
+        ok: true,
+        data: Some(serde_json::to_value(capabilities)?),
+        errors: None,
+        encoding: None,
+    })
+}
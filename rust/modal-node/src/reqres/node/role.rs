@@ -0,0 +1,68 @@
+use anyhow::{anyhow, Result};
+use libp2p::PeerId;
+use tokio::sync::mpsc;
+
+use modal_common::error_codes::ErrorCode;
+
+use crate::actions::roles::Role;
+use crate::reqres::Response;
+
+/// Request path of role changes, e.g. `{"role": "miner"}`
+pub const PATH: &str = "/node/role";
+
+/// Handler for /node/role, the admin request switching a running node's
+/// role. The networking task answers it rather than the dispatcher, since
+/// only `admin_peers` may ask.
+pub fn handler(
+    requester: &PeerId,
+    data: Option<serde_json::Value>,
+    admin_peers: &[String],
+    role_tx: &mpsc::UnboundedSender<Role>,
+) -> Result<Response> {
+    if !admin_peers.contains(&requester.to_string()) {
+        return Ok(Response::error_with_code(
+            ErrorCode::Unauthorized,
+            format!("Peer {} may not change this node's role", requester),
+        ));
+    }
+    let role: Role = data
+        .as_ref()
+        .and_then(|data| data.get("role"))
+        .and_then(|role| role.as_str())
+        .ok_or_else(|| anyhow!("Missing role"))?
+        .parse()?;
+    role_tx
+        .send(role)
+        .map_err(|_| anyhow!("This node isn't accepting role changes"))?;
+
+    Ok(Response {
+        ok: true,
+        data: Some(serde_json::json!({ "role": role })),
+        errors: None,
+        encoding: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_only_admin_peers_change_role() {
+        let admin = PeerId::random();
+        let stranger = PeerId::random();
+        let admin_peers = vec![admin.to_string()];
+        let (role_tx, mut role_rx) = mpsc::unbounded_channel();
+
+        let refused = handler(&stranger, Some(json!({"role": "miner"})), &admin_peers, &role_tx).unwrap();
+        assert!(!refused.ok);
+        assert!(role_rx.try_recv().is_err());
+
+        assert!(handler(&admin, Some(json!({"role": "gateway"})), &admin_peers, &role_tx).is_err());
+
+        let accepted = handler(&admin, Some(json!({"role": "miner"})), &admin_peers, &role_tx).unwrap();
+        assert!(accepted.ok);
+        assert_eq!(role_rx.try_recv().unwrap(), Role::Miner);
+    }
+}
@@ -0,0 +1,27 @@
+use anyhow::Result;
+use modal_datastore::DatastoreManager;
+use modal_datastore::models::SequencedLog;
+use crate::reqres::Response;
+
+/// Handler for GET /sequencer/log/head
+/// Returns the sequence number the next entry gets, so followers can tell
+/// whether there are new entries to fetch
+pub async fn handler(
+    _data: Option<serde_json::Value>,
+    datastore_manager: &DatastoreManager,
+) -> Result<Response> {
+    match SequencedLog::next_seq_multi(datastore_manager).await {
+        Ok(next_seq) => Ok(Response {
+            ok: true,
+            data: Some(serde_json::json!({ "next_seq": next_seq })),
+            errors: None,
+            encoding: None,
+        }),
+        Err(e) => Ok(Response {
+            ok: false,
+            data: None,
+            errors: Some(serde_json::json!({"error": e.to_string()})),
+            encoding: None,
+        }),
+    }
+}
@@ -0,0 +1,41 @@
+use anyhow::Result;
+use crate::constants::MAX_MEMPOOL_ENTRIES_PER_REQUEST;
+use crate::mempool::SharedMempool;
+use crate::reqres::Response;
+
+/// Handler for GET /sequencer/mempool/list
+/// Returns up to `limit` pending transactions in the order the workers
+/// would batch them (the ones waiting on a nonce gap last), and the pool's
+/// counters, including how many transactions were evicted and replaced
+pub async fn handler(data: Option<serde_json::Value>, mempool: &SharedMempool) -> Result<Response> {
+    let data = data.unwrap_or_default();
+    let limit = data.get("limit")
+        .and_then(|v| v.as_u64())
+        .map(|limit| limit as usize)
+        .unwrap_or(MAX_MEMPOOL_ENTRIES_PER_REQUEST)
+        .min(MAX_MEMPOOL_ENTRIES_PER_REQUEST);
+
+    let mempool = mempool.lock().await;
+    let transactions: Vec<serde_json::Value> = mempool
+        .contents(limit)
+        .into_iter()
+        .map(|entry| serde_json::json!({
+            "sender": entry.transaction.sender,
+            "nonce": entry.transaction.nonce,
+            "fee": entry.transaction.fee,
+            "data": hex::encode(&entry.transaction.data),
+            "timestamp": entry.transaction.timestamp,
+            "ready": entry.ready,
+        }))
+        .collect();
+
+    Ok(Response {
+        ok: true,
+        data: Some(serde_json::json!({
+            "transactions": transactions,
+            "stats": mempool.stats(),
+        })),
+        errors: None,
+        encoding: None,
+    })
+}
@@ -0,0 +1,7 @@
+//! Sequencer mempool request handlers.
+
+/// Submit a transaction to the mempool
+pub mod submit;
+
+/// List the mempool's transactions and counters
+pub mod list;
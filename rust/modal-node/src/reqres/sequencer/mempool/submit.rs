@@ -0,0 +1,83 @@
+use anyhow::Result;
+use modal_common::error_codes::{ErrorCode, HasErrorCode};
+use modal_validator::{Admission, MempoolTransaction};
+use crate::mempool::SharedMempool;
+use crate::reqres::Response;
+
+/// Handler for POST /sequencer/mempool/submit
+/// Queues a transaction `{sender, nonce, fee, data, signature}` (data
+/// hex-encoded, signed by the sender's key over the rest) for the
+/// validator's workers. A transaction with the nonce of a pending one
+/// replaces it if it pays enough more.
+pub async fn handler(data: Option<serde_json::Value>, mempool: &SharedMempool) -> Result<Response> {
+    let data = data.unwrap_or_default();
+
+    let tx = match parse(&data) {
+        Ok(tx) => tx,
+        Err(e) => return Ok(Response::error_with_code(ErrorCode::InvalidParams, e)),
+    };
+    let result = mempool.lock().await.submit(tx);
+    match result {
+        Ok(admission) => {
+            let previous_fee = match admission {
+                Admission::Added => None,
+                Admission::Replaced { previous_fee } => Some(previous_fee),
+            };
+            Ok(Response {
+                ok: true,
+                data: Some(serde_json::json!({
+                    "replaced": previous_fee.is_some(),
+                    "previous_fee": previous_fee,
+                })),
+                errors: None,
+                encoding: None,
+            })
+        }
+        Err(e) => Ok(Response::error_with_code(e.error_code(), e.to_string())),
+    }
+}
+
+fn parse(data: &serde_json::Value) -> std::result::Result<MempoolTransaction, String> {
+    let Some(sender) = data.get("sender").and_then(|v| v.as_str()) else {
+        return Err("Missing 'sender' parameter".to_string());
+    };
+    let Some(nonce) = data.get("nonce").and_then(|v| v.as_u64()) else {
+        return Err("Missing 'nonce' parameter".to_string());
+    };
+    let fee = data.get("fee").and_then(|v| v.as_u64()).unwrap_or(0);
+    let Some(hex_data) = data.get("data").and_then(|v| v.as_str()) else {
+        return Err("Missing 'data' parameter".to_string());
+    };
+    let tx_data = hex::decode(hex_data).map_err(|e| format!("Invalid 'data': {}", e))?;
+    let Some(signature) = data.get("signature").and_then(|v| v.as_str()) else {
+        return Err("Missing 'signature' parameter".to_string());
+    };
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    Ok(MempoolTransaction {
+        sender: sender.to_string(),
+        nonce,
+        fee,
+        data: tx_data,
+        timestamp,
+        signature: signature.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_submission() {
+        let tx = parse(&serde_json::json!({"sender": "alice", "nonce": 3, "fee": 7, "data": "0a0b", "signature": "sig"})).unwrap();
+        assert_eq!((tx.sender.as_str(), tx.nonce, tx.fee, tx.data), ("alice", 3, 7, vec![10, 11]));
+        assert_eq!(tx.signature, "sig");
+
+        assert!(parse(&serde_json::json!({"sender": "alice", "data": "0a", "signature": "sig"})).is_err());
+        assert!(parse(&serde_json::json!({"sender": "alice", "nonce": 0, "data": "zz", "signature": "sig"})).is_err());
+        assert!(parse(&serde_json::json!({"sender": "alice", "nonce": 0, "data": "0a"})).is_err());
+    }
+}
@@ -0,0 +1,15 @@
+//! Sequencer log request handlers.
+//!
+//! The sequencer log is the final order of committed transactions. Peers
+//! follow it by fetching ranges from the log head they last saw. The
+//! mempool holds the transactions submitted to this node until they're
+//! batched.
+
+/// Get a range of sequenced log entries
+pub mod range;
+
+/// Get the log head (the next sequence number)
+pub mod head;
+
+/// Submit to and list the mempool
+pub mod mempool;
@@ -0,0 +1,99 @@
+use anyhow::Result;
+use modal_datastore::DatastoreManager;
+use modal_datastore::models::SequencedLog;
+use crate::constants::MAX_SEQUENCED_ENTRIES_PER_REQUEST;
+use crate::reqres::Response;
+
+/// Handler for GET /sequencer/log/range
+/// Returns up to `limit` sequenced log entries starting at `from`, and the
+/// log head to continue from
+pub async fn handler(
+    data: Option<serde_json::Value>,
+    datastore_manager: &DatastoreManager,
+) -> Result<Response> {
+    let data = data.unwrap_or_default();
+
+    let Some(from) = data.get("from").and_then(|v| v.as_u64()) else {
+        return Ok(Response {
+            ok: false,
+            data: None,
+            errors: Some(serde_json::json!({"error": "Missing 'from' parameter"})),
+            encoding: None,
+        });
+    };
+    let limit = data.get("limit")
+        .and_then(|v| v.as_u64())
+        .map(|limit| limit as usize)
+        .unwrap_or(MAX_SEQUENCED_ENTRIES_PER_REQUEST)
+        .min(MAX_SEQUENCED_ENTRIES_PER_REQUEST);
+
+    let result = async {
+        let next_seq = SequencedLog::next_seq_multi(datastore_manager).await?;
+        let entries = SequencedLog::find_range_multi(datastore_manager, from, limit).await?;
+        Ok::<_, modal_datastore::Error>((next_seq, entries))
+    }
+    .await;
+
+    match result {
+        Ok((next_seq, entries)) => {
+            let to = from + entries.len() as u64;
+            Ok(Response {
+                ok: true,
+                data: Some(serde_json::json!({
+                    "from": from,
+                    "entries": entries,
+                    "count": entries.len(),
+                    "next_seq": next_seq,
+                    "has_more": to < next_seq,
+                })),
+                errors: None,
+                encoding: None,
+            })
+        }
+        Err(e) => Ok(Response {
+            ok: false,
+            data: None,
+            errors: Some(serde_json::json!({"error": e.to_string()})),
+            encoding: None,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sequenced_log_range() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        for seq in 0..5 {
+            let entry = SequencedLog {
+                seq,
+                round: 0,
+                author: "peer".to_string(),
+                certificate: "cert".to_string(),
+                batch: "batch".to_string(),
+                data: format!("{:02x}", seq),
+                timestamp: 1000,
+                sequenced_at: 2000,
+            };
+            entry.append_multi(&mgr).await.unwrap();
+        }
+
+        let response = handler(Some(serde_json::json!({"from": 1, "limit": 2})), &mgr).await.unwrap();
+        assert!(response.ok);
+        let data = response.data.unwrap();
+        assert_eq!(data["count"], 2);
+        assert_eq!(data["entries"][0]["seq"], 1);
+        assert_eq!(data["next_seq"], 5);
+        assert_eq!(data["has_more"], true);
+
+        // Caught up: nothing new until the head moves
+        let response = handler(Some(serde_json::json!({"from": 5})), &mgr).await.unwrap();
+        let data = response.data.unwrap();
+        assert_eq!(data["count"], 0);
+        assert_eq!(data["has_more"], false);
+
+        assert!(!handler(None, &mgr).await.unwrap().ok);
+    }
+}
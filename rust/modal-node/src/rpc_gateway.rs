@@ -0,0 +1,314 @@
+//! Read-only RPC gateway
+//!
+//! Nodes running as `gateway` serve the modal-rpc read methods straight from
+//! the datastore, through an RPC server in gateway mode: writes are refused,
+//! clients are rate limited and responses cached per block. See
+//! `modal_rpc::gateway` for the caching and headers.
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use modal_datastore::models::{Commit, Contract};
+use modal_datastore::{ConsensusLiveness, DatastoreManager};
+use modal_rpc::types::*;
+use modal_rpc::{GatewayConfig, RpcError, RpcHandler, RpcServer, RpcServerConfig};
+
+use crate::build_info::BuildInfo;
+use crate::chain::metrics::get_chain_tip;
+
+/// Answers read methods from a node's datastore
+pub struct NodeRpcHandler {
+    datastore_manager: Arc<Mutex<DatastoreManager>>,
+}
+
+impl NodeRpcHandler {
+    pub fn new(datastore_manager: Arc<Mutex<DatastoreManager>>) -> Self {
+        Self { datastore_manager }
+    }
+
+    /// A contract's commits in the order they were received
+    async fn commits(&self, contract_id: &str) -> Result<Vec<Commit>, RpcError> {
+        let mgr = self.datastore_manager.lock().await;
+        if Contract::find_by_id_multi(&mgr, contract_id).await.map_err(internal)?.is_none() {
+            return Err(RpcError::ContractNotFound(contract_id.to_string()));
+        }
+        let mut commits = Commit::find_by_contract_multi(&mgr, contract_id).await.map_err(internal)?;
+        commits.sort_by(|a, b| (a.timestamp, &a.commit_id).cmp(&(b.timestamp, &b.commit_id)));
+        Ok(commits)
+    }
+}
+
+fn internal(e: anyhow::Error) -> RpcError {
+    RpcError::InternalError(e.to_string())
+}
+
+/// The `{body, head}` of a stored commit
+fn commit_payload(commit: &Commit) -> Value {
+    serde_json::from_str(&commit.commit_data).unwrap_or(Value::Null)
+}
+
+fn commit_detail(commit: &Commit) -> CommitDetail {
+    let payload = commit_payload(commit);
+    let head = payload.get("head").cloned().unwrap_or_else(|| json!({}));
+    CommitDetail {
+        hash: commit.commit_id.clone(),
+        parent: head.get("parent").and_then(|p| p.as_str()).map(str::to_string),
+        commit_type: "commit".to_string(),
+        path: None,
+        timestamp: commit.timestamp,
+        signatures: head
+            .get("signatures")
+            .and_then(|s| s.as_object())
+            .map(|obj| {
+                obj.iter()
+                    .map(|(k, v)| SignatureInfo {
+                        public_key: k.clone(),
+                        signature: v.as_str().unwrap_or("").to_string(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        payload,
+    }
+}
+
+fn contract_state(commits: &[Commit]) -> Result<Value, RpcError> {
+    let state = crate::contract_export::replay(commits.iter().map(|c| c.commit_data.as_str())).map_err(internal)?;
+    Ok(json!(state))
+}
+
+#[async_trait]
+impl RpcHandler for NodeRpcHandler {
+    async fn get_health(&self) -> Result<HealthResponse, RpcError> {
+        Ok(HealthResponse {
+            status: "ok".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            node_type: NodeType::Network,
+        })
+    }
+
+    async fn get_version(&self) -> Result<String, RpcError> {
+        Ok(env!("CARGO_PKG_VERSION").to_string())
+    }
+
+    async fn get_build_info(&self) -> Result<BuildInfoResponse, RpcError> {
+        let info = BuildInfo::new(env!("CARGO_PKG_VERSION"), None);
+        Ok(BuildInfoResponse {
+            version: info.version,
+            git_commit: info.git_commit,
+            protocol_version: info.protocol_version,
+            min_protocol_version: info.min_protocol_version,
+            wire_version: info.wire_version,
+            features: info.features,
+        })
+    }
+
+    async fn get_block_height(&self) -> Result<BlockHeightResponse, RpcError> {
+        let mgr = self.datastore_manager.lock().await;
+        let tip = get_chain_tip(&mgr).await.map_err(internal)?;
+        Ok(BlockHeightResponse {
+            height: tip.as_ref().map(|b| b.index).unwrap_or(0),
+            hash: tip.as_ref().map(|b| b.hash.clone()),
+            timestamp: tip.map(|b| b.timestamp.max(0) as u64),
+        })
+    }
+
+    async fn get_contract(&self, params: GetContractParams) -> Result<ContractResponse, RpcError> {
+        let created_at = {
+            let mgr = self.datastore_manager.lock().await;
+            Contract::find_by_id_multi(&mgr, &params.contract_id)
+                .await
+                .map_err(internal)?
+                .map(|c| c.created_at)
+        };
+        let commits = self.commits(&params.contract_id).await?;
+
+        let commit_infos = params.include_commits.then(|| {
+            commits
+                .iter()
+                .map(|commit| {
+                    let detail = commit_detail(commit);
+                    CommitInfo {
+                        hash: detail.hash,
+                        parent: detail.parent,
+                        commit_type: detail.commit_type,
+                        timestamp: detail.timestamp,
+                        signer_count: detail.signatures.len() as u32,
+                    }
+                })
+                .collect()
+        });
+        let state = if params.include_state {
+            Some(contract_state(&commits)?)
+        } else {
+            None
+        };
+
+        Ok(ContractResponse {
+            id: params.contract_id,
+            head: commits.last().map(|c| c.commit_id.clone()),
+            commit_count: commits.len() as u64,
+            created_at,
+            updated_at: commits.last().map(|c| c.timestamp),
+            commits: commit_infos,
+            state,
+        })
+    }
+
+    async fn get_contract_state(&self, contract_id: &str) -> Result<Value, RpcError> {
+        contract_state(&self.commits(contract_id).await?)
+    }
+
+    async fn get_commits(&self, params: GetCommitsParams) -> Result<CommitsResponse, RpcError> {
+        let commits = self.commits(&params.contract_id).await?;
+        let position = |hash: &Option<String>| hash.as_ref().and_then(|h| commits.iter().position(|c| &c.commit_id == h));
+        let start = position(&params.after).map(|i| i + 1).unwrap_or(0);
+        let end = position(&params.before).unwrap_or(commits.len()).max(start);
+        let limit = params.limit.unwrap_or(100) as usize;
+
+        let page: Vec<CommitDetail> = commits[start..end].iter().take(limit).map(commit_detail).collect();
+        Ok(CommitsResponse {
+            contract_id: params.contract_id,
+            has_more: end - start > page.len(),
+            commits: page,
+        })
+    }
+
+    async fn get_commit(&self, contract_id: &str, hash: &str) -> Result<CommitDetail, RpcError> {
+        let keys = [
+            ("contract_id".to_string(), contract_id.to_string()),
+            ("commit_id".to_string(), hash.to_string()),
+        ]
+        .into_iter()
+        .collect();
+        let mgr = self.datastore_manager.lock().await;
+        let commit = Commit::find_one_multi(&mgr, keys)
+            .await
+            .map_err(internal)?
+            .ok_or_else(|| RpcError::CommitNotFound(hash.to_string()))?;
+        Ok(commit_detail(&commit))
+    }
+
+    async fn submit_commit(&self, _params: SubmitCommitParams) -> Result<SubmitCommitResponse, RpcError> {
+        Err(RpcError::MethodNotFound("submitCommit".to_string()))
+    }
+
+    async fn get_consensus_status(&self) -> Result<ConsensusStatusResponse, RpcError> {
+        let mgr = self.datastore_manager.lock().await;
+        let liveness = mgr.consensus_liveness().map_err(|e| internal(e.into()))?;
+        Ok(consensus_status(liveness))
+    }
+}
+
+/// A recorded consensus liveness as RPC returns it; "inactive" if the node
+/// isn't running consensus
+pub fn consensus_status(liveness: Option<ConsensusLiveness>) -> ConsensusStatusResponse {
+    let mode = liveness.as_ref().map(|l| l.mode.as_str()).unwrap_or("inactive").to_string();
+    let liveness = liveness.unwrap_or_default();
+    ConsensusStatusResponse {
+        mode,
+        validator_epoch: liveness.validator_epoch,
+        round: liveness.round,
+        last_certified_round: liveness.last_certified_round,
+        committee_size: liveness.committee_size,
+        quorum: liveness.quorum,
+        live_validators: liveness.live_validators,
+        mode_since: liveness.mode_since,
+        fallbacks: liveness.fallbacks,
+        updated_at: liveness.updated_at,
+    }
+}
+
+/// Start the read-only RPC gateway on the specified port
+pub async fn start_rpc_gateway(
+    port: u16,
+    gateway: GatewayConfig,
+    datastore_manager: Arc<Mutex<DatastoreManager>>,
+) -> Result<tokio::task::JoinHandle<()>, anyhow::Error> {
+    let config = RpcServerConfig {
+        port,
+        gateway: Some(gateway),
+        ..Default::default()
+    };
+    let server = RpcServer::new(NodeRpcHandler::new(datastore_manager), config);
+
+    let handle = tokio::spawn(async move {
+        if let Err(e) = server.run().await {
+            log::error!("RPC gateway stopped: {}", e);
+        }
+    });
+
+    Ok(handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use modal_common::canonical::sha256_hex;
+
+    async fn save_commit(mgr: &DatastoreManager, timestamp: u64, body: Value) -> String {
+        let commit_data = json!({"body": body, "head": {}}).to_string();
+        let commit = Commit {
+            contract_id: "c1".to_string(),
+            commit_id: sha256_hex(commit_data.as_bytes()),
+            commit_data,
+            timestamp,
+            in_batch: None,
+        };
+        commit.save_to_final(mgr).await.unwrap();
+        commit.commit_id
+    }
+
+    #[tokio::test]
+    async fn test_reads_contract_from_datastore() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        let contract = Contract {
+            contract_id: "c1".to_string(),
+            genesis: "{}".to_string(),
+            created_at: 1,
+        };
+        contract.save_to_final(&mgr).await.unwrap();
+        let first = save_commit(&mgr, 10, json!([{"method": "post", "path": "/data/n.number", "value": 1}])).await;
+        let second = save_commit(&mgr, 20, json!([{"method": "post", "path": "/data/n.number", "value": 2}])).await;
+        let handler = NodeRpcHandler::new(Arc::new(Mutex::new(mgr)));
+
+        let state = handler.get_contract_state("c1").await.unwrap();
+        assert_eq!(state["/data/n.number"], json!(2));
+
+        let page = handler
+            .get_commits(GetCommitsParams {
+                contract_id: "c1".to_string(),
+                limit: Some(1),
+                before: None,
+                after: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(page.commits[0].hash, first);
+        assert!(page.has_more);
+
+        assert_eq!(handler.get_commit("c1", &second).await.unwrap().hash, second);
+        assert!(matches!(handler.get_contract_state("missing").await, Err(RpcError::ContractNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_consensus_status() {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        let handler = NodeRpcHandler::new(Arc::new(Mutex::new(mgr)));
+        assert_eq!(handler.get_consensus_status().await.unwrap().mode, "inactive");
+
+        handler.datastore_manager.lock().await.save_consensus_liveness(&ConsensusLiveness {
+            mode: modal_datastore::ConsensusMode::MiningOnly,
+            committee_size: 4,
+            quorum: 3,
+            live_validators: 2,
+            fallbacks: 1,
+            ..Default::default()
+        }).unwrap();
+        let status = handler.get_consensus_status().await.unwrap();
+        assert_eq!(status.mode, "mining_only");
+        assert_eq!((status.live_validators, status.quorum), (2, 3));
+    }
+}
@@ -0,0 +1,125 @@
+//! Rolling history of node status samples.
+//!
+//! The status page only shows a snapshot of the node. This module keeps a
+//! fixed-size ring buffer of periodic samples so the status server can expose
+//! `/api/history.json` and render sparkline charts.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// A single point-in-time sample of node status
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusSample {
+    /// Unix timestamp (seconds) when the sample was taken
+    pub timestamp: i64,
+    pub block_height: u64,
+    pub connected_peers: usize,
+    pub difficulty: u128,
+    pub miner_hashrate: f64,
+    pub network_hashrate: f64,
+}
+
+/// Fixed-capacity ring buffer of status samples (oldest first)
+#[derive(Debug, Clone)]
+pub struct StatusHistory {
+    samples: VecDeque<StatusSample>,
+    capacity: usize,
+}
+
+impl StatusHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Append a sample, evicting the oldest one when full
+    pub fn push(&mut self, sample: StatusSample) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn latest(&self) -> Option<&StatusSample> {
+        self.samples.back()
+    }
+
+    /// All samples, oldest first
+    pub fn samples(&self) -> Vec<StatusSample> {
+        self.samples.iter().cloned().collect()
+    }
+
+    /// Extract a single numeric series (oldest first), e.g. for charting
+    pub fn series<F>(&self, f: F) -> Vec<f64>
+    where
+        F: Fn(&StatusSample) -> f64,
+    {
+        self.samples.iter().map(f).collect()
+    }
+}
+
+impl Default for StatusHistory {
+    fn default() -> Self {
+        Self::new(crate::constants::STATUS_HISTORY_CAPACITY)
+    }
+}
+
+/// Wrapper for thread-safe access to status history
+pub type SharedStatusHistory = Arc<RwLock<StatusHistory>>;
+
+/// Create a new shared status history with the default capacity
+pub fn create_shared_history() -> SharedStatusHistory {
+    Arc::new(RwLock::new(StatusHistory::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(height: u64) -> StatusSample {
+        StatusSample {
+            timestamp: height as i64 * 30,
+            block_height: height,
+            connected_peers: 3,
+            difficulty: 100 + height as u128,
+            miner_hashrate: 10.0,
+            network_hashrate: 40.0,
+        }
+    }
+
+    #[test]
+    fn test_ring_buffer_evicts_oldest() {
+        let mut history = StatusHistory::new(3);
+        for h in 0..5 {
+            history.push(sample(h));
+        }
+        assert_eq!(history.len(), 3);
+        let heights: Vec<u64> = history.samples().iter().map(|s| s.block_height).collect();
+        assert_eq!(heights, vec![2, 3, 4]);
+        assert_eq!(history.latest().unwrap().block_height, 4);
+    }
+
+    #[test]
+    fn test_series_extraction() {
+        let mut history = StatusHistory::new(10);
+        history.push(sample(1));
+        history.push(sample(2));
+        assert_eq!(history.series(|s| s.difficulty as f64), vec![101.0, 102.0]);
+    }
+}
@@ -5,24 +5,36 @@
 
 use std::sync::Arc;
 use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
 use warp::Filter;
 
-use modal_datastore::DatastoreManager;
+use modal_common::difficulty::DifficultyConfig;
+use modal_common::eras::EraSchedule;
+use modal_datastore::{ConsensusLiveness, ConsensusMode, DatastoreManager};
 use modal_datastore::models::MinerBlock;
 use modal_datastore::models::validator::ValidatorBlock;
 
 use crate::constants::{
-    BLOCKS_PER_EPOCH, STATUS_PAGE_REFRESH_SECS, STATUS_RECENT_BLOCKS_COUNT,
+    STATUS_PAGE_REFRESH_SECS, STATUS_RECENT_BLOCKS_COUNT,
     STATUS_FIRST_BLOCKS_COUNT, STATUS_EPOCHS_TO_SHOW, NETWORK_HASHRATE_SAMPLE_SIZE,
-    STATUS_FINALIZED_ROUNDS_TO_SHOW, BFT_THRESHOLD_PERCENTAGE,
+    STATUS_FINALIZED_ROUNDS_TO_SHOW, BFT_THRESHOLD_PERCENTAGE, STATUS_HISTORY_SAMPLE_SECS,
+    STATUS_CHART_WINDOW_SECS, METRICS_PRUNE_INTERVAL_SECS,
+    METRIC_BLOCK_HEIGHT, METRIC_DIFFICULTY, METRIC_NETWORK_HASHRATE, METRIC_MINER_HASHRATE,
+    METRIC_CONNECTED_PEERS, METRIC_CONSENSUS_ROUND, METRIC_CACHE_HIT_RATE,
 };
+use crate::bootstrapper_health::unix_now;
+use crate::status_history::{SharedStatusHistory, StatusSample};
+use crate::autoupgrade::rollout::{AutoupgradeStatus, SharedAutoupgradeStatus};
+use crate::anomaly_monitor::SharedAnomalyDetector;
+use crate::reqres::dispatcher::RequestDispatcher;
 use crate::templates::{
     render_block_row, render_listener_item,
     render_block_0_info, render_block_0_not_found, render_empty_blocks_message,
     render_empty_peers_message, render_epoch_nominees_section, render_nominee_row,
     render_finalized_rounds_section, render_finalized_round_row, render_empty_finalized_rounds,
-    render_status_page, StatusPageVars,
+    render_status_page, render_sparkline, render_history_charts, StatusPageVars,
+    render_alerts_section, render_alert_row, render_empty_alerts,
 };
 
 /// Start HTTP status server on the specified port
@@ -30,11 +42,16 @@ pub async fn start_status_server(
     port: u16,
     peerid: libp2p_identity::PeerId,
     datastore_manager: Arc<Mutex<DatastoreManager>>,
-    swarm: Arc<Mutex<crate::swarm::NodeSwarm>>,
+    swarm: crate::swarm_driver::SwarmHandle,
     listeners: Vec<libp2p::Multiaddr>,
     mining_metrics: crate::mining_metrics::SharedMiningMetrics,
     network_name: String,
     role: String,
+    status_history: SharedStatusHistory,
+    autoupgrade_status: SharedAutoupgradeStatus,
+    anomaly_detector: SharedAnomalyDetector,
+    reqres_dispatcher: Arc<RequestDispatcher>,
+    expect_peers: bool,
 ) -> Result<tokio::task::JoinHandle<()>, anyhow::Error> {
     let status_route = warp::path::end()
         .and(warp::get())
@@ -45,13 +62,65 @@ pub async fn start_status_server(
         .and(with_mining_metrics(mining_metrics.clone()))
         .and(with_network_name(network_name.clone()))
         .and(with_role(role.clone()))
+        .and(with_autoupgrade_status(autoupgrade_status.clone()))
+        .and(with_anomaly_detector(anomaly_detector.clone()))
         .and_then(status_handler);
 
-    let routes = status_route;
+    let status_json_route = warp::path!("api" / "status.json")
+        .and(warp::get())
+        .and(with_peerid(peerid))
+        .and(with_datastore(datastore_manager.clone()))
+        .and(with_swarm(swarm.clone()))
+        .and(with_listeners(listeners.clone()))
+        .and(with_mining_metrics(mining_metrics.clone()))
+        .and(with_network_name(network_name.clone()))
+        .and(with_role(role.clone()))
+        .and(with_autoupgrade_status(autoupgrade_status.clone()))
+        .and(with_anomaly_detector(anomaly_detector.clone()))
+        .and_then(status_json_handler);
 
-    log::info!("Starting HTTP status server on http://0.0.0.0:{}", port);
+    let history_json_route = warp::path!("api" / "history.json")
+        .and(warp::get())
+        .and(with_status_history(status_history.clone()))
+        .and_then(history_json_handler);
+
+    let alerts_json_route = warp::path!("api" / "alerts.json")
+        .and(warp::get())
+        .and(with_anomaly_detector(anomaly_detector.clone()))
+        .and_then(alerts_json_handler);
 
-    let server = warp::serve(routes).bind(([0, 0, 0, 0], port));
+    let duties_json_route = warp::path!("api" / "duties.json")
+        .and(warp::get())
+        .and(with_peerid(peerid))
+        .and(with_datastore(datastore_manager.clone()))
+        .and_then(duties_json_handler);
+
+    let reqres_json_route = warp::path!("api" / "reqres.json")
+        .and(warp::get())
+        .and(warp::any().map(move || reqres_dispatcher.clone()))
+        .and_then(reqres_json_handler);
+
+    let ready_route = warp::path!("ready")
+        .and(warp::get())
+        .and(with_peerid(peerid))
+        .and(with_datastore(datastore_manager.clone()))
+        .and(with_swarm(swarm.clone()))
+        .and(warp::any().map(move || expect_peers))
+        .and_then(ready_handler);
+
+    let routes = status_route
+        .or(status_json_route)
+        .or(history_json_route)
+        .or(alerts_json_route)
+        .or(duties_json_route)
+        .or(reqres_json_route)
+        .or(ready_route);
+
+    // A node hiding behind a proxy doesn't serve its status on a public address
+    let host = if crate::proxy::outbound().is_some() { [127, 0, 0, 1] } else { [0, 0, 0, 0] };
+    log::info!("Starting HTTP status server on http://{}:{}", std::net::Ipv4Addr::from(host), port);
+
+    let server = warp::serve(routes).bind((host, port));
 
     let handle = tokio::spawn(async move {
         server.await;
@@ -66,6 +135,24 @@ fn with_mining_metrics(
     warp::any().map(move || mining_metrics.clone())
 }
 
+fn with_status_history(
+    status_history: SharedStatusHistory,
+) -> impl Filter<Extract = (SharedStatusHistory,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || status_history.clone())
+}
+
+fn with_autoupgrade_status(
+    autoupgrade_status: SharedAutoupgradeStatus,
+) -> impl Filter<Extract = (SharedAutoupgradeStatus,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || autoupgrade_status.clone())
+}
+
+fn with_anomaly_detector(
+    anomaly_detector: SharedAnomalyDetector,
+) -> impl Filter<Extract = (SharedAnomalyDetector,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || anomaly_detector.clone())
+}
+
 fn with_network_name(
     network_name: String,
 ) -> impl Filter<Extract = (String,), Error = std::convert::Infallible> + Clone {
@@ -92,8 +179,8 @@ fn with_datastore(
 }
 
 fn with_swarm(
-    swarm: Arc<Mutex<crate::swarm::NodeSwarm>>,
-) -> impl Filter<Extract = (Arc<Mutex<crate::swarm::NodeSwarm>>,), Error = std::convert::Infallible> + Clone
+    swarm: crate::swarm_driver::SwarmHandle,
+) -> impl Filter<Extract = (crate::swarm_driver::SwarmHandle,), Error = std::convert::Infallible> + Clone
 {
     warp::any().map(move || swarm.clone())
 }
@@ -104,21 +191,201 @@ fn with_listeners(
     warp::any().map(move || listeners.clone())
 }
 
+/// Machine-readable status snapshot served at `/api/status.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatusSummary {
+    pub peer_id: String,
+    pub version: String,
+    pub network_name: String,
+    pub role: String,
+    pub listeners: Vec<String>,
+    pub connected_peers: usize,
+    pub block_height: u64,
+    pub latest_block_hash: Option<String>,
+    pub total_miner_blocks: usize,
+    pub cumulative_difficulty: u128,
+    pub current_difficulty: String,
+    pub difficulty_algorithm: String,
+    pub current_epoch: u64,
+    pub current_round: u64,
+    pub blocks_mined_by_node: usize,
+    pub miner_hashrate: f64,
+    pub miner_thread_hashrates: Vec<f64>,
+    pub network_hashrate: f64,
+    pub autoupgrade: Option<AutoupgradeStatus>,
+    /// Recent anomaly alerts, newest first
+    pub alerts: Vec<modal_observer::Alert>,
+    /// Consensus liveness, if this node runs consensus
+    pub consensus: Option<ConsensusLiveness>,
+}
+
+impl StatusSummary {
+    /// Build a summary from canonical miner blocks and live node counters
+    pub fn from_blocks(
+        peerid: libp2p_identity::PeerId,
+        miner_blocks: &[MinerBlock],
+        connected_peers: usize,
+        current_round: u64,
+        miner_hashrate: f64,
+        listeners: &[libp2p::Multiaddr],
+        network_name: String,
+        role: String,
+    ) -> Self {
+        let latest_block = miner_blocks.iter().max_by_key(|b| b.index);
+        let peerid_str = peerid.to_string();
+
+        Self {
+            peer_id: peerid_str.clone(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            network_name,
+            role,
+            listeners: listeners.iter().map(|l| l.to_string()).collect(),
+            connected_peers,
+            block_height: latest_block.map(|b| b.index).unwrap_or(0),
+            latest_block_hash: latest_block.map(|b| b.hash.clone()),
+            total_miner_blocks: miner_blocks.len(),
+            cumulative_difficulty: miner_blocks
+                .iter()
+                .filter_map(|block| block.target_difficulty.parse::<u128>().ok())
+                .sum(),
+            current_difficulty: latest_block
+                .map(|b| b.target_difficulty.clone())
+                .unwrap_or_else(|| "0".to_string()),
+            difficulty_algorithm: DifficultyConfig::default().to_string(),
+            current_epoch: latest_block.map(|b| b.epoch).unwrap_or(0),
+            current_round,
+            blocks_mined_by_node: miner_blocks
+                .iter()
+                .filter(|block| block.nominated_peer_id == peerid_str)
+                .count(),
+            miner_hashrate,
+            miner_thread_hashrates: Vec::new(),
+            network_hashrate: calculate_network_hashrate(miner_blocks),
+            autoupgrade: None,
+            alerts: Vec::new(),
+            consensus: None,
+        }
+    }
+
+    /// Values to record in the metrics history
+    pub fn to_metrics(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            (METRIC_BLOCK_HEIGHT, self.block_height as f64),
+            (METRIC_DIFFICULTY, self.current_difficulty.parse::<f64>().unwrap_or(0.0)),
+            (METRIC_NETWORK_HASHRATE, self.network_hashrate),
+            (METRIC_MINER_HASHRATE, self.miner_hashrate),
+            (METRIC_CONNECTED_PEERS, self.connected_peers as f64),
+            (METRIC_CONSENSUS_ROUND, self.current_round as f64),
+        ]
+    }
+
+    /// Convert to a history sample taken at `timestamp`
+    pub fn to_sample(&self, timestamp: i64) -> StatusSample {
+        StatusSample {
+            timestamp,
+            block_height: self.block_height,
+            connected_peers: self.connected_peers,
+            difficulty: self.current_difficulty.parse().unwrap_or(0),
+            miner_hashrate: self.miner_hashrate,
+            network_hashrate: self.network_hashrate,
+        }
+    }
+}
+
+/// Collect a status summary from the live node state
+pub async fn collect_status_summary(
+    peerid: libp2p_identity::PeerId,
+    datastore_manager: &Arc<Mutex<DatastoreManager>>,
+    swarm: &crate::swarm_driver::SwarmHandle,
+    listeners: &[libp2p::Multiaddr],
+    mining_metrics: &crate::mining_metrics::SharedMiningMetrics,
+    network_name: String,
+    role: String,
+) -> Result<StatusSummary, anyhow::Error> {
+    let connected_peers = swarm.connected_peers().await?.len();
+    let (miner_hashrate, miner_thread_hashrates) = {
+        let metrics = mining_metrics.read().await;
+        (metrics.average_hashrate(), metrics.thread_hashrates.clone())
+    };
+
+    let mgr = datastore_manager.lock().await;
+    let current_round = mgr.get_current_round().await.unwrap_or(0);
+    let miner_blocks = MinerBlock::find_all_canonical_multi(&mgr).await.unwrap_or_default();
+    let difficulty_config = mgr
+        .get_network_config()
+        .await
+        .ok()
+        .flatten()
+        .and_then(|config| DifficultyConfig::from_network_config(&config).ok())
+        .unwrap_or_default();
+    let consensus = mgr.consensus_liveness().ok().flatten();
+    drop(mgr);
+
+    let mut summary = StatusSummary::from_blocks(
+        peerid,
+        &miner_blocks,
+        connected_peers,
+        current_round,
+        miner_hashrate,
+        listeners,
+        network_name,
+        role,
+    );
+    summary.difficulty_algorithm = difficulty_config.to_string();
+    summary.miner_thread_hashrates = miner_thread_hashrates;
+    summary.consensus = consensus;
+    Ok(summary)
+}
+
+/// Build the sparkline charts row from the last day of the metrics store
+async fn build_history_charts_html(datastore_manager: &Arc<Mutex<DatastoreManager>>) -> String {
+    let now = unix_now();
+    let mgr = datastore_manager.lock().await;
+    let load = |name: &str| -> Vec<f64> {
+        mgr.node_metrics()
+            .query(name, now - STATUS_CHART_WINDOW_SECS, now, now)
+            .map(|s| s.points.iter().map(|p| p.mean()).collect())
+            .unwrap_or_default()
+    };
+    let heights = load(METRIC_BLOCK_HEIGHT);
+    let peers = load(METRIC_CONNECTED_PEERS);
+    let difficulty = load(METRIC_DIFFICULTY);
+    let network_hashrate = load(METRIC_NETWORK_HASHRATE);
+    drop(mgr);
+    if heights.is_empty() {
+        return String::new();
+    }
+    let latest = |values: &[f64]| values.last().copied().unwrap_or(0.0);
+
+    let charts = [
+        render_sparkline("Block Height", &heights, &(latest(&heights) as u64).to_string()),
+        render_sparkline("Peers", &peers, &(latest(&peers).round() as u64).to_string()),
+        render_sparkline("Difficulty", &difficulty, &(latest(&difficulty) as u128).to_string()),
+        render_sparkline(
+            "Network Hashrate",
+            &network_hashrate,
+            &format!("{} H/s", format_hashrate(latest(&network_hashrate))),
+        ),
+    ];
+
+    render_history_charts(&charts.join("\n            "))
+}
+
 /// Generate status HTML content
 pub async fn generate_status_html(
     peerid: libp2p_identity::PeerId,
     datastore_manager: Arc<Mutex<DatastoreManager>>,
-    swarm: Arc<Mutex<crate::swarm::NodeSwarm>>,
+    swarm: crate::swarm_driver::SwarmHandle,
     listeners: Vec<libp2p::Multiaddr>,
     mining_metrics: crate::mining_metrics::SharedMiningMetrics,
     network_name: String,
     role: String,
+    autoupgrade_status: SharedAutoupgradeStatus,
+    anomaly_detector: SharedAnomalyDetector,
 ) -> Result<String, anyhow::Error> {
     // Get connected peers information
-    let peer_info = {
-        let swarm_lock = swarm.lock().await;
-        swarm_lock.connected_peers().cloned().collect::<Vec<_>>()
-    };
+    let peer_info = swarm.connected_peers().await?;
     let connected_peers = peer_info.len();
     
     // Get node status information
@@ -128,36 +395,25 @@ pub async fn generate_status_html(
     
     // Get miner blocks information
     let miner_blocks = MinerBlock::find_all_canonical_multi(&mgr).await.unwrap_or_default();
-    let total_miner_blocks = miner_blocks.len();
-    
-    // Get latest block for current difficulty
-    let latest_block = miner_blocks.iter().max_by_key(|b| b.index);
-    let current_difficulty = latest_block
-        .map(|b| b.target_difficulty.clone())
-        .unwrap_or_else(|| "0".to_string());
-    let current_epoch = latest_block.map(|b| b.epoch).unwrap_or(0);
-    
-    // Calculate cumulative difficulty
-    let cumulative_difficulty: u128 = miner_blocks
-        .iter()
-        .filter_map(|block| block.target_difficulty.parse::<u128>().ok())
-        .sum();
-    
-    // Count blocks mined by this node
-    let peerid_str = peerid.to_string();
-    let blocks_mined_by_node = miner_blocks
-        .iter()
-        .filter(|block| block.nominated_peer_id == peerid_str)
-        .count();
-    
-    // Calculate network hashrate from recent blocks
-    let network_hashrate = calculate_network_hashrate(&miner_blocks);
     
     // Get miner hashrate (average over all mining activity)
     let miner_hashrate = {
         let metrics = mining_metrics.read().await;
         metrics.average_hashrate()
     };
+
+    // Summary values shared with /api/status.json
+    let summary = StatusSummary::from_blocks(
+        peerid,
+        &miner_blocks,
+        connected_peers,
+        current_round,
+        miner_hashrate,
+        &listeners,
+        network_name.clone(),
+        role.clone(),
+    );
+    let current_epoch = summary.current_epoch;
     
     // Get Block 0 (genesis block)
     let block_0 = MinerBlock::find_canonical_by_index_simple(&mgr, 0).await.ok().flatten();
@@ -179,10 +435,11 @@ pub async fn generate_status_html(
         .collect();
     
     // Calculate epoch nominees with shuffle order for previous epochs
-    let epoch_nominees_data = calculate_epoch_nominees(&miner_blocks, current_epoch);
+    let epoch_nominees_data = calculate_epoch_nominees(&miner_blocks, current_epoch, mgr.era_schedule());
     
     // Calculate finalized rounds data
     let finalized_rounds_data = calculate_finalized_rounds(&mgr, current_round).await;
+    let consensus_mode = describe_consensus(mgr.consensus_liveness().ok().flatten().as_ref(), unix_now());
 
     // Build peers list HTML
     let peers_html = if peer_info.is_empty() {
@@ -238,6 +495,10 @@ pub async fn generate_status_html(
     // Build finalized rounds HTML section
     let finalized_rounds_section = build_finalized_rounds_html(&finalized_rounds_data);
 
+    let history_charts_html = build_history_charts_html(&datastore_manager).await;
+    let autoupgrade_stage = autoupgrade_status.read().await.describe(unix_now());
+    let alerts_section = build_alerts_html(&anomaly_detector.lock().await.recent_alerts());
+
     // Build listeners HTML
     let listeners_html = listeners
         .iter()
@@ -249,8 +510,8 @@ pub async fn generate_status_html(
     let vars = StatusPageVars {
         refresh_interval: STATUS_PAGE_REFRESH_SECS,
         connected_peers,
-        total_miner_blocks,
-        cumulative_difficulty,
+        total_miner_blocks: summary.total_miner_blocks,
+        cumulative_difficulty: summary.cumulative_difficulty,
         peerid: peerid.to_string(),
         network_name,
         role,
@@ -259,10 +520,11 @@ pub async fn generate_status_html(
         latest_round,
         block_0_html,
         peers_html,
-        blocks_mined_by_node,
-        current_difficulty,
-        miner_hashrate: format_hashrate(miner_hashrate),
-        network_hashrate: format_hashrate(network_hashrate),
+        blocks_mined_by_node: summary.blocks_mined_by_node,
+        current_difficulty: summary.current_difficulty.clone(),
+        difficulty_algorithm: summary.difficulty_algorithm.clone(),
+        miner_hashrate: format_hashrate(summary.miner_hashrate),
+        network_hashrate: format_hashrate(summary.network_hashrate),
         recent_blocks_count: STATUS_RECENT_BLOCKS_COUNT,
         blocks_html,
         first_blocks_count: STATUS_FIRST_BLOCKS_COUNT,
@@ -271,11 +533,46 @@ pub async fn generate_status_html(
         completed_epochs: current_epoch,
         epoch_nominees_sections,
         finalized_rounds_section,
+        history_charts_html,
+        autoupgrade_stage,
+        consensus_mode,
+        alerts_section,
     };
 
     Ok(render_status_page(vars))
 }
 
+/// One line on how the chain is being finalized
+fn describe_consensus(liveness: Option<&ConsensusLiveness>, now: i64) -> String {
+    match liveness {
+        None => "not running".to_string(),
+        Some(l) if l.mode == ConsensusMode::MiningOnly => {
+            let stalled_for = (now - l.mode_since).max(0);
+            format!(
+                "mining-only for {}h {}m · {}/{} validators live, {} needed",
+                stalled_for / 3600,
+                (stalled_for % 3600) / 60,
+                l.live_validators,
+                l.committee_size,
+                l.quorum
+            )
+        }
+        Some(l) => format!("hybrid · round {} · {}/{} validators live", l.round, l.live_validators, l.committee_size),
+    }
+}
+
+/// Build the anomaly alerts section
+fn build_alerts_html(alerts: &[modal_observer::Alert]) -> String {
+    if alerts.is_empty() {
+        return render_alerts_section(&render_empty_alerts());
+    }
+    let rows: Vec<String> = alerts
+        .iter()
+        .map(|alert| render_alert_row(alert.severity.as_str(), alert.kind.as_str(), alert.block_index, &alert.message))
+        .collect();
+    render_alerts_section(&rows.join("\n                        "))
+}
+
 /// Build HTML for a list of blocks
 fn build_blocks_html(
     blocks: &[MinerBlock],
@@ -313,6 +610,7 @@ fn build_blocks_html(
 fn calculate_epoch_nominees(
     miner_blocks: &[MinerBlock],
     current_epoch: u64,
+    era_schedule: &EraSchedule,
 ) -> Vec<(u64, Vec<(usize, String, String, u64)>)> {
     let mut epoch_nominees_data = Vec::new();
     
@@ -324,8 +622,9 @@ fn calculate_epoch_nominees(
     
         for epoch_offset in 1..=epochs_to_show {
             let epoch = current_epoch - epoch_offset;
-            let epoch_start = epoch * BLOCKS_PER_EPOCH;
-            let epoch_end = epoch_start + BLOCKS_PER_EPOCH;
+            let epoch_length = era_schedule.epoch_length(epoch);
+            let epoch_start = era_schedule.epoch_start(epoch);
+            let epoch_end = epoch_start + epoch_length;
             
             // Get all blocks from this epoch
             let epoch_blocks: Vec<&MinerBlock> = miner_blocks
@@ -334,14 +633,11 @@ fn calculate_epoch_nominees(
                 .collect();
             
             // Only process complete epochs
-            if epoch_blocks.len() == BLOCKS_PER_EPOCH as usize {
+            if epoch_blocks.len() == epoch_length as usize {
                 // Calculate XOR seed from all nonces
-                let mut seed = 0u64;
-                for block in &epoch_blocks {
-                    if let Ok(nonce) = block.nonce.parse::<u128>() {
-                        seed ^= nonce as u64;
-                    }
-                }
+                let seed = modal_miner::epoch::epoch_seed(
+                    epoch_blocks.iter().filter_map(|block| block.nonce.parse::<u128>().ok()),
+                );
                 
                 // Get shuffled indices using Fisher-Yates
                 let shuffled_indices = modal_common::shuffle::fisher_yates_shuffle(seed, epoch_blocks.len());
@@ -470,18 +766,100 @@ fn build_finalized_rounds_html(finalized_rounds_data: &[RoundFinalizationData])
 async fn status_handler(
     peerid: libp2p_identity::PeerId,
     datastore_manager: Arc<Mutex<DatastoreManager>>,
-    swarm: Arc<Mutex<crate::swarm::NodeSwarm>>,
+    swarm: crate::swarm_driver::SwarmHandle,
     listeners: Vec<libp2p::Multiaddr>,
     mining_metrics: crate::mining_metrics::SharedMiningMetrics,
     network_name: String,
     role: String,
+    autoupgrade_status: SharedAutoupgradeStatus,
+    anomaly_detector: SharedAnomalyDetector,
 ) -> Result<impl warp::Reply, warp::Rejection> {
-    let html = generate_status_html(peerid, datastore_manager, swarm, listeners, mining_metrics, network_name, role)
+    let html = generate_status_html(peerid, datastore_manager, swarm, listeners, mining_metrics, network_name, role, autoupgrade_status, anomaly_detector)
         .await
         .map_err(|_| warp::reject::not_found())?;
     Ok(warp::reply::html(html))
 }
 
+async fn status_json_handler(
+    peerid: libp2p_identity::PeerId,
+    datastore_manager: Arc<Mutex<DatastoreManager>>,
+    swarm: crate::swarm_driver::SwarmHandle,
+    listeners: Vec<libp2p::Multiaddr>,
+    mining_metrics: crate::mining_metrics::SharedMiningMetrics,
+    network_name: String,
+    role: String,
+    autoupgrade_status: SharedAutoupgradeStatus,
+    anomaly_detector: SharedAnomalyDetector,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut summary = collect_status_summary(peerid, &datastore_manager, &swarm, &listeners, &mining_metrics, network_name, role)
+        .await
+        .map_err(|_| warp::reject::not_found())?;
+    summary.autoupgrade = Some(autoupgrade_status.read().await.clone());
+    summary.alerts = anomaly_detector.lock().await.recent_alerts();
+    Ok(warp::reply::json(&summary))
+}
+
+async fn alerts_json_handler(
+    anomaly_detector: SharedAnomalyDetector,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let alerts = anomaly_detector.lock().await.recent_alerts();
+    Ok(warp::reply::json(&serde_json::json!({ "alerts": alerts })))
+}
+
+async fn reqres_json_handler(
+    reqres_dispatcher: Arc<RequestDispatcher>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&reqres_dispatcher.metrics()))
+}
+
+async fn duties_json_handler(
+    peerid: libp2p_identity::PeerId,
+    datastore_manager: Arc<Mutex<DatastoreManager>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mgr = datastore_manager.lock().await;
+    let calendar = crate::duties::duty_calendar(&mgr, &peerid.to_string())
+        .await
+        .map_err(|_| warp::reject::not_found())?;
+    Ok(warp::reply::json(&calendar))
+}
+
+async fn ready_handler(
+    peerid: libp2p_identity::PeerId,
+    datastore_manager: Arc<Mutex<DatastoreManager>>,
+    swarm: crate::swarm_driver::SwarmHandle,
+    expect_peers: bool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let connected_peers = swarm.connected_peers().await.map(|peers| peers.len()).unwrap_or(0);
+    let mgr = datastore_manager.lock().await;
+    let readiness = crate::readiness::check_readiness(&mgr, &peerid.to_string(), connected_peers, expect_peers, unix_now())
+        .await
+        .unwrap_or_else(|e| crate::readiness::Readiness {
+            ready: false,
+            checks: vec![crate::readiness::ReadinessCheck {
+                name: "datastore".to_string(),
+                ok: false,
+                detail: e.to_string(),
+            }],
+        });
+    let status = if readiness.ready {
+        warp::http::StatusCode::OK
+    } else {
+        warp::http::StatusCode::SERVICE_UNAVAILABLE
+    };
+    Ok(warp::reply::with_status(warp::reply::json(&readiness), status))
+}
+
+async fn history_json_handler(
+    status_history: SharedStatusHistory,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let history = status_history.read().await;
+    Ok(warp::reply::json(&serde_json::json!({
+        "interval_secs": STATUS_HISTORY_SAMPLE_SECS,
+        "capacity": history.capacity(),
+        "samples": history.samples(),
+    })))
+}
+
 /// Calculate network hashrate based on recent blocks
 /// Uses the difficulty and block times to estimate the network's total mining power
 fn calculate_network_hashrate(miner_blocks: &[MinerBlock]) -> f64 {
@@ -535,7 +913,7 @@ fn calculate_network_hashrate(miner_blocks: &[MinerBlock]) -> f64 {
 }
 
 /// Format hashrate for display (with K, M, G, T suffixes)
-fn format_hashrate(hashrate: f64) -> String {
+pub fn format_hashrate(hashrate: f64) -> String {
     if hashrate == 0.0 {
         return "0".to_string();
     }
@@ -558,11 +936,13 @@ pub async fn start_status_html_writer(
     dir: PathBuf,
     peerid: libp2p_identity::PeerId,
     datastore_manager: Arc<Mutex<DatastoreManager>>,
-    swarm: Arc<Mutex<crate::swarm::NodeSwarm>>,
+    swarm: crate::swarm_driver::SwarmHandle,
     listeners: Vec<libp2p::Multiaddr>,
     mining_metrics: crate::mining_metrics::SharedMiningMetrics,
     network_name: String,
     role: String,
+    autoupgrade_status: SharedAutoupgradeStatus,
+    anomaly_detector: SharedAnomalyDetector,
     mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
 ) -> Result<tokio::task::JoinHandle<()>, anyhow::Error> {
     // Create the directory if it doesn't exist
@@ -583,6 +963,8 @@ pub async fn start_status_html_writer(
                         mining_metrics.clone(),
                         network_name.clone(),
                         role.clone(),
+                        autoupgrade_status.clone(),
+                        anomaly_detector.clone(),
                     ).await {
                         Ok(html) => {
                             let index_path = dir.join("index.html");
@@ -607,3 +989,63 @@ pub async fn start_status_html_writer(
 
     Ok(handle)
 }
+
+/// Start the status sampler task that periodically records history samples
+pub fn start_status_sampler(
+    peerid: libp2p_identity::PeerId,
+    datastore_manager: Arc<Mutex<DatastoreManager>>,
+    swarm: crate::swarm_driver::SwarmHandle,
+    listeners: Vec<libp2p::Multiaddr>,
+    mining_metrics: crate::mining_metrics::SharedMiningMetrics,
+    network_name: String,
+    role: String,
+    status_history: SharedStatusHistory,
+    mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(STATUS_HISTORY_SAMPLE_SECS));
+        let mut last_prune = 0i64;
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    match collect_status_summary(
+                        peerid,
+                        &datastore_manager,
+                        &swarm,
+                        &listeners,
+                        &mining_metrics,
+                        network_name.clone(),
+                        role.clone(),
+                    ).await {
+                        Ok(summary) => {
+                            let now = unix_now();
+                            status_history.write().await.push(summary.to_sample(now));
+                            let mgr = datastore_manager.lock().await;
+                            let mut metrics = summary.to_metrics();
+                            metrics.push((METRIC_CACHE_HIT_RATE, mgr.cache_stats().hit_rate()));
+                            if let Err(e) = mgr.node_metrics().record(now, &metrics) {
+                                log::warn!("Failed to record metrics history: {}", e);
+                            }
+                            if now - last_prune >= METRICS_PRUNE_INTERVAL_SECS as i64 {
+                                last_prune = now;
+                                match mgr.node_metrics().prune(now) {
+                                    Ok(pruned) if pruned > 0 => log::debug!("Pruned {} expired metrics points", pruned),
+                                    Ok(_) => {}
+                                    Err(e) => log::warn!("Failed to prune metrics history: {}", e),
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            log::warn!("Failed to collect status sample: {}", e);
+                        }
+                    }
+                }
+                _ = shutdown_rx.recv() => {
+                    log::info!("Status sampler task shutting down");
+                    break;
+                }
+            }
+        }
+    })
+}
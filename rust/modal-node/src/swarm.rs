@@ -7,17 +7,23 @@ use libp2p::{identify, identity};
 use libp2p::{swarm::NetworkBehaviour, swarm::Swarm, SwarmBuilder};
 use libp2p::kad;
 use libp2p::gossipsub;
+use libp2p::core::transport::MemoryTransport;
+use libp2p::core::{upgrade, Transport};
 use std::time::Duration;
 
+use crate::contract_sync;
 use crate::reqres;
 // use crate::gossip;
 
 #[derive(NetworkBehaviour)]
 pub struct NodeBehaviour {
     // pub stream: libp2p_stream::Behaviour,
+    pub ban_list: crate::ban_list::Behaviour,
+    pub peer_slots: crate::peer_slots::Behaviour,
     pub ping: ping::Behaviour,
     pub identify: identify::Behaviour,
     pub reqres: reqres::Behaviour,
+    pub contract_sync: contract_sync::Behaviour,
     pub gossipsub: gossipsub::Behaviour,
     pub kademlia: kad::Behaviour<kad::store::MemoryStore>,
 }
@@ -25,18 +31,49 @@ pub struct NodeBehaviour {
 pub type NodeSwarm = Swarm<NodeBehaviour>;
 
 pub async fn create_swarm(local_key: identity::Keypair) -> Result<NodeSwarm> {
-    create_swarm_with_metadata(local_key, None, None).await
+    create_swarm_with_metadata(local_key, None, None, None, None).await
 }
 
 pub async fn create_swarm_with_status_url(local_key: identity::Keypair, status_url: Option<String>) -> Result<NodeSwarm> {
-    create_swarm_with_metadata(local_key, status_url, None).await
+    create_swarm_with_metadata(local_key, status_url, None, None, None).await
 }
 
-pub async fn create_swarm_with_metadata(local_key: identity::Keypair, status_url: Option<String>, role: Option<String>) -> Result<NodeSwarm> {
+pub async fn create_swarm_with_metadata(
+    local_key: identity::Keypair,
+    status_url: Option<String>,
+    role: Option<String>,
+    genesis: Option<String>,
+    manifest: Option<&modal_datastore::models::NodeManifest>,
+) -> Result<NodeSwarm> {
+    let behaviour = create_behaviour(&local_key, status_url, role, genesis, manifest)?;
+    create_swarm_with_behaviours(local_key, behaviour).await
+}
+
+/// Like `create_swarm_with_metadata`, but over the in-process memory
+/// transport, so it can only reach swarms in the same process
+pub async fn create_memory_swarm_with_metadata(
+    local_key: identity::Keypair,
+    status_url: Option<String>,
+    role: Option<String>,
+    genesis: Option<String>,
+    manifest: Option<&modal_datastore::models::NodeManifest>,
+) -> Result<NodeSwarm> {
+    let behaviour = create_behaviour(&local_key, status_url, role, genesis, manifest)?;
+    create_memory_swarm_with_behaviours(local_key, behaviour).await
+}
+
+fn create_behaviour(
+    local_key: &identity::Keypair,
+    status_url: Option<String>,
+    role: Option<String>,
+    genesis: Option<String>,
+    manifest: Option<&modal_datastore::models::NodeManifest>,
+) -> Result<NodeBehaviour> {
     // let stream_behaviour = libp2p_stream::Behaviour::new();
 
-    // Create agent version string that includes status_url and role if provided
-    // Format: "modal-node/0.1.0;status_url=https://...;role=Miner"
+    // Create agent version string that includes status_url, role and genesis block hash if provided,
+    // the binary gossip format this node reads, the message protocol versions and features it speaks and its signed manifest
+    // Format: "modal-node/0.1.0;status_url=https://...;role=Miner;genesis=...;wire=cbor1;proto=1;min_proto=1;features=...;manifest=..."
     let mut agent_parts = vec!["modal-node/0.1.0".to_string()];
     if let Some(url) = status_url {
         agent_parts.push(format!("status_url={}", url));
@@ -44,6 +81,15 @@ pub async fn create_swarm_with_metadata(local_key: identity::Keypair, status_url
     if let Some(r) = role {
         agent_parts.push(format!("role={}", r));
     }
+    if let Some(hash) = genesis {
+        agent_parts.push(format!("genesis={}", hash));
+    }
+    agent_parts.push(format!("wire={}", crate::gossip::wire::WIRE_VERSION));
+    agent_parts.push(format!("proto={}", crate::constants::MESSAGE_PROTOCOL_VERSION));
+    agent_parts.extend(crate::build_info::agent_parts());
+    if let Some(manifest) = manifest {
+        agent_parts.push(crate::manifest::encode(manifest)?);
+    }
     let agent_version = agent_parts.join(";");
 
     let identify_behaviour = identify::Behaviour::new(
@@ -54,11 +100,21 @@ pub async fn create_swarm_with_metadata(local_key: identity::Keypair, status_url
     let ping_behaviour = ping::Behaviour::new(ping::Config::new());
 
     let reqres_behaviour = reqres::Behaviour::new(
-        [(swarm::StreamProtocol::new(reqres::PROTOCOL), request_response::ProtocolSupport::Full)],
+        // Binary first, so peers that support it pick it
+        [
+            (swarm::StreamProtocol::new(reqres::BINARY_PROTOCOL), request_response::ProtocolSupport::Full),
+            (swarm::StreamProtocol::new(reqres::PROTOCOL), request_response::ProtocolSupport::Full),
+        ],
         request_response::Config::default()
             .with_request_timeout(Duration::from_secs(60)) // Longer timeout for large transfers
     );
 
+    let contract_sync_behaviour = contract_sync::Behaviour::new(
+        [(swarm::StreamProtocol::new(contract_sync::PROTOCOL), request_response::ProtocolSupport::Full)],
+        request_response::Config::default()
+            .with_request_timeout(Duration::from_secs(60))
+    );
+
 
     let peer_id = local_key.clone().public().to_peer_id();
     let kademlia_behaviour = kad::Behaviour::new(
@@ -78,15 +134,31 @@ pub async fn create_swarm_with_metadata(local_key: identity::Keypair, status_url
 
     let behaviour = NodeBehaviour {
         // stream: stream_behaviour,
+        ban_list: crate::ban_list::Behaviour::default(),
+        peer_slots: crate::peer_slots::Behaviour::default(),
         ping: ping_behaviour,
         identify: identify_behaviour,
         reqres: reqres_behaviour,
+        contract_sync: contract_sync_behaviour,
         gossipsub: gossipsub_behaviour,
         kademlia: kademlia_behaviour,
     };
-    let swarm = create_swarm_with_behaviours(local_key, behaviour).await?;
 
-    Ok(swarm)
+    Ok(behaviour)
+}
+
+/// Like `create_swarm_with_metadata`, but dialing every peer through a
+/// SOCKS5 proxy; see `proxy`
+pub async fn create_proxied_swarm_with_metadata(
+    local_key: identity::Keypair,
+    proxy: crate::proxy::ProxyConfig,
+    status_url: Option<String>,
+    role: Option<String>,
+    genesis: Option<String>,
+    manifest: Option<&modal_datastore::models::NodeManifest>,
+) -> Result<NodeSwarm> {
+    let behaviour = create_behaviour(&local_key, status_url, role, genesis, manifest)?;
+    create_proxied_swarm_with_behaviours(local_key, proxy, behaviour).await
 }
 
 pub async fn create_swarm_with_behaviours(
@@ -112,3 +184,53 @@ pub async fn create_swarm_with_behaviours(
     let swarm = swarm.build();
     Ok(swarm)
 }
+
+pub async fn create_proxied_swarm_with_behaviours(
+    local_key: identity::Keypair,
+    proxy: crate::proxy::ProxyConfig,
+    behaviour: NodeBehaviour,
+) -> Result<NodeSwarm> {
+    let swarm = SwarmBuilder::with_existing_identity(local_key)
+        .with_tokio()
+        .with_other_transport(|key| {
+            // No DNS transport, so names reach the proxy unresolved
+            let websocket = libp2p::websocket::WsConfig::new(crate::proxy::Transport::new(proxy.clone()));
+            Ok::<_, Box<dyn std::error::Error + Send + Sync>>(
+                websocket
+                    .or_transport(crate::proxy::Transport::new(proxy))
+                    .upgrade(upgrade::Version::V1)
+                    .authenticate(libp2p::noise::Config::new(key)?)
+                    .multiplex(libp2p::yamux::Config::default())
+                    .boxed(),
+            )
+        })?
+        .with_behaviour(|_key| behaviour)?
+        .with_swarm_config(|cfg| {
+            cfg.with_idle_connection_timeout(Duration::from_secs(60))
+        })
+        .build();
+    Ok(swarm)
+}
+
+pub async fn create_memory_swarm_with_behaviours(
+    local_key: identity::Keypair,
+    behaviour: NodeBehaviour,
+) -> Result<NodeSwarm> {
+    let swarm = SwarmBuilder::with_existing_identity(local_key)
+        .with_tokio()
+        .with_other_transport(|key| {
+            Ok::<_, Box<dyn std::error::Error + Send + Sync>>(
+                MemoryTransport::default()
+                    .upgrade(upgrade::Version::V1)
+                    .authenticate(libp2p::noise::Config::new(key)?)
+                    .multiplex(libp2p::yamux::Config::default())
+                    .boxed(),
+            )
+        })?
+        .with_behaviour(|_key| behaviour)?
+        .with_swarm_config(|cfg| {
+            cfg.with_idle_connection_timeout(Duration::from_secs(60))
+        })
+        .build();
+    Ok(swarm)
+}
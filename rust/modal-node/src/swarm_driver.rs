@@ -0,0 +1,360 @@
+//! The task that owns the node's swarm
+//!
+//! A swarm only makes progress while something polls it, and only one task
+//! can poll it at a time. Rather than share it behind a lock, one task owns
+//! it and everything else sends that task commands through a `SwarmHandle`:
+//! dial, publish, send a request and get its response back on a oneshot, or
+//! run a closure against the swarm for anything rarer. The driver keeps
+//! polling the swarm between commands, so nobody holds it while waiting on a
+//! peer, and it answers each request's caller itself, so no response or
+//! outbound failure is lost to whoever happened to be polling.
+//!
+//! Events the driver doesn't consume go to the receiver taken with
+//! `SwarmHandle::events`, which is the networking task once it runs.
+
+use anyhow::{anyhow, Result};
+use futures::prelude::*;
+use libp2p::gossipsub::{IdentTopic, MessageId};
+use libp2p::multiaddr::Protocol;
+use libp2p::request_response::{self, OutboundRequestId, ResponseChannel};
+use libp2p::swarm::SwarmEvent;
+use libp2p::{Multiaddr, PeerId};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::contract_sync::{self, ContractSyncRequest, ContractSyncResponse};
+use crate::gossip::topics::TopicRouter;
+use crate::reqres;
+use crate::swarm::{NodeBehaviourEvent, NodeSwarm};
+
+pub type NodeSwarmEvent = SwarmEvent<NodeBehaviourEvent>;
+
+type Reply<T> = oneshot::Sender<Result<T>>;
+
+enum Command {
+    Dial { address: Multiaddr, reply: Reply<()> },
+    Connect { address: Multiaddr, peer_id: PeerId, reply: Reply<()> },
+    Disconnect { peer_id: PeerId, reply: Reply<()> },
+    Publish { topic: String, data: Vec<u8>, reply: Reply<MessageId> },
+    SendRequest { peer_id: PeerId, request: reqres::Request, reply: Reply<reqres::Response> },
+    SendContractSyncRequest { peer_id: PeerId, request: ContractSyncRequest, reply: Reply<ContractSyncResponse> },
+    Respond { channel: ResponseChannel<reqres::Response>, response: reqres::Response },
+    RespondContractSync { channel: ResponseChannel<ContractSyncResponse>, response: ContractSyncResponse },
+    Events { reply: oneshot::Sender<mpsc::UnboundedReceiver<NodeSwarmEvent>> },
+    Run(Box<dyn FnOnce(&mut NodeSwarm) + Send>),
+}
+
+/// Sends commands to the task driving a swarm; cheap to clone
+#[derive(Clone)]
+pub struct SwarmHandle {
+    commands: mpsc::UnboundedSender<Command>,
+    topics: Arc<TopicRouter>,
+}
+
+impl std::fmt::Debug for SwarmHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SwarmHandle").finish_non_exhaustive()
+    }
+}
+
+fn stopped() -> anyhow::Error {
+    anyhow!("Swarm driver stopped")
+}
+
+impl SwarmHandle {
+    /// Spawn a task driving `swarm` until every handle to it is dropped,
+    /// publishing and subscribing to gossip topics as `topics` routes them
+    pub fn spawn(swarm: NodeSwarm, topics: TopicRouter) -> Self {
+        let (commands, commands_rx) = mpsc::unbounded_channel();
+        tokio::spawn(Driver::new(swarm).run(commands_rx));
+        Self {
+            commands,
+            topics: Arc::new(topics),
+        }
+    }
+
+    /// How this network's gossip topics map to the wire
+    pub fn topics(&self) -> &TopicRouter {
+        &self.topics
+    }
+
+    fn send(&self, command: Command) -> Result<()> {
+        self.commands.send(command).map_err(|_| stopped())
+    }
+
+    async fn request<T>(&self, command: impl FnOnce(Reply<T>) -> Command) -> Result<T> {
+        let (reply, rx) = oneshot::channel();
+        self.send(command(reply))?;
+        rx.await.map_err(|_| stopped())?
+    }
+
+    /// Run `f` against the swarm on the driver task
+    pub async fn run<R: Send + 'static>(&self, f: impl FnOnce(&mut NodeSwarm) -> R + Send + 'static) -> Result<R> {
+        let (tx, rx) = oneshot::channel();
+        self.send(Command::Run(Box::new(move |swarm| {
+            let _ = tx.send(f(swarm));
+        })))?;
+        rx.await.map_err(|_| stopped())
+    }
+
+    /// Start dialing `address`, without waiting for the connection
+    pub async fn dial(&self, address: Multiaddr) -> Result<()> {
+        self.request(|reply| Command::Dial { address, reply }).await
+    }
+
+    /// Dial `address`, which must end in `/p2p`, and wait until connected
+    pub async fn connect(&self, address: Multiaddr) -> Result<PeerId> {
+        let Some(Protocol::P2p(peer_id)) = address.iter().last() else {
+            anyhow::bail!("Provided address must end in `/p2p` and include PeerID");
+        };
+        self.request(|reply| Command::Connect { address, peer_id, reply }).await?;
+        Ok(peer_id)
+    }
+
+    /// Close every connection to `peer_id` and wait until they are closed
+    pub async fn disconnect(&self, peer_id: PeerId) -> Result<()> {
+        self.request(|reply| Command::Disconnect { peer_id, reply }).await
+    }
+
+    /// Close every connection, without waiting
+    pub async fn disconnect_all(&self) -> Result<()> {
+        self.run(|swarm| {
+            let peers: Vec<PeerId> = swarm.connected_peers().cloned().collect();
+            for peer_id in peers {
+                let _ = swarm.disconnect_peer_id(peer_id);
+            }
+        })
+        .await
+    }
+
+    /// Publish already encoded `data` on `topic`, see `gossip::wire::publish`
+    pub async fn publish(&self, topic: &str, data: Vec<u8>) -> Result<MessageId> {
+        self.publish_sharded(topic, 0, data).await
+    }
+
+    /// Publish on the shard of `topic` that `shard_key` falls in, or on
+    /// `topic` if it isn't sharded
+    pub async fn publish_sharded(&self, topic: &str, shard_key: u64, data: Vec<u8>) -> Result<MessageId> {
+        let topic = self.topics.publish_topic(topic, shard_key);
+        self.request(|reply| Command::Publish { topic, data, reply }).await
+    }
+
+    /// Subscribe to `topic`, or the shards of it this node follows. Returns
+    /// whether any of them weren't subscribed to already.
+    pub async fn subscribe(&self, topic: &str) -> Result<bool> {
+        let topics: Vec<IdentTopic> = self.topics.subscriptions(topic).into_iter().map(IdentTopic::new).collect();
+        self.run(move |swarm| -> Result<bool> {
+            let mut subscribed = false;
+            for topic in &topics {
+                subscribed |= swarm.behaviour_mut().gossipsub.subscribe(topic)?;
+            }
+            Ok(subscribed)
+        })
+        .await?
+    }
+
+    /// Send `request` to `peer_id` and wait for the response, or the failure
+    /// to get one
+    pub async fn send_request(&self, peer_id: PeerId, request: reqres::Request) -> Result<reqres::Response> {
+        self.request(|reply| Command::SendRequest { peer_id, request, reply }).await
+    }
+
+    pub async fn send_contract_sync_request(&self, peer_id: PeerId, request: ContractSyncRequest) -> Result<ContractSyncResponse> {
+        self.request(|reply| Command::SendContractSyncRequest { peer_id, request, reply }).await
+    }
+
+    /// Answer an inbound reqres request
+    pub fn respond(&self, channel: ResponseChannel<reqres::Response>, response: reqres::Response) -> Result<()> {
+        self.send(Command::Respond { channel, response })
+    }
+
+    /// Answer an inbound contract sync request
+    pub fn respond_contract_sync(&self, channel: ResponseChannel<ContractSyncResponse>, response: ContractSyncResponse) -> Result<()> {
+        self.send(Command::RespondContractSync { channel, response })
+    }
+
+    /// Events the driver doesn't consume itself, from now on. Only the last
+    /// receiver taken gets them; until one is, they are dropped.
+    pub async fn events(&self) -> Result<mpsc::UnboundedReceiver<NodeSwarmEvent>> {
+        let (reply, rx) = oneshot::channel();
+        self.send(Command::Events { reply })?;
+        rx.await.map_err(|_| stopped())
+    }
+
+    pub async fn connected_peers(&self) -> Result<Vec<PeerId>> {
+        self.run(|swarm| swarm.connected_peers().cloned().collect()).await
+    }
+
+    pub async fn is_connected(&self, peer_id: PeerId) -> Result<bool> {
+        self.run(move |swarm| swarm.is_connected(&peer_id)).await
+    }
+
+    /// Remember `address` for `peer_id`, in the swarm and the Kademlia table
+    pub async fn add_peer_address(&self, peer_id: PeerId, address: Multiaddr) -> Result<()> {
+        self.run(move |swarm| {
+            swarm.add_peer_address(peer_id, address.clone());
+            swarm.behaviour_mut().kademlia.add_address(&peer_id, address);
+        })
+        .await
+    }
+}
+
+struct Driver {
+    swarm: NodeSwarm,
+    requests: HashMap<OutboundRequestId, Reply<reqres::Response>>,
+    contract_sync_requests: HashMap<OutboundRequestId, Reply<ContractSyncResponse>>,
+    connects: HashMap<PeerId, Vec<Reply<()>>>,
+    disconnects: HashMap<PeerId, Vec<Reply<()>>>,
+    events: Option<mpsc::UnboundedSender<NodeSwarmEvent>>,
+}
+
+impl Driver {
+    fn new(swarm: NodeSwarm) -> Self {
+        Self {
+            swarm,
+            requests: HashMap::new(),
+            contract_sync_requests: HashMap::new(),
+            connects: HashMap::new(),
+            disconnects: HashMap::new(),
+            events: None,
+        }
+    }
+
+    async fn run(mut self, mut commands: mpsc::UnboundedReceiver<Command>) {
+        loop {
+            tokio::select! {
+                command = commands.recv() => match command {
+                    Some(command) => self.handle_command(command),
+                    None => break,
+                },
+                event = self.swarm.select_next_some() => self.handle_event(event),
+            }
+        }
+        log::debug!("Swarm driver stopped");
+    }
+
+    fn handle_command(&mut self, command: Command) {
+        match command {
+            Command::Dial { address, reply } => {
+                let _ = reply.send(self.swarm.dial(address).map_err(Into::into));
+            }
+            Command::Connect { address, peer_id, reply } => {
+                if self.swarm.is_connected(&peer_id) {
+                    let _ = reply.send(Ok(()));
+                } else if let Err(e) = self.swarm.dial(address) {
+                    let _ = reply.send(Err(e.into()));
+                } else {
+                    self.connects.entry(peer_id).or_default().push(reply);
+                }
+            }
+            Command::Disconnect { peer_id, reply } => {
+                if self.swarm.disconnect_peer_id(peer_id).is_err() {
+                    // Not connected, so nothing to wait for
+                    let _ = reply.send(Ok(()));
+                } else {
+                    self.disconnects.entry(peer_id).or_default().push(reply);
+                }
+            }
+            Command::Publish { topic, data, reply } => {
+                let published = self.swarm.behaviour_mut().gossipsub.publish(IdentTopic::new(topic), data);
+                let _ = reply.send(published.map_err(Into::into));
+            }
+            Command::SendRequest { peer_id, request, reply } => {
+                let request_id = self.swarm.behaviour_mut().reqres.send_request(&peer_id, request);
+                self.requests.insert(request_id, reply);
+            }
+            Command::SendContractSyncRequest { peer_id, request, reply } => {
+                let request_id = self.swarm.behaviour_mut().contract_sync.send_request(&peer_id, request);
+                self.contract_sync_requests.insert(request_id, reply);
+            }
+            Command::Respond { channel, response } => {
+                if self.swarm.behaviour_mut().reqres.send_response(channel, response).is_err() {
+                    log::warn!("Reqres peer went away before the response was sent");
+                }
+            }
+            Command::RespondContractSync { channel, response } => {
+                if self.swarm.behaviour_mut().contract_sync.send_response(channel, response).is_err() {
+                    log::warn!("Contract sync peer went away before the response was sent");
+                }
+            }
+            Command::Events { reply } => {
+                let (tx, rx) = mpsc::unbounded_channel();
+                self.events = Some(tx);
+                let _ = reply.send(rx);
+            }
+            Command::Run(f) => f(&mut self.swarm),
+        }
+    }
+
+    fn handle_event(&mut self, event: NodeSwarmEvent) {
+        match event {
+            SwarmEvent::Behaviour(NodeBehaviourEvent::Reqres(request_response::Event::Message {
+                message: request_response::Message::Response { request_id, response },
+                ..
+            })) => match self.requests.remove(&request_id) {
+                Some(reply) => {
+                    let _ = reply.send(response.decode());
+                }
+                None => log::warn!("Received response for unknown request {:?}", request_id),
+            },
+            SwarmEvent::Behaviour(NodeBehaviourEvent::Reqres(request_response::Event::OutboundFailure {
+                request_id, error, peer, ..
+            })) => {
+                if let Some(reply) = self.requests.remove(&request_id) {
+                    let _ = reply.send(Err(anyhow!("Request to {} failed: {}", peer, error)));
+                }
+            }
+            SwarmEvent::Behaviour(NodeBehaviourEvent::ContractSync(request_response::Event::Message {
+                message: request_response::Message::Response { request_id, response },
+                ..
+            })) => {
+                if let Some(reply) = self.contract_sync_requests.remove(&request_id) {
+                    let _ = reply.send(Ok(response));
+                }
+            }
+            SwarmEvent::Behaviour(NodeBehaviourEvent::ContractSync(request_response::Event::OutboundFailure {
+                request_id, error, ..
+            })) => {
+                if let Some(reply) = self.contract_sync_requests.remove(&request_id) {
+                    let failure = match error {
+                        request_response::OutboundFailure::UnsupportedProtocols => contract_sync::UnsupportedProtocol.into(),
+                        error => anyhow!("Contract sync request failed: {}", error),
+                    };
+                    let _ = reply.send(Err(failure));
+                }
+            }
+            event => {
+                match &event {
+                    SwarmEvent::ConnectionEstablished { peer_id, .. } => {
+                        for reply in self.connects.remove(peer_id).into_iter().flatten() {
+                            let _ = reply.send(Ok(()));
+                        }
+                    }
+                    SwarmEvent::OutgoingConnectionError { peer_id: Some(peer_id), error, .. } => {
+                        for reply in self.connects.remove(peer_id).into_iter().flatten() {
+                            let _ = reply.send(Err(anyhow!("Failed to dial peer {}: {}", peer_id, error)));
+                        }
+                    }
+                    SwarmEvent::ConnectionClosed { peer_id, num_established: 0, .. } => {
+                        for reply in self.disconnects.remove(peer_id).into_iter().flatten() {
+                            let _ = reply.send(Ok(()));
+                        }
+                    }
+                    _ => {}
+                }
+                self.forward(event);
+            }
+        }
+    }
+
+    fn forward(&mut self, event: NodeSwarmEvent) {
+        let Some(events) = &self.events else {
+            log::debug!("Unhandled swarm event {:?}", event);
+            return;
+        };
+        if events.send(event).is_err() {
+            self.events = None;
+        }
+    }
+}
@@ -0,0 +1,248 @@
+//! Bootstrap from cold storage archives.
+//!
+//! Networks can list archive URLs in their NetworkInfo (`archives`). Before
+//! syncing from peers, a node whose chain is behind an archive downloads the
+//! segments past its tip, checks each against the archive manifest, and
+//! imports them, leaving only the recent epochs to sync over the network.
+//! See `modal_datastore::models::miner::archive` for the archive layout.
+//!
+//! Archives aren't trusted: archived blocks are validated like synced ones
+//! before they're imported, and a node with no chain yet only starts one from
+//! an archive whose genesis block is the one its network pins.
+
+use anyhow::{anyhow, Context, Result};
+use modal_common::difficulty::DifficultySample;
+use modal_datastore::models::miner::archive::{self, ArchiveManifest, SegmentData, ARCHIVE_MANIFEST_NAME};
+use modal_datastore::models::MinerBlock;
+use modal_datastore::DatastoreManager;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::actions::observer::get_chain_tip_index;
+use crate::sync::validation::{self, ProofOfWork};
+
+/// Archive URLs from the loaded network config
+pub async fn archive_urls(datastore: &Arc<Mutex<DatastoreManager>>) -> Vec<String> {
+    let network_config = datastore.lock().await.get_network_config().await.ok().flatten();
+    network_config
+        .and_then(|config| config.get("archives").cloned())
+        .and_then(|archives| serde_json::from_value(archives).ok())
+        .unwrap_or_default()
+}
+
+/// Import archived epochs past the local tip from the first archive that
+/// serves them. Returns the number of blocks imported.
+pub async fn bootstrap_from_archives(datastore: &Arc<Mutex<DatastoreManager>>, urls: &[String]) -> Result<usize> {
+    let rules = Arc::new(ProofOfWork::for_network(datastore).await?);
+    bootstrap_with_rules(datastore, urls, rules).await
+}
+
+async fn bootstrap_with_rules(
+    datastore: &Arc<Mutex<DatastoreManager>>,
+    urls: &[String],
+    rules: Arc<ProofOfWork>,
+) -> Result<usize> {
+    for url in urls {
+        match bootstrap_from_archive(datastore, url, rules.clone()).await {
+            Ok(imported) => return Ok(imported),
+            Err(e) => log::warn!("Failed to bootstrap from archive {}: {}", url, e),
+        }
+    }
+    Ok(0)
+}
+
+async fn bootstrap_from_archive(
+    datastore: &Arc<Mutex<DatastoreManager>>,
+    url: &str,
+    rules: Arc<ProofOfWork>,
+) -> Result<usize> {
+    let manifest = ArchiveManifest::from_slice(&fetch(url, ARCHIVE_MANIFEST_NAME).await?)?;
+    let mut next_index = {
+        let ds = datastore.lock().await;
+        MinerBlock::find_all_canonical_multi(&ds).await?.last().map(|b| b.index + 1).unwrap_or(0)
+    };
+    let mut history: Vec<DifficultySample> = validation::difficulty_history(&*datastore.lock().await, next_index).await?;
+
+    let segments: Vec<_> = manifest.segments_from(next_index).cloned().collect();
+    if segments.is_empty() {
+        return Ok(0);
+    }
+    log::info!("📦 Importing {} archived epochs from {}", segments.len(), url);
+
+    let mut imported = 0;
+    for segment in segments {
+        // Download without holding the datastore lock
+        let bytes = fetch(url, &segment.file).await?;
+        let data = SegmentData::decode(&bytes, &segment)?;
+
+        let new_blocks: Vec<MinerBlock> = data.blocks.iter().filter(|b| b.index >= next_index).cloned().collect();
+        if let Some(genesis) = new_blocks.first().filter(|b| b.index == 0) {
+            check_genesis(datastore, genesis).await?;
+        }
+        let validated = validation::shared()
+            .validate_with(new_blocks, rules.clone(), history.clone())
+            .await
+            .into_result()
+            .with_context(|| format!("Segment {} failed validation", segment.file))?;
+
+        let ds = datastore.lock().await;
+        imported += archive::import_segment_multi(&ds, &data).await?;
+        drop(ds);
+        for block in &validated {
+            history.push(DifficultySample {
+                index: block.index,
+                timestamp: block.timestamp,
+                difficulty: block.get_target_difficulty_u128()?,
+            });
+        }
+        if let Some(last) = validated.last() {
+            next_index = last.index + 1;
+        }
+    }
+    log::info!("✓ Imported {} archived blocks, up to block {}", imported, get_chain_tip_index(datastore).await);
+    Ok(imported)
+}
+
+/// An archive doesn't get to choose the chain: it can only start one on a
+/// network that pins its genesis block, and with that block
+async fn check_genesis(datastore: &Arc<Mutex<DatastoreManager>>, block: &MinerBlock) -> Result<()> {
+    let network_config = datastore.lock().await.get_network_config().await?.unwrap_or_default();
+    let spec = crate::genesis::genesis_spec(&network_config)?
+        .filter(|spec| spec.block_hash.is_some())
+        .ok_or_else(|| anyhow!("The network doesn't pin its genesis block, so the archive's can't be checked"))?;
+    spec.check_block(&block.hash, block.get_target_difficulty_u128()?)
+        .map_err(|e| anyhow!("Archive doesn't match the network's genesis: {}", e))
+}
+
+/// Fetch a file from an archive: `http(s)://` URLs over HTTP, `file://` URLs
+/// and plain paths from the local filesystem
+async fn fetch(base_url: &str, name: &str) -> Result<Vec<u8>> {
+    let base = base_url.trim_end_matches('/');
+    if !base.starts_with("http://") && !base.starts_with("https://") {
+        let dir = base.strip_prefix("file://").unwrap_or(base);
+        let path = std::path::Path::new(dir).join(name);
+        return tokio::fs::read(&path).await.with_context(|| format!("Failed to read {}", path.display()));
+    }
+
+    let url = format!("{}/{}", base, name);
+    let response = crate::proxy::http_get(&url).await.with_context(|| format!("Failed to fetch {}", url))?;
+    if !response.status().is_success() {
+        return Err(anyhow!("Failed to fetch {}: HTTP {}", url, response.status()));
+    }
+    Ok(response.bytes().await?.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use modal_common::difficulty::DifficultyConfig;
+    use modal_common::hash_tax;
+    use modal_miner::{Block, BlockData, ChainConfig};
+    use modal_networks::GenesisSpec;
+
+    fn genesis() -> MinerBlock {
+        MinerBlock::new_canonical(
+            "genesis".to_string(), 0, 0, 1_700_000_000, String::new(), String::new(), 0, 1, "peer".to_string(), 0,
+        )
+    }
+
+    fn mine(index: u64, previous_hash: &str) -> MinerBlock {
+        let mut block = Block::new(index, previous_hash.to_string(), BlockData::new("peer".to_string(), index), 1);
+        let nonce = (0..)
+            .find(|nonce| {
+                let hash = block.header.calculate_hash_with(*nonce, "sha256").unwrap();
+                hash_tax::is_hash_acceptable(&hash, 1, "sha256")
+            })
+            .unwrap();
+        block.header.nonce = nonce;
+        block.header.hash = block.header.calculate_hash_with(nonce, "sha256").unwrap();
+        MinerBlock::new_canonical(
+            block.header.hash.clone(),
+            index,
+            index / 2,
+            block.header.timestamp.timestamp(),
+            block.header.previous_hash.clone(),
+            block.header.data_hash.clone(),
+            nonce,
+            1,
+            block.data.nominated_peer_id.clone(),
+            block.data.miner_number,
+        )
+    }
+
+    fn rules() -> Arc<ProofOfWork> {
+        let chain_config = ChainConfig {
+            initial_difficulty: 1,
+            difficulty_algorithm: DifficultyConfig::Fixed,
+            ..Default::default()
+        };
+        Arc::new(ProofOfWork::new("sha256", &chain_config))
+    }
+
+    /// An archive of `blocks`, one epoch per two blocks, with the last epoch held back
+    async fn export(blocks: &[MinerBlock]) -> tempfile::TempDir {
+        let source = DatastoreManager::create_in_memory().unwrap();
+        for block in blocks {
+            block.save_to_active(&source).await.unwrap();
+        }
+        let dir = tempfile::tempdir().unwrap();
+        archive::export_archive_multi(&source, dir.path(), 1).await.unwrap();
+        dir
+    }
+
+    async fn target(genesis_hash: &str) -> Arc<Mutex<DatastoreManager>> {
+        let mgr = DatastoreManager::create_in_memory().unwrap();
+        let spec = GenesisSpec { block_hash: Some(genesis_hash.to_string()), ..Default::default() };
+        mgr.load_network_config(&serde_json::json!({ "genesis": spec })).await.unwrap();
+        Arc::new(Mutex::new(mgr))
+    }
+
+    #[tokio::test]
+    async fn test_bootstrap_from_local_archive() {
+        let mut blocks = vec![genesis()];
+        for index in 1..6 {
+            let previous_hash = blocks.last().unwrap().hash.clone();
+            blocks.push(mine(index, &previous_hash));
+        }
+        let dir = export(&blocks).await;
+
+        let target = target("genesis").await;
+        let urls = vec![
+            "file:///nonexistent/archive".to_string(),
+            format!("file://{}", dir.path().display()),
+        ];
+        assert_eq!(bootstrap_with_rules(&target, &urls, rules()).await.unwrap(), 4);
+        assert_eq!(get_chain_tip_index(&target).await, 3);
+
+        // Nothing left to import
+        assert_eq!(bootstrap_with_rules(&target, &urls, rules()).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_archive_is_validated() {
+        let mut blocks = vec![genesis()];
+        for index in 1..6 {
+            let previous_hash = blocks.last().unwrap().hash.clone();
+            blocks.push(mine(index, &previous_hash));
+        }
+        let urls = |dir: &tempfile::TempDir| vec![format!("file://{}", dir.path().display())];
+
+        // Another network's genesis
+        let dir = export(&blocks).await;
+        let other = target("other_genesis").await;
+        assert_eq!(bootstrap_with_rules(&other, &urls(&dir), rules()).await.unwrap(), 0);
+        assert_eq!(get_chain_tip_index(&other).await, 0);
+
+        // Blocks that link up but were never mined
+        let mut unmined = vec![genesis()];
+        for index in 1..6u64 {
+            let mut block = mine(index, &unmined.last().unwrap().hash);
+            block.hash = format!("block_{}", index);
+            unmined.push(block);
+        }
+        let dir = export(&unmined).await;
+        let target = target("genesis").await;
+        assert_eq!(bootstrap_with_rules(&target, &urls(&dir), rules()).await.unwrap(), 0);
+        assert!(MinerBlock::find_all_canonical_multi(&*target.lock().await).await.unwrap().is_empty());
+    }
+}
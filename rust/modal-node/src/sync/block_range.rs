@@ -5,12 +5,10 @@
 use anyhow::Result;
 use modal_datastore::models::MinerBlock;
 use modal_datastore::DatastoreManager;
-use std::sync::Arc;
-use tokio::sync::Mutex;
 
-use crate::constants::REQRES_TIMEOUT_SECS;
+use crate::constants::{REQRES_BUSY_RETRIES, REQRES_TIMEOUT_SECS};
 use crate::reqres;
-use crate::sync::common_ancestor::wait_for_reqres_response;
+use crate::swarm_driver::SwarmHandle;
 
 /// Result of a block range request
 #[derive(Debug, Clone)]
@@ -21,6 +19,8 @@ pub struct BlockRangeResult {
     pub has_more: bool,
     /// Next index to request from (if has_more is true)
     pub next_from_index: u64,
+    /// Index below which the peer only serves block headers (None if unpruned)
+    pub pruned_below: Option<u64>,
 }
 
 /// Request a range of blocks from a peer.
@@ -30,16 +30,14 @@ pub struct BlockRangeResult {
 /// * `peer_addr` - The peer address to query
 /// * `from_index` - Start index (inclusive)
 /// * `to_index` - End index (inclusive)
-/// * `reqres_response_txs` - Channel map for response routing
 ///
 /// # Returns
 /// BlockRangeResult with the received blocks
 pub async fn request_block_range(
-    swarm: &Arc<Mutex<crate::swarm::NodeSwarm>>,
+    swarm: &SwarmHandle,
     peer_addr: &str,
     from_index: u64,
     to_index: u64,
-    reqres_response_txs: &Arc<Mutex<std::collections::HashMap<libp2p::request_response::OutboundRequestId, tokio::sync::oneshot::Sender<reqres::Response>>>>,
 ) -> Result<BlockRangeResult> {
     use libp2p::multiaddr::Multiaddr;
     
@@ -56,19 +54,28 @@ pub async fn request_block_range(
             "from_index": from_index,
             "to_index": to_index
         })),
+        accept_encoding: Some(reqres::encoding::AcceptEncoding::zstd()),
     };
     
-    let request_id = {
-        let mut swarm_lock = swarm.lock().await;
-        swarm_lock.behaviour_mut().reqres.send_request(&target_peer_id, request)
+    let mut busy_retries = 0;
+    let response = loop {
+        let response = tokio::time::timeout(
+            std::time::Duration::from_secs(REQRES_TIMEOUT_SECS),
+            swarm.send_request(target_peer_id, request.clone())
+        ).await;
+        // A busy peer says when to come back
+        if let Ok(Ok(ref resp)) = response {
+            if let Some(retry_after_ms) = resp.retry_after_ms().filter(|_| busy_retries < REQRES_BUSY_RETRIES) {
+                busy_retries += 1;
+                log::debug!("Peer busy, retrying block range request in {}ms", retry_after_ms);
+                tokio::time::sleep(std::time::Duration::from_millis(retry_after_ms)).await;
+                continue;
+            }
+        }
+        break response;
     };
     
-    log::debug!("Block range request sent with ID: {:?}", request_id);
-    
-    let response = match tokio::time::timeout(
-        std::time::Duration::from_secs(REQRES_TIMEOUT_SECS),
-        wait_for_reqres_response(reqres_response_txs, request_id)
-    ).await {
+    let response = match response {
         Ok(Ok(resp)) => resp,
         Ok(Err(e)) => {
             log::warn!("Failed to get block range: {}", e);
@@ -76,6 +83,7 @@ pub async fn request_block_range(
                 blocks: vec![],
                 has_more: false,
                 next_from_index: from_index,
+                pruned_below: None,
             });
         }
         Err(_) => {
@@ -84,6 +92,7 @@ pub async fn request_block_range(
                 blocks: vec![],
                 has_more: false,
                 next_from_index: from_index,
+                pruned_below: None,
             });
         }
     };
@@ -94,6 +103,7 @@ pub async fn request_block_range(
             blocks: vec![],
             has_more: false,
             next_from_index: from_index,
+            pruned_below: None,
         });
     }
     
@@ -103,6 +113,7 @@ pub async fn request_block_range(
             blocks: vec![],
             has_more: false,
             next_from_index: from_index,
+            pruned_below: None,
         });
     };
     
@@ -113,13 +124,17 @@ pub async fn request_block_range(
             blocks: vec![],
             has_more: false,
             next_from_index: from_index,
+            pruned_below: None,
         });
     };
     
     let mut blocks = Vec::with_capacity(blocks_json.len());
     for block_json in blocks_json {
-        match serde_json::from_value(block_json.clone()) {
-            Ok(block) => blocks.push(block),
+        match serde_json::from_value::<MinerBlock>(block_json.clone()) {
+            Ok(block) => match block.validate_fields() {
+                Ok(()) => blocks.push(block),
+                Err(e) => log::warn!("Rejected malformed block: {}", e),
+            },
             Err(e) => {
                 log::warn!("Failed to parse block: {}", e);
             }
@@ -128,6 +143,10 @@ pub async fn request_block_range(
     
     let has_more = data.get("has_more").and_then(|v| v.as_bool()).unwrap_or(false);
     let next_from_index = from_index + blocks.len() as u64;
+    let pruned_below = data.get("pruned_below").and_then(|v| v.as_u64());
+    if let Some(pruned_below) = pruned_below.filter(|p| from_index < *p) {
+        log::info!("Peer is pruned: blocks below {} come without their commits", pruned_below);
+    }
     
     log::info!(
         "Received {} blocks from peer (indices {}..{})",
@@ -140,6 +159,7 @@ pub async fn request_block_range(
         blocks,
         has_more,
         next_from_index,
+        pruned_below,
     })
 }
 
@@ -150,16 +170,14 @@ pub async fn request_block_range(
 /// * `peer_addr` - The peer address to query
 /// * `from_index` - Start index (inclusive)
 /// * `to_index` - End index (inclusive)
-/// * `reqres_response_txs` - Channel map for response routing
 ///
 /// # Returns
 /// All blocks in the range
 pub async fn request_all_blocks_in_range(
-    swarm: &Arc<Mutex<crate::swarm::NodeSwarm>>,
+    swarm: &SwarmHandle,
     peer_addr: &str,
     from_index: u64,
     to_index: u64,
-    reqres_response_txs: &Arc<Mutex<std::collections::HashMap<libp2p::request_response::OutboundRequestId, tokio::sync::oneshot::Sender<reqres::Response>>>>,
 ) -> Result<Vec<MinerBlock>> {
     let mut all_blocks = Vec::new();
     let mut current_from = from_index;
@@ -170,7 +188,6 @@ pub async fn request_all_blocks_in_range(
             peer_addr,
             current_from,
             to_index,
-            reqres_response_txs,
         ).await?;
         
         if result.blocks.is_empty() {
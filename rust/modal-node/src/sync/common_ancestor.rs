@@ -12,6 +12,7 @@ use tokio::sync::Mutex;
 
 use crate::constants::{MAX_CHECKPOINTS_PER_REQUEST, REQRES_TIMEOUT_SECS};
 use crate::reqres;
+use crate::swarm_driver::SwarmHandle;
 
 /// Result of common ancestor search
 #[derive(Debug, Clone)]
@@ -24,21 +25,6 @@ pub struct AncestorSearchResult {
     pub remote_cumulative_difficulty: u128,
 }
 
-/// Wait for a reqres response using channels (no swarm lock contention).
-pub async fn wait_for_reqres_response(
-    node_reqres_txs: &Arc<Mutex<std::collections::HashMap<libp2p::request_response::OutboundRequestId, tokio::sync::oneshot::Sender<reqres::Response>>>>,
-    request_id: libp2p::request_response::OutboundRequestId,
-) -> Result<reqres::Response> {
-    let (tx, rx) = tokio::sync::oneshot::channel();
-    
-    {
-        let mut txs = node_reqres_txs.lock().await;
-        txs.insert(request_id, tx);
-    }
-    
-    rx.await.map_err(|_| anyhow::anyhow!("Response channel closed"))
-}
-
 /// Efficiently find the common ancestor between local and remote chains using binary search.
 ///
 /// This function uses the `/data/miner_block/find_ancestor` route to iteratively find
@@ -49,16 +35,14 @@ pub async fn wait_for_reqres_response(
 /// * `swarm` - The swarm for making requests
 /// * `peer_addr` - The peer address to query
 /// * `datastore` - Local datastore to get our chain
-/// * `reqres_response_txs` - Channel map for response routing
 ///
 /// # Returns
 /// * `Ok(AncestorSearchResult)` with ancestor info and peer chain metrics
 /// * `Err(_)` - Error during the search
 pub async fn find_common_ancestor_efficient(
-    swarm: &Arc<Mutex<crate::swarm::NodeSwarm>>,
+    swarm: &SwarmHandle,
     peer_addr: String,
     datastore: &Arc<Mutex<DatastoreManager>>,
-    reqres_response_txs: &Arc<Mutex<std::collections::HashMap<libp2p::request_response::OutboundRequestId, tokio::sync::oneshot::Sender<reqres::Response>>>>,
 ) -> Result<AncestorSearchResult> {
     use libp2p::multiaddr::Multiaddr;
     
@@ -81,7 +65,7 @@ pub async fn find_common_ancestor_efficient(
         
         // Still need to get the peer's chain info
         let (remote_chain_length, remote_cumulative_difficulty) = 
-            get_peer_chain_info(swarm, &target_peer_id, reqres_response_txs).await?;
+            get_peer_chain_info(swarm, &target_peer_id).await?;
         
         return Ok(AncestorSearchResult {
             ancestor_index: None,
@@ -99,7 +83,7 @@ pub async fn find_common_ancestor_efficient(
     
     // Make the initial request
     let (highest_match, matches, remote_chain_length, remote_cumulative_difficulty) = 
-        send_find_ancestor_request(swarm, &target_peer_id, &checkpoints, reqres_response_txs).await?;
+        send_find_ancestor_request(swarm, &target_peer_id, &checkpoints).await?;
     
     log::info!(
         "Remote chain length: {}, cumulative difficulty: {}, Initial highest match: {:?}",
@@ -135,7 +119,6 @@ pub async fn find_common_ancestor_efficient(
         search_low,
         search_high,
         highest_match_idx,
-        reqres_response_txs,
     ).await?;
     
     log::info!("✅ Found common ancestor at block index {}", highest_match_idx);
@@ -149,23 +132,18 @@ pub async fn find_common_ancestor_efficient(
 
 /// Get peer chain info (length and cumulative difficulty).
 async fn get_peer_chain_info(
-    swarm: &Arc<Mutex<crate::swarm::NodeSwarm>>,
+    swarm: &SwarmHandle,
     target_peer_id: &libp2p::PeerId,
-    reqres_response_txs: &Arc<Mutex<std::collections::HashMap<libp2p::request_response::OutboundRequestId, tokio::sync::oneshot::Sender<reqres::Response>>>>,
 ) -> Result<(u64, u128)> {
     let request = reqres::Request {
         path: "/data/miner_block/chain_info".to_string(),
         data: None,
-    };
-    
-    let request_id = {
-        let mut swarm_lock = swarm.lock().await;
-        swarm_lock.behaviour_mut().reqres.send_request(target_peer_id, request)
+        accept_encoding: None,
     };
     
     let response = match tokio::time::timeout(
         std::time::Duration::from_secs(30),
-        wait_for_reqres_response(reqres_response_txs, request_id)
+        swarm.send_request(*target_peer_id, request)
     ).await {
         Ok(Ok(response)) => response,
         Ok(Err(e)) => {
@@ -234,10 +212,9 @@ fn build_exponential_checkpoints(
 
 /// Send find_ancestor request and parse response.
 async fn send_find_ancestor_request(
-    swarm: &Arc<Mutex<crate::swarm::NodeSwarm>>,
+    swarm: &SwarmHandle,
     target_peer_id: &libp2p::PeerId,
     checkpoints: &[(u64, String)],
-    reqres_response_txs: &Arc<Mutex<std::collections::HashMap<libp2p::request_response::OutboundRequestId, tokio::sync::oneshot::Sender<reqres::Response>>>>,
 ) -> Result<(Option<u64>, Vec<serde_json::Value>, u64, u128)> {
     let request = reqres::Request {
         path: "/data/miner_block/find_ancestor".to_string(),
@@ -249,16 +226,12 @@ async fn send_find_ancestor_request(
                 })
             }).collect::<Vec<_>>()
         })),
-    };
-    
-    let request_id = {
-        let mut swarm_lock = swarm.lock().await;
-        swarm_lock.behaviour_mut().reqres.send_request(target_peer_id, request)
+        accept_encoding: None,
     };
     
     let response = match tokio::time::timeout(
         std::time::Duration::from_secs(REQRES_TIMEOUT_SECS / 3),
-        wait_for_reqres_response(reqres_response_txs, request_id)
+        swarm.send_request(*target_peer_id, request)
     ).await {
         Ok(Ok(resp)) => resp,
         Ok(Err(e)) => return Err(e),
@@ -309,13 +282,12 @@ fn determine_binary_search_bounds(
 
 /// Perform batched binary search to find exact common ancestor.
 async fn batched_binary_search(
-    swarm: &Arc<Mutex<crate::swarm::NodeSwarm>>,
+    swarm: &SwarmHandle,
     target_peer_id: &libp2p::PeerId,
     local_blocks: &[MinerBlock],
     mut search_low: u64,
     mut search_high: u64,
     mut highest_match_idx: u64,
-    reqres_response_txs: &Arc<Mutex<std::collections::HashMap<libp2p::request_response::OutboundRequestId, tokio::sync::oneshot::Sender<reqres::Response>>>>,
 ) -> Result<u64> {
     while search_low < search_high && search_high - search_low > 1 {
         let range_size = (search_high - search_low) as usize;
@@ -343,7 +315,6 @@ async fn batched_binary_search(
             swarm,
             target_peer_id,
             &checkpoints,
-            reqres_response_txs,
         ).await?;
         
         // Update bounds based on results
@@ -4,10 +4,14 @@
 //! - Finding common ancestors between chains
 //! - Requesting block ranges from peers
 //! - Full chain synchronization coordination
+//! - Validating synced blocks on a worker pool
+//! - Bootstrapping from cold storage archives
 
 pub mod common_ancestor;
 pub mod block_range;
 pub mod peer_sync;
+pub mod archive;
+pub mod validation;
 
 // Re-export commonly used items
 pub use common_ancestor::find_common_ancestor_efficient;
@@ -11,10 +11,9 @@ use tokio::sync::Mutex;
 
 use crate::chain::{compare_chains, ForkChoiceResult};
 use crate::chain::metrics::calculate_cumulative_difficulty;
-use crate::chain::reorg::{orphan_blocks_after, validate_block_chain};
+use crate::chain::reorg::orphan_blocks_after;
 use crate::sync::common_ancestor::find_common_ancestor_efficient;
 use crate::sync::block_range::request_all_blocks_in_range;
-use crate::reqres;
 
 /// Result of a sync operation
 #[derive(Debug, Clone)]
@@ -37,22 +36,19 @@ pub enum SyncResult {
 
 /// Coordinator for peer synchronization operations
 pub struct SyncCoordinator {
-    swarm: Arc<Mutex<crate::swarm::NodeSwarm>>,
+    swarm: crate::swarm_driver::SwarmHandle,
     datastore: Arc<Mutex<DatastoreManager>>,
-    reqres_response_txs: Arc<Mutex<std::collections::HashMap<libp2p::request_response::OutboundRequestId, tokio::sync::oneshot::Sender<reqres::Response>>>>,
 }
 
 impl SyncCoordinator {
     /// Create a new sync coordinator.
     pub fn new(
-        swarm: Arc<Mutex<crate::swarm::NodeSwarm>>,
+        swarm: crate::swarm_driver::SwarmHandle,
         datastore: Arc<Mutex<DatastoreManager>>,
-        reqres_response_txs: Arc<Mutex<std::collections::HashMap<libp2p::request_response::OutboundRequestId, tokio::sync::oneshot::Sender<reqres::Response>>>>,
     ) -> Self {
         Self {
             swarm,
             datastore,
-            reqres_response_txs,
         }
     }
     
@@ -68,7 +64,6 @@ impl SyncCoordinator {
             &self.swarm,
             peer_addr.to_string(),
             &self.datastore,
-            &self.reqres_response_txs,
         ).await?;
         
         // Step 2: Get local chain info for comparison
@@ -120,7 +115,6 @@ impl SyncCoordinator {
             peer_addr,
             from_index,
             ancestor_result.remote_chain_length,
-            &self.reqres_response_txs,
         ).await?;
         
         if peer_blocks.is_empty() {
@@ -129,15 +123,15 @@ impl SyncCoordinator {
             });
         }
         
-        // Step 5: Validate received blocks
-        let mut sorted_blocks = peer_blocks;
-        sorted_blocks.sort_by_key(|b| b.index);
-        
-        if let Err(e) = validate_block_chain(&sorted_blocks) {
-            return Ok(SyncResult::Failed {
-                reason: format!("Invalid peer chain: {}", e),
-            });
-        }
+        // Step 5: Validate received blocks, parents before children
+        let sorted_blocks = match crate::sync::validation::shared().validate(&self.datastore, peer_blocks).await.into_result() {
+            Ok(blocks) => blocks,
+            Err(e) => {
+                return Ok(SyncResult::Failed {
+                    reason: format!("Invalid peer chain: {}", e),
+                });
+            }
+        };
         
         // Step 6: Verify connection to local chain
         if let Some(first_block) = sorted_blocks.first() {
@@ -0,0 +1,405 @@
+//! Parallel validation of synced blocks.
+//!
+//! A block's own checks (wire fields, commits root, proof-of-work hash,
+//! meeting its target and actualized difficulty) don't depend on any other
+//! block, and rehashing with RandomX is by far the slowest part of initial
+//! sync. So blocks are checked in chunks on blocking threads, a few chunks at
+//! a time, while the sync task links the results in index order: a block is
+//! only accepted once its parent has been, and its target difficulty must be
+//! the one the network's difficulty algorithm expects after the blocks before
+//! it. The first block that fails, or doesn't follow its parent, ends the
+//! accepted run. Gossiped blocks go through the same checks, one at a time.
+
+use anyhow::{anyhow, Result};
+use modal_common::difficulty::DifficultySample;
+use modal_common::hash_tax::{self, HashRegistry};
+use modal_datastore::models::MinerBlock;
+use modal_datastore::DatastoreManager;
+use modal_miner::epoch::EpochManager;
+use modal_miner::ChainConfig;
+use std::sync::{Arc, LazyLock};
+use tokio::sync::{Mutex, Semaphore};
+
+/// Blocks checked together on one blocking thread
+pub const DEFAULT_CHUNK_SIZE: usize = 8;
+
+/// What a network's mined blocks must meet
+#[derive(Debug, Clone)]
+pub struct ProofOfWork {
+    /// The network's mining hash function
+    pub hash_func: String,
+    /// Hash functions as the network configures them
+    pub hashes: HashRegistry,
+    /// Regtest networks mine at zero difficulty; no other network may
+    pub regtest: bool,
+    /// Computes the target difficulty expected at each height
+    pub difficulty: EpochManager,
+}
+
+impl ProofOfWork {
+    pub fn new(hash_func: impl Into<String>, chain_config: &ChainConfig) -> Self {
+        Self {
+            hash_func: hash_func.into(),
+            hashes: HashRegistry::default(),
+            regtest: chain_config.regtest,
+            difficulty: chain_config.epoch_manager(),
+        }
+    }
+
+    /// Rules of the network in the datastore's config, starting from the
+    /// genesis block's difficulty, hashed with the functions it configures
+    pub async fn for_network(datastore: &Arc<Mutex<DatastoreManager>>) -> Result<Self> {
+        let (genesis_difficulty, hash_func, hashes) = {
+            let mgr = datastore.lock().await;
+            let genesis_difficulty = MinerBlock::find_canonical_by_index_simple(&mgr, 0)
+                .await?
+                .map(|genesis| genesis.get_target_difficulty_u128())
+                .transpose()?;
+            (genesis_difficulty, mgr.mining_hash_func().to_string(), mgr.hash_registry().clone())
+        };
+        let chain_config = crate::actions::miner::network_chain_config(datastore, genesis_difficulty, None).await;
+        Ok(Self { hashes, ..Self::new(hash_func, &chain_config) })
+    }
+
+    /// Whether a block's hash is the one its header hashes to
+    fn verify_hash(&self, block: &modal_miner::Block) -> bool {
+        self.hashes
+            .hash_with_nonce(&block.mining_data(), block.header.nonce, &self.hash_func)
+            .is_ok_and(|hash| hash == block.header.hash)
+    }
+
+    /// Target difficulty of the block at `index`, after `history`
+    pub fn expected_difficulty(&self, index: u64, history: &[DifficultySample]) -> u128 {
+        self.difficulty.difficulty_for_samples(index, history)
+    }
+
+    fn is_hash_acceptable(&self, hash: &str, difficulty: u128) -> bool {
+        if self.regtest {
+            hash_tax::is_hash_acceptable_regtest(hash, difficulty, &self.hash_func)
+        } else {
+            hash_tax::is_hash_acceptable(hash, difficulty, &self.hash_func)
+        }
+    }
+}
+
+/// Blocks that passed validation, in index order, and why the next one didn't
+#[derive(Debug)]
+pub struct ValidatedBlocks {
+    pub valid: Vec<MinerBlock>,
+    pub rejected: Option<(u64, String)>,
+}
+
+impl ValidatedBlocks {
+    /// The blocks, if every one of them was valid
+    pub fn into_result(self) -> Result<Vec<MinerBlock>> {
+        match self.rejected {
+            Some((index, reason)) => Err(anyhow!("Invalid block {}: {}", index, reason)),
+            None => Ok(self.valid),
+        }
+    }
+}
+
+/// Checks a block needs no other block for
+pub fn check_block(block: &MinerBlock, rules: &ProofOfWork) -> Result<()> {
+    block.validate_fields()?;
+    // Genesis isn't mined; it's checked against the network config instead
+    if block.index == 0 {
+        return Ok(());
+    }
+    let mined = modal_miner::persistence::miner_block_to_block(block)?;
+    // Pruned peers send blocks without their commits, just the root
+    if !block.commits.is_empty() && !mined.verify_commits_root() {
+        return Err(anyhow!("Commits root doesn't match commits"));
+    }
+    if !rules.verify_hash(&mined) {
+        return Err(anyhow!("Invalid hash"));
+    }
+    if !rules.is_hash_acceptable(&block.hash, mined.header.difficulty) {
+        return Err(anyhow!("Block doesn't meet difficulty requirement"));
+    }
+    let actualized = hash_tax::hash_to_actualized_difficulty(&block.hash).map_err(|e| anyhow!(e.to_string()))?;
+    if block.get_actualized_difficulty_u128()? != actualized {
+        return Err(anyhow!("Actualized difficulty doesn't match hash"));
+    }
+    // Checked once the work is, as evidence costs signature checks; its
+    // drafts must be signed by the offender
+    for evidence in &block.evidence {
+        modal_datastore::slashing::verify_evidence(evidence)
+            .map_err(|e| anyhow!("Invalid evidence against {}: {}", evidence.peer_id, e))?;
+    }
+    Ok(())
+}
+
+/// Validates block ranges on blocking threads, at most `workers` chunks at once
+#[derive(Debug, Clone)]
+pub struct BlockValidator {
+    workers: Arc<Semaphore>,
+    chunk_size: usize,
+}
+
+impl BlockValidator {
+    pub fn new(workers: usize, chunk_size: usize) -> Self {
+        Self {
+            workers: Arc::new(Semaphore::new(workers.max(1))),
+            chunk_size: chunk_size.max(1),
+        }
+    }
+
+    /// The longest run of `blocks` (sorted by index) that are each valid and
+    /// each follow the one before, under the rules of the network in the
+    /// datastore and after its canonical blocks below the first of them
+    pub async fn validate(&self, datastore: &Arc<Mutex<DatastoreManager>>, blocks: Vec<MinerBlock>) -> ValidatedBlocks {
+        let first_index = blocks.iter().map(|b| b.index).min().unwrap_or(0);
+        let loaded = async {
+            let rules = ProofOfWork::for_network(datastore).await?;
+            let history = difficulty_history(&*datastore.lock().await, first_index).await?;
+            anyhow::Ok((rules, history))
+        };
+        match loaded.await {
+            Ok((rules, history)) => self.validate_with(blocks, Arc::new(rules), history).await,
+            Err(e) => ValidatedBlocks {
+                valid: Vec::new(),
+                rejected: Some((first_index, format!("Can't load the chain to validate against: {}", e))),
+            },
+        }
+    }
+
+    /// Like `validate`, with `history` holding the samples of every block
+    /// below the first of `blocks`, in index order
+    pub async fn validate_with(
+        &self,
+        mut blocks: Vec<MinerBlock>,
+        rules: Arc<ProofOfWork>,
+        mut history: Vec<DifficultySample>,
+    ) -> ValidatedBlocks {
+        blocks.sort_by_key(|b| b.index);
+
+        let workers = self.workers.clone();
+        let chunk_size = self.chunk_size;
+        let chunk_rules = rules.clone();
+        let (tasks_tx, mut tasks_rx) = tokio::sync::mpsc::unbounded_channel();
+        let chunks: Vec<Vec<MinerBlock>> = blocks.chunks(chunk_size).map(|chunk| chunk.to_vec()).collect();
+        // Chunks start as workers free up, while earlier results are linked below
+        let spawner = tokio::spawn(async move {
+            for chunk in chunks {
+                let permit = workers.clone().acquire_owned().await.expect("validator semaphore is never closed");
+                let rules = chunk_rules.clone();
+                let task = tokio::task::spawn_blocking(move || {
+                    let _permit = permit;
+                    let results: Vec<Result<()>> = chunk.iter().map(|block| check_block(block, &rules)).collect();
+                    (chunk, results)
+                });
+                if tasks_tx.send(task).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut valid: Vec<MinerBlock> = Vec::with_capacity(blocks.len());
+        let mut rejected = None;
+        'chunks: while let Some(task) = tasks_rx.recv().await {
+            let (chunk, results) = match task.await {
+                Ok(checked) => checked,
+                Err(e) => {
+                    let index = valid.last().map(|b| b.index + 1).unwrap_or(0);
+                    rejected = Some((index, format!("Validation task failed: {}", e)));
+                    break;
+                }
+            };
+            for (block, result) in chunk.into_iter().zip(results) {
+                if let Some(parent) = valid.last() {
+                    if block.index != parent.index + 1 || block.previous_hash != parent.hash {
+                        rejected = Some((block.index, format!("Doesn't follow block {}", parent.index)));
+                        break 'chunks;
+                    }
+                }
+                if let Err(e) = result {
+                    rejected = Some((block.index, e.to_string()));
+                    break 'chunks;
+                }
+                if let Err(e) = check_target_difficulty(&block, &rules, &history) {
+                    rejected = Some((block.index, e.to_string()));
+                    break 'chunks;
+                }
+                history.push(DifficultySample {
+                    index: block.index,
+                    timestamp: block.timestamp,
+                    difficulty: block.get_target_difficulty_u128().unwrap_or_default(),
+                });
+                valid.push(block);
+            }
+        }
+        // Nothing left to link against, so stop checking the rest
+        drop(tasks_rx);
+        spawner.abort();
+
+        if let Some((index, reason)) = &rejected {
+            log::warn!("Synced block {} failed validation: {}", index, reason);
+        }
+        ValidatedBlocks { valid, rejected }
+    }
+}
+
+/// Check a single block, e.g. one gossiped by a peer, as sync would: under
+/// the rules of the network in the datastore and against the target
+/// difficulty expected after the canonical blocks below it
+pub async fn check_new_block(datastore: &Arc<Mutex<DatastoreManager>>, block: &MinerBlock) -> Result<()> {
+    let rules = ProofOfWork::for_network(datastore).await?;
+    let history = difficulty_history(&*datastore.lock().await, block.index).await?;
+    let block = block.clone();
+    tokio::task::spawn_blocking(move || {
+        check_block(&block, &rules)?;
+        check_target_difficulty(&block, &rules, &history)
+    })
+    .await?
+}
+
+/// Check a block's target difficulty is the one expected after `history`,
+/// which must hold every block below it
+fn check_target_difficulty(block: &MinerBlock, rules: &ProofOfWork, history: &[DifficultySample]) -> Result<()> {
+    if block.index == 0 {
+        return Ok(());
+    }
+    let complete = history.len() as u64 == block.index
+        && history.iter().enumerate().all(|(i, sample)| sample.index == i as u64);
+    if !complete {
+        return Err(anyhow!("Blocks below it are missing, so its difficulty can't be checked"));
+    }
+    let expected = rules.expected_difficulty(block.index, history);
+    let declared = block.get_target_difficulty_u128()?;
+    if declared != expected {
+        return Err(anyhow!("Target difficulty {} but expected {}", declared, expected));
+    }
+    Ok(())
+}
+
+/// Difficulty samples of the canonical blocks below `index`, in index order
+pub async fn difficulty_history(mgr: &DatastoreManager, index: u64) -> Result<Vec<DifficultySample>> {
+    let mut blocks: Vec<MinerBlock> = MinerBlock::find_all_canonical_multi(mgr)
+        .await?
+        .into_iter()
+        .filter(|b| b.index < index)
+        .collect();
+    blocks.sort_by_key(|b| b.index);
+    blocks
+        .iter()
+        .map(|b| {
+            Ok(DifficultySample {
+                index: b.index,
+                timestamp: b.timestamp,
+                difficulty: b.get_target_difficulty_u128()?,
+            })
+        })
+        .collect()
+}
+
+/// A validator with a worker per core, shared by every sync in the process
+pub fn shared() -> &'static BlockValidator {
+    static VALIDATOR: LazyLock<BlockValidator> = LazyLock::new(|| {
+        let workers = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4);
+        BlockValidator::new(workers, DEFAULT_CHUNK_SIZE)
+    });
+    &VALIDATOR
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use modal_common::difficulty::DifficultyConfig;
+    use modal_miner::{Block, BlockData};
+
+    fn mine(index: u64, previous_hash: &str, difficulty: u128) -> MinerBlock {
+        let mut block = Block::new(index, previous_hash.to_string(), BlockData::new("peer".to_string(), index), difficulty);
+        let nonce = (0..)
+            .find(|nonce| {
+                let hash = block.header.calculate_hash_with(*nonce, "sha256").unwrap();
+                hash_tax::is_hash_acceptable_regtest(&hash, difficulty, "sha256")
+            })
+            .unwrap();
+        block.header.nonce = nonce;
+        block.header.hash = block.header.calculate_hash_with(nonce, "sha256").unwrap();
+        MinerBlock::new_canonical(
+            block.header.hash.clone(),
+            index,
+            0,
+            block.header.timestamp.timestamp(),
+            block.header.previous_hash.clone(),
+            block.header.data_hash.clone(),
+            nonce,
+            difficulty,
+            block.data.nominated_peer_id.clone(),
+            block.data.miner_number,
+        )
+    }
+
+    fn chain(len: u64, difficulty: u128) -> Vec<MinerBlock> {
+        let mut blocks: Vec<MinerBlock> = Vec::new();
+        for index in 1..=len {
+            let previous_hash = blocks.last().map(|b| b.hash.clone()).unwrap_or_else(|| "00".repeat(32));
+            blocks.push(mine(index, &previous_hash, difficulty));
+        }
+        blocks
+    }
+
+    /// Rules of a network whose every block has `difficulty`
+    fn fixed(difficulty: u128) -> Arc<ProofOfWork> {
+        let chain_config = ChainConfig {
+            initial_difficulty: difficulty,
+            difficulty_algorithm: DifficultyConfig::Fixed,
+            ..Default::default()
+        };
+        Arc::new(ProofOfWork::new("sha256", &chain_config))
+    }
+
+    fn genesis(difficulty: u128) -> Vec<DifficultySample> {
+        vec![DifficultySample { index: 0, timestamp: 0, difficulty }]
+    }
+
+    #[tokio::test]
+    async fn test_validates_in_parallel_parents_first() {
+        let validator = BlockValidator::new(3, 2);
+        let mut blocks = chain(9, 1);
+        blocks.reverse();
+        let validated = validator.validate_with(blocks.clone(), fixed(1), genesis(1)).await;
+        assert!(validated.rejected.is_none());
+        let indexes: Vec<u64> = validated.valid.iter().map(|b| b.index).collect();
+        assert_eq!(indexes, (1..=9).collect::<Vec<u64>>());
+
+        // A bad proof of work at block 5 keeps blocks 1 to 4
+        let mut tampered = chain(9, 1);
+        tampered[4].nonce = (tampered[4].get_nonce_u128().unwrap() + 1).to_string();
+        let validated = validator.validate_with(tampered, fixed(1), genesis(1)).await;
+        assert_eq!(validated.valid.len(), 4);
+        assert_eq!(validated.rejected.as_ref().map(|(index, _)| *index), Some(5));
+        assert!(validated.into_result().is_err());
+
+        // Valid blocks that skip one aren't linked
+        let mut gapped = chain(6, 1);
+        gapped.remove(2);
+        let validated = validator.validate_with(gapped, fixed(1), genesis(1)).await;
+        assert_eq!(validated.valid.len(), 2);
+        assert_eq!(validated.rejected.map(|(index, _)| index), Some(4));
+    }
+
+    #[tokio::test]
+    async fn test_blocks_meet_the_expected_difficulty() {
+        let validator = BlockValidator::new(2, 2);
+
+        // Blocks declaring less than the network expects are rejected
+        let validated = validator.validate_with(chain(3, 1), fixed(2), genesis(2)).await;
+        assert!(validated.valid.is_empty());
+        assert!(validated.rejected.unwrap().1.contains("expected 2"));
+
+        // Without the blocks below, the difficulty can't be checked
+        let validated = validator.validate_with(chain(3, 1), fixed(1), Vec::new()).await;
+        assert_eq!(validated.rejected.map(|(index, _)| index), Some(1));
+
+        // Zero-work blocks only validate on regtest
+        let validated = validator.validate_with(chain(3, 0), fixed(0), genesis(0)).await;
+        assert_eq!(validated.rejected.map(|(index, _)| index), Some(1));
+        let regtest = Arc::new(ProofOfWork::new("sha256", &ChainConfig::regtest()));
+        let validated = validator.validate_with(chain(3, 0), regtest, genesis(0)).await;
+        assert!(validated.rejected.is_none());
+        assert_eq!(validated.valid.len(), 3);
+    }
+}
@@ -0,0 +1,90 @@
+//! HTML templates for the chain explorer.
+
+/// The explorer page layout
+pub const EXPLORER_TEMPLATE: &str = include_str!("explorer.html");
+
+/// Escape text for use in HTML content and attributes
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Link to `href` showing `text`, both escaped
+pub fn render_link(href: &str, text: &str) -> String {
+    format!(r#"<a href="{}">{}</a>"#, escape_html(href), escape_html(text))
+}
+
+/// Shorten a hash or peer id to its first and last characters
+pub fn truncate_middle(text: &str, keep: usize) -> String {
+    if text.len() > keep * 2 + 3 {
+        format!("{}...{}", &text[..keep], &text[text.len() - keep..])
+    } else {
+        text.to_string()
+    }
+}
+
+/// A card with a table of already rendered cells, or `empty_message` if there are no rows
+pub fn render_table(headers: &[&str], rows: &[Vec<String>], empty_message: &str) -> String {
+    if rows.is_empty() {
+        return format!(r#"<div class="card"><p class="empty">{}</p></div>"#, escape_html(empty_message));
+    }
+    let header_html: String = headers.iter().map(|h| format!("<th>{}</th>", escape_html(h))).collect();
+    let rows_html: String = rows
+        .iter()
+        .map(|row| format!("<tr>{}</tr>", row.iter().map(|cell| format!("<td>{}</td>", cell)).collect::<String>()))
+        .collect();
+    format!(
+        r#"<div class="card"><table><thead><tr>{}</tr></thead><tbody>{}</tbody></table></div>"#,
+        header_html, rows_html
+    )
+}
+
+/// A card listing label / already rendered value pairs
+pub fn render_fields(fields: &[(&str, String)]) -> String {
+    let rows: String = fields
+        .iter()
+        .map(|(label, value)| format!(r#"<tr><th>{}</th><td>{}</td></tr>"#, escape_html(label), value))
+        .collect();
+    format!(r#"<div class="card"><table>{}</table></div>"#, rows)
+}
+
+/// A card with escaped preformatted text, such as commit JSON
+pub fn render_preformatted(heading: &str, text: &str) -> String {
+    format!(
+        r#"<div class="card"><h2>{}</h2><pre>{}</pre></div>"#,
+        escape_html(heading),
+        escape_html(text)
+    )
+}
+
+/// Render an explorer page around already rendered content
+pub fn render_explorer_page(network_name: &str, title: &str, content: &str) -> String {
+    EXPLORER_TEMPLATE
+        .replace("{network_name}", &escape_html(network_name))
+        .replace("{title}", &escape_html(title))
+        // Convert double braces back to single braces for CSS
+        .replace("{{", "{")
+        .replace("}}", "}")
+        // Content last, so braces in it (e.g. JSON) are kept
+        .replace("{content}", content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_explorer_page_escapes_and_keeps_content_braces() {
+        let content = render_preformatted("Commit", r#"{"body": [], "head": {}} <script>"#);
+        let html = render_explorer_page("devnet<1>", "Commit abc", &content);
+        assert!(html.contains("devnet&lt;1&gt; Explorer"));
+        assert!(html.contains(r#"{&quot;body&quot;: [], &quot;head&quot;: {}} &lt;script&gt;"#));
+        assert!(html.contains("body {"));
+        assert!(!html.contains("{{"));
+        assert_eq!(truncate_middle("0123456789abcdef0123", 4), "0123...0123");
+        assert!(render_table(&["Height"], &[], "No blocks").contains("No blocks"));
+    }
+}
@@ -3,6 +3,8 @@
 //! This module provides the status page HTML template and helper functions
 //! for rendering dynamic content.
 
+pub mod explorer;
+
 /// The main status page HTML template
 pub const STATUS_TEMPLATE: &str = include_str!("status.html");
 
@@ -247,6 +249,98 @@ pub fn render_empty_finalized_rounds() -> String {
     "<tr><td colspan='5' style='text-align: center; padding: 20px; color: #666;'>No finalized rounds yet</td></tr>".to_string()
 }
 
+/// Template for the anomaly alerts section
+pub fn render_alerts_section(alerts_html: &str) -> String {
+    format!(
+        r#"
+        <div class="status-card">
+            <h2>Anomaly Alerts</h2>
+            <div class="blocks-container">
+                <table>
+                    <thead>
+                        <tr>
+                            <th>Severity</th>
+                            <th>Kind</th>
+                            <th>Block</th>
+                            <th>Details</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {}
+                    </tbody>
+                </table>
+            </div>
+        </div>"#,
+        alerts_html
+    )
+}
+
+/// Template for an anomaly alert row
+pub fn render_alert_row(severity: &str, kind: &str, block_index: u64, message: &str) -> String {
+    let severity_color = match severity {
+        "critical" => "#f87171",
+        "warning" => "#fbbf24",
+        _ => "#888",
+    };
+    format!(
+        r#"<tr><td style="color: {};">{}</td><td>{}</td><td>{}</td><td>{}</td></tr>"#,
+        severity_color, severity, kind, block_index, message
+    )
+}
+
+/// Template for no anomaly alerts
+pub fn render_empty_alerts() -> String {
+    "<tr><td colspan='4' style='text-align: center; padding: 20px; color: #666;'>No anomalies detected</td></tr>".to_string()
+}
+
+/// Template for an inline SVG sparkline of a numeric series
+pub fn render_sparkline(label: &str, values: &[f64], latest: &str) -> String {
+    const WIDTH: f64 = 240.0;
+    const HEIGHT: f64 = 40.0;
+
+    let points = if values.len() < 2 {
+        String::new()
+    } else {
+        let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let range = if max > min { max - min } else { 1.0 };
+        let step = WIDTH / (values.len() - 1) as f64;
+
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                let x = i as f64 * step;
+                let y = HEIGHT - ((v - min) / range) * HEIGHT;
+                format!("{:.1},{:.1}", x, y)
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    format!(
+        r##"<div class="stat-box">
+                <div class="stat-label">{}</div>
+                <svg width="{}" height="{}" viewBox="0 0 {} {}" preserveAspectRatio="none"><polyline fill="none" stroke="#4ade80" stroke-width="1.5" points="{}"/></svg>
+                <div style="font-size: 0.8em; color: #888; margin-top: 8px;">{}</div>
+            </div>"##,
+        label, WIDTH, HEIGHT, WIDTH, HEIGHT, points, latest
+    )
+}
+
+/// Template for the history charts row
+pub fn render_history_charts(charts_html: &str) -> String {
+    if charts_html.is_empty() {
+        return String::new();
+    }
+    format!(
+        r#"<div class="status-grid">
+            {}
+        </div>"#,
+        charts_html
+    )
+}
+
 /// Render the complete status page by replacing placeholders in the template
 pub fn render_status_page(vars: StatusPageVars) -> String {
     STATUS_TEMPLATE
@@ -264,6 +358,7 @@ pub fn render_status_page(vars: StatusPageVars) -> String {
         .replace("{peers_html}", &vars.peers_html)
         .replace("{blocks_mined_by_node}", &vars.blocks_mined_by_node.to_string())
         .replace("{current_difficulty}", &vars.current_difficulty)
+        .replace("{difficulty_algorithm}", &vars.difficulty_algorithm)
         .replace("{miner_hashrate}", &vars.miner_hashrate)
         .replace("{network_hashrate}", &vars.network_hashrate)
         .replace("{recent_blocks_count}", &vars.recent_blocks_count.to_string())
@@ -274,6 +369,10 @@ pub fn render_status_page(vars: StatusPageVars) -> String {
         .replace("{completed_epochs}", &vars.completed_epochs.to_string())
         .replace("{epoch_nominees_sections}", &vars.epoch_nominees_sections)
         .replace("{finalized_rounds_section}", &vars.finalized_rounds_section)
+        .replace("{history_charts_html}", &vars.history_charts_html)
+        .replace("{autoupgrade_stage}", &vars.autoupgrade_stage)
+        .replace("{consensus_mode}", &vars.consensus_mode)
+        .replace("{alerts_section}", &vars.alerts_section)
         // Convert double braces back to single braces for CSS/JavaScript
         .replace("{{", "{")
         .replace("}}", "}")
@@ -295,6 +394,7 @@ pub struct StatusPageVars {
     pub peers_html: String,
     pub blocks_mined_by_node: usize,
     pub current_difficulty: String,
+    pub difficulty_algorithm: String,
     pub miner_hashrate: String,
     pub network_hashrate: String,
     pub recent_blocks_count: usize,
@@ -305,6 +405,10 @@ pub struct StatusPageVars {
     pub completed_epochs: u64,
     pub epoch_nominees_sections: String,
     pub finalized_rounds_section: String,
+    pub history_charts_html: String,
+    pub autoupgrade_stage: String,
+    pub consensus_mode: String,
+    pub alerts_section: String,
 }
 
 #[cfg(test)]
@@ -328,6 +432,7 @@ mod tests {
             peers_html: "<tr><td>Test Peer</td></tr>".to_string(),
             blocks_mined_by_node: 9,
             current_difficulty: "12".to_string(),
+            difficulty_algorithm: "lwma (window 45)".to_string(),
             miner_hashrate: "0".to_string(),
             network_hashrate: "64.75".to_string(),
             recent_blocks_count: 80,
@@ -338,6 +443,10 @@ mod tests {
             completed_epochs: 4,
             epoch_nominees_sections: "<div>Epoch data</div>".to_string(),
             finalized_rounds_section: "<div>Finalized rounds</div>".to_string(),
+            history_charts_html: String::new(),
+            autoupgrade_stage: "disabled".to_string(),
+            consensus_mode: "hybrid · round 12 · 4/4 validators live".to_string(),
+            alerts_section: render_alerts_section(&render_empty_alerts()),
         };
 
         let html = render_status_page(vars);
@@ -360,6 +469,20 @@ mod tests {
         assert!(html.contains("TestNet"), "Network name placeholder should be replaced");
         assert!(html.contains("12D3KooWBGR3m1JmVFm2aZYR7TZXicjA7HSVSWi2fama5cPpgQiX"), "Peer ID placeholder should be replaced");
         assert!(html.contains("170"), "Block count placeholder should be replaced");
+        assert!(!html.contains("{history_charts_html}"), "History placeholder should be replaced");
+        assert!(!html.contains("{autoupgrade_stage}"), "Autoupgrade placeholder should be replaced");
+        assert!(html.contains("hybrid · round 12"), "Consensus mode should be shown");
+        assert!(html.contains("No anomalies detected"), "Alerts section should be rendered");
+        assert!(html.contains("Current Difficulty (lwma (window 45))"), "Difficulty algorithm should be shown");
+    }
+
+    #[test]
+    fn test_render_sparkline_scales_points() {
+        let svg = render_sparkline("Height", &[0.0, 5.0, 10.0], "10");
+        assert!(svg.contains("points=\"0.0,40.0 120.0,20.0 240.0,0.0\""));
+
+        let empty = render_sparkline("Height", &[1.0], "1");
+        assert!(empty.contains("points=\"\""));
     }
 }
 
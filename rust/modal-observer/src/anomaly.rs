@@ -0,0 +1,262 @@
+//! Anomaly detection
+//!
+//! Accepted blocks can still be suspicious: a timestamp far from the clock,
+//! a sudden drop in target difficulty, or one peer nominated in a large share
+//! of recent blocks. None of these make a block invalid, so the
+//! [`AnomalyDetector`] only raises [`Alert`]s, which are logged and kept in a
+//! bounded history for the status page and RPC.
+
+use modal_datastore::models::MinerBlock;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Number of alerts kept in the detector's history
+pub const MAX_RECENT_ALERTS: usize = 100;
+
+/// Thresholds for the anomaly checks
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnomalyConfig {
+    /// Flag blocks timestamped more than this many seconds ahead of the local clock
+    pub max_future_drift_secs: i64,
+    /// Flag blocks timestamped more than this many seconds behind the local clock
+    /// when they extend the tip
+    pub max_past_drift_secs: i64,
+    /// Number of preceding blocks the target difficulty is compared against
+    pub difficulty_window: usize,
+    /// Flag blocks whose target difficulty is below this fraction of the window average
+    pub min_difficulty_ratio: f64,
+    /// Number of recent blocks nominations are counted over
+    pub nomination_window: usize,
+    /// Flag peers nominated in more than this fraction of the window
+    pub max_nomination_share: f64,
+}
+
+impl AnomalyConfig {
+    /// Number of preceding canonical blocks the checks look at
+    pub fn history_len(&self) -> usize {
+        self.difficulty_window.max(self.nomination_window)
+    }
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            max_future_drift_secs: 2 * 60 * 60,
+            max_past_drift_secs: 24 * 60 * 60,
+            difficulty_window: 10,
+            min_difficulty_ratio: 0.5,
+            nomination_window: 100,
+            max_nomination_share: 0.5,
+        }
+    }
+}
+
+/// What kind of pattern an alert flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    FutureTimestamp,
+    StaleTimestamp,
+    DifficultyDrop,
+    NominationConcentration,
+}
+
+impl AlertKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertKind::FutureTimestamp => "future_timestamp",
+            AlertKind::StaleTimestamp => "stale_timestamp",
+            AlertKind::DifficultyDrop => "difficulty_drop",
+            AlertKind::NominationConcentration => "nomination_concentration",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertSeverity {
+    Warning,
+    Critical,
+}
+
+impl AlertSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertSeverity::Warning => "warning",
+            AlertSeverity::Critical => "critical",
+        }
+    }
+}
+
+/// A suspicious pattern seen at a block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Alert {
+    pub kind: AlertKind,
+    pub severity: AlertSeverity,
+    pub block_index: u64,
+    pub block_hash: String,
+    pub message: String,
+    /// Unix timestamp of detection
+    pub detected_at: i64,
+}
+
+/// Checks blocks for anomalies and keeps the most recent alerts
+#[derive(Debug, Clone, Default)]
+pub struct AnomalyDetector {
+    config: AnomalyConfig,
+    alerts: VecDeque<Alert>,
+}
+
+impl AnomalyDetector {
+    pub fn new(config: AnomalyConfig) -> Self {
+        Self {
+            config,
+            alerts: VecDeque::new(),
+        }
+    }
+
+    pub fn config(&self) -> &AnomalyConfig {
+        &self.config
+    }
+
+    /// Check `block` against the canonical blocks before it (`history`, in
+    /// index order) at local time `now`. Alerts are logged and recorded.
+    pub fn check_block(&mut self, block: &MinerBlock, history: &[MinerBlock], now: i64) -> Vec<Alert> {
+        let alerts = self.detect(block, history, now);
+        for alert in &alerts {
+            log::warn!("Anomaly at block {} ({:?}): {}", alert.block_index, alert.kind, alert.message);
+            if self.alerts.len() == MAX_RECENT_ALERTS {
+                self.alerts.pop_front();
+            }
+            self.alerts.push_back(alert.clone());
+        }
+        alerts
+    }
+
+    /// Recent alerts, newest first
+    pub fn recent_alerts(&self) -> Vec<Alert> {
+        self.alerts.iter().rev().cloned().collect()
+    }
+
+    fn detect(&self, block: &MinerBlock, history: &[MinerBlock], now: i64) -> Vec<Alert> {
+        let config = &self.config;
+        let alert = |kind, severity, message: String| Alert {
+            kind,
+            severity,
+            block_index: block.index,
+            block_hash: block.hash.clone(),
+            message,
+            detected_at: now,
+        };
+        let mut alerts = Vec::new();
+
+        let drift = block.timestamp - now;
+        if drift > config.max_future_drift_secs {
+            alerts.push(alert(
+                AlertKind::FutureTimestamp,
+                AlertSeverity::Critical,
+                format!("timestamp {} is {}s ahead of local time", block.timestamp, drift),
+            ));
+        } else if -drift > config.max_past_drift_secs && history.last().map(|p| p.index + 1) == Some(block.index) {
+            // Old blocks are expected while syncing; only the tip should be recent
+            alerts.push(alert(
+                AlertKind::StaleTimestamp,
+                AlertSeverity::Warning,
+                format!("timestamp {} is {}s behind local time", block.timestamp, -drift),
+            ));
+        }
+
+        let window = &history[history.len().saturating_sub(config.difficulty_window)..];
+        let difficulties: Vec<u128> = window.iter().filter_map(|b| b.get_target_difficulty_u128().ok()).collect();
+        if let (Ok(difficulty), false) = (block.get_target_difficulty_u128(), difficulties.is_empty()) {
+            let average = difficulties.iter().sum::<u128>() as f64 / difficulties.len() as f64;
+            if (difficulty as f64) < average * config.min_difficulty_ratio {
+                alerts.push(alert(
+                    AlertKind::DifficultyDrop,
+                    AlertSeverity::Warning,
+                    format!(
+                        "target difficulty {} is {:.0}% of the {}-block average {:.0}",
+                        difficulty,
+                        difficulty as f64 / average * 100.0,
+                        difficulties.len(),
+                        average
+                    ),
+                ));
+            }
+        }
+
+        // Only meaningful once the window is full
+        if history.len() + 1 >= config.nomination_window && config.nomination_window > 0 {
+            let window = &history[history.len() + 1 - config.nomination_window..];
+            let nominations = window
+                .iter()
+                .chain(std::iter::once(block))
+                .filter(|b| b.nominated_peer_id == block.nominated_peer_id)
+                .count();
+            let share = nominations as f64 / config.nomination_window as f64;
+            if share > config.max_nomination_share {
+                alerts.push(alert(
+                    AlertKind::NominationConcentration,
+                    AlertSeverity::Warning,
+                    format!(
+                        "peer {} nominated in {} of the last {} blocks",
+                        block.nominated_peer_id, nominations, config.nomination_window
+                    ),
+                ));
+            }
+        }
+
+        alerts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn block(index: u64, timestamp: i64, difficulty: u128, nominee: &str) -> MinerBlock {
+        MinerBlock::new_canonical(
+            format!("block_{}", index),
+            index,
+            0,
+            timestamp,
+            String::new(),
+            String::new(),
+            0,
+            difficulty,
+            nominee.to_string(),
+            index,
+        )
+    }
+
+    #[test]
+    fn test_detects_anomalies() {
+        let mut detector = AnomalyDetector::new(AnomalyConfig {
+            nomination_window: 4,
+            ..Default::default()
+        });
+        let history: Vec<MinerBlock> = (0..3)
+            .map(|i| block(i, NOW - 60, 1000, if i == 0 { "a" } else { "b" }))
+            .collect();
+
+        assert!(detector.check_block(&block(3, NOW, 900, "a"), &history, NOW).is_empty());
+
+        let alerts = detector.check_block(&block(3, NOW + 3 * 60 * 60, 400, "b"), &history, NOW);
+        let kinds: Vec<AlertKind> = alerts.iter().map(|a| a.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![AlertKind::FutureTimestamp, AlertKind::DifficultyDrop, AlertKind::NominationConcentration]
+        );
+
+        // A stale timestamp only matters at the tip
+        let stale = block(3, NOW - 2 * 24 * 60 * 60, 1000, "a");
+        assert_eq!(detector.check_block(&stale, &history, NOW)[0].kind, AlertKind::StaleTimestamp);
+        assert!(detector.check_block(&stale, &history[..1], NOW).is_empty());
+
+        let recent = detector.recent_alerts();
+        assert_eq!(recent.len(), 4);
+        assert_eq!(recent[0].kind, AlertKind::StaleTimestamp);
+    }
+}
@@ -1,9 +1,15 @@
 use anyhow::Result;
+use modal_common::block_commits::{commits_root, validate_commits, CommitLimits};
+use modal_common::difficulty::{DifficultyAlgorithm, DifficultyParams, DifficultySample};
+use modal_datastore::models::miner::{MinerBranch, MinerCheckpoint, OrphanPoolEntry};
 use modal_datastore::models::MinerBlock;
 use modal_datastore::DatastoreManager;
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, Mutex};
+
+use crate::anomaly::{Alert, AnomalyConfig, AnomalyDetector};
+use crate::reorg::{BlockRef, ReorgEvent, REORG_CHANNEL_CAPACITY};
 
 /// Safely truncate a hash string for display in log messages
 fn truncate_hash(hash: &str) -> String {
@@ -14,6 +20,9 @@ fn truncate_hash(hash: &str) -> String {
     }
 }
 
+/// How long an orphan waits in the pool for its parent before it's evicted
+pub const DEFAULT_ORPHAN_POOL_TTL_SECS: i64 = 60 * 60;
+
 /// Configuration for forced fork specification
 /// Allows node operators to override fork choice rules at specific heights
 #[derive(Debug, Clone)]
@@ -27,6 +36,8 @@ pub struct ForkConfig {
     pub fork_recovery_min_peers: Option<usize>,
     /// Pause mining if peers report chains this many epochs ahead
     pub fork_recovery_epoch_threshold: Option<u64>,
+    /// Refuse reorgs that would replace more than this many canonical blocks
+    pub max_reorg_depth: Option<u64>,
 }
 
 impl ForkConfig {
@@ -37,6 +48,7 @@ impl ForkConfig {
             minimum_block_timestamp: None,
             fork_recovery_min_peers: None,
             fork_recovery_epoch_threshold: None,
+            max_reorg_depth: None,
         }
     }
     
@@ -47,6 +59,7 @@ impl ForkConfig {
             minimum_block_timestamp: None,
             fork_recovery_min_peers: None,
             fork_recovery_epoch_threshold: None,
+            max_reorg_depth: None,
         }
     }
     
@@ -56,6 +69,12 @@ impl ForkConfig {
         self
     }
     
+    /// Create a fork config with a maximum reorg depth
+    pub fn with_max_reorg_depth(mut self, depth: u64) -> Self {
+        self.max_reorg_depth = Some(depth);
+        self
+    }
+    
     /// Check if a block is required at this height
     pub fn is_forced_at(&self, height: u64) -> bool {
         self.forced_blocks.contains_key(&height)
@@ -99,6 +118,11 @@ pub struct ChainObserver {
     datastore: Arc<Mutex<DatastoreManager>>,
     chain_tip_index: Arc<Mutex<u64>>,
     fork_config: ForkConfig,
+    difficulty_validation: Option<(DifficultyParams, Arc<dyn DifficultyAlgorithm>)>,
+    commit_limits: CommitLimits,
+    orphan_pool_ttl_secs: i64,
+    reorg_tx: broadcast::Sender<ReorgEvent>,
+    anomaly_detector: Arc<Mutex<AnomalyDetector>>,
 }
 
 impl ChainObserver {
@@ -108,6 +132,11 @@ impl ChainObserver {
             datastore,
             chain_tip_index: Arc::new(Mutex::new(0)),
             fork_config: ForkConfig::new(),
+            difficulty_validation: None,
+            commit_limits: CommitLimits::default(),
+            orphan_pool_ttl_secs: DEFAULT_ORPHAN_POOL_TTL_SECS,
+            reorg_tx: broadcast::channel(REORG_CHANNEL_CAPACITY).0,
+            anomaly_detector: Arc::new(Mutex::new(AnomalyDetector::default())),
         }
     }
     
@@ -117,7 +146,420 @@ impl ChainObserver {
             datastore,
             chain_tip_index: Arc::new(Mutex::new(0)),
             fork_config,
+            difficulty_validation: None,
+            commit_limits: CommitLimits::default(),
+            orphan_pool_ttl_secs: DEFAULT_ORPHAN_POOL_TTL_SECS,
+            reorg_tx: broadcast::channel(REORG_CHANNEL_CAPACITY).0,
+            anomaly_detector: Arc::new(Mutex::new(AnomalyDetector::default())),
+        }
+    }
+    
+    /// Reject gossiped blocks whose target difficulty doesn't match the given algorithm
+    ///
+    /// Blocks are only checked when their parent is the current canonical tip
+    /// and the canonical chain below it is complete.
+    pub fn with_difficulty_validation(
+        mut self,
+        params: DifficultyParams,
+        algorithm: Arc<dyn DifficultyAlgorithm>,
+    ) -> Self {
+        self.difficulty_validation = Some((params, algorithm));
+        self
+    }
+    
+    /// Bound the contract commits a gossiped block may include
+    pub fn with_commit_limits(mut self, commit_limits: CommitLimits) -> Self {
+        self.commit_limits = commit_limits;
+        self
+    }
+    
+    /// Evict orphans that have waited this long for their parent
+    pub fn with_orphan_pool_ttl(mut self, ttl_secs: i64) -> Self {
+        self.orphan_pool_ttl_secs = ttl_secs;
+        self
+    }
+    
+    /// Use custom thresholds for anomaly alerts
+    pub fn with_anomaly_config(mut self, config: AnomalyConfig) -> Self {
+        self.anomaly_detector = Arc::new(Mutex::new(AnomalyDetector::new(config)));
+        self
+    }
+    
+    /// Recent anomaly alerts raised for accepted blocks, newest first
+    pub async fn get_alerts(&self) -> Vec<Alert> {
+        self.anomaly_detector.lock().await.recent_alerts()
+    }
+    
+    /// Run the anomaly checks on a newly accepted block
+    async fn check_anomalies(&self, block: &MinerBlock) -> Result<()> {
+        let mut detector = self.anomaly_detector.lock().await;
+        let history_len = detector.config().history_len();
+        let history: Vec<MinerBlock> = {
+            let ds = self.datastore.lock().await;
+            MinerBlock::find_all_canonical_multi(&ds).await?
+        }
+        .into_iter()
+        .filter(|b| b.index < block.index)
+        .collect();
+        let history = &history[history.len().saturating_sub(history_len)..];
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        detector.check_block(block, history, now);
+        Ok(())
+    }
+    
+    /// Receive an event every time canonical blocks are replaced
+    pub fn subscribe_reorgs(&self) -> broadcast::Receiver<ReorgEvent> {
+        self.reorg_tx.subscribe()
+    }
+    
+    /// Publish a reorg from `old_canonical` to `new_canonical`, which both
+    /// start right after their common ancestor
+    async fn publish_reorg(
+        &self,
+        ds: &DatastoreManager,
+        old_canonical: &[MinerBlock],
+        new_canonical: &[MinerBlock],
+        reason: String,
+    ) -> Result<()> {
+        let (Some(old_tip), Some(new_tip)) = (old_canonical.last(), new_canonical.last()) else {
+            return Ok(());
+        };
+        let common_ancestor = match new_canonical.first() {
+            Some(first) if first.index > 0 => MinerBlock::find_by_hash_multi(ds, &first.previous_hash)
+                .await?
+                .map(|b| BlockRef::from(&b)),
+            _ => None,
+        };
+        let event = ReorgEvent::new(
+            old_tip.into(),
+            new_tip.into(),
+            common_ancestor,
+            old_canonical.iter().map(BlockRef::from).collect(),
+            reason,
+        );
+        log::info!(
+            "Reorg of depth {}: tip {} at {} -> {} at {}",
+            event.depth(), truncate_hash(&event.old_tip.hash), event.old_tip.index,
+            truncate_hash(&event.new_tip.hash), event.new_tip.index
+        );
+        // No subscribers is fine
+        let _ = self.reorg_tx.send(event);
+        Ok(())
+    }
+    
+    /// Wait for `block`'s parent in the orphan pool
+    async fn add_to_orphan_pool(&self, ds: &DatastoreManager, block: &MinerBlock) -> Result<()> {
+        let evicted = OrphanPoolEntry::evict_expired_multi(ds, self.orphan_pool_ttl_secs).await?;
+        if evicted > 0 {
+            log::debug!("Evicted {} expired orphans from the pool", evicted);
+        }
+        OrphanPoolEntry::new(block.hash.clone(), block.index, block.previous_hash.clone())
+            .save(ds)
+            .await
+    }
+    
+    /// Re-evaluate the orphans waiting for `parent_hash`, and in turn the
+    /// orphans waiting for them
+    async fn process_orphans_of(&self, parent_hash: String) -> Result<usize> {
+        let mut promoted = 0;
+        let mut parents = vec![parent_hash];
+        while let Some(parent_hash) = parents.pop() {
+            let waiting = {
+                let ds = self.datastore.lock().await;
+                OrphanPoolEntry::take_by_parent_multi(&ds, &parent_hash).await?
+            };
+            for entry in waiting {
+                let orphan = {
+                    let ds = self.datastore.lock().await;
+                    MinerBlock::find_by_hash_multi(&ds, &entry.block_hash).await?
+                };
+                let Some(mut orphan) = orphan else {
+                    continue;
+                };
+                // Re-evaluate it as if freshly gossiped, so a path that saves
+                // it as canonical doesn't keep the stored orphan state
+                orphan.is_canonical = true;
+                orphan.is_orphaned = false;
+                orphan.orphan_reason = None;
+                orphan.orphaned_at = None;
+                orphan.competing_hash = None;
+                log::debug!("Parent {} arrived, re-evaluating orphan {} at index {}",
+                    truncate_hash(&parent_hash), truncate_hash(&orphan.hash), orphan.index);
+                if self.process_block(orphan).await? {
+                    promoted += 1;
+                }
+                parents.push(entry.block_hash);
+            }
+        }
+        Ok(promoted)
+    }
+    
+    /// Get the orphans waiting for a missing parent
+    pub async fn get_orphan_pool(&self) -> Result<Vec<OrphanPoolEntry>> {
+        let ds = self.datastore.lock().await;
+        OrphanPoolEntry::find_all_multi(&ds).await
+    }
+    
+    /// Check a block's included commits: their root, the per-block limits,
+    /// and that none was already included by an earlier canonical block
+    async fn check_commits(&self, ds: &DatastoreManager, block: &MinerBlock) -> Result<Result<(), String>> {
+        if block.commits_root != commits_root(&block.commits) {
+            return Ok(Err("commits root does not match the included commits".to_string()));
+        }
+        if let Err(e) = validate_commits(&block.commits, &self.commit_limits) {
+            return Ok(Err(e));
+        }
+        if block.commits.is_empty() {
+            return Ok(Ok(()));
+        }
+        for earlier in MinerBlock::find_all_canonical_multi(ds).await? {
+            if earlier.index >= block.index {
+                continue;
+            }
+            if let Some(dup) = block.commits.iter().find(|c| earlier.commits.contains(c)) {
+                return Ok(Err(format!(
+                    "commit {} was already included in block {}",
+                    dup.commit_id, earlier.index
+                )));
+            }
+        }
+        Ok(Ok(()))
+    }
+    
+    /// Expected target difficulty for `block`, if it can be determined from the canonical chain
+    async fn expected_difficulty(&self, ds: &DatastoreManager, block: &MinerBlock) -> Result<Option<u128>> {
+        let Some((params, algorithm)) = self.difficulty_validation.as_ref() else {
+            return Ok(None);
+        };
+        if block.index == 0 {
+            return Ok(None);
+        }
+        
+        let mut history: Vec<MinerBlock> = MinerBlock::find_all_canonical_multi(ds)
+            .await?
+            .into_iter()
+            .filter(|b| b.index < block.index)
+            .collect();
+        history.sort_by_key(|b| b.index);
+        
+        let complete = history.len() as u64 == block.index
+            && history.iter().enumerate().all(|(i, b)| b.index == i as u64);
+        let extends_parent = history.last().is_some_and(|parent| parent.hash == block.previous_hash);
+        if !complete || !extends_parent {
+            return Ok(None);
+        }
+        
+        let samples: Vec<DifficultySample> = history
+            .iter()
+            .filter_map(|b| {
+                Some(DifficultySample {
+                    index: b.index,
+                    timestamp: b.timestamp,
+                    difficulty: b.target_difficulty.parse().ok()?,
+                })
+            })
+            .collect();
+        Ok(Some(algorithm.next_difficulty(params, block.index, &samples)))
+    }
+    
+    /// Trace `block` back through stored non-canonical blocks to the canonical chain
+    ///
+    /// Returns the index of the canonical block it forks from (None for a
+    /// branch from genesis) and the non-canonical ancestors between that block
+    /// and `block`, lowest first. Returns None if an ancestor is missing.
+    async fn trace_branch(&self, ds: &DatastoreManager, block: &MinerBlock) -> Result<Option<(Option<u64>, Vec<MinerBlock>)>> {
+        let mut ancestors = Vec::new();
+        let mut child_index = block.index;
+        let mut parent_hash = block.previous_hash.clone();
+        
+        while child_index > 0 {
+            let Some(parent) = MinerBlock::find_by_hash_multi(ds, &parent_hash).await? else {
+                return Ok(None);
+            };
+            if parent.index + 1 != child_index {
+                return Ok(None);
+            }
+            if parent.is_canonical {
+                ancestors.reverse();
+                return Ok(Some((Some(parent.index), ancestors)));
+            }
+            child_index = parent.index;
+            parent_hash = parent.previous_hash.clone();
+            ancestors.push(parent);
+        }
+        
+        ancestors.reverse();
+        Ok(Some((None, ancestors)))
+    }
+    
+    /// Canonical blocks above `fork_index` (all of them for a fork at genesis)
+    async fn canonical_above(ds: &DatastoreManager, fork_index: Option<u64>) -> Result<Vec<MinerBlock>> {
+        let mut blocks: Vec<MinerBlock> = MinerBlock::find_all_canonical_multi(ds)
+            .await?
+            .into_iter()
+            .filter(|b| fork_index.is_none_or(|fork| b.index > fork))
+            .collect();
+        blocks.sort_by_key(|b| b.index);
+        Ok(blocks)
+    }
+    
+    /// Why a reorg forking at `fork_index` isn't allowed, if it isn't: it
+    /// would replace more blocks than the max reorg depth, or rewrite history
+    /// covered by a checkpoint
+    async fn reorg_violation(&self, ds: &DatastoreManager, fork_index: Option<u64>) -> Result<Option<String>> {
+        let tip = *self.chain_tip_index.lock().await;
+        let depth = match fork_index {
+            Some(fork) => tip.saturating_sub(fork),
+            None => tip + 1,
+        };
+        if let Some(max_depth) = self.fork_config.max_reorg_depth {
+            if depth > max_depth {
+                return Ok(Some(format!(
+                    "reorg of {} blocks exceeds the maximum depth of {}",
+                    depth, max_depth
+                )));
+            }
+        }
+        if let Some(checkpoint) = MinerCheckpoint::find_latest_multi(ds).await? {
+            if fork_index.is_none_or(|fork| fork < checkpoint.last_block_index) {
+                return Ok(Some(format!(
+                    "branch forks below the checkpoint at block {}",
+                    checkpoint.last_block_index
+                )));
+            }
+        }
+        Ok(None)
+    }
+    
+    /// Replace the canonical blocks above `fork_index` with `branch`
+    async fn reorganize(&self, ds: &DatastoreManager, fork_index: Option<u64>, branch: &[MinerBlock], reason: String) -> Result<()> {
+        let old_canonical = Self::canonical_above(ds, fork_index).await?;
+        for canonical in old_canonical.iter().cloned() {
+            let replacement = branch.iter().find(|b| b.index == canonical.index).map(|b| b.hash.clone());
+            let mut orphaned = canonical;
+            orphaned.mark_as_orphaned(reason.clone(), replacement);
+            orphaned.save_to_active(ds).await?;
+            log::debug!("Orphaned old canonical block {} at index {}", &orphaned.hash, orphaned.index);
+        }
+        
+        for block in branch {
+            let mut promoted = MinerBlock::find_by_hash_multi(ds, &block.hash).await?.unwrap_or_else(|| block.clone());
+            promoted.is_canonical = true;
+            promoted.is_orphaned = false;
+            promoted.orphan_reason = None;
+            promoted.orphaned_at = None;
+            promoted.competing_hash = None;
+            promoted.save_to_active(ds).await?;
+            log::debug!("Promoted block {} at index {} to canonical", &promoted.hash, promoted.index);
+        }
+        
+        if let Some(new_tip) = branch.last() {
+            *self.chain_tip_index.lock().await = new_tip.index;
+        }
+        self.publish_reorg(ds, &old_canonical, branch, reason).await
+    }
+    
+    /// Fork choice for a block on a branch off the canonical chain: adopt the
+    /// branch if it now has more cumulative difficulty than the canonical
+    /// blocks above the fork point, otherwise track it in MinerForks
+    async fn process_branch_block(
+        &self,
+        ds: &DatastoreManager,
+        new_block: MinerBlock,
+        fork_index: Option<u64>,
+        ancestors: Vec<MinerBlock>,
+    ) -> Result<bool> {
+        let mut branch = ancestors;
+        branch.push(new_block.clone());
+        let canonical = Self::canonical_above(ds, fork_index).await?;
+        
+        let branch_difficulty = MinerBlock::calculate_cumulative_difficulty(&branch)?;
+        let canonical_difficulty = MinerBlock::calculate_cumulative_difficulty(&canonical)?;
+        let fork_label = fork_index.map_or("genesis".to_string(), |fork| fork.to_string());
+        
+        log::info!(
+            "Branch from {} at block {}: {} blocks with difficulty {} vs canonical {} blocks with difficulty {}",
+            fork_label, new_block.index, branch.len(), branch_difficulty, canonical.len(), canonical_difficulty
+        );
+        
+        // Heavier branch wins; equal difficulty goes to the longer branch
+        let heavier = branch_difficulty > canonical_difficulty
+            || (branch_difficulty == canonical_difficulty && branch.len() > canonical.len());
+        
+        if heavier {
+            if let Some(violation) = self.reorg_violation(ds, fork_index).await? {
+                log::warn!("Block {} at height {} rejected: {}", &new_block.hash, new_block.index, violation);
+                let mut orphaned = new_block;
+                orphaned.is_canonical = false;
+                orphaned.is_orphaned = true;
+                orphaned.orphan_reason = Some(format!("Rejected: {}", violation));
+                orphaned.save_to_active(ds).await?;
+                return Ok(false);
+            }
+            
+            self.reorganize(
+                ds,
+                fork_index,
+                &branch,
+                format!(
+                    "Replaced by branch with higher cumulative difficulty ({} vs {})",
+                    branch_difficulty, canonical_difficulty
+                ),
+            ).await?;
+            MinerBranch::delete_by_tip_multi(ds, &new_block.previous_hash).await?;
+            
+            // The replaced blocks become a branch that can win back later
+            if let Some(old_tip) = canonical.last() {
+                MinerBranch::new(
+                    old_tip.hash.clone(),
+                    old_tip.index,
+                    fork_index,
+                    canonical_difficulty,
+                    canonical.len() as u64,
+                )
+                .save_to_forks(ds, None)
+                .await?;
+            }
+            
+            log::info!(
+                "🔀 Reorganized to branch from {}: {} blocks replaced, new tip {} at index {}",
+                fork_label, canonical.len(), &new_block.hash, new_block.index
+            );
+            return Ok(true);
         }
+        
+        let competing_hash = canonical.iter().find(|b| b.index == new_block.index).map(|b| b.hash.clone());
+        let mut orphaned = new_block;
+        orphaned.is_canonical = false;
+        orphaned.is_orphaned = true;
+        orphaned.orphan_reason = Some(format!(
+            "Fork detected: branch from {} has cumulative difficulty {} vs canonical {}",
+            fork_label, branch_difficulty, canonical_difficulty
+        ));
+        orphaned.competing_hash = competing_hash;
+        orphaned.save_to_active(ds).await?;
+        
+        MinerBranch::new(
+            orphaned.hash.clone(),
+            orphaned.index,
+            fork_index,
+            branch_difficulty,
+            branch.len() as u64,
+        )
+        .save_to_forks(ds, Some(&orphaned.previous_hash))
+        .await?;
+        
+        log::debug!("Stored block {} at index {} on branch from {}", &orphaned.hash, orphaned.index, fork_label);
+        Ok(false)
+    }
+    
+    /// Get the fork branches currently tracked, heaviest first
+    pub async fn get_fork_branches(&self) -> Result<Vec<MinerBranch>> {
+        let ds = self.datastore.lock().await;
+        MinerBranch::find_all_multi(&ds).await
     }
     
     /// Initialize the observer by loading the current chain tip
@@ -279,7 +721,23 @@ impl ChainObserver {
     
     /// Process a gossiped block with proper fork choice rules
     /// Returns Ok(true) if block was accepted, Ok(false) if rejected
+    ///
+    /// Orphans waiting in the pool for this block are re-evaluated afterwards.
     pub async fn process_gossiped_block(&self, new_block: MinerBlock) -> Result<bool> {
+        let hash = new_block.hash.clone();
+        let accepted = self.process_block(new_block.clone()).await?;
+        if accepted {
+            self.check_anomalies(&new_block).await?;
+        }
+        let promoted = self.process_orphans_of(hash).await?;
+        if promoted > 0 {
+            log::info!("Promoted {} orphans from the pool after their parent arrived", promoted);
+        }
+        Ok(accepted)
+    }
+    
+    /// Fork choice for a single block, without touching the orphan pool's dependents
+    async fn process_block(&self, new_block: MinerBlock) -> Result<bool> {
         let ds = self.datastore.lock().await;
         
         // Check if this block violates timestamp requirements
@@ -331,6 +789,59 @@ impl ChainObserver {
             }
         }
         
+        // Check the target difficulty against the configured adjustment algorithm
+        if let Some(expected) = self.expected_difficulty(&ds, &new_block).await? {
+            if new_block.target_difficulty != expected.to_string() {
+                log::warn!(
+                    "Block {} at height {} rejected: target difficulty {} but expected {}",
+                    &new_block.hash, new_block.index, new_block.target_difficulty, expected
+                );
+                
+                let mut orphaned = new_block;
+                orphaned.is_canonical = false;
+                orphaned.is_orphaned = true;
+                orphaned.orphan_reason = Some(format!(
+                    "Rejected: target difficulty {} does not match expected {}",
+                    orphaned.target_difficulty, expected
+                ));
+                orphaned.save_to_active(&ds).await?;
+                
+                return Ok(false);
+            }
+        }
+        
+        // Check the uncles it references
+        if let Err(e) = modal_common::uncles::validate_uncles(new_block.index, &new_block.uncles) {
+            log::warn!(
+                "Block {} at height {} rejected: invalid uncles: {}",
+                &new_block.hash, new_block.index, e
+            );
+            
+            let mut orphaned = new_block;
+            orphaned.is_canonical = false;
+            orphaned.is_orphaned = true;
+            orphaned.orphan_reason = Some(format!("Rejected: invalid uncles: {}", e));
+            orphaned.save_to_active(&ds).await?;
+            
+            return Ok(false);
+        }
+        
+        // Check the contract commits it includes
+        if let Err(e) = self.check_commits(&ds, &new_block).await? {
+            log::warn!(
+                "Block {} at height {} rejected: invalid commits: {}",
+                &new_block.hash, new_block.index, e
+            );
+            
+            let mut orphaned = new_block;
+            orphaned.is_canonical = false;
+            orphaned.is_orphaned = true;
+            orphaned.orphan_reason = Some(format!("Rejected: invalid commits: {}", e));
+            orphaned.save_to_active(&ds).await?;
+            
+            return Ok(false);
+        }
+        
         // Check if we already have this exact block
         if let Ok(Some(existing)) = MinerBlock::find_by_hash_multi(&ds, &new_block.hash).await {
             // If it's already canonical or orphaned due to first-seen rule, skip it
@@ -349,10 +860,47 @@ impl ChainObserver {
             }
         }
         
+        // Blocks that build on a branch, or compete with canonical blocks
+        // below the tip, are decided by cumulative difficulty
+        if !self.fork_config.is_forced_at(new_block.index) {
+            let tip = *self.chain_tip_index.lock().await;
+            if let Some((fork_index, ancestors)) = self.trace_branch(&ds, &new_block).await? {
+                let extends_tip = fork_index == Some(tip);
+                let competes_with_tip = ancestors.is_empty() && new_block.index == tip;
+                if !extends_tip && !competes_with_tip {
+                    return self.process_branch_block(&ds, new_block, fork_index, ancestors).await;
+                }
+            }
+        }
+        
         // Check if there's an existing canonical block at this index
         // Calculate current epoch from chain tip (or use block's epoch as estimate)
         let current_epoch = ds.block_index_to_epoch(*self.chain_tip_index.lock().await);
         if let Some(existing) = MinerBlock::find_canonical_by_index_multi(&ds, new_block.index, current_epoch).await? {
+            // Only a block on the same canonical parent can replace it; one
+            // whose ancestors are missing waits for them in the orphan pool
+            if !self.fork_config.is_forced_at(new_block.index) && new_block.index > 0 {
+                let parent = MinerBlock::find_canonical_by_index_multi(&ds, new_block.index - 1, current_epoch).await?;
+                if parent.is_none_or(|p| p.hash != new_block.previous_hash) {
+                    let mut orphaned = new_block;
+                    orphaned.is_canonical = false;
+                    orphaned.is_orphaned = true;
+                    orphaned.orphan_reason = Some(format!(
+                        "Parent not found: block references parent hash {} which is not in the canonical chain. Missing block at index {}.",
+                        truncate_hash(&orphaned.previous_hash),
+                        orphaned.index - 1
+                    ));
+                    orphaned.competing_hash = Some(existing.hash.clone());
+                    orphaned.save_to_active(&ds).await?;
+                    self.add_to_orphan_pool(&ds, &orphaned).await?;
+
+                    log::debug!(
+                        "Stored orphan block {} at index {} (ancestors missing, competes with canonical block {})",
+                        truncate_hash(&orphaned.hash), orphaned.index, truncate_hash(&existing.hash)
+                    );
+                    return Ok(false);
+                }
+            }
             drop(ds);
             
             // Check if forced fork requires replacement
@@ -371,6 +919,12 @@ impl ChainObserver {
                 
                 // Save new block as canonical
                 new_block.save_to_active(&ds).await?;
+                self.publish_reorg(
+                    &ds,
+                    std::slice::from_ref(&existing),
+                    std::slice::from_ref(&new_block),
+                    "Replaced by forced fork specification".to_string(),
+                ).await?;
                 
                 // Update chain tip if needed
                 let current_tip = *self.chain_tip_index.lock().await;
@@ -390,15 +944,19 @@ impl ChainObserver {
                 let existing_actualized = existing.get_actualized_difficulty_u128().unwrap_or(0);
                 
                 // Mark old block as orphaned
+                let reason = format!("Replaced by block with higher actualized difficulty ({} vs {})", new_actualized, existing_actualized);
                 let mut orphaned = existing.clone();
-                orphaned.mark_as_orphaned(
-                    format!("Replaced by block with higher actualized difficulty ({} vs {})", new_actualized, existing_actualized),
-                    Some(new_block.hash.clone())
-                );
+                orphaned.mark_as_orphaned(reason.clone(), Some(new_block.hash.clone()));
                 orphaned.save_to_active(&ds).await?;
                 
                 // Save new block as canonical
                 new_block.save_to_active(&ds).await?;
+                self.publish_reorg(
+                    &ds,
+                    std::slice::from_ref(&existing),
+                    std::slice::from_ref(&new_block),
+                    reason,
+                ).await?;
                 
                 // Update chain tip if needed
                 let current_tip = *self.chain_tip_index.lock().await;
@@ -494,6 +1052,7 @@ impl ChainObserver {
                     &prev_hash_short
                 ));
                 orphaned.save_to_active(&ds).await?;
+                self.add_to_orphan_pool(&ds, &orphaned).await?;
 
                 log::debug!(
                     "Stored orphan block {} at index {} (fork - parent hash mismatch)",
@@ -551,6 +1110,7 @@ impl ChainObserver {
             orphaned.index - 1
         ));
         orphaned.save_to_active(&ds).await?;
+        self.add_to_orphan_pool(&ds, &orphaned).await?;
 
         log::debug!(
             "Stored orphan block {} at index {} (parent not found in canonical chain)",
@@ -649,6 +1209,14 @@ impl ChainObserver {
             }
         };
         
+        // Refuse reorgs that are too deep or rewrite checkpointed history
+        {
+            let ds = self.datastore.lock().await;
+            if let Some(violation) = self.reorg_violation(&ds, fork_point).await? {
+                anyhow::bail!("Competing chain rejected: {}", violation);
+            }
+        }
+        
         // Step 1: Store all competing blocks as non-canonical
         {
             let ds = self.datastore.lock().await;
@@ -668,41 +1236,23 @@ impl ChainObserver {
             }
         }
         
-        // Step 2: Calculate cumulative difficulty of competing chain
+        // Step 2: Compare against the whole canonical chain above the fork point
         let competing_difficulty = MinerBlock::calculate_cumulative_difficulty(&sorted_blocks)?;
-        
-        // Step 3: Calculate cumulative difficulty of canonical chain at same range
-        let local_difficulty = if let Some(fork) = fork_point {
-            self.calculate_chain_difficulty_at_range(fork + 1, last_block.index).await?
-        } else {
-            // Competing from genesis
-            self.calculate_chain_difficulty_at_range(0, last_block.index).await?
-        };
+        let ds = self.datastore.lock().await;
+        let local_blocks = Self::canonical_above(&ds, fork_point).await?;
+        let local_difficulty = MinerBlock::calculate_cumulative_difficulty(&local_blocks)?;
         
         log::info!(
             "Chain weight comparison: local difficulty {} vs competing difficulty {}",
             local_difficulty, competing_difficulty
         );
         
-        // Step 4: Decide whether to adopt
-        let should_adopt = if competing_difficulty > local_difficulty {
-            true
-        } else if competing_difficulty == local_difficulty {
-            // Tiebreaker: check if competing chain is longer
-            let ds = self.datastore.lock().await;
-            let canonical_in_range = MinerBlock::find_all_canonical_multi(&ds).await?;
-            let canonical_count = canonical_in_range.iter()
-                .filter(|b| b.index >= first_block.index && b.index <= last_block.index)
-                .count();
-            
-            sorted_blocks.len() > canonical_count
-        } else {
-            false
-        };
+        // Step 3: Decide whether to adopt (equal difficulty goes to the longer chain)
+        let should_adopt = competing_difficulty > local_difficulty
+            || (competing_difficulty == local_difficulty && sorted_blocks.len() > local_blocks.len());
         
         if !should_adopt {
             // Mark competing blocks as orphaned
-            let ds = self.datastore.lock().await;
             for block in &sorted_blocks {
                 if let Ok(Some(mut existing)) = MinerBlock::find_by_hash_multi(&ds, &block.hash).await {
                     if !existing.is_canonical {
@@ -723,54 +1273,31 @@ impl ChainObserver {
             return Ok(false);
         }
         
-        // Step 5: Adopt the competing chain
+        // Step 4: Adopt the competing chain
         log::info!(
             "Adopting competing chain: higher cumulative difficulty ({} vs {})",
             competing_difficulty, local_difficulty
         );
         
-        let ds = self.datastore.lock().await;
-        
-        // Orphan the old canonical blocks in the range
-        let canonical_blocks = MinerBlock::find_all_canonical_multi(&ds).await?;
-        for canonical in canonical_blocks {
-            if canonical.index >= first_block.index && canonical.index <= last_block.index {
-                let mut orphaned = canonical;
-                orphaned.is_canonical = false;
-                orphaned.is_orphaned = true;
-                orphaned.orphan_reason = Some(format!(
-                    "Replaced by competing chain with higher cumulative difficulty ({} vs {})",
-                    competing_difficulty, local_difficulty
-                ));
-                orphaned.save_to_active(&ds).await?;
-                
-                log::debug!("Orphaned old canonical block {} at index {}", &orphaned.hash, orphaned.index);
-            }
-        }
-        
-        // Promote competing blocks to canonical
-        for block in &sorted_blocks {
-            if let Ok(Some(mut existing)) = MinerBlock::find_by_hash_multi(&ds, &block.hash).await {
-                existing.is_canonical = true;
-                existing.is_orphaned = false;
-                existing.orphan_reason = None;
-                existing.save_to_active(&ds).await?;
-                
-                log::debug!("Promoted competing block {} at index {} to canonical", &existing.hash, existing.index);
-            }
-        }
-        
-        // Update chain tip
-        let current_tip = *self.chain_tip_index.lock().await;
-        if last_block.index > current_tip {
-            *self.chain_tip_index.lock().await = last_block.index;
-            log::info!("Updated chain tip to {}", last_block.index);
-        }
+        self.reorganize(
+            &ds,
+            fork_point,
+            &sorted_blocks,
+            format!(
+                "Replaced by competing chain with higher cumulative difficulty ({} vs {})",
+                competing_difficulty, local_difficulty
+            ),
+        ).await?;
+        log::info!("Updated chain tip to {}", last_block.index);
         
         log::info!(
             "✅ Successfully adopted competing chain: {} blocks from {} to {}",
             sorted_blocks.len(), first_block.index, last_block.index
         );
+        drop(ds);
+        
+        // Orphans that were waiting on the adopted blocks can now follow them
+        self.process_orphans_of(last_block.hash.clone()).await?;
         
         Ok(true)
     }
@@ -808,6 +1335,10 @@ mod tests {
             actualized_difficulty: actualized_difficulty.to_string(),
             nominated_peer_id: format!("peer_{}", index),
             miner_number: index,
+            uncles: Vec::new(),
+            commits: Vec::new(),
+            commits_root: String::new(),
+            evidence: Vec::new(),
             is_orphaned: false,
             is_canonical: true,
             seen_at: Some(chrono::Utc::now().timestamp()),
@@ -2079,5 +2610,273 @@ mod tests {
         
         assert_eq!(observer.get_chain_tip().await, 10);
     }
+    
+    #[tokio::test]
+    async fn test_difficulty_validation_rejects_wrong_target() {
+        let datastore = Arc::new(Mutex::new(
+            DatastoreManager::create_in_memory().unwrap()
+        ));
+        
+        {
+            let ds = datastore.lock().await;
+            create_test_chain(&ds, 0, 5, 1000).await;
+        }
+        
+        let params = DifficultyParams {
+            initial_difficulty: 1000,
+            target_block_time_secs: 60,
+            blocks_per_epoch: 40,
+            min_difficulty: 1,
+            max_difficulty: u128::MAX,
+        };
+        let observer = ChainObserver::new(datastore.clone())
+            .with_difficulty_validation(params, Arc::new(modal_common::difficulty::Fixed));
+        observer.initialize().await.unwrap();
+        
+        let wrong = create_test_block(6, "block_6_easy", "block_5", 500);
+        assert!(!observer.process_gossiped_block(wrong).await.unwrap());
+        let orphans = observer.get_orphaned_blocks_at_index(6).await.unwrap();
+        assert!(orphans[0].orphan_reason.as_ref().unwrap().contains("target difficulty"));
+        
+        let right = create_test_block(6, "block_6", "block_5", 1000);
+        assert!(observer.process_gossiped_block(right).await.unwrap());
+        assert_eq!(observer.get_chain_tip().await, 6);
+    }
+    
+    #[tokio::test]
+    async fn test_commit_validation() {
+        use modal_common::block_commits::CommitDigest;
+        
+        let datastore = Arc::new(Mutex::new(
+            DatastoreManager::create_in_memory().unwrap()
+        ));
+        
+        {
+            let ds = datastore.lock().await;
+            create_test_chain(&ds, 0, 5, 1000).await;
+        }
+        
+        let observer = ChainObserver::new(datastore.clone());
+        observer.initialize().await.unwrap();
+        
+        let commit = CommitDigest::new("contract".to_string(), "commit_1".to_string(), 100);
+        
+        // Root that doesn't cover the included commits
+        let mut tampered = create_test_block(6, "block_6_tampered", "block_5", 1000)
+            .with_commits(vec![commit.clone()]);
+        tampered.commits_root = "0".repeat(64);
+        assert!(!observer.process_gossiped_block(tampered).await.unwrap());
+        let orphans = observer.get_orphaned_blocks_at_index(6).await.unwrap();
+        assert!(orphans[0].orphan_reason.as_ref().unwrap().contains("invalid commits"));
+        
+        let block_6 = create_test_block(6, "block_6", "block_5", 1000).with_commits(vec![commit.clone()]);
+        assert!(observer.process_gossiped_block(block_6).await.unwrap());
+        
+        // The same commit can't be included again
+        let repeat = create_test_block(7, "block_7", "block_6", 1000).with_commits(vec![commit]);
+        assert!(!observer.process_gossiped_block(repeat).await.unwrap());
+        assert_eq!(observer.get_chain_tip().await, 6);
+    }
+    
+    // Heaviest-chain fork choice tests
+    async fn gossip_fork_branch(observer: &ChainObserver) -> Vec<bool> {
+        // Branch from block_3 that overtakes blocks 4-5 once it reaches index 6
+        let mut accepted = Vec::new();
+        for (index, prev) in [(4, "block_3"), (5, "fork_4"), (6, "fork_5")] {
+            let block = create_test_block(index, &format!("fork_{}", index), prev, 1000);
+            accepted.push(observer.process_gossiped_block(block).await.unwrap());
+        }
+        accepted
+    }
+    
+    #[tokio::test]
+    async fn test_gossiped_branch_reorgs_when_heavier() {
+        let datastore = Arc::new(Mutex::new(
+            DatastoreManager::create_in_memory().unwrap()
+        ));
+        
+        {
+            let ds = datastore.lock().await;
+            create_test_chain(&ds, 0, 5, 1000).await;
+        }
+        
+        let observer = ChainObserver::new(datastore.clone());
+        observer.initialize().await.unwrap();
+        
+        assert_eq!(gossip_fork_branch(&observer).await, vec![false, false, true]);
+        
+        let canonical = observer.get_all_canonical_blocks().await.unwrap();
+        let hashes: Vec<&str> = canonical.iter().map(|b| b.hash.as_str()).collect();
+        assert_eq!(hashes, vec!["block_0", "block_1", "block_2", "block_3", "fork_4", "fork_5", "fork_6"]);
+        assert_eq!(observer.get_chain_tip().await, 6);
+        
+        // The replaced blocks are tracked as a branch in case they overtake again
+        let branches = observer.get_fork_branches().await.unwrap();
+        assert_eq!(branches.len(), 1);
+        assert_eq!(branches[0].tip_hash, "block_5");
+        assert_eq!(branches[0].fork_index, Some(3));
+        assert_eq!(branches[0].cumulative_difficulty, "2000");
+    }
+    
+    #[tokio::test]
+    async fn test_reorg_event_published() {
+        let datastore = Arc::new(Mutex::new(
+            DatastoreManager::create_in_memory().unwrap()
+        ));
+        
+        {
+            let ds = datastore.lock().await;
+            create_test_chain(&ds, 0, 5, 1000).await;
+        }
+        
+        let observer = ChainObserver::new(datastore.clone());
+        observer.initialize().await.unwrap();
+        let mut reorgs = observer.subscribe_reorgs();
+        
+        gossip_fork_branch(&observer).await;
+        
+        let event = reorgs.try_recv().unwrap();
+        assert_eq!(event.old_tip.hash, "block_5");
+        assert_eq!(event.new_tip, BlockRef { index: 6, hash: "fork_6".to_string() });
+        assert_eq!(event.common_ancestor.unwrap().hash, "block_3");
+        let orphaned: Vec<&str> = event.orphaned.iter().map(|b| b.hash.as_str()).collect();
+        assert_eq!(orphaned, vec!["block_4", "block_5"]);
+        assert!(reorgs.try_recv().is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_reorg_depth_is_bounded() {
+        let datastore = Arc::new(Mutex::new(
+            DatastoreManager::create_in_memory().unwrap()
+        ));
+        
+        {
+            let ds = datastore.lock().await;
+            create_test_chain(&ds, 0, 5, 1000).await;
+        }
+        
+        let observer = ChainObserver::new_with_fork_config(
+            datastore.clone(),
+            ForkConfig::new().with_max_reorg_depth(1),
+        );
+        observer.initialize().await.unwrap();
+        
+        assert_eq!(gossip_fork_branch(&observer).await, vec![false, false, false]);
+        let orphans = observer.get_orphaned_blocks_at_index(6).await.unwrap();
+        assert!(orphans[0].orphan_reason.as_ref().unwrap().contains("maximum depth"));
+        assert_eq!(observer.get_canonical_block(5).await.unwrap().unwrap().hash, "block_5");
+        
+        let competing_chain = vec![
+            create_test_block(4, "competing_4", "block_3", 1500),
+            create_test_block(5, "competing_5", "competing_4", 1500),
+        ];
+        assert!(observer.process_competing_chain(competing_chain).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_checkpoint_protects_against_reorg() {
+        let datastore = Arc::new(Mutex::new(
+            DatastoreManager::create_in_memory().unwrap()
+        ));
+        
+        {
+            let ds = datastore.lock().await;
+            create_test_chain(&ds, 0, 5, 1000).await;
+            MinerCheckpoint::new_manual(1, 4, "block_4".to_string(), "block_4".to_string(), 1, None)
+                .save_to_canon(&ds)
+                .await
+                .unwrap();
+        }
+        
+        let observer = ChainObserver::new(datastore.clone());
+        observer.initialize().await.unwrap();
+        
+        assert_eq!(gossip_fork_branch(&observer).await, vec![false, false, false]);
+        let orphans = observer.get_orphaned_blocks_at_index(6).await.unwrap();
+        assert!(orphans[0].orphan_reason.as_ref().unwrap().contains("checkpoint"));
+        assert_eq!(observer.get_chain_tip().await, 5);
+    }
+    
+    #[tokio::test]
+    async fn test_orphans_promoted_when_parent_arrives() {
+        let datastore = Arc::new(Mutex::new(
+            DatastoreManager::create_in_memory().unwrap()
+        ));
+        
+        {
+            let ds = datastore.lock().await;
+            create_test_chain(&ds, 0, 3, 1000).await;
+        }
+        
+        let observer = ChainObserver::new(datastore.clone());
+        observer.initialize().await.unwrap();
+        
+        // Blocks 6 and 5 arrive before block 4
+        for (index, prev) in [(6, "block_5"), (5, "block_4")] {
+            let block = create_test_block(index, &format!("block_{}", index), prev, 1000);
+            assert!(!observer.process_gossiped_block(block).await.unwrap());
+        }
+        assert_eq!(observer.get_orphan_pool().await.unwrap().len(), 2);
+        
+        // Block 4 pulls in both waiting descendants without them being resent
+        let block_4 = create_test_block(4, "block_4", "block_3", 1000);
+        assert!(observer.process_gossiped_block(block_4).await.unwrap());
+        
+        assert_eq!(observer.get_chain_tip().await, 6);
+        assert_eq!(observer.get_all_canonical_blocks().await.unwrap().len(), 7);
+        assert!(observer.get_all_orphaned_blocks().await.unwrap().is_empty());
+        assert!(observer.get_orphan_pool().await.unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_pooled_orphan_can_replace_canonical_block() {
+        let datastore = Arc::new(Mutex::new(
+            DatastoreManager::create_in_memory().unwrap()
+        ));
+        
+        {
+            let ds = datastore.lock().await;
+            create_test_chain(&ds, 0, 2, 1000).await;
+        }
+        
+        let observer = ChainObserver::new(datastore.clone());
+        observer.initialize().await.unwrap();
+        
+        // Two blocks at index 4 wait for block 3; the heavier one should win
+        // once both are re-evaluated
+        let light = create_test_block(4, "block_4_light", "block_3", 500);
+        let heavy = create_test_block(4, "block_4_heavy", "block_3", 2000);
+        assert!(!observer.process_gossiped_block(light).await.unwrap());
+        assert!(!observer.process_gossiped_block(heavy).await.unwrap());
+        
+        let block_3 = create_test_block(3, "block_3", "block_2", 1000);
+        assert!(observer.process_gossiped_block(block_3).await.unwrap());
+        
+        assert_eq!(observer.get_chain_tip().await, 4);
+        let tip = observer.get_canonical_block(4).await.unwrap().unwrap();
+        assert_eq!(tip.hash, "block_4_heavy");
+        assert!(!tip.is_orphaned);
+    }
+    
+    #[tokio::test]
+    async fn test_block_with_missing_ancestors_does_not_replace_canonical() {
+        let datastore = Arc::new(Mutex::new(
+            DatastoreManager::create_in_memory().unwrap()
+        ));
+        
+        {
+            let ds = datastore.lock().await;
+            create_test_chain(&ds, 0, 4, 1000).await;
+        }
+        
+        let observer = ChainObserver::new(datastore.clone());
+        observer.initialize().await.unwrap();
+        
+        // Heavier than block_4, but builds on a block 3 we haven't seen
+        let detached = create_test_block(4, "block_4_detached", "block_3_unseen", 5000);
+        assert!(!observer.process_gossiped_block(detached).await.unwrap());
+        
+        assert_eq!(observer.get_canonical_block(4).await.unwrap().unwrap().hash, "block_4");
+        assert_eq!(observer.get_orphan_pool().await.unwrap().len(), 1);
+    }
 }
-
@@ -17,3 +17,14 @@ pub enum ValidationError {
 
 pub type Result<T> = std::result::Result<T, ValidationError>;
 
+impl modal_common::error_codes::HasErrorCode for ValidationError {
+    fn error_code(&self) -> modal_common::error_codes::ErrorCode {
+        use modal_common::error_codes::ErrorCode;
+        match self {
+            ValidationError::ChainObservation(_) => ErrorCode::ObservationFailed,
+            ValidationError::Database(_) => ErrorCode::StorageError,
+            ValidationError::InvalidBlock(_) => ErrorCode::InvalidBlock,
+            ValidationError::Sync(_) => ErrorCode::SyncFailed,
+        }
+    }
+}
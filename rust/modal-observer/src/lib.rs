@@ -9,9 +9,13 @@
 //! - Participate in consensus operations
 //! - Do NOT mine blocks
 
+pub mod anomaly;
 pub mod chain_observer;
 pub mod error;
+pub mod reorg;
 
+pub use anomaly::{Alert, AlertKind, AlertSeverity, AnomalyConfig, AnomalyDetector};
 pub use chain_observer::{ChainObserver, ForkConfig};
 pub use error::{Result, ValidationError};
+pub use reorg::{BlockRef, ReorgEvent};
 
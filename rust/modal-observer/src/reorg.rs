@@ -0,0 +1,69 @@
+//! Reorg notifications
+//!
+//! Whenever canonical blocks are replaced, the observer publishes a
+//! [`ReorgEvent`] on a broadcast channel so downstream indexers can
+//! invalidate anything they derived from the orphaned blocks.
+
+use modal_datastore::models::MinerBlock;
+use serde::{Deserialize, Serialize};
+
+/// Capacity of the reorg broadcast channel; slow subscribers miss older events
+pub const REORG_CHANNEL_CAPACITY: usize = 100;
+
+/// A block identified by index and hash
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockRef {
+    pub index: u64,
+    pub hash: String,
+}
+
+impl From<&MinerBlock> for BlockRef {
+    fn from(block: &MinerBlock) -> Self {
+        Self {
+            index: block.index,
+            hash: block.hash.clone(),
+        }
+    }
+}
+
+/// A change of canonical chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReorgEvent {
+    pub old_tip: BlockRef,
+    pub new_tip: BlockRef,
+    /// Last block shared by the old and new chain (None when they share nothing above genesis)
+    pub common_ancestor: Option<BlockRef>,
+    /// Previously canonical blocks that were orphaned, in index order
+    pub orphaned: Vec<BlockRef>,
+    pub reason: String,
+    /// Unix timestamp of the reorg
+    pub timestamp: i64,
+}
+
+impl ReorgEvent {
+    pub fn new(
+        old_tip: BlockRef,
+        new_tip: BlockRef,
+        common_ancestor: Option<BlockRef>,
+        orphaned: Vec<BlockRef>,
+        reason: String,
+    ) -> Self {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        Self {
+            old_tip,
+            new_tip,
+            common_ancestor,
+            orphaned,
+            reason,
+            timestamp,
+        }
+    }
+
+    /// Number of blocks rolled back
+    pub fn depth(&self) -> usize {
+        self.orphaned.len()
+    }
+}
@@ -0,0 +1,237 @@
+//! Property tests for fork choice and reorg safety
+//!
+//! Each case grows a random block tree (competing genesis blocks, forks at
+//! any height, varying difficulty), then gossips it to an observer out of
+//! order, with some blocks withheld and some delivered more than once.
+//! After every delivery the canonical chain must still be connected, its
+//! cumulative difficulty must not have dropped, and no reorg may be deeper
+//! than the configured maximum or reach below a checkpoint.
+
+use modal_datastore::models::miner::MinerCheckpoint;
+use modal_datastore::models::MinerBlock;
+use modal_datastore::DatastoreManager;
+use modal_observer::{ChainObserver, ForkConfig, ReorgEvent};
+use proptest::prelude::*;
+use std::sync::Arc;
+use tokio::sync::{broadcast, Mutex};
+
+const GENESIS_PARENT: &str = "genesis";
+
+/// A block tree and how it is delivered
+#[derive(Clone)]
+struct Scenario {
+    blocks: Vec<MinerBlock>,
+    /// Indexes into `blocks`, in arrival order; may repeat and may skip
+    arrivals: Vec<usize>,
+    max_reorg_depth: Option<u64>,
+    /// Checkpoint the canonical block below the tip after this many arrivals
+    checkpoint_after: Option<usize>,
+}
+
+impl std::fmt::Debug for Scenario {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let blocks: Vec<String> = self
+            .blocks
+            .iter()
+            .map(|b| format!("{}@{} <- {} ({})", b.hash, b.index, b.previous_hash, b.target_difficulty))
+            .collect();
+        f.debug_struct("Scenario")
+            .field("blocks", &blocks)
+            .field("arrivals", &self.arrivals)
+            .field("max_reorg_depth", &self.max_reorg_depth)
+            .field("checkpoint_after", &self.checkpoint_after)
+            .finish()
+    }
+}
+
+fn block(id: usize, index: u64, previous_hash: &str, difficulty: u128) -> MinerBlock {
+    MinerBlock::new_canonical(
+        format!("block_{}", id),
+        index,
+        index / 40,
+        1_640_000_000 + index as i64 * 60,
+        previous_hash.to_string(),
+        format!("data_{}", id),
+        id as u128,
+        difficulty,
+        format!("peer_{}", id % 3),
+        id as u64,
+    )
+}
+
+/// Blocks whose parent is picked among the blocks before them; a pick past
+/// the end starts a competing genesis block
+fn block_tree() -> impl Strategy<Value = Vec<MinerBlock>> {
+    prop::collection::vec((any::<prop::sample::Index>(), 1u128..=8, prop::bool::weighted(0.1)), 1..24).prop_map(
+        |specs| {
+            let mut blocks: Vec<MinerBlock> = Vec::new();
+            for (id, (parent, difficulty, new_root)) in specs.into_iter().enumerate() {
+                let difficulty = difficulty * 100;
+                let block = if blocks.is_empty() || new_root {
+                    block(id, 0, GENESIS_PARENT, difficulty)
+                } else {
+                    let parent = &blocks[parent.index(blocks.len())];
+                    block(id, parent.index + 1, &parent.hash, difficulty)
+                };
+                blocks.push(block);
+            }
+            blocks
+        },
+    )
+}
+
+fn scenario() -> impl Strategy<Value = Scenario> {
+    block_tree()
+        .prop_flat_map(|blocks| {
+            let len = blocks.len();
+            (
+                Just(blocks),
+                // Each block at most once in a shuffled order, some withheld
+                Just((0..len).collect::<Vec<_>>()).prop_shuffle(),
+                prop::collection::vec(prop::bool::weighted(0.9), len),
+                // Redeliveries spliced in anywhere
+                prop::collection::vec((0..len, any::<prop::sample::Index>()), 0..4),
+                prop::option::of(1u64..=4),
+                prop::option::of(0..len + 1),
+            )
+        })
+        .prop_map(|(blocks, order, delivered, duplicates, max_reorg_depth, checkpoint_after)| {
+            let mut arrivals: Vec<usize> = order.into_iter().filter(|&i| delivered[i]).collect();
+            for (id, at) in duplicates {
+                let at = at.index(arrivals.len() + 1);
+                arrivals.insert(at, id);
+            }
+            Scenario {
+                blocks,
+                arrivals,
+                max_reorg_depth,
+                checkpoint_after,
+            }
+        })
+}
+
+/// Canonical blocks sorted by index
+async fn canonical(observer: &ChainObserver) -> Vec<MinerBlock> {
+    let mut blocks = observer.get_all_canonical_blocks().await.unwrap();
+    blocks.sort_by_key(|b| b.index);
+    blocks
+}
+
+fn assert_connected(chain: &[MinerBlock], tip: u64) -> Result<(), TestCaseError> {
+    for (position, block) in chain.iter().enumerate() {
+        prop_assert_eq!(block.index, position as u64, "canonical chain has a gap or duplicate height");
+        let expected_parent = match position {
+            0 => GENESIS_PARENT,
+            _ => chain[position - 1].hash.as_str(),
+        };
+        prop_assert_eq!(
+            &block.previous_hash, expected_parent,
+            "canonical block {} doesn't build on the canonical block below it", block.hash
+        );
+    }
+    if let Some(last) = chain.last() {
+        prop_assert_eq!(last.index, tip, "chain tip doesn't match the highest canonical block");
+    }
+    Ok(())
+}
+
+async fn run(scenario: Scenario) -> Result<(), TestCaseError> {
+    let datastore = Arc::new(Mutex::new(DatastoreManager::create_in_memory().unwrap()));
+    let mut fork_config = ForkConfig::new();
+    if let Some(depth) = scenario.max_reorg_depth {
+        fork_config = fork_config.with_max_reorg_depth(depth);
+    }
+    let observer = ChainObserver::new_with_fork_config(datastore.clone(), fork_config);
+    observer.initialize().await.unwrap();
+    let mut reorgs: broadcast::Receiver<ReorgEvent> = observer.subscribe_reorgs();
+
+    let mut difficulty = 0;
+    let mut checkpointed: Vec<MinerBlock> = Vec::new();
+    for (step, &id) in scenario.arrivals.iter().enumerate() {
+        if scenario.checkpoint_after == Some(step) {
+            let chain = canonical(&observer).await;
+            if chain.len() >= 2 {
+                let at = &chain[chain.len() - 2];
+                let ds = datastore.lock().await;
+                MinerCheckpoint::from_block_index(at.index, at.hash.clone(), ds.era_schedule())
+                    .save_to_canon(&ds)
+                    .await
+                    .unwrap();
+                checkpointed = chain[..chain.len() - 1].to_vec();
+            }
+        }
+
+        observer.process_gossiped_block(scenario.blocks[id].clone()).await.unwrap();
+
+        let chain = canonical(&observer).await;
+        assert_connected(&chain, observer.get_chain_tip().await)?;
+
+        let new_difficulty = MinerBlock::calculate_cumulative_difficulty(&chain).unwrap();
+        prop_assert!(
+            new_difficulty >= difficulty,
+            "cumulative difficulty fell from {} to {} after block {}", difficulty, new_difficulty, id
+        );
+        difficulty = new_difficulty;
+
+        while let Ok(event) = reorgs.try_recv() {
+            if let Some(depth) = scenario.max_reorg_depth {
+                prop_assert!(
+                    event.depth() as u64 <= depth,
+                    "reorg of depth {} exceeds the maximum of {}", event.depth(), depth
+                );
+            }
+            if let Some(last) = checkpointed.last() {
+                prop_assert!(
+                    event.orphaned.iter().all(|b| b.index > last.index),
+                    "reorg orphaned blocks at or below the checkpoint at {}", last.index
+                );
+            }
+        }
+        for (kept, block) in checkpointed.iter().zip(&chain) {
+            prop_assert_eq!(&kept.hash, &block.hash, "checkpointed block at {} was replaced", kept.index);
+        }
+    }
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(128))]
+
+    #[test]
+    fn fork_choice_keeps_chain_safe(scenario in scenario()) {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(run(scenario))?;
+    }
+
+    /// Delivering the full tree in any order settles on the same difficulty
+    /// as delivering it parents first, unless a reorg limit got in the way
+    #[test]
+    fn arrival_order_does_not_lose_work(blocks in block_tree(), seed in any::<u64>()) {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(async {
+            let mut shuffled: Vec<usize> = (0..blocks.len()).collect();
+            // Cheap deterministic shuffle from the seed
+            let mut state = seed | 1;
+            for i in (1..shuffled.len()).rev() {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                shuffled.swap(i, (state % (i as u64 + 1)) as usize);
+            }
+
+            let mut difficulties = Vec::new();
+            for order in [(0..blocks.len()).collect::<Vec<_>>(), shuffled] {
+                let datastore = Arc::new(Mutex::new(DatastoreManager::create_in_memory().unwrap()));
+                let observer = ChainObserver::new(datastore);
+                for id in order {
+                    observer.process_gossiped_block(blocks[id].clone()).await.unwrap();
+                }
+                let chain = canonical(&observer).await;
+                assert_connected(&chain, observer.get_chain_tip().await)?;
+                difficulties.push(MinerBlock::calculate_cumulative_difficulty(&chain).unwrap());
+            }
+            prop_assert_eq!(difficulties[0], difficulties[1]);
+            Ok(())
+        })?;
+    }
+}
@@ -185,6 +185,12 @@ impl RpcClient {
         Ok(serde_json::from_value(result)?)
     }
 
+    /// Get the build's version, commit and protocol features
+    pub async fn get_build_info(&self) -> Result<BuildInfoResponse, RpcError> {
+        let result = self.request("getBuildInfo", serde_json::json!({})).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
     /// Get block height
     pub async fn get_block_height(&self) -> Result<BlockHeightResponse, RpcError> {
         let result = self.request("getBlockHeight", serde_json::json!({})).await?;
@@ -226,6 +232,33 @@ impl RpcClient {
         Ok(serde_json::from_value(result)?)
     }
 
+    /// Push a chain of commits to a hub
+    pub async fn push_commits(&self, contract_id: &str, commits: Vec<serde_json::Value>) -> Result<PushCommitsResponse, RpcError> {
+        let result = self.request("pushCommits", serde_json::json!({
+            "contract_id": contract_id,
+            "commits": commits,
+        })).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Pull a contract's commits from a hub, all or those after `since`
+    pub async fn pull_commits(&self, contract_id: &str, since: Option<&str>) -> Result<PullCommitsResponse, RpcError> {
+        let result = self.request("pullCommits", serde_json::json!({
+            "contract_id": contract_id,
+            "since": since,
+        })).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Check a commit against a contract's model and rules without applying it
+    pub async fn validate_commit(&self, contract_id: &str, commit: serde_json::Value) -> Result<ValidateCommitResponse, RpcError> {
+        let result = self.request("validateCommit", serde_json::json!({
+            "contract_id": contract_id,
+            "commit": commit,
+        })).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
     /// Subscribe to events
     pub async fn subscribe(&self, contract_id: Option<&str>, events: Vec<EventType>) -> Result<SubscribeResponse, RpcError> {
         let result = self.request("subscribe", serde_json::json!({
@@ -254,4 +287,101 @@ impl RpcClient {
         let result = self.request("getValidators", serde_json::json!({})).await?;
         Ok(serde_json::from_value(result)?)
     }
+
+    /// Get whether consensus is certifying rounds or has fallen back to mining-only (network nodes only)
+    pub async fn get_consensus_status(&self) -> Result<ConsensusStatusResponse, RpcError> {
+        let result = self.request("getConsensusStatus", serde_json::json!({})).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Apply a commit speculatively, optionally on top of or into a snapshot
+    pub async fn simulate_commit(&self, params: SimulateCommitParams) -> Result<SimulateCommitResponse, RpcError> {
+        let result = self.request("simulateCommit", serde_json::to_value(params)?).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Roll back and close a snapshot kept by simulate_commit
+    pub async fn discard_snapshot(&self, snapshot_id: u64) -> Result<bool, RpcError> {
+        let result = self.request("discardSnapshot", serde_json::json!({
+            "snapshot_id": snapshot_id,
+        })).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Get a contract's gas usage and quotas, for the current epoch by default
+    pub async fn get_gas_usage(&self, contract_id: &str, epoch: Option<u64>) -> Result<GasUsageResponse, RpcError> {
+        let result = self.request("getGasUsage", serde_json::json!({
+            "contract_id": contract_id,
+            "epoch": epoch,
+        })).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Get a range of the sequenced log (sequencing nodes only)
+    pub async fn get_sequenced_log(&self, from: u64, limit: Option<u32>) -> Result<SequencedLogResponse, RpcError> {
+        let result = self.request("getSequencedLog", serde_json::json!({
+            "from": from,
+            "limit": limit,
+        })).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Queue a transaction in the sequencer mempool; `data` is hex-encoded
+    /// and `signature` is the sender's over `{sender, nonce, fee, data}`
+    pub async fn submit_transaction(
+        &self,
+        sender: &str,
+        nonce: u64,
+        fee: u64,
+        data: &str,
+        signature: &str,
+    ) -> Result<SubmitTransactionResponse, RpcError> {
+        let result = self.request("submitTransaction", serde_json::json!({
+            "sender": sender,
+            "nonce": nonce,
+            "fee": fee,
+            "data": data,
+            "signature": signature,
+        })).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Get the sequencer mempool's transactions and counters
+    pub async fn get_mempool(&self, limit: Option<u32>) -> Result<MempoolResponse, RpcError> {
+        let result = self.request("getMempool", serde_json::json!({
+            "limit": limit,
+        })).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Get a node metrics series over a time range, or the series names when `series` is None
+    pub async fn get_metrics_history(
+        &self,
+        series: Option<&str>,
+        from: Option<i64>,
+        to: Option<i64>,
+    ) -> Result<MetricsHistoryResponse, RpcError> {
+        let result = self.request("getMetricsHistory", serde_json::json!({
+            "series": series,
+            "from": from,
+            "to": to,
+        })).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Read journal events from `cursor`, or from `consumer`'s stored cursor.
+    /// Polling with a consumer and a cursor stores the cursor for it.
+    pub async fn events_poll(
+        &self,
+        cursor: Option<u64>,
+        consumer: Option<&str>,
+        limit: Option<u32>,
+    ) -> Result<EventsPollResponse, RpcError> {
+        let result = self.request("eventsPoll", serde_json::json!({
+            "cursor": cursor,
+            "consumer": consumer,
+            "limit": limit,
+        })).await?;
+        Ok(serde_json::from_value(result)?)
+    }
 }
@@ -1,5 +1,6 @@
 //! RPC error types
 
+use modal_common::error_codes::{ErrorCode, HasErrorCode};
 use thiserror::Error;
 use crate::types::RpcErrorObject;
 
@@ -52,9 +53,29 @@ pub enum RpcError {
     Internal(String),
 }
 
+impl HasErrorCode for RpcError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            RpcError::ParseError(_) => ErrorCode::ParseError,
+            RpcError::InvalidRequest(_) => ErrorCode::InvalidRequest,
+            RpcError::MethodNotFound(_) => ErrorCode::MethodNotFound,
+            RpcError::InvalidParams(_) => ErrorCode::InvalidParams,
+            RpcError::ContractNotFound(_) => ErrorCode::ContractNotFound,
+            RpcError::BlockNotFound(_) => ErrorCode::BlockNotFound,
+            RpcError::CommitNotFound(_) => ErrorCode::CommitNotFound,
+            RpcError::InvalidSignature => ErrorCode::InvalidSignature,
+            RpcError::RuleViolation(_) => ErrorCode::RuleViolation,
+            RpcError::WebSocketError(_) | RpcError::ConnectionError(_) => ErrorCode::ConnectionError,
+            RpcError::Timeout => ErrorCode::Timeout,
+            RpcError::InternalError(_) | RpcError::Custom { .. } | RpcError::Internal(_) => ErrorCode::Internal,
+        }
+    }
+}
+
 impl From<RpcError> for RpcErrorObject {
     fn from(err: RpcError) -> Self {
-        match err {
+        let error_code = err.error_code();
+        let object = match err {
             RpcError::ParseError(msg) => RpcErrorObject::parse_error().with_data(serde_json::json!({ "details": msg })),
             RpcError::InvalidRequest(msg) => RpcErrorObject::invalid_request().with_data(serde_json::json!({ "details": msg })),
             RpcError::MethodNotFound(method) => RpcErrorObject::method_not_found().with_data(serde_json::json!({ "method": method })),
@@ -70,7 +91,8 @@ impl From<RpcError> for RpcErrorObject {
             RpcError::Timeout => RpcErrorObject::internal_error().with_data(serde_json::json!({ "details": "Request timed out" })),
             RpcError::Custom { code, message } => RpcErrorObject::new(code, message),
             RpcError::Internal(msg) => RpcErrorObject::internal_error().with_data(serde_json::json!({ "details": msg })),
-        }
+        };
+        object.with_error_code(error_code)
     }
 }
 
@@ -79,3 +101,22 @@ impl From<serde_json::Error> for RpcError {
         RpcError::ParseError(err.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_object_carries_error_code() {
+        let object: RpcErrorObject = RpcError::ContractNotFound("c1".to_string()).into();
+        assert_eq!(object.code, -32000);
+        let data = object.data.unwrap();
+        assert_eq!(data["contract_id"], "c1");
+        assert_eq!(data["error_code"], 2001);
+        assert_eq!(data["category"], "not_found");
+        assert_eq!(data["retriable"], false);
+
+        let object: RpcErrorObject = RpcError::Timeout.into();
+        assert_eq!(object.error_code(), Some(ErrorCode::Timeout));
+    }
+}
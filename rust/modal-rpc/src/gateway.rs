@@ -0,0 +1,368 @@
+//! Public read-only gateway mode
+//!
+//! A server given a [`GatewayConfig`] is safe to put behind public explorers
+//! and wallets: it answers only the read-only methods, limits how many
+//! requests each client address may make a minute, and caches responses.
+//! Content-addressed lookups (a commit by hash) never change and are cached
+//! for good; everything else that reads the chain is cached per block, so a
+//! response is reused until the chain grows. Responses carry an ETag, and
+//! `GET /rpc/<method>` serves them with Cache-Control headers so CDNs and
+//! browsers can cache them as well.
+
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use axum::http::HeaderMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+
+use crate::error::RpcError;
+use crate::methods::method_names::*;
+use crate::methods::{dispatch_request, RpcHandler};
+use crate::types::RpcRequest;
+
+/// Methods a gateway answers
+pub const READ_ONLY_METHODS: &[&str] = &[
+    GET_HEALTH,
+    GET_VERSION,
+    GET_BUILD_INFO,
+    GET_BLOCK_HEIGHT,
+    GET_CONTRACT,
+    GET_CONTRACT_STATE,
+    GET_COMMITS,
+    GET_COMMIT,
+    GET_GAS_USAGE,
+    GET_NETWORK_INFO,
+    GET_VALIDATORS,
+    GET_EPOCH_INFO,
+    GET_ALERTS,
+    GET_CONSENSUS_STATUS,
+    GET_SEQUENCED_LOG,
+    GET_MEMPOOL,
+    GET_METRICS_HISTORY,
+    EVENTS_POLL,
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GatewayConfig {
+    /// Requests each client address may make a minute, in bursts of up to as many
+    pub requests_per_minute: u32,
+    /// Most responses to keep cached
+    pub cache_entries: usize,
+    /// Cache-Control max-age of responses that change with the chain
+    pub max_age_secs: u64,
+    /// Origins allowed to call the gateway from a browser (default: any)
+    pub allowed_origins: Option<Vec<String>>,
+    /// Rate limit by the first X-Forwarded-For address, for gateways behind
+    /// a trusted reverse proxy
+    pub trust_forwarded_for: bool,
+}
+
+impl Default for GatewayConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: 120,
+            cache_entries: 10_000,
+            max_age_secs: 5,
+            allowed_origins: None,
+            trust_forwarded_for: false,
+        }
+    }
+}
+
+/// How long a method's response stays valid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheScope {
+    /// Never changes once it exists
+    Immutable,
+    /// Valid until the chain grows
+    PerBlock,
+    /// Changes independently of blocks
+    Uncached,
+}
+
+pub fn is_read_only(method: &str) -> bool {
+    READ_ONLY_METHODS.contains(&method)
+}
+
+pub fn cache_scope(method: &str) -> CacheScope {
+    match method {
+        GET_COMMIT => CacheScope::Immutable,
+        GET_HEALTH | GET_ALERTS | GET_CONSENSUS_STATUS | GET_MEMPOOL | GET_METRICS_HISTORY | EVENTS_POLL => CacheScope::Uncached,
+        _ => CacheScope::PerBlock,
+    }
+}
+
+/// Strong ETag of a response
+pub fn etag(value: &Value) -> String {
+    let digest = Sha256::digest(value.to_string().as_bytes());
+    let hex: String = digest.iter().take(16).map(|b| format!("{:02x}", b)).collect();
+    format!("\"{}\"", hex)
+}
+
+/// Whether an If-None-Match header matches `etag`
+pub fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match
+        .split(',')
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == "*" || tag == etag)
+}
+
+/// Token bucket per client address
+#[derive(Debug)]
+pub struct RateLimiter {
+    capacity: f64,
+    refill_per_sec: f64,
+    buckets: HashMap<IpAddr, (f64, Instant)>,
+}
+
+/// Buckets to track before dropping the full ones
+const MAX_TRACKED_CLIENTS: usize = 100_000;
+
+impl RateLimiter {
+    pub fn new(requests_per_minute: u32) -> Self {
+        let capacity = requests_per_minute.max(1) as f64;
+        Self {
+            capacity,
+            refill_per_sec: capacity / 60.0,
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Take a token for `client`, or say how long until one is available
+    pub fn check(&mut self, client: IpAddr, now: Instant) -> Result<(), Duration> {
+        if self.buckets.len() >= MAX_TRACKED_CLIENTS && !self.buckets.contains_key(&client) {
+            self.prune(now);
+        }
+        let (capacity, refill_per_sec) = (self.capacity, self.refill_per_sec);
+        let (tokens, updated) = self.buckets.entry(client).or_insert((capacity, now));
+        let elapsed = now.saturating_duration_since(*updated).as_secs_f64();
+        *tokens = (*tokens + elapsed * refill_per_sec).min(capacity);
+        *updated = now;
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - *tokens) / refill_per_sec))
+        }
+    }
+
+    /// Forget clients whose bucket has refilled, as they'd start full anyway
+    fn prune(&mut self, now: Instant) {
+        let (capacity, refill_per_sec) = (self.capacity, self.refill_per_sec);
+        self.buckets.retain(|_, (tokens, updated)| {
+            *tokens + now.saturating_duration_since(*updated).as_secs_f64() * refill_per_sec < capacity
+        });
+    }
+}
+
+/// A cached response and its ETag
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedResponse {
+    pub value: Value,
+    pub etag: String,
+}
+
+impl CachedResponse {
+    pub fn new(value: Value) -> Self {
+        let etag = etag(&value);
+        Self { value, etag }
+    }
+}
+
+/// Responses by method, params and, for per-block methods, block height;
+/// the oldest entry is evicted when full
+#[derive(Debug)]
+pub struct ResponseCache {
+    capacity: usize,
+    entries: HashMap<String, CachedResponse>,
+    order: VecDeque<String>,
+}
+
+impl ResponseCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Key of a response; `height` is the chain height for per-block methods
+    pub fn key(method: &str, params: &Value, height: Option<u64>) -> String {
+        match height {
+            Some(height) => format!("{}@{}:{}", method, height, params),
+            None => format!("{}:{}", method, params),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&CachedResponse> {
+        self.entries.get(key)
+    }
+
+    pub fn insert(&mut self, key: String, response: CachedResponse) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.insert(key.clone(), response).is_none() {
+            self.order.push_back(key);
+        }
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// How long to reuse the chain height before asking the handler again
+const HEIGHT_TTL: Duration = Duration::from_secs(1);
+
+/// A running gateway's limiter and caches
+pub(crate) struct Gateway {
+    pub config: GatewayConfig,
+    pub limiter: Mutex<RateLimiter>,
+    cache: Mutex<ResponseCache>,
+    height: Mutex<Option<(u64, Instant)>>,
+}
+
+impl Gateway {
+    pub fn new(config: GatewayConfig) -> Self {
+        Self {
+            limiter: Mutex::new(RateLimiter::new(config.requests_per_minute)),
+            cache: Mutex::new(ResponseCache::new(config.cache_entries)),
+            height: Mutex::new(None),
+            config,
+        }
+    }
+
+    /// Address to rate limit a request by
+    pub fn client_address(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.config.trust_forwarded_for {
+            return peer;
+        }
+        headers
+            .get("x-forwarded-for")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(',').next())
+            .and_then(|first| first.trim().parse().ok())
+            .unwrap_or(peer)
+    }
+
+    /// Answer a read-only request from the cache, or else the handler
+    pub async fn call<H: RpcHandler>(
+        &self,
+        handler: &H,
+        request: &RpcRequest,
+    ) -> Result<(CachedResponse, CacheScope), RpcError> {
+        if !is_read_only(&request.method) {
+            return Err(RpcError::MethodNotFound(request.method.clone()));
+        }
+        let scope = cache_scope(&request.method);
+        let key = match scope {
+            CacheScope::Uncached => {
+                let value = dispatch_request(handler, request).await?;
+                return Ok((CachedResponse::new(value), scope));
+            }
+            CacheScope::Immutable => ResponseCache::key(&request.method, &request.params, None),
+            CacheScope::PerBlock => {
+                let height = self.height(handler).await?;
+                ResponseCache::key(&request.method, &request.params, Some(height))
+            }
+        };
+
+        if let Some(cached) = self.cache.lock().await.get(&key) {
+            return Ok((cached.clone(), scope));
+        }
+        let response = CachedResponse::new(dispatch_request(handler, request).await?);
+        self.cache.lock().await.insert(key, response.clone());
+        Ok((response, scope))
+    }
+
+    async fn height<H: RpcHandler>(&self, handler: &H) -> Result<u64, RpcError> {
+        let mut height = self.height.lock().await;
+        if let Some((value, checked)) = *height {
+            if checked.elapsed() < HEIGHT_TTL {
+                return Ok(value);
+            }
+        }
+        let value = handler.get_block_height().await?.height;
+        *height = Some((value, Instant::now()));
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_read_only_methods() {
+        assert!(is_read_only(GET_COMMIT));
+        assert!(!is_read_only(SUBMIT_COMMIT));
+        assert!(!is_read_only(PUSH_COMMITS));
+        assert!(!is_read_only(SIMULATE_COMMIT));
+        assert!(!is_read_only(SUBSCRIBE));
+        assert_eq!(cache_scope(GET_COMMIT), CacheScope::Immutable);
+        assert_eq!(cache_scope(GET_CONTRACT_STATE), CacheScope::PerBlock);
+        assert_eq!(cache_scope(GET_MEMPOOL), CacheScope::Uncached);
+        assert!(is_read_only(GET_CONSENSUS_STATUS));
+        assert_eq!(cache_scope(GET_CONSENSUS_STATUS), CacheScope::Uncached);
+    }
+
+    #[test]
+    fn test_rate_limiter_refills() {
+        let client: IpAddr = "203.0.113.7".parse().unwrap();
+        let other: IpAddr = "203.0.113.8".parse().unwrap();
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(60);
+        for _ in 0..60 {
+            assert!(limiter.check(client, start).is_ok());
+        }
+        let retry_after = limiter.check(client, start).unwrap_err();
+        assert_eq!(retry_after.as_secs(), 1);
+        assert!(limiter.check(other, start).is_ok());
+        assert!(limiter.check(client, start + Duration::from_secs(1)).is_ok());
+        assert!(limiter.check(client, start + Duration::from_secs(1)).is_err());
+    }
+
+    #[test]
+    fn test_cache_evicts_oldest() {
+        let mut cache = ResponseCache::new(2);
+        let params = json!({"contract_id": "c1"});
+        let at_10 = ResponseCache::key(GET_CONTRACT_STATE, &params, Some(10));
+        let at_11 = ResponseCache::key(GET_CONTRACT_STATE, &params, Some(11));
+        assert_ne!(at_10, at_11);
+
+        cache.insert(at_10.clone(), CachedResponse::new(json!({"a": 1})));
+        cache.insert(at_11.clone(), CachedResponse::new(json!({"a": 2})));
+        cache.insert("commit".to_string(), CachedResponse::new(json!({"a": 2})));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&at_10).is_none());
+        assert_eq!(cache.get(&at_11).unwrap().etag, cache.get("commit").unwrap().etag);
+    }
+
+    #[test]
+    fn test_etag_matches() {
+        let tag = etag(&json!({"height": 5}));
+        assert!(tag.starts_with('"') && tag.ends_with('"'));
+        assert_ne!(tag, etag(&json!({"height": 6})));
+        assert!(etag_matches(&tag, &tag));
+        assert!(etag_matches(&format!("\"other\", W/{}", tag), &tag));
+        assert!(etag_matches("*", &tag));
+        assert!(!etag_matches("\"other\"", &tag));
+    }
+}
@@ -8,10 +8,12 @@ pub mod methods;
 pub mod server;
 pub mod client;
 pub mod error;
+pub mod gateway;
 
 pub use types::*;
 pub use methods::*;
 pub use server::{RpcServer, RpcServerConfig};
+pub use gateway::GatewayConfig;
 pub use client::RpcClient;
 pub use error::RpcError;
 
@@ -9,6 +9,7 @@ pub mod method_names {
     // System methods
     pub const GET_HEALTH: &str = "getHealth";
     pub const GET_VERSION: &str = "getVersion";
+    pub const GET_BUILD_INFO: &str = "getBuildInfo";
     
     // Block/chain methods
     pub const GET_BLOCK_HEIGHT: &str = "getBlockHeight";
@@ -21,6 +22,12 @@ pub mod method_names {
     pub const GET_COMMITS: &str = "getCommits";
     pub const GET_COMMIT: &str = "getCommit";
     pub const SUBMIT_COMMIT: &str = "submitCommit";
+    pub const SIMULATE_COMMIT: &str = "simulateCommit";
+    pub const DISCARD_SNAPSHOT: &str = "discardSnapshot";
+    pub const GET_GAS_USAGE: &str = "getGasUsage";
+    pub const PUSH_COMMITS: &str = "pushCommits";
+    pub const PULL_COMMITS: &str = "pullCommits";
+    pub const VALIDATE_COMMIT: &str = "validateCommit";
     
     // Subscription methods (WebSocket)
     pub const SUBSCRIBE: &str = "subscribe";
@@ -30,6 +37,13 @@ pub mod method_names {
     pub const GET_NETWORK_INFO: &str = "getNetworkInfo";
     pub const GET_VALIDATORS: &str = "getValidators";
     pub const GET_EPOCH_INFO: &str = "getEpochInfo";
+    pub const GET_ALERTS: &str = "getAlerts";
+    pub const GET_CONSENSUS_STATUS: &str = "getConsensusStatus";
+    pub const GET_SEQUENCED_LOG: &str = "getSequencedLog";
+    pub const SUBMIT_TRANSACTION: &str = "submitTransaction";
+    pub const GET_MEMPOOL: &str = "getMempool";
+    pub const GET_METRICS_HISTORY: &str = "getMetricsHistory";
+    pub const EVENTS_POLL: &str = "eventsPoll";
 }
 
 /// RPC handler trait - implement this for hubs and network nodes
@@ -41,6 +55,11 @@ pub trait RpcHandler: Send + Sync {
     /// Get version info
     async fn get_version(&self) -> Result<String, RpcError>;
     
+    /// Get the build's version, commit and protocol features
+    async fn get_build_info(&self) -> Result<BuildInfoResponse, RpcError> {
+        Err(RpcError::MethodNotFound("getBuildInfo".to_string()))
+    }
+    
     /// Get current block height
     async fn get_block_height(&self) -> Result<BlockHeightResponse, RpcError>;
     
@@ -59,6 +78,36 @@ pub trait RpcHandler: Send + Sync {
     /// Submit a new commit
     async fn submit_commit(&self, params: SubmitCommitParams) -> Result<SubmitCommitResponse, RpcError>;
     
+    /// Apply a commit speculatively and report its state changes
+    async fn simulate_commit(&self, _params: SimulateCommitParams) -> Result<SimulateCommitResponse, RpcError> {
+        Err(RpcError::MethodNotFound("simulateCommit".to_string()))
+    }
+    
+    /// Roll back and close a snapshot kept by simulateCommit
+    async fn discard_snapshot(&self, _params: DiscardSnapshotParams) -> Result<bool, RpcError> {
+        Err(RpcError::MethodNotFound("discardSnapshot".to_string()))
+    }
+    
+    /// Get a contract's gas usage and quotas for an epoch
+    async fn get_gas_usage(&self, _params: GetGasUsageParams) -> Result<GasUsageResponse, RpcError> {
+        Err(RpcError::MethodNotFound("getGasUsage".to_string()))
+    }
+    
+    /// Append a chain of commits to a contract (hubs only)
+    async fn push_commits(&self, _params: PushCommitsParams) -> Result<PushCommitsResponse, RpcError> {
+        Err(RpcError::MethodNotFound("pushCommits".to_string()))
+    }
+    
+    /// Get a contract's commits after a given commit (hubs only)
+    async fn pull_commits(&self, _params: PullCommitsParams) -> Result<PullCommitsResponse, RpcError> {
+        Err(RpcError::MethodNotFound("pullCommits".to_string()))
+    }
+    
+    /// Check a commit against the contract's model and rules without applying it
+    async fn validate_commit(&self, _params: ValidateCommitParams) -> Result<ValidateCommitResponse, RpcError> {
+        Err(RpcError::MethodNotFound("validateCommit".to_string()))
+    }
+    
     /// Subscribe to events (returns subscription ID)
     async fn subscribe(&self, _params: SubscribeParams) -> Result<SubscribeResponse, RpcError> {
         // Default: not supported
@@ -80,6 +129,42 @@ pub trait RpcHandler: Send + Sync {
     async fn get_validators(&self) -> Result<ValidatorsResponse, RpcError> {
         Err(RpcError::MethodNotFound("getValidators".to_string()))
     }
+    
+    /// Get recent anomaly alerts (network nodes only)
+    async fn get_alerts(&self) -> Result<AlertsResponse, RpcError> {
+        Err(RpcError::MethodNotFound("getAlerts".to_string()))
+    }
+    
+    /// Get whether consensus is certifying rounds or has fallen back to
+    /// mining-only (network nodes only)
+    async fn get_consensus_status(&self) -> Result<ConsensusStatusResponse, RpcError> {
+        Err(RpcError::MethodNotFound("getConsensusStatus".to_string()))
+    }
+    
+    /// Get a range of the sequenced log (sequencing nodes only)
+    async fn get_sequenced_log(&self, _params: GetSequencedLogParams) -> Result<SequencedLogResponse, RpcError> {
+        Err(RpcError::MethodNotFound("getSequencedLog".to_string()))
+    }
+    
+    /// Queue a transaction in the sequencer mempool (sequencing nodes only)
+    async fn submit_transaction(&self, _params: SubmitTransactionParams) -> Result<SubmitTransactionResponse, RpcError> {
+        Err(RpcError::MethodNotFound("submitTransaction".to_string()))
+    }
+    
+    /// Get the mempool's transactions and counters (sequencing nodes only)
+    async fn get_mempool(&self, _params: GetMempoolParams) -> Result<MempoolResponse, RpcError> {
+        Err(RpcError::MethodNotFound("getMempool".to_string()))
+    }
+    
+    /// Get a node metrics series over a time range (network nodes only)
+    async fn get_metrics_history(&self, _params: GetMetricsHistoryParams) -> Result<MetricsHistoryResponse, RpcError> {
+        Err(RpcError::MethodNotFound("getMetricsHistory".to_string()))
+    }
+    
+    /// Read events from the node's event journal after a cursor (network nodes only)
+    async fn events_poll(&self, _params: EventsPollParams) -> Result<EventsPollResponse, RpcError> {
+        Err(RpcError::MethodNotFound("eventsPoll".to_string()))
+    }
 }
 
 /// Blanket implementation for Arc<H> so we can share handlers across threads
@@ -93,6 +178,10 @@ impl<H: RpcHandler> RpcHandler for std::sync::Arc<H> {
         (**self).get_version().await
     }
     
+    async fn get_build_info(&self) -> Result<BuildInfoResponse, RpcError> {
+        (**self).get_build_info().await
+    }
+    
     async fn get_block_height(&self) -> Result<BlockHeightResponse, RpcError> {
         (**self).get_block_height().await
     }
@@ -117,6 +206,18 @@ impl<H: RpcHandler> RpcHandler for std::sync::Arc<H> {
         (**self).submit_commit(params).await
     }
     
+    async fn push_commits(&self, params: PushCommitsParams) -> Result<PushCommitsResponse, RpcError> {
+        (**self).push_commits(params).await
+    }
+    
+    async fn pull_commits(&self, params: PullCommitsParams) -> Result<PullCommitsResponse, RpcError> {
+        (**self).pull_commits(params).await
+    }
+    
+    async fn validate_commit(&self, params: ValidateCommitParams) -> Result<ValidateCommitResponse, RpcError> {
+        (**self).validate_commit(params).await
+    }
+    
     async fn subscribe(&self, params: SubscribeParams) -> Result<SubscribeResponse, RpcError> {
         (**self).subscribe(params).await
     }
@@ -132,6 +233,46 @@ impl<H: RpcHandler> RpcHandler for std::sync::Arc<H> {
     async fn get_validators(&self) -> Result<ValidatorsResponse, RpcError> {
         (**self).get_validators().await
     }
+    
+    async fn get_alerts(&self) -> Result<AlertsResponse, RpcError> {
+        (**self).get_alerts().await
+    }
+    
+    async fn get_consensus_status(&self) -> Result<ConsensusStatusResponse, RpcError> {
+        (**self).get_consensus_status().await
+    }
+    
+    async fn get_sequenced_log(&self, params: GetSequencedLogParams) -> Result<SequencedLogResponse, RpcError> {
+        (**self).get_sequenced_log(params).await
+    }
+    
+    async fn submit_transaction(&self, params: SubmitTransactionParams) -> Result<SubmitTransactionResponse, RpcError> {
+        (**self).submit_transaction(params).await
+    }
+    
+    async fn get_mempool(&self, params: GetMempoolParams) -> Result<MempoolResponse, RpcError> {
+        (**self).get_mempool(params).await
+    }
+    
+    async fn simulate_commit(&self, params: SimulateCommitParams) -> Result<SimulateCommitResponse, RpcError> {
+        (**self).simulate_commit(params).await
+    }
+    
+    async fn discard_snapshot(&self, params: DiscardSnapshotParams) -> Result<bool, RpcError> {
+        (**self).discard_snapshot(params).await
+    }
+    
+    async fn get_gas_usage(&self, params: GetGasUsageParams) -> Result<GasUsageResponse, RpcError> {
+        (**self).get_gas_usage(params).await
+    }
+    
+    async fn get_metrics_history(&self, params: GetMetricsHistoryParams) -> Result<MetricsHistoryResponse, RpcError> {
+        (**self).get_metrics_history(params).await
+    }
+    
+    async fn events_poll(&self, params: EventsPollParams) -> Result<EventsPollResponse, RpcError> {
+        (**self).events_poll(params).await
+    }
 }
 
 /// Dispatch an RPC request to the appropriate handler method
@@ -152,6 +293,11 @@ pub async fn dispatch_request<H: RpcHandler>(
             Ok(serde_json::to_value(result)?)
         }
         
+        GET_BUILD_INFO => {
+            let result = handler.get_build_info().await?;
+            Ok(serde_json::to_value(result)?)
+        }
+        
         GET_BLOCK_HEIGHT => {
             let result = handler.get_block_height().await?;
             Ok(serde_json::to_value(result)?)
@@ -197,6 +343,27 @@ pub async fn dispatch_request<H: RpcHandler>(
             Ok(serde_json::to_value(result)?)
         }
         
+        PUSH_COMMITS => {
+            let params: PushCommitsParams = serde_json::from_value(request.params.clone())
+                .map_err(|e| RpcError::InvalidParams(e.to_string()))?;
+            let result = handler.push_commits(params).await?;
+            Ok(serde_json::to_value(result)?)
+        }
+        
+        PULL_COMMITS => {
+            let params: PullCommitsParams = serde_json::from_value(request.params.clone())
+                .map_err(|e| RpcError::InvalidParams(e.to_string()))?;
+            let result = handler.pull_commits(params).await?;
+            Ok(serde_json::to_value(result)?)
+        }
+        
+        VALIDATE_COMMIT => {
+            let params: ValidateCommitParams = serde_json::from_value(request.params.clone())
+                .map_err(|e| RpcError::InvalidParams(e.to_string()))?;
+            let result = handler.validate_commit(params).await?;
+            Ok(serde_json::to_value(result)?)
+        }
+        
         SUBSCRIBE => {
             let params: SubscribeParams = serde_json::from_value(request.params.clone())
                 .map_err(|e| RpcError::InvalidParams(e.to_string()))?;
@@ -221,6 +388,72 @@ pub async fn dispatch_request<H: RpcHandler>(
             Ok(serde_json::to_value(result)?)
         }
         
+        GET_ALERTS => {
+            let result = handler.get_alerts().await?;
+            Ok(serde_json::to_value(result)?)
+        }
+        
+        GET_CONSENSUS_STATUS => {
+            let result = handler.get_consensus_status().await?;
+            Ok(serde_json::to_value(result)?)
+        }
+        
+        GET_SEQUENCED_LOG => {
+            let params: GetSequencedLogParams = serde_json::from_value(request.params.clone())
+                .map_err(|e| RpcError::InvalidParams(e.to_string()))?;
+            let result = handler.get_sequenced_log(params).await?;
+            Ok(serde_json::to_value(result)?)
+        }
+        
+        SUBMIT_TRANSACTION => {
+            let params: SubmitTransactionParams = serde_json::from_value(request.params.clone())
+                .map_err(|e| RpcError::InvalidParams(e.to_string()))?;
+            let result = handler.submit_transaction(params).await?;
+            Ok(serde_json::to_value(result)?)
+        }
+        
+        GET_MEMPOOL => {
+            let params: GetMempoolParams = serde_json::from_value(request.params.clone())
+                .map_err(|e| RpcError::InvalidParams(e.to_string()))?;
+            let result = handler.get_mempool(params).await?;
+            Ok(serde_json::to_value(result)?)
+        }
+        
+        SIMULATE_COMMIT => {
+            let params: SimulateCommitParams = serde_json::from_value(request.params.clone())
+                .map_err(|e| RpcError::InvalidParams(e.to_string()))?;
+            let result = handler.simulate_commit(params).await?;
+            Ok(serde_json::to_value(result)?)
+        }
+        
+        DISCARD_SNAPSHOT => {
+            let params: DiscardSnapshotParams = serde_json::from_value(request.params.clone())
+                .map_err(|e| RpcError::InvalidParams(e.to_string()))?;
+            let result = handler.discard_snapshot(params).await?;
+            Ok(serde_json::to_value(result)?)
+        }
+        
+        GET_GAS_USAGE => {
+            let params: GetGasUsageParams = serde_json::from_value(request.params.clone())
+                .map_err(|e| RpcError::InvalidParams(e.to_string()))?;
+            let result = handler.get_gas_usage(params).await?;
+            Ok(serde_json::to_value(result)?)
+        }
+        
+        GET_METRICS_HISTORY => {
+            let params: GetMetricsHistoryParams = serde_json::from_value(request.params.clone())
+                .map_err(|e| RpcError::InvalidParams(e.to_string()))?;
+            let result = handler.get_metrics_history(params).await?;
+            Ok(serde_json::to_value(result)?)
+        }
+        
+        EVENTS_POLL => {
+            let params: EventsPollParams = serde_json::from_value(request.params.clone())
+                .map_err(|e| RpcError::InvalidParams(e.to_string()))?;
+            let result = handler.events_poll(params).await?;
+            Ok(serde_json::to_value(result)?)
+        }
+        
         _ => Err(RpcError::MethodNotFound(request.method.clone())),
     }
 }
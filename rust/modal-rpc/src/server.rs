@@ -3,22 +3,28 @@
 use std::sync::Arc;
 use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::time::Instant;
 
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        State,
+        ConnectInfo, Path, Query, Request, State,
     },
-    response::IntoResponse,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
 use futures_util::{SinkExt, StreamExt};
 use tokio::sync::{broadcast, RwLock, Mutex};
 use tracing::{info, warn};
+use modal_common::error_codes::ErrorCode;
 
 use crate::types::*;
-use crate::methods::{dispatch_request, RpcHandler};
+use crate::error::RpcError;
+use crate::gateway::{etag_matches, CacheScope, Gateway, GatewayConfig};
+use crate::methods::{dispatch_request, method_names, RpcHandler};
 
 /// RPC Server configuration
 #[derive(Debug, Clone)]
@@ -27,6 +33,8 @@ pub struct RpcServerConfig {
     pub port: u16,
     pub max_connections: usize,
     pub enable_cors: bool,
+    /// Serve only read-only methods, rate limited and cached, for public use
+    pub gateway: Option<GatewayConfig>,
 }
 
 impl Default for RpcServerConfig {
@@ -36,13 +44,14 @@ impl Default for RpcServerConfig {
             port: crate::DEFAULT_PORT,
             max_connections: 1000,
             enable_cors: true,
+            gateway: None,
         }
     }
 }
 
 /// Subscription state
 struct SubscriptionState {
-    #[allow(dead_code)]
+    /// Subscriptions of all WebSocket connections
     subscriptions: RwLock<HashMap<String, SubscribeParams>>,
     event_tx: broadcast::Sender<EventNotification>,
 }
@@ -74,41 +83,76 @@ impl<H: RpcHandler + 'static> RpcServer<H> {
         let _ = self.subscriptions.event_tx.send(event);
     }
 
+    /// Sender for pushing events to subscribers once the server is running
+    pub fn event_sender(&self) -> broadcast::Sender<EventNotification> {
+        self.subscriptions.event_tx.clone()
+    }
+
+    /// Push events from `event_tx` to subscribers, for handlers that
+    /// create their channel before the server
+    pub fn with_event_sender(self, event_tx: broadcast::Sender<EventNotification>) -> Self {
+        Self {
+            subscriptions: Arc::new(SubscriptionState {
+                subscriptions: RwLock::new(HashMap::new()),
+                event_tx,
+            }),
+            ..self
+        }
+    }
+
     /// Start the server
     pub async fn run(self) -> Result<(), std::io::Error> {
         let addr: SocketAddr = format!("{}:{}", self.config.host, self.config.port)
             .parse()
             .expect("Invalid address");
 
+        let gateway = self.config.gateway.clone().map(|config| Arc::new(Gateway::new(config)));
         let state = AppState {
             handler: self.handler.clone(),
             subscriptions: self.subscriptions.clone(),
+            gateway: gateway.clone(),
         };
 
         // Build the router
         let app = Router::new()
             .route("/", post(handle_rpc_post))
-            .route("/ws", get(handle_websocket))
-            .route("/health", get(handle_health))
-            .with_state(state);
+            .route("/health", get(handle_health));
+        let app = match &gateway {
+            // Gateways don't hold WebSocket connections open for the public
+            Some(gateway) => app
+                .route("/rpc/:method", get(handle_rpc_get))
+                .with_state(state)
+                .layer(middleware::from_fn_with_state(gateway.clone(), rate_limit)),
+            None => app.route("/ws", get(handle_websocket)).with_state(state),
+        };
 
         // Add CORS if enabled
         let app = if self.config.enable_cors {
-            use tower_http::cors::{Any, CorsLayer};
+            use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+            let allowed_origins = gateway.as_ref().and_then(|g| g.config.allowed_origins.as_ref());
+            let origins = match allowed_origins {
+                Some(origins) => AllowOrigin::list(origins.iter().filter_map(|o| o.parse().ok())),
+                None => AllowOrigin::any(),
+            };
             app.layer(
                 CorsLayer::new()
-                    .allow_origin(Any)
+                    .allow_origin(origins)
                     .allow_methods(Any)
-                    .allow_headers(Any),
+                    .allow_headers(Any)
+                    .expose_headers([header::ETAG, header::CACHE_CONTROL, header::RETRY_AFTER]),
             )
         } else {
             app
         };
 
-        info!("Starting RPC server on {}", addr);
+        if gateway.is_some() {
+            info!("Starting read-only RPC gateway on {}", addr);
+        } else {
+            info!("Starting RPC server on {}", addr);
+        }
         
         let listener = tokio::net::TcpListener::bind(addr).await?;
-        axum::serve(listener, app).await?;
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
         
         Ok(())
     }
@@ -118,6 +162,7 @@ impl<H: RpcHandler + 'static> RpcServer<H> {
 struct AppState<H: RpcHandler + 'static> {
     handler: Arc<H>,
     subscriptions: Arc<SubscriptionState>,
+    gateway: Option<Arc<Gateway>>,
 }
 
 impl<H: RpcHandler + 'static> Clone for AppState<H> {
@@ -125,6 +170,7 @@ impl<H: RpcHandler + 'static> Clone for AppState<H> {
         Self {
             handler: self.handler.clone(),
             subscriptions: self.subscriptions.clone(),
+            gateway: self.gateway.clone(),
         }
     }
 }
@@ -134,10 +180,104 @@ async fn handle_rpc_post<H: RpcHandler>(
     State(state): State<AppState<H>>,
     Json(request): Json<RpcRequest>,
 ) -> Json<RpcResponse> {
-    let response = process_request(&state.handler, request).await;
+    let response = match &state.gateway {
+        Some(gateway) => match gateway.call(&state.handler, &request).await {
+            Ok((response, _)) => RpcResponse::success(request.id, response.value),
+            Err(err) => RpcResponse::error(request.id, err.into()),
+        },
+        None => process_request(&state.handler, request).await,
+    };
     Json(response)
 }
 
+/// Handle `GET /rpc/<method>` on a gateway. Params are read as JSON from the
+/// `params` query parameter, or else taken as strings from the query.
+async fn handle_rpc_get<H: RpcHandler>(
+    State(state): State<AppState<H>>,
+    Path(method): Path<String>,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> Response {
+    let Some(gateway) = state.gateway.as_ref() else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let params = match query.get("params") {
+        Some(params) => match serde_json::from_str(params) {
+            Ok(params) => params,
+            Err(e) => return error_response(RpcError::InvalidParams(e.to_string())),
+        },
+        None => serde_json::Value::Object(
+            query.into_iter().map(|(key, value)| (key, serde_json::Value::String(value))).collect(),
+        ),
+    };
+    let request = RpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: RpcId::Null,
+        method,
+        params,
+    };
+
+    let (response, scope) = match gateway.call(&state.handler, &request).await {
+        Ok(result) => result,
+        Err(err) => return error_response(err),
+    };
+    let cache_control = match scope {
+        CacheScope::Immutable => "public, max-age=31536000, immutable".to_string(),
+        CacheScope::PerBlock => format!("public, max-age={}", gateway.config.max_age_secs),
+        CacheScope::Uncached => "no-cache".to_string(),
+    };
+    let cache_headers = [(header::ETAG, response.etag.clone()), (header::CACHE_CONTROL, cache_control)];
+
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| etag_matches(value, &response.etag));
+    if not_modified {
+        (StatusCode::NOT_MODIFIED, cache_headers).into_response()
+    } else {
+        (cache_headers, Json(response.value)).into_response()
+    }
+}
+
+/// A JSON-RPC error with the matching HTTP status, for GET requests
+fn error_response(err: RpcError) -> Response {
+    let status = match &err {
+        RpcError::MethodNotFound(_)
+        | RpcError::ContractNotFound(_)
+        | RpcError::BlockNotFound(_)
+        | RpcError::CommitNotFound(_) => StatusCode::NOT_FOUND,
+        RpcError::InvalidParams(_) | RpcError::InvalidRequest(_) | RpcError::ParseError(_) => StatusCode::BAD_REQUEST,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, Json(RpcResponse::error(RpcId::Null, err.into()))).into_response()
+}
+
+/// Refuse clients over a gateway's rate limit
+async fn rate_limit(
+    State(gateway): State<Arc<Gateway>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let client = gateway.client_address(peer.ip(), request.headers());
+    let allowed = gateway.limiter.lock().await.check(client, Instant::now());
+    match allowed {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            let retry_after = HeaderValue::from(retry_after.as_secs_f64().ceil() as u64);
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after)],
+                Json(RpcResponse::error(
+                    RpcId::Null,
+                    RpcErrorObject::new(-32005, "Rate limit exceeded").with_error_code(ErrorCode::RateLimited),
+                )),
+            )
+                .into_response()
+        }
+    }
+}
+
 /// Handle health check endpoint
 async fn handle_health<H: RpcHandler>(
     State(state): State<AppState<H>>,
@@ -166,23 +306,44 @@ async fn handle_ws_connection<H: RpcHandler>(socket: WebSocket, state: AppState<
     let (sender, mut receiver) = socket.split();
     let sender = Arc::new(Mutex::new(sender));
     
-    // Subscribe to events
+    // Subscriptions made on this connection
+    let connection_subscriptions: Arc<RwLock<HashMap<String, SubscribeParams>>> =
+        Arc::new(RwLock::new(HashMap::new()));
     let mut event_rx = state.subscriptions.event_tx.subscribe();
     
-    // Spawn a task to forward events to the client
+    // Spawn a task to forward subscribed events to the client
     let sender_for_events = sender.clone();
+    let subscriptions_for_events = connection_subscriptions.clone();
     let event_task = tokio::spawn(async move {
-        while let Ok(event) = event_rx.recv().await {
-            let msg = serde_json::to_string(&RpcResponse {
-                jsonrpc: "2.0".to_string(),
-                id: RpcId::Null,
-                result: Some(serde_json::to_value(&event).unwrap()),
-                error: None,
-            }).unwrap();
-            
-            let mut sender = sender_for_events.lock().await;
-            if sender.send(Message::Text(msg)).await.is_err() {
-                break;
+        loop {
+            let event = match event_rx.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("WebSocket subscriber lagged, skipped {} events", skipped);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let matching: Vec<String> = subscriptions_for_events
+                .read()
+                .await
+                .iter()
+                .filter(|(_, params)| params.matches(&event))
+                .map(|(id, _)| id.clone())
+                .collect();
+            for subscription_id in matching {
+                let notification = EventNotification { subscription_id, ..event.clone() };
+                let msg = serde_json::to_string(&RpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    id: RpcId::Null,
+                    result: Some(serde_json::to_value(&notification).unwrap()),
+                    error: None,
+                }).unwrap();
+                
+                let mut sender = sender_for_events.lock().await;
+                if sender.send(Message::Text(msg)).await.is_err() {
+                    return;
+                }
             }
         }
     });
@@ -194,7 +355,12 @@ async fn handle_ws_connection<H: RpcHandler>(socket: WebSocket, state: AppState<
                 // Parse and process the request
                 match serde_json::from_str::<RpcRequest>(&text) {
                     Ok(request) => {
-                        let response = process_request(&state.handler, request).await;
+                        let response = match request.method.as_str() {
+                            method_names::SUBSCRIBE | method_names::UNSUBSCRIBE => {
+                                process_subscription(&state, &connection_subscriptions, request).await
+                            }
+                            _ => process_request(&state.handler, request).await,
+                        };
                         let response_text = serde_json::to_string(&response).unwrap();
                         
                         let mut sender_guard = sender.lock().await;
@@ -233,6 +399,43 @@ async fn handle_ws_connection<H: RpcHandler>(socket: WebSocket, state: AppState<
     
     // Clean up
     event_task.abort();
+    let mut all = state.subscriptions.subscriptions.write().await;
+    for subscription_id in connection_subscriptions.read().await.keys() {
+        all.remove(subscription_id);
+    }
+}
+
+/// Add or remove a subscription of a WebSocket connection
+async fn process_subscription<H: RpcHandler>(
+    state: &AppState<H>,
+    connection_subscriptions: &RwLock<HashMap<String, SubscribeParams>>,
+    request: RpcRequest,
+) -> RpcResponse {
+    let invalid_params = |e: serde_json::Error| {
+        RpcErrorObject::invalid_params().with_data(serde_json::json!({ "details": e.to_string() }))
+    };
+    
+    if request.method == method_names::SUBSCRIBE {
+        let params: SubscribeParams = match serde_json::from_value(request.params.clone()) {
+            Ok(params) => params,
+            Err(e) => return RpcResponse::error(request.id, invalid_params(e)),
+        };
+        let subscription_id = uuid::Uuid::new_v4().to_string();
+        connection_subscriptions.write().await.insert(subscription_id.clone(), params.clone());
+        state.subscriptions.subscriptions.write().await.insert(subscription_id.clone(), params);
+        RpcResponse::success(request.id, serde_json::json!(SubscribeResponse { subscription_id }))
+    } else {
+        let params: UnsubscribeParams = match serde_json::from_value(request.params.clone()) {
+            Ok(params) => params,
+            Err(e) => return RpcResponse::error(request.id, invalid_params(e)),
+        };
+        // Only this connection's subscriptions can be removed through it
+        let removed = connection_subscriptions.write().await.remove(&params.subscription_id).is_some();
+        if removed {
+            state.subscriptions.subscriptions.write().await.remove(&params.subscription_id);
+        }
+        RpcResponse::success(request.id, serde_json::json!(removed))
+    }
 }
 
 /// Process an RPC request
@@ -1,5 +1,6 @@
 //! RPC types - request and response structures
 
+use modal_common::error_codes::ErrorCode;
 use serde::{Deserialize, Serialize};
 
 /// JSON-RPC 2.0 request
@@ -86,6 +87,27 @@ impl RpcErrorObject {
         self
     }
 
+    /// Add the shared error code to `data`, next to any details
+    pub fn with_error_code(mut self, code: ErrorCode) -> Self {
+        let mut data = match self.data.take() {
+            Some(serde_json::Value::Object(map)) => map,
+            Some(other) => serde_json::Map::from_iter([("details".to_string(), other)]),
+            None => serde_json::Map::new(),
+        };
+        data.insert("error_code".to_string(), code.code().into());
+        data.insert("error_name".to_string(), code.name().into());
+        data.insert("category".to_string(), serde_json::json!(code.category()));
+        data.insert("retriable".to_string(), code.retriable().into());
+        self.data = Some(serde_json::Value::Object(data));
+        self
+    }
+
+    /// The shared error code in `data`, if there is one this side knows
+    pub fn error_code(&self) -> Option<ErrorCode> {
+        let code = self.data.as_ref()?.get("error_code")?.as_u64()?;
+        ErrorCode::from_code(code as u32)
+    }
+
     // Standard JSON-RPC error codes
     pub fn parse_error() -> Self {
         Self::new(-32700, "Parse error")
@@ -141,6 +163,18 @@ pub struct HealthResponse {
     pub node_type: NodeType,
 }
 
+/// Build info response: the binary's version and the protocol it speaks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildInfoResponse {
+    pub version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_commit: Option<String>,
+    pub protocol_version: u32,
+    pub min_protocol_version: u32,
+    pub wire_version: String,
+    pub features: Vec<String>,
+}
+
 /// Node type (hub or network node)
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -246,6 +280,115 @@ pub struct SubmitCommitResponse {
     pub error: Option<String>,
 }
 
+/// Push commits request: commits in order, each building on the one before
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushCommitsParams {
+    pub contract_id: String,
+    /// Commits as `{hash, parent, body, head}`; `data` is accepted for `body`
+    pub commits: Vec<serde_json::Value>,
+}
+
+/// Push commits response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushCommitsResponse {
+    pub contract_id: String,
+    /// Commits accepted (ones the hub already had aren't counted)
+    pub pushed: u64,
+    pub head: Option<String>,
+}
+
+/// Pull commits request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PullCommitsParams {
+    pub contract_id: String,
+    /// Only commits after this one
+    #[serde(default)]
+    pub since: Option<String>,
+}
+
+/// Pull commits response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PullCommitsResponse {
+    pub contract_id: String,
+    pub head: Option<String>,
+    /// Commits as `{hash, parent, body, head, timestamp}`, oldest first
+    pub commits: Vec<serde_json::Value>,
+}
+
+/// Validate commit request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidateCommitParams {
+    pub contract_id: String,
+    /// Commit data with `body` and `head`
+    pub commit: serde_json::Value,
+}
+
+/// Validate commit response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidateCommitResponse {
+    pub valid: bool,
+    pub hash: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Simulate commit request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulateCommitParams {
+    pub contract_id: String,
+    /// Commit data with `body` and `head`
+    pub commit: serde_json::Value,
+    #[serde(default)]
+    pub commit_id: Option<String>,
+    /// Apply on top of a snapshot kept by an earlier simulation
+    #[serde(default)]
+    pub snapshot_id: Option<u64>,
+    /// Keep the result in a snapshot so later simulations can build on it
+    #[serde(default)]
+    pub keep_snapshot: bool,
+}
+
+/// Simulate commit response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulateCommitResponse {
+    pub success: bool,
+    /// State changes the commit would make
+    pub state_changes: Vec<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Snapshot holding the result, if it was kept
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot_id: Option<u64>,
+}
+
+/// Discard snapshot request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscardSnapshotParams {
+    pub snapshot_id: u64,
+}
+
+/// Get gas usage request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetGasUsageParams {
+    pub contract_id: String,
+    /// Defaults to the current epoch
+    #[serde(default)]
+    pub epoch: Option<u64>,
+}
+
+/// Get gas usage response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GasUsageResponse {
+    pub contract_id: String,
+    pub epoch: u64,
+    pub gas_used: u64,
+    pub commits: u64,
+    /// Per-commit quota, if any
+    pub commit_quota: Option<u64>,
+    /// Per-epoch quota, if any
+    pub epoch_quota: Option<u64>,
+}
+
 /// Subscription request (for WebSocket)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SubscribeParams {
@@ -260,6 +403,12 @@ pub enum EventType {
     NewCommit,
     NewBlock,
     ContractUpdate,
+    /// Canonical blocks were replaced; data is the reorg event
+    Reorg,
+    /// A transaction was appended to the sequenced log; data is the entry
+    Sequenced,
+    /// A commit was accepted or rejected by the contract's rules; data is the verdict
+    RuleVerdict,
     All,
 }
 
@@ -269,11 +418,27 @@ pub struct SubscribeResponse {
     pub subscription_id: String,
 }
 
+impl SubscribeParams {
+    /// Whether the subscription wants `event`
+    pub fn matches(&self, event: &EventNotification) -> bool {
+        let contract_matches = match &self.contract_id {
+            Some(contract_id) => event.contract_id.as_ref() == Some(contract_id),
+            None => true,
+        };
+        let type_matches = self.events.is_empty()
+            || self.events.iter().any(|t| *t == EventType::All || *t == event.event_type);
+        contract_matches && type_matches
+    }
+}
+
 /// Event notification (pushed to subscribers)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EventNotification {
     pub subscription_id: String,
     pub event_type: EventType,
+    /// Contract the event is about, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contract_id: Option<String>,
     pub data: serde_json::Value,
     pub timestamp: u64,
 }
@@ -315,3 +480,206 @@ pub struct ValidatorsResponse {
     pub epoch: u64,
     pub validators: Vec<ValidatorInfo>,
 }
+
+/// Anomaly alert raised by a network node's chain observer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertInfo {
+    /// e.g. "future_timestamp", "difficulty_drop", "nomination_concentration"
+    pub kind: String,
+    /// "warning" or "critical"
+    pub severity: String,
+    pub block_index: u64,
+    pub block_hash: String,
+    pub message: String,
+    pub detected_at: i64,
+}
+
+/// Get alerts response (newest first)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertsResponse {
+    pub alerts: Vec<AlertInfo>,
+}
+
+/// Get consensus status response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsensusStatusResponse {
+    /// "hybrid", "mining_only", or "inactive" if this node doesn't run consensus
+    pub mode: String,
+    pub validator_epoch: u64,
+    pub round: u64,
+    pub last_certified_round: Option<u64>,
+    pub committee_size: usize,
+    /// Validators needed to certify a round
+    pub quorum: usize,
+    /// Validators heard from in recent rounds
+    pub live_validators: usize,
+    /// Unix seconds when the mode last changed
+    pub mode_since: i64,
+    /// Times the node has fallen back to mining-only
+    pub fallbacks: u64,
+    /// Unix seconds when the node last recorded its consensus status
+    pub updated_at: i64,
+}
+
+/// Get sequenced log request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetSequencedLogParams {
+    /// Sequence number of the first entry
+    pub from: u64,
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+/// A committed transaction in the final order
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SequencedEntry {
+    pub seq: u64,
+    pub round: u64,
+    pub author: String,
+    pub certificate: String,
+    pub batch: String,
+    /// Hex-encoded transaction data
+    pub data: String,
+    pub timestamp: u64,
+}
+
+/// Get sequenced log response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequencedLogResponse {
+    pub entries: Vec<SequencedEntry>,
+    /// Sequence number the next entry will get
+    pub next_seq: u64,
+}
+
+/// Submit a transaction to the sequencer mempool request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmitTransactionParams {
+    pub sender: String,
+    pub nonce: u64,
+    #[serde(default)]
+    pub fee: u64,
+    /// Hex-encoded transaction data
+    pub data: String,
+    /// Sender's signature over `{sender, nonce, fee, data}`
+    pub signature: String,
+}
+
+/// Submit a transaction response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmitTransactionResponse {
+    /// Whether it replaced a pending transaction with the same nonce
+    pub replaced: bool,
+    pub previous_fee: Option<u64>,
+}
+
+/// Get mempool request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetMempoolParams {
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+/// A transaction waiting in the mempool
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MempoolTransactionInfo {
+    pub sender: String,
+    pub nonce: u64,
+    pub fee: u64,
+    /// Hex-encoded transaction data
+    pub data: String,
+    pub timestamp: u64,
+    /// False while it waits on an earlier nonce from its sender
+    pub ready: bool,
+}
+
+/// Mempool counters
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MempoolStatsInfo {
+    pub pending: usize,
+    pub ready: usize,
+    pub senders: usize,
+    pub accepted: u64,
+    pub replaced: u64,
+    pub evicted: u64,
+    pub rejected: u64,
+    /// Handed to the workers for batching
+    pub drained: u64,
+}
+
+/// Get mempool response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MempoolResponse {
+    /// Ready transactions in batching order, then the waiting ones
+    pub transactions: Vec<MempoolTransactionInfo>,
+    pub stats: MempoolStatsInfo,
+}
+
+/// Get metrics history request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetMetricsHistoryParams {
+    /// Series name, e.g. "mining.hashrate"; omit to list the series
+    #[serde(default)]
+    pub series: Option<String>,
+    /// Start of the range in Unix seconds (default: an hour ago)
+    #[serde(default)]
+    pub from: Option<i64>,
+    /// End of the range in Unix seconds (default: now)
+    #[serde(default)]
+    pub to: Option<i64>,
+}
+
+/// One bucket of a metrics series
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MetricPointInfo {
+    pub timestamp: i64,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub count: u64,
+}
+
+/// Get metrics history response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsHistoryResponse {
+    /// Recorded series names
+    pub available: Vec<String>,
+    pub series: Option<String>,
+    /// Bucket width of the points; 0 for points as recorded
+    pub resolution_secs: u64,
+    pub points: Vec<MetricPointInfo>,
+}
+
+/// Poll the event journal request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventsPollParams {
+    /// Sequence number of the first event wanted. With a consumer, this also
+    /// stores the consumer's cursor: everything before it is acknowledged.
+    #[serde(default)]
+    pub cursor: Option<u64>,
+    /// Consumer name; without a cursor, polling resumes at its stored cursor
+    #[serde(default)]
+    pub consumer: Option<String>,
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+/// An event from the journal
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JournalEventInfo {
+    pub seq: u64,
+    /// "new_block", "reorg", "commit_applied", "round_finalized",
+    /// "consensus_stalled", "consensus_resumed" or "offense_recorded"
+    pub kind: String,
+    pub timestamp: i64,
+    pub data: serde_json::Value,
+}
+
+/// Poll the event journal response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventsPollResponse {
+    pub events: Vec<JournalEventInfo>,
+    /// Cursor to poll with next
+    pub next_cursor: u64,
+    /// Sequence number the next event will get
+    pub head: u64,
+}
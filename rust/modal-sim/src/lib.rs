@@ -0,0 +1,265 @@
+//! Deterministic simulation of full Modality networks in-process
+//!
+//! Starts several complete nodes on a regtest network, connected to each
+//! other over libp2p's in-process memory transport, so no sockets are opened.
+//! A schedule drawn from a seed then drives the workload: nodes mine blocks,
+//! race each other for the same height, and push contract commits to their
+//! peers. Every block travels over gossip and is checked by the nodes that
+//! receive it; commits are announced and pulled the way they are between
+//! real nodes.
+//!
+//! The seed fixes the schedule and the node keys, but not when messages
+//! arrive. After each step the simulation waits for the network to settle and
+//! checks what must hold whatever the timing: every node has the same
+//! connected canonical chain and every commit pushed so far. At the end each
+//! commit must be in that chain exactly once. Without races the chain itself
+//! follows from the seed; with them, which losing blocks a node kept, and so
+//! the uncles it names later, depends on arrival order.
+//!
+//! Racing blocks at regtest difficulty usually tie, and a tie goes to the
+//! block a node saw first, so after a race the nodes may each keep their own
+//! block. They are only required to agree again once the next block is mined
+//! on one side and the others sync to the longer chain.
+//!
+//! Validator committees have their own simulation in
+//! `modal_validator_consensus::sim`.
+
+pub mod node;
+pub mod schedule;
+
+pub use node::{ChainView, SimNode};
+pub use schedule::{Action, ScheduleConfig};
+
+use anyhow::{bail, Result};
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+/// How long chains must stay unchanged after a race before the next step
+const QUIET_MS: u64 = 1_000;
+
+/// Simulation settings
+#[derive(Debug, Clone)]
+pub struct SimConfig {
+    /// Seed for node keys and the schedule
+    pub seed: u64,
+    pub schedule: ScheduleConfig,
+    /// How long the network may take to agree after a step
+    pub settle_timeout_ms: u64,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            schedule: ScheduleConfig::default(),
+            settle_timeout_ms: 30_000,
+        }
+    }
+}
+
+/// What happened in a run
+#[derive(Debug, Clone)]
+pub struct SimReport {
+    pub actions: Vec<Action>,
+    /// The chain every node ended on
+    pub chain: ChainView,
+    /// Commits pushed during the run
+    pub commits: usize,
+}
+
+/// A running simulated network
+pub struct Sim {
+    pub nodes: Vec<SimNode>,
+    config: SimConfig,
+    /// Contract and commit ID of every commit pushed so far
+    pushed: Vec<(String, String)>,
+    /// Whether a race may have left nodes on different tips
+    split: bool,
+    _dir: tempfile::TempDir,
+}
+
+impl Sim {
+    /// Start the nodes and wait until each is connected to all the others
+    pub async fn start(config: SimConfig) -> Result<Self> {
+        let dir = tempfile::tempdir()?;
+        let identities = (0..config.schedule.nodes)
+            .map(|index| node::identity(index, config.seed))
+            .collect::<Result<Vec<_>>>()?;
+        let mut nodes = Vec::with_capacity(identities.len());
+        for (index, (address, keypair)) in identities.iter().enumerate() {
+            let bootstrappers = identities
+                .iter()
+                .filter(|(other, _)| other != address)
+                .map(|(other, _)| other.clone())
+                .collect();
+            nodes.push(SimNode::start(index, address.clone(), keypair.clone(), bootstrappers, dir.path()).await?);
+        }
+        for (index, node) in nodes.iter().enumerate() {
+            for peer in &nodes[index + 1..] {
+                node.dial(&peer.address).await?;
+            }
+        }
+
+        let sim = Self {
+            nodes,
+            config,
+            pushed: Vec::new(),
+            split: false,
+            _dir: dir,
+        };
+        let peers = sim.nodes.len() - 1;
+        sim.wait_until("nodes to connect and subscribe", || async {
+            for node in &sim.nodes {
+                if node.subscribed_peers().await < peers {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        })
+        .await?;
+        Ok(sim)
+    }
+
+    /// Run the schedule for `config`, checking the network after every step
+    pub async fn run(config: SimConfig) -> Result<SimReport> {
+        let mut actions = schedule::generate(config.seed, &config.schedule);
+        // A last block takes in the commits still pending
+        actions.push(Action::Mine { node: 0, count: 1 });
+
+        let mut sim = Self::start(config).await?;
+        let result = sim.run_actions(&actions).await;
+        sim.stop();
+        let chain = result?;
+
+        Ok(SimReport {
+            actions,
+            chain,
+            commits: sim.pushed.len(),
+        })
+    }
+
+    async fn run_actions(&mut self, actions: &[Action]) -> Result<ChainView> {
+        for (step, action) in actions.iter().enumerate() {
+            log::info!("Step {}: {:?}", step, action);
+            self.apply(action).await?;
+            self.settle()
+                .await
+                .map_err(|e| anyhow::anyhow!("After step {} ({:?}): {}", step, action, e))?;
+        }
+
+        let chain = self.settle().await?;
+        let included: Vec<_> = chain.commits.iter().collect();
+        let unique: HashSet<_> = included.iter().collect();
+        if unique.len() != included.len() {
+            bail!("A commit is included in the canonical chain more than once");
+        }
+        if let Some((contract_id, commit_id)) = self.pushed.iter().find(|c| !unique.contains(c)) {
+            bail!("Commit {} to {} never made it into the canonical chain", commit_id, contract_id);
+        }
+        Ok(chain)
+    }
+
+    pub async fn apply(&mut self, action: &Action) -> Result<()> {
+        match action {
+            Action::Mine { node, count } => {
+                self.nodes[*node].mine(*count).await?;
+                self.split = false;
+                Ok(())
+            }
+            Action::Race { nodes } => {
+                let racers = nodes.iter().map(|&index| self.nodes[index].mine(1));
+                futures::future::try_join_all(racers).await?;
+                self.split = true;
+                Ok(())
+            }
+            Action::Commit { from, to, contract, nonce } => {
+                let contract_id = contract_id(*contract);
+                let target = self.nodes[*to].peer_id;
+                let commit_id = self.nodes[*from].push_commit(target, &contract_id, *nonce).await?;
+                self.pushed.push((contract_id, commit_id));
+                Ok(())
+            }
+        }
+    }
+
+    /// Wait until every node has the same connected canonical chain and
+    /// every commit pushed so far, and return node 0's chain. Right after a
+    /// race the chains only need to be as long as each other and to have
+    /// stopped changing, so syncs the race set off are done before the next
+    /// step.
+    pub async fn settle(&self) -> Result<ChainView> {
+        let last_change = std::sync::Mutex::new((Vec::new(), Instant::now()));
+        let settled = self
+            .wait_until("the network to settle", || async {
+                let mut chains = Vec::with_capacity(self.nodes.len());
+                for node in &self.nodes {
+                    chains.push(node.chain().await?);
+                }
+                let agree = if self.split {
+                    let mut last_change = last_change.lock().unwrap();
+                    if last_change.0 != chains {
+                        *last_change = (chains.clone(), Instant::now());
+                    }
+                    last_change.1.elapsed() >= Duration::from_millis(QUIET_MS)
+                        && chains.iter().all(|chain| chain.height() == chains[0].height())
+                } else {
+                    chains.iter().all(|chain| *chain == chains[0])
+                };
+                if !agree {
+                    return Ok(false);
+                }
+                for node in &self.nodes {
+                    for (contract_id, commit_id) in &self.pushed {
+                        if !node.has_commit(contract_id, commit_id).await? {
+                            return Ok(false);
+                        }
+                    }
+                }
+                Ok(true)
+            })
+            .await;
+        if let Err(e) = settled {
+            let mut views = Vec::new();
+            for node in &self.nodes {
+                let chain = node.chain().await?;
+                views.push(format!("node {} at height {} on {}", node.index, chain.height(), chain.tip()));
+            }
+            bail!("{}: {}", e, views.join(", "));
+        }
+
+        for node in &self.nodes {
+            if let Some(fault) = node.chain_fault().await? {
+                bail!("Node {} has a broken canonical chain: {}", node.index, fault);
+            }
+        }
+        self.nodes[0].chain().await
+    }
+
+    async fn wait_until<F, Fut>(&self, what: &str, mut check: F) -> Result<()>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<bool>>,
+    {
+        let deadline = Instant::now() + Duration::from_millis(self.config.settle_timeout_ms);
+        while !check().await? {
+            if Instant::now() >= deadline {
+                bail!("Timed out waiting for {}", what);
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        Ok(())
+    }
+
+    /// Stop every node's networking and background tasks
+    pub fn stop(&self) {
+        for node in &self.nodes {
+            node.stop();
+        }
+    }
+}
+
+/// ID of simulated contract `n`
+pub fn contract_id(n: usize) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(format!("modal-sim/contract/{}", n).as_bytes()))
+}
@@ -0,0 +1,227 @@
+use anyhow::{Context, Result};
+use libp2p::gossipsub::IdentTopic;
+use libp2p::multiaddr::Protocol;
+use libp2p::{Multiaddr, PeerId};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use modal_common::canonical::CanonicalHash;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use modal_datastore::models::{Commit, MinerBlock};
+use modal_miner::{Blockchain, ChainConfig};
+use modal_node::actions::miner::{self, regtest};
+use modal_node::config::Config;
+use modal_node::gossip;
+use modal_node::node::Node;
+
+/// Memory transport ports are shared by the whole process, so nodes of
+/// simulations running side by side each get their own
+static NEXT_PORT: AtomicU64 = AtomicU64::new(1);
+
+/// A full node in a simulated network
+pub struct SimNode {
+    pub index: usize,
+    pub peer_id: PeerId,
+    /// Where it listens, ending in `/p2p/<peer_id>`
+    pub address: Multiaddr,
+    pub node: Node,
+}
+
+/// The canonical chain as one node sees it, lowest block first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainView {
+    pub hashes: Vec<String>,
+    /// Contract and commit ID of every commit included in the chain
+    pub commits: Vec<(String, String)>,
+}
+
+impl ChainView {
+    pub fn height(&self) -> u64 {
+        self.hashes.len().saturating_sub(1) as u64
+    }
+
+    pub fn tip(&self) -> &str {
+        self.hashes.last().map(String::as_str).unwrap_or("")
+    }
+}
+
+/// Where node `index` of the simulation seeded with `seed` will listen, ending
+/// in `/p2p/<peer_id>`, and the key it will use. Its key, and so its peer ID
+/// and the blocks it mines, follow from the seed.
+pub fn identity(index: usize, seed: u64) -> Result<(Multiaddr, libp2p_identity::Keypair)> {
+    let secret: [u8; 32] = StdRng::seed_from_u64(seed ^ ((index as u64 + 1) << 32)).gen();
+    let keypair = libp2p_identity::Keypair::ed25519_from_bytes(secret)?;
+    let port = NEXT_PORT.fetch_add(1, Ordering::Relaxed);
+    let address = Multiaddr::empty()
+        .with(Protocol::Memory(port))
+        .with(Protocol::P2p(keypair.public().to_peer_id()));
+    Ok((address, keypair))
+}
+
+impl SimNode {
+    /// Start node `index` at `address` with `keypair`, from [`identity`],
+    /// keeping its passfile in `dir`. Like a mining node, it syncs with its
+    /// bootstrappers when gossip brings a block whose parent it hasn't seen.
+    pub async fn start(
+        index: usize,
+        address: Multiaddr,
+        keypair: libp2p_identity::Keypair,
+        bootstrappers: Vec<Multiaddr>,
+        dir: &Path,
+    ) -> Result<Self> {
+        let peer_id = keypair.public().to_peer_id();
+        let passfile_path = dir.join(format!("node{}.modal_passfile", index));
+        modal_common::keypair::Keypair::from_libp2p_keypair(keypair)?
+            .as_json_file(passfile_path.to_str().context("Non-UTF-8 passfile path")?)?;
+
+        let listener: Multiaddr = address
+            .iter()
+            .filter(|protocol| !matches!(protocol, Protocol::P2p(_)))
+            .collect();
+        let config = Config {
+            passfile_path: Some(passfile_path),
+            network_config_path: Some("modal-networks://regtest".into()),
+            listeners: Some(vec![listener]),
+            bootstrappers: Some(bootstrappers),
+            memory_transport: Some(true),
+            networking_tick_ms: Some(20),
+            miner_hash_func: Some("sha256".to_string()),
+            bootup_enabled: Some(false),
+            ..Default::default()
+        };
+
+        let mut node = Node::from_config(config.clone()).await?;
+        node.setup(&config).await?;
+
+        // Regtest nodes that mine in turn need the same genesis block
+        Blockchain::load_or_create_with_fork_config_default(
+            ChainConfig::regtest(),
+            node.datastore_manager.clone(),
+            node.fork_config.clone(),
+        )
+        .await?;
+
+        // Blocks are mined on demand, so nothing waits for chain tip updates
+        let _ = miner::start_orphan_sync(&mut node);
+        gossip::add_miner_event_listeners(&mut node).await?;
+        gossip::add_contract_event_listeners(&mut node).await?;
+        node.start_networking().await?;
+
+        Ok(Self {
+            index,
+            peer_id,
+            address,
+            node,
+        })
+    }
+
+    pub async fn dial(&self, address: &Multiaddr) -> Result<()> {
+        self.node.swarm.dial(address.clone()).await
+    }
+
+    /// Peers this node knows are subscribed to every topic the simulation uses
+    pub async fn subscribed_peers(&self) -> usize {
+        let routes = self.node.swarm.topics();
+        let topics: Vec<_> = [gossip::miner::block::TOPIC, gossip::contract::commits::TOPIC]
+            .iter()
+            .flat_map(|topic| routes.subscriptions(topic))
+            .map(|topic| IdentTopic::new(topic).hash())
+            .collect();
+        self.node
+            .swarm
+            .run(move |swarm| {
+                swarm
+                    .behaviour()
+                    .gossipsub
+                    .all_peers()
+                    .filter(|(_, subscribed)| topics.iter().all(|topic| subscribed.contains(&topic)))
+                    .count()
+            })
+            .await
+            .unwrap_or(0)
+    }
+
+    pub async fn mine(&self, count: u64) -> Result<()> {
+        regtest::mine_blocks(&self.node, count).await?;
+        Ok(())
+    }
+
+    /// Push a commit to `contract_id` on `target`, returning its ID
+    pub async fn push_commit(&self, target: PeerId, contract_id: &str, nonce: u64) -> Result<String> {
+        let body = serde_json::json!([{
+            "method": "post",
+            "path": format!("/sim/{}.text", nonce),
+            "value": format!("step {}", nonce),
+        }]);
+        let head = serde_json::json!({});
+        let commit_id = serde_json::json!({ "body": body, "head": head }).canonical_hash()?;
+
+        let data = serde_json::json!({
+            "contract_id": contract_id,
+            "commits": [{ "commit_id": commit_id, "body": body, "head": head }],
+        });
+        let response = self
+            .node
+            .send_request_via_networking(target, "/contract/push".to_string(), Some(data))
+            .await?;
+        let pushed = response
+            .data
+            .as_ref()
+            .and_then(|data| data.get("pushed_count"))
+            .and_then(|count| count.as_u64());
+        if !response.ok || pushed != Some(1) {
+            anyhow::bail!("Node {} didn't store commit {}: {:?}", target, commit_id, response.errors);
+        }
+        Ok(commit_id)
+    }
+
+    pub async fn has_commit(&self, contract_id: &str, commit_id: &str) -> Result<bool> {
+        let keys: HashMap<String, String> = [
+            ("contract_id".to_string(), contract_id.to_string()),
+            ("commit_id".to_string(), commit_id.to_string()),
+        ]
+        .into_iter()
+        .collect();
+        let mgr = self.node.datastore_manager.lock().await;
+        Ok(Commit::find_one_multi(&mgr, keys).await?.is_some())
+    }
+
+    pub async fn chain(&self) -> Result<ChainView> {
+        let mut blocks = {
+            let mgr = self.node.datastore_manager.lock().await;
+            MinerBlock::find_all_canonical_multi(&mgr).await?
+        };
+        blocks.sort_by_key(|b| b.index);
+        Ok(ChainView {
+            hashes: blocks.iter().map(|b| b.hash.clone()).collect(),
+            commits: blocks
+                .iter()
+                .flat_map(|b| b.commits.iter().map(|c| (c.contract_id.clone(), c.commit_id.clone())))
+                .collect(),
+        })
+    }
+
+    /// Why this node's canonical chain isn't one chain from genesis, if it isn't
+    pub async fn chain_fault(&self) -> Result<Option<String>> {
+        let mut blocks = {
+            let mgr = self.node.datastore_manager.lock().await;
+            MinerBlock::find_all_canonical_multi(&mgr).await?
+        };
+        blocks.sort_by_key(|b| b.index);
+        for (position, block) in blocks.iter().enumerate() {
+            if block.index != position as u64 {
+                return Ok(Some(format!("no single canonical block at height {}", position)));
+            }
+            if position > 0 && block.previous_hash != blocks[position - 1].hash {
+                return Ok(Some(format!("block {} at height {} doesn't build on the canonical block below it", block.hash, block.index)));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn stop(&self) {
+        self.node.signal_shutdown();
+    }
+}
@@ -0,0 +1,93 @@
+use rand::rngs::StdRng;
+use rand::seq::index::sample;
+use rand::{Rng, SeedableRng};
+
+/// One step of a simulated workload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// A node mines blocks on top of its chain tip
+    Mine { node: usize, count: u64 },
+    /// Several nodes mine the next block at once, forking the chain until
+    /// fork choice settles on one of them
+    Race { nodes: Vec<usize> },
+    /// A node pushes a commit to a contract on another node, over the network
+    Commit { from: usize, to: usize, contract: usize, nonce: u64 },
+}
+
+/// Shape of a generated schedule
+#[derive(Debug, Clone)]
+pub struct ScheduleConfig {
+    pub nodes: usize,
+    pub steps: usize,
+    /// Share of steps that are mining races, from 0.0 to 1.0
+    pub race_rate: f64,
+    /// Share of steps that push a contract commit, from 0.0 to 1.0
+    pub commit_rate: f64,
+    /// Contracts commits are spread over
+    pub contracts: usize,
+}
+
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        Self {
+            nodes: 3,
+            steps: 12,
+            race_rate: 0.2,
+            commit_rate: 0.3,
+            contracts: 2,
+        }
+    }
+}
+
+/// The actions for `config`, drawn from `seed`
+pub fn generate(seed: u64, config: &ScheduleConfig) -> Vec<Action> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut actions = Vec::with_capacity(config.steps);
+    for nonce in 0..config.steps as u64 {
+        let roll: f64 = rng.gen();
+        let action = if config.nodes >= 2 && roll < config.race_rate {
+            let racers = rng.gen_range(2..=config.nodes);
+            let mut nodes = sample(&mut rng, config.nodes, racers).into_vec();
+            nodes.sort_unstable();
+            Action::Race { nodes }
+        } else if config.nodes >= 2 && roll < config.race_rate + config.commit_rate {
+            let picked = sample(&mut rng, config.nodes, 2);
+            Action::Commit {
+                from: picked.index(0),
+                to: picked.index(1),
+                contract: rng.gen_range(0..config.contracts.max(1)),
+                nonce,
+            }
+        } else {
+            Action::Mine {
+                node: rng.gen_range(0..config.nodes),
+                count: rng.gen_range(1..=3),
+            }
+        };
+        actions.push(action);
+    }
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_is_seeded() {
+        let config = ScheduleConfig {
+            steps: 50,
+            ..Default::default()
+        };
+        assert_eq!(generate(7, &config), generate(7, &config));
+        assert_ne!(generate(7, &config), generate(8, &config));
+
+        for action in generate(7, &config) {
+            match action {
+                Action::Mine { node, count } => assert!(node < 3 && (1..=3).contains(&count)),
+                Action::Race { nodes } => assert!(nodes.len() >= 2 && nodes.iter().all(|&n| n < 3)),
+                Action::Commit { from, to, contract, .. } => assert!(from != to && to < 3 && contract < 2),
+            }
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use modal_sim::{Action, ScheduleConfig, Sim, SimConfig};
+
+fn config(seed: u64, schedule: ScheduleConfig) -> SimConfig {
+    SimConfig {
+        seed,
+        schedule,
+        ..Default::default()
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn mining_nodes_converge_on_one_chain() {
+    let schedule = ScheduleConfig {
+        race_rate: 0.0,
+        commit_rate: 0.0,
+        ..Default::default()
+    };
+    let report = Sim::run(config(1, schedule)).await.unwrap();
+
+    let mined: u64 = report
+        .actions
+        .iter()
+        .map(|action| match action {
+            Action::Mine { count, .. } => *count,
+            _ => 0,
+        })
+        .sum();
+    assert_eq!(report.chain.height(), mined);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn mining_races_settle_on_one_block() {
+    let schedule = ScheduleConfig {
+        race_rate: 0.6,
+        commit_rate: 0.0,
+        ..Default::default()
+    };
+    let report = Sim::run(config(2, schedule)).await.unwrap();
+
+    let races = report
+        .actions
+        .iter()
+        .filter(|action| matches!(action, Action::Race { .. }))
+        .count();
+    assert!(races > 0);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn pushed_commits_are_included_once() {
+    let schedule = ScheduleConfig {
+        commit_rate: 0.4,
+        ..Default::default()
+    };
+    let report = Sim::run(config(3, schedule)).await.unwrap();
+
+    assert!(report.commits > 0);
+    assert_eq!(report.chain.commits.len(), report.commits);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn same_seed_gives_same_chain() {
+    // Which losing race blocks a node keeps, and so names as uncles, depends on timing
+    let schedule = ScheduleConfig {
+        race_rate: 0.0,
+        ..Default::default()
+    };
+    let first = Sim::run(config(4, schedule.clone())).await.unwrap();
+    let second = Sim::run(config(4, schedule)).await.unwrap();
+
+    assert_eq!(first.actions, second.actions);
+    assert_eq!(first.chain, second.chain);
+}
@@ -41,7 +41,7 @@ fn create_cert(author: u8, round: u64, parents: Vec<[u8; 32]>, committee_size: u
         header: Header {
             author: vec![author],
             round,
-            batch_digest: [round as u8; 32],
+            payload: vec![([round as u8; 32], 0)],
             parents,
             timestamp: 1000 + round * 1000,
         },
@@ -61,7 +61,7 @@ fn bench_certificate_formation(c: &mut Criterion) {
         let header = Header {
             author: vec![0],
             round: 1,
-            batch_digest: [0u8; 32],
+            payload: vec![([0u8; 32], 0)],
             parents: vec![],
             timestamp: 1000,
         };
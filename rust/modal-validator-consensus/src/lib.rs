@@ -6,5 +6,7 @@ pub mod narwhal;
 
 pub mod shoal;
 
+pub mod sim;
+
 #[cfg(feature = "persistence")]
 pub mod persistence;
\ No newline at end of file
@@ -0,0 +1,339 @@
+//! Checks a transaction passes before a worker batches it
+//!
+//! A transaction is admitted if:
+//! - its data is between 1 byte and `max_transaction_bytes`
+//! - if it is a contract commit (a JSON object with `body` and `head`), every
+//!   signature in `head.signatures` is a base64 ed25519 signature (64 bytes)
+//!   keyed by a non-empty signer. The signatures themselves are checked when
+//!   the commit is applied.
+//! - its data wasn't seen recently, in a batch of ours or of a peer.
+//!
+//! Recently seen transactions are kept in a bloom filter shared by all of a
+//! validator's workers, keyed by the SHA-256 of the data, so a commit
+//! submitted twice, or through two validators, lands in one batch. The filter
+//! has two generations: once the current one holds `recent_capacity`
+//! transactions it becomes the previous one, and the one before is dropped.
+//! A false positive (about 1 in 2000 at capacity) drops a new transaction,
+//! which its sender has to submit again.
+
+use crate::narwhal::{Batch, Transaction};
+use base64::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+use thiserror::Error;
+
+/// Length of an ed25519 signature
+const SIGNATURE_BYTES: usize = 64;
+
+/// Filter bits per transaction it holds
+const BLOOM_BITS_PER_ITEM: usize = 16;
+
+/// Bits set per transaction
+const BLOOM_HASHES: u32 = 11;
+
+/// Smallest filter, in 64-bit words
+const BLOOM_MIN_WORDS: usize = 16;
+
+/// Admission limits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdmissionConfig {
+    /// Largest transaction admitted, in bytes of data
+    pub max_transaction_bytes: usize,
+    /// Transactions remembered per filter generation
+    pub recent_capacity: usize,
+}
+
+impl Default for AdmissionConfig {
+    fn default() -> Self {
+        Self {
+            max_transaction_bytes: 256 * 1024,
+            recent_capacity: 100_000,
+        }
+    }
+}
+
+/// Why a transaction wasn't admitted
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum AdmissionError {
+    #[error("Transaction has no data")]
+    Empty,
+
+    #[error("Transaction is {size} bytes, more than {max}")]
+    TooLarge { size: usize, max: usize },
+
+    #[error("Malformed signature: {0}")]
+    BadSignature(String),
+
+    #[error("Transaction was already batched")]
+    Duplicate,
+}
+
+/// Counters since the workers were created
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdmissionStats {
+    pub admitted: u64,
+    pub rejected_empty: u64,
+    pub rejected_too_large: u64,
+    pub rejected_signature: u64,
+    /// Dropped because they were seen recently
+    pub duplicates_dropped: u64,
+    /// Transactions in peers' batches that were already seen; they can't be
+    /// dropped without changing the batch
+    pub peer_duplicates: u64,
+}
+
+/// Bloom filter over transaction digests
+#[derive(Debug, Clone)]
+struct BloomFilter {
+    bits: Vec<u64>,
+    items: usize,
+}
+
+impl BloomFilter {
+    fn new(capacity: usize) -> Self {
+        let words = (capacity * BLOOM_BITS_PER_ITEM).div_ceil(64).max(BLOOM_MIN_WORDS);
+        Self {
+            bits: vec![0; words],
+            items: 0,
+        }
+    }
+
+    /// Bit positions of a digest, by double hashing its first 16 bytes
+    fn positions(&self, digest: &[u8; 32]) -> impl Iterator<Item = usize> {
+        let h1 = u64::from_le_bytes(digest[..8].try_into().expect("8 bytes"));
+        let h2 = u64::from_le_bytes(digest[8..16].try_into().expect("8 bytes"));
+        let len = (self.bits.len() * 64) as u64;
+        (0..BLOOM_HASHES as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % len) as usize)
+    }
+
+    fn contains(&self, digest: &[u8; 32]) -> bool {
+        self.positions(digest).all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    fn insert(&mut self, digest: &[u8; 32]) {
+        let positions: Vec<usize> = self.positions(digest).collect();
+        for bit in positions {
+            self.bits[bit / 64] |= 1 << (bit % 64);
+        }
+        self.items += 1;
+    }
+}
+
+/// Transactions seen recently, in two generations
+#[derive(Debug, Clone)]
+struct RecentTransactions {
+    capacity: usize,
+    current: BloomFilter,
+    previous: BloomFilter,
+}
+
+impl RecentTransactions {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            current: BloomFilter::new(capacity),
+            previous: BloomFilter::new(capacity),
+        }
+    }
+
+    fn contains(&self, digest: &[u8; 32]) -> bool {
+        self.current.contains(digest) || self.previous.contains(digest)
+    }
+
+    fn insert(&mut self, digest: &[u8; 32]) {
+        if self.current.items >= self.capacity {
+            self.previous = std::mem::replace(&mut self.current, BloomFilter::new(self.capacity));
+        }
+        self.current.insert(digest);
+    }
+}
+
+/// Admission checks shared by a validator's workers
+#[derive(Debug, Clone)]
+pub struct TransactionAdmission {
+    config: AdmissionConfig,
+    recent: RecentTransactions,
+    stats: AdmissionStats,
+}
+
+impl Default for TransactionAdmission {
+    fn default() -> Self {
+        Self::new(AdmissionConfig::default())
+    }
+}
+
+impl TransactionAdmission {
+    pub fn new(config: AdmissionConfig) -> Self {
+        Self {
+            recent: RecentTransactions::new(config.recent_capacity),
+            config,
+            stats: AdmissionStats::default(),
+        }
+    }
+
+    pub fn config(&self) -> &AdmissionConfig {
+        &self.config
+    }
+
+    pub fn stats(&self) -> AdmissionStats {
+        self.stats.clone()
+    }
+
+    /// Check a transaction and remember it if it's admitted
+    pub fn admit(&mut self, tx: &Transaction) -> Result<(), AdmissionError> {
+        let result = self.check(tx);
+        match &result {
+            Ok(()) => self.stats.admitted += 1,
+            Err(AdmissionError::Empty) => self.stats.rejected_empty += 1,
+            Err(AdmissionError::TooLarge { .. }) => self.stats.rejected_too_large += 1,
+            Err(AdmissionError::BadSignature(_)) => self.stats.rejected_signature += 1,
+            Err(AdmissionError::Duplicate) => self.stats.duplicates_dropped += 1,
+        }
+        result
+    }
+
+    fn check(&mut self, tx: &Transaction) -> Result<(), AdmissionError> {
+        if tx.data.is_empty() {
+            return Err(AdmissionError::Empty);
+        }
+        if tx.data.len() > self.config.max_transaction_bytes {
+            return Err(AdmissionError::TooLarge {
+                size: tx.data.len(),
+                max: self.config.max_transaction_bytes,
+            });
+        }
+        check_signatures(&tx.data)?;
+
+        let digest = transaction_digest(tx);
+        if self.recent.contains(&digest) {
+            return Err(AdmissionError::Duplicate);
+        }
+        self.recent.insert(&digest);
+        Ok(())
+    }
+
+    /// Remember the transactions of a peer's batch, so ours don't repeat them
+    pub fn observe_batch(&mut self, batch: &Batch) {
+        for tx in &batch.transactions {
+            let digest = transaction_digest(tx);
+            if self.recent.contains(&digest) {
+                self.stats.peer_duplicates += 1;
+            } else {
+                self.recent.insert(&digest);
+            }
+        }
+    }
+}
+
+/// What duplicates are detected by: the data, not when it was submitted
+fn transaction_digest(tx: &Transaction) -> [u8; 32] {
+    Sha256::digest(&tx.data).into()
+}
+
+/// Check the signatures of a transaction that is a contract commit; other
+/// transactions are opaque and pass
+fn check_signatures(data: &[u8]) -> Result<(), AdmissionError> {
+    let Ok(serde_json::Value::Object(commit)) = serde_json::from_slice::<serde_json::Value>(data) else {
+        return Ok(());
+    };
+    if !commit.contains_key("body") {
+        return Ok(());
+    }
+    let Some(signatures) = commit.get("head").and_then(|head| head.get("signatures")) else {
+        return Ok(());
+    };
+    if signatures.is_null() {
+        return Ok(());
+    }
+    let signatures = signatures
+        .as_object()
+        .ok_or_else(|| AdmissionError::BadSignature("signatures aren't a map of signer to signature".to_string()))?;
+    for (signer, signature) in signatures {
+        if signer.is_empty() {
+            return Err(AdmissionError::BadSignature("signature without a signer".to_string()));
+        }
+        let bytes = signature
+            .as_str()
+            .and_then(|signature| BASE64_STANDARD.decode(signature).ok())
+            .ok_or_else(|| AdmissionError::BadSignature(format!("signature of {} isn't base64", signer)))?;
+        if bytes.len() != SIGNATURE_BYTES {
+            return Err(AdmissionError::BadSignature(format!(
+                "signature of {} is {} bytes, not {}",
+                signer,
+                bytes.len(),
+                SIGNATURE_BYTES
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(data: &[u8]) -> Transaction {
+        Transaction {
+            data: data.to_vec(),
+            timestamp: 1000,
+        }
+    }
+
+    fn commit(signature: serde_json::Value) -> Transaction {
+        let commit = serde_json::json!({"body": [], "head": {"signatures": {"alice": signature}}});
+        tx(commit.to_string().as_bytes())
+    }
+
+    #[test]
+    fn test_admission_checks() {
+        let mut admission = TransactionAdmission::new(AdmissionConfig {
+            max_transaction_bytes: 512,
+            recent_capacity: 100,
+        });
+
+        assert_eq!(admission.admit(&tx(&[])), Err(AdmissionError::Empty));
+        assert!(matches!(admission.admit(&tx(&[0; 513])), Err(AdmissionError::TooLarge { size: 513, .. })));
+        assert!(admission.admit(&tx(&[1, 2, 3])).is_ok());
+
+        let signature = BASE64_STANDARD.encode([7u8; 64]);
+        assert!(admission.admit(&commit(signature.into())).is_ok());
+        assert!(matches!(admission.admit(&commit("sig".into())), Err(AdmissionError::BadSignature(_))));
+        let short = BASE64_STANDARD.encode([7u8; 32]);
+        assert!(matches!(admission.admit(&commit(short.into())), Err(AdmissionError::BadSignature(_))));
+
+        let stats = admission.stats();
+        assert_eq!((stats.admitted, stats.rejected_empty, stats.rejected_too_large, stats.rejected_signature), (2, 1, 1, 2));
+    }
+
+    #[test]
+    fn test_duplicates_are_dropped() {
+        let mut admission = TransactionAdmission::new(AdmissionConfig {
+            max_transaction_bytes: 512,
+            recent_capacity: 2,
+        });
+        assert!(admission.admit(&tx(b"a")).is_ok());
+
+        // Resubmitted later, it's still the same transaction
+        let mut again = tx(b"a");
+        again.timestamp = 2000;
+        assert_eq!(admission.admit(&again), Err(AdmissionError::Duplicate));
+
+        // Transactions in a peer's batch aren't batched again
+        admission.observe_batch(&Batch {
+            transactions: vec![tx(b"a"), tx(b"b")],
+            worker_id: 0,
+            timestamp: 1000,
+        });
+        assert_eq!(admission.admit(&tx(b"b")), Err(AdmissionError::Duplicate));
+
+        // Two generations later, "a" is forgotten
+        for data in [b"c", b"d", b"e"] {
+            assert!(admission.admit(&tx(data)).is_ok());
+        }
+        assert!(admission.admit(&tx(b"a")).is_ok());
+
+        let stats = admission.stats();
+        assert_eq!(stats.duplicates_dropped, 2);
+        assert_eq!(stats.peer_duplicates, 1);
+    }
+}
@@ -31,10 +31,12 @@ impl CertificateBuilder {
             bail!("duplicate vote from {:?}", voter);
         }
 
-        // TODO: Verify signature
-        // In a real implementation, we would verify:
-        // signature.verify(&voter, header.digest())
-        
+        // Without BLS keys votes aren't verified yet
+        if self.committee.uses_bls() {
+            let key = self.committee.bls_key(&voter).expect("BLS committee has every key");
+            bls::verify_vote(key, &self.header, &signature)?;
+        }
+
         self.votes.insert(voter, signature);
         
         Ok(())
@@ -64,11 +66,15 @@ impl CertificateBuilder {
             }
         }
 
-        // TODO: Aggregate signatures
-        // In a real implementation with BLS, we would:
-        // aggregated_sig = aggregate_signatures(self.votes.values())
-        let aggregated_signature = AggregatedSignature {
-            signature: vec![], // Placeholder
+        let aggregated_signature = if self.committee.uses_bls() {
+            let signatures: Vec<&[u8]> = self.votes.values().map(Vec::as_slice).collect();
+            AggregatedSignature {
+                signature: bls::aggregate(&signatures)?,
+            }
+        } else {
+            AggregatedSignature {
+                signature: vec![], // Placeholder
+            }
         };
 
         Ok(Certificate {
@@ -100,9 +106,15 @@ pub fn verify_certificate(cert: &Certificate, committee: &Committee) -> Result<(
             // In real implementation, verify the validator at this index signed
     }
 
-    // TODO: Verify aggregated signature
-    // In real implementation:
-    // aggregated_signature.verify(&cert.header.digest(), &signers_pubkeys)
+    if committee.uses_bls() {
+        let signer_keys: Vec<&[u8]> = cert
+            .get_signer_indices()
+            .into_iter()
+            .filter_map(|idx| committee.validator_order.get(idx))
+            .filter_map(|key| committee.bls_key(key))
+            .collect();
+        bls::verify_aggregate(&signer_keys, &cert.header, &cert.aggregated_signature.signature)?;
+    }
 
     Ok(())
 }
@@ -121,6 +133,60 @@ pub fn create_vote(header: &Header, voter: PublicKey, _private_key: &[u8]) -> Vo
     }
 }
 
+/// Create a vote for a header, signed with the voter's BLS key
+#[cfg(feature = "bls")]
+pub fn create_bls_vote(header: &Header, voter: PublicKey, keypair: &modal_common::bls::BlsKeypair) -> Vote {
+    Vote {
+        header_digest: header.digest(),
+        round: header.round,
+        voter,
+        signature: keypair.sign(&header.digest()),
+    }
+}
+
+/// BLS signatures over header digests
+#[cfg(feature = "bls")]
+mod bls {
+    use crate::narwhal::Header;
+    use anyhow::Result;
+    use modal_common::bls;
+
+    pub fn verify_vote(key: &[u8], header: &Header, signature: &[u8]) -> Result<()> {
+        bls::verify(key, &header.digest(), signature)
+    }
+
+    pub fn aggregate(signatures: &[&[u8]]) -> Result<Vec<u8>> {
+        bls::aggregate(signatures)
+    }
+
+    pub fn verify_aggregate(keys: &[&[u8]], header: &Header, signature: &[u8]) -> Result<()> {
+        bls::verify_aggregate(keys, &header.digest(), signature)
+    }
+}
+
+/// Committees with BLS keys can't be used without the `bls` feature
+#[cfg(not(feature = "bls"))]
+mod bls {
+    use crate::narwhal::Header;
+    use anyhow::{bail, Result};
+
+    fn unsupported<T>() -> Result<T> {
+        bail!("committee signs with BLS but BLS support is not enabled")
+    }
+
+    pub fn verify_vote(_key: &[u8], _header: &Header, _signature: &[u8]) -> Result<()> {
+        unsupported()
+    }
+
+    pub fn aggregate(_signatures: &[&[u8]]) -> Result<Vec<u8>> {
+        unsupported()
+    }
+
+    pub fn verify_aggregate(_keys: &[&[u8]], _header: &Header, _signature: &[u8]) -> Result<()> {
+        unsupported()
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -152,7 +218,7 @@ mod tests {
         Header {
             author: test_peer_id(0),
             round: 1,
-            batch_digest: [0u8; 32],
+            payload: vec![([0u8; 32], 0)],
             parents: vec![],
             timestamp: 1000,
         }
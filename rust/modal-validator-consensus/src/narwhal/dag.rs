@@ -5,6 +5,8 @@ use std::collections::{BTreeMap, HashMap};
 #[cfg(feature = "persistence")]
 use modal_datastore::DatastoreManager;
 #[cfg(feature = "persistence")]
+use crate::narwhal::Batch;
+#[cfg(feature = "persistence")]
 use crate::persistence::{ToPersistenceModel, digest_to_hex};
 
 /// DAG storage and management
@@ -61,8 +63,26 @@ impl DAG {
             }
         }
 
+        self.index(digest, cert);
+        Ok(())
+    }
+
+    /// Insert a certificate whose parents aren't in the DAG, such as a tip
+    /// handed to a validator joining the committee. Equivocation is still checked.
+    pub fn graft(&mut self, cert: Certificate) -> Result<()> {
+        if self.detect_equivocation(&cert) {
+            bail!("equivocation detected for author {:?} in round {}", cert.header.author, cert.header.round);
+        }
+        self.index(cert.digest(), cert);
+        Ok(())
+    }
+
+    fn index(&mut self, digest: CertificateDigest, cert: Certificate) {
+        let round = cert.header.round;
+        let author = cert.header.author;
+
         // Insert into primary storage
-        self.certificates.insert(digest, cert.clone());
+        self.certificates.insert(digest, cert);
 
         // Update round index
         self.by_round
@@ -77,8 +97,6 @@ impl DAG {
             .insert(round, digest);
 
         log::debug!("inserted certificate {} for round {} from {:?}", hex::encode(digest), round, &author);
-        
-        Ok(())
     }
 
     /// Get a certificate by digest
@@ -107,6 +125,11 @@ impl DAG {
             .and_then(|digest| self.certificates.get(digest))
     }
 
+    /// Get the highest round in which an author has a certificate
+    pub fn last_authored_round(&self, author: &PublicKey) -> Option<u64> {
+        self.by_author.get(author)?.keys().next_back().copied()
+    }
+
     /// Check if there is a path from `from` certificate to `to` certificate
     pub fn has_path(&self, from: &CertificateDigest, to: &CertificateDigest) -> bool {
         if from == to {
@@ -447,7 +470,7 @@ mod tests {
             header: Header {
                 author,
                 round,
-                batch_digest: [0u8; 32],
+                payload: vec![([0u8; 32], 0)],
                 parents,
                 timestamp: 1000,
             },
@@ -501,7 +524,7 @@ mod tests {
         
         // Try to insert different cert from same author in same round
         let mut cert2 = make_test_cert(test_peer_id(1), 0, vec![]);
-        cert2.header.batch_digest = [1u8; 32]; // Different batch
+        cert2.header.payload = vec![([1u8; 32], 0)]; // Different batch
         
         assert!(dag.detect_equivocation(&cert2));
         assert!(dag.insert(cert2).is_err());
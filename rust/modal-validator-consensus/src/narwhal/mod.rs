@@ -1,8 +1,12 @@
 pub mod types;
+pub mod admission;
 pub mod dag;
 pub mod certificate;
 pub mod worker;
+pub mod worker_pool;
 pub mod primary;
+pub mod reconfiguration;
+pub mod round_timer;
 pub mod sync;
 pub mod sync_client;
 
@@ -10,8 +14,15 @@ pub use types::{
     AggregatedSignature, Batch, BatchDigest, Certificate, CertificateDigest, Committee, Digest, Header, 
     PublicKey, Signature, Transaction, Validator, Vote, WorkerId,
 };
-pub use worker::Worker;
+pub use admission::{AdmissionConfig, AdmissionError, AdmissionStats, TransactionAdmission};
+pub use worker::{Worker, WorkerMessage};
+pub use worker_pool::WorkerPool;
 pub use primary::Primary;
+pub use reconfiguration::{
+    CommitteeChange, CommitteeSchedule, ReconfigurationCertificate, ReconfigurationSignature, ReconfigurationVotes,
+    StateHandoff, ACTIVATION_DELAY,
+};
+pub use round_timer::{RoundTimer, RoundTimerConfig};
 pub use sync::{SyncRequest, SyncResponse};
 pub use sync_client::{SyncClient, SyncStats};
 
@@ -1,4 +1,4 @@
-use crate::narwhal::{BatchDigest, Certificate, CertificateDigest, Committee, Header, PublicKey};
+use crate::narwhal::{BatchDigest, Certificate, CertificateDigest, Committee, Header, PublicKey, WorkerId};
 use crate::narwhal::certificate::CertificateBuilder;
 use crate::narwhal::dag::DAG;
 use anyhow::Result;
@@ -32,10 +32,11 @@ impl Primary {
         }
     }
 
-    /// Propose a new header for the current round
+    /// Propose a new header for the current round, referencing the batches
+    /// our workers sealed by digest
     pub async fn propose(
         &mut self,
-        batch_digest: BatchDigest,
+        payload: Vec<(BatchDigest, WorkerId)>,
     ) -> Result<Header> {
         let dag = self.dag.read().await;
         
@@ -67,7 +68,7 @@ impl Primary {
         let header = Header {
             author: self.validator,
             round: self.current_round,
-            batch_digest,
+            payload,
             parents,
             timestamp: std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
@@ -150,8 +151,7 @@ mod tests {
         let dag = Arc::new(RwLock::new(DAG::new()));
         let mut primary = Primary::new(test_peer_id(1), committee, dag);
 
-        let batch_digest = [0u8; 32];
-        let header = primary.propose(batch_digest).await.unwrap();
+        let header = primary.propose(vec![([0u8; 32], 0)]).await.unwrap();
 
         assert_eq!(header.round, 0);
         assert_eq!(header.author, vec![1]);
@@ -170,7 +170,7 @@ mod tests {
                 header: Header {
                     author: vec![i],
                     round: 0,
-                    batch_digest: [0u8; 32],
+                    payload: vec![([0u8; 32], 0)],
                     parents: vec![],
                     timestamp: 1000,
                 },
@@ -185,11 +185,11 @@ mod tests {
         // Advance to round 1
         primary.advance_round();
 
-        let batch_digest = [1u8; 32];
-        let header = primary.propose(batch_digest).await.unwrap();
+        let header = primary.propose(vec![([1u8; 32], 0), ([2u8; 32], 1)]).await.unwrap();
 
         assert_eq!(header.round, 1);
         assert_eq!(header.parents.len(), 4); // All genesis certificates
+        assert_eq!(header.batch_digests(), vec![[1u8; 32], [2u8; 32]]);
     }
 
     #[tokio::test]
@@ -202,7 +202,7 @@ mod tests {
             header: Header {
                 author: test_peer_id(1),
                 round: 0,
-                batch_digest: [0u8; 32],
+                payload: vec![([0u8; 32], 0)],
                 parents: vec![],
                 timestamp: 1000,
             },
@@ -0,0 +1,513 @@
+use crate::narwhal::{Certificate, Committee, PublicKey, Validator};
+use anyhow::{bail, Result};
+use modal_common::json_stringify_deterministic::stringify_deterministic;
+use modal_common::key_rotation::KeyRotation;
+use modal_common::keypair::Keypair;
+use modal_common::signer::Signer;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+
+/// Rounds between committing a reconfiguration and the new committee taking over.
+/// A change committed in round R is active from round R + 2, giving joining
+/// validators a round to receive their state handoff.
+pub const ACTIVATION_DELAY: u64 = 2;
+
+/// What members of the current committee sign to endorse a new committee:
+/// its epoch and each validator's key and stake, in order
+pub fn reconfiguration_payload(epoch: u64, validators: &[Validator]) -> Value {
+    let validators: Vec<Value> = validators
+        .iter()
+        .map(|validator| serde_json::json!({ "public_key": validator.public_key.to_string(), "stake": validator.stake }))
+        .collect();
+    serde_json::json!({ "epoch": epoch, "validators": validators })
+}
+
+/// A committee member's endorsement of a new committee
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReconfigurationSignature {
+    pub signer: PublicKey,
+    pub signature: String,
+}
+
+impl ReconfigurationSignature {
+    /// Endorse the committee of `epoch` made of `validators`
+    pub fn sign(signer: &dyn Signer, epoch: u64, validators: &[Validator]) -> Result<Self> {
+        let payload = stringify_deterministic(&reconfiguration_payload(epoch, validators), None);
+        Ok(Self {
+            signer: signer.public_key_as_base58_identity().parse()?,
+            signature: signer.sign_string_as_base64_pad(&payload)?,
+        })
+    }
+
+    /// Whether this is the signer's endorsement of the committee of `epoch`
+    /// made of `validators`
+    pub fn verify(&self, epoch: u64, validators: &[Validator]) -> bool {
+        Keypair::from_public_key(&self.signer.to_string(), "ed25519")
+            .and_then(|key| key.verify_json(&self.signature, &reconfiguration_payload(epoch, validators)))
+            .unwrap_or(false)
+    }
+}
+
+/// A committee change signed by a quorum of the committee it replaces
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconfigurationCertificate {
+    /// Epoch of the new committee
+    pub epoch: u64,
+    /// Round the reconfiguration was committed in: the round of the
+    /// certified block that carried it, not part of what was signed
+    #[serde(default)]
+    pub round: u64,
+    /// Validators of the new committee
+    pub validators: Vec<Validator>,
+    /// Endorsements by members of the current committee
+    pub signatures: Vec<ReconfigurationSignature>,
+}
+
+impl ReconfigurationCertificate {
+    /// First round the new committee is active in
+    pub fn activation_round(&self) -> u64 {
+        self.round + ACTIVATION_DELAY
+    }
+
+    /// The new committee
+    pub fn committee(&self) -> Committee {
+        Committee::new(self.validators.clone())
+    }
+
+    /// The certificate as committed in `round`
+    pub fn committed_in(&self, round: u64) -> Self {
+        Self { round, ..self.clone() }
+    }
+
+    /// Members whose endorsements are valid, each counted once
+    pub fn signers(&self) -> Vec<PublicKey> {
+        let mut seen = HashSet::new();
+        self.signatures
+            .iter()
+            .filter(|signature| signature.verify(self.epoch, &self.validators))
+            .map(|signature| signature.signer)
+            .filter(|signer| seen.insert(*signer))
+            .collect()
+    }
+
+    /// Check the change is to a non-empty committee and signed by a quorum of `committee`
+    pub fn verify(&self, committee: &Committee) -> Result<()> {
+        if self.validators.is_empty() {
+            bail!("reconfiguration for epoch {} has an empty committee", self.epoch);
+        }
+        if let Some(forged) = self.signatures.iter().find(|s| !s.verify(self.epoch, &self.validators)) {
+            bail!("invalid reconfiguration signature from {}", forged.signer);
+        }
+
+        let signers = self.signers();
+        if let Some(outsider) = signers.iter().find(|signer| !committee.contains(signer)) {
+            bail!("reconfiguration signer {} is not in the committee", outsider);
+        }
+        if !committee.check_quorum(&signers) {
+            bail!(
+                "reconfiguration lacks a quorum: {} of {} stake",
+                committee.get_stake(&signers),
+                committee.quorum_threshold()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Endorsements of proposed committees, gathered until one has a quorum
+#[derive(Debug, Clone, Default)]
+pub struct ReconfigurationVotes {
+    /// Signing payload -> certificate so far
+    proposals: BTreeMap<String, ReconfigurationCertificate>,
+}
+
+impl ReconfigurationVotes {
+    /// Count `vote` for the committee of `epoch` made of `validators`.
+    /// Returns the certificate once the votes of `committee` members reach a
+    /// quorum, the first time they do.
+    pub fn add(
+        &mut self,
+        epoch: u64,
+        validators: &[Validator],
+        vote: ReconfigurationSignature,
+        committee: &Committee,
+    ) -> Option<ReconfigurationCertificate> {
+        if validators.is_empty() || !committee.contains(&vote.signer) || !vote.verify(epoch, validators) {
+            return None;
+        }
+        let key = stringify_deterministic(&reconfiguration_payload(epoch, validators), None);
+        let proposal = self.proposals.entry(key).or_insert_with(|| ReconfigurationCertificate {
+            epoch,
+            round: 0,
+            validators: validators.to_vec(),
+            signatures: Vec::new(),
+        });
+        let had_quorum = committee.check_quorum(&proposal.signers());
+        if proposal.signatures.iter().any(|s| s.signer == vote.signer) {
+            return None;
+        }
+        proposal.signatures.push(vote);
+        (!had_quorum && committee.check_quorum(&proposal.signers())).then(|| proposal.clone())
+    }
+
+    /// Forget proposals for epochs up to `epoch`
+    pub fn clear_through(&mut self, epoch: u64) {
+        self.proposals.retain(|_, proposal| proposal.epoch > epoch);
+    }
+}
+
+/// Who joins and who leaves when a new committee takes over
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitteeChange {
+    pub epoch: u64,
+    pub activation_round: u64,
+    pub joining: Vec<PublicKey>,
+    pub leaving: Vec<PublicKey>,
+}
+
+impl CommitteeChange {
+    fn between(old: &Committee, new: &Committee, epoch: u64, activation_round: u64) -> Self {
+        Self {
+            epoch,
+            activation_round,
+            joining: new.validator_order.iter().filter(|v| !old.contains(v)).copied().collect(),
+            leaving: old.validator_order.iter().filter(|v| !new.contains(v)).copied().collect(),
+        }
+    }
+}
+
+/// State a joining validator needs to take part from the activation round
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateHandoff {
+    /// Epoch of the committee being joined
+    pub epoch: u64,
+    /// First round the committee is active in
+    pub activation_round: u64,
+    /// Validators of the committee being joined
+    pub validators: Vec<Validator>,
+    /// Certificates of the highest round in the sender's DAG
+    pub tips: Vec<Certificate>,
+    /// Round of the sender's last DAG checkpoint, if any
+    pub last_checkpoint_round: Option<u64>,
+}
+
+impl StateHandoff {
+    /// The committee being joined
+    pub fn committee(&self) -> Committee {
+        Committee::new(self.validators.clone())
+    }
+
+    /// Round the joining validator proposes first: the activation round, or
+    /// the round after the tips if the DAG has already moved past it
+    pub fn start_round(&self) -> u64 {
+        let after_tips = self.tips.iter().map(|tip| tip.header.round + 1).max().unwrap_or(0);
+        self.activation_round.max(after_tips)
+    }
+}
+
+/// The active committee and the changes scheduled to replace it
+#[derive(Debug, Clone)]
+pub struct CommitteeSchedule {
+    epoch: u64,
+    committee: Committee,
+    activated_at: u64,
+    /// Activation round -> (epoch, committee)
+    pending: BTreeMap<u64, (u64, Committee)>,
+}
+
+impl CommitteeSchedule {
+    /// Schedule starting with `committee` at genesis
+    pub fn new(committee: Committee) -> Self {
+        Self::with_epoch(committee, 0, 0)
+    }
+
+    /// Schedule starting with `committee` of `epoch`, active since `activated_at`
+    pub fn with_epoch(committee: Committee, epoch: u64, activated_at: u64) -> Self {
+        Self {
+            epoch,
+            committee,
+            activated_at,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// The active committee
+    pub fn committee(&self) -> &Committee {
+        &self.committee
+    }
+
+    /// Epoch of the active committee
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Round the active committee took over
+    pub fn activated_at(&self) -> u64 {
+        self.activated_at
+    }
+
+    /// The most recently scheduled committee with its epoch and activation round
+    /// (the active one if nothing is pending)
+    pub fn latest(&self) -> (u64, &Committee, u64) {
+        match self.pending.iter().next_back() {
+            Some((round, (epoch, committee))) => (*epoch, committee, *round),
+            None => (self.epoch, &self.committee, self.activated_at),
+        }
+    }
+
+    /// Committee in charge of `round`
+    pub fn committee_at(&self, round: u64) -> &Committee {
+        self.pending
+            .range(..=round)
+            .next_back()
+            .map(|(_, (_, committee))| committee)
+            .unwrap_or(&self.committee)
+    }
+
+    /// Verify and schedule a committed reconfiguration. The same change
+    /// committed again in an earlier round moves its activation earlier.
+    pub fn schedule(&mut self, cert: &ReconfigurationCertificate) -> Result<CommitteeChange> {
+        let recommitted = self
+            .pending
+            .iter()
+            .find(|(_, (epoch, _))| *epoch == cert.epoch)
+            .map(|(activation_round, _)| *activation_round);
+        if let Some(activation_round) = recommitted {
+            if cert.activation_round() >= activation_round {
+                bail!("reconfiguration for epoch {} already activates at round {}", cert.epoch, activation_round);
+            }
+            let replaced = self.pending.remove(&activation_round);
+            let result = self.schedule(cert);
+            if let (Err(_), Some(replaced)) = (&result, replaced) {
+                self.pending.insert(activation_round, replaced);
+            }
+            return result;
+        }
+
+        let (latest_epoch, latest, latest_activation) = self.latest();
+        if cert.epoch <= latest_epoch {
+            bail!("stale reconfiguration for epoch {} (at epoch {})", cert.epoch, latest_epoch);
+        }
+        if cert.activation_round() <= latest_activation {
+            bail!(
+                "reconfiguration activates at round {}, not after round {}",
+                cert.activation_round(),
+                latest_activation
+            );
+        }
+        cert.verify(self.committee_at(cert.round))?;
+
+        let committee = cert.committee();
+        let change = CommitteeChange::between(latest, &committee, cert.epoch, cert.activation_round());
+        self.pending.insert(cert.activation_round(), (cert.epoch, committee));
+        Ok(change)
+    }
+
+    /// Verify and schedule a validator's key rotation. From the rotation's
+    /// effective round, as a new epoch, the new key holds the old key's seat
+    /// with its stake and address. Earlier rounds keep their committee, so
+    /// certificates the old key signed still verify.
+    pub fn schedule_key_rotation(&mut self, rotation: &KeyRotation) -> Result<CommitteeChange> {
+        if !rotation.verify() {
+            bail!("invalid signature on rotation of {}", rotation.old_key);
+        }
+        let old: PublicKey = rotation.old_key.parse()?;
+        let new: PublicKey = rotation.new_key.parse()?;
+        let (latest_epoch, latest, latest_activation) = self.latest();
+        let activation_round = rotation.effective_height;
+        if activation_round <= latest_activation {
+            bail!(
+                "key rotation activates at round {}, not after round {}",
+                activation_round,
+                latest_activation
+            );
+        }
+        if !latest.contains(&old) {
+            bail!("rotated key {} is not in the committee", old);
+        }
+        if latest.contains(&new) {
+            bail!("new key {} is already in the committee", new);
+        }
+
+        let validators = latest
+            .validator_order
+            .iter()
+            .filter_map(|key| latest.validators.get(key))
+            .map(|validator| Validator {
+                public_key: if validator.public_key == old { new } else { validator.public_key },
+                ..validator.clone()
+            })
+            .collect();
+        let committee = Committee::new(validators);
+        let epoch = latest_epoch + 1;
+        let change = CommitteeChange::between(latest, &committee, epoch, activation_round);
+        self.pending.insert(activation_round, (epoch, committee));
+        Ok(change)
+    }
+
+    /// Activate every change due by `round`. Returns the combined change, if any.
+    pub fn activate(&mut self, round: u64) -> Option<CommitteeChange> {
+        let due: Vec<u64> = self.pending.range(..=round).map(|(r, _)| *r).collect();
+        let last = *due.last()?;
+        let previous = self.committee.clone();
+        for activation_round in due {
+            let (epoch, committee) = self.pending.remove(&activation_round)?;
+            self.epoch = epoch;
+            self.committee = committee;
+            self.activated_at = activation_round;
+        }
+        Some(CommitteeChange::between(&previous, &self.committee, self.epoch, last))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+
+    /// Helper to create a deterministic signing key for testing
+    fn test_keypair(seed: u8) -> Keypair {
+        use libp2p_identity::ed25519;
+        use modal_common::keypair::KeypairOrPublicKey;
+        let mut secret_bytes = [0u8; 32];
+        secret_bytes[0] = seed;
+        let secret = ed25519::SecretKey::try_from_bytes(secret_bytes).expect("valid secret key");
+        Keypair::new(KeypairOrPublicKey::Keypair(ed25519::Keypair::from(secret).into()))
+    }
+
+    /// Helper to create a deterministic PeerId for testing
+    fn test_peer_id(seed: u8) -> libp2p_identity::PeerId {
+        test_keypair(seed).as_public_address().parse().unwrap()
+    }
+
+    fn validators(seeds: &[u8]) -> Vec<Validator> {
+        seeds
+            .iter()
+            .map(|seed| Validator {
+                public_key: test_peer_id(*seed),
+                stake: 1,
+                network_address: SocketAddr::from(([127, 0, 0, 1], 8000 + *seed as u16)),
+            })
+            .collect()
+    }
+
+    fn reconfiguration(epoch: u64, round: u64, to: &[u8], signed_by: &[u8]) -> ReconfigurationCertificate {
+        let validators = validators(to);
+        ReconfigurationCertificate {
+            epoch,
+            round,
+            signatures: signed_by
+                .iter()
+                .map(|seed| ReconfigurationSignature::sign(&test_keypair(*seed), epoch, &validators).unwrap())
+                .collect(),
+            validators,
+        }
+    }
+
+    #[test]
+    fn test_committee_schedule_activates_after_delay() {
+        let mut schedule = CommitteeSchedule::new(Committee::new(validators(&[1, 2, 3, 4])));
+
+        // Validator 5 joins and validator 4 leaves
+        let change = schedule.schedule(&reconfiguration(1, 10, &[1, 2, 3, 5], &[1, 2, 3])).unwrap();
+        assert_eq!(change.activation_round, 12);
+        assert_eq!(change.joining, vec![test_peer_id(5)]);
+        assert_eq!(change.leaving, vec![test_peer_id(4)]);
+
+        assert!(schedule.committee_at(11).contains(&test_peer_id(4)));
+        assert!(schedule.committee_at(12).contains(&test_peer_id(5)));
+        assert!(schedule.activate(11).is_none());
+        assert_eq!(schedule.activate(12), Some(change));
+        assert_eq!(schedule.epoch(), 1);
+        assert_eq!(schedule.activated_at(), 12);
+        assert!(!schedule.committee().contains(&test_peer_id(4)));
+    }
+
+    #[test]
+    fn test_committee_schedule_rejects_invalid_reconfigurations() {
+        let mut schedule = CommitteeSchedule::new(Committee::new(validators(&[1, 2, 3, 4])));
+
+        // Two signers, one counted twice, are short of the 3 needed
+        assert!(schedule.schedule(&reconfiguration(1, 10, &[1, 2, 3], &[1, 2, 2])).is_err());
+        // Signed by a validator outside the committee
+        assert!(schedule.schedule(&reconfiguration(1, 10, &[1, 2, 3], &[1, 2, 5])).is_err());
+        assert!(schedule.schedule(&reconfiguration(1, 10, &[], &[1, 2, 3])).is_err());
+
+        // A signature over a different committee doesn't count
+        let mut forged = reconfiguration(1, 10, &[1, 2, 3], &[1, 2, 3]);
+        forged.signatures[2] = ReconfigurationSignature::sign(&test_keypair(3), 1, &validators(&[3])).unwrap();
+        assert!(schedule.schedule(&forged).is_err());
+
+        schedule.schedule(&reconfiguration(1, 10, &[1, 2, 3], &[1, 2, 3])).unwrap();
+        // The same change committed again later doesn't move; earlier, it does
+        assert!(schedule.schedule(&reconfiguration(1, 11, &[1, 2, 3], &[1, 2, 3])).is_err());
+        assert_eq!(schedule.schedule(&reconfiguration(1, 9, &[1, 2, 3], &[1, 2, 3])).unwrap().activation_round, 11);
+        assert!(!schedule.committee_at(11).contains(&test_peer_id(4)));
+        // Replayed epoch
+        assert!(schedule.schedule(&reconfiguration(1, 20, &[1, 2], &[1, 2, 3])).is_err());
+    }
+
+    #[test]
+    fn test_reconfiguration_votes_reach_quorum() {
+        let committee = Committee::new(validators(&[1, 2, 3, 4]));
+        let proposed = validators(&[1, 2, 3, 5]);
+        let vote = |seed: u8| ReconfigurationSignature::sign(&test_keypair(seed), 1, &proposed).unwrap();
+        let mut votes = ReconfigurationVotes::default();
+
+        assert!(votes.add(1, &proposed, vote(1), &committee).is_none());
+        // Repeated votes and votes from outside the committee don't count
+        assert!(votes.add(1, &proposed, vote(1), &committee).is_none());
+        assert!(votes.add(1, &proposed, vote(5), &committee).is_none());
+        // Nor do votes for another committee
+        assert!(votes.add(1, &validators(&[1, 2]), vote(2), &committee).is_none());
+
+        assert!(votes.add(1, &proposed, vote(2), &committee).is_none());
+        let cert = votes.add(1, &proposed, vote(3), &committee).unwrap();
+        assert_eq!(cert.signers(), vec![test_peer_id(1), test_peer_id(2), test_peer_id(3)]);
+        assert!(cert.committed_in(10).verify(&committee).is_ok());
+        assert_eq!(cert.committed_in(10).activation_round(), 12);
+        // The certificate is produced once
+        assert!(votes.add(1, &proposed, vote(4), &committee).is_none());
+
+        votes.clear_through(1);
+        assert!(votes.add(1, &proposed, vote(4), &committee).is_none());
+    }
+
+    #[test]
+    fn test_committee_schedule_key_rotation() {
+        let old = Keypair::generate().unwrap();
+        let old_key: PublicKey = old.as_public_address().parse().unwrap();
+        let new_key: PublicKey = Keypair::generate().unwrap().as_public_address().parse().unwrap();
+        let mut members = validators(&[1, 2, 3]);
+        members.push(Validator { public_key: old_key, stake: 5, ..validators(&[4]).remove(0) });
+        let mut schedule = CommitteeSchedule::new(Committee::new(members));
+
+        let mut forged = KeyRotation::sign(&old, &new_key.to_string(), 10, None).unwrap();
+        forged.effective_height = 11;
+        assert!(schedule.schedule_key_rotation(&forged).is_err());
+
+        let rotation = KeyRotation::sign(&old, &new_key.to_string(), 10, None).unwrap();
+        let change = schedule.schedule_key_rotation(&rotation).unwrap();
+        assert_eq!((change.epoch, change.activation_round), (1, 10));
+        assert_eq!((change.joining, change.leaving), (vec![new_key], vec![old_key]));
+
+        // Rounds before the rotation keep the old key; the new one inherits its stake
+        assert!(schedule.committee_at(9).contains(&old_key));
+        assert_eq!(schedule.committee_at(10).get_stake(&[new_key]), 5);
+        assert!(schedule.schedule_key_rotation(&rotation).is_err());
+    }
+
+    #[test]
+    fn test_state_handoff_start_round() {
+        let handoff = StateHandoff {
+            epoch: 1,
+            activation_round: 12,
+            validators: validators(&[1, 2, 3, 5]),
+            tips: vec![],
+            last_checkpoint_round: None,
+        };
+        assert_eq!(handoff.start_round(), 12);
+        assert!(handoff.committee().contains(&test_peer_id(5)));
+    }
+}
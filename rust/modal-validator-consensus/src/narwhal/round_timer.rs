@@ -0,0 +1,196 @@
+//! Adaptive round timeouts
+//!
+//! A round times out after a high percentile of recently observed certificate
+//! latencies (how long it took from starting a round to certifying our header
+//! in it), scaled by a safety margin. Each round that times out without a
+//! certificate doubles the timeout until one completes, so slow links still
+//! make progress; the observed latencies bring it back down on fast networks.
+//! The timeout always stays between the configured floor and ceiling.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Round timer settings
+#[derive(Debug, Clone)]
+pub struct RoundTimerConfig {
+    /// Timeout until enough latencies have been observed
+    pub initial_ms: u64,
+    /// Shortest timeout
+    pub floor_ms: u64,
+    /// Longest timeout, also the limit for backoff
+    pub ceiling_ms: u64,
+    /// Latency percentile the timeout is based on, from 0.0 to 1.0
+    pub percentile: f64,
+    /// Margin the percentile latency is multiplied by
+    pub multiplier: f64,
+    /// Number of recent latencies kept
+    pub window: usize,
+    /// Latencies needed before the timeout adapts
+    pub min_samples: usize,
+}
+
+impl Default for RoundTimerConfig {
+    fn default() -> Self {
+        Self {
+            initial_ms: 2_000,
+            floor_ms: 250,
+            ceiling_ms: 30_000,
+            percentile: 0.9,
+            multiplier: 1.5,
+            window: 50,
+            min_samples: 5,
+        }
+    }
+}
+
+/// Tracks certificate latencies and derives the round timeout from them
+#[derive(Debug, Clone)]
+pub struct RoundTimer {
+    config: RoundTimerConfig,
+    latencies: VecDeque<Duration>,
+    /// Rounds in a row that timed out without a certificate
+    consecutive_timeouts: u32,
+    /// Current round and when it started
+    current: Option<(u64, Instant)>,
+    certified: bool,
+}
+
+impl RoundTimer {
+    pub fn new(config: RoundTimerConfig) -> Self {
+        Self {
+            latencies: VecDeque::with_capacity(config.window),
+            config,
+            consecutive_timeouts: 0,
+            current: None,
+            certified: false,
+        }
+    }
+
+    pub fn config(&self) -> &RoundTimerConfig {
+        &self.config
+    }
+
+    /// Start timing `round`. A previous round that wasn't certified counts
+    /// as a timeout.
+    pub fn start_round(&mut self, round: u64, now: Instant) {
+        if self.current.is_some() && !self.certified {
+            self.record_timeout();
+        }
+        self.current = Some((round, now));
+        self.certified = false;
+    }
+
+    /// Our header for `round` was certified at `now`. Only the first
+    /// certificate of the current round is a latency sample.
+    pub fn on_certified(&mut self, round: u64, now: Instant) {
+        match self.current {
+            Some((current, started)) if current == round && !self.certified => {
+                self.certified = true;
+                self.record_latency(now.saturating_duration_since(started));
+            }
+            _ => {}
+        }
+    }
+
+    /// Add a certificate latency sample
+    pub fn record_latency(&mut self, latency: Duration) {
+        if self.latencies.len() == self.config.window {
+            self.latencies.pop_front();
+        }
+        self.latencies.push_back(latency);
+        self.consecutive_timeouts = 0;
+    }
+
+    /// A round ended without a certificate
+    pub fn record_timeout(&mut self) {
+        self.consecutive_timeouts = self.consecutive_timeouts.saturating_add(1);
+    }
+
+    pub fn consecutive_timeouts(&self) -> u32 {
+        self.consecutive_timeouts
+    }
+
+    /// The configured percentile of the recent latencies, if there are enough
+    pub fn latency_percentile(&self) -> Option<Duration> {
+        if self.latencies.is_empty() || self.latencies.len() < self.config.min_samples {
+            return None;
+        }
+        let mut sorted: Vec<Duration> = self.latencies.iter().copied().collect();
+        sorted.sort();
+        let rank = (self.config.percentile.clamp(0.0, 1.0) * (sorted.len() - 1) as f64).round() as usize;
+        Some(sorted[rank])
+    }
+
+    /// How long the current round may take
+    pub fn timeout(&self) -> Duration {
+        let base_ms = match self.latency_percentile() {
+            Some(latency) => latency.as_millis() as f64 * self.config.multiplier,
+            None => self.config.initial_ms as f64,
+        };
+        // Double for every round in a row that timed out; 2^20 is past any ceiling
+        let backoff = 2f64.powi(self.consecutive_timeouts.min(20) as i32);
+        let ceiling_ms = self.config.ceiling_ms.max(self.config.floor_ms);
+        let ms = (base_ms * backoff).clamp(self.config.floor_ms as f64, ceiling_ms as f64);
+        Duration::from_millis(ms as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timer() -> RoundTimer {
+        RoundTimer::new(RoundTimerConfig {
+            initial_ms: 2_000,
+            floor_ms: 100,
+            ceiling_ms: 10_000,
+            percentile: 0.9,
+            multiplier: 2.0,
+            window: 10,
+            min_samples: 3,
+        })
+    }
+
+    #[test]
+    fn test_timeout_follows_latency_percentile() {
+        let mut timer = timer();
+        assert_eq!(timer.timeout(), Duration::from_millis(2_000));
+
+        for ms in [200, 220, 250] {
+            timer.record_latency(Duration::from_millis(ms));
+        }
+        assert_eq!(timer.latency_percentile(), Some(Duration::from_millis(250)));
+        assert_eq!(timer.timeout(), Duration::from_millis(500));
+
+        // Old samples leave the window
+        for _ in 0..10 {
+            timer.record_latency(Duration::from_millis(20));
+        }
+        assert_eq!(timer.timeout(), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_timeouts_back_off_to_ceiling() {
+        let mut timer = timer();
+        let start = Instant::now();
+        timer.start_round(1, start);
+        for round in 2..=4 {
+            timer.start_round(round, start);
+        }
+        assert_eq!(timer.consecutive_timeouts(), 3);
+        assert_eq!(timer.timeout(), Duration::from_millis(10_000));
+
+        // A certificate resets the backoff
+        timer.on_certified(4, start + Duration::from_millis(400));
+        timer.on_certified(4, start + Duration::from_millis(900));
+        assert_eq!(timer.consecutive_timeouts(), 0);
+        timer.start_round(5, start);
+        assert_eq!(timer.consecutive_timeouts(), 0);
+        assert_eq!(timer.timeout(), Duration::from_millis(2_000));
+
+        // Certificates for other rounds aren't samples
+        timer.on_certified(4, start + Duration::from_millis(50));
+        timer.start_round(6, start);
+        assert_eq!(timer.consecutive_timeouts(), 1);
+    }
+}
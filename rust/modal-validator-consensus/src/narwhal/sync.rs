@@ -123,6 +123,16 @@ impl SyncResponse {
         Self::Empty
     }
     
+    /// Check the certificates in a response received from a peer
+    pub fn validate_fields(&self) -> anyhow::Result<()> {
+        if let Self::Certificates { certificates, .. } = self {
+            for cert in certificates {
+                cert.validate_fields()?;
+            }
+        }
+        Ok(())
+    }
+
     /// Check if this is an error response
     pub fn is_error(&self) -> bool {
         matches!(self, Self::Error { .. })
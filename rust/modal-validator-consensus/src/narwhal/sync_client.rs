@@ -228,7 +228,7 @@ mod tests {
             header: Header {
                 author: test_peer_id(author_seed),
                 round,
-                batch_digest: [round as u8; 32],
+                payload: vec![([round as u8; 32], 0)],
                 parents,
                 timestamp: 1000 + round,
             },
@@ -11,6 +11,19 @@ pub type BatchDigest = Digest;
 pub type CertificateDigest = Digest;
 pub type WorkerId = u32;
 
+/// Most batches a header may reference
+pub const MAX_HEADER_PAYLOAD: usize = 1024;
+
+/// Most parents a header may reference, and most signers a certificate may
+/// list; both are bounded by the committee size
+pub const MAX_COMMITTEE_SIZE: usize = 1024;
+
+/// Largest aggregated signature accepted
+pub const MAX_SIGNATURE_BYTES: usize = 1024;
+
+/// Largest certificate accepted from a peer, in bytes of JSON
+pub const MAX_CERTIFICATE_JSON_BYTES: usize = 1024 * 1024;
+
 /// A transaction to be ordered by consensus
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Transaction {
@@ -47,8 +60,9 @@ pub struct Header {
     pub author: PublicKey,
     /// Round number (monotonically increasing)
     pub round: u64,
-    /// Digest of the batch this header references
-    pub batch_digest: BatchDigest,
+    /// Digests of the batches this header references, with the worker that
+    /// holds each one. Batch contents travel between workers, never in headers.
+    pub payload: Vec<(BatchDigest, WorkerId)>,
     /// References to certificates from previous round (parents)
     pub parents: Vec<CertificateDigest>,
     /// Timestamp of header creation
@@ -65,6 +79,18 @@ impl Header {
         hasher.finalize().into()
     }
 
+    /// Digests of the batches referenced by this header
+    pub fn batch_digests(&self) -> Vec<BatchDigest> {
+        self.payload.iter().map(|(digest, _)| *digest).collect()
+    }
+
+    /// Check the reference lists of a header received from a peer are bounded
+    pub fn validate_fields(&self) -> anyhow::Result<()> {
+        modal_common::wire::check_len("header payload", self.payload.len(), MAX_HEADER_PAYLOAD)?;
+        modal_common::wire::check_len("header parents", self.parents.len(), MAX_COMMITTEE_SIZE)?;
+        Ok(())
+    }
+
     /// Verify that parent references are valid for this round
     pub fn verify_parents(&self, expected_round: u64) -> anyhow::Result<()> {
         if self.round != expected_round {
@@ -85,8 +111,25 @@ impl Header {
     }
 }
 
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Header {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let secret = libp2p_identity::ed25519::SecretKey::try_from_bytes(u.arbitrary::<[u8; 32]>()?)
+            .map_err(|_| arbitrary::Error::IncorrectFormat)?;
+        let keypair = libp2p_identity::ed25519::Keypair::from(secret);
+        Ok(Self {
+            author: PeerId::from_public_key(&keypair.public().into()),
+            round: u.arbitrary()?,
+            payload: u.arbitrary()?,
+            parents: u.arbitrary()?,
+            timestamp: u.arbitrary()?,
+        })
+    }
+}
+
 /// Aggregated signature from multiple validators
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct AggregatedSignature {
     /// The aggregated signature bytes
     pub signature: Vec<u8>,
@@ -97,6 +140,7 @@ pub type BitVec = Vec<bool>;
 
 /// A certificate: header + 2f+1 signatures
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Certificate {
     /// The header being certified
     pub header: Header,
@@ -107,6 +151,26 @@ pub struct Certificate {
 }
 
 impl Certificate {
+    /// Parse a certificate received from a peer and check its fields
+    pub fn from_json_slice(data: &[u8]) -> anyhow::Result<Self> {
+        let cert: Self = modal_common::wire::from_json_slice("certificate", data, MAX_CERTIFICATE_JSON_BYTES)?;
+        cert.validate_fields()?;
+        Ok(cert)
+    }
+
+    /// Check the fields of a certificate received from a peer are bounded.
+    /// Says nothing about whether its signatures verify.
+    pub fn validate_fields(&self) -> anyhow::Result<()> {
+        self.header.validate_fields()?;
+        modal_common::wire::check_len("signer list", self.signers.len(), MAX_COMMITTEE_SIZE)?;
+        modal_common::wire::check_len(
+            "aggregated signature",
+            self.aggregated_signature.signature.len(),
+            MAX_SIGNATURE_BYTES,
+        )?;
+        Ok(())
+    }
+
     /// Compute the digest of this certificate
     pub fn digest(&self) -> CertificateDigest {
         // Certificate digest is same as header digest
@@ -162,6 +226,10 @@ pub struct Committee {
     pub validators: HashMap<PublicKey, Validator>,
     /// Ordered list of public keys (for indexing in bitvecs)
     pub validator_order: Vec<PublicKey>,
+    /// BLS public keys of validators that sign with BLS. When every validator
+    /// has one, certificates carry a single aggregate signature.
+    #[serde(default)]
+    pub bls_keys: HashMap<PublicKey, Vec<u8>>,
 }
 
 impl Committee {
@@ -176,9 +244,41 @@ impl Committee {
         Self {
             validators,
             validator_order,
+            bls_keys: HashMap::new(),
         }
     }
 
+    /// Register a validator's BLS public key after checking its proof of possession
+    #[cfg(feature = "bls")]
+    pub fn add_bls_key(&mut self, validator: &PublicKey, key: Vec<u8>, proof: &[u8]) -> anyhow::Result<()> {
+        if !self.contains(validator) {
+            anyhow::bail!("validator {} is not in the committee", validator);
+        }
+        modal_common::bls::verify_possession(&key, proof)?;
+        self.bls_keys.insert(*validator, key);
+        Ok(())
+    }
+
+    /// Keep the BLS keys `previous` registered for validators still in this committee
+    pub fn inherit_bls_keys(&mut self, previous: &Committee) {
+        for key in &self.validator_order {
+            if let Some(bls_key) = previous.bls_keys.get(key) {
+                self.bls_keys.insert(*key, bls_key.clone());
+            }
+        }
+    }
+
+    /// Whether every validator signs with BLS
+    pub fn uses_bls(&self) -> bool {
+        !self.validator_order.is_empty()
+            && self.validator_order.iter().all(|key| self.bls_keys.contains_key(key))
+    }
+
+    /// Get a validator's BLS public key
+    pub fn bls_key(&self, key: &PublicKey) -> Option<&[u8]> {
+        self.bls_keys.get(key).map(Vec::as_slice)
+    }
+
     /// Get the total number of validators
     pub fn size(&self) -> usize {
         self.validators.len()
@@ -198,6 +298,13 @@ impl Committee {
         crate::consensus_math::calculate_2f_plus_1(total as f64)
     }
     
+    /// Get the validity threshold (f+1) - stake-weighted
+    ///
+    /// Any set of validators with this much stake includes an honest one.
+    pub fn validity_threshold(&self) -> u64 {
+        self.total_stake() - self.quorum_threshold() + 1
+    }
+
     /// Get the quorum threshold based on validator count (old behavior)
     /// 
     /// This is kept for compatibility but quorum_threshold() is preferred
@@ -295,7 +402,7 @@ mod tests {
         let header = Header {
             author: test_peer_id(1),
             round: 0,
-            batch_digest: [0u8; 32],
+            payload: vec![([0u8; 32], 0)],
             parents: vec![],
             timestamp: 1000,
         };
@@ -315,7 +422,7 @@ mod tests {
         let header = Header {
             author: test_peer_id(1),
             round: 1,
-            batch_digest: [0u8; 32],
+            payload: vec![([0u8; 32], 0)],
             parents: vec![[1u8; 32], [2u8; 32]],
             timestamp: 1000,
         };
@@ -337,7 +444,7 @@ mod tests {
             header: Header {
                 author: test_peer_id(1),
                 round: 1,
-                batch_digest: [0u8; 32],
+                payload: vec![([0u8; 32], 0)],
                 parents: vec![],
                 timestamp: 1000,
             },
@@ -357,6 +464,28 @@ mod tests {
         assert!(!cert_no_quorum.has_quorum(4));
     }
 
+    #[test]
+    fn test_certificate_from_json_slice() {
+        let cert = Certificate {
+            header: Header {
+                author: test_peer_id(1),
+                round: 1,
+                payload: vec![([0u8; 32], 0)],
+                parents: vec![[1u8; 32]],
+                timestamp: 1000,
+            },
+            aggregated_signature: AggregatedSignature { signature: vec![] },
+            signers: vec![true],
+        };
+        let json = serde_json::to_vec(&cert).unwrap();
+        assert_eq!(Certificate::from_json_slice(&json).unwrap().digest(), cert.digest());
+        assert!(Certificate::from_json_slice(&json[1..]).is_err());
+
+        let mut oversized = cert.clone();
+        oversized.signers = vec![false; MAX_COMMITTEE_SIZE + 1];
+        assert!(Certificate::from_json_slice(&serde_json::to_vec(&oversized).unwrap()).is_err());
+    }
+
     #[test]
     fn test_committee_quorum_threshold() {
         let validators = vec![
@@ -385,6 +514,7 @@ mod tests {
         let committee = Committee::new(validators);
         assert_eq!(committee.size(), 4);
         assert_eq!(committee.quorum_threshold(), 3); // 2*4/3 + 1 = 3
+        assert_eq!(committee.validity_threshold(), 2); // 4 - 3 + 1 = 2
         assert_eq!(committee.max_byzantine(), 1); // (4-1)/3 = 1
     }
 
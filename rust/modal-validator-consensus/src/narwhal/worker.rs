@@ -1,7 +1,16 @@
 use crate::narwhal::{Batch, BatchDigest, PublicKey, Transaction, WorkerId};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{mpsc, Mutex};
+
+/// Message streamed between workers with the same ID on different validators
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum WorkerMessage {
+    /// A batch sealed by `author`'s worker
+    Batch { author: PublicKey, batch: Batch },
+}
 
 /// Worker node that collects transactions and forms batches
 pub struct Worker {
@@ -15,8 +24,10 @@ pub struct Worker {
     pub max_batch_bytes: usize,
     /// Buffer of pending transactions
     tx_buffer: Vec<Transaction>,
-    /// Storage for batches (digest -> batch)
+    /// Storage for batches (digest -> batch), both our own and peers'
     storage: Arc<Mutex<HashMap<BatchDigest, Batch>>>,
+    /// Outbound stream of sealed batches to peer workers
+    network: Option<mpsc::UnboundedSender<WorkerMessage>>,
 }
 
 impl Worker {
@@ -34,9 +45,16 @@ impl Worker {
             max_batch_bytes,
             tx_buffer: Vec::new(),
             storage: Arc::new(Mutex::new(HashMap::new())),
+            network: None,
         }
     }
 
+    /// Stream every sealed batch to peer workers through `network`
+    pub fn with_network(mut self, network: mpsc::UnboundedSender<WorkerMessage>) -> Self {
+        self.network = Some(network);
+        self
+    }
+
     /// Add a transaction to the buffer
     pub fn add_transaction(&mut self, tx: Transaction) {
         self.tx_buffer.push(tx);
@@ -48,10 +66,17 @@ impl Worker {
             return None;
         }
 
-        // Take transactions up to batch_size
-        let transactions: Vec<Transaction> = self.tx_buffer
-            .drain(..self.tx_buffer.len().min(self.batch_size))
-            .collect();
+        // Take transactions up to batch_size and max_batch_bytes (always at least one)
+        let mut count = 0;
+        let mut bytes = 0;
+        for tx in self.tx_buffer.iter().take(self.batch_size) {
+            bytes += tx.data.len();
+            if count > 0 && bytes > self.max_batch_bytes {
+                break;
+            }
+            count += 1;
+        }
+        let transactions: Vec<Transaction> = self.tx_buffer.drain(..count).collect();
 
         if transactions.is_empty() {
             return None;
@@ -71,10 +96,41 @@ impl Worker {
         // Store batch
         let mut storage = self.storage.lock().await;
         storage.insert(digest, batch.clone());
+        drop(storage);
+
+        if let Some(network) = &self.network {
+            let message = WorkerMessage::Batch {
+                author: self.validator,
+                batch: batch.clone(),
+            };
+            if network.send(message).is_err() {
+                log::warn!("worker {} could not stream batch: network closed", self.id);
+            }
+        }
 
         Some((batch, digest))
     }
 
+    /// Store a batch streamed by the worker with our ID on another validator
+    pub async fn receive_batch(&self, author: PublicKey, batch: Batch) -> Result<BatchDigest> {
+        if batch.worker_id != self.id {
+            anyhow::bail!(
+                "batch from {} is for worker {}, not worker {}",
+                author,
+                batch.worker_id,
+                self.id
+            );
+        }
+        let digest = batch.digest();
+        self.storage.lock().await.insert(digest, batch);
+        Ok(digest)
+    }
+
+    /// Whether this worker holds the batch
+    pub async fn has_batch(&self, digest: &BatchDigest) -> bool {
+        self.storage.lock().await.contains_key(digest)
+    }
+
     /// Serve a batch by digest (for availability protocol)
     pub async fn serve_batch(&self, digest: BatchDigest) -> Option<Batch> {
         let storage = self.storage.lock().await;
@@ -155,6 +211,34 @@ mod tests {
         assert!(not_found.is_none());
     }
 
+    #[tokio::test]
+    async fn test_worker_streams_batches_to_peers() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut worker = Worker::new(1, test_peer_id(1), 100, 4).with_network(tx);
+        let peer = Worker::new(1, test_peer_id(2), 100, 4);
+
+        for i in 0..3 {
+            worker.add_transaction(Transaction {
+                data: vec![i; 2],
+                timestamp: 1000,
+            });
+        }
+
+        // Only two 2-byte transactions fit in 4 bytes
+        let (batch, digest) = worker.form_batch().await.unwrap();
+        assert_eq!(batch.transactions.len(), 2);
+        assert_eq!(worker.pending_count(), 1);
+
+        let WorkerMessage::Batch { author, batch } = rx.try_recv().unwrap();
+        assert_eq!(author, test_peer_id(1));
+        assert_eq!(peer.receive_batch(author, batch.clone()).await.unwrap(), digest);
+        assert!(peer.has_batch(&digest).await);
+
+        // Batches only go to the worker with the same ID
+        let other = Worker::new(2, test_peer_id(2), 100, 4);
+        assert!(other.receive_batch(author, batch).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_worker_batch_size_limit() {
         let mut worker = Worker::new(0, test_peer_id(1), 3, 1024 * 512); // Max 3 transactions
@@ -0,0 +1,255 @@
+use crate::narwhal::admission::{AdmissionConfig, AdmissionError, AdmissionStats, TransactionAdmission};
+use crate::narwhal::worker::WorkerMessage;
+use crate::narwhal::{Batch, BatchDigest, PublicKey, SyncRequest, SyncResponse, Transaction, Worker, WorkerId};
+use anyhow::Result;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+
+/// The workers of one validator
+///
+/// Transactions are spread across the workers, each of which seals its own
+/// batches and streams them to the worker with the same ID on every other
+/// validator. The primary only sees the resulting batch digests.
+/// Transactions go through the pool's admission checks first, so the same
+/// transaction isn't batched twice by any of the workers.
+pub struct WorkerPool {
+    /// This validator's public key
+    pub validator: PublicKey,
+    /// Workers, indexed by worker ID
+    workers: Vec<Arc<Mutex<Worker>>>,
+    /// Worker that receives the next submitted transaction
+    next_worker: AtomicUsize,
+    /// Checks shared by all the workers
+    admission: Mutex<TransactionAdmission>,
+}
+
+impl WorkerPool {
+    /// Create `count` workers for `validator`
+    pub fn new(validator: PublicKey, count: usize, batch_size: usize, max_batch_bytes: usize) -> Self {
+        Self::with_network(validator, count, batch_size, max_batch_bytes, None)
+    }
+
+    /// Create `count` workers that stream their batches through `network`
+    pub fn with_network(
+        validator: PublicKey,
+        count: usize,
+        batch_size: usize,
+        max_batch_bytes: usize,
+        network: Option<mpsc::UnboundedSender<WorkerMessage>>,
+    ) -> Self {
+        let workers = (0..count.max(1))
+            .map(|id| {
+                let worker = Worker::new(id as WorkerId, validator, batch_size, max_batch_bytes);
+                let worker = match &network {
+                    Some(network) => worker.with_network(network.clone()),
+                    None => worker,
+                };
+                Arc::new(Mutex::new(worker))
+            })
+            .collect();
+        Self {
+            validator,
+            workers,
+            next_worker: AtomicUsize::new(0),
+            admission: Mutex::new(TransactionAdmission::default()),
+        }
+    }
+
+    /// Admit transactions within these limits
+    pub fn with_admission(self, config: AdmissionConfig) -> Self {
+        Self {
+            admission: Mutex::new(TransactionAdmission::new(config)),
+            ..self
+        }
+    }
+
+    /// Number of workers
+    pub fn len(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Whether the pool has no workers
+    pub fn is_empty(&self) -> bool {
+        self.workers.is_empty()
+    }
+
+    /// Hand a transaction to the next worker, round-robin, if it passes the
+    /// admission checks
+    pub async fn submit_transaction(&self, tx: Transaction) -> Result<(), AdmissionError> {
+        self.admission.lock().await.admit(&tx)?;
+        let index = self.next_worker.fetch_add(1, Ordering::Relaxed) % self.workers.len();
+        self.workers[index].lock().await.add_transaction(tx);
+        Ok(())
+    }
+
+    /// Admission counters, including duplicates dropped
+    pub async fn admission_stats(&self) -> AdmissionStats {
+        self.admission.lock().await.stats()
+    }
+
+    /// Seal a batch on every worker with pending transactions.
+    /// Returns the payload for the primary's next header.
+    pub async fn seal_batches(&self) -> Vec<(BatchDigest, WorkerId)> {
+        let mut payload = Vec::new();
+        for worker in &self.workers {
+            let mut worker = worker.lock().await;
+            if let Some((batch, digest)) = worker.form_batch().await {
+                log::debug!(
+                    "worker {} sealed batch with {} transactions",
+                    worker.id,
+                    batch.transactions.len()
+                );
+                payload.push((digest, worker.id));
+            }
+        }
+        payload
+    }
+
+    /// Store a batch streamed by a peer validator's worker
+    pub async fn handle_message(&self, message: WorkerMessage) -> Result<BatchDigest> {
+        match message {
+            WorkerMessage::Batch { author, batch } => {
+                let worker = self.worker(batch.worker_id)?;
+                self.admission.lock().await.observe_batch(&batch);
+                let digest = worker.lock().await.receive_batch(author, batch).await?;
+                Ok(digest)
+            }
+        }
+    }
+
+    /// Get a batch held by the given worker
+    pub async fn get_batch(&self, digest: BatchDigest, worker_id: WorkerId) -> Option<Batch> {
+        let worker = self.worker(worker_id).ok()?;
+        let worker = worker.lock().await;
+        worker.serve_batch(digest).await
+    }
+
+    /// Entries of a header's payload whose batches haven't reached our workers.
+    /// A header should only be voted for once this is empty.
+    pub async fn missing_batches(&self, payload: &[(BatchDigest, WorkerId)]) -> Vec<(BatchDigest, WorkerId)> {
+        let mut missing = Vec::new();
+        for (digest, worker_id) in payload {
+            let available = match self.worker(*worker_id) {
+                Ok(worker) => worker.lock().await.has_batch(digest).await,
+                Err(_) => false,
+            };
+            if !available {
+                missing.push((*digest, *worker_id));
+            }
+        }
+        missing
+    }
+
+    /// Serve batch requests from peers (other requests are for the DAG)
+    pub async fn handle_sync_request(&self, request: SyncRequest) -> SyncResponse {
+        let digests = match request {
+            SyncRequest::GetBatch { digest } => vec![digest],
+            SyncRequest::GetBatches { digests } => digests,
+            _ => return SyncResponse::error("not a batch request".to_string()),
+        };
+        let mut batches = Vec::new();
+        for digest in digests {
+            for worker in &self.workers {
+                if let Some(batch) = worker.lock().await.serve_batch(digest).await {
+                    batches.push(batch);
+                    break;
+                }
+            }
+        }
+        if batches.is_empty() {
+            SyncResponse::empty()
+        } else {
+            SyncResponse::batches(batches)
+        }
+    }
+
+    /// Number of transactions waiting to be batched across all workers
+    pub async fn pending_count(&self) -> usize {
+        let mut total = 0;
+        for worker in &self.workers {
+            total += worker.lock().await.pending_count();
+        }
+        total
+    }
+
+    fn worker(&self, worker_id: WorkerId) -> Result<&Arc<Mutex<Worker>>> {
+        self.workers
+            .get(worker_id as usize)
+            .ok_or_else(|| anyhow::anyhow!("no worker {}", worker_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Helper to create a deterministic PeerId for testing
+    fn test_peer_id(seed: u8) -> libp2p_identity::PeerId {
+        use libp2p_identity::ed25519;
+        let mut secret_bytes = [0u8; 32];
+        secret_bytes[0] = seed;
+        let secret = ed25519::SecretKey::try_from_bytes(secret_bytes).expect("valid secret key");
+        let keypair = ed25519::Keypair::from(secret);
+        libp2p_identity::PeerId::from_public_key(&keypair.public().into())
+    }
+
+    #[tokio::test]
+    async fn test_worker_pool_disseminates_batches() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let pool = WorkerPool::with_network(test_peer_id(1), 3, 100, 1024, Some(tx));
+        let peer = WorkerPool::new(test_peer_id(2), 3, 100, 1024);
+
+        for i in 0..5u8 {
+            pool.submit_transaction(Transaction { data: vec![i], timestamp: 1000 }).await.unwrap();
+        }
+        assert_eq!(pool.pending_count().await, 5);
+
+        // Round-robin spreads the transactions over all three workers
+        let payload = pool.seal_batches().await;
+        let worker_ids: Vec<WorkerId> = payload.iter().map(|(_, id)| *id).collect();
+        assert_eq!(worker_ids, vec![0, 1, 2]);
+        assert_eq!(pool.pending_count().await, 0);
+
+        // The peer can't vote for the payload until the batches arrive
+        assert_eq!(peer.missing_batches(&payload).await.len(), 3);
+        while let Ok(message) = rx.try_recv() {
+            peer.handle_message(message).await.unwrap();
+        }
+        assert!(peer.missing_batches(&payload).await.is_empty());
+
+        let (digest, worker_id) = payload[0];
+        let batch = peer.get_batch(digest, worker_id).await.unwrap();
+        assert_eq!(batch.transactions.len(), 2);
+        assert!(matches!(
+            peer.handle_sync_request(SyncRequest::batch(digest)).await,
+            SyncResponse::Batches { batches } if batches.len() == 1
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_worker_pool_drops_duplicates() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let pool = WorkerPool::with_network(test_peer_id(1), 2, 100, 1024, Some(tx));
+        let peer = WorkerPool::new(test_peer_id(2), 2, 100, 1024).with_admission(AdmissionConfig {
+            max_transaction_bytes: 4,
+            ..Default::default()
+        });
+
+        let commit = Transaction { data: vec![1, 2], timestamp: 1000 };
+        pool.submit_transaction(commit.clone()).await.unwrap();
+        assert_eq!(pool.submit_transaction(commit.clone()).await, Err(AdmissionError::Duplicate));
+        assert_eq!(pool.pending_count().await, 1);
+        pool.seal_batches().await;
+
+        // Once the peer has our batch, it won't batch the same transaction
+        peer.handle_message(rx.try_recv().unwrap()).await.unwrap();
+        assert_eq!(peer.submit_transaction(commit).await, Err(AdmissionError::Duplicate));
+        assert!(matches!(
+            peer.submit_transaction(Transaction { data: vec![0; 5], timestamp: 1000 }).await,
+            Err(AdmissionError::TooLarge { .. })
+        ));
+        assert_eq!(pool.admission_stats().await.duplicates_dropped, 1);
+        assert_eq!(peer.admission_stats().await.duplicates_dropped, 1);
+    }
+}
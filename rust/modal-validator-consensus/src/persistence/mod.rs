@@ -1,14 +1,13 @@
 pub mod recovery;
 
 use crate::narwhal::{
-    AggregatedSignature, Batch, BatchDigest, Certificate, CertificateDigest,
-    Header, PublicKey, Transaction,
+    AggregatedSignature, Batch, Certificate, CertificateDigest, Header, Transaction,
 };
+use crate::narwhal::dag::DAG;
 use anyhow::{Context, Result};
 use libp2p_identity::PeerId;
-use modal_datastore::models::{
-    DAGBatch, DAGCertificate, ConsensusMetadata, DAGState,
-};
+use modal_datastore::models::{DAGBatch, DAGCertificate};
+use modal_datastore::DatastoreManager;
 use std::str::FromStr;
 
 /// Convert a digest (32-byte array) to hex string
@@ -37,6 +36,52 @@ pub fn string_to_peer_id(s: &str) -> Result<PeerId> {
     PeerId::from_str(s).context("invalid peer id")
 }
 
+/// Flag certificates as committed in the datastore, so recovery can rebuild
+/// the consensus state. `anchor_round` is the round of the committing anchor.
+pub async fn mark_committed_multi(
+    datastore: &DatastoreManager,
+    dag: &DAG,
+    digests: &[CertificateDigest],
+    anchor_round: u64,
+) {
+    let mut committed = Vec::with_capacity(digests.len());
+    for digest in digests {
+        let cert_round = dag.get(digest).map(|c| c.header.round).unwrap_or(0);
+        
+        // Load certificate model and mark as committed
+        let keys = [
+            ("round".to_string(), cert_round.to_string()),
+            ("digest".to_string(), digest_to_hex(digest)),
+        ].into_iter().collect();
+        
+        if let Ok(Some(mut cert_model)) = DAGCertificate::find_one_multi(datastore, keys).await {
+            cert_model.committed = true;
+            cert_model.committed_at_round = Some(anchor_round);
+            committed.push(cert_model);
+        }
+    }
+    
+    // A commit can cover many rounds of certificates; save them in one write
+    if let Err(e) = DAGCertificate::save_all_to_final(datastore, &committed).await {
+        log::warn!("failed to mark {} certificates as committed: {}", committed.len(), e);
+    }
+}
+
+/// Flag a certificate as the anchor selected for `round`, so recovery can
+/// restore the anchors the commit rule counts
+pub async fn mark_anchor_multi(datastore: &DatastoreManager, round: u64, digest: &CertificateDigest) {
+    let keys = [
+        ("round".to_string(), round.to_string()),
+        ("digest".to_string(), digest_to_hex(digest)),
+    ].into_iter().collect();
+    
+    if let Ok(Some(mut cert_model)) = DAGCertificate::find_one_multi(datastore, keys).await {
+        if cert_model.mark_anchor_multi(datastore).await.is_err() {
+            log::warn!("failed to mark certificate {:?} as anchor", digest);
+        }
+    }
+}
+
 /// Trait for converting consensus types to persistence models
 pub trait ToPersistenceModel<T> {
     fn to_persistence_model(&self) -> Result<T>;
@@ -64,11 +109,12 @@ impl ToPersistenceModel<DAGCertificate> for Certificate {
             header: serde_json::to_string(&self.header)?,
             aggregated_signature: serde_json::to_string(&self.aggregated_signature)?,
             signers: self.signers.clone(),
-            batch_digest: digest_to_hex(&self.header.batch_digest),
+            batch_digests: self.header.batch_digests().iter().map(digest_to_hex).collect(),
             parents: self.header.parents.iter().map(digest_to_hex).collect(),
             timestamp: self.header.timestamp,
             committed: false,
             committed_at_round: None,
+            anchor: false,
             created_at: now,
         })
     }
@@ -161,7 +207,7 @@ mod tests {
         let header = Header {
             author: peer_id,
             round: 1,
-            batch_digest: [1u8; 32],
+            payload: vec![([1u8; 32], 0)],
             parents: vec![[2u8; 32], [3u8; 32]],
             timestamp: 1000,
         };
@@ -1,9 +1,10 @@
-use crate::narwhal::Certificate;
+use crate::narwhal::{Batch, Certificate, PublicKey};
 use crate::narwhal::dag::DAG;
-use crate::persistence::FromPersistenceModel;
+use crate::persistence::{hex_to_digest, string_to_peer_id, FromPersistenceModel};
 use crate::shoal::{ConsensusState, ReputationState};
 use anyhow::{Context, Result};
-use modal_datastore::models::{DAGCertificate, DAGState};
+use modal_datastore::models::{DAGBatch, DAGCertificate, DAGState};
+use modal_datastore::stores::Store;
 use modal_datastore::DatastoreManager;
 
 /// Strategy for recovering DAG state from persistent storage
@@ -26,6 +27,31 @@ pub struct RecoveryResult {
     pub used_checkpoint: bool,
     pub consensus_state: Option<ConsensusState>,
     pub reputation_state: Option<ReputationState>,
+    /// Stored batches, each with the validator that authored it
+    pub batches: Vec<(PublicKey, Batch)>,
+}
+
+impl RecoveryResult {
+    /// Last round committed before the restart
+    pub fn last_committed_round(&self) -> u64 {
+        self.consensus_state
+            .as_ref()
+            .map(|state| state.last_committed_round)
+            .unwrap_or(0)
+    }
+
+    /// Round a validator should resume proposing at.
+    ///
+    /// This is past every round the validator already has a certificate in,
+    /// so a restarted validator never proposes a second header for a round.
+    pub fn resume_round(&self, validator: &PublicKey) -> u64 {
+        let next_own_round = self
+            .dag
+            .last_authored_round(validator)
+            .map(|round| round + 1)
+            .unwrap_or(0);
+        next_own_round.max(self.highest_round)
+    }
 }
 
 /// Recover DAG from persistent storage using specified strategy (multi-store version)
@@ -53,66 +79,34 @@ pub async fn recover_dag_multi(
 async fn recover_from_scratch_multi(datastore: &DatastoreManager) -> Result<RecoveryResult> {
     log::info!("Recovering DAG from scratch...");
     
-    let mut dag = DAG::new();
-    let prefix = "/dag/certificates/round";
-    let mut cert_models = Vec::new();
-    
-    // Iterate through all certificates from ValidatorFinal store
-    let store = datastore.validator_final();
-    let iterator = store.iterator(prefix);
-    for result in iterator {
-        let (key, _) = result.context("failed to iterate certificates")?;
-        let key_str = String::from_utf8(key.to_vec()).context("invalid key UTF-8")?;
-        
-        // Parse key to extract round and digest
-        let parts: Vec<&str> = key_str.split('/').collect();
-        if parts.len() >= 6 {
-            if let (Some(round_str), Some(digest)) = (parts.get(4), parts.get(6)) {
-                let keys = [
-                    ("round".to_string(), round_str.to_string()),
-                    ("digest".to_string(), digest.to_string()),
-                ].into_iter().collect();
-                
-                if let Some(cert_model) = DAGCertificate::find_one_multi(datastore, keys)
-                    .await
-                    .context("failed to load certificate")? 
-                {
-                    cert_models.push(cert_model);
-                }
-            }
-        }
-    }
-    
+    let cert_models = load_certificate_models(datastore).await?;
     log::info!("Found {} certificates to load", cert_models.len());
     
-    // Sort by round to ensure parents are loaded before children
-    let mut certs_with_round: Vec<(u64, Certificate)> = Vec::new();
+    // Models are sorted by round, so parents are inserted before children
+    let mut dag = DAG::new();
     for model in &cert_models {
         let cert = Certificate::from_persistence_model(model)
             .context("failed to deserialize certificate")?;
-        certs_with_round.push((model.round, cert));
-    }
-    certs_with_round.sort_by_key(|(round, _)| *round);
-    
-    // Insert certificates into DAG
-    let mut highest_round = 0;
-    for (round, cert) in certs_with_round {
         dag.insert(cert).context("failed to insert certificate into DAG")?;
-        if round > highest_round {
-            highest_round = round;
-        }
     }
+    let highest_round = dag.highest_round();
     
     log::info!("Successfully loaded {} certificates, highest round: {}", 
                cert_models.len(), highest_round);
     
+    // Without a checkpoint, consensus state comes from the anchor and committed flags
+    let mut consensus_state = ConsensusState::new();
+    consensus_state.current_round = highest_round;
+    restore_consensus_progress(&mut consensus_state, &cert_models)?;
+    
     Ok(RecoveryResult {
         dag,
         certificates_loaded: cert_models.len(),
         highest_round,
         used_checkpoint: false,
-        consensus_state: None,
+        consensus_state: Some(consensus_state),
         reputation_state: None,
+        batches: load_batches(datastore).await?,
     })
 }
 
@@ -132,11 +126,11 @@ async fn recover_from_checkpoint_multi(datastore: &DatastoreManager) -> Result<R
     use base64::Engine;
     let dag_bytes = base64::engine::general_purpose::STANDARD.decode(&checkpoint.dag_snapshot)
         .context("failed to decode DAG snapshot")?;
-    let dag: DAG = bincode::deserialize(&dag_bytes)
+    let mut dag: DAG = bincode::deserialize(&dag_bytes)
         .context("failed to deserialize DAG")?;
     
     // Deserialize consensus state
-    let consensus_state: Option<ConsensusState> = 
+    let mut consensus_state: Option<ConsensusState> = 
         serde_json::from_str(&checkpoint.consensus_state).ok();
     
     // Deserialize reputation state
@@ -144,54 +138,118 @@ async fn recover_from_checkpoint_multi(datastore: &DatastoreManager) -> Result<R
         serde_json::from_str(&checkpoint.reputation_state).ok();
     
     // Load any certificates created after the checkpoint
-    let mut final_dag = dag;
-    let prefix = "/dag/certificates/round";
+    let cert_models = load_certificate_models(datastore).await?;
     let mut newer_certs = 0;
+    for model in cert_models.iter().filter(|m| m.round > checkpoint.checkpoint_round) {
+        let cert = Certificate::from_persistence_model(model)
+            .context("failed to deserialize certificate")?;
+        if dag.get(&cert.digest()).is_none() {
+            dag.insert(cert).context("failed to insert certificate")?;
+            newer_certs += 1;
+        }
+    }
     
+    log::info!("Loaded checkpoint + {} newer certificates", newer_certs);
+    
+    // Anchors may have been selected and committed after the checkpoint was taken
+    if let Some(state) = consensus_state.as_mut() {
+        restore_consensus_progress(state, &cert_models)?;
+    }
+    
+    Ok(RecoveryResult {
+        highest_round: dag.highest_round(),
+        dag,
+        certificates_loaded: checkpoint.certificate_count + newer_certs,
+        used_checkpoint: true,
+        consensus_state,
+        reputation_state,
+        batches: load_batches(datastore).await?,
+    })
+}
+
+/// Load all stored certificates, sorted by round
+async fn load_certificate_models(datastore: &DatastoreManager) -> Result<Vec<DAGCertificate>> {
+    let prefix = "/dag/certificates/round";
+    let mut cert_models = Vec::new();
+    
+    // Iterate through all certificates from ValidatorFinal store
     let store = datastore.validator_final();
-    let iterator = store.iterator(prefix);
-    for result in iterator {
+    for result in store.iterator(prefix) {
         let (key, _) = result.context("failed to iterate certificates")?;
         let key_str = String::from_utf8(key.to_vec()).context("invalid key UTF-8")?;
         
         // Parse key to extract round and digest
         let parts: Vec<&str> = key_str.split('/').collect();
-        if parts.len() >= 6 {
-            if let (Some(round_str), Some(digest)) = (parts.get(4), parts.get(6)) {
-                let round = round_str.parse::<u64>().unwrap_or(0);
-                
-                if round > checkpoint.checkpoint_round {
-                    let keys = [
-                        ("round".to_string(), round_str.to_string()),
-                        ("digest".to_string(), digest.to_string()),
-                    ].into_iter().collect();
-                    
-                    if let Some(cert_model) = DAGCertificate::find_one_multi(datastore, keys)
-                        .await
-                        .context("failed to load certificate")? 
-                    {
-                        let cert = Certificate::from_persistence_model(&cert_model)
-                            .context("failed to deserialize certificate")?;
-                        final_dag.insert(cert).context("failed to insert certificate")?;
-                        newer_certs += 1;
-                    }
-                }
+        if let (Some(round_str), Some(digest)) = (parts.get(4), parts.get(6)) {
+            let keys = [
+                ("round".to_string(), round_str.to_string()),
+                ("digest".to_string(), digest.to_string()),
+            ].into_iter().collect();
+            
+            if let Some(cert_model) = DAGCertificate::find_one_multi(datastore, keys)
+                .await
+                .context("failed to load certificate")? 
+            {
+                cert_models.push(cert_model);
             }
         }
     }
     
-    log::info!("Loaded checkpoint + {} newer certificates", newer_certs);
+    // Keys sort lexically ("10" < "2"), so order by round explicitly
+    cert_models.sort_by_key(|model| model.round);
+    Ok(cert_models)
+}
+
+/// Load all stored batches along with their authors
+async fn load_batches(datastore: &DatastoreManager) -> Result<Vec<(PublicKey, Batch)>> {
+    let prefix = "/dag/batches/digest";
+    let mut batches = Vec::new();
     
-    Ok(RecoveryResult {
-        dag: final_dag,
-        certificates_loaded: checkpoint.certificate_count + newer_certs,
-        highest_round: checkpoint.highest_round.max(
-            checkpoint.checkpoint_round + (newer_certs > 0) as u64
-        ),
-        used_checkpoint: true,
-        consensus_state,
-        reputation_state,
-    })
+    let store = datastore.validator_final();
+    for result in store.iterator(prefix) {
+        let (key, _) = result.context("failed to iterate batches")?;
+        let key_str = String::from_utf8(key.to_vec()).context("invalid key UTF-8")?;
+        
+        if let Some(digest) = key_str.split('/').nth(4) {
+            let keys = [("digest".to_string(), digest.to_string())].into_iter().collect();
+            let Some(model) = DAGBatch::find_one_multi(datastore, keys)
+                .await
+                .context("failed to load batch")?
+            else {
+                continue;
+            };
+            
+            match string_to_peer_id(&model.author) {
+                Ok(author) => batches.push((author, Batch::from_persistence_model(&model)?)),
+                Err(_) => log::warn!("skipping batch {} with unknown author", model.digest),
+            }
+        }
+    }
+    
+    Ok(batches)
+}
+
+/// Apply the anchor and committed flags stored with the certificates to `state`
+fn restore_consensus_progress(state: &mut ConsensusState, cert_models: &[DAGCertificate]) -> Result<()> {
+    for model in cert_models {
+        if !model.anchor && !model.committed {
+            continue;
+        }
+        let digest = hex_to_digest(&model.digest)?;
+        if model.anchor {
+            match state.get_anchor(model.round) {
+                Some(anchor) if *anchor != digest => state.add_pipelined_anchor(model.round, digest),
+                _ => state.set_anchor(model.round, digest),
+            }
+        }
+        if model.committed {
+            state.commit(digest);
+            if let Some(anchor_round) = model.committed_at_round {
+                state.last_committed_round = state.last_committed_round.max(anchor_round);
+            }
+        }
+    }
+    Ok(())
 }
 
 /// Verify DAG consistency after recovery
@@ -264,7 +322,7 @@ mod tests {
             header: Header {
                 author: test_peer_id(1),
                 round: 0,
-                batch_digest: [1u8; 32],
+                payload: vec![([1u8; 32], 0)],
                 parents: vec![],
                 timestamp: 1000,
             },
@@ -276,7 +334,7 @@ mod tests {
             header: Header {
                 author: test_peer_id(2),
                 round: 1,
-                batch_digest: [2u8; 32],
+                payload: vec![([2u8; 32], 0)],
                 parents: vec![cert1.digest()],
                 timestamp: 2000,
             },
@@ -298,6 +356,53 @@ mod tests {
         assert_eq!(result.dag.round_size(1), 1);
     }
 
+    #[tokio::test]
+    async fn test_recover_consensus_progress() {
+        let datastore = setup_test_datastore().await;
+        
+        let genesis = Certificate {
+            header: Header {
+                author: test_peer_id(1),
+                round: 0,
+                payload: vec![([1u8; 32], 0)],
+                parents: vec![],
+                timestamp: 1000,
+            },
+            aggregated_signature: AggregatedSignature { signature: vec![1, 2, 3] },
+            signers: vec![true, true, true],
+        };
+        let next = Certificate {
+            header: Header {
+                author: test_peer_id(2),
+                round: 1,
+                payload: vec![([2u8; 32], 0)],
+                parents: vec![genesis.digest()],
+                timestamp: 2000,
+            },
+            aggregated_signature: AggregatedSignature { signature: vec![4, 5, 6] },
+            signers: vec![true, true, true],
+        };
+        
+        // Genesis was selected as anchor and committed on its votes
+        let mut model = genesis.to_persistence_model().unwrap();
+        model.anchor = true;
+        model.committed = true;
+        model.committed_at_round = Some(0);
+        model.save_to_final(&datastore).await.unwrap();
+        next.to_persistence_model().unwrap().save_to_final(&datastore).await.unwrap();
+        
+        let result = recover_from_scratch_multi(&datastore).await.unwrap();
+        let state = result.consensus_state.as_ref().unwrap();
+        assert_eq!(state.get_anchor(0), Some(&genesis.digest()));
+        assert!(state.is_committed(&genesis.digest()));
+        assert!(!state.is_committed(&next.digest()));
+        assert_eq!(result.last_committed_round(), 0);
+        
+        // Validator 2 already has a certificate in round 1; validator 1 can still fill it
+        assert_eq!(result.resume_round(&test_peer_id(2)), 2);
+        assert_eq!(result.resume_round(&test_peer_id(1)), 1);
+    }
+
     #[tokio::test]
     async fn test_verify_dag_consistency() {
         let mut dag = DAG::new();
@@ -306,7 +411,7 @@ mod tests {
             header: Header {
                 author: test_peer_id(1),
                 round: 0,
-                batch_digest: [1u8; 32],
+                payload: vec![([1u8; 32], 0)],
                 parents: vec![],
                 timestamp: 1000,
             },
@@ -0,0 +1,217 @@
+//! Canonical ordering and encoding of committed batches
+//!
+//! Every validator has to turn the same committed certificates into the same
+//! sequence of transactions, whatever order the certificates and batches
+//! arrived in. Certificates are ordered parents first; certificates that are
+//! ready at the same time go by round and then by digest. Batches follow
+//! their certificate's payload order. The encoding is deterministic JSON, so
+//! the output of two nodes can be compared or hashed byte for byte.
+
+use crate::narwhal::dag::DAG;
+use crate::narwhal::{Batch, BatchDigest, CertificateDigest, PublicKey, Transaction, WorkerId};
+use anyhow::{anyhow, bail, Result};
+use modal_common::json_stringify_deterministic::stringify_deterministic;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+
+/// A committed batch's place in the canonical order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchRef {
+    pub round: u64,
+    pub author: PublicKey,
+    pub certificate: CertificateDigest,
+    pub digest: BatchDigest,
+    pub worker_id: WorkerId,
+}
+
+/// A committed batch with its transactions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommittedBatch {
+    pub round: u64,
+    pub author: PublicKey,
+    pub certificate: CertificateDigest,
+    pub digest: BatchDigest,
+    pub transactions: Vec<Transaction>,
+}
+
+impl CommittedBatch {
+    /// Attach the batch's transactions to its place in the order
+    pub fn new(batch_ref: BatchRef, batch: Batch) -> Result<Self> {
+        if batch.digest() != batch_ref.digest {
+            bail!("batch does not match digest {}", hex::encode(batch_ref.digest));
+        }
+        Ok(Self {
+            round: batch_ref.round,
+            author: batch_ref.author,
+            certificate: batch_ref.certificate,
+            digest: batch_ref.digest,
+            transactions: batch.transactions,
+        })
+    }
+
+    fn to_value(&self) -> Value {
+        json!({
+            "round": self.round,
+            "author": self.author.to_string(),
+            "certificate": hex::encode(self.certificate),
+            "digest": hex::encode(self.digest),
+            "transactions": self
+                .transactions
+                .iter()
+                .map(|tx| json!({ "data": hex::encode(&tx.data), "timestamp": tx.timestamp }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// Order committed certificates: parents before children, ties by (round, digest)
+pub fn order_certificates(dag: &DAG, committed: &BTreeSet<CertificateDigest>) -> Result<Vec<CertificateDigest>> {
+    let mut pending_parents: HashMap<CertificateDigest, usize> = HashMap::new();
+    let mut children: HashMap<CertificateDigest, Vec<CertificateDigest>> = HashMap::new();
+    let mut ready = BTreeSet::new();
+
+    for digest in committed {
+        let cert = dag
+            .get(digest)
+            .ok_or_else(|| anyhow!("committed certificate {} is not in the DAG", hex::encode(digest)))?;
+        let parents: Vec<&CertificateDigest> =
+            cert.header.parents.iter().filter(|parent| committed.contains(*parent)).collect();
+        for parent in &parents {
+            children.entry(**parent).or_default().push(*digest);
+        }
+        if parents.is_empty() {
+            ready.insert((cert.header.round, *digest));
+        } else {
+            pending_parents.insert(*digest, parents.len());
+        }
+    }
+
+    let mut ordered = Vec::with_capacity(committed.len());
+    while let Some((_, digest)) = ready.pop_first() {
+        ordered.push(digest);
+        for child in children.get(&digest).into_iter().flatten() {
+            let remaining = pending_parents.get_mut(child).expect("child has pending parents");
+            *remaining -= 1;
+            if *remaining == 0 {
+                pending_parents.remove(child);
+                let round = dag.get(child).map(|cert| cert.header.round).unwrap_or_default();
+                ready.insert((round, *child));
+            }
+        }
+    }
+
+    if ordered.len() != committed.len() {
+        bail!("cycle detected in DAG or missing certificates");
+    }
+    Ok(ordered)
+}
+
+/// The batches of the committed certificates in canonical order
+pub fn order_batches(dag: &DAG, committed: &BTreeSet<CertificateDigest>) -> Result<Vec<BatchRef>> {
+    let mut batches = Vec::new();
+    for digest in order_certificates(dag, committed)? {
+        let cert = dag.get(&digest).expect("ordered certificate is in the DAG");
+        for (batch, worker_id) in &cert.header.payload {
+            batches.push(BatchRef {
+                round: cert.header.round,
+                author: cert.header.author,
+                certificate: digest,
+                digest: *batch,
+                worker_id: *worker_id,
+            });
+        }
+    }
+    Ok(batches)
+}
+
+/// Encode committed batches as deterministic JSON
+pub fn encode(batches: &[CommittedBatch]) -> String {
+    let value = Value::Array(batches.iter().map(CommittedBatch::to_value).collect());
+    stringify_deterministic(&value, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::narwhal::{AggregatedSignature, Certificate, Header};
+
+    /// Helper to create a deterministic PeerId for testing
+    fn test_peer_id(seed: u8) -> libp2p_identity::PeerId {
+        use libp2p_identity::ed25519;
+        let mut secret_bytes = [0u8; 32];
+        secret_bytes[0] = seed;
+        let secret = ed25519::SecretKey::try_from_bytes(secret_bytes).expect("valid secret key");
+        let keypair = ed25519::Keypair::from(secret);
+        libp2p_identity::PeerId::from_public_key(&keypair.public().into())
+    }
+
+    fn make_test_cert(author: u8, round: u64, parents: Vec<CertificateDigest>) -> Certificate {
+        Certificate {
+            header: Header {
+                author: test_peer_id(author),
+                round,
+                payload: vec![([author; 32], 0), ([author + 100; 32], 1)],
+                parents,
+                timestamp: 1000 + round,
+            },
+            aggregated_signature: AggregatedSignature { signature: vec![] },
+            signers: vec![true, true, true, false],
+        }
+    }
+
+    #[test]
+    fn test_order_independent_of_insertion() {
+        let genesis: Vec<Certificate> = (1..=3).map(|a| make_test_cert(a, 0, vec![])).collect();
+        let parents: Vec<CertificateDigest> = genesis.iter().map(|c| c.digest()).collect();
+        let round1: Vec<Certificate> = (1..=3).map(|a| make_test_cert(a, 1, parents.clone())).collect();
+        let certs: Vec<Certificate> = genesis.into_iter().chain(round1).collect();
+        let committed: BTreeSet<CertificateDigest> = certs.iter().map(|c| c.digest()).collect();
+
+        let mut forward = DAG::new();
+        for cert in &certs {
+            forward.insert(cert.clone()).unwrap();
+        }
+        let mut backward = DAG::new();
+        for cert in certs.iter().take(3).rev().chain(certs.iter().skip(3).rev()) {
+            backward.insert(cert.clone()).unwrap();
+        }
+
+        let ordered = order_certificates(&forward, &committed).unwrap();
+        assert_eq!(ordered, order_certificates(&backward, &committed).unwrap());
+
+        // Round 0 comes first, each round by digest
+        let mut round0 = parents.clone();
+        round0.sort();
+        assert_eq!(ordered[..3], round0[..]);
+
+        let batches = order_batches(&forward, &committed).unwrap();
+        assert_eq!(batches.len(), 12);
+        assert_eq!(batches[0].certificate, ordered[0]);
+        assert_eq!((batches[0].worker_id, batches[1].worker_id), (0, 1));
+    }
+
+    #[test]
+    fn test_encode_is_stable() {
+        let batch = Batch {
+            transactions: vec![Transaction { data: vec![1, 2], timestamp: 5 }],
+            worker_id: 0,
+            timestamp: 1000,
+        };
+        let batch_ref = BatchRef {
+            round: 0,
+            author: test_peer_id(1),
+            certificate: [7u8; 32],
+            digest: batch.digest(),
+            worker_id: 0,
+        };
+        let committed = CommittedBatch::new(batch_ref.clone(), batch).unwrap();
+        let encoded = encode(&[committed.clone()]);
+
+        assert_eq!(encoded, encode(&[committed]));
+        assert!(encoded.starts_with(r#"[{"author":""#));
+        assert!(encoded.contains(r#""transactions":[{"data":"0102","timestamp":5}]"#));
+
+        let wrong = Batch { transactions: vec![], worker_id: 0, timestamp: 1000 };
+        assert!(CommittedBatch::new(batch_ref, wrong).is_err());
+    }
+}
@@ -1,8 +1,9 @@
-use crate::narwhal::{Certificate, CertificateDigest, Committee};
+use crate::narwhal::{Certificate, CertificateDigest, Committee, PublicKey};
 use crate::narwhal::dag::DAG;
-use crate::shoal::{ConsensusState, PerformanceRecord};
-use crate::shoal::reputation::ReputationManager;
+use crate::shoal::{canonical, ConsensusState, ShoalConfig};
+use crate::shoal::reputation::{ReputationManager, REPUTATION_UPDATE_ROUNDS};
 use anyhow::Result;
+use std::collections::BTreeSet;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -19,6 +20,8 @@ pub struct ShoalConsensus {
     pub state: ConsensusState,
     /// Committee
     pub committee: Committee,
+    /// Leader selection settings
+    pub config: ShoalConfig,
     /// Optional datastore for persistence
     #[cfg(feature = "persistence")]
     pub datastore: Option<Arc<DatastoreManager>>,
@@ -36,11 +39,18 @@ impl ShoalConsensus {
             reputation,
             state: ConsensusState::new(),
             committee,
+            config: ShoalConfig::default(),
             #[cfg(feature = "persistence")]
             datastore: None,
         }
     }
 
+    /// Set the leader selection settings
+    pub fn with_config(mut self, config: ShoalConfig) -> Self {
+        self.config = config;
+        self
+    }
+
     /// Set the datastore for persistence
     #[cfg(feature = "persistence")]
     pub fn with_datastore(mut self, datastore: Arc<DatastoreManager>) -> Self {
@@ -49,6 +59,9 @@ impl ShoalConsensus {
     }
 
     /// Process a new certificate and potentially commit
+    ///
+    /// Leaders' certificates in even rounds are anchors. Certificates in odd
+    /// rounds vote for the previous round's anchor by referencing it.
     pub async fn process_certificate(&mut self, cert: Certificate) -> Result<Vec<CertificateDigest>> {
         let round = cert.header.round;
         let digest = cert.digest();
@@ -63,105 +76,149 @@ impl ShoalConsensus {
             }
         }
 
-        // Update reputation based on certificate arrival
-        self.record_certificate_performance(&cert);
-
-        // Try to select anchor for this round
-        if let Some(anchor) = self.try_select_anchor(round).await? {
-            log::info!("selected anchor {} for round {}", hex::encode(anchor), round);
-            self.state.set_anchor(round, anchor);
-
-            // Check commit rule
-            if self.check_commit_rule(&anchor).await? {
-                log::info!("committing anchor {} for round {}", hex::encode(anchor), round);
-                return self.commit_certificate(anchor).await;
-            }
+        if round % 2 == 0 {
+            self.record_anchor(&cert).await;
+            return Ok(Vec::new());
         }
+        self.try_commit(round - 1).await
+    }
 
-        Ok(vec![])
+    /// Leaders of a round, in priority order. Only even rounds have anchors,
+    /// so leaders rotate every other round.
+    pub fn leaders(&self, round: u64) -> Vec<PublicKey> {
+        let count = self.config.leaders_per_round.max(1);
+        if self.config.leader_reputation {
+            return self.reputation.select_leaders(round, count);
+        }
+        let order = &self.committee.validator_order;
+        (0..count.min(order.len()))
+            .map(|i| order[(round as usize / 2 + i) % order.len()])
+            .collect()
     }
 
-    /// Try to select an anchor for a round
-    async fn try_select_anchor(&self, round: u64) -> Result<Option<CertificateDigest>> {
-        // Check if we already have an anchor for this round
-        if self.state.get_anchor(round).is_some() {
-            return Ok(None);
+    /// Record a certificate from one of an even round's leaders as an anchor
+    async fn record_anchor(&mut self, cert: &Certificate) {
+        let round = cert.header.round;
+        let digest = cert.digest();
+        match self.leaders(round).iter().position(|leader| *leader == cert.header.author) {
+            Some(0) if self.state.get_anchor(round).is_none() => {
+                log::debug!("selected anchor {} for round {}", hex::encode(digest), round);
+                self.state.set_anchor(round, digest);
+            }
+            // With pipelined leaders, the further leaders' certificates are anchors too
+            Some(position) if position > 0 && !self.state.round_anchors(round).contains(&digest) => {
+                log::debug!("selected pipelined anchor {} for round {}", hex::encode(digest), round);
+                self.state.add_pipelined_anchor(round, digest);
+            }
+            _ => return,
         }
+        #[cfg(feature = "persistence")]
+        if let Some(datastore) = &self.datastore {
+            crate::persistence::mark_anchor_multi(datastore, round, &digest).await;
+        }
+    }
 
-        let dag = self.dag.read().await;
+    /// The certificate of a round's first leader, if it's in the DAG
+    fn leader_cert(&self, dag: &DAG, round: u64) -> Option<CertificateDigest> {
+        let leader = self.leaders(round).first().copied()?;
+        dag.get_author_cert(&leader, round).map(Certificate::digest)
+    }
 
-        // Select leader based on reputation
-        let leader = self.reputation.select_leader(round);
+    /// Round of the last anchor committed
+    fn last_anchor_round(&self) -> Option<u64> {
+        self.state
+            .anchors
+            .iter()
+            .rev()
+            .find(|(_, anchor)| self.state.is_committed(anchor))
+            .map(|(round, _)| *round)
+    }
 
-        // Try to get leader's certificate for this round
-        if let Some(leader_cert) = dag.get_author_cert(&leader, round) {
-            return Ok(Some(leader_cert.digest()));
+    /// Commit the anchor of an even round once validators with f+1 stake
+    /// vote for it, after the skipped anchors it reaches since the last
+    /// committed one. Any certificate of a later round reaches an anchor with
+    /// f+1 votes, so every validator commits the same anchors in the same
+    /// order whichever certificates it saw first.
+    async fn try_commit(&mut self, round: u64) -> Result<Vec<CertificateDigest>> {
+        let last_anchor_round = self.last_anchor_round();
+        if last_anchor_round.is_some_and(|last| round <= last) {
+            return Ok(Vec::new());
         }
 
-        // Fallback: if leader's certificate not available, select next-best
-        // (implements prevalent responsiveness)
-        let certs = dag.get_round(round);
-        if !certs.is_empty() {
-            // Select based on reputation of available certificates
-            let mut cert_scores: Vec<(CertificateDigest, f64)> = certs
+        let chain = {
+            let dag = self.dag.read().await;
+            let Some(anchor) = self.leader_cert(&dag, round).filter(|anchor| !self.state.is_committed(anchor)) else {
+                return Ok(Vec::new());
+            };
+            let voters: Vec<PublicKey> = dag
+                .get_round(round + 1)
                 .iter()
-                .map(|cert| {
-                    let score = self.reputation.get_score(&cert.header.author);
-                    (cert.digest(), score)
-                })
+                .filter(|cert| cert.header.parents.contains(&anchor))
+                .map(|cert| cert.header.author)
                 .collect();
+            if self.committee.get_stake(&voters) < self.committee.validity_threshold() {
+                return Ok(Vec::new());
+            }
 
-            cert_scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-
-            if let Some((digest, _)) = cert_scores.first() {
-                log::info!("using fallback anchor (leader unavailable) for round {}", round);
-                return Ok(Some(*digest));
+            // Walk back to the last committed anchor, keeping each anchor the
+            // one after it reaches
+            let mut chain = vec![(round, anchor)];
+            let mut earlier_round = round;
+            while earlier_round >= 2 {
+                earlier_round -= 2;
+                if last_anchor_round.is_some_and(|last| earlier_round <= last) {
+                    break;
+                }
+                let later = chain[chain.len() - 1].1;
+                if let Some(earlier) = self.leader_cert(&dag, earlier_round) {
+                    if self.state.is_committed(&earlier) {
+                        break;
+                    }
+                    if dag.has_path(&later, &earlier) {
+                        chain.push((earlier_round, earlier));
+                    }
+                }
+            }
+            chain.reverse();
+            chain
+        };
+
+        let mut committed = Vec::new();
+        let mut previous_round = last_anchor_round;
+        for (anchor_round, anchor) in chain {
+            if let Some(previous_round) = previous_round {
+                committed.extend(self.commit_pipelined(previous_round, &anchor).await?);
             }
+            log::info!("committing anchor {} for round {}", hex::encode(anchor), anchor_round);
+            committed.extend(self.commit_certificate(anchor).await?);
+            previous_round = Some(anchor_round);
         }
-
-        Ok(None)
+        Ok(committed)
     }
 
-    /// Check if an anchor satisfies the commit rule
-    async fn check_commit_rule(&self, anchor: &CertificateDigest) -> Result<bool> {
-        let dag = self.dag.read().await;
-        
-        let anchor_cert = dag.get(anchor)
-            .ok_or_else(|| anyhow::anyhow!("anchor certificate not found"))?;
-        
-        let current_round = anchor_cert.header.round;
-
-        // Genesis can commit immediately
-        if current_round == 0 {
-            return Ok(true);
-        }
-
-        // For round > 0: need path to 2f+1 anchors from previous round
-        let prev_round = current_round - 1;
-
-        // Get anchors from previous round
-        let prev_anchors: Vec<CertificateDigest> = (0..=prev_round)
-            .filter_map(|r| self.state.get_anchor(r).copied())
-            .collect();
-
-        if prev_anchors.is_empty() {
-            // No previous anchors yet
-            return Ok(false);
-        }
-
-        // Count how many previous anchors are reachable
-        let mut reachable_count = 0;
-        for prev_anchor in &prev_anchors {
-            if dag.has_path(anchor, prev_anchor) {
-                reachable_count += 1;
-            }
+    /// Commit the further leaders' certificates of a committed anchor's round
+    /// that the next anchor reaches, in leader order
+    async fn commit_pipelined(&mut self, round: u64, next_anchor: &CertificateDigest) -> Result<Vec<CertificateDigest>> {
+        let reached: Vec<CertificateDigest> = {
+            let dag = self.dag.read().await;
+            self.leaders(round)
+                .iter()
+                .skip(1)
+                .filter_map(|leader| dag.get_author_cert(leader, round).map(Certificate::digest))
+                .filter(|digest| !self.state.is_committed(digest) && dag.has_path(next_anchor, digest))
+                .collect()
+        };
+
+        let mut committed = Vec::new();
+        for anchor in reached {
+            log::info!("committing pipelined anchor {} for round {}", hex::encode(anchor), round);
+            committed.extend(self.commit_certificate(anchor).await?);
         }
-
-        let quorum = self.committee.quorum_threshold() as usize;
-        Ok(reachable_count >= quorum)
+        Ok(committed)
     }
 
-    /// Commit a certificate and return all newly committed certificates
+    /// Commit a certificate and return all newly committed certificates, in
+    /// canonical order
     async fn commit_certificate(&mut self, anchor: CertificateDigest) -> Result<Vec<CertificateDigest>> {
         let dag = self.dag.read().await;
         
@@ -169,84 +226,57 @@ impl ShoalConsensus {
             .ok_or_else(|| anyhow::anyhow!("anchor certificate not found"))?;
         
         let round = anchor_cert.header.round;
+        let previous_committed_round = self.state.last_committed_round;
 
-        // Mark anchor as committed
-        self.state.commit(anchor);
-        self.state.last_committed_round = round;
-
-        // Collect all certificates that are now committed (causal history)
-        let mut newly_committed = vec![anchor];
+        // Collect the anchor's causal history that isn't committed yet
+        let mut history = BTreeSet::new();
         let mut to_process = vec![anchor];
-        let mut visited = std::collections::HashSet::new();
-
         while let Some(current) = to_process.pop() {
-            if visited.contains(&current) {
+            let Some(cert) = dag.get(&current) else {
+                continue;
+            };
+            if self.state.is_committed(&current) || !history.insert(current) {
                 continue;
             }
-            visited.insert(current);
-
-            if let Some(cert) = dag.get(&current) {
-                for parent in &cert.header.parents {
-                    if !self.state.is_committed(parent) && !visited.contains(parent) {
-                        self.state.commit(*parent);
-                        newly_committed.push(*parent);
-                        to_process.push(*parent);
-                    }
-                }
-            }
+            to_process.extend(cert.header.parents.iter().copied());
         }
 
+        let newly_committed = canonical::order_certificates(&dag, &history)?;
+        for digest in &newly_committed {
+            self.state.commit(*digest);
+        }
+        self.state.last_committed_round = self.state.last_committed_round.max(round);
+
         log::info!("committed {} certificates (anchor round {})", newly_committed.len(), round);
 
+        // Score leaders on whether their certificates made it into the anchor's parents
+        if self.config.leader_reputation && round > 0 {
+            let included: Vec<PublicKey> = anchor_cert.header.parents
+                .iter()
+                .filter_map(|parent| dag.get(parent))
+                .map(|parent| parent.header.author)
+                .collect();
+            self.reputation.record_inclusion(round - 1, &included, anchor_cert.header.timestamp);
+
+            // Rescore when the committed rounds cross a boundary, so every
+            // validator rescores at the same point of the same history
+            if round / REPUTATION_UPDATE_ROUNDS > previous_committed_round / REPUTATION_UPDATE_ROUNDS {
+                self.reputation.update_scores();
+            }
+        }
+
         // Persist committed certificates to datastore
         #[cfg(feature = "persistence")]
         if let Some(datastore) = &self.datastore {
-            use modal_datastore::models::DAGCertificate;
-            use crate::persistence::digest_to_hex;
-            
-            for digest in &newly_committed {
-                let digest_hex = digest_to_hex(digest);
-                let cert_round = dag.get(digest).map(|c| c.header.round).unwrap_or(0);
-                
-                // Load certificate model and mark as committed
-                let keys = [
-                    ("round".to_string(), cert_round.to_string()),
-                    ("digest".to_string(), digest_hex),
-                ].into_iter().collect();
-                
-                if let Ok(Some(mut cert_model)) = DAGCertificate::find_one_multi(datastore, keys).await {
-                    if cert_model.mark_committed_multi(datastore, round).await.is_err() {
-                        log::warn!("failed to mark certificate {:?} as committed", digest);
-                    }
-                }
-            }
+            crate::persistence::mark_committed_multi(datastore, &dag, &newly_committed, round).await;
         }
 
         Ok(newly_committed)
     }
 
-    /// Record certificate performance for reputation
-    fn record_certificate_performance(&mut self, cert: &Certificate) {
-        // TODO: Calculate actual latency from round start
-        // For now, assume fast certificates (this would be replaced with real timing)
-        let latency_ms = 500; // Placeholder
-
-        self.reputation.record_performance(PerformanceRecord {
-            validator: cert.header.author,
-            round: cert.header.round,
-            latency_ms,
-            success: true,
-            timestamp: cert.header.timestamp,
-        });
-    }
-
     /// Advance to the next round
     pub fn advance_round(&mut self) {
         self.state.advance_round();
-        // Update reputation scores periodically
-        if self.state.current_round.is_multiple_of(10) {
-            self.reputation.update_scores();
-        }
     }
 
     /// Get the current consensus round
@@ -308,7 +338,7 @@ mod tests {
             header: Header {
                 author,
                 round,
-                batch_digest: [0u8; 32],
+                payload: vec![([0u8; 32], 0)],
                 parents,
                 timestamp: 1000 + round * 1000,
             },
@@ -319,18 +349,29 @@ mod tests {
         }
     }
 
+    fn make_consensus(committee: Committee) -> ShoalConsensus {
+        let dag = Arc::new(RwLock::new(DAG::new()));
+        let reputation = ReputationManager::new(committee.clone(), ReputationConfig::default());
+        ShoalConsensus::new(dag, reputation, committee)
+    }
+
     #[tokio::test]
     async fn test_shoal_consensus_process_genesis() {
         let committee = make_test_committee();
-        let dag = Arc::new(RwLock::new(DAG::new()));
-        let reputation = ReputationManager::new(committee.clone(), ReputationConfig::default());
-        let mut consensus = ShoalConsensus::new(dag, reputation, committee);
+        let order = committee.validator_order.clone();
+        let mut consensus = make_consensus(committee);
 
-        let cert = make_test_cert(vec![1], 0, vec![]);
-        let committed = consensus.process_certificate(cert).await.unwrap();
+        let genesis: Vec<Certificate> = order.iter().map(|author| make_test_cert(*author, 0, vec![])).collect();
+        for cert in &genesis {
+            assert!(consensus.process_certificate(cert.clone()).await.unwrap().is_empty());
+        }
 
-        // Genesis should commit immediately
-        assert!(!committed.is_empty());
+        // Genesis commits once f+1 validators vote for the leader's certificate
+        let parents: Vec<CertificateDigest> = genesis.iter().map(Certificate::digest).collect();
+        let first = consensus.process_certificate(make_test_cert(order[0], 1, parents.clone())).await.unwrap();
+        assert!(first.is_empty());
+        let second = consensus.process_certificate(make_test_cert(order[1], 1, parents)).await.unwrap();
+        assert_eq!(second, vec![genesis[0].digest()]);
         assert_eq!(consensus.last_committed_round(), 0);
     }
 
@@ -350,19 +391,136 @@ mod tests {
     #[tokio::test]
     async fn test_shoal_consensus_anchor_selection() {
         let committee = make_test_committee();
-        let dag = Arc::new(RwLock::new(DAG::new()));
-        let reputation = ReputationManager::new(committee.clone(), ReputationConfig::default());
-        let mut consensus = ShoalConsensus::new(dag.clone(), reputation, committee);
+        let order = committee.validator_order.clone();
+        let mut consensus = make_consensus(committee);
 
         // Add genesis certificates and process them
-        for i in 1..=4 {
-            let cert = make_test_cert(vec![i], 0, vec![]);
-            consensus.process_certificate(cert).await.unwrap();
+        let genesis: Vec<Certificate> = order.iter().map(|author| make_test_cert(*author, 0, vec![])).collect();
+        for cert in &genesis {
+            consensus.process_certificate(cert.clone()).await.unwrap();
         }
+        assert_eq!(consensus.state.get_anchor(0), Some(&genesis[0].digest()));
 
-        // We should have an anchor now for round 0
-        let anchor = consensus.state.get_anchor(0);
-        assert!(anchor.is_some(), "anchor should be selected for round 0");
+        // Odd rounds have no anchors
+        let parents: Vec<CertificateDigest> = genesis.iter().map(Certificate::digest).collect();
+        for author in &order {
+            consensus.process_certificate(make_test_cert(*author, 1, parents.clone())).await.unwrap();
+        }
+        assert!(consensus.state.get_anchor(1).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_shoal_consensus_pipelined_leaders() {
+        let committee = make_test_committee();
+        let order = committee.validator_order.clone();
+        let mut consensus = make_consensus(committee).with_config(ShoalConfig {
+            leader_reputation: false,
+            leaders_per_round: 2,
+        });
+
+        // Leaders rotate round-robin every other round without reputation
+        assert_eq!(consensus.leaders(0), vec![order[0], order[1]]);
+        assert_eq!(consensus.leaders(6), vec![order[3], order[0]]);
+
+        let mut rounds: Vec<Vec<Certificate>> = Vec::new();
+        let mut committed = Vec::new();
+        for round in 0..4 {
+            let parents: Vec<CertificateDigest> =
+                rounds.last().map(|certs| certs.iter().map(Certificate::digest).collect()).unwrap_or_default();
+            let certs: Vec<Certificate> = order.iter().map(|author| make_test_cert(*author, round, parents.clone())).collect();
+            for cert in &certs {
+                committed.push(consensus.process_certificate(cert.clone()).await.unwrap());
+            }
+            rounds.push(certs);
+        }
+
+        // Both leaders' certificates of round 0 are anchors
+        assert_eq!(consensus.state.round_anchors(0), vec![rounds[0][0].digest(), rounds[0][1].digest()]);
+
+        // The first leader's commits on its votes; the second leader's commits
+        // ahead of the next anchor that reaches it
+        let first: Vec<CertificateDigest> = committed[4..8].concat();
+        assert_eq!(first, vec![rounds[0][0].digest()]);
+        let next: Vec<CertificateDigest> = committed[12..].concat();
+        assert_eq!(next.first(), Some(&rounds[0][1].digest()));
+        assert_eq!(next.last(), Some(&rounds[2][1].digest()));
+    }
+
+    #[tokio::test]
+    async fn test_shoal_consensus_commit_order_is_independent_of_arrival() {
+        let committee = make_test_committee();
+        let order = committee.validator_order.clone();
+
+        // Round 2's leader gets a single vote, so it's committed through round 4's anchor
+        let mut rounds: Vec<Vec<Certificate>> = vec![order.iter().map(|author| make_test_cert(*author, 0, vec![])).collect()];
+        for round in 1..6 {
+            let previous = &rounds[rounds.len() - 1];
+            let certs: Vec<Certificate> = order
+                .iter()
+                .enumerate()
+                .map(|(i, author)| {
+                    let parents = previous
+                        .iter()
+                        .filter(|parent| round != 3 || i == 0 || parent.header.author != order[1])
+                        .map(Certificate::digest)
+                        .collect();
+                    make_test_cert(*author, round, parents)
+                })
+                .collect();
+            rounds.push(certs);
+        }
+        let leader_2 = rounds[2][1].digest();
+        let leader_4 = rounds[4][2].digest();
+
+        let mut in_order = make_consensus(committee.clone());
+        let mut reversed = make_consensus(committee);
+        let mut in_order_commits = Vec::new();
+        let mut reversed_commits = Vec::new();
+        for certs in &rounds {
+            for cert in certs {
+                in_order_commits.extend(in_order.process_certificate(cert.clone()).await.unwrap());
+            }
+            for cert in certs.iter().rev() {
+                reversed_commits.extend(reversed.process_certificate(cert.clone()).await.unwrap());
+            }
+        }
+
+        assert_eq!(in_order_commits, reversed_commits);
+        let position = |digest| in_order_commits.iter().position(|committed| *committed == digest).unwrap();
+        assert!(position(leader_2) < position(leader_4));
+        assert_eq!(in_order.last_committed_round(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_shoal_consensus_rescores_at_committed_boundaries() {
+        let committee = make_test_committee();
+        let order = committee.validator_order.clone();
+        let mut consensus = make_consensus(committee).with_config(ShoalConfig {
+            leader_reputation: true,
+            leaders_per_round: 1,
+        });
+
+        // The last validator's certificates are never included as parents, so
+        // its anchors go uncommitted
+        let mut parents = Vec::new();
+        let mut round = 0;
+        while consensus.last_committed_round() < REPUTATION_UPDATE_ROUNDS {
+            assert!(round < 4 * REPUTATION_UPDATE_ROUNDS, "anchors stopped committing");
+            let certs: Vec<Certificate> = order.iter().map(|author| make_test_cert(*author, round, parents.clone())).collect();
+            parents = certs[..3].iter().map(Certificate::digest).collect();
+            for cert in certs {
+                consensus.process_certificate(cert).await.unwrap();
+            }
+
+            // Local rounds don't move the scores, only committed ones do
+            consensus.advance_round();
+            if consensus.last_committed_round() < REPUTATION_UPDATE_ROUNDS {
+                assert_eq!(consensus.reputation.get_score(&order[3]), 1.0);
+            }
+            round += 1;
+        }
+        assert!(consensus.last_committed_round() >= REPUTATION_UPDATE_ROUNDS);
+        assert!(consensus.reputation.get_score(&order[3]) < 1.0);
     }
 }
 
@@ -2,6 +2,7 @@ pub mod types;
 pub mod reputation;
 pub mod consensus;
 pub mod ordering;
+pub mod canonical;
 
-pub use types::{ConsensusState, PerformanceRecord, ReputationConfig, ReputationState};
+pub use types::{ConsensusState, PerformanceRecord, ReputationConfig, ReputationState, ShoalConfig};
 
@@ -1,7 +1,8 @@
 use crate::narwhal::{CertificateDigest, Transaction};
 use crate::narwhal::dag::DAG;
+use crate::shoal::canonical::{self, BatchRef};
 use anyhow::Result;
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::BTreeSet;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -33,111 +34,32 @@ impl OrderingEngine {
                 // Note: In a real implementation, we would fetch the actual batch
                 // For now, we log that we would process this batch
                 log::debug!(
-                    "would extract transactions from batch {:?} in cert {}",
-                    cert.header.batch_digest,
+                    "would extract transactions from {} batches in cert {}",
+                    cert.header.payload.len(),
                     hex::encode(cert_digest)
                 );
                 
                 // Placeholder: actual batch fetching would go here
-                // transactions.extend(fetch_batch(cert.header.batch_digest).transactions);
+                // transactions.extend(workers.get_batch(digest, worker_id).transactions);
             }
         }
 
         Ok(transactions)
     }
 
-    /// Perform topological sort on committed certificates
+    /// The batches of the committed certificates in canonical order
+    pub async fn order_batches(&self, committed: &BTreeSet<CertificateDigest>) -> Result<Vec<BatchRef>> {
+        let dag = self.dag.read().await;
+        canonical::order_batches(&dag, committed)
+    }
+
+    /// Order committed certificates canonically (parents first, ties by round and digest)
     fn topological_sort(
         &self,
         dag: &DAG,
         committed: &BTreeSet<CertificateDigest>,
     ) -> Result<Vec<CertificateDigest>> {
-        // Kahn's algorithm with deterministic tie-breaking
-        
-        // Build in-degree map
-        let mut in_degree: HashMap<CertificateDigest, usize> = HashMap::new();
-        let mut adjacency: HashMap<CertificateDigest, Vec<CertificateDigest>> = HashMap::new();
-
-        for &cert_digest in committed {
-            in_degree.entry(cert_digest).or_insert(0);
-            
-            if let Some(cert) = dag.get(&cert_digest) {
-                // Add edges from parents to this certificate
-                for parent_digest in &cert.header.parents {
-                    if committed.contains(parent_digest) {
-                        adjacency
-                            .entry(*parent_digest)
-                            .or_default()
-                            .push(cert_digest);
-                        *in_degree.entry(cert_digest).or_insert(0) += 1;
-                    }
-                }
-            }
-        }
-
-        // Find all nodes with in-degree 0
-        let mut queue: Vec<CertificateDigest> = in_degree
-            .iter()
-            .filter(|(_, &degree)| degree == 0)
-            .map(|(&digest, _)| digest)
-            .collect();
-
-        // Sort queue for deterministic ordering
-        self.sort_by_round_and_author(dag, &mut queue);
-
-        let mut result = Vec::new();
-        let mut visited = HashSet::new();
-
-        while let Some(current) = queue.pop() {
-            if visited.contains(&current) {
-                continue;
-            }
-            visited.insert(current);
-            result.push(current);
-
-            // Process neighbors
-            if let Some(neighbors) = adjacency.get(&current) {
-                for &neighbor in neighbors {
-                    if let Some(degree) = in_degree.get_mut(&neighbor) {
-                        *degree -= 1;
-                        if *degree == 0 {
-                            queue.push(neighbor);
-                        }
-                    }
-                }
-            }
-
-            // Re-sort queue for deterministic tie-breaking
-            self.sort_by_round_and_author(dag, &mut queue);
-        }
-
-        // Check if all nodes were processed (no cycles)
-        if result.len() != committed.len() {
-            anyhow::bail!("cycle detected in DAG or missing certificates");
-        }
-
-        Ok(result)
-    }
-
-    /// Sort certificates deterministically by (round, author)
-    fn sort_by_round_and_author(&self, dag: &DAG, certs: &mut [CertificateDigest]) {
-        certs.sort_by(|a, b| {
-            let cert_a = dag.get(a);
-            let cert_b = dag.get(b);
-
-            match (cert_a, cert_b) {
-                (Some(ca), Some(cb)) => {
-                    // Sort by round first (lower first)
-                    ca.header.round
-                        .cmp(&cb.header.round)
-                        // Then by author (lexicographic)
-                        .then_with(|| ca.header.author.cmp(&cb.header.author))
-                }
-                (Some(_), None) => std::cmp::Ordering::Less,
-                (None, Some(_)) => std::cmp::Ordering::Greater,
-                (None, None) => std::cmp::Ordering::Equal,
-            }
-        });
+        canonical::order_certificates(dag, committed)
     }
 }
 
@@ -155,7 +77,7 @@ mod tests {
             header: Header {
                 author,
                 round,
-                batch_digest: [0u8; 32],
+                payload: vec![([0u8; 32], 0)],
                 parents,
                 timestamp: 1000 + round * 1000,
             },
@@ -1,6 +1,10 @@
 use crate::narwhal::{Committee, PublicKey};
 use crate::shoal::{PerformanceRecord, ReputationConfig, ReputationState};
 
+/// Committed rounds between reputation updates. Scores only change when the
+/// committed rounds cross a multiple of this.
+pub const REPUTATION_UPDATE_ROUNDS: u64 = 10;
+
 /// Manager for leader reputation and selection
 #[derive(Clone)]
 pub struct ReputationManager {
@@ -25,8 +29,21 @@ impl ReputationManager {
         &self.state
     }
 
+    /// Replace the reputation state, e.g. with one recovered after a restart
+    pub fn restore_state(&mut self, state: ReputationState) {
+        self.state = state;
+    }
+
     /// Select the leader for a given round based on reputation
     pub fn select_leader(&self, round: u64) -> PublicKey {
+        self.select_leaders(round, 1)
+            .first()
+            .copied()
+            .unwrap_or_else(|| self.committee.validator_order[0])
+    }
+
+    /// Select up to `count` leaders for a round, best reputation first
+    pub fn select_leaders(&self, round: u64, count: usize) -> Vec<PublicKey> {
         // Get all validators sorted by reputation score (descending)
         let mut validators: Vec<(PublicKey, f64)> = self.state.scores
             .iter()
@@ -40,11 +57,7 @@ impl ReputationManager {
                 .then_with(|| self.deterministic_tie_break(round, &a.0, &b.0))
         });
 
-        // Return top validator
-        validators
-            .first()
-            .map(|(key, _)| *key)
-            .unwrap_or_else(|| self.committee.validator_order[0])
+        validators.into_iter().take(count).map(|(key, _)| key).collect()
     }
 
     /// Deterministic tie-breaking for leader selection
@@ -70,6 +83,21 @@ impl ReputationManager {
         self.state.record_performance(record);
     }
 
+    /// Record which validators had their certificate from `round` included by
+    /// a committed anchor of the next round. Committed anchors are the same on
+    /// every validator, so every validator arrives at the same scores.
+    pub fn record_inclusion(&mut self, round: u64, included: &[PublicKey], timestamp: u64) {
+        for validator in &self.committee.validator_order {
+            self.state.record_performance(PerformanceRecord {
+                validator: *validator,
+                round,
+                latency_ms: 0,
+                success: included.contains(validator),
+                timestamp,
+            });
+        }
+    }
+
     /// Update all reputation scores based on recent performance
     pub fn update_scores(&mut self) {
         self.state.update_scores();
@@ -224,6 +252,45 @@ mod tests {
         assert_ne!(fallback.unwrap(), primary_leader);
     }
 
+    #[test]
+    fn test_reputation_manager_select_leaders() {
+        let committee = make_test_committee();
+        let mut manager = ReputationManager::new(committee, ReputationConfig::default());
+
+        let leaders = manager.select_leaders(3, 2);
+        assert_eq!(leaders.len(), 2);
+        assert_eq!(leaders[0], manager.select_leader(3));
+        assert_ne!(leaders[0], leaders[1]);
+
+        // A validator that keeps missing drops out of the leaders
+        for round in 0..5 {
+            manager.record_performance(PerformanceRecord {
+                validator: leaders[0],
+                round,
+                latency_ms: 0,
+                success: false,
+                timestamp: 1000,
+            });
+        }
+        manager.update_scores();
+        assert!(!manager.select_leaders(3, 2).contains(&leaders[0]));
+        assert_eq!(manager.select_leaders(3, 10).len(), 4);
+    }
+
+    #[test]
+    fn test_reputation_manager_record_inclusion() {
+        let committee = make_test_committee();
+        let mut manager = ReputationManager::new(committee, ReputationConfig::default());
+
+        // Validator 4's certificate missed the anchor
+        manager.record_inclusion(0, &[test_peer_id(1), test_peer_id(2), test_peer_id(3)], 1000);
+        manager.update_scores();
+
+        assert_eq!(manager.get_score(&test_peer_id(1)), 1.0);
+        assert!(manager.get_score(&test_peer_id(4)) < 1.0);
+        assert!(!manager.select_leaders(1, 3).contains(&test_peer_id(4)));
+    }
+
     #[test]
     fn test_reputation_manager_deterministic_tie_break() {
         let committee = make_test_committee();
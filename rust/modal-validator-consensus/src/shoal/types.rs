@@ -42,6 +42,29 @@ impl Default for ReputationConfig {
     }
 }
 
+/// Configuration for Shoal leader selection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShoalConfig {
+    /// Rank leaders by reputation, scored on whether their certificates were
+    /// included in time by committed anchors. Leaders rotate round-robin
+    /// otherwise. Off by default: scores change as a validator commits, so
+    /// validators whose committed rounds lag each other can rank a round's
+    /// leaders differently.
+    pub leader_reputation: bool,
+    /// Leaders per round. Each leader's certificate is an anchor, so more of
+    /// the DAG is committed directly instead of through a later round's anchor.
+    pub leaders_per_round: usize,
+}
+
+impl Default for ShoalConfig {
+    fn default() -> Self {
+        Self {
+            leader_reputation: false,
+            leaders_per_round: 1,
+        }
+    }
+}
+
 /// Reputation state tracking validator performance
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReputationState {
@@ -162,6 +185,9 @@ pub struct ConsensusState {
     pub current_round: u64,
     /// Anchors selected for each round (round -> certificate digest)
     pub anchors: BTreeMap<u64, CertificateDigest>,
+    /// Anchors of the further leaders of each round when leaders are pipelined
+    #[serde(default)]
+    pub pipelined_anchors: BTreeMap<u64, Vec<CertificateDigest>>,
     /// Set of committed certificate digests
     pub committed: BTreeSet<CertificateDigest>,
     /// Last round that was committed
@@ -174,6 +200,7 @@ impl ConsensusState {
         Self {
             current_round: 0,
             anchors: BTreeMap::new(),
+            pipelined_anchors: BTreeMap::new(),
             committed: BTreeSet::new(),
             last_committed_round: 0,
         }
@@ -189,6 +216,24 @@ impl ConsensusState {
         self.anchors.insert(round, anchor);
     }
 
+    /// Add the anchor of a further leader of a round
+    pub fn add_pipelined_anchor(&mut self, round: u64, anchor: CertificateDigest) {
+        let anchors = self.pipelined_anchors.entry(round).or_default();
+        if !anchors.contains(&anchor) {
+            anchors.push(anchor);
+        }
+    }
+
+    /// All anchors of a round, the first leader's first
+    pub fn round_anchors(&self, round: u64) -> Vec<CertificateDigest> {
+        self.anchors
+            .get(&round)
+            .into_iter()
+            .chain(self.pipelined_anchors.get(&round).into_iter().flatten())
+            .copied()
+            .collect()
+    }
+
     /// Check if a certificate is committed
     pub fn is_committed(&self, digest: &CertificateDigest) -> bool {
         self.committed.contains(digest)
@@ -296,6 +341,10 @@ mod tests {
         state.set_anchor(1, anchor);
         assert_eq!(state.get_anchor(1), Some(&anchor));
         assert_eq!(state.get_anchor(2), None);
+        
+        state.add_pipelined_anchor(1, [2u8; 32]);
+        state.add_pipelined_anchor(1, [2u8; 32]);
+        assert_eq!(state.round_anchors(1), vec![anchor, [2u8; 32]]);
     }
 
     #[test]
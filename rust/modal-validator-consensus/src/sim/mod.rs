@@ -0,0 +1,298 @@
+//! Deterministic simulation of a validator committee
+//!
+//! Runs N in-process validators over an in-memory transport in simulated
+//! time. The network delays messages by a random latency, drops a share of
+//! them and can be partitioned for a while; validators can crash and restart.
+//! Validators propose headers, vote, form certificates and run Shoal on their
+//! own DAG, retransmitting and syncing missing certificates on a timer. The
+//! run is driven by a seeded RNG, so a failing scenario replays exactly.
+//!
+//! The report checks safety (validators commit the same certificates in the
+//! same order) and liveness (validators that are up keep committing).
+
+pub mod network;
+pub mod node;
+
+pub use network::{Latency, Partition, SimNetwork};
+pub use node::{Recipient, SimMessage, SimNode};
+
+use crate::narwhal::{CertificateDigest, Committee, Validator};
+use crate::shoal::ShoalConfig;
+use anyhow::{bail, Result};
+use libp2p_identity::{ed25519, PeerId};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+
+/// Simulation settings
+#[derive(Debug, Clone)]
+pub struct SimConfig {
+    pub validators: usize,
+    /// Seed for latencies, drops and peer choices
+    pub seed: u64,
+    /// Rounds each validator proposes in
+    pub rounds: u64,
+    pub latency: Latency,
+    /// Share of messages lost, from 0.0 to 1.0
+    pub drop_rate: f64,
+    /// How often validators retransmit and request missing certificates
+    pub retry_interval_ms: u64,
+    /// Simulated time after which the run stops
+    pub max_time_ms: u64,
+    pub shoal: ShoalConfig,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            validators: 4,
+            seed: 0,
+            rounds: 20,
+            latency: Latency::default(),
+            drop_rate: 0.0,
+            retry_interval_ms: 200,
+            max_time_ms: 60_000,
+            shoal: ShoalConfig::default(),
+        }
+    }
+}
+
+/// A fault injected into the run
+#[derive(Debug, Clone)]
+pub enum Fault {
+    Partition(Partition),
+    /// Crash a validator, restarting it later if `restart_ms` is set
+    Crash {
+        node: usize,
+        at_ms: u64,
+        restart_ms: Option<u64>,
+    },
+}
+
+enum Event {
+    Deliver { from: usize, to: usize, message: SimMessage },
+    Tick { node: usize },
+    Crash { node: usize },
+    Restart { node: usize },
+}
+
+/// What happened in a run
+#[derive(Debug, Clone)]
+pub struct SimReport {
+    /// Simulated time the run took
+    pub elapsed_ms: u64,
+    /// Each validator's committed certificates, in commit order
+    pub commits: Vec<Vec<CertificateDigest>>,
+    /// Round each validator reached
+    pub rounds: Vec<u64>,
+    /// Whether each validator was up at the end
+    pub up: Vec<bool>,
+    pub messages_sent: u64,
+    pub messages_dropped: u64,
+}
+
+impl SimReport {
+    /// Validators must agree on the commit order: every validator's commits
+    /// are a prefix of the longest sequence
+    pub fn check_safety(&self) -> Result<()> {
+        let Some(longest) = self.commits.iter().max_by_key(|commits| commits.len()) else {
+            return Ok(());
+        };
+        for (node, commits) in self.commits.iter().enumerate() {
+            if let Some(position) = commits.iter().zip(longest).position(|(a, b)| a != b) {
+                bail!(
+                    "validator {} diverges at commit {}: {} instead of {}",
+                    node,
+                    position,
+                    hex::encode(commits[position]),
+                    hex::encode(longest[position])
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Every validator that is up has committed at least `min_commits` certificates
+    pub fn check_liveness(&self, min_commits: usize) -> Result<()> {
+        for (node, commits) in self.commits.iter().enumerate() {
+            if self.up[node] && commits.len() < min_commits {
+                bail!(
+                    "validator {} committed {} certificates, expected at least {} (reached round {})",
+                    node,
+                    commits.len(),
+                    min_commits,
+                    self.rounds[node]
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A committee of simulated validators and the network between them
+pub struct Simulation {
+    config: SimConfig,
+    faults: Vec<Fault>,
+}
+
+impl Simulation {
+    pub fn new(config: SimConfig) -> Self {
+        Self {
+            config,
+            faults: Vec::new(),
+        }
+    }
+
+    pub fn with_fault(mut self, fault: Fault) -> Self {
+        self.faults.push(fault);
+        self
+    }
+
+    /// Committee of the simulated validators, with deterministic keys
+    pub fn committee(validators: usize) -> Committee {
+        Committee::new(
+            (0..validators)
+                .map(|i| Validator {
+                    public_key: sim_peer_id(i),
+                    stake: 1,
+                    network_address: SocketAddr::from(([127, 0, 0, 1], 9000 + i as u16)),
+                })
+                .collect(),
+        )
+    }
+
+    /// Run until every validator that is up has moved past the last round,
+    /// or the time limit is reached
+    pub async fn run(self) -> Result<SimReport> {
+        let config = self.config;
+        let committee = Self::committee(config.validators);
+        let mut nodes: Vec<SimNode> = (0..config.validators)
+            .map(|i| SimNode::new(i, committee.clone(), config.shoal.clone(), config.rounds))
+            .collect();
+        let mut network = SimNetwork::new(config.seed, config.latency, config.drop_rate);
+        let mut queue = EventQueue::default();
+
+        for fault in self.faults {
+            match fault {
+                Fault::Partition(partition) => network.add_partition(partition),
+                Fault::Crash { node, at_ms, restart_ms } => {
+                    queue.push(at_ms, Event::Crash { node });
+                    if let Some(restart_ms) = restart_ms {
+                        queue.push(restart_ms, Event::Restart { node });
+                    }
+                }
+            }
+        }
+        for (i, node) in nodes.iter_mut().enumerate() {
+            let outbox = node.start(0).await;
+            send(&mut queue, &mut network, i, outbox, 0, config.validators);
+            queue.push(config.retry_interval_ms, Event::Tick { node: i });
+        }
+
+        let mut now = 0;
+        while let Some((time, event)) = queue.pop() {
+            if time > config.max_time_ms {
+                break;
+            }
+            now = time;
+            let (node, outbox) = match event {
+                Event::Deliver { from, to, message } => {
+                    if !nodes[to].up {
+                        continue;
+                    }
+                    (to, nodes[to].handle(from, message, now).await?)
+                }
+                Event::Tick { node } => {
+                    let done = nodes.iter().all(|n| !n.up || n.round() >= config.rounds);
+                    if done && queue.pending_restarts() == 0 {
+                        break;
+                    }
+                    queue.push(now + config.retry_interval_ms, Event::Tick { node });
+                    if !nodes[node].up {
+                        continue;
+                    }
+                    (node, nodes[node].tick(now).await)
+                }
+                Event::Crash { node } => {
+                    log::debug!("sim: validator {} crashed at {}ms", node, now);
+                    nodes[node].crash();
+                    continue;
+                }
+                Event::Restart { node } => {
+                    log::debug!("sim: validator {} restarted at {}ms", node, now);
+                    (node, nodes[node].restart(now).await)
+                }
+            };
+            send(&mut queue, &mut network, node, outbox, now, config.validators);
+        }
+
+        Ok(SimReport {
+            elapsed_ms: now,
+            commits: nodes.iter().map(|node| node.committed.clone()).collect(),
+            rounds: nodes.iter().map(|node| node.round()).collect(),
+            up: nodes.iter().map(|node| node.up).collect(),
+            messages_sent: network.sent,
+            messages_dropped: network.dropped,
+        })
+    }
+}
+
+/// Events ordered by time, then by when they were scheduled
+#[derive(Default)]
+struct EventQueue {
+    events: BTreeMap<(u64, u64), Event>,
+    next_seq: u64,
+    restarts: usize,
+}
+
+impl EventQueue {
+    fn push(&mut self, time: u64, event: Event) {
+        if matches!(event, Event::Restart { .. }) {
+            self.restarts += 1;
+        }
+        self.events.insert((time, self.next_seq), event);
+        self.next_seq += 1;
+    }
+
+    fn pop(&mut self) -> Option<(u64, Event)> {
+        let ((time, _), event) = self.events.pop_first()?;
+        if matches!(event, Event::Restart { .. }) {
+            self.restarts -= 1;
+        }
+        Some((time, event))
+    }
+
+    /// Crashed validators still to be restarted
+    fn pending_restarts(&self) -> usize {
+        self.restarts
+    }
+}
+
+fn send(queue: &mut EventQueue, network: &mut SimNetwork, from: usize, outbox: node::Outbox, now: u64, size: usize) {
+    for (recipient, message) in outbox {
+        let recipients: Vec<usize> = match recipient {
+            Recipient::All => (0..size).filter(|to| *to != from).collect(),
+            Recipient::Node(to) => vec![to],
+            Recipient::AnyPeer => vec![network.pick_peer(from, size)],
+        };
+        for to in recipients {
+            if let Some(delay) = network.route(from, to, now) {
+                queue.push(
+                    now + delay,
+                    Event::Deliver {
+                        from,
+                        to,
+                        message: message.clone(),
+                    },
+                );
+            }
+        }
+    }
+}
+
+fn sim_peer_id(index: usize) -> PeerId {
+    let mut secret_bytes = [0u8; 32];
+    secret_bytes[..8].copy_from_slice(&(index as u64 + 1).to_le_bytes());
+    let secret = ed25519::SecretKey::try_from_bytes(secret_bytes).expect("valid secret key");
+    let keypair = ed25519::Keypair::from(secret);
+    PeerId::from_public_key(&keypair.public().into())
+}
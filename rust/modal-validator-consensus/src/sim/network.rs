@@ -0,0 +1,88 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Range message delivery delays are drawn from, uniformly
+#[derive(Debug, Clone, Copy)]
+pub struct Latency {
+    pub min_ms: u64,
+    pub max_ms: u64,
+}
+
+impl Default for Latency {
+    fn default() -> Self {
+        Self { min_ms: 10, max_ms: 50 }
+    }
+}
+
+/// Validators split into groups that can't reach each other for a while.
+/// A validator in none of the groups is cut off from everyone.
+#[derive(Debug, Clone)]
+pub struct Partition {
+    pub groups: Vec<Vec<usize>>,
+    pub from_ms: u64,
+    pub until_ms: u64,
+}
+
+impl Partition {
+    /// Whether the partition separates `a` from `b` at time `now`
+    pub fn separates(&self, a: usize, b: usize, now: u64) -> bool {
+        if now < self.from_ms || now >= self.until_ms {
+            return false;
+        }
+        let group_of = |node: usize| self.groups.iter().position(|group| group.contains(&node));
+        match (group_of(a), group_of(b)) {
+            (Some(x), Some(y)) => x != y,
+            _ => true,
+        }
+    }
+}
+
+/// In-memory transport: decides whether and when each message arrives
+pub struct SimNetwork {
+    rng: StdRng,
+    latency: Latency,
+    drop_rate: f64,
+    partitions: Vec<Partition>,
+    /// Messages handed to the network
+    pub sent: u64,
+    /// Messages lost to drops or partitions
+    pub dropped: u64,
+}
+
+impl SimNetwork {
+    pub fn new(seed: u64, latency: Latency, drop_rate: f64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            latency,
+            drop_rate,
+            partitions: Vec::new(),
+            sent: 0,
+            dropped: 0,
+        }
+    }
+
+    pub fn add_partition(&mut self, partition: Partition) {
+        self.partitions.push(partition);
+    }
+
+    /// Delay of a message sent from `from` to `to` at `now`, or None if it's lost
+    pub fn route(&mut self, from: usize, to: usize, now: u64) -> Option<u64> {
+        self.sent += 1;
+        let partitioned = self.partitions.iter().any(|p| p.separates(from, to, now));
+        if partitioned || (self.drop_rate > 0.0 && self.rng.gen_bool(self.drop_rate)) {
+            self.dropped += 1;
+            return None;
+        }
+        Some(self.rng.gen_range(self.latency.min_ms..=self.latency.max_ms.max(self.latency.min_ms)))
+    }
+
+    /// A random validator other than `node`, out of `size`
+    pub fn pick_peer(&mut self, node: usize, size: usize) -> usize {
+        let peer = self.rng.gen_range(0..size - 1);
+        if peer >= node {
+            peer + 1
+        } else {
+            peer
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use crate::narwhal::certificate::CertificateBuilder;
+use crate::narwhal::dag::DAG;
+use crate::narwhal::{Certificate, CertificateDigest, Committee, Digest, Header, PublicKey, SyncRequest, SyncResponse, Vote};
+use crate::shoal::consensus::ShoalConsensus;
+use crate::shoal::reputation::ReputationManager;
+use crate::shoal::{ReputationConfig, ShoalConfig};
+use anyhow::Result;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Messages validators exchange in the simulation
+#[derive(Debug, Clone)]
+pub enum SimMessage {
+    Header(Header),
+    Vote(Vote),
+    Certificate(Certificate),
+    SyncRequest(SyncRequest),
+    SyncResponse(SyncResponse),
+}
+
+/// Who a message goes to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recipient {
+    All,
+    Node(usize),
+    /// A random other validator
+    AnyPeer,
+}
+
+pub type Outbox = Vec<(Recipient, SimMessage)>;
+
+/// An in-process validator: proposes headers, votes, forms certificates and
+/// runs Shoal on its own DAG
+pub struct SimNode {
+    pub index: usize,
+    pub key: PublicKey,
+    committee: Committee,
+    dag: Arc<RwLock<DAG>>,
+    consensus: ShoalConsensus,
+    /// Rounds after which the validator stops proposing
+    rounds: u64,
+    /// Round the validator is proposing in
+    round: u64,
+    /// Own header collecting votes
+    pending: Option<(Digest, Header, CertificateBuilder)>,
+    /// Header voted for per author and round, so no author gets two votes
+    voted: HashMap<(PublicKey, u64), Digest>,
+    /// Certificates waiting for their parents
+    buffered: BTreeMap<CertificateDigest, Certificate>,
+    /// Committed certificates, in commit order
+    pub committed: Vec<CertificateDigest>,
+    pub up: bool,
+}
+
+impl SimNode {
+    pub fn new(index: usize, committee: Committee, shoal: ShoalConfig, rounds: u64) -> Self {
+        let key = committee.validator_order[index];
+        let dag = Arc::new(RwLock::new(DAG::new()));
+        let reputation = ReputationManager::new(committee.clone(), ReputationConfig::default());
+        let consensus = ShoalConsensus::new(dag.clone(), reputation, committee.clone()).with_config(shoal);
+        Self {
+            index,
+            key,
+            committee,
+            dag,
+            consensus,
+            rounds,
+            round: 0,
+            pending: None,
+            voted: HashMap::new(),
+            buffered: BTreeMap::new(),
+            committed: Vec::new(),
+            up: true,
+        }
+    }
+
+    /// Round the validator is proposing in
+    pub fn round(&self) -> u64 {
+        self.round
+    }
+
+    /// Propose the first header
+    pub async fn start(&mut self, now: u64) -> Outbox {
+        self.propose(now).await
+    }
+
+    /// Crash: everything not in the DAG or the consensus state is lost
+    pub fn crash(&mut self) {
+        self.up = false;
+        self.pending = None;
+        self.buffered.clear();
+    }
+
+    /// Restart on the recovered DAG and consensus state. The validator proposes
+    /// for the round after its last certificate, since a header for a round it
+    /// already proposed in would look like equivocation.
+    pub async fn restart(&mut self, now: u64) -> Outbox {
+        self.up = true;
+        let last_authored = self.dag.read().await.last_authored_round(&self.key);
+        self.round = last_authored.map(|round| round + 1).unwrap_or(0);
+        let mut outbox = self.try_advance(now).await;
+        if self.pending.is_none() {
+            outbox.extend(self.propose(now).await);
+        }
+        outbox
+    }
+
+    /// Handle a message from validator `from`
+    pub async fn handle(&mut self, from: usize, message: SimMessage, now: u64) -> Result<Outbox> {
+        match message {
+            SimMessage::Header(header) => self.handle_header(from, header).await,
+            SimMessage::Vote(vote) => self.handle_vote(vote, now).await,
+            SimMessage::Certificate(cert) => self.handle_certificate(from, cert, now).await,
+            SimMessage::SyncRequest(request) => {
+                let response = self.dag.read().await.handle_sync_request(request);
+                Ok(vec![(Recipient::Node(from), SimMessage::SyncResponse(response))])
+            }
+            SimMessage::SyncResponse(SyncResponse::Certificates { mut certificates, .. }) => {
+                certificates.sort_by_key(|cert| cert.header.round);
+                let mut outbox = Vec::new();
+                for cert in certificates {
+                    outbox.extend(self.handle_certificate(from, cert, now).await?);
+                }
+                Ok(outbox)
+            }
+            SimMessage::SyncResponse(_) => Ok(vec![]),
+        }
+    }
+
+    /// Retransmit what may have been lost and ask for what's missing
+    pub async fn tick(&mut self, now: u64) -> Outbox {
+        let mut outbox = Vec::new();
+        if let Some((_, header, _)) = &self.pending {
+            outbox.push((Recipient::All, SimMessage::Header(header.clone())));
+        } else if self.round < self.rounds {
+            outbox.extend(self.propose(now).await);
+        }
+
+        let dag = self.dag.read().await;
+        if let Some(round) = self.round.checked_sub(1) {
+            if let Some(cert) = dag.get_author_cert(&self.key, round) {
+                outbox.push((Recipient::All, SimMessage::Certificate(cert.clone())));
+            }
+        }
+        let missing: Vec<CertificateDigest> = self
+            .buffered
+            .values()
+            .flat_map(|cert| dag.get_missing_parents(cert))
+            .filter(|digest| !self.buffered.contains_key(digest))
+            .collect();
+        if !missing.is_empty() {
+            outbox.push((Recipient::AnyPeer, SimMessage::SyncRequest(SyncRequest::certificates(missing))));
+        }
+        if !self.has_quorum(&dag, self.round) {
+            outbox.push((
+                Recipient::AnyPeer,
+                SimMessage::SyncRequest(SyncRequest::certificates_in_round(self.round)),
+            ));
+        }
+        outbox
+    }
+
+    async fn propose(&mut self, now: u64) -> Outbox {
+        if self.round >= self.rounds || self.pending.is_some() {
+            return vec![];
+        }
+        let dag = self.dag.read().await;
+        if dag.get_author_cert(&self.key, self.round).is_some() {
+            return vec![];
+        }
+        let parents: Vec<CertificateDigest> = match self.round.checked_sub(1) {
+            Some(previous) if !self.has_quorum(&dag, previous) => return vec![],
+            Some(previous) => dag.get_round(previous).iter().map(|cert| cert.digest()).collect(),
+            None => vec![],
+        };
+        drop(dag);
+
+        let header = Header {
+            author: self.key,
+            round: self.round,
+            payload: vec![],
+            parents,
+            timestamp: now,
+        };
+        let digest = header.digest();
+        let mut builder = CertificateBuilder::new(header.clone(), self.committee.clone());
+        // Voting for our own header can't fail
+        let _ = builder.add_vote(self.key, vec![]);
+        self.voted.insert((self.key, self.round), digest);
+        self.pending = Some((digest, header.clone(), builder));
+        vec![(Recipient::All, SimMessage::Header(header))]
+    }
+
+    async fn handle_header(&mut self, from: usize, header: Header) -> Result<Outbox> {
+        if !self.committee.contains(&header.author) {
+            return Ok(vec![]);
+        }
+        let digest = header.digest();
+        match self.voted.get(&(header.author, header.round)) {
+            Some(voted) if *voted != digest => return Ok(vec![]),
+            _ => {}
+        }
+
+        // Only vote for headers whose parents we have
+        let missing: Vec<CertificateDigest> = {
+            let dag = self.dag.read().await;
+            header.parents.iter().filter(|parent| dag.get(parent).is_none()).copied().collect()
+        };
+        if !missing.is_empty() {
+            return Ok(vec![(Recipient::Node(from), SimMessage::SyncRequest(SyncRequest::certificates(missing)))]);
+        }
+
+        self.voted.insert((header.author, header.round), digest);
+        let vote = Vote {
+            header_digest: digest,
+            round: header.round,
+            voter: self.key,
+            signature: vec![],
+        };
+        Ok(vec![(Recipient::Node(from), SimMessage::Vote(vote))])
+    }
+
+    async fn handle_vote(&mut self, vote: Vote, now: u64) -> Result<Outbox> {
+        let Some((digest, _, builder)) = &mut self.pending else {
+            return Ok(vec![]);
+        };
+        if vote.header_digest != *digest || builder.add_vote(vote.voter, vote.signature).is_err() || !builder.has_quorum() {
+            return Ok(vec![]);
+        }
+
+        let (_, _, builder) = self.pending.take().expect("pending header");
+        let cert = builder.build()?;
+        let mut outbox = vec![(Recipient::All, SimMessage::Certificate(cert.clone()))];
+        outbox.extend(self.accept(cert, now).await?);
+        Ok(outbox)
+    }
+
+    async fn handle_certificate(&mut self, from: usize, cert: Certificate, now: u64) -> Result<Outbox> {
+        let missing = {
+            let dag = self.dag.read().await;
+            if dag.get(&cert.digest()).is_some() {
+                return Ok(vec![]);
+            }
+            dag.get_missing_parents(&cert)
+        };
+        if !missing.is_empty() {
+            self.buffered.insert(cert.digest(), cert);
+            return Ok(vec![(Recipient::Node(from), SimMessage::SyncRequest(SyncRequest::certificates(missing)))]);
+        }
+        self.accept(cert, now).await
+    }
+
+    /// Run a certificate with all its parents through consensus, then any
+    /// buffered certificates it unblocks, and move to the next round once
+    /// there's a quorum
+    async fn accept(&mut self, cert: Certificate, now: u64) -> Result<Outbox> {
+        let mut ready = vec![cert];
+        while let Some(cert) = ready.pop() {
+            let digest = cert.digest();
+            self.buffered.remove(&digest);
+            if self.dag.read().await.get(&digest).is_some() {
+                continue;
+            }
+            let committed = self.consensus.process_certificate(cert).await?;
+            self.committed.extend(committed);
+
+            let dag = self.dag.read().await;
+            ready.extend(
+                self.buffered
+                    .values()
+                    .filter(|buffered| buffered.header.parents.contains(&digest) && dag.has_all_parents(buffered))
+                    .cloned(),
+            );
+        }
+        Ok(self.try_advance(now).await)
+    }
+
+    async fn try_advance(&mut self, now: u64) -> Outbox {
+        let mut outbox = Vec::new();
+        loop {
+            let complete = self.has_quorum(&*self.dag.read().await, self.round);
+            if !complete {
+                break;
+            }
+            // Our own header lost out if the round completed without it
+            self.pending = None;
+            self.round += 1;
+            self.consensus.advance_round();
+            outbox.extend(self.propose(now).await);
+        }
+        outbox
+    }
+
+    fn has_quorum(&self, dag: &DAG, round: u64) -> bool {
+        let authors: Vec<PublicKey> = dag.get_round(round).iter().map(|cert| cert.header.author).collect();
+        self.committee.check_quorum(&authors)
+    }
+}
@@ -0,0 +1,94 @@
+//! Certificates of a committee that signs with BLS carry one aggregate
+//! signature over the header, verified against the signers' keys.
+#![cfg(feature = "bls")]
+
+use modal_common::bls::{BlsKeypair, SIGNATURE_LEN};
+use modal_validator_consensus::narwhal::certificate::{create_bls_vote, verify_certificate, CertificateBuilder};
+use modal_validator_consensus::narwhal::{Committee, Header, Validator};
+use libp2p_identity::{ed25519, PeerId};
+use std::net::SocketAddr;
+
+fn test_peer_id(seed: u8) -> PeerId {
+    let mut secret_bytes = [0u8; 32];
+    secret_bytes[0] = seed;
+    let secret = ed25519::SecretKey::try_from_bytes(secret_bytes)
+        .expect("valid secret key");
+    let keypair = ed25519::Keypair::from(secret);
+    PeerId::from_public_key(&keypair.public().into())
+}
+
+/// A committee of four where every validator has a BLS key
+fn bls_committee() -> (Committee, Vec<BlsKeypair>) {
+    let validators: Vec<Validator> = (1..=4u8)
+        .map(|seed| Validator {
+            public_key: test_peer_id(seed),
+            stake: 1,
+            network_address: SocketAddr::from(([127, 0, 0, 1], 8000 + seed as u16)),
+        })
+        .collect();
+    let keypairs: Vec<BlsKeypair> = (1..=4u8)
+        .map(|seed| BlsKeypair::from_seed(&[seed; 32]).unwrap())
+        .collect();
+
+    let mut committee = Committee::new(validators);
+    for (seed, keypair) in (1..=4u8).zip(&keypairs) {
+        committee
+            .add_bls_key(&test_peer_id(seed), keypair.public_key_bytes(), &keypair.prove_possession())
+            .unwrap();
+    }
+    (committee, keypairs)
+}
+
+fn header() -> Header {
+    Header {
+        author: test_peer_id(1),
+        round: 0,
+        payload: vec![],
+        parents: vec![],
+        timestamp: 1000,
+    }
+}
+
+#[test]
+fn test_certificate_carries_aggregate_signature() {
+    let (committee, keypairs) = bls_committee();
+    assert!(committee.uses_bls());
+
+    let header = header();
+    let mut builder = CertificateBuilder::new(header.clone(), committee.clone());
+    for (seed, keypair) in (1..=3u8).zip(&keypairs) {
+        let vote = create_bls_vote(&header, test_peer_id(seed), keypair);
+        builder.add_vote(vote.voter, vote.signature).unwrap();
+    }
+    let cert = builder.build().unwrap();
+
+    assert_eq!(cert.aggregated_signature.signature.len(), SIGNATURE_LEN);
+    assert!(verify_certificate(&cert, &committee).is_ok());
+
+    // Claiming the fourth validator signed breaks the aggregate
+    let mut forged = cert.clone();
+    forged.signers = vec![true; 4];
+    assert!(verify_certificate(&forged, &committee).is_err());
+
+    // So does certifying a different header with the same signature
+    let mut forged = cert;
+    forged.header.round = 1;
+    assert!(verify_certificate(&forged, &committee).is_err());
+}
+
+#[test]
+fn test_invalid_votes_and_keys_are_rejected() {
+    let (mut committee, keypairs) = bls_committee();
+    let header = header();
+
+    // A vote signed with another validator's key
+    let mut builder = CertificateBuilder::new(header.clone(), committee.clone());
+    let vote = create_bls_vote(&header, test_peer_id(1), &keypairs[1]);
+    assert!(builder.add_vote(vote.voter, vote.signature).is_err());
+
+    // A key registered without a proof of possession for it
+    let rogue = BlsKeypair::from_seed(&[9u8; 32]).unwrap();
+    assert!(committee
+        .add_bls_key(&test_peer_id(1), rogue.public_key_bytes(), &keypairs[0].prove_possession())
+        .is_err());
+}
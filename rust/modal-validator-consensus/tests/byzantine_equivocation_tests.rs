@@ -205,12 +205,21 @@ async fn test_consensus_continues_after_equivocation() {
     }
     
     // Process honest certificates through consensus
+    let mut parents = vec![cert1.digest()];
+    parents.extend(honest_certs.iter().map(|cert| cert.digest()));
     let mut total_committed = 0;
     for cert in honest_certs {
         let committed = consensus.process_certificate(cert).await.unwrap();
         total_committed += committed.len();
     }
     
+    // Honest validators vote for round 0's leader in round 1
+    for i in 2..=4 {
+        let cert = create_test_certificate(test_peer_id(i), 1, parents.clone(), [i as u8 + 10; 32], &committee);
+        let committed = consensus.process_certificate(cert).await.unwrap();
+        total_committed += committed.len();
+    }
+    
     // Verify DAG has 4 certificates total (1 from Byzantine, 3 from honest)
     let dag_guard = dag.read().await;
     assert_eq!(
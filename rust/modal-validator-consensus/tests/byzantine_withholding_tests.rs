@@ -236,12 +236,26 @@ async fn test_consensus_with_silent_validator() {
     }
     
     // Process honest certificates through consensus
+    let mut parents: Vec<_> = honest_certs.iter().map(|cert| cert.digest()).collect();
     let mut total_committed = 0;
     for cert in honest_certs {
         let committed = consensus.process_certificate(cert).await.unwrap();
         total_committed += committed.len();
     }
     
+    // Honest validators keep building rounds; whichever of the round 0 and
+    // round 2 leaders is honest commits
+    for round in 1..=3 {
+        let mut next_parents = Vec::new();
+        for i in 1..=3 {
+            let cert = create_test_certificate(test_peer_id(i), round, parents.clone(), [i as u8 + 10 * round as u8; 32], &committee);
+            next_parents.push(cert.digest());
+            let committed = consensus.process_certificate(cert).await.unwrap();
+            total_committed += committed.len();
+        }
+        parents = next_parents;
+    }
+    
     // Verify DAG has 3 certificates (from honest validators only)
     let dag_guard = dag.read().await;
     assert_eq!(
@@ -65,7 +65,7 @@ pub fn create_test_certificate(
     let header = Header {
         author,
         round,
-        batch_digest,
+        payload: vec![(batch_digest, 0)],
         parents,
         timestamp: 1000 + round * 1000,
     };
@@ -258,7 +258,7 @@ mod tests {
         assert_eq!(cert1.header.round, cert2.header.round);
         
         // Different batch digests (conflicting)
-        assert_ne!(cert1.header.batch_digest, cert2.header.batch_digest);
+        assert_ne!(cert1.header.payload, cert2.header.payload);
         
         // Different certificate digests
         assert_ne!(cert1.digest(), cert2.digest());
@@ -41,7 +41,7 @@ fn create_test_cert(
     let header = Header {
         author,
         round,
-        batch_digest: [0u8; 32],
+        payload: vec![([0u8; 32], 0)],
         parents,
         timestamp: 1000 + round * 1000,
     };
@@ -73,12 +73,18 @@ async fn test_multi_validator_genesis() {
         genesis_certs.push(cert);
     }
     
-    // Process all genesis certificates
-    let mut any_committed = false;
+    // Process all genesis certificates; none commit before they're voted for
+    let genesis_digests: Vec<[u8; 32]> = genesis_certs.iter().map(|c| c.digest()).collect();
     for cert in genesis_certs {
         let committed = consensus.process_certificate(cert).await.unwrap();
-        
-        // At least one genesis certificate should trigger commits
+        assert!(committed.is_empty(), "genesis should wait for votes");
+    }
+    
+    // Round 1 certificates vote for the genesis leader
+    let mut any_committed = false;
+    for i in 0..4 {
+        let cert = create_test_cert(vec![i], 1, genesis_digests.clone(), &committee);
+        let committed = consensus.process_certificate(cert).await.unwrap();
         if !committed.is_empty() {
             any_committed = true;
         }
@@ -88,8 +94,8 @@ async fn test_multi_validator_genesis() {
     let dag = dag.read().await;
     assert_eq!(dag.round_size(0), 4, "should have 4 genesis certificates");
     
-    // Verify that at least one committed
-    assert!(any_committed, "at least one genesis should have committed");
+    // Verify that the genesis leader committed
+    assert!(any_committed, "the genesis leader should have committed");
 }
 
 #[tokio::test]
@@ -189,7 +195,7 @@ async fn test_equivocation_detection() {
     
     // Validator 0 tries to propose DIFFERENT certificate in same round (equivocation)
     let mut cert2 = create_test_cert(vec![0], 0, vec![], &committee);
-    cert2.header.batch_digest = [1u8; 32]; // Different batch
+    cert2.header.payload = vec![([1u8; 32], 0)]; // Different batch
     
     // Should detect equivocation and reject
     let result = dag.write().await.insert(cert2);
@@ -292,7 +298,7 @@ async fn test_byzantine_validator_isolation() {
     // Byzantine validator (3) creates TWO different certificates (equivocation)
     let byzantine_cert1 = create_test_cert(vec![3], 0, vec![], &committee);
     let mut byzantine_cert2 = create_test_cert(vec![3], 0, vec![], &committee);
-    byzantine_cert2.header.batch_digest = [1u8; 32]; // Different batch
+    byzantine_cert2.header.payload = vec![([1u8; 32], 0)]; // Different batch
     
     // First Byzantine certificate succeeds
     dag.write().await.insert(byzantine_cert1).unwrap();
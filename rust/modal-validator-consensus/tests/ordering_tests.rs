@@ -0,0 +1,140 @@
+//! Cross-node ordering determinism: nodes that receive the same certificates
+//! and batches in different orders must produce identical ordered output.
+
+use modal_validator_consensus::narwhal::dag::DAG;
+use modal_validator_consensus::narwhal::worker::WorkerMessage;
+use modal_validator_consensus::narwhal::{
+    AggregatedSignature, Certificate, CertificateDigest, Header, Transaction, WorkerPool,
+};
+use modal_validator_consensus::shoal::canonical::{self, CommittedBatch};
+use libp2p_identity::{ed25519, PeerId};
+use std::collections::BTreeSet;
+use tokio::sync::mpsc;
+
+const VALIDATORS: u8 = 4;
+const ROUNDS: u64 = 3;
+const WORKERS: usize = 2;
+
+fn test_peer_id(seed: u8) -> PeerId {
+    let mut secret_bytes = [0u8; 32];
+    secret_bytes[0] = seed;
+    let secret = ed25519::SecretKey::try_from_bytes(secret_bytes)
+        .expect("valid secret key");
+    let keypair = ed25519::Keypair::from(secret);
+    PeerId::from_public_key(&keypair.public().into())
+}
+
+/// Every validator seals batches through its workers and certifies them in
+/// each round, referencing all of the previous round's certificates.
+/// Returns the certificates and the batches streamed by the workers.
+async fn build_history() -> (Vec<Certificate>, Vec<WorkerMessage>) {
+    let (network, mut outbound) = mpsc::unbounded_channel();
+    let pools: Vec<WorkerPool> = (1..=VALIDATORS)
+        .map(|seed| WorkerPool::with_network(test_peer_id(seed), WORKERS, 100, 1024, Some(network.clone())))
+        .collect();
+
+    let mut certs = Vec::new();
+    let mut parents: Vec<CertificateDigest> = Vec::new();
+    for round in 0..ROUNDS {
+        let mut round_certs = Vec::new();
+        for (i, pool) in pools.iter().enumerate() {
+            for n in 0..3u8 {
+                pool.submit_transaction(Transaction {
+                    data: vec![round as u8, i as u8, n],
+                    timestamp: 1000 + round,
+                })
+                .await
+                .unwrap();
+            }
+            let cert = Certificate {
+                header: Header {
+                    author: pool.validator,
+                    round,
+                    payload: pool.seal_batches().await,
+                    parents: parents.clone(),
+                    timestamp: 1000 + round,
+                },
+                aggregated_signature: AggregatedSignature { signature: vec![] },
+                signers: vec![true; VALIDATORS as usize],
+            };
+            round_certs.push(cert);
+        }
+        parents = round_certs.iter().map(|cert| cert.digest()).collect();
+        certs.extend(round_certs);
+    }
+
+    let mut batches = Vec::new();
+    while let Ok(message) = outbound.try_recv() {
+        batches.push(message);
+    }
+    (certs, batches)
+}
+
+/// A node that receives the certificates and batches in the given orders and
+/// returns its canonical encoding of everything committed
+async fn node_output(certs: &[Certificate], batches: &[WorkerMessage], order: &[usize]) -> String {
+    let mut dag = DAG::new();
+    // Certificates have to arrive after their parents, so shuffle within rounds
+    for round in 0..ROUNDS {
+        for &i in order {
+            let cert = &certs[round as usize * VALIDATORS as usize + i];
+            dag.insert(cert.clone()).unwrap();
+        }
+    }
+
+    let workers = WorkerPool::new(test_peer_id(100), WORKERS, 100, 1024);
+    // Batches stream in independently of the certificates
+    for message in batches.iter().rev().cycle().skip(order[0]).take(batches.len()) {
+        workers.handle_message(message.clone()).await.unwrap();
+    }
+
+    let committed: BTreeSet<CertificateDigest> = certs.iter().map(|cert| cert.digest()).collect();
+    let mut committed_batches = Vec::new();
+    for batch_ref in canonical::order_batches(&dag, &committed).unwrap() {
+        let batch = workers.get_batch(batch_ref.digest, batch_ref.worker_id).await.unwrap();
+        committed_batches.push(CommittedBatch::new(batch_ref, batch).unwrap());
+    }
+    canonical::encode(&committed_batches)
+}
+
+#[tokio::test]
+async fn test_nodes_order_same_dag_identically() {
+    let (certs, batches) = build_history().await;
+    assert_eq!(batches.len(), (VALIDATORS as usize) * WORKERS * ROUNDS as usize);
+
+    let reference = node_output(&certs, &batches, &[0, 1, 2, 3]).await;
+    for order in [[3, 2, 1, 0], [1, 3, 0, 2], [2, 0, 3, 1]] {
+        assert_eq!(node_output(&certs, &batches, &order).await, reference);
+    }
+
+    // Every transaction is in the output exactly once
+    let output: serde_json::Value = serde_json::from_str(&reference).unwrap();
+    let transactions: usize = output
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|batch| batch["transactions"].as_array().unwrap().len())
+        .sum();
+    assert_eq!(transactions, VALIDATORS as usize * 3 * ROUNDS as usize);
+}
+
+#[tokio::test]
+async fn test_ordering_respects_causality() {
+    let (certs, _) = build_history().await;
+    let mut dag = DAG::new();
+    for cert in &certs {
+        dag.insert(cert.clone()).unwrap();
+    }
+
+    let committed: BTreeSet<CertificateDigest> = certs.iter().map(|cert| cert.digest()).collect();
+    let ordered = canonical::order_certificates(&dag, &committed).unwrap();
+    let rounds: Vec<u64> = ordered.iter().map(|digest| dag.get(digest).unwrap().header.round).collect();
+    let mut sorted = rounds.clone();
+    sorted.sort();
+    assert_eq!(rounds, sorted);
+
+    // Certificates of a round are ordered by digest
+    for round in ordered.chunks(VALIDATORS as usize) {
+        assert!(round.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+}
@@ -7,6 +7,7 @@ use modal_validator_consensus::persistence::{
 };
 use modal_validator_consensus::shoal::{ConsensusState, ReputationState, ReputationConfig};
 use modal_datastore::DatastoreManager;
+use modal_datastore::stores::Store;
 use modal_datastore::models::{DAGCertificate, DAGBatch, DAGState, ConsensusMetadata};
 use libp2p_identity::{ed25519, PeerId};
 use std::net::SocketAddr;
@@ -44,6 +45,7 @@ fn create_test_committee(n: usize) -> Committee {
     Committee {
         validators,
         validator_order,
+        bls_keys: std::collections::HashMap::new(),
     }
 }
 
@@ -55,7 +57,7 @@ async fn test_certificate_save_load_roundtrip() {
         header: Header {
             author: test_peer_id(1),
             round: 0,
-            batch_digest: [1u8; 32],
+            payload: vec![([1u8; 32], 0)],
             parents: vec![],
             timestamp: 1000,
         },
@@ -122,7 +124,7 @@ async fn test_dag_recovery_from_scratch() {
         header: Header {
             author: test_peer_id(1),
             round: 0,
-            batch_digest: [1u8; 32],
+            payload: vec![([1u8; 32], 0)],
             parents: vec![],
             timestamp: 1000,
         },
@@ -134,7 +136,7 @@ async fn test_dag_recovery_from_scratch() {
         header: Header {
             author: test_peer_id(2),
             round: 1,
-            batch_digest: [2u8; 32],
+            payload: vec![([2u8; 32], 0)],
             parents: vec![cert1.digest()],
             timestamp: 2000,
         },
@@ -172,7 +174,7 @@ async fn test_checkpoint_creation_and_recovery() {
             header: Header {
                 author: test_peer_id(i + 1),
                 round: 0,
-                batch_digest: [i as u8; 32],
+                payload: vec![([i as u8; 32], 0)],
                 parents: vec![],
                 timestamp: 1000 + i as u64,
             },
@@ -275,7 +277,7 @@ async fn test_hybrid_recovery_strategy() {
         header: Header {
             author: test_peer_id(1),
             round: 0,
-            batch_digest: [1u8; 32],
+            payload: vec![([1u8; 32], 0)],
             parents: vec![],
             timestamp: 1000,
         },
@@ -323,7 +325,7 @@ async fn test_persistence_during_multi_round_consensus() {
             header: Header {
                 author: test_peer_id(i + 1),
                 round: 0,
-                batch_digest: [i as u8; 32],
+                payload: vec![([i as u8; 32], 0)],
                 parents: vec![],
                 timestamp: 1000 + i as u64,
             },
@@ -342,7 +344,7 @@ async fn test_persistence_during_multi_round_consensus() {
             header: Header {
                 author: test_peer_id(i + 1),
                 round: 1,
-                batch_digest: [10 + i as u8; 32],
+                payload: vec![([10 + i as u8; 32], 0)],
                 parents: genesis_certs.clone(),
                 timestamp: 2000 + i as u64,
             },
@@ -371,7 +373,7 @@ async fn test_mark_certificate_committed() {
         header: Header {
             author: test_peer_id(1),
             round: 0,
-            batch_digest: [1u8; 32],
+            payload: vec![([1u8; 32], 0)],
             parents: vec![],
             timestamp: 1000,
         },
@@ -413,7 +415,7 @@ async fn test_batch_persistence_with_certificate_reference() {
         header: Header {
             author: test_peer_id(1),
             round: 0,
-            batch_digest: batch.digest(),
+            payload: vec![(batch.digest(), batch.worker_id)],
             parents: vec![],
             timestamp: 1000,
         },
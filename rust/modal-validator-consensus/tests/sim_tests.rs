@@ -0,0 +1,104 @@
+//! Consensus under simulated network faults: message drops, partitions and
+//! validator crashes, with liveness and safety checked on every run.
+
+use modal_validator_consensus::sim::{Fault, Latency, Partition, SimConfig, SimReport, Simulation};
+
+const ROUNDS: u64 = 20;
+
+/// Certificates each live validator should have committed: with 4 validators
+/// at least a quorum of certificates in all but the last few rounds
+const MIN_COMMITS: usize = 3 * (ROUNDS as usize - 5);
+
+async fn run(config: SimConfig, faults: Vec<Fault>) -> SimReport {
+    let mut sim = Simulation::new(SimConfig { rounds: ROUNDS, ..config });
+    for fault in faults {
+        sim = sim.with_fault(fault);
+    }
+    sim.run().await.unwrap()
+}
+
+#[tokio::test]
+async fn test_simulation_is_deterministic() {
+    let config = SimConfig {
+        seed: 7,
+        drop_rate: 0.1,
+        ..Default::default()
+    };
+    let first = run(config.clone(), vec![]).await;
+    let second = run(config, vec![]).await;
+
+    assert_eq!(first.commits, second.commits);
+    assert_eq!(first.elapsed_ms, second.elapsed_ms);
+    assert_eq!(first.messages_dropped, second.messages_dropped);
+}
+
+#[tokio::test]
+async fn test_liveness_with_message_drops() {
+    for seed in 0..3 {
+        let report = run(
+            SimConfig {
+                seed,
+                drop_rate: 0.2,
+                latency: Latency { min_ms: 5, max_ms: 100 },
+                ..Default::default()
+            },
+            vec![],
+        )
+        .await;
+
+        assert!(report.messages_dropped > 0);
+        assert_eq!(report.rounds, vec![ROUNDS; 4]);
+        report.check_liveness(MIN_COMMITS).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_liveness_after_partition_heals() {
+    // Neither half has a quorum while the partition lasts
+    let partition = Partition {
+        groups: vec![vec![0, 1], vec![2, 3]],
+        from_ms: 300,
+        until_ms: 2_000,
+    };
+    let report = run(SimConfig::default(), vec![Fault::Partition(partition)]).await;
+
+    assert!(report.elapsed_ms > 2_000);
+    assert_eq!(report.rounds, vec![ROUNDS; 4]);
+    report.check_liveness(MIN_COMMITS).unwrap();
+}
+
+#[tokio::test]
+async fn test_liveness_with_crashed_validator() {
+    let crash = Fault::Crash {
+        node: 3,
+        at_ms: 300,
+        restart_ms: None,
+    };
+    let report = run(SimConfig::default(), vec![crash]).await;
+
+    assert!(!report.up[3]);
+    assert_eq!(report.rounds[..3], [ROUNDS; 3]);
+    report.check_liveness(MIN_COMMITS).unwrap();
+}
+
+#[tokio::test]
+async fn test_restarted_validator_catches_up() {
+    let crash = Fault::Crash {
+        node: 0,
+        at_ms: 300,
+        restart_ms: Some(1_500),
+    };
+    let report = run(SimConfig::default(), vec![crash]).await;
+
+    assert!(report.up[0]);
+    assert_eq!(report.rounds, vec![ROUNDS; 4]);
+    report.check_liveness(MIN_COMMITS).unwrap();
+}
+
+#[tokio::test]
+async fn test_validators_agree_on_commit_order() {
+    for seed in 0..3 {
+        let report = run(SimConfig { seed, ..Default::default() }, vec![]).await;
+        report.check_safety().unwrap();
+    }
+}
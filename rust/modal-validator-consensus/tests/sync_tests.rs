@@ -20,7 +20,7 @@ fn make_test_cert(author_seed: u8, round: u64, parents: Vec<[u8; 32]>) -> Certif
         header: Header {
             author: test_peer_id(author_seed),
             round,
-            batch_digest: [round as u8; 32],
+            payload: vec![([round as u8; 32], 0)],
             parents,
             timestamp: 1000 + round,
         },
@@ -1,16 +1,24 @@
 use anyhow::Result;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use modal_datastore::DatastoreManager;
-use modal_datastore::models::{ContractAsset, AssetBalance, Commit, ReceivedSend, WasmModule};
+use modal_datastore::{DatastoreManager, JournalEventKind};
+use modal_datastore::governance::{GovernancePath, ParameterProposal, ProposalApproval, GOVERNANCE_CONTRACT_ID};
+use modal_datastore::key_rotations::KEYS_CONTRACT_ID;
+use modal_datastore::models::{ContractAsset, AssetBalance, Commit, ContractGasUsage, ContractMessage, ReceivedSend, WasmModule};
 use serde_json::Value;
-use modal_wasm_runtime::{WasmExecutor, DEFAULT_GAS_LIMIT};
+use modal_wasm_runtime::{WasmExecutor, DEFAULT_GAS_LIMIT, VALIDATION_GAS_PER_BYTE};
 use modal_wasm_validation::{PredicateContext, ProgramContext};
 use crate::predicate_executor::PredicateExecutor;
 use crate::program_executor::ProgramExecutor;
+use crate::state_snapshot::{SnapshotId, StateSnapshot};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 /// Represents a state change from processing a commit action
-#[derive(Debug, Clone)]
+/// 
+/// Serializes with a `type` tag, e.g. for simulateCommit RPC responses.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum StateChange {
     AssetCreated {
         contract_id: String,
@@ -42,6 +50,8 @@ pub enum StateChange {
         module_name: String,
         sha256_hash: String,
         gas_limit: u64,
+        /// Gas charged for validating the module
+        gas_used: u64,
     },
     WasmExecuted {
         contract_id: String,
@@ -54,6 +64,29 @@ pub enum StateChange {
         gas_used: u64,
         actions_count: usize,
     },
+    MessageEnqueued {
+        from_contract: String,
+        to_contract: String,
+        message_id: String,
+    },
+    MessageDelivered {
+        from_contract: String,
+        to_contract: String,
+        message_id: String,
+        commit_id: String,
+    },
+}
+
+impl StateChange {
+    /// Gas the change was charged
+    pub fn gas_used(&self) -> u64 {
+        match self {
+            StateChange::WasmUploaded { gas_used, .. }
+            | StateChange::WasmExecuted { gas_used, .. }
+            | StateChange::ProgramInvoked { gas_used, .. } => *gas_used,
+            _ => 0,
+        }
+    }
 }
 
 /// Processes contract commits and manages asset state during consensus
@@ -61,6 +94,11 @@ pub struct ContractProcessor {
     datastore: Arc<Mutex<DatastoreManager>>,
     predicate_executor: PredicateExecutor,
     program_executor: ProgramExecutor,
+    /// Open snapshots, by id
+    snapshots: std::sync::Mutex<HashMap<SnapshotId, StateSnapshot>>,
+    next_snapshot_id: AtomicU64,
+    /// Epoch gas usage is accounted to
+    epoch: u64,
 }
 
 impl ContractProcessor {
@@ -73,20 +111,147 @@ impl ContractProcessor {
             Arc::clone(&datastore),
             DEFAULT_GAS_LIMIT
         );
-        Self { datastore, predicate_executor, program_executor }
+        Self {
+            datastore,
+            predicate_executor,
+            program_executor,
+            snapshots: std::sync::Mutex::new(HashMap::new()),
+            next_snapshot_id: AtomicU64::new(1),
+            epoch: 0,
+        }
+    }
+
+    /// Account gas used to `epoch` and hold contracts to its quota
+    pub fn with_epoch(mut self, epoch: u64) -> Self {
+        self.epoch = epoch;
+        self
+    }
+
+    /// Gas a contract used in an epoch
+    pub async fn gas_usage(&self, contract_id: &str, epoch: u64) -> Result<ContractGasUsage> {
+        let ds = self.datastore.lock().await;
+        ContractGasUsage::find_or_default_multi(&ds, contract_id, epoch).await
     }
 
     /// Process a commit during consensus ordering
     /// 
     /// This method:
     /// 1. Saves the commit to the datastore for future reference
-    /// 2. Processes all actions in the commit
-    /// 3. Returns state changes that occurred
+    /// 2. Delivers messages other contracts sent to this one
+    /// 3. Processes all actions in the commit
+    /// 4. Records the applied commit in the event journal
+    /// 5. Returns state changes that occurred
+    /// 
+    /// If any action fails, or the commit uses more gas than the per-commit
+    /// quota or its contract's remaining epoch quota allow, everything the
+    /// commit wrote is rolled back.
     pub async fn process_commit(
         &self,
         contract_id: &str,
         commit_id: &str,
         commit_data: &str,
+    ) -> Result<Vec<StateChange>> {
+        let mut snapshot = StateSnapshot::new();
+        let result = self.apply_commit(&mut snapshot, contract_id, commit_id, commit_data).await;
+        let ds = self.datastore.lock().await;
+        match &result {
+            Ok(changes) => {
+                ds.append_event(JournalEventKind::CommitApplied, serde_json::json!({
+                    "contract_id": contract_id,
+                    "commit_id": commit_id,
+                    "state_changes": changes.len(),
+                }))?;
+            }
+            Err(_) => snapshot.rollback(&ds)?,
+        }
+        result
+    }
+
+    /// Open a snapshot of the contract state
+    /// 
+    /// Commits processed in the snapshot can be rolled back together. Their
+    /// writes are visible until then, so speculative runs against a live
+    /// datastore should use `dry_run`, which rolls back right away.
+    pub fn begin_snapshot(&self) -> SnapshotId {
+        let id = self.next_snapshot_id.fetch_add(1, Ordering::SeqCst);
+        self.snapshots.lock().unwrap().insert(id, StateSnapshot::new());
+        id
+    }
+
+    /// Process a commit inside an open snapshot
+    /// 
+    /// A commit that fails is rolled back on its own; the snapshot keeps the
+    /// writes of the commits that succeeded.
+    pub async fn process_commit_in_snapshot(
+        &self,
+        snapshot_id: SnapshotId,
+        contract_id: &str,
+        commit_id: &str,
+        commit_data: &str,
+    ) -> Result<Vec<StateChange>> {
+        if !self.snapshots.lock().unwrap().contains_key(&snapshot_id) {
+            anyhow::bail!("Snapshot {} not found", snapshot_id);
+        }
+
+        let mut commit_snapshot = StateSnapshot::new();
+        let result = self.apply_commit(&mut commit_snapshot, contract_id, commit_id, commit_data).await;
+        if result.is_err() {
+            let ds = self.datastore.lock().await;
+            commit_snapshot.rollback(&ds)?;
+        } else {
+            let mut snapshots = self.snapshots.lock().unwrap();
+            match snapshots.get_mut(&snapshot_id) {
+                Some(snapshot) => snapshot.absorb(commit_snapshot),
+                // Released while the commit ran; its writes stay
+                None => log::warn!("Snapshot {} closed while processing commit {}", snapshot_id, commit_id),
+            }
+        }
+        result
+    }
+
+    /// Undo everything written in the snapshot and close it
+    pub async fn rollback_snapshot(&self, snapshot_id: SnapshotId) -> Result<()> {
+        let snapshot = self.snapshots.lock().unwrap().remove(&snapshot_id)
+            .ok_or_else(|| anyhow::anyhow!("Snapshot {} not found", snapshot_id))?;
+        let ds = self.datastore.lock().await;
+        snapshot.rollback(&ds)
+    }
+
+    /// Keep everything written in the snapshot and close it
+    pub fn release_snapshot(&self, snapshot_id: SnapshotId) -> Result<()> {
+        self.snapshots.lock().unwrap().remove(&snapshot_id)
+            .map(|_| ())
+            .ok_or_else(|| anyhow::anyhow!("Snapshot {} not found", snapshot_id))
+    }
+
+    /// Ids of the open snapshots
+    pub fn open_snapshots(&self) -> Vec<SnapshotId> {
+        let mut ids: Vec<SnapshotId> = self.snapshots.lock().unwrap().keys().copied().collect();
+        ids.sort();
+        ids
+    }
+
+    /// Process a commit speculatively: return the state changes it would
+    /// make, then roll it back
+    pub async fn dry_run(
+        &self,
+        contract_id: &str,
+        commit_id: &str,
+        commit_data: &str,
+    ) -> Result<Vec<StateChange>> {
+        let mut snapshot = StateSnapshot::new();
+        let result = self.apply_commit(&mut snapshot, contract_id, commit_id, commit_data).await;
+        let ds = self.datastore.lock().await;
+        snapshot.rollback(&ds)?;
+        result
+    }
+
+    async fn apply_commit(
+        &self,
+        snapshot: &mut StateSnapshot,
+        contract_id: &str,
+        commit_id: &str,
+        commit_data: &str,
     ) -> Result<Vec<StateChange>> {
         // Save the commit to the datastore so it can be referenced by RECV actions
         {
@@ -102,7 +267,7 @@ impl ContractProcessor {
                 timestamp,
                 in_batch: None,
             };
-            commit.save_to_final(&ds).await?;
+            snapshot.save_model(&ds, &commit).await?;
         }
 
         let commit: serde_json::Value = serde_json::from_str(commit_data)?;
@@ -110,9 +275,9 @@ impl ContractProcessor {
             .and_then(|v| v.as_array())
             .ok_or_else(|| anyhow::anyhow!("Invalid commit structure"))?;
 
-        let mut state_changes = Vec::new();
+        let mut state_changes = self.deliver_messages(snapshot, contract_id, commit_id).await?;
 
-        for action in body {
+        for (index, action) in body.iter().enumerate() {
             let method = action.get("method")
                 .and_then(|v| v.as_str())
                 .ok_or_else(|| anyhow::anyhow!("Action missing method"))?;
@@ -121,40 +286,81 @@ impl ContractProcessor {
                 "create" => {
                     let value = action.get("value")
                         .ok_or_else(|| anyhow::anyhow!("Action missing value"))?;
-                    state_changes.push(self.process_create(contract_id, commit_id, value).await?);
+                    state_changes.push(self.process_create(snapshot, contract_id, commit_id, value).await?);
                 }
                 "send" => {
                     let value = action.get("value")
                         .ok_or_else(|| anyhow::anyhow!("Action missing value"))?;
-                    state_changes.push(self.process_send(contract_id, commit_id, value).await?);
+                    state_changes.push(self.process_send(snapshot, contract_id, commit_id, value).await?);
                 }
                 "recv" => {
                     let value = action.get("value")
                         .ok_or_else(|| anyhow::anyhow!("Action missing value"))?;
-                    state_changes.push(self.process_recv(contract_id, commit_id, value).await?);
+                    state_changes.push(self.process_recv(snapshot, contract_id, commit_id, value).await?);
                 }
                 "post" => {
-                    state_changes.push(self.process_post(contract_id, action).await?);
+                    state_changes.push(self.process_post(snapshot, contract_id, action).await?);
                 }
                 "repost" => {
-                    state_changes.push(self.process_repost(contract_id, action).await?);
+                    state_changes.push(self.process_repost(snapshot, contract_id, action).await?);
                 }
                 "invoke" => {
                     // Process INVOKE action - execute program and process resulting actions
-                    let invoke_changes = self.process_invoke(contract_id, commit_id, action).await?;
+                    let invoke_changes = self.process_invoke(snapshot, contract_id, commit_id, action).await?;
                     state_changes.extend(invoke_changes);
                 }
+                "message" => {
+                    let value = action.get("value")
+                        .ok_or_else(|| anyhow::anyhow!("Action missing value"))?;
+                    state_changes.push(self.process_message(snapshot, contract_id, commit_id, index, value).await?);
+                }
                 _ => {
                     // Other actions are not processed
                 }
             }
         }
 
+        let gas_used = state_changes.iter().map(StateChange::gas_used).sum();
+        self.charge_gas(snapshot, contract_id, commit_id, gas_used).await?;
+
         Ok(state_changes)
     }
 
+    /// Add a commit's gas to its contract's usage for the epoch, failing if
+    /// that goes over a quota
+    async fn charge_gas(
+        &self,
+        snapshot: &mut StateSnapshot,
+        contract_id: &str,
+        commit_id: &str,
+        gas_used: u64,
+    ) -> Result<()> {
+        let ds = self.datastore.lock().await;
+        let quotas = ds.get_gas_quotas().await?;
+
+        if let Some(quota) = quotas.per_commit {
+            if gas_used > quota {
+                anyhow::bail!("Commit {} used {} gas, over the per-commit quota of {}", commit_id, gas_used, quota);
+            }
+        }
+
+        let mut usage = ContractGasUsage::find_or_default_multi(&ds, contract_id, self.epoch).await?;
+        usage.gas_used = usage.gas_used.saturating_add(gas_used);
+        usage.commits += 1;
+        if let Some(quota) = quotas.per_epoch {
+            if usage.gas_used > quota {
+                anyhow::bail!(
+                    "Commit {} would bring contract {} to {} gas in epoch {}, over the quota of {}",
+                    commit_id, contract_id, usage.gas_used, self.epoch, quota
+                );
+            }
+        }
+        snapshot.save_model(&ds, &usage).await
+    }
+
     async fn process_create(
         &self,
+        snapshot: &mut StateSnapshot,
         contract_id: &str,
         commit_id: &str,
         value: &Value,
@@ -196,7 +402,7 @@ impl ContractProcessor {
             creator_commit_id: commit_id.to_string(),
         };
 
-        asset.save_to_final(&ds).await?;
+        snapshot.save_model(&ds, &asset).await?;
 
         // Initialize balance for the creating contract
         let balance = AssetBalance {
@@ -206,7 +412,7 @@ impl ContractProcessor {
             balance: quantity,
         };
 
-        balance.save_to_final(&ds).await?;
+        snapshot.save_model(&ds, &balance).await?;
 
         Ok(StateChange::AssetCreated {
             contract_id: contract_id.to_string(),
@@ -228,6 +434,7 @@ impl ContractProcessor {
     /// - Records the SEND (but doesn't transfer until RECV)
     async fn process_send(
         &self,
+        snapshot: &mut StateSnapshot,
         contract_id: &str,
         commit_id: &str,
         value: &Value,
@@ -275,7 +482,7 @@ impl ContractProcessor {
 
         // Deduct from sender
         balance.balance -= amount;
-        balance.save_to_final(&ds).await?;
+        snapshot.save_model(&ds, &balance).await?;
 
         Ok(StateChange::AssetSent {
             contract_id: contract_id.to_string(),
@@ -298,6 +505,7 @@ impl ContractProcessor {
     /// - Credits the amount to receiver's balance
     async fn process_recv(
         &self,
+        snapshot: &mut StateSnapshot,
         contract_id: &str,
         commit_id: &str,
         value: &Value,
@@ -375,7 +583,7 @@ impl ContractProcessor {
             recv_commit_id: commit_id.to_string(),
             received_at: timestamp,
         };
-        received_send.save_to_final(&ds).await?;
+        snapshot.save_model(&ds, &received_send).await?;
 
         // Get or create balance for receiving contract
         let mut balance_keys = std::collections::HashMap::new();
@@ -398,7 +606,7 @@ impl ContractProcessor {
 
         // Add to receiver
         balance.balance += amount;
-        balance.save_to_final(&ds).await?;
+        snapshot.save_model(&ds, &balance).await?;
 
         Ok(StateChange::AssetReceived {
             from_contract: from_contract.to_string(),
@@ -409,6 +617,116 @@ impl ContractProcessor {
         })
     }
 
+    /// Process a MESSAGE action during consensus
+    /// 
+    /// Queues the message for the recipient contract. It is delivered in the
+    /// recipient's next processed commit.
+    async fn process_message(
+        &self,
+        snapshot: &mut StateSnapshot,
+        contract_id: &str,
+        commit_id: &str,
+        action_index: usize,
+        value: &Value,
+    ) -> Result<StateChange> {
+        let to_contract = value.get("to_contract")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("MESSAGE missing to_contract"))?;
+
+        let payload = value.get("payload")
+            .ok_or_else(|| anyhow::anyhow!("MESSAGE missing payload"))?;
+
+        let message_id = ContractMessage::message_id_for(commit_id, action_index);
+
+        let ds = self.datastore.lock().await;
+
+        // A commit processed twice must not send its messages twice
+        let mut keys = std::collections::HashMap::new();
+        keys.insert("to_contract".to_string(), to_contract.to_string());
+        keys.insert("message_id".to_string(), message_id.clone());
+        if ContractMessage::find_one_multi(&ds, keys).await?.is_some() {
+            anyhow::bail!("Message {} already enqueued", message_id);
+        }
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+
+        let message = ContractMessage {
+            message_id: message_id.clone(),
+            from_contract: contract_id.to_string(),
+            to_contract: to_contract.to_string(),
+            sender_commit_id: commit_id.to_string(),
+            payload: serde_json::to_string(payload)?,
+            enqueued_at: timestamp,
+            delivered_in_commit: None,
+            delivered_at: None,
+        };
+        snapshot.save_model(&ds, &message).await?;
+
+        Ok(StateChange::MessageEnqueued {
+            from_contract: contract_id.to_string(),
+            to_contract: to_contract.to_string(),
+            message_id,
+        })
+    }
+
+    /// Deliver the pending messages to a contract in one of its commits
+    /// 
+    /// Each message is delivered exactly once, in message id order:
+    /// - The payload is stored in the recipient's inbox at /inbox/{message_id}.json
+    /// - A receipt is stored in the sender's state at /receipts/{message_id}.json
+    /// - The message is marked delivered in this commit
+    async fn deliver_messages(
+        &self,
+        snapshot: &mut StateSnapshot,
+        contract_id: &str,
+        commit_id: &str,
+    ) -> Result<Vec<StateChange>> {
+        let ds = self.datastore.lock().await;
+
+        let pending = ContractMessage::find_pending_multi(&ds, contract_id).await?;
+        if pending.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+
+        let mut state_changes = Vec::new();
+        for mut message in pending {
+            let inbox_key = format!("/contracts/{}/inbox/{}.json", contract_id, message.message_id);
+            snapshot.set_data(&ds, &inbox_key, message.payload.as_bytes()).await?;
+
+            let receipt = serde_json::json!({
+                "message_id": message.message_id,
+                "to_contract": contract_id,
+                "delivered_in_commit": commit_id,
+                "delivered_at": timestamp,
+            });
+            let receipt_key = format!("/contracts/{}/receipts/{}.json", message.from_contract, message.message_id);
+            snapshot.set_data(&ds, &receipt_key, serde_json::to_string(&receipt)?.as_bytes()).await?;
+
+            message.delivered_in_commit = Some(commit_id.to_string());
+            message.delivered_at = Some(timestamp);
+            snapshot.save_model(&ds, &message).await?;
+
+            log::debug!(
+                "Delivered message {} from {} to {} in commit {}",
+                message.message_id, message.from_contract, contract_id, commit_id
+            );
+            state_changes.push(StateChange::MessageDelivered {
+                from_contract: message.from_contract,
+                to_contract: contract_id.to_string(),
+                message_id: message.message_id,
+                commit_id: commit_id.to_string(),
+            });
+        }
+
+        Ok(state_changes)
+    }
+
     /// Evaluate a predicate and return the result as a proposition
     /// 
     /// This method:
@@ -434,11 +752,28 @@ impl ContractProcessor {
             timestamp,
         };
 
+        // A contract that used up its epoch quota can't evaluate predicates
+        let quotas = self.datastore.lock().await.get_gas_quotas().await?;
+        if let Some(quota) = quotas.per_epoch {
+            let usage = self.gas_usage(contract_id, self.epoch).await?;
+            if usage.gas_used >= quota {
+                anyhow::bail!("Contract {} used its gas quota of {} for epoch {}", contract_id, quota, self.epoch);
+            }
+        }
+
         // Execute the predicate
         let result = self.predicate_executor
             .evaluate_predicate(contract_id, predicate_path, args, context)
             .await?;
 
+        // Predicate gas counts towards the contract's epoch usage
+        {
+            let ds = self.datastore.lock().await;
+            let mut usage = ContractGasUsage::find_or_default_multi(&ds, contract_id, self.epoch).await?;
+            usage.gas_used = usage.gas_used.saturating_add(result.gas_used);
+            usage.save_to_final(&ds).await?;
+        }
+
         // Convert result to proposition string
         Ok(PredicateExecutor::result_to_proposition(&predicate_name, &result))
     }
@@ -451,6 +786,7 @@ impl ContractProcessor {
     /// Special handling for .wasm extensions: uploads WASM modules to the datastore
     async fn process_post(
         &self,
+        snapshot: &mut StateSnapshot,
         contract_id: &str,
         action: &Value,
     ) -> Result<StateChange> {
@@ -463,7 +799,14 @@ impl ContractProcessor {
         
         // Check if this is a WASM upload (path ends with .wasm)
         if path.ends_with(".wasm") {
-            return self.process_wasm_post(contract_id, path, value).await;
+            return self.process_wasm_post(snapshot, contract_id, path, value).await;
+        }
+        
+        if contract_id == GOVERNANCE_CONTRACT_ID {
+            self.check_governance_post(path, value).await?;
+        }
+        if contract_id == KEYS_CONTRACT_ID {
+            self.check_key_rotation_post(path, value).await?;
         }
         
         // Convert value to string for storage
@@ -482,7 +825,7 @@ impl ContractProcessor {
         let key = format!("/contracts/{}{}", contract_id, path);
         
         let ds = self.datastore.lock().await;
-        ds.set_data_by_key(&key, value_str.as_bytes()).await?;
+        snapshot.set_data(&ds, &key, value_str.as_bytes()).await?;
         
         log::debug!("Stored POST: {} = {}", key, value_str);
         
@@ -493,6 +836,56 @@ impl ContractProcessor {
         })
     }
 
+    /// Governance contract posts must be well formed proposals or validly
+    /// signed approvals of an existing proposal
+    async fn check_governance_post(&self, path: &str, value: &Value) -> Result<()> {
+        let ds = self.datastore.lock().await;
+        match GovernancePath::parse(path) {
+            Some(GovernancePath::Proposal { id }) => {
+                let proposal: ParameterProposal = serde_json::from_value(value.clone())
+                    .map_err(|e| anyhow::anyhow!("Invalid proposal at {}: {}", path, e))?;
+                if proposal.id != id {
+                    anyhow::bail!("Proposal {} posted at {}", proposal.id, path);
+                }
+                proposal.validate()?;
+                // Approvals sign the proposal, so it can't change under them
+                if ds.governance_proposal(&id)?.is_some() {
+                    anyhow::bail!("Proposal {} already exists", id);
+                }
+            }
+            Some(GovernancePath::Approval { proposal_id, validator }) => {
+                let approval: ProposalApproval = serde_json::from_value(value.clone())
+                    .map_err(|e| anyhow::anyhow!("Invalid approval at {}: {}", path, e))?;
+                if approval.proposal_id != proposal_id || approval.validator != validator {
+                    anyhow::bail!("Approval of {} by {} posted at {}", approval.proposal_id, approval.validator, path);
+                }
+                let proposal = ds.governance_proposal(&proposal_id)?
+                    .ok_or_else(|| anyhow::anyhow!("Proposal {} not found", proposal_id))?;
+                if !approval.verify(&proposal) {
+                    anyhow::bail!("Invalid signature on approval of {} by {}", proposal_id, validator);
+                }
+            }
+            None => anyhow::bail!("Governance contract has no path {}", path),
+        }
+        Ok(())
+    }
+
+    /// Key rotation contract posts must be rotations signed by the old key
+    /// that fit the committed history of validator keys
+    async fn check_key_rotation_post(&self, path: &str, value: &Value) -> Result<()> {
+        use modal_datastore::key_rotations::{old_key_from_path, KeyRotation};
+
+        let old_key = old_key_from_path(path)
+            .ok_or_else(|| anyhow::anyhow!("Key rotation contract has no path {}", path))?;
+        let rotation: KeyRotation = serde_json::from_value(value.clone())
+            .map_err(|e| anyhow::anyhow!("Invalid key rotation at {}: {}", path, e))?;
+        if rotation.old_key != old_key {
+            anyhow::bail!("Rotation of {} posted at {}", rotation.old_key, path);
+        }
+        let ds = self.datastore.lock().await;
+        ds.key_history()?.check(&rotation)
+    }
+
     /// Process a REPOST action during consensus
     /// 
     /// REPOST copies data from another contract into a local namespace.
@@ -504,6 +897,7 @@ impl ContractProcessor {
     /// - Reposted value matches source contract's LATEST value (hub/network responsibility)
     async fn process_repost(
         &self,
+        snapshot: &mut StateSnapshot,
         contract_id: &str,
         action: &Value,
     ) -> Result<StateChange> {
@@ -554,7 +948,7 @@ impl ContractProcessor {
         // Store the reposted data in this contract's namespace
         // Keep the full $contract_id:/path format as the key for provenance tracking
         let store_key = format!("/contracts/{}/reposts/{}{}", contract_id, source_contract_id, remote_path);
-        ds.set_data_by_key(&store_key, repost_value_str.as_bytes()).await?;
+        snapshot.set_data(&ds, &store_key, repost_value_str.as_bytes()).await?;
         
         log::info!(
             "REPOST validated: {} <- {}:{}",
@@ -598,6 +992,7 @@ impl ContractProcessor {
     /// - gas_limit: optional gas limit (defaults to DEFAULT_GAS_LIMIT)
     async fn process_wasm_post(
         &self,
+        snapshot: &mut StateSnapshot,
         contract_id: &str,
         path: &str,
         value: &Value,
@@ -649,9 +1044,10 @@ impl ContractProcessor {
         );
         
         let sha256_hash = wasm_module.sha256_hash.clone();
+        let gas_used = (wasm_module.wasm_bytes.len() as u64).saturating_mul(VALIDATION_GAS_PER_BYTE);
         
         let ds = self.datastore.lock().await;
-        wasm_module.save_to_final(&ds).await?;
+        snapshot.save_model(&ds, &wasm_module).await?;
         
         log::info!(
             "Uploaded WASM module '{}' for contract {} via POST {}, hash: {}, gas_limit: {}",
@@ -667,6 +1063,7 @@ impl ContractProcessor {
             module_name: module_name.to_string(),
             sha256_hash,
             gas_limit,
+            gas_used,
         })
     }
 
@@ -719,6 +1116,7 @@ impl ContractProcessor {
     /// 4. Returns all state changes from those actions + a ProgramInvoked change
     async fn process_invoke(
         &self,
+        snapshot: &mut StateSnapshot,
         contract_id: &str,
         commit_id: &str,
         action: &Value,
@@ -788,22 +1186,22 @@ impl ContractProcessor {
             match program_action.method.as_str() {
                 "create" => {
                     state_changes.push(
-                        self.process_create(contract_id, commit_id, &program_action.value).await?
+                        self.process_create(snapshot, contract_id, commit_id, &program_action.value).await?
                     );
                 }
                 "send" => {
                     state_changes.push(
-                        self.process_send(contract_id, commit_id, &program_action.value).await?
+                        self.process_send(snapshot, contract_id, commit_id, &program_action.value).await?
                     );
                 }
                 "recv" => {
                     state_changes.push(
-                        self.process_recv(contract_id, commit_id, &program_action.value).await?
+                        self.process_recv(snapshot, contract_id, commit_id, &program_action.value).await?
                     );
                 }
                 "post" => {
                     state_changes.push(
-                        self.process_post(contract_id, &action_value).await?
+                        self.process_post(snapshot, contract_id, &action_value).await?
                     );
                 }
                 "rule" => {
@@ -976,11 +1374,12 @@ mod tests {
         
         // Verify it's a WASM uploaded state change
         match &state_changes[0] {
-            StateChange::WasmUploaded { contract_id, module_name, sha256_hash, gas_limit } => {
+            StateChange::WasmUploaded { contract_id, module_name, sha256_hash, gas_limit, gas_used } => {
                 assert_eq!(contract_id, "contract1");
                 assert_eq!(module_name, "primary");
                 assert!(!sha256_hash.is_empty());
                 assert_eq!(*gas_limit, DEFAULT_GAS_LIMIT);
+                assert_eq!(*gas_used, 8 * VALIDATION_GAS_PER_BYTE);
             }
             _ => panic!("Expected WasmUploaded state change"),
         }
@@ -1039,4 +1438,236 @@ mod tests {
             _ => panic!("Expected WasmUploaded state change"),
         }
     }
+
+    #[tokio::test]
+    async fn test_message_delivered_exactly_once() {
+        let datastore = Arc::new(Mutex::new(
+            DatastoreManager::create_in_memory().unwrap()
+        ));
+        
+        let processor = ContractProcessor::new(datastore.clone());
+        
+        let commit = |body: Value| serde_json::json!({ "body": body, "head": {} }).to_string();
+        let message = commit(serde_json::json!([
+            { "method": "post", "path": "/status.text", "value": "sent" },
+            { "method": "message", "value": { "to_contract": "bob", "payload": { "greeting": "hi" } } }
+        ]));
+        
+        let changes = processor.process_commit("alice", "a1", &message).await.unwrap();
+        let message_id = ContractMessage::message_id_for("a1", 1);
+        assert!(matches!(&changes[1], StateChange::MessageEnqueued { to_contract, message_id: id, .. } if to_contract == "bob" && *id == message_id));
+        
+        // The same commit can't queue the message again
+        assert!(processor.process_commit("alice", "a1", &message).await.is_err());
+        
+        // Bob's next commit delivers it
+        let bob_commit = commit(serde_json::json!([{ "method": "post", "path": "/x.text", "value": "1" }]));
+        let changes = processor.process_commit("bob", "b1", &bob_commit).await.unwrap();
+        assert!(matches!(&changes[0], StateChange::MessageDelivered { from_contract, commit_id, .. } if from_contract == "alice" && commit_id == "b1"));
+        
+        {
+            let ds = datastore.lock().await;
+            let inbox = ds.get_string(&format!("/contracts/bob/inbox/{}.json", message_id)).await.unwrap().unwrap();
+            assert_eq!(serde_json::from_str::<Value>(&inbox).unwrap(), serde_json::json!({ "greeting": "hi" }));
+            
+            let receipt = ds.get_string(&format!("/contracts/alice/receipts/{}.json", message_id)).await.unwrap().unwrap();
+            let receipt: Value = serde_json::from_str(&receipt).unwrap();
+            assert_eq!(receipt["delivered_in_commit"], "b1");
+            assert!(ContractMessage::find_pending_multi(&ds, "bob").await.unwrap().is_empty());
+        }
+        
+        // And later commits don't deliver it again
+        let changes = processor.process_commit("bob", "b2", &bob_commit).await.unwrap();
+        assert_eq!(changes.len(), 1);
+        assert!(matches!(&changes[0], StateChange::Posted { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_failed_commit_leaves_no_state() {
+        let datastore = Arc::new(Mutex::new(
+            DatastoreManager::create_in_memory().unwrap()
+        ));
+        
+        let processor = ContractProcessor::new(datastore.clone());
+        
+        // The SEND fails after the CREATE and POST succeeded
+        let commit_data = serde_json::json!({
+            "body": [
+                { "method": "create", "value": { "asset_id": "token", "quantity": 100, "divisibility": 1 } },
+                { "method": "post", "path": "/name.text", "value": "alice" },
+                { "method": "send", "value": { "asset_id": "token", "to_contract": "bob", "amount": 500 } }
+            ],
+            "head": {}
+        }).to_string();
+        assert!(processor.process_commit("alice", "a1", &commit_data).await.is_err());
+        
+        let ds = datastore.lock().await;
+        let asset_keys: HashMap<String, String> = [
+            ("contract_id".to_string(), "alice".to_string()),
+            ("asset_id".to_string(), "token".to_string()),
+        ].into_iter().collect();
+        assert!(ContractAsset::find_one_multi(&ds, asset_keys).await.unwrap().is_none());
+        assert!(ds.get_string("/contracts/alice/name.text").await.unwrap().is_none());
+        let commit_keys: HashMap<String, String> = [
+            ("contract_id".to_string(), "alice".to_string()),
+            ("commit_id".to_string(), "a1".to_string()),
+        ].into_iter().collect();
+        assert!(Commit::find_one_multi(&ds, commit_keys).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_rollback_and_dry_run() {
+        let datastore = Arc::new(Mutex::new(
+            DatastoreManager::create_in_memory().unwrap()
+        ));
+        
+        let processor = ContractProcessor::new(datastore.clone());
+        
+        let post = |value: &str| serde_json::json!({
+            "body": [{ "method": "post", "path": "/status.text", "value": value }],
+            "head": {}
+        }).to_string();
+        processor.process_commit("alice", "a1", &post("live")).await.unwrap();
+        
+        // A dry run reports its changes without keeping them
+        let changes = processor.dry_run("alice", "a2", &post("speculative")).await.unwrap();
+        assert_eq!(serde_json::to_value(&changes[0]).unwrap()["type"], "posted");
+        assert!(matches!(&changes[0], StateChange::Posted { value, .. } if value == "speculative"));
+        assert_eq!(datastore.lock().await.get_string("/contracts/alice/status.text").await.unwrap().as_deref(), Some("live"));
+        
+        // Commits in a snapshot build on each other until it is rolled back
+        let snapshot_id = processor.begin_snapshot();
+        assert_eq!(processor.open_snapshots(), vec![snapshot_id]);
+        processor.process_commit_in_snapshot(snapshot_id, "alice", "a2", &post("first")).await.unwrap();
+        processor.process_commit_in_snapshot(snapshot_id, "alice", "a3", &post("second")).await.unwrap();
+        assert_eq!(datastore.lock().await.get_string("/contracts/alice/status.text").await.unwrap().as_deref(), Some("second"));
+        
+        processor.rollback_snapshot(snapshot_id).await.unwrap();
+        assert_eq!(datastore.lock().await.get_string("/contracts/alice/status.text").await.unwrap().as_deref(), Some("live"));
+        assert!(processor.open_snapshots().is_empty());
+        assert!(processor.rollback_snapshot(snapshot_id).await.is_err());
+        assert!(processor.process_commit_in_snapshot(snapshot_id, "alice", "a4", &post("x")).await.is_err());
+        
+        // Released snapshots keep their writes
+        let snapshot_id = processor.begin_snapshot();
+        processor.process_commit_in_snapshot(snapshot_id, "alice", "a5", &post("kept")).await.unwrap();
+        processor.release_snapshot(snapshot_id).unwrap();
+        assert_eq!(datastore.lock().await.get_string("/contracts/alice/status.text").await.unwrap().as_deref(), Some("kept"));
+    }
+
+    #[tokio::test]
+    async fn test_gas_quotas() {
+        let datastore = Arc::new(Mutex::new(
+            DatastoreManager::create_in_memory().unwrap()
+        ));
+        datastore.lock().await.store_gas_quotas(&modal_datastore::GasQuotas {
+            per_commit: Some(100),
+            per_epoch: Some(200),
+        }).await.unwrap();
+        
+        let processor = ContractProcessor::new(datastore.clone()).with_epoch(3);
+        
+        // Each upload of the minimal module is charged 80 gas
+        use base64::{Engine as _, engine::general_purpose};
+        let wasm_base64 = general_purpose::STANDARD.encode([0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]);
+        let uploads = |n: usize| serde_json::json!({
+            "body": (0..n).map(|i| serde_json::json!({
+                "method": "post",
+                "path": format!("/m{}.wasm", i),
+                "value": wasm_base64,
+            })).collect::<Vec<_>>(),
+            "head": {}
+        }).to_string();
+        
+        let err = processor.process_commit("alice", "a1", &uploads(2)).await.unwrap_err();
+        assert!(err.to_string().contains("per-commit quota"), "{}", err);
+        assert_eq!(processor.gas_usage("alice", 3).await.unwrap().gas_used, 0);
+        
+        processor.process_commit("alice", "a2", &uploads(1)).await.unwrap();
+        processor.process_commit("alice", "a3", &uploads(1)).await.unwrap();
+        let err = processor.process_commit("alice", "a4", &uploads(1)).await.unwrap_err();
+        assert!(err.to_string().contains("epoch 3"), "{}", err);
+        
+        let usage = processor.gas_usage("alice", 3).await.unwrap();
+        assert_eq!((usage.gas_used, usage.commits), (160, 2));
+        
+        // Other contracts and the next epoch have their own quota
+        processor.process_commit("bob", "b1", &uploads(1)).await.unwrap();
+        let next_epoch = ContractProcessor::new(datastore.clone()).with_epoch(4);
+        next_epoch.process_commit("alice", "a4", &uploads(1)).await.unwrap();
+        let ds = datastore.lock().await;
+        assert_eq!(ContractGasUsage::find_by_epoch_multi(&ds, 3).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_governance_posts_are_checked() {
+        use modal_common::keypair::Keypair;
+        use modal_datastore::governance::ParameterChange;
+
+        let datastore = Arc::new(Mutex::new(
+            DatastoreManager::create_in_memory().unwrap()
+        ));
+        let processor = ContractProcessor::new(datastore.clone());
+        let commit = |actions: Vec<Value>| serde_json::json!({ "body": actions, "head": {} }).to_string();
+
+        let proposal = ParameterProposal {
+            id: "gas".to_string(),
+            proposer: "peer".to_string(),
+            activation_height: 100,
+            changes: ParameterChange { commit_gas_quota: Some(1000), ..Default::default() },
+            description: String::new(),
+        };
+        let keypair = Keypair::generate().unwrap();
+        let approval = ProposalApproval::sign(&proposal, &keypair).unwrap();
+
+        // An approval needs its proposal
+        assert!(processor.process_commit(GOVERNANCE_CONTRACT_ID, "c1", &commit(vec![approval.commit_action()])).await.is_err());
+        processor.process_commit(
+            GOVERNANCE_CONTRACT_ID, "c2", &commit(vec![proposal.commit_action(), approval.commit_action()])
+        ).await.unwrap();
+        assert_eq!(datastore.lock().await.governance_proposals().unwrap(), vec![proposal.clone()]);
+
+        // Proposals can't be replaced, approvals must be signed by the validator at their path
+        let mut changed = proposal.clone();
+        changed.activation_height = 200;
+        assert!(processor.process_commit(GOVERNANCE_CONTRACT_ID, "c3", &commit(vec![changed.commit_action()])).await.is_err());
+        let mut forged = ProposalApproval::sign(&proposal, &Keypair::generate().unwrap()).unwrap();
+        forged.validator = Keypair::generate().unwrap().as_public_key_id();
+        assert!(processor.process_commit(GOVERNANCE_CONTRACT_ID, "c4", &commit(vec![forged.commit_action()])).await.is_err());
+        let other = serde_json::json!({ "method": "post", "path": "/notes.text", "value": "hi" });
+        assert!(processor.process_commit(GOVERNANCE_CONTRACT_ID, "c5", &commit(vec![other])).await.is_err());
+
+        let ds = datastore.lock().await;
+        assert_eq!(ds.proposal_approvals(&proposal).unwrap(), vec![approval]);
+    }
+
+    #[tokio::test]
+    async fn test_key_rotation_posts_are_checked() {
+        use modal_datastore::key_rotations::KeyRotation;
+        use modal_common::keypair::Keypair;
+
+        let datastore = Arc::new(Mutex::new(
+            DatastoreManager::create_in_memory().unwrap()
+        ));
+        let processor = ContractProcessor::new(datastore.clone());
+        let commit = |actions: Vec<Value>| serde_json::json!({ "body": actions, "head": {} }).to_string();
+
+        let old = Keypair::generate().unwrap();
+        let new_key = Keypair::generate().unwrap().as_public_address();
+        let rotation = KeyRotation::sign(&old, &new_key, 10, None).unwrap();
+
+        // Must be signed by the old key, at the old key's path
+        let mut forged = rotation.clone();
+        forged.new_key = Keypair::generate().unwrap().as_public_address();
+        assert!(processor.process_commit(KEYS_CONTRACT_ID, "k1", &commit(vec![forged.commit_action()])).await.is_err());
+        let misplaced = serde_json::json!({ "method": "post", "path": "/rotations/other.json", "value": rotation });
+        assert!(processor.process_commit(KEYS_CONTRACT_ID, "k2", &commit(vec![misplaced])).await.is_err());
+
+        processor.process_commit(KEYS_CONTRACT_ID, "k3", &commit(vec![rotation.commit_action()])).await.unwrap();
+        assert_eq!(datastore.lock().await.key_rotations().unwrap(), vec![rotation.clone()]);
+
+        // A key is only rotated once
+        let again = KeyRotation::sign(&old, &Keypair::generate().unwrap().as_public_address(), 20, None).unwrap();
+        assert!(processor.process_commit(KEYS_CONTRACT_ID, "k4", &commit(vec![again.commit_action()])).await.is_err());
+    }
 }
@@ -0,0 +1,304 @@
+//! Parallel execution of contract commits
+//!
+//! Each commit's actions are analyzed for the contracts it reads and writes.
+//! Commits are placed in waves: a commit goes in the wave after the last
+//! earlier commit it conflicts with, so commits in the same wave touch
+//! disjoint state and run concurrently on the worker pool, while conflicting
+//! commits keep their committed order. Outcomes are returned in the order the
+//! commits were given, whatever order they finished in.
+
+use crate::contract_processor::{ContractProcessor, StateChange};
+use anyhow::Result;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// A committed contract commit to apply
+#[derive(Debug, Clone)]
+pub struct CommitTask {
+    pub contract_id: String,
+    pub commit_id: String,
+    pub commit_data: String,
+}
+
+/// State a commit reads and writes, by resource key
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccessSet {
+    pub reads: BTreeSet<String>,
+    pub writes: BTreeSet<String>,
+    /// Conflicts with every other commit (programs can touch any state)
+    pub exclusive: bool,
+}
+
+impl AccessSet {
+    /// Whether the two commits can't run at the same time
+    pub fn conflicts_with(&self, other: &AccessSet) -> bool {
+        self.exclusive
+            || other.exclusive
+            || !self.writes.is_disjoint(&other.writes)
+            || !self.writes.is_disjoint(&other.reads)
+            || !self.reads.is_disjoint(&other.writes)
+    }
+}
+
+fn contract_key(contract_id: &str) -> String {
+    format!("contract:{}", contract_id)
+}
+
+/// Read and write sets of a commit. `commit_contracts` maps the ids of the
+/// other commits being scheduled to their contract, so a RECV of a SEND in
+/// the same schedule runs after it. A MESSAGE writes to the recipient's
+/// queue, so it orders with the recipient's commits that deliver it.
+pub fn analyze_commit(task: &CommitTask, commit_contracts: &HashMap<String, String>) -> Result<AccessSet> {
+    let commit: Value = serde_json::from_str(&task.commit_data)?;
+    let body = commit.get("body")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| anyhow::anyhow!("Invalid commit structure"))?;
+
+    // Every commit is stored under its own contract
+    let mut access = AccessSet::default();
+    access.writes.insert(contract_key(&task.contract_id));
+
+    for action in body {
+        match action.get("method").and_then(|v| v.as_str()) {
+            Some("recv") => {
+                if let Some(send_commit_id) = action.get("value")
+                    .and_then(|v| v.get("send_commit_id"))
+                    .and_then(|v| v.as_str())
+                {
+                    access.writes.insert(format!("send:{}", send_commit_id));
+                    if let Some(sender) = commit_contracts.get(send_commit_id) {
+                        access.reads.insert(contract_key(sender));
+                    }
+                }
+            }
+            Some("repost") => {
+                let source = action.get("path")
+                    .and_then(|v| v.as_str())
+                    .and_then(|path| path.strip_prefix('$'))
+                    .and_then(|path| path.split_once(":/"))
+                    .map(|(source, _)| source);
+                if let Some(source) = source {
+                    access.reads.insert(contract_key(source));
+                }
+            }
+            Some("message") => {
+                if let Some(to_contract) = action.get("value")
+                    .and_then(|v| v.get("to_contract"))
+                    .and_then(|v| v.as_str())
+                {
+                    access.writes.insert(contract_key(to_contract));
+                }
+            }
+            Some("invoke") => access.exclusive = true,
+            _ => {}
+        }
+    }
+    Ok(access)
+}
+
+/// Group commits into waves of non-conflicting commits, by index into `tasks`.
+/// Commits that can't be analyzed run alone, in order.
+pub fn plan_waves(tasks: &[CommitTask]) -> Vec<Vec<usize>> {
+    let commit_contracts: HashMap<String, String> = tasks
+        .iter()
+        .map(|task| (task.commit_id.clone(), task.contract_id.clone()))
+        .collect();
+    let accesses: Vec<AccessSet> = tasks
+        .iter()
+        .map(|task| {
+            analyze_commit(task, &commit_contracts).unwrap_or(AccessSet {
+                exclusive: true,
+                ..Default::default()
+            })
+        })
+        .collect();
+
+    let mut wave_of: Vec<usize> = Vec::with_capacity(tasks.len());
+    let mut waves: Vec<Vec<usize>> = Vec::new();
+    for (i, access) in accesses.iter().enumerate() {
+        let wave = (0..i)
+            .filter(|&j| access.conflicts_with(&accesses[j]))
+            .map(|j| wave_of[j] + 1)
+            .max()
+            .unwrap_or(0);
+        wave_of.push(wave);
+        if wave == waves.len() {
+            waves.push(Vec::new());
+        }
+        waves[wave].push(i);
+    }
+    waves
+}
+
+/// Result of applying one commit
+#[derive(Debug)]
+pub struct CommitOutcome {
+    pub contract_id: String,
+    pub commit_id: String,
+    pub result: Result<Vec<StateChange>>,
+}
+
+/// Applies commits for independent contracts in parallel
+pub struct ContractScheduler {
+    processor: Arc<ContractProcessor>,
+    workers: usize,
+}
+
+impl ContractScheduler {
+    /// A scheduler running up to `workers` commits at a time
+    pub fn new(processor: ContractProcessor, workers: usize) -> Self {
+        Self {
+            processor: Arc::new(processor),
+            workers: workers.max(1),
+        }
+    }
+
+    /// A scheduler with one worker per available CPU
+    pub fn with_default_workers(processor: ContractProcessor) -> Self {
+        let workers = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        Self::new(processor, workers)
+    }
+
+    /// Apply the commits, in the order given as far as they conflict, and
+    /// return their outcomes in that order
+    pub async fn execute(&self, tasks: Vec<CommitTask>) -> Vec<CommitOutcome> {
+        let waves = plan_waves(&tasks);
+        let permits = Arc::new(Semaphore::new(self.workers));
+        let mut results: Vec<Option<Result<Vec<StateChange>>>> = (0..tasks.len()).map(|_| None).collect();
+
+        for wave in waves {
+            let mut running = JoinSet::new();
+            for index in wave {
+                let task = tasks[index].clone();
+                let processor = self.processor.clone();
+                let permits = permits.clone();
+                running.spawn(async move {
+                    let _permit = permits.acquire_owned().await;
+                    let result = processor
+                        .process_commit(&task.contract_id, &task.commit_id, &task.commit_data)
+                        .await;
+                    (index, result)
+                });
+            }
+            while let Some(joined) = running.join_next().await {
+                match joined {
+                    Ok((index, result)) => results[index] = Some(result),
+                    Err(e) => log::error!("Contract commit task failed: {}", e),
+                }
+            }
+        }
+
+        tasks
+            .into_iter()
+            .zip(results)
+            .map(|(task, result)| CommitOutcome {
+                contract_id: task.contract_id,
+                commit_id: task.commit_id,
+                result: result.unwrap_or_else(|| Err(anyhow::anyhow!("Commit was not executed"))),
+            })
+            .collect()
+    }
+}
+
+/// State changes of the successful outcomes, in commit order
+pub fn merge_state_changes(outcomes: &[CommitOutcome]) -> Vec<StateChange> {
+    outcomes
+        .iter()
+        .filter_map(|outcome| outcome.result.as_ref().ok())
+        .flatten()
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use modal_datastore::DatastoreManager;
+    use tokio::sync::Mutex;
+
+    fn task(contract_id: &str, commit_id: &str, body: Value) -> CommitTask {
+        CommitTask {
+            contract_id: contract_id.to_string(),
+            commit_id: commit_id.to_string(),
+            commit_data: serde_json::json!({ "body": body, "head": {} }).to_string(),
+        }
+    }
+
+    fn create(asset_id: &str, quantity: u64) -> Value {
+        serde_json::json!([{
+            "method": "create",
+            "value": { "asset_id": asset_id, "quantity": quantity, "divisibility": 1 }
+        }])
+    }
+
+    fn send(asset_id: &str, to_contract: &str, amount: u64) -> Value {
+        serde_json::json!([{
+            "method": "send",
+            "value": { "asset_id": asset_id, "to_contract": to_contract, "amount": amount }
+        }])
+    }
+
+    fn recv(send_commit_id: &str) -> Value {
+        serde_json::json!([{ "method": "recv", "value": { "send_commit_id": send_commit_id } }])
+    }
+
+    #[test]
+    fn test_plan_waves() {
+        let tasks = vec![
+            task("alice", "a1", create("token", 100)),
+            task("bob", "b1", create("coin", 50)),
+            task("alice", "a2", send("token", "carol", 10)),
+            task("carol", "c1", recv("a2")),
+            task("dave", "d1", serde_json::json!([{ "method": "post", "path": "/x.text", "value": "1" }])),
+            task("erin", "e1", serde_json::json!([{ "method": "repost", "path": "$dave:/x.text", "value": "1" }])),
+            task("frank", "f1", serde_json::json!([{ "method": "invoke", "path": "/p.wasm", "value": { "args": {} } }])),
+            task("bob", "b2", serde_json::json!("not a body")),
+            task("gina", "g1", serde_json::json!([{ "method": "message", "value": { "to_contract": "dave", "payload": {} } }])),
+        ];
+
+        assert_eq!(
+            plan_waves(&tasks),
+            vec![
+                vec![0, 1, 4],
+                // alice's send after its create, erin's repost after dave's post
+                vec![2, 5],
+                // carol's recv after alice's send
+                vec![3],
+                // invoke and the commit that can't be analyzed run alone
+                vec![6],
+                vec![7],
+                // gina's message to dave after dave's post
+                vec![8],
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_matches_sequential_order() {
+        let datastore = Arc::new(Mutex::new(DatastoreManager::create_in_memory().unwrap()));
+        let scheduler = ContractScheduler::new(ContractProcessor::new(datastore.clone()), 4);
+
+        let tasks = vec![
+            task("alice", "a1", create("token", 100)),
+            task("bob", "b1", create("coin", 50)),
+            task("alice", "a2", send("token", "carol", 10)),
+            task("bob", "b2", send("coin", "carol", 60)),
+            task("carol", "c1", recv("a2")),
+            task("carol", "c2", recv("a2")),
+        ];
+        let outcomes = scheduler.execute(tasks).await;
+
+        let commit_ids: Vec<&str> = outcomes.iter().map(|o| o.commit_id.as_str()).collect();
+        assert_eq!(commit_ids, vec!["a1", "b1", "a2", "b2", "c1", "c2"]);
+        // Bob can't send more than he has, and a SEND is received once
+        assert!(outcomes[3].result.is_err());
+        assert!(outcomes[5].result.is_err());
+
+        let changes = merge_state_changes(&outcomes);
+        assert_eq!(changes.len(), 4);
+        assert!(matches!(&changes[3], StateChange::AssetReceived { to_contract, amount: 10, .. } if to_contract == "carol"));
+    }
+}
@@ -1,3 +1,4 @@
+use modal_common::error_codes::{ErrorCode, HasErrorCode};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -23,4 +24,15 @@ pub enum ValidatorError {
 
 pub type Result<T> = std::result::Result<T, ValidatorError>;
 
-
+impl HasErrorCode for ValidatorError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            ValidatorError::ObserverError(e) => e.error_code(),
+            ValidatorError::DatastoreError(e) => e.error_code(),
+            ValidatorError::InitializationFailed(_) => ErrorCode::ValidatorInitFailed,
+            ValidatorError::ObservationFailed(_) => ErrorCode::ObservationFailed,
+            ValidatorError::Custom(_) => ErrorCode::Internal,
+            ValidatorError::ConsensusError(_) => ErrorCode::ConsensusFailed,
+        }
+    }
+}
@@ -13,19 +13,29 @@
 //!
 //! - `validator`: Observer-based validator (legacy)
 //! - `shoal_validator`: Shoal consensus-based validator (new)
+//! - `sequencer`: ordered log of the transactions the validator commits
+//! - `mempool`: transactions waiting to be batched, by fee and nonce
 
 pub mod validator;
 pub mod shoal_validator;
+pub mod sequencer;
+pub mod mempool;
 pub mod error;
 pub mod contract_processor;
+pub mod contract_scheduler;
+pub mod state_snapshot;
 pub mod predicate_executor;
 pub mod program_executor;
 pub mod modality_processor;
 
 pub use validator::{Validator, ValidatorConfig};
 pub use shoal_validator::{ShoalValidator, ShoalValidatorConfig, NarwhalConfig};
+pub use sequencer::Sequencer;
+pub use mempool::{Admission, Mempool, MempoolConfig, MempoolEntry, MempoolError, MempoolStats, MempoolTransaction};
 pub use error::{Result, ValidatorError};
 pub use contract_processor::{ContractProcessor, StateChange};
+pub use contract_scheduler::{CommitOutcome, CommitTask, ContractScheduler};
+pub use state_snapshot::{SnapshotId, StateSnapshot};
 pub use predicate_executor::PredicateExecutor;
 pub use program_executor::ProgramExecutor;
 pub use modality_processor::{ModalityContractProcessor, ModalityStateChange, ModalityError};
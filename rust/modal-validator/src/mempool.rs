@@ -0,0 +1,465 @@
+//! Transactions waiting to be batched by the Narwhal workers
+//!
+//! Each sender's transactions are held in nonce order and only the run
+//! starting at the sender's next nonce is ready; a transaction after a gap
+//! waits until the gap is filled. Ready transactions are handed out highest
+//! fee first, one sender's head at a time, so a sender's transactions never
+//! leave out of order. A transaction with the nonce of one already pending
+//! replaces it if it pays at least `replacement_bump_percent` more. When the
+//! pool is full, a new transaction evicts the cheapest sender tail if it pays
+//! more than it, and is rejected otherwise.
+//!
+//! The pool doesn't see account state, so a sender's next nonce is the one
+//! after the last transaction it handed out; until then it is the sender's
+//! lowest pending nonce. Senders are peer IDs, and only transactions the
+//! sender signed are admitted, so nobody else can fill or replace its queue.
+
+use modal_common::keypair::Keypair;
+use modal_validator_consensus::narwhal::Transaction;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
+use thiserror::Error;
+
+/// Mempool limits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MempoolConfig {
+    /// Most transactions held at once
+    pub max_transactions: usize,
+    /// Most transactions held for one sender
+    pub max_per_sender: usize,
+    /// How much more a replacement must pay, in percent of the fee it replaces
+    pub replacement_bump_percent: u64,
+}
+
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        Self {
+            max_transactions: 10_000,
+            max_per_sender: 64,
+            replacement_bump_percent: 10,
+        }
+    }
+}
+
+/// A transaction submitted for sequencing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MempoolTransaction {
+    pub sender: String,
+    pub nonce: u64,
+    pub fee: u64,
+    pub data: Vec<u8>,
+    pub timestamp: u64,
+    /// The sender's signature over `signing_payload`
+    pub signature: String,
+}
+
+impl MempoolTransaction {
+    /// What the sender signs: everything but the arrival timestamp
+    pub fn signing_payload(&self) -> serde_json::Value {
+        serde_json::json!({
+            "sender": self.sender,
+            "nonce": self.nonce,
+            "fee": self.fee,
+            "data": hex::encode(&self.data),
+        })
+    }
+
+    /// Sign as the sender, which must be `keypair`'s peer ID
+    pub fn sign(&mut self, keypair: &Keypair) -> anyhow::Result<()> {
+        self.signature = keypair.sign_json(&self.signing_payload())?;
+        Ok(())
+    }
+
+    /// Whether the sender signed the transaction
+    pub fn verify_signature(&self) -> bool {
+        Keypair::from_public_key(&self.sender, "ed25519")
+            .and_then(|key| key.verify_json(&self.signature, &self.signing_payload()))
+            .unwrap_or(false)
+    }
+
+    /// The transaction as handed to a worker
+    pub fn into_transaction(self) -> Transaction {
+        Transaction {
+            data: self.data,
+            timestamp: self.timestamp,
+        }
+    }
+}
+
+/// How a transaction got into the pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Admission {
+    Added,
+    /// It replaced the pending transaction with the same nonce
+    Replaced { previous_fee: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum MempoolError {
+    #[error("Transaction is not signed by its sender {sender}")]
+    InvalidSignature { sender: String },
+
+    #[error("Nonce {nonce} of {sender} is below its next nonce {next_nonce}")]
+    StaleNonce { sender: String, nonce: u64, next_nonce: u64 },
+
+    #[error("Replacement fee too low: need at least {required}")]
+    FeeTooLow { required: u64 },
+
+    #[error("Too many pending transactions from {sender}")]
+    SenderFull { sender: String },
+
+    #[error("Mempool full: need a fee above {min_fee}")]
+    Full { min_fee: u64 },
+}
+
+impl modal_common::error_codes::HasErrorCode for MempoolError {
+    fn error_code(&self) -> modal_common::error_codes::ErrorCode {
+        use modal_common::error_codes::ErrorCode;
+        match self {
+            MempoolError::InvalidSignature { .. } => ErrorCode::InvalidSignature,
+            MempoolError::StaleNonce { .. } => ErrorCode::StaleNonce,
+            MempoolError::FeeTooLow { .. } => ErrorCode::FeeTooLow,
+            MempoolError::SenderFull { .. } => ErrorCode::SenderFull,
+            MempoolError::Full { .. } => ErrorCode::MempoolFull,
+        }
+    }
+}
+
+/// Counters since the pool was created
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MempoolStats {
+    pub pending: usize,
+    pub ready: usize,
+    pub senders: usize,
+    pub accepted: u64,
+    pub replaced: u64,
+    pub evicted: u64,
+    pub rejected: u64,
+    /// Handed to the workers
+    pub drained: u64,
+}
+
+/// A pending transaction and whether it can be batched now
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MempoolEntry {
+    #[serde(flatten)]
+    pub transaction: MempoolTransaction,
+    pub ready: bool,
+}
+
+#[derive(Debug)]
+struct Pending {
+    tx: MempoolTransaction,
+    /// Order of arrival, breaking ties between equal fees
+    arrival: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct Mempool {
+    config: MempoolConfig,
+    pending: HashMap<String, BTreeMap<u64, Pending>>,
+    /// Nonce after the last transaction handed out, per sender
+    next_nonces: HashMap<String, u64>,
+    len: usize,
+    arrivals: u64,
+    stats: MempoolStats,
+}
+
+impl Mempool {
+    pub fn new(config: MempoolConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn set_config(&mut self, config: MempoolConfig) {
+        self.config = config;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Add a transaction signed by its sender, replacing or evicting as needed
+    pub fn submit(&mut self, tx: MempoolTransaction) -> Result<Admission, MempoolError> {
+        let result = self.admit(tx);
+        match result {
+            Ok(Admission::Added) => self.stats.accepted += 1,
+            Ok(Admission::Replaced { .. }) => self.stats.replaced += 1,
+            Err(_) => self.stats.rejected += 1,
+        }
+        result
+    }
+
+    fn admit(&mut self, tx: MempoolTransaction) -> Result<Admission, MempoolError> {
+        if !tx.verify_signature() {
+            return Err(MempoolError::InvalidSignature { sender: tx.sender });
+        }
+        if let Some(&next_nonce) = self.next_nonces.get(&tx.sender) {
+            if tx.nonce < next_nonce {
+                return Err(MempoolError::StaleNonce { sender: tx.sender, nonce: tx.nonce, next_nonce });
+            }
+        }
+        self.arrivals += 1;
+        let arrival = self.arrivals;
+
+        if let Some(existing) = self.pending.get_mut(&tx.sender).and_then(|queue| queue.get_mut(&tx.nonce)) {
+            let previous_fee = existing.tx.fee;
+            let bump = (previous_fee.saturating_mul(self.config.replacement_bump_percent) / 100).max(1);
+            let required = previous_fee.saturating_add(bump);
+            if tx.fee < required {
+                return Err(MempoolError::FeeTooLow { required });
+            }
+            *existing = Pending { tx, arrival };
+            return Ok(Admission::Replaced { previous_fee });
+        }
+
+        let sender_len = self.pending.get(&tx.sender).map_or(0, BTreeMap::len);
+        if sender_len >= self.config.max_per_sender {
+            return Err(MempoolError::SenderFull { sender: tx.sender });
+        }
+        if self.len >= self.config.max_transactions {
+            let Some((victim, nonce, min_fee)) = self.cheapest_tail() else {
+                return Err(MempoolError::Full { min_fee: 0 });
+            };
+            if tx.fee <= min_fee {
+                return Err(MempoolError::Full { min_fee });
+            }
+            self.remove(&victim, nonce);
+            self.stats.evicted += 1;
+        }
+
+        self.pending.entry(tx.sender.clone()).or_default().insert(tx.nonce, Pending { tx, arrival });
+        self.len += 1;
+        Ok(Admission::Added)
+    }
+
+    /// The sender whose last transaction pays least, newest first among
+    /// equal fees. Evicting tails keeps every sender's run gap-free.
+    fn cheapest_tail(&self) -> Option<(String, u64, u64)> {
+        self.pending
+            .iter()
+            .filter_map(|(sender, queue)| queue.last_key_value().map(|(nonce, p)| (sender, *nonce, p)))
+            .min_by_key(|(_, _, p)| (p.tx.fee, Reverse(p.arrival)))
+            .map(|(sender, nonce, p)| (sender.clone(), nonce, p.tx.fee))
+    }
+
+    fn remove(&mut self, sender: &str, nonce: u64) -> Option<MempoolTransaction> {
+        let queue = self.pending.get_mut(sender)?;
+        let removed = queue.remove(&nonce)?;
+        if queue.is_empty() {
+            self.pending.remove(sender);
+        }
+        self.len -= 1;
+        Some(removed.tx)
+    }
+
+    /// Ready transactions in the order they'd be handed out: highest fee
+    /// first among the senders' next transactions
+    fn ready_order(&self) -> Vec<(&str, u64)> {
+        let mut heads = BinaryHeap::new();
+        let mut runs = HashMap::new();
+        for (sender, queue) in &self.pending {
+            let mut run = queue.iter().peekable();
+            let Some(&(&first, _)) = run.peek() else { continue };
+            if self.next_nonces.get(sender).is_some_and(|&next| next != first) {
+                continue;
+            }
+            let mut expected = first;
+            let run: Vec<&Pending> = run
+                .take_while(|&(&nonce, _)| {
+                    let contiguous = nonce == expected;
+                    expected += 1;
+                    contiguous
+                })
+                .map(|(_, p)| p)
+                .collect();
+            heads.push((run[0].tx.fee, Reverse(run[0].arrival), sender.as_str(), 0usize));
+            runs.insert(sender.as_str(), run);
+        }
+
+        let mut order = Vec::new();
+        while let Some((_, _, sender, index)) = heads.pop() {
+            let run = &runs[sender];
+            order.push((sender, run[index].tx.nonce));
+            if let Some(next) = run.get(index + 1) {
+                heads.push((next.tx.fee, Reverse(next.arrival), sender, index + 1));
+            }
+        }
+        order
+    }
+
+    /// Take up to `max_count` ready transactions totalling at most
+    /// `max_bytes` of data, in priority order
+    pub fn take_ready(&mut self, max_count: usize, max_bytes: usize) -> Vec<MempoolTransaction> {
+        let order: Vec<(String, u64)> = self
+            .ready_order()
+            .into_iter()
+            .map(|(sender, nonce)| (sender.to_string(), nonce))
+            .collect();
+        let mut taken = Vec::new();
+        let mut bytes = 0;
+        for (sender, nonce) in order {
+            if taken.len() >= max_count {
+                break;
+            }
+            let size = self.pending[&sender][&nonce].tx.data.len();
+            // A prefix of the order never leaves a sender's nonces out of order
+            if bytes + size > max_bytes && !taken.is_empty() {
+                break;
+            }
+            bytes += size;
+            if let Some(tx) = self.remove(&sender, nonce) {
+                self.next_nonces.insert(sender, nonce + 1);
+                taken.push(tx);
+            }
+        }
+        self.stats.drained += taken.len() as u64;
+        taken
+    }
+
+    /// Up to `limit` pending transactions, ready ones first in priority
+    /// order, then the ones waiting on a nonce gap
+    pub fn contents(&self, limit: usize) -> Vec<MempoolEntry> {
+        let order = self.ready_order();
+        let ready: HashSet<(&str, u64)> = order.iter().copied().collect();
+        let mut waiting = Vec::new();
+        for (sender, queue) in &self.pending {
+            waiting.extend(queue.values().map(|p| &p.tx).filter(|tx| !ready.contains(&(sender.as_str(), tx.nonce))));
+        }
+        waiting.sort_by(|a: &&MempoolTransaction, b| (&a.sender, a.nonce).cmp(&(&b.sender, b.nonce)));
+
+        order
+            .iter()
+            .map(|(sender, nonce)| MempoolEntry {
+                transaction: self.pending[*sender][nonce].tx.clone(),
+                ready: true,
+            })
+            .chain(waiting.into_iter().map(|tx| MempoolEntry { transaction: tx.clone(), ready: false }))
+            .take(limit)
+            .collect()
+    }
+
+    pub fn stats(&self) -> MempoolStats {
+        MempoolStats {
+            pending: self.len,
+            ready: self.ready_order().len(),
+            senders: self.pending.len(),
+            ..self.stats.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SENDERS: [&str; 3] = ["alice", "bob", "carol"];
+
+    /// Deterministic key of a test sender
+    fn key(name: &str) -> Keypair {
+        use libp2p_identity::ed25519;
+        use modal_common::keypair::KeypairOrPublicKey;
+        let mut secret_bytes = [0u8; 32];
+        secret_bytes[0] = SENDERS.iter().position(|sender| *sender == name).unwrap() as u8 + 1;
+        let secret = ed25519::SecretKey::try_from_bytes(secret_bytes).expect("valid secret key");
+        Keypair::new(KeypairOrPublicKey::Keypair(ed25519::Keypair::from(secret).into()))
+    }
+
+    fn name(sender: &str) -> &'static str {
+        SENDERS.into_iter().find(|name| key(name).as_public_address() == sender).unwrap()
+    }
+
+    fn tx(sender: &str, nonce: u64, fee: u64) -> MempoolTransaction {
+        let mut tx = MempoolTransaction {
+            sender: key(sender).as_public_address(),
+            nonce,
+            fee,
+            data: vec![nonce as u8],
+            timestamp: 1000,
+            signature: String::new(),
+        };
+        tx.sign(&key(sender)).unwrap();
+        tx
+    }
+
+    fn ids(txs: &[MempoolTransaction]) -> Vec<(&str, u64)> {
+        txs.iter().map(|tx| (name(&tx.sender), tx.nonce)).collect()
+    }
+
+    #[test]
+    fn test_requires_sender_signature() {
+        let mut pool = Mempool::new(MempoolConfig::default());
+        let mut forged = tx("alice", 0, 10);
+        forged.sender = key("bob").as_public_address();
+        assert!(matches!(pool.submit(forged), Err(MempoolError::InvalidSignature { .. })));
+
+        let mut bumped = tx("alice", 0, 10);
+        bumped.fee = 1000;
+        assert!(matches!(pool.submit(bumped), Err(MempoolError::InvalidSignature { .. })));
+        assert!(pool.is_empty());
+        assert_eq!(pool.stats().rejected, 2);
+
+        pool.submit(tx("alice", 0, 10)).unwrap();
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn test_fee_priority_keeps_nonce_order() {
+        let mut pool = Mempool::new(MempoolConfig::default());
+        pool.submit(tx("alice", 0, 5)).unwrap();
+        pool.submit(tx("alice", 1, 50)).unwrap();
+        pool.submit(tx("bob", 0, 10)).unwrap();
+        // Waits on nonce 3
+        pool.submit(tx("bob", 2, 100)).unwrap();
+
+        assert_eq!(pool.stats().ready, 3);
+        let taken = pool.take_ready(10, usize::MAX);
+        assert_eq!(ids(&taken), vec![("bob", 0), ("alice", 0), ("alice", 1)]);
+        assert_eq!(pool.len(), 1);
+
+        // Handed-out nonces can't come back
+        assert!(matches!(pool.submit(tx("alice", 1, 500)), Err(MempoolError::StaleNonce { .. })));
+        pool.submit(tx("bob", 1, 1)).unwrap();
+        assert_eq!(ids(&pool.take_ready(10, usize::MAX)), vec![("bob", 1), ("bob", 2)]);
+    }
+
+    #[test]
+    fn test_replace_by_fee() {
+        let mut pool = Mempool::new(MempoolConfig::default());
+        pool.submit(tx("alice", 0, 100)).unwrap();
+        assert_eq!(pool.submit(tx("alice", 0, 105)), Err(MempoolError::FeeTooLow { required: 110 }));
+        assert_eq!(pool.submit(tx("alice", 0, 110)), Ok(Admission::Replaced { previous_fee: 100 }));
+        assert_eq!(pool.len(), 1);
+
+        let stats = pool.stats();
+        assert_eq!((stats.accepted, stats.replaced, stats.rejected), (1, 1, 1));
+        assert_eq!(pool.take_ready(1, usize::MAX)[0].fee, 110);
+    }
+
+    #[test]
+    fn test_full_pool_evicts_cheapest_tail() {
+        let mut pool = Mempool::new(MempoolConfig {
+            max_transactions: 3,
+            max_per_sender: 2,
+            ..Default::default()
+        });
+        pool.submit(tx("alice", 0, 10)).unwrap();
+        pool.submit(tx("alice", 1, 20)).unwrap();
+        pool.submit(tx("bob", 0, 5)).unwrap();
+        assert!(matches!(pool.submit(tx("alice", 2, 99)), Err(MempoolError::SenderFull { .. })));
+        assert_eq!(pool.submit(tx("carol", 0, 5)), Err(MempoolError::Full { min_fee: 5 }));
+
+        pool.submit(tx("carol", 0, 6)).unwrap();
+        assert_eq!(pool.stats().evicted, 1);
+        let contents = pool.contents(10);
+        assert_eq!(contents.len(), 3);
+        assert!(contents.iter().all(|entry| name(&entry.transaction.sender) != "bob"));
+    }
+}
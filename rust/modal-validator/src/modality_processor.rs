@@ -72,6 +72,20 @@ impl std::fmt::Display for ModalityError {
 
 impl std::error::Error for ModalityError {}
 
+impl modal_common::error_codes::HasErrorCode for ModalityError {
+    fn error_code(&self) -> modal_common::error_codes::ErrorCode {
+        use modal_common::error_codes::ErrorCode;
+        match self {
+            ModalityError::InvalidSignature { .. } => ErrorCode::InvalidSignature,
+            ModalityError::UnauthorizedParty { .. } => ErrorCode::UnauthorizedParty,
+            ModalityError::FormulaViolation { .. } => ErrorCode::RuleViolation,
+            ModalityError::InvalidPhase { .. } => ErrorCode::InvalidPhase,
+            ModalityError::ContractNotFound { .. } => ErrorCode::ContractNotFound,
+            ModalityError::ParseError { .. } => ErrorCode::ParseError,
+        }
+    }
+}
+
 /// Processes Modality contract commits
 pub struct ModalityContractProcessor {
     datastore: Arc<Mutex<DatastoreManager>>,
@@ -0,0 +1,113 @@
+//! Sequencer output: the append-only log of committed transactions
+//!
+//! Every commit's batches are queued in canonical order. Transactions are
+//! appended to the log with consecutive sequence numbers as the batches
+//! become available locally; a batch that hasn't arrived yet holds back
+//! everything after it, so each validator's log has the same order. Appended
+//! entries are stored in the datastore and broadcast to subscribers.
+
+use crate::error::Result;
+use modal_datastore::models::SequencedLog;
+use modal_datastore::DatastoreManager;
+use modal_validator_consensus::narwhal::WorkerPool;
+use modal_validator_consensus::shoal::canonical::BatchRef;
+use std::collections::VecDeque;
+use tokio::sync::broadcast;
+
+/// Entries a subscriber can fall behind by before it misses some
+const SUBSCRIBER_CAPACITY: usize = 1024;
+
+/// Assigns sequence numbers to committed transactions
+pub struct Sequencer {
+    next_seq: u64,
+    /// Committed batches not yet appended, in order
+    pending: VecDeque<BatchRef>,
+    sender: broadcast::Sender<SequencedLog>,
+}
+
+impl Default for Sequencer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sequencer {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(SUBSCRIBER_CAPACITY);
+        Self {
+            next_seq: 0,
+            pending: VecDeque::new(),
+            sender,
+        }
+    }
+
+    /// Sequence number the next entry gets
+    pub fn next_seq(&self) -> u64 {
+        self.next_seq
+    }
+
+    /// Continue numbering after a log recovered from the datastore
+    pub fn resume_at(&mut self, next_seq: u64) {
+        self.next_seq = next_seq;
+    }
+
+    /// Committed batches waiting to be appended
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Receive entries as they're appended
+    pub fn subscribe(&self) -> broadcast::Receiver<SequencedLog> {
+        self.sender.subscribe()
+    }
+
+    /// Queue a commit's batches, in canonical order
+    pub fn enqueue(&mut self, batches: Vec<BatchRef>) {
+        self.pending.extend(batches);
+    }
+
+    /// Append the transactions of queued batches that are available, up to
+    /// the first one that isn't
+    pub async fn drain(
+        &mut self,
+        workers: &WorkerPool,
+        datastore: Option<&DatastoreManager>,
+    ) -> Result<Vec<SequencedLog>> {
+        let mut appended = Vec::new();
+        while let Some(batch_ref) = self.pending.front() {
+            let Some(batch) = workers.get_batch(batch_ref.digest, batch_ref.worker_id).await else {
+                log::debug!(
+                    "sequencer waiting for batch {} from round {}",
+                    hex::encode(batch_ref.digest),
+                    batch_ref.round
+                );
+                break;
+            };
+            let sequenced_at = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            for tx in batch.transactions {
+                let entry = SequencedLog {
+                    seq: self.next_seq,
+                    round: batch_ref.round,
+                    author: batch_ref.author.to_string(),
+                    certificate: hex::encode(batch_ref.certificate),
+                    batch: hex::encode(batch_ref.digest),
+                    data: hex::encode(&tx.data),
+                    timestamp: tx.timestamp,
+                    sequenced_at,
+                };
+                if let Some(datastore) = datastore {
+                    entry.append_multi(datastore).await?;
+                }
+                self.next_seq += 1;
+                // Nobody listening is fine
+                let _ = self.sender.send(entry.clone());
+                appended.push(entry);
+            }
+            self.pending.pop_front();
+        }
+        Ok(appended)
+    }
+}
@@ -1,17 +1,26 @@
 use crate::error::{Result, ValidatorError};
+use crate::contract_scheduler::CommitTask;
+use crate::mempool::{Admission, Mempool, MempoolConfig, MempoolEntry, MempoolError, MempoolStats, MempoolTransaction};
+use crate::sequencer::Sequencer;
 use modal_datastore::DatastoreManager;
+use modal_datastore::models::SequencedLog;
 use modal_validator_consensus::narwhal::{
-    Certificate, Committee, Primary, PublicKey, Transaction, Validator, Worker,
-    SyncClient, SyncRequest, SyncResponse,
+    AdmissionConfig, AdmissionStats, Certificate, CertificateDigest, Committee, CommitteeChange, CommitteeSchedule, Header, Primary, PublicKey,
+    ReconfigurationCertificate, RoundTimer, RoundTimerConfig, StateHandoff, Transaction, Validator, Vote,
+    WorkerMessage, WorkerPool, SyncClient, SyncRequest, SyncResponse,
 };
+use modal_validator_consensus::narwhal::certificate;
 use modal_validator_consensus::narwhal::dag::DAG;
-use modal_validator_consensus::shoal::ReputationConfig;
+use modal_validator_consensus::shoal::{ReputationConfig, ShoalConfig};
 use modal_validator_consensus::shoal::reputation::ReputationManager;
 use modal_validator_consensus::shoal::consensus::ShoalConsensus;
 use modal_validator_consensus::shoal::ordering::OrderingEngine;
+use modal_validator_consensus::shoal::canonical::{self, CommittedBatch};
+use std::collections::BTreeSet;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc, Mutex, RwLock};
 
 /// Configuration for Shoal consensus
 #[derive(Debug, Clone)]
@@ -27,6 +36,13 @@ pub struct ShoalValidatorConfig {
     
     /// Shoal reputation configuration
     pub reputation_config: ReputationConfig,
+    
+    /// Shoal leader selection configuration
+    pub shoal_config: ShoalConfig,
+    
+    /// Key this validator signs certificate votes with, when the committee has BLS keys
+    #[cfg(feature = "bls")]
+    pub bls_keypair: Option<modal_common::bls::BlsKeypair>,
 }
 
 /// Narwhal-specific configuration
@@ -40,6 +56,15 @@ pub struct NarwhalConfig {
     
     /// Maximum batch size in bytes
     pub max_batch_bytes: usize,
+    
+    /// Round timeouts, adapted to observed certificate latency
+    pub round_timer: RoundTimerConfig,
+    
+    /// Limits of the mempool feeding the workers
+    pub mempool: MempoolConfig,
+    
+    /// Checks transactions pass before the workers batch them
+    pub admission: AdmissionConfig,
 }
 
 impl Default for NarwhalConfig {
@@ -48,6 +73,9 @@ impl Default for NarwhalConfig {
             workers_per_validator: 4,
             batch_size: 1000,
             max_batch_bytes: 512 * 1024, // 512KB
+            round_timer: RoundTimerConfig::default(),
+            mempool: MempoolConfig::default(),
+            admission: AdmissionConfig::default(),
         }
     }
 }
@@ -92,6 +120,9 @@ impl ShoalValidatorConfig {
             committee,
             narwhal_config: NarwhalConfig::default(),
             reputation_config: ReputationConfig::default(),
+            shoal_config: ShoalConfig::default(),
+            #[cfg(feature = "bls")]
+            bls_keypair: None,
         }
     }
 
@@ -166,8 +197,48 @@ impl ShoalValidatorConfig {
             committee,
             narwhal_config: NarwhalConfig::default(),
             reputation_config: ReputationConfig::default(),
+            shoal_config: ShoalConfig::default(),
+            #[cfg(feature = "bls")]
+            bls_keypair: None,
         })
     }
+    
+    /// Register the committee's BLS keys, each with its proof of possession,
+    /// and the keypair this validator signs votes with.
+    ///
+    /// Keys of peers outside the committee are ignored. Once every validator
+    /// has a key, certificates carry an aggregate of the voters' signatures,
+    /// so the keypair must match the key registered for this validator.
+    #[cfg(feature = "bls")]
+    pub fn with_bls_keys(
+        mut self,
+        keypair: Option<modal_common::bls::BlsKeypair>,
+        keys: impl IntoIterator<Item = (PublicKey, Vec<u8>, Vec<u8>)>,
+    ) -> Result<Self> {
+        for (validator, key, proof) in keys {
+            if self.committee.validators.contains_key(&validator) {
+                self.committee.add_bls_key(&validator, key, &proof)
+                    .map_err(|e| ValidatorError::InitializationFailed(
+                        format!("invalid BLS key for {}: {}", validator, e)
+                    ))?;
+            }
+        }
+        
+        if let Some(registered) = self.committee.bls_key(&self.validator_key) {
+            match &keypair {
+                Some(keypair) if keypair.public_key_bytes() == registered => {}
+                Some(_) => return Err(ValidatorError::InitializationFailed(
+                    "BLS keypair does not match the key registered for this validator".to_string()
+                )),
+                None => return Err(ValidatorError::InitializationFailed(
+                    "a BLS key is registered for this validator but no BLS keypair is configured".to_string()
+                )),
+            }
+        }
+        
+        self.bls_keypair = keypair;
+        Ok(self)
+    }
 }
 
 /// Shoal-based validator implementation
@@ -185,7 +256,10 @@ pub struct ShoalValidator {
     primary: Arc<Mutex<Primary>>,
     
     /// Worker nodes
-    workers: Vec<Arc<Mutex<Worker>>>,
+    workers: WorkerPool,
+    
+    /// Transactions waiting for the workers, by fee
+    mempool: Arc<Mutex<Mempool>>,
     
     /// Shoal consensus engine
     consensus: Arc<Mutex<ShoalConsensus>>,
@@ -195,6 +269,15 @@ pub struct ShoalValidator {
     
     /// Sync client for DAG synchronization
     sync_client: SyncClient,
+    
+    /// Active committee and scheduled reconfigurations
+    schedule: Mutex<CommitteeSchedule>,
+    
+    /// Ordered log of committed transactions
+    sequencer: Mutex<Sequencer>,
+    
+    /// Timeout for the current round
+    round_timer: Mutex<RoundTimer>,
 }
 
 impl ShoalValidator {
@@ -207,16 +290,13 @@ impl ShoalValidator {
         let dag = Arc::new(RwLock::new(DAG::new()));
         
         // Create workers
-        let mut workers = Vec::new();
-        for i in 0..config.narwhal_config.workers_per_validator {
-            let worker = Worker::new(
-                i as u32,
-                config.validator_key,
-                config.narwhal_config.batch_size,
-                config.narwhal_config.max_batch_bytes,
-            );
-            workers.push(Arc::new(Mutex::new(worker)));
-        }
+        let workers = WorkerPool::new(
+            config.validator_key,
+            config.narwhal_config.workers_per_validator,
+            config.narwhal_config.batch_size,
+            config.narwhal_config.max_batch_bytes,
+        )
+        .with_admission(config.narwhal_config.admission.clone());
         
         // Create primary
         let primary = Primary::new(
@@ -238,7 +318,7 @@ impl ShoalValidator {
                 dag.clone(),
                 reputation,
                 config.committee.clone(),
-            );
+            ).with_config(config.shoal_config.clone());
             Arc::new(Mutex::new(cons))
         };
         
@@ -248,6 +328,13 @@ impl ShoalValidator {
         // Create sync client
         let sync_client = SyncClient::new(dag.clone());
         
+        let schedule = Mutex::new(CommitteeSchedule::new(config.committee.clone()));
+        
+        let mut round_timer = RoundTimer::new(config.narwhal_config.round_timer.clone());
+        round_timer.start_round(0, Instant::now());
+        
+        let mempool = Arc::new(Mutex::new(Mempool::new(config.narwhal_config.mempool.clone())));
+        
         log::info!(
             "created Shoal validator (multi-store) for validator {:?}",
             config.validator_key
@@ -259,73 +346,322 @@ impl ShoalValidator {
             dag,
             primary,
             workers,
+            mempool,
             consensus,
             ordering,
             sync_client,
+            schedule,
+            sequencer: Mutex::new(Sequencer::new()),
+            round_timer: Mutex::new(round_timer),
         })
     }
     
+    /// Create a validator joining the committee described by `handoff`.
+    /// It starts from the handed-off DAG tips and proposes from the
+    /// committee's activation round.
+    pub async fn join(
+        datastore_manager: Arc<Mutex<DatastoreManager>>,
+        mut config: ShoalValidatorConfig,
+        handoff: StateHandoff,
+    ) -> Result<Self> {
+        let mut committee = handoff.committee();
+        committee.inherit_bls_keys(&config.committee);
+        config.committee = committee.clone();
+        let validator = Self::new(datastore_manager, config).await?;
+        
+        let start_round = handoff.start_round();
+        *validator.schedule.lock().await = CommitteeSchedule::with_epoch(
+            committee,
+            handoff.epoch,
+            handoff.activation_round,
+        );
+        {
+            let mut dag = validator.dag.write().await;
+            for tip in handoff.tips {
+                dag.graft(tip)?;
+            }
+        }
+        validator.primary.lock().await.current_round = start_round;
+        validator.consensus.lock().await.state.current_round = start_round;
+        
+        log::info!(
+            "joining committee for epoch {} at round {}",
+            handoff.epoch,
+            start_round
+        );
+        Ok(validator)
+    }
+    
     /// Initialize the validator by loading existing state
+    ///
+    /// With persistence enabled, this restores the certificates, batches and
+    /// consensus state stored before a restart, and resumes proposing at a
+    /// round this validator has no certificate in yet.
     pub async fn initialize(&self) -> Result<()> {
-        // TODO: Load DAG and consensus state from datastore
+        if let Some(ds) = &self.datastore_manager {
+            let ds = ds.lock().await;
+            #[cfg(feature = "persistence")]
+            self.recover(&ds).await?;
+            let next_seq = SequencedLog::next_seq_multi(&ds).await?;
+            self.sequencer.lock().await.resume_at(next_seq);
+        }
         log::info!("Shoal validator initialized");
         Ok(())
     }
     
+    /// Rebuild the in-memory DAG, consensus state and worker batches from the datastore
+    #[cfg(feature = "persistence")]
+    async fn recover(&self, datastore: &DatastoreManager) -> Result<()> {
+        use modal_validator_consensus::persistence::recovery::{
+            recover_dag_multi, verify_dag_consistency, RecoveryStrategy,
+        };
+        
+        let result = recover_dag_multi(datastore, RecoveryStrategy::Hybrid).await?;
+        if result.certificates_loaded == 0 && result.batches.is_empty() {
+            return Ok(());
+        }
+        verify_dag_consistency(&result.dag)?;
+        
+        let resume_round = result.resume_round(&self.config.validator_key);
+        let last_committed_round = result.last_committed_round();
+        let batch_count = result.batches.len();
+        
+        for (author, batch) in result.batches {
+            self.workers.handle_message(WorkerMessage::Batch { author, batch }).await?;
+        }
+        *self.dag.write().await = result.dag;
+        
+        let mut consensus = self.consensus.lock().await;
+        if let Some(state) = result.consensus_state {
+            consensus.state = state;
+        }
+        if let Some(state) = result.reputation_state {
+            consensus.reputation.restore_state(state);
+        }
+        consensus.state.current_round = resume_round;
+        self.primary.lock().await.current_round = resume_round;
+        
+        log::info!(
+            "recovered {} certificates and {} batches, last committed round {}, resuming at round {}",
+            result.certificates_loaded,
+            batch_count,
+            last_committed_round,
+            resume_round
+        );
+        Ok(())
+    }
+    
+    /// Store a certificate in the datastore so it survives a restart
+    #[cfg(feature = "persistence")]
+    async fn persist_certificate(&self, cert: &Certificate) -> Result<()> {
+        if let Some(ds) = &self.datastore_manager {
+            let ds = ds.lock().await;
+            self.dag.read().await.persist_certificate(cert, &ds).await?;
+        }
+        Ok(())
+    }
+    
+    /// Flag the anchors selected for `round` beyond the `had_anchors` it
+    /// already had, and newly committed certificates, in the datastore, and
+    /// journal the round they finalized
+    #[cfg(feature = "persistence")]
+    async fn persist_consensus_progress(
+        &self,
+        consensus: &ShoalConsensus,
+        round: u64,
+        had_anchors: usize,
+        committed: &[CertificateDigest],
+    ) {
+        use modal_validator_consensus::persistence::{mark_anchor_multi, mark_committed_multi};
+        
+        let Some(ds) = &self.datastore_manager else {
+            return;
+        };
+        let ds = ds.lock().await;
+        for anchor in consensus.state.round_anchors(round).iter().skip(had_anchors) {
+            mark_anchor_multi(&ds, round, anchor).await;
+        }
+        if !committed.is_empty() {
+            let dag = self.dag.read().await;
+            mark_committed_multi(&ds, &dag, committed, consensus.last_committed_round()).await;
+            let event = serde_json::json!({
+                "round": consensus.last_committed_round(),
+                "certificates": committed.iter().map(hex::encode).collect::<Vec<_>>(),
+            });
+            if let Err(e) = ds.append_event(modal_datastore::JournalEventKind::RoundFinalized, event) {
+                log::warn!("failed to journal finalized round {}: {}", consensus.last_committed_round(), e);
+            }
+        }
+    }
+    
+    /// Store a batch held by our workers so it survives a restart
+    #[cfg(feature = "persistence")]
+    async fn persist_batch(
+        &self,
+        batch: &modal_validator_consensus::narwhal::Batch,
+        author: &PublicKey,
+        cert_digest: Option<&CertificateDigest>,
+    ) -> Result<()> {
+        if let Some(ds) = &self.datastore_manager {
+            let ds = ds.lock().await;
+            self.dag.read().await.persist_batch(batch, author, cert_digest, &ds).await?;
+        }
+        Ok(())
+    }
+    
+    /// Stream batches sealed by this validator's workers through `network`,
+    /// to be delivered to the matching workers of the other validators
+    pub fn with_worker_network(mut self, network: mpsc::UnboundedSender<WorkerMessage>) -> Self {
+        self.workers = WorkerPool::with_network(
+            self.config.validator_key,
+            self.config.narwhal_config.workers_per_validator,
+            self.config.narwhal_config.batch_size,
+            self.config.narwhal_config.max_batch_bytes,
+            Some(network),
+        )
+        .with_admission(self.config.narwhal_config.admission.clone());
+        self
+    }
+    
     /// Submit a transaction for ordering
     pub async fn submit_transaction(&self, tx: Transaction) -> Result<()> {
-        // Add transaction to first available worker
-        if let Some(worker) = self.workers.first() {
-            let mut worker = worker.lock().await;
-            worker.add_transaction(tx);
-            log::debug!("transaction submitted, {} pending", worker.pending_count());
-            Ok(())
-        } else {
-            Err(ValidatorError::InitializationFailed(
+        if self.workers.is_empty() {
+            return Err(ValidatorError::InitializationFailed(
                 "no workers available".to_string(),
-            ))
+            ));
         }
+        self.workers
+            .submit_transaction(tx)
+            .await
+            .map_err(|e| ValidatorError::Custom(format!("Transaction not admitted: {}", e)))?;
+        log::debug!("transaction submitted, {} pending", self.workers.pending_count().await);
+        Ok(())
+    }
+    
+    /// Counters of the workers' admission checks, including duplicates dropped
+    pub async fn worker_admission_stats(&self) -> AdmissionStats {
+        self.workers.admission_stats().await
+    }
+    
+    /// Share `mempool` with whoever accepts transactions for this validator
+    pub fn with_mempool(mut self, mempool: Arc<Mutex<Mempool>>) -> Self {
+        self.mempool = mempool;
+        self
+    }
+    
+    /// Queue a transaction in the mempool, to be batched by fee and nonce
+    pub async fn submit_to_mempool(&self, tx: MempoolTransaction) -> std::result::Result<Admission, MempoolError> {
+        self.mempool.lock().await.submit(tx)
+    }
+    
+    /// Up to `limit` mempool transactions, in the order they'd be batched
+    pub async fn mempool_contents(&self, limit: usize) -> Vec<MempoolEntry> {
+        self.mempool.lock().await.contents(limit)
+    }
+    
+    pub async fn mempool_stats(&self) -> MempoolStats {
+        self.mempool.lock().await.stats()
+    }
+    
+    /// Hand the workers the best-paying ready mempool transactions, up to a
+    /// full batch each
+    async fn fill_workers(&self) {
+        let narwhal_config = &self.config.narwhal_config;
+        let workers = self.workers.len();
+        let ready = self.mempool.lock().await.take_ready(
+            narwhal_config.batch_size * workers,
+            narwhal_config.max_batch_bytes * workers,
+        );
+        for tx in ready {
+            let sender = tx.sender.clone();
+            if let Err(e) = self.workers.submit_transaction(tx.into_transaction()).await {
+                log::debug!("dropped mempool transaction from {}: {}", sender, e);
+            }
+        }
+    }
+    
+    /// Store a batch streamed by another validator's worker
+    pub async fn handle_worker_message(&self, message: WorkerMessage) -> Result<()> {
+        #[cfg(feature = "persistence")]
+        let WorkerMessage::Batch { author, batch } = &message;
+        #[cfg(feature = "persistence")]
+        self.persist_batch(batch, author, None).await?;
+        
+        self.workers.handle_message(message).await?;
+        
+        // The batch may be the one holding up the sequencer
+        self.drain_sequencer().await?;
+        Ok(())
     }
     
     /// Propose a new batch (called periodically by consensus loop)
     pub async fn propose_batch(&self) -> Result<Option<Certificate>> {
-        // Form batch from first worker
-        let batch_opt = if let Some(worker) = self.workers.first() {
-            let mut worker = worker.lock().await;
-            worker.form_batch().await
-        } else {
-            None
-        };
+        if self.is_retired().await {
+            return Ok(None);
+        }
+        
+        self.fill_workers().await;
+        
+        // Seal a batch on every worker with pending transactions
+        let payload = self.workers.seal_batches().await;
         
-        if let Some((batch, batch_digest)) = batch_opt {
-            log::info!("formed batch with {} transactions", batch.transactions.len());
+        if !payload.is_empty() {
+            log::info!("sealed {} batches", payload.len());
             
-            // Create header
+            // Create header referencing the batch digests
             let mut primary = self.primary.lock().await;
-            let header = primary.propose(batch_digest).await?;
+            let header = primary.propose(payload).await?;
             
             log::info!("proposed header for round {}", header.round);
             
-            // In a real implementation, we would broadcast header and collect votes
-            // For now, simulate immediate certificate formation for testing
-            let mut builder = primary.create_certificate_builder(header);
+            let round = header.round;
+            let mut builder = primary.create_certificate_builder(header.clone());
+            builder.add_vote(self.config.validator_key, self.vote(&header).signature)?;
             
-            // Simulate votes from all validators (for testing)
-            for validator in &self.config.committee.validator_order {
-                builder.add_vote(*validator, vec![])?;
+            // Headers aren't broadcast yet, so the other validators' votes are
+            // simulated. A committee with BLS keys only certifies signed votes.
+            if !primary.committee.uses_bls() {
+                for validator in &primary.committee.validator_order {
+                    if *validator != self.config.validator_key {
+                        builder.add_vote(*validator, vec![])?;
+                    }
+                }
+            }
+            if !builder.has_quorum() {
+                return Err(ValidatorError::Custom(format!(
+                    "header for round {} has {} votes, short of a quorum of signed votes",
+                    round,
+                    builder.vote_count()
+                )));
             }
             
             let cert = builder.build()?;
+            self.round_timer.lock().await.on_certified(cert.header.round, Instant::now());
             
             // Process certificate through consensus
-            let _digest = cert.digest();
             primary.process_certificate(cert.clone()).await?;
             
+            #[cfg(feature = "persistence")]
+            {
+                let digest = cert.digest();
+                for (batch_digest, worker_id) in &cert.header.payload {
+                    if let Some(batch) = self.workers.get_batch(*batch_digest, *worker_id).await {
+                        self.persist_batch(&batch, &self.config.validator_key, Some(&digest)).await?;
+                    }
+                }
+                self.persist_certificate(&cert).await?;
+            }
+            
             let mut consensus = self.consensus.lock().await;
+            #[cfg(feature = "persistence")]
+            let had_anchors = consensus.state.round_anchors(cert.header.round).len();
             let committed = consensus.process_certificate(cert.clone()).await?;
+            #[cfg(feature = "persistence")]
+            self.persist_consensus_progress(&consensus, cert.header.round, had_anchors, &committed).await;
             
             if !committed.is_empty() {
                 log::info!("committed {} certificates", committed.len());
+                self.sequence_commits(&committed).await?;
             }
             
             Ok(Some(cert))
@@ -334,27 +670,41 @@ impl ShoalValidator {
         }
     }
     
+    /// This validator's vote for a header, signed with its BLS key if it has one
+    pub fn vote(&self, header: &Header) -> Vote {
+        #[cfg(feature = "bls")]
+        if let Some(keypair) = &self.config.bls_keypair {
+            return certificate::create_bls_vote(header, self.config.validator_key, keypair);
+        }
+        certificate::create_vote(header, self.config.validator_key, &[])
+    }
+    
     /// Process a certificate received from another validator
     pub async fn process_certificate(&self, cert: Certificate) -> Result<Vec<Transaction>> {
-        // Note: Certificate persistence requires DatastoreManager support in DAG (TODO)
-        // #[cfg(feature = "persistence")]
-        // {
-        //     if let Some(ref ds) = self.datastore_manager {
-        //         let dag = self.dag.read().await;
-        //         let ds_guard = ds.lock().await;
-        //         // TODO: Update DAG to support DatastoreManager
-        //     }
-        // }
+        #[cfg(feature = "persistence")]
+        let is_new = self.dag.read().await.get(&cert.digest()).is_none();
         
         let primary = self.primary.lock().await;
         primary.process_certificate(cert.clone()).await?;
         drop(primary);
         
+        #[cfg(feature = "persistence")]
+        if is_new {
+            self.persist_certificate(&cert).await?;
+        }
+        
+        #[cfg(feature = "persistence")]
+        let round = cert.header.round;
         let mut consensus = self.consensus.lock().await;
+        #[cfg(feature = "persistence")]
+        let had_anchors = consensus.state.round_anchors(round).len();
         let committed = consensus.process_certificate(cert).await?;
+        #[cfg(feature = "persistence")]
+        self.persist_consensus_progress(&consensus, round, had_anchors, &committed).await;
         
         if !committed.is_empty() {
             log::info!("committed {} certificates", committed.len());
+            self.sequence_commits(&committed).await?;
             
             // Create checkpoint every 100 rounds
             // Note: Checkpoint creation requires DatastoreManager support in DAG (TODO)
@@ -378,55 +728,29 @@ impl ShoalValidator {
             
             // Process contract commits for asset state updates
             use crate::contract_processor::ContractProcessor;
+            use crate::contract_scheduler::ContractScheduler;
             // Use datastore_manager if available, otherwise skip contract processing
             let Some(datastore_for_contracts) = self.datastore_manager.clone() else {
                 log::debug!("No datastore manager available, skipping contract processing");
                 return Ok(transactions);
             };
-            let contract_processor = ContractProcessor::new(datastore_for_contracts);
             
-            for tx in &transactions {
-                // Parse transaction to see if it contains a contract commit
-                if let Ok(tx_str) = std::str::from_utf8(&tx.data) {
-                    if let Ok(tx_json) = serde_json::from_str::<serde_json::Value>(tx_str) {
-                        // Check if this is a contract push transaction
-                        if let Some(req_type) = tx_json.get("type").and_then(|v| v.as_str()) {
-                            if req_type == "contract_push" {
-                                // Extract contract data
-                                if let Some(data) = tx_json.get("data") {
-                                    if let (Some(contract_id), Some(commits)) = (
-                                        data.get("contract_id").and_then(|v| v.as_str()),
-                                        data.get("commits").and_then(|v| v.as_array())
-                                    ) {
-                                        // Process each commit
-                                        for commit_entry in commits {
-                                            if let (Some(commit_id), Some(commit_data_obj)) = (
-                                                commit_entry.get("commit_id").and_then(|v| v.as_str()),
-                                                commit_entry.get("body")
-                                            ) {
-                                                // Reconstruct commit data string
-                                                let commit_data = serde_json::json!({
-                                                    "body": commit_data_obj,
-                                                    "head": commit_entry.get("head")
-                                                });
-                                                let commit_data_str = serde_json::to_string(&commit_data).unwrap_or_default();
-                                                
-                                                // Process the commit
-                                                match contract_processor.process_commit(contract_id, commit_id, &commit_data_str).await {
-                                                    Ok(state_changes) => {
-                                                        log::info!("Processed commit {} for contract {}: {} state changes", 
-                                                            commit_id, contract_id, state_changes.len());
-                                                    }
-                                                    Err(e) => {
-                                                        log::warn!("Failed to process commit {} for contract {}: {}", 
-                                                            commit_id, contract_id, e);
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
+            let tasks: Vec<CommitTask> = transactions.iter().flat_map(contract_push_commits).collect();
+            if !tasks.is_empty() {
+                // Commits of independent contracts are applied in parallel,
+                // with gas accounted to the committee's epoch
+                let epoch = self.schedule.lock().await.epoch();
+                let processor = ContractProcessor::new(datastore_for_contracts).with_epoch(epoch);
+                let scheduler = ContractScheduler::with_default_workers(processor);
+                for outcome in scheduler.execute(tasks).await {
+                    match outcome.result {
+                        Ok(state_changes) => {
+                            log::info!("Processed commit {} for contract {}: {} state changes", 
+                                outcome.commit_id, outcome.contract_id, state_changes.len());
+                        }
+                        Err(e) => {
+                            log::warn!("Failed to process commit {} for contract {}: {}", 
+                                outcome.commit_id, outcome.contract_id, e);
                         }
                     }
                 }
@@ -450,7 +774,8 @@ impl ShoalValidator {
         consensus.last_committed_round()
     }
     
-    /// Advance to the next round
+    /// Advance to the next round, handing over to a new committee if one
+    /// activates in it
     pub async fn advance_round(&self) {
         let mut primary = self.primary.lock().await;
         primary.advance_round();
@@ -458,9 +783,105 @@ impl ShoalValidator {
         let mut consensus = self.consensus.lock().await;
         consensus.advance_round();
         
+        let mut schedule = self.schedule.lock().await;
+        if let Some(change) = schedule.activate(consensus.current_round()) {
+            let mut committee = schedule.committee().clone();
+            committee.inherit_bls_keys(&primary.committee);
+            primary.committee = committee.clone();
+            consensus.committee = committee.clone();
+            consensus.reputation = ReputationManager::new(committee, self.config.reputation_config.clone());
+            
+            log::info!(
+                "committee for epoch {} active: {} joined, {} left",
+                change.epoch,
+                change.joining.len(),
+                change.leaving.len()
+            );
+            if change.leaving.contains(&self.config.validator_key) {
+                log::info!("validator left the committee, retiring");
+            }
+        }
+        
+        // A round we didn't get a certificate in backs the timeout off
+        self.round_timer.lock().await.start_round(consensus.current_round(), Instant::now());
+        
         log::info!("advanced to round {}", consensus.current_round());
     }
     
+    /// How long to wait for the current round before advancing anyway
+    pub async fn round_timeout(&self) -> Duration {
+        self.round_timer.lock().await.timeout()
+    }
+    
+    /// Settings for the round timer
+    pub fn round_timer_config(&self) -> &RoundTimerConfig {
+        &self.config.narwhal_config.round_timer
+    }
+    
+    /// Schedule a committed reconfiguration. The new committee takes over
+    /// at the certificate's activation round.
+    pub async fn apply_reconfiguration(&self, cert: &ReconfigurationCertificate) -> Result<CommitteeChange> {
+        let change = self.schedule.lock().await.schedule(cert)?;
+        log::info!(
+            "committee for epoch {} activates at round {}: {} joining, {} leaving",
+            change.epoch,
+            change.activation_round,
+            change.joining.len(),
+            change.leaving.len()
+        );
+        Ok(change)
+    }
+    
+    /// State for validators joining the most recently scheduled committee
+    pub async fn state_handoff(&self) -> StateHandoff {
+        let (epoch, validators, activation_round) = {
+            let schedule = self.schedule.lock().await;
+            let (epoch, committee, activation_round) = schedule.latest();
+            let validators: Vec<Validator> = committee
+                .validator_order
+                .iter()
+                .filter_map(|key| committee.get_validator(key).cloned())
+                .collect();
+            (epoch, validators, activation_round)
+        };
+        let tips = {
+            let dag = self.dag.read().await;
+            dag.get_round(dag.highest_round()).into_iter().cloned().collect()
+        };
+        
+        StateHandoff {
+            epoch,
+            activation_round,
+            validators,
+            tips,
+            last_checkpoint_round: self.last_checkpoint_round().await,
+        }
+    }
+    
+    /// The active committee
+    pub async fn committee(&self) -> Committee {
+        self.schedule.lock().await.committee().clone()
+    }
+    
+    /// Whether this validator has left the active committee
+    pub async fn is_retired(&self) -> bool {
+        !self.schedule.lock().await.committee().contains(&self.config.validator_key)
+    }
+    
+    /// Round of the latest DAG checkpoint in the datastore
+    async fn last_checkpoint_round(&self) -> Option<u64> {
+        #[cfg(feature = "persistence")]
+        if let Some(ds) = &self.datastore_manager {
+            let ds = ds.lock().await;
+            return modal_datastore::models::DAGState::get_latest_multi(&ds)
+                .await
+                .ok()
+                .flatten()
+                .map(|checkpoint| checkpoint.checkpoint_round);
+        }
+        None
+    }
+    
     /// Get committed transactions up to a certain round
     pub async fn get_committed_transactions(&self, _from_round: u64, _to_round: u64) -> Result<Vec<Transaction>> {
         // TODO: Implement range queries
@@ -471,20 +892,89 @@ impl ShoalValidator {
         Ok(transactions)
     }
     
+    /// Get the committed batches with their transactions, in canonical order.
+    /// Validators with the same committed certificates return the same batches
+    /// in the same order.
+    pub async fn get_committed_batches(&self) -> Result<Vec<CommittedBatch>> {
+        let committed = self.consensus.lock().await.state.committed.clone();
+        let mut batches = Vec::new();
+        for batch_ref in self.ordering.order_batches(&committed).await? {
+            let batch = self.workers
+                .get_batch(batch_ref.digest, batch_ref.worker_id)
+                .await
+                .ok_or_else(|| ValidatorError::Custom(format!(
+                    "committed batch from {} in round {} is not available",
+                    batch_ref.author, batch_ref.round
+                )))?;
+            batches.push(CommittedBatch::new(batch_ref, batch)?);
+        }
+        Ok(batches)
+    }
+    
+    /// Deterministic encoding of the committed batches, for comparing validators' output
+    pub async fn encode_committed_batches(&self) -> Result<String> {
+        Ok(canonical::encode(&self.get_committed_batches().await?))
+    }
+    
+    /// Queue newly committed certificates' batches for the sequenced log,
+    /// and append what's available
+    async fn sequence_commits(&self, committed: &[CertificateDigest]) -> Result<()> {
+        let committed: BTreeSet<CertificateDigest> = committed.iter().copied().collect();
+        let batches = self.ordering.order_batches(&committed).await?;
+        self.sequencer.lock().await.enqueue(batches);
+        self.drain_sequencer().await
+    }
+    
+    /// Append the queued committed batches we have to the sequenced log
+    async fn drain_sequencer(&self) -> Result<()> {
+        let mut sequencer = self.sequencer.lock().await;
+        if sequencer.pending_count() == 0 {
+            return Ok(());
+        }
+        let appended = match &self.datastore_manager {
+            Some(ds) => {
+                let ds = ds.lock().await;
+                sequencer.drain(&self.workers, Some(&ds)).await?
+            }
+            None => sequencer.drain(&self.workers, None).await?,
+        };
+        if !appended.is_empty() {
+            log::debug!("sequenced {} transactions, next seq {}", appended.len(), sequencer.next_seq());
+        }
+        Ok(())
+    }
+    
+    /// Receive sequenced log entries as they're appended
+    pub async fn subscribe_sequenced(&self) -> broadcast::Receiver<SequencedLog> {
+        self.sequencer.lock().await.subscribe()
+    }
+    
+    /// Up to `limit` sequenced log entries starting at `from`
+    pub async fn get_sequenced_range(&self, from: u64, limit: usize) -> Result<Vec<SequencedLog>> {
+        let Some(ds) = &self.datastore_manager else {
+            return Ok(vec![]);
+        };
+        let ds = ds.lock().await;
+        Ok(SequencedLog::find_range_multi(&ds, from, limit).await?)
+    }
+    
+    /// Sequence number the next sequenced log entry gets
+    pub async fn next_sequence_number(&self) -> u64 {
+        self.sequencer.lock().await.next_seq()
+    }
+    
     /// Get the number of pending transactions
     pub async fn pending_transaction_count(&self) -> usize {
-        let mut total = 0;
-        for worker in &self.workers {
-            let worker = worker.lock().await;
-            total += worker.pending_count();
-        }
-        total
+        self.workers.pending_count().await
     }
     
     // Sync methods for DAG synchronization
     
     /// Handle sync request from another node
     pub async fn handle_sync_request(&self, request: SyncRequest) -> SyncResponse {
+        if matches!(request, SyncRequest::GetBatch { .. } | SyncRequest::GetBatches { .. }) {
+            return self.workers.handle_sync_request(request).await;
+        }
         let dag = self.dag.read().await;
         dag.handle_sync_request(request)
     }
@@ -502,7 +992,7 @@ impl ShoalValidator {
     /// Request specific certificates from a peer
     pub async fn request_certificates<F, Fut>(
         &self,
-        digests: Vec<modal_validator_consensus::narwhal::CertificateDigest>,
+        digests: Vec<CertificateDigest>,
         request_fn: F,
     ) -> Result<Vec<Certificate>>
     where
@@ -551,15 +1041,52 @@ impl ShoalValidator {
     
     /// Check if we have all certificates in a round
     pub async fn has_complete_round(&self, round: u64) -> bool {
+        let quorum_threshold = self.schedule.lock().await.committee_at(round).quorum_threshold();
         let dag = self.dag.read().await;
-        let quorum_threshold = self.config.committee.quorum_threshold();
         dag.round_size(round) >= quorum_threshold as usize
     }
 }
 
+/// The commits carried by a contract push transaction, in push order
+fn contract_push_commits(tx: &Transaction) -> Vec<CommitTask> {
+    let Ok(tx_json) = serde_json::from_slice::<serde_json::Value>(&tx.data) else {
+        return vec![];
+    };
+    if tx_json.get("type").and_then(|v| v.as_str()) != Some("contract_push") {
+        return vec![];
+    }
+    let Some(data) = tx_json.get("data") else {
+        return vec![];
+    };
+    let (Some(contract_id), Some(commits)) = (
+        data.get("contract_id").and_then(|v| v.as_str()),
+        data.get("commits").and_then(|v| v.as_array())
+    ) else {
+        return vec![];
+    };
+    commits
+        .iter()
+        .filter_map(|commit_entry| {
+            let commit_id = commit_entry.get("commit_id").and_then(|v| v.as_str())?;
+            let body = commit_entry.get("body")?;
+            // Reconstruct commit data string
+            let commit_data = serde_json::json!({
+                "body": body,
+                "head": commit_entry.get("head")
+            });
+            Some(CommitTask {
+                contract_id: contract_id.to_string(),
+                commit_id: commit_id.to_string(),
+                commit_data: serde_json::to_string(&commit_data).unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use modal_validator_consensus::narwhal::{AggregatedSignature, Header};
     use tempfile::TempDir;
     
     async fn create_test_validator(validator_index: usize) -> (ShoalValidator, TempDir) {
@@ -574,6 +1101,39 @@ mod tests {
         (validator, temp_dir)
     }
     
+    /// The test validator that leads round 0
+    async fn create_leader_validator() -> (ShoalValidator, TempDir) {
+        let leader = ShoalValidatorConfig::new_test(4, 0).committee.validator_order[0];
+        let index = (0..4)
+            .find(|i| ShoalValidatorConfig::new_test(4, *i).validator_key == leader)
+            .unwrap();
+        create_test_validator(index).await
+    }
+    
+    /// Vote for a certificate with next-round certificates from f+1 other validators
+    async fn vote_for(validator: &ShoalValidator, cert: &Certificate) {
+        let voters: Vec<PublicKey> = validator.config.committee.validator_order
+            .iter()
+            .filter(|key| **key != cert.header.author)
+            .take(2)
+            .copied()
+            .collect();
+        for author in voters {
+            let vote = Certificate {
+                header: Header {
+                    author,
+                    round: cert.header.round + 1,
+                    payload: vec![],
+                    parents: vec![cert.digest()],
+                    timestamp: cert.header.timestamp + 1,
+                },
+                aggregated_signature: AggregatedSignature { signature: vec![] },
+                signers: vec![true; 4],
+            };
+            validator.process_certificate(vote).await.unwrap();
+        }
+    }
+    
     #[tokio::test]
     async fn test_shoal_validator_create() {
         let (validator, _temp) = create_test_validator(0).await;
@@ -583,6 +1143,49 @@ mod tests {
         assert_eq!(validator.get_chain_tip().await, 0);
     }
     
+    #[cfg(feature = "bls")]
+    #[tokio::test]
+    async fn test_with_bls_keys_signs_votes() {
+        use modal_common::bls::BlsKeypair;
+        
+        let keypairs: Vec<BlsKeypair> = (1..=4u8)
+            .map(|seed| BlsKeypair::from_seed(&[seed; 32]).unwrap())
+            .collect();
+        let config = ShoalValidatorConfig::new_test(4, 0);
+        let keys: Vec<(PublicKey, Vec<u8>, Vec<u8>)> = config.committee.validator_order
+            .iter()
+            .zip(&keypairs)
+            .map(|(validator, keypair)| (*validator, keypair.public_key_bytes(), keypair.prove_possession()))
+            .collect();
+        
+        // Our keypair must be the one registered for us, with valid proofs for all
+        assert!(config.clone().with_bls_keys(None, keys.clone()).is_err());
+        assert!(config.clone().with_bls_keys(Some(keypairs[1].clone()), keys.clone()).is_err());
+        let mut forged = keys.clone();
+        forged[2].2 = keypairs[3].prove_possession();
+        assert!(config.clone().with_bls_keys(Some(keypairs[0].clone()), forged).is_err());
+        
+        let config = config.with_bls_keys(Some(keypairs[0].clone()), keys).unwrap();
+        assert!(config.committee.uses_bls());
+        
+        let temp_dir = TempDir::new().unwrap();
+        let datastore_manager = Arc::new(Mutex::new(DatastoreManager::open(temp_dir.path()).unwrap()));
+        let validator = ShoalValidator::new(datastore_manager, config.clone()).await.unwrap();
+        let header = Header {
+            author: config.validator_key,
+            round: 1,
+            payload: vec![],
+            parents: vec![],
+            timestamp: 1,
+        };
+        let mut builder = certificate::CertificateBuilder::new(header.clone(), config.committee.clone());
+        builder.add_vote(config.validator_key, validator.vote(&header).signature).unwrap();
+        
+        // Votes can't be simulated once the committee signs with BLS
+        let other = config.committee.validator_order[1];
+        assert!(builder.add_vote(other, vec![]).is_err());
+    }
+    
     #[tokio::test]
     async fn test_shoal_validator_from_peer_ids() {
         // Test creating a validator configuration from peer IDs
@@ -649,17 +1252,203 @@ mod tests {
             validator.submit_transaction(tx).await.unwrap();
         }
         
-        // Propose batch (should form certificate and commit for genesis)
+        // Propose batch (should form certificate for genesis)
         let cert = validator.propose_batch().await.unwrap();
         assert!(cert.is_some());
         
         let cert = cert.unwrap();
         assert_eq!(cert.header.round, 0); // Genesis round
+        assert_eq!(cert.header.payload.len(), 4); // One batch per worker
         
-        // Should be committed
+        // Nothing commits before the certificate is voted for
+        assert!(validator.get_committed_batches().await.unwrap().is_empty());
         assert_eq!(validator.get_chain_tip().await, 0);
     }
     
+    #[tokio::test]
+    async fn test_shoal_validator_batches_mempool() {
+        let (validator, _temp) = create_test_validator(0).await;
+        validator.initialize().await.unwrap();
+        
+        let sender = modal_common::keypair::Keypair::generate().unwrap();
+        let tx = |nonce: u64, fee: u64| {
+            let mut tx = MempoolTransaction {
+                sender: sender.as_public_address(),
+                nonce,
+                fee,
+                data: vec![nonce as u8],
+                timestamp: 1000,
+                signature: String::new(),
+            };
+            tx.sign(&sender).unwrap();
+            tx
+        };
+        validator.submit_to_mempool(tx(0, 10)).await.unwrap();
+        validator.submit_to_mempool(tx(1, 10)).await.unwrap();
+        // Nonce 3 waits for nonce 2
+        validator.submit_to_mempool(tx(3, 10)).await.unwrap();
+        assert_eq!(validator.mempool_stats().await.ready, 2);
+        
+        let cert = validator.propose_batch().await.unwrap().unwrap();
+        assert_eq!(cert.header.payload.len(), 2);
+        let stats = validator.mempool_stats().await;
+        assert_eq!((stats.pending, stats.drained), (1, 2));
+        assert!(!validator.mempool_contents(10).await[0].ready);
+    }
+    
+    #[tokio::test]
+    async fn test_shoal_validator_committed_batches() {
+        let (validator, _temp) = create_leader_validator().await;
+        validator.initialize().await.unwrap();
+        
+        validator.submit_transaction(Transaction { data: vec![1], timestamp: 1000 }).await.unwrap();
+        validator.submit_transaction(Transaction { data: vec![2], timestamp: 1001 }).await.unwrap();
+        let cert = validator.propose_batch().await.unwrap().unwrap();
+        vote_for(&validator, &cert).await;
+        
+        // The genesis leader commits on its votes; its batches come out in payload order
+        let batches = validator.get_committed_batches().await.unwrap();
+        let digests: Vec<_> = batches.iter().map(|batch| batch.digest).collect();
+        let payload: Vec<_> = cert.header.payload.iter().map(|(digest, _)| *digest).collect();
+        assert_eq!(digests, payload);
+        assert_eq!(batches[0].transactions[0].data, vec![1]);
+        assert_eq!(
+            validator.encode_committed_batches().await.unwrap(),
+            canonical::encode(&batches)
+        );
+    }
+    
+    #[tokio::test]
+    async fn test_shoal_validator_round_timeout_backs_off() {
+        let (validator, _temp) = create_test_validator(0).await;
+        let initial = Duration::from_millis(validator.round_timer_config().initial_ms);
+        assert_eq!(validator.round_timeout().await, initial);
+        
+        // Our header is certified in round 0, so moving on doesn't back off
+        validator.submit_transaction(Transaction { data: vec![1], timestamp: 1000 }).await.unwrap();
+        validator.propose_batch().await.unwrap().unwrap();
+        validator.advance_round().await;
+        assert_eq!(validator.round_timeout().await, initial);
+        
+        // Rounds without a certificate double the timeout
+        validator.advance_round().await;
+        validator.advance_round().await;
+        assert_eq!(validator.round_timeout().await, initial * 4);
+    }
+    
+    #[tokio::test]
+    async fn test_shoal_validator_sequenced_log() {
+        let (network, mut outbound) = mpsc::unbounded_channel();
+        let (validator, _temp) = create_leader_validator().await;
+        let validator = validator.with_worker_network(network);
+        validator.initialize().await.unwrap();
+        let mut entries = validator.subscribe_sequenced().await;
+
+        validator.submit_transaction(Transaction { data: vec![1], timestamp: 1000 }).await.unwrap();
+        validator.submit_transaction(Transaction { data: vec![2], timestamp: 1001 }).await.unwrap();
+        let cert = validator.propose_batch().await.unwrap().unwrap();
+        vote_for(&validator, &cert).await;
+
+        // The genesis leader commits on its votes, so both transactions are sequenced
+        let log = validator.get_sequenced_range(0, 10).await.unwrap();
+        assert_eq!(log.iter().map(|entry| entry.seq).collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(log[0].data, hex::encode([1]));
+        assert_eq!(log[0].certificate, hex::encode(cert.digest()));
+        assert_eq!(entries.recv().await.unwrap(), log[0]);
+        assert_eq!(entries.recv().await.unwrap(), log[1]);
+
+        // A peer commits the certificate before it has the batches, and
+        // sequences the transactions once they arrive
+        let (peer, _peer_temp) = create_test_validator(1).await;
+        peer.initialize().await.unwrap();
+        peer.process_certificate(cert.clone()).await.unwrap();
+        vote_for(&peer, &cert).await;
+        assert_eq!(peer.next_sequence_number().await, 0);
+        while let Ok(message) = outbound.try_recv() {
+            peer.handle_worker_message(message).await.unwrap();
+        }
+        let peer_log = peer.get_sequenced_range(0, 10).await.unwrap();
+        assert_eq!(peer_log.len(), 2);
+        assert_eq!(peer_log[1].data, log[1].data);
+        assert_eq!(peer.next_sequence_number().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_shoal_validator_streams_batches_to_peers() {
+        let (network, mut outbound) = mpsc::unbounded_channel();
+        let (validator, _temp) = create_test_validator(0).await;
+        let validator = validator.with_worker_network(network);
+        let (peer, _peer_temp) = create_test_validator(1).await;
+        
+        validator.submit_transaction(Transaction { data: vec![1], timestamp: 1000 }).await.unwrap();
+        let cert = validator.propose_batch().await.unwrap().unwrap();
+        let (digest, _) = cert.header.payload[0];
+        
+        // The header carries only the digest; the batch comes from the worker stream
+        assert!(peer.handle_sync_request(SyncRequest::batch(digest)).await.is_empty());
+        peer.handle_worker_message(outbound.try_recv().unwrap()).await.unwrap();
+        assert!(matches!(
+            peer.handle_sync_request(SyncRequest::batch(digest)).await,
+            SyncResponse::Batches { batches } if batches[0].transactions.len() == 1
+        ));
+    }
+    
+    #[tokio::test]
+    async fn test_shoal_validator_reconfiguration_handoff() {
+        let (leaving, _temp) = create_test_validator(3).await;
+        leaving.submit_transaction(Transaction { data: vec![1], timestamp: 1000 }).await.unwrap();
+        leaving.propose_batch().await.unwrap().unwrap();
+        
+        // Validators 0-2 sign a change that swaps validator 3 for a fifth validator
+        let joiner_config = ShoalValidatorConfig::new_test(5, 4);
+        let old_keys = ShoalValidatorConfig::new_test(4, 0).committee.validator_order;
+        let validators: Vec<Validator> = joiner_config
+            .committee
+            .validator_order
+            .iter()
+            .filter(|key| **key != old_keys[3])
+            .map(|key| joiner_config.committee.get_validator(key).unwrap().clone())
+            .collect();
+        let signatures = (1..=3u8)
+            .map(|seed| {
+                use libp2p_identity::ed25519;
+                use modal_common::keypair::{Keypair, KeypairOrPublicKey};
+                let mut secret_bytes = [0u8; 32];
+                secret_bytes[0] = seed;
+                let secret = ed25519::SecretKey::try_from_bytes(secret_bytes).unwrap();
+                let keypair = Keypair::new(KeypairOrPublicKey::Keypair(ed25519::Keypair::from(secret).into()));
+                modal_validator_consensus::narwhal::ReconfigurationSignature::sign(&keypair, 1, &validators).unwrap()
+            })
+            .collect();
+        let cert = ReconfigurationCertificate {
+            epoch: 1,
+            round: 0,
+            validators,
+            signatures,
+        };
+        let change = leaving.apply_reconfiguration(&cert).await.unwrap();
+        assert_eq!(change.activation_round, 2);
+        assert_eq!(change.joining, vec![joiner_config.validator_key]);
+        assert_eq!(change.leaving, vec![old_keys[3]]);
+        
+        // The old committee stays in charge until the activation round
+        leaving.advance_round().await;
+        assert!(!leaving.is_retired().await);
+        leaving.advance_round().await;
+        assert!(leaving.is_retired().await);
+        leaving.submit_transaction(Transaction { data: vec![2], timestamp: 1001 }).await.unwrap();
+        assert!(leaving.propose_batch().await.unwrap().is_none());
+        
+        let handoff = leaving.state_handoff().await;
+        assert_eq!(handoff.tips.len(), 1);
+        let temp_dir = TempDir::new().unwrap();
+        let datastore = Arc::new(Mutex::new(DatastoreManager::open(temp_dir.path()).unwrap()));
+        let joiner = ShoalValidator::join(datastore, joiner_config, handoff).await.unwrap();
+        assert!(!joiner.is_retired().await);
+        assert_eq!(joiner.get_current_round().await, 2);
+        assert_eq!(joiner.get_highest_round().await, 0);
+    }
+    
     #[tokio::test]
     async fn test_shoal_validator_advance_round() {
         let (validator, _temp) = create_test_validator(0).await;
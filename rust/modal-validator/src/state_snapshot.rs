@@ -0,0 +1,134 @@
+//! Contract state snapshots
+//!
+//! A snapshot is an undo journal: before the contract processor first writes
+//! a key, the key's previous value is recorded. Rolling the snapshot back
+//! restores those values (or deletes keys that didn't exist), so a commit
+//! that fails part way leaves no state behind. Only the keys the commit
+//! wrote are touched, so commits applied concurrently to other contracts are
+//! not affected.
+
+use anyhow::Result;
+use modal_datastore::{DatastoreManager, Model, Store};
+use std::collections::HashSet;
+
+/// Identifies an open snapshot of a `ContractProcessor`
+pub type SnapshotId = u64;
+
+/// Store a journaled key lives in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum StoreKind {
+    ValidatorFinal,
+    NodeState,
+}
+
+/// Previous values of the keys written since the snapshot was taken
+#[derive(Debug, Default)]
+pub struct StateSnapshot {
+    entries: Vec<(StoreKind, String, Option<Vec<u8>>)>,
+    recorded: HashSet<(StoreKind, String)>,
+}
+
+impl StateSnapshot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of keys written since the snapshot was taken
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn record(&mut self, ds: &DatastoreManager, kind: StoreKind, key: &str) -> Result<()> {
+        if self.recorded.insert((kind, key.to_string())) {
+            let previous = match kind {
+                StoreKind::ValidatorFinal => ds.validator_final().get(key)?,
+                StoreKind::NodeState => ds.node_state().get(key)?,
+            };
+            self.entries.push((kind, key.to_string(), previous));
+        }
+        Ok(())
+    }
+
+    /// Save a model to the ValidatorFinal store
+    pub async fn save_model<M: Model + Sync>(&mut self, ds: &DatastoreManager, model: &M) -> Result<()> {
+        self.record(ds, StoreKind::ValidatorFinal, &model.get_id())?;
+        model.save_to_store(ds.validator_final()).await
+    }
+
+    /// Set contract data in the NodeState store
+    pub async fn set_data(&mut self, ds: &DatastoreManager, key: &str, value: &[u8]) -> Result<()> {
+        self.record(ds, StoreKind::NodeState, key)?;
+        ds.set_data_by_key(key, value).await?;
+        Ok(())
+    }
+
+    /// Add the writes of a later snapshot. Keys this snapshot already
+    /// recorded keep their older value.
+    pub fn absorb(&mut self, later: StateSnapshot) {
+        for (kind, key, previous) in later.entries {
+            if self.recorded.insert((kind, key.clone())) {
+                self.entries.push((kind, key, previous));
+            }
+        }
+    }
+
+    /// Restore every written key to its value when the snapshot was taken
+    pub fn rollback(self, ds: &DatastoreManager) -> Result<()> {
+        for (kind, key, previous) in self.entries.into_iter().rev() {
+            match (kind, previous) {
+                (StoreKind::ValidatorFinal, Some(value)) => ds.validator_final().put(&key, &value)?,
+                (StoreKind::ValidatorFinal, None) => ds.validator_final().delete(&key)?,
+                (StoreKind::NodeState, Some(value)) => ds.node_state().put(&key, &value)?,
+                (StoreKind::NodeState, None) => ds.node_state().delete(&key)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use modal_datastore::models::AssetBalance;
+
+    fn balance(amount: u64) -> AssetBalance {
+        AssetBalance {
+            contract_id: "alice".to_string(),
+            asset_id: "token".to_string(),
+            owner_contract_id: "alice".to_string(),
+            balance: amount,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rollback_restores_previous_values() {
+        let ds = DatastoreManager::create_in_memory().unwrap();
+        balance(100).save_to_store(ds.validator_final()).await.unwrap();
+        ds.set_data_by_key("/contracts/alice/name.text", b"alice").await.unwrap();
+
+        let mut snapshot = StateSnapshot::new();
+        snapshot.save_model(&ds, &balance(60)).await.unwrap();
+        snapshot.save_model(&ds, &balance(10)).await.unwrap();
+        snapshot.set_data(&ds, "/contracts/alice/name.text", b"bob").await.unwrap();
+        snapshot.set_data(&ds, "/contracts/alice/new.text", b"x").await.unwrap();
+        assert_eq!(snapshot.len(), 3);
+
+        // A later snapshot's writes join, without replacing older values
+        let mut later = StateSnapshot::new();
+        later.save_model(&ds, &balance(5)).await.unwrap();
+        later.set_data(&ds, "/contracts/alice/later.text", b"y").await.unwrap();
+        snapshot.absorb(later);
+        assert_eq!(snapshot.len(), 4);
+
+        snapshot.rollback(&ds).unwrap();
+        let restored = ds.validator_final().get(&balance(0).get_id()).unwrap().unwrap();
+        assert_eq!(AssetBalance::from_json_string(&String::from_utf8(restored).unwrap()).unwrap().balance, 100);
+        assert_eq!(ds.get_string("/contracts/alice/name.text").await.unwrap().as_deref(), Some("alice"));
+        assert!(ds.get_string("/contracts/alice/new.text").await.unwrap().is_none());
+        assert!(ds.get_string("/contracts/alice/later.text").await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,103 @@
+//! End-to-end crash recovery: a validator is restarted on its datastore in the
+//! middle of consensus and has to carry on from where it stopped.
+#![cfg(feature = "persistence")]
+
+use modal_datastore::DatastoreManager;
+use modal_validator::{ShoalValidator, ShoalValidatorConfig};
+use modal_validator_consensus::narwhal::{Certificate, SyncRequest, SyncResponse, Transaction};
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+use tokio::sync::Mutex;
+
+const COMMITTEE_SIZE: usize = 4;
+
+/// Open the datastore at `path` and start validator `index` on it
+async fn start_validator(path: &Path, index: usize) -> ShoalValidator {
+    let datastore = DatastoreManager::open(path).unwrap();
+    let config = ShoalValidatorConfig::new_test(COMMITTEE_SIZE, index);
+    let validator = ShoalValidator::new(Arc::new(Mutex::new(datastore)), config)
+        .await
+        .unwrap();
+    validator.initialize().await.unwrap();
+    validator
+}
+
+/// Every validator proposes for the current round, then processes the other
+/// validators' certificates and advances. Returns the certificates, in
+/// validator order.
+async fn run_round(validators: &[ShoalValidator], round: u64) -> Vec<Certificate> {
+    let mut certs = Vec::new();
+    for (i, validator) in validators.iter().enumerate() {
+        let tx = Transaction {
+            data: format!("round {} validator {}", round, i).into_bytes(),
+            timestamp: 1000 + round,
+        };
+        validator.submit_transaction(tx).await.unwrap();
+        certs.push(validator.propose_batch().await.unwrap().unwrap());
+    }
+
+    for (i, validator) in validators.iter().enumerate() {
+        for (j, cert) in certs.iter().enumerate() {
+            if i != j {
+                validator.process_certificate(cert.clone()).await.unwrap();
+            }
+        }
+        validator.advance_round().await;
+    }
+    certs
+}
+
+#[tokio::test]
+async fn test_validator_recovers_after_restart() {
+    let dirs: Vec<TempDir> = (0..COMMITTEE_SIZE).map(|_| TempDir::new().unwrap()).collect();
+    let mut validators = Vec::new();
+    for (i, dir) in dirs.iter().enumerate() {
+        validators.push(start_validator(dir.path(), i).await);
+    }
+
+    let mut certs = Vec::new();
+    for round in 0..5 {
+        certs = run_round(&validators, round).await;
+    }
+
+    let highest_round = validators[0].get_highest_round().await;
+    let chain_tip = validators[0].get_chain_tip().await;
+    let committed = validators[0].get_committed_transactions(0, highest_round).await.unwrap();
+    let (own_batch, _) = certs[0].header.payload[0];
+    assert_eq!(highest_round, 4);
+    assert!(chain_tip > 0);
+
+    // Crash validator 0 and restart it on the same datastore
+    drop(validators.remove(0));
+    let restarted = start_validator(dirs[0].path(), 0).await;
+
+    assert_eq!(restarted.get_highest_round().await, highest_round);
+    assert!(restarted.has_complete_round(highest_round).await);
+    assert_eq!(restarted.get_chain_tip().await, chain_tip);
+    assert_eq!(restarted.get_current_round().await, highest_round + 1);
+    let recovered = restarted.get_committed_transactions(0, highest_round).await.unwrap();
+    assert_eq!(recovered.len(), committed.len());
+    assert!(matches!(
+        restarted.handle_sync_request(SyncRequest::batch(own_batch)).await,
+        SyncResponse::Batches { batches } if batches.len() == 1
+    ));
+
+    // The restarted validator proposes for the next round rather than one it
+    // already has a certificate in, so its peers accept it without seeing
+    // an equivocation, and consensus keeps committing
+    validators.insert(0, restarted);
+    let certs = run_round(&validators, 5).await;
+    assert_eq!(certs[0].header.round, highest_round + 1);
+    assert!(validators[0].get_chain_tip().await > chain_tip);
+}
+
+#[tokio::test]
+async fn test_fresh_datastore_starts_at_genesis() {
+    let dir = TempDir::new().unwrap();
+    let validator = start_validator(dir.path(), 0).await;
+
+    assert_eq!(validator.get_highest_round().await, 0);
+    assert_eq!(validator.get_current_round().await, 0);
+    assert_eq!(validator.get_chain_tip().await, 0);
+}
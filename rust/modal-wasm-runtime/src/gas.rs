@@ -4,6 +4,9 @@ pub const DEFAULT_GAS_LIMIT: u64 = 10_000_000;
 /// Maximum gas limit allowed (100 million instructions)
 pub const MAX_GAS_LIMIT: u64 = 100_000_000;
 
+/// Gas charged per byte of a WASM module that is validated
+pub const VALIDATION_GAS_PER_BYTE: u64 = 10;
+
 /// Gas metrics for tracking execution costs
 #[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub struct GasMetrics {
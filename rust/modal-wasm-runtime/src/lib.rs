@@ -4,7 +4,7 @@ pub mod registry;
 pub mod cache;
 
 pub use executor::WasmExecutor;
-pub use gas::{GasMetrics, DEFAULT_GAS_LIMIT, MAX_GAS_LIMIT};
+pub use gas::{GasMetrics, DEFAULT_GAS_LIMIT, MAX_GAS_LIMIT, VALIDATION_GAS_PER_BYTE};
 pub use registry::ModuleRegistry;
 pub use cache::{WasmModuleCache, CacheStats};
 
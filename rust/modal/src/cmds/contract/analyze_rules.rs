@@ -0,0 +1,150 @@
+use anyhow::Result;
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::utils::output;
+use modal_common::contract_store::ContractStore;
+use modality_lang::rule_coverage::{self, OverlapKind, RuleStatus};
+use modality_lang::{parse_content_lalrpop, Formula, FormulaParser};
+
+#[derive(Debug, Parser)]
+#[command(about = "Report what a contract's rules leave unconstrained, and rules that are vacuous or overlap")]
+pub struct Opts {
+    /// Contract directory (defaults to current directory)
+    #[clap(long)]
+    dir: Option<PathBuf>,
+
+    /// Model to analyze against (default: model/default.modality)
+    #[clap(long)]
+    model: Option<PathBuf>,
+
+    /// Output format (json, yaml or text)
+    #[clap(long, default_value = "text")]
+    output: String,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    let contract_dir = if let Some(d) = &opts.dir {
+        d.clone()
+    } else {
+        std::env::current_dir()?
+    };
+    let store = ContractStore::open(&contract_dir)?;
+
+    let model_path = opts
+        .model
+        .clone()
+        .unwrap_or_else(|| contract_dir.join("model").join("default.modality"));
+    let model_content = std::fs::read_to_string(&model_path)
+        .map_err(|e| anyhow::anyhow!("Failed to read model {}: {}", model_path.display(), e))?;
+    let model = parse_content_lalrpop(&model_content)
+        .map_err(|e| anyhow::anyhow!("Invalid model {}: {}", model_path.display(), e))?;
+
+    let mut rules = Vec::new();
+    for path in store.list_rules_files()? {
+        let Some(value) = store.read_rule(&path)? else {
+            continue;
+        };
+        let Some(content) = value.as_str() else {
+            continue;
+        };
+        rules.push(rule_formula(&path, content)?);
+    }
+
+    let coverage = rule_coverage::analyze(&model, &rules, &store.list_state_files()?);
+
+    let format = output::resolve(&opts.output);
+    if format.is_structured() {
+        return output::print_structured(format, &coverage);
+    }
+
+    println!("Rule coverage for {} ({} rule(s))", model.name, coverage.rules.len());
+    println!();
+    for rule in &coverage.rules {
+        let (icon, note) = match rule.status {
+            RuleStatus::Effective => ("✅", "holds in"),
+            RuleStatus::Vacuous => ("⚠️ ", "vacuous, holds in"),
+            RuleStatus::Unsatisfiable => ("❌", "unsatisfiable, holds in"),
+            RuleStatus::Unreachable => ("⚠️ ", "unreachable, holds in"),
+        };
+        println!(
+            "{} {}: {} {}/{} states",
+            icon, rule.name, note, rule.satisfying_states, rule.total_states
+        );
+        if !rule.unknown_actions.is_empty() {
+            println!("     not in the model: {}", rule.unknown_actions.join(", "));
+        }
+    }
+
+    if !coverage.unconstrained_actions.is_empty() {
+        println!();
+        println!("Actions no rule constrains:");
+        for action in &coverage.unconstrained_actions {
+            println!("  - {}", action);
+        }
+    }
+    if !coverage.unconstrained_paths.is_empty() {
+        println!();
+        println!("State paths no rule refers to:");
+        for path in &coverage.unconstrained_paths {
+            println!("  - {}", path);
+        }
+    }
+    if !coverage.overlaps.is_empty() {
+        println!();
+        println!("Overlapping rules:");
+        for overlap in &coverage.overlaps {
+            let relation = match overlap.kind {
+                OverlapKind::Equivalent => "is equivalent to",
+                OverlapKind::Implies => "implies",
+                OverlapKind::SharedActions => "shares actions with",
+            };
+            print!("  - {} {} {}", overlap.first, relation, overlap.second);
+            if !overlap.shared_actions.is_empty() {
+                print!(" ({})", overlap.shared_actions.join(", "));
+            }
+            println!();
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse the formula of a rule file (`rule { formula { ... } }`), named
+/// after the file
+fn rule_formula(path: &str, content: &str) -> Result<Formula> {
+    let name: String = std::path::Path::new(path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+
+    let start = content
+        .find("formula")
+        .ok_or_else(|| anyhow::anyhow!("{}: no formula", path))?;
+    let open = start + content[start..]
+        .find('{')
+        .ok_or_else(|| anyhow::anyhow!("{}: no formula body", path))?;
+    let mut depth = 0;
+    let mut close = None;
+    for (i, c) in content[open..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(open + i);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let close = close.ok_or_else(|| anyhow::anyhow!("{}: unclosed formula", path))?;
+
+    FormulaParser::new()
+        .parse(&format!("formula {} {{ {} }}", name, &content[open + 1..close]))
+        .map_err(|e| anyhow::anyhow!("{}: {:?}", path, e))
+}
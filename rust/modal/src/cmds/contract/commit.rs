@@ -3,8 +3,12 @@ use clap::Parser;
 use serde_json::Value;
 use std::path::PathBuf;
 
-use modal_common::contract_store::{ContractStore, CommitFile};
+use modal_common::contract_store::{ContractStore, CommitFile, OfflineCommit};
 use modal_common::keypair::Keypair;
+use modal_common::signer::Signer;
+use modal_common::hub_client::is_hub_url;
+use modal_node::actions::contract_sync::{self, SimulateCommitResponse};
+use modal_node::node::Node;
 
 #[derive(Debug, Parser)]
 #[command(about = "Add a commit to a local contract")]
@@ -60,6 +64,24 @@ pub struct Opts {
     /// Path to passfile for signing the commit
     #[clap(long)]
     sign: Option<PathBuf>,
+
+    /// Write the commit to a file for signing elsewhere (see
+    /// `modal contract sign`) instead of committing it
+    #[clap(long, conflicts_with = "sign")]
+    offline: bool,
+
+    /// Where to write the offline commit (default: commit-<digest>.json)
+    #[clap(long, requires = "offline")]
+    out: Option<PathBuf>,
+    
+    /// Dry-run the commit on a validator before signing it, and stop if it
+    /// would be rejected
+    #[clap(long, conflicts_with = "offline")]
+    simulate: bool,
+
+    /// Remote to simulate against (default: origin)
+    #[clap(long, default_value = "origin")]
+    remote_name: String,
     
     /// Commit all changes from state directory
     #[clap(short = 'a', long)]
@@ -192,6 +214,18 @@ pub async fn run(opts: &Opts) -> Result<()> {
         );
     }
 
+    if opts.offline {
+        return write_offline(opts, &store, config.contract_id, commit, parent_id);
+    }
+
+    if opts.simulate {
+        let remote_url = config.get_remote(&opts.remote_name)
+            .ok_or_else(|| anyhow::anyhow!("Remote '{}' not found. Add one with `modal contract remote add`.", opts.remote_name))?
+            .url
+            .clone();
+        simulate(opts, &config.contract_id, &remote_url, &commit).await?;
+    }
+
     // Sign the commit if a passfile is provided
     if let Some(passfile_path) = &opts.sign {
         let passfile_str = passfile_path.to_string_lossy();
@@ -199,8 +233,7 @@ pub async fn run(opts: &Opts) -> Result<()> {
         let public_key = keypair.public_key_as_base58_identity();
         
         // Sign the body (canonical JSON)
-        let body_json = serde_json::to_string(&commit.body)?;
-        let signature = keypair.sign_string_as_base64_pad(&body_json)?;
+        let signature = keypair.sign_string_as_base64_pad(&commit.signing_payload()?)?;
         
         // Add signature to head
         let sig_obj = serde_json::json!({
@@ -220,22 +253,7 @@ pub async fn run(opts: &Opts) -> Result<()> {
 
     // Replace $PARENT placeholder in rule values with parent commit ID
     if let Some(parent) = &parent_id {
-        for action in &mut commit.body {
-            if action.method == "rule" {
-                if let Value::String(s) = &action.value {
-                    if s.contains("$PARENT") {
-                        let replaced = s.replace("$PARENT", parent);
-                        
-                        // Also update the local rule file so it matches
-                        if let Some(path) = &action.path {
-                            let _ = store.write_rule(path, &Value::String(replaced.clone()));
-                        }
-                        
-                        action.value = Value::String(replaced);
-                    }
-                }
-            }
-        }
+        replace_parent_placeholder(&store, &mut commit, parent);
         // Recompute commit ID since content changed
         commit_id = commit.compute_id()?;
     }
@@ -270,6 +288,120 @@ pub async fn run(opts: &Opts) -> Result<()> {
     Ok(())
 }
 
+/// Dry-run the commit on the remote's validator and report what it would
+/// do, failing if the validator would reject it
+async fn simulate(opts: &Opts, contract_id: &str, remote_url: &str, commit: &CommitFile) -> Result<()> {
+    if is_hub_url(remote_url) {
+        anyhow::bail!("--simulate needs a validator remote, not a hub");
+    }
+
+    let mut node = Node::from_config(modal_node::config::Config::default()).await?;
+    let commit_json = serde_json::to_value(commit)?;
+    let result = contract_sync::simulate_commit(
+        &mut node,
+        remote_url,
+        contract_id,
+        commit_json["body"].clone(),
+        commit_json["head"].clone(),
+    ).await?;
+
+    if opts.output != "json" {
+        print_simulation(&result);
+    }
+    if !result.valid {
+        anyhow::bail!(
+            "Validators would reject this commit: {}",
+            result.error.as_deref().unwrap_or("unknown error")
+        );
+    }
+    Ok(())
+}
+
+fn print_simulation(result: &SimulateCommitResponse) {
+    if result.valid {
+        println!("🔎 Simulated on a validator (epoch {}): accepted", result.epoch);
+    } else {
+        println!("⚠️  Simulated on a validator (epoch {}): rejected", result.epoch);
+        if let Some(error) = &result.error {
+            println!("   Reason: {}", error);
+        }
+    }
+    println!("   Gas used: {}", result.gas_used);
+    if !result.state_changes.is_empty() {
+        println!("   State changes:");
+        for change in &result.state_changes {
+            println!("     - {}", change);
+        }
+    }
+    println!();
+}
+
+/// Replace $PARENT in rule values with the parent commit ID
+fn replace_parent_placeholder(store: &ContractStore, commit: &mut CommitFile, parent: &str) {
+    for action in &mut commit.body {
+        if action.method == "rule" {
+            if let Value::String(s) = &action.value {
+                if s.contains("$PARENT") {
+                    let replaced = s.replace("$PARENT", parent);
+                    
+                    // Also update the local rule file so it matches
+                    if let Some(path) = &action.path {
+                        let _ = store.write_rule(path, &Value::String(replaced.clone()));
+                    }
+                    
+                    action.value = Value::String(replaced);
+                }
+            }
+        }
+    }
+}
+
+/// Write the commit to a file to be signed elsewhere instead of committing
+/// it
+fn write_offline(
+    opts: &Opts,
+    store: &ContractStore,
+    contract_id: String,
+    mut commit: CommitFile,
+    parent_id: Option<String>,
+) -> Result<()> {
+    // Signatures cover the body, so it has to be final before it leaves
+    if let Some(parent) = &parent_id {
+        replace_parent_placeholder(store, &mut commit, parent);
+    }
+    commit.validate()?;
+
+    let offline = OfflineCommit::new(contract_id, commit)?;
+    let out = opts
+        .out
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(format!("commit-{}.json", &offline.digest[..12])));
+    offline.save(&out)?;
+
+    if opts.output == "json" {
+        println!("{}", serde_json::to_string_pretty(&serde_json::json!({
+            "contract_id": offline.contract_id,
+            "parent": parent_id,
+            "digest": offline.digest,
+            "file": out,
+            "status": "unsigned",
+        }))?);
+    } else {
+        println!("📝 Unsigned commit written to {}", out.display());
+        println!("   Contract ID: {}", offline.contract_id);
+        if let Some(parent) = parent_id {
+            println!("   Parent: {}", parent);
+        }
+        println!("   Digest: {}", offline.digest);
+        println!();
+        println!("Next steps:");
+        println!("  - modal contract sign --passfile <passfile> {}  (on the signing machine)", out.display());
+        println!("  - modal contract push --signed {}  (back here)", out.display());
+    }
+
+    Ok(())
+}
+
 fn build_create_value(opts: &Opts) -> Result<Value> {
     let asset_id = opts.asset_id.as_ref()
         .ok_or_else(|| anyhow::anyhow!("--asset-id is required for CREATE method"))?;
@@ -333,16 +465,21 @@ fn build_invoke_value(opts: &Opts) -> Result<Value> {
     }
 }
 
-/// Load a signing key from a passfile, prompting for password if encrypted
-fn load_signing_key(path: &str) -> anyhow::Result<Keypair> {
+/// Load a signing key from a passfile, prompting for password if encrypted,
+/// or connect to its hardware signer
+pub(crate) fn load_signing_key(path: &str) -> anyhow::Result<Box<dyn Signer>> {
+    let key = modal_common::passfile_v2::load_key(path)?;
+    if let Some(signer) = &key.signer {
+        return signer.connect(Some(&key.id));
+    }
     // Try loading as unencrypted first
-    let keypair = Keypair::from_json_file(path)?;
+    let keypair = key.keypair()?;
     if keypair.can_sign() {
-        return Ok(keypair);
+        return Ok(Box::new(keypair));
     }
     // Has encrypted private key — prompt for password
     eprint!("Password: ");
     let password = rpassword::read_password()
         .map_err(|e| anyhow::anyhow!("Failed to read password: {}", e))?;
-    Keypair::from_encrypted_json_file(path, &password)
+    Ok(Box::new(Keypair::from_encrypted_json_file(path, &password)?))
 }
@@ -0,0 +1,101 @@
+use anyhow::Result;
+use clap::Parser;
+use std::path::PathBuf;
+
+use modal_common::contract_store::ContractStore;
+use modal_common::hub_client::is_hub_url;
+use modal_node::actions::contract_sync;
+use modal_node::node::Node;
+
+use crate::utils::output;
+
+#[derive(Debug, Parser)]
+#[command(about = "Export a contract's state as of a finalized checkpoint, for offline verification")]
+pub struct Opts {
+    /// Epoch of the finalized checkpoint to export at
+    #[clap(long)]
+    at_checkpoint: u64,
+
+    /// Contract directory (defaults to current directory)
+    #[clap(long)]
+    dir: Option<PathBuf>,
+
+    /// Contract to export (default: the directory's contract)
+    #[clap(long)]
+    contract_id: Option<String>,
+
+    /// Validator remote to export from (default: origin)
+    #[clap(long, default_value = "origin")]
+    remote_name: String,
+
+    /// Path to passfile to sign the bundle with, vouching for it as exporter
+    #[clap(long)]
+    sign: Option<PathBuf>,
+
+    /// Where to write the bundle (default: <contract>-checkpoint-<epoch>.json)
+    #[clap(short = 'o', long)]
+    out: Option<PathBuf>,
+
+    /// Output format (json or text)
+    #[clap(long, default_value = "text")]
+    output: String,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    let dir = if let Some(d) = &opts.dir {
+        d.clone()
+    } else {
+        std::env::current_dir()?
+    };
+    let store = ContractStore::open(&dir)?;
+    let config = store.load_config()?;
+    let contract_id = opts.contract_id.clone().unwrap_or_else(|| config.contract_id.clone());
+    let remote_url = config.get_remote(&opts.remote_name)
+        .ok_or_else(|| anyhow::anyhow!("Remote '{}' not found. Add one with `modal contract remote add`.", opts.remote_name))?
+        .url
+        .clone();
+    if is_hub_url(&remote_url) {
+        anyhow::bail!("Exports come from a validator remote, not a hub");
+    }
+
+    let mut node = Node::from_config(modal_node::config::Config::default()).await?;
+    let mut export = contract_sync::export_contract(&mut node, &remote_url, &contract_id, opts.at_checkpoint).await?;
+
+    // Don't hand out a bundle that wouldn't verify
+    let report = export.verify(None)?;
+    if let Some(passfile) = &opts.sign {
+        let signer = super::commit::load_signing_key(&passfile.to_string_lossy())?;
+        export.sign(signer.as_ref())?;
+    }
+
+    let out = opts.out.clone().unwrap_or_else(|| {
+        PathBuf::from(format!("{}-checkpoint-{}.json", contract_id, opts.at_checkpoint))
+    });
+    std::fs::write(&out, serde_json::to_string_pretty(&export)?)?;
+
+    let format = output::resolve(&opts.output);
+    if format.is_structured() {
+        return output::print_structured(format, &serde_json::json!({
+            "file": out.display().to_string(),
+            "contract_id": contract_id,
+            "epoch": report.epoch,
+            "last_block_index": report.last_block_index,
+            "state_root": report.state_root,
+            "commits": report.commits,
+            "signed_by": export.exporter.as_ref().map(|e| e.signer.clone()),
+        }));
+    }
+
+    println!("📦 Exported {} at checkpoint {} (block {})", contract_id, report.epoch, report.last_block_index);
+    println!("   Commits:     {}", report.commits);
+    println!("   State paths: {}", report.state_paths);
+    println!("   State root:  {}", report.state_root);
+    println!("   Validators:  {}/{} signed the checkpoint", report.signers.len(), report.validators);
+    if let Some(exporter) = &export.exporter {
+        println!("   Signed by:   {}", exporter.signer);
+    }
+    println!("   Written to:  {}", out.display());
+    println!();
+    println!("Verify it anywhere with: modal contract verify-export {}", out.display());
+    Ok(())
+}
@@ -12,6 +12,7 @@ pub mod get;
 pub mod id;
 pub mod log;
 pub mod push;
+pub mod sign;
 pub mod pull;
 pub mod repost;
 pub mod status;
@@ -23,4 +24,8 @@ pub mod pack;
 pub mod unpack;
 pub mod remote;
 pub mod add_rule;
+pub mod analyze_rules;
 pub mod download;
+pub mod export;
+pub mod verify_export;
+pub mod watch;
@@ -5,7 +5,8 @@ use std::path::PathBuf;
 
 use modal_common::contract_store::{ContractStore, CommitFile};
 use modal_common::hub_client::{HubClient, HubCredentials, is_hub_url};
-use modal_node::actions::request;
+use modal_node::actions::{contract_sync, request};
+use modal_node::contract_sync::UnsupportedProtocol;
 use modal_node::node::Node;
 
 #[derive(Debug, Parser)]
@@ -35,6 +36,10 @@ pub struct Opts {
     #[clap(long)]
     hub_creds: Option<PathBuf>,
     
+    /// Keep pulling as the network announces new commits (node remotes only)
+    #[clap(long)]
+    follow: bool,
+
     /// Output format (json or text)
     #[clap(long, default_value = "text")]
     output: String,
@@ -66,6 +71,10 @@ pub async fn run(opts: &Opts) -> Result<()> {
             .url.clone()
     };
 
+    if opts.follow {
+        return follow(opts, &store, &config.contract_id, &remote_url).await;
+    }
+
     // Get current remote HEAD (what we last pulled)
     let since_commit = store.get_remote_head(&opts.remote_name)?;
 
@@ -89,48 +98,22 @@ pub async fn run(opts: &Opts) -> Result<()> {
         commits
     } else {
         // P2P node pull
-        let node_config = if let Some(node_dir) = &opts.node_dir {
-            let config_path = node_dir.join("config.json");
-            if config_path.exists() {
-                let config_json = std::fs::read_to_string(&config_path)?;
-                let mut config: modal_node::config::Config = serde_json::from_str(&config_json)?;
-                config.storage_path = None;
-                config.logs_path = None;
-                let passfile_path = node_dir.join("node.modal_passfile");
-                if passfile_path.exists() {
-                    config.passfile_path = Some(passfile_path);
-                }
-                config
-            } else {
-                modal_node::config::Config::default()
-            }
-        } else {
-            modal_node::config::Config::default()
-        };
-
-        let mut node = Node::from_config(node_config).await?;
-
-        let request_data = json!({
-            "contract_id": config.contract_id,
-            "since_commit_id": since_commit,
-        });
+        let mut node = Node::from_config(node_config(opts)?).await?;
 
-        let response = request::run(
+        match contract_sync::pull(
             &mut node,
-            remote_url.clone(),
-            "/contract/pull".to_string(),
-            serde_json::to_string(&request_data)?,
-        ).await?;
-
-        if !response.ok {
-            anyhow::bail!("Failed to pull commits: {:?}", response.errors);
+            &remote_url,
+            &config.contract_id,
+            store.list_commits()?,
+            &store.contract_dir().join("sync"),
+        ).await {
+            Ok(summary) => commit_values(summary.commits)?,
+            Err(e) if e.is::<UnsupportedProtocol>() => {
+                // Older nodes only have the reqres paths
+                pull_reqres(&mut node, &remote_url, &config.contract_id, since_commit).await?
+            }
+            Err(e) => return Err(e),
         }
-
-        let data = response.data.ok_or_else(|| anyhow::anyhow!("No data in response"))?;
-        data.get("commits")
-            .and_then(|c| c.as_array())
-            .cloned()
-            .ok_or_else(|| anyhow::anyhow!("Invalid response format"))?
     };
 
     if commits.is_empty() {
@@ -145,11 +128,38 @@ pub async fn run(opts: &Opts) -> Result<()> {
         return Ok(());
     }
 
+    let pulled_ids = apply_pulled(&store, &opts.remote_name, &commits)?;
+
+    if opts.output == "json" {
+        println!("{}", serde_json::to_string_pretty(&json!({
+            "status": "pulled",
+            "pulled_count": pulled_ids.len(),
+            "commits": pulled_ids,
+        }))?);
+    } else {
+        println!("✅ Successfully pulled {} commit(s)!", pulled_ids.len());
+        println!("   Contract ID: {}", config.contract_id);
+        println!("   Remote: {} ({})", opts.remote_name, remote_url);
+        println!();
+        if !pulled_ids.is_empty() {
+            println!("Pulled commits:");
+            for commit_id in &pulled_ids {
+                println!("  - {}", commit_id);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Save pulled commits, move the remote's HEAD and check out the new state.
+/// Returns the commits that were new to us.
+fn apply_pulled(store: &ContractStore, remote_name: &str, commits: &[serde_json::Value]) -> Result<Vec<String>> {
     // Save commits locally
     let mut pulled_ids = Vec::new();
     let mut latest_commit_id = None;
 
-    for commit_data in &commits {
+    for commit_data in commits {
         // Handle both hub format (hash/data/parent) and p2p format (commit_id/body/head)
         let commit_id = commit_data.get("hash")
             .or_else(|| commit_data.get("commit_id"))
@@ -189,7 +199,7 @@ pub async fn run(opts: &Opts) -> Result<()> {
 
     // Update remote HEAD
     if let Some(latest) = latest_commit_id {
-        store.set_remote_head(&opts.remote_name, &latest)?;
+        store.set_remote_head(remote_name, &latest)?;
         
         // If local HEAD is not set or is behind, update it
         let local_head = store.get_head()?;
@@ -203,21 +213,78 @@ pub async fn run(opts: &Opts) -> Result<()> {
         store.checkout_state()?;
     }
 
-    if opts.output == "json" {
-        println!("{}", serde_json::to_string_pretty(&json!({
-            "status": "pulled",
-            "pulled_count": pulled_ids.len(),
-            "commits": pulled_ids,
-        }))?);
-    } else {
-        println!("✅ Successfully pulled {} commit(s)!", pulled_ids.len());
-        println!("   Contract ID: {}", config.contract_id);
-        println!("   Remote: {} ({})", opts.remote_name, remote_url);
+
+    Ok(pulled_ids)
+}
+
+/// Commits fetched with the contract sync protocol, in the p2p pull format
+fn commit_values(commits: Vec<(String, Vec<u8>)>) -> Result<Vec<serde_json::Value>> {
+    let mut values = Vec::new();
+    for (commit_id, payload) in commits {
+        let commit: CommitFile = serde_json::from_slice(&payload)?;
+        values.push(json!({
+            "commit_id": commit_id,
+            "body": commit.body,
+            "head": commit.head,
+        }));
+    }
+    Ok(values)
+}
+
+/// Node config for the temporary node that talks to the remote
+fn node_config(opts: &Opts) -> Result<modal_node::config::Config> {
+    let Some(node_dir) = &opts.node_dir else {
+        return Ok(modal_node::config::Config::default());
+    };
+    let config_path = node_dir.join("config.json");
+    if !config_path.exists() {
+        return Ok(modal_node::config::Config::default());
+    }
+    let config_json = std::fs::read_to_string(&config_path)?;
+    let mut config: modal_node::config::Config = serde_json::from_str(&config_json)?;
+    config.storage_path = None;
+    config.logs_path = None;
+    let passfile_path = node_dir.join("node.modal_passfile");
+    if passfile_path.exists() {
+        config.passfile_path = Some(passfile_path);
+    }
+    Ok(config)
+}
+
+/// Pull, then pull again whenever the network announces a new commit of
+/// the contract, until interrupted
+async fn follow(opts: &Opts, store: &ContractStore, contract_id: &str, remote_url: &str) -> Result<()> {
+    if is_hub_url(remote_url) {
+        anyhow::bail!("--follow needs a node remote (a multiaddress), not a hub URL");
+    }
+
+    let mut node = Node::from_config(node_config(opts)?).await?;
+    contract_sync::follow(&mut node, remote_url).await?;
+    if opts.output != "json" {
+        println!("👀 Following {} on {} (Ctrl+C to stop)", contract_id, remote_url);
         println!();
-        if !pulled_ids.is_empty() {
-            println!("Pulled commits:");
-            for commit_id in &pulled_ids {
-                println!("  - {}", commit_id);
+    }
+
+    let sync_dir = store.contract_dir().join("sync");
+    loop {
+        let summary = contract_sync::pull(&mut node, remote_url, contract_id, store.list_commits()?, &sync_dir).await?;
+        let pulled_ids = apply_pulled(store, &opts.remote_name, &commit_values(summary.commits)?)?;
+        for commit_id in &pulled_ids {
+            if opts.output == "json" {
+                println!("{}", serde_json::to_string(&json!({
+                    "status": "pulled",
+                    "commit": commit_id,
+                }))?);
+            } else {
+                println!("⬇️  Pulled {}", commit_id);
+            }
+        }
+
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            announcement = contract_sync::next_announcement(&mut node, contract_id) => {
+                let announcement = announcement?;
+                log::debug!("Commit {} announced at height {}", announcement.commit_id, announcement.height);
             }
         }
     }
@@ -225,6 +292,37 @@ pub async fn run(opts: &Opts) -> Result<()> {
     Ok(())
 }
 
+/// Pull with a single `/contract/pull` request, for nodes without the
+/// contract sync protocol
+async fn pull_reqres(
+    node: &mut Node,
+    remote_url: &str,
+    contract_id: &str,
+    since_commit: Option<String>,
+) -> Result<Vec<serde_json::Value>> {
+    let request_data = json!({
+        "contract_id": contract_id,
+        "since_commit_id": since_commit,
+    });
+
+    let response = request::run(
+        node,
+        remote_url.to_string(),
+        "/contract/pull".to_string(),
+        serde_json::to_string(&request_data)?,
+    ).await?;
+
+    if !response.ok {
+        anyhow::bail!("Failed to pull commits: {:?}", response.errors);
+    }
+
+    let data = response.data.ok_or_else(|| anyhow::anyhow!("No data in response"))?;
+    data.get("commits")
+        .and_then(|c| c.as_array())
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("Invalid response format"))
+}
+
 /// Clone a contract from a full URL like https://hub/contracts/<id>
 /// Creates a local directory and pulls all commits via the public /log endpoint.
 async fn clone_from_url(url: &str, opts: &Opts) -> Result<()> {
@@ -1,11 +1,12 @@
 use anyhow::Result;
 use clap::Parser;
 use serde_json::json;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use modal_common::contract_store::ContractStore;
+use modal_common::contract_store::{ContractStore, OfflineCommit};
 use modal_common::hub_client::{HubClient, HubCredentials, is_hub_url};
-use modal_node::actions::request;
+use modal_node::actions::{contract_sync, request};
+use modal_node::contract_sync::UnsupportedProtocol;
 use modal_node::node::Node;
 
 #[derive(Debug, Parser)]
@@ -31,6 +32,10 @@ pub struct Opts {
     #[clap(long)]
     hub_creds: Option<PathBuf>,
     
+    /// Commit signed offline (see `modal contract sign`) to add before pushing
+    #[clap(long)]
+    signed: Option<PathBuf>,
+
     /// Output format (json or text)
     #[clap(long, default_value = "text")]
     output: String,
@@ -60,6 +65,10 @@ pub async fn run(opts: &Opts) -> Result<()> {
             .url.clone()
     };
 
+    if let Some(signed_path) = &opts.signed {
+        apply_signed_commit(&store, &config.contract_id, signed_path, &opts.output)?;
+    }
+
     // Get unpushed commits
     let unpushed = store.get_unpushed_commits(&opts.remote_name)?;
 
@@ -189,45 +198,145 @@ pub async fn run(opts: &Opts) -> Result<()> {
 
         let mut node = Node::from_config(node_config).await?;
 
-        let request_data = json!({
-            "contract_id": config.contract_id,
-            "commits": commits_data,
-        });
+        let mut payloads = Vec::new();
+        for commit_id in &unpushed {
+            payloads.push((commit_id.clone(), store.load_commit(commit_id)?.payload()?));
+        }
 
-        let response = request::run(
+        let response_data = match contract_sync::push(
             &mut node,
-            remote_url.clone(),
-            "/contract/push".to_string(),
-            serde_json::to_string(&request_data)?,
-        ).await?;
-
-        if response.ok {
-            if let Some(last_commit) = unpushed.last() {
-                store.set_remote_head(&opts.remote_name, last_commit)?;
-            }
-
-            if opts.output == "json" {
-                println!("{}", serde_json::to_string_pretty(&json!({
-                    "status": "pushed",
-                    "pushed_count": unpushed.len(),
-                    "commits": unpushed,
-                    "response": response.data,
-                }))?);
-            } else {
-                println!("✅ Successfully pushed {} commit(s)!", unpushed.len());
-                println!("   Contract ID: {}", config.contract_id);
-                println!("   Remote: {} ({})", opts.remote_name, remote_url);
-                println!();
-                println!("Pushed commits:");
-                for commit_id in &unpushed {
-                    println!("  - {}", commit_id);
+            &remote_url,
+            &config.contract_id,
+            store.list_commits()?,
+            &payloads,
+        ).await {
+            Ok(summary) => {
+                if !summary.resumed.is_empty() && opts.output != "json" {
+                    println!("↻ Resumed {} interrupted commit(s)", summary.resumed.len());
                 }
+                json!({
+                    "pushed": summary.pushed,
+                    "resumed": summary.resumed,
+                    "head": summary.head,
+                })
             }
+            Err(e) if e.is::<UnsupportedProtocol>() => {
+                // Older nodes only have the reqres paths
+                push_reqres(&mut node, &remote_url, &config.contract_id, commits_data).await?
+            }
+            Err(e) => return Err(e),
+        };
+
+        if let Some(last_commit) = unpushed.last() {
+            store.set_remote_head(&opts.remote_name, last_commit)?;
+        }
+
+        if opts.output == "json" {
+            println!("{}", serde_json::to_string_pretty(&json!({
+                "status": "pushed",
+                "pushed_count": unpushed.len(),
+                "commits": unpushed,
+                "response": response_data,
+            }))?);
         } else {
-            anyhow::bail!("Failed to push commits: {:?}", response.errors);
+            println!("✅ Successfully pushed {} commit(s)!", unpushed.len());
+            println!("   Contract ID: {}", config.contract_id);
+            println!("   Remote: {} ({})", opts.remote_name, remote_url);
+            println!();
+            println!("Pushed commits:");
+            for commit_id in &unpushed {
+                println!("  - {}", commit_id);
+            }
         }
     }
 
     Ok(())
 }
 
+/// Push with a single `/contract/push` request, for nodes without the
+/// contract sync protocol
+async fn push_reqres(
+    node: &mut Node,
+    remote_url: &str,
+    contract_id: &str,
+    commits_data: Vec<serde_json::Value>,
+) -> Result<serde_json::Value> {
+    let request_data = json!({
+        "contract_id": contract_id,
+        "commits": commits_data,
+    });
+
+    let response = request::run(
+        node,
+        remote_url.to_string(),
+        "/contract/push".to_string(),
+        serde_json::to_string(&request_data)?,
+    ).await?;
+
+    if !response.ok {
+        anyhow::bail!("Failed to push commits: {:?}", response.errors);
+    }
+    Ok(response.data.unwrap_or_default())
+}
+
+/// Add a commit signed offline to the contract, checking it's still valid
+/// here
+fn apply_signed_commit(
+    store: &ContractStore,
+    contract_id: &str,
+    path: &Path,
+    output: &str,
+) -> Result<()> {
+    let offline = OfflineCommit::load(path)?;
+    if offline.contract_id != contract_id {
+        anyhow::bail!(
+            "{} is for contract {}, not {}",
+            path.display(),
+            offline.contract_id,
+            contract_id
+        );
+    }
+
+    let signers = offline.verify_signatures()?;
+    if signers.is_empty() {
+        anyhow::bail!(
+            "{} isn't signed. Sign it with: modal contract sign --passfile <passfile> {}",
+            path.display(),
+            path.display()
+        );
+    }
+
+    let commit = offline.commit;
+    let commit_id = commit.compute_id()?;
+    if store.has_commit(&commit_id) {
+        // Already applied, e.g. by an earlier push that failed to send
+        return Ok(());
+    }
+
+    let head = store.get_head()?;
+    if commit.head.parent != head {
+        anyhow::bail!(
+            "{} was made on parent {}, but HEAD is now {}; make the commit again",
+            path.display(),
+            commit.head.parent.as_deref().unwrap_or("(none)"),
+            head.as_deref().unwrap_or("(none)")
+        );
+    }
+
+    commit.validate()?;
+    store.validate_commit_against_rules(&commit)?;
+
+    store.save_commit(&commit_id, &commit)?;
+    store.set_head(&commit_id)?;
+
+    if output != "json" {
+        println!("✍️  Applied signed commit {}", commit_id);
+        println!("   Digest: {}", offline.digest);
+        for signer in &signers {
+            println!("   Signed by: {}", signer);
+        }
+        println!();
+    }
+
+    Ok(())
+}
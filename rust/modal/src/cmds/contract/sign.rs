@@ -0,0 +1,93 @@
+use anyhow::Result;
+use clap::Parser;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use modal_common::contract_store::OfflineCommit;
+
+#[derive(Debug, Parser)]
+#[command(about = "Sign a commit made with `modal contract commit --offline`")]
+pub struct Opts {
+    /// Offline commit file
+    file: PathBuf,
+
+    /// Path to passfile for signing the commit
+    #[clap(long)]
+    passfile: String,
+
+    /// Where to write the signed commit (default: overwrite the file)
+    #[clap(long)]
+    out: Option<PathBuf>,
+
+    /// Sign without asking for confirmation
+    #[clap(long, short = 'y')]
+    yes: bool,
+
+    /// Output format (json or text)
+    #[clap(long, default_value = "text")]
+    output: String,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    let mut offline = OfflineCommit::load(&opts.file)?;
+    let existing = offline.verify_signatures()?;
+
+    if opts.output != "json" {
+        println!("📝 Commit to sign");
+        println!("   Contract ID: {}", offline.contract_id);
+        println!("   Parent: {}", offline.commit.head.parent.as_deref().unwrap_or("(none)"));
+        println!("   Actions:");
+        for action in &offline.commit.body {
+            println!(
+                "     - {} {} = {}",
+                action.method,
+                action.path.as_deref().unwrap_or("-"),
+                action.value
+            );
+        }
+        for signer in &existing {
+            println!("   Signed by: {}", signer);
+        }
+        println!("   Digest: {}", offline.digest);
+    }
+
+    // The digest is what to compare with the machine that made the commit
+    if !opts.yes {
+        println!();
+        print!("Does the digest match and do you want to sign? (yes/no): ");
+        io::stdout().flush()?;
+
+        let mut response = String::new();
+        io::stdin().read_line(&mut response)?;
+        let response = response.trim().to_lowercase();
+
+        if response != "yes" && response != "y" {
+            println!("❌  Signing cancelled.");
+            return Ok(());
+        }
+    }
+
+    let signer = super::commit::load_signing_key(&opts.passfile)?;
+    let signed_by = offline.sign(signer.as_ref())?;
+
+    let out = opts.out.clone().unwrap_or_else(|| opts.file.clone());
+    offline.save(&out)?;
+
+    if opts.output == "json" {
+        println!("{}", serde_json::to_string_pretty(&serde_json::json!({
+            "contract_id": offline.contract_id,
+            "digest": offline.digest,
+            "signed_by": signed_by,
+            "file": out,
+            "status": "signed",
+        }))?);
+    } else {
+        println!("✍️  Signed by {}", signed_by);
+        println!("   Written to {}", out.display());
+        println!();
+        println!("Next steps:");
+        println!("  - modal contract push --signed {}  (on the machine with the contract)", out.display());
+    }
+
+    Ok(())
+}
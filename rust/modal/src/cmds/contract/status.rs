@@ -3,6 +3,7 @@ use clap::Parser;
 use serde_json::json;
 use std::path::PathBuf;
 
+use crate::utils::output;
 use modal_common::contract_store::ContractStore;
 use modality_lang::parse_content_lalrpop;
 
@@ -17,7 +18,7 @@ pub struct Opts {
     #[clap(long, default_value = "origin")]
     remote: String,
     
-    /// Output format (json or text)
+    /// Output format (json, yaml or text)
     #[clap(long, default_value = "text")]
     output: String,
 }
@@ -129,8 +130,9 @@ pub async fn run(opts: &Opts) -> Result<()> {
     
     let has_changes = !added.is_empty() || !modified.is_empty() || !deleted.is_empty();
 
-    if opts.output == "json" {
-        println!("{}", serde_json::to_string_pretty(&json!({
+    let format = output::resolve(&opts.output);
+    if format.is_structured() {
+        output::print_structured(format, &json!({
             "contract_id": config.contract_id,
             "directory": contract_dir.display().to_string(),
             "model_state": current_model_state,
@@ -146,7 +148,7 @@ pub async fn run(opts: &Opts) -> Result<()> {
                 "modified": modified,
                 "deleted": deleted,
             },
-        }))?);
+        }))?;
     } else {
         println!("Contract Status");
         println!("═══════════════");
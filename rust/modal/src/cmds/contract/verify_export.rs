@@ -0,0 +1,51 @@
+use anyhow::Result;
+use clap::Parser;
+use std::path::PathBuf;
+
+use modal_node::contract_export::ContractExport;
+
+use crate::utils::output;
+
+#[derive(Debug, Parser)]
+#[command(about = "Verify a contract export offline")]
+pub struct Opts {
+    /// Export bundle written by `modal contract export`
+    file: PathBuf,
+
+    /// Validator set to check the checkpoint against, instead of the one in
+    /// the bundle (comma separated peer ids)
+    #[clap(long, value_delimiter = ',')]
+    validators: Option<Vec<String>>,
+
+    /// Output format (json or text)
+    #[clap(long, default_value = "text")]
+    output: String,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    let content = std::fs::read_to_string(&opts.file)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", opts.file.display(), e))?;
+    let export: ContractExport = serde_json::from_str(&content)?;
+    let report = export.verify(opts.validators.as_deref())?;
+
+    let format = output::resolve(&opts.output);
+    if format.is_structured() {
+        return output::print_structured(format, &report);
+    }
+
+    println!("✅ Export of {} verifies", report.contract_id);
+    println!("   Checkpoint:  epoch {}, block {} ({})", report.epoch, report.last_block_index, report.last_block_hash);
+    println!("   Validators:  {}/{} signed", report.signers.len(), report.validators);
+    println!("   Commits:     {}", report.commits);
+    println!("   State paths: {}", report.state_paths);
+    println!("   State root:  {}", report.state_root);
+    match &report.exporter {
+        Some(exporter) => println!("   Exported by: {}", exporter),
+        None => println!("   Exported by: (unsigned)"),
+    }
+    if !report.validators_trusted {
+        println!();
+        println!("⚠️  The validator set came from the bundle; pass --validators to check it against a set you trust");
+    }
+    Ok(())
+}
@@ -0,0 +1,189 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use colored::Colorize;
+use serde_json::Value;
+
+use modal_rpc::client::{RpcClient, RpcClientConfig};
+use modal_rpc::types::{EventNotification, EventType};
+use modal_node::actions::contract_sync;
+use modal_node::node::Node;
+
+#[derive(Debug, Parser)]
+#[command(about = "Stream a contract's commits, state changes and rule verdicts")]
+pub struct Opts {
+    /// Contract ID to watch
+    contract_id: String,
+
+    /// WebSocket RPC endpoint of the validator or hub
+    #[clap(long, default_value_t = format!("ws://localhost:{}/ws", modal_rpc::DEFAULT_PORT))]
+    url: String,
+
+    /// Follow commit announcements from this node (multiaddress) instead of
+    /// an RPC endpoint. Only new commits are shown.
+    #[clap(long, conflicts_with = "url")]
+    node: Option<String>,
+
+    /// Only show state changes under this path (repeatable)
+    #[clap(long = "path")]
+    paths: Vec<String>,
+
+    /// Print one JSON event per line, for piping
+    #[clap(long)]
+    json: bool,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    if let Some(target) = &opts.node {
+        return watch_node(opts, target).await;
+    }
+
+    let mut client = RpcClient::connect(RpcClientConfig {
+        url: opts.url.clone(),
+        ..Default::default()
+    })
+    .await
+    .with_context(|| format!("Failed to connect to {}", opts.url))?;
+    let mut events = client
+        .take_event_receiver()
+        .context("Event stream already taken")?;
+
+    let events_wanted = vec![EventType::NewCommit, EventType::ContractUpdate, EventType::RuleVerdict];
+    client.subscribe(Some(&opts.contract_id), events_wanted).await?;
+
+    if !opts.json {
+        println!("👀 Watching {} on {} (Ctrl+C to stop)\n", opts.contract_id.cyan(), opts.url);
+    }
+
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            event = events.recv() => {
+                let Some(event) = event else {
+                    anyhow::bail!("Connection to {} closed", opts.url);
+                };
+                let Some(event) = filter_paths(event, &opts.paths) else {
+                    continue;
+                };
+                if opts.json {
+                    println!("{}", serde_json::to_string(&event)?);
+                } else {
+                    print_event(&event);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Watch commits as nodes announce them over gossip, fetching each one
+async fn watch_node(opts: &Opts, target: &str) -> Result<()> {
+    let mut node = Node::from_config(modal_node::config::Config::default()).await?;
+    contract_sync::follow(&mut node, target)
+        .await
+        .with_context(|| format!("Failed to connect to {}", target))?;
+
+    if !opts.json {
+        println!("👀 Watching {} on {} (Ctrl+C to stop)\n", opts.contract_id.cyan(), target);
+    }
+
+    let partial_dir = std::env::temp_dir().join("modal-watch");
+    loop {
+        let announcement = tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            announcement = contract_sync::next_announcement(&mut node, &opts.contract_id) => announcement?,
+        };
+        let payload = contract_sync::fetch(&mut node, target, &opts.contract_id, &announcement.commit_id, &partial_dir).await?;
+        let commit: Value = serde_json::from_slice(&payload)?;
+
+        let event = EventNotification {
+            subscription_id: String::new(),
+            event_type: EventType::NewCommit,
+            contract_id: Some(announcement.contract_id),
+            data: serde_json::json!({
+                "hash": announcement.commit_id,
+                "height": announcement.height,
+                "parent": commit.get("head").and_then(|h| h.get("parent")),
+                "body": commit.get("body"),
+            }),
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)?
+                .as_secs(),
+        };
+        if opts.json {
+            println!("{}", serde_json::to_string(&event)?);
+        } else {
+            print_event(&event);
+        }
+    }
+
+    Ok(())
+}
+
+/// Drop state changes outside `paths` from a contract update, and the
+/// update itself if none are left. Other events pass through.
+fn filter_paths(mut event: EventNotification, paths: &[String]) -> Option<EventNotification> {
+    if paths.is_empty() || event.event_type != EventType::ContractUpdate {
+        return Some(event);
+    }
+    let under_paths = |change: &Value| {
+        let path = change.get("path").and_then(|p| p.as_str()).unwrap_or("");
+        paths.iter().any(|prefix| is_under(path, prefix))
+    };
+    let changes = event.data.get_mut("changes")?.as_array_mut()?;
+    changes.retain(under_paths);
+    if changes.is_empty() {
+        None
+    } else {
+        Some(event)
+    }
+}
+
+/// Whether `path` is `prefix` or inside it, ignoring leading slashes
+fn is_under(path: &str, prefix: &str) -> bool {
+    let path = path.trim_start_matches('/');
+    let prefix = prefix.trim_matches('/');
+    prefix.is_empty()
+        || path == prefix
+        || path.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('/'))
+}
+
+fn print_event(event: &EventNotification) {
+    let time = chrono::DateTime::from_timestamp(event.timestamp as i64, 0)
+        .map(|dt| dt.format("%H:%M:%S").to_string())
+        .unwrap_or_default();
+    let data = &event.data;
+    let str_field = |name: &str| data.get(name).and_then(|v| v.as_str()).unwrap_or("").to_string();
+
+    match event.event_type {
+        EventType::NewCommit => {
+            let actions = data.get("body").and_then(|b| b.as_array()).map(|b| b.len()).unwrap_or(0);
+            println!("{} {} {} ({} actions)", time.dimmed(), "commit".bold(), str_field("hash").yellow(), actions);
+        }
+        EventType::ContractUpdate => {
+            for change in data.get("changes").and_then(|c| c.as_array()).into_iter().flatten() {
+                let path = change.get("path").and_then(|p| p.as_str()).unwrap_or("");
+                let new = change.get("new").filter(|v| !v.is_null());
+                match new {
+                    Some(value) => println!("{}   {} = {}", time.dimmed(), path.cyan(), value),
+                    None => println!("{}   {} {}", time.dimmed(), path.cyan(), "removed".red()),
+                }
+            }
+        }
+        EventType::RuleVerdict => {
+            let accepted = data.get("accepted").and_then(|a| a.as_bool()).unwrap_or(false);
+            if accepted {
+                println!("{} {} {}", time.dimmed(), "✓ accepted".green(), str_field("commit_hash"));
+            } else {
+                println!(
+                    "{} {} {}: {}",
+                    time.dimmed(),
+                    "✗ rejected".red(),
+                    str_field("commit_hash"),
+                    str_field("reason")
+                );
+            }
+        }
+        _ => println!("{} {:?} {}", time.dimmed(), event.event_type, data),
+    }
+}
@@ -0,0 +1,50 @@
+use anyhow::Result;
+use clap::Parser;
+use std::path::PathBuf;
+
+use modal_devnet::local::LocalDevnet;
+
+use super::{running_pid, DEFAULT_DEVNET_DIR};
+use crate::cmds::node::stop;
+
+#[derive(Debug, Parser)]
+#[command(about = "Stop the nodes of a local devnet")]
+pub struct Opts {
+    /// Devnet directory
+    #[clap(long, default_value = DEFAULT_DEVNET_DIR)]
+    pub dir: PathBuf,
+
+    /// Force kill (SIGKILL) instead of graceful shutdown (SIGTERM)
+    #[clap(long, short)]
+    pub force: bool,
+
+    /// Remove the devnet directory once the nodes are stopped
+    #[clap(long)]
+    pub clean: bool,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    let devnet = LocalDevnet::load(&opts.dir)?;
+
+    for node in &devnet.nodes {
+        let node_dir = LocalDevnet::node_dir(&opts.dir, node);
+        if running_pid(&node_dir).is_none() {
+            println!("✓ {} is not running", node.name);
+            continue;
+        }
+        println!("■ Stopping {}", node.name);
+        stop::run(&stop::Opts {
+            config: None,
+            dir: Some(node_dir),
+            force: opts.force,
+        })
+        .await?;
+    }
+
+    if opts.clean {
+        std::fs::remove_dir_all(&opts.dir)?;
+        println!("🧹 Removed {}", opts.dir.display());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::path::PathBuf;
+
+use modal_devnet::local::LocalDevnet;
+
+use super::DEFAULT_DEVNET_DIR;
+use crate::cmds::node::logs;
+
+#[derive(Debug, Parser)]
+#[command(about = "Show the logs of a devnet node")]
+pub struct Opts {
+    /// Node name (e.g. node1)
+    pub node: String,
+
+    /// Devnet directory
+    #[clap(long, default_value = DEFAULT_DEVNET_DIR)]
+    pub dir: PathBuf,
+
+    /// Number of lines to show from the end of the log (default: 50)
+    #[clap(long, short = 'n', default_value = "50")]
+    pub lines: usize,
+
+    /// Follow the log file (like tail -f)
+    #[clap(long, short = 'f')]
+    pub follow: bool,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    let devnet = LocalDevnet::load(&opts.dir)?;
+    let node = devnet.node(&opts.node).with_context(|| {
+        let names: Vec<&str> = devnet.nodes.iter().map(|n| n.name.as_str()).collect();
+        format!("No node '{}' in the devnet; nodes are {}", opts.node, names.join(", "))
+    })?;
+
+    logs::run(&logs::Opts {
+        config: None,
+        dir: Some(LocalDevnet::node_dir(&opts.dir, node)),
+        lines: opts.lines,
+        follow: opts.follow,
+        // Logs of a node that crashed are the interesting ones
+        offline: true,
+    })
+    .await
+}
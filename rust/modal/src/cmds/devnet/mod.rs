@@ -0,0 +1,33 @@
+//! Local devnet commands.
+//!
+//! `up` lays out a devnet of miners, validators and observers with the
+//! devnet keypairs and starts each node in the background; `status`,
+//! `logs` and `down` find the nodes through the layout it writes.
+
+use std::path::Path;
+
+pub mod down;
+pub mod logs;
+pub mod status;
+pub mod up;
+
+/// Default devnet directory
+pub const DEFAULT_DEVNET_DIR: &str = "./devnet";
+
+/// PID of a node, if it's running
+pub(crate) fn running_pid(node_dir: &Path) -> Option<u32> {
+    let pid = modal_node::pid::read_pid_file(node_dir).ok().flatten()?;
+    #[cfg(unix)]
+    {
+        use nix::sys::signal;
+        use nix::unistd::Pid;
+
+        signal::kill(Pid::from_raw(pid as i32), None).ok().map(|_| pid)
+    }
+
+    #[cfg(not(unix))]
+    {
+        // On non-Unix, assume running if PID file exists
+        Some(pid)
+    }
+}
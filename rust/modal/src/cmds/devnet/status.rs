@@ -0,0 +1,66 @@
+use anyhow::Result;
+use clap::Parser;
+use serde_json::json;
+use std::path::PathBuf;
+
+use modal_devnet::local::LocalDevnet;
+
+use super::{running_pid, DEFAULT_DEVNET_DIR};
+use crate::utils::output;
+
+#[derive(Debug, Parser)]
+#[command(about = "Show the nodes of a local devnet")]
+pub struct Opts {
+    /// Devnet directory
+    #[clap(long, default_value = DEFAULT_DEVNET_DIR)]
+    pub dir: PathBuf,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    let devnet = LocalDevnet::load(&opts.dir)?;
+    let pids: Vec<Option<u32>> = devnet
+        .nodes
+        .iter()
+        .map(|node| running_pid(&LocalDevnet::node_dir(&opts.dir, node)))
+        .collect();
+
+    let format = output::global();
+    if format.is_structured() {
+        let nodes: Vec<_> = devnet
+            .nodes
+            .iter()
+            .zip(&pids)
+            .map(|(node, pid)| json!({
+                "name": node.name,
+                "role": node.role,
+                "peer_id": node.peer_id,
+                "address": node.address(),
+                "running": pid.is_some(),
+                "pid": pid,
+            }))
+            .collect();
+        return output::print_structured(format, &nodes);
+    }
+
+    println!("Devnet in {}\n", opts.dir.display());
+    println!("{:<8} {:<10} {:<6} {:<9} {:<8} PEER ID", "NODE", "ROLE", "PORT", "STATUS", "PID");
+    for (node, pid) in devnet.nodes.iter().zip(&pids) {
+        let (status, pid) = match pid {
+            Some(pid) => ("running", pid.to_string()),
+            None => ("stopped", "-".to_string()),
+        };
+        println!(
+            "{:<8} {:<10} {:<6} {:<9} {:<8} {}",
+            node.name,
+            node.role.as_str(),
+            node.port,
+            status,
+            pid,
+            node.peer_id
+        );
+    }
+    let running = pids.iter().filter(|p| p.is_some()).count();
+    println!("\n{} of {} nodes running", running, devnet.nodes.len());
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use anyhow::{bail, Result};
+use clap::Parser;
+use std::path::PathBuf;
+
+use modal_devnet::local::{LocalDevnet, DEFAULT_BASE_PORT, LAYOUT_FILE};
+
+use super::{running_pid, DEFAULT_DEVNET_DIR};
+use crate::cmds::node::start;
+
+#[derive(Debug, Parser)]
+#[command(about = "Start a local multi-node devnet")]
+pub struct Opts {
+    /// Number of nodes
+    #[clap(long, default_value = "3")]
+    pub nodes: usize,
+
+    /// How many of the nodes mine
+    #[clap(long, default_value = "1")]
+    pub miners: usize,
+
+    /// How many of the nodes validate; the rest observe
+    #[clap(long, default_value = "2")]
+    pub validators: usize,
+
+    /// Devnet directory, holding a directory per node
+    #[clap(long, default_value = DEFAULT_DEVNET_DIR)]
+    pub dir: PathBuf,
+
+    /// Port of the first node; the others use the following ports
+    #[clap(long, default_value_t = DEFAULT_BASE_PORT)]
+    pub base_port: u16,
+
+    /// Remove an existing devnet in the directory and lay out a new one
+    #[clap(long)]
+    pub fresh: bool,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    let existing = opts.dir.join(LAYOUT_FILE).exists();
+    if existing && opts.fresh {
+        if let Ok(devnet) = LocalDevnet::load(&opts.dir) {
+            if devnet.nodes.iter().any(|n| running_pid(&LocalDevnet::node_dir(&opts.dir, n)).is_some()) {
+                bail!("The devnet in {} is running. Use 'modal devnet down' first.", opts.dir.display());
+            }
+        }
+        std::fs::remove_dir_all(&opts.dir)?;
+    }
+
+    let devnet = if existing && !opts.fresh {
+        println!("📂 Restarting the devnet in {} (use --fresh for a new one)", opts.dir.display());
+        LocalDevnet::load(&opts.dir)?
+    } else {
+        if opts.dir.exists() && opts.dir.read_dir()?.next().is_some() {
+            bail!("{} exists and isn't a devnet directory", opts.dir.display());
+        }
+        let devnet = LocalDevnet::plan(opts.nodes, opts.miners, opts.validators, opts.base_port)?;
+        devnet.write(&opts.dir)?;
+        println!(
+            "🏗️  Created a devnet of {} nodes in {}",
+            devnet.nodes.len(),
+            opts.dir.display()
+        );
+        devnet
+    };
+
+    for node in &devnet.nodes {
+        let node_dir = LocalDevnet::node_dir(&opts.dir, node);
+        if let Some(pid) = running_pid(&node_dir) {
+            println!("✓ {} is already running (PID {})", node.name, pid);
+            continue;
+        }
+        println!("\n▶ {} ({}, port {})", node.name, node.role.as_str(), node.port);
+        start::run(&start::Opts {
+            config: None,
+            dir: Some(node_dir),
+            node_type: None,
+        })
+        .await?;
+    }
+
+    println!("\n🚀 Devnet is up. Check on it with:");
+    println!("   modal devnet status --dir {}", opts.dir.display());
+    println!("   modal devnet logs node1 --dir {}", opts.dir.display());
+    println!("   modal devnet down --dir {}", opts.dir.display());
+
+    Ok(())
+}
@@ -6,7 +6,7 @@
 use ed25519_dalek::{Signature, Verifier, VerifyingKey};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use sha2::{Digest, Sha256};
+use modal_common::canonical::CanonicalHash;
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -1212,7 +1212,7 @@ impl HubCore {
         });
 
         // Create message to verify (canonical body JSON)
-        let message = serde_json::to_string(body).unwrap_or_default();
+        let message = body.canonical_json().unwrap_or_default();
         let message_hex = hex::encode(message.as_bytes());
 
         // Validate any_signed if required
@@ -1529,10 +1529,7 @@ fn compute_hash(body: &Value, head: &Value) -> String {
         "body": body,
         "head": head,
     });
-    let json_str = serde_json::to_string(&commit_json).unwrap_or_default();
-    let mut hasher = Sha256::new();
-    hasher.update(json_str.as_bytes());
-    format!("{:x}", hasher.finalize())
+    commit_json.canonical_hash().unwrap_or_default()
 }
 
 #[cfg(test)]
@@ -10,14 +10,16 @@ use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use sha2::{Sha256, Digest};
+use tokio::sync::{broadcast, RwLock};
+use modal_common::canonical::CanonicalHash;
 
 /// Hub state
 pub struct HubHandler {
     data_dir: PathBuf,
     /// In-memory cache of contracts: contract_id -> commits
     contracts: Arc<RwLock<HashMap<String, ContractData>>>,
+    /// Commit, state change and rule verdict events for WebSocket subscribers
+    events: broadcast::Sender<EventNotification>,
 }
 
 /// Contract data stored in hub
@@ -68,12 +70,53 @@ struct StoredCommit {
 impl HubHandler {
     /// Create a new hub handler
     pub fn new(data_dir: PathBuf) -> Self {
+        let (events, _) = broadcast::channel(1000);
         Self {
             data_dir,
             contracts: Arc::new(RwLock::new(HashMap::new())),
+            events,
         }
     }
 
+    /// Sender the RPC server forwards events from
+    pub fn event_sender(&self) -> broadcast::Sender<EventNotification> {
+        self.events.clone()
+    }
+
+    /// Publish an event about a contract
+    fn emit(&self, event_type: EventType, contract_id: &str, data: Value) {
+        // No receivers just means nobody is watching
+        let _ = self.events.send(EventNotification {
+            subscription_id: String::new(),
+            event_type,
+            contract_id: Some(contract_id.to_string()),
+            data,
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        });
+    }
+
+    /// Paths whose values differ between two states built by `build_state`
+    fn state_changes(before: &Value, after: &Value) -> Vec<Value> {
+        let empty = serde_json::Map::new();
+        let before = before.as_object().unwrap_or(&empty);
+        let after = after.as_object().unwrap_or(&empty);
+        let mut paths: Vec<&String> = before.keys().chain(after.keys()).collect();
+        paths.sort();
+        paths.dedup();
+        paths
+            .into_iter()
+            .filter(|path| before.get(*path) != after.get(*path))
+            .map(|path| json!({
+                "path": format!("/{}", path),
+                "old": before.get(path),
+                "new": after.get(path),
+            }))
+            .collect()
+    }
+
     /// Load existing contracts from disk
     pub async fn load_from_disk(&self) -> Result<(), std::io::Error> {
         let contracts_dir = self.data_dir.join("contracts");
@@ -210,6 +253,86 @@ impl HubHandler {
         Ok(())
     }
 
+    /// Check a commit's actions against the contract's rules and state
+    async fn check_commit(&self, contract_id: &str, body: &Value, head: &Value) -> Result<(), RpcError> {
+        let contracts = self.contracts.read().await;
+        
+        if let Some(actions) = body.as_array() {
+            for action in actions {
+                let method = action.get("method")
+                    .and_then(|m| m.as_str())
+                    .unwrap_or("")
+                    .to_lowercase();
+
+                match method.as_str() {
+                    "repost" => {
+                        // Validate REPOST against source contract
+                        self.validate_repost(body).await?;
+                    }
+                    "create" => {
+                        self.validate_create(contract_id, action, &contracts).await?;
+                    }
+                    "send" => {
+                        self.validate_send(contract_id, action, &contracts).await?;
+                    }
+                    "recv" => {
+                        self.validate_recv(contract_id, action, &contracts).await?;
+                    }
+                    "model" => {
+                        // Validate MODEL commit: new model must satisfy all existing rules
+                        if let Some(contract) = contracts.get(contract_id) {
+                            let model_content = action.get("value")
+                                .and_then(|v| v.as_str())
+                                .unwrap_or("");
+                            
+                            self.validate_model(contract_id, model_content, &contract.commits)?;
+                        }
+                    }
+                    "action" => {
+                        let action_name = action.get("action")
+                            .and_then(|a| a.as_str())
+                            .unwrap_or("");
+                        
+                        if let Some(contract) = contracts.get(contract_id) {
+                            let state = self.build_state(&contract.commits);
+                            let signers: Vec<String> = head.get("signatures")
+                                .and_then(|s| s.as_object())
+                                .map(|obj| obj.keys().cloned().collect())
+                                .unwrap_or_default();
+                            
+                            match action_name {
+                                "WITHDRAW" => {
+                                    self.validate_withdraw(action, &state, &signers)?;
+                                }
+                                "ADD_MEMBER" => {
+                                    self.validate_add_member(&state, &signers)?;
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                    _ => {
+                        // post, rule, genesis, etc. - no special validation needed
+                    }
+                }
+            }
+        }
+        
+        // For contracts with /members.json, validate signer is a member
+        if let Some(contract) = contracts.get(contract_id) {
+            let state = self.build_state(&contract.commits);
+            if state.get("members.json").is_some() {
+                let signers: Vec<String> = head.get("signatures")
+                    .and_then(|s| s.as_object())
+                    .map(|obj| obj.keys().cloned().collect())
+                    .unwrap_or_default();
+                self.validate_member_signed(&state, &signers)?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Build state from commits
     fn build_state(&self, commits: &[StoredCommit]) -> Value {
         let mut state = serde_json::Map::new();
@@ -337,10 +460,7 @@ impl HubHandler {
             "body": body,
             "head": head,
         });
-        let json_str = serde_json::to_string(&commit_json).unwrap_or_default();
-        let mut hasher = Sha256::new();
-        hasher.update(json_str.as_bytes());
-        format!("{:x}", hasher.finalize())
+        commit_json.canonical_hash().unwrap_or_default()
     }
 
     /// Build asset state from commits (for loading from disk)
@@ -915,6 +1035,18 @@ impl RpcHandler for HubHandler {
         Ok(env!("CARGO_PKG_VERSION").to_string())
     }
 
+    async fn get_build_info(&self) -> Result<BuildInfoResponse, RpcError> {
+        let info = crate::utils::build_info::current();
+        Ok(BuildInfoResponse {
+            version: info.version,
+            git_commit: info.git_commit,
+            protocol_version: info.protocol_version,
+            min_protocol_version: info.min_protocol_version,
+            wire_version: info.wire_version,
+            features: info.features,
+        })
+    }
+
     async fn get_block_height(&self) -> Result<BlockHeightResponse, RpcError> {
         // Hub doesn't have blocks, return 0
         Ok(BlockHeightResponse {
@@ -1066,87 +1198,133 @@ impl RpcHandler for HubHandler {
             .cloned()
             .unwrap_or(json!({}));
 
-        // Validate all actions in the commit
-        {
-            let contracts = self.contracts.read().await;
-            
-            if let Some(actions) = body.as_array() {
-                for action in actions {
-                    let method = action.get("method")
-                        .and_then(|m| m.as_str())
-                        .unwrap_or("")
-                        .to_lowercase();
+        let hash = self.accept_commit(&params.contract_id, params.commit.parent.clone(), body, head).await?;
 
-                    match method.as_str() {
-                        "repost" => {
-                            // Validate REPOST against source contract
-                            self.validate_repost(&body).await?;
-                        }
-                        "create" => {
-                            self.validate_create(&params.contract_id, action, &contracts).await?;
-                        }
-                        "send" => {
-                            self.validate_send(&params.contract_id, action, &contracts).await?;
-                        }
-                        "recv" => {
-                            self.validate_recv(&params.contract_id, action, &contracts).await?;
-                        }
-                        "model" => {
-                            // Validate MODEL commit: new model must satisfy all existing rules
-                            if let Some(contract) = contracts.get(&params.contract_id) {
-                                let model_content = action.get("value")
-                                    .and_then(|v| v.as_str())
-                                    .unwrap_or("");
-                                
-                                self.validate_model(&params.contract_id, model_content, &contract.commits)?;
-                            }
-                        }
-                        "action" => {
-                            let action_name = action.get("action")
-                                .and_then(|a| a.as_str())
-                                .unwrap_or("");
-                            
-                            if let Some(contract) = contracts.get(&params.contract_id) {
-                                let state = self.build_state(&contract.commits);
-                                let signers: Vec<String> = head.get("signatures")
-                                    .and_then(|s| s.as_object())
-                                    .map(|obj| obj.keys().cloned().collect())
-                                    .unwrap_or_default();
-                                
-                                match action_name {
-                                    "WITHDRAW" => {
-                                        self.validate_withdraw(action, &state, &signers)?;
-                                    }
-                                    "ADD_MEMBER" => {
-                                        self.validate_add_member(&state, &signers)?;
-                                    }
-                                    _ => {}
-                                }
-                            }
-                        }
-                        _ => {
-                            // post, rule, genesis, etc. - no special validation needed
-                        }
-                    }
+        Ok(SubmitCommitResponse {
+            success: true,
+            hash,
+            error: None,
+        })
+    }
+
+    async fn push_commits(&self, params: PushCommitsParams) -> Result<PushCommitsResponse, RpcError> {
+        let mut pushed = 0;
+        for commit in &params.commits {
+            let body = commit.get("body")
+                .or_else(|| commit.get("data"))
+                .cloned()
+                .unwrap_or(json!([]));
+            let head = commit.get("head").cloned().unwrap_or(json!({}));
+            let parent = head.get("parent")
+                .or_else(|| commit.get("parent"))
+                .and_then(|p| p.as_str())
+                .map(|s| s.to_string());
+
+            let hash = self.compute_commit_hash(&body, &head);
+            if let Some(declared) = commit.get("hash").and_then(|h| h.as_str()) {
+                if declared != hash {
+                    return Err(RpcError::InvalidParams(format!(
+                        "Commit {} hashes to {} on the hub", declared, hash
+                    )));
                 }
             }
-            
-            // For contracts with /members.json, validate signer is a member
-            if let Some(contract) = contracts.get(&params.contract_id) {
-                let state = self.build_state(&contract.commits);
-                if state.get("members.json").is_some() {
-                    let signers: Vec<String> = head.get("signatures")
-                        .and_then(|s| s.as_object())
-                        .map(|obj| obj.keys().cloned().collect())
-                        .unwrap_or_default();
-                    self.validate_member_signed(&state, &signers)?;
+
+            {
+                let contracts = self.contracts.read().await;
+                let current_head = contracts.get(&params.contract_id).and_then(|c| c.head.clone());
+                if contracts.get(&params.contract_id).is_some_and(|c| c.commits.iter().any(|c| c.hash == hash)) {
+                    // Already have it, e.g. from an earlier push that failed partway
+                    continue;
+                }
+                if parent != current_head {
+                    return Err(RpcError::Custom {
+                        code: -32003,
+                        message: format!(
+                            "Commit {} builds on {} but the contract's head is {}; pull first",
+                            hash,
+                            parent.as_deref().unwrap_or("nothing"),
+                            current_head.as_deref().unwrap_or("empty")
+                        ),
+                    });
                 }
             }
+
+            self.accept_commit(&params.contract_id, parent, body, head).await?;
+            pushed += 1;
         }
 
-        // Compute hash
+        let head = self.contracts.read().await
+            .get(&params.contract_id)
+            .and_then(|c| c.head.clone());
+        Ok(PushCommitsResponse {
+            contract_id: params.contract_id,
+            pushed,
+            head,
+        })
+    }
+
+    async fn pull_commits(&self, params: PullCommitsParams) -> Result<PullCommitsResponse, RpcError> {
+        let contracts = self.contracts.read().await;
+        
+        let contract = contracts.get(&params.contract_id)
+            .ok_or_else(|| RpcError::Custom {
+                code: -32000,
+                message: format!("Contract not found: {}", params.contract_id),
+            })?;
+
+        let start = match &params.since {
+            Some(since) => contract.commits.iter()
+                .position(|c| &c.hash == since)
+                .map(|i| i + 1)
+                .ok_or_else(|| RpcError::Custom {
+                    code: -32002,
+                    message: format!("Commit not found: {}", since),
+                })?,
+            None => 0,
+        };
+
+        Ok(PullCommitsResponse {
+            contract_id: params.contract_id.clone(),
+            head: contract.head.clone(),
+            commits: contract.commits[start..].iter()
+                .map(|c| json!({
+                    "hash": c.hash,
+                    "parent": c.parent,
+                    "body": c.body,
+                    "head": c.head,
+                    "timestamp": c.timestamp,
+                }))
+                .collect(),
+        })
+    }
+
+    async fn validate_commit(&self, params: ValidateCommitParams) -> Result<ValidateCommitResponse, RpcError> {
+        let body = params.commit.get("body").cloned().unwrap_or(json!([]));
+        let head = params.commit.get("head").cloned().unwrap_or(json!({}));
         let hash = self.compute_commit_hash(&body, &head);
 
+        Ok(match self.check_commit(&params.contract_id, &body, &head).await {
+            Ok(()) => ValidateCommitResponse { valid: true, hash, error: None },
+            Err(e) => ValidateCommitResponse { valid: false, hash, error: Some(e.to_string()) },
+        })
+    }
+}
+
+impl HubHandler {
+    /// Validate a commit, then store it as the contract's new head and tell
+    /// subscribers. Returns the commit's hash.
+    async fn accept_commit(&self, contract_id: &str, parent: Option<String>, body: Value, head: Value) -> Result<String, RpcError> {
+        let hash = self.compute_commit_hash(&body, &head);
+
+        if let Err(e) = self.check_commit(contract_id, &body, &head).await {
+            self.emit(EventType::RuleVerdict, contract_id, json!({
+                "commit_hash": hash,
+                "accepted": false,
+                "reason": e.to_string(),
+            }));
+            return Err(e);
+        }
+
         let timestamp = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .map(|d| d.as_secs())
@@ -1154,21 +1332,21 @@ impl RpcHandler for HubHandler {
 
         let stored_commit = StoredCommit {
             hash: hash.clone(),
-            parent: params.commit.parent.clone(),
+            parent: parent.clone(),
             body: body.clone(),
             head,
             timestamp,
         };
 
         // Save to disk
-        if let Err(e) = self.save_commit_to_disk(&params.contract_id, &stored_commit) {
+        if let Err(e) = self.save_commit_to_disk(contract_id, &stored_commit) {
             return Err(RpcError::Internal(format!("Failed to save commit: {}", e)));
         }
 
         // Update in-memory state
-        {
+        let changes = {
             let mut contracts = self.contracts.write().await;
-            let contract = contracts.entry(params.contract_id.clone())
+            let contract = contracts.entry(contract_id.to_string())
                 .or_insert_with(|| ContractData {
                     head: None,
                     commits: Vec::new(),
@@ -1180,19 +1358,34 @@ impl RpcHandler for HubHandler {
                 });
 
             // Apply commit to update asset state
-            Self::apply_commit_to_state(&params.contract_id, &stored_commit, contract);
+            Self::apply_commit_to_state(contract_id, &stored_commit, contract);
 
+            let before = self.build_state(&contract.commits);
             contract.commits.push(stored_commit);
             contract.head = Some(hash.clone());
-        }
+            Self::state_changes(&before, &self.build_state(&contract.commits))
+        };
 
-        tracing::info!("Accepted commit {} for contract {}", hash, params.contract_id);
+        tracing::info!("Accepted commit {} for contract {}", hash, contract_id);
 
-        Ok(SubmitCommitResponse {
-            success: true,
-            hash,
-            error: None,
-        })
+        self.emit(EventType::RuleVerdict, contract_id, json!({
+            "commit_hash": hash,
+            "accepted": true,
+        }));
+        self.emit(EventType::NewCommit, contract_id, json!({
+            "hash": hash,
+            "parent": parent,
+            "body": body,
+            "timestamp": timestamp,
+        }));
+        if !changes.is_empty() {
+            self.emit(EventType::ContractUpdate, contract_id, json!({
+                "commit_hash": hash,
+                "changes": changes,
+            }));
+        }
+
+        Ok(hash)
     }
 }
 
@@ -1432,4 +1625,52 @@ mod tests {
         let result = handler.validate_add_member(&state, &signers);
         assert!(result.is_ok(), "First member can be added by anyone");
     }
+
+    #[tokio::test]
+    async fn test_push_and_pull_commits() {
+        let dir = std::env::temp_dir().join(format!("hub-push-pull-{}", std::process::id()));
+        let handler = HubHandler::new(dir.clone());
+        let commit = |path: &str| json!({
+            "body": [{ "method": "post", "path": path, "value": "x" }],
+            "head": {},
+        });
+
+        let pushed = handler.push_commits(PushCommitsParams {
+            contract_id: "c1".into(),
+            commits: vec![commit("/a.text")],
+        }).await.unwrap();
+        assert_eq!(pushed.pushed, 1);
+        let head = pushed.head.unwrap();
+
+        // Pushing the same commit again is a no-op
+        let again = handler.push_commits(PushCommitsParams {
+            contract_id: "c1".into(),
+            commits: vec![commit("/a.text")],
+        }).await.unwrap();
+        assert_eq!(again.pushed, 0);
+
+        // A commit that doesn't build on the head is refused
+        match handler.push_commits(PushCommitsParams {
+            contract_id: "c1".into(),
+            commits: vec![commit("/b.text")],
+        }).await.unwrap_err() {
+            RpcError::Custom { code, .. } => assert_eq!(code, -32003),
+            _ => panic!("Expected Custom error"),
+        }
+
+        let pulled = handler.pull_commits(PullCommitsParams {
+            contract_id: "c1".into(),
+            since: None,
+        }).await.unwrap();
+        assert_eq!(pulled.head.as_deref(), Some(head.as_str()));
+        assert_eq!(pulled.commits.len(), 1);
+
+        let pulled = handler.pull_commits(PullCommitsParams {
+            contract_id: "c1".into(),
+            since: Some(head),
+        }).await.unwrap();
+        assert!(pulled.commits.is_empty());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
 }
@@ -1,6 +1,7 @@
 //! Hub commands - run a contract hub server
 
 pub mod start;
+pub mod serve;
 pub mod handler;
 pub mod model_validator;
 pub mod core;
@@ -0,0 +1,78 @@
+//! Serve contracts over modal-rpc only, without the REST API
+
+use anyhow::Result;
+use clap::Parser;
+use std::path::PathBuf;
+use tracing::info;
+
+use modal_rpc::server::{RpcServer, RpcServerConfig};
+use super::handler::HubHandler;
+
+#[derive(Debug, Parser)]
+#[command(about = "Serve contracts over modal-rpc (push, pull and validate; no mining or consensus)")]
+pub struct Opts {
+    /// Host to bind to
+    #[clap(long, default_value = "0.0.0.0")]
+    host: String,
+
+    /// Port for the RPC interface
+    #[clap(long, default_value_t = modal_rpc::DEFAULT_PORT)]
+    port: u16,
+
+    /// Data directory for storing contracts
+    #[clap(long, default_value = ".hub")]
+    data_dir: PathBuf,
+
+    /// Enable CORS for browser access
+    #[clap(long, default_value = "true")]
+    cors: bool,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    // Initialize logging
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::from_default_env()
+                .add_directive("modal=info".parse()?)
+                .add_directive("modal_rpc=info".parse()?)
+        )
+        .init();
+
+    // Ensure data directory exists
+    std::fs::create_dir_all(&opts.data_dir)?;
+
+    info!("Starting Modality Hub (RPC only)");
+    info!("  Data directory: {}", opts.data_dir.display());
+
+    let handler = HubHandler::new(opts.data_dir.clone());
+    handler.load_from_disk().await
+        .map_err(|e| anyhow::anyhow!("Failed to load data: {}", e))?;
+
+    info!("Hub ready - accepting connections");
+    info!("");
+    info!("RPC endpoints:");
+    info!("  POST http://{}:{}/          (JSON-RPC)", opts.host, opts.port);
+    info!("  WS   ws://{}:{}/ws          (WebSocket)", opts.host, opts.port);
+    info!("");
+    info!("Contract methods:");
+    info!("  pushCommits      (Append commits on top of a contract's head)");
+    info!("  pullCommits      (Fetch commits after a known hash)");
+    info!("  validateCommit   (Check a commit against the contract's models and rules)");
+    info!("");
+
+    let config = RpcServerConfig {
+        host: opts.host.clone(),
+        port: opts.port,
+        max_connections: 1000,
+        enable_cors: opts.cors,
+        gateway: None,
+    };
+    let events = handler.event_sender();
+    RpcServer::new(handler, config)
+        .with_event_sender(events)
+        .run()
+        .await
+        .map_err(|e| anyhow::anyhow!("RPC server error: {}", e))?;
+
+    Ok(())
+}
@@ -104,8 +104,10 @@ pub async fn run(opts: &Opts) -> Result<()> {
             port: opts.rpc_port,
             max_connections: 1000,
             enable_cors: opts.cors,
+            gateway: None,
         };
-        let rpc_server = RpcServer::new(rpc_handler, rpc_config);
+        let events = rpc_handler.event_sender();
+        let rpc_server = RpcServer::new(rpc_handler, rpc_config).with_event_sender(events);
 
         // Run both servers
         tokio::select! {
@@ -1,8 +1,11 @@
 use anyhow::Result;
 use clap::Parser;
+use serde::Serialize;
 use std::path::PathBuf;
 use std::fs;
 
+use crate::utils::output;
+
 #[cfg(target_family = "unix")]
 use nix::sys::signal::kill;
 #[cfg(target_family = "unix")]
@@ -28,7 +31,7 @@ pub struct Opts {
     pub dir: Option<PathBuf>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct NodeInfo {
     pub pid: u32,
     pub dir: PathBuf,
@@ -56,6 +59,11 @@ pub async fn run(opts: &Opts) -> Result<()> {
         nodes = filter_nodes_by_network(nodes, filter);
     }
     
+    let format = output::global();
+    if format.is_structured() {
+        return output::print_structured(format, &nodes);
+    }
+    
     if nodes.is_empty() {
         if opts.dir.is_some() {
             println!("No running modal nodes found in the specified directory.");
@@ -9,6 +9,7 @@
 //! - `predicate`: Predicate management and testing
 //! - `program`: Program management and creation
 //! - `chain`: Chain validation and testing
+//! - `devnet`: Local multi-node devnets (up, status, logs, down)
 
 pub mod local;
 pub mod net;
@@ -18,3 +19,4 @@ pub mod hub;
 pub mod predicate;
 pub mod program;
 pub mod chain;
+pub mod devnet;
@@ -0,0 +1,387 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use modal_datastore::DatastoreManager;
+use modal_datastore::models::PeerInfo;
+use modal_node::config_resolution::load_config_with_node_dir;
+use modal_node::status_server::{format_hashrate, StatusSummary};
+
+#[derive(Debug, Parser)]
+#[command(about = "Poll the status pages of known peers and show an aggregate network view")]
+pub struct Opts {
+    /// Path to node configuration file
+    #[clap(long)]
+    pub config: Option<PathBuf>,
+
+    /// Node directory containing config.json (defaults to current directory)
+    #[clap(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Additional status URL to poll (can be repeated)
+    #[clap(long = "url")]
+    pub urls: Vec<String>,
+
+    /// Write the dashboard as a single HTML page instead of printing a table
+    #[clap(long)]
+    pub html: Option<PathBuf>,
+
+    /// Per-peer request timeout in seconds
+    #[clap(long, default_value = "5")]
+    pub timeout: u64,
+}
+
+/// A status page to poll
+#[derive(Debug, Clone)]
+struct Target {
+    peer_id: Option<String>,
+    role: Option<String>,
+    status_url: String,
+}
+
+/// Result of polling one status page
+#[derive(Debug)]
+struct PeerStatus {
+    target: Target,
+    status: std::result::Result<StatusSummary, String>,
+}
+
+/// Peers that report different tips at the same height
+#[derive(Debug, PartialEq)]
+struct Fork {
+    height: u64,
+    tips: BTreeMap<String, Vec<String>>,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    // If neither config nor dir is provided, default to current directory
+    let dir = if opts.config.is_none() && opts.dir.is_none() {
+        Some(std::env::current_dir()?)
+    } else {
+        opts.dir.clone()
+    };
+
+    let config = load_config_with_node_dir(opts.config.clone(), dir)?;
+
+    let mut targets: Vec<Target> = opts
+        .urls
+        .iter()
+        .map(|url| Target { peer_id: None, role: None, status_url: url.clone() })
+        .collect();
+
+    if let Some(ref url) = config.status_url {
+        targets.push(Target { peer_id: None, role: config.run_as.clone(), status_url: url.clone() });
+    }
+
+    // Peers learned via identify are recorded in the node's datastore
+    if let Some(data_dir) = config.data_dir.as_ref().or(config.storage_path.as_ref()) {
+        let datastore_manager = DatastoreManager::open(data_dir)
+            .context("Failed to open datastore (is the node running? use --url to poll status pages directly)")?;
+        for peer in PeerInfo::find_all(&datastore_manager).await? {
+            if let Some(status_url) = peer.status_url {
+                targets.push(Target { peer_id: Some(peer.peer_id), role: peer.role, status_url });
+            }
+        }
+    }
+
+    let targets = dedup_targets(targets);
+    if targets.is_empty() {
+        println!("⚠️  No status URLs known. Peers advertise theirs via identify; you can also pass --url.");
+        return Ok(());
+    }
+
+    println!("🔍 Polling {} status pages...", targets.len());
+    let peers = poll_all(targets, Duration::from_secs(opts.timeout)).await;
+    let forks = detect_forks(&peers);
+
+    if let Some(ref path) = opts.html {
+        std::fs::write(path, render_html(&peers, &forks))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        println!("✅ Dashboard written to {}", path.display());
+    } else {
+        print_table(&peers, &forks);
+    }
+
+    Ok(())
+}
+
+/// Drop duplicate status URLs, keeping the first entry (and any peer id learned later)
+fn dedup_targets(targets: Vec<Target>) -> Vec<Target> {
+    let mut by_url: BTreeMap<String, Target> = BTreeMap::new();
+    for target in targets {
+        let key = status_base_url(&target.status_url);
+        match by_url.get_mut(&key) {
+            Some(existing) => {
+                if existing.peer_id.is_none() {
+                    existing.peer_id = target.peer_id;
+                }
+                if existing.role.is_none() {
+                    existing.role = target.role;
+                }
+            }
+            None => {
+                by_url.insert(key, target);
+            }
+        }
+    }
+    by_url.into_values().collect()
+}
+
+fn status_base_url(status_url: &str) -> String {
+    status_url.trim_end_matches('/').to_string()
+}
+
+async fn poll_all(targets: Vec<Target>, timeout: Duration) -> Vec<PeerStatus> {
+    let client = reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .unwrap_or_default();
+
+    let handles: Vec<_> = targets
+        .into_iter()
+        .map(|target| {
+            let client = client.clone();
+            tokio::spawn(async move {
+                let status = fetch_status(&client, &target.status_url).await.map_err(|e| e.to_string());
+                PeerStatus { target, status }
+            })
+        })
+        .collect();
+
+    let mut peers = Vec::new();
+    for handle in handles {
+        if let Ok(peer) = handle.await {
+            peers.push(peer);
+        }
+    }
+    peers
+}
+
+async fn fetch_status(client: &reqwest::Client, status_url: &str) -> Result<StatusSummary> {
+    let url = format!("{}/api/status.json", status_base_url(status_url));
+    let summary = client
+        .get(&url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<StatusSummary>()
+        .await?;
+    Ok(summary)
+}
+
+/// Group reachable peers by height and report heights with more than one tip
+fn detect_forks(peers: &[PeerStatus]) -> Vec<Fork> {
+    let mut by_height: BTreeMap<u64, BTreeMap<String, Vec<String>>> = BTreeMap::new();
+    for peer in peers {
+        let Ok(ref status) = peer.status else { continue };
+        let Some(ref hash) = status.latest_block_hash else { continue };
+        by_height
+            .entry(status.block_height)
+            .or_default()
+            .entry(hash.clone())
+            .or_default()
+            .push(status.peer_id.clone());
+    }
+
+    by_height
+        .into_iter()
+        .filter(|(_, tips)| tips.len() > 1)
+        .map(|(height, tips)| Fork { height, tips })
+        .collect()
+}
+
+fn short(s: &str) -> String {
+    if s.len() > 12 {
+        format!("{}…", &s[..12])
+    } else {
+        s.to_string()
+    }
+}
+
+fn peer_label(peer: &PeerStatus) -> String {
+    match (&peer.status, &peer.target.peer_id) {
+        (Ok(status), _) if !status.peer_id.is_empty() => status.peer_id.clone(),
+        (_, Some(peer_id)) => peer_id.clone(),
+        _ => "-".to_string(),
+    }
+}
+
+fn print_table(peers: &[PeerStatus], forks: &[Fork]) {
+    let reachable: Vec<&StatusSummary> = peers.iter().filter_map(|p| p.status.as_ref().ok()).collect();
+    let max_height = reachable.iter().map(|s| s.block_height).max().unwrap_or(0);
+    let versions: BTreeSet<&str> = reachable.iter().map(|s| s.version.as_str()).filter(|v| !v.is_empty()).collect();
+    let networks: BTreeSet<&str> = reachable.iter().map(|s| s.network_name.as_str()).collect();
+
+    println!();
+    println!("📡 Network Dashboard");
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("  Peers Polled:  {}", peers.len());
+    println!("  Reachable:     {}", reachable.len());
+    println!("  Networks:      {}", networks.into_iter().collect::<Vec<_>>().join(", "));
+    println!("  Max Height:    {}", max_height);
+    println!("  Versions:      {}", versions.into_iter().collect::<Vec<_>>().join(", "));
+    println!("  Forks:         {}", forks.len());
+    println!();
+
+    println!(
+        "{:<14} {:<10} {:<9} {:>8} {:<14} {:>6} {:>12}  STATUS URL",
+        "PEER", "ROLE", "VERSION", "HEIGHT", "TIP", "PEERS", "HASHRATE"
+    );
+    for peer in peers {
+        match &peer.status {
+            Ok(status) => println!(
+                "{:<14} {:<10} {:<9} {:>8} {:<14} {:>6} {:>12}  {}",
+                short(&peer_label(peer)),
+                status.role,
+                status.version,
+                status.block_height,
+                short(status.latest_block_hash.as_deref().unwrap_or("-")),
+                status.connected_peers,
+                format!("{} H/s", format_hashrate(status.network_hashrate)),
+                peer.target.status_url,
+            ),
+            Err(e) => println!(
+                "{:<14} {:<10} {:<9} {:>8} {:<14} {:>6} {:>12}  {} (❌ {})",
+                short(&peer_label(peer)),
+                peer.target.role.as_deref().unwrap_or("-"),
+                "-", "-", "-", "-", "-",
+                peer.target.status_url,
+                e,
+            ),
+        }
+    }
+
+    if !forks.is_empty() {
+        println!("\n⚠️  Forks Detected:");
+        for fork in forks {
+            println!("  Height {}:", fork.height);
+            for (hash, peer_ids) in &fork.tips {
+                let peer_ids: Vec<String> = peer_ids.iter().map(|p| short(p)).collect();
+                println!("    {} ← {}", short(hash), peer_ids.join(", "));
+            }
+        }
+    }
+    println!();
+}
+
+fn render_html(peers: &[PeerStatus], forks: &[Fork]) -> String {
+    let rows: Vec<String> = peers
+        .iter()
+        .map(|peer| match &peer.status {
+            Ok(status) => format!(
+                r#"<tr><td><code>{}</code></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td><code>{}</code></td><td>{}</td><td>{} H/s</td><td><a href="{}">🔗 Status</a></td></tr>"#,
+                short(&peer_label(peer)),
+                status.network_name,
+                status.role,
+                status.version,
+                status.block_height,
+                short(status.latest_block_hash.as_deref().unwrap_or("-")),
+                status.connected_peers,
+                format_hashrate(status.network_hashrate),
+                peer.target.status_url,
+            ),
+            Err(e) => format!(
+                r#"<tr class="down"><td><code>{}</code></td><td colspan="7">unreachable: {}</td><td><a href="{}">🔗 Status</a></td></tr>"#,
+                short(&peer_label(peer)),
+                e,
+                peer.target.status_url,
+            ),
+        })
+        .collect();
+
+    let forks_html = if forks.is_empty() {
+        "<p>No forks detected.</p>".to_string()
+    } else {
+        forks
+            .iter()
+            .map(|fork| {
+                let tips: Vec<String> = fork
+                    .tips
+                    .iter()
+                    .map(|(hash, peer_ids)| format!("<li><code>{}</code> ← {}</li>", short(hash), peer_ids.iter().map(|p| short(p)).collect::<Vec<_>>().join(", ")))
+                    .collect();
+                format!("<p>Height {}:</p><ul>{}</ul>", fork.height, tips.join(""))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    format!(
+        r##"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <title>Modality Network Dashboard</title>
+    <style>
+        body {{ background: #1a1a1a; color: #e0e0e0; font-family: monospace; padding: 20px; }}
+        h1, h2 {{ color: #4ade80; }}
+        table {{ border-collapse: collapse; width: 100%; }}
+        th, td {{ border-bottom: 1px solid #333; padding: 6px 10px; text-align: left; }}
+        a {{ color: #4ade80; text-decoration: none; }}
+        tr.down td {{ color: #f87171; }}
+    </style>
+</head>
+<body>
+    <h1>Modality Network Dashboard</h1>
+    <p>Generated {} · {} peers polled</p>
+    <table>
+        <tr><th>Peer</th><th>Network</th><th>Role</th><th>Version</th><th>Height</th><th>Tip</th><th>Peers</th><th>Network Hashrate</th><th></th></tr>
+        {}
+    </table>
+    <h2>Forks</h2>
+    {}
+</body>
+</html>
+"##,
+        chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
+        peers.len(),
+        rows.join("\n        "),
+        forks_html,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reachable(peer_id: &str, height: u64, hash: &str) -> PeerStatus {
+        PeerStatus {
+            target: Target { peer_id: None, role: None, status_url: format!("http://{}/", peer_id) },
+            status: Ok(StatusSummary {
+                peer_id: peer_id.to_string(),
+                block_height: height,
+                latest_block_hash: Some(hash.to_string()),
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn test_detect_forks_at_same_height() {
+        let peers = vec![
+            reachable("a", 10, "aaa"),
+            reachable("b", 10, "bbb"),
+            reachable("c", 10, "aaa"),
+            reachable("d", 9, "ccc"),
+        ];
+        let forks = detect_forks(&peers);
+        assert_eq!(forks.len(), 1);
+        assert_eq!(forks[0].height, 10);
+        assert_eq!(forks[0].tips["aaa"], vec!["a".to_string(), "c".to_string()]);
+        assert_eq!(forks[0].tips["bbb"], vec!["b".to_string()]);
+    }
+
+    #[test]
+    fn test_dedup_targets_merges_peer_ids() {
+        let targets = vec![
+            Target { peer_id: None, role: None, status_url: "http://node1:8080/".to_string() },
+            Target { peer_id: Some("12D3".to_string()), role: Some("miner".to_string()), status_url: "http://node1:8080".to_string() },
+        ];
+        let targets = dedup_targets(targets);
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].peer_id.as_deref(), Some("12D3"));
+        assert_eq!(targets[0].role.as_deref(), Some("miner"));
+    }
+}
@@ -0,0 +1,104 @@
+use anyhow::Result;
+use clap::Parser;
+use std::path::PathBuf;
+
+use modal_datastore::models::miner::EpochReport;
+use modal_datastore::models::MinerBlock;
+use modal_node::actions;
+use modal_node::config::Config;
+use modal_node::node::Node;
+
+use crate::cmds::net::storage::{open_datastore, shorten};
+use crate::utils::output;
+
+#[derive(Debug, Parser)]
+#[command(about = "Show who mined, was nominated and validated in a finished epoch")]
+pub struct Opts {
+    /// Epoch to report on
+    epoch: u64,
+
+    /// Path to node configuration file
+    #[clap(long)]
+    config: PathBuf,
+
+    /// Ask this node instead of reading the local datastore
+    /// (e.g., /ip4/127.0.0.1/tcp/10001/p2p/12D3KooW...)
+    #[clap(long)]
+    target: Option<String>,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    let report = match &opts.target {
+        Some(target) => fetch_report(opts, target).await?,
+        None => local_report(opts).await?,
+    };
+
+    let format = output::global();
+    if format.is_structured() {
+        return output::print_structured(format, &report);
+    }
+
+    println!();
+    println!("📊 Epoch {} Report", report.epoch);
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("  Blocks: {} ({} → {})", report.block_count, report.first_block_index, report.last_block_index);
+    println!("  Uncles: {}", report.uncle_count);
+    println!("  Certificates: {}", report.certificate_count);
+    println!("  Last block: {}", report.last_block_hash);
+    println!();
+    println!(
+        "{:<19}  {:>6}  {:>6}  {:>11}  {:>9}  {:>12}  {:>7}",
+        "Peer", "Blocks", "Uncles", "Nominations", "Validator", "Certificates", "Anchors"
+    );
+    println!("─────────────────────────────────────────────────────────────────────────────────");
+    for peer in &report.peers {
+        println!(
+            "{:<19}  {:>6}  {:>6}  {:>11}  {:>9}  {:>12}  {:>7}",
+            shorten(&peer.peer_id),
+            peer.blocks_mined,
+            peer.uncles,
+            peer.nominations,
+            if peer.active_validator { "yes" } else { "-" },
+            format!("{}/{}", peer.certificates_committed, peer.certificates),
+            peer.anchors,
+        );
+    }
+    println!();
+    println!("Blocks count towards the peer they nominate. Certificates are committed/authored.");
+
+    Ok(())
+}
+
+/// The stored report, or one generated from the datastore if the node
+/// hasn't written it yet
+async fn local_report(opts: &Opts) -> Result<EpochReport> {
+    let mgr = open_datastore(&opts.config)?;
+    if let Some(report) = EpochReport::find_by_epoch_multi(&mgr, opts.epoch).await? {
+        return Ok(report);
+    }
+    let current_epoch = MinerBlock::find_all_canonical_multi(&mgr)
+        .await?
+        .iter()
+        .max_by_key(|b| b.index)
+        .map(|b| b.epoch)
+        .unwrap_or(0);
+    EpochReport::generate_multi(&mgr, opts.epoch, current_epoch).await
+}
+
+async fn fetch_report(opts: &Opts, target: &str) -> Result<EpochReport> {
+    let config = Config::from_filepath(&opts.config)?;
+    let mut node = Node::from_config_filepath(opts.config.clone()).await?;
+    node.setup(&config).await?;
+
+    let response = actions::request::run(
+        &mut node,
+        target.to_string(),
+        "/data/miner_block/epoch_report".to_string(),
+        serde_json::json!({ "epoch": opts.epoch }).to_string(),
+    ).await?;
+    if !response.ok {
+        anyhow::bail!("Failed to get epoch report: {:?}", response.errors);
+    }
+    let data = response.data.ok_or_else(|| anyhow::anyhow!("No data in response"))?;
+    Ok(serde_json::from_value(data)?)
+}
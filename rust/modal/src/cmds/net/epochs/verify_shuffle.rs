@@ -0,0 +1,129 @@
+use anyhow::Result;
+use clap::Parser;
+use serde::Serialize;
+use std::path::PathBuf;
+
+use modal_datastore::models::validator::{epoch_nominations_multi, generate_validator_set_from_epoch_multi, ValidatorSet};
+use modal_miner::epoch::{epoch_seed, seat_nominees, shuffle_nominees, verify_seating, Seating};
+
+use crate::cmds::net::storage::{open_datastore, shorten};
+use crate::utils::output;
+
+#[derive(Debug, Parser)]
+#[command(about = "Recompute an epoch's nominee shuffle and check the validators seated from it")]
+pub struct Opts {
+    /// Nomination epoch to verify
+    epoch: u64,
+
+    /// Path to node configuration file
+    #[clap(long)]
+    config: PathBuf,
+}
+
+#[derive(Debug, Serialize)]
+struct Verification {
+    epoch: u64,
+    seed: u64,
+    nominees: usize,
+    shuffled: Vec<String>,
+    /// Peers the slashing ledger excludes at the epoch
+    excluded: Vec<String>,
+    expected: Seating,
+    /// Validator set the node derives from its datastore
+    derived_matches: bool,
+    /// Validator set the node stored for the epoch, if any
+    stored_matches: Option<bool>,
+    mismatches: Vec<String>,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    let mgr = open_datastore(&opts.config)?;
+    let nominations = epoch_nominations_multi(&mgr, opts.epoch).await?;
+
+    let seed = epoch_seed(nominations.nonces.iter().copied());
+    let shuffled = shuffle_nominees(seed, &nominations.nominees);
+    let excluded = mgr.excluded_peers(opts.epoch).await?;
+    let expected = seat_nominees(&shuffled, &excluded);
+
+    let mut mismatches = Vec::new();
+    let mut check = |label: &str, set: &ValidatorSet| {
+        let seating = Seating {
+            nominated: set.nominated_validators.clone(),
+            alternates: set.alternate_validators.clone(),
+        };
+        match verify_seating(nominations.nonces.iter().copied(), &nominations.nominees, &excluded, &seating) {
+            Ok(()) => true,
+            Err(e) => {
+                mismatches.push(format!("{}: {}", label, e));
+                false
+            }
+        }
+    };
+    let derived = generate_validator_set_from_epoch_multi(&mgr, opts.epoch).await?;
+    let derived_matches = check("derived set", &derived);
+    let stored_matches = ValidatorSet::find_by_epoch_multi(&mgr, opts.epoch)
+        .await?
+        .map(|stored| check("stored set", &stored));
+
+    let verification = Verification {
+        epoch: opts.epoch,
+        seed,
+        nominees: nominations.nominees.len(),
+        shuffled,
+        excluded: excluded.iter().cloned().collect(),
+        expected,
+        derived_matches,
+        stored_matches,
+        mismatches,
+    };
+
+    let format = output::global();
+    if format.is_structured() {
+        output::print_structured(format, &verification)?;
+    } else {
+        print_verification(&verification, nominations.uncle_nominees);
+    }
+
+    if !verification.mismatches.is_empty() {
+        anyhow::bail!("Epoch {} seating doesn't match its nominee shuffle", opts.epoch);
+    }
+    Ok(())
+}
+
+fn print_verification(verification: &Verification, uncle_nominees: usize) {
+    println!();
+    println!("🔀 Epoch {} Nominee Shuffle", verification.epoch);
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("  Seed: {} (XOR of block nonces)", verification.seed);
+    println!("  Nominees: {} ({} from uncles)", verification.nominees, uncle_nominees);
+    if !verification.excluded.is_empty() {
+        println!("  Excluded by slashing: {}", verification.excluded.len());
+    }
+    println!();
+    println!("{:>4}  {:<19}  Seat", "Rank", "Peer");
+    println!("──────────────────────────────────────");
+    for (rank, peer_id) in verification.shuffled.iter().enumerate() {
+        let seat = if verification.expected.nominated.contains(peer_id) {
+            "nominated"
+        } else if verification.expected.alternates.contains(peer_id) {
+            "alternate"
+        } else if verification.excluded.contains(peer_id) {
+            "excluded"
+        } else {
+            "-"
+        };
+        println!("{:>4}  {:<19}  {}", rank, shorten(peer_id), seat);
+    }
+    println!();
+    println!("  Derived validator set: {}", if verification.derived_matches { "✅ matches" } else { "❌ differs" });
+    match verification.stored_matches {
+        Some(true) => println!("  Stored validator set:  ✅ matches"),
+        Some(false) => println!("  Stored validator set:  ❌ differs"),
+        None => println!("  Stored validator set:  (none)"),
+    }
+    for mismatch in &verification.mismatches {
+        println!("  ⚠️  {}", mismatch);
+    }
+    println!();
+    println!("A peer nominated more than once is seated at its first rank.");
+}
@@ -1,17 +1,42 @@
 use anyhow::{Context, Result};
 use clap::Parser;
-use modal_networks::networks;
+use libp2p::Multiaddr;
+use modal_networks::health::{rank_bootstrappers, BootstrapperHealth};
+use modal_networks::registry;
+use modal_node::bootstrapper_health;
+use serde_json::json;
+
+use crate::utils::output;
 
 #[derive(Parser, Debug)]
 pub struct Opts {
     /// Network name (e.g., testnet, mainnet, devnet1). Defaults to mainnet.
     #[arg(default_value = "mainnet")]
     network: String,
+
+    /// Probe each bootstrapper (dial, ping, chain height) and list them healthiest first
+    #[arg(long)]
+    probe: bool,
 }
 
 pub async fn run(opts: &Opts) -> Result<()> {
-    let network = networks::by_name(&opts.network)
+    let network = registry::find(&opts.network)?
         .with_context(|| format!("Network '{}' not found", opts.network))?;
+    let dns_record = format!("_dnsaddr.{}.modality.network", network.name);
+
+    let format = output::global();
+    if format.is_structured() {
+        let mut info = json!({
+            "name": network.name,
+            "description": network.description,
+            "bootstrappers": network.bootstrappers,
+            "dns_record": dns_record,
+        });
+        if opts.probe && !network.bootstrappers.is_empty() {
+            info["health"] = serde_json::to_value(probe_bootstrappers(&network.bootstrappers).await?)?;
+        }
+        return output::print_structured(format, &info);
+    }
 
     println!("\n╔═══════════════════════════════════════════════════════════════════╗");
     println!("║                     Modality Network Information                  ║");
@@ -31,13 +56,17 @@ pub async fn run(opts: &Opts) -> Result<()> {
         println!("\n⚠️  No bootstrapper addresses configured for this network.");
     }
 
+    if opts.probe && !network.bootstrappers.is_empty() {
+        print_health(&probe_bootstrappers(&network.bootstrappers).await?);
+    }
+
     println!("\n📍 DNS Record:");
     println!("─────────────────────────────────────────────────────────────────────");
-    println!("  _dnsaddr.{}.modality.network", network.name);
+    println!("  {}", dns_record);
 
     if !network.bootstrappers.is_empty() {
         println!("\n🔍 Query DNS records with:");
-        println!("  dig +short txt _dnsaddr.{}.modality.network", network.name);
+        println!("  dig +short txt {}", dns_record);
     }
 
     println!();
@@ -45,3 +74,37 @@ pub async fn run(opts: &Opts) -> Result<()> {
     Ok(())
 }
 
+
+/// Probe each bootstrapper, healthiest first
+async fn probe_bootstrappers(bootstrappers: &[String]) -> Result<Vec<BootstrapperHealth>> {
+    let keypair = libp2p::identity::Keypair::generate_ed25519();
+    let mut swarm = modal_node::swarm::create_swarm(keypair).await?;
+
+    let mut results = Vec::new();
+    for address in bootstrappers {
+        let health = match address.parse::<Multiaddr>() {
+            Ok(multiaddr) => bootstrapper_health::probe_with_swarm(&mut swarm, &multiaddr).await,
+            Err(e) => BootstrapperHealth::unreachable(address.clone(), e.to_string(), chrono::Utc::now().timestamp(), None),
+        };
+        results.push(health);
+    }
+
+    let ranked = rank_bootstrappers(bootstrappers, &results, chrono::Utc::now().timestamp());
+    Ok(ranked
+        .iter()
+        .filter_map(|address| results.iter().find(|h| &h.address == address).cloned())
+        .collect())
+}
+
+fn print_health(ranked: &[BootstrapperHealth]) {
+    println!("\nBootstrapper Health (healthiest first):");
+    println!("─────────────────────────────────────────────────────────────────────");
+    for (i, health) in ranked.iter().enumerate() {
+        if health.reachable {
+            let height = health.height.map(|h| h.to_string()).unwrap_or_else(|| "?".to_string());
+            println!("  {}. ✓ {}ms, height {}  {}", i + 1, health.latency_ms.unwrap_or_default(), height, health.address);
+        } else {
+            println!("  {}. ✗ {}  {}", i + 1, health.error.as_deref().unwrap_or("unreachable"), health.address);
+        }
+    }
+}
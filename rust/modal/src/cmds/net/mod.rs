@@ -1,3 +1,6 @@
+pub mod dashboard;
+pub mod epochs;
 pub mod info;
 pub mod mining;
+pub mod registry_sync;
 pub mod storage;
@@ -0,0 +1,37 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::path::PathBuf;
+
+use modal_networks::registry;
+
+#[derive(Debug, Parser)]
+#[command(about = "Fetch a signed network registry and install its networks")]
+pub struct Opts {
+    /// Registry URL
+    #[clap(long)]
+    pub url: String,
+
+    /// Peer id of a trusted registry signer (can be repeated)
+    #[clap(long = "signer", required = true)]
+    pub signers: Vec<String>,
+
+    /// Directory to install networks into (defaults to ~/.modality/networks)
+    #[clap(long)]
+    pub dir: Option<PathBuf>,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    let dir = match &opts.dir {
+        Some(dir) => dir.clone(),
+        None => registry::networks_dir().context("Could not determine the home directory")?,
+    };
+
+    let networks = registry::fetch_registry(&opts.url, &opts.signers).await?;
+    let written = registry::install(&networks, &dir)?;
+
+    println!("✓ Verified registry {} ({} networks)", opts.url, networks.len());
+    for (network, path) in networks.iter().zip(&written) {
+        println!("  {} → {}", network.name, path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,183 @@
+use anyhow::Result;
+use clap::Parser;
+use serde::Serialize;
+use std::path::PathBuf;
+
+use modal_datastore::models::MinerBlock;
+
+use super::{export_rows, shorten, Row};
+use crate::utils::output;
+
+#[derive(Debug, Parser)]
+#[command(about = "Query miner blocks by epoch, peer, height or hash prefix")]
+pub struct Opts {
+    /// Path to node configuration file
+    #[clap(long)]
+    config: PathBuf,
+
+    /// Blocks in this epoch
+    #[clap(long)]
+    epoch: Option<u64>,
+
+    /// Blocks nominating this peer
+    #[clap(long)]
+    peer: Option<String>,
+
+    /// Blocks at this height, including orphans
+    #[clap(long)]
+    index: Option<u64>,
+
+    /// Blocks whose hash starts with this prefix, including orphans
+    #[clap(long)]
+    hash: Option<String>,
+
+    /// Only orphaned blocks
+    #[clap(long)]
+    orphans: bool,
+
+    /// Only heights where more than one block competed
+    #[clap(long)]
+    forks: bool,
+
+    /// Limit number of blocks to display (exports include every block)
+    #[clap(long, default_value = "50")]
+    limit: usize,
+
+    /// Write the blocks to a .csv or .json file
+    #[clap(long)]
+    export: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize)]
+struct BlockRow {
+    index: u64,
+    epoch: u64,
+    hash: String,
+    previous_hash: String,
+    status: &'static str,
+    nominated_peer_id: String,
+    timestamp: i64,
+    target_difficulty: String,
+    nonce: String,
+    orphan_reason: Option<String>,
+}
+
+impl From<&MinerBlock> for BlockRow {
+    fn from(block: &MinerBlock) -> Self {
+        Self {
+            index: block.index,
+            epoch: block.epoch,
+            hash: block.hash.clone(),
+            previous_hash: block.previous_hash.clone(),
+            status: if block.is_canonical {
+                "canonical"
+            } else if block.is_orphaned {
+                "orphaned"
+            } else {
+                "pending"
+            },
+            nominated_peer_id: block.nominated_peer_id.clone(),
+            timestamp: block.timestamp,
+            target_difficulty: block.target_difficulty.clone(),
+            nonce: block.nonce.clone(),
+            orphan_reason: block.orphan_reason.clone(),
+        }
+    }
+}
+
+impl Row for BlockRow {
+    const HEADERS: &'static [&'static str] = &[
+        "index", "epoch", "hash", "previous_hash", "status", "nominated_peer_id",
+        "timestamp", "target_difficulty", "nonce", "orphan_reason",
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.index.to_string(),
+            self.epoch.to_string(),
+            self.hash.clone(),
+            self.previous_hash.clone(),
+            self.status.to_string(),
+            self.nominated_peer_id.clone(),
+            self.timestamp.to_string(),
+            self.target_difficulty.clone(),
+            self.nonce.clone(),
+            self.orphan_reason.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+impl Opts {
+    /// Whether a block matches every filter given
+    fn matches(&self, block: &MinerBlock) -> bool {
+        self.epoch.is_none_or(|epoch| block.epoch == epoch)
+            && self.peer.as_ref().is_none_or(|peer| &block.nominated_peer_id == peer)
+            && self.index.is_none_or(|index| block.index == index)
+            && self.hash.as_ref().is_none_or(|prefix| block.hash.starts_with(prefix.as_str()))
+            && (!self.orphans || block.is_orphaned)
+    }
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    let mgr = super::open_datastore(&opts.config)?;
+
+    // Look blocks up by the most selective filter's index, then apply the rest.
+    // Epoch and peer queries on their own list canonical blocks.
+    let blocks = if opts.forks {
+        MinerBlock::find_forks_multi(&mgr).await?
+    } else if opts.orphans {
+        MinerBlock::find_all_orphaned_multi(&mgr).await?
+    } else if let Some(prefix) = &opts.hash {
+        MinerBlock::find_by_hash_prefix_multi(&mgr, prefix).await?
+    } else if let Some(index) = opts.index {
+        MinerBlock::find_by_index_multi(&mgr, index).await?
+    } else if let Some(peer) = &opts.peer {
+        MinerBlock::find_canonical_by_peer_multi(&mgr, peer).await?
+    } else if let Some(epoch) = opts.epoch {
+        // Any epoch counts as old enough, so MinerCanon is searched too
+        MinerBlock::find_canonical_by_epoch_multi(&mgr, epoch, u64::MAX).await?
+    } else {
+        MinerBlock::find_all_canonical_multi(&mgr).await?
+    };
+    let rows: Vec<BlockRow> = blocks
+        .iter()
+        .filter(|block| opts.matches(block))
+        .map(BlockRow::from)
+        .collect();
+
+    if let Some(path) = &opts.export {
+        return export_rows(path, &rows);
+    }
+
+    let format = output::global();
+    if format.is_structured() {
+        return output::print_structured(format, &rows);
+    }
+
+    if rows.is_empty() {
+        println!("\n⚠️  No matching miner blocks found");
+        return Ok(());
+    }
+
+    println!();
+    println!("{:>8}  {:>6}  {:<9}  {:<19}  {:<19}  Difficulty", "Index", "Epoch", "Status", "Hash", "Miner");
+    println!("─────────────────────────────────────────────────────────────────────────────────");
+    for row in rows.iter().take(opts.limit) {
+        println!(
+            "{:>8}  {:>6}  {:<9}  {:<19}  {:<19}  {}",
+            row.index,
+            row.epoch,
+            row.status,
+            shorten(&row.hash),
+            shorten(&row.nominated_peer_id),
+            row.target_difficulty
+        );
+    }
+    if rows.len() > opts.limit {
+        println!("\n(Showing first {} of {} blocks; use --limit or --export for more)", opts.limit, rows.len());
+    } else {
+        println!("\n{} block(s)", rows.len());
+    }
+
+    Ok(())
+}
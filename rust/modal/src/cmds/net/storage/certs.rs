@@ -0,0 +1,166 @@
+use anyhow::Result;
+use clap::Parser;
+use serde::Serialize;
+use std::path::PathBuf;
+
+use modal_datastore::models::DAGCertificate;
+
+use super::{export_rows, shorten, Row};
+use crate::utils::output;
+
+#[derive(Debug, Parser)]
+#[command(about = "Query DAG certificates by round, author or digest prefix")]
+pub struct Opts {
+    /// Path to node configuration file
+    #[clap(long)]
+    config: PathBuf,
+
+    /// Certificates in this round
+    #[clap(long)]
+    round: Option<u64>,
+
+    /// Certificates by this validator
+    #[clap(long)]
+    author: Option<String>,
+
+    /// Certificates whose digest starts with this prefix
+    #[clap(long)]
+    digest: Option<String>,
+
+    /// Only committed certificates
+    #[clap(long)]
+    committed: bool,
+
+    /// Limit number of certificates to display (exports include every certificate)
+    #[clap(long, default_value = "50")]
+    limit: usize,
+
+    /// Write the certificates to a .csv or .json file
+    #[clap(long)]
+    export: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize)]
+struct CertRow {
+    round: u64,
+    digest: String,
+    author: String,
+    parents: usize,
+    batches: usize,
+    committed: bool,
+    committed_at_round: Option<u64>,
+    anchor: bool,
+    timestamp: u64,
+}
+
+impl From<&DAGCertificate> for CertRow {
+    fn from(cert: &DAGCertificate) -> Self {
+        Self {
+            round: cert.round,
+            digest: cert.digest.clone(),
+            author: cert.author.clone(),
+            parents: cert.parents.len(),
+            batches: cert.batch_digests.len(),
+            committed: cert.committed,
+            committed_at_round: cert.committed_at_round,
+            anchor: cert.anchor,
+            timestamp: cert.timestamp,
+        }
+    }
+}
+
+impl Row for CertRow {
+    const HEADERS: &'static [&'static str] = &[
+        "round", "digest", "author", "parents", "batches",
+        "committed", "committed_at_round", "anchor", "timestamp",
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.round.to_string(),
+            self.digest.clone(),
+            self.author.clone(),
+            self.parents.to_string(),
+            self.batches.to_string(),
+            self.committed.to_string(),
+            self.committed_at_round.map(|r| r.to_string()).unwrap_or_default(),
+            self.anchor.to_string(),
+            self.timestamp.to_string(),
+        ]
+    }
+}
+
+impl Opts {
+    /// Whether a certificate matches every filter given
+    fn matches(&self, cert: &DAGCertificate) -> bool {
+        self.round.is_none_or(|round| cert.round == round)
+            && self.author.as_ref().is_none_or(|author| &cert.author == author)
+            && self.digest.as_ref().is_none_or(|prefix| cert.digest.starts_with(prefix.as_str()))
+            && (!self.committed || cert.committed)
+    }
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    let mgr = super::open_datastore(&opts.config)?;
+
+    // Look certificates up by the most selective filter, then apply the rest
+    let mut certs = if let Some(prefix) = &opts.digest {
+        DAGCertificate::find_by_digest_prefix_multi(&mgr, prefix).await?
+    } else if let Some(round) = opts.round {
+        DAGCertificate::find_all_in_round_multi(&mgr, round).await?
+    } else if let Some(author) = &opts.author {
+        DAGCertificate::find_by_author_multi(&mgr, author).await?
+    } else if opts.committed {
+        DAGCertificate::find_all_committed_multi(&mgr).await?
+    } else {
+        // Every digest starts with the empty prefix
+        DAGCertificate::find_by_digest_prefix_multi(&mgr, "").await?
+    };
+    certs.sort_by(|a, b| a.round.cmp(&b.round).then_with(|| a.digest.cmp(&b.digest)));
+    let rows: Vec<CertRow> = certs
+        .iter()
+        .filter(|cert| opts.matches(cert))
+        .map(CertRow::from)
+        .collect();
+
+    if let Some(path) = &opts.export {
+        return export_rows(path, &rows);
+    }
+
+    let format = output::global();
+    if format.is_structured() {
+        return output::print_structured(format, &rows);
+    }
+
+    if rows.is_empty() {
+        println!("\n⚠️  No matching certificates found");
+        return Ok(());
+    }
+
+    println!();
+    println!("{:>8}  {:<19}  {:<19}  {:>7}  Status", "Round", "Digest", "Author", "Parents");
+    println!("─────────────────────────────────────────────────────────────────────────────────");
+    for row in rows.iter().take(opts.limit) {
+        let status = match (row.committed, row.anchor) {
+            (true, true) => "committed, anchor",
+            (true, false) => "committed",
+            (false, true) => "anchor",
+            (false, false) => "pending",
+        };
+        println!(
+            "{:>8}  {:<19}  {:<19}  {:>7}  {}",
+            row.round,
+            shorten(&row.digest),
+            shorten(&row.author),
+            row.parents,
+            status
+        );
+    }
+    if rows.len() > opts.limit {
+        println!("\n(Showing first {} of {} certificates; use --limit or --export for more)", opts.limit, rows.len());
+    } else {
+        println!("\n{} certificate(s)", rows.len());
+    }
+
+    Ok(())
+}
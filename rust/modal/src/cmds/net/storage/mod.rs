@@ -1,17 +1,26 @@
 use anyhow::Result;
-use clap::Parser;
-use std::path::PathBuf;
+use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
 use std::collections::HashMap;
+use serde::Serialize;
 
 use modal_node::config::Config;
 use modal_datastore::DatastoreManager;
 use modal_datastore::models::MinerBlock;
 
+pub mod blocks;
+pub mod certs;
+pub mod reindex;
+
 #[derive(Debug, Parser)]
 #[command(about = "Inspect network datastore and show miner block statistics")]
+#[command(subcommand_negates_reqs = true)]
 pub struct Opts {
-    #[clap(long, help = "Path to node configuration file")]
-    config: PathBuf,
+    #[command(subcommand)]
+    command: Option<StorageCommands>,
+
+    #[clap(long, required = true, help = "Path to node configuration file")]
+    config: Option<PathBuf>,
 
     #[clap(long, help = "Show detailed list of all blocks", default_value = "false")]
     detailed: bool,
@@ -23,9 +32,69 @@ pub struct Opts {
     limit: usize,
 }
 
-pub async fn run(opts: &Opts) -> Result<()> {
+#[derive(Debug, Subcommand)]
+enum StorageCommands {
+    #[command(about = "Query miner blocks by epoch, peer, height or hash prefix")]
+    Blocks(blocks::Opts),
+
+    #[command(about = "Query DAG certificates by round, author or digest prefix")]
+    Certs(certs::Opts),
+
+    #[command(about = "Rebuild the datastore's secondary indexes")]
+    Reindex(reindex::Opts),
+}
+
+/// A query result that can be exported as a CSV row
+trait Row: Serialize {
+    const HEADERS: &'static [&'static str];
+
+    fn fields(&self) -> Vec<String>;
+}
+
+/// Write rows to `path` as CSV or JSON, picked by its extension
+fn export_rows<R: Row>(path: &Path, rows: &[R]) -> Result<()> {
+    let content = match path.extension().and_then(|e| e.to_str()) {
+        Some("csv") => {
+            let mut csv = String::new();
+            csv.push_str(&R::HEADERS.join(","));
+            csv.push('\n');
+            for row in rows {
+                let fields: Vec<String> = row.fields().iter().map(|f| csv_field(f)).collect();
+                csv.push_str(&fields.join(","));
+                csv.push('\n');
+            }
+            csv
+        }
+        Some("json") => serde_json::to_string_pretty(rows)?,
+        _ => anyhow::bail!("Export file must end in .csv or .json: {}", path.display()),
+    };
+    std::fs::write(path, content)?;
+    println!("💾 Exported {} rows to {}", rows.len(), path.display());
+    Ok(())
+}
+
+/// Quote a CSV field if it needs it
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// First and last 8 characters of a long hash or peer ID
+pub(crate) fn shorten(s: &str) -> String {
+    if s.len() > 16 {
+        format!("{}...{}", &s[..8], &s[s.len()-8..])
+    } else {
+        s.to_string()
+    }
+}
+
+/// Open the datastore of the node configured at `config_path`
+pub(crate) fn open_datastore(config_path: &Path) -> Result<DatastoreManager> {
     // Load the config to get the data directory
-    let config = Config::from_filepath(&opts.config)?;
+    let config = Config::from_filepath(config_path)?;
 
     // Use data_dir if available, otherwise fall back to storage_path
     let data_dir = config.data_dir
@@ -36,8 +105,22 @@ pub async fn run(opts: &Opts) -> Result<()> {
         anyhow::bail!("Data directory does not exist: {:?}", data_dir);
     }
 
-    println!("📁 Opening datastore at: {:?}", data_dir);
-    let mgr = DatastoreManager::open(&data_dir)?;
+    // On stderr so it doesn't mix with structured output
+    eprintln!("📁 Opening datastore at: {:?}", data_dir);
+    Ok(DatastoreManager::open(&data_dir)?)
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    match &opts.command {
+        Some(StorageCommands::Blocks(blocks_opts)) => return blocks::run(blocks_opts).await,
+        Some(StorageCommands::Certs(certs_opts)) => return certs::run(certs_opts).await,
+        Some(StorageCommands::Reindex(reindex_opts)) => return reindex::run(reindex_opts).await,
+        None => {}
+    }
+
+    let config = opts.config.as_deref()
+        .ok_or_else(|| anyhow::anyhow!("--config is required"))?;
+    let mgr = open_datastore(config)?;
 
     println!();
 
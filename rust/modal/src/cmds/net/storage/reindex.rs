@@ -0,0 +1,25 @@
+use anyhow::Result;
+use clap::Parser;
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+#[command(about = "Rebuild the datastore's secondary indexes")]
+pub struct Opts {
+    /// Path to node configuration file
+    #[clap(long)]
+    config: PathBuf,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    let mgr = super::open_datastore(&opts.config)?;
+
+    println!("\n🔧 Rebuilding secondary indexes...");
+    for (store, collection, count) in mgr.rebuild_indexes()? {
+        println!("  {} {}: {} records indexed", store, collection, count);
+    }
+    mgr.flush_all()?;
+
+    println!("\n✅ Reindex complete!\n");
+
+    Ok(())
+}
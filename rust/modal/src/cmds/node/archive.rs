@@ -0,0 +1,62 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::path::PathBuf;
+
+use modal_datastore::models::miner::archive;
+use modal_datastore::DatastoreManager;
+use modal_node::config_resolution::load_config_with_node_dir;
+
+#[derive(Debug, Parser)]
+#[command(about = "Export finalized epochs to a cold storage archive")]
+pub struct Opts {
+    /// Path to node configuration file
+    #[clap(long)]
+    pub config: Option<PathBuf>,
+
+    /// Node directory containing config.json (defaults to current directory)
+    #[clap(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Archive directory; segments already in it are kept
+    #[clap(long)]
+    pub out: PathBuf,
+
+    /// Only archive epochs at least this many epochs behind the chain tip
+    #[clap(long, default_value = "2")]
+    pub keep_epochs: u64,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    // If neither config nor dir is provided, default to current directory
+    let dir = if opts.config.is_none() && opts.dir.is_none() {
+        Some(std::env::current_dir()?)
+    } else {
+        opts.dir.clone()
+    };
+
+    let config = load_config_with_node_dir(opts.config.clone(), dir.clone())?;
+
+    let data_dir = config.data_dir.as_ref()
+        .or(config.storage_path.as_ref())
+        .context("No data_dir or storage_path in config")?;
+
+    let datastore_manager = DatastoreManager::open(data_dir)
+        .context("Failed to open datastore (stop the node before archiving it)")?;
+
+    let (manifest, written) = archive::export_archive_multi(&datastore_manager, &opts.out, opts.keep_epochs).await?;
+
+    println!("📦 Wrote {} new segments to {}", written, opts.out.display());
+    match (manifest.segments.first(), manifest.segments.last()) {
+        (Some(first), Some(last)) => {
+            let size: u64 = manifest.segments.iter().map(|s| s.size).sum();
+            println!(
+                "   Archive holds epochs {} to {} (blocks {} to {}, {} bytes)",
+                first.epoch, last.epoch, first.first_block_index, last.last_block_index, size
+            );
+            println!("   Upload the directory to object storage and list its URL under `archives` in the network config");
+        }
+        _ => println!("   No epochs are final yet"),
+    }
+
+    Ok(())
+}
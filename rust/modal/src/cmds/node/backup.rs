@@ -0,0 +1,67 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::path::PathBuf;
+
+use modal_datastore::DatastoreManager;
+use modal_node::config_resolution::load_config_with_node_dir;
+
+#[derive(Debug, Parser)]
+#[command(about = "Back up node storage to a compressed archive")]
+pub struct Opts {
+    /// Path to node configuration file
+    #[clap(long)]
+    pub config: Option<PathBuf>,
+
+    /// Node directory containing config.json (defaults to current directory)
+    #[clap(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Archive to write (e.g. backup.tar.zst)
+    #[clap(long, short)]
+    pub output: PathBuf,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    // If neither config nor dir is provided, default to current directory
+    let dir = if opts.config.is_none() && opts.dir.is_none() {
+        Some(std::env::current_dir()?)
+    } else {
+        opts.dir.clone()
+    };
+
+    let config = load_config_with_node_dir(opts.config.clone(), dir.clone())?;
+
+    let data_dir = config.data_dir.as_ref()
+        .or(config.storage_path.as_ref())
+        .context("No data_dir or storage_path in config")?;
+
+    // Opening the stores fails while the node is running, so nothing writes during the backup
+    let datastore_manager = DatastoreManager::open(data_dir)
+        .context("Failed to open datastore (stop the node before backing it up)")?;
+
+    if opts.output.exists() {
+        anyhow::bail!("Output file already exists: {}", opts.output.display());
+    }
+
+    println!("💾 Backing up {}...", data_dir.display());
+    let file = std::fs::File::create(&opts.output)
+        .with_context(|| format!("Failed to create {}", opts.output.display()))?;
+    let manifest = match datastore_manager.backup(std::io::BufWriter::new(file)).await {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            let _ = std::fs::remove_file(&opts.output);
+            return Err(e.into());
+        }
+    };
+
+    for store in &manifest.stores {
+        println!("  {}: {} entries", store.name, store.entries);
+    }
+    if let Some(tip) = &manifest.miner_tip {
+        println!("  Miner tip: #{} {}", tip.index, tip.hash);
+    }
+    println!("  Validator round: {}", manifest.current_round);
+    println!("✅ Backup written to {}", opts.output.display());
+
+    Ok(())
+}
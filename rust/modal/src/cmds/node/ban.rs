@@ -0,0 +1,69 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::path::PathBuf;
+
+use modal_node::ban_list::{self, BanList, BanRule};
+use modal_node::config_resolution::load_config_with_node_dir;
+
+#[derive(Debug, Parser)]
+#[command(about = "Ban an IP address or subnet from connecting to a node")]
+pub struct Opts {
+    /// Address, subnet or multiaddr to ban, e.g. 203.0.113.5, 203.0.113.0/24 or /ip4/203.0.113.5/tcp/10001
+    address: String,
+
+    /// Path to node configuration file
+    #[clap(long)]
+    config: Option<PathBuf>,
+
+    /// Node directory containing config.json (defaults to current directory)
+    #[clap(long)]
+    dir: Option<PathBuf>,
+
+    /// Why the address is banned
+    #[clap(long, default_value = "Banned by operator")]
+    reason: String,
+
+    /// Lift the ban after this many seconds (default: never)
+    #[clap(long)]
+    duration_secs: Option<i64>,
+
+    /// Remove the ban instead
+    #[clap(long)]
+    remove: bool,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    // If neither config nor dir is provided, default to current directory
+    let dir = if opts.config.is_none() && opts.dir.is_none() {
+        Some(std::env::current_dir()?)
+    } else {
+        opts.dir.clone()
+    };
+    let config = load_config_with_node_dir(opts.config.clone(), dir)?;
+    let path = ban_list::path_for(&config).context("No ban_list_path, data_dir or storage_path in config")?;
+    let mut list = BanList::load(&path)?;
+
+    if opts.remove {
+        if list.unban(&opts.address)? {
+            list.save(&path)?;
+            println!("✅ Unbanned {}", opts.address);
+        } else {
+            println!("{} was not banned", opts.address);
+        }
+        return Ok(());
+    }
+
+    let subnet = ban_list::parse_subnet(&opts.address)?;
+    let now = chrono::Utc::now().timestamp();
+    list.ban(BanRule {
+        subnet: subnet.to_string(),
+        reason: opts.reason.clone(),
+        created_at: now,
+        expires_at: opts.duration_secs.map(|secs| now + secs),
+        automatic: false,
+    });
+    list.save(&path)?;
+    println!("✅ Banned {}", subnet);
+    println!("   A running node applies the ban within a minute.");
+    Ok(())
+}
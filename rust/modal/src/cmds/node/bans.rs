@@ -0,0 +1,62 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::path::PathBuf;
+
+use modal_node::ban_list::{self, BanList, FirewallFormat};
+use modal_node::config_resolution::load_config_with_node_dir;
+use crate::utils::output;
+
+#[derive(Debug, Parser)]
+#[command(about = "List a node's IP bans or export them as firewall rules")]
+pub struct Opts {
+    /// Path to node configuration file
+    #[clap(long)]
+    config: Option<PathBuf>,
+
+    /// Node directory containing config.json (defaults to current directory)
+    #[clap(long)]
+    dir: Option<PathBuf>,
+
+    /// Print the active bans as nftables or iptables rules
+    #[clap(long, value_enum)]
+    export: Option<FirewallFormat>,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    // If neither config nor dir is provided, default to current directory
+    let dir = if opts.config.is_none() && opts.dir.is_none() {
+        Some(std::env::current_dir()?)
+    } else {
+        opts.dir.clone()
+    };
+    let config = load_config_with_node_dir(opts.config.clone(), dir)?;
+    let path = ban_list::path_for(&config).context("No ban_list_path, data_dir or storage_path in config")?;
+    let list = BanList::load(&path)?;
+    let now = chrono::Utc::now().timestamp();
+
+    if let Some(format) = opts.export {
+        print!("{}", ban_list::export_rules(&list, format, now));
+        return Ok(());
+    }
+
+    let active: Vec<_> = list.active(now).collect();
+    let format = output::global();
+    if format.is_structured() {
+        return output::print_structured(format, &active);
+    }
+
+    if active.is_empty() {
+        println!("No active bans in {}", path.display());
+        return Ok(());
+    }
+    println!("{:<43}  {:<9}  {:<20}  Reason", "Subnet", "Kind", "Expires");
+    for rule in active {
+        let expires = rule.expires_at
+            .and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+            .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+            .unwrap_or_else(|| "never".to_string());
+        let kind = if rule.automatic { "automatic" } else { "manual" };
+        println!("{:<43}  {:<9}  {:<20}  {}", rule.subnet, kind, expires, rule.reason);
+    }
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use anyhow::Result;
+use clap::Parser;
+use std::time::Duration;
+
+use modal_common::hash_tax;
+
+#[derive(Debug, Parser)]
+#[command(about = "Measure local mining hashrate across hash functions and thread counts")]
+pub struct Opts {
+    /// How long to hash for in each configuration (e.g. 30s, 500ms, 2m)
+    #[clap(long, default_value = "10s", value_parser = parse_duration)]
+    duration: Duration,
+
+    /// Hash function to benchmark; repeat to compare several (defaults to all)
+    #[clap(long = "hash-func")]
+    hash_funcs: Vec<String>,
+
+    /// Thread count to benchmark; repeat to compare several (defaults to 1 and all cores)
+    #[clap(long)]
+    threads: Vec<usize>,
+
+    /// Delay between hash attempts in milliseconds, as for mining_delay_ms
+    #[clap(long)]
+    mining_delay_ms: Option<u64>,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    let hash_funcs = if opts.hash_funcs.is_empty() {
+        hash_tax::hash_function_names()
+    } else {
+        opts.hash_funcs.clone()
+    };
+    let thread_counts = if opts.threads.is_empty() {
+        let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        let mut counts = vec![1, cores];
+        counts.dedup();
+        counts
+    } else {
+        opts.threads.clone()
+    };
+
+    let mut results = Vec::new();
+    for hash_func in &hash_funcs {
+        for &threads in &thread_counts {
+            eprintln!("⏱️  Benchmarking {} on {} thread(s) for {:?}...", hash_func, threads, opts.duration);
+            let (hash_func, duration, mining_delay_ms) = (hash_func.clone(), opts.duration, opts.mining_delay_ms);
+            let result = tokio::task::spawn_blocking(move || {
+                hash_tax::benchmark(&hash_func, threads, duration, mining_delay_ms).map_err(|e| anyhow::anyhow!("{}", e))
+            })
+            .await??;
+            results.push(serde_json::json!({
+                "hash_func": result.hash_func_name,
+                "threads": threads,
+                "mining_delay_ms": opts.mining_delay_ms,
+                "attempts": result.attempts as u64,
+                "duration_secs": result.duration_secs,
+                "hashrate": result.hashrate(),
+                "thread_hashrates": result.threads.iter().map(|t| t.hashrate()).collect::<Vec<_>>(),
+            }));
+        }
+    }
+
+    println!("{}", serde_json::to_string_pretty(&serde_json::json!({ "results": results }))?);
+
+    Ok(())
+}
+
+/// Parse a duration like `30s`, `500ms` or `2m` (plain numbers are seconds)
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (value, unit) = s.split_at(split);
+    let value: u64 = value.parse().map_err(|_| format!("invalid duration: {}", s))?;
+    match unit {
+        "ms" => Ok(Duration::from_millis(value)),
+        "" | "s" => Ok(Duration::from_secs(value)),
+        "m" => Ok(Duration::from_secs(value * 60)),
+        _ => Err(format!("invalid duration unit '{}' (use ms, s or m)", unit)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration("5"), Ok(Duration::from_secs(5)));
+        assert!(parse_duration("5h").is_err());
+        assert!(parse_duration("s").is_err());
+    }
+}
@@ -430,7 +430,7 @@ pub async fn run(opts: &Opts) -> Result<()> {
         // This allows templates to work with embedded network configs from modal-networks
         if let Some(network_name) = &template_network {
             // Verify the network exists in modal-networks
-            if modal_networks::networks::by_name(network_name).is_some() {
+            if modal_networks::registry::find(network_name)?.is_some() {
                 // Use a special marker that the node will recognize to load from embedded configs
                 obj.insert("network_config_path".to_string(), json!(format!("modal-networks://{}", network_name)));
                 println!("📋 Network config: {} (from modal-networks)", network_name);
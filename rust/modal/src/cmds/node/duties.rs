@@ -0,0 +1,93 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::path::PathBuf;
+
+use modal_datastore::DatastoreManager;
+use modal_node::config_resolution::load_config_with_node_dir;
+use modal_node::duties::{duty_calendar, DutyRole, DutyStatus};
+use crate::utils::output;
+
+#[derive(Debug, Parser)]
+#[command(about = "Predict which upcoming epochs this node validates")]
+pub struct Opts {
+    /// Path to node configuration file
+    #[clap(long)]
+    config: Option<PathBuf>,
+
+    /// Node directory containing config.json (defaults to current directory)
+    #[clap(long)]
+    dir: Option<PathBuf>,
+
+    /// Peer to predict duties for (defaults to the node's own ID)
+    #[clap(long)]
+    peer: Option<String>,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    // If neither config nor dir is provided, default to current directory
+    let dir = if opts.config.is_none() && opts.dir.is_none() {
+        Some(std::env::current_dir()?)
+    } else {
+        opts.dir.clone()
+    };
+    let config = load_config_with_node_dir(opts.config.clone(), dir)?;
+    let peer_id = match &opts.peer {
+        Some(peer) => peer.clone(),
+        None => config.id.clone().context("No peer ID in config")?,
+    };
+
+    let data_dir = config.data_dir.as_ref()
+        .or(config.storage_path.as_ref())
+        .context("No data_dir or storage_path in config")?;
+    let mut mgr = DatastoreManager::open(data_dir)?;
+    if let Some(schedule) = mgr.get_stored_era_schedule().await? {
+        mgr.set_era_schedule(schedule);
+    }
+    let calendar = duty_calendar(&mgr, &peer_id).await?;
+
+    let format = output::global();
+    if format.is_structured() {
+        return output::print_structured(format, &calendar);
+    }
+
+    println!();
+    println!("🗓️  Validator duties for {}", calendar.peer_id);
+    println!("   Mode: {}", if calendar.mode == "static" { "static validators" } else { "hybrid (epoch N-2 nominations)" });
+    match calendar.tip_height {
+        Some(height) => println!("   Current epoch: {} (height {})", calendar.current_epoch, height),
+        None => println!("   Current epoch: {} (no blocks yet)", calendar.current_epoch),
+    }
+    println!();
+    println!("{:>7}  {:>11}  {:<11}  {:<10}  {:>11}  {:>10}  Starts", "Epoch", "Nominations", "Role", "Status", "Validators", "Height");
+    println!("─────────────────────────────────────────────────────────────────────────────────────────");
+    for duty in &calendar.duties {
+        let role = match duty.role {
+            DutyRole::Validator => "✅ validator",
+            DutyRole::Alternate => "alternate",
+            DutyRole::None => "-",
+        };
+        let status = match duty.status {
+            DutyStatus::Final => "final",
+            DutyStatus::Provisional => "provisional",
+            DutyStatus::Unknown => "unknown",
+        };
+        let starts = duty.estimated_start
+            .and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+            .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let nominations = match duty.nomination_epoch {
+            Some(epoch) => format!("{} (e{})", duty.nominations, epoch),
+            None => "-".to_string(),
+        };
+        println!(
+            "{:>7}  {:>11}  {:<11}  {:<10}  {:>11}  {:>10}  {}",
+            duty.epoch, nominations, role, status, duty.validators, duty.start_height, starts
+        );
+    }
+    if calendar.duties.iter().any(|d| d.status == DutyStatus::Provisional) {
+        println!();
+        println!("Provisional duties follow the nominations so far and can change until their nomination epoch ends.");
+    }
+
+    Ok(())
+}
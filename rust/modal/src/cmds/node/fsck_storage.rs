@@ -0,0 +1,89 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::path::PathBuf;
+
+use modal_datastore::{DatastoreManager, IssueKind};
+use modal_node::config_resolution::load_config_with_node_dir;
+
+#[derive(Debug, Parser)]
+#[command(about = "Check node storage for corrupt or truncated records")]
+pub struct Opts {
+    /// Path to node configuration file
+    #[clap(long)]
+    pub config: Option<PathBuf>,
+
+    /// Node directory containing config.json (defaults to current directory)
+    #[clap(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Repair damaged records from other stores, or mark them for re-sync
+    #[clap(long)]
+    pub repair: bool,
+
+    /// Print the report as JSON
+    #[clap(long)]
+    pub json: bool,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    // If neither config nor dir is provided, default to current directory
+    let dir = if opts.config.is_none() && opts.dir.is_none() {
+        Some(std::env::current_dir()?)
+    } else {
+        opts.dir.clone()
+    };
+
+    let config = load_config_with_node_dir(opts.config.clone(), dir.clone())?;
+
+    let data_dir = config.data_dir.as_ref()
+        .or(config.storage_path.as_ref())
+        .context("No data_dir or storage_path in config")?;
+
+    // Opening the stores fails while the node is running, so nothing writes during the check
+    let datastore_manager = DatastoreManager::open(data_dir)
+        .context("Failed to open datastore (stop the node before checking it)")?;
+
+    let report = datastore_manager.fsck(opts.repair).await?;
+    let resync = datastore_manager.resync_ranges()?;
+
+    if opts.json {
+        println!("{}", serde_json::to_string_pretty(&serde_json::json!({
+            "records_checked": report.records_checked,
+            "issues": report.issues,
+            "resync_ranges": resync,
+        }))?);
+    } else {
+        println!("🔍 Checked {} records in {}", report.records_checked, data_dir.display());
+        let unchecked = report.issues.iter().filter(|i| i.kind == IssueKind::Unchecked).count();
+        for issue in report.issues.iter().filter(|i| i.kind != IssueKind::Unchecked) {
+            match &issue.repair {
+                Some(repair) => println!("  {} {}: {:?} → {:?}", issue.store, issue.key, issue.kind, repair),
+                None => println!("  {} {}: {:?}", issue.store, issue.key, issue.kind),
+            }
+        }
+        if unchecked > 0 {
+            if opts.repair {
+                println!("  {} records written before checksums now have one", unchecked);
+            } else {
+                println!("  {} records have no checksum yet (written before checksums; --repair adds them)", unchecked);
+            }
+        }
+        for range in &resync {
+            println!("  Blocks {}..={} will be re-synced from peers", range.start, range.end);
+        }
+        if report.has_damage() {
+            if opts.repair {
+                println!("🔧 Repaired damaged records");
+            } else {
+                println!("❌ Storage has damaged records (run with --repair)");
+            }
+        } else {
+            println!("✅ No damaged records");
+        }
+    }
+
+    if report.has_damage() && !opts.repair {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -1,10 +1,12 @@
 use anyhow::{Result, Context};
 use clap::Parser;
+use serde_json::json;
 use std::path::PathBuf;
 
 use modal_node::config_resolution::load_config_with_node_dir;
 use modal_datastore::DatastoreManager;
 use modal_datastore::models::miner::MinerBlock;
+use crate::utils::output;
 
 #[derive(Debug, Parser)]
 #[command(about = "Display information about a node")]
@@ -61,6 +63,26 @@ pub async fn run(opts: &Opts) -> Result<()> {
         (vec![], None, None, 0)
     };
     
+    let format = output::global();
+    if format.is_structured() {
+        return output::print_structured(format, &json!({
+            "peer_id": config.id,
+            "listeners": config.listeners.iter().flatten().map(|l| l.to_string()).collect::<Vec<_>>(),
+            "bootstrappers": config.bootstrappers.iter().flatten().map(|b| b.to_string()).collect::<Vec<_>>(),
+            "storage_path": config.storage_path,
+            "chain": {
+                "height": chain_tip.as_ref().map(|b| b.index),
+                "tip_hash": chain_tip.as_ref().map(|b| b.hash.clone()),
+                "tip_timestamp": chain_tip.as_ref().map(|b| b.timestamp),
+                "tip_epoch": chain_tip.as_ref().map(|b| b.epoch),
+                "genesis_hash": genesis_block.as_ref().map(|b| b.hash.clone()),
+                "canonical_blocks": canonical_blocks.len(),
+                "blocks_mined_by_node": blocks_mined_by_node,
+            },
+            "status_port": config.status_port,
+        }));
+    }
+    
     // Print basic node information
     println!("╭─────────────────────────────────────────────────────────────╮");
     println!("│  Modal Node Information                                     │");
@@ -204,6 +226,15 @@ pub async fn run(opts: &Opts) -> Result<()> {
         if let Some(check_interval) = config.autoupgrade_check_interval_secs {
             println!("    Check Interval: {} seconds", check_interval);
         }
+        if let Some(canary_percent) = config.autoupgrade_canary_percent {
+            println!("    Canary Percent: {}%", canary_percent);
+        }
+        if let Some(delay) = config.autoupgrade_rollout_delay_secs {
+            println!("    Rollout Delay: {} seconds", delay);
+        }
+        if let Some(ref windows) = config.autoupgrade_windows {
+            println!("    Upgrade Windows (UTC): {}", windows.join(", "));
+        }
         println!();
     }
     
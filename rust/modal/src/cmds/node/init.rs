@@ -0,0 +1,478 @@
+//! Set up a new node interactively.
+//!
+//! Asks for the network, role, key handling, ports and autoupgrade
+//! preferences, creates the node directory the way `modal node create`
+//! does, and can register a systemd (Linux) or launchd (macOS) service
+//! that runs the node.
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use modality::constants::{DEFAULT_AUTOUPGRADE_BASE_URL, DEFAULT_AUTOUPGRADE_CHECK_INTERVAL_SECS};
+
+use super::create;
+
+#[derive(Debug, Parser)]
+#[command(about = "Set up a new node interactively")]
+pub struct Opts {
+    /// Node directory to create (asked for when not given)
+    #[clap(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Accept the default answer to every question
+    #[clap(long, short)]
+    pub yes: bool,
+}
+
+/// Roles a node can run as
+const ROLES: &[&str] = &["miner", "validator", "observer"];
+
+/// Default port for the p2p listener
+const DEFAULT_P2P_PORT: u16 = 4040;
+
+/// Where the node's key comes from
+#[derive(Debug, Clone, PartialEq)]
+enum KeySource {
+    Generate,
+    Mnemonic,
+    Import(PathBuf),
+}
+
+/// Everything the wizard asks for
+#[derive(Debug, Clone, PartialEq)]
+struct Answers {
+    dir: PathBuf,
+    network: String,
+    bootstrappers: Vec<String>,
+    role: String,
+    keys: KeySource,
+    p2p_port: u16,
+    status_port: Option<u16>,
+    explorer_port: Option<u16>,
+    /// Branch to autoupgrade from, if enabled
+    autoupgrade_branch: Option<String>,
+    register_service: bool,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    let stdin = io::stdin();
+    let mut prompter = Prompter {
+        input: stdin.lock(),
+        output: io::stdout(),
+        use_defaults: opts.yes,
+    };
+
+    println!("🧙 Modality node setup\n");
+    let answers = ask(&mut prompter, opts)?;
+
+    println!("\n📋 Summary");
+    println!("   Directory:   {}", answers.dir.display());
+    println!("   Network:     {}", answers.network);
+    println!("   Role:        {}", answers.role);
+    println!("   P2P port:    {}", answers.p2p_port);
+    if let Some(port) = answers.status_port {
+        println!("   Status port: {}", port);
+    }
+    if let Some(port) = answers.explorer_port {
+        println!("   Explorer:    {}", port);
+    }
+    match &answers.autoupgrade_branch {
+        Some(branch) => println!("   Autoupgrade: {} branch", branch),
+        None => println!("   Autoupgrade: disabled"),
+    }
+    if !prompter.confirm("\nCreate the node?", true)? {
+        println!("❌  Setup cancelled.");
+        return Ok(());
+    }
+    drop(prompter);
+    println!();
+
+    create_node(&answers).await?;
+
+    if answers.register_service {
+        println!();
+        register_service(&answers)?;
+    }
+
+    Ok(())
+}
+
+/// Asks questions on a terminal, or takes the defaults
+struct Prompter<R, W> {
+    input: R,
+    output: W,
+    use_defaults: bool,
+}
+
+impl<R: BufRead, W: Write> Prompter<R, W> {
+    /// Free-form answer, `default` when left empty
+    fn ask(&mut self, question: &str, default: &str) -> Result<String> {
+        if self.use_defaults {
+            return Ok(default.to_string());
+        }
+        if default.is_empty() {
+            write!(self.output, "{}: ", question)?;
+        } else {
+            write!(self.output, "{} [{}]: ", question, default)?;
+        }
+        self.output.flush()?;
+
+        let mut answer = String::new();
+        if self.input.read_line(&mut answer)? == 0 {
+            bail!("Setup cancelled");
+        }
+        let answer = answer.trim();
+        Ok(if answer.is_empty() { default.to_string() } else { answer.to_string() })
+    }
+
+    /// Index of one of `options`, picked by number or name
+    fn choose(&mut self, question: &str, options: &[&str], default: usize) -> Result<usize> {
+        if !self.use_defaults {
+            writeln!(self.output, "{}", question)?;
+            for (i, option) in options.iter().enumerate() {
+                writeln!(self.output, "  {}. {}", i + 1, option)?;
+            }
+        }
+        loop {
+            let answer = self.ask("Choice", &(default + 1).to_string())?;
+            let index = answer
+                .parse::<usize>()
+                .ok()
+                .filter(|n| (1..=options.len()).contains(n))
+                .map(|n| n - 1)
+                .or_else(|| options.iter().position(|o| o.eq_ignore_ascii_case(&answer)));
+            match index {
+                Some(index) => return Ok(index),
+                None => writeln!(self.output, "Please enter 1-{} or an option name", options.len())?,
+            }
+        }
+    }
+
+    fn confirm(&mut self, question: &str, default: bool) -> Result<bool> {
+        loop {
+            let answer = self
+                .ask(&format!("{} (y/n)", question), if default { "y" } else { "n" })?
+                .to_lowercase();
+            match answer.as_str() {
+                "y" | "yes" => return Ok(true),
+                "n" | "no" => return Ok(false),
+                _ => writeln!(self.output, "Please answer y or n")?,
+            }
+        }
+    }
+
+    /// A port, or None when answered "none"
+    fn ask_port(&mut self, question: &str, default: Option<u16>) -> Result<Option<u16>> {
+        let default = default.map(|p| p.to_string()).unwrap_or_else(|| "none".to_string());
+        loop {
+            let answer = self.ask(question, &default)?;
+            if answer == "none" {
+                return Ok(None);
+            }
+            match answer.parse::<u16>() {
+                Ok(port) if port > 0 => return Ok(Some(port)),
+                _ => writeln!(self.output, "Please enter a port number or 'none'")?,
+            }
+        }
+    }
+}
+
+fn ask<R: BufRead, W: Write>(prompter: &mut Prompter<R, W>, opts: &Opts) -> Result<Answers> {
+    let networks = modal_networks::registry::all()?;
+    if networks.is_empty() {
+        bail!("No networks known");
+    }
+    let names: Vec<&str> = networks.iter().map(|n| n.name.as_str()).collect();
+    let default_network = names.iter().position(|n| *n == "testnet").unwrap_or(0);
+    let network = &networks[prompter.choose("Which network should the node join?", &names, default_network)?];
+
+    let role = ROLES[prompter.choose("What should the node run as?", ROLES, 0)?].to_string();
+
+    let dir = match &opts.dir {
+        Some(dir) => dir.clone(),
+        None => PathBuf::from(prompter.ask("Node directory", &format!("./{}-{}", network.name, role))?),
+    };
+    if dir.join("config.json").exists() {
+        bail!("{} already has a config.json", dir.display());
+    }
+
+    let key_options = ["generate a new key", "generate a key from a new seed phrase", "import a passfile"];
+    let keys = match prompter.choose("How should the node get its key?", &key_options, 0)? {
+        0 => KeySource::Generate,
+        1 => KeySource::Mnemonic,
+        _ => loop {
+            let path = PathBuf::from(prompter.ask("Passfile to import", "")?);
+            if path.is_file() {
+                break KeySource::Import(path);
+            }
+            if prompter.use_defaults {
+                bail!("A passfile path is needed to import a key");
+            }
+            writeln!(prompter.output, "No file at {}", path.display())?;
+        },
+    };
+
+    let p2p_port = loop {
+        if let Some(port) = prompter.ask_port("P2P listen port", Some(DEFAULT_P2P_PORT))? {
+            break port;
+        }
+        writeln!(prompter.output, "The node needs a p2p port")?;
+    };
+    let status_port = prompter.ask_port("Status page port", None)?;
+    let explorer_port = prompter.ask_port("Chain explorer port", None)?;
+
+    let public_network = matches!(network.name.as_str(), "mainnet" | "testnet");
+    let autoupgrade_branch = if prompter.confirm("Upgrade the node automatically?", public_network)? {
+        Some(prompter.ask("Autoupgrade branch", &network.name)?)
+    } else {
+        None
+    };
+
+    let register_service = cfg!(any(target_os = "linux", target_os = "macos"))
+        && prompter.confirm("Register a service that runs the node at login?", false)?;
+
+    Ok(Answers {
+        dir,
+        network: network.name.clone(),
+        bootstrappers: network.bootstrappers.clone(),
+        role,
+        keys,
+        p2p_port,
+        status_port,
+        explorer_port,
+        autoupgrade_branch,
+        register_service,
+    })
+}
+
+/// Create the node directory, then set what `node create` doesn't cover
+async fn create_node(answers: &Answers) -> Result<()> {
+    let mut args = vec!["create".to_string(), "--dir".to_string(), answers.dir.to_string_lossy().to_string()];
+    if !answers.bootstrappers.is_empty() {
+        args.push("--bootstrappers".to_string());
+        args.push(answers.bootstrappers.join(","));
+    }
+    match &answers.keys {
+        KeySource::Generate => {}
+        KeySource::Mnemonic => args.push("--use-mnemonic".to_string()),
+        KeySource::Import(path) => {
+            args.push("--from-passfile".to_string());
+            args.push(path.to_string_lossy().to_string());
+        }
+    }
+    create::run(&create::Opts::try_parse_from(args)?).await?;
+
+    let config_path = answers.dir.join("config.json");
+    let content = std::fs::read_to_string(&config_path)
+        .with_context(|| format!("Failed to read {}", config_path.display()))?;
+    let mut config: Value = serde_json::from_str(&content)?;
+    apply_answers(&mut config, answers);
+    std::fs::write(&config_path, serde_json::to_string_pretty(&config)?)
+        .with_context(|| format!("Failed to write {}", config_path.display()))?;
+
+    println!("⚙️  Configured as a {} on {}", answers.role, answers.network);
+    Ok(())
+}
+
+/// Set the network, role, ports and autoupgrade settings in a config
+fn apply_answers(config: &mut Value, answers: &Answers) {
+    config["network_config_path"] = json!(format!("modal-networks://{}", answers.network));
+    config["run_as"] = json!(answers.role);
+    config["run_miner"] = json!(answers.role == "miner");
+    config["listeners"] = json!([format!("/ip4/0.0.0.0/tcp/{}/ws", answers.p2p_port)]);
+    if let Some(port) = answers.status_port {
+        config["status_port"] = json!(port);
+    }
+    if let Some(port) = answers.explorer_port {
+        config["explorer_port"] = json!(port);
+    }
+    if let Some(branch) = &answers.autoupgrade_branch {
+        config["autoupgrade_enabled"] = json!(true);
+        config["autoupgrade_base_url"] = json!(DEFAULT_AUTOUPGRADE_BASE_URL);
+        config["autoupgrade_branch"] = json!(branch);
+        config["autoupgrade_check_interval_secs"] = json!(DEFAULT_AUTOUPGRADE_CHECK_INTERVAL_SECS);
+    }
+}
+
+/// `modal node` subcommand running a role in the foreground
+fn run_command(role: &str) -> &'static str {
+    match role {
+        "miner" => "run-miner",
+        "validator" => "run-validator",
+        "observer" => "run-observer",
+        _ => "run",
+    }
+}
+
+/// Service name for a node directory, e.g. `modal-node-testnet-miner`
+fn service_name(dir: &Path) -> String {
+    let name: String = dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '-' })
+        .collect();
+    format!("modal-node-{}", name.trim_matches('-'))
+}
+
+/// systemd user unit running the node
+fn systemd_unit(exe: &Path, dir: &Path, role: &str) -> String {
+    format!(
+        "[Unit]\n\
+         Description=Modality {role} node ({dir})\n\
+         After=network-online.target\n\
+         \n\
+         [Service]\n\
+         ExecStart=\"{exe}\" node {command} --dir \"{dir}\"\n\
+         WorkingDirectory={dir}\n\
+         Restart=on-failure\n\
+         RestartSec=5\n\
+         \n\
+         [Install]\n\
+         WantedBy=default.target\n",
+        role = role,
+        exe = exe.display(),
+        command = run_command(role),
+        dir = dir.display(),
+    )
+}
+
+/// launchd agent running the node
+fn launchd_plist(label: &str, exe: &Path, dir: &Path, role: &str) -> String {
+    let arguments: String = [exe.to_string_lossy().as_ref(), "node", run_command(role), "--dir", dir.to_string_lossy().as_ref()]
+        .iter()
+        .map(|arg| format!("        <string>{}</string>\n", escape_xml(arg)))
+        .collect();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+{arguments}    </array>
+    <key>WorkingDirectory</key>
+    <string>{dir}</string>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+</dict>
+</plist>
+"#,
+        label = escape_xml(label),
+        arguments = arguments,
+        dir = escape_xml(&dir.to_string_lossy()),
+    )
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Write a service definition for the node and enable it
+fn register_service(answers: &Answers) -> Result<()> {
+    let exe = std::env::current_exe().context("Failed to get current executable path")?;
+    let dir = answers.dir.canonicalize()
+        .with_context(|| format!("Failed to resolve {}", answers.dir.display()))?;
+    let home = dirs::home_dir().context("Cannot determine home directory")?;
+    let name = service_name(&dir);
+
+    if cfg!(target_os = "macos") {
+        let label = format!("network.modality.{}", name);
+        let path = home.join("Library/LaunchAgents").join(format!("{}.plist", label));
+        write_service_file(&path, &launchd_plist(&label, &exe, &dir, &answers.role))?;
+        run_service_command("launchctl", &["load", "-w", &path.to_string_lossy()]);
+        println!("   Stop it with: launchctl unload -w {}", path.display());
+    } else {
+        let path = home.join(".config/systemd/user").join(format!("{}.service", name));
+        write_service_file(&path, &systemd_unit(&exe, &dir, &answers.role))?;
+        run_service_command("systemctl", &["--user", "daemon-reload"]);
+        run_service_command("systemctl", &["--user", "enable", &name]);
+        println!("   Start it now with: systemctl --user start {}", name);
+    }
+    Ok(())
+}
+
+fn write_service_file(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    std::fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+    println!("🛠️  Wrote service definition {}", path.display());
+    Ok(())
+}
+
+/// Run a service manager command, warning instead of failing, since the
+/// service file is already written and can be enabled by hand
+fn run_service_command(program: &str, args: &[&str]) {
+    let command = format!("{} {}", program, args.join(" "));
+    match std::process::Command::new(program).args(args).status() {
+        Ok(status) if status.success() => println!("✓ {}", command),
+        Ok(status) => println!("⚠️  '{}' failed ({}); run it by hand", command, status),
+        Err(e) => println!("⚠️  Couldn't run '{}': {}; run it by hand", command, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompter(input: &str) -> Prompter<&[u8], Vec<u8>> {
+        Prompter { input: input.as_bytes(), output: Vec::new(), use_defaults: false }
+    }
+
+    #[test]
+    fn test_prompter_answers() {
+        let mut p = prompter("\nvalidator\n9\n2\nmaybe\nn\nabc\n8080\n");
+        assert_eq!(p.ask("Directory", "./node").unwrap(), "./node");
+        assert_eq!(p.choose("Role?", ROLES, 0).unwrap(), 1);
+        // Out of range, then by number
+        assert_eq!(p.choose("Role?", ROLES, 0).unwrap(), 1);
+        assert!(!p.confirm("Register?", true).unwrap());
+        assert_eq!(p.ask_port("Port", None).unwrap(), Some(8080));
+        assert!(p.ask("More", "").is_err());
+    }
+
+    #[test]
+    fn test_answers_applied_to_config() {
+        let answers = Answers {
+            dir: PathBuf::from("/srv/testnet-validator"),
+            network: "testnet".to_string(),
+            bootstrappers: vec![],
+            role: "validator".to_string(),
+            keys: KeySource::Generate,
+            p2p_port: 4041,
+            status_port: Some(8080),
+            explorer_port: None,
+            autoupgrade_branch: Some("testnet".to_string()),
+            register_service: true,
+        };
+        let mut config = json!({ "id": "peer", "listeners": ["/ip4/0.0.0.0/tcp/4040/ws"] });
+        apply_answers(&mut config, &answers);
+        assert_eq!(config["run_as"], "validator");
+        assert_eq!(config["run_miner"], false);
+        assert_eq!(config["listeners"], json!(["/ip4/0.0.0.0/tcp/4041/ws"]));
+        assert_eq!(config["status_port"], 8080);
+        assert!(config.get("explorer_port").is_none());
+        assert_eq!(config["network_config_path"], "modal-networks://testnet");
+        assert_eq!(config["autoupgrade_branch"], "testnet");
+
+        let name = service_name(&answers.dir);
+        assert_eq!(name, "modal-node-testnet-validator");
+        let unit = systemd_unit(Path::new("/usr/bin/modal"), &answers.dir, "validator");
+        assert!(unit.contains("ExecStart=\"/usr/bin/modal\" node run-validator --dir \"/srv/testnet-validator\""));
+        let plist = launchd_plist("network.modality.x", Path::new("/usr/bin/modal"), &answers.dir, "validator");
+        assert!(plist.contains("<string>run-validator</string>"));
+    }
+}
@@ -1,6 +1,8 @@
 use anyhow::{Result, Context};
 use clap::Parser;
+use serde_json::json;
 use std::path::PathBuf;
+use crate::utils::output::{self, OutputFormat};
 use modal_node::config_resolution::load_config_with_node_dir;
 use modal_datastore::DatastoreManager;
 use modal_datastore::models::miner::MinerBlock;
@@ -32,6 +34,22 @@ pub struct Opts {
     /// Block index (required when command is block)
     #[clap(name = "INDEX")]
     pub block_index: Option<u64>,
+
+    /// Show recorded metrics history instead of current state
+    #[clap(long)]
+    pub history: bool,
+
+    /// Metrics series to show with --history (e.g. mining.hashrate); lists all series when omitted
+    #[clap(long, requires = "history")]
+    pub series: Option<String>,
+
+    /// Hours of history to show with --history
+    #[clap(long, default_value = "24", requires = "history")]
+    pub hours: u64,
+
+    /// Show how long each phase of the node's last startup took
+    #[clap(long, conflicts_with = "history")]
+    pub boot_profile: bool,
 }
 
 pub async fn run(opts: &Opts) -> Result<()> {
@@ -89,6 +107,11 @@ pub async fn run(opts: &Opts) -> Result<()> {
     // Check if node is running by looking for PID file and verifying process
     let is_running = check_node_running(&node_dir);
     
+    let format = output::global();
+    if format.is_structured() {
+        return inspect_structured(format, opts, command, &config, is_running).await;
+    }
+    
     // Use read-only mode to allow inspection while node is running
     if is_running {
         println!("🔍 Inspecting node (Online - Read-only mode)");
@@ -109,6 +132,14 @@ pub async fn run(opts: &Opts) -> Result<()> {
     let datastore_manager = DatastoreManager::open(data_dir)
         .context("Failed to open datastore")?;
     
+    if opts.history {
+        return inspect_history(&datastore_manager, opts.series.as_deref(), opts.hours);
+    }
+    
+    if opts.boot_profile {
+        return inspect_boot_profile(&datastore_manager);
+    }
+    
     match command {
         "general" | "blocks" => {
             inspect_blocks(&datastore_manager).await?;
@@ -130,6 +161,90 @@ pub async fn run(opts: &Opts) -> Result<()> {
     Ok(())
 }
 
+/// Print the inspection as JSON or YAML
+async fn inspect_structured(
+    format: OutputFormat,
+    opts: &Opts,
+    command: &str,
+    config: &modal_node::config::Config,
+    is_running: bool,
+) -> Result<()> {
+    let data_dir = config.data_dir.as_ref()
+        .or(config.storage_path.as_ref())
+        .context("No data_dir or storage_path in config")?;
+    let datastore_manager = DatastoreManager::open(data_dir)
+        .context("Failed to open datastore")?;
+    
+    let mut result = json!({
+        "peer_id": config.id,
+        "running": is_running,
+        "listeners": config.listeners.iter().flatten().map(|l| l.to_string()).collect::<Vec<_>>(),
+        "bootstrappers": config.bootstrappers.iter().flatten().map(|b| b.to_string()).collect::<Vec<_>>(),
+    });
+    
+    if opts.history {
+        let metrics = datastore_manager.node_metrics();
+        let now = chrono::Utc::now().timestamp();
+        let from = now - (opts.hours * 3600) as i64;
+        let series = match &opts.series {
+            Some(series) => vec![series.clone()],
+            None => metrics.series_names()?,
+        };
+        let history = series
+            .iter()
+            .map(|name| metrics.query(name, from, now, now))
+            .collect::<Result<Vec<_>, _>>()?;
+        result["history"] = serde_json::to_value(history)?;
+        return output::print_structured(format, &result);
+    }
+    
+    if opts.boot_profile {
+        result["boot_profile"] = serde_json::to_value(datastore_manager.boot_profile()?)?;
+        return output::print_structured(format, &result);
+    }
+    
+    match command {
+        "general" | "blocks" => {
+            let canonical_blocks = MinerBlock::find_all_canonical_multi(&datastore_manager).await?;
+            let orphaned_blocks = MinerBlock::find_all_orphaned_multi(&datastore_manager).await?;
+            let tip = canonical_blocks.iter().max_by_key(|b| b.index);
+            let epochs: std::collections::BTreeSet<u64> = canonical_blocks.iter().map(|b| b.epoch).collect();
+            result["blocks"] = json!({
+                "canonical": canonical_blocks.len(),
+                "orphaned": orphaned_blocks.len(),
+                "tip_index": tip.map(|b| b.index),
+                "tip_hash": tip.map(|b| b.hash.clone()),
+                "epochs": epochs.len(),
+                "first_epoch": epochs.first(),
+                "last_epoch": epochs.last(),
+            });
+        }
+        "mining" => {
+            let canonical_blocks = MinerBlock::find_all_canonical_multi(&datastore_manager).await?;
+            let latest = canonical_blocks.iter().max_by_key(|b| b.index);
+            let total_difficulty: u128 = canonical_blocks
+                .iter()
+                .filter_map(|b| b.get_target_difficulty_u128().ok())
+                .sum();
+            result["mining"] = json!({
+                "is_miner": config.run_miner.unwrap_or(false),
+                "miner_nominees": config.miner_nominees,
+                "blocks": canonical_blocks.len(),
+                "latest_block": latest,
+                "average_difficulty": total_difficulty.checked_div(canonical_blocks.len() as u128).map(|d| d.to_string()),
+            });
+        }
+        "block" => {
+            let index = opts.block_index
+                .context("block command requires an INDEX argument")?;
+            result["blocks_at_index"] = serde_json::to_value(MinerBlock::find_by_index_multi(&datastore_manager, index).await?)?;
+        }
+        _ => anyhow::bail!("Unknown inspection command: {}", command),
+    }
+    
+    output::print_structured(format, &result)
+}
+
 /// Check if the node is currently running by verifying PID file and process
 fn check_node_running(node_dir: &PathBuf) -> bool {
     // Try to read PID file
@@ -347,3 +462,89 @@ async fn inspect_block_by_index(
     Ok(())
 }
 
+fn inspect_history(datastore_manager: &DatastoreManager, series: Option<&str>, hours: u64) -> Result<()> {
+    let metrics = datastore_manager.node_metrics();
+    let now = chrono::Utc::now().timestamp();
+    let from = now - (hours * 3600) as i64;
+    
+    println!("📈 Metrics History (last {}h)", hours);
+    println!("==========================");
+    println!();
+    
+    if let Some(series) = series {
+        let history = metrics.query(series, from, now, now)?;
+        if history.points.is_empty() {
+            println!("No points recorded for {}", series);
+            return Ok(());
+        }
+        let resolution = if history.resolution_secs == 0 {
+            "as recorded".to_string()
+        } else {
+            format!("{}s buckets", history.resolution_secs)
+        };
+        println!("{} ({}, {} points)", series, resolution, history.points.len());
+        println!();
+        for point in &history.points {
+            let time = chrono::DateTime::from_timestamp(point.timestamp, 0)
+                .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_else(|| point.timestamp.to_string());
+            if point.count > 1 {
+                println!("  {}  mean {:.2}  min {:.2}  max {:.2}", time, point.mean(), point.min, point.max);
+            } else {
+                println!("  {}  {:.2}", time, point.mean());
+            }
+        }
+        return Ok(());
+    }
+    
+    let names = metrics.series_names()?;
+    if names.is_empty() {
+        println!("No metrics recorded yet");
+        return Ok(());
+    }
+    println!("{:<26} {:>7} {:>14} {:>14} {:>14}", "Series", "Points", "Min", "Max", "Latest");
+    for name in &names {
+        let history = metrics.query(name, from, now, now)?;
+        let min = history.points.iter().map(|p| p.min).fold(f64::INFINITY, f64::min);
+        let max = history.points.iter().map(|p| p.max).fold(f64::NEG_INFINITY, f64::max);
+        match history.points.last() {
+            Some(latest) => println!("{:<26} {:>7} {:>14.2} {:>14.2} {:>14.2}",
+                name, history.points.len(), min, max, latest.mean()),
+            None => println!("{:<26} {:>7} {:>14} {:>14} {:>14}", name, 0, "-", "-", "-"),
+        }
+    }
+    println!();
+    println!("Use --series <name> to show the points of one series");
+    
+    Ok(())
+}
+
+fn inspect_boot_profile(datastore_manager: &DatastoreManager) -> Result<()> {
+    println!("⏱️  Boot Profile");
+    println!("===============");
+    println!();
+    
+    let Some(profile) = datastore_manager.boot_profile()? else {
+        println!("No startup recorded yet");
+        return Ok(());
+    };
+    
+    let started = chrono::DateTime::from_timestamp(profile.started_at, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| profile.started_at.to_string());
+    println!("Started: {}", started);
+    println!();
+    println!("{:<20} {:>12} {:>8}", "Phase", "Time (ms)", "Share");
+    let share = |millis: u64| match profile.total_millis {
+        0 => 0.0,
+        total => millis as f64 * 100.0 / total as f64,
+    };
+    for phase in &profile.phases {
+        println!("{:<20} {:>12} {:>7.1}%", phase.name, phase.millis, share(phase.millis));
+    }
+    let other = profile.total_millis.saturating_sub(profile.phase_millis());
+    println!("{:<20} {:>12} {:>7.1}%", "other", other, share(other));
+    println!("{:<20} {:>12}", "total", profile.total_millis);
+    
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use anyhow::Result;
+use clap::Parser;
+use std::path::PathBuf;
+
+use modal_node::actions::miner::regtest;
+use modal_node::config_resolution::load_config_with_node_dir;
+use modal_node::node::Node;
+
+#[derive(Debug, Parser)]
+#[command(about = "Mine blocks on demand on a regtest network")]
+pub struct Opts {
+    /// Path to node configuration file
+    #[clap(long)]
+    config: Option<PathBuf>,
+
+    /// Node directory containing config.json (defaults to current directory)
+    #[clap(long)]
+    dir: Option<PathBuf>,
+
+    /// Number of blocks to mine
+    #[clap(long, default_value_t = 1)]
+    count: u64,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    // If neither config nor dir is provided, default to current directory
+    let dir = if opts.config.is_none() && opts.dir.is_none() {
+        Some(std::env::current_dir()?)
+    } else {
+        opts.dir.clone()
+    };
+    
+    let config = load_config_with_node_dir(opts.config.clone(), dir)?;
+    let node = Node::from_config(config).await?;
+    
+    let blocks = regtest::mine_blocks(&node, opts.count).await?;
+    for block in &blocks {
+        println!("⛏️  Block {}: {}", block.header.index, block.header.hash);
+    }
+    println!("✅  Mined {} block(s)", blocks.len());
+    
+    Ok(())
+}
@@ -4,24 +4,35 @@
 //! Modality network nodes including miners, observers, and validators.
 
 pub mod address;
+pub mod archive;
+pub mod backup;
+pub mod ban;
+pub mod bans;
+pub mod bench_miner;
 pub mod clear;
 pub mod clear_storage;
 pub mod compare;
 pub mod config;
 pub mod create;
+pub mod duties;
+pub mod fsck_storage;
 pub mod info;
+pub mod init;
 pub mod inspect;
 pub mod kill;
 pub mod logs;
+pub mod mine_blocks;
 pub mod pid;
 pub mod ping;
 pub mod restart;
+pub mod restore;
 pub mod run;
 pub mod run_miner;
 pub mod run_noop;
 pub mod run_observer;
 pub mod run_validator;
 pub mod runner;
+pub mod set_role;
 pub mod start;
 pub mod stats;
 pub mod stop;
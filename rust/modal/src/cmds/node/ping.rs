@@ -1,14 +1,18 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
+use serde::Serialize;
 use std::path::PathBuf;
 
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use modal_networks::registry;
 use modal_node::actions;
 use modal_node::node::Node;
 use modal_node::config_resolution::load_config_with_node_dir;
 use modal_node::logging;
 use rand::Rng;
 
+use crate::utils::output;
+
 #[derive(Debug, Parser)]
 #[command(about = "Ping a Modality Network node")]
 pub struct Opts {
@@ -20,66 +24,218 @@ pub struct Opts {
     #[clap(long)]
     dir: Option<PathBuf>,
 
-    #[clap(long)]
-    target: String,
+    #[clap(long, required_unless_present = "all_bootstrappers")]
+    target: Option<String>,
+
+    /// Number of pings to send to each target
+    #[clap(long, alias = "times", default_value = "1")]
+    count: u32,
+
+    /// Milliseconds to wait between pings
+    #[clap(long, default_value = "1000")]
+    interval: u64,
+
+    /// Ping every bootstrapper of the network and compare them
+    #[clap(long, conflicts_with = "target")]
+    all_bootstrappers: bool,
+
+    /// Network whose bootstrappers to ping (defaults to the node's bootstrappers)
+    #[clap(long, requires = "all_bootstrappers")]
+    network: Option<String>,
+}
 
-    #[clap(long, default_value = "1")]
-    times: u32,
+/// Latency and loss over a series of pings
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PingStats {
+    pub target: String,
+    pub sent: u32,
+    pub received: u32,
+    pub loss_percent: f64,
+    pub min_ms: Option<f64>,
+    pub avg_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+}
+
+impl PingStats {
+    /// Summarize pings, where `None` is a ping that got no reply
+    pub fn from_samples(target: &str, samples: &[Option<Duration>]) -> Self {
+        let mut latencies: Vec<f64> = samples
+            .iter()
+            .flatten()
+            .map(|d| d.as_secs_f64() * 1000.0)
+            .collect();
+        latencies.sort_by(|a, b| a.total_cmp(b));
+
+        let sent = samples.len() as u32;
+        let received = latencies.len() as u32;
+        let loss_percent = if sent == 0 {
+            0.0
+        } else {
+            (sent - received) as f64 * 100.0 / sent as f64
+        };
+        // Nearest-rank percentile
+        let p95_ms = if latencies.is_empty() {
+            None
+        } else {
+            let rank = (latencies.len() as f64 * 0.95).ceil() as usize;
+            Some(latencies[rank.saturating_sub(1)])
+        };
+
+        Self {
+            target: target.to_string(),
+            sent,
+            received,
+            loss_percent,
+            min_ms: latencies.first().copied(),
+            avg_ms: (!latencies.is_empty())
+                .then(|| latencies.iter().sum::<f64>() / latencies.len() as f64),
+            p95_ms,
+        }
+    }
 }
 
 pub async fn run(opts: &Opts) -> Result<()> {
     // Initialize console logging for ping output
     // Use None for log_level to allow RUST_LOG env var to control verbosity
     logging::init_logging(None, Some(false), None)?;
-    
+
     // If neither config nor dir is provided, default to current directory
     let dir = if opts.config.is_none() && opts.dir.is_none() {
         Some(std::env::current_dir()?)
     } else {
         opts.dir.clone()
     };
-    
+
     let config = load_config_with_node_dir(opts.config.clone(), dir)?;
-    
-    let times_to_ping = opts.times;
+
+    let targets = if opts.all_bootstrappers {
+        let bootstrappers = if let Some(name) = &opts.network {
+            registry::find(name)?
+                .with_context(|| format!("Network '{}' not found", name))?
+                .bootstrappers
+        } else {
+            config
+                .bootstrappers
+                .clone()
+                .unwrap_or_default()
+                .iter()
+                .map(|addr| addr.to_string())
+                .collect()
+        };
+        if bootstrappers.is_empty() {
+            anyhow::bail!("No bootstrappers to ping. Use --network to pick a network.");
+        }
+        bootstrappers
+    } else {
+        vec![opts.target.clone().expect("clap requires --target")]
+    };
+
     let mut node = Node::from_config(config.clone()).await?;
     log::info!("Pinging from node: {:?}", node.peerid);
     node.setup(&config).await?;
-    let target = opts.target.clone();
 
-    let start = Instant::now();
+    let mut results = Vec::new();
+    for target in &targets {
+        results.push(ping_target(&mut node, target, opts).await);
+    }
+
+    let format = output::global();
+    if format.is_structured() {
+        if opts.all_bootstrappers {
+            output::print_structured(format, &results)?;
+        } else {
+            output::print_structured(format, &results[0])?;
+        }
+    } else if opts.all_bootstrappers {
+        print_comparison(&results);
+    } else {
+        print_stats(&results[0]);
+    }
+
+    if results.iter().all(|stats| stats.received == 0) {
+        anyhow::bail!("No replies received");
+    }
 
+    Ok(())
+}
+
+async fn ping_target(node: &mut Node, target: &str, opts: &Opts) -> PingStats {
     let random_hex = generate_random_hex_string();
 
-    log::info!("Pinging {} {} time(s)...", target, times_to_ping);
-    
-    for i in 0..times_to_ping {
+    log::info!("Pinging {} {} time(s)...", target, opts.count);
+
+    let mut samples = Vec::new();
+    for i in 0..opts.count {
+        if i > 0 {
+            tokio::time::sleep(Duration::from_millis(opts.interval)).await;
+        }
+
         let path = String::from("/ping");
         let data = serde_json::json!({
             "random": random_hex
         }).to_string();
-        
+
         let ping_start = Instant::now();
-        match actions::request::run(&mut node, target.clone(), path, data).await {
+        match actions::request::run(node, target.to_string(), path, data).await {
             Ok(_) => {
                 let ping_duration = ping_start.elapsed();
                 log::info!("Ping {} successful: {:?}", i + 1, ping_duration);
+                samples.push(Some(ping_duration));
             }
             Err(e) => {
-                log::error!("Ping {} failed: {}", i + 1, e);
-                return Err(e);
+                log::warn!("Ping {} failed: {}", i + 1, e);
+                samples.push(None);
             }
         }
     }
-    
-    let duration = start.elapsed();
-    log::info!("");
-    log::info!("--- Ping Statistics ---");
-    log::info!("Total pings: {}", times_to_ping);
-    log::info!("Total time: {:?}", duration);
-    log::info!("Average time: {:?}", duration / times_to_ping);
 
-    Ok(())
+    PingStats::from_samples(target, &samples)
+}
+
+fn format_ms(ms: Option<f64>) -> String {
+    ms.map(|ms| format!("{:.1}ms", ms)).unwrap_or_else(|| "-".to_string())
+}
+
+fn print_stats(stats: &PingStats) {
+    println!();
+    println!("--- {} ping statistics ---", stats.target);
+    println!(
+        "{} sent, {} received, {:.1}% loss",
+        stats.sent, stats.received, stats.loss_percent
+    );
+    if stats.received > 0 {
+        println!(
+            "min {}, avg {}, p95 {}",
+            format_ms(stats.min_ms),
+            format_ms(stats.avg_ms),
+            format_ms(stats.p95_ms)
+        );
+    }
+}
+
+/// Table of every bootstrapper, fastest first and unreachable ones last
+fn print_comparison(results: &[PingStats]) {
+    let mut ranked: Vec<&PingStats> = results.iter().collect();
+    ranked.sort_by(|a, b| match (a.avg_ms, b.avg_ms) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+
+    println!();
+    println!("{:>6}  {:>10}  {:>10}  {:>10}  Bootstrapper", "Loss", "Min", "Avg", "P95");
+    println!("─────────────────────────────────────────────────────────────────────");
+    for stats in ranked {
+        println!(
+            "{:>5.1}%  {:>10}  {:>10}  {:>10}  {}",
+            stats.loss_percent,
+            format_ms(stats.min_ms),
+            format_ms(stats.avg_ms),
+            format_ms(stats.p95_ms),
+            stats.target
+        );
+    }
 }
 
 fn generate_random_hex_string() -> String {
@@ -92,3 +248,34 @@ fn generate_random_hex_string() -> String {
         .collect::<String>()
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ping_stats() {
+        let mut samples: Vec<Option<Duration>> = (1..=19).map(|ms| Some(Duration::from_millis(ms))).collect();
+        samples.push(Some(Duration::from_millis(100)));
+        samples.push(None);
+        samples.push(None);
+
+        let stats = PingStats::from_samples("peer", &samples);
+        assert_eq!(stats.sent, 22);
+        assert_eq!(stats.received, 20);
+        assert!((stats.loss_percent - 9.09).abs() < 0.01);
+        assert_eq!(stats.min_ms, Some(1.0));
+        assert_eq!(stats.avg_ms, Some(14.5));
+        // 19th of 20 sorted samples
+        assert_eq!(stats.p95_ms, Some(19.0));
+    }
+
+    #[test]
+    fn test_ping_stats_all_lost() {
+        let stats = PingStats::from_samples("peer", &[None, None]);
+        assert_eq!(stats.received, 0);
+        assert_eq!(stats.loss_percent, 100.0);
+        assert_eq!(stats.min_ms, None);
+        assert_eq!(stats.avg_ms, None);
+        assert_eq!(stats.p95_ms, None);
+    }
+}
@@ -0,0 +1,79 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::path::PathBuf;
+
+use modal_datastore::DatastoreManager;
+use modal_node::config_resolution::load_config_with_node_dir;
+
+#[derive(Debug, Parser)]
+#[command(about = "Restore node storage from a backup archive")]
+pub struct Opts {
+    /// Path to node configuration file
+    #[clap(long)]
+    pub config: Option<PathBuf>,
+
+    /// Node directory containing config.json (defaults to current directory)
+    #[clap(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Archive written by `modal node backup`
+    #[clap(long, short)]
+    pub input: PathBuf,
+
+    /// Move existing storage aside instead of refusing to restore over it
+    #[clap(long)]
+    pub force: bool,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    // If neither config nor dir is provided, default to current directory
+    let dir = if opts.config.is_none() && opts.dir.is_none() {
+        Some(std::env::current_dir()?)
+    } else {
+        opts.dir.clone()
+    };
+
+    let config = load_config_with_node_dir(opts.config.clone(), dir.clone())?;
+
+    let data_dir = config.data_dir.as_ref()
+        .or(config.storage_path.as_ref())
+        .context("No data_dir or storage_path in config")?
+        .clone();
+
+    let in_use = data_dir.exists() && data_dir.read_dir()?.next().is_some();
+    if in_use {
+        if !opts.force {
+            anyhow::bail!(
+                "Storage already exists at {} (use --force to move it aside)",
+                data_dir.display()
+            );
+        }
+        let aside = PathBuf::from(format!(
+            "{}.before-restore-{}",
+            data_dir.display(),
+            chrono::Utc::now().format("%Y%m%d%H%M%S")
+        ));
+        std::fs::rename(&data_dir, &aside)
+            .with_context(|| format!("Failed to move {} aside", data_dir.display()))?;
+        println!("📦 Moved existing storage to {}", aside.display());
+    }
+
+    println!("♻️  Restoring {} into {}...", opts.input.display(), data_dir.display());
+    let file = std::fs::File::open(&opts.input)
+        .with_context(|| format!("Failed to open {}", opts.input.display()))?;
+    let storage = config.storage.clone().unwrap_or_default();
+    let (_, manifest) = DatastoreManager::restore_with_config(&data_dir, &storage, std::io::BufReader::new(file))
+        .await
+        .context("Restore failed")?;
+
+    for store in &manifest.stores {
+        println!("  {}: {} entries verified", store.name, store.entries);
+    }
+    if let Some(tip) = &manifest.miner_tip {
+        println!("  Miner tip: #{} {}", tip.index, tip.hash);
+    }
+    println!("  Validator round: {}", manifest.current_round);
+    println!("✅ Restore complete");
+
+    Ok(())
+}
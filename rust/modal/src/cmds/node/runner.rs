@@ -1,7 +1,7 @@
 //! Shared node runner functionality.
 //!
 //! This module provides common patterns for running different types of nodes
-//! (miner, observer, validator, noop) with consistent setup, logging, and cleanup.
+//! (miner, observer, validator, gateway, indexer, noop) with consistent setup, logging, and cleanup.
 
 use anyhow::Result;
 use clap::Args;
@@ -26,6 +26,11 @@ pub struct CommonNodeOpts {
     /// Node directory containing config.json (defaults to current directory)
     #[clap(long)]
     pub dir: Option<PathBuf>,
+
+    /// Check every canonical block's linkage at startup, not just the blocks
+    /// added since the last check
+    #[clap(long)]
+    pub full: bool,
 }
 
 impl CommonNodeOpts {
@@ -51,6 +56,10 @@ pub enum NodeRole {
     Observer,
     /// Validator node that validates blocks
     Validator,
+    /// Observer node that also serves read-only RPC to the public
+    Gateway,
+    /// Observer node that also indexes contracts and answers index queries
+    Indexer,
     /// Noop node that only handles autoupgrade
     Noop,
     /// Server mode - determined by config
@@ -64,6 +73,8 @@ impl NodeRole {
             NodeRole::Miner => "mining node",
             NodeRole::Observer => "observer node",
             NodeRole::Validator => "validator node",
+            NodeRole::Gateway => "gateway node",
+            NodeRole::Indexer => "indexer node",
             NodeRole::Noop => "noop node",
             NodeRole::Server => "server node",
         }
@@ -86,7 +97,10 @@ impl NodeRole {
 /// * `manage_pid` - Whether to create and manage a PID file
 pub async fn run_node(opts: &CommonNodeOpts, role: NodeRole, manage_pid: bool) -> Result<()> {
     let dir = opts.resolve_dir()?;
-    let config = load_config_with_node_dir(opts.config.clone(), dir.clone())?;
+    let mut config = load_config_with_node_dir(opts.config.clone(), dir.clone())?;
+    if opts.full {
+        config.full_integrity_check = Some(true);
+    }
 
     // Initialize logging
     logging::init_logging(
@@ -116,6 +130,8 @@ pub async fn run_node(opts: &CommonNodeOpts, role: NodeRole, manage_pid: bool) -
         NodeRole::Miner => actions::miner::run(&mut node).await?,
         NodeRole::Observer => actions::observer::run(&mut node).await?,
         NodeRole::Validator => actions::validator::run(&mut node).await?,
+        NodeRole::Gateway => actions::gateway::run(&mut node).await?,
+        NodeRole::Indexer => actions::indexer::run(&mut node).await?,
         NodeRole::Noop => actions::noop::run(&mut node).await?,
         NodeRole::Server => {
             if config.run_miner.unwrap_or(false) {
@@ -157,14 +173,20 @@ pub async fn run_noop(opts: &CommonNodeOpts) -> Result<()> {
 pub async fn run_server(opts: &CommonNodeOpts) -> Result<()> {
     let dir = opts.resolve_dir()?;
     let config = load_config_with_node_dir(opts.config.clone(), dir.clone())?;
-    
+
+    if config.networks.is_some() {
+        return run_multi_network(config, dir).await;
+    }
+
     // Determine role from config.run_as, falling back to run_miner logic
     let role = match config.run_as.as_deref() {
         Some("miner") => NodeRole::Miner,
         Some("observer") => NodeRole::Observer,
         Some("validator") => NodeRole::Validator,
+        Some("gateway") => NodeRole::Gateway,
+        Some("indexer") => NodeRole::Indexer,
         Some("noop") => NodeRole::Noop,
-        Some(unknown) => anyhow::bail!("Unknown run_as value in config: '{}'. Valid values: miner, observer, validator, noop", unknown),
+        Some(unknown) => anyhow::bail!("Unknown run_as value in config: '{}'. Valid values: miner, observer, validator, gateway, indexer, noop", unknown),
         None => {
             // Fall back to legacy run_miner behavior
             if config.run_miner.unwrap_or(false) {
@@ -178,3 +200,18 @@ pub async fn run_server(opts: &CommonNodeOpts) -> Result<()> {
     run_node(opts, role, true).await
 }
 
+/// Run one node per entry in `config.networks` from this process.
+async fn run_multi_network(config: Config, dir: Option<PathBuf>) -> Result<()> {
+    logging::init_logging(
+        config.logs_path.clone(),
+        config.logs_enabled,
+        config.log_level.clone(),
+    )?;
+
+    let pid_dir = dir.unwrap_or_else(|| {
+        std::env::current_dir().expect("Failed to get current directory")
+    });
+    let _pid_guard = PidGuard::new(&pid_dir)?;
+
+    modal_node::multi_network::run(&config).await
+}
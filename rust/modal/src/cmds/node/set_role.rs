@@ -0,0 +1,51 @@
+use anyhow::Result;
+use clap::Parser;
+use std::path::PathBuf;
+
+use modal_node::actions::roles::{self, Role};
+use modal_node::config_resolution::load_config_with_node_dir;
+use modal_node::logging;
+use modal_node::node::Node;
+
+#[derive(Debug, Parser)]
+#[command(about = "Switch a running node between the observer, miner and validator roles")]
+pub struct Opts {
+    /// Role to switch to: observer, miner or validator
+    role: Role,
+
+    /// Address of the node to switch, ending in /p2p/<peer id>
+    #[clap(long)]
+    target: String,
+
+    /// Path to the configuration file of the node to send the request as,
+    /// which must be in the target's admin_peers
+    #[clap(long)]
+    config: Option<PathBuf>,
+
+    /// Node directory containing config.json (defaults to current directory)
+    #[clap(long)]
+    dir: Option<PathBuf>,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    logging::init_logging(None, Some(false), None)?;
+
+    // If neither config nor dir is provided, default to current directory
+    let dir = if opts.config.is_none() && opts.dir.is_none() {
+        Some(std::env::current_dir()?)
+    } else {
+        opts.dir.clone()
+    };
+
+    let config = load_config_with_node_dir(opts.config.clone(), dir)?;
+    let mut node = Node::from_config(config.clone()).await?;
+    node.setup(&config).await?;
+
+    let response = roles::request_role(&mut node, opts.target.clone(), opts.role).await?;
+    if !response.ok {
+        anyhow::bail!("Role change refused: {}", response.errors.unwrap_or_default());
+    }
+    println!("🔀 {} is switching to the {} role", opts.target, opts.role);
+
+    Ok(())
+}
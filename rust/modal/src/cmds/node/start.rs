@@ -89,7 +89,8 @@ pub async fn run(opts: &Opts) -> Result<()> {
         "miner" => "run-miner",
         "observer" => "run-observer",
         "validator" => "run-validator",
-        "server" => "run",
+        // Gateways and indexers are only configured through run_as, which `run` follows
+        "server" | "gateway" | "indexer" => "run",
         _ => bail!("Unknown node type: {}", node_type),
     };
 
@@ -1,6 +1,9 @@
 use anyhow::Result;
 use clap::Args;
 use colored::Colorize;
+use serde_json::json;
+
+use crate::utils::output;
 
 #[derive(Args, Debug)]
 pub struct Opts {
@@ -9,21 +12,44 @@ pub struct Opts {
     contract_id: String,
 }
 
+/// Standard network predicates: name, path, description, arguments, gas usage
+const STANDARD_PREDICATES: &[(&str, &str, &str, &str, &str)] = &[
+    ("signed_by", "/_code/modal/signed_by.wasm", "Verify cryptographic signatures", "{ message, signature, public_key }", "100-200"),
+    ("amount_in_range", "/_code/modal/amount_in_range.wasm", "Check numeric bounds", "{ amount, min, max }", "20-30"),
+    ("has_property", "/_code/modal/has_property.wasm", "Check JSON property existence", "{ path, required }", "30-50"),
+    ("timestamp_valid", "/_code/modal/timestamp_valid.wasm", "Validate timestamp constraints", "{ timestamp, max_age_seconds? }", "25-35"),
+    ("post_to_path", "/_code/modal/post_to_path.wasm", "Verify commit actions", "{ path }", "40-100"),
+];
+
 pub async fn run(opts: &Opts) -> Result<()> {
+    let format = output::global();
+    if format.is_structured() {
+        if opts.contract_id != "modal.money" {
+            anyhow::bail!("Listing predicates of custom contracts requires datastore access");
+        }
+        let predicates: Vec<_> = STANDARD_PREDICATES
+            .iter()
+            .map(|(name, path, description, args, gas)| json!({
+                "name": name,
+                "path": path,
+                "description": description,
+                "arguments": args,
+                "gas": gas,
+            }))
+            .collect();
+        return output::print_structured(format, &json!({
+            "contract_id": opts.contract_id,
+            "predicates": predicates,
+        }));
+    }
+
     println!("\n📋 Predicates in contract: {}\n", opts.contract_id.cyan());
 
     if opts.contract_id == "modal.money" {
         println!("{}", "Standard Network Predicates:".bold());
         println!("{}", "━".repeat(80));
 
-        let predicates = vec![
-            ("signed_by", "/_code/modal/signed_by.wasm", "Verify cryptographic signatures", "{ message, signature, public_key }", "100-200"),
-            ("amount_in_range", "/_code/modal/amount_in_range.wasm", "Check numeric bounds", "{ amount, min, max }", "20-30"),
-            ("has_property", "/_code/modal/has_property.wasm", "Check JSON property existence", "{ path, required }", "30-50"),
-            ("timestamp_valid", "/_code/modal/timestamp_valid.wasm", "Validate timestamp constraints", "{ timestamp, max_age_seconds? }", "25-35"),
-            ("post_to_path", "/_code/modal/post_to_path.wasm", "Verify commit actions", "{ path }", "40-100"),
-        ];
-
+        let predicates = STANDARD_PREDICATES;
         let count = predicates.len();
         for (name, path, description, args, gas) in predicates {
             println!("\n  {}", name.green().bold());
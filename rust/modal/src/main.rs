@@ -1,9 +1,8 @@
 use modal::cmds;
-#[allow(unused_imports)]
 use modal::utils;
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
 
 const VERSION: &str = concat!(
     env!("CARGO_PKG_VERSION"),
@@ -24,8 +23,16 @@ struct Cli {
     #[arg(short = 'v', long = "version", action = clap::ArgAction::Version)]
     version: Option<bool>,
 
+    /// Print the version, git commit and protocol features of this build
+    #[arg(long)]
+    build_info: bool,
+
+    /// Output format for info, status, inspect and list commands (given before the command)
+    #[arg(long, value_enum, env = "MODAL_OUTPUT")]
+    output: Option<utils::OutputFormat>,
+
     #[command(subcommand)]
-    command: Commands,
+    command: Option<Commands>,
 }
 
 #[derive(Subcommand)]
@@ -55,6 +62,12 @@ enum Commands {
         command: LocalCommands,
     },
 
+    #[command(about = "Local multi-node devnet commands")]
+    Devnet {
+        #[command(subcommand)]
+        command: DevnetCommands,
+    },
+
     #[command(alias = "network")]
     #[command(about = "Network related commands")]
     Net {
@@ -98,6 +111,12 @@ enum Commands {
 
     #[command(about = "Download a packed contract file")]
     Download(cmds::contract::download::Opts),
+    
+    #[command(about = "Export a contract's state as of a finalized checkpoint, for offline verification")]
+    Export(cmds::contract::export::Opts),
+    
+    #[command(name = "verify-export", about = "Verify a contract export offline")]
+    VerifyExport(cmds::contract::verify_export::Opts),
 
     #[command(about = "Run node shortcuts")]
     Run {
@@ -128,6 +147,12 @@ enum Commands {
 
     #[command(about = "Upgrade modal to the latest version")]
     Upgrade(modality::cmds::upgrade::Opts),
+
+    #[command(about = "Print a shell completion script")]
+    Completions {
+        /// Shell to generate completions for
+        shell: clap_complete::Shell,
+    },
 }
 
 #[derive(Subcommand)]
@@ -136,12 +161,18 @@ enum IdCommands {
     Derive(modality::cmds::id::derive::Opts),
     #[command(about = "Get ID from passfile by name or path")]
     Get(modality::cmds::id::get::Opts),
+    Hardware(modality::cmds::id::hardware::Opts),
+    Publish(modality::cmds::id::publish::Opts),
+    Rotate(modality::cmds::id::rotate::Opts),
 }
 
 #[derive(Subcommand)]
 enum PassfileCommands {
     Decrypt(modality::cmds::passfile::decrypt::Opts),
     Encrypt(modality::cmds::passfile::encrypt::Opts),
+    Rotate(modality::cmds::passfile::rotate::Opts),
+    Add(modality::cmds::passfile::add::Opts),
+    List(modality::cmds::passfile::list::Opts),
 }
 
 #[derive(Subcommand)]
@@ -152,11 +183,38 @@ enum NetworkCommands {
     #[command(about = "Inspect network datastore and show statistics")]
     Storage(cmds::net::storage::Opts),
 
+    #[command(about = "Poll known peers' status pages and show an aggregate network view")]
+    Dashboard(cmds::net::dashboard::Opts),
+
+    #[command(about = "Install networks from a signed network registry")]
+    RegistrySync(cmds::net::registry_sync::Opts),
+
     #[command(about = "Mining related commands")]
     Mining {
         #[command(subcommand)]
         command: MiningCommands,
     },
+
+    #[command(about = "Epoch reports")]
+    Epochs {
+        #[command(subcommand)]
+        command: EpochsCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum DevnetCommands {
+    #[command(about = "Start a local multi-node devnet")]
+    Up(cmds::devnet::up::Opts),
+
+    #[command(about = "Show the nodes of a local devnet")]
+    Status(cmds::devnet::status::Opts),
+
+    #[command(about = "Show the logs of a devnet node")]
+    Logs(cmds::devnet::logs::Opts),
+
+    #[command(about = "Stop the nodes of a local devnet")]
+    Down(cmds::devnet::down::Opts),
 }
 
 #[derive(Subcommand)]
@@ -176,9 +234,18 @@ enum NodeCommands {
     #[command(about = "Create a new node directory with config.json and node.modal_passfile")]
     Create(cmds::node::create::Opts),
 
+    #[command(about = "Set up a new node interactively")]
+    Init(cmds::node::init::Opts),
+
     #[command(about = "Display information about a node")]
     Info(cmds::node::info::Opts),
 
+    #[command(about = "Ban an IP address or subnet from connecting to a node")]
+    Ban(cmds::node::ban::Opts),
+
+    #[command(about = "List a node's IP bans or export them as nftables/iptables rules")]
+    Bans(cmds::node::bans::Opts),
+
     #[command(about = "Inspect a node's state (running or offline)")]
     Inspect(cmds::node::inspect::Opts),
 
@@ -233,8 +300,32 @@ enum NodeCommands {
     #[command(about = "Clear all values from node storage")]
     ClearStorage(cmds::node::clear_storage::Opts),
 
+    #[command(about = "Back up node storage to a compressed archive")]
+    Backup(cmds::node::backup::Opts),
+
+    #[command(about = "Restore node storage from a backup archive")]
+    Restore(cmds::node::restore::Opts),
+
+    #[command(about = "Check node storage for corrupt or truncated records")]
+    FsckStorage(cmds::node::fsck_storage::Opts),
+
+    #[command(about = "Export finalized epochs to a cold storage archive")]
+    Archive(cmds::node::archive::Opts),
+
+    #[command(about = "Mine blocks on demand on a regtest network")]
+    MineBlocks(cmds::node::mine_blocks::Opts),
+
+    #[command(about = "Measure local mining hashrate across hash functions and thread counts")]
+    BenchMiner(cmds::node::bench_miner::Opts),
+
     #[command(about = "Display summary statistics from recent blocks")]
     Stats(cmds::node::stats::Opts),
+
+    #[command(about = "Predict which upcoming epochs this node validates")]
+    Duties(cmds::node::duties::Opts),
+
+    #[command(about = "Switch a running node between the observer, miner and validator roles")]
+    SetRole(cmds::node::set_role::Opts),
 }
 
 #[derive(Subcommand)]
@@ -243,6 +334,15 @@ enum MiningCommands {
     Sync(cmds::net::mining::sync::Opts),
 }
 
+#[derive(Subcommand)]
+enum EpochsCommands {
+    #[command(about = "Show per-peer blocks, nominations, uncles and validator participation for an epoch")]
+    Report(cmds::net::epochs::report::Opts),
+
+    #[command(name = "verify-shuffle", about = "Recompute an epoch's nominee shuffle and check the validators seated from it")]
+    VerifyShuffle(cmds::net::epochs::verify_shuffle::Opts),
+}
+
 #[derive(Subcommand)]
 enum ContractCommands {
     #[command(about = "Create a new contract")]
@@ -263,6 +363,9 @@ enum ContractCommands {
     #[command(about = "Get the contract ID from the current directory")]
     Id(cmds::contract::id::Opts),
     
+    #[command(about = "Sign a commit made with --offline")]
+    Sign(cmds::contract::sign::Opts),
+    
     #[command(about = "Push commits to chain validators")]
     Push(cmds::contract::push::Opts),
     
@@ -302,14 +405,23 @@ enum ContractCommands {
     #[command(name = "add-rule", about = "Add a rule to the contract")]
     AddRule(cmds::contract::add_rule::Opts),
     
+    #[command(name = "analyze-rules", about = "Report what a contract's rules leave unconstrained, and rules that are vacuous or overlap")]
+    AnalyzeRules(cmds::contract::analyze_rules::Opts),
+    
     #[command(about = "Download a packed contract file")]
     Download(cmds::contract::download::Opts),
+    
+    #[command(about = "Stream a contract's commits, state changes and rule verdicts")]
+    Watch(cmds::contract::watch::Opts),
 }
 
 #[derive(Subcommand)]
 enum HubCommands {
     #[command(about = "Start a contract hub server")]
     Start(cmds::hub::start::Opts),
+
+    #[command(about = "Serve contracts over modal-rpc only (no mining or consensus)")]
+    Serve(cmds::hub::serve::Opts),
 }
 
 #[derive(Subcommand)]
@@ -366,25 +478,44 @@ enum ChainCommands {
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
-    match &cli.command {
+    if let Some(format) = cli.output {
+        utils::output::set_global(format);
+    }
+    if cli.build_info {
+        return utils::build_info::print();
+    }
+    let Some(command) = &cli.command else {
+        Cli::command().print_help()?;
+        return Ok(());
+    };
+    match command {
         Commands::Id { command } => {
             match command {
                 IdCommands::Create(opts) => modality::cmds::id::create::run(opts).await?,
                 IdCommands::Derive(opts) => modality::cmds::id::derive::run(opts).await?,
                 IdCommands::Get(opts) => modality::cmds::id::get::run(opts).await?,
+                IdCommands::Hardware(opts) => modality::cmds::id::hardware::run(opts).await?,
+                IdCommands::Publish(opts) => modality::cmds::id::publish::run(opts).await?,
+                IdCommands::Rotate(opts) => modality::cmds::id::rotate::run(opts).await?,
             }
         }
         Commands::Passfile { command } => {
             match command {
                 PassfileCommands::Decrypt(opts) => modality::cmds::passfile::decrypt::run(opts).await?,
                 PassfileCommands::Encrypt(opts) => modality::cmds::passfile::encrypt::run(opts).await?,
+                PassfileCommands::Rotate(opts) => modality::cmds::passfile::rotate::run(opts).await?,
+                PassfileCommands::Add(opts) => modality::cmds::passfile::add::run(opts).await?,
+                PassfileCommands::List(opts) => modality::cmds::passfile::list::run(opts).await?,
             }
         }
         Commands::Node { command } => {
             match command {
                 NodeCommands::Address(opts) => cmds::node::address::run(opts).await?,
                 NodeCommands::Create(opts) => cmds::node::create::run(opts).await?,
+                NodeCommands::Init(opts) => cmds::node::init::run(opts).await?,
                 NodeCommands::Info(opts) => cmds::node::info::run(opts).await?,
+                NodeCommands::Ban(opts) => cmds::node::ban::run(opts).await?,
+                NodeCommands::Bans(opts) => cmds::node::bans::run(opts).await?,
                 NodeCommands::Inspect(opts) => cmds::node::inspect::run(opts).await?,
                 NodeCommands::Compare(opts) => cmds::node::compare::run(opts).await?,
                 NodeCommands::Config(opts) => cmds::node::config::run(opts).await?,
@@ -403,7 +534,15 @@ async fn main() -> Result<()> {
                 NodeCommands::Sync(opts) => cmds::node::sync::run(opts).await?,
                 NodeCommands::Clear(opts) => cmds::node::clear::run(opts).await?,
                 NodeCommands::ClearStorage(opts) => cmds::node::clear_storage::run(opts).await?,
+                NodeCommands::Backup(opts) => cmds::node::backup::run(opts).await?,
+                NodeCommands::Restore(opts) => cmds::node::restore::run(opts).await?,
+                NodeCommands::FsckStorage(opts) => cmds::node::fsck_storage::run(opts).await?,
+                NodeCommands::Archive(opts) => cmds::node::archive::run(opts).await?,
+                NodeCommands::MineBlocks(opts) => cmds::node::mine_blocks::run(opts).await?,
+                NodeCommands::BenchMiner(opts) => cmds::node::bench_miner::run(opts).await?,
                 NodeCommands::Stats(opts) => cmds::node::stats::run(opts).await?,
+                NodeCommands::Duties(opts) => cmds::node::duties::run(opts).await?,
+                NodeCommands::SetRole(opts) => cmds::node::set_role::run(opts).await?,
             }
         }
         Commands::Local { command } => {
@@ -412,15 +551,31 @@ async fn main() -> Result<()> {
                 LocalCommands::KillallNodes(opts) => cmds::local::killall_nodes::run(opts).await?,
             }
         }
+        Commands::Devnet { command } => {
+            match command {
+                DevnetCommands::Up(opts) => cmds::devnet::up::run(opts).await?,
+                DevnetCommands::Status(opts) => cmds::devnet::status::run(opts).await?,
+                DevnetCommands::Logs(opts) => cmds::devnet::logs::run(opts).await?,
+                DevnetCommands::Down(opts) => cmds::devnet::down::run(opts).await?,
+            }
+        }
         Commands::Net { command } => {
             match command {
                 NetworkCommands::Info(opts) => cmds::net::info::run(opts).await?,
                 NetworkCommands::Storage(opts) => cmds::net::storage::run(opts).await?,
+                NetworkCommands::Dashboard(opts) => cmds::net::dashboard::run(opts).await?,
+                NetworkCommands::RegistrySync(opts) => cmds::net::registry_sync::run(opts).await?,
                 NetworkCommands::Mining { command } => {
                     match command {
                         MiningCommands::Sync(opts) => cmds::net::mining::sync::run(opts).await?,
                     }
                 }
+                NetworkCommands::Epochs { command } => {
+                    match command {
+                        EpochsCommands::Report(opts) => cmds::net::epochs::report::run(opts).await?,
+                        EpochsCommands::VerifyShuffle(opts) => cmds::net::epochs::verify_shuffle::run(opts).await?,
+                    }
+                }
             }
         }
         Commands::Contract { command } => {
@@ -431,6 +586,7 @@ async fn main() -> Result<()> {
                 ContractCommands::Diff(opts) => cmds::contract::diff::run(opts).await?,
                 ContractCommands::CommitId(opts) => cmds::contract::commit_id::run(opts).await?,
                 ContractCommands::Id(opts) => cmds::contract::id::run(opts).await?,
+                ContractCommands::Sign(opts) => cmds::contract::sign::run(opts).await?,
                 ContractCommands::Push(opts) => cmds::contract::push::run(opts).await?,
                 ContractCommands::Pull(opts) => cmds::contract::pull::run(opts).await?,
                 ContractCommands::Status(opts) => cmds::contract::status::run(opts).await?,
@@ -444,12 +600,17 @@ async fn main() -> Result<()> {
                 ContractCommands::Unpack(opts) => cmds::contract::unpack::run(opts).await?,
                 ContractCommands::Repost(opts) => cmds::contract::repost::run(opts).await?,
                 ContractCommands::AddRule(opts) => cmds::contract::add_rule::run(opts).await?,
+                ContractCommands::AnalyzeRules(opts) => cmds::contract::analyze_rules::run(opts).await?,
+                ContractCommands::Export(opts) => cmds::contract::export::run(opts).await?,
+                ContractCommands::VerifyExport(opts) => cmds::contract::verify_export::run(opts).await?,
                 ContractCommands::Download(opts) => cmds::contract::download::run(opts).await?,
+                ContractCommands::Watch(opts) => cmds::contract::watch::run(opts).await?,
             }
         }
         Commands::Hub { command } => {
             match command {
                 HubCommands::Start(opts) => cmds::hub::start::run(opts).await?,
+                HubCommands::Serve(opts) => cmds::hub::serve::run(opts).await?,
             }
         }
         Commands::Run { command } => {
@@ -490,6 +651,9 @@ async fn main() -> Result<()> {
         Commands::Download(opts) => cmds::contract::download::run(opts).await?,
         Commands::Killall(opts) => cmds::local::killall_nodes::run(opts).await?,
         Commands::Upgrade(opts) => modality::cmds::upgrade::run(opts).await?,
+        Commands::Completions { shell } => {
+            clap_complete::generate(*shell, &mut Cli::command(), "modal", &mut std::io::stdout());
+        }
         Commands::Status(opts) => {
             // Check if we're in (or nested inside) a contract directory
             let dir = std::env::current_dir()?;
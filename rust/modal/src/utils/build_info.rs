@@ -0,0 +1,26 @@
+//! Build info of this modal binary, for `modal --build-info` and the hub's
+//! `getBuildInfo`
+
+use modal_node::build_info::BuildInfo;
+
+use super::output;
+
+/// This binary's version, commit and protocol
+pub fn current() -> BuildInfo {
+    BuildInfo::new(env!("CARGO_PKG_VERSION"), Some(env!("GIT_COMMIT")))
+}
+
+/// Print the build info in the invocation's output format
+pub fn print() -> anyhow::Result<()> {
+    let info = current();
+    let format = output::global();
+    if format.is_structured() {
+        return output::print_structured(format, &info);
+    }
+    println!("modal {}", info.version);
+    println!("  commit:   {} ({})", info.git_commit.as_deref().unwrap_or("unknown"), env!("GIT_BRANCH"));
+    println!("  protocol: {} (talks to {} and up)", info.protocol_version, info.min_protocol_version);
+    println!("  wire:     {}", info.wire_version);
+    println!("  features: {}", info.features.join(", "));
+    Ok(())
+}
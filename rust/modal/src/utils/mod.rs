@@ -3,6 +3,7 @@
 //! This module provides common functionality used across multiple CLI commands,
 //! including directory resolution, output formatting, and other helpers.
 
+pub mod build_info;
 pub mod dir;
 pub mod output;
 
@@ -1,12 +1,21 @@
 //! Output formatting utilities for CLI commands.
+//!
+//! `modal --output json|yaml|table <command>` sets the format for the whole
+//! invocation; commands read it with `global()` (or `resolve()` when they
+//! also take their own `--output` flag) and print their data with
+//! `print_structured` instead of the human-readable text.
 
 use serde::Serialize;
 use std::str::FromStr;
+use std::sync::OnceLock;
 
 /// Output format for CLI commands.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
 pub enum OutputFormat {
     Json,
+    Yaml,
+    /// Human-readable text and tables
+    #[value(name = "table", alias = "text")]
     Text,
 }
 
@@ -16,6 +25,11 @@ impl OutputFormat {
     pub fn is_json(&self) -> bool {
         matches!(self, OutputFormat::Json)
     }
+
+    /// Check if this is a machine-readable format (JSON or YAML).
+    pub fn is_structured(&self) -> bool {
+        !matches!(self, OutputFormat::Text)
+    }
 }
 
 impl FromStr for OutputFormat {
@@ -24,6 +38,7 @@ impl FromStr for OutputFormat {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(match s.to_lowercase().as_str() {
             "json" => OutputFormat::Json,
+            "yaml" | "yml" => OutputFormat::Yaml,
             _ => OutputFormat::Text,
         })
     }
@@ -41,13 +56,40 @@ impl From<String> for OutputFormat {
     }
 }
 
+static GLOBAL_FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// Set the output format for this invocation (from `modal --output`).
+pub fn set_global(format: OutputFormat) {
+    let _ = GLOBAL_FORMAT.set(format);
+}
+
+/// The output format for this invocation, text unless set.
+pub fn global() -> OutputFormat {
+    GLOBAL_FORMAT.get().copied().unwrap_or(OutputFormat::Text)
+}
+
+/// The format for a command with its own `--output` flag: `modal --output`
+/// wins when given, otherwise the command's flag.
+pub fn resolve(local: &str) -> OutputFormat {
+    GLOBAL_FORMAT.get().copied().unwrap_or_else(|| local.into())
+}
+
+/// Print data as pretty-printed JSON or YAML.
+pub fn print_structured<T: Serialize + ?Sized>(format: OutputFormat, data: &T) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(data)?),
+        _ => println!("{}", serde_json::to_string_pretty(data)?),
+    }
+    Ok(())
+}
+
 /// Format and print output based on the format type.
 ///
-/// For JSON format, serializes the data as pretty-printed JSON.
+/// For JSON and YAML formats, serializes the data.
 /// For Text format, uses the provided text formatter function.
 ///
 /// # Arguments
-/// * `format` - The output format (Json or Text)
+/// * `format` - The output format (Json, Yaml or Text)
 /// * `data` - The data to serialize (must implement Serialize)
 /// * `text_formatter` - A function that produces the text output
 #[allow(dead_code)]
@@ -57,14 +99,12 @@ where
     F: FnOnce() -> String,
 {
     match format {
-        OutputFormat::Json => {
-            if let Ok(json) = serde_json::to_string_pretty(data) {
-                println!("{}", json);
-            }
-        }
         OutputFormat::Text => {
             println!("{}", text_formatter());
         }
+        structured => {
+            let _ = print_structured(*structured, data);
+        }
     }
 }
 
@@ -82,8 +122,8 @@ where
 #[macro_export]
 macro_rules! output_result {
     ($format:expr, $json:tt, $text:expr) => {
-        if $format.is_json() {
-            println!("{}", serde_json::to_string_pretty(&serde_json::json!($json)).unwrap_or_default());
+        if $format.is_structured() {
+            let _ = $crate::utils::output::print_structured($format, &serde_json::json!($json));
         } else {
             println!("{}", $text);
         }
@@ -98,6 +138,7 @@ mod tests {
     fn test_output_format_from_str() {
         assert_eq!("json".parse::<OutputFormat>().unwrap(), OutputFormat::Json);
         assert_eq!("JSON".parse::<OutputFormat>().unwrap(), OutputFormat::Json);
+        assert_eq!("yaml".parse::<OutputFormat>().unwrap(), OutputFormat::Yaml);
         assert_eq!("text".parse::<OutputFormat>().unwrap(), OutputFormat::Text);
         assert_eq!("anything".parse::<OutputFormat>().unwrap(), OutputFormat::Text);
     }
@@ -106,6 +147,17 @@ mod tests {
     fn test_is_json() {
         assert!(OutputFormat::Json.is_json());
         assert!(!OutputFormat::Text.is_json());
+        assert!(OutputFormat::Yaml.is_structured());
+        assert!(!OutputFormat::Text.is_structured());
+    }
+
+    #[test]
+    fn test_value_names() {
+        use clap::ValueEnum;
+        let parse = |s| <OutputFormat as ValueEnum>::from_str(s, false).unwrap();
+        assert_eq!(parse("table"), OutputFormat::Text);
+        assert_eq!(parse("text"), OutputFormat::Text);
+        assert_eq!(parse("yaml"), OutputFormat::Yaml);
     }
 }
 
@@ -0,0 +1,394 @@
+//! Export models to external model checkers
+//!
+//! Translates a model and its formulas into a NuSMV or TLA+ specification, so
+//! results can be cross-checked with mature tools on large systems.
+//!
+//! Each part becomes a state machine whose state is the node it is at and the
+//! transition that took it there (`none` at the start, or when it has nowhere
+//! to go). Transition labels are kept by number, so `<+A> φ` becomes "some
+//! next transition compatible with +A leads to φ", matching `ModelChecker`.
+//! Specs are checked from each part's initial node: the model's `initial`, or
+//! else the source of its first transition.
+//!
+//! `ModelChecker`'s temporal operators are branching-time (`eventually` is
+//! EF, `always` is AG, `until` is E[U], `next` is EX), so formulas translate
+//! to CTL exactly, apart from fixed points (`lfp`/`gfp`), which CTL cannot
+//! express. TLA+ properties are linear-time, so the TLA+ export only carries
+//! `always` over state predicates, as invariants; other formulas are left as
+//! comments.
+
+use crate::ast::{Model, Part, Property, Formula, FormulaExpr};
+
+/// Export `model` and `formulas` as a NuSMV module with one CTLSPEC per
+/// formula and part
+pub fn export_smv(model: &Model, formulas: &[Formula]) -> Result<String, String> {
+    let parts = parts_of(model);
+    let mut out = String::new();
+    out.push_str(&format!("-- Exported from modality model {}\n", model.name));
+
+    for part in &parts {
+        let nodes = nodes_of(part);
+        let module = format!("part_{}", ident(&part.name));
+        out.push_str(&format!("\nMODULE {}\n", module));
+        out.push_str("VAR\n");
+        out.push_str(&format!(
+            "  node : {{{}}};\n",
+            nodes.iter().map(|n| smv_node(n)).collect::<Vec<_>>().join(", ")
+        ));
+        let mut labels = vec!["none".to_string()];
+        labels.extend((0..part.transitions.len()).map(|i| format!("t{}", i)));
+        out.push_str(&format!("  t : {{{}}};\n", labels.join(", ")));
+        for (i, transition) in part.transitions.iter().enumerate() {
+            out.push_str(&format!(
+                "  -- t{}: {} --> {}{}\n",
+                i,
+                transition.from,
+                transition.to,
+                label_comment(&transition.properties)
+            ));
+        }
+        out.push_str(&format!(
+            "INIT node = {} & t = none\n",
+            smv_node(&initial_node(model, part))
+        ));
+
+        let mut moves: Vec<String> = part
+            .transitions
+            .iter()
+            .enumerate()
+            .map(|(i, t)| {
+                format!(
+                    "(node = {} & next(node) = {} & next(t) = t{})",
+                    smv_node(&t.from),
+                    smv_node(&t.to),
+                    i
+                )
+            })
+            .collect();
+        // NuSMV needs every state to have a successor; nodes with nowhere to
+        // go stay put, and `t = none` keeps that out of the modalities
+        for node in &nodes {
+            if !part.transitions.iter().any(|t| &t.from == node) {
+                moves.push(format!(
+                    "(node = {} & next(node) = {} & next(t) = none)",
+                    smv_node(node),
+                    smv_node(node)
+                ));
+            }
+        }
+        out.push_str(&format!("TRANS\n    {}\n", moves.join("\n  | ")));
+    }
+
+    out.push_str("\nMODULE main\nVAR\n");
+    for part in &parts {
+        out.push_str(&format!("  {} : part_{};\n", ident(&part.name), ident(&part.name)));
+    }
+    for formula in formulas {
+        for part in &parts {
+            let expr = smv_expr(&formula.expression, part, &ident(&part.name))
+                .map_err(|e| format!("formula {}: {}", formula.name, e))?;
+            out.push_str(&format!(
+                "CTLSPEC NAME {}_{} := {};\n",
+                ident(&formula.name),
+                ident(&part.name),
+                expr
+            ));
+        }
+    }
+
+    Ok(out)
+}
+
+/// Export `model` as a TLA+ module. Formulas of the form `always(P)`, with
+/// `P` a state predicate, become invariants to give TLC.
+pub fn export_tla(model: &Model, formulas: &[Formula]) -> String {
+    let parts = parts_of(model);
+    let mut out = String::new();
+    out.push_str(&format!("---- MODULE {} ----\n", ident(&model.name)));
+    out.push_str(&format!("\\* Exported from modality model {}\n\n", model.name));
+
+    let vars: Vec<String> = parts
+        .iter()
+        .flat_map(|p| [format!("node_{}", ident(&p.name)), format!("t_{}", ident(&p.name))])
+        .collect();
+    out.push_str(&format!("VARIABLES {}\n", vars.join(", ")));
+    out.push_str(&format!("vars == <<{}>>\n\n", vars.join(", ")));
+
+    let init: Vec<String> = parts
+        .iter()
+        .map(|p| {
+            format!(
+                "node_{} = \"{}\" /\\ t_{} = \"none\"",
+                ident(&p.name),
+                initial_node(model, p),
+                ident(&p.name)
+            )
+        })
+        .collect();
+    out.push_str(&format!("Init == {}\n\n", init.join(" /\\ ")));
+
+    let mut nexts = Vec::new();
+    for part in &parts {
+        let name = ident(&part.name);
+        let others: Vec<String> = parts
+            .iter()
+            .filter(|p| p.name != part.name)
+            .flat_map(|p| [format!("node_{}", ident(&p.name)), format!("t_{}", ident(&p.name))])
+            .collect();
+        let unchanged = if others.is_empty() {
+            String::new()
+        } else {
+            format!(" /\\ UNCHANGED <<{}>>", others.join(", "))
+        };
+        let moves: Vec<String> = part
+            .transitions
+            .iter()
+            .enumerate()
+            .map(|(i, t)| {
+                format!(
+                    "    \\/ node_{n} = \"{}\" /\\ node_{n}' = \"{}\" /\\ t_{n}' = \"t{}\"{}  \\* {}",
+                    t.from,
+                    t.to,
+                    i,
+                    unchanged,
+                    label_comment(&t.properties).trim(),
+                    n = name
+                )
+            })
+            .collect();
+        let action = format!("Next_{}", name);
+        if moves.is_empty() {
+            out.push_str(&format!("{} == FALSE\n\n", action));
+        } else {
+            out.push_str(&format!("{} ==\n{}\n\n", action, moves.join("\n")));
+        }
+        nexts.push(action);
+    }
+    out.push_str(&format!("Next == {}\n\n", nexts.join(" \\/ ")));
+    out.push_str("Spec == Init /\\ [][Next]_vars\n\n");
+
+    for formula in formulas {
+        let name = ident(&formula.name);
+        let invariant = match strip_parens(&formula.expression) {
+            FormulaExpr::Always(inner) => parts
+                .iter()
+                .map(|p| tla_predicate(inner, p))
+                .collect::<Option<Vec<_>>>(),
+            _ => None,
+        };
+        match invariant {
+            Some(predicates) => {
+                out.push_str(&format!("\\* INVARIANT {}\n", name));
+                out.push_str(&format!("{} == {}\n\n", name, predicates.join(" /\\ ")));
+            }
+            None => out.push_str(&format!(
+                "\\* {}: not expressible as a TLA+ invariant; check it with the SMV export\n\n",
+                name
+            )),
+        }
+    }
+
+    out.push_str("====\n");
+    out
+}
+
+/// The model's parts, with direct transitions as a part named after the model
+fn parts_of(model: &Model) -> Vec<Part> {
+    let mut parts = model.parts.clone();
+    if !model.transitions.is_empty() {
+        let mut part = Part::new(model.name.clone());
+        for transition in &model.transitions {
+            part.add_transition(transition.clone());
+        }
+        parts.insert(0, part);
+    }
+    parts
+}
+
+fn nodes_of(part: &Part) -> Vec<String> {
+    let mut nodes = Vec::new();
+    for transition in &part.transitions {
+        for node in [&transition.from, &transition.to] {
+            if !nodes.contains(node) {
+                nodes.push(node.clone());
+            }
+        }
+    }
+    nodes
+}
+
+fn initial_node(model: &Model, part: &Part) -> String {
+    let nodes = nodes_of(part);
+    match &model.initial {
+        Some(initial) if nodes.contains(initial) => initial.clone(),
+        _ => nodes.into_iter().next().unwrap_or_else(|| "init".to_string()),
+    }
+}
+
+/// Transitions of `part` usable for `properties`, as `ModelChecker` decides:
+/// each property is either on the transition or not mentioned by it
+fn compatible_transitions(part: &Part, properties: &[Property]) -> Vec<usize> {
+    part.transitions
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| {
+            properties.iter().all(|property| {
+                t.properties.iter().any(|p| p == property)
+                    || !t.properties.iter().any(|p| p.name == property.name)
+            })
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+fn smv_expr(expr: &FormulaExpr, part: &Part, prefix: &str) -> Result<String, String> {
+    let sub = |e: &FormulaExpr| smv_expr(e, part, prefix);
+    let moved = |properties: &[Property]| {
+        let ids = compatible_transitions(part, properties);
+        if ids.is_empty() {
+            "FALSE".to_string()
+        } else {
+            format!(
+                "{}.t in {{{}}}",
+                prefix,
+                ids.iter().map(|i| format!("t{}", i)).collect::<Vec<_>>().join(", ")
+            )
+        }
+    };
+    Ok(match expr {
+        FormulaExpr::True => "TRUE".to_string(),
+        FormulaExpr::False => "FALSE".to_string(),
+        FormulaExpr::Prop(node) => format!("{}.node = {}", prefix, smv_node(node)),
+        FormulaExpr::And(l, r) => format!("({} & {})", sub(l)?, sub(r)?),
+        FormulaExpr::Or(l, r) => format!("({} | {})", sub(l)?, sub(r)?),
+        FormulaExpr::Not(e) => format!("!({})", sub(e)?),
+        FormulaExpr::Implies(l, r) => format!("({} -> {})", sub(l)?, sub(r)?),
+        FormulaExpr::Paren(e) => sub(e)?,
+        FormulaExpr::Diamond(properties, e) => format!("EX ({} & {})", moved(properties), sub(e)?),
+        FormulaExpr::Box(properties, e) => format!("AX ({} -> {})", moved(properties), sub(e)?),
+        FormulaExpr::DiamondBox(..) => sub(&expr.expand_diamond_box())?,
+        FormulaExpr::Eventually(e) => format!("EF {}", sub(e)?),
+        FormulaExpr::Always(e) => format!("AG {}", sub(e)?),
+        FormulaExpr::Until(l, r) => format!("E [{} U {}]", sub(l)?, sub(r)?),
+        FormulaExpr::Next(e) => format!("EX ({}.t != none & {})", prefix, sub(e)?),
+        FormulaExpr::Lfp(..) | FormulaExpr::Gfp(..) | FormulaExpr::Var(_) => {
+            return Err("fixed points can't be expressed in CTL".to_string());
+        }
+    })
+}
+
+/// A state predicate over `part`, or `None` for anything temporal
+fn tla_predicate(expr: &FormulaExpr, part: &Part) -> Option<String> {
+    let sub = |e: &FormulaExpr| tla_predicate(e, part);
+    Some(match expr {
+        FormulaExpr::True => "TRUE".to_string(),
+        FormulaExpr::False => "FALSE".to_string(),
+        FormulaExpr::Prop(node) => format!("node_{} = \"{}\"", ident(&part.name), node),
+        FormulaExpr::And(l, r) => format!("({} /\\ {})", sub(l)?, sub(r)?),
+        FormulaExpr::Or(l, r) => format!("({} \\/ {})", sub(l)?, sub(r)?),
+        FormulaExpr::Not(e) => format!("~({})", sub(e)?),
+        FormulaExpr::Implies(l, r) => format!("({} => {})", sub(l)?, sub(r)?),
+        FormulaExpr::Paren(e) => sub(e)?,
+        _ => return None,
+    })
+}
+
+fn strip_parens(expr: &FormulaExpr) -> &FormulaExpr {
+    match expr {
+        FormulaExpr::Paren(e) => strip_parens(e),
+        other => other,
+    }
+}
+
+fn label_comment(properties: &[Property]) -> String {
+    if properties.is_empty() {
+        return String::new();
+    }
+    let labels: Vec<String> = properties
+        .iter()
+        .map(|p| {
+            let sign = if p.sign == crate::ast::PropertySign::Plus { "+" } else { "-" };
+            match p.get_predicate().and_then(|(_, args)| args.get("arg")).and_then(|a| a.as_str()) {
+                Some(arg) => format!("{}{}({})", sign, p.name, arg),
+                None => format!("{}{}", sign, p.name),
+            }
+        })
+        .collect();
+    format!(" : {}", labels.join(" "))
+}
+
+/// Node names go in SMV enums, where `init` and `next` are keywords
+fn smv_node(node: &str) -> String {
+    format!("s_{}", ident(node))
+}
+
+fn ident(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::{PropertySign, Transition};
+
+    fn escrow() -> Model {
+        let transition = |from: &str, to: &str, label: &str| {
+            let mut t = Transition::new(from.to_string(), to.to_string());
+            t.add_property(Property::new(PropertySign::Plus, label.to_string()));
+            t
+        };
+        Model::new_simple(
+            "escrow".to_string(),
+            "init".to_string(),
+            vec![
+                transition("init", "funded", "DEPOSIT"),
+                transition("funded", "done", "RELEASE"),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_export_smv() {
+        let formulas = vec![
+            Formula::new(
+                "can_deposit".to_string(),
+                FormulaExpr::Diamond(
+                    vec![Property::new(PropertySign::Plus, "DEPOSIT".to_string())],
+                    Box::new(FormulaExpr::True),
+                ),
+            ),
+            Formula::new("finishes".to_string(), FormulaExpr::Eventually(Box::new(FormulaExpr::Prop("done".to_string())))),
+        ];
+        let smv = export_smv(&escrow(), &formulas).unwrap();
+        assert!(smv.contains("node : {s_init, s_funded, s_done};"));
+        assert!(smv.contains("INIT node = s_init & t = none"));
+        assert!(smv.contains("(node = s_done & next(node) = s_done & next(t) = none)"));
+        assert!(smv.contains("CTLSPEC NAME can_deposit_escrow := EX (escrow.t in {t0, t1} & TRUE);"));
+        assert!(smv.contains("CTLSPEC NAME finishes_escrow := EF escrow.node = s_done;"));
+
+        let fixed_point = Formula::new(
+            "fp".to_string(),
+            FormulaExpr::Lfp("X".to_string(), Box::new(FormulaExpr::Var("X".to_string()))),
+        );
+        assert!(export_smv(&escrow(), &[fixed_point]).is_err());
+    }
+
+    #[test]
+    fn test_export_tla() {
+        let formulas = vec![
+            Formula::new(
+                "never_stuck".to_string(),
+                FormulaExpr::Always(Box::new(FormulaExpr::Not(Box::new(FormulaExpr::Prop("stuck".to_string()))))),
+            ),
+            Formula::new("finishes".to_string(), FormulaExpr::Eventually(Box::new(FormulaExpr::Prop("done".to_string())))),
+        ];
+        let tla = export_tla(&escrow(), &formulas);
+        assert!(tla.starts_with("---- MODULE escrow ----"));
+        assert!(tla.contains("Init == node_escrow = \"init\" /\\ t_escrow = \"none\""));
+        assert!(tla.contains("never_stuck == ~(node_escrow = \"stuck\")"));
+        assert!(tla.contains("\\* finishes: not expressible"));
+        assert!(tla.trim_end().ends_with("===="));
+    }
+}
@@ -24,6 +24,8 @@ pub mod nl_mapper;
 pub mod formula_synthesis;
 pub mod llm_synthesis;
 pub mod validation;
+pub mod rule_coverage;
+pub mod export;
 
 // Include the generated parser
 use lalrpop_util::lalrpop_mod;
@@ -32,7 +34,9 @@ lalrpop_mod!(pub grammar);
 pub use lalrpop_parser::{parse_file_lalrpop, parse_content_lalrpop, parse_all_models_lalrpop, parse_all_models_content_lalrpop, parse_all_formulas_content_lalrpop, parse_all_actions_lalrpop, parse_all_actions_content_lalrpop, parse_action_call_lalrpop, parse_all_tests_lalrpop, parse_all_tests_content_lalrpop};
 pub use ast::{Model, Part, Transition, Property, PropertySign, PropertySource, Formula, FormulaExpr, PartState, Action, ActionCall, Test, TestStatement};
 pub use mermaid::{generate_mermaid_diagram, generate_mermaid_diagrams, generate_mermaid_diagram_with_styling, generate_mermaid_diagram_with_state};
+pub use export::{export_smv, export_tla};
 pub use model_checker::{ModelChecker, State, ModelCheckResult};
+pub use rule_coverage::{RuleCoverage, RuleReport, RuleStatus, RuleOverlap, OverlapKind};
 pub use synthesis::{synthesize, synthesize_from_pattern, identify_pattern, SynthesisResult, RulePattern};
 pub use printer::print_model;
 pub use evolution::{EvolvableContract, Amendment, Proposal, ProposalStatus, Approval, EvolutionRecord};
@@ -0,0 +1,295 @@
+//! Rule coverage analysis
+//!
+//! Compares a contract's rules with its model using the model checker's
+//! satisfaction sets: which actions and state paths no rule mentions, which
+//! rules hold everywhere or nowhere (and so constrain nothing), and which
+//! rules overlap with each other.
+
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeSet, HashSet};
+
+use crate::ast::{Model, Part, Property, Formula, FormulaExpr};
+use crate::model_checker::{ModelChecker, State};
+
+/// What a rule's satisfaction set says about it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleStatus {
+    /// Holds in some states of the model and not others
+    Effective,
+    /// Holds in every state, so it rules nothing out
+    Vacuous,
+    /// Holds in no state, so no commit can satisfy it
+    Unsatisfiable,
+    /// Only mentions actions the model never takes
+    Unreachable,
+}
+
+/// How two rules' satisfaction sets relate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlapKind {
+    /// Both hold in exactly the same states
+    Equivalent,
+    /// Wherever the first holds, the second does too
+    Implies,
+    /// Their states differ, but they constrain some of the same actions
+    SharedActions,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleReport {
+    pub name: String,
+    pub status: RuleStatus,
+    /// States of the model the rule holds in, out of `total_states`
+    pub satisfying_states: usize,
+    pub total_states: usize,
+    /// Action labels the rule mentions
+    pub actions: Vec<String>,
+    /// Labels the rule mentions that no transition of the model carries
+    pub unknown_actions: Vec<String>,
+    /// State paths the rule's predicates refer to
+    pub paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleOverlap {
+    pub first: String,
+    pub second: String,
+    pub kind: OverlapKind,
+    pub shared_actions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RuleCoverage {
+    /// Actions of the model that no rule mentions
+    pub unconstrained_actions: Vec<String>,
+    /// State paths that no rule refers to
+    pub unconstrained_paths: Vec<String>,
+    pub rules: Vec<RuleReport>,
+    pub overlaps: Vec<RuleOverlap>,
+}
+
+/// Analyze `rules` against `model`. `state_paths` are the contract's state
+/// paths, to report those no rule refers to.
+pub fn analyze(model: &Model, rules: &[Formula], state_paths: &[String]) -> RuleCoverage {
+    let checker = ModelChecker::new(with_direct_transitions(model));
+    let all_states = satisfaction_set(&checker, &Formula::new("true".to_string(), FormulaExpr::True));
+    let model_actions: BTreeSet<String> = model
+        .all_transitions()
+        .iter()
+        .flat_map(|t| t.properties.iter().map(|p| p.name.clone()))
+        .collect();
+
+    let mut reports = Vec::new();
+    let mut sets = Vec::new();
+    let mut mentioned_actions = BTreeSet::new();
+    let mut mentioned_paths = BTreeSet::new();
+    for rule in rules {
+        let mut properties = Vec::new();
+        collect_properties(&rule.expression, &mut properties);
+        let actions: BTreeSet<String> = properties.iter().map(|p| p.name.clone()).collect();
+        let paths: BTreeSet<String> = properties.iter().flat_map(predicate_paths).collect();
+        let unknown_actions: Vec<String> = actions.difference(&model_actions).cloned().collect();
+
+        let satisfying = satisfaction_set(&checker, rule);
+        let status = if satisfying.is_empty() {
+            RuleStatus::Unsatisfiable
+        } else if satisfying == all_states {
+            RuleStatus::Vacuous
+        } else if !actions.is_empty() && unknown_actions.len() == actions.len() {
+            RuleStatus::Unreachable
+        } else {
+            RuleStatus::Effective
+        };
+
+        mentioned_actions.extend(actions.iter().cloned());
+        mentioned_paths.extend(paths.iter().cloned());
+        reports.push(RuleReport {
+            name: rule.name.clone(),
+            status,
+            satisfying_states: satisfying.len(),
+            total_states: all_states.len(),
+            actions: actions.into_iter().collect(),
+            unknown_actions,
+            paths: paths.into_iter().collect(),
+        });
+        sets.push(satisfying);
+    }
+
+    let mut overlaps = Vec::new();
+    for i in 0..reports.len() {
+        for j in (i + 1)..reports.len() {
+            // Vacuous and unsatisfiable rules are already reported, and would
+            // overlap with everything
+            if reports[i].status != RuleStatus::Effective || reports[j].status != RuleStatus::Effective {
+                continue;
+            }
+            let shared_actions: Vec<String> = reports[i]
+                .actions
+                .iter()
+                .filter(|a| reports[j].actions.contains(a))
+                .cloned()
+                .collect();
+            let (first, second, kind) = if sets[i] == sets[j] {
+                (i, j, OverlapKind::Equivalent)
+            } else if sets[i].is_subset(&sets[j]) {
+                (i, j, OverlapKind::Implies)
+            } else if sets[j].is_subset(&sets[i]) {
+                (j, i, OverlapKind::Implies)
+            } else if !shared_actions.is_empty() {
+                (i, j, OverlapKind::SharedActions)
+            } else {
+                continue;
+            };
+            overlaps.push(RuleOverlap {
+                first: reports[first].name.clone(),
+                second: reports[second].name.clone(),
+                kind,
+                shared_actions,
+            });
+        }
+    }
+
+    RuleCoverage {
+        unconstrained_actions: model_actions.difference(&mentioned_actions).cloned().collect(),
+        unconstrained_paths: state_paths
+            .iter()
+            .filter(|path| !mentioned_paths.iter().any(|m| path_covers(m, path)))
+            .cloned()
+            .collect(),
+        rules: reports,
+        overlaps,
+    }
+}
+
+/// The model checker only looks at parts, so give a model written as bare
+/// transitions a part of its own
+fn with_direct_transitions(model: &Model) -> Model {
+    let mut model = model.clone();
+    if !model.transitions.is_empty() {
+        let mut part = Part::new(model.name.clone());
+        for transition in std::mem::take(&mut model.transitions) {
+            part.add_transition(transition);
+        }
+        model.add_part(part);
+    }
+    model
+}
+
+fn satisfaction_set(checker: &ModelChecker, formula: &Formula) -> HashSet<State> {
+    checker.check_formula_any_state(formula).satisfying_states.into_iter().collect()
+}
+
+fn collect_properties(expr: &FormulaExpr, out: &mut Vec<Property>) {
+    match expr {
+        FormulaExpr::True | FormulaExpr::False | FormulaExpr::Prop(_) | FormulaExpr::Var(_) => {}
+        FormulaExpr::Not(e)
+        | FormulaExpr::Paren(e)
+        | FormulaExpr::Lfp(_, e)
+        | FormulaExpr::Gfp(_, e)
+        | FormulaExpr::Eventually(e)
+        | FormulaExpr::Always(e)
+        | FormulaExpr::Next(e) => collect_properties(e, out),
+        FormulaExpr::And(l, r)
+        | FormulaExpr::Or(l, r)
+        | FormulaExpr::Implies(l, r)
+        | FormulaExpr::Until(l, r) => {
+            collect_properties(l, out);
+            collect_properties(r, out);
+        }
+        FormulaExpr::Diamond(props, e) | FormulaExpr::Box(props, e) | FormulaExpr::DiamondBox(props, e) => {
+            out.extend(props.iter().cloned());
+            collect_properties(e, out);
+        }
+    }
+}
+
+/// Paths among a predicate's arguments, e.g. `/users/alice.id` in
+/// `signed_by(/users/alice.id)`
+fn predicate_paths(property: &Property) -> Vec<String> {
+    let Some((_, args)) = property.get_predicate() else {
+        return Vec::new();
+    };
+    let values: Vec<&serde_json::Value> = match (args.get("arg"), args.get("args")) {
+        (Some(arg), _) => vec![arg],
+        (None, Some(serde_json::Value::Array(args))) => args.iter().collect(),
+        _ => Vec::new(),
+    };
+    values
+        .into_iter()
+        .filter_map(|v| v.as_str())
+        .filter(|s| s.starts_with('/'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Whether a rule referring to `mentioned` covers `path`: the same path, or a
+/// directory above it
+fn path_covers(mentioned: &str, path: &str) -> bool {
+    mentioned == path || path.starts_with(&format!("{}/", mentioned.trim_end_matches('/')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::{PropertySign, Transition};
+
+    fn transition(from: &str, to: &str, label: &str) -> Transition {
+        let mut t = Transition::new(from.to_string(), to.to_string());
+        t.add_property(Property::new(PropertySign::Plus, label.to_string()));
+        t
+    }
+
+    fn diamond(label: &str) -> FormulaExpr {
+        FormulaExpr::Diamond(
+            vec![Property::new(PropertySign::Plus, label.to_string())],
+            Box::new(FormulaExpr::True),
+        )
+    }
+
+    #[test]
+    fn test_rule_coverage() {
+        let model = Model::new_simple(
+            "escrow".to_string(),
+            "init".to_string(),
+            vec![
+                transition("init", "funded", "DEPOSIT"),
+                transition("funded", "done", "RELEASE"),
+                transition("funded", "init", "REFUND"),
+            ],
+        );
+        let rules = vec![
+            Formula::new("can_deposit".to_string(), diamond("DEPOSIT")),
+            Formula::new("can_deposit_too".to_string(), diamond("DEPOSIT")),
+            Formula::new("anything".to_string(), FormulaExpr::True),
+            Formula::new("nothing".to_string(), FormulaExpr::False),
+            Formula::new("can_mint".to_string(), diamond("MINT")),
+            Formula::new(
+                "signed".to_string(),
+                FormulaExpr::Diamond(
+                    vec![Property::new_predicate_from_call("signed_by".to_string(), "/users/alice.id".to_string())],
+                    Box::new(FormulaExpr::True),
+                ),
+            ),
+        ];
+        let paths = vec!["/users/alice.id".to_string(), "/data/notes.text".to_string()];
+
+        let coverage = analyze(&model, &rules, &paths);
+        assert_eq!(coverage.unconstrained_actions, vec!["REFUND".to_string(), "RELEASE".to_string()]);
+        assert_eq!(coverage.unconstrained_paths, vec!["/data/notes.text".to_string()]);
+
+        let status = |name: &str| coverage.rules.iter().find(|r| r.name == name).unwrap().status.clone();
+        assert_eq!(status("can_deposit"), RuleStatus::Effective);
+        assert_eq!(status("anything"), RuleStatus::Vacuous);
+        assert_eq!(status("nothing"), RuleStatus::Unsatisfiable);
+        assert_eq!(status("can_mint"), RuleStatus::Unreachable);
+
+        assert!(coverage.overlaps.contains(&RuleOverlap {
+            first: "can_deposit".to_string(),
+            second: "can_deposit_too".to_string(),
+            kind: OverlapKind::Equivalent,
+            shared_actions: vec!["DEPOSIT".to_string()],
+        }));
+    }
+}
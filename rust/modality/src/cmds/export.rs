@@ -0,0 +1,55 @@
+use anyhow::Result;
+use clap::Parser;
+
+/// Export a model and its formulas for an external model checker
+#[derive(Parser, Debug)]
+pub struct Opts {
+    /// Path to the .modality file
+    pub input: String,
+
+    /// Name of the model to export (optional, defaults to first model)
+    #[arg(short, long)]
+    pub model: Option<String>,
+
+    /// Target format: smv (NuSMV) or tla (TLA+)
+    #[arg(long, default_value = "smv")]
+    pub format: String,
+
+    /// File to write (default: stdout)
+    #[arg(short, long)]
+    pub output: Option<String>,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    let content = std::fs::read_to_string(&opts.input)?;
+
+    let models = modality_lang::parse_all_models_content_lalrpop(&content)
+        .map_err(|e| anyhow::anyhow!("Failed to parse models: {}", e))?;
+    let model = if let Some(model_name) = &opts.model {
+        models.into_iter()
+            .find(|m| m.name == *model_name)
+            .ok_or_else(|| anyhow::anyhow!("Model '{}' not found", model_name))?
+    } else {
+        models.into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No models found in file"))?
+    };
+
+    let formulas = modality_lang::parse_all_formulas_content_lalrpop(&content)
+        .map_err(|e| anyhow::anyhow!("Failed to parse formulas: {}", e))?;
+
+    let spec = match opts.format.as_str() {
+        "smv" => modality_lang::export_smv(&model, &formulas)
+            .map_err(|e| anyhow::anyhow!("Can't export to SMV: {}", e))?,
+        "tla" => modality_lang::export_tla(&model, &formulas),
+        other => anyhow::bail!("Unknown format '{}' (expected smv or tla)", other),
+    };
+
+    if let Some(path) = &opts.output {
+        std::fs::write(path, spec)?;
+        eprintln!("Wrote {} spec for model {} to {}", opts.format, model.name, path);
+    } else {
+        print!("{}", spec);
+    }
+    Ok(())
+}
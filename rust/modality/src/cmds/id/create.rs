@@ -44,6 +44,11 @@ pub struct Opts {
     #[clap(long, default_value = "0")]
     index: u32,
 
+    /// Full hardened derivation path, e.g. m/44'/177017'/1'/0'/0'
+    /// (instead of --account, --change and --index)
+    #[clap(long, requires = "use_mnemonic", conflicts_with_all = ["account", "change", "index"])]
+    derivation_path: Option<String>,
+
     /// BIP39 passphrase (optional, for additional security)
     #[clap(long)]
     passphrase: Option<String>,
@@ -51,9 +56,24 @@ pub struct Opts {
     /// Don't store the mnemonic in the passfile (only applicable with --use-mnemonic)
     #[clap(long)]
     no_store_mnemonic: bool,
+
+    /// Key scheme: ed25519 for a Modality ID, or bls for a BLS12-381 consensus signing key
+    #[clap(long, default_value = "ed25519")]
+    scheme: String,
 }
 
 pub async fn run(opts: &Opts) -> Result<()> {
+    match opts.scheme.as_str() {
+        "ed25519" => {}
+        "bls" => return create_bls_key(opts),
+        other => {
+            return Err(anyhow::anyhow!(
+                "Unknown key scheme '{}'. Expected ed25519 or bls.",
+                other
+            ))
+        }
+    }
+
     // Generate or import keypair based on options
     let (keypair, mnemonic_phrase, derivation_path) = if opts.use_mnemonic {
         let (mnemonic, is_new) = if let Some(phrase) = &opts.mnemonic_phrase {
@@ -84,18 +104,14 @@ pub async fn run(opts: &Opts) -> Result<()> {
             println!("   Never share it with anyone!\n");
         }
 
-        let path = format!(
-            "m/44'/177017'/{}'/{}'/{}'",
-            opts.account, opts.change, opts.index
-        );
+        let path = opts.derivation_path.clone().unwrap_or_else(|| {
+            format!(
+                "m/44'/177017'/{}'/{}'/{}'",
+                opts.account, opts.change, opts.index
+            )
+        });
         
-        let kp = Keypair::from_mnemonic(
-            &mnemonic,
-            opts.account,
-            opts.change,
-            opts.index,
-            opts.passphrase.as_deref(),
-        )
+        let kp = Keypair::from_mnemonic_at_path(&mnemonic, &path, opts.passphrase.as_deref())
         .map_err(|e| {
             eprintln!("Failed to derive keypair from mnemonic: {}", e);
             e
@@ -118,32 +134,7 @@ pub async fn run(opts: &Opts) -> Result<()> {
 
     let address = keypair.as_public_address();
 
-    // Create path using proper path handling
-    let filepath = if opts.path.is_some() {
-        opts.path.clone().unwrap()
-    } else {
-        let filename = opts.name.clone().unwrap_or_else(|| address.clone());
-        let default_dir = if let Some(home) = dirs::home_dir() {
-            let home_dot_modality = home.join(".modality");
-            std::fs::create_dir_all(&home_dot_modality).expect("Failed to create directory");
-            home_dot_modality
-        } else {
-            PathBuf::from(".")
-        };
-        opts.dir
-            .clone()
-            .unwrap_or(default_dir)
-            .join(format!("{}.mod_passfile", filename))
-    };
-
-    // Check if file already exists to prevent accidental overwrites
-    if filepath.exists() {
-        return Err(anyhow::anyhow!(
-            "Key file already exists at {}. Please choose a different name or remove the existing file.",
-            filepath.display()
-        ));
-    }
-
+    let filepath = passfile_path(opts, &address)?;
     let filepath_str = filepath.to_str().ok_or_else(|| {
         anyhow::anyhow!("Invalid file path: contains non-Unicode characters")
     })?;
@@ -182,6 +173,77 @@ pub async fn run(opts: &Opts) -> Result<()> {
     Ok(())
 }
 
+/// Create a BLS12-381 key for signing consensus votes. Certificates of a
+/// committee whose validators all have one carry a single aggregate signature.
+#[cfg(feature = "bls")]
+fn create_bls_key(opts: &Opts) -> Result<()> {
+    use modal_common::bls::BlsKeypair;
+
+    if opts.use_mnemonic {
+        return Err(anyhow::anyhow!("--use-mnemonic is not supported with --scheme bls"));
+    }
+
+    let keypair = BlsKeypair::generate()?;
+    let public_key = keypair.public_key_hex();
+    let filepath = passfile_path(opts, &public_key)?;
+    let filepath_str = filepath.to_str().ok_or_else(|| {
+        anyhow::anyhow!("Invalid file path: contains non-Unicode characters")
+    })?;
+
+    if opts.encrypt {
+        let password = get_password().context("Failed to get password")?;
+        keypair.as_encrypted_json_file(filepath_str, &password)?;
+    } else {
+        keypair.as_json_file(filepath_str)?;
+    }
+
+    println!("✨ Successfully created a new BLS consensus key!");
+    println!("📍 BLS Public Key: {}", public_key);
+    println!("🔏 Proof of Possession: {}", hex::encode(keypair.prove_possession()));
+    println!("💾 Modality Passfile saved to: {}", filepath.display());
+    println!("\n🚨🚨🚨  IMPORTANT: Keep your passfile secure and never share it! 🚨🚨🚨");
+
+    Ok(())
+}
+
+#[cfg(not(feature = "bls"))]
+fn create_bls_key(_opts: &Opts) -> Result<()> {
+    Err(anyhow::anyhow!(
+        "This build of modal does not support BLS keys. Rebuild with the `bls` feature."
+    ))
+}
+
+/// Where to save the passfile, refusing to overwrite an existing one
+fn passfile_path(opts: &Opts, default_name: &str) -> Result<PathBuf> {
+    // Create path using proper path handling
+    let filepath = if opts.path.is_some() {
+        opts.path.clone().unwrap()
+    } else {
+        let filename = opts.name.clone().unwrap_or_else(|| default_name.to_string());
+        let default_dir = if let Some(home) = dirs::home_dir() {
+            let home_dot_modality = home.join(".modality");
+            std::fs::create_dir_all(&home_dot_modality).expect("Failed to create directory");
+            home_dot_modality
+        } else {
+            PathBuf::from(".")
+        };
+        opts.dir
+            .clone()
+            .unwrap_or(default_dir)
+            .join(format!("{}.mod_passfile", filename))
+    };
+
+    // Check if file already exists to prevent accidental overwrites
+    if filepath.exists() {
+        return Err(anyhow::anyhow!(
+            "Key file already exists at {}. Please choose a different name or remove the existing file.",
+            filepath.display()
+        ));
+    }
+
+    Ok(filepath)
+}
+
 fn get_password() -> Result<String> {
     eprint!("Enter password to encrypt the passfile: ");
 
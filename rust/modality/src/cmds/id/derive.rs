@@ -4,6 +4,7 @@ use rpassword::read_password;
 use std::path::PathBuf;
 
 use modal_common::keypair::Keypair;
+use modal_common::mnemonic::{format_derivation_path, parse_derivation_path, Mnemonic};
 
 #[derive(Debug, Parser)]
 #[command(about = "Derive a keypair from a BIP39 mnemonic seed phrase")]
@@ -24,11 +25,20 @@ pub struct Opts {
     #[clap(long, default_value = "0")]
     index: u32,
 
+    /// Full hardened derivation path, e.g. m/44'/177017'/1'/0'/0'
+    /// (instead of --account, --change and --index)
+    #[clap(long, conflicts_with_all = ["account", "change", "index"])]
+    derivation_path: Option<String>,
+
+    /// Derive this many identities, incrementing the last path component
+    #[clap(long, default_value = "1")]
+    count: u32,
+
     /// BIP39 passphrase (optional, for additional security)
     #[clap(long)]
     passphrase: Option<String>,
 
-    /// Output file path for the passfile
+    /// Output file path for the passfile (only with --count 1)
     #[clap(long)]
     path: Option<PathBuf>,
 
@@ -36,7 +46,8 @@ pub struct Opts {
     #[clap(long)]
     dir: Option<PathBuf>,
 
-    /// Name for the passfile (defaults to the generated ID)
+    /// Name for the passfile (defaults to the generated ID); with --count,
+    /// passfiles are named <name>-<n>
     #[clap(long)]
     name: Option<String>,
 
@@ -62,23 +73,74 @@ pub async fn run(opts: &Opts) -> Result<()> {
         return Err(anyhow::anyhow!("Mnemonic phrase cannot be empty"));
     }
 
-    // Derive keypair
-    let derivation_path = format!(
-        "m/44'/177017'/{}'/{}'/{}'",
-        opts.account, opts.change, opts.index
-    );
+    // Check the phrase once before deriving anything from it
+    Mnemonic::from_phrase(&mnemonic)?;
+
+    if opts.count == 0 {
+        return Err(anyhow::anyhow!("--count must be at least 1"));
+    }
+    if opts.count > 1 && opts.path.is_some() {
+        return Err(anyhow::anyhow!("--path names a single passfile; use --dir and --name with --count"));
+    }
+
+    let first_path = match &opts.derivation_path {
+        Some(path) => parse_derivation_path(path)?,
+        None => parse_derivation_path(&Mnemonic::default_derivation_path(
+            opts.account,
+            opts.change,
+            opts.index,
+        ))?,
+    };
+
+    // Ask once for all the passfiles
+    let password = if opts.encrypt {
+        Some(get_password().context("Failed to get password")?)
+    } else {
+        None
+    };
+
+    for n in 0..opts.count {
+        let mut components = first_path.clone();
+        let last = components.last_mut().expect("paths have a component");
+        *last = last
+            .checked_add(n)
+            .filter(|index| *index < 0x80000000)
+            .ok_or_else(|| anyhow::anyhow!("Derivation path index out of range"))?;
+        let name = match &opts.name {
+            Some(name) if opts.count > 1 => Some(format!("{}-{}", name, n)),
+            name => name.clone(),
+        };
+        derive_one(opts, &mnemonic, &format_derivation_path(&components), name, password.as_deref())?;
+    }
+
+    if opts.store_mnemonic {
+        if opts.encrypt {
+            println!("🔐 Mnemonic stored encrypted in the passfile");
+        } else {
+            println!("⚠️  Mnemonic stored in plaintext in the passfile");
+        }
+    } else {
+        println!("ℹ️  Mnemonic NOT stored in the passfile");
+    }
+    
+    println!("\n🚨🚨🚨  IMPORTANT: Keep your passfile secure and never share it! 🚨🚨🚨");
+
+    Ok(())
+}
 
+/// Derive the identity at `derivation_path` and save its passfile
+fn derive_one(
+    opts: &Opts,
+    mnemonic: &str,
+    derivation_path: &str,
+    name: Option<String>,
+    password: Option<&str>,
+) -> Result<()> {
     println!("🔑 Deriving keypair from mnemonic...");
     println!("   Derivation Path: {}", derivation_path);
 
-    let keypair = Keypair::from_mnemonic(
-        &mnemonic,
-        opts.account,
-        opts.change,
-        opts.index,
-        opts.passphrase.as_deref(),
-    )
-    .context("Failed to derive keypair from mnemonic")?;
+    let keypair = Keypair::from_mnemonic_at_path(mnemonic, derivation_path, opts.passphrase.as_deref())
+        .context("Failed to derive keypair from mnemonic")?;
 
     let address = keypair.as_public_address();
 
@@ -86,7 +148,7 @@ pub async fn run(opts: &Opts) -> Result<()> {
     let filepath = if opts.path.is_some() {
         opts.path.clone().unwrap()
     } else {
-        let filename = opts.name.clone().unwrap_or_else(|| address.clone());
+        let filename = name.unwrap_or_else(|| address.clone());
         let default_dir = if let Some(home) = dirs::home_dir() {
             let home_dot_modality = home.join(".modality");
             std::fs::create_dir_all(&home_dot_modality).expect("Failed to create directory");
@@ -113,20 +175,19 @@ pub async fn run(opts: &Opts) -> Result<()> {
     })?;
 
     let mnemonic_to_store = if opts.store_mnemonic {
-        Some(mnemonic)
+        Some(mnemonic.to_string())
     } else {
         None
     };
 
     // Save keypair
-    if opts.encrypt {
-        let password = get_password().context("Failed to get password")?;
+    if let Some(password) = password {
         keypair
             .as_encrypted_json_file_with_mnemonic(
                 filepath_str,
-                &password,
+                password,
                 mnemonic_to_store,
-                Some(derivation_path.clone()),
+                Some(derivation_path.to_string()),
             )
             .context("Failed to save encrypted keypair to file")?;
     } else {
@@ -134,7 +195,7 @@ pub async fn run(opts: &Opts) -> Result<()> {
             .as_json_file_with_mnemonic(
                 filepath_str,
                 mnemonic_to_store,
-                Some(derivation_path.clone()),
+                Some(derivation_path.to_string()),
             )
             .context("Failed to save keypair to file")?;
     }
@@ -142,19 +203,7 @@ pub async fn run(opts: &Opts) -> Result<()> {
     println!("\n✨ Successfully derived Modality ID from mnemonic!");
     println!("📍 Modality ID: {}", address);
     println!("🔑 BIP44 Derivation Path: {}", derivation_path);
-    println!("💾 Modality Passfile saved to: {}", filepath.display());
-    
-    if opts.store_mnemonic {
-        if opts.encrypt {
-            println!("🔐 Mnemonic stored encrypted in the passfile");
-        } else {
-            println!("⚠️  Mnemonic stored in plaintext in the passfile");
-        }
-    } else {
-        println!("ℹ️  Mnemonic NOT stored in the passfile");
-    }
-    
-    println!("\n🚨🚨🚨  IMPORTANT: Keep your passfile secure and never share it! 🚨🚨🚨");
+    println!("💾 Modality Passfile saved to: {}\n", filepath.display());
 
     Ok(())
 }
@@ -0,0 +1,77 @@
+use anyhow::Result;
+use clap::Parser;
+use std::path::PathBuf;
+
+use modal_common::mnemonic::{parse_derivation_path, Mnemonic};
+use modal_common::signer::ExternalSigner;
+
+#[derive(Debug, Parser)]
+#[command(about = "Register a Modality ID whose key is held by a hardware signer")]
+pub struct Opts {
+    /// Signer helper program (see modal_common::signer for its protocol)
+    #[clap(long, env = "MODAL_SIGNER_PROGRAM")]
+    program: String,
+
+    /// Key on the device, as a hardened derivation path
+    #[clap(long, default_value_t = Mnemonic::default_derivation_path(0, 0, 0))]
+    key: String,
+
+    /// Output file path for the passfile
+    #[clap(long)]
+    path: Option<PathBuf>,
+
+    /// Output directory for the passfile
+    #[clap(long)]
+    dir: Option<PathBuf>,
+
+    /// Name for the passfile (defaults to the ID)
+    #[clap(long)]
+    name: Option<String>,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    parse_derivation_path(&opts.key)?;
+
+    println!("🔌 Asking {} for the key at {}...", opts.program, opts.key);
+    let signer = ExternalSigner::connect(&opts.program, &opts.key)?;
+    let address = signer.public_keypair().as_public_address();
+
+    let filepath = if let Some(path) = &opts.path {
+        path.clone()
+    } else {
+        let filename = opts.name.clone().unwrap_or_else(|| address.clone());
+        let default_dir = if let Some(home) = dirs::home_dir() {
+            let home_dot_modality = home.join(".modality");
+            std::fs::create_dir_all(&home_dot_modality)?;
+            home_dot_modality
+        } else {
+            PathBuf::from(".")
+        };
+        opts.dir
+            .clone()
+            .unwrap_or(default_dir)
+            .join(format!("{}.mod_passfile", filename))
+    };
+
+    if filepath.exists() {
+        return Err(anyhow::anyhow!(
+            "Passfile already exists at {}. Please choose a different name or remove the existing file.",
+            filepath.display()
+        ));
+    }
+
+    // The passfile holds only the public key and how to reach the signer
+    let mut json = signer.public_keypair().as_public_json()?;
+    json.derivation_path = Some(opts.key.clone());
+    json.signer = Some(signer.config());
+    std::fs::write(&filepath, serde_json::to_string(&json)?)?;
+
+    println!("✨ Registered a hardware-backed Modality ID!");
+    println!("📍 Modality ID: {}", address);
+    println!("🔑 Device Key: {}", opts.key);
+    println!("💾 Modality Passfile saved to: {}", filepath.display());
+    println!("\nSign commits with it as with any passfile:");
+    println!("   modal contract commit ... --sign {}", filepath.display());
+
+    Ok(())
+}
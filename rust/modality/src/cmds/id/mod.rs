@@ -1,4 +1,29 @@
 pub mod create;
 pub mod create_sub;
 pub mod derive;
-pub mod get;
\ No newline at end of file
+pub mod get;
+pub mod hardware;
+pub mod publish;
+pub mod rotate;
+
+use anyhow::Result;
+use rpassword::read_password;
+
+use modal_common::keypair::Keypair;
+use modal_common::signer::Signer;
+
+/// Load a passfile's signing key, prompting for its password if it's
+/// encrypted, or connect to its hardware signer
+fn load_signer(path: &str) -> Result<Box<dyn Signer>> {
+    let key = modal_common::passfile_v2::load_key(path)?;
+    if let Some(signer) = &key.signer {
+        return signer.connect(Some(&key.id));
+    }
+    let keypair = key.keypair()?;
+    if keypair.can_sign() {
+        return Ok(Box::new(keypair));
+    }
+    eprint!("Password: ");
+    let password = read_password()?;
+    Ok(Box::new(Keypair::from_encrypted_json_file(path, &password)?))
+}
@@ -0,0 +1,98 @@
+use anyhow::Result;
+use clap::Parser;
+use std::path::PathBuf;
+
+use modal_common::contract_store::identity::{document_path, ServiceEndpoint};
+use modal_common::contract_store::{CommitFile, ContractStore, IdentityDocument};
+
+use super::load_signer;
+
+#[derive(Debug, Parser)]
+#[command(about = "Publish a signed identity document to /ids/<id>.json in a contract")]
+pub struct Opts {
+    /// Passfile of the key signing the document: the identity itself, or one
+    /// of its current keys when rotating
+    #[clap(long)]
+    sign: PathBuf,
+
+    /// Identity to publish for (defaults to the signing key's ID)
+    #[clap(long)]
+    id: Option<String>,
+
+    /// Key that signs for the identity (repeatable). Replaces the published keys.
+    #[clap(long = "key")]
+    keys: Vec<String>,
+
+    /// Service endpoint as <type>=<url> (repeatable)
+    #[clap(long = "service")]
+    services: Vec<String>,
+
+    /// URL where the identity's status can be checked
+    #[clap(long)]
+    status_url: Option<String>,
+
+    /// Contract directory (defaults to current directory)
+    #[clap(long)]
+    dir: Option<PathBuf>,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    let dir = match &opts.dir {
+        Some(d) => d.clone(),
+        None => std::env::current_dir()?,
+    };
+    let store = ContractStore::open(&dir)?;
+
+    let signer = load_signer(&opts.sign.to_string_lossy())?;
+    let signer_id = signer.public_key_as_base58_identity();
+    let id = opts.id.clone().unwrap_or_else(|| signer_id.clone());
+
+    let previous = store.resolve_identity(&id)?;
+    let mut document = IdentityDocument::new(&id, previous.as_ref().map_or(1, |p| p.version + 1));
+    document.keys = opts.keys.clone();
+    document.services = opts.services
+        .iter()
+        .map(|s| parse_service(s))
+        .collect::<Result<_>>()?;
+    document.status_url = opts.status_url.clone();
+    document.updated_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+    document.sign(signer.as_ref())?;
+
+    let parent_id = store.get_head()?;
+    let mut commit = match &parent_id {
+        Some(parent) => CommitFile::with_parent(parent.clone()),
+        None => CommitFile::new(),
+    };
+    let path = document_path(&id);
+    commit.add_action("post".to_string(), Some(path.clone()), serde_json::to_value(&document)?);
+    let signature = signer.sign_string_as_base64_pad(&commit.signing_payload()?)?;
+    commit.head.signatures = Some(serde_json::json!({ signer_id.clone(): signature }));
+
+    commit.validate()?;
+    store.validate_commit_against_rules(&commit)?;
+    let commit_id = commit.compute_id()?;
+    store.save_commit(&commit_id, &commit)?;
+    store.set_head(&commit_id)?;
+
+    println!("✅ Published identity document v{} for {}", document.version, id);
+    println!("   Path: {}", path);
+    println!("   Signed by: {}", signer_id);
+    println!("   Commit ID: {}", commit_id);
+    if !document.keys.is_empty() {
+        println!("   Keys: {}", document.keys.join(", "));
+    }
+
+    Ok(())
+}
+
+fn parse_service(s: &str) -> Result<ServiceEndpoint> {
+    let (service_type, url) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("Service must be <type>=<url>, got: {}", s))?;
+    Ok(ServiceEndpoint {
+        service_type: service_type.to_string(),
+        url: url.to_string(),
+    })
+}
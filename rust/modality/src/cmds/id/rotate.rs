@@ -0,0 +1,95 @@
+use anyhow::Result;
+use clap::Parser;
+use std::path::PathBuf;
+
+use modal_common::contract_store::{CommitFile, ContractStore};
+use modal_common::key_rotation::{KeyHistory, KeyRotation};
+use modal_common::keypair::Keypair;
+
+use super::load_signer;
+
+#[derive(Debug, Parser)]
+#[command(about = "Replace a key with a new one, signed by the old key")]
+pub struct Opts {
+    /// Passfile of the key being replaced
+    #[clap(long)]
+    sign: PathBuf,
+
+    /// Passfile of the new key
+    #[clap(long, conflicts_with = "new_key")]
+    to: Option<PathBuf>,
+
+    /// ID of the new key
+    #[clap(long)]
+    new_key: Option<String>,
+
+    /// First height the new key signs at (defaults to the contract's next commit)
+    #[clap(long)]
+    effective_height: Option<u64>,
+
+    /// Why the key is replaced, e.g. retired or compromised
+    #[clap(long)]
+    reason: Option<String>,
+
+    /// Contract directory to commit the rotation to (defaults to current directory)
+    #[clap(long)]
+    dir: Option<PathBuf>,
+
+    /// Write the rotation record to a file instead of committing it, e.g. to
+    /// submit to the network's modal.keys contract
+    #[clap(long, requires = "effective_height")]
+    out: Option<PathBuf>,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    let new_key = match (&opts.to, &opts.new_key) {
+        (Some(path), _) => Keypair::from_json_file(&path.to_string_lossy())?.as_public_address(),
+        (None, Some(key)) => key.clone(),
+        (None, None) => anyhow::bail!("Must specify --to or --new-key"),
+    };
+    let signer = load_signer(&opts.sign.to_string_lossy())?;
+
+    if let Some(out) = &opts.out {
+        let effective_height = opts.effective_height.unwrap_or_default();
+        let rotation = KeyRotation::sign(signer.as_ref(), &new_key, effective_height, opts.reason.clone())?;
+        KeyHistory::new().check(&rotation)?;
+        std::fs::write(out, serde_json::to_string_pretty(&rotation.commit_action())?)?;
+        println!("✅ Wrote rotation of {} to {}", rotation.old_key, out.display());
+        println!("   New key: {}", rotation.new_key);
+        println!("   Effective height: {}", rotation.effective_height);
+        return Ok(());
+    }
+
+    let dir = match &opts.dir {
+        Some(d) => d.clone(),
+        None => std::env::current_dir()?,
+    };
+    let store = ContractStore::open(&dir)?;
+    let effective_height = match opts.effective_height {
+        Some(height) => height,
+        None => store.height()? + 1,
+    };
+    let rotation = KeyRotation::sign(signer.as_ref(), &new_key, effective_height, opts.reason.clone())?;
+
+    let parent_id = store.get_head()?;
+    let mut commit = match &parent_id {
+        Some(parent) => CommitFile::with_parent(parent.clone()),
+        None => CommitFile::new(),
+    };
+    commit.add_action("post".to_string(), Some(rotation.path()), serde_json::to_value(&rotation)?);
+    let signature = signer.sign_string_as_base64_pad(&commit.signing_payload()?)?;
+    commit.head.signatures = Some(serde_json::json!({ rotation.old_key.clone(): signature }));
+
+    commit.validate()?;
+    store.validate_commit_against_rules(&commit)?;
+    let commit_id = commit.compute_id()?;
+    store.save_commit(&commit_id, &commit)?;
+    store.set_head(&commit_id)?;
+
+    println!("✅ Rotated {} to {}", rotation.old_key, rotation.new_key);
+    println!("   Path: {}", rotation.path());
+    println!("   Effective height: {}", rotation.effective_height);
+    println!("   Commit ID: {}", commit_id);
+
+    Ok(())
+}
@@ -205,6 +205,18 @@ fn print_pretty(data: &InspectionData) {
         for bootstrapper in &net.bootstrappers {
             println!("    - {}", bootstrapper);
         }
+        if let Some(ref slots) = net.peer_slots {
+            println!("  Peer Slots: {}/{} inbound, {}/{} outbound ({} reserved, {} reserved peers connected)",
+                slots.inbound, slots.max_inbound, slots.outbound, slots.max_outbound,
+                slots.reserved_slots, slots.reserved_connected);
+            println!("  Evicted Peers: {}", slots.evicted);
+            if let Some(ref scores) = slots.scores {
+                println!("  Peer Scores:");
+                for (peer, score) in scores {
+                    println!("    - {}: {}", peer, score);
+                }
+            }
+        }
         println!();
     }
 
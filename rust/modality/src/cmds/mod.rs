@@ -1,6 +1,7 @@
 pub mod check;
 #[cfg(feature = "contract")]
 pub mod contract;
+pub mod export;
 #[cfg(feature = "identity")]
 pub mod id;
 #[cfg(feature = "node")]
@@ -0,0 +1,94 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use rpassword::read_password;
+use std::fs;
+use std::path::PathBuf;
+
+use modal_common::keypair::Keypair;
+use modal_common::passfile_v2::{is_encrypted_json, split_key_name, PassfileKey, PassfileV2};
+
+#[derive(Debug, Parser)]
+#[command(about = "Add a named key to a passfile, creating it if needed")]
+pub struct Opts {
+    /// Passfile to add the key to
+    #[clap(long)]
+    path: PathBuf,
+
+    /// Name of the key in the passfile
+    #[clap(long)]
+    name: String,
+
+    /// Passfile to copy the key from (path or path#name); a new key is
+    /// generated if not given
+    #[clap(long)]
+    from: Option<String>,
+
+    /// Make the key the passfile's default
+    #[clap(long)]
+    set_default: bool,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    let path_str = opts.path.to_str().ok_or_else(|| {
+        anyhow::anyhow!("Invalid file path: contains non-Unicode characters")
+    })?;
+
+    let key = match &opts.from {
+        Some(from) => load_source_key(from)?,
+        None => PassfileKey::from_keypair(&Keypair::generate()?, None, None)?,
+    };
+
+    let mut passfile = if opts.path.exists() {
+        let content = fs::read_to_string(&opts.path)?;
+        if is_encrypted_json(&content) {
+            eprint!("Enter password of {}: ", opts.path.display());
+            let password = read_password()?;
+            let mut passfile = PassfileV2::load(path_str, Some(&password))?;
+            passfile.decrypt(&password)?;
+            passfile.insert(&opts.name, key)?;
+            passfile.encrypt(&password)?;
+            passfile
+        } else {
+            let mut passfile = PassfileV2::load(path_str, None)?;
+            passfile.insert(&opts.name, key)?;
+            passfile
+        }
+    } else {
+        let mut passfile = PassfileV2::default();
+        passfile.insert(&opts.name, key)?;
+        passfile
+    };
+
+    if opts.set_default {
+        passfile.default_key = Some(opts.name.clone());
+    }
+    passfile.save(path_str)?;
+
+    let (_, key) = passfile.key(Some(&opts.name))?;
+    println!("✨ Added key '{}' to {}", opts.name, opts.path.display());
+    println!("📍 Modality ID: {}", key.id);
+    println!("\nUse it as {}#{}", opts.path.display(), opts.name);
+    if !passfile.is_encrypted() {
+        println!("\n🚨 The passfile isn't encrypted; run 'modal passfile encrypt --path {}'", opts.path.display());
+    }
+
+    Ok(())
+}
+
+/// The key in `from`, decrypted
+fn load_source_key(from: &str) -> Result<PassfileKey> {
+    let (path, name) = split_key_name(from);
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+    let password = if is_encrypted_json(&content) {
+        eprint!("Enter password of {}: ", path);
+        Some(read_password()?)
+    } else {
+        None
+    };
+    let mut passfile = PassfileV2::load(path, password.as_deref())?;
+    if let Some(password) = &password {
+        passfile.decrypt(password)?;
+    }
+    let (_, key) = passfile.key(name)?;
+    Ok(key.clone())
+}
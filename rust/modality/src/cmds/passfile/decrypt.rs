@@ -6,6 +6,7 @@ use std::fs;
 use std::path::PathBuf;
 
 use modal_common::keypair::Keypair;
+use modal_common::passfile_v2::{is_encrypted_json, PassfileV2};
 
 #[derive(Debug, Parser)]
 #[command(about = "Decrypt Modality passfile file in place")]
@@ -64,7 +65,20 @@ pub async fn run(opts: &Opts) -> Result<()> {
             if ext == "mod_passfile" {
                 // Try to read as json to check if encrypted
                 if let Ok(content) = fs::read_to_string(&path) {
-                    if content.contains("encrypted_private_key") {
+                    if PassfileV2::is_v2_json(&content) && is_encrypted_json(&content) {
+                        let path_str = path.to_str().ok_or_else(|| {
+                            anyhow::anyhow!("Invalid file path: contains non-Unicode characters")
+                        })?;
+                        let mut passfile = PassfileV2::from_json_string(&content)?;
+                        passfile.decrypt(&password).map_err(|e| {
+                            eprintln!("Failed to decrypt passfile {}: {}", path.display(), e);
+                            e
+                        })?;
+                        passfile.save(path_str)?;
+
+                        println!("🔓 Decrypted {}", path.display());
+                        decrypted_count += 1;
+                    } else if content.contains("encrypted_private_key") {
                         // Decrypt keypair from file
                         let keypair = Keypair::from_encrypted_json_file(
                             path.to_str().ok_or_else(|| {
@@ -5,7 +5,7 @@ use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use modal_common::keypair::Keypair;
+use modal_common::passfile_v2::{is_encrypted_json, PassfileV2};
 
 #[derive(Debug, Parser)]
 #[command(about = "Encrypt Modality passfile file in place")]
@@ -22,29 +22,21 @@ pub struct Opts {
     path: Option<PathBuf>,
 }
 
+/// Encrypt a passfile as v2. An encrypted v1 passfile is upgraded if it opens
+/// with `password`.
 pub async fn encrypt_passfile_file(path: &Path, password: &str) -> Result<()> {
-    let keypair = Keypair::from_json_file(path.to_str().ok_or_else(|| {
+    let path_str = path.to_str().ok_or_else(|| {
         anyhow::anyhow!("Invalid file path: contains non-Unicode characters")
-    })?)
-    .map_err(|e| {
-        eprintln!("Failed to read keypair from file {}: {}", path.display(), e);
+    })?;
+    let mut passfile = PassfileV2::load(path_str, Some(password)).map_err(|e| {
+        eprintln!("Failed to read passfile {}: {}", path.display(), e);
         e
     })?;
-
-    // Encrypt and save back to same file
-    keypair
-        .as_encrypted_json_file(
-            path.to_str().ok_or_else(|| {
-                anyhow::anyhow!(
-                    "Invalid file path: contains non-Unicode characters"
-                )
-            })?,
-            password,
-        )
-        .map_err(|e| {
-            eprintln!("Failed to save encrypted keypair to file: {}", e);
-            e
-        })
+    passfile.encrypt(password)?;
+    passfile.save(path_str).map_err(|e| {
+        eprintln!("Failed to save encrypted passfile: {}", e);
+        e
+    })
 }
 
 pub async fn run(opts: &Opts) -> Result<()> {
@@ -87,12 +79,19 @@ pub async fn run(opts: &Opts) -> Result<()> {
         let path = path_result?;
         if let Some(ext) = path.extension() {
             if ext == "mod_passfile" {
-                // Try to read as json to check if already encrypted
+                // Encrypt unencrypted passfiles and upgrade encrypted v1 ones
                 if let Ok(content) = fs::read_to_string(&path) {
-                    if content.contains("private_key") {
-                        // Read keypair from unencrypted file
+                    let is_v2 = PassfileV2::is_v2_json(&content);
+                    if is_v2 && is_encrypted_json(&content) {
+                        continue;
+                    }
+                    if is_v2 || content.contains("private_key") {
                         encrypt_passfile_file(&path, &password).await?;
-                        println!("🔒 Encrypted {}", path.display());
+                        if is_encrypted_json(&content) {
+                            println!("⬆️  Upgraded {} to an Argon2id v2 passfile", path.display());
+                        } else {
+                            println!("🔒 Encrypted {}", path.display());
+                        }
                         encrypted_count += 1;
                     }
                 }
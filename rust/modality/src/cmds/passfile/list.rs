@@ -0,0 +1,41 @@
+use anyhow::Result;
+use clap::Parser;
+use std::path::PathBuf;
+
+use modal_common::passfile_v2::PassfileV2;
+
+#[derive(Debug, Parser)]
+#[command(about = "List the keys in a passfile")]
+pub struct Opts {
+    /// Path to the passfile
+    #[clap(long)]
+    path: PathBuf,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    let content = std::fs::read_to_string(&opts.path)?;
+    if !PassfileV2::is_v2_json(&content) {
+        let keypair = modal_common::keypair::Keypair::from_json_string(&content)?;
+        println!("{} is a v1 passfile with a single key:", opts.path.display());
+        println!("  {}", keypair.as_public_address());
+        return Ok(());
+    }
+
+    let passfile = PassfileV2::from_json_string(&content)?;
+    let encryption = match &passfile.kdf {
+        Some(kdf) => format!("encrypted ({}, m={} KiB, t={}, p={})", kdf.algorithm, kdf.m_cost, kdf.t_cost, kdf.p_cost),
+        None => "not encrypted".to_string(),
+    };
+    println!("{} (v{}, {})\n", opts.path.display(), passfile.version, encryption);
+    for (name, key) in &passfile.keys {
+        let default = if passfile.default_key.as_deref() == Some(name.as_str()) {
+            " (default)"
+        } else {
+            ""
+        };
+        let signer = if key.signer.is_some() { " [hardware]" } else { "" };
+        println!("  {:<16} {}{}{}", name, key.id, signer, default);
+    }
+
+    Ok(())
+}
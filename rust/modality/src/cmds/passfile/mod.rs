@@ -1,2 +1,5 @@
+pub mod add;
 pub mod decrypt;
-pub mod encrypt;
\ No newline at end of file
+pub mod encrypt;
+pub mod list;
+pub mod rotate;
@@ -0,0 +1,94 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use rpassword::read_password;
+use std::fs;
+use std::path::PathBuf;
+
+use modal_common::passfile_v2::{is_encrypted_json, PassfileV2};
+
+#[derive(Debug, Parser)]
+#[command(about = "Re-encrypt Modality passfiles with a new password, keeping their identities")]
+#[command(group = clap::ArgGroup::new("source")
+    .required(true)
+    .args(&["dir", "path"]))]
+pub struct Opts {
+    /// Dir to search for passfile files.
+    #[clap(long, value_parser)]
+    dir: Option<PathBuf>,
+
+    /// Direct path to passfile files
+    #[clap(long, value_parser)]
+    path: Option<PathBuf>,
+}
+
+pub async fn run(opts: &Opts) -> Result<()> {
+    let paths: Vec<PathBuf> = if let Some(path) = &opts.path {
+        vec![path.clone()]
+    } else {
+        let root_dir = opts.dir.clone().unwrap();
+        if !root_dir.is_dir() {
+            return Err(anyhow::anyhow!("Not a directory: {}", root_dir.display()));
+        }
+        println!("\nSearching for passfile files in: {}", root_dir.display());
+        fs::read_dir(&root_dir)?
+            .map(|res| res.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .filter(|path| path.extension().is_some_and(|ext| ext == "mod_passfile"))
+            .collect()
+    };
+
+    // Only encrypted passfiles have a password to rotate
+    let paths: Vec<PathBuf> = paths
+        .into_iter()
+        .filter(|path| fs::read_to_string(path).is_ok_and(|content| is_encrypted_json(&content)))
+        .collect();
+    if paths.is_empty() {
+        println!("\nℹ️ No encrypted passfile files found.");
+        return Ok(());
+    }
+
+    eprint!("Enter current password: ");
+    let old_password = read_password()?;
+    let new_password = get_new_password().context("Failed to get new password")?;
+
+    for path in &paths {
+        let path_str = path.to_str().ok_or_else(|| {
+            anyhow::anyhow!("Invalid file path: contains non-Unicode characters")
+        })?;
+        // v1 passfiles are decrypted on load and saved as v2
+        let mut passfile = PassfileV2::load(path_str, Some(&old_password))
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        passfile
+            .rotate(&old_password, &new_password)
+            .with_context(|| format!("Failed to rotate {}", path.display()))?;
+        passfile.save(path_str)?;
+
+        println!("🔁 Rotated {}", path.display());
+        for (name, id) in passfile.ids() {
+            println!("   {} {}", name, id);
+        }
+    }
+
+    println!("\n✨ Successfully rotated {} passfile files!", paths.len());
+
+    Ok(())
+}
+
+fn get_new_password() -> Result<String> {
+    eprint!("Enter new password: ");
+
+    let password = read_password()?;
+    if password.is_empty() {
+        return Err(anyhow::anyhow!("Password cannot be empty"));
+    }
+
+    eprint!("Confirm new password: ");
+
+    let confirm = read_password()?;
+    if password != confirm {
+        return Err(anyhow::anyhow!("Passwords do not match"));
+    }
+
+    Ok(password)
+}
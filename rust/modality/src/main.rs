@@ -77,6 +77,9 @@ enum IdCommands {
     Derive(cmds::id::derive::Opts),
     #[command(about = "Get ID from passfile by name or path")]
     Get(cmds::id::get::Opts),
+    Hardware(cmds::id::hardware::Opts),
+    Publish(cmds::id::publish::Opts),
+    Rotate(cmds::id::rotate::Opts),
 }
 
 #[cfg(feature = "passfile")]
@@ -85,6 +88,9 @@ enum PassfileCommands {
     Decrypt(cmds::passfile::decrypt::Opts),
 
     Encrypt(cmds::passfile::encrypt::Opts),
+    Rotate(cmds::passfile::rotate::Opts),
+    Add(cmds::passfile::add::Opts),
+    List(cmds::passfile::list::Opts),
 }
 
 #[derive(Subcommand)]
@@ -103,6 +109,9 @@ enum ModelCommands {
 
     #[command(about = "Validate a contract model (predicates only, no raw propositions)")]
     Validate(cmds::validate::Opts),
+
+    #[command(about = "Export a model and its formulas to NuSMV or TLA+")]
+    Export(cmds::export::Opts),
 }
 
 #[cfg(feature = "node")]
@@ -122,11 +131,17 @@ async fn main() -> Result<()> {
             IdCommands::CreateSub(opts) => cmds::id::create_sub::run(opts).await?,
             IdCommands::Derive(opts) => cmds::id::derive::run(opts).await?,
             IdCommands::Get(opts) => cmds::id::get::run(opts).await?,
+            IdCommands::Hardware(opts) => cmds::id::hardware::run(opts).await?,
+            IdCommands::Publish(opts) => cmds::id::publish::run(opts).await?,
+            IdCommands::Rotate(opts) => cmds::id::rotate::run(opts).await?,
         },
         #[cfg(feature = "passfile")]
         Commands::Passfile { command } => match command {
             PassfileCommands::Decrypt(opts) => cmds::passfile::decrypt::run(opts).await?,
             PassfileCommands::Encrypt(opts) => cmds::passfile::encrypt::run(opts).await?,
+            PassfileCommands::Rotate(opts) => cmds::passfile::rotate::run(opts).await?,
+            PassfileCommands::Add(opts) => cmds::passfile::add::run(opts).await?,
+            PassfileCommands::List(opts) => cmds::passfile::list::run(opts).await?,
         },
         Commands::Model { command } => match command {
             ModelCommands::Mermaid(opts) => cmds::mermaid::run(opts).await?,
@@ -134,6 +149,7 @@ async fn main() -> Result<()> {
             ModelCommands::Create(opts) => cmds::model_create::run(opts).await?,
             ModelCommands::Synthesize(opts) => cmds::synthesize::run(opts).await?,
             ModelCommands::Validate(opts) => cmds::validate::run(opts).await?,
+            ModelCommands::Export(opts) => cmds::export::run(opts).await?,
         },
         #[cfg(feature = "node")]
         Commands::Node { command } => match command {